@@ -18,6 +18,14 @@ fn main() {
             pub const MOCK_DA_ELF: &[u8] = &[];
             pub const BITCOIN_DA_ID: [u32; 8] = [0u32; 8];
             pub const MOCK_DA_ID: [u32; 8] = [0u32; 8];
+            pub const BITCOIN_DA_AGGREGATION_ELF: &[u8] = &[];
+            pub const MOCK_DA_AGGREGATION_ELF: &[u8] = &[];
+            pub const BITCOIN_DA_AGGREGATION_ID: [u32; 8] = [0u32; 8];
+            pub const MOCK_DA_AGGREGATION_ID: [u32; 8] = [0u32; 8];
+            pub const BITCOIN_DA_LIGHT_CLIENT_ELF: &[u8] = &[];
+            pub const MOCK_DA_LIGHT_CLIENT_ELF: &[u8] = &[];
+            pub const BITCOIN_DA_LIGHT_CLIENT_ID: [u32; 8] = [0u32; 8];
+            pub const MOCK_DA_LIGHT_CLIENT_ID: [u32; 8] = [0u32; 8];
         "#;
 
         std::fs::write(methods_path, elf).expect("Failed to write mock rollup elf");
@@ -2,7 +2,7 @@
 use bitcoin_da::spec::RollupParams;
 use bitcoin_da::verifier::BitcoinVerifier;
 
-use citrea_primitives::{DA_TX_ID_LEADING_ZEROS, ROLLUP_NAME};
+use citrea_primitives::{DA_PAYLOAD_MODE, DA_TX_ID_LEADING_ZEROS, ROLLUP_NAME};
 use citrea_stf::runtime::Runtime;
 use citrea_stf::StfVerifier;
 #[cfg(feature = "bench")]
@@ -46,7 +46,8 @@ pub fn main() {
             rollup_name: ROLLUP_NAME.to_string(),
             reveal_tx_id_prefix: DA_TX_ID_LEADING_ZEROS.to_vec(),
         }),
-    );
+    )
+    .with_da_payload_mode(DA_PAYLOAD_MODE);
 
     stf_verifier
         .run_sequencer_commitments_in_da_slot(guest, storage)
@@ -0,0 +1,21 @@
+#![no_main]
+use bitcoin_da::spec::RollupParams;
+use bitcoin_da::verifier::BitcoinVerifier;
+use citrea_primitives::{DA_TX_ID_LEADING_ZEROS, ROLLUP_NAME};
+use citrea_stf::verifier::prove_light_client;
+use sov_risc0_adapter::guest::Risc0Guest;
+use sov_rollup_interface::da::DaVerifier;
+use sov_state::{Storage, ZkStorage};
+
+risc0_zkvm::guest::entry!(main);
+
+pub fn main() {
+    let guest = Risc0Guest::new();
+
+    let da_verifier = BitcoinVerifier::new(RollupParams {
+        rollup_name: ROLLUP_NAME.to_string(),
+        reveal_tx_id_prefix: DA_TX_ID_LEADING_ZEROS.to_vec(),
+    });
+
+    prove_light_client::<_, <ZkStorage as Storage>::Root, _>(guest, da_verifier);
+}
@@ -0,0 +1,13 @@
+#![no_main]
+use citrea_stf::verifier::aggregate_state_transitions;
+use sov_mock_da::MockDaSpec;
+use sov_risc0_adapter::guest::Risc0Guest;
+use sov_state::{Storage, ZkStorage};
+
+risc0_zkvm::guest::entry!(main);
+
+pub fn main() {
+    let guest = Risc0Guest::new();
+
+    aggregate_state_transitions::<MockDaSpec, <ZkStorage as Storage>::Root, _>(guest);
+}
@@ -0,0 +1,13 @@
+#![no_main]
+use citrea_stf::verifier::prove_light_client;
+use sov_mock_da::MockDaVerifier;
+use sov_risc0_adapter::guest::Risc0Guest;
+use sov_state::{Storage, ZkStorage};
+
+risc0_zkvm::guest::entry!(main);
+
+pub fn main() {
+    let guest = Risc0Guest::new();
+
+    prove_light_client::<_, <ZkStorage as Storage>::Root, _>(guest, MockDaVerifier {});
+}
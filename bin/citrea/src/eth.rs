@@ -1,17 +1,22 @@
 use std::str::FromStr;
+use std::time::Duration;
 
 use anyhow::Context as _;
 use ethereum_rpc::{EthRpcConfig, FeeHistoryCacheConfig, GasPriceOracleConfig};
+use sov_db::ledger_db::LedgerDB;
 use sov_modules_api::default_context::DefaultContext;
 use sov_prover_storage_manager::SnapshotManager;
 use sov_rollup_interface::services::da::DaService;
 use sov_state::ProverStorage;
+use sov_stf_runner::RpcConfig;
 use tokio::sync::broadcast;
 
 // register ethereum methods.
 pub(crate) fn register_ethereum<Da: DaService>(
     da_service: Da,
     storage: ProverStorage<sov_state::DefaultStorageSpec, SnapshotManager>,
+    ledger_db: LedgerDB,
+    rpc_config: &RpcConfig,
     methods: &mut jsonrpsee::RpcModule<()>,
     sequencer_client_url: Option<String>,
     soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
@@ -22,6 +27,9 @@ pub(crate) fn register_ethereum<Da: DaService>(
             eth_signer,
             gas_price_oracle_config: GasPriceOracleConfig::default(),
             fee_history_cache_config: FeeHistoryCacheConfig::default(),
+            sequencer_tx_fallback: rpc_config
+                .sequencer_tx_fallback
+                .then(|| Duration::from_millis(rpc_config.sequencer_tx_fallback_negative_cache_ms)),
         }
     };
 
@@ -29,6 +37,8 @@ pub(crate) fn register_ethereum<Da: DaService>(
         da_service,
         eth_rpc_config,
         storage,
+        ledger_db,
+        rpc_config.trace_cache_size,
         sequencer_client_url,
         soft_confirmation_rx,
     );
@@ -3,18 +3,62 @@
 
 use std::env;
 use std::str::FromStr;
+use std::sync::OnceLock;
 
+use sov_stf_runner::TelemetryConfig;
 use tracing::Level;
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
-use tracing_subscriber::{fmt, EnvFilter};
+use tracing_subscriber::{fmt, reload, EnvFilter, Registry};
+
+use crate::json_logs::JsonLayer;
 
 mod eth;
+mod json_logs;
 mod rollup;
+mod telemetry;
 pub use rollup::*;
+pub use telemetry::shutdown_telemetry;
+
+/// Handle to change the log filter after logging is initialized
+static LOG_FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Format of the log lines
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable lines
+    Text,
+    /// JSON lines with the fields of the event and of the spans it is in,
+    /// like `module`, `l2_height`, `da_height`, `tx_hash` and `request_id`
+    Json,
+}
+
+impl LogFormat {
+    /// JSON if the `JSON_LOGS` environment variable is set, text otherwise
+    pub fn from_env() -> Self {
+        if env::var("JSON_LOGS").is_ok() {
+            LogFormat::Json
+        } else {
+            LogFormat::Text
+        }
+    }
+}
 
-/// Default initialization of logging
+/// Default initialization of logging, in the format of [`LogFormat::from_env`]
 pub fn initialize_logging(level: Level) {
+    initialize_logging_with_format(level, LogFormat::from_env(), None, "citrea")
+        .expect("Logging without telemetry can't fail")
+}
+
+/// Initializes logging with the format.
+/// If telemetry is configured, spans are also exported under the service name of the config,
+/// or `default_service_name` if it has none.
+pub fn initialize_logging_with_format(
+    level: Level,
+    format: LogFormat,
+    telemetry: Option<&TelemetryConfig>,
+    default_service_name: &str,
+) -> anyhow::Result<()> {
     let env_filter = EnvFilter::from_str(&env::var("RUST_LOG").unwrap_or_else(|_| {
         let debug_components = vec![
             level.as_str().to_owned(),
@@ -35,17 +79,36 @@ pub fn initialize_logging(level: Level) {
         debug_components.join(",")
     }))
     .unwrap();
-    if std::env::var("JSON_LOGS").is_ok() {
-        tracing_subscriber::registry()
-            .with(fmt::layer().json())
+    let (env_filter, handle) = reload::Layer::new(env_filter);
+    let _ = LOG_FILTER.set(handle);
+    let telemetry = match telemetry {
+        Some(config) => Some(telemetry::init_tracer(config, default_service_name)?),
+        None => None,
+    }
+    .map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer));
+    match format {
+        LogFormat::Json => tracing_subscriber::registry()
             .with(env_filter)
-            .init();
-    } else {
-        tracing_subscriber::registry()
-            .with(fmt::layer())
+            .with(telemetry)
+            .with(JsonLayer::new(std::io::stdout))
+            .init(),
+        LogFormat::Text => tracing_subscriber::registry()
             .with(env_filter)
-            .init();
+            .with(telemetry)
+            .with(fmt::layer())
+            .init(),
     }
 
     log_panics::init();
+    Ok(())
+}
+
+/// Replaces the log filter, in the syntax of `RUST_LOG`
+pub fn set_log_filter(filter: &str) -> anyhow::Result<()> {
+    let env_filter = EnvFilter::from_str(filter)?;
+    LOG_FILTER
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("Logging is not initialized"))?
+        .reload(env_filter)?;
+    Ok(())
 }
@@ -1,17 +1,26 @@
 use core::fmt::Debug as DebugTrait;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use anyhow::Context as _;
 use bitcoin_da::service::DaServiceConfig;
-use citrea::{initialize_logging, BitcoinRollup, CitreaRollupBlueprint, MockDemoRollup};
+use citrea::{
+    initialize_logging_with_format, set_log_filter, shutdown_telemetry, BitcoinRollup,
+    CitreaRollupBlueprint, LogFormat, MockDemoRollup,
+};
 use citrea_sequencer::SequencerConfig;
 use citrea_stf::genesis_config::GenesisPaths;
 use clap::Parser;
+use sov_db::backup::restore_backup;
 use sov_mock_da::MockDaConfig;
+use sov_modules_api::default_context::ZkDefaultContext;
 use sov_modules_api::Spec;
 use sov_modules_rollup_blueprint::RollupBlueprint;
 use sov_state::storage::NativeStorage;
-use sov_stf_runner::{from_toml_path, FullNodeConfig, ProverConfig};
-use tracing::{error, instrument};
+use sov_stf_runner::{
+    from_toml_path, ConfigLoader, ConfigReloader, FullNodeConfig, ProverConfig, TelemetryConfig,
+};
+use tracing::{error, info, instrument, warn};
 
 #[cfg(test)]
 mod test_rpc;
@@ -25,6 +34,7 @@ mod test_rpc;
 struct Args {
     /// Path to the genesis configuration.
     /// Defines the genesis of module states like evm.
+    /// Either a directory with a JSON file per module or a single JSON or TOML file with a section per module.
     #[arg(long)]
     genesis_paths: String,
 
@@ -36,6 +46,12 @@ struct Args {
     #[arg(long, default_value = "resources/configs/mock/rollup_config.toml")]
     rollup_config_path: String,
 
+    /// Overrides a field of the rollup config by its dotted path, like `--set rpc.bind_port=8081`.
+    /// Takes precedence over environment variables like `CITREA__RPC__BIND_PORT`,
+    /// which take precedence over the rollup config file.
+    #[arg(long = "set", value_name = "PATH=VALUE", value_parser = parse_config_override)]
+    config_overrides: Vec<(String, String)>,
+
     /// The path to the sequencer config. If set, runs the node in sequencer mode, otherwise in full node mode.
     #[arg(long, conflicts_with = "prover_config_path")]
     sequencer_config_path: Option<String>,
@@ -44,12 +60,65 @@ struct Args {
     #[arg(long, conflicts_with = "sequencer_config_path")]
     prover_config_path: Option<String>,
 
+    /// If set, runs the node in light verifier mode. It only follows the DA layer and verifies proofs, without executing L2 blocks.
+    #[arg(long, conflicts_with_all = ["sequencer_config_path", "prover_config_path"])]
+    light_verifier: bool,
+
+    /// Path to a backup created by `citrea_createBackup`. If set, the node databases are restored
+    /// from it before starting. The storage path must not contain any databases.
+    #[arg(long)]
+    restore_backup: Option<String>,
+
+    /// If set, checks the node databases for consistency and exits instead of running the node.
+    /// With `repair`, the ledger is truncated to the last consistent L2 height.
+    #[arg(long, value_enum)]
+    db_check: Option<DbCheckMode>,
+
+    /// If set, replays the L2 blocks proven for the given L1 height with the witnesses recorded
+    /// by the prover, prints where they diverge from the native execution and exits.
+    #[arg(long, requires = "prover_config_path", conflicts_with = "db_check")]
+    replay_range: Option<u64>,
+
+    /// If set, writes the zk guest input proving the sequencer commitments of the given L1 height,
+    /// built from the witnesses recorded by the prover, to `guest_input_path` and exits.
+    #[arg(
+        long,
+        requires = "prover_config_path",
+        conflicts_with_all = ["db_check", "replay_range"]
+    )]
+    export_guest_input: Option<u64>,
+
+    /// File the guest input is written to. Defaults to `guest-input-<l1 height>.bin`.
+    #[arg(long, requires = "export_guest_input")]
+    guest_input_path: Option<String>,
+
     /// Logging verbosity
     #[arg(long, short = 'v', action = clap::ArgAction::Count, default_value = "2")]
     verbose: u8,
     /// Logging verbosity
     #[arg(long, short = 'q', action)]
     quiet: bool,
+    /// Format of the log lines, JSON if the `JSON_LOGS` environment variable is set and text otherwise
+    #[arg(long, value_enum)]
+    log_format: Option<LogFormat>,
+}
+
+/// Prefix of the environment variables overriding fields of the rollup config
+const CONFIG_ENV_PREFIX: &str = "CITREA";
+
+fn parse_config_override(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((path, value)) if !path.is_empty() => Ok((path.to_owned(), value.to_owned())),
+        _ => Err(format!("expected PATH=VALUE, got `{}`", arg)),
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum DbCheckMode {
+    /// Only report the first inconsistency
+    Report,
+    /// Truncate the ledger to the last consistent L2 height
+    Repair,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -73,9 +142,40 @@ async fn main() -> Result<(), anyhow::Error> {
         4 => tracing::Level::TRACE,
         _ => tracing::Level::INFO,
     };
-    initialize_logging(logging_level);
+    let rollup_config_loader = args.config_overrides.iter().fold(
+        ConfigLoader::new(&args.rollup_config_path).with_env_prefix(CONFIG_ENV_PREFIX),
+        |loader, (path, value)| loader.with_override(path, value),
+    );
 
-    let rollup_config_path = args.rollup_config_path.as_str();
+    // Spans are exported from the start, so the telemetry config is read before the rest.
+    // Errors of the rest of the config are reported once logging is initialized.
+    let telemetry: Option<TelemetryConfig> = match rollup_config_loader
+        .load_value()
+        .ok()
+        .and_then(|config| config.get("telemetry").cloned())
+    {
+        Some(telemetry) => Some(
+            telemetry
+                .try_into()
+                .context("Invalid telemetry configuration")?,
+        ),
+        None => None,
+    };
+    let service_name = if args.sequencer_config_path.is_some() {
+        "citrea-sequencer"
+    } else if args.prover_config_path.is_some() {
+        "citrea-prover"
+    } else if args.light_verifier {
+        "citrea-light-verifier"
+    } else {
+        "citrea-full-node"
+    };
+    initialize_logging_with_format(
+        logging_level,
+        args.log_format.unwrap_or_else(LogFormat::from_env),
+        telemetry.as_ref(),
+        service_name,
+    )?;
 
     let sequencer_config: Option<SequencerConfig> =
         args.sequencer_config_path.clone().map(|path| {
@@ -96,28 +196,47 @@ async fn main() -> Result<(), anyhow::Error> {
         ));
     }
 
-    match args.da_layer {
+    let export_guest_input = args.export_guest_input.map(|l1_height| {
+        let path = args
+            .guest_input_path
+            .clone()
+            .unwrap_or_else(|| format!("guest-input-{}.bin", l1_height));
+        (l1_height, PathBuf::from(path))
+    });
+
+    let result = match args.da_layer {
         SupportedDaLayer::Mock => {
             start_rollup::<MockDemoRollup, MockDaConfig>(
-                &GenesisPaths::from_dir(&args.genesis_paths),
-                rollup_config_path,
+                &GenesisPaths::from_path(&args.genesis_paths),
+                rollup_config_loader,
                 prover_config,
                 sequencer_config,
+                args.light_verifier,
+                args.restore_backup.as_deref(),
+                args.db_check,
+                args.replay_range,
+                export_guest_input.clone(),
             )
-            .await?;
+            .await
         }
         SupportedDaLayer::Bitcoin => {
             start_rollup::<BitcoinRollup, DaServiceConfig>(
-                &GenesisPaths::from_dir(&args.genesis_paths),
-                rollup_config_path,
+                &GenesisPaths::from_path(&args.genesis_paths),
+                rollup_config_loader,
                 prover_config,
                 sequencer_config,
+                args.light_verifier,
+                args.restore_backup.as_deref(),
+                args.db_check,
+                args.replay_range,
+                export_guest_input.clone(),
             )
-            .await?;
+            .await
         }
-    }
+    };
 
-    Ok(())
+    shutdown_telemetry();
+    result
 }
 
 #[instrument(level = "trace", skip_all, err)]
@@ -126,30 +245,114 @@ async fn start_rollup<S, DaC>(
         <S as RollupBlueprint>::NativeContext,
         <S as RollupBlueprint>::DaSpec,
     >>::GenesisPaths,
-    rollup_config_path: &str,
+    rollup_config_loader: ConfigLoader,
     prover_config: Option<ProverConfig>,
     sequencer_config: Option<SequencerConfig>,
+    light_verifier: bool,
+    restore_backup_path: Option<&str>,
+    db_check: Option<DbCheckMode>,
+    replay_range: Option<u64>,
+    export_guest_input: Option<(u64, PathBuf)>,
 ) -> Result<(), anyhow::Error>
 where
     DaC: serde::de::DeserializeOwned + DebugTrait + Clone,
-    S: CitreaRollupBlueprint<DaConfig = DaC>,
+    S: CitreaRollupBlueprint<DaConfig = DaC, ZkContext = ZkDefaultContext>,
     <<S as RollupBlueprint>::NativeContext as Spec>::Storage: NativeStorage,
 {
-    let rollup_config: FullNodeConfig<DaC> = from_toml_path(rollup_config_path)
+    let rollup_config: FullNodeConfig<DaC> = rollup_config_loader
+        .load()
         .context("Failed to read rollup configuration")
         .unwrap();
+    if let Some(log_level) = &rollup_config.log_level {
+        set_log_filter(log_level).context("Invalid log_level")?;
+    }
+    if let Some(backup_path) = restore_backup_path {
+        restore_backup(Path::new(backup_path), &rollup_config.storage.path)
+            .context("Failed to restore backup")?;
+    }
+
     let rollup_blueprint = S::new();
 
-    if let Some(sequencer_config) = sequencer_config {
-        let sequencer_rollup = rollup_blueprint
+    if let Some(db_check) = db_check {
+        let report = rollup_blueprint
+            .check_databases(rollup_config, db_check == DbCheckMode::Repair)
+            .await?;
+        match report.issue {
+            None => info!(
+                "Databases are consistent from L2 height {} to {}",
+                report.checked_from, report.head_l2_height
+            ),
+            Some(issue) if db_check == DbCheckMode::Repair => info!(
+                "Repaired databases: {}. Ledger truncated to L2 height {}",
+                issue, report.last_consistent_l2_height
+            ),
+            Some(issue) => anyhow::bail!(
+                "Databases are consistent up to L2 height {}: {}",
+                report.last_consistent_l2_height,
+                issue
+            ),
+        }
+        return Ok(());
+    }
+
+    if let Some(l1_height) = replay_range {
+        let report = rollup_blueprint
+            .replay_range(rollup_config, l1_height)
+            .await?;
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        match report.divergence {
+            None => info!(
+                "L2 blocks {} to {} replay to the native state root",
+                report.l2_range.0, report.l2_range.1
+            ),
+            Some(divergence) => anyhow::bail!(
+                "Replay diverges from the native execution at L2 block #{}",
+                divergence.l2_height
+            ),
+        }
+        return Ok(());
+    }
+
+    if let Some((l1_height, path)) = export_guest_input {
+        rollup_blueprint
+            .export_guest_input(rollup_config, l1_height, &path)
+            .await?;
+        info!(
+            "Exported the guest input of L1 height {} to {}",
+            l1_height,
+            path.display()
+        );
+        return Ok(());
+    }
+
+    if rollup_config.storage.replica.is_some() {
+        if sequencer_config.is_some() || prover_config.is_some() || light_verifier {
+            anyhow::bail!("RPC replica can only be run in full node mode");
+        }
+        let replica =
+            CitreaRollupBlueprint::create_new_rpc_replica(&rollup_blueprint, rollup_config)
+                .await
+                .expect("Could not start RPC replica");
+        if let Err(e) = replica.run().await {
+            error!("Error: {}", e);
+        }
+    } else if let Some(sequencer_config) = sequencer_config {
+        let config_reloader = create_config_reloader(rollup_config_loader)?;
+        let mut sequencer_rollup = rollup_blueprint
             .create_new_sequencer(rt_genesis_paths, rollup_config.clone(), sequencer_config)
             .await
             .expect("Could not start sequencer");
+        sequencer_rollup.runner = sequencer_rollup
+            .runner
+            .with_config_reloader(config_reloader.clone());
+        #[cfg(unix)]
+        tokio::spawn(reload_config_on_sighup(config_reloader));
         if let Err(e) = sequencer_rollup.run().await {
             error!("Error: {}", e);
         }
     } else if let Some(prover_config) = prover_config {
-        let prover = CitreaRollupBlueprint::create_new_prover(
+        let config_reloader = create_config_reloader(rollup_config_loader)?;
+        let mut prover = CitreaRollupBlueprint::create_new_prover(
             &rollup_blueprint,
             rt_genesis_paths,
             rollup_config,
@@ -157,17 +360,35 @@ where
         )
         .await
         .expect("Coult not start prover");
+        prover.runner = prover.runner.with_config_reloader(config_reloader.clone());
+        #[cfg(unix)]
+        tokio::spawn(reload_config_on_sighup(config_reloader));
         if let Err(e) = prover.run().await {
             error!("Error: {}", e);
         }
+    } else if light_verifier {
+        let light_verifier = CitreaRollupBlueprint::create_new_light_verifier(
+            &rollup_blueprint,
+            rt_genesis_paths,
+            rollup_config,
+        )
+        .await
+        .expect("Could not start light verifier");
+        if let Err(e) = light_verifier.run().await {
+            error!("Error: {}", e);
+        }
     } else {
-        let rollup = CitreaRollupBlueprint::create_new_rollup(
+        let config_reloader = create_config_reloader(rollup_config_loader)?;
+        let mut rollup = CitreaRollupBlueprint::create_new_rollup(
             &rollup_blueprint,
             rt_genesis_paths,
             rollup_config,
         )
         .await
         .expect("Could not start full-node");
+        rollup.runner = rollup.runner.with_config_reloader(config_reloader.clone());
+        #[cfg(unix)]
+        tokio::spawn(reload_config_on_sighup(config_reloader));
         if let Err(e) = rollup.run().await {
             error!("Error: {}", e);
         }
@@ -175,3 +396,34 @@ where
 
     Ok(())
 }
+
+/// Reloads the config the node was started with, applying changes of the log level to the logs
+fn create_config_reloader(loader: ConfigLoader) -> anyhow::Result<Arc<ConfigReloader>> {
+    Ok(Arc::new(
+        ConfigReloader::from_loader(loader)?.with_log_level_hook(Box::new(set_log_filter)),
+    ))
+}
+
+/// Reloads the rollup config whenever the node receives SIGHUP
+#[cfg(unix)]
+async fn reload_config_on_sighup(config_reloader: Arc<ConfigReloader>) {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(e) => {
+            error!("Could not listen for SIGHUP: {}", e);
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        match config_reloader.reload() {
+            Ok(report) if !report.requires_restart.is_empty() => warn!(
+                "Config changes only take effect after a restart: {:?}",
+                report.requires_restart
+            ),
+            Ok(_) => {}
+            Err(e) => error!("Could not reload config: {:?}", e),
+        }
+    }
+}
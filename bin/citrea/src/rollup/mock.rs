@@ -1,11 +1,14 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
 use citrea_prover::prover_service::ParallelProverService;
 use citrea_risc0_bonsai_adapter::host::Risc0BonsaiHost;
 use citrea_risc0_bonsai_adapter::Digest;
 use citrea_stf::genesis_config::StorageConfig;
 use citrea_stf::runtime::Runtime;
+use citrea_stf::verifier::{aggregate_state_transitions, StateTransitionVerifier};
 use sov_db::ledger_db::LedgerDB;
-use sov_mock_da::{MockDaConfig, MockDaService, MockDaSpec};
+use sov_mock_da::{MockDaConfig, MockDaService, MockDaSpec, MockDaVerifier};
 use sov_modules_api::default_context::{DefaultContext, ZkDefaultContext};
 use sov_modules_api::{Address, Spec};
 use sov_modules_rollup_blueprint::RollupBlueprint;
@@ -13,9 +16,10 @@ use sov_modules_stf_blueprint::StfBlueprint;
 use sov_prover_storage_manager::ProverStorageManager;
 use sov_rollup_interface::zk::{Zkvm, ZkvmHost};
 use sov_state::{DefaultStorageSpec, Storage, ZkStorage};
-use sov_stf_runner::{FullNodeConfig, ProverConfig};
+use sov_stf_runner::{FullNodeConfig, ProverConfig, RpcConfig};
 use tokio::sync::broadcast;
 
+use super::{create_local_proving_backend, create_proving_backend, GuestProgram};
 use crate::CitreaRollupBlueprint;
 
 /// Rollup with MockDa
@@ -55,6 +59,7 @@ impl RollupBlueprint for MockDemoRollup {
         storage: &<Self::NativeContext as Spec>::Storage,
         ledger_db: &LedgerDB,
         da_service: &Self::DaService,
+        rpc_config: &RpcConfig,
         sequencer_client_url: Option<String>,
         soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
     ) -> Result<jsonrpsee::RpcModule<()>, anyhow::Error> {
@@ -71,6 +76,8 @@ impl RollupBlueprint for MockDemoRollup {
         crate::eth::register_ethereum::<Self::DaService>(
             da_service.clone(),
             storage.clone(),
+            ledger_db.clone(),
+            rpc_config,
             &mut rpc_methods,
             sequencer_client_url,
             soft_confirmation_rx,
@@ -83,6 +90,15 @@ impl RollupBlueprint for MockDemoRollup {
         Digest::new(citrea_risc0::MOCK_DA_ID)
     }
 
+    fn get_aggregation_code_commitment(&self) -> <Self::Vm as Zkvm>::CodeCommitment {
+        Digest::new(citrea_risc0::MOCK_DA_AGGREGATION_ID)
+    }
+
+    #[instrument(level = "trace", skip(self), ret)]
+    fn get_light_client_code_commitment(&self) -> <Self::Vm as Zkvm>::CodeCommitment {
+        Digest::new(citrea_risc0::MOCK_DA_LIGHT_CLIENT_ID)
+    }
+
     async fn create_da_service(
         &self,
         rollup_config: &FullNodeConfig<Self::DaConfig>,
@@ -105,14 +121,91 @@ impl RollupBlueprint for MockDemoRollup {
         let zk_storage = ZkStorage::new();
         let da_verifier = Default::default();
 
-        ParallelProverService::new_with_default_workers(
+        // The mock backend runs the guest programs natively
+        let native_verifier = StateTransitionVerifier::<
+            StfBlueprint<
+                Self::ZkContext,
+                Self::DaSpec,
+                <Self::Vm as ZkvmHost>::Guest,
+                Self::ZkRuntime,
+            >,
+            _,
+            _,
+        >::new(StfBlueprint::new(), MockDaVerifier::default());
+        let backend = create_proving_backend::<
+            Self::DaSpec,
+            <<Self::NativeContext as Spec>::Storage as Storage>::Root,
+        >(
+            &prover_config,
+            GuestProgram {
+                elf: citrea_risc0::MOCK_DA_ELF,
+                method_id: citrea_risc0::MOCK_DA_ID,
+                bonsai_vm: vm.clone(),
+                native: Arc::new(move |guest| {
+                    let output = native_verifier
+                        .run_sequencer_commitments_in_da_slot(guest, ZkStorage::new())
+                        .map_err(|e| anyhow::anyhow!("Guest execution failed: {:?}", e))?;
+                    Risc0BonsaiHost::public_input_proof(&output)
+                }),
+                sp1_elf_path_var: "SP1_ELF_PATH",
+            },
+        )
+        .expect("Should be able to create proving backend");
+
+        // Aggregated proofs are proven on this machine, as they are not range proofs
+        // the remote prover could prove
+        let aggregation_backend = prover_config.aggregate_proofs.then(|| {
+            let aggregation_bonsai_vm = Risc0BonsaiHost::new(
+                citrea_risc0::MOCK_DA_AGGREGATION_ELF,
+                std::env::var("BONSAI_API_URL").unwrap_or("".to_string()),
+                std::env::var("BONSAI_API_KEY").unwrap_or("".to_string()),
+            );
+            create_local_proving_backend(
+                &prover_config,
+                GuestProgram {
+                    elf: citrea_risc0::MOCK_DA_AGGREGATION_ELF,
+                    method_id: citrea_risc0::MOCK_DA_AGGREGATION_ID,
+                    bonsai_vm: aggregation_bonsai_vm,
+                    native: Arc::new(|guest| {
+                        Risc0BonsaiHost::public_input_proof(&aggregate_state_transitions::<
+                            Self::DaSpec,
+                            <ZkStorage as Storage>::Root,
+                            _,
+                        >(guest))
+                    }),
+                    sp1_elf_path_var: "SP1_AGGREGATION_ELF_PATH",
+                },
+            )
+            .expect("Should be able to create aggregation proving backend")
+        });
+        let light_client_proofs = prover_config.light_client_proofs;
+        let mut prover_service = ParallelProverService::new_with_default_workers(
             vm,
+            backend,
             zk_stf,
             da_verifier,
             prover_config,
             zk_storage,
         )
-        .expect("Should be able to instantiate prover service")
+        .expect("Should be able to instantiate prover service");
+
+        if let Some(aggregation_backend) = aggregation_backend {
+            prover_service =
+                prover_service.with_aggregation(aggregation_backend, self.get_code_commitment());
+        }
+        if light_client_proofs {
+            let light_client_vm = Risc0BonsaiHost::new(
+                citrea_risc0::MOCK_DA_LIGHT_CLIENT_ELF,
+                std::env::var("BONSAI_API_URL").unwrap_or("".to_string()),
+                std::env::var("BONSAI_API_KEY").unwrap_or("".to_string()),
+            );
+            prover_service = prover_service.with_light_client(
+                light_client_vm,
+                self.get_light_client_code_commitment(),
+                MockDaVerifier {},
+            );
+        }
+        prover_service
     }
 
     fn create_storage_manager(
@@ -122,6 +215,22 @@ impl RollupBlueprint for MockDemoRollup {
         let storage_config = StorageConfig {
             path: rollup_config.storage.path.clone(),
         };
-        ProverStorageManager::new(storage_config)
+        match &rollup_config.storage.replica {
+            Some(replica_config) => ProverStorageManager::with_secondary_db(
+                storage_config,
+                &replica_config.secondary_path,
+                &rollup_config.storage.state_db,
+            ),
+            None => {
+                let storage_manager = ProverStorageManager::with_db_config(
+                    storage_config,
+                    &rollup_config.storage.state_db,
+                )?;
+                Ok(match rollup_config.storage.state_cache_size {
+                    Some(capacity) => storage_manager.with_state_cache(capacity),
+                    None => storage_manager,
+                })
+            }
+        }
     }
 }
@@ -1,18 +1,49 @@
+use std::path::Path;
+use std::sync::Arc;
+
+#[cfg(feature = "sp1")]
+use anyhow::Context;
 use async_trait::async_trait;
 pub use bitcoin::*;
-use citrea_fullnode::{CitreaFullnode, FullNode};
-use citrea_prover::{CitreaProver, Prover};
+use borsh::BorshDeserialize;
+use citrea_fullnode::{
+    CitreaFullnode, CitreaLightVerifier, CitreaRpcReplica, FullNode, LightVerifier, RpcReplica,
+};
+#[cfg(feature = "sp1")]
+use citrea_prover::prover_service::Sp1Backend;
+use citrea_prover::prover_service::{
+    NativeBackend, NativeGuestProgram, ProvingBackend, RemoteBackend, ZkvmBackend,
+};
+use citrea_prover::{
+    fetch_replay_input, generate_guest_input, replay_range, write_guest_input, CitreaProver,
+    Prover, ReplayReport,
+};
+use citrea_risc0_bonsai_adapter::host::Risc0BonsaiHost;
+use citrea_risc0_bonsai_adapter::Digest;
 use citrea_sequencer::{CitreaSequencer, Sequencer, SequencerConfig};
 pub use mock::*;
-use sov_db::ledger_db::SharedLedgerOps;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sov_db::ledger_db::{L2IntegrityReport, LedgerDB, SharedLedgerOps};
+use sov_db::schema::types::BatchNumber;
+use sov_modules_api::default_context::ZkDefaultContext;
 use sov_modules_api::storage::HierarchicalStorageManager;
 use sov_modules_api::Spec;
 use sov_modules_rollup_blueprint::RollupBlueprint;
-use sov_modules_stf_blueprint::{Runtime as RuntimeTrait, StfBlueprint};
+use sov_modules_stf_blueprint::{
+    Runtime as RuntimeTrait, SequencerOutcome, StfBlueprint, TxEffect,
+};
+use sov_risc0_adapter::host::{Risc0Host, Risc0ProvingOptions};
+use sov_risc0_adapter::Risc0MethodId;
+use sov_rollup_interface::da::DaSpec;
+use sov_rollup_interface::stf::StateTransitionFunction;
 use sov_state::storage::NativeStorage;
-use sov_stf_runner::{FullNodeConfig, InitVariant, ProverConfig};
+use sov_state::ZkStorage;
+use sov_stf_runner::{
+    Accelerator, FullNodeConfig, InitVariant, ProverConfig, ProvingBackendConfig, RpcConfig,
+};
 use tokio::sync::broadcast;
-use tracing::instrument;
+use tracing::{info, instrument};
 mod bitcoin;
 mod mock;
 
@@ -57,6 +88,7 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
             &prover_storage,
             &ledger_db,
             &da_service,
+            &rollup_config.rpc,
             None,
             soft_confirmation_rx,
         )?;
@@ -124,7 +156,10 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
         let mut storage_manager = self.create_storage_manager(&rollup_config)?;
         let prover_storage = storage_manager.create_finalized_storage()?;
 
-        let runner_config = rollup_config.runner.expect("Runner config is missing");
+        let mut runner_config = rollup_config.runner.expect("Runner config is missing");
+        runner_config
+            .diagnostics_dir
+            .get_or_insert_with(|| rollup_config.storage.path.join("diagnostics"));
         let (soft_confirmation_tx, soft_confirmation_rx) = broadcast::channel(10);
         // If subscriptions disabled, pass None
         let soft_confirmation_rx = if rollup_config.rpc.enable_subscriptions {
@@ -137,7 +172,8 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
             &prover_storage,
             &ledger_db,
             &da_service,
-            Some(runner_config.sequencer_client_url.clone()),
+            &rollup_config.rpc,
+            Some(runner_config.sequencer_client_url.primary().to_owned()),
             soft_confirmation_rx,
         )?;
 
@@ -145,11 +181,22 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
 
         let genesis_root = prover_storage.get_root_hash(1);
 
-        let prev_data = match ledger_db.get_head_soft_batch()? {
-            Some((number, soft_batch)) => {
-                Some((prover_storage.get_root_hash(number.0 + 1)?, soft_batch.hash))
+        // Full node commits ledger data before state,
+        // so resume from the last L2 block whose state has been finalized
+        let prev_data = match storage_manager.get_last_finalized_l2_height()? {
+            Some(l2_height) if l2_height > 0 => {
+                let soft_batch = ledger_db
+                    .get_soft_batch_by_number(&BatchNumber(l2_height))?
+                    .ok_or(anyhow::anyhow!(
+                        "Soft batch #{} is missing in ledger",
+                        l2_height
+                    ))?;
+                Some((
+                    prover_storage.get_root_hash(l2_height + 1)?,
+                    soft_batch.hash,
+                ))
             }
-            None => None,
+            _ => None,
         };
         let init_variant = match prev_data {
             Some((root_hash, batch_hash)) => InitVariant::Initialized((root_hash, batch_hash)),
@@ -171,6 +218,7 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
             storage_manager,
             init_variant,
             code_commitment,
+            self.get_aggregation_code_commitment(),
             rollup_config.sync_blocks_count,
             soft_confirmation_tx,
         )?;
@@ -181,6 +229,233 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
         })
     }
 
+    /// Creates a new read-only RPC replica of the node owning the storage path
+    #[instrument(level = "trace", skip_all)]
+    async fn create_new_rpc_replica(
+        &self,
+        rollup_config: FullNodeConfig<Self::DaConfig>,
+    ) -> Result<RpcReplica<Self>, anyhow::Error>
+    where
+        <Self::NativeContext as Spec>::Storage: NativeStorage,
+    {
+        let replica_config = rollup_config
+            .storage
+            .replica
+            .clone()
+            .ok_or(anyhow::anyhow!("Replica config is missing"))?;
+
+        let da_service = self.create_da_service(&rollup_config).await;
+
+        // Both are opened in secondary mode, as the storage has a replica config
+        let ledger_db = self.create_ledger_db(&rollup_config);
+        let mut storage_manager = self.create_storage_manager(&rollup_config)?;
+        let prover_storage = storage_manager.create_finalized_storage()?;
+
+        // Transactions are forwarded to the sequencer as on the primary.
+        // Soft confirmations are never executed by the replica, so subscriptions are not served.
+        // The ledger db of the replica is read only, so traces are not cached in it
+        let rpc_methods = self.create_rpc_methods(
+            &prover_storage,
+            &ledger_db,
+            &da_service,
+            &RpcConfig {
+                trace_cache_size: None,
+                ..rollup_config.rpc.clone()
+            },
+            rollup_config
+                .runner
+                .as_ref()
+                .map(|runner_config| runner_config.sequencer_client_url.primary().to_owned()),
+            None,
+        )?;
+
+        let runner = CitreaRpcReplica::new(
+            replica_config,
+            rollup_config.rpc,
+            storage_manager,
+            ledger_db,
+        );
+
+        Ok(RpcReplica {
+            runner,
+            rpc_methods,
+        })
+    }
+
+    /// Checks that the ledger db is consistent and matches the finalized state.
+    /// If `repair` is set, truncates the ledger db to the last consistent L2 height,
+    /// so the node resyncs the rest on the next start.
+    #[instrument(level = "trace", skip_all, err)]
+    async fn check_databases(
+        &self,
+        rollup_config: FullNodeConfig<Self::DaConfig>,
+        repair: bool,
+    ) -> Result<L2IntegrityReport, anyhow::Error>
+    where
+        <Self::NativeContext as Spec>::Storage: NativeStorage,
+    {
+        let ledger_db = self.create_ledger_db(&rollup_config);
+        let mut storage_manager = self.create_storage_manager(&rollup_config)?;
+        let finalized_l2_height = storage_manager.get_last_finalized_l2_height()?;
+        let finalized_storage = storage_manager.create_finalized_storage()?;
+
+        let mut report = ledger_db.check_l2_integrity(|l2_height| {
+            if finalized_l2_height.map_or(true, |finalized| l2_height > finalized) {
+                return Ok(None);
+            }
+            // State of l2 block at height `h` is written with version `h + 1`
+            let root = finalized_storage.get_root_hash(l2_height + 1)?;
+            Ok(Some(root.0.to_vec()))
+        })?;
+
+        if let Some(finalized_l2_height) = finalized_l2_height {
+            if report.is_consistent() && finalized_l2_height > report.head_l2_height {
+                report.issue = Some(format!(
+                    "state is finalized at L2 height {}, ahead of the ledger",
+                    finalized_l2_height
+                ));
+            }
+            if repair && finalized_l2_height > report.last_consistent_l2_height {
+                anyhow::bail!(
+                    "Cannot repair: state is finalized at L2 height {}, above the last consistent L2 height {}. The node must be resynced",
+                    finalized_l2_height,
+                    report.last_consistent_l2_height
+                );
+            }
+        }
+
+        if repair && !report.is_consistent() {
+            info!(
+                "Truncating ledger db to L2 height {}",
+                report.last_consistent_l2_height
+            );
+            ledger_db.truncate_l2(BatchNumber(report.last_consistent_l2_height))?;
+        }
+
+        Ok(report)
+    }
+
+    /// Replays the L2 blocks proven for an L1 block with the zk STF and the witnesses
+    /// recorded by the prover, and reports the first L2 block diverging from the native execution.
+    #[instrument(level = "trace", skip_all, err)]
+    async fn replay_range(
+        &self,
+        rollup_config: FullNodeConfig<Self::DaConfig>,
+        l1_height: u64,
+    ) -> Result<ReplayReport, anyhow::Error>
+    where
+        Self: RollupBlueprint<ZkContext = ZkDefaultContext>,
+    {
+        let da_service = self.create_da_service(&rollup_config).await;
+        let ledger_db = self.create_ledger_db(&rollup_config);
+        let input = fetch_replay_input(&ledger_db, &da_service, l1_height).await?;
+
+        let zk_stf: StfBlueprint<Self::ZkContext, Self::DaSpec, Self::Vm, Self::ZkRuntime> =
+            StfBlueprint::new();
+        replay_range::<_, Self::Vm, _, _>(
+            &zk_stf,
+            ZkStorage::new(),
+            &ledger_db,
+            &rollup_config.public_keys.sequencer_public_key,
+            input,
+        )
+    }
+
+    /// Writes the zk guest input proving the sequencer commitments of an L1 block to a file,
+    /// built from the witnesses recorded by the prover, so other provers can be run on it.
+    #[instrument(level = "trace", skip_all, err)]
+    async fn export_guest_input(
+        &self,
+        rollup_config: FullNodeConfig<Self::DaConfig>,
+        l1_height: u64,
+        path: &Path,
+    ) -> Result<(), anyhow::Error> {
+        let da_service = self.create_da_service(&rollup_config).await;
+        let ledger_db = self.create_ledger_db(&rollup_config);
+
+        let input = generate_guest_input::<
+            _,
+            _,
+            <StfBlueprint<Self::NativeContext, Self::DaSpec, Self::Vm, Self::NativeRuntime> as StateTransitionFunction<Self::Vm, Self::DaSpec>>::StateRoot,
+            <StfBlueprint<Self::NativeContext, Self::DaSpec, Self::Vm, Self::NativeRuntime> as StateTransitionFunction<Self::Vm, Self::DaSpec>>::Witness,
+        >(
+            &ledger_db,
+            &da_service,
+            l1_height,
+            &rollup_config.public_keys.sequencer_public_key,
+            rollup_config.public_keys.sequencer_da_pub_key_at(l1_height),
+        )
+        .await?;
+        write_guest_input(&input, path)
+    }
+
+    /// Creates a new light verifier
+    #[instrument(level = "trace", skip_all)]
+    async fn create_new_light_verifier(
+        &self,
+        runtime_genesis_paths: &<Self::NativeRuntime as RuntimeTrait<
+            Self::NativeContext,
+            Self::DaSpec,
+        >>::GenesisPaths,
+        rollup_config: FullNodeConfig<Self::DaConfig>,
+    ) -> Result<LightVerifier<Self>, anyhow::Error>
+    where
+        <Self::NativeContext as Spec>::Storage: NativeStorage,
+    {
+        let da_service = self.create_da_service(&rollup_config).await;
+
+        let ledger_db = self.create_ledger_db(&rollup_config);
+        let genesis_config = self.create_genesis_config(runtime_genesis_paths, &rollup_config)?;
+
+        let mut storage_manager = self.create_storage_manager(&rollup_config)?;
+        let prover_storage = storage_manager.create_finalized_storage()?;
+
+        let runner_config = rollup_config.runner.expect("Runner config is missing");
+
+        // Light verifier only needs the genesis state root, so state is never touched after genesis
+        let genesis_root = match prover_storage.get_root_hash(1) {
+            Ok(root_hash) => root_hash,
+            Err(_) => {
+                let native_stf: StfBlueprint<
+                    Self::NativeContext,
+                    Self::DaSpec,
+                    Self::Vm,
+                    Self::NativeRuntime,
+                > = StfBlueprint::new();
+                let storage = storage_manager.create_storage_on_l2_height(0)?;
+                let (genesis_root, initialized_storage) =
+                    native_stf.init_chain(storage, genesis_config);
+                storage_manager.save_change_set_l2(0, initialized_storage)?;
+                storage_manager.finalize_l2(0)?;
+                genesis_root
+            }
+        };
+
+        // No state to serve, so only ledger rpc is exposed
+        let mut rpc_methods = jsonrpsee::RpcModule::new(());
+        rpc_methods.merge(sov_ledger_rpc::server::rpc_module::<
+            LedgerDB,
+            SequencerOutcome<<Self::NativeContext as Spec>::Address>,
+            TxEffect,
+        >(ledger_db.clone())?)?;
+
+        let runner = CitreaLightVerifier::new(
+            runner_config,
+            rollup_config.public_keys,
+            rollup_config.rpc,
+            da_service,
+            ledger_db,
+            genesis_root,
+            self.get_code_commitment(),
+            self.get_aggregation_code_commitment(),
+        )?;
+
+        Ok(LightVerifier {
+            runner,
+            rpc_methods,
+        })
+    }
+
     /// Creates a new prover
     #[instrument(level = "trace", skip_all)]
     async fn create_new_prover(
@@ -224,7 +499,8 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
             &prover_storage,
             &ledger_db,
             &da_service,
-            Some(runner_config.sequencer_client_url.clone()),
+            &rollup_config.rpc,
+            Some(runner_config.sequencer_client_url.primary().to_owned()),
             soft_confirmation_rx,
         )?;
 
@@ -270,3 +546,108 @@ pub trait CitreaRollupBlueprint: RollupBlueprint {
         })
     }
 }
+
+/// A guest program, in the forms the proving backends run it in
+pub(crate) struct GuestProgram {
+    /// Risc0 build of the guest
+    pub(crate) elf: &'static [u8],
+    /// Method id of the Risc0 build
+    pub(crate) method_id: [u32; 8],
+    /// Runs the Risc0 build on Bonsai
+    pub(crate) bonsai_vm: Risc0BonsaiHost<'static>,
+    /// The guest program run natively by the mock backend
+    pub(crate) native: NativeGuestProgram<Risc0BonsaiHost<'static>>,
+    /// Environment variable with the path of the SP1 build of the guest
+    pub(crate) sp1_elf_path_var: &'static str,
+}
+
+/// Creates the backend proving the guest program, on a remote proving service if one
+/// is configured.
+fn create_proving_backend<Da, Root>(
+    prover_config: &ProverConfig,
+    guest: GuestProgram,
+) -> anyhow::Result<Arc<dyn ProvingBackend>>
+where
+    Da: DaSpec + 'static,
+    Root: Serialize + DeserializeOwned + BorshDeserialize + AsRef<[u8]> + 'static,
+{
+    let method_id = guest.method_id;
+    let local_backend = create_local_proving_backend(prover_config, guest)?;
+
+    Ok(match &prover_config.remote_prover {
+        Some(remote_prover_config) => Arc::new(RemoteBackend::<Risc0BonsaiHost, Da, Root>::new(
+            remote_prover_config.clone(),
+            Digest::new(method_id),
+            local_backend,
+        )?),
+        None => local_backend,
+    })
+}
+
+/// Creates the backend of the prover config proving the guest program on this machine
+/// or on the proving service of the zkVM.
+fn create_local_proving_backend(
+    prover_config: &ProverConfig,
+    guest: GuestProgram,
+) -> anyhow::Result<Arc<dyn ProvingBackend>> {
+    let GuestProgram {
+        elf,
+        method_id,
+        bonsai_vm,
+        native,
+        sp1_elf_path_var,
+    } = guest;
+    Ok(match prover_config.proving_backend {
+        ProvingBackendConfig::Bonsai => Arc::new(ZkvmBackend::new(
+            "bonsai",
+            bonsai_vm,
+            Digest::new(method_id),
+        )),
+        ProvingBackendConfig::Risc0 => {
+            let acceleration = prover_config.acceleration;
+            // The GPU prover is picked when the binary is built, it can only be checked here
+            match acceleration.accelerator {
+                Accelerator::Cpu => {}
+                Accelerator::Cuda if cfg!(feature = "cuda") => {}
+                Accelerator::Metal if cfg!(feature = "metal") => {}
+                accelerator => anyhow::bail!(
+                    "Proving on {:?} requires building citrea with the {:?} feature",
+                    accelerator,
+                    format!("{:?}", accelerator).to_lowercase()
+                ),
+            }
+            info!("Proving locally on {:?}", acceleration.accelerator);
+            Arc::new(ZkvmBackend::new(
+                "risc0",
+                Risc0Host::new(elf).with_options(Risc0ProvingOptions {
+                    segment_limit_po2: acceleration.segment_limit_po2,
+                    proving_threads: acceleration.proving_threads,
+                }),
+                Risc0MethodId::new(method_id),
+            ))
+        }
+        ProvingBackendConfig::Sp1 => create_sp1_backend(sp1_elf_path_var)?,
+        ProvingBackendConfig::Mock => Arc::new(NativeBackend::new(
+            "mock",
+            bonsai_vm,
+            Digest::new(method_id),
+            native,
+        )),
+    })
+}
+
+/// Creates the backend proving the SP1 build of a guest, read from the path in the
+/// environment variable
+#[cfg(feature = "sp1")]
+fn create_sp1_backend(elf_path_var: &str) -> anyhow::Result<Arc<dyn ProvingBackend>> {
+    let elf_path = std::env::var(elf_path_var)
+        .with_context(|| format!("{} must be set to prove with SP1", elf_path_var))?;
+    let elf = std::fs::read(&elf_path)
+        .with_context(|| format!("Failed to read the SP1 guest at {}", elf_path))?;
+    Ok(Arc::new(Sp1Backend::new(elf)))
+}
+
+#[cfg(not(feature = "sp1"))]
+fn create_sp1_backend(_elf_path_var: &str) -> anyhow::Result<Arc<dyn ProvingBackend>> {
+    anyhow::bail!("Proving with SP1 requires building citrea with the \"sp1\" feature")
+}
@@ -0,0 +1,43 @@
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::{self, Sampler, Tracer};
+use opentelemetry_sdk::{runtime, Resource};
+use sov_stf_runner::TelemetryConfig;
+
+/// Starts exporting spans to the OTLP endpoint of the config in the background,
+/// returns the tracer the spans are exported with
+pub(crate) fn init_tracer(
+    config: &TelemetryConfig,
+    default_service_name: &str,
+) -> anyhow::Result<Tracer> {
+    anyhow::ensure!(
+        (0.0..=1.0).contains(&config.sampling_ratio),
+        "telemetry.sampling_ratio must be between 0 and 1"
+    );
+    let service_name = config
+        .service_name
+        .clone()
+        .unwrap_or_else(|| default_service_name.to_owned());
+
+    // Spans are sampled with their root span, so sampled traces are complete
+    let sampler = Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sampling_ratio)));
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(&config.otlp_endpoint),
+        )
+        .with_trace_config(
+            trace::config()
+                .with_sampler(sampler)
+                .with_resource(Resource::new([KeyValue::new("service.name", service_name)])),
+        )
+        .install_batch(runtime::Tokio)?;
+    Ok(tracer)
+}
+
+/// Exports the spans which are not exported yet, before the node exits
+pub fn shutdown_telemetry() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
@@ -101,6 +101,13 @@ fn test_helper(
             batch_requests_limit: 50,
             enable_subscriptions: true,
             max_subscriptions_per_connection: 100,
+            trace_cache_size: None,
+            operator: None,
+            public_operator_methods: false,
+            sequencer_tx_fallback: true,
+            sequencer_tx_fallback_negative_cache_ms: 1000,
+            method_timeouts_ms: Default::default(),
+            default_method_timeout_ms: None,
         };
 
         queries_test_runner(test_queries, rpc_config).await;
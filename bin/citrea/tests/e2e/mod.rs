@@ -103,7 +103,17 @@ async fn test_all_flow() {
             Some(ProverConfig {
                 proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
                 proof_sampling_number: 0,
+                proving_strategy: None,
                 db_config: Some(SharedBackupDbConfig::default().set_db_name(psql_db_name)),
+                max_parallel_proving_jobs: 1,
+                proving_memory_budget_mb: None,
+                aggregate_proofs: false,
+                proving_backend: Default::default(),
+                remote_prover: None,
+                acceleration: Default::default(),
+                light_client_proofs: false,
+                proof_posting: None,
+                proof_challenges: Default::default(),
             }),
             NodeMode::Prover(seq_port),
             prover_db_dir,
@@ -277,6 +287,11 @@ async fn test_all_flow() {
         assert_eq!(status, SoftConfirmationStatus::Proven);
     }
 
+    assert_eq!(
+        full_node_test_client.citrea_get_proven_height().await,
+        Some(4)
+    );
+
     let balance = full_node_test_client
         .eth_get_balance(addr, None)
         .await
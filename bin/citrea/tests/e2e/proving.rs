@@ -69,7 +69,17 @@ async fn test_db_get_proof() {
             Some(ProverConfig {
                 proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
                 proof_sampling_number: 0,
+                proving_strategy: None,
                 db_config: Some(SharedBackupDbConfig::default().set_db_name(psql_db_name)),
+                max_parallel_proving_jobs: 1,
+                proving_memory_budget_mb: None,
+                aggregate_proofs: false,
+                proving_backend: Default::default(),
+                remote_prover: None,
+                acceleration: Default::default(),
+                light_client_proofs: false,
+                proof_posting: None,
+                proof_challenges: Default::default(),
             }),
             NodeMode::Prover(seq_port),
             prover_db_dir,
@@ -193,7 +203,17 @@ async fn full_node_verify_proof_and_store() {
             Some(ProverConfig {
                 proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
                 proof_sampling_number: 0,
+                proving_strategy: None,
                 db_config: None,
+                max_parallel_proving_jobs: 1,
+                proving_memory_budget_mb: None,
+                aggregate_proofs: false,
+                proving_backend: Default::default(),
+                remote_prover: None,
+                acceleration: Default::default(),
+                light_client_proofs: false,
+                proof_posting: None,
+                proof_challenges: Default::default(),
             }),
             NodeMode::Prover(seq_port),
             prover_db_dir,
@@ -4,9 +4,13 @@
 use std::str::FromStr;
 use std::time::Duration;
 
+use citrea_sequencer::DaPayloadMode;
 use citrea_stf::genesis_config::GenesisPaths;
 use reth_primitives::{Address, BlockNumberOrTag};
 use sov_mock_da::{MockAddress, MockDaService};
+use sov_modules_api::BlobReaderTrait;
+use sov_rollup_interface::da::DaData;
+use sov_rollup_interface::services::da::DaService;
 use sov_stf_runner::ProverConfig;
 use tokio::runtime::Runtime;
 use tokio::time::sleep;
@@ -14,8 +18,8 @@ use tokio::time::sleep;
 use crate::e2e::copy_dir_recursive;
 use crate::evm::{init_test_rollup, make_test_client};
 use crate::test_helpers::{
-    start_rollup, tempdir_with_children, wait_for_l1_block, wait_for_l2_block,
-    wait_for_prover_l1_height, NodeMode,
+    create_default_sequencer_config, start_rollup, tempdir_with_children, wait_for_l1_block,
+    wait_for_l2_block, wait_for_prover_l1_height, NodeMode,
 };
 use crate::{
     DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT, DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
@@ -297,6 +301,102 @@ async fn test_reopen_sequencer() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// The state diffs of the L2 blocks produced before a restart are posted with their commitment
+#[tokio::test(flavor = "multi_thread")]
+async fn test_reopen_sequencer_posts_state_diff() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let mut sequencer_config =
+        create_default_sequencer_config(4, Some(true), DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT);
+    sequencer_config.da_payload_mode = DaPayloadMode::StateDiff;
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+    let sequencer_db_dir_cloned = sequencer_db_dir.clone();
+    let da_db_dir_cloned = da_db_dir.clone();
+    let sequencer_config_cloned = sequencer_config.clone();
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir_cloned,
+            da_db_dir_cloned,
+            4,
+            true,
+            None,
+            Some(sequencer_config_cloned),
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = make_test_client(seq_port).await;
+    seq_test_client.send_publish_batch_request().await;
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&seq_test_client, 2, None).await;
+
+    seq_task.abort();
+
+    // Copy the db to a new path with the same contents because
+    // the lock is not released on the db directory even though the task is aborted
+    let _ = copy_dir_recursive(
+        &sequencer_db_dir,
+        &storage_dir.path().join("sequencer_copy"),
+    );
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+    let sequencer_db_dir = storage_dir.path().join("sequencer_copy");
+    let da_db_dir_cloned = da_db_dir.clone();
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir_cloned,
+            4,
+            true,
+            None,
+            Some(sequencer_config),
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = make_test_client(seq_port).await;
+    // The 4th L2 block triggers a commitment, which creates a new L1 block
+    seq_test_client.send_publish_batch_request().await;
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&seq_test_client, 4, None).await;
+
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+    wait_for_l1_block(&da_service, 2, None).await;
+    let block = da_service.get_block_at(2).await.unwrap();
+    let mut blobs = da_service.extract_relevant_blobs(&block);
+    assert_eq!(blobs.len(), 1);
+
+    let DaData::CommitmentWithStateDiff(data) = DaData::decode(blobs[0].full_data()).unwrap()
+    else {
+        panic!("Expected the commitment to be posted with its state diff after a restart");
+    };
+    assert_eq!(data.commitment.l2_start_block_number, 1);
+    assert_eq!(data.commitment.l2_end_block_number, 4);
+    assert!(!data.state_diff.is_empty());
+
+    seq_task.abort();
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_reopen_prover() -> Result<(), anyhow::Error> {
     // citrea::initialize_logging(tracing::Level::DEBUG);
@@ -3,13 +3,21 @@ use std::str::FromStr;
 use std::time::Duration;
 
 use alloy::consensus::{Signed, TxEip1559, TxEnvelope};
+use alloy::rpc::types::eth::Block;
 use alloy::signers::wallet::LocalWallet;
 use alloy::signers::Signer;
 use alloy_rlp::{BytesMut, Encodable};
+use citrea_evm::DevSigner;
 use citrea_primitives::TEST_PRIVATE_KEY;
-use citrea_sequencer::{SequencerConfig, SequencerMempoolConfig};
+use citrea_sequencer::{
+    EmptyBlockPolicy, LeaseConfig, RelayConfig, SequencerConfig, SequencerMempoolConfig,
+    StandbyConfig,
+};
 use citrea_stf::genesis_config::GenesisPaths;
-use reth_primitives::{Address, BlockNumberOrTag};
+use reth_primitives::{
+    keccak256, Address, BlockNumberOrTag, Transaction, TxEip1559 as RethTxEip1559, TxKind, B256,
+    U256,
+};
 use shared_backup_db::{PostgresConnector, SharedBackupDbConfig};
 use sov_mock_da::{MockAddress, MockDaService, MockDaSpec};
 use tokio::time::sleep;
@@ -61,6 +69,16 @@ async fn test_sequencer_fill_missing_da_blocks() -> Result<(), anyhow::Error> {
                 db_config: Default::default(),
                 da_update_interval_ms: 500,
                 block_production_interval_ms: 500,
+                max_l2_block_state_diff_size: 100 * 1024,
+                empty_block_policy: Default::default(),
+                empty_block_heartbeat_ms: 60_000,
+                commitment_policy: Default::default(),
+                enable_admin_rpc: false,
+                standby: None,
+                lease: None,
+                tx_gossip_peers: vec![],
+                da_payload_mode: Default::default(),
+                relay: None,
             }),
             Some(true),
             DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
@@ -130,6 +148,102 @@ async fn test_sequencer_fill_missing_da_blocks() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Run the sequencer with a block time, skipping empty blocks.
+/// Check that ticks without txs don't produce blocks, that a tx is included
+/// in the next tick and that an empty block is produced on the heartbeat.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sequencer_skips_empty_blocks_until_heartbeat() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let mut sequencer_config = create_default_sequencer_config(
+        DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+        Some(false),
+        DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+    );
+    sequencer_config.block_production_interval_ms = 200;
+    sequencer_config.empty_block_policy = EmptyBlockPolicy::Skip;
+    sequencer_config.empty_block_heartbeat_ms = 5_000;
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let seq_task = tokio::spawn(async move {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir,
+            DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+            true,
+            None,
+            Some(sequencer_config),
+            Some(false),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = make_test_client(seq_port).await;
+
+    // The first block is the heartbeat block
+    sleep(Duration::from_secs(2)).await;
+    assert_eq!(
+        seq_test_client
+            .ledger_get_head_soft_batch_height()
+            .await
+            .unwrap(),
+        None
+    );
+    wait_for_l2_block(&seq_test_client, 1, Some(Duration::from_secs(10))).await;
+
+    // Ticks without txs are skipped
+    sleep(Duration::from_secs(2)).await;
+    assert_eq!(
+        seq_test_client
+            .ledger_get_head_soft_batch_height()
+            .await
+            .unwrap(),
+        Some(1)
+    );
+
+    // A tx is included on the next tick
+    let tx_hash = *seq_test_client
+        .send_eth(Address::random(), None, None, None, 1u128)
+        .await
+        .unwrap()
+        .tx_hash();
+    wait_for_l2_block(&seq_test_client, 2, Some(Duration::from_secs(2))).await;
+    let block = seq_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(2)))
+        .await;
+    assert_eq!(block.transactions.len(), 1);
+    assert_eq!(block.transactions.hashes().last().unwrap().clone(), tx_hash);
+
+    sleep(Duration::from_secs(2)).await;
+    assert_eq!(
+        seq_test_client
+            .ledger_get_head_soft_batch_height()
+            .await
+            .unwrap(),
+        Some(2)
+    );
+
+    // The next heartbeat block is empty
+    wait_for_l2_block(&seq_test_client, 3, Some(Duration::from_secs(10))).await;
+    let block = seq_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(3)))
+        .await;
+    assert!(block.transactions.is_empty());
+
+    seq_task.abort();
+}
+
 /// Run the sequencer.
 /// Send spam transactions.
 /// Check if the sequencer triggers a commitment after a certain state diff size since it's last commitment.
@@ -226,7 +340,8 @@ async fn test_sequencer_commitment_threshold() {
 }
 
 /// Run the sequencer.
-/// Send a traensaction that can cover base fee and prioiity fee but not the L1 fee.
+/// Send a transaction that can't cover the minimum L1 fee and check that it is rejected.
+/// Send a traensaction that can cover base fee, prioiity fee and the minimum L1 fee but not its actual L1 fee.
 /// Check if the transaction is removed from the mempool and not included in the block.
 #[tokio::test(flavor = "multi_thread")]
 async fn test_transaction_failing_on_l1_is_removed_from_mempool() -> Result<(), anyhow::Error> {
@@ -251,6 +366,8 @@ async fn test_transaction_failing_on_l1_is_removed_from_mempool() -> Result<(),
     let random_wallet_address = random_wallet.address();
 
     let second_block_base_fee = 768641461;
+    // L1 fee for the smallest possible state diff (112 bytes) at mock DA's fee rate (10)
+    let min_l1_fee = 1120;
 
     let _pending = seq_test_client
         .send_eth(
@@ -258,8 +375,9 @@ async fn test_transaction_failing_on_l1_is_removed_from_mempool() -> Result<(),
             None,
             None,
             None,
-            // gas needed for transaction + 500 (to send) but this won't be enough for L1 fees
-            21000 * second_block_base_fee + 500,
+            // gas needed for transaction + 500 (to send) + min L1 fee
+            // but this won't be enough for the actual L1 fee
+            21000 * second_block_base_fee + 500 + min_l1_fee,
         )
         .await
         .unwrap();
@@ -275,6 +393,19 @@ async fn test_transaction_failing_on_l1_is_removed_from_mempool() -> Result<(),
     )
     .await;
 
+    // Can't pay the min L1 fee on top of the gas and value, so it is rejected by the mempool
+    let err = random_test_client
+        .send_eth_with_gas(
+            Address::from_str("0x0000000000000000000000000000000000000000").unwrap(),
+            Some(0),
+            Some(second_block_base_fee),
+            21000,
+            501,
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("insufficient funds"));
+
     let tx = random_test_client
         .send_eth_with_gas(
             Address::from_str("0x0000000000000000000000000000000000000000").unwrap(),
@@ -376,6 +507,16 @@ async fn test_gas_limit_too_high() {
                 db_config: Default::default(),
                 da_update_interval_ms: 1000,
                 block_production_interval_ms: 1000,
+                max_l2_block_state_diff_size: 100 * 1024,
+                empty_block_policy: Default::default(),
+                empty_block_heartbeat_ms: 60_000,
+                commitment_policy: Default::default(),
+                enable_admin_rpc: false,
+                standby: None,
+                lease: None,
+                tx_gossip_peers: vec![],
+                da_payload_mode: Default::default(),
+                relay: None,
             }),
             Some(true),
             100,
@@ -469,6 +610,424 @@ async fn test_gas_limit_too_high() {
     full_node_task.abort();
 }
 
+/// Blocks are packed under the state diff budget, the txs which do not fit are left
+/// for the next blocks, and the full node executes the packed blocks to the same state.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_state_diff_budget_splits_txs_across_blocks() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+    let full_node_db_dir = db_dir.path().join("full-node").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+    let seq_da_dir = da_db_dir.clone();
+    let seq_task = tokio::spawn(async move {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            seq_da_dir,
+            DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+            true,
+            None,
+            // A transfer to a new account takes about 170 bytes of the budget
+            Some(SequencerConfig {
+                max_l2_block_state_diff_size: 800,
+                ..create_default_sequencer_config(
+                    DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+                    Some(true),
+                    DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+                )
+            }),
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = make_test_client(seq_port).await;
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+    let full_node_da_dir = da_db_dir.clone();
+    let full_node_task = tokio::spawn(async move {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::FullNode(seq_port),
+            full_node_db_dir,
+            full_node_da_dir,
+            DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+            true,
+            None,
+            None,
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+    let full_node_port = full_node_port_rx.await.unwrap();
+    let full_node_test_client = make_test_client(full_node_port).await;
+
+    let tx_count = 10;
+    let mut tx_hashes = vec![];
+    for i in 0..tx_count {
+        let tx = seq_test_client
+            .send_eth(Address::from([0xa0 + i; 20]), None, None, None, 1_000_000)
+            .await
+            .unwrap();
+        tx_hashes.push(*tx.tx_hash());
+    }
+
+    let mut included = vec![];
+    for l2_height in 1..=tx_count as u64 {
+        seq_test_client.send_publish_batch_request().await;
+        wait_for_l2_block(&full_node_test_client, l2_height, None).await;
+
+        let block = seq_test_client
+            .eth_get_block_by_number(Some(BlockNumberOrTag::Number(l2_height)))
+            .await;
+        let block_txs = block.transactions.as_hashes().unwrap().to_vec();
+        // Never all txs in a single block
+        assert!(block_txs.len() < tx_count as usize);
+        included.extend(block_txs);
+
+        let block_from_full_node = full_node_test_client
+            .eth_get_block_by_number(Some(BlockNumberOrTag::Number(l2_height)))
+            .await;
+        assert_eq!(block_from_full_node.header.hash, block.header.hash);
+        assert_eq!(
+            block_from_full_node.header.state_root,
+            block.header.state_root
+        );
+
+        if included.len() == tx_hashes.len() {
+            break;
+        }
+    }
+    // The txs of the sender are included in nonce order
+    assert_eq!(included, tx_hashes);
+
+    seq_task.abort();
+    full_node_task.abort();
+}
+
+/// Bundles are included with all of their txs, in order, in a single block or not at all.
+/// A bundle without a block number is tried again in the next blocks, and one clashing
+/// with the nonce of a mempool tx is rejected.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bundles_are_included_atomically() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+    tokio::spawn(async move {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir,
+            DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+            true,
+            None,
+            None,
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await;
+
+    let secret_key = secp256k1::SecretKey::from_str(
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+    )
+    .unwrap();
+    let signer = DevSigner::new(vec![secret_key]);
+    let sender = signer.signers()[0];
+    let transfer = |nonce: u64| {
+        let tx = Transaction::Eip1559(RethTxEip1559 {
+            chain_id: test_client.chain_id,
+            nonce,
+            gas_limit: 21_000,
+            max_fee_per_gas: 100_000_000_000,
+            to: TxKind::Call(Address::from([0x42; 20])),
+            value: U256::from(1),
+            ..Default::default()
+        });
+        let rlp = signer
+            .sign_transaction(tx, sender)
+            .unwrap()
+            .envelope_encoded();
+        (keccak256(&rlp), rlp)
+    };
+    let block_tx_hashes = |block: Block| block.transactions.as_hashes().unwrap().to_vec();
+
+    // Both txs are included in the same block, in order
+    let (first_hash, first_tx) = transfer(0);
+    let (second_hash, second_tx) = transfer(1);
+    test_client
+        .eth_send_bundle(vec![first_tx, second_tx], None)
+        .await
+        .unwrap();
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 1, None).await;
+    let block = test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(1)))
+        .await;
+    assert_eq!(block_tx_hashes(block), vec![first_hash, second_hash]);
+
+    // The second tx can't be executed, so neither is included
+    let (_, valid_tx) = transfer(2);
+    let (_, invalid_tx) = transfer(5);
+    test_client
+        .eth_send_bundle(vec![valid_tx, invalid_tx], Some(2))
+        .await
+        .unwrap();
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 2, None).await;
+    let block = test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(2)))
+        .await;
+    assert!(block_tx_hashes(block).is_empty());
+    assert_eq!(
+        test_client
+            .eth_get_transaction_count(sender, None)
+            .await
+            .unwrap(),
+        2
+    );
+
+    // A bundle clashing with the nonce of a mempool tx is rejected
+    test_client.sync_nonce().await;
+    let mempool_tx = test_client
+        .send_eth(Address::from([0x43; 20]), None, None, None, 1)
+        .await
+        .unwrap();
+    let (_, clashing_tx) = transfer(2);
+    assert!(test_client
+        .eth_send_bundle(vec![clashing_tx], None)
+        .await
+        .is_err());
+
+    // A bundle without a block number waits for the tx of the mempool it follows,
+    // which is only included after the bundles of the block
+    let (later_hash, later_tx) = transfer(3);
+    test_client
+        .eth_send_bundle(vec![later_tx], None)
+        .await
+        .unwrap();
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 3, None).await;
+    let block = test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(3)))
+        .await;
+    assert_eq!(block_tx_hashes(block), vec![*mempool_tx.tx_hash()]);
+
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 4, None).await;
+    let block = test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(4)))
+        .await;
+    assert_eq!(block_tx_hashes(block), vec![later_hash]);
+}
+
+/// Simulated blocks are built on a snapshot of the head L2 block without committing anything,
+/// and blocks are produced while they are simulated.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_simulate_block_does_not_commit() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+    tokio::spawn(async move {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir,
+            DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+            true,
+            None,
+            None,
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await;
+
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 1, None).await;
+
+    let secret_key = secp256k1::SecretKey::from_str(
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+    )
+    .unwrap();
+    let signer = DevSigner::new(vec![secret_key]);
+    let sender = signer.signers()[0];
+    let transfer = |nonce: u64| {
+        let tx = Transaction::Eip1559(RethTxEip1559 {
+            chain_id: test_client.chain_id,
+            nonce,
+            gas_limit: 21_000,
+            max_fee_per_gas: 100_000_000_000,
+            to: TxKind::Call(Address::from([0x42; 20])),
+            value: U256::from(1),
+            ..Default::default()
+        });
+        let rlp = signer
+            .sign_transaction(tx, sender)
+            .unwrap()
+            .envelope_encoded();
+        (keccak256(&rlp), rlp)
+    };
+    let (first_hash, first_tx) = transfer(0);
+    let (second_hash, second_tx) = transfer(1);
+    let (gapped_hash, gapped_tx) = transfer(5);
+
+    let simulation = test_client
+        .citrea_simulate_block(vec![first_tx, second_tx, gapped_tx])
+        .await
+        .unwrap();
+    assert_eq!(simulation["l2Height"], 2);
+    assert_ne!(simulation["stateRoot"], simulation["parentStateRoot"]);
+    let txs = simulation["txs"].as_array().unwrap();
+    let tx = |i: usize| {
+        (
+            serde_json::from_value::<B256>(txs[i]["hash"].clone()).unwrap(),
+            txs[i]["included"].as_bool().unwrap(),
+        )
+    };
+    assert_eq!(
+        (tx(0), tx(1), tx(2)),
+        (
+            (first_hash, true),
+            (second_hash, true),
+            (gapped_hash, false)
+        )
+    );
+    assert_eq!(txs[0]["gasUsed"], 21_000);
+
+    // Nothing is committed or added to the mempool
+    assert_eq!(
+        test_client
+            .eth_get_transaction_count(sender, None)
+            .await
+            .unwrap(),
+        0
+    );
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 2, None).await;
+    let block = test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Number(2)))
+        .await;
+    assert!(block.transactions.as_hashes().unwrap().is_empty());
+    assert_eq!(
+        test_client
+            .eth_get_block_by_number(None)
+            .await
+            .header
+            .number,
+        Some(2)
+    );
+}
+
+/// Meta-transactions are not relayed while no trusted forwarder is deployed,
+/// so the relayer does not pay for calls to an empty account.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_meta_transactions_are_not_relayed_without_forwarder() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let relayer_private_key = "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+    let mut sequencer_config = create_default_sequencer_config(
+        DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+        Some(true),
+        DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+    );
+    sequencer_config.relay = Some(RelayConfig {
+        relayer_private_key: relayer_private_key.to_string(),
+        max_txs_per_origin: 10,
+        quota_window_ms: 3_600_000,
+        max_gas: 500_000,
+        max_gas_per_window: 50_000_000,
+    });
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+    tokio::spawn(async move {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir,
+            DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+            true,
+            None,
+            Some(sequencer_config),
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await;
+
+    let relayer = DevSigner::new(vec![
+        secp256k1::SecretKey::from_str(relayer_private_key).unwrap()
+    ])
+    .signers()[0];
+    let request = serde_json::json!({
+        "from": Address::from([0x11; 20]),
+        "to": Address::from([0x42; 20]),
+        "gas": U256::from(50_000),
+        "nonce": U256::ZERO,
+        "deadline": U256::from(u64::MAX),
+        "data": "0x",
+        "signature": format!("0x{}", "11".repeat(65)),
+    });
+    let jsonrpsee::core::ClientError::Call(err) = test_client
+        .citrea_relay_meta_transaction(request)
+        .await
+        .unwrap_err()
+    else {
+        panic!("Relaying was not rejected by the sequencer");
+    };
+    assert!(err
+        .data()
+        .unwrap()
+        .get()
+        .contains("Trusted forwarder is not deployed"));
+
+    assert_eq!(
+        test_client
+            .eth_get_transaction_count(relayer, None)
+            .await
+            .unwrap(),
+        0
+    );
+}
+
 /// Run the sequencer.
 /// Fill the mempool with transactions.
 /// Create a block with a system transaction.
@@ -518,6 +1077,16 @@ async fn test_system_tx_effect_on_block_gas_limit() -> Result<(), anyhow::Error>
                 db_config: Default::default(),
                 da_update_interval_ms: 1000,
                 block_production_interval_ms: 500,
+                max_l2_block_state_diff_size: 100 * 1024,
+                empty_block_policy: Default::default(),
+                empty_block_heartbeat_ms: 60_000,
+                commitment_policy: Default::default(),
+                enable_admin_rpc: false,
+                standby: None,
+                lease: None,
+                tx_gossip_peers: vec![],
+                da_payload_mode: Default::default(),
+                relay: None,
             }),
             Some(true),
             DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
@@ -647,6 +1216,149 @@ async fn test_system_tx_effect_on_block_gas_limit() -> Result<(), anyhow::Error>
     Ok(())
 }
 
+/// Run a primary sequencer and a standby sequencer following it.
+/// Check that a forced promotion does not make the standby produce while the primary holds the lease.
+/// Shut the primary down, which releases the lease, and promote the standby.
+/// Check that the standby continues the chain of the primary without committing its blocks again.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_standby_sequencer_promotion() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "standby"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let standby_db_dir = storage_dir.path().join("standby").to_path_buf();
+
+    let mut sequencer_config = create_default_sequencer_config(1000, Some(true), 10);
+    sequencer_config.enable_admin_rpc = true;
+    sequencer_config.lease = Some(LeaseConfig {
+        node_id: "primary".to_string(),
+        start_l1_height: 1,
+        duration_l1_blocks: 6,
+    });
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+    let da_db_dir_cloned = da_db_dir.clone();
+    let primary_config = sequencer_config.clone();
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir_cloned,
+            1000,
+            true,
+            None,
+            Some(primary_config),
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let seq_test_client = init_test_rollup(seq_port).await;
+
+    sequencer_config.standby = Some(StandbyConfig {
+        primary_rpc_url: format!("http://localhost:{}", seq_port.port()),
+        sync_interval_ms: 200,
+    });
+    sequencer_config.lease = Some(LeaseConfig {
+        node_id: "standby".to_string(),
+        start_l1_height: 1,
+        duration_l1_blocks: 6,
+    });
+
+    let (standby_port_tx, standby_port_rx) = tokio::sync::oneshot::channel();
+    let da_db_dir_cloned = da_db_dir.clone();
+    let standby_task = tokio::spawn(async {
+        start_rollup(
+            standby_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            standby_db_dir,
+            da_db_dir_cloned,
+            1000,
+            true,
+            None,
+            Some(sequencer_config),
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+
+    let standby_port = standby_port_rx.await.unwrap();
+    let standby_test_client = make_test_client(standby_port).await;
+
+    for _ in 0..3 {
+        seq_test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&seq_test_client, 3, None).await;
+    wait_for_l2_block(&standby_test_client, 3, None).await;
+
+    let primary_head = seq_test_client
+        .ledger_get_soft_batch_by_number::<MockDaSpec>(3)
+        .await
+        .unwrap();
+    let standby_head = standby_test_client
+        .ledger_get_soft_batch_by_number::<MockDaSpec>(3)
+        .await
+        .unwrap();
+    assert_eq!(primary_head.hash, standby_head.hash);
+    assert_eq!(primary_head.state_root, standby_head.state_root);
+
+    // A forced promotion waits for the lease, which the primary still holds
+    standby_test_client.citrea_promote_to_primary(true).await;
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&seq_test_client, 4, None).await;
+    wait_for_l2_block(&standby_test_client, 4, None).await;
+    sleep(Duration::from_secs(2)).await;
+
+    let primary_head = seq_test_client
+        .ledger_get_soft_batch_by_number::<MockDaSpec>(4)
+        .await
+        .unwrap();
+    let standby_head = standby_test_client
+        .ledger_get_soft_batch_by_number::<MockDaSpec>(4)
+        .await
+        .unwrap();
+    assert_eq!(primary_head.hash, standby_head.hash);
+    assert_eq!(
+        standby_test_client
+            .ledger_get_head_soft_batch_height()
+            .await
+            .unwrap(),
+        Some(4)
+    );
+
+    // The primary commits blocks #1-4 on shutdown, and releases the lease
+    seq_test_client.citrea_shutdown().await;
+    tokio::time::timeout(Duration::from_secs(30), seq_task)
+        .await
+        .expect("Primary did not shut down")
+        .unwrap();
+
+    standby_test_client.citrea_promote_to_primary(false).await;
+    // Wait for the promotion
+    sleep(Duration::from_secs(2)).await;
+
+    standby_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&standby_test_client, 5, None).await;
+
+    let new_head = standby_test_client
+        .ledger_get_soft_batch_by_number::<MockDaSpec>(5)
+        .await
+        .unwrap();
+    assert_eq!(new_head.prev_hash, primary_head.hash);
+
+    standby_task.abort();
+    Ok(())
+}
+
 fn find_subarray(haystack: &[u8], needle: &[u8]) -> Option<usize> {
     haystack
         .windows(needle.len())
@@ -306,6 +306,16 @@ async fn test_prover_sync_with_commitments() -> Result<(), anyhow::Error> {
                 proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
                 db_config: Some(SharedBackupDbConfig::default()),
                 proof_sampling_number: 0,
+                proving_strategy: None,
+                max_parallel_proving_jobs: 1,
+                proving_memory_budget_mb: None,
+                aggregate_proofs: false,
+                proving_backend: Default::default(),
+                remote_prover: None,
+                acceleration: Default::default(),
+                light_client_proofs: false,
+                proof_posting: None,
+                proof_challenges: Default::default(),
             }),
             NodeMode::Prover(seq_port),
             prover_db_dir,
@@ -398,7 +408,7 @@ async fn test_prover_sync_with_commitments() -> Result<(), anyhow::Error> {
 
     let da_data = blob.data.accumulator();
 
-    let proof: DaData = borsh::BorshDeserialize::try_from_slice(da_data).unwrap();
+    let proof = DaData::decode(da_data).unwrap();
 
     assert!(matches!(proof, DaData::ZKProof(_)));
 
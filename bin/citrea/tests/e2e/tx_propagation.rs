@@ -67,6 +67,68 @@ async fn test_full_node_send_tx() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Txs sent to the sequencer are in the pending block until it is built,
+/// full nodes serve the pending block of the sequencer.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_pending_block_is_built_by_the_sequencer() -> Result<(), anyhow::Error> {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = storage_dir.path().join("full-node").to_path_buf();
+
+    let (seq_test_client, full_node_test_client, seq_task, full_node_task, addr) =
+        initialize_test(TestConfig {
+            da_path: da_db_dir,
+            sequencer_path: sequencer_db_dir,
+            fullnode_path: fullnode_db_dir,
+            ..Default::default()
+        })
+        .await;
+
+    let tx_hash = *seq_test_client
+        .send_eth(addr, None, None, None, 0u128)
+        .await
+        .unwrap()
+        .tx_hash();
+
+    for test_client in [&seq_test_client, &full_node_test_client] {
+        let pending = test_client
+            .eth_get_block_by_number(Some(BlockNumberOrTag::Pending))
+            .await;
+        assert_eq!(pending.header.number.unwrap(), 1);
+        assert_eq!(pending.transactions.as_hashes().unwrap(), &[tx_hash]);
+        let latest_nonce = test_client
+            .eth_get_transaction_count(seq_test_client.from_addr, Some(BlockNumberOrTag::Latest))
+            .await
+            .unwrap();
+        let pending_nonce = test_client
+            .eth_get_transaction_count(seq_test_client.from_addr, Some(BlockNumberOrTag::Pending))
+            .await
+            .unwrap();
+        assert_eq!(pending_nonce, latest_nonce + 1);
+    }
+
+    seq_test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&full_node_test_client, 1, None).await;
+
+    let latest = full_node_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Latest))
+        .await;
+    assert_eq!(latest.transactions.as_hashes().unwrap(), &[tx_hash]);
+    let pending = full_node_test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Pending))
+        .await;
+    assert_eq!(pending.header.number.unwrap(), 2);
+    assert!(pending.transactions.as_hashes().unwrap().is_empty());
+
+    seq_task.abort();
+    full_node_task.abort();
+
+    Ok(())
+}
+
 /// Full node receives transaction from RPC.
 /// Sends it to the sequencer.
 /// We send eth_getTransactionByHash RPC to the full node.
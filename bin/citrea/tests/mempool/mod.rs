@@ -144,6 +144,32 @@ async fn test_nonce_too_high() {
     seq_task.abort();
 }
 
+/// Transaction with nonce too far ahead of account's nonce should not be accepted by mempool.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_nonce_gap_too_large() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+    let (seq_task, test_client) = initialize_test(sequencer_db_dir, da_db_dir).await;
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+
+    // default max account slots is 16
+    let _pending = test_client
+        .send_eth(addr, None, None, Some(16), 0u128)
+        .await
+        .unwrap();
+
+    let res = test_client
+        .send_eth(addr, None, None, Some(17), 0u128)
+        .await;
+    assert!(res.unwrap_err().to_string().contains("nonce too high"));
+
+    seq_task.abort();
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_order_by_fee() {
     // citrea::initialize_logging();
@@ -466,3 +492,53 @@ async fn test_same_nonce_tx_replacement() {
 
     seq_task.abort();
 }
+
+/// The account queue tells apart the txs which can be included in the next block
+/// and the ones stuck behind a nonce gap.
+#[tokio::test(flavor = "multi_thread")]
+async fn test_account_queue_shows_nonce_gaps() {
+    let db_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+    let (seq_task, test_client) = initialize_test(sequencer_db_dir, da_db_dir).await;
+
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+
+    for nonce in [0, 1, 4] {
+        test_client
+            .send_eth(addr, None, None, Some(nonce), 0u128)
+            .await
+            .unwrap();
+    }
+
+    let queue = test_client
+        .citrea_get_account_queue(test_client.from_addr)
+        .await;
+    assert_eq!(queue["accountNonce"], 0);
+    let pending_nonces: Vec<_> = queue["pending"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|tx| tx["nonce"].as_u64().unwrap())
+        .collect();
+    assert_eq!(pending_nonces, vec![0, 1]);
+    assert_eq!(queue["queued"][0]["nonce"], 4);
+    assert_eq!(queue["queued"][0]["reason"], "nonceGap");
+    assert_eq!(queue["nonceGaps"], serde_json::json!([[2, 3]]));
+
+    // Filling the gap makes all txs executable
+    for nonce in [2, 3] {
+        test_client
+            .send_eth(addr, None, None, Some(nonce), 0u128)
+            .await
+            .unwrap();
+    }
+    let queue = test_client
+        .citrea_get_account_queue(test_client.from_addr)
+        .await;
+    assert_eq!(queue["pending"].as_array().unwrap().len(), 5);
+    assert!(queue["queued"].as_array().unwrap().is_empty());
+    assert!(queue["nonceGaps"].as_array().unwrap().is_empty());
+
+    seq_task.abort();
+}
@@ -1,13 +1,15 @@
+use std::str::FromStr;
 use std::time::Duration;
 
-use borsh::BorshDeserialize;
+use citrea_sequencer::DaPayloadMode;
 use citrea_stf::genesis_config::GenesisPaths;
+use reth_primitives::{Address, BlockNumberOrTag, U256};
 use rs_merkle::algorithms::Sha256;
-use rs_merkle::MerkleTree;
+use rs_merkle::{MerkleProof, MerkleTree};
 use shared_backup_db::{PostgresConnector, SharedBackupDbConfig};
 use sov_mock_da::{MockAddress, MockDaService, MockDaSpec};
 use sov_modules_api::BlobReaderTrait;
-use sov_rollup_interface::da::DaData;
+use sov_rollup_interface::da::{CommitmentWithStateDiff, DaData, SequencerCommitment};
 use sov_rollup_interface::services::da::DaService;
 use sov_stf_runner::ProverConfig;
 
@@ -150,7 +152,7 @@ async fn check_sequencer_commitment(
 
     let data = blob.full_data();
 
-    let commitment = DaData::try_from_slice(data).unwrap();
+    let commitment = DaData::decode(data).unwrap();
 
     matches!(commitment, DaData::SequencerCommitment(_));
 
@@ -183,6 +185,172 @@ async fn check_sequencer_commitment(
     assert_eq!(commitment.merkle_root, merkle_tree.root().unwrap());
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn sequencer_commits_on_shutdown() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+    let mut sequencer_config = create_default_sequencer_config(1000, Some(true), 10);
+    sequencer_config.enable_admin_rpc = true;
+
+    let da_db_dir_cloned = da_db_dir.clone();
+    let seq_task = tokio::spawn(async move {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir_cloned,
+            1000,
+            true,
+            None,
+            Some(sequencer_config),
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await;
+
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    // publish 3 soft confirmations, far below the commitment threshold
+    for _ in 0..3 {
+        test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&test_client, 3, None).await;
+
+    let mut soft_batch_hashes = vec![];
+    for i in 1..=3 {
+        soft_batch_hashes.push(
+            test_client
+                .ledger_get_soft_batch_by_number::<MockDaSpec>(i)
+                .await
+                .unwrap()
+                .hash,
+        );
+    }
+
+    test_client.citrea_shutdown().await;
+
+    // The sequencer exits once the commitment is on DA
+    tokio::time::timeout(Duration::from_secs(30), seq_task)
+        .await
+        .expect("Sequencer did not shut down")
+        .unwrap();
+
+    let last_finalized_height = da_service
+        .get_last_finalized_block_header()
+        .await
+        .unwrap()
+        .height;
+    let block = da_service
+        .get_block_at(last_finalized_height)
+        .await
+        .unwrap();
+    let mut blobs = da_service.extract_relevant_blobs(&block);
+    assert_eq!(blobs.len(), 1);
+
+    let DaData::SequencerCommitment(commitment) =
+        DaData::decode(blobs.pop().unwrap().full_data()).unwrap()
+    else {
+        panic!("Expected SequencerCommitment");
+    };
+
+    let merkle_tree = MerkleTree::<Sha256>::from_leaves(soft_batch_hashes.as_slice());
+    assert_eq!(commitment.l2_start_block_number, 1);
+    assert_eq!(commitment.l2_end_block_number, 3);
+    assert_eq!(commitment.merkle_root, merkle_tree.root().unwrap());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sequencer_restores_mempool_after_shutdown() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+
+    let start_sequencer = || {
+        let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+        let mut sequencer_config = create_default_sequencer_config(1000, Some(true), 10);
+        sequencer_config.enable_admin_rpc = true;
+        let sequencer_db_dir = sequencer_db_dir.clone();
+        let da_db_dir = da_db_dir.clone();
+        let seq_task = tokio::spawn(async move {
+            start_rollup(
+                seq_port_tx,
+                GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+                None,
+                NodeMode::SequencerNode,
+                sequencer_db_dir,
+                da_db_dir,
+                1000,
+                true,
+                None,
+                Some(sequencer_config),
+                Some(true),
+                DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+            )
+            .await;
+        });
+        (seq_task, seq_port_rx)
+    };
+
+    let (seq_task, seq_port_rx) = start_sequencer();
+    let test_client = make_test_client(seq_port_rx.await.unwrap()).await;
+
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 1, None).await;
+
+    // The tx is still in the mempool when the sequencer shuts down
+    let addr = Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").unwrap();
+    let tx_hash = *test_client
+        .send_eth(addr, None, None, None, 0u128)
+        .await
+        .unwrap()
+        .tx_hash();
+    test_client.citrea_shutdown().await;
+    tokio::time::timeout(Duration::from_secs(30), seq_task)
+        .await
+        .expect("Sequencer did not shut down")
+        .unwrap();
+
+    let (_seq_task, seq_port_rx) = start_sequencer();
+    let test_client = make_test_client(seq_port_rx.await.unwrap()).await;
+
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 2, None).await;
+
+    let receipt = test_client
+        .eth_get_transaction_receipt(tx_hash)
+        .await
+        .expect("Tx of the mempool was not restored");
+    assert_eq!(receipt.block_number, Some(2));
+
+    // The L2 block committed on shutdown is not committed again on restart
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+    let mut commitments = vec![];
+    for height in 1..=da_service.get_height().await {
+        let block = da_service.get_block_at(height).await.unwrap();
+        for mut blob in da_service.extract_relevant_blobs(&block) {
+            if let Ok(DaData::SequencerCommitment(commitment)) = DaData::decode(blob.full_data()) {
+                commitments.push(commitment);
+            }
+        }
+    }
+    assert_eq!(commitments.len(), 1);
+    assert_eq!(commitments[0].l2_start_block_number, 1);
+    assert_eq!(commitments[0].l2_end_block_number, 1);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn check_commitment_in_offchain_db() {
     // citrea::initialize_logging(tracing::Level::INFO);
@@ -351,6 +519,201 @@ async fn test_ledger_get_commitments_on_slot() {
         .unwrap();
     assert_eq!(commitments_hash, commitments);
 
+    let commitment_by_l2_height = full_node_test_client
+        .citrea_get_sequencer_commitment_by_l2_height(2)
+        .await
+        .unwrap();
+    assert_eq!(commitment_by_l2_height.found_in_l1, 3);
+    assert_eq!(commitment_by_l2_height.l2_start_block_number, 1);
+    assert_eq!(commitment_by_l2_height.l2_end_block_number, 4);
+    assert_eq!(
+        full_node_test_client
+            .citrea_get_sequencer_commitment_by_txid(commitment_by_l2_height.da_tx_id)
+            .await,
+        Some(commitment_by_l2_height.clone())
+    );
+    assert_eq!(
+        full_node_test_client
+            .citrea_get_sequencer_commitment_by_l2_height(5)
+            .await,
+        None
+    );
+
+    let inclusion_proof = full_node_test_client
+        .citrea_get_block_inclusion_proof(2)
+        .await
+        .unwrap();
+    assert_eq!(inclusion_proof.commitment, commitment_by_l2_height);
+    assert_eq!(inclusion_proof.leaf_index, 1);
+    assert_eq!(inclusion_proof.leaf_count, 4);
+    let soft_batch = full_node_test_client
+        .ledger_get_soft_batch_by_number::<MockDaSpec>(2)
+        .await
+        .unwrap();
+    assert_eq!(inclusion_proof.soft_confirmation_hash, soft_batch.hash);
+    let proof_hashes: Vec<[u8; 32]> = inclusion_proof.proof.iter().map(|hash| hash.0).collect();
+    assert!(MerkleProof::<Sha256>::new(proof_hashes).verify(
+        commitment_by_l2_height.merkle_root,
+        &[inclusion_proof.leaf_index as usize],
+        &[inclusion_proof.soft_confirmation_hash],
+        inclusion_proof.leaf_count as usize,
+    ));
+    assert_eq!(
+        full_node_test_client
+            .citrea_get_block_inclusion_proof(5)
+            .await,
+        None
+    );
+
+    seq_task.abort();
+    full_node_task.abort();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_withdrawal_proof() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = db_dir.path().join("full-node").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let da_db_dir_cloned = da_db_dir.clone();
+    let seq_task = tokio::spawn(async {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir_cloned,
+            4,
+            true,
+            None,
+            None,
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await;
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+
+    let full_node_task = tokio::spawn(async move {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::FullNode(seq_port),
+            fullnode_db_dir,
+            da_db_dir,
+            4,
+            true,
+            None,
+            None,
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+
+    let full_node_port = full_node_port_rx.await.unwrap();
+
+    let full_node_test_client = make_test_client(full_node_port).await;
+    da_service.publish_test_block().await.unwrap();
+    wait_for_l1_block(&da_service, 2, None).await;
+
+    // batchWithdraw(bytes32[]) of 0.01 cBTC per Bitcoin address
+    let bitcoin_addresses = [[1u8; 32], [2u8; 32], [3u8; 32]];
+    let mut data = hex::decode("59c19cee").unwrap();
+    data.extend(U256::from(32).to_be_bytes::<32>());
+    data.extend(U256::from(bitcoin_addresses.len()).to_be_bytes::<32>());
+    for bitcoin_address in &bitcoin_addresses {
+        data.extend(bitcoin_address);
+    }
+    let withdraw_tx = test_client
+        .contract_transaction_with_custom_fee(
+            Address::from_str("0x3100000000000000000000000000000000000002").unwrap(),
+            data,
+            1_000_000_000,
+            100_000_000_000,
+            Some(30_000_000_000_000_000),
+            None,
+        )
+        .await;
+
+    test_client.send_publish_batch_request().await;
+    withdraw_tx.get_receipt().await.unwrap();
+    test_client.send_publish_batch_request().await;
+    test_client.send_publish_batch_request().await;
+    test_client.send_publish_batch_request().await;
+    wait_for_l1_block(&da_service, 3, None).await;
+
+    // full node gets the commitment
+    test_client.send_publish_batch_request().await;
+
+    wait_for_l2_block(&full_node_test_client, 5, None).await;
+
+    let commitment = full_node_test_client
+        .citrea_get_sequencer_commitment_by_l2_height(1)
+        .await
+        .unwrap();
+    assert_eq!(commitment.l2_start_block_number, 1);
+    assert_eq!(commitment.l2_end_block_number, 4);
+
+    let withdrawal_proofs = [
+        full_node_test_client
+            .citrea_get_withdrawal_proof(0)
+            .await
+            .unwrap(),
+        full_node_test_client
+            .citrea_get_withdrawal_proof(1)
+            .await
+            .unwrap(),
+        full_node_test_client
+            .citrea_get_withdrawal_proof(2)
+            .await
+            .unwrap(),
+    ];
+    for (index, withdrawal_proof) in withdrawal_proofs.iter().enumerate() {
+        assert_eq!(withdrawal_proof.index, index as u64);
+        assert_eq!(withdrawal_proof.l2_height, 1);
+        assert_eq!(withdrawal_proof.bitcoin_address, bitcoin_addresses[index]);
+        assert_eq!(withdrawal_proof.leaf_index, index as u64);
+        assert_eq!(withdrawal_proof.leaf_count, 3);
+        assert_eq!(withdrawal_proof.commitment, commitment);
+        assert_eq!(
+            withdrawal_proof.withdrawal_root,
+            withdrawal_proofs[0].withdrawal_root
+        );
+
+        let proof_hashes: Vec<[u8; 32]> =
+            withdrawal_proof.proof.iter().map(|hash| hash.0).collect();
+        assert!(MerkleProof::<Sha256>::new(proof_hashes).verify(
+            withdrawal_proof.withdrawal_root,
+            &[withdrawal_proof.leaf_index as usize],
+            &[withdrawal_proof.leaf],
+            withdrawal_proof.leaf_count as usize,
+        ));
+    }
+    let leaves: Vec<[u8; 32]> = withdrawal_proofs.iter().map(|proof| proof.leaf).collect();
+    assert_eq!(
+        MerkleTree::<Sha256>::from_leaves(&leaves).root(),
+        Some(withdrawal_proofs[0].withdrawal_root)
+    );
+
+    // not initiated yet
+    assert_eq!(
+        full_node_test_client.citrea_get_withdrawal_proof(3).await,
+        None
+    );
+
     seq_task.abort();
     full_node_task.abort();
 }
@@ -398,7 +761,17 @@ async fn test_ledger_get_commitments_on_slot_prover() {
             Some(ProverConfig {
                 proving_mode: sov_stf_runner::ProverGuestRunConfig::Execute,
                 proof_sampling_number: 0,
+                proving_strategy: None,
                 db_config: None,
+                max_parallel_proving_jobs: 1,
+                proving_memory_budget_mb: None,
+                aggregate_proofs: false,
+                proving_backend: Default::default(),
+                remote_prover: None,
+                acceleration: Default::default(),
+                light_client_proofs: false,
+                proof_posting: None,
+                proof_challenges: Default::default(),
             }),
             NodeMode::Prover(seq_port),
             prover_db_dir,
@@ -460,3 +833,403 @@ async fn test_ledger_get_commitments_on_slot_prover() {
     seq_task.abort();
     prover_node_task.abort();
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sequencer_posts_state_diff_with_commitments() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+    let mut sequencer_config = create_default_sequencer_config(4, Some(true), 10);
+    sequencer_config.da_payload_mode = DaPayloadMode::StateDiff;
+
+    let da_db_dir_cloned = da_db_dir.clone();
+    let seq_task = tokio::spawn(async move {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir_cloned,
+            4,
+            true,
+            None,
+            Some(sequencer_config),
+            Some(true),
+            10,
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await;
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    // The 4th L2 block triggers a commitment, which creates a new L1 block
+    for _ in 0..4 {
+        test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&test_client, 4, None).await;
+    wait_for_l1_block(&da_service, 2, None).await;
+
+    let block = da_service.get_block_at(2).await.unwrap();
+    let mut blobs = da_service.extract_relevant_blobs(&block);
+    assert_eq!(blobs.len(), 1);
+
+    let DaData::CommitmentWithStateDiff(data) = DaData::decode(blobs[0].full_data()).unwrap()
+    else {
+        panic!("Expected the commitment to be posted with its state diff");
+    };
+    assert_eq!(data.commitment.l2_start_block_number, 1);
+    assert_eq!(data.commitment.l2_end_block_number, 4);
+
+    // The diff is sorted by key, without duplicates
+    assert!(!data.state_diff.is_empty());
+    assert!(data.state_diff.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+    seq_task.abort();
+}
+
+/// Full nodes check the state diffs posted with commitments against the execution of their L2 blocks
+#[tokio::test(flavor = "multi_thread")]
+async fn full_node_rejects_mismatching_state_diff() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = db_dir.path().join("full-node").to_path_buf();
+
+    // The commitments are posted by the test
+    let min_soft_confirmations_per_commitment = 1_000;
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+    let da_db_dir_cloned = da_db_dir.clone();
+    let seq_task = tokio::spawn(async move {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir_cloned,
+            min_soft_confirmations_per_commitment,
+            true,
+            None,
+            None,
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await;
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+    let da_db_dir_cloned = da_db_dir.clone();
+    let full_node_task = tokio::spawn(async move {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::FullNode(seq_port),
+            fullnode_db_dir,
+            da_db_dir_cloned,
+            min_soft_confirmations_per_commitment,
+            true,
+            None,
+            None,
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+    let full_node_port = full_node_port_rx.await.unwrap();
+    let full_node_test_client = make_test_client(full_node_port).await;
+
+    for _ in 0..4 {
+        test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, 4, None).await;
+
+    let mut soft_batch_hashes = vec![];
+    for l2_height in 1..=4 {
+        let soft_batch = full_node_test_client
+            .ledger_get_soft_batch_by_number::<MockDaSpec>(l2_height)
+            .await
+            .unwrap();
+        soft_batch_hashes.push(soft_batch.hash);
+    }
+    let commitment = SequencerCommitment {
+        merkle_root: MerkleTree::<Sha256>::from_leaves(&soft_batch_hashes)
+            .root()
+            .unwrap(),
+        l2_start_block_number: 1,
+        l2_end_block_number: 4,
+    };
+
+    // Posted with the sequencer DA key
+    let sequencer_da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+    let mismatching = DaData::CommitmentWithStateDiff(CommitmentWithStateDiff {
+        commitment: commitment.clone(),
+        state_diff: vec![(b"key".to_vec(), Some(b"value".to_vec()))],
+    });
+    sequencer_da_service
+        .send_transaction(&mismatching.encode())
+        .await
+        .unwrap();
+    let mismatching_l1_height = sequencer_da_service.get_height().await;
+    sequencer_da_service
+        .send_transaction(&DaData::SequencerCommitment(commitment).encode())
+        .await
+        .unwrap();
+    let l1_height = sequencer_da_service.get_height().await;
+
+    // L2 blocks on the new L1 blocks make the full node process them
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&full_node_test_client, 5, None).await;
+
+    let mut commitments = None;
+    for _ in 0..100 {
+        commitments = full_node_test_client
+            .ledger_get_sequencer_commitments_on_slot_by_number(l1_height)
+            .await
+            .unwrap();
+        if commitments.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(commitments.unwrap().len(), 1);
+    assert_eq!(
+        full_node_test_client
+            .ledger_get_sequencer_commitments_on_slot_by_number(mismatching_l1_height)
+            .await
+            .unwrap(),
+        None
+    );
+
+    seq_task.abort();
+    full_node_task.abort();
+}
+
+/// Full nodes skip commitments which are posted again on a later L1 block
+#[tokio::test(flavor = "multi_thread")]
+async fn full_node_skips_duplicate_commitments() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir = tempdir_with_children(&["DA", "sequencer", "full-node"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+    let fullnode_db_dir = db_dir.path().join("full-node").to_path_buf();
+
+    // The commitments are posted by the test
+    let min_soft_confirmations_per_commitment = 1_000;
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+    let da_db_dir_cloned = da_db_dir.clone();
+    let seq_task = tokio::spawn(async move {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir_cloned,
+            min_soft_confirmations_per_commitment,
+            true,
+            None,
+            None,
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await;
+
+    let (full_node_port_tx, full_node_port_rx) = tokio::sync::oneshot::channel();
+    let da_db_dir_cloned = da_db_dir.clone();
+    let full_node_task = tokio::spawn(async move {
+        start_rollup(
+            full_node_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::FullNode(seq_port),
+            fullnode_db_dir,
+            da_db_dir_cloned,
+            min_soft_confirmations_per_commitment,
+            true,
+            None,
+            None,
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+    let full_node_port = full_node_port_rx.await.unwrap();
+    let full_node_test_client = make_test_client(full_node_port).await;
+
+    for _ in 0..5 {
+        test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&full_node_test_client, 5, None).await;
+
+    let mut soft_batch_hashes = vec![];
+    for l2_height in 1..=5 {
+        let soft_batch = full_node_test_client
+            .ledger_get_soft_batch_by_number::<MockDaSpec>(l2_height)
+            .await
+            .unwrap();
+        soft_batch_hashes.push(soft_batch.hash);
+    }
+    let commitment_of = |l2_range: std::ops::RangeInclusive<u64>| SequencerCommitment {
+        merkle_root: MerkleTree::<Sha256>::from_leaves(
+            &soft_batch_hashes[*l2_range.start() as usize - 1..*l2_range.end() as usize],
+        )
+        .root()
+        .unwrap(),
+        l2_start_block_number: *l2_range.start(),
+        l2_end_block_number: *l2_range.end(),
+    };
+
+    // Posted with the sequencer DA key
+    let sequencer_da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+    let mut l1_heights = vec![];
+    for commitment in [
+        commitment_of(1..=4),
+        commitment_of(1..=4),
+        commitment_of(5..=5),
+    ] {
+        sequencer_da_service
+            .send_transaction(&DaData::SequencerCommitment(commitment).encode())
+            .await
+            .unwrap();
+        l1_heights.push(sequencer_da_service.get_height().await);
+    }
+
+    // L2 blocks on the new L1 blocks make the full node process them
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&full_node_test_client, 6, None).await;
+
+    // The commitment after the duplicate is processed
+    let mut commitments = None;
+    for _ in 0..100 {
+        commitments = full_node_test_client
+            .ledger_get_sequencer_commitments_on_slot_by_number(l1_heights[2])
+            .await
+            .unwrap();
+        if commitments.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(commitments.unwrap().len(), 1);
+    assert_eq!(
+        full_node_test_client
+            .ledger_get_sequencer_commitments_on_slot_by_number(l1_heights[0])
+            .await
+            .unwrap()
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(
+        full_node_test_client
+            .ledger_get_sequencer_commitments_on_slot_by_number(l1_heights[1])
+            .await
+            .unwrap(),
+        None
+    );
+
+    seq_task.abort();
+    full_node_task.abort();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn block_tags_follow_commitments() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let db_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = db_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = db_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let da_db_dir_cloned = da_db_dir.clone();
+    let seq_task = tokio::spawn(async move {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir_cloned,
+            4,
+            true,
+            None,
+            None,
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await;
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    for _ in 0..3 {
+        test_client.send_publish_batch_request().await;
+    }
+    wait_for_l2_block(&test_client, 3, None).await;
+
+    // Nothing is committed to DA yet
+    assert!(test_client
+        .eth_get_balance(test_client.from_addr, Some(BlockNumberOrTag::Safe))
+        .await
+        .is_err());
+
+    // The 4th L2 block triggers a commitment
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 4, None).await;
+    wait_for_l1_block(&da_service, 2, None).await;
+    // The commitment is recorded once DA accepted it
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    test_client.send_publish_batch_request().await;
+    wait_for_l2_block(&test_client, 5, None).await;
+
+    let safe = test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Safe))
+        .await;
+    assert_eq!(safe.header.number.unwrap(), 4);
+    // The block the sequencer builds next
+    let pending = test_client
+        .eth_get_block_by_number(Some(BlockNumberOrTag::Pending))
+        .await;
+    assert_eq!(pending.header.number.unwrap(), 6);
+    assert_eq!(pending.header.hash, None);
+
+    // State methods agree with the block methods
+    assert_eq!(
+        test_client
+            .eth_get_balance(test_client.from_addr, Some(BlockNumberOrTag::Safe))
+            .await
+            .unwrap(),
+        test_client
+            .eth_get_balance(test_client.from_addr, Some(BlockNumberOrTag::Number(4)))
+            .await
+            .unwrap()
+    );
+
+    // The sequencer does not verify proofs
+    assert!(test_client
+        .eth_get_balance(test_client.from_addr, Some(BlockNumberOrTag::Finalized))
+        .await
+        .is_err());
+
+    seq_task.abort();
+}
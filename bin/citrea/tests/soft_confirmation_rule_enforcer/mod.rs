@@ -1,7 +1,12 @@
+use std::str::FromStr;
 use std::time::Duration;
 
+use citrea_evm::DevSigner;
 use citrea_stf::genesis_config::GenesisPaths;
+use reth_primitives::{keccak256, Address, Transaction, TxEip1559, TxKind, U256};
 use sov_mock_da::{MockAddress, MockDaService};
+use sov_rollup_interface::da::DaData;
+use sov_rollup_interface::services::da::DaService;
 use tokio::time::sleep;
 
 use crate::evm::make_test_client;
@@ -9,7 +14,10 @@ use crate::evm::make_test_client;
 use crate::test_helpers::{
     start_rollup, tempdir_with_children, wait_for_l1_block, wait_for_l2_block, NodeMode,
 };
-use crate::{DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT, DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT};
+use crate::{
+    DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT, DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+    TEST_DATA_GENESIS_PATH,
+};
 
 /// Transaction with equal nonce to last tx should not be accepted by mempool.
 #[tokio::test(flavor = "multi_thread")]
@@ -92,3 +100,92 @@ async fn too_many_l2_block_per_l1_block() {
         }
     }
 }
+
+/// A transaction posted to the DA layer has to be included by the sequencer,
+/// even though it was never sent to its mempool.
+#[tokio::test(flavor = "multi_thread")]
+async fn forced_tx_is_included_by_sequencer() {
+    // citrea::initialize_logging(tracing::Level::INFO);
+
+    let storage_dir = tempdir_with_children(&["DA", "sequencer"]);
+    let da_db_dir = storage_dir.path().join("DA").to_path_buf();
+    let sequencer_db_dir = storage_dir.path().join("sequencer").to_path_buf();
+
+    let (seq_port_tx, seq_port_rx) = tokio::sync::oneshot::channel();
+
+    let da_db_dir_cloned = da_db_dir.clone();
+    tokio::spawn(async move {
+        start_rollup(
+            seq_port_tx,
+            GenesisPaths::from_dir(TEST_DATA_GENESIS_PATH),
+            None,
+            NodeMode::SequencerNode,
+            sequencer_db_dir,
+            da_db_dir_cloned,
+            DEFAULT_MIN_SOFT_CONFIRMATIONS_PER_COMMITMENT,
+            true,
+            None,
+            None,
+            Some(true),
+            DEFAULT_DEPOSIT_MEMPOOL_FETCH_LIMIT,
+        )
+        .await;
+    });
+    let seq_port = seq_port_rx.await.unwrap();
+    let test_client = make_test_client(seq_port).await;
+
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), &da_db_dir);
+
+    let secret_key = secp256k1::SecretKey::from_str(
+        "ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+    )
+    .unwrap();
+    let signer = DevSigner::new(vec![secret_key]);
+    let sender = signer.signers()[0];
+    let tx = Transaction::Eip1559(TxEip1559 {
+        chain_id: test_client.chain_id,
+        nonce: 0,
+        gas_limit: 21_000,
+        max_fee_per_gas: 100_000_000_000,
+        to: TxKind::Call(Address::from_str("0x0000000000000000000000000000000000000042").unwrap()),
+        value: U256::from(1),
+        ..Default::default()
+    });
+    let rlp = signer
+        .sign_transaction(tx, sender)
+        .unwrap()
+        .envelope_encoded()
+        .to_vec();
+    let tx_hash = keccak256(&rlp);
+
+    da_service
+        .send_transaction(&DaData::ForcedTransaction(rlp).encode())
+        .await
+        .unwrap();
+
+    // Wait for the sequencer DA update interval to pass for it to recognize
+    // the new DA block.
+    sleep(Duration::from_secs(1)).await;
+
+    // The tx is in the first L2 block on the DA block it was posted on,
+    // after the sequencer catches up with the DA blocks before it
+    let mut tx = None;
+    for l2_height in 1..=5 {
+        test_client.send_publish_batch_request().await;
+        wait_for_l2_block(&test_client, l2_height, None).await;
+        tx = test_client.eth_get_transaction_by_hash(tx_hash, None).await;
+        if tx.is_some() {
+            break;
+        }
+    }
+
+    let tx = tx.expect("Forced transaction was not included");
+    assert!(tx.block_number.is_some());
+    assert_eq!(
+        test_client
+            .eth_get_transaction_count(sender, None)
+            .await
+            .unwrap(),
+        1
+    );
+}
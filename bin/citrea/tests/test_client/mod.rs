@@ -20,8 +20,9 @@ use reth_rpc_types::trace::geth::{GethDebugTracingOptions, GethTrace};
 use reth_rpc_types::RichBlock;
 use sequencer_client::GetSoftBatchResponse;
 use sov_rollup_interface::rpc::{
-    LastVerifiedProofResponse, ProofResponse, SequencerCommitmentResponse, SoftBatchResponse,
-    SoftConfirmationStatus, VerifiedProofResponse,
+    BlockInclusionProofResponse, IndexedSequencerCommitmentResponse, LastVerifiedProofResponse,
+    ProofResponse, SequencerCommitmentResponse, SoftBatchResponse, SoftConfirmationStatus,
+    VerifiedProofResponse, WithdrawalProofResponse,
 };
 
 pub const MAX_FEE_PER_GAS: u128 = 1000000001;
@@ -97,6 +98,22 @@ impl TestClient {
         tokio::time::sleep(Duration::from_millis(100)).await;
     }
 
+    pub(crate) async fn citrea_shutdown(&self) {
+        let _: () = self
+            .http_client
+            .request("citrea_shutdown", rpc_params![])
+            .await
+            .unwrap();
+    }
+
+    pub(crate) async fn citrea_promote_to_primary(&self, force: bool) {
+        let _: () = self
+            .http_client
+            .request("citrea_promoteToPrimary", rpc_params![force])
+            .await
+            .unwrap();
+    }
+
     pub(crate) async fn sync_nonce(&self) {
         let nonce = self
             .eth_get_transaction_count(self.from_addr, None)
@@ -753,6 +770,105 @@ impl TestClient {
             .await
             .unwrap()
     }
+
+    pub(crate) async fn citrea_get_account_queue(&self, address: Address) -> serde_json::Value {
+        self.http_client
+            .request("citrea_getAccountQueue", rpc_params![address])
+            .await
+            .unwrap()
+    }
+
+    /// Sends the RLP encoded txs as a bundle, returns the bundle hash
+    pub(crate) async fn eth_send_bundle(
+        &self,
+        txs: Vec<Bytes>,
+        block_number: Option<u64>,
+    ) -> Result<B256, jsonrpsee::core::ClientError> {
+        let request = serde_json::json!({
+            "txs": txs,
+            "blockNumber": block_number.map(U64::from),
+        });
+        let response: serde_json::Value = self
+            .http_client
+            .request("eth_sendBundle", rpc_params![request])
+            .await?;
+        Ok(serde_json::from_value(response["bundleHash"].clone()).unwrap())
+    }
+
+    pub(crate) async fn citrea_relay_meta_transaction(
+        &self,
+        request: serde_json::Value,
+    ) -> Result<B256, jsonrpsee::core::ClientError> {
+        self.http_client
+            .request("citrea_relayMetaTransaction", rpc_params![request])
+            .await
+    }
+
+    pub(crate) async fn citrea_simulate_block(
+        &self,
+        txs: Vec<Bytes>,
+    ) -> Result<serde_json::Value, jsonrpsee::core::ClientError> {
+        self.http_client
+            .request("citrea_simulateBlock", rpc_params![txs])
+            .await
+    }
+
+    pub(crate) async fn citrea_get_proven_height(&self) -> Option<u64> {
+        self.http_client
+            .request("citrea_getProvenHeight", rpc_params![])
+            .await
+            .unwrap()
+    }
+
+    #[allow(dead_code)]
+    pub(crate) async fn citrea_get_sequencer_commitment_by_txid(
+        &self,
+        txid: [u8; 32],
+    ) -> Option<IndexedSequencerCommitmentResponse> {
+        self.http_client
+            .request(
+                "citrea_getSequencerCommitmentByTxid",
+                rpc_params![hex::encode(txid)],
+            )
+            .await
+            .unwrap()
+    }
+
+    #[allow(dead_code)]
+    pub(crate) async fn citrea_get_sequencer_commitment_by_l2_height(
+        &self,
+        l2_height: u64,
+    ) -> Option<IndexedSequencerCommitmentResponse> {
+        self.http_client
+            .request(
+                "citrea_getSequencerCommitmentByL2Height",
+                rpc_params![l2_height],
+            )
+            .await
+            .unwrap()
+    }
+
+    #[allow(dead_code)]
+    pub(crate) async fn citrea_get_block_inclusion_proof(
+        &self,
+        l2_height: u64,
+    ) -> Option<BlockInclusionProofResponse> {
+        self.http_client
+            .request("citrea_getBlockInclusionProof", rpc_params![l2_height])
+            .await
+            .unwrap()
+    }
+
+    #[allow(dead_code)]
+    pub(crate) async fn citrea_get_withdrawal_proof(
+        &self,
+        index: u64,
+    ) -> Option<WithdrawalProofResponse> {
+        self.http_client
+            .request("citrea_getWithdrawalProof", rpc_params![index])
+            .await
+            .unwrap()
+    }
 }
 
 #[derive(serde::Deserialize, Debug)]
@@ -132,9 +132,14 @@ pub fn create_default_rollup_config(
             ],
             sequencer_da_pub_key: vec![0; 32],
             prover_da_pub_key: vec![0; 32],
+            sequencer_da_pub_key_rotations: vec![],
         },
         storage: StorageConfig {
             path: rollup_path.to_path_buf(),
+            ledger_db: Default::default(),
+            state_db: Default::default(),
+            replica: None,
+            state_cache_size: None,
         },
         rpc: RpcConfig {
             bind_host: "127.0.0.1".into(),
@@ -145,12 +150,28 @@ pub fn create_default_rollup_config(
             batch_requests_limit: 50,
             enable_subscriptions: true,
             max_subscriptions_per_connection: 100,
+            trace_cache_size: None,
+            operator: None,
+            public_operator_methods: true,
+            sequencer_tx_fallback: true,
+            sequencer_tx_fallback_negative_cache_ms: 1000,
+            method_timeouts_ms: Default::default(),
+            default_method_timeout_ms: None,
         },
         runner: match node_mode {
             NodeMode::FullNode(socket_addr) | NodeMode::Prover(socket_addr) => Some(RunnerConfig {
                 include_tx_body,
-                sequencer_client_url: format!("http://localhost:{}", socket_addr.port()),
+                sequencer_client_url: format!("http://localhost:{}", socket_addr.port()).into(),
                 accept_public_input_as_proven: Some(true),
+                pruning_config: Default::default(),
+                proving_strategy: Default::default(),
+                da_monitor: None,
+                watchtower: None,
+                parallel_evm_execution: false,
+                diagnostics_dir: None,
+                challenge_window: None,
+                backfill_history: false,
+                backup_dir: None,
             }),
             NodeMode::SequencerNode => None,
         },
@@ -159,6 +180,8 @@ pub fn create_default_rollup_config(
             db_path: da_path.to_path_buf(),
         },
         sync_blocks_count: 10,
+        log_level: None,
+        telemetry: None,
     }
 }
 
@@ -177,6 +200,16 @@ pub fn create_default_sequencer_config(
         db_config: None,
         da_update_interval_ms: 500,
         block_production_interval_ms: 500, // since running in test mode, we can set this to a lower value
+        max_l2_block_state_diff_size: 100 * 1024,
+        empty_block_policy: Default::default(),
+        empty_block_heartbeat_ms: 60_000,
+        commitment_policy: Default::default(),
+        enable_admin_rpc: false,
+        standby: None,
+        lease: None,
+        tx_gossip_peers: vec![],
+        da_payload_mode: Default::default(),
+        relay: None,
     }
 }
 
@@ -0,0 +1,228 @@
+use core::fmt::Debug;
+
+use async_trait::async_trait;
+use bitcoin::consensus::encode::deserialize;
+use bitcoin::hash_types::WitnessMerkleNode;
+use bitcoin::hashes::Hash;
+use bitcoin::Block;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use tracing::{instrument, warn};
+
+use crate::rpc::{BitcoinNode, RPCError};
+use crate::spec::block::BitcoinBlock;
+use crate::spec::header::HeaderWrapper;
+use crate::spec::transaction::TransactionWrapper;
+
+// Error code of bitcoind for heights above the tip
+const RPC_INVALID_PARAMETER: i32 = -8;
+
+/// Source of the blocks the DA service reads.
+/// Inscriptions are always sent with the wallet of the bitcoin node.
+#[async_trait]
+pub trait BlockSource: Debug + Send + Sync {
+    /// The height of the tip of the chain
+    async fn get_block_count(&self) -> Result<u64, anyhow::Error>;
+
+    /// The hash of the block at the given height, `None` if there is no such block yet
+    async fn get_block_hash(&self, height: u64) -> Result<Option<String>, anyhow::Error>;
+
+    /// The hash of the tip of the chain
+    async fn get_best_blockhash(&self) -> Result<String, anyhow::Error>;
+
+    /// The block with the given hash, with all its transactions
+    async fn get_block(&self, hash: String) -> Result<BitcoinBlock, anyhow::Error>;
+
+    /// The header of the block with the given hash
+    async fn get_block_header(&self, hash: String) -> Result<HeaderWrapper, anyhow::Error> {
+        // The full block is requested here because txs_commitment is the witness root
+        Ok(self.get_block(hash).await?.header)
+    }
+}
+
+/// Where the DA service reads blocks from
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BlockSourceConfig {
+    /// The bitcoin node of the DA service, which needs `txindex` for full nodes
+    #[default]
+    Bitcoind,
+    /// An Esplora HTTP API, e.g. of electrs or https://blockstream.info/api.
+    /// Plain Electrum servers can't serve whole blocks, so electrs is used through its HTTP API.
+    Esplora {
+        /// Base URL of the API, e.g. https://blockstream.info/testnet/api
+        url: String,
+    },
+}
+
+impl BlockSourceConfig {
+    /// Creates the block source of the config
+    pub(crate) fn block_source(&self, client: &BitcoinNode) -> Box<dyn BlockSource> {
+        match self {
+            BlockSourceConfig::Bitcoind => Box::new(client.clone()),
+            BlockSourceConfig::Esplora { url } => Box::new(EsploraClient::new(url)),
+        }
+    }
+}
+
+#[async_trait]
+impl BlockSource for BitcoinNode {
+    async fn get_block_count(&self) -> Result<u64, anyhow::Error> {
+        BitcoinNode::get_block_count(self).await
+    }
+
+    async fn get_block_hash(&self, height: u64) -> Result<Option<String>, anyhow::Error> {
+        match BitcoinNode::get_block_hash(self, height).await {
+            Ok(hash) => Ok(Some(hash)),
+            Err(e)
+                if e.downcast_ref::<RPCError>()
+                    .is_some_and(|e| e.code == RPC_INVALID_PARAMETER) =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn get_best_blockhash(&self) -> Result<String, anyhow::Error> {
+        BitcoinNode::get_best_blockhash(self).await
+    }
+
+    async fn get_block(&self, hash: String) -> Result<BitcoinBlock, anyhow::Error> {
+        BitcoinNode::get_block(self, hash).await
+    }
+}
+
+/// Height of a block returned by the Esplora API, among others
+#[derive(Debug, Deserialize)]
+struct EsploraBlock {
+    height: u64,
+}
+
+/// Reads blocks from an Esplora HTTP API
+#[derive(Debug, Clone)]
+pub struct EsploraClient {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl EsploraClient {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// GETs the path of the API, `None` if it is not found.
+    /// Retries like the calls to bitcoind if the request could not be sent.
+    #[instrument(level = "trace", skip(self), err)]
+    async fn get(&self, path: &str) -> Result<Option<reqwest::Response>, anyhow::Error> {
+        let mut attempt = 1;
+        loop {
+            match self
+                .client
+                .get(format!("{}{}", self.url, path))
+                .send()
+                .await
+            {
+                Ok(response) if response.status() == StatusCode::NOT_FOUND => return Ok(None),
+                Ok(response) => return Ok(Some(response.error_for_status()?)),
+                Err(error) if error.is_connect() || error.is_timeout() => {
+                    warn!(error=?error, attempt=attempt, "Failed to send a request to esplora");
+                    attempt += 1;
+                    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+                }
+                Err(error) => anyhow::bail!(error),
+            }
+        }
+    }
+
+    async fn get_text(&self, path: &str) -> Result<String, anyhow::Error> {
+        let Some(response) = self.get(path).await? else {
+            anyhow::bail!("{} not found in esplora", path);
+        };
+        Ok(response.text().await?)
+    }
+}
+
+#[async_trait]
+impl BlockSource for EsploraClient {
+    async fn get_block_count(&self) -> Result<u64, anyhow::Error> {
+        Ok(self.get_text("/blocks/tip/height").await?.trim().parse()?)
+    }
+
+    async fn get_block_hash(&self, height: u64) -> Result<Option<String>, anyhow::Error> {
+        match self.get(&format!("/block-height/{}", height)).await? {
+            Some(response) => Ok(Some(response.text().await?.trim().to_string())),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_best_blockhash(&self) -> Result<String, anyhow::Error> {
+        Ok(self.get_text("/blocks/tip/hash").await?.trim().to_string())
+    }
+
+    async fn get_block(&self, hash: String) -> Result<BitcoinBlock, anyhow::Error> {
+        let Some(info) = self.get(&format!("/block/{}", hash)).await? else {
+            anyhow::bail!("Block {} not found in esplora", hash);
+        };
+        let height = info.json::<EsploraBlock>().await?.height;
+
+        let Some(raw) = self.get(&format!("/block/{}/raw", hash)).await? else {
+            anyhow::bail!("Block {} not found in esplora", hash);
+        };
+        let block = block_from_raw(&raw.bytes().await?, height)?;
+        if block.header.block_hash().to_string() != hash {
+            anyhow::bail!("Esplora returned another block than {}", hash);
+        }
+        Ok(block)
+    }
+}
+
+/// Decodes a consensus encoded block at the given height
+fn block_from_raw(raw: &[u8], height: u64) -> Result<BitcoinBlock, anyhow::Error> {
+    let block: Block = deserialize(raw)?;
+
+    let txs: Vec<TransactionWrapper> = block.txdata.into_iter().map(Into::into).collect();
+    let witness_root =
+        BitcoinNode::calculate_witness_root(&txs).unwrap_or(WitnessMerkleNode::all_zeros());
+
+    Ok(BitcoinBlock {
+        header: HeaderWrapper::new(block.header, txs.len() as u32, height, witness_root),
+        txdata: txs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::consensus::encode::serialize;
+    use sov_rollup_interface::da::BlockHeaderTrait;
+
+    use super::*;
+
+    #[test]
+    fn decodes_raw_blocks() {
+        let genesis = bitcoin::blockdata::constants::genesis_block(bitcoin::Network::Regtest);
+
+        let block = block_from_raw(&serialize(&genesis), 0).unwrap();
+        assert_eq!(block.header.block_hash(), genesis.block_hash());
+        assert_eq!(block.header.height(), 0);
+        assert_eq!(block.txdata.len(), 1);
+
+        assert!(block_from_raw(&[0; 10], 0).is_err());
+    }
+
+    #[test]
+    fn parses_block_sources() {
+        let config: BlockSourceConfig =
+            serde_json::from_str(r#"{ "type": "esplora", "url": "https://blockstream.info/api" }"#)
+                .unwrap();
+        assert_eq!(
+            config,
+            BlockSourceConfig::Esplora {
+                url: "https://blockstream.info/api".to_string()
+            }
+        );
+    }
+}
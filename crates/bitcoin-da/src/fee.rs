@@ -0,0 +1,206 @@
+use core::fmt::Debug;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{instrument, warn};
+
+use crate::rpc::BitcoinNode;
+
+/// Source of the fee rates of the commit and reveal transactions
+#[async_trait]
+pub trait FeeEstimator: Debug + Send + Sync {
+    /// Estimates the fee rate in sat/vB to confirm a transaction in the next block
+    async fn estimate_fee_rate(&self) -> Result<f64, anyhow::Error>;
+}
+
+/// Where fee rates are estimated from
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeeSource {
+    /// `estimatesmartfee` of the bitcoin node of the DA service
+    #[default]
+    Bitcoind,
+    /// The recommended fees of a mempool.space instance
+    MempoolSpace {
+        /// Base URL of the instance, with the network path, e.g. https://mempool.space/signet
+        #[serde(default = "default_mempool_space_url")]
+        url: String,
+    },
+    /// A fixed fee rate in sat/vB
+    Static { fee_rate: f64 },
+}
+
+fn default_mempool_space_url() -> String {
+    "https://mempool.space".to_string()
+}
+
+/// Configuration of the fee estimation of the DA service
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FeeEstimationConfig {
+    #[serde(default)]
+    pub source: FeeSource,
+    /// Estimated fee rates in sat/vB are raised to at least this
+    #[serde(default = "default_min_fee_rate")]
+    pub min_fee_rate: f64,
+    /// Estimated fee rates in sat/vB are lowered to at most this
+    #[serde(default = "default_max_fee_rate")]
+    pub max_fee_rate: f64,
+}
+
+impl Default for FeeEstimationConfig {
+    fn default() -> Self {
+        Self {
+            source: FeeSource::default(),
+            min_fee_rate: default_min_fee_rate(),
+            max_fee_rate: default_max_fee_rate(),
+        }
+    }
+}
+
+#[inline]
+const fn default_min_fee_rate() -> f64 {
+    1.0
+}
+
+#[inline]
+const fn default_max_fee_rate() -> f64 {
+    500.0
+}
+
+impl FeeEstimationConfig {
+    /// Creates the estimator of the configured source
+    pub(crate) fn estimator(&self, client: &BitcoinNode) -> Box<dyn FeeEstimator> {
+        match &self.source {
+            FeeSource::Bitcoind => Box::new(BitcoindFeeEstimator {
+                client: client.clone(),
+            }),
+            FeeSource::MempoolSpace { url } => Box::new(MempoolSpaceFeeEstimator {
+                url: url.trim_end_matches('/').to_string(),
+                client: reqwest::Client::new(),
+            }),
+            FeeSource::Static { fee_rate } => Box::new(StaticFeeEstimator {
+                fee_rate: *fee_rate,
+            }),
+        }
+    }
+
+    /// Clamps an estimated fee rate into the configured bounds.
+    /// Fails for rates which are not a positive number, those are estimation errors.
+    pub fn clamp(&self, fee_rate: f64) -> Result<f64, anyhow::Error> {
+        if !fee_rate.is_finite() || fee_rate <= 0.0 {
+            anyhow::bail!("Invalid estimated fee rate {}", fee_rate);
+        }
+        if fee_rate < self.min_fee_rate || fee_rate > self.max_fee_rate {
+            warn!(
+                fee_rate,
+                min_fee_rate = self.min_fee_rate,
+                max_fee_rate = self.max_fee_rate,
+                "Estimated fee rate is out of bounds"
+            );
+        }
+        // not f64::clamp, which panics if the bounds are misconfigured
+        Ok(fee_rate.max(self.min_fee_rate).min(self.max_fee_rate))
+    }
+}
+
+/// Estimates fee rates with `estimatesmartfee` of the bitcoin node
+#[derive(Debug)]
+pub struct BitcoindFeeEstimator {
+    client: BitcoinNode,
+}
+
+#[async_trait]
+impl FeeEstimator for BitcoindFeeEstimator {
+    async fn estimate_fee_rate(&self) -> Result<f64, anyhow::Error> {
+        self.client.estimate_smart_fee().await
+    }
+}
+
+/// Fee rates recommended by mempool.space, in sat/vB
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecommendedFees {
+    fastest_fee: f64,
+}
+
+/// Estimates fee rates with the recommended fees of a mempool.space instance
+#[derive(Debug)]
+pub struct MempoolSpaceFeeEstimator {
+    url: String,
+    client: reqwest::Client,
+}
+
+#[async_trait]
+impl FeeEstimator for MempoolSpaceFeeEstimator {
+    #[instrument(level = "trace", skip(self), err, ret)]
+    async fn estimate_fee_rate(&self) -> Result<f64, anyhow::Error> {
+        let fees = self
+            .client
+            .get(format!("{}/api/v1/fees/recommended", self.url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<RecommendedFees>()
+            .await?;
+
+        Ok(fees.fastest_fee)
+    }
+}
+
+/// Always estimates the same fee rate
+#[derive(Debug)]
+pub struct StaticFeeEstimator {
+    fee_rate: f64,
+}
+
+#[async_trait]
+impl FeeEstimator for StaticFeeEstimator {
+    async fn estimate_fee_rate(&self) -> Result<f64, anyhow::Error> {
+        Ok(self.fee_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clamps_estimated_fee_rates() {
+        let config = FeeEstimationConfig {
+            source: FeeSource::Static { fee_rate: 10.0 },
+            min_fee_rate: 2.0,
+            max_fee_rate: 100.0,
+        };
+
+        assert_eq!(config.clamp(10.5).unwrap(), 10.5);
+        assert_eq!(config.clamp(1.0).unwrap(), 2.0);
+        assert_eq!(config.clamp(1_000.0).unwrap(), 100.0);
+        assert!(config.clamp(0.0).is_err());
+        assert!(config.clamp(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn parses_fee_sources() {
+        let config: FeeEstimationConfig = serde_json::from_str(
+            r#"{ "source": { "type": "mempool_space" }, "max_fee_rate": 50.0 }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.source,
+            FeeSource::MempoolSpace {
+                url: "https://mempool.space".to_string()
+            }
+        );
+        assert_eq!(config.min_fee_rate, 1.0);
+
+        let config: FeeEstimationConfig =
+            serde_json::from_str(r#"{ "source": { "type": "static", "fee_rate": 3.5 } }"#).unwrap();
+        assert_eq!(config.source, FeeSource::Static { fee_rate: 3.5 });
+
+        let fees: RecommendedFees = serde_json::from_str(
+            r#"{"fastestFee":12,"halfHourFee":10,"hourFee":8,"economyFee":4,"minimumFee":2}"#,
+        )
+        .unwrap();
+        assert_eq!(fees.fastest_fee, 12.0);
+    }
+}
@@ -22,7 +22,10 @@ use bitcoin::{
 };
 use tracing::{instrument, trace, warn};
 
-use crate::helpers::{BODY_TAG, PUBLICKEY_TAG, RANDOM_TAG, ROLLUP_NAME_TAG, SIGNATURE_TAG};
+use crate::helpers::chunks::InscriptionBody;
+use crate::helpers::{
+    BODY_TAG, CHUNK_TAG, COMPRESSION_TAG, PUBLICKEY_TAG, RANDOM_TAG, ROLLUP_NAME_TAG, SIGNATURE_TAG,
+};
 use crate::spec::utxo::UTXO;
 use crate::REVEAL_OUTPUT_AMOUNT;
 
@@ -300,6 +303,65 @@ fn build_reveal_transaction(
     Ok(tx)
 }
 
+/// Builds a transaction spending all the UTXOs to outputs of the given values,
+/// and the rest to `recipient` if it is not dust. Used to split and consolidate UTXOs of the wallet.
+#[instrument(level = "trace", skip(utxos), err)]
+pub fn build_utxo_management_transaction(
+    utxos: &[UTXO],
+    recipient: &Address,
+    output_values: &[u64],
+    fee_rate: f64,
+) -> Result<Transaction, anyhow::Error> {
+    let inputs: Vec<_> = utxos
+        .iter()
+        .map(|u| TxIn {
+            previous_output: OutPoint {
+                txid: u.tx_id,
+                vout: u.vout,
+            },
+            script_sig: script::Builder::new().into_script(),
+            witness: Witness::new(),
+            sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+        })
+        .collect();
+    let mut outputs: Vec<_> = output_values
+        .iter()
+        .map(|value| TxOut {
+            value: Amount::from_sat(*value),
+            script_pubkey: recipient.script_pubkey(),
+        })
+        .collect();
+
+    let sum: u64 = utxos.iter().map(|u| u.amount).sum();
+    let output_total: u64 = output_values.iter().sum();
+
+    // first assume there is a change output
+    outputs.push(TxOut {
+        value: Amount::ZERO,
+        script_pubkey: recipient.script_pubkey(),
+    });
+    let fee = ((get_size(&inputs, &outputs, None, None) as f64) * fee_rate).ceil() as u64;
+    let change = sum
+        .checked_sub(output_total + fee)
+        .ok_or_else(|| anyhow!("not enough UTXOs"))?;
+
+    if change >= REVEAL_OUTPUT_AMOUNT {
+        outputs.last_mut().expect("Change output is added").value = Amount::from_sat(change);
+    } else {
+        outputs.pop();
+        if outputs.is_empty() {
+            return Err(anyhow!("UTXOs do not cover the fee"));
+        }
+    }
+
+    Ok(Transaction {
+        lock_time: LockTime::ZERO,
+        version: bitcoin::transaction::Version(2),
+        input: inputs,
+        output: outputs,
+    })
+}
+
 /// Both transaction and its hash
 #[derive(Clone)]
 pub struct TxWithId {
@@ -321,11 +383,12 @@ impl fmt::Debug for TxWithId {
 // TODO: parametrize hardness
 // so tests are easier
 // Creates the inscription transactions (commit and reveal)
+// The compression version byte and the chunk header, if any, are inscribed before the body
 #[allow(clippy::too_many_arguments)]
 #[instrument(level = "trace", skip_all, err)]
 pub fn create_inscription_transactions(
     rollup_name: &str,
-    body: Vec<u8>,
+    inscription: InscriptionBody,
     signature: Vec<u8>,
     sequencer_public_key: Vec<u8>,
     prev_tx: Option<TxWithId>,
@@ -376,13 +439,22 @@ pub fn create_inscription_transactions(
         // ownerships are moved to the loop
         let mut reveal_script_builder = reveal_script_builder.clone();
 
-        // push first random number and body tag
+        // push first random number, compression version, chunk header if any and body tag
         reveal_script_builder = reveal_script_builder
             .push_int(nonce)
-            .push_slice(PushBytesBuf::from(BODY_TAG));
+            .push_slice(PushBytesBuf::from(COMPRESSION_TAG))
+            .push_slice(PushBytesBuf::from(&[inscription.compression.to_byte()]));
+        if let Some(chunk) = &inscription.chunk {
+            reveal_script_builder = reveal_script_builder
+                .push_slice(PushBytesBuf::from(CHUNK_TAG))
+                .push_slice(
+                    PushBytesBuf::try_from(chunk.to_bytes()).expect("Cannot push chunk header"),
+                );
+        }
+        reveal_script_builder = reveal_script_builder.push_slice(PushBytesBuf::from(BODY_TAG));
 
         // push body in chunks of 520 bytes
-        for chunk in body.chunks(520) {
+        for chunk in inscription.data.chunks(520) {
             reveal_script_builder = reveal_script_builder.push_slice(
                 PushBytesBuf::try_from(chunk.to_vec()).expect("Cannot push body chunk"),
             );
@@ -527,11 +599,15 @@ mod tests {
     use bitcoin::hashes::Hash;
     use bitcoin::secp256k1::constants::SCHNORR_SIGNATURE_SIZE;
     use bitcoin::secp256k1::schnorr::Signature;
+    use bitcoin::secp256k1::SecretKey;
     use bitcoin::taproot::ControlBlock;
     use bitcoin::{Address, Amount, ScriptBuf, TxOut, Txid};
 
-    use crate::helpers::compression::{compress_blob, decompress_blob};
+    use super::sign_blob_with_private_key;
+    use crate::helpers::chunks::{split_into_inscriptions, InscriptionBody};
+    use crate::helpers::compression::{compress_blob, decompress_blob, CompressionCodec};
     use crate::helpers::parsers::parse_transaction;
+    use crate::service::CompressionConfig;
     use crate::spec::utxo::UTXO;
     use crate::REVEAL_OUTPUT_AMOUNT;
 
@@ -539,25 +615,32 @@ mod tests {
     fn compression_decompression() {
         let blob = std::fs::read("test_data/blob.txt").unwrap();
 
-        // compress and measure time
-        let time = std::time::Instant::now();
-        let compressed_blob = compress_blob(&blob);
-        println!("compression time: {:?}", time.elapsed());
+        for codec in [CompressionCodec::Brotli, CompressionCodec::Zstd] {
+            let config = CompressionConfig {
+                codec,
+                ..Default::default()
+            };
 
-        // decompress and measure time
-        let time = std::time::Instant::now();
-        let decompressed_blob = decompress_blob(&compressed_blob);
-        println!("decompression time: {:?}", time.elapsed());
+            // compress and measure time
+            let time = std::time::Instant::now();
+            let compressed_blob = compress_blob(&blob, &config);
+            println!("{:?} compression time: {:?}", codec, time.elapsed());
 
-        assert_eq!(blob, decompressed_blob);
+            // decompress and measure time
+            let time = std::time::Instant::now();
+            let decompressed_blob = decompress_blob(&compressed_blob, codec).unwrap();
+            println!("{:?} decompression time: {:?}", codec, time.elapsed());
 
-        // size
-        println!("blob size: {}", blob.len());
-        println!("compressed blob size: {}", compressed_blob.len());
-        println!(
-            "compression ratio: {}",
-            (blob.len() as f64) / (compressed_blob.len() as f64)
-        );
+            assert_eq!(blob, decompressed_blob);
+
+            // size
+            println!("blob size: {}", blob.len());
+            println!("compressed blob size: {}", compressed_blob.len());
+            println!(
+                "compression ratio: {}",
+                (blob.len() as f64) / (compressed_blob.len() as f64)
+            );
+        }
     }
 
     #[test]
@@ -967,6 +1050,46 @@ mod tests {
         assert!(tx.is_err());
         assert_eq!(format!("{}", tx.unwrap_err()), "input UTXO not big enough");
     }
+
+    #[test]
+    fn build_utxo_management_transaction() {
+        let (_, _, _, _, address, utxos) = get_mock_data();
+
+        // split
+        let tx = super::build_utxo_management_transaction(
+            &utxos[..1],
+            &address,
+            &[100_000, 100_000, 100_000],
+            2.0,
+        )
+        .unwrap();
+
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx.input[0].previous_output.txid, utxos[0].tx_id);
+        assert_eq!(tx.output.len(), 4);
+        assert!(tx.output[..3]
+            .iter()
+            .all(|output| output.value == Amount::from_sat(100_000)));
+        let fee = 1_000_000 - tx.output.iter().map(|o| o.value.to_sat()).sum::<u64>();
+        assert!(fee > 0 && fee < 1_000);
+        assert!(tx
+            .output
+            .iter()
+            .all(|output| output.script_pubkey == address.script_pubkey()));
+
+        // consolidation
+        let tx = super::build_utxo_management_transaction(&utxos[1..], &address, &[], 2.0).unwrap();
+
+        assert_eq!(tx.input.len(), 2);
+        assert_eq!(tx.output.len(), 1);
+        assert!(tx.output[0].value.to_sat() > 109_000);
+
+        let tx = super::build_utxo_management_transaction(&utxos[2..], &address, &[10_000], 2.0);
+
+        assert!(tx.is_err());
+        assert_eq!(format!("{}", tx.unwrap_err()), "not enough UTXOs");
+    }
+
     #[test]
     fn create_inscription_transactions() {
         let (rollup_name, body, signature, sequencer_public_key, address, utxos) = get_mock_data();
@@ -974,7 +1097,11 @@ mod tests {
         let tx_prefix = &[0u8];
         let (commit, reveal) = super::create_inscription_transactions(
             rollup_name,
-            body.clone(),
+            InscriptionBody {
+                compression: CompressionCodec::Zstd,
+                chunk: None,
+                data: body.clone(),
+            },
             signature.clone(),
             sequencer_public_key.clone(),
             None,
@@ -1034,5 +1161,60 @@ mod tests {
             inscription.public_key, sequencer_public_key,
             "sequencer public key should be correct"
         );
+        assert_eq!(inscription.chunk, None, "body should not be a chunk");
+        assert_eq!(
+            inscription.compression,
+            Some(CompressionCodec::Zstd),
+            "compression version should be correct"
+        );
+    }
+
+    #[test]
+    fn create_chunk_inscription_transactions() {
+        let (rollup_name, body, _, _, address, utxos) = get_mock_data();
+        let private_key = SecretKey::from_slice(&[7; 32]).unwrap();
+
+        let inscriptions =
+            split_into_inscriptions(body.clone(), CompressionCodec::Brotli, body.len() / 2);
+        assert_eq!(inscriptions.len(), 2);
+        let inscription = inscriptions[1].clone();
+        let (signature, sequencer_public_key) =
+            sign_blob_with_private_key(&inscription.signed_message(), &private_key).unwrap();
+
+        let (_, reveal) = super::create_inscription_transactions(
+            rollup_name,
+            inscription.clone(),
+            signature,
+            sequencer_public_key,
+            None,
+            utxos,
+            address,
+            546,
+            12.0,
+            10.0,
+            bitcoin::Network::Bitcoin,
+            &[0u8],
+        )
+        .unwrap();
+
+        let parsed = parse_transaction(&reveal.tx, rollup_name).unwrap();
+
+        assert_eq!(parsed.body, inscription.data, "chunk should be correct");
+        assert_eq!(
+            parsed.chunk, inscription.chunk,
+            "chunk header should be correct"
+        );
+        assert!(
+            parsed.get_sig_verified_hash().is_some(),
+            "chunk header should be signed"
+        );
+
+        // the signature does not hold for another chunk header or codec
+        let mut tampered = parsed.clone();
+        tampered.chunk.as_mut().unwrap().index = 0;
+        assert!(tampered.get_sig_verified_hash().is_none());
+        let mut tampered = parsed;
+        tampered.compression = Some(CompressionCodec::Zstd);
+        assert!(tampered.get_sig_verified_hash().is_none());
     }
 }
@@ -0,0 +1,230 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use bitcoin::hashes::{sha256d, Hash};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+use super::compression::CompressionCodec;
+
+/// Compressed blobs larger than this are inscribed in chunks of at most this size,
+/// so reveal txs stay well below the standard transaction weight
+pub const MAX_CHUNK_SIZE: usize = 100_000;
+
+/// Max. number of chunks of a blob. Each chunk is inscribed with a commit and a reveal tx
+/// chained on the txs of the previous chunk, and bitcoind does not accept chains of more than
+/// 25 unconfirmed txs into its mempool.
+pub const MAX_CHUNKS: usize = 12;
+
+/// Header of an inscription carrying a chunk of a compressed blob.
+/// All the chunks of a blob have to be included in the same block to be reassembled,
+/// blobs split across blocks are lost and have to be sent again.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize, Serialize, Deserialize,
+)]
+pub struct ChunkHeader {
+    /// Double sha256 of the whole compressed blob, which is the hash of the reassembled blob
+    pub checksum: [u8; 32],
+    /// Length of the whole compressed blob
+    pub total_len: u32,
+    /// Index of the chunk in the blob, starting from 0
+    pub index: u16,
+}
+
+impl ChunkHeader {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(self).expect("Serialization to vec is infallible")
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        Self::try_from_slice(bytes).ok()
+    }
+}
+
+/// The body of an inscription, a whole compressed blob or a chunk of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct InscriptionBody {
+    /// Codec the whole blob is compressed with
+    pub compression: CompressionCodec,
+    pub chunk: Option<ChunkHeader>,
+    pub data: Vec<u8>,
+}
+
+impl InscriptionBody {
+    /// The message the sender signs for the inscription
+    pub fn signed_message(&self) -> Cow<'_, [u8]> {
+        signed_message(Some(self.compression), self.chunk.as_ref(), &self.data)
+    }
+}
+
+/// Splits a compressed blob into the bodies of the inscriptions carrying it.
+/// Blobs up to `max_chunk_size` are inscribed whole, larger ones in chunks.
+pub fn split_into_inscriptions(
+    blob: Vec<u8>,
+    compression: CompressionCodec,
+    max_chunk_size: usize,
+) -> Vec<InscriptionBody> {
+    if blob.len() <= max_chunk_size {
+        return vec![InscriptionBody {
+            compression,
+            chunk: None,
+            data: blob,
+        }];
+    }
+
+    let checksum = sha256d::Hash::hash(&blob).to_byte_array();
+    let total_len = u32::try_from(blob.len()).expect("Blob is too large to be chunked");
+    blob.chunks(max_chunk_size)
+        .enumerate()
+        .map(|(index, data)| InscriptionBody {
+            compression,
+            chunk: Some(ChunkHeader {
+                checksum,
+                total_len,
+                index: u16::try_from(index).expect("Blob has too many chunks"),
+            }),
+            data: data.to_vec(),
+        })
+        .collect()
+}
+
+/// The message the sender signs for an inscription.
+/// The compression version byte and the header of a chunk are signed too,
+/// so chunks can't be reordered or mixed and blobs can't be decompressed with another codec.
+pub fn signed_message<'a>(
+    compression: Option<CompressionCodec>,
+    chunk: Option<&ChunkHeader>,
+    body: &'a [u8],
+) -> Cow<'a, [u8]> {
+    if compression.is_none() && chunk.is_none() {
+        return Cow::Borrowed(body);
+    }
+    let mut message = compression
+        .map(|codec| vec![codec.to_byte()])
+        .unwrap_or_default();
+    if let Some(header) = chunk {
+        message.extend(header.to_bytes());
+    }
+    message.extend_from_slice(body);
+    Cow::Owned(message)
+}
+
+/// Reassembles the chunked blobs of a block from their chunks, in the order they are found
+#[derive(Debug, Default)]
+pub struct ChunkAssembler {
+    /// Chunks found so far, by the sender and the checksum of their blob
+    pending: BTreeMap<(Vec<u8>, [u8; 32]), BTreeMap<u16, Vec<u8>>>,
+}
+
+impl ChunkAssembler {
+    /// Adds a chunk with a verified signature of the sender. Returns the compressed blob
+    /// when its last chunk is added, if the chunks add up to the checksum of the blob.
+    pub fn add(&mut self, sender: &[u8], header: ChunkHeader, data: Vec<u8>) -> Option<Vec<u8>> {
+        // no blob is sent in more chunks, so the chunks kept in memory are bounded
+        if header.index as usize >= MAX_CHUNKS
+            || header.total_len as usize > MAX_CHUNKS * MAX_CHUNK_SIZE
+        {
+            return None;
+        }
+        let key = (sender.to_vec(), header.checksum);
+        let chunks = self.pending.entry(key.clone()).or_default();
+        // an inscription can be repeated, the first one is kept
+        chunks.entry(header.index).or_insert(data);
+
+        let len: usize = chunks.values().map(Vec::len).sum();
+        if len < header.total_len as usize {
+            return None;
+        }
+
+        let chunks = self.pending.remove(&key)?;
+        let contiguous = chunks
+            .keys()
+            .copied()
+            .eq(0..u16::try_from(chunks.len()).ok()?);
+        let blob = chunks.into_values().flatten().collect::<Vec<u8>>();
+        if !contiguous || sha256d::Hash::hash(&blob).to_byte_array() != header.checksum {
+            return None;
+        }
+        Some(blob)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_and_reassembles_blobs() {
+        let blob: Vec<u8> = (0..250).map(|i| i as u8).collect();
+
+        let inscriptions = split_into_inscriptions(blob.clone(), CompressionCodec::Zstd, 250);
+        assert_eq!(
+            inscriptions,
+            vec![InscriptionBody {
+                compression: CompressionCodec::Zstd,
+                chunk: None,
+                data: blob.clone(),
+            }]
+        );
+
+        let inscriptions = split_into_inscriptions(blob.clone(), CompressionCodec::Zstd, 100);
+        assert_eq!(inscriptions.len(), 3);
+        assert_eq!(inscriptions[2].data.len(), 50);
+
+        let header = inscriptions[0].chunk.unwrap();
+        assert_eq!(ChunkHeader::from_bytes(&header.to_bytes()), Some(header));
+
+        // chunks of other senders don't complete the blob
+        let mut assembler = ChunkAssembler::default();
+        for inscription in inscriptions.iter().rev().take(2) {
+            let chunk = inscription.chunk.unwrap();
+            assert_eq!(
+                assembler.add(b"sender", chunk, inscription.data.clone()),
+                None
+            );
+        }
+        assert_eq!(
+            assembler.add(b"other", header, inscriptions[0].data.clone()),
+            None
+        );
+        assert_eq!(
+            assembler.add(b"sender", header, inscriptions[0].data.clone()),
+            Some(blob)
+        );
+    }
+
+    #[test]
+    fn rejects_chunks_not_matching_checksum() {
+        let blob = vec![1; 300];
+        let mut inscriptions = split_into_inscriptions(blob, CompressionCodec::Brotli, 100);
+        inscriptions[1].data[0] = 2;
+
+        let mut assembler = ChunkAssembler::default();
+        let completed = inscriptions
+            .into_iter()
+            .filter_map(|inscription| {
+                assembler.add(b"sender", inscription.chunk.unwrap(), inscription.data)
+            })
+            .count();
+        assert_eq!(completed, 0);
+    }
+
+    #[test]
+    fn rejects_chunks_beyond_max_chunks() {
+        let blob = vec![1; MAX_CHUNKS + 1];
+        let inscriptions = split_into_inscriptions(blob, CompressionCodec::Brotli, 1);
+
+        let mut assembler = ChunkAssembler::default();
+        let completed = inscriptions
+            .into_iter()
+            .filter_map(|inscription| {
+                assembler.add(b"sender", inscription.chunk.unwrap(), inscription.data)
+            })
+            .count();
+        assert_eq!(completed, 0);
+        assert!(assembler
+            .pending
+            .values()
+            .all(|chunks| chunks.len() <= MAX_CHUNKS));
+    }
+}
@@ -1,16 +1,76 @@
-use std::io::Write;
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "native")]
+use crate::service::CompressionConfig;
+
+/// Codec a blob is compressed with before it is inscribed.
+/// Inscriptions without a compression version byte are compressed with brotli.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompressionCodec {
+    Brotli,
+    #[default]
+    Zstd,
+}
+
+impl CompressionCodec {
+    /// Version byte of the codec in the envelope of an inscription
+    pub fn to_byte(self) -> u8 {
+        match self {
+            CompressionCodec::Brotli => 0,
+            CompressionCodec::Zstd => 1,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(CompressionCodec::Brotli),
+            1 => Some(CompressionCodec::Zstd),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CompressionCodec::Brotli => "brotli",
+            CompressionCodec::Zstd => "zstd",
+        }
+    }
+}
 
 #[cfg(feature = "native")]
-pub fn compress_blob(blob: &[u8]) -> Vec<u8> {
-    use brotli::CompressorWriter;
-    let mut writer = CompressorWriter::new(Vec::new(), 4096, 11, 22);
-    writer.write_all(blob).unwrap();
-    writer.into_inner()
+pub fn compress_blob(blob: &[u8], config: &CompressionConfig) -> Vec<u8> {
+    match config.codec {
+        CompressionCodec::Brotli => {
+            use brotli::CompressorWriter;
+            let mut writer = CompressorWriter::new(Vec::new(), 4096, 11, 22);
+            writer.write_all(blob).unwrap();
+            writer.into_inner()
+        }
+        CompressionCodec::Zstd => {
+            zstd::encode_all(blob, config.zstd_level).expect("Compression to vec is infallible")
+        }
+    }
 }
 
-pub fn decompress_blob(blob: &[u8]) -> Vec<u8> {
-    use brotli::DecompressorWriter;
-    let mut writer = DecompressorWriter::new(Vec::new(), 4096);
-    writer.write_all(blob).unwrap();
-    writer.into_inner().expect("decompression failed")
+/// Decompresses a blob, `None` if it is not valid for the codec.
+/// zstd is decoded with a pure Rust decoder natively too,
+/// so the guest and the native node accept exactly the same blobs.
+pub fn decompress_blob(blob: &[u8], codec: CompressionCodec) -> Option<Vec<u8>> {
+    match codec {
+        CompressionCodec::Brotli => {
+            use brotli::DecompressorWriter;
+            let mut writer = DecompressorWriter::new(Vec::new(), 4096);
+            writer.write_all(blob).ok()?;
+            writer.into_inner().ok()
+        }
+        CompressionCodec::Zstd => {
+            let mut decoder = ruzstd::StreamingDecoder::new(blob).ok()?;
+            let mut decompressed = Vec::new();
+            decoder.read_to_end(&mut decompressed).ok()?;
+            Some(decompressed)
+        }
+    }
 }
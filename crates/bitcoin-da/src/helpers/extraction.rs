@@ -0,0 +1,359 @@
+use bitcoin::Transaction;
+
+use super::chunks::ChunkAssembler;
+use super::compression::{decompress_blob, CompressionCodec};
+use super::parsers::parse_transaction;
+
+/// A blob of a relevant inscription with a valid signature
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedBlob {
+    /// The decompressed blob
+    pub blob: Vec<u8>,
+    pub sender: Vec<u8>,
+    /// Hash of the signed message, or the checksum of the compressed blob if it is chunked
+    pub hash: [u8; 32],
+}
+
+/// Extracts the blob of a reveal transaction of the rollup.
+/// Both the DA service and the verifier extract blobs with this, so look-alike inscriptions
+/// which fail to parse, fail the signature check or fail to decompress are skipped the same way
+/// natively and in the zk guest. Returns `None` for chunks until the last chunk of a blob is found.
+pub fn extract_blob(
+    tx: &Transaction,
+    rollup_name: &str,
+    chunks: &mut ChunkAssembler,
+) -> Option<ExtractedBlob> {
+    let inscription = parse_transaction(tx, rollup_name).ok()?;
+    let hash = inscription.get_sig_verified_hash()?;
+
+    let (body, hash) = match inscription.chunk {
+        None => (inscription.body, hash),
+        Some(header) => {
+            let body = chunks.add(&inscription.public_key, header, inscription.body)?;
+            (body, header.checksum)
+        }
+    };
+
+    let codec = inscription.compression.unwrap_or(CompressionCodec::Brotli);
+    let blob = decompress_blob(&body, codec)?;
+
+    Some(ExtractedBlob {
+        blob,
+        sender: inscription.public_key,
+        hash,
+    })
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use bitcoin::absolute::LockTime;
+    use bitcoin::hashes::{sha256d, Hash};
+    use bitcoin::key::XOnlyPublicKey;
+    use bitcoin::opcodes::all::{OP_CHECKSIG, OP_DROP, OP_ENDIF, OP_IF};
+    use bitcoin::opcodes::OP_FALSE;
+    use bitcoin::script::{self, PushBytesBuf};
+    use bitcoin::secp256k1::{self, Message, Secp256k1, SecretKey};
+    use bitcoin::{OutPoint, ScriptBuf, Sequence, Transaction, TxIn, Witness};
+
+    use super::*;
+    use crate::helpers::chunks::{split_into_inscriptions, InscriptionBody, MAX_CHUNKS};
+    use crate::helpers::{
+        BODY_TAG, CHUNK_TAG, COMPRESSION_TAG, PUBLICKEY_TAG, RANDOM_TAG, ROLLUP_NAME_TAG,
+        SIGNATURE_TAG,
+    };
+
+    const ROLLUP_NAME: &str = "sov-btc";
+
+    /// Order of the secp256k1 curve
+    const CURVE_ORDER: [u8; 32] = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xfe, 0xba, 0xae, 0xdc, 0xe6, 0xaf, 0x48, 0xa0, 0x3b, 0xbf, 0xd2, 0x5e, 0x8c, 0xd0, 0x36,
+        0x41, 0x41,
+    ];
+
+    fn push(bytes: &[u8]) -> PushBytesBuf {
+        PushBytesBuf::try_from(bytes.to_vec()).unwrap()
+    }
+
+    fn sign(inscription: &InscriptionBody, key: &SecretKey) -> (Vec<u8>, Vec<u8>) {
+        let secp = Secp256k1::new();
+        let hash = sha256d::Hash::hash(&inscription.signed_message()).to_byte_array();
+        let message = Message::from_digest_slice(&hash).unwrap();
+        let signature = secp.sign_ecdsa(&message, key).serialize_compact().to_vec();
+        let public_key = secp256k1::PublicKey::from_secret_key(&secp, key)
+            .serialize()
+            .to_vec();
+        (signature, public_key)
+    }
+
+    /// The envelope the builders inscribe, before it is closed
+    fn envelope(
+        inscription: &InscriptionBody,
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> script::Builder {
+        let mut builder = script::Builder::new()
+            .push_x_only_key(&XOnlyPublicKey::from_slice(&[1; 32]).unwrap())
+            .push_opcode(OP_CHECKSIG)
+            .push_opcode(OP_FALSE)
+            .push_opcode(OP_IF)
+            .push_slice(push(ROLLUP_NAME_TAG))
+            .push_slice(push(ROLLUP_NAME.as_bytes()))
+            .push_slice(push(SIGNATURE_TAG))
+            .push_slice(push(signature))
+            .push_slice(push(PUBLICKEY_TAG))
+            .push_slice(push(public_key))
+            .push_slice(push(RANDOM_TAG))
+            .push_int(42)
+            .push_slice(push(COMPRESSION_TAG))
+            .push_slice(push(&[inscription.compression.to_byte()]));
+        if let Some(header) = inscription.chunk {
+            builder = builder
+                .push_slice(push(CHUNK_TAG))
+                .push_slice(push(&header.to_bytes()));
+        }
+        builder = builder.push_slice(push(BODY_TAG));
+        for chunk in inscription.data.chunks(520) {
+            builder = builder.push_slice(push(chunk));
+        }
+        builder
+    }
+
+    fn reveal_tx(script: ScriptBuf) -> Transaction {
+        Transaction {
+            version: bitcoin::transaction::Version(2),
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn {
+                previous_output: OutPoint::null(),
+                script_sig: ScriptBuf::new(),
+                sequence: Sequence::ENABLE_RBF_NO_LOCKTIME,
+                // the tapscript is the second to last element, before the control block
+                witness: Witness::from_slice(&[vec![0; 64], script.to_bytes(), vec![0xc0; 33]]),
+            }],
+            output: vec![],
+        }
+    }
+
+    fn inscription(blob: &[u8], codec: CompressionCodec) -> InscriptionBody {
+        let config = crate::service::CompressionConfig {
+            codec,
+            ..Default::default()
+        };
+        let compressed = crate::helpers::compression::compress_blob(blob, &config);
+        split_into_inscriptions(compressed, codec, usize::MAX).remove(0)
+    }
+
+    fn extract(script: ScriptBuf) -> Option<ExtractedBlob> {
+        extract_blob(
+            &reveal_tx(script),
+            ROLLUP_NAME,
+            &mut ChunkAssembler::default(),
+        )
+    }
+
+    #[test]
+    fn extracts_signed_inscriptions() {
+        let key = SecretKey::from_slice(&[7; 32]).unwrap();
+        for codec in [CompressionCodec::Brotli, CompressionCodec::Zstd] {
+            let inscription = inscription(b"a blob of the sequencer", codec);
+            let (signature, public_key) = sign(&inscription, &key);
+
+            let script = envelope(&inscription, &signature, &public_key)
+                .push_opcode(OP_ENDIF)
+                .into_script();
+            let extracted = extract(script).unwrap();
+            assert_eq!(extracted.blob, b"a blob of the sequencer");
+            assert_eq!(extracted.sender, public_key);
+        }
+
+        // chunks are extracted together once the last one is found
+        let blob: Vec<u8> = (0..100_000u32).flat_map(|i| i.to_le_bytes()).collect();
+        let config = crate::service::CompressionConfig::default();
+        let compressed = crate::helpers::compression::compress_blob(&blob, &config);
+        let chunk_size = compressed.len().div_ceil(MAX_CHUNKS);
+        let inscriptions = split_into_inscriptions(compressed, config.codec, chunk_size);
+        assert!(inscriptions.len() > 1);
+
+        let mut chunks = ChunkAssembler::default();
+        let extracted: Vec<ExtractedBlob> = inscriptions
+            .iter()
+            .filter_map(|inscription| {
+                let (signature, public_key) = sign(inscription, &key);
+                let script = envelope(inscription, &signature, &public_key)
+                    .push_opcode(OP_ENDIF)
+                    .into_script();
+                extract_blob(&reveal_tx(script), ROLLUP_NAME, &mut chunks)
+            })
+            .collect();
+        assert_eq!(extracted.len(), 1);
+        assert_eq!(extracted[0].blob, blob);
+        assert_eq!(extracted[0].hash, inscriptions[0].chunk.unwrap().checksum);
+
+        // the header of a chunk is signed
+        let (signature, public_key) = sign(&inscriptions[0], &key);
+        let mut reordered = inscriptions[0].clone();
+        reordered.chunk.as_mut().unwrap().index = 1;
+        let script = envelope(&reordered, &signature, &public_key)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(extract(script), None);
+    }
+
+    #[test]
+    fn skips_inscriptions_failing_signature_verification() {
+        let key = SecretKey::from_slice(&[7; 32]).unwrap();
+        let inscription = inscription(&[3; 1000], CompressionCodec::Zstd);
+        let (signature, public_key) = sign(&inscription, &key);
+
+        // signed by another key
+        let (_, other_public_key) = sign(&inscription, &SecretKey::from_slice(&[8; 32]).unwrap());
+        let script = envelope(&inscription, &signature, &other_public_key)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(extract(script), None);
+
+        // body modified after signing
+        let mut modified = inscription.clone();
+        modified.data[0] ^= 1;
+        let script = envelope(&modified, &signature, &public_key)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(extract(script), None);
+
+        // compression version byte modified after signing
+        let mut modified = inscription.clone();
+        modified.compression = CompressionCodec::Brotli;
+        let script = envelope(&modified, &signature, &public_key)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(extract(script), None);
+
+        // malleated signature with the high s value
+        let mut malleated = signature.clone();
+        let mut borrow = 0u16;
+        for i in (0..32).rev() {
+            let diff = CURVE_ORDER[i] as u16 + 256 - signature[32 + i] as u16 - borrow;
+            malleated[32 + i] = diff as u8;
+            borrow = u16::from(diff < 256);
+        }
+        let script = envelope(&inscription, &malleated, &public_key)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(extract(script), None);
+
+        // uncompressed key of the signer
+        let uncompressed = secp256k1::PublicKey::from_slice(&public_key)
+            .unwrap()
+            .serialize_uncompressed();
+        let script = envelope(&inscription, &signature, &uncompressed)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(extract(script), None);
+
+        // truncated signature
+        let script = envelope(&inscription, &signature[..63], &public_key)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(extract(script), None);
+    }
+
+    #[test]
+    fn skips_malleated_scripts() {
+        let key = SecretKey::from_slice(&[7; 32]).unwrap();
+        let inscription = inscription(&[3; 1000], CompressionCodec::Zstd);
+        let (signature, public_key) = sign(&inscription, &key);
+
+        // not closed
+        let script = envelope(&inscription, &signature, &public_key).into_script();
+        assert_eq!(extract(script), None);
+
+        // an opcode inside the envelope
+        let script = envelope(&inscription, &signature, &public_key)
+            .push_opcode(OP_DROP)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(extract(script), None);
+
+        // an envelope nested in the envelope
+        let script = envelope(&inscription, &signature, &public_key)
+            .push_opcode(OP_FALSE)
+            .push_opcode(OP_IF)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(extract(script), None);
+
+        // OP_IF not right after OP_FALSE doesn't open an envelope
+        let valid = envelope(&inscription, &signature, &public_key)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        let mut bytes = valid.to_bytes();
+        let op_if = bytes
+            .windows(2)
+            .position(|w| w == [OP_FALSE.to_u8(), OP_IF.to_u8()])
+            .unwrap();
+        bytes.insert(op_if + 1, 0x01);
+        bytes.insert(op_if + 2, 0x00);
+        assert_eq!(extract(ScriptBuf::from_bytes(bytes)), None);
+
+        // a tag repeated
+        let script = envelope(&inscription, &signature, &public_key)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        let mut bytes = script.to_bytes();
+        let compression_field = [0x01, COMPRESSION_TAG[0], 0x01, 0x01];
+        let field = bytes
+            .windows(4)
+            .position(|w| w == compression_field)
+            .unwrap();
+        for (i, byte) in compression_field.iter().enumerate() {
+            bytes.insert(field + i, *byte);
+        }
+        assert_eq!(extract(ScriptBuf::from_bytes(bytes)), None);
+
+        // an unknown codec
+        let mut bytes = valid.to_bytes();
+        bytes[field + 3] = 0xff;
+        assert_eq!(extract(ScriptBuf::from_bytes(bytes)), None);
+
+        // no input to take the script from
+        let mut tx = reveal_tx(valid);
+        tx.input.clear();
+        assert_eq!(
+            extract_blob(&tx, ROLLUP_NAME, &mut ChunkAssembler::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn skips_truncated_payloads() {
+        let key = SecretKey::from_slice(&[7; 32]).unwrap();
+        let inscription = inscription(
+            &(0..2000).map(|i| i as u8).collect::<Vec<_>>(),
+            CompressionCodec::Zstd,
+        );
+        let (signature, public_key) = sign(&inscription, &key);
+        let script = envelope(&inscription, &signature, &public_key)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        let bytes = script.to_bytes();
+        assert!(extract(script).is_some());
+
+        // cut off at every position, in the middle of pushes too
+        for len in 0..bytes.len() {
+            let truncated = ScriptBuf::from_bytes(bytes[..len].to_vec());
+            assert_eq!(extract(truncated), None, "truncated to {} bytes", len);
+        }
+
+        // a signed body which is not a valid compressed blob
+        let garbage = InscriptionBody {
+            compression: CompressionCodec::Zstd,
+            chunk: None,
+            data: vec![0xaa; 100],
+        };
+        let (signature, public_key) = sign(&garbage, &key);
+        let script = envelope(&garbage, &signature, &public_key)
+            .push_opcode(OP_ENDIF)
+            .into_script();
+        assert_eq!(extract(script), None);
+    }
+}
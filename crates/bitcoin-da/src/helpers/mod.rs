@@ -4,10 +4,16 @@ const SIGNATURE_TAG: &[u8; 1] = &[2; 1];
 const PUBLICKEY_TAG: &[u8; 1] = &[3; 1];
 const RANDOM_TAG: &[u8; 1] = &[4; 1];
 const BODY_TAG: &[u8; 0] = &[];
+const CHUNK_TAG: &[u8; 1] = &[5; 1];
+const COMPRESSION_TAG: &[u8; 1] = &[6; 1];
 
 #[cfg(feature = "native")]
 pub mod builders;
+pub mod chunks;
 pub mod compression;
+pub mod extraction;
 pub mod parsers;
 #[cfg(test)]
 pub mod test_utils;
+#[cfg(feature = "native")]
+pub mod utxo_management;
@@ -8,26 +8,39 @@ use bitcoin::opcodes::all::{
     OP_PUSHNUM_15, OP_PUSHNUM_16, OP_PUSHNUM_2, OP_PUSHNUM_3, OP_PUSHNUM_4, OP_PUSHNUM_5,
     OP_PUSHNUM_6, OP_PUSHNUM_7, OP_PUSHNUM_8, OP_PUSHNUM_9,
 };
-use bitcoin::opcodes::OP_FALSE;
 use bitcoin::secp256k1::{ecdsa, Message, Secp256k1};
 use bitcoin::{secp256k1, Script, Transaction};
 use serde::{Deserialize, Serialize};
 
-use super::{BODY_TAG, PUBLICKEY_TAG, RANDOM_TAG, ROLLUP_NAME_TAG, SIGNATURE_TAG};
+use super::chunks::{signed_message, ChunkHeader};
+use super::compression::CompressionCodec;
+use super::{
+    BODY_TAG, CHUNK_TAG, COMPRESSION_TAG, PUBLICKEY_TAG, RANDOM_TAG, ROLLUP_NAME_TAG, SIGNATURE_TAG,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ParsedInscription {
     pub body: Vec<u8>,
     pub signature: Vec<u8>,
     pub public_key: Vec<u8>,
+    /// Set if the body is a chunk of a blob
+    pub chunk: Option<ChunkHeader>,
+    /// Codec of the blob, brotli if the envelope has no compression version byte
+    pub compression: Option<CompressionCodec>,
 }
 
 impl ParsedInscription {
-    /// Verifies the signature of the inscription and returns the hash of the body
+    /// Verifies the signature of the inscription and returns the hash of the signed message,
+    /// which is the body itself if the envelope has no compression version byte or chunk header
     pub fn get_sig_verified_hash(&self) -> Option<[u8; 32]> {
+        // only compressed keys, an uncompressed key of the same signer would be another sender
+        if self.public_key.len() != secp256k1::constants::PUBLIC_KEY_SIZE {
+            return None;
+        }
         let public_key = secp256k1::PublicKey::from_slice(&self.public_key);
         let signature = ecdsa::Signature::from_compact(&self.signature);
-        let hash = sha256d::Hash::hash(&self.body).to_byte_array();
+        let message = signed_message(self.compression, self.chunk.as_ref(), &self.body);
+        let hash = sha256d::Hash::hash(&message).to_byte_array();
         let message = Message::from_digest_slice(&hash).unwrap(); // cannot fail
 
         let secp = Secp256k1::new();
@@ -65,9 +78,9 @@ pub fn parse_transaction(
 
 // Returns the script from the first input of the transaction
 fn get_script(tx: &Transaction) -> Result<&Script, ParserError> {
-    tx.input[0]
-        .witness
-        .tapscript()
+    tx.input
+        .first()
+        .and_then(|input| input.witness.tapscript())
         .ok_or(ParserError::NonTapscriptWitness)
 }
 
@@ -77,29 +90,45 @@ fn parse_relevant_inscriptions(
     instructions: &mut Peekable<Instructions>,
     rollup_name: &str,
 ) -> Result<ParsedInscription, ParserError> {
-    let mut last_op = None;
+    let mut after_op_false = false;
     let mut inside_envelope = false;
+    let mut envelope_closed = false;
     let mut inside_envelope_index = 0;
 
     let mut body: Vec<u8> = Vec::new();
     let mut signature: Vec<u8> = Vec::new();
     let mut public_key: Vec<u8> = Vec::new();
+    // optional fields between the random number and the body tag, each a tag and its value
+    let mut field_tag: Option<&[u8]> = None;
+    let mut body_started = false;
+    let mut chunk: Option<ChunkHeader> = None;
+    let mut compression: Option<CompressionCodec> = None;
 
-    // this while loop is optimized for the least amount of iterations
+    // this loop is optimized for the least amount of iterations
     // for a strict envelope structure
     // nothing other than data pushes should be inside the envelope
     // the loop will break after the first envelope is parsed
-    while let Some(Ok(instruction)) = instructions.next() {
+    for instruction in instructions {
+        let instruction = match instruction {
+            Ok(instruction) => instruction,
+            // a truncated push inside the envelope invalidates it, even if the parsed part looks fine
+            Err(_) if inside_envelope => return Err(ParserError::EnvelopeHasIncorrectFormat),
+            Err(_) => break,
+        };
+        // rust bitcoin pushes [] instead of op_false
+        let is_op_false = matches!(instruction, Instruction::PushBytes(bytes) if bytes.is_empty());
+
         match instruction {
             Instruction::Op(OP_IF) => {
-                if last_op == Some(OP_FALSE) {
-                    inside_envelope = true;
-                } else if inside_envelope {
+                if inside_envelope {
                     return Err(ParserError::EnvelopeHasNonPushOp);
+                } else if after_op_false {
+                    inside_envelope = true;
                 }
             }
             Instruction::Op(OP_ENDIF) => {
                 if inside_envelope {
+                    envelope_closed = true;
                     break; // we are done parsing
                 }
             }
@@ -141,7 +170,6 @@ fn parse_relevant_inscriptions(
                         || (inside_envelope_index == 2 && bytes.as_bytes() != SIGNATURE_TAG)
                         || (inside_envelope_index == 4 && bytes.as_bytes() != PUBLICKEY_TAG)
                         || (inside_envelope_index == 6 && bytes.as_bytes() != RANDOM_TAG)
-                        || (inside_envelope_index == 8 && bytes.as_bytes() != BODY_TAG)
                     {
                         return Err(ParserError::EnvelopeHasIncorrectFormat);
                     } else if inside_envelope_index == 1
@@ -152,27 +180,51 @@ fn parse_relevant_inscriptions(
                         signature.extend(bytes.as_bytes());
                     } else if inside_envelope_index == 5 {
                         public_key.extend(bytes.as_bytes());
-                    } else if inside_envelope_index >= 9 {
+                    } else if body_started {
                         body.extend(bytes.as_bytes());
+                    } else if inside_envelope_index >= 8 {
+                        let bytes = bytes.as_bytes();
+                        match field_tag.take() {
+                            Some(tag) if tag == CHUNK_TAG => {
+                                chunk = Some(
+                                    ChunkHeader::from_bytes(bytes)
+                                        .ok_or(ParserError::EnvelopeHasIncorrectFormat)?,
+                                );
+                            }
+                            Some(_) => {
+                                let codec = match bytes {
+                                    [byte] => CompressionCodec::from_byte(*byte),
+                                    _ => None,
+                                };
+                                compression =
+                                    Some(codec.ok_or(ParserError::EnvelopeHasIncorrectFormat)?);
+                            }
+                            None if bytes == BODY_TAG => body_started = true,
+                            None if (bytes == CHUNK_TAG && chunk.is_none())
+                                || (bytes == COMPRESSION_TAG && compression.is_none()) =>
+                            {
+                                field_tag = Some(bytes);
+                            }
+                            None => return Err(ParserError::EnvelopeHasIncorrectFormat),
+                        }
                     }
 
                     inside_envelope_index += 1;
-                } else if bytes.is_empty() {
-                    last_op = Some(OP_FALSE); // rust bitcoin pushes [] instead of op_false
                 }
             }
-            Instruction::Op(another_op) => {
+            Instruction::Op(_) => {
                 // don't allow anything except data pushes inside envelope
                 if inside_envelope {
                     return Err(ParserError::EnvelopeHasNonPushOp);
                 }
-
-                last_op = Some(another_op);
             }
         }
+
+        after_op_false = is_op_false;
     }
 
-    if body.is_empty() || signature.is_empty() || public_key.is_empty() {
+    // an envelope cut off before its end is not an inscription
+    if !envelope_closed || body.is_empty() || signature.is_empty() || public_key.is_empty() {
         return Err(ParserError::EnvelopeHasIncorrectFormat);
     }
 
@@ -180,6 +232,8 @@ fn parse_relevant_inscriptions(
         body,
         signature,
         public_key,
+        chunk,
+        compression,
     })
 }
 
@@ -7,7 +7,7 @@ use bitcoin::string::FromHexStr;
 use bitcoin::{BlockHash, CompactTarget, Transaction};
 use sov_rollup_interface::da::{DaSpec, DaVerifier};
 
-use crate::helpers::compression::decompress_blob;
+use crate::helpers::compression::{decompress_blob, CompressionCodec};
 use crate::helpers::parsers::{parse_hex_transaction, parse_transaction};
 use crate::spec::blob::BlobWithSender;
 use crate::spec::header::HeaderWrapper;
@@ -31,7 +31,7 @@ pub(crate) fn get_blob_with_sender(tx: &Transaction) -> BlobWithSender {
     let blob = parsed_inscription.body;
 
     // Decompress the blob
-    let decompressed_blob = decompress_blob(&blob);
+    let decompressed_blob = decompress_blob(&blob, CompressionCodec::Brotli).unwrap();
 
     BlobWithSender::new(
         decompressed_blob,
@@ -83,20 +83,22 @@ pub(crate) fn get_mock_data() -> (
     .map(Into::into)
     .collect();
 
-    let mut inclusion_proof = InclusionMultiProof {
-        txids: block_txs
+    let mut wtxids: Vec<[u8; 32]> = block_txs
+        .iter()
+        .map(|t| t.wtxid().to_byte_array())
+        .collect();
+    // Coinbase tx wtxid should be [0u8;32]
+    wtxids[0] = [0; 32];
+
+    let inclusion_proof = InclusionMultiProof::new(
+        block_txs
             .iter()
             .map(|t| t.txid().to_raw_hash().to_byte_array())
             .collect(),
-        wtxids: block_txs
-            .iter()
-            .map(|t| t.wtxid().to_byte_array())
-            .collect(),
-        coinbase_tx: block_txs[0].clone().into(),
-    };
-
-    // Coinbase tx wtxid should be [0u8;32]
-    inclusion_proof.wtxids[0] = [0; 32];
+        wtxids,
+        &[6, 8, 10, 12],
+        block_txs[0].clone().into(),
+    );
 
     let txs: Vec<BlobWithSender> = vec![
         get_blob_with_sender(&block_txs[6]),
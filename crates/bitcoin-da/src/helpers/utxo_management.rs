@@ -0,0 +1,182 @@
+use bitcoin::Txid;
+
+use crate::service::UtxoManagementConfig;
+use crate::spec::utxo::UTXO;
+
+/// Virtual size of a taproot key path input, which the wallet UTXOs are spent with
+const KEY_SPEND_INPUT_VSIZE: u64 = 58;
+/// Virtual size of a taproot output
+const TAPROOT_OUTPUT_VSIZE: u64 = 43;
+/// Virtual size of a transaction without inputs and outputs
+const TX_OVERHEAD_VSIZE: u64 = 11;
+
+/// A transaction managing the UTXOs of the wallet
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum UtxoManagementAction {
+    /// Splits the UTXO into `output_count` UTXOs of the pool amount, and the change
+    Split { input: UTXO, output_count: usize },
+    /// Merges dust UTXOs into a single UTXO
+    Consolidate { inputs: Vec<UTXO> },
+}
+
+/// Decides the transaction to send to keep a pool of confirmed UTXOs for commit transactions,
+/// so inscriptions don't chain on unconfirmed change. The pool is refilled first,
+/// dust is consolidated only when the pool is full and the fee rate is low.
+/// `reserved` is an output which must not be spent, the one the next commit transaction spends.
+pub(crate) fn plan_utxo_management(
+    utxos: &[UTXO],
+    reserved: Option<(Txid, u32)>,
+    config: &UtxoManagementConfig,
+    fee_rate: f64,
+) -> Option<UtxoManagementAction> {
+    let confirmed: Vec<&UTXO> = utxos
+        .iter()
+        .filter(|utxo| {
+            utxo.confirmations > 0
+                && utxo.spendable
+                && utxo.solvable
+                && reserved != Some((utxo.tx_id, utxo.vout))
+        })
+        .collect();
+
+    let pool_count = confirmed
+        .iter()
+        .filter(|utxo| utxo.amount >= config.utxo_amount)
+        .count();
+    if pool_count < config.pool_size {
+        let input = confirmed.iter().max_by_key(|utxo| utxo.amount)?;
+        // The split input leaves the pool if it was in it
+        let missing =
+            config.pool_size - pool_count + usize::from(input.amount >= config.utxo_amount);
+        let affordable = (0..=missing)
+            .rev()
+            .find(|&count| {
+                let vsize = TX_OVERHEAD_VSIZE
+                    + KEY_SPEND_INPUT_VSIZE
+                    + TAPROOT_OUTPUT_VSIZE * (count as u64 + 1);
+                let fee = (vsize as f64 * fee_rate).ceil() as u64;
+                input.amount >= count as u64 * config.utxo_amount + fee
+            })
+            .unwrap_or(0);
+        // Splitting into a single UTXO does not grow the pool
+        if affordable >= 2 {
+            return Some(UtxoManagementAction::Split {
+                input: (*input).clone(),
+                output_count: affordable,
+            });
+        }
+        return None;
+    }
+
+    if fee_rate > config.max_consolidation_fee_rate {
+        return None;
+    }
+    // Only dust which is worth more than the fee to spend it
+    let spend_fee = (KEY_SPEND_INPUT_VSIZE as f64 * fee_rate).ceil() as u64;
+    let mut dust: Vec<UTXO> = confirmed
+        .into_iter()
+        .filter(|utxo| utxo.amount < config.dust_threshold && utxo.amount > spend_fee)
+        .cloned()
+        .collect();
+    if dust.len() < 2 {
+        return None;
+    }
+    dust.sort_by_key(|utxo| utxo.amount);
+    dust.truncate(config.max_consolidation_inputs);
+    Some(UtxoManagementAction::Consolidate { inputs: dust })
+}
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::hashes::Hash;
+
+    use super::*;
+
+    fn utxo(id: u8, amount: u64, confirmations: u64) -> UTXO {
+        UTXO {
+            tx_id: Txid::from_byte_array([id; 32]),
+            vout: 0,
+            address: "ANY".into(),
+            script_pubkey: "".into(),
+            amount,
+            confirmations,
+            spendable: true,
+            solvable: true,
+        }
+    }
+
+    fn config() -> UtxoManagementConfig {
+        UtxoManagementConfig {
+            pool_size: 4,
+            utxo_amount: 100_000,
+            dust_threshold: 10_000,
+            max_consolidation_fee_rate: 5.0,
+            max_consolidation_inputs: 2,
+            check_interval_secs: 600,
+        }
+    }
+
+    #[test]
+    fn splits_largest_confirmed_utxo_to_refill_pool() {
+        let utxos = vec![
+            utxo(1, 100_000, 1),
+            utxo(2, 10_000_000, 0),
+            utxo(3, 1_000_000, 3),
+        ];
+
+        // 1 UTXO in the pool besides the split one
+        let action = plan_utxo_management(&utxos, None, &config(), 2.0);
+        assert_eq!(
+            action,
+            Some(UtxoManagementAction::Split {
+                input: utxos[2].clone(),
+                output_count: 3,
+            })
+        );
+
+        // Only as many UTXOs as the input can pay for
+        let utxos = vec![utxo(1, 250_000, 1)];
+        let action = plan_utxo_management(&utxos, None, &config(), 2.0);
+        assert_eq!(
+            action,
+            Some(UtxoManagementAction::Split {
+                input: utxos[0].clone(),
+                output_count: 2,
+            })
+        );
+
+        let utxos = vec![utxo(1, 150_000, 1)];
+        assert_eq!(plan_utxo_management(&utxos, None, &config(), 2.0), None);
+    }
+
+    #[test]
+    fn consolidates_dust_when_pool_is_full() {
+        let mut utxos: Vec<UTXO> = (1..=4).map(|id| utxo(id, 100_000, 1)).collect();
+        utxos.extend([
+            utxo(5, 546, 2),
+            utxo(6, 5_000, 1),
+            utxo(7, 1_000, 1),
+            utxo(8, 2_000, 0),
+        ]);
+
+        let action = plan_utxo_management(&utxos, None, &config(), 2.0);
+        assert_eq!(
+            action,
+            Some(UtxoManagementAction::Consolidate {
+                inputs: vec![utxos[4].clone(), utxos[6].clone()],
+            })
+        );
+
+        // The output the next commit tx spends is left alone
+        let action = plan_utxo_management(&utxos, Some((utxos[4].tx_id, 0)), &config(), 2.0);
+        assert_eq!(
+            action,
+            Some(UtxoManagementAction::Consolidate {
+                inputs: vec![utxos[6].clone(), utxos[5].clone()],
+            })
+        );
+
+        // Not while fees are high
+        assert_eq!(plan_utxo_management(&utxos, None, &config(), 10.0), None);
+    }
+}
@@ -1,5 +1,11 @@
+#[cfg(feature = "native")]
+pub mod block_source;
+#[cfg(feature = "native")]
+pub mod fee;
 mod helpers;
 #[cfg(feature = "native")]
+mod metrics;
+#[cfg(feature = "native")]
 mod rpc;
 pub mod spec;
 
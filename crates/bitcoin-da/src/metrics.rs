@@ -0,0 +1,57 @@
+use once_cell::sync::Lazy;
+use prometheus::{
+    linear_buckets, register_histogram_vec, register_int_counter, HistogramVec, IntCounter,
+};
+
+pub static BITCOIN_DA_FEE_BUMPS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "bitcoin_da_fee_bumps",
+        // metric description
+        "Commit and reveal transactions replaced with higher fee ones"
+    )
+    .unwrap()
+});
+
+pub static BITCOIN_DA_FEES_PAID_SATS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "bitcoin_da_fees_paid_sats",
+        // metric description
+        "Fees of the commit and reveal transactions sent, including the replaced ones"
+    )
+    .unwrap()
+});
+
+pub static BITCOIN_DA_BLOB_BYTES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "bitcoin_da_blob_bytes",
+        // metric description
+        "Bytes of the blobs sent, before compression"
+    )
+    .unwrap()
+});
+
+pub static BITCOIN_DA_COMPRESSED_BLOB_BYTES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "bitcoin_da_compressed_blob_bytes",
+        // metric description
+        "Bytes of the blobs sent, after compression"
+    )
+    .unwrap()
+});
+
+pub static BITCOIN_DA_COMPRESSION_RATIO: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!(
+        // metric name
+        "bitcoin_da_compression_ratio",
+        // metric description
+        "Size of the blobs sent divided by their compressed size",
+        // metric labels (dimensions)
+        &["codec"],
+        linear_buckets(/*start=*/ 1.0, /*width=*/ 0.5, /*count=*/ 20).unwrap(),
+    )
+    .unwrap()
+});
@@ -29,6 +29,26 @@ impl Display for RPCError {
     }
 }
 
+// Error code of bitcoind for unknown transactions, among others
+const RPC_INVALID_ADDRESS_OR_KEY: i32 = -5;
+
+fn is_rpc_error(error: &anyhow::Error, code: i32) -> bool {
+    error
+        .downcast_ref::<RPCError>()
+        .is_some_and(|error| error.code == code)
+}
+
+// WalletTransaction is a transaction of the wallets of bitcoind, returned by gettransaction
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WalletTransaction {
+    // negative if the transaction conflicts with a confirmed transaction
+    pub confirmations: i64,
+    pub hex: String,
+    // the block the transaction is included in, if it is confirmed
+    #[serde(default)]
+    pub blockhash: Option<String>,
+}
+
 // Response is a struct that represents a response returned by the Bitcoin RPC
 // It is generic over the type of the result field, which is usually a String in Bitcoin Core
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
@@ -145,7 +165,9 @@ impl BitcoinNode {
         self.call::<String>("getbestblockhash", vec![]).await
     }
 
-    fn calculate_witness_root(txdata: &[TransactionWrapper]) -> Option<WitnessMerkleNode> {
+    pub(crate) fn calculate_witness_root(
+        txdata: &[TransactionWrapper],
+    ) -> Option<WitnessMerkleNode> {
         let hashes = txdata.iter().enumerate().map(|(i, t)| {
             if i == 0 {
                 // Replace the first hash with zeroes.
@@ -281,4 +303,32 @@ impl BitcoinNode {
         self.call::<String>("getrawtransaction", vec![to_value(txid)?])
             .await
     }
+
+    /// Get a transaction of the wallets of bitcoind by its txid.
+    /// Returns `None` if the transaction is not known to the wallets.
+    pub async fn get_wallet_transaction(
+        &self,
+        txid: String,
+    ) -> Result<Option<WalletTransaction>, anyhow::Error> {
+        match self
+            .call::<WalletTransaction>("gettransaction", vec![to_value(txid)?])
+            .await
+        {
+            Ok(tx) => Ok(Some(tx)),
+            Err(e) if is_rpc_error(&e, RPC_INVALID_ADDRESS_OR_KEY) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Whether the transaction is in the mempool of the node
+    pub async fn is_in_mempool(&self, txid: String) -> Result<bool, anyhow::Error> {
+        match self
+            .call::<Box<RawValue>>("getmempoolentry", vec![to_value(txid)?])
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(e) if is_rpc_error(&e, RPC_INVALID_ADDRESS_OR_KEY) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
 }
@@ -4,46 +4,74 @@
 use core::result::Result::Ok;
 use core::str::FromStr;
 use core::time::Duration;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
 
 // use std::sync::Arc;
 use async_trait::async_trait;
 use bitcoin::consensus::encode;
-use bitcoin::hashes::{sha256d, Hash};
+use bitcoin::hashes::Hash;
 use bitcoin::secp256k1::SecretKey;
 use bitcoin::{Address, BlockHash, Transaction, Txid};
 use hex::ToHex;
 use serde::{Deserialize, Serialize};
-use sov_rollup_interface::da::DaSpec;
-use sov_rollup_interface::services::da::{BlobWithNotifier, DaService};
+use sov_rollup_interface::da::{DaChainState, DaSpec};
+use sov_rollup_interface::services::da::{
+    BlobWithNotifier, DaService, FeeBump, FeeCeilings, TxStatus,
+};
 use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio::sync::oneshot::channel as oneshot_channel;
-use tracing::{debug, error, info, instrument, trace};
+use tracing::{debug, error, info, instrument, trace, warn};
 
+use crate::block_source::{BlockSource, BlockSourceConfig};
+use crate::fee::{FeeEstimationConfig, FeeEstimator, FeeSource};
 use crate::helpers::builders::{
-    create_inscription_transactions, sign_blob_with_private_key, write_reveal_tx, TxWithId,
+    build_utxo_management_transaction, create_inscription_transactions, sign_blob_with_private_key,
+    write_reveal_tx, TxWithId,
+};
+use crate::helpers::chunks::{
+    split_into_inscriptions, ChunkAssembler, InscriptionBody, MAX_CHUNKS, MAX_CHUNK_SIZE,
+};
+use crate::helpers::compression::{compress_blob, CompressionCodec};
+use crate::helpers::extraction::extract_blob;
+use crate::helpers::parsers::parse_hex_transaction;
+use crate::helpers::utxo_management::{plan_utxo_management, UtxoManagementAction};
+use crate::metrics::{
+    BITCOIN_DA_BLOB_BYTES, BITCOIN_DA_COMPRESSED_BLOB_BYTES, BITCOIN_DA_COMPRESSION_RATIO,
+    BITCOIN_DA_FEES_PAID_SATS, BITCOIN_DA_FEE_BUMPS,
 };
-use crate::helpers::compression::{compress_blob, decompress_blob};
-use crate::helpers::parsers::{parse_hex_transaction, parse_transaction};
-use crate::rpc::{BitcoinNode, RPCError};
+use crate::rpc::{BitcoinNode, WalletTransaction};
 use crate::spec::blob::BlobWithSender;
 use crate::spec::block::BitcoinBlock;
+use crate::spec::header::HeaderWrapper;
 use crate::spec::header_stream::BitcoinHeaderStream;
 use crate::spec::proof::InclusionMultiProof;
 use crate::spec::utxo::UTXO;
 use crate::spec::{BitcoinSpec, RollupParams};
-use crate::verifier::BitcoinVerifier;
+use crate::verifier::{BitcoinVerifier, DifficultyRules, DIFFICULTY_ADJUSTMENT_INTERVAL};
 use crate::REVEAL_OUTPUT_AMOUNT;
 
 /// A service that provides data and data availability proofs for Bitcoin
 #[derive(Debug, Clone)]
 pub struct BitcoinService {
     client: BitcoinNode,
+    block_source: Arc<dyn BlockSource>,
     rollup_name: String,
     network: bitcoin::Network,
     da_private_key: Option<SecretKey>,
     reveal_tx_id_prefix: Vec<u8>,
     inscribes_queue: UnboundedSender<BlobWithNotifier<TxidWrapper>>,
+    utxo_management: Option<UtxoManagementConfig>,
+    fee_bumping: Option<FeeBumpingConfig>,
+    compression: CompressionConfig,
+    fee_estimation: FeeEstimationConfig,
+    fee_estimator: Arc<dyn FeeEstimator>,
+    // reloaded maximum fee rates, which override the ones of the config
+    fee_ceilings: Arc<Mutex<FeeCeilings>>,
+    // replaced reveal txs and their replacements, so the status of a replaced tx can be followed
+    replacements: Arc<Mutex<HashMap<Txid, Txid>>>,
+    // reveal txs of the earlier chunks of chunked blobs, by the reveal tx of their last chunk
+    chunk_reveals: Arc<Mutex<HashMap<Txid, Vec<Txid>>>>,
 }
 
 /// Runtime configuration for the DA service
@@ -62,10 +90,195 @@ pub struct DaServiceConfig {
 
     // number of last paid fee rates to average if estimation fails
     pub fee_rates_to_avg: Option<usize>,
+
+    // keeps a pool of confirmed utxos for inscriptions and consolidates dust, if set
+    pub utxo_management: Option<UtxoManagementConfig>,
+
+    // replaces stalled commit and reveal txs with higher fee ones, if set
+    pub fee_bumping: Option<FeeBumpingConfig>,
+
+    // compression of blobs before they are inscribed, zstd if not set
+    pub compression: Option<CompressionConfig>,
+
+    // source and bounds of the fee rates, estimatesmartfee of the node if not set
+    pub fee_estimation: Option<FeeEstimationConfig>,
+
+    // where blocks are read from, the bitcoin node if not set
+    // inscriptions are always sent with the wallet of the bitcoin node
+    pub block_source: Option<BlockSourceConfig>,
+}
+
+/// Configuration of the compression of blobs before they are inscribed.
+/// The codec is inscribed with the blob, so readers don't need to know it.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CompressionConfig {
+    #[serde(default)]
+    pub codec: CompressionCodec,
+    /// Compression level of zstd, from 1 to 22
+    #[serde(default = "default_zstd_level")]
+    pub zstd_level: i32,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            codec: CompressionCodec::default(),
+            zstd_level: default_zstd_level(),
+        }
+    }
+}
+
+#[inline]
+const fn default_zstd_level() -> i32 {
+    19
+}
+
+/// Configuration of the UTXO management of the DA service.
+/// Commit transactions spend UTXOs of a pool of confirmed UTXOs, which is refilled by
+/// splitting the largest UTXO, so sustained publishing doesn't wait for change to confirm.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct UtxoManagementConfig {
+    /// Number of confirmed UTXOs of `utxo_amount` kept in the pool
+    #[serde(default = "default_pool_size")]
+    pub pool_size: usize,
+    /// Amount of the UTXOs of the pool in satoshis, enough to pay for a commit transaction
+    #[serde(default = "default_utxo_amount")]
+    pub utxo_amount: u64,
+    /// Confirmed UTXOs below this amount in satoshis are consolidated
+    #[serde(default = "default_dust_threshold")]
+    pub dust_threshold: u64,
+    /// Dust is only consolidated when the fee rate in sat/vB is at most this
+    #[serde(default = "default_max_consolidation_fee_rate")]
+    pub max_consolidation_fee_rate: f64,
+    /// Maximum number of UTXOs consolidated in a transaction
+    #[serde(default = "default_max_consolidation_inputs")]
+    pub max_consolidation_inputs: usize,
+    /// Seconds between checks of the UTXOs of the wallet
+    #[serde(default = "default_check_interval_secs")]
+    pub check_interval_secs: u64,
+}
+
+#[inline]
+const fn default_pool_size() -> usize {
+    10
+}
+
+#[inline]
+const fn default_utxo_amount() -> u64 {
+    100_000
+}
+
+#[inline]
+const fn default_dust_threshold() -> u64 {
+    10_000
+}
+
+#[inline]
+const fn default_max_consolidation_fee_rate() -> f64 {
+    5.0
+}
+
+#[inline]
+const fn default_max_consolidation_inputs() -> usize {
+    50
+}
+
+#[inline]
+const fn default_check_interval_secs() -> u64 {
+    600
+}
+
+/// Configuration of the replace-by-fee bumping of the latest commit and reveal transactions.
+/// Only the latest ones are replaced, the earlier ones are their ancestors.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FeeBumpingConfig {
+    /// Number of blocks without confirmation after which the transactions are replaced
+    #[serde(default = "default_blocks_until_bump")]
+    pub blocks_until_bump: u64,
+    /// Percentage the fee rate is increased by in each replacement
+    #[serde(default = "default_fee_bump_percent")]
+    pub fee_bump_percent: u64,
+    /// The fee rate in sat/vB is never bumped above this
+    #[serde(default = "default_max_fee_rate")]
+    pub max_fee_rate: f64,
+}
+
+#[inline]
+const fn default_blocks_until_bump() -> u64 {
+    3
+}
+
+#[inline]
+const fn default_fee_bump_percent() -> u64 {
+    50
+}
+
+#[inline]
+const fn default_max_fee_rate() -> f64 {
+    200.0
+}
+
+impl Default for FeeBumpingConfig {
+    fn default() -> Self {
+        Self {
+            blocks_until_bump: default_blocks_until_bump(),
+            fee_bump_percent: default_fee_bump_percent(),
+            max_fee_rate: default_max_fee_rate(),
+        }
+    }
+}
+
+impl FeeBumpingConfig {
+    /// Fee rate to replace transactions sent with `fee_rate` and unconfirmed for
+    /// `blocks_unconfirmed` blocks with. `None` if they should not be replaced.
+    pub fn bumped_fee_rate(&self, fee_rate: f64, blocks_unconfirmed: u64) -> Option<f64> {
+        if blocks_unconfirmed < self.blocks_until_bump {
+            return None;
+        }
+        self.raised_fee_rate(fee_rate, self.fee_bump_percent)
+    }
+
+    /// `fee_rate` raised by `percent`, capped at the maximum fee rate.
+    /// `None` if `fee_rate` is at the maximum already.
+    pub fn raised_fee_rate(&self, fee_rate: f64, percent: u64) -> Option<f64> {
+        if fee_rate >= self.max_fee_rate {
+            return None;
+        }
+        let raised = fee_rate * (1.0 + percent as f64 / 100.0);
+        Some(raised.min(self.max_fee_rate))
+    }
+}
+
+/// The latest commit and reveal txs sent, which are replaced if they stall
+struct LatestInscription {
+    inscription: InscriptionBody,
+    fee_sat_per_vbyte: f64,
+    /// Block count of the chain when the txs were sent or last replaced
+    sent_at_height: Option<u64>,
 }
 
 const FINALITY_DEPTH: u64 = 4; // blocks
 const POLLING_INTERVAL: u64 = 10; // seconds
+const MAX_SEND_ATTEMPTS: u32 = 10; // per tx, before the blob is given up on
+
+/// Whether the wallet tx `chunk_tx` is included in the block the confirmed `tx` is included in
+fn in_same_block(chunk_tx: Option<&WalletTransaction>, tx: &WalletTransaction) -> bool {
+    chunk_tx.is_some_and(|chunk_tx| {
+        chunk_tx.confirmations > 0
+            && chunk_tx.blockhash.is_some()
+            && chunk_tx.blockhash == tx.blockhash
+    })
+}
+
+/// Waits for the next tick of the interval, forever if there is no interval
+async fn tick(interval: Option<&mut tokio::time::Interval>) {
+    match interval {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
 
 impl BitcoinService {
     // Create a new instance of the DA service from the given configuration.
@@ -88,6 +301,11 @@ impl BitcoinService {
             private_key,
             chain_params.reveal_tx_id_prefix,
             tx,
+            config.utxo_management,
+            config.fee_bumping,
+            config.compression.unwrap_or_default(),
+            config.fee_estimation.unwrap_or_default(),
+            config.block_source.unwrap_or_default(),
         )
         .await;
 
@@ -117,39 +335,181 @@ impl BitcoinService {
 
                 trace!("BitcoinDA queue is initialized. Waiting for the first request...");
 
+                // UTXOs are managed and stalled txs are replaced between requests,
+                // so UTXOs are not spent by two txs at once
+                let mut utxo_checks = this.utxo_management.as_ref().map(|config| {
+                    tokio::time::interval(Duration::from_secs(config.check_interval_secs))
+                });
+                let mut fee_bump_checks = this
+                    .fee_bumping
+                    .as_ref()
+                    .map(|_| tokio::time::interval(Duration::from_secs(POLLING_INTERVAL)));
+                let mut latest_inscription: Option<LatestInscription> = None;
+
                 // We execute commit and reveal txs one by one to chain them
-                while let Some(request) = rx.recv().await {
+                loop {
+                    let request = tokio::select! {
+                        request = rx.recv() => request,
+                        _ = tick(utxo_checks.as_mut()) => {
+                            if let Err(e) = this.manage_utxos(prev_tx.as_ref()).await {
+                                error!(?e, "Failed to manage UTXOs");
+                            }
+                            continue;
+                        }
+                        _ = tick(fee_bump_checks.as_mut()) => {
+                            if let Err(e) = this
+                                .bump_stalled_transaction(&mut prev_tx, &mut latest_inscription)
+                                .await
+                            {
+                                error!(?e, "Failed to bump fee of stalled transaction");
+                            }
+                            continue;
+                        }
+                    };
+                    let Some(request) = request else {
+                        break;
+                    };
                     trace!("A new request is received");
-                    let prev = prev_tx.take();
-                    loop {
-                        // Build and send tx with retries:
-                        let blob = request.blob.clone();
-                        let fee_sat_per_vbyte = match this.get_fee_rate().await {
-                            Ok(rate) => rate,
+                    let mut inscriptions = match this.compress_into_inscriptions(&request.blob) {
+                        Ok(inscriptions) => inscriptions,
+                        Err(e) => {
+                            error!(?e, "Blob can't be sent to BitcoinDA");
+                            let _ = request.notify.send(Err(e));
+                            continue;
+                        }
+                    };
+                    if let Some(fee_bump) = request.fee_bump {
+                        // Failed replacements are not retried here, the sender decides to retry
+                        let replaced_tx = this.latest_replacement(fee_bump.replaced_tx.0);
+                        // Only the last chunk of a blob is replaced, the others are its ancestors
+                        let inscription = inscriptions.pop().expect("A blob has an inscription");
+                        // Never lower than the fee rate the replaced tx pays
+                        let paid_fee_rate = latest_inscription
+                            .as_ref()
+                            .map_or(0.0, |latest| latest.fee_sat_per_vbyte);
+                        // Requested bumps are capped like the bumps of stalled txs
+                        let fee_bumping =
+                            this.with_fee_ceiling(this.fee_bumping.clone().unwrap_or_default());
+                        let result = match this.get_fee_rate().await {
+                            Ok(fee_rate) => {
+                                let Some(fee_sat_per_vbyte) = fee_bumping.raised_fee_rate(
+                                    fee_rate.max(paid_fee_rate),
+                                    fee_bump.fee_bump_percent,
+                                ) else {
+                                    warn!(
+                                        max_fee_rate = fee_bumping.max_fee_rate,
+                                        "Tx is not replaced, its fee rate is at the maximum"
+                                    );
+                                    let _ = request.notify.send(Err(anyhow::anyhow!(
+                                        "Fee rate is at the maximum of {} sat/vB",
+                                        fee_bumping.max_fee_rate
+                                    )));
+                                    continue;
+                                };
+                                this.replace_latest_transaction(
+                                    prev_tx.as_ref(),
+                                    replaced_tx,
+                                    inscription.clone(),
+                                    fee_sat_per_vbyte,
+                                )
+                                .await
+                                .map(|tx| (tx, fee_sat_per_vbyte))
+                            }
+                            Err(e) => Err(e),
+                        };
+                        match result {
+                            Ok((tx, fee_sat_per_vbyte)) => {
+                                let tx_id = TxidWrapper(tx.id);
+                                info!(%tx.id, "Replaced tx on BitcoinDA");
+                                prev_tx = Some(tx);
+                                latest_inscription = Some(LatestInscription {
+                                    inscription,
+                                    fee_sat_per_vbyte,
+                                    sent_at_height: this.block_source.get_block_count().await.ok(),
+                                });
+                                let _ = request.notify.send(Ok(tx_id));
+                            }
                             Err(e) => {
-                                error!(?e, "Failed to call get_fee_rate. Retrying...");
-                                tokio::time::sleep(Duration::from_secs(1)).await;
-                                continue;
+                                error!(?e, "Failed to replace transaction on DA layer");
+                                let _ = request.notify.send(Err(e));
+                            }
+                        }
+                        continue;
+                    }
+                    if inscriptions.len() > 1 {
+                        info!(
+                            chunks = inscriptions.len(),
+                            "Sending blob in chunks to BitcoinDA"
+                        );
+                    }
+                    // Chunks are sent one by one, a failed chunk is retried after the sent ones.
+                    // The blob is given up on if a chunk keeps failing, the sender decides to retry.
+                    let mut reveals = vec![];
+                    let mut fee_sat_per_vbyte = 0.0;
+                    let mut failure = None;
+                    for inscription in inscriptions.iter() {
+                        let mut attempts = 0;
+                        let tx = loop {
+                            attempts += 1;
+                            // Build and send tx with retries:
+                            let result = match this.get_fee_rate().await {
+                                Ok(rate) => {
+                                    fee_sat_per_vbyte = rate;
+                                    this.send_transaction_with_fee_rate(
+                                        prev_tx.clone(),
+                                        inscription.clone(),
+                                        fee_sat_per_vbyte,
+                                    )
+                                    .await
+                                }
+                                Err(e) => Err(e),
+                            };
+                            match result {
+                                Ok(tx) => break Ok(tx),
+                                Err(e) if attempts >= MAX_SEND_ATTEMPTS => break Err(e),
+                                Err(e) => {
+                                    error!(
+                                        ?e,
+                                        "Failed to send transaction to DA layer. Retrying..."
+                                    );
+                                    tokio::time::sleep(Duration::from_secs(1)).await;
+                                }
                             }
                         };
-                        match this
-                            .send_transaction_with_fee_rate(prev.clone(), blob, fee_sat_per_vbyte)
-                            .await
-                        {
+                        match tx {
                             Ok(tx) => {
-                                let tx_id = TxidWrapper(tx.id);
                                 info!(%tx.id, "Sent tx to BitcoinDA");
+                                reveals.push(tx.id);
                                 prev_tx = Some(tx);
-                                let _ = request.notify.send(Ok(tx_id));
+                                latest_inscription = Some(LatestInscription {
+                                    inscription: inscription.clone(),
+                                    fee_sat_per_vbyte,
+                                    sent_at_height: this.block_source.get_block_count().await.ok(),
+                                });
                             }
                             Err(e) => {
-                                error!(?e, "Failed to send transaction to DA layer");
-                                tokio::time::sleep(Duration::from_secs(1)).await;
-                                continue;
+                                failure = Some(e);
+                                break;
                             }
                         }
-                        break;
                     }
+                    if let Some(e) = failure {
+                        error!(
+                            ?e,
+                            chunks_sent = reveals.len(),
+                            "Failed to send blob to BitcoinDA"
+                        );
+                        let _ = request.notify.send(Err(e));
+                        continue;
+                    }
+                    let last_reveal = reveals.pop().expect("A blob has an inscription");
+                    if !reveals.is_empty() {
+                        this.chunk_reveals
+                            .lock()
+                            .unwrap()
+                            .insert(last_reveal, reveals);
+                    }
+                    let _ = request.notify.send(Ok(TxidWrapper(last_reveal)));
                 }
 
                 error!("BitcoinDA queue stopped");
@@ -172,16 +532,34 @@ impl BitcoinService {
 
         let (tx, _rx) = unbounded_channel();
 
+        let fee_estimation = config.fee_estimation.unwrap_or_default();
+        let fee_estimator = fee_estimation.estimator(&client).into();
+        let block_source = config
+            .block_source
+            .unwrap_or_default()
+            .block_source(&client)
+            .into();
+
         Self {
             client,
+            block_source,
             rollup_name: chain_params.rollup_name,
             network,
             da_private_key: private_key,
             reveal_tx_id_prefix: chain_params.reveal_tx_id_prefix,
             inscribes_queue: tx,
+            utxo_management: config.utxo_management,
+            fee_bumping: config.fee_bumping,
+            compression: config.compression.unwrap_or_default(),
+            fee_estimation,
+            fee_estimator,
+            fee_ceilings: Default::default(),
+            replacements: Default::default(),
+            chunk_reveals: Default::default(),
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn with_client(
         client: BitcoinNode,
         rollup_name: String,
@@ -189,6 +567,11 @@ impl BitcoinService {
         da_private_key: Option<SecretKey>,
         reveal_tx_id_prefix: Vec<u8>,
         inscribes_queue: UnboundedSender<BlobWithNotifier<TxidWrapper>>,
+        utxo_management: Option<UtxoManagementConfig>,
+        fee_bumping: Option<FeeBumpingConfig>,
+        compression: CompressionConfig,
+        fee_estimation: FeeEstimationConfig,
+        block_source: BlockSourceConfig,
     ) -> Self {
         let wallets = client
             .list_wallets()
@@ -199,13 +582,25 @@ impl BitcoinService {
             tracing::warn!("No loaded wallet found!");
         }
 
+        let fee_estimator = fee_estimation.estimator(&client).into();
+        let block_source = block_source.block_source(&client).into();
+
         Self {
             client,
+            block_source,
             rollup_name,
             network,
             da_private_key,
             reveal_tx_id_prefix,
             inscribes_queue,
+            utxo_management,
+            fee_bumping,
+            compression,
+            fee_estimation,
+            fee_estimator,
+            fee_ceilings: Default::default(),
+            replacements: Default::default(),
+            chunk_reveals: Default::default(),
         }
     }
 
@@ -260,35 +655,301 @@ impl BitcoinService {
     pub async fn send_transaction_with_fee_rate(
         &self,
         prev_tx: Option<TxWithId>,
-        blob: Vec<u8>,
+        inscription: InscriptionBody,
         fee_sat_per_vbyte: f64,
     ) -> Result<TxWithId, anyhow::Error> {
-        let client = self.client.clone();
-        let network = self.network;
-
-        let rollup_name = self.rollup_name.clone();
-        let da_private_key = self.da_private_key.expect("No private key set");
-
-        // Compress the blob
-        let blob = compress_blob(&blob);
-
         // get all available utxos
-        let utxos: Vec<UTXO> = self.get_utxos().await?;
+        let mut utxos: Vec<UTXO> = self.get_utxos().await?;
+
+        // spend the pool of confirmed utxos, so commit txs don't chain on unconfirmed change
+        if let Some(config) = &self.utxo_management {
+            if utxos
+                .iter()
+                .any(|utxo| utxo.confirmations > 0 && utxo.amount >= config.utxo_amount)
+            {
+                utxos.retain(|utxo| utxo.confirmations > 0);
+            }
+        }
 
         // get address from a utxo
         let address = Address::from_str(&utxos[0].address.clone())
             .unwrap()
-            .require_network(network)
+            .require_network(self.network)
             .expect("Invalid network for address");
 
-        // sign the blob for authentication of the sequencer
+        self.inscribe_blob(prev_tx, utxos, address, inscription, fee_sat_per_vbyte)
+            .await
+    }
+
+    /// Sends the inscription in a transaction replacing the commit and reveal transactions of
+    /// `replaced_tx`, which must be the latest transaction sent.
+    /// The replacement spends an input of the replaced commit transaction,
+    /// so only one of them can be included in a block.
+    #[instrument(level = "trace", skip(self, latest_tx, inscription), err)]
+    async fn replace_latest_transaction(
+        &self,
+        latest_tx: Option<&TxWithId>,
+        replaced_tx: Txid,
+        inscription: InscriptionBody,
+        fee_sat_per_vbyte: f64,
+    ) -> Result<TxWithId, anyhow::Error> {
+        let replaced_tx = latest_tx
+            .filter(|tx| tx.id == replaced_tx)
+            .ok_or_else(|| anyhow::anyhow!("Only the latest transaction can be replaced"))?;
+
+        // the reveal tx pays to the address of the wallet
+        let address = Address::from_script(&replaced_tx.tx.output[0].script_pubkey, self.network)?;
+
+        // The replacement has to spend an input of the replaced commit tx.
+        // An input spending an output 0 is required the way the output 0 of prev_tx is.
+        let commit_tx = self
+            .get_wallet_transaction(replaced_tx.tx.input[0].previous_output.txid)
+            .await?;
+        let mut required_tx = None;
+        let mut utxos = vec![];
+        for input in commit_tx.input.iter() {
+            let outpoint = input.previous_output;
+            let parent_tx = self.get_wallet_transaction(outpoint.txid).await?;
+            if outpoint.vout == 0 && required_tx.is_none() {
+                required_tx = Some(TxWithId {
+                    id: outpoint.txid,
+                    tx: parent_tx,
+                });
+                continue;
+            }
+            let output = &parent_tx.output[outpoint.vout as usize];
+            // fields other then tx_id, vout, script_pubkey and amount are not really important.
+            utxos.push(UTXO {
+                tx_id: outpoint.txid,
+                vout: outpoint.vout,
+                script_pubkey: output.script_pubkey.to_hex_string(),
+                address: "ANY".into(),
+                amount: output.value.to_sat(),
+                confirmations: 0,
+                spendable: true,
+                solvable: true,
+            });
+        }
+        // Other utxos can only pay the higher fee if an input of the replaced tx is required
+        if required_tx.is_some() {
+            utxos.extend(self.get_utxos().await.unwrap_or_default());
+        }
+
+        info!(
+            replaced_tx = %replaced_tx.id,
+            fee_sat_per_vbyte, "Replacing stalled tx on BitcoinDA"
+        );
+        let replacement = self
+            .inscribe_blob(required_tx, utxos, address, inscription, fee_sat_per_vbyte)
+            .await?;
+
+        BITCOIN_DA_FEE_BUMPS.inc();
+        self.replacements
+            .lock()
+            .unwrap()
+            .insert(replaced_tx.id, replacement.id);
+        // the replacement is the last chunk of the same blob
+        let mut chunk_reveals = self.chunk_reveals.lock().unwrap();
+        if let Some(reveals) = chunk_reveals.get(&replaced_tx.id).cloned() {
+            chunk_reveals.insert(replacement.id, reveals);
+        }
+        drop(chunk_reveals);
+        Ok(replacement)
+    }
+
+    /// Follows the replacements of the tx to the latest one
+    fn latest_replacement(&self, mut txid: Txid) -> Txid {
+        let replacements = self.replacements.lock().unwrap();
+        while let Some(replacement) = replacements.get(&txid) {
+            txid = *replacement;
+        }
+        txid
+    }
+
+    /// Replaces the latest commit and reveal txs with higher fee ones if they are not
+    /// confirmed for `blocks_until_bump` blocks, until the fee rate reaches the maximum.
+    #[instrument(level = "trace", skip_all, err)]
+    async fn bump_stalled_transaction(
+        &self,
+        prev_tx: &mut Option<TxWithId>,
+        latest_inscription: &mut Option<LatestInscription>,
+    ) -> Result<(), anyhow::Error> {
+        let (Some(config), Some(tx), Some(inscription)) = (
+            self.fee_bumping
+                .clone()
+                .map(|config| self.with_fee_ceiling(config)),
+            prev_tx.clone(),
+            latest_inscription.as_mut(),
+        ) else {
+            return Ok(());
+        };
+
+        let height = self.block_source.get_block_count().await?;
+        let Some(sent_at_height) = inscription.sent_at_height else {
+            inscription.sent_at_height = Some(height);
+            return Ok(());
+        };
+
+        match self.get_transaction_status(&TxidWrapper(tx.id)).await? {
+            TxStatus::Pending => {}
+            TxStatus::Confirmed(_) => {
+                *latest_inscription = None;
+                return Ok(());
+            }
+            TxStatus::Dropped => {
+                warn!(%tx.id, "Latest tx on BitcoinDA is dropped, it is not replaced");
+                *latest_inscription = None;
+                return Ok(());
+            }
+        }
+
+        let Some(fee_sat_per_vbyte) = config.bumped_fee_rate(
+            inscription.fee_sat_per_vbyte,
+            height.saturating_sub(sent_at_height),
+        ) else {
+            return Ok(());
+        };
+
+        let replacement = self
+            .replace_latest_transaction(
+                Some(&tx),
+                tx.id,
+                inscription.inscription.clone(),
+                fee_sat_per_vbyte,
+            )
+            .await?;
+        info!(
+            replaced_tx = %tx.id,
+            %replacement.id,
+            fee_sat_per_vbyte,
+            "Bumped fee of stalled tx on BitcoinDA"
+        );
+
+        inscription.fee_sat_per_vbyte = fee_sat_per_vbyte;
+        inscription.sent_at_height = Some(height);
+        *prev_tx = Some(replacement);
+        Ok(())
+    }
+
+    /// The fee bumping config with the reloaded maximum fee rate of bumps, if one is set
+    fn with_fee_ceiling(&self, mut config: FeeBumpingConfig) -> FeeBumpingConfig {
+        if let Some(max_fee_bump_rate) = self.fee_ceilings.lock().unwrap().max_fee_bump_rate {
+            config.max_fee_rate = max_fee_bump_rate;
+        }
+        config
+    }
+
+    /// Refills the pool of confirmed UTXOs or consolidates dust, if needed.
+    /// The output of `prev_tx` the next commit transaction spends is not touched.
+    #[instrument(level = "trace", skip_all, err)]
+    async fn manage_utxos(&self, prev_tx: Option<&TxWithId>) -> Result<(), anyhow::Error> {
+        let Some(config) = &self.utxo_management else {
+            return Ok(());
+        };
+
+        let utxos = self.client.get_utxos().await?;
+        let fee_sat_per_vbyte = self.get_fee_rate().await?;
+        let reserved = prev_tx.map(|tx| (tx.id, 0));
+        let Some(action) = plan_utxo_management(&utxos, reserved, config, fee_sat_per_vbyte) else {
+            return Ok(());
+        };
+
+        let (inputs, output_values) = match action {
+            UtxoManagementAction::Split {
+                input,
+                output_count,
+            } => (vec![input], vec![config.utxo_amount; output_count]),
+            UtxoManagementAction::Consolidate { inputs } => (inputs, vec![]),
+        };
+
+        let address = Address::from_str(&inputs[0].address)?.require_network(self.network)?;
+        let tx = build_utxo_management_transaction(
+            &inputs,
+            &address,
+            &output_values,
+            fee_sat_per_vbyte,
+        )?;
+
+        let signed_tx = self
+            .client
+            .sign_raw_transaction_with_wallet(encode::serialize(&tx).encode_hex())
+            .await?;
+        let txid = self.client.send_raw_transaction(signed_tx).await?;
+        info!(
+            %txid,
+            inputs = inputs.len(),
+            outputs = tx.output.len(),
+            "Sent UTXO management tx"
+        );
+
+        Ok(())
+    }
+
+    async fn get_wallet_transaction(&self, txid: Txid) -> Result<Transaction, anyhow::Error> {
+        let tx = self
+            .client
+            .get_wallet_transaction(txid.to_string())
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Transaction {} is not known to the wallet", txid))?;
+        Ok(parse_hex_transaction(&tx.hex)?)
+    }
+
+    /// Compresses the blob with the configured codec and splits it into inscriptions
+    /// Fails if the compressed blob does not fit into the max. number of chunks.
+    fn compress_into_inscriptions(&self, blob: &[u8]) -> anyhow::Result<Vec<InscriptionBody>> {
+        let codec = self.compression.codec;
+        let compressed = compress_blob(blob, &self.compression);
+
+        BITCOIN_DA_BLOB_BYTES.inc_by(blob.len() as u64);
+        BITCOIN_DA_COMPRESSED_BLOB_BYTES.inc_by(compressed.len() as u64);
+        if !compressed.is_empty() {
+            BITCOIN_DA_COMPRESSION_RATIO
+                .with_label_values(&[codec.as_str()])
+                .observe(blob.len() as f64 / compressed.len() as f64);
+        }
+
+        if compressed.len() > MAX_CHUNKS * MAX_CHUNK_SIZE {
+            anyhow::bail!(
+                "Compressed blob of {} bytes is larger than {} chunks",
+                compressed.len(),
+                MAX_CHUNKS
+            );
+        }
+        Ok(split_into_inscriptions(compressed, codec, MAX_CHUNK_SIZE))
+    }
+
+    async fn inscribe_blob(
+        &self,
+        prev_tx: Option<TxWithId>,
+        utxos: Vec<UTXO>,
+        address: Address,
+        inscription: InscriptionBody,
+        fee_sat_per_vbyte: f64,
+    ) -> Result<TxWithId, anyhow::Error> {
+        let client = self.client.clone();
+        let network = self.network;
+
+        let rollup_name = self.rollup_name.clone();
+        let da_private_key = self.da_private_key.expect("No private key set");
+
+        // sign the blob, the compression version and chunk header for authentication of the sequencer
         let (signature, public_key) =
-            sign_blob_with_private_key(&blob, &da_private_key).expect("Sequencer sign the blob");
+            sign_blob_with_private_key(&inscription.signed_message(), &da_private_key)
+                .expect("Sequencer sign the blob");
+
+        // amounts of the outputs the commit tx can spend, to report its fee
+        let mut spendable_amounts: HashMap<(Txid, u32), u64> = utxos
+            .iter()
+            .map(|utxo| ((utxo.tx_id, utxo.vout), utxo.amount))
+            .collect();
+        if let Some(prev_tx) = &prev_tx {
+            spendable_amounts.insert((prev_tx.id, 0), prev_tx.tx.output[0].value.to_sat());
+        }
 
         // create inscribe transactions
         let (unsigned_commit_tx, reveal_tx) = create_inscription_transactions(
             &rollup_name,
-            blob,
+            inscription,
             signature,
             public_key,
             prev_tx,
@@ -326,21 +987,59 @@ impl BitcoinService {
 
         info!("Blob inscribe tx sent. Hash: {}", reveal_tx_hash);
 
+        let spent: u64 = unsigned_commit_tx
+            .input
+            .iter()
+            .filter_map(|input| {
+                let outpoint = input.previous_output;
+                spendable_amounts.get(&(outpoint.txid, outpoint.vout))
+            })
+            .sum();
+        // everything spent, except the change of the commit tx and the output of the reveal tx
+        let kept: u64 = unsigned_commit_tx.output[1..]
+            .iter()
+            .chain(&reveal_tx.tx.output)
+            .map(|output| output.value.to_sat())
+            .sum();
+        BITCOIN_DA_FEES_PAID_SATS.inc_by(spent.saturating_sub(kept));
+
         Ok(reveal_tx)
     }
 
+    /// Header of the block at the given height, which has to be mined
+    async fn get_header_at(&self, height: u64) -> Result<HeaderWrapper, anyhow::Error> {
+        let block_hash = self
+            .block_source
+            .get_block_hash(height)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Block {} not found", height))?;
+        self.block_source.get_block_header(block_hash).await
+    }
+
     #[instrument(level = "trace", skip_all, ret)]
     pub async fn get_fee_rate(&self) -> Result<f64, anyhow::Error> {
-        if self.network == bitcoin::Network::Regtest {
+        if self.network == bitcoin::Network::Regtest
+            && self.fee_estimation.source == FeeSource::Bitcoind
+        {
             // sometimes local mempool is empty, node cannot estimate
             return Ok(2.0);
         }
 
-        self.client.estimate_smart_fee().await
+        self.estimate_fee_rate().await
+    }
+
+    /// Estimates the fee rate with the configured source, clamped into the configured bounds
+    async fn estimate_fee_rate(&self) -> Result<f64, anyhow::Error> {
+        let fee_rate = self.fee_estimator.estimate_fee_rate().await?;
+        let mut fee_estimation = self.fee_estimation.clone();
+        if let Some(max_fee_rate) = self.fee_ceilings.lock().unwrap().max_fee_rate {
+            fee_estimation.max_fee_rate = max_fee_rate;
+        }
+        fee_estimation.clamp(fee_rate)
     }
 }
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, core::hash::Hash)]
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, core::hash::Hash)]
 pub struct TxidWrapper(Txid);
 impl From<TxidWrapper> for [u8; 32] {
     fn from(val: TxidWrapper) -> Self {
@@ -368,32 +1067,16 @@ impl DaService for BitcoinService {
     async fn get_block_at(&self, height: u64) -> Result<Self::FilteredBlock, Self::Error> {
         debug!("Getting block at height {}", height);
 
-        let block_hash;
-        loop {
-            block_hash = match self.client.get_block_hash(height).await {
-                Ok(block_hash_response) => block_hash_response,
-                Err(error) => {
-                    match error.downcast_ref::<RPCError>() {
-                        Some(error) => {
-                            if error.code == -8 {
-                                info!("Block not found, waiting");
-                                tokio::time::sleep(Duration::from_secs(POLLING_INTERVAL)).await;
-                                continue;
-                            } else {
-                                // other error, return message
-                                return Err(anyhow::anyhow!(error.message.clone()));
-                            }
-                        }
-                        None => {
-                            return Err(anyhow::anyhow!(error));
-                        }
-                    }
+        let block_hash = loop {
+            match self.block_source.get_block_hash(height).await? {
+                Some(block_hash) => break block_hash,
+                None => {
+                    info!("Block not found, waiting");
+                    tokio::time::sleep(Duration::from_secs(POLLING_INTERVAL)).await;
                 }
-            };
-
-            break;
-        }
-        let block = self.client.get_block(block_hash).await?;
+            }
+        };
+        let block = self.block_source.get_block(block_hash).await?;
 
         Ok(block)
     }
@@ -403,14 +1086,18 @@ impl DaService for BitcoinService {
     async fn get_last_finalized_block_header(
         &self,
     ) -> Result<<Self::Spec as DaSpec>::BlockHeader, Self::Error> {
-        let block_count = self.client.get_block_count().await?;
+        let block_count = self.block_source.get_block_count().await?;
 
         let finalized_blockhash = self
-            .client
+            .block_source
             .get_block_hash(block_count - FINALITY_DEPTH)
-            .await?;
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Finalized block not found"))?;
 
-        let finalized_block_header = self.client.get_block_header(finalized_blockhash).await?;
+        let finalized_block_header = self
+            .block_source
+            .get_block_header(finalized_blockhash)
+            .await?;
 
         Ok(finalized_block_header)
     }
@@ -424,13 +1111,47 @@ impl DaService for BitcoinService {
     async fn get_head_block_header(
         &self,
     ) -> Result<<Self::Spec as DaSpec>::BlockHeader, Self::Error> {
-        let best_blockhash = self.client.get_best_blockhash().await?;
+        let best_blockhash = self.block_source.get_best_blockhash().await?;
 
-        let head_block_header = self.client.get_block_header(best_blockhash).await?;
+        let head_block_header = self.block_source.get_block_header(best_blockhash).await?;
 
         Ok(head_block_header)
     }
 
+    // The chain state is derived from the headers of the block and of the first block of its
+    // difficulty adjustment period. The work of the light client starts from zero.
+    #[instrument(level = "trace", skip(self), err)]
+    async fn get_chain_state_at(&self, height: u64) -> Result<DaChainState, Self::Error> {
+        let rules = DifficultyRules::of(self.network);
+        let header = self.get_header_at(height).await?;
+        let period_start = height - height % DIFFICULTY_ADJUSTMENT_INTERVAL;
+        let period_start_time = self.get_header_at(period_start).await?.timestamp() as i64;
+
+        let target_bits =
+            if !rules.no_retargeting && (height + 1) % DIFFICULTY_ADJUSTMENT_INTERVAL == 0 {
+                // The next block starts a new period
+                rules.retarget(header.bits(), header.timestamp() as i64 - period_start_time)
+            } else if rules.allow_min_difficulty_blocks && !rules.no_retargeting {
+                // Blocks at the lowest difficulty keep the target of the last block before them
+                let mut last = header.clone();
+                while last.bits() == rules.pow_limit_bits
+                    && last.height % DIFFICULTY_ADJUSTMENT_INTERVAL != 0
+                {
+                    last = self.get_header_at(last.height - 1).await?;
+                }
+                last.bits()
+            } else {
+                header.bits()
+            };
+
+        Ok(DaChainState {
+            target_bits,
+            tip_time: header.timestamp() as u64,
+            period_start_time: period_start_time as u64,
+            chain_work: [0; 32],
+        })
+    }
+
     // Extract the blob transactions relevant to a particular rollup from a block.
     #[instrument(level = "trace", skip_all)]
     fn extract_relevant_blobs(
@@ -464,20 +1185,24 @@ impl DaService for BitcoinService {
 
         let mut txids = Vec::with_capacity(block.txdata.len());
         let mut wtxids = Vec::with_capacity(block.txdata.len());
+        // positions of the txs of the completeness proof, their wtxids are proven together
+        let mut positions = Vec::new();
         wtxids.push([0u8; 32]);
         let coinbase_tx_hash = block.txdata[0].txid().to_raw_hash().to_byte_array();
         txids.push(coinbase_tx_hash);
         if coinbase_tx_hash.starts_with(self.reveal_tx_id_prefix.as_slice()) {
             completeness_proof.push(block.txdata[0].clone());
+            positions.push(0);
         }
 
-        block.txdata[1..].iter().for_each(|tx| {
+        block.txdata[1..].iter().enumerate().for_each(|(i, tx)| {
             let txid = tx.txid().to_raw_hash().to_byte_array();
             let wtxid = tx.wtxid().to_raw_hash().to_byte_array();
 
             // if tx_hash has two leading zeros, it is in the completeness proof
             if txid.starts_with(self.reveal_tx_id_prefix.as_slice()) {
                 completeness_proof.push(tx.clone());
+                positions.push(i + 1);
             }
 
             wtxids.push(wtxid);
@@ -485,7 +1210,7 @@ impl DaService for BitcoinService {
         });
 
         (
-            InclusionMultiProof::new(txids, wtxids, block.txdata[0].clone()),
+            InclusionMultiProof::new(txids, wtxids, &positions, block.txdata[0].clone()),
             completeness_proof,
         )
     }
@@ -521,6 +1246,7 @@ impl DaService for BitcoinService {
         queue.send(BlobWithNotifier {
             blob: blob.to_vec(),
             notify: tx,
+            fee_bump: None,
         })?;
         rx.await?
     }
@@ -529,6 +1255,56 @@ impl DaService for BitcoinService {
         self.inscribes_queue.clone()
     }
 
+    #[instrument(level = "trace", skip(self))]
+    async fn get_transaction_status(
+        &self,
+        tx_id: &Self::TransactionId,
+    ) -> Result<TxStatus, Self::Error> {
+        let txid = self.latest_replacement(tx_id.0).to_string();
+        let Some(tx) = self.client.get_wallet_transaction(txid.clone()).await? else {
+            return Ok(TxStatus::Dropped);
+        };
+        if tx.confirmations > 0 {
+            // The chunks of a blob are only reassembled if they are in the same block,
+            // a blob split across blocks is lost and reported dropped, so it is sent again
+            let earlier_chunks = self.chunk_reveals.lock().unwrap().get(&tx_id.0).cloned();
+            for chunk_txid in earlier_chunks.unwrap_or_default() {
+                let chunk_tx = self
+                    .client
+                    .get_wallet_transaction(chunk_txid.to_string())
+                    .await?;
+                if !in_same_block(chunk_tx.as_ref(), &tx) {
+                    warn!(%chunk_txid, %txid, "Chunks of a blob are split across blocks");
+                    return Ok(TxStatus::Dropped);
+                }
+            }
+            return Ok(TxStatus::Confirmed(tx.confirmations as u64));
+        }
+        // Txs conflicting with a confirmed tx have negative confirmations,
+        // evicted txs are still known to the wallet but not in the mempool
+        if tx.confirmations == 0 && self.client.is_in_mempool(txid).await? {
+            Ok(TxStatus::Pending)
+        } else {
+            Ok(TxStatus::Dropped)
+        }
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn replace_transaction(
+        &self,
+        blob: &[u8],
+        fee_bump: FeeBump<Self::TransactionId>,
+    ) -> Result<Self::TransactionId, Self::Error> {
+        let queue = self.get_send_transaction_queue();
+        let (tx, rx) = oneshot_channel();
+        queue.send(BlobWithNotifier {
+            blob: blob.to_vec(),
+            notify: tx,
+            fee_bump: Some(fee_bump),
+        })?;
+        rx.await?
+    }
+
     async fn send_aggregated_zk_proof(
         &self,
         _aggregated_proof_data: &[u8],
@@ -542,8 +1318,7 @@ impl DaService for BitcoinService {
 
     #[instrument(level = "trace", skip(self))]
     async fn get_fee_rate(&self) -> Result<u128, Self::Error> {
-        // This already returns ceil, so the conversion should work
-        let res = self.client.estimate_smart_fee().await? as u128;
+        let res = self.estimate_fee_rate().await?.ceil() as u128;
         // multiply with 10^10/4 = 25*10^8 = 2_500_000_000
         let multiplied_fee = res.saturating_mul(2_500_000_000);
         Ok(multiplied_fee)
@@ -555,7 +1330,7 @@ impl DaService for BitcoinService {
 
         let hash = BlockHash::from_byte_array(hash);
 
-        let block = self.client.get_block(hash.to_string()).await?;
+        let block = self.block_source.get_block(hash.to_string()).await?;
         Ok(block)
     }
 
@@ -570,6 +1345,15 @@ impl DaService for BitcoinService {
             self.reveal_tx_id_prefix.as_slice(),
         )
     }
+
+    fn set_fee_ceilings(&self, ceilings: FeeCeilings) {
+        info!(
+            max_fee_rate = ceilings.max_fee_rate,
+            max_fee_bump_rate = ceilings.max_fee_bump_rate,
+            "Fee ceilings of BitcoinDA reloaded"
+        );
+        *self.fee_ceilings.lock().unwrap() = ceilings;
+    }
 }
 
 fn get_relevant_blobs_from_txs(
@@ -578,6 +1362,8 @@ fn get_relevant_blobs_from_txs(
     reveal_tx_id_prefix: &[u8],
 ) -> Vec<BlobWithSender> {
     let mut relevant_txs = Vec::new();
+    // chunked blobs are extracted once their last chunk is found
+    let mut chunks = ChunkAssembler::default();
 
     for tx in txs {
         if !tx
@@ -589,21 +1375,15 @@ fn get_relevant_blobs_from_txs(
             continue;
         }
 
-        let parsed_inscription = parse_transaction(&tx, rollup_name);
+        if let Some(extracted) = extract_blob(&tx, rollup_name, &mut chunks) {
+            // Txids are displayed in reverse byte order
+            let mut txid = tx.txid().to_byte_array();
+            txid.reverse();
 
-        if let Ok(inscription) = parsed_inscription {
-            if inscription.get_sig_verified_hash().is_some() {
-                // Decompress the blob
-                let decompressed_blob = decompress_blob(&inscription.body);
+            let relevant_tx = BlobWithSender::new(extracted.blob, extracted.sender, extracted.hash)
+                .with_txid(txid);
 
-                let relevant_tx = BlobWithSender::new(
-                    decompressed_blob,
-                    inscription.public_key,
-                    sha256d::Hash::hash(&inscription.body).to_byte_array(),
-                );
-
-                relevant_txs.push(relevant_tx);
-            }
+            relevant_txs.push(relevant_tx);
         }
     }
     relevant_txs
@@ -620,12 +1400,14 @@ mod tests {
     use bitcoin::string::FromHexStr;
     use bitcoin::{BlockHash, CompactTarget};
     use sov_rollup_interface::da::DaVerifier;
-    use sov_rollup_interface::services::da::{DaService, SlotData};
+    use sov_rollup_interface::services::da::{DaService, FeeCeilings, SlotData};
 
-    use super::BitcoinService;
+    use super::{in_same_block, BitcoinService};
+    use crate::fee::{FeeEstimationConfig, FeeSource};
     use crate::helpers::parsers::parse_hex_transaction;
     use crate::helpers::test_utils::{get_mock_data, get_mock_txs};
-    use crate::service::DaServiceConfig;
+    use crate::rpc::WalletTransaction;
+    use crate::service::{DaServiceConfig, FeeBumpingConfig};
     use crate::spec::block::BitcoinBlock;
     use crate::spec::header::HeaderWrapper;
     use crate::spec::transaction::TransactionWrapper;
@@ -642,6 +1424,11 @@ mod tests {
                 "E9873D79C6D87DC0FB6A5778633389F4453213303DA61F20BD67FC233AA33262".to_string(), // Test key, safe to publish
             ),
             fee_rates_to_avg: Some(2), // small to speed up tests
+            utxo_management: None,
+            fee_bumping: None,
+            compression: None,
+            fee_estimation: None,
+            block_source: None,
         };
 
         BitcoinService::new_without_client(
@@ -758,6 +1545,11 @@ mod tests {
                 "E9873D79C6D87DC0FB6A5778633389F4453213303DA61F20BD67FC233AA33261".to_string(), // Test key, safe to publish
             ),
             fee_rates_to_avg: Some(2), // small to speed up tests
+            utxo_management: None,
+            fee_bumping: None,
+            compression: None,
+            fee_estimation: None,
+            block_source: None,
         };
 
         let incorrect_service = BitcoinService::new_without_client(
@@ -877,4 +1669,92 @@ mod tests {
             "Publickey recovered incorrectly!"
         );
     }
+
+    #[test]
+    fn bumps_fee_rate_of_stalled_txs_up_to_max() {
+        let config = FeeBumpingConfig {
+            blocks_until_bump: 3,
+            fee_bump_percent: 50,
+            max_fee_rate: 20.0,
+        };
+
+        assert_eq!(config.bumped_fee_rate(10.0, 2), None);
+        assert_eq!(config.bumped_fee_rate(10.0, 3), Some(15.0));
+        assert_eq!(config.bumped_fee_rate(15.0, 5), Some(20.0));
+        assert_eq!(config.bumped_fee_rate(20.0, 5), None);
+    }
+
+    #[test]
+    fn detects_chunks_split_across_blocks() {
+        let wallet_tx = |confirmations, blockhash: Option<&str>| WalletTransaction {
+            confirmations,
+            hex: String::new(),
+            blockhash: blockhash.map(str::to_string),
+        };
+        let last_chunk = wallet_tx(1, Some("aa"));
+
+        assert!(in_same_block(Some(&wallet_tx(1, Some("aa"))), &last_chunk));
+        // included in an earlier block
+        assert!(!in_same_block(Some(&wallet_tx(2, Some("bb"))), &last_chunk));
+        // pending, evicted or unknown to the wallet
+        assert!(!in_same_block(Some(&wallet_tx(0, None)), &last_chunk));
+        assert!(!in_same_block(None, &last_chunk));
+    }
+
+    #[test]
+    fn raises_requested_fee_bumps_up_to_max() {
+        let config = FeeBumpingConfig {
+            max_fee_rate: 20.0,
+            ..Default::default()
+        };
+
+        assert_eq!(config.raised_fee_rate(10.0, 25), Some(12.5));
+        // Repeated bumps stop at the maximum instead of compounding above it
+        assert_eq!(config.raised_fee_rate(12.5, 100), Some(20.0));
+        assert_eq!(config.raised_fee_rate(20.0, 25), None);
+    }
+
+    #[tokio::test]
+    async fn reloaded_fee_ceilings_cap_fee_rates() {
+        let runtime_config = DaServiceConfig {
+            node_url: "http://localhost:38332".to_string(),
+            node_username: "chainway".to_string(),
+            node_password: "topsecret".to_string(),
+            network: "regtest".to_string(),
+            da_private_key: None,
+            fee_rates_to_avg: None,
+            utxo_management: None,
+            fee_bumping: None,
+            compression: None,
+            fee_estimation: Some(FeeEstimationConfig {
+                source: FeeSource::Static { fee_rate: 50.0 },
+                min_fee_rate: 1.0,
+                max_fee_rate: 100.0,
+            }),
+            block_source: None,
+        };
+        let da_service = BitcoinService::new_without_client(
+            runtime_config,
+            RollupParams {
+                rollup_name: "sov-btc".to_string(),
+                reveal_tx_id_prefix: vec![0, 0],
+            },
+        )
+        .await;
+        let bumping = || da_service.with_fee_ceiling(FeeBumpingConfig::default());
+        assert_eq!(da_service.get_fee_rate().await.unwrap(), 50.0);
+        assert_eq!(bumping().max_fee_rate, 200.0);
+
+        da_service.set_fee_ceilings(FeeCeilings {
+            max_fee_rate: Some(20.0),
+            max_fee_bump_rate: Some(30.0),
+        });
+        assert_eq!(da_service.get_fee_rate().await.unwrap(), 20.0);
+        assert_eq!(bumping().max_fee_rate, 30.0);
+        assert_eq!(bumping().raised_fee_rate(25.0, 50), Some(30.0));
+
+        // Unset ceilings fall back to the config
+        da_service.set_fee_ceilings(FeeCeilings::default());
+        assert_eq!(da_service.get_fee_rate().await.unwrap(), 50.0);
+    }
 }
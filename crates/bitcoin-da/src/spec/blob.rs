@@ -22,8 +22,15 @@ impl BlobWithSender {
             }),
             sender: AddressWrapper(sender),
             hash,
+            txid: None,
         }
     }
+
+    /// Sets the id of the reveal transaction which carried the blob
+    pub fn with_txid(mut self, txid: [u8; 32]) -> Self {
+        self.txid = Some(txid);
+        self
+    }
 }
 
 impl Buf for BlobBuf {
@@ -48,6 +55,12 @@ pub struct BlobWithSender {
     pub sender: AddressWrapper,
 
     pub blob: CountedBufReader<BlobBuf>,
+
+    /// Id of the reveal transaction, in the byte order shown by explorers.
+    /// Only known natively and not serialized, so proofs are not affected.
+    #[borsh(skip)]
+    #[serde(skip)]
+    pub txid: Option<[u8; 32]>,
 }
 
 impl BlobReaderTrait for BlobWithSender {
@@ -69,6 +82,11 @@ impl BlobReaderTrait for BlobWithSender {
         self.blob.total_len()
     }
 
+    #[cfg(feature = "native")]
+    fn da_tx_id(&self) -> Option<[u8; 32]> {
+        self.txid
+    }
+
     #[cfg(feature = "native")]
     fn advance(&mut self, num_bytes: usize) -> &[u8] {
         self.blob.advance(num_bytes);
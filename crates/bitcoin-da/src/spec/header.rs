@@ -4,7 +4,7 @@ use bitcoin::block::Header as BitcoinHeader;
 use bitcoin::consensus::{Decodable, Encodable};
 use bitcoin::hash_types::WitnessMerkleNode;
 use bitcoin::hashes::Hash;
-use bitcoin::BlockHash;
+use bitcoin::{BlockHash, Target, Work};
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use sov_rollup_interface::da::BlockHeaderTrait;
@@ -68,6 +68,31 @@ impl HeaderWrapper {
     pub fn merkle_root(&self) -> [u8; 32] {
         self.header.merkle_root.to_byte_array()
     }
+
+    /// Whether the hash of the header meets the target encoded in it
+    pub fn has_valid_pow(&self) -> bool {
+        self.header.validate_pow(self.header.target()).is_ok()
+    }
+
+    /// The target encoded in the header, in compact form
+    pub fn bits(&self) -> u32 {
+        self.header.bits.to_consensus()
+    }
+
+    /// The target encoded in the header
+    pub fn target(&self) -> Target {
+        self.header.target()
+    }
+
+    /// The expected number of hashes to mine the header
+    pub fn work(&self) -> Work {
+        self.header.work()
+    }
+
+    /// Timestamp of the header in seconds
+    pub fn timestamp(&self) -> u32 {
+        self.header.time
+    }
 }
 
 /// BitcoinHeaderWrapper is a wrapper around BitcoinHeaderWrapper to implement borsh serde
@@ -1,37 +1,213 @@
+use bitcoin::hashes::{sha256d, Hash, HashEngine};
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
 use crate::spec::TransactionWrapper;
 
 // Set of proofs for inclusion of a transaction in a block
+// All the txids are included so the verifier can check no relevant tx is left out,
+// but only the witness tree hashes needed for the wtxids of the relevant txs,
+// so all the relevant txs of a block are proven with a single witness multiproof.
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 pub struct InclusionMultiProof {
     pub txids: Vec<[u8; 32]>,
-    pub wtxids: Vec<[u8; 32]>,
+    // Hashes of the witness tree which can't be computed from the wtxids of the relevant txs,
+    // in the order they are needed while hashing from the leaves to the witness root
+    pub wtxid_proof: Vec<[u8; 32]>,
     pub coinbase_tx: TransactionWrapper,
 }
 
-#[cfg(feature = "native")]
+#[cfg(any(feature = "native", test))]
 impl InclusionMultiProof {
+    /// Creates the proof of the wtxids at the given positions, which have to be ascending.
+    /// The wtxid of the coinbase tx is expected to be all zeros.
     pub(crate) fn new(
         txids: Vec<[u8; 32]>,
         wtxids: Vec<[u8; 32]>,
+        positions: &[usize],
         coinbase_tx: TransactionWrapper,
     ) -> Self {
+        let mut wtxid_proof = Vec::new();
+        let mut level = wtxids;
+        let mut known = positions.to_vec();
+        while level.len() > 1 {
+            let mut parents = Vec::with_capacity(known.len());
+            let mut i = 0;
+            while i < known.len() {
+                let index = known[i];
+                let sibling = index ^ 1;
+                if index % 2 == 0 && known.get(i + 1) == Some(&sibling) {
+                    // both children are known
+                    i += 1;
+                } else if sibling < level.len() {
+                    wtxid_proof.push(level[sibling]);
+                }
+                // the last node of an odd level is hashed with itself
+                parents.push(index / 2);
+                i += 1;
+            }
+            level = level
+                .chunks(2)
+                .map(|pair| hash_pair(&pair[0], pair.get(1).unwrap_or(&pair[0])))
+                .collect();
+            known = parents;
+        }
+
         InclusionMultiProof {
             txids,
-            wtxids,
+            wtxid_proof,
             coinbase_tx,
         }
     }
 }
 
+impl InclusionMultiProof {
+    /// Computes the witness root from the wtxids of the relevant txs at their positions
+    /// in the block and the hashes of the proof. `None` if the proof does not fit the leaves.
+    pub fn witness_root(&self, leaves: &[(usize, [u8; 32])]) -> Option<[u8; 32]> {
+        let mut width = self.txids.len();
+        if leaves.is_empty() || leaves.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return None;
+        }
+
+        let mut proof = self.wtxid_proof.iter();
+        let mut level = leaves.to_vec();
+        while width > 1 {
+            let mut parents = Vec::with_capacity(level.len());
+            let mut i = 0;
+            while i < level.len() {
+                let (index, hash) = level[i];
+                if index >= width {
+                    return None;
+                }
+                let sibling_index = index ^ 1;
+                let sibling = if index % 2 == 0
+                    && level.get(i + 1).map(|(index, _)| *index) == Some(sibling_index)
+                {
+                    i += 1;
+                    level[i].1
+                } else if sibling_index >= width {
+                    // the last node of an odd level is hashed with itself
+                    hash
+                } else {
+                    *proof.next()?
+                };
+                let parent = if index % 2 == 0 {
+                    hash_pair(&hash, &sibling)
+                } else {
+                    hash_pair(&sibling, &hash)
+                };
+                parents.push((index / 2, parent));
+                i += 1;
+            }
+            level = parents;
+            width = width.div_ceil(2);
+        }
+
+        // all the hashes of the proof have to be used
+        if proof.next().is_some() {
+            return None;
+        }
+        Some(level[0].1)
+    }
+}
+
+fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut engine = sha256d::Hash::engine();
+    engine.input(left);
+    engine.input(right);
+    sha256d::Hash::from_engine(engine).to_byte_array()
+}
+
 impl Default for InclusionMultiProof {
     fn default() -> Self {
         InclusionMultiProof {
             txids: vec![],
-            wtxids: vec![],
+            wtxid_proof: vec![],
             coinbase_tx: TransactionWrapper::empty(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::{merkle_tree, Txid};
+
+    use super::*;
+
+    #[test]
+    fn witness_root_of_multiproof_matches_merkle_root() {
+        for count in 1..20usize {
+            let wtxids: Vec<[u8; 32]> = (0..count).map(|i| [i as u8; 32]).collect();
+            let root =
+                merkle_tree::calculate_root(wtxids.iter().copied().map(Txid::from_byte_array))
+                    .unwrap()
+                    .to_byte_array();
+
+            let position_sets: Vec<Vec<usize>> = vec![
+                vec![0],
+                vec![count - 1],
+                (0..count).collect(),
+                (0..count).step_by(2).collect(),
+                (1..count).step_by(3).collect(),
+            ];
+            for positions in position_sets.into_iter().filter(|p| !p.is_empty()) {
+                let proof = InclusionMultiProof::new(
+                    vec![[0; 32]; count],
+                    wtxids.clone(),
+                    &positions,
+                    TransactionWrapper::empty(),
+                );
+                let leaves: Vec<(usize, [u8; 32])> =
+                    positions.iter().map(|&i| (i, wtxids[i])).collect();
+                assert_eq!(
+                    proof.witness_root(&leaves),
+                    Some(root),
+                    "{count} {positions:?}"
+                );
+
+                // the proof is as small as the positions allow
+                if positions.len() == count {
+                    assert!(proof.wtxid_proof.is_empty());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_proofs_not_fitting_leaves() {
+        let wtxids: Vec<[u8; 32]> = (0..13).map(|i| [i as u8; 32]).collect();
+        let positions = [6, 8, 10, 12];
+        let proof = InclusionMultiProof::new(
+            vec![[0; 32]; 13],
+            wtxids.clone(),
+            &positions,
+            TransactionWrapper::empty(),
+        );
+        let leaves: Vec<(usize, [u8; 32])> = positions.iter().map(|&i| (i, wtxids[i])).collect();
+        let root = proof.witness_root(&leaves).unwrap();
+
+        // another wtxid
+        let mut tampered = leaves.clone();
+        tampered[1].1 = [1; 32];
+        assert_ne!(proof.witness_root(&tampered), Some(root));
+
+        // a leaf left out
+        assert_eq!(proof.witness_root(&leaves[1..]), None);
+
+        // leaves out of order
+        let mut reordered = leaves.clone();
+        reordered.swap(0, 1);
+        assert_eq!(proof.witness_root(&reordered), None);
+
+        // a hash too many
+        let mut longer = proof.clone();
+        longer.wtxid_proof.push([0; 32]);
+        assert_eq!(longer.witness_root(&leaves), None);
+
+        // another number of txs
+        let mut wider = proof.clone();
+        wider.txids.push([0; 32]);
+        assert_eq!(wider.witness_root(&leaves), None);
+    }
+}
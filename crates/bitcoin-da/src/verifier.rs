@@ -1,21 +1,125 @@
 use std::collections::HashSet;
 
 use bitcoin::hashes::{sha256d, Hash};
-use bitcoin::{merkle_tree, Txid};
+use bitcoin::{merkle_tree, CompactTarget, Network, Target, Txid, Work};
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
-use sov_rollup_interface::da::{BlockHeaderTrait, DaSpec, DaVerifier};
+use sov_rollup_interface::da::{BlockHeaderTrait, DaChainState, DaSpec, DaVerifier};
 use sov_rollup_interface::digest::Digest;
 use sov_rollup_interface::zk::ValidityCondition;
 use thiserror::Error;
 
-use crate::helpers::compression::decompress_blob;
-use crate::helpers::parsers::parse_transaction;
+use crate::helpers::chunks::ChunkAssembler;
+use crate::helpers::extraction::extract_blob;
 use crate::spec::BitcoinSpec;
 
+/// Number of blocks between difficulty adjustments
+pub const DIFFICULTY_ADJUSTMENT_INTERVAL: u64 = 2016;
+
+/// Time a difficulty adjustment period is expected to take, in seconds
+const POW_TARGET_TIMESPAN: i64 = 14 * 24 * 60 * 60;
+
+/// Time a block is expected to take, in seconds
+const POW_TARGET_SPACING: u64 = 10 * 60;
+
 pub struct BitcoinVerifier {
     rollup_name: String,
     reveal_tx_id_prefix: Vec<u8>,
+    difficulty_rules: DifficultyRules,
+}
+
+/// Difficulty rules of a bitcoin network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DifficultyRules {
+    /// The highest target of a block, in compact form
+    pub pow_limit_bits: u32,
+    /// Blocks mined more than 20 minutes after the previous block can have the highest target,
+    /// except the first block of a difficulty adjustment period
+    pub allow_min_difficulty_blocks: bool,
+    /// The target is never adjusted
+    pub no_retargeting: bool,
+}
+
+impl DifficultyRules {
+    pub fn of(network: Network) -> Self {
+        match network {
+            Network::Testnet => Self {
+                pow_limit_bits: 0x1d00ffff,
+                allow_min_difficulty_blocks: true,
+                no_retargeting: false,
+            },
+            Network::Signet => Self {
+                pow_limit_bits: 0x1e0377ae,
+                allow_min_difficulty_blocks: false,
+                no_retargeting: false,
+            },
+            Network::Regtest => Self {
+                pow_limit_bits: 0x207fffff,
+                allow_min_difficulty_blocks: true,
+                no_retargeting: true,
+            },
+            _ => Self {
+                pow_limit_bits: 0x1d00ffff,
+                allow_min_difficulty_blocks: false,
+                no_retargeting: false,
+            },
+        }
+    }
+
+    /// Target of the next difficulty adjustment period, in compact form: the target of the
+    /// last block of the period, scaled by the time the period took
+    pub fn retarget(&self, bits: u32, timespan: i64) -> u32 {
+        let timespan = timespan.clamp(POW_TARGET_TIMESPAN / 4, POW_TARGET_TIMESPAN * 4);
+        let target = mul_div(
+            target_of_bits(bits).to_be_bytes(),
+            timespan as u64,
+            POW_TARGET_TIMESPAN as u64,
+        );
+        let target = Target::from_be_bytes(target).min(target_of_bits(self.pow_limit_bits));
+        target.to_compact_lossy().to_consensus()
+    }
+}
+
+impl BitcoinVerifier {
+    /// Checks the block headers against the difficulty rules of the network,
+    /// instead of the rules of mainnet
+    pub fn with_network(mut self, network: Network) -> Self {
+        self.difficulty_rules = DifficultyRules::of(network);
+        self
+    }
+}
+
+fn target_of_bits(bits: u32) -> Target {
+    Target::from_compact(CompactTarget::from_consensus(bits))
+}
+
+/// `value * mul / div` of a big-endian 256-bit integer, saturating on overflow
+fn mul_div(value: [u8; 32], mul: u64, div: u64) -> [u8; 32] {
+    // Limbs from the least significant one, with a limb for the overflow of the product
+    let mut limbs = [0u64; 5];
+    for (limb, chunk) in limbs.iter_mut().zip(value.rchunks(8)) {
+        *limb = u64::from_be_bytes(chunk.try_into().expect("Chunks are 8 bytes"));
+    }
+    let mut carry = 0u128;
+    for limb in limbs.iter_mut() {
+        let product = *limb as u128 * mul as u128 + carry;
+        *limb = product as u64;
+        carry = product >> 64;
+    }
+    let mut remainder = 0u128;
+    for limb in limbs.iter_mut().rev() {
+        let dividend = (remainder << 64) | *limb as u128;
+        *limb = (dividend / div as u128) as u64;
+        remainder = dividend % div as u128;
+    }
+    if limbs[4] != 0 {
+        return [0xff; 32];
+    }
+    let mut result = [0; 32];
+    for (chunk, limb) in result.rchunks_mut(8).zip(limbs) {
+        chunk.copy_from_slice(&limb.to_be_bytes());
+    }
+    result
 }
 
 // TODO: custom errors based on our implementation
@@ -34,6 +138,8 @@ pub enum ValidationError {
     IncorrectInclusionProof,
     FailedToCalculateMerkleRoot,
     RelevantTxNotFoundInBlock,
+    InvalidProofOfWork,
+    InvalidDifficulty,
 }
 
 #[derive(
@@ -78,6 +184,7 @@ impl DaVerifier for BitcoinVerifier {
         Self {
             rollup_name: params.rollup_name,
             reveal_tx_id_prefix: params.reveal_tx_id_prefix,
+            difficulty_rules: DifficultyRules::of(Network::Bitcoin),
         }
     }
 
@@ -94,84 +201,16 @@ impl DaVerifier for BitcoinVerifier {
             block_hash: block_header.block_hash().to_byte_array(),
         };
 
-        // check that wtxid's of transactions in completeness proof are included in the InclusionMultiProof
-        // and are in the same order as in the completeness proof
-        let mut iter = inclusion_proof.wtxids.iter();
-        completeness_proof
-            .iter()
-            .all(|tx| iter.any(|&y| y == tx.wtxid().to_byte_array()));
-
-        // verify that one of the outputs of the coinbase transaction has script pub key starting with 0x6a24aa21a9ed,
-        // and the rest of the script pub key is the commitment of witness data.
-        if !completeness_proof.is_empty() {
-            let coinbase_tx = &inclusion_proof.coinbase_tx;
-            // If there are more than one scriptPubKey matching the pattern,
-            // the one with highest output index is assumed to be the commitment.
-            // That  is why the iterator is reversed.
-            let commitment_idx = coinbase_tx.output.iter().rev().position(|output| {
-                output
-                    .script_pubkey
-                    .to_bytes()
-                    .starts_with(&[0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed])
-            });
-            match commitment_idx {
-                // If commitmet does not exist
-                None => {
-                    // Relevant txs should be empty if there is no wtiness data because data is inscribed in the witness
-                    if !blobs.is_empty() {
-                        return Err(ValidationError::InvalidBlock);
-                    }
-                    // Check if all the wtxids are equal to txids
-                    for (wtxid, txid) in inclusion_proof
-                        .wtxids
-                        .iter()
-                        .zip(inclusion_proof.txids.iter())
-                    {
-                        if wtxid != txid {
-                            return Err(ValidationError::InvalidSegWitCommitment);
-                        }
-                    }
-                }
-                Some(mut commitment_idx) => {
-                    let wtxids = inclusion_proof
-                        .wtxids
-                        .iter()
-                        .copied()
-                        .map(Txid::from_byte_array);
-
-                    let merkle_root = merkle_tree::calculate_root(wtxids).unwrap();
-
-                    let input_witness_value = coinbase_tx.input[0].witness.iter().next().unwrap();
-
-                    let mut vec_merkle = merkle_root.to_byte_array().to_vec();
-
-                    vec_merkle.extend_from_slice(input_witness_value);
-
-                    // check with sha256(sha256(<merkle root><witness value>))
-                    let commitment = sha256d::Hash::hash(&vec_merkle);
-
-                    // check if the commitment is correct
-                    // on signet there is an additional commitment after the segwit commitment
-                    // so we check only the first 32 bytes after commitment header (bytes [2, 5])
-                    commitment_idx = coinbase_tx.output.len() - commitment_idx - 1; // The index is reversed
-                    let script_pubkey = coinbase_tx.output[commitment_idx].script_pubkey.to_bytes();
-                    if script_pubkey[6..38] != *commitment.as_byte_array() {
-                        return Err(ValidationError::NonMatchingScript);
-                    }
-                }
-            }
-        }
-
-        // create hash set of blobs
-        let mut blobs_iter = blobs.iter();
-
-        let mut inclusion_iter = inclusion_proof.txids.iter();
+        let mut inclusion_iter = inclusion_proof.txids.iter().enumerate();
 
         let prefix = self.reveal_tx_id_prefix.as_slice();
         // Check starting bytes tx that parsed correctly is in blobs
         let mut completeness_tx_hashes = HashSet::new();
+        // wtxids of the completeness txs at their positions in the block,
+        // all of them are proven against the witness root at once
+        let mut wtxid_leaves = Vec::with_capacity(completeness_proof.len());
 
-        for (index_completeness, tx) in completeness_proof.iter().enumerate() {
+        for tx in completeness_proof.iter() {
             let txid = tx.txid().to_byte_array();
 
             // make sure it starts with the correct prefix
@@ -182,47 +221,59 @@ impl DaVerifier for BitcoinVerifier {
             // make sure completeness txs are ordered same in inclusion proof
             // this logic always start seaching from the last found index
             // ordering should be preserved naturally
-            let is_found_in_block = inclusion_iter.any(|&txid_in_proof| txid_in_proof == txid);
+            let found_in_block = inclusion_iter.find(|&(_, &txid_in_proof)| txid_in_proof == txid);
 
             // assert tx is included in inclusion proof, thus in block
-            if !is_found_in_block {
+            let Some((position, _)) = found_in_block else {
                 return Err(ValidationError::RelevantTxNotFoundInBlock);
-            }
+            };
 
-            // it must be parsed correctly
-            if let Ok(parsed_tx) = parse_transaction(tx, &self.rollup_name) {
-                if let Some(blob_hash) = parsed_tx.get_sig_verified_hash() {
-                    let blob = blobs_iter.next();
+            // the wtxid of the coinbase tx is all zeros in the witness tree
+            let wtxid = if position == 0 {
+                [0; 32]
+            } else {
+                tx.wtxid().to_byte_array()
+            };
+            wtxid_leaves.push((position, wtxid));
 
-                    if blob.is_none() {
-                        return Err(ValidationError::ValidBlobNotFoundInBlobs);
-                    }
+            completeness_tx_hashes.insert(txid);
+        }
 
-                    let blob = blob.unwrap();
-                    if blob.hash != blob_hash {
-                        return Err(ValidationError::BlobWasTamperedWith);
-                    }
+        // create hash set of blobs
+        let mut blobs_iter = blobs.iter();
 
-                    if parsed_tx.public_key != blob.sender.0 {
-                        return Err(ValidationError::IncorrectSenderInBlob);
-                    }
+        // chunked blobs are expected in blobs once their last chunk is found
+        let mut chunks = ChunkAssembler::default();
 
-                    // decompress the blob
-                    let decompressed_blob = decompress_blob(&parsed_tx.body);
+        for tx in completeness_proof.iter() {
+            // it must be parsed correctly, signed and decompressed
+            // the same way the DA service extracts it, look-alike inscriptions are skipped
+            if let Some(extracted) = extract_blob(tx, &self.rollup_name, &mut chunks) {
+                let blob = blobs_iter.next();
 
-                    // read the supplied blob from txs
-                    let mut blob_content = blobs[index_completeness].blob.clone();
-                    blob_content.advance(blob_content.total_len());
-                    let blob_content = blob_content.accumulator();
+                if blob.is_none() {
+                    return Err(ValidationError::ValidBlobNotFoundInBlobs);
+                }
 
-                    // assert tx content is not modified
-                    if blob_content != decompressed_blob {
-                        return Err(ValidationError::BlobContentWasModified);
-                    }
+                let blob = blob.unwrap();
+                if blob.hash != extracted.hash {
+                    return Err(ValidationError::BlobWasTamperedWith);
                 }
-            }
 
-            completeness_tx_hashes.insert(txid);
+                if extracted.sender != blob.sender.0 {
+                    return Err(ValidationError::IncorrectSenderInBlob);
+                }
+
+                // read the supplied blob from txs
+                let mut blob_content = blob.blob.clone();
+                blob_content.advance(blob_content.total_len());
+                let blob_content = blob_content.accumulator();
+
+                // assert tx content is not modified
+                if blob_content != extracted.blob {
+                    return Err(ValidationError::BlobContentWasModified);
+                }
+            }
         }
 
         // assert no extra txs than the ones in the completeness proof are left
@@ -246,6 +297,62 @@ impl DaVerifier for BitcoinVerifier {
             return Err(ValidationError::NonRelevantTxInProof);
         }
 
+        // verify that one of the outputs of the coinbase transaction has script pub key starting with 0x6a24aa21a9ed,
+        // and the rest of the script pub key is the commitment of witness data.
+        if !completeness_proof.is_empty() {
+            let coinbase_tx = &inclusion_proof.coinbase_tx;
+            // If there are more than one scriptPubKey matching the pattern,
+            // the one with highest output index is assumed to be the commitment.
+            // That  is why the iterator is reversed.
+            let commitment_idx = coinbase_tx.output.iter().rev().position(|output| {
+                output
+                    .script_pubkey
+                    .to_bytes()
+                    .starts_with(&[0x6a, 0x24, 0xaa, 0x21, 0xa9, 0xed])
+            });
+            match commitment_idx {
+                // If commitmet does not exist
+                None => {
+                    // Relevant txs should be empty if there is no wtiness data because data is inscribed in the witness
+                    if !blobs.is_empty() {
+                        return Err(ValidationError::InvalidBlock);
+                    }
+                    // Txs can't have witness data without a commitment
+                    if completeness_proof
+                        .iter()
+                        .any(|tx| tx.wtxid().to_byte_array() != tx.txid().to_byte_array())
+                    {
+                        return Err(ValidationError::InvalidSegWitCommitment);
+                    }
+                }
+                Some(mut commitment_idx) => {
+                    // Only the wtxids of the completeness txs are hashed up to the witness root,
+                    // the rest of the witness tree comes from the proof
+                    let merkle_root = inclusion_proof
+                        .witness_root(&wtxid_leaves)
+                        .ok_or(ValidationError::IncorrectInclusionProof)?;
+
+                    let input_witness_value = coinbase_tx.input[0].witness.iter().next().unwrap();
+
+                    let mut vec_merkle = merkle_root.to_vec();
+
+                    vec_merkle.extend_from_slice(input_witness_value);
+
+                    // check with sha256(sha256(<merkle root><witness value>))
+                    let commitment = sha256d::Hash::hash(&vec_merkle);
+
+                    // check if the commitment is correct
+                    // on signet there is an additional commitment after the segwit commitment
+                    // so we check only the first 32 bytes after commitment header (bytes [2, 5])
+                    commitment_idx = coinbase_tx.output.len() - commitment_idx - 1; // The index is reversed
+                    let script_pubkey = coinbase_tx.output[commitment_idx].script_pubkey.to_bytes();
+                    if script_pubkey[6..38] != *commitment.as_byte_array() {
+                        return Err(ValidationError::NonMatchingScript);
+                    }
+                }
+            }
+        }
+
         let tx_root = block_header.merkle_root();
 
         // Inclusion proof is all the txs in the block.
@@ -268,6 +375,54 @@ impl DaVerifier for BitcoinVerifier {
             Err(ValidationError::FailedToCalculateMerkleRoot)
         }
     }
+
+    // Check the target of each header against the difficulty adjustments of the network,
+    // and its proof of work against the target, and add up the work of the headers.
+    // The median time past rule is not checked, it needs the timestamps of 11 blocks.
+    fn verify_headers(
+        &self,
+        chain_state: &DaChainState,
+        block_headers: &[<Self::Spec as DaSpec>::BlockHeader],
+    ) -> Result<DaChainState, Self::Error> {
+        let rules = &self.difficulty_rules;
+        let pow_limit = target_of_bits(rules.pow_limit_bits);
+        let mut state = chain_state.clone();
+
+        for header in block_headers {
+            let height = header.height();
+            let time = header.timestamp() as u64;
+            let period_start = height % DIFFICULTY_ADJUSTMENT_INTERVAL == 0;
+
+            let min_difficulty = rules.allow_min_difficulty_blocks
+                && !period_start
+                && time > state.tip_time + 2 * POW_TARGET_SPACING;
+            let expected_bits = if min_difficulty {
+                rules.pow_limit_bits
+            } else {
+                state.target_bits
+            };
+            if header.bits() != expected_bits {
+                return Err(ValidationError::InvalidDifficulty);
+            }
+            if header.target() > pow_limit || !header.has_valid_pow() {
+                return Err(ValidationError::InvalidProofOfWork);
+            }
+
+            if period_start {
+                state.period_start_time = time;
+            }
+            // The last block of a period sets the target of the next one
+            if !rules.no_retargeting && (height + 1) % DIFFICULTY_ADJUSTMENT_INTERVAL == 0 {
+                let timespan = time as i64 - state.period_start_time as i64;
+                state.target_bits = rules.retarget(header.bits(), timespan);
+            }
+            state.tip_time = time;
+            state.chain_work =
+                (Work::from_be_bytes(state.chain_work) + header.work()).to_be_bytes();
+        }
+
+        Ok(state)
+    }
 }
 
 #[cfg(test)]
@@ -282,10 +437,10 @@ mod tests {
     use bitcoin::hash_types::{TxMerkleNode, WitnessMerkleNode};
     use bitcoin::hashes::Hash;
     use bitcoin::string::FromHexStr;
-    use bitcoin::{BlockHash, CompactTarget, ScriptBuf, Witness};
-    use sov_rollup_interface::da::DaVerifier;
+    use bitcoin::{BlockHash, CompactTarget, Network, ScriptBuf, Witness};
+    use sov_rollup_interface::da::{DaChainState, DaVerifier};
 
-    use super::BitcoinVerifier;
+    use super::{BitcoinVerifier, DifficultyRules};
     use crate::helpers::parsers::parse_transaction;
     use crate::helpers::test_utils::{
         get_blob_with_sender, get_mock_data, get_mock_txs, get_non_segwit_mock_txs,
@@ -361,17 +516,20 @@ mod tests {
         // only used so the completeness proof is not empty
         let completeness_proof = vec![];
 
-        let inclusion_proof = InclusionMultiProof {
-            txids: block_txs
+        let wtxids: Vec<[u8; 32]> = block_txs
+            .iter()
+            .map(|t| t.wtxid().to_byte_array())
+            .collect();
+
+        let inclusion_proof = InclusionMultiProof::new(
+            block_txs
                 .iter()
                 .map(|t| t.txid().to_raw_hash().to_byte_array())
                 .collect(),
-            wtxids: block_txs
-                .iter()
-                .map(|t| t.wtxid().to_byte_array())
-                .collect(),
-            coinbase_tx: block_txs[0].clone(),
-        };
+            wtxids,
+            &[],
+            block_txs[0].clone(),
+        );
 
         // There should not be any blobs
         let txs: Vec<BlobWithSender> = vec![];
@@ -437,20 +595,22 @@ mod tests {
         .map(Into::into)
         .collect();
 
-        let mut inclusion_proof = InclusionMultiProof {
-            txids: block_txs
+        let mut wtxids: Vec<[u8; 32]> = block_txs
+            .iter()
+            .map(|t| t.wtxid().to_byte_array())
+            .collect();
+        // Coinbase tx wtxid should be [0u8;32]
+        wtxids[0] = [0; 32];
+
+        let inclusion_proof = InclusionMultiProof::new(
+            block_txs
                 .iter()
                 .map(|t| t.txid().to_raw_hash().to_byte_array())
                 .collect(),
-            wtxids: block_txs
-                .iter()
-                .map(|t| t.wtxid().to_byte_array())
-                .collect(),
-            coinbase_tx: block_txs[0].clone(),
-        };
-
-        // Coinbase tx wtxid should be [0u8;32]
-        inclusion_proof.wtxids[0] = [0; 32];
+            wtxids,
+            &[6, 8, 10, 12],
+            block_txs[0].clone(),
+        );
 
         let txs: Vec<BlobWithSender> = vec![
             get_blob_with_sender(&block_txs[6]),
@@ -533,20 +693,22 @@ mod tests {
         .map(Into::into)
         .collect();
 
-        let mut inclusion_proof = InclusionMultiProof {
-            txids: block_txs
+        let mut wtxids: Vec<[u8; 32]> = block_txs
+            .iter()
+            .map(|t| t.wtxid().to_byte_array())
+            .collect();
+        // Coinbase tx wtxid should be [0u8;32]
+        wtxids[0] = [0; 32];
+
+        let inclusion_proof = InclusionMultiProof::new(
+            block_txs
                 .iter()
                 .map(|t| t.txid().to_raw_hash().to_byte_array())
                 .collect(),
-            wtxids: block_txs
-                .iter()
-                .map(|t| t.wtxid().to_byte_array())
-                .collect(),
-            coinbase_tx: block_txs[0].clone(),
-        };
-
-        // Coinbase tx wtxid should be [0u8;32]
-        inclusion_proof.wtxids[0] = [0; 32];
+            wtxids,
+            &[6, 8, 10, 12],
+            block_txs[0].clone(),
+        );
 
         let txs: Vec<BlobWithSender> = vec![
             get_blob_with_sender(&block_txs[6]),
@@ -649,20 +811,22 @@ mod tests {
         .map(Into::into)
         .collect();
 
-        let mut inclusion_proof = InclusionMultiProof {
-            txids: block_txs
+        let mut wtxids: Vec<[u8; 32]> = block_txs
+            .iter()
+            .map(|t| t.wtxid().to_byte_array())
+            .collect();
+        // Coinbase tx wtxid should be [0u8;32]
+        wtxids[0] = [0; 32];
+
+        let inclusion_proof = InclusionMultiProof::new(
+            block_txs
                 .iter()
                 .map(|t| t.txid().to_raw_hash().to_byte_array())
                 .collect(),
-            wtxids: block_txs
-                .iter()
-                .map(|t| t.wtxid().to_byte_array())
-                .collect(),
-            coinbase_tx: block_txs[0].clone(),
-        };
-
-        // Coinbase tx wtxid should be [0u8;32]
-        inclusion_proof.wtxids[0] = [0; 32];
+            wtxids,
+            &[6, 8, 10, 12],
+            block_txs[0].clone(),
+        );
 
         let txs: Vec<BlobWithSender> = vec![
             get_blob_with_sender(&block_txs[6]),
@@ -701,8 +865,9 @@ mod tests {
             )
             .is_ok());
 
-        // cahnging the witness txid of coinbase tx to [1; 32] will make it fail
-        inclusion_proof.wtxids[0] = [1; 32];
+        // changing a hash of the witness proof will make it fail
+        let wtxid_proof = inclusion_proof.wtxid_proof.clone();
+        inclusion_proof.wtxid_proof[0] = [1; 32];
 
         assert!(verifier
             .verify_relevant_tx_list(
@@ -713,9 +878,9 @@ mod tests {
             )
             .is_err());
 
-        inclusion_proof.wtxids[0] = [0; 32];
+        inclusion_proof.wtxid_proof = wtxid_proof;
 
-        inclusion_proof.wtxids[1] = [16; 32];
+        inclusion_proof.wtxid_proof.push([16; 32]);
 
         assert!(verifier
             .verify_relevant_tx_list(
@@ -1019,4 +1184,116 @@ mod tests {
             Err(ValidationError::ValidBlobNotFoundInBlobs)
         );
     }
+
+    /// Serialized headers of the first mainnet blocks
+    const MAINNET_HEADERS: [&str; 3] = [
+        "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c",
+        "010000006fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000982051fd1e4ba744bbbe680e1fee14677ba1a3c3540bf7b1cdb606e857233e0e61bc6649ffff001d01e36299",
+        "010000004860eb18bf1b1620e37e9490fc8a427514416fd75159ab86688e9a8300000000d5fdcc541e25de1c7a5addedf24858b8bb665c9f36ef744ee42c316022c90f9bb0bc6649ffff001d08d2bd61",
+    ];
+
+    fn mainnet_header(height: usize) -> Header {
+        bitcoin::consensus::deserialize(&hex::decode(MAINNET_HEADERS[height]).unwrap()).unwrap()
+    }
+
+    fn wrap_header(header: Header, height: usize) -> HeaderWrapper {
+        HeaderWrapper::new(header, 1, height as u64, WitnessMerkleNode::all_zeros())
+    }
+
+    /// Chain state of the mainnet genesis block
+    fn genesis_chain_state() -> DaChainState {
+        DaChainState {
+            target_bits: 0x1d00ffff,
+            tip_time: 1231006505,
+            period_start_time: 1231006505,
+            chain_work: [0; 32],
+        }
+    }
+
+    fn verifier() -> BitcoinVerifier {
+        BitcoinVerifier::new(RollupParams {
+            rollup_name: "sov-btc".to_string(),
+            reveal_tx_id_prefix: vec![0, 0],
+        })
+    }
+
+    #[test]
+    fn verifies_mainnet_headers() {
+        assert_eq!(
+            mainnet_header(2).block_hash().to_string(),
+            "000000006a625f06636b8bb6ac7b960a8d03705d1ace08b1a19da3fdcc99ddbd"
+        );
+        let headers = vec![
+            wrap_header(mainnet_header(1), 1),
+            wrap_header(mainnet_header(2), 2),
+        ];
+
+        let chain_state = verifier()
+            .verify_headers(&genesis_chain_state(), &headers)
+            .unwrap();
+
+        // The work of a block at the lowest difficulty is 0x100010001
+        let mut chain_work = [0; 32];
+        chain_work[27..].copy_from_slice(&[0x02, 0x00, 0x02, 0x00, 0x02]);
+        assert_eq!(
+            chain_state,
+            DaChainState {
+                target_bits: 0x1d00ffff,
+                tip_time: 1231469744,
+                period_start_time: 1231006505,
+                chain_work,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_headers_off_the_difficulty() {
+        let headers = vec![wrap_header(mainnet_header(1), 1)];
+
+        // The headers have the lowest difficulty, while a higher one is expected
+        let chain_state = DaChainState {
+            target_bits: 0x1c3fffc0,
+            ..genesis_chain_state()
+        };
+        assert_eq!(
+            verifier().verify_headers(&chain_state, &headers),
+            Err(ValidationError::InvalidDifficulty)
+        );
+
+        // Regtest targets are not accepted on mainnet
+        let regtest_header = Header {
+            bits: CompactTarget::from_consensus(0x207fffff),
+            ..mainnet_header(1)
+        };
+        let chain_state = DaChainState {
+            target_bits: 0x207fffff,
+            ..genesis_chain_state()
+        };
+        assert_eq!(
+            verifier().verify_headers(&chain_state, &[wrap_header(regtest_header, 1)]),
+            Err(ValidationError::InvalidProofOfWork)
+        );
+
+        let mut tampered_header = mainnet_header(1);
+        tampered_header.nonce += 1;
+        assert_eq!(
+            verifier().verify_headers(&genesis_chain_state(), &[wrap_header(tampered_header, 1)]),
+            Err(ValidationError::InvalidProofOfWork)
+        );
+    }
+
+    #[test]
+    fn retargets_by_the_time_of_the_period() {
+        let rules = DifficultyRules::of(Network::Bitcoin);
+        let timespan = 14 * 24 * 60 * 60;
+
+        assert_eq!(rules.retarget(0x1d00ffff, timespan), 0x1d00ffff);
+        assert_eq!(rules.retarget(0x1d00ffff, timespan / 4), 0x1c3fffc0);
+        assert_eq!(rules.retarget(0x1c3fffc0, timespan * 2), 0x1c7fff80);
+        // The adjustment is limited to a factor of 4
+        assert_eq!(rules.retarget(0x1d00ffff, timespan / 8), 0x1c3fffc0);
+        // and the target to the highest target of the network
+        assert_eq!(rules.retarget(0x1d00ffff, timespan * 2), 0x1d00ffff);
+        assert_eq!(rules.retarget(0x1c3fffc0, timespan * 8), 0x1d00ffff);
+    }
 }
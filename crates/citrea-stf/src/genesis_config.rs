@@ -2,11 +2,15 @@
 //! specific module configurations are obtained from files. This code is responsible for the logic
 //! that transforms module genesis data into Rollup genesis data.
 
+use std::collections::HashSet;
 use std::convert::AsRef;
 use std::path::{Path, PathBuf};
 
-use anyhow::Context as _;
+use anyhow::{bail, ensure, Context as _};
 use citrea_evm::EvmConfig;
+use citrea_sequencer_registry::SequencerRegistryConfig;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 use soft_confirmation_rule_enforcer::SoftConfirmationRuleEnforcerConfig;
 use sov_accounts::AccountConfig;
 pub use sov_modules_api::default_context::DefaultContext;
@@ -21,13 +25,20 @@ use crate::runtime::GenesisConfig;
 use crate::runtime::Runtime;
 
 /// Paths pointing to genesis files.
-pub struct GenesisPaths {
-    /// Accounts genesis path.
-    pub accounts_genesis_path: PathBuf,
-    /// EVM genesis path.
-    pub evm_genesis_path: PathBuf,
-    /// Soft Confirmation Rule Enforcer genesis path.
-    pub soft_confirmation_rule_enforcer_genesis_path: PathBuf,
+pub enum GenesisPaths {
+    /// One genesis file per module
+    Modules {
+        /// Accounts genesis path.
+        accounts_genesis_path: PathBuf,
+        /// EVM genesis path.
+        evm_genesis_path: PathBuf,
+        /// Soft Confirmation Rule Enforcer genesis path.
+        soft_confirmation_rule_enforcer_genesis_path: PathBuf,
+        /// Sequencer Registry genesis path.
+        sequencer_registry_genesis_path: PathBuf,
+    },
+    /// A single JSON or TOML genesis file with a section per module, see [`GenesisBuilder::from_file`]
+    File(PathBuf),
 }
 
 impl GenesisPaths {
@@ -37,22 +48,221 @@ impl GenesisPaths {
     /// Take a look at the contents of the `test_data` directory to see the
     /// expected files.
     pub fn from_dir(dir: impl AsRef<Path>) -> Self {
-        Self {
+        Self::Modules {
             accounts_genesis_path: dir.as_ref().join("accounts.json"),
             evm_genesis_path: dir.as_ref().join("evm.json"),
             soft_confirmation_rule_enforcer_genesis_path: dir
                 .as_ref()
                 .join("soft_confirmation_rule_enforcer.json"),
+            sequencer_registry_genesis_path: dir.as_ref().join("sequencer_registry.json"),
+        }
+    }
+
+    /// Creates a new [`GenesisPaths`] from a directory of per module files or a single genesis file.
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        if path.as_ref().is_dir() {
+            Self::from_dir(path)
+        } else {
+            Self::File(path.as_ref().to_path_buf())
         }
     }
 }
 
+/// Section of the accounts genesis
+const ACCOUNTS: &str = "accounts";
+/// Section of the EVM genesis
+const EVM: &str = "evm";
+/// Section of the soft confirmation rule enforcer genesis
+const SOFT_CONFIRMATION_RULE_ENFORCER: &str = "soft_confirmation_rule_enforcer";
+/// Section of the sequencer registry genesis
+const SEQUENCER_REGISTRY: &str = "sequencer_registry";
+
+/// Assembles the genesis of all modules and validates it.
+///
+/// The genesis of each module is kept as JSON until [`GenesisBuilder::build`],
+/// so it can be read from files or set programmatically, e.g. with `serde_json::json!`.
+#[derive(Debug, Clone, Default)]
+pub struct GenesisBuilder {
+    accounts: Option<Value>,
+    evm: Option<Value>,
+    soft_confirmation_rule_enforcer: Option<Value>,
+    sequencer_registry: Option<Value>,
+}
+
+impl GenesisBuilder {
+    /// Reads the genesis of all modules from the paths
+    pub fn from_paths(genesis_paths: &GenesisPaths) -> anyhow::Result<Self> {
+        match genesis_paths {
+            GenesisPaths::Modules {
+                accounts_genesis_path,
+                evm_genesis_path,
+                soft_confirmation_rule_enforcer_genesis_path,
+                sequencer_registry_genesis_path,
+            } => Ok(Self::default()
+                .accounts(read_json_file(accounts_genesis_path)?)
+                .evm(read_json_file(evm_genesis_path)?)
+                .soft_confirmation_rule_enforcer(read_json_file(
+                    soft_confirmation_rule_enforcer_genesis_path,
+                )?)
+                .sequencer_registry(read_json_file(sequencer_registry_genesis_path)?)),
+            GenesisPaths::File(path) => Self::from_file(path),
+        }
+    }
+
+    /// Reads the genesis of all modules from a single file.
+    /// The file has a section per module: `accounts`, `evm`, `soft_confirmation_rule_enforcer`
+    /// and `sequencer_registry`, each with the content of the module's genesis file.
+    /// Files with a `.toml` extension are read as TOML, all others as JSON.
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read genesis from {}", path.display()))?;
+        let genesis: Value = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            toml::from_str(&data)
+                .with_context(|| format!("Failed to parse genesis from {}", path.display()))?
+        } else {
+            serde_json::from_str(&data)
+                .with_context(|| format!("Failed to parse genesis from {}", path.display()))?
+        };
+        Self::from_value(genesis).with_context(|| format!("Invalid genesis in {}", path.display()))
+    }
+
+    /// Takes the genesis of all modules from a JSON object with a section per module
+    pub fn from_value(genesis: Value) -> anyhow::Result<Self> {
+        let Value::Object(sections) = genesis else {
+            bail!("Genesis must be an object with a section per module");
+        };
+        let mut builder = Self::default();
+        for (section, config) in sections {
+            builder = match section.as_str() {
+                ACCOUNTS => builder.accounts(config),
+                EVM => builder.evm(config),
+                SOFT_CONFIRMATION_RULE_ENFORCER => builder.soft_confirmation_rule_enforcer(config),
+                SEQUENCER_REGISTRY => builder.sequencer_registry(config),
+                _ => bail!(
+                    "Unknown genesis section `{}`, expected one of `{}`, `{}`, `{}` or `{}`",
+                    section,
+                    ACCOUNTS,
+                    EVM,
+                    SOFT_CONFIRMATION_RULE_ENFORCER,
+                    SEQUENCER_REGISTRY
+                ),
+            };
+        }
+        Ok(builder)
+    }
+
+    /// Sets the genesis of the accounts module
+    pub fn accounts(mut self, config: Value) -> Self {
+        self.accounts = Some(config);
+        self
+    }
+
+    /// Sets the genesis of the EVM, with its accounts and predeployed contracts
+    pub fn evm(mut self, config: Value) -> Self {
+        self.evm = Some(config);
+        self
+    }
+
+    /// Sets the genesis of the soft confirmation rule enforcer
+    pub fn soft_confirmation_rule_enforcer(mut self, config: Value) -> Self {
+        self.soft_confirmation_rule_enforcer = Some(config);
+        self
+    }
+
+    /// Sets the genesis of the sequencer registry
+    pub fn sequencer_registry(mut self, config: Value) -> Self {
+        self.sequencer_registry = Some(config);
+        self
+    }
+
+    /// Validates the genesis of all modules and assembles the genesis config of the runtime
+    pub fn build<C: Context, Da: DaSpec>(
+        self,
+    ) -> anyhow::Result<<Runtime<C, Da> as RuntimeTrait<C, Da>>::GenesisConfig> {
+        let accounts_config: AccountConfig<C> = parse_section(ACCOUNTS, self.accounts)?;
+
+        let evm_config: EvmConfig = parse_section(EVM, self.evm)?;
+        validate_evm_config(&evm_config).context("Invalid `evm` genesis")?;
+
+        let soft_confirmation_rule_enforcer = self.soft_confirmation_rule_enforcer;
+        if let Some(config) = &soft_confirmation_rule_enforcer {
+            validate_soft_confirmation_rule_enforcer_config(config)
+                .context("Invalid `soft_confirmation_rule_enforcer` genesis")?;
+        }
+        let soft_confirmation_rule_enforcer_config: SoftConfirmationRuleEnforcerConfig<C> =
+            parse_section(
+                SOFT_CONFIRMATION_RULE_ENFORCER,
+                soft_confirmation_rule_enforcer,
+            )?;
+
+        let sequencer_registry_config: SequencerRegistryConfig<C> =
+            parse_section(SEQUENCER_REGISTRY, self.sequencer_registry)?;
+
+        Ok(GenesisConfig::new(
+            accounts_config,
+            evm_config,
+            soft_confirmation_rule_enforcer_config,
+            sequencer_registry_config,
+            (),
+        ))
+    }
+}
+
+fn parse_section<T: DeserializeOwned>(section: &str, config: Option<Value>) -> anyhow::Result<T> {
+    let config = config.with_context(|| format!("Missing `{}` genesis", section))?;
+    serde_json::from_value(config).with_context(|| format!("Invalid `{}` genesis", section))
+}
+
+fn validate_evm_config(config: &EvmConfig) -> anyhow::Result<()> {
+    ensure!(config.chain_id != 0, "Chain id must not be 0");
+    ensure!(config.block_gas_limit != 0, "Block gas limit must not be 0");
+    ensure!(
+        config.spec.contains_key(&0),
+        "Spec must set the hardfork of block 0"
+    );
+
+    let mut addresses = HashSet::new();
+    for account in &config.data {
+        ensure!(
+            addresses.insert(account.address),
+            "Account {} is set more than once",
+            account.address
+        );
+    }
+    Ok(())
+}
+
+fn validate_soft_confirmation_rule_enforcer_config(config: &Value) -> anyhow::Result<()> {
+    if let Some(max_l2_blocks_per_l1) = config.get("max_l2_blocks_per_l1") {
+        ensure!(
+            max_l2_blocks_per_l1.as_u64() != Some(0),
+            "`max_l2_blocks_per_l1` must not be 0"
+        );
+    }
+    if let Some(percentage) = config
+        .get("l1_fee_rate_change_percentage")
+        .and_then(Value::as_u64)
+    {
+        ensure!(
+            percentage <= 100,
+            "`l1_fee_rate_change_percentage` is {}, it must be at most 100",
+            percentage
+        );
+    }
+    Ok(())
+}
+
 /// Creates genesis configuration.
 pub fn get_genesis_config<C: Context, Da: DaSpec>(
     genesis_paths: &GenesisPaths,
 ) -> Result<<Runtime<C, Da> as RuntimeTrait<C, Da>>::GenesisConfig, anyhow::Error> {
-    let genesis_config =
-        create_genesis_config(genesis_paths).context("Unable to read genesis configuration")?;
+    let genesis_config = GenesisBuilder::from_paths(genesis_paths)
+        .and_then(GenesisBuilder::build::<C, Da>)
+        .context("Unable to read genesis configuration")?;
     validate_config(genesis_config)
 }
 
@@ -64,19 +274,142 @@ pub(crate) fn validate_config<C: Context, Da: DaSpec>(
     Ok(genesis_config)
 }
 
-fn create_genesis_config<C: Context, Da: DaSpec>(
-    genesis_paths: &GenesisPaths,
-) -> anyhow::Result<<Runtime<C, Da> as RuntimeTrait<C, Da>>::GenesisConfig> {
-    let accounts_config: AccountConfig<C> = read_json_file(&genesis_paths.accounts_genesis_path)?;
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use sov_mock_da::MockDaSpec;
+
+    use super::*;
+
+    const GENESIS_DIR: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../../resources/test-data/integration-tests"
+    );
+
+    const AUTHORITY: &str = "sov1l6n2cku82yfqld30lanm2nfw43n2auc8clw7r5u5m6s7p8jrm4zqrr8r94";
+
+    fn build(builder: GenesisBuilder) -> anyhow::Result<Value> {
+        let genesis_config = builder.build::<DefaultContext, MockDaSpec>()?;
+        Ok(serde_json::to_value(genesis_config)?)
+    }
+
+    /// The genesis of the integration tests with a section per module
+    fn genesis_sections() -> Value {
+        let section =
+            |file: &str| -> Value { read_json_file(Path::new(GENESIS_DIR).join(file)).unwrap() };
+        json!({
+            "accounts": section("accounts.json"),
+            "evm": section("evm.json"),
+            "soft_confirmation_rule_enforcer": section("soft_confirmation_rule_enforcer.json"),
+            "sequencer_registry": section("sequencer_registry.json"),
+        })
+    }
+
+    fn error_message(result: anyhow::Result<Value>) -> String {
+        format!("{:#}", result.unwrap_err())
+    }
+
+    #[test]
+    fn test_reads_the_same_genesis_from_one_json_file_as_from_module_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("genesis.json");
+        std::fs::write(&path, genesis_sections().to_string()).unwrap();
+
+        let from_dir =
+            build(GenesisBuilder::from_paths(&GenesisPaths::from_dir(GENESIS_DIR)).unwrap())
+                .unwrap();
+        let from_file =
+            build(GenesisBuilder::from_paths(&GenesisPaths::from_path(&path)).unwrap()).unwrap();
+        assert_eq!(from_file, from_dir);
+    }
+
+    #[test]
+    fn test_reads_genesis_from_one_toml_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("genesis.toml");
+        std::fs::write(
+            &path,
+            format!(
+                r#"
+[accounts]
+pub_keys = []
+
+[evm]
+chain_id = 5655
+coinbase = "0x3100000000000000000000000000000000000005"
+starting_base_fee = 1000000000
+block_gas_limit = 30000000
+difficulty = 0
+extra_data = "0x"
+timestamp = 0
+nonce = 0
+base_fee_params = {{ max_change_denominator = 8, elasticity_multiplier = 2 }}
+spec = {{ "0" = "SHANGHAI" }}
+
+[[evm.data]]
+address = "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+balance = "0xffffffffffffffffffffffffffffff"
+code = "0x"
+
+[soft_confirmation_rule_enforcer]
+max_l2_blocks_per_l1 = 86400
+authority = "{AUTHORITY}"
+l1_fee_rate_change_percentage = 10
 
-    let evm_config: EvmConfig = read_json_file(&genesis_paths.evm_genesis_path)?;
+[sequencer_registry]
+authority = "{AUTHORITY}"
+"#
+            ),
+        )
+        .unwrap();
 
-    let soft_confirmation_rule_enforcer_config: SoftConfirmationRuleEnforcerConfig<C> =
-        read_json_file(&genesis_paths.soft_confirmation_rule_enforcer_genesis_path)?;
+        let genesis = build(GenesisBuilder::from_file(&path).unwrap()).unwrap();
+        assert_eq!(genesis["evm"]["chain_id"], 5655);
+        assert_eq!(
+            genesis["evm"]["data"][0]["address"],
+            "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+        );
+        assert_eq!(
+            genesis["soft_confirmation_rule_enforcer"]["max_l2_blocks_per_l1"],
+            86400
+        );
+    }
+
+    #[test]
+    fn test_rejects_invalid_genesis_with_the_module_in_the_error() {
+        let builder = || GenesisBuilder::from_value(genesis_sections()).unwrap();
+        let mut evm = genesis_sections()["evm"].clone();
+
+        evm["chain_id"] = json!(0);
+        let error = error_message(build(builder().evm(evm.clone())));
+        assert!(error.contains("Invalid `evm` genesis: Chain id must not be 0"));
+
+        evm["chain_id"] = json!(5655);
+        let duplicate = evm["data"][0].clone();
+        evm["data"].as_array_mut().unwrap().push(duplicate);
+        let error = error_message(build(builder().evm(evm)));
+        assert!(error.contains("is set more than once"));
 
-    Ok(GenesisConfig::new(
-        accounts_config,
-        evm_config,
-        soft_confirmation_rule_enforcer_config,
-    ))
+        let error = error_message(build(builder().soft_confirmation_rule_enforcer(json!({
+            "max_l2_blocks_per_l1": 86400,
+            "authority": AUTHORITY,
+            "l1_fee_rate_change_percentage": 101,
+        }))));
+        assert!(error.contains("`l1_fee_rate_change_percentage` is 101, it must be at most 100"));
+
+        let error = error_message(build(builder().sequencer_registry(json!({}))));
+        assert!(error.contains("Invalid `sequencer_registry` genesis"));
+
+        let mut sections = genesis_sections();
+        sections.as_object_mut().unwrap().remove("accounts");
+        let error = error_message(build(GenesisBuilder::from_value(sections).unwrap()));
+        assert!(error.contains("Missing `accounts` genesis"));
+
+        let mut sections = genesis_sections();
+        sections["bank"] = json!({});
+        let error = GenesisBuilder::from_value(sections)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Unknown genesis section `bank`"));
+    }
 }
@@ -1,17 +1,18 @@
+use citrea_evm::CallMessage;
 use sov_accounts::AccountsTxHook;
 use sov_modules_api::hooks::{
     ApplyBlobHooks, ApplySoftConfirmationError, ApplySoftConfirmationHooks, FinalizeHook,
     HookSoftConfirmationInfo, SlotHooks, TxHooks,
 };
 use sov_modules_api::transaction::Transaction;
-use sov_modules_api::{AccessoryWorkingSet, Context, Spec, WorkingSet};
+use sov_modules_api::{AccessoryWorkingSet, Context, DispatchCall, Spec, WorkingSet};
 use sov_modules_stf_blueprint::{RuntimeTxHook, SequencerOutcome};
 use sov_rollup_interface::da::{BlobReaderTrait, DaSpec};
 use sov_state::Storage;
 #[cfg(feature = "native")]
 use tracing::instrument;
 
-use crate::runtime::Runtime;
+use crate::runtime::{Runtime, RuntimeCall};
 
 impl<C: Context, Da: DaSpec> TxHooks for Runtime<C, Da> {
     type Context = C;
@@ -42,6 +43,12 @@ impl<C: Context, Da: DaSpec> TxHooks for Runtime<C, Da> {
     ) -> anyhow::Result<()> {
         self.accounts.post_dispatch_tx_hook(tx, ctx, working_set)?;
 
+        // Forced txs are included once they are in the block, even if they revert
+        if let Ok(RuntimeCall::evm(CallMessage { txs })) = Self::decode_call(tx.runtime_msg()) {
+            self.soft_confirmation_rule_enforcer
+                .include_forced_txs(txs.iter().map(|tx| &tx.rlp[..]), working_set);
+        }
+
         Ok(())
     }
 }
@@ -83,6 +90,11 @@ impl<C: Context, Da: DaSpec> ApplySoftConfirmationHooks<Da> for Runtime<C, Da> {
 
         self.evm
             .begin_soft_confirmation_hook(soft_batch, working_set);
+        // Priority fees go to the address of the sequencer registered on chain
+        if let Some(sequencer) = self.sequencer_registry.active_sequencer(working_set) {
+            self.evm
+                .set_pending_coinbase(sequencer.evm_address.into(), working_set);
+        }
 
         Ok(())
     }
@@ -92,6 +104,12 @@ impl<C: Context, Da: DaSpec> ApplySoftConfirmationHooks<Da> for Runtime<C, Da> {
         &self,
         working_set: &mut WorkingSet<C>,
     ) -> Result<(), ApplySoftConfirmationError> {
+        // Withdrawals are read from the pending transactions, which the EVM clears when it seals the block
+        let l2_height = self.evm.pending_block_number(working_set);
+        let withdrawals = self.evm.pending_withdrawals(working_set);
+        self.withdrawal_queue
+            .record_withdrawals(l2_height, withdrawals, working_set);
+
         self.evm.end_soft_confirmation_hook(working_set);
         Ok(())
     }
@@ -34,6 +34,10 @@
 #[cfg(feature = "native")]
 use citrea_evm::{EvmRpcImpl, EvmRpcServer};
 #[cfg(feature = "native")]
+use citrea_sequencer_registry::{SequencerRegistryRpcImpl, SequencerRegistryRpcServer};
+#[cfg(feature = "native")]
+use citrea_withdrawal_queue::{WithdrawalQueueRpcImpl, WithdrawalQueueRpcServer};
+#[cfg(feature = "native")]
 use soft_confirmation_rule_enforcer::{
     SoftConfirmationRuleEnforcerRpcImpl, SoftConfirmationRuleEnforcerRpcServer,
 };
@@ -46,7 +50,7 @@ use sov_modules_api::macros::DefaultRuntime;
 use sov_modules_api::macros::{expose_rpc, CliWallet};
 #[cfg(feature = "native")]
 use sov_modules_api::Spec;
-use sov_modules_api::{Context, DispatchCall, Genesis, MessageCodec};
+use sov_modules_api::{Context, DispatchCall, Genesis, MessageCodec, WorkingSet};
 use sov_rollup_interface::da::DaSpec;
 
 #[cfg(feature = "native")]
@@ -66,6 +70,12 @@ pub struct Runtime<C: Context, Da: DaSpec> {
     /// The soft confirmation rule enforcer module.
     pub soft_confirmation_rule_enforcer:
         soft_confirmation_rule_enforcer::SoftConfirmationRuleEnforcer<C, Da>,
+    #[cfg_attr(feature = "native", cli_skip)]
+    /// The sequencer registry module.
+    pub sequencer_registry: citrea_sequencer_registry::SequencerRegistry<C>,
+    #[cfg_attr(feature = "native", cli_skip)]
+    /// The withdrawal queue module.
+    pub withdrawal_queue: citrea_withdrawal_queue::WithdrawalQueue<C>,
 }
 
 impl<C, Da> sov_modules_stf_blueprint::Runtime<C, Da> for Runtime<C, Da>
@@ -89,4 +99,10 @@ where
     ) -> Result<Self::GenesisConfig, anyhow::Error> {
         crate::genesis_config::get_genesis_config(genesis_paths)
     }
+
+    fn sequencer_public_key(&self, working_set: &mut WorkingSet<C>) -> Option<Vec<u8>> {
+        self.sequencer_registry
+            .active_sequencer(working_set)
+            .map(|sequencer| sequencer.public_key)
+    }
 }
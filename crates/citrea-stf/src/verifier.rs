@@ -1,8 +1,22 @@
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 
-use sov_rollup_interface::da::{BlockHeaderTrait, DaVerifier};
+use borsh::{BorshDeserialize, BorshSerialize};
+use citrea_sequencer_registry::is_sequencer_da_pub_key_at;
+use citrea_withdrawal_queue::{withdrawal_root, Withdrawal, WithdrawalQueue};
+use sov_modules_api::default_context::ZkDefaultContext;
+use sov_modules_api::Spec;
+use sov_rollup_interface::da::{
+    BlobReaderTrait, BlockHeaderTrait, DaChainState, DaData, DaPayloadMode, DaSpec, DaVerifier,
+    SequencerCommitment, SlotInbox,
+};
 use sov_rollup_interface::stf::StateTransitionFunction;
-use sov_rollup_interface::zk::{StateTransition, StateTransitionData, Zkvm, ZkvmGuest};
+use sov_rollup_interface::zk::{
+    AggregatedStateTransition, AggregationData, LightClientData, LightClientOutput, Proof,
+    SlotBlobs, StateTransition, StateTransitionData, Zkvm, ZkvmGuest,
+};
+#[cfg(all(target_os = "zkvm", feature = "bench"))]
+use sov_zk_cycle_macros::cycle_tracker;
 
 /// Verifies a state transition
 pub struct StateTransitionVerifier<ST, Da, Zk>
@@ -13,6 +27,7 @@ where
 {
     app: ST,
     da_verifier: Da,
+    da_payload_mode: DaPayloadMode,
     phantom: PhantomData<Zk>,
 }
 
@@ -27,25 +42,103 @@ where
         Self {
             app,
             da_verifier,
+            da_payload_mode: DaPayloadMode::default(),
             phantom: Default::default(),
         }
     }
 
-    /// Verify the next block
+    /// Sets what the sequencer of the network posts with its commitments.
+    /// With [`DaPayloadMode::StateDiff`], only commitments posted with their state diff are proven.
+    pub fn with_da_payload_mode(mut self, da_payload_mode: DaPayloadMode) -> Self {
+        self.da_payload_mode = da_payload_mode;
+        self
+    }
+
+    #[cfg_attr(
+        all(target_os = "zkvm", feature = "bench"),
+        cycle_tracker("da_verification")
+    )]
+    fn verify_da_data(
+        &self,
+        block_header: &<Da::Spec as DaSpec>::BlockHeader,
+        txs: &[<Da::Spec as DaSpec>::BlobTransaction],
+        inclusion_proof: <Da::Spec as DaSpec>::InclusionMultiProof,
+        completeness_proof: <Da::Spec as DaSpec>::CompletenessProof,
+    ) -> Result<<Da::Spec as DaSpec>::ValidityCondition, Da::Error> {
+        self.da_verifier.verify_relevant_tx_list(
+            block_header,
+            txs,
+            inclusion_proof,
+            completeness_proof,
+        )
+    }
+
+    /// Verify the next block, and return the state transition it commits
     pub fn run_sequencer_commitments_in_da_slot(
         &self,
         zkvm: Zk,
         pre_state: Stf::PreState,
-    ) -> Result<(), Da::Error> {
+    ) -> Result<StateTransition<Da::Spec, Stf::StateRoot>, Da::Error> {
         println!("Running sequencer commitments in DA slot");
         let data: StateTransitionData<Stf::StateRoot, _, Da::Spec> = zkvm.read_from_host();
-        let validity_condition = self.da_verifier.verify_relevant_tx_list(
+        let validity_condition = self.verify_da_data(
             &data.da_block_header_of_commitments,
             &data.da_data,
             data.inclusion_proof,
             data.completeness_proof,
         )?;
 
+        let commitments = processed_sequencer_commitments(
+            &data.da_data,
+            &data.sequencer_da_public_key,
+            data.sequencer_commitments_range,
+        );
+        if self.da_payload_mode == DaPayloadMode::StateDiff {
+            assert!(
+                commitments
+                    .iter()
+                    .all(|(_, has_state_diff)| *has_state_diff),
+                "Sequencer commitments must be posted with their state diff"
+            );
+        }
+        // The posted state diffs are checked against the execution by the STF
+        let commitment_l2_ranges: Vec<_> = commitments
+            .into_iter()
+            .map(|(commitment, _)| {
+                (
+                    commitment.l2_start_block_number,
+                    commitment.l2_end_block_number,
+                )
+            })
+            .collect();
+
+        // The inboxes of the DA blocks of the soft confirmations are read from their verified blobs
+        let slot_inboxes = data
+            .da_block_headers_of_soft_confirmations
+            .iter()
+            .zip(data.da_blobs_of_soft_confirmations)
+            .map(|(headers, slot_blobs)| {
+                assert_eq!(
+                    headers.len(),
+                    slot_blobs.len(),
+                    "Blobs of every DA block of the soft confirmations must be given"
+                );
+                headers
+                    .iter()
+                    .zip(slot_blobs)
+                    .map(|(header, slot_blobs)| {
+                        self.verify_da_data(
+                            header,
+                            &slot_blobs.blobs,
+                            slot_blobs.inclusion_proof,
+                            slot_blobs.completeness_proof,
+                        )?;
+                        Ok(SlotInbox::from_blobs(&slot_blobs.blobs))
+                    })
+                    .collect::<Result<Vec<_>, Da::Error>>()
+            })
+            .collect::<Result<VecDeque<_>, Da::Error>>()?;
+
         println!("going into apply_soft_confirmations_from_sequencer_commitments");
         let (final_state_root, state_diff) = self
             .app
@@ -57,8 +150,9 @@ where
                 pre_state,
                 data.da_data,
                 data.sequencer_commitments_range,
-                data.state_transition_witnesses,
+                data.state_transition_witnesses.expand(),
                 data.da_block_headers_of_soft_confirmations,
+                slot_inboxes,
                 &validity_condition,
                 data.soft_confirmations,
             );
@@ -70,6 +164,20 @@ where
             "Invalid final state root"
         );
 
+        let withdrawals =
+            WithdrawalQueue::<ZkDefaultContext>::default().withdrawals_from_state_diff(&state_diff);
+        let withdrawal_roots = commitment_l2_ranges
+            .into_iter()
+            .map(|(start, end)| {
+                let commitment_withdrawals: Vec<Withdrawal> = withdrawals
+                    .iter()
+                    .filter(|withdrawal| (start..=end).contains(&withdrawal.l2_height))
+                    .cloned()
+                    .collect();
+                withdrawal_root(&commitment_withdrawals)
+            })
+            .collect();
+
         let out: StateTransition<Da::Spec, _> = StateTransition {
             initial_state_root: data.initial_state_root,
             final_state_root,
@@ -80,9 +188,144 @@ where
             sequencer_public_key: data.sequencer_public_key,
             sequencer_da_public_key: data.sequencer_da_public_key,
             sequencer_commitments_range: data.sequencer_commitments_range,
+            withdrawal_roots,
         };
 
         zkvm.commit(&out);
-        Ok(())
+        Ok(out)
+    }
+}
+
+/// Returns the processed sequencer commitments, and whether they were posted with their
+/// state diff, read from the DA data the same way the state transition function reads them.
+fn processed_sequencer_commitments<B: BlobReaderTrait>(
+    da_data: &[B],
+    sequencer_da_public_key: &[u8],
+    sequencer_commitments_range: (u32, u32),
+) -> Vec<(SequencerCommitment, bool)> {
+    da_data
+        .iter()
+        .filter(|blob| {
+            is_sequencer_da_pub_key_at(sequencer_da_public_key, None, blob.sender().as_ref())
+        })
+        .filter_map(
+            |blob| match DaData::decode_activated(blob.verified_data()) {
+                Ok(DaData::SequencerCommitment(commitment)) => Some((commitment, false)),
+                Ok(DaData::CommitmentWithStateDiff(data)) => Some((data.commitment, true)),
+                _ => None,
+            },
+        )
+        .skip(sequencer_commitments_range.0 as usize)
+        .take((sequencer_commitments_range.1 - sequencer_commitments_range.0) as usize + 1)
+        .collect()
+}
+
+/// Verifies the proofs of consecutive state transitions, which the host adds as assumptions,
+/// and commits and returns the state transition covering all of them.
+pub fn aggregate_state_transitions<Da, Root, Zk>(zkvm: Zk) -> AggregatedStateTransition<Da, Root>
+where
+    Da: DaSpec,
+    Root: BorshSerialize + BorshDeserialize + AsRef<[u8]>,
+    Zk: ZkvmGuest,
+{
+    let data: AggregationData<Da, Root> = zkvm.read_from_host();
+
+    for state_transition in &data.state_transitions {
+        let output = borsh::to_vec(state_transition).expect("Serialization to vec is infallible");
+        zkvm.verify_assumption(&data.range_code_commitment, &output);
+    }
+
+    let out =
+        AggregatedStateTransition::aggregate(data.range_code_commitment, data.state_transitions)
+            .expect("Aggregated state transitions must be consecutive");
+
+    zkvm.commit(&out);
+    out
+}
+
+/// Hash function of the digest of the recent DA block hashes in [`LightClientOutput`]
+type LightClientHasher = <ZkDefaultContext as Spec>::Hasher;
+
+/// Extends the light client output over new DA blocks, see [`LightClientData::output`].
+/// `chain_state` is the chain state after the DA block headers.
+/// Returns the new output, and the hashes of the latest DA blocks the next proof is given.
+pub fn light_client_output<Da, Root>(
+    data: &LightClientData<Da, Root>,
+    chain_state: DaChainState,
+) -> anyhow::Result<(LightClientOutput<Root>, Vec<[u8; 32]>)>
+where
+    Da: DaSpec,
+    Root: Clone + AsRef<[u8]>,
+{
+    data.output::<LightClientHasher>(chain_state)
+}
+
+/// Verifies the DA block headers and the proofs of the state transitions posted in them,
+/// which the host adds as assumptions along with the previous light client proof,
+/// and commits the latest proven L2 state root.
+pub fn prove_light_client<Da, Root, Zk>(zkvm: Zk, da_verifier: Da)
+where
+    Da: DaVerifier,
+    Root: BorshSerialize + BorshDeserialize + Clone + AsRef<[u8]>,
+    Zk: ZkvmGuest,
+{
+    let data: LightClientData<Da::Spec, Root> = zkvm.read_from_host();
+
+    if let Some(previous_output) = &data.previous_output {
+        let output = borsh::to_vec(previous_output).expect("Serialization to vec is infallible");
+        zkvm.verify_assumption(&data.light_client_code_commitment, &output);
+    }
+
+    let chain_state = da_verifier
+        .verify_headers(data.chain_state(), &data.da_block_headers)
+        .expect("DA block headers must be valid");
+
+    let (out, _) =
+        light_client_output(&data, chain_state).expect("Light client data must be consistent");
+
+    let posted_outputs =
+        posted_proof_outputs(&zkvm, &da_verifier, &data.da_block_headers, data.da_blobs);
+    for state_transition in &data.state_transitions {
+        let output = borsh::to_vec(state_transition).expect("Serialization to vec is infallible");
+        assert!(
+            posted_outputs.contains(&output),
+            "Batch proof of the state transition is not posted in the DA blocks"
+        );
+        zkvm.verify_assumption(&data.batch_proof_code_commitment, &output);
+    }
+
+    zkvm.commit(&out);
+}
+
+/// Outputs of the batch proofs posted in the DA blocks, read from their verified blobs
+fn posted_proof_outputs<Da, Zk>(
+    zkvm: &Zk,
+    da_verifier: &Da,
+    da_block_headers: &[<Da::Spec as DaSpec>::BlockHeader],
+    da_blobs: Vec<SlotBlobs<Da::Spec>>,
+) -> Vec<Vec<u8>>
+where
+    Da: DaVerifier,
+    Zk: ZkvmGuest,
+{
+    let mut outputs = vec![];
+    for (header, slot_blobs) in da_block_headers.iter().zip(da_blobs) {
+        let SlotBlobs {
+            blobs,
+            inclusion_proof,
+            completeness_proof,
+        } = slot_blobs;
+        da_verifier
+            .verify_relevant_tx_list(header, &blobs, inclusion_proof, completeness_proof)
+            .expect("Blobs of the DA block must be valid");
+
+        for blob in &blobs {
+            if let Ok(DaData::ZKProof(Proof::Full(proof))) =
+                DaData::decode_activated(blob.verified_data())
+            {
+                outputs.extend(zkvm.proof_output(&proof));
+            }
+        }
     }
+    outputs
 }
@@ -1,14 +1,24 @@
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 #[cfg(feature = "local")]
 use citrea_evm::DevSigner;
 use citrea_evm::Evm;
-use reth_primitives::U256;
-use reth_rpc_types::trace::geth::GethTrace;
+use citrea_withdrawal_queue::{withdrawal_proof, withdrawal_root, WithdrawalQueue};
+use jsonrpsee::types::ErrorObjectOwned;
+use reth_primitives::{B256, U256};
 use rustc_version_runtime::version;
 use schnellru::{ByLength, LruMap};
 use sequencer_client::SequencerClient;
+use sov_db::ledger_db::{LedgerDB, NodeLedgerOps, SharedLedgerOps};
+use sov_db::schema::types::{BatchNumber, SlotNumber};
+use sov_modules_api::utils::to_jsonrpsee_error_object;
 use sov_modules_api::WorkingSet;
+use sov_rollup_interface::da::BlockHeaderTrait;
+use sov_rollup_interface::rpc::{
+    HexHash, IndexedSequencerCommitmentResponse, L1FeeRateResponse, SoftConfirmationStatus,
+    TransactionStatusResponse, WithdrawalProofResponse,
+};
 use sov_rollup_interface::services::da::DaService;
 use sov_rollup_interface::CITREA_VERSION;
 use tokio::sync::broadcast;
@@ -17,8 +27,13 @@ use tracing::instrument;
 use crate::gas_price::fee_history::FeeHistoryCacheConfig;
 use crate::gas_price::gas_oracle::{GasPriceOracle, GasPriceOracleConfig};
 use crate::subscription::SubscriptionManager;
+use crate::trace_cache::{BlockTraceCache, TxTraceCache};
 
 const MAX_TRACE_BLOCK: u32 = 1000;
+/// Max. number of L2 blocks per `citrea_getL1FeeRateHistory` request
+const MAX_L1_FEE_RATE_HISTORY: u64 = 1024;
+/// Max. number of transactions remembered as unknown to the sequencer
+const UNKNOWN_TX_CACHE_SIZE: u32 = 10_000;
 
 #[derive(Clone)]
 pub struct EthRpcConfig {
@@ -26,19 +41,57 @@ pub struct EthRpcConfig {
     pub fee_history_cache_config: FeeHistoryCacheConfig,
     #[cfg(feature = "local")]
     pub eth_signer: DevSigner,
+    /// Full nodes look up transactions they have not synced in the mempool of the sequencer.
+    /// Contains how long a transaction unknown to the sequencer is not looked up again.
+    pub sequencer_tx_fallback: Option<Duration>,
+}
+
+/// Transactions recently found to be unknown to the sequencer,
+/// so repeated polls for them are not all forwarded to the sequencer
+pub(crate) struct UnknownTxCache {
+    ttl: Duration,
+    looked_up_at: Mutex<LruMap<B256, Instant, ByLength>>,
+}
+
+impl UnknownTxCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            looked_up_at: Mutex::new(LruMap::new(ByLength::new(UNKNOWN_TX_CACHE_SIZE))),
+        }
+    }
+
+    /// Whether the sequencer did not know the transaction recently
+    fn contains(&self, tx_hash: &B256) -> bool {
+        self.looked_up_at
+            .lock()
+            .unwrap()
+            .get(tx_hash)
+            .is_some_and(|instant| instant.elapsed() < self.ttl)
+    }
+
+    fn insert(&self, tx_hash: B256) {
+        self.looked_up_at
+            .lock()
+            .unwrap()
+            .insert(tx_hash, Instant::now());
+    }
 }
 
 pub struct Ethereum<C: sov_modules_api::Context, Da: DaService> {
-    #[allow(dead_code)]
     pub(crate) da_service: Da,
     pub(crate) gas_price_oracle: GasPriceOracle<C>,
     #[cfg(feature = "local")]
     pub(crate) eth_signer: DevSigner,
     pub(crate) storage: C::Storage,
+    pub(crate) ledger_db: LedgerDB,
     pub(crate) sequencer_client: Option<SequencerClient>,
     pub(crate) web3_client_version: String,
-    pub(crate) trace_cache: Mutex<LruMap<u64, Vec<GethTrace>, ByLength>>,
+    pub(crate) trace_cache: BlockTraceCache,
+    pub(crate) tx_trace_cache: Option<TxTraceCache>,
     pub(crate) subscription_manager: Option<SubscriptionManager>,
+    /// Set if unsynced transactions are looked up in the mempool of the sequencer
+    pub(crate) unknown_tx_cache: Option<UnknownTxCache>,
 }
 
 impl<C: sov_modules_api::Context, Da: DaService> Ethereum<C, Da> {
@@ -48,8 +101,11 @@ impl<C: sov_modules_api::Context, Da: DaService> Ethereum<C, Da> {
         fee_history_cache_config: FeeHistoryCacheConfig,
         #[cfg(feature = "local")] eth_signer: DevSigner,
         storage: C::Storage,
+        ledger_db: LedgerDB,
         sequencer_client: Option<SequencerClient>,
         soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
+        tx_trace_cache: Option<TxTraceCache>,
+        sequencer_tx_fallback: Option<Duration>,
     ) -> Self {
         let evm = Evm::<C>::default();
         let gas_price_oracle =
@@ -61,7 +117,7 @@ impl<C: sov_modules_api::Context, Da: DaService> Ethereum<C, Da> {
 
         let current_version = format!("{}/{}/{}/rust-{}", rollup, CITREA_VERSION, arch, rustc_v);
 
-        let trace_cache = Mutex::new(LruMap::new(ByLength::new(MAX_TRACE_BLOCK)));
+        let trace_cache = BlockTraceCache::new(MAX_TRACE_BLOCK);
 
         let subscription_manager =
             soft_confirmation_rx.map(|rx| SubscriptionManager::new::<C>(storage.clone(), rx));
@@ -72,10 +128,13 @@ impl<C: sov_modules_api::Context, Da: DaService> Ethereum<C, Da> {
             #[cfg(feature = "local")]
             eth_signer,
             storage,
+            ledger_db,
             sequencer_client,
             web3_client_version: current_version,
             trace_cache,
+            tx_trace_cache,
             subscription_manager,
+            unknown_tx_cache: sequencer_tx_fallback.map(UnknownTxCache::new),
         }
     }
 
@@ -99,6 +158,207 @@ impl<C: sov_modules_api::Context, Da: DaService> Ethereum<C, Da> {
         (U256::from(base_fee), U256::from(suggested_tip))
     }
 
+    /// Proof that the withdrawal is in the withdrawal root of its sequencer commitment,
+    /// `None` if the withdrawal was not initiated or its block is not in a commitment found on DA yet
+    pub(crate) fn withdrawal_proof(
+        &self,
+        index: u64,
+    ) -> Result<Option<WithdrawalProofResponse>, ErrorObjectOwned> {
+        let withdrawal_queue = WithdrawalQueue::<C>::default();
+        let mut working_set = WorkingSet::<C>::new(self.storage.clone());
+
+        let Some(withdrawal) = withdrawal_queue.withdrawal(index, &mut working_set) else {
+            return Ok(None);
+        };
+        let Some(commitment) = self
+            .ledger_db
+            .get_sequencer_commitment_by_l2_height(BatchNumber(withdrawal.l2_height))
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))?
+        else {
+            return Ok(None);
+        };
+        let commitment = IndexedSequencerCommitmentResponse::from(commitment);
+        let l2_start = commitment.l2_start_block_number;
+        let l2_end = commitment.l2_end_block_number;
+
+        // All blocks of the commitment must be executed to know all of its withdrawals
+        let synced_l2_height: u64 = Evm::<C>::default()
+            .block_number(&mut working_set)?
+            .saturating_to();
+        if synced_l2_height < l2_end {
+            return Err(to_jsonrpsee_error_object(
+                "WITHDRAWAL_PROOF_ERROR",
+                format!(
+                    "L2 blocks {}-{} of the commitment are not all synced",
+                    l2_start, l2_end
+                ),
+            ));
+        }
+
+        let withdrawals = withdrawal_queue
+            .withdrawals_in_l2_range(l2_start..=l2_end, &mut working_set)
+            .map_err(|e| to_jsonrpsee_error_object("WITHDRAWAL_PROOF_ERROR", e))?;
+        let leaf_index = withdrawals
+            .iter()
+            .position(|commitment_withdrawal| commitment_withdrawal.index == index)
+            .ok_or_else(|| {
+                to_jsonrpsee_error_object(
+                    "WITHDRAWAL_PROOF_ERROR",
+                    format!(
+                        "Withdrawal {} is not in L2 blocks {}-{} of its commitment",
+                        index, l2_start, l2_end
+                    ),
+                )
+            })?;
+
+        Ok(Some(WithdrawalProofResponse {
+            index,
+            l2_height: withdrawal.l2_height,
+            bitcoin_address: withdrawal.bitcoin_address,
+            leaf: withdrawal.leaf(),
+            leaf_index: leaf_index as u64,
+            leaf_count: withdrawals.len() as u64,
+            proof: withdrawal_proof(&withdrawals, leaf_index)
+                .into_iter()
+                .map(HexHash)
+                .collect(),
+            withdrawal_root: withdrawal_root(&withdrawals),
+            commitment,
+        }))
+    }
+
+    /// L1 fee rates of the L2 blocks from `from_block` to `to_block`, inclusive.
+    /// Blocks the node has not synced are skipped.
+    pub(crate) fn l1_fee_rate_history(
+        &self,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<L1FeeRateResponse>, ErrorObjectOwned> {
+        if from_block > to_block || to_block - from_block >= MAX_L1_FEE_RATE_HISTORY {
+            return Err(to_jsonrpsee_error_object(
+                "INVALID_BLOCK_RANGE",
+                format!(
+                    "Block range must not be empty and at most {} blocks",
+                    MAX_L1_FEE_RATE_HISTORY
+                ),
+            ));
+        }
+        let l1_fee_rates = self
+            .ledger_db
+            .get_l1_fee_rate_range(&(BatchNumber(from_block)..BatchNumber(to_block + 1)))
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))?;
+        Ok(l1_fee_rates
+            .into_iter()
+            .map(|(l2_height, l1_fee_rate)| L1FeeRateResponse {
+                l2_height: l2_height.0,
+                l1_fee_rate,
+            })
+            .collect())
+    }
+
+    /// Looks up a transaction the node has not synced yet in the mempool of the sequencer.
+    /// Returns `None` without asking the sequencer if the lookup is disabled,
+    /// or if the sequencer did not know the transaction recently.
+    pub(crate) async fn unsynced_transaction(
+        &self,
+        tx_hash: B256,
+    ) -> Result<Option<reth_rpc_types::Transaction>, ErrorObjectOwned> {
+        let (Some(sequencer_client), Some(unknown_tx_cache)) = (
+            self.sequencer_client.as_ref(),
+            self.unknown_tx_cache.as_ref(),
+        ) else {
+            return Ok(None);
+        };
+        if unknown_tx_cache.contains(&tx_hash) {
+            return Ok(None);
+        }
+
+        let tx = sequencer_client
+            .get_tx_by_hash(tx_hash, Some(true))
+            .await
+            .map_err(|e| match e {
+                jsonrpsee::core::client::Error::Call(e_owned) => e_owned,
+                _ => to_jsonrpsee_error_object("SEQUENCER_CLIENT_ERROR", e),
+            })?;
+        if tx.is_none() {
+            unknown_tx_cache.insert(tx_hash);
+        }
+        // Mempool transactions are not in a block yet
+        Ok(tx.map(|mut tx| {
+            tx.block_hash = None;
+            tx.block_number = None;
+            tx.transaction_index = None;
+            tx
+        }))
+    }
+
+    /// Status of the transaction, from the ledger data of the node and its view of the DA layer.
+    /// Pending transactions are only known to nodes with a sequencer client,
+    /// the sequencer itself reports them as unknown.
+    pub(crate) async fn transaction_status(
+        &self,
+        tx_hash: B256,
+    ) -> Result<TransactionStatusResponse, ErrorObjectOwned> {
+        let mut working_set = WorkingSet::<C>::new(self.storage.clone());
+        let tx = Evm::<C>::default().get_transaction_by_hash(tx_hash, &mut working_set)?;
+
+        let Some(l2_height) = tx.and_then(|tx| tx.block_number) else {
+            let Some(sequencer_client) = self.sequencer_client.as_ref() else {
+                return Ok(TransactionStatusResponse::Unknown);
+            };
+            let pending_tx = sequencer_client
+                .get_tx_by_hash(tx_hash, Some(true))
+                .await
+                .map_err(|e| match e {
+                    jsonrpsee::core::client::Error::Call(e_owned) => e_owned,
+                    _ => to_jsonrpsee_error_object("SEQUENCER_CLIENT_ERROR", e),
+                })?;
+            return Ok(match pending_tx {
+                Some(_) => TransactionStatusResponse::Pending,
+                None => TransactionStatusResponse::Unknown,
+            });
+        };
+
+        let Some(commitment) = self
+            .ledger_db
+            .get_sequencer_commitment_by_l2_height(BatchNumber(l2_height))
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))?
+        else {
+            return Ok(TransactionStatusResponse::SoftConfirmed { l2_height });
+        };
+        let commitment = IndexedSequencerCommitmentResponse::from(commitment);
+
+        let status = self
+            .ledger_db
+            .get_l2_soft_confirmation_status(BatchNumber(l2_height))
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))?;
+        if status == Some(SoftConfirmationStatus::Proven) {
+            let proof_da_tx_id = self
+                .ledger_db
+                .get_proof_da_tx_id(SlotNumber(commitment.found_in_l1))
+                .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))?;
+            return Ok(TransactionStatusResponse::Proven {
+                l2_height,
+                da_tx_id: commitment.da_tx_id,
+                l1_height: commitment.found_in_l1,
+                proof_da_tx_id: proof_da_tx_id.map(HexHash),
+            });
+        }
+
+        let da_head_height = self
+            .da_service
+            .get_head_block_header()
+            .await
+            .map_err(|e| to_jsonrpsee_error_object("DA_RPC_ERROR", e))?
+            .height();
+        Ok(TransactionStatusResponse::DaCommitted {
+            l2_height,
+            da_tx_id: commitment.da_tx_id,
+            l1_height: commitment.found_in_l1,
+            depth: (da_head_height + 1).saturating_sub(commitment.found_in_l1),
+        })
+    }
+
     //     fn make_raw_tx(
     //         &self,
     //         raw_tx: RlpEvmTransaction,
@@ -2,6 +2,7 @@ mod ethereum;
 mod gas_price;
 mod subscription;
 mod trace;
+mod trace_cache;
 
 #[cfg(feature = "local")]
 pub use citrea_evm::DevSigner;
@@ -16,13 +17,15 @@ use reth_rpc::eth::error::EthApiError;
 use reth_rpc_types::trace::geth::{GethDebugTracingOptions, GethTrace};
 use reth_rpc_types::{FeeHistory, Index};
 use sequencer_client::SequencerClient;
-use serde_json::json;
+use serde_json::{json, Value};
+use sov_db::ledger_db::LedgerDB;
 use sov_modules_api::utils::to_jsonrpsee_error_object;
 use sov_modules_api::WorkingSet;
 use sov_rollup_interface::services::da::DaService;
 use subscription::{handle_logs_subscription, handle_new_heads_subscription};
 use tokio::sync::broadcast;
 use trace::{debug_trace_by_block_number, handle_debug_trace_chain};
+use trace_cache::TxTraceCache;
 use tracing::info;
 
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
@@ -43,6 +46,8 @@ pub fn get_ethereum_rpc<C: sov_modules_api::Context, Da: DaService>(
     da_service: Da,
     eth_rpc_config: EthRpcConfig,
     storage: C::Storage,
+    ledger_db: LedgerDB,
+    trace_cache_size: Option<u32>,
     sequencer_client_url: Option<String>,
     soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
 ) -> RpcModule<Ethereum<C, Da>> {
@@ -52,11 +57,15 @@ pub fn get_ethereum_rpc<C: sov_modules_api::Context, Da: DaService>(
         eth_signer,
         gas_price_oracle_config,
         fee_history_cache_config,
+        sequencer_tx_fallback,
     } = eth_rpc_config;
 
     // If the node does not have a sequencer client, then it is the sequencer.
     let is_sequencer = sequencer_client_url.is_none();
     let enable_subscriptions = soft_confirmation_rx.is_some();
+    let tx_trace_cache = trace_cache_size.filter(|size| *size > 0).map(|size| {
+        TxTraceCache::new(ledger_db.clone(), size).expect("Failed to load the trace cache")
+    });
 
     // If the running node is a full node rpc context should also have sequencer client so that it can send txs to sequencer
     let mut rpc = RpcModule::new(Ethereum::new(
@@ -66,8 +75,11 @@ pub fn get_ethereum_rpc<C: sov_modules_api::Context, Da: DaService>(
         #[cfg(feature = "local")]
         eth_signer,
         storage,
+        ledger_db,
         sequencer_client_url.map(SequencerClient::new),
         soft_confirmation_rx,
+        tx_trace_cache,
+        sequencer_tx_fallback,
     ));
 
     register_rpc_methods(&mut rpc, is_sequencer, enable_subscriptions)
@@ -433,14 +445,20 @@ fn register_rpc_methods<C: sov_modules_api::Context, Da: DaService>(
             let block_number = match block_number {
                 BlockNumberOrTag::Number(block_number) => block_number,
                 BlockNumberOrTag::Latest => evm.block_number(&mut working_set)?.saturating_to(),
-                _ => return Err(EthApiError::Unsupported("Earliest, pending, safe and finalized are not supported for debug_traceBlockByNumber").into()),
+                // The pending block is only executed once it is built
+                _ => {
+                    return Err(EthApiError::Unsupported(
+                        "Earliest and pending are not supported for debug_traceBlockByNumber",
+                    )
+                    .into())
+                }
             };
 
             debug_trace_by_block_number(block_number, None, &ethereum, &evm, &mut working_set, opts)
         },
     )?;
 
-    rpc.register_async_method::<Result<GethTrace, ErrorObjectOwned>, _, _>(
+    rpc.register_async_method::<Result<Value, ErrorObjectOwned>, _, _>(
         "debug_traceTransaction",
         |parameters, ethereum| async move {
             // the main rpc handler for debug_traceTransaction
@@ -449,6 +467,8 @@ fn register_rpc_methods<C: sov_modules_api::Context, Da: DaService>(
             // else; calls the debug_trace_transaction_block function in evm
             // that function traces the entire block, returns all the traces to here
             // then we put them into cache and return the trace of the requested transaction
+            // If the trace cache is enabled, the trace of the transaction with the requested
+            // tracer and config is looked up there first and cached there afterwards
             info!(params = ?parameters, "eth module: debug_traceTransaction");
 
             let mut params = parameters.sequence();
@@ -472,6 +492,18 @@ fn register_rpc_methods<C: sov_modules_api::Context, Da: DaService>(
 
             let opts: Option<GethDebugTracingOptions> = params.optional_next()?;
 
+            let block_hash = tx
+                .block_hash
+                .expect("Block hash must be set for tx inside block");
+            let cache_key = TxTraceCache::key(tx_hash, block_hash, &opts);
+            if let Some(trace) = ethereum
+                .tx_trace_cache
+                .as_ref()
+                .and_then(|cache| cache.get(block_number, cache_key))
+            {
+                return Ok(trace);
+            }
+
             let traces = debug_trace_by_block_number(
                 block_number,
                 Some(trace_idx as usize),
@@ -480,7 +512,41 @@ fn register_rpc_methods<C: sov_modules_api::Context, Da: DaService>(
                 &mut working_set,
                 opts,
             )?;
-            Ok(traces[0].clone())
+            let trace = serde_json::to_value(&traces[0])
+                .map_err(|e| to_jsonrpsee_error_object("TRACE_ERROR", e))?;
+            if let Some(cache) = &ethereum.tx_trace_cache {
+                cache.insert(block_number, cache_key, &trace);
+            }
+            Ok(trace)
+        },
+    )?;
+
+    rpc.register_async_method("citrea_getWithdrawalProof", |params, ethereum| async move {
+        let index: u64 = params.one()?;
+        info!("eth module: citrea_getWithdrawalProof({})", index);
+        ethereum.withdrawal_proof(index)
+    })?;
+
+    rpc.register_async_method(
+        "citrea_getTransactionStatus",
+        |params, ethereum| async move {
+            let tx_hash: B256 = params.one()?;
+            info!("eth module: citrea_getTransactionStatus({})", tx_hash);
+            ethereum.transaction_status(tx_hash).await
+        },
+    )?;
+
+    rpc.register_async_method(
+        "citrea_getL1FeeRateHistory",
+        |params, ethereum| async move {
+            let mut params = params.sequence();
+            let from_block: u64 = params.next()?;
+            let to_block: u64 = params.next()?;
+            info!(
+                "eth module: citrea_getL1FeeRateHistory({}, {})",
+                from_block, to_block
+            );
+            ethereum.l1_fee_rate_history(from_block, to_block)
         },
     )?;
 
@@ -567,30 +633,13 @@ fn register_rpc_methods<C: sov_modules_api::Context, Da: DaService>(
                         }
                     }
                     _ => {
-                        // if mempool_only is not true ask evm first then sequencer
+                        // if mempool_only is not true ask evm first then sequencer, if enabled
                         let evm = Evm::<C>::default();
                         let mut working_set = WorkingSet::<C>::new(ethereum.storage.clone());
                         match evm.get_transaction_by_hash(hash, &mut working_set) {
                             Ok(Some(tx)) => Ok(Some(tx)),
-                            Ok(None) => {
-                                // if not found in evm then ask to sequencer mempool
-                                match ethereum
-                                    .sequencer_client
-                                    .as_ref()
-                                    .unwrap()
-                                    .get_tx_by_hash(hash, Some(true))
-                                    .await
-                                {
-                                    Ok(tx) => Ok(tx),
-                                    Err(e) => match e {
-                                        jsonrpsee::core::client::Error::Call(e_owned) => Err(e_owned),
-                                        _ => Err(to_jsonrpsee_error_object(
-                                            "SEQUENCER_CLIENT_ERROR",
-                                            e,
-                                        )),
-                                    },
-                                }
-                            }
+                            // if not found in evm then ask to sequencer mempool
+                            Ok(None) => ethereum.unsynced_transaction(hash).await,
                             Err(e) => {
                                 // return error
                                 Err(e)
@@ -601,6 +650,31 @@ fn register_rpc_methods<C: sov_modules_api::Context, Da: DaService>(
             },
         )?;
 
+        // Only the sequencer knows the block it is building, which is served for the `pending` tag
+        for method in [
+            "citrea_getPendingBlock",
+            "citrea_getPendingBlockTransactionCount",
+            "citrea_getPendingTransactionByIndex",
+            "citrea_getPendingTransactionCount",
+        ] {
+            rpc.register_async_method::<Result<Value, ErrorObjectOwned>, _, _>(
+                method,
+                move |parameters, ethereum| async move {
+                    let params: Option<Vec<Value>> = parameters.parse()?;
+                    ethereum
+                        .sequencer_client
+                        .as_ref()
+                        .unwrap()
+                        .forward(method, params.unwrap_or_default())
+                        .await
+                        .map_err(|e| match e {
+                            jsonrpsee::core::client::Error::Call(e_owned) => e_owned,
+                            _ => to_jsonrpsee_error_object("SEQUENCER_CLIENT_ERROR", e),
+                        })
+                },
+            )?;
+        }
+
         rpc.register_async_method::<Result<CitreaStatus, ErrorObjectOwned>, _, _>(
             "citrea_syncStatus",
             |_, ethereum| async move {
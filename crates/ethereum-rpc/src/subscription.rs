@@ -1,9 +1,16 @@
+use std::collections::{BTreeSet, VecDeque};
+
 use citrea_evm::{log_matches_filter, Evm, Filter, LogResponse};
 use jsonrpsee::{SubscriptionMessage, SubscriptionSink};
+use reth_primitives::{B256, U256};
 use reth_rpc_types::{BlockNumberOrTag, RichBlock};
 use sov_modules_api::WorkingSet;
 use tokio::sync::broadcast;
 
+/// Number of delivered blocks whose logs are removed if the blocks are replaced.
+/// Logs of older blocks are considered final.
+const LOG_HISTORY_DEPTH: u64 = 256;
+
 pub(crate) struct SubscriptionManager {
     new_heads_tx: broadcast::Sender<RichBlock>,
     logs_tx: broadcast::Sender<Vec<LogResponse>>,
@@ -26,6 +33,7 @@ impl SubscriptionManager {
         // and send the corresponding ethereum block to subscribers
         tokio::spawn(async move {
             let evm = Evm::<C>::default();
+            let mut delivered_blocks = DeliveredBlocks::default();
             loop {
                 let Ok(height) = soft_confirmation_rx.recv().await else {
                     return;
@@ -48,17 +56,17 @@ impl SubscriptionManager {
 
                 if logs_tx.receiver_count() != 0 {
                     let mut working_set = WorkingSet::<C>::new(storage.clone());
-                    let logs = evm
-                        .get_logs_in_block_range(
-                            &mut working_set,
-                            &Filter::default(),
-                            height,
-                            height,
-                        )
-                        .expect("Error getting logs in block range");
+                    let Some(logs) =
+                        new_block_logs(&evm, height, &mut delivered_blocks, &mut working_set)
+                    else {
+                        continue;
+                    };
 
                     // Only possible error is no receiver
                     let _ = logs_tx.send(logs);
+                } else {
+                    // Logs delivered to no one are not removed
+                    delivered_blocks = DeliveredBlocks::default();
                 }
             }
         });
@@ -106,6 +114,7 @@ pub async fn handle_logs_subscription(
     filter: Filter,
 ) {
     tokio::spawn(async move {
+        let mut delivered_logs = DeliveredLogs::default();
         loop {
             let Ok(logs) = rx.recv().await else {
                 // Connection closed
@@ -113,12 +122,21 @@ pub async fn handle_logs_subscription(
             };
 
             for log in logs {
-                if log_matches_filter(
+                // Removed logs are only sent to the subscriptions they were delivered to
+                let deliver = if log.removed {
+                    delivered_logs.remove(&log)
+                } else if log_matches_filter(
                     &log.clone().try_into().unwrap(),
                     &filter,
                     log.block_hash.as_ref().unwrap(),
                     &log.block_number.as_ref().unwrap().to::<u64>(),
                 ) {
+                    delivered_logs.insert(&log);
+                    true
+                } else {
+                    false
+                };
+                if deliver {
                     let msg = SubscriptionMessage::new(
                         subscription.method_name(),
                         subscription.subscription_id(),
@@ -134,3 +152,194 @@ pub async fn handle_logs_subscription(
         }
     });
 }
+
+/// Logs to deliver for the new soft confirmation at `height`: the logs of the delivered blocks
+/// which are no longer canonical, marked as removed and newest first, then the logs of the new
+/// block. `None` if the block was already delivered.
+fn new_block_logs<C: sov_modules_api::Context>(
+    evm: &Evm<C>,
+    height: u64,
+    delivered_blocks: &mut DeliveredBlocks,
+    working_set: &mut WorkingSet<C>,
+) -> Option<Vec<LogResponse>> {
+    let mut logs =
+        delivered_blocks.remove_replaced(|number| evm.block_hash_from_number(number, working_set));
+    if delivered_blocks.tip().is_some_and(|tip| tip >= height) {
+        return (!logs.is_empty()).then_some(logs);
+    }
+
+    let hash = evm
+        .block_hash_from_number(height, working_set)
+        .expect("Received signal but evm block is not found");
+    let block_logs = evm
+        .get_logs_in_block_range(working_set, &Filter::default(), height, height)
+        .expect("Error getting logs in block range");
+    delivered_blocks.push(height, hash, block_logs.clone());
+    logs.extend(block_logs);
+    Some(logs)
+}
+
+/// A block whose logs were sent to the log subscriptions
+struct DeliveredBlock {
+    number: u64,
+    hash: B256,
+    logs: Vec<LogResponse>,
+}
+
+/// The last [`LOG_HISTORY_DEPTH`] blocks delivered to the log subscriptions,
+/// to tell them which logs are removed when blocks are rolled back.
+///
+/// Nodes finalize the state of an L2 block once it is executed, and only roll back L2 blocks
+/// with `--repair` at startup, before subscriptions are served. So no block is replaced while
+/// subscriptions are open yet, a rollback at runtime has to send the new head height for its
+/// removed logs to be delivered.
+#[derive(Default)]
+struct DeliveredBlocks {
+    blocks: VecDeque<DeliveredBlock>,
+}
+
+impl DeliveredBlocks {
+    /// Number of the newest delivered block
+    fn tip(&self) -> Option<u64> {
+        self.blocks.back().map(|block| block.number)
+    }
+
+    fn push(&mut self, number: u64, hash: B256, logs: Vec<LogResponse>) {
+        self.blocks.push_back(DeliveredBlock { number, hash, logs });
+        while self.blocks.len() as u64 > LOG_HISTORY_DEPTH {
+            self.blocks.pop_front();
+        }
+    }
+
+    /// Drops the delivered blocks whose hash differs from the canonical block of their number,
+    /// and returns their logs marked as removed, newest first.
+    fn remove_replaced(
+        &mut self,
+        mut canonical_hash: impl FnMut(u64) -> Option<B256>,
+    ) -> Vec<LogResponse> {
+        let mut removed = Vec::new();
+        while let Some(block) = self.blocks.back() {
+            if canonical_hash(block.number) == Some(block.hash) {
+                break;
+            }
+            let block = self.blocks.pop_back().expect("Block is checked above");
+            removed.extend(block.logs.into_iter().rev().map(|mut log| {
+                log.removed = true;
+                log
+            }));
+        }
+        removed
+    }
+}
+
+/// Position of a log in the chain, by block number, block hash and log index
+type LogPosition = (u64, B256, U256);
+
+/// Positions of the recent logs delivered to a log subscription
+#[derive(Default)]
+struct DeliveredLogs {
+    positions: BTreeSet<LogPosition>,
+}
+
+impl DeliveredLogs {
+    fn position(log: &LogResponse) -> Option<LogPosition> {
+        Some((
+            log.block_number?.to::<u64>(),
+            log.block_hash?,
+            log.log_index?,
+        ))
+    }
+
+    fn insert(&mut self, log: &LogResponse) {
+        let Some(position) = Self::position(log) else {
+            return;
+        };
+        self.positions.insert(position);
+        // Logs older than the history are never removed
+        let oldest = position.0.saturating_sub(LOG_HISTORY_DEPTH);
+        self.positions = self.positions.split_off(&(oldest, B256::ZERO, U256::ZERO));
+    }
+
+    /// Whether the log was delivered, forgetting it
+    fn remove(&mut self, log: &LogResponse) -> bool {
+        Self::position(log).is_some_and(|position| self.positions.remove(&position))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(block_number: u64, block_hash: B256, log_index: u64) -> LogResponse {
+        LogResponse {
+            address: Default::default(),
+            topics: vec![],
+            data: Default::default(),
+            block_hash: Some(block_hash),
+            block_number: Some(U256::from(block_number)),
+            transaction_hash: Some(B256::ZERO),
+            transaction_index: Some(U256::ZERO),
+            log_index: Some(U256::from(log_index)),
+            removed: false,
+        }
+    }
+
+    #[test]
+    fn test_removes_logs_of_replaced_blocks() {
+        let mut blocks = DeliveredBlocks::default();
+        for number in 1..=3 {
+            let hash = B256::with_last_byte(number as u8);
+            blocks.push(
+                number,
+                hash,
+                vec![log(number, hash, 0), log(number, hash, 1)],
+            );
+        }
+
+        // Nothing is removed while the delivered blocks are canonical
+        assert!(blocks
+            .remove_replaced(|number| Some(B256::with_last_byte(number as u8)))
+            .is_empty());
+        assert_eq!(blocks.tip(), Some(3));
+
+        // Blocks 2 and 3 are rolled back, block 2 is replaced
+        let removed = blocks.remove_replaced(|number| match number {
+            1 => Some(B256::with_last_byte(1)),
+            2 => Some(B256::with_last_byte(0xff)),
+            _ => None,
+        });
+        let positions: Vec<_> = removed
+            .iter()
+            .map(|log| {
+                assert!(log.removed);
+                DeliveredLogs::position(log).unwrap()
+            })
+            .collect();
+        assert_eq!(
+            positions,
+            vec![
+                (3, B256::with_last_byte(3), U256::from(1)),
+                (3, B256::with_last_byte(3), U256::from(0)),
+                (2, B256::with_last_byte(2), U256::from(1)),
+                (2, B256::with_last_byte(2), U256::from(0)),
+            ]
+        );
+        assert_eq!(blocks.tip(), Some(1));
+    }
+
+    #[test]
+    fn test_removes_only_delivered_logs() {
+        let hash = B256::with_last_byte(1);
+        let mut delivered_logs = DeliveredLogs::default();
+        delivered_logs.insert(&log(1, hash, 0));
+
+        assert!(delivered_logs.remove(&log(1, hash, 0)));
+        assert!(!delivered_logs.remove(&log(1, hash, 0)));
+        assert!(!delivered_logs.remove(&log(1, hash, 1)));
+
+        // Logs beyond the history are forgotten
+        delivered_logs.insert(&log(1, hash, 0));
+        delivered_logs.insert(&log(1 + LOG_HISTORY_DEPTH + 1, hash, 0));
+        assert!(!delivered_logs.remove(&log(1, hash, 0)));
+    }
+}
@@ -84,6 +84,10 @@ pub async fn handle_debug_trace_chain<C: sov_modules_api::Context, Da: DaService
     let subscription = pending.accept().await.unwrap();
     tokio::spawn(async move {
         for block_number in start_block + 1..=end_block {
+            // Traces are not computed for clients which unsubscribed or disconnected
+            if subscription.is_closed() {
+                return;
+            }
             let mut working_set = WorkingSet::<C>::new(ethereum.storage.clone());
             let traces = debug_trace_by_block_number(
                 block_number,
@@ -147,7 +151,11 @@ pub fn debug_trace_by_block_number<C: sov_modules_api::Context, Da: DaService>(
     let tracer_type = requested_opts.tracer.unwrap();
     let tracer_config = requested_opts.tracer_config;
 
-    if let Some(traces) = ethereum.trace_cache.lock().unwrap().get(&block_number) {
+    // Traces of a block replaced since they were cached are not returned
+    let block_hash = evm
+        .block_hash_from_number(block_number, working_set)
+        .ok_or(EthApiError::UnknownBlockNumber)?;
+    if let Some(traces) = ethereum.trace_cache.get(block_number, block_hash) {
         // If traces are found in cache convert them to specified opts and then return
         let traces = match trace_idx {
             Some(idx) => vec![traces[idx].clone()],
@@ -167,9 +175,7 @@ pub fn debug_trace_by_block_number<C: sov_modules_api::Context, Da: DaService>(
     )?;
     ethereum
         .trace_cache
-        .lock()
-        .unwrap()
-        .insert(block_number, traces.clone());
+        .insert(block_number, block_hash, traces.clone());
 
     // Convert the traces to the requested tracer and config
     let traces = match trace_idx {
@@ -0,0 +1,240 @@
+use std::sync::Mutex;
+
+use reth_primitives::{keccak256, B256};
+use reth_rpc_types::trace::geth::{GethDebugTracingOptions, GethTrace};
+use schnellru::{ByLength, LruMap};
+use serde_json::Value;
+use sov_db::ledger_db::{LedgerDB, SharedLedgerOps};
+use sov_db::schema::types::BatchNumber;
+use tracing::warn;
+
+/// Traces of whole blocks, made with the options the requested tracers are converted from.
+///
+/// Traces are kept with the hash of the block they were made of,
+/// so a block replaced since is traced again.
+pub(crate) struct BlockTraceCache {
+    traces: Mutex<LruMap<u64, (B256, Vec<GethTrace>), ByLength>>,
+}
+
+impl BlockTraceCache {
+    pub(crate) fn new(size: u32) -> Self {
+        Self {
+            traces: Mutex::new(LruMap::new(ByLength::new(size))),
+        }
+    }
+
+    /// Gets the traces of the block with the number and hash, if they are cached
+    pub(crate) fn get(&self, block_number: u64, block_hash: B256) -> Option<Vec<GethTrace>> {
+        let mut traces = self.traces.lock().unwrap();
+        let (cached_hash, cached_traces) = traces.get(&block_number)?;
+        if *cached_hash == block_hash {
+            return Some(cached_traces.clone());
+        }
+        traces.remove(&block_number);
+        None
+    }
+
+    pub(crate) fn insert(&self, block_number: u64, block_hash: B256, traces: Vec<GethTrace>) {
+        self.traces
+            .lock()
+            .unwrap()
+            .insert(block_number, (block_hash, traces));
+    }
+}
+
+/// Results of `debug_traceTransaction`, persisted in the ledger db so indexers
+/// tracing the same transactions again don't re-execute their blocks, also after a restart.
+///
+/// Only the keys are kept in memory, to evict the least recently used traces from the db.
+/// The keys commit to the hash of the block of the transaction, so traces of replaced blocks
+/// are never returned. They are deleted from the db together with the blocks when the ledger
+/// is truncated or pruned.
+pub(crate) struct TxTraceCache {
+    ledger_db: LedgerDB,
+    size: usize,
+    /// L2 heights of the cached traces by their key
+    keys: Mutex<LruMap<B256, u64, ByLength>>,
+}
+
+impl TxTraceCache {
+    /// Loads the keys of the traces cached before the restart,
+    /// the traces of the lowest L2 heights are evicted if there are more than `size`
+    pub(crate) fn new(ledger_db: LedgerDB, size: u32) -> anyhow::Result<Self> {
+        let mut keys = LruMap::new(ByLength::new(size));
+        let mut evicted = vec![];
+        for (l2_height, key) in ledger_db.get_cached_trace_keys()? {
+            if keys.len() >= size as usize {
+                evicted.extend(pop_oldest(&mut keys));
+            }
+            keys.insert(B256::from(key), l2_height.0);
+        }
+        if !evicted.is_empty() {
+            ledger_db.delete_cached_traces(&evicted)?;
+        }
+
+        Ok(Self {
+            ledger_db,
+            size: size as usize,
+            keys: Mutex::new(keys),
+        })
+    }
+
+    /// Key of the trace of a transaction in the block, with the tracer and config of the options
+    pub(crate) fn key(
+        tx_hash: B256,
+        block_hash: B256,
+        opts: &Option<GethDebugTracingOptions>,
+    ) -> B256 {
+        let mut preimage = tx_hash.to_vec();
+        preimage.extend(block_hash.as_slice());
+        preimage.extend(serde_json::to_vec(opts).expect("Tracing options must serialize"));
+        keccak256(preimage)
+    }
+
+    /// Gets the trace of a transaction of the L2 height, if it is cached
+    pub(crate) fn get(&self, l2_height: u64, key: B256) -> Option<Value> {
+        // Marks the trace as recently used
+        self.keys.lock().unwrap().get(&key)?;
+
+        match self
+            .ledger_db
+            .get_cached_trace(BatchNumber(l2_height), key.0)
+        {
+            Ok(Some(trace)) => serde_json::from_slice(&trace).ok(),
+            Ok(None) => {
+                // Deleted from the db when its L2 block was pruned or truncated
+                self.keys.lock().unwrap().remove(&key);
+                None
+            }
+            Err(e) => {
+                warn!("Failed to read cached trace: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// Caches the trace of a transaction of the L2 height, evicting the least recently used trace if full
+    pub(crate) fn insert(&self, l2_height: u64, key: B256, trace: &Value) {
+        let trace = serde_json::to_vec(trace).expect("JSON values must serialize");
+        if let Err(e) = self
+            .ledger_db
+            .put_cached_trace(BatchNumber(l2_height), key.0, &trace)
+        {
+            warn!("Failed to cache trace: {:?}", e);
+            return;
+        }
+
+        let evicted = {
+            let mut keys = self.keys.lock().unwrap();
+            let evicted = if keys.peek(&key).is_none() && keys.len() >= self.size {
+                pop_oldest(&mut keys)
+            } else {
+                None
+            };
+            keys.insert(key, l2_height);
+            evicted
+        };
+        if let Some(evicted) = evicted {
+            if let Err(e) = self.ledger_db.delete_cached_traces(&[evicted]) {
+                warn!("Failed to delete evicted trace: {:?}", e);
+            }
+        }
+    }
+}
+
+fn pop_oldest(keys: &mut LruMap<B256, u64, ByLength>) -> Option<(BatchNumber, [u8; 32])> {
+    keys.pop_oldest()
+        .map(|(key, l2_height)| (BatchNumber(l2_height), key.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_rpc_types::trace::geth::NoopFrame;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn block_traces_of_replaced_blocks_are_dropped() {
+        let cache = BlockTraceCache::new(10);
+        cache.insert(
+            1,
+            B256::repeat_byte(1),
+            vec![GethTrace::NoopTracer(NoopFrame::default())],
+        );
+
+        assert_eq!(cache.get(1, B256::repeat_byte(1)).unwrap().len(), 1);
+        assert!(cache.get(1, B256::repeat_byte(2)).is_none());
+        // Not returned for the old hash either once dropped
+        assert!(cache.get(1, B256::repeat_byte(1)).is_none());
+    }
+
+    #[test]
+    fn tx_trace_keys_commit_to_the_block() {
+        let tx_hash = B256::repeat_byte(1);
+        assert_ne!(
+            TxTraceCache::key(tx_hash, B256::repeat_byte(2), &None),
+            TxTraceCache::key(tx_hash, B256::repeat_byte(3), &None)
+        );
+    }
+
+    #[test]
+    fn evicts_least_recently_used_tx_traces_from_the_db() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        let cache = TxTraceCache::new(ledger_db.clone(), 2).unwrap();
+        let trace = json!({ "gas": 21000 });
+
+        cache.insert(1, B256::repeat_byte(1), &trace);
+        cache.insert(2, B256::repeat_byte(2), &trace);
+        // The first trace is used more recently than the second one
+        assert_eq!(cache.get(1, B256::repeat_byte(1)), Some(trace.clone()));
+        cache.insert(3, B256::repeat_byte(3), &trace);
+
+        assert_eq!(cache.get(2, B256::repeat_byte(2)), None);
+        assert_eq!(
+            ledger_db.get_cached_trace_keys().unwrap(),
+            vec![(BatchNumber(1), [1; 32]), (BatchNumber(3), [3; 32])]
+        );
+    }
+
+    #[test]
+    fn evicts_lowest_l2_heights_when_reloaded() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        let trace = json!({ "gas": 21000 });
+        {
+            let cache = TxTraceCache::new(ledger_db.clone(), 3).unwrap();
+            for l2_height in 1..=3 {
+                cache.insert(l2_height, B256::repeat_byte(l2_height as u8), &trace);
+            }
+        }
+
+        let cache = TxTraceCache::new(ledger_db.clone(), 2).unwrap();
+        assert_eq!(
+            ledger_db.get_cached_trace_keys().unwrap(),
+            vec![(BatchNumber(2), [2; 32]), (BatchNumber(3), [3; 32])]
+        );
+        assert_eq!(cache.get(3, B256::repeat_byte(3)), Some(trace));
+    }
+
+    #[test]
+    fn forgets_tx_traces_deleted_from_the_db() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        let cache = TxTraceCache::new(ledger_db.clone(), 2).unwrap();
+        cache.insert(1, B256::repeat_byte(1), &json!({ "gas": 21000 }));
+
+        // As when the L2 block is pruned
+        ledger_db
+            .delete_cached_traces(&[(BatchNumber(1), [1; 32])])
+            .unwrap();
+        assert_eq!(cache.get(1, B256::repeat_byte(1)), None);
+        assert!(cache
+            .keys
+            .lock()
+            .unwrap()
+            .peek(&B256::repeat_byte(1))
+            .is_none());
+    }
+}
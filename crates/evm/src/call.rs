@@ -1,18 +1,27 @@
 use core::panic;
 
 use anyhow::Result;
-use reth_primitives::TransactionSignedEcRecovered;
-use revm::primitives::{CfgEnv, CfgEnvWithHandlerCfg, EVMError, SpecId};
+use reth_primitives::{Address, TransactionSignedEcRecovered, U256};
+use revm::primitives::{Account, CfgEnv, CfgEnvWithHandlerCfg, EVMError, SpecId, State};
+use revm::{Database, DatabaseCommit};
 use sov_modules_api::prelude::*;
 use sov_modules_api::{native_error, CallResponse, WorkingSet};
 
 use crate::evm::db::EvmDb;
 use crate::evm::executor::{self};
 use crate::evm::handler::{CitreaExternal, CitreaExternalExt};
+#[cfg(feature = "native")]
+use crate::evm::parallel_executor::{self, parallel_execution_enabled};
 use crate::evm::primitive_types::{BlockEnv, Receipt, TransactionSignedAndRecovered};
-use crate::evm::{EvmChainConfig, RlpEvmTransaction};
+use crate::evm::storage_rent::{collect_rent, live_storage_slots};
+use crate::evm::{
+    EvmChainConfig, RlpEvmTransaction, StorageRentAccount, BASE_FEE_VAULT,
+    STORAGE_RENT_DEPOSIT_ADDRESS,
+};
 use crate::system_contracts::{BitcoinLightClient, Bridge};
 use crate::system_events::{create_system_transactions, SYSTEM_SIGNER};
+#[cfg(feature = "native")]
+use crate::CitreaError;
 use crate::{Evm, PendingTransaction, SystemEvent};
 
 #[cfg_attr(
@@ -69,10 +78,11 @@ impl<C: sov_modules_api::Context> Evm<C> {
             .map(|acc| acc.info.nonce)
             .unwrap_or(0);
 
+        let shares_code = self.shares_code(block_env.number, working_set);
         let db: EvmDb<'_, C> = self.get_db(working_set);
         let system_txs = create_system_transactions(system_events, system_nonce, cfg_env.chain_id);
 
-        let mut citrea_handler_ext = CitreaExternal::new(l1_fee_rate);
+        let mut citrea_handler_ext = CitreaExternal::new(l1_fee_rate).with_shared_code(shares_code);
         let block_number = block_env.number;
         let tx_results = executor::execute_system_txs(
             db,
@@ -151,7 +161,8 @@ impl<C: sov_modules_api::Context> Evm<C> {
             .l1_fee_rate
             .get(working_set)
             .expect("L1 fee rate must be set");
-        let mut citrea_handler_ext = CitreaExternal::new(l1_fee_rate);
+        let mut citrea_handler_ext = CitreaExternal::new(l1_fee_rate)
+            .with_shared_code(self.shares_code(block_env.number, working_set));
 
         let block_number = block_env.number;
         let mut cumulative_gas_used = 0;
@@ -163,6 +174,27 @@ impl<C: sov_modules_api::Context> Evm<C> {
 
         let evm_db: EvmDb<'_, C> = self.get_db(working_set);
 
+        #[cfg(feature = "native")]
+        let results = if parallel_execution_enabled() {
+            parallel_executor::execute_multiple_tx_parallel(
+                evm_db,
+                block_env,
+                &users_txs,
+                cfg_env,
+                &mut citrea_handler_ext,
+                cumulative_gas_used,
+            )
+        } else {
+            executor::execute_multiple_tx(
+                evm_db,
+                block_env,
+                &users_txs,
+                cfg_env,
+                &mut citrea_handler_ext,
+                cumulative_gas_used,
+            )
+        };
+        #[cfg(not(feature = "native"))]
         let results = executor::execute_multiple_tx(
             evm_db,
             block_env,
@@ -202,6 +234,12 @@ impl<C: sov_modules_api::Context> Evm<C> {
                     };
                     log_index_start += logs_len;
 
+                    if result.is_success()
+                        && evm_tx_recovered.to() == Some(STORAGE_RENT_DEPOSIT_ADDRESS)
+                    {
+                        self.process_storage_rent_transaction(&evm_tx_recovered, working_set);
+                    }
+
                     let pending_transaction = PendingTransaction {
                         transaction: TransactionSignedAndRecovered {
                             signer: evm_tx_recovered.signer(),
@@ -221,11 +259,11 @@ impl<C: sov_modules_api::Context> Evm<C> {
                         // This is a transactional error, so we can skip it without doing anything.
                         continue;
                     }
-                    EVMError::Custom(msg) => {
+                    EVMError::Custom(ref msg) => {
                         #[cfg(feature = "native")]
-                        if !msg.starts_with("Gas used") {
-                            // not really good way to seperate these transactions but it's the best we can do for now
-                            // TODO: replace this branching with a better one.
+                        if let Some(CitreaError::InsufficientFundsForL1Fee { .. }) =
+                            CitreaError::from_evm_error(&err)
+                        {
                             self.l1_fee_failed_txs
                                 .push(&evm_tx_recovered.hash(), &mut working_set.accessory_state());
                         }
@@ -241,8 +279,97 @@ impl<C: sov_modules_api::Context> Evm<C> {
                 },
             }
         }
+
+        // Rent paid from the deposits leaves the deposit address
+        let collected = self
+            .storage_rent_collected
+            .get(working_set)
+            .unwrap_or_default();
+        if !collected.is_zero() {
+            self.storage_rent_collected.set(&U256::ZERO, working_set);
+            self.transfer_from_rent_deposits(BASE_FEE_VAULT, collected, working_set);
+        }
         Ok(CallResponse::default())
     }
+
+    /// Adds the value of a transaction to [`STORAGE_RENT_DEPOSIT_ADDRESS`] to the storage rent deposit
+    /// of the account in its calldata, or of its sender. A transaction without value withdraws
+    /// the remaining deposit of its sender.
+    /// The value is sent back, if the storage rent accounting is disabled.
+    fn process_storage_rent_transaction(
+        &self,
+        tx: &TransactionSignedEcRecovered,
+        working_set: &mut WorkingSet<C>,
+    ) {
+        let Some(config) = self.storage_rent.get(working_set) else {
+            self.transfer_from_rent_deposits(tx.signer(), tx.value(), working_set);
+            return;
+        };
+        let block_number = self
+            .block_env
+            .get(working_set)
+            .expect("Pending block must be set")
+            .number;
+
+        let beneficiary = match tx.input().len() {
+            20 if !tx.value().is_zero() => Address::from_slice(tx.input()),
+            _ => tx.signer(),
+        };
+        let mut rent_account = match self.storage_rent_accounts.get(&beneficiary, working_set) {
+            Some(rent_account) => rent_account,
+            None => StorageRentAccount {
+                settled_at: block_number,
+                storage_slots: self
+                    .accounts
+                    .get(&beneficiary, working_set)
+                    .map(|db_account| live_storage_slots(&db_account, working_set))
+                    .unwrap_or_default(),
+                ..Default::default()
+            },
+        };
+        // Settle with the unchanged size, so the new deposit is not charged for past blocks
+        let size = rent_account.size;
+        let mut paid = rent_account.settle(&config, block_number, size);
+        let mut withdrawn = U256::ZERO;
+        if tx.value().is_zero() {
+            withdrawn = std::mem::take(&mut rent_account.deposit);
+        } else {
+            paid += rent_account.add_deposit(tx.value());
+        }
+        // Stored before the transfer, which settles the rent of the changed accounts again
+        self.storage_rent_accounts
+            .set(&beneficiary, &rent_account, working_set);
+        collect_rent(&self.storage_rent_collected, paid, working_set);
+        self.transfer_from_rent_deposits(beneficiary, withdrawn, working_set);
+    }
+
+    /// Moves value held by [`STORAGE_RENT_DEPOSIT_ADDRESS`] to the account.
+    fn transfer_from_rent_deposits(
+        &self,
+        to: Address,
+        amount: U256,
+        working_set: &mut WorkingSet<C>,
+    ) {
+        if amount.is_zero() {
+            return;
+        }
+        let mut evm_db = self.get_db(working_set);
+        let deposits = evm_db
+            .basic(STORAGE_RENT_DEPOSIT_ADDRESS)
+            .unwrap_or_else(|e| match e {});
+        let recipient = evm_db.basic(to).unwrap_or_else(|e| match e {});
+        let mut deposits = Account::from(deposits.unwrap_or_default());
+        let mut recipient = Account::from(recipient.unwrap_or_default());
+        let amount = amount.min(deposits.info.balance);
+        deposits.info.balance -= amount;
+        recipient.info.balance = recipient.info.balance.saturating_add(amount);
+        deposits.mark_touch();
+        recipient.mark_touch();
+        evm_db.commit(State::from_iter([
+            (STORAGE_RENT_DEPOSIT_ADDRESS, deposits),
+            (to, recipient),
+        ]));
+    }
 }
 
 /// Get cfg env for a given block number
@@ -0,0 +1,60 @@
+use reth_primitives::{keccak256, Address};
+use sov_modules_api::{AccessoryStateMap, AccessoryWorkingSet, Context, StateMapAccessor};
+use sov_state::codec::BcsCodec;
+
+/// Number of bits of the filter, stored in words of 64 bits
+const FILTER_BITS: u64 = 1 << 26;
+/// Number of bits set per account
+const FILTER_HASHES: usize = 4;
+
+/// Bloom filter over the addresses of the existing EVM accounts, kept in accessory state.
+///
+/// Accounts are added when they are first committed, and never removed.
+/// Lookups of accounts missing from the filter do not read the accounts from state,
+/// so they are only enabled for RPC queries, which do not record a witness.
+#[derive(Clone)]
+pub(crate) struct AccountFilter {
+    pub(crate) words: AccessoryStateMap<u32, u64, BcsCodec>,
+    /// Whether lookups of accounts missing from the filter are skipped.
+    /// Only set if the filter was maintained since genesis.
+    pub(crate) skip_missing: bool,
+}
+
+impl AccountFilter {
+    /// Adds the account to the filter.
+    pub(crate) fn insert<C: Context>(
+        &self,
+        address: &Address,
+        working_set: &mut AccessoryWorkingSet<C>,
+    ) {
+        for (index, mask) in bit_positions(address) {
+            let word = self.words.get(&index, working_set).unwrap_or_default();
+            if word & mask == 0 {
+                self.words.set(&index, &(word | mask), working_set);
+            }
+        }
+    }
+
+    /// Whether the account might exist. False if it was never committed.
+    pub(crate) fn may_contain<C: Context>(
+        &self,
+        address: &Address,
+        working_set: &mut AccessoryWorkingSet<C>,
+    ) -> bool {
+        bit_positions(address).into_iter().all(|(index, mask)| {
+            self.words.get(&index, working_set).unwrap_or_default() & mask != 0
+        })
+    }
+}
+
+/// Word indexes and bit masks of the bits of the account.
+fn bit_positions(address: &Address) -> [(u32, u64); FILTER_HASHES] {
+    let hash = keccak256(address);
+    std::array::from_fn(|i| {
+        let bytes: [u8; 8] = hash[i * 8..(i + 1) * 8]
+            .try_into()
+            .expect("Hash has 32 bytes");
+        let bit = u64::from_be_bytes(bytes) % FILTER_BITS;
+        ((bit / 64) as u32, 1 << (bit % 64))
+    })
+}
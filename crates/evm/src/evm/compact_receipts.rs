@@ -0,0 +1,241 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use reth_primitives::{Address, Bytes, Log, TxType, B256};
+use serde::{Deserialize, Serialize};
+
+use crate::evm::primitive_types::Receipt;
+
+/// Receipts of the txs of an L2 block, stored column by column to keep them small.
+///
+/// Addresses and topics repeat a lot in the logs of a block, e.g. the `Transfer` topic or
+/// the addresses of the pools swapped through, so each of them is stored once per block
+/// and logs refer to them by index. Data of the logs is concatenated in a single column.
+/// Logs are read through borrowed views, so filters can check their addresses and topics
+/// without building the logs.
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub(crate) struct BlockReceipts {
+    receipts: Vec<ReceiptMeta>,
+    /// Distinct addresses of the logs of the block
+    addresses: Vec<Address>,
+    /// Distinct topics of the logs of the block
+    topics: Vec<B256>,
+    /// Logs of all the receipts, in order
+    logs: Vec<CompactLog>,
+    /// Indexes in `topics` of the topics of all the logs, in order
+    log_topics: Vec<u32>,
+    /// Data of all the logs, concatenated
+    data: Bytes,
+}
+
+/// Fields of a receipt other than its logs
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct ReceiptMeta {
+    tx_type: TxType,
+    success: bool,
+    cumulative_gas_used: u64,
+    gas_used: u128,
+    log_index_start: u64,
+    l1_diff_size: u64,
+    log_count: u32,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+struct CompactLog {
+    /// Index in `addresses`
+    address: u32,
+    topic_count: u8,
+    data_len: u32,
+}
+
+impl BlockReceipts {
+    pub(crate) fn new<'a>(receipts: impl IntoIterator<Item = &'a Receipt>) -> Self {
+        let mut block_receipts = Self::default();
+        let mut address_indexes = HashMap::new();
+        let mut topic_indexes = HashMap::new();
+        let mut data = Vec::new();
+
+        for receipt in receipts {
+            for log in &receipt.receipt.logs {
+                let address = dictionary_index(
+                    &mut block_receipts.addresses,
+                    &mut address_indexes,
+                    log.address,
+                );
+                for topic in log.topics() {
+                    let topic =
+                        dictionary_index(&mut block_receipts.topics, &mut topic_indexes, *topic);
+                    block_receipts.log_topics.push(topic);
+                }
+                data.extend_from_slice(&log.data.data);
+                block_receipts.logs.push(CompactLog {
+                    address,
+                    topic_count: log.topics().len() as u8,
+                    data_len: log.data.data.len() as u32,
+                });
+            }
+
+            block_receipts.receipts.push(ReceiptMeta {
+                tx_type: receipt.receipt.tx_type,
+                success: receipt.receipt.success,
+                cumulative_gas_used: receipt.receipt.cumulative_gas_used,
+                gas_used: receipt.gas_used,
+                log_index_start: receipt.log_index_start,
+                l1_diff_size: receipt.l1_diff_size,
+                log_count: receipt.receipt.logs.len() as u32,
+            });
+        }
+
+        block_receipts.data = data.into();
+        block_receipts
+    }
+
+    /// Receipt of the `index`th tx of the block. Only its own logs are built.
+    pub(crate) fn receipt(&self, index: usize) -> Option<Receipt> {
+        let meta = self.receipts.get(index)?;
+        let first_log: usize = self.receipts[..index]
+            .iter()
+            .map(|receipt| receipt.log_count as usize)
+            .sum();
+        let logs = self
+            .logs()
+            .skip(first_log)
+            .take(meta.log_count as usize)
+            .map(|log| log.to_log())
+            .collect();
+        Some(meta.to_receipt(logs))
+    }
+
+    /// Receipts of all the txs of the block
+    pub(crate) fn receipts(&self) -> Vec<Receipt> {
+        let mut logs: Vec<Vec<Log>> = self
+            .receipts
+            .iter()
+            .map(|receipt| Vec::with_capacity(receipt.log_count as usize))
+            .collect();
+        for log in self.logs() {
+            logs[log.receipt_index].push(log.to_log());
+        }
+        self.receipts
+            .iter()
+            .zip(logs)
+            .map(|(meta, logs)| meta.to_receipt(logs))
+            .collect()
+    }
+
+    /// Views of the logs of all the receipts, in order
+    pub(crate) fn logs(&self) -> LogViews<'_> {
+        LogViews {
+            block_receipts: self,
+            log_index: 0,
+            receipt_index: 0,
+            logs_left_in_receipt: self.receipts.first().map_or(0, |r| r.log_count),
+            topic_offset: 0,
+            data_offset: 0,
+        }
+    }
+}
+
+impl ReceiptMeta {
+    fn to_receipt(&self, logs: Vec<Log>) -> Receipt {
+        Receipt {
+            receipt: reth_primitives::Receipt {
+                tx_type: self.tx_type,
+                success: self.success,
+                cumulative_gas_used: self.cumulative_gas_used,
+                logs,
+            },
+            gas_used: self.gas_used,
+            log_index_start: self.log_index_start,
+            l1_diff_size: self.l1_diff_size,
+        }
+    }
+}
+
+/// A log of [`BlockReceipts`], borrowing its topics and data
+#[derive(Debug, Clone)]
+pub(crate) struct LogView<'a> {
+    /// Index in the block of the tx which emitted the log
+    pub(crate) receipt_index: usize,
+    pub(crate) address: Address,
+    pub(crate) data: &'a [u8],
+    topic_indexes: &'a [u32],
+    topic_dictionary: &'a [B256],
+}
+
+impl LogView<'_> {
+    pub(crate) fn topics(&self) -> Vec<B256> {
+        self.topic_indexes
+            .iter()
+            .map(|index| self.topic_dictionary[*index as usize])
+            .collect()
+    }
+
+    pub(crate) fn to_log(&self) -> Log {
+        Log::new_unchecked(
+            self.address,
+            self.topics(),
+            Bytes::copy_from_slice(self.data),
+        )
+    }
+
+    /// The log without its data, enough to match it against a filter
+    pub(crate) fn to_log_without_data(&self) -> Log {
+        Log::new_unchecked(self.address, self.topics(), Bytes::new())
+    }
+}
+
+/// Iterator over the logs of [`BlockReceipts`]
+pub(crate) struct LogViews<'a> {
+    block_receipts: &'a BlockReceipts,
+    log_index: usize,
+    receipt_index: usize,
+    logs_left_in_receipt: u32,
+    topic_offset: usize,
+    data_offset: usize,
+}
+
+impl<'a> Iterator for LogViews<'a> {
+    type Item = LogView<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let block_receipts = self.block_receipts;
+        let log = block_receipts.logs.get(self.log_index)?;
+
+        // Receipts without logs are skipped
+        while self.logs_left_in_receipt == 0 {
+            self.receipt_index += 1;
+            self.logs_left_in_receipt = block_receipts.receipts.get(self.receipt_index)?.log_count;
+        }
+
+        let topics_end = self.topic_offset + log.topic_count as usize;
+        let data_end = self.data_offset + log.data_len as usize;
+        let view = LogView {
+            receipt_index: self.receipt_index,
+            address: *block_receipts.addresses.get(log.address as usize)?,
+            data: block_receipts.data.get(self.data_offset..data_end)?,
+            topic_indexes: block_receipts
+                .log_topics
+                .get(self.topic_offset..topics_end)?,
+            topic_dictionary: &block_receipts.topics,
+        };
+
+        self.log_index += 1;
+        self.logs_left_in_receipt -= 1;
+        self.topic_offset = topics_end;
+        self.data_offset = data_end;
+        Some(view)
+    }
+}
+
+/// Index of the value in the dictionary, adding it if it is not there yet
+fn dictionary_index<T: Copy + Eq + Hash>(
+    dictionary: &mut Vec<T>,
+    indexes: &mut HashMap<T, u32>,
+    value: T,
+) -> u32 {
+    *indexes.entry(value).or_insert_with(|| {
+        dictionary.push(value);
+        (dictionary.len() - 1) as u32
+    })
+}
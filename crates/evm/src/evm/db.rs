@@ -4,12 +4,18 @@ use revm::Database;
 use sov_modules_api::{StateMapAccessor, WorkingSet};
 use sov_state::codec::BcsCodec;
 
+use super::account_filter::AccountFilter;
+use super::storage_rent::StorageRent;
 use super::DbAccount;
 
 pub(crate) struct EvmDb<'a, C: sov_modules_api::Context> {
     pub(crate) accounts: sov_modules_api::StateMap<Address, DbAccount, BcsCodec>,
     pub(crate) code: sov_modules_api::StateMap<B256, Bytecode, BcsCodec>,
     pub(crate) last_block_hashes: sov_modules_api::StateMap<U256, B256, BcsCodec>,
+    /// Storage rent settled on commit, if the storage rent accounting is enabled
+    pub(crate) storage_rent: Option<StorageRent>,
+    /// Filter of the existing accounts, accounts are added on commit
+    pub(crate) account_filter: AccountFilter,
     pub(crate) working_set: &'a mut WorkingSet<C>,
 }
 
@@ -18,15 +24,27 @@ impl<'a, C: sov_modules_api::Context> EvmDb<'a, C> {
         accounts: sov_modules_api::StateMap<Address, DbAccount, BcsCodec>,
         code: sov_modules_api::StateMap<B256, Bytecode, BcsCodec>,
         last_block_hashes: sov_modules_api::StateMap<U256, B256, BcsCodec>,
+        storage_rent: Option<StorageRent>,
+        account_filter: AccountFilter,
         working_set: &'a mut WorkingSet<C>,
     ) -> Self {
         Self {
             accounts,
             code,
             last_block_hashes,
+            storage_rent,
+            account_filter,
             working_set,
         }
     }
+
+    /// Whether the account is known not to exist, without reading it from state.
+    fn is_missing_account(&mut self, address: &Address) -> bool {
+        self.account_filter.skip_missing
+            && !self
+                .account_filter
+                .may_contain(address, &mut self.working_set.accessory_state())
+    }
 }
 
 // infallible
@@ -50,6 +68,9 @@ impl<'a, C: sov_modules_api::Context> Database for EvmDb<'a, C> {
     type Error = DBError;
 
     fn basic(&mut self, address: Address) -> Result<Option<ReVmAccountInfo>, Self::Error> {
+        if self.is_missing_account(&address) {
+            return Ok(None);
+        }
         let db_account = self.accounts.get(&address, self.working_set);
         Ok(db_account.map(|acc| acc.info.into()))
     }
@@ -63,6 +84,9 @@ impl<'a, C: sov_modules_api::Context> Database for EvmDb<'a, C> {
     }
 
     fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        if self.is_missing_account(&address) {
+            return Ok(U256::default());
+        }
         let storage_value: U256 = if let Some(acc) = self.accounts.get(&address, self.working_set) {
             acc.storage
                 .get(&index, self.working_set)
@@ -6,6 +6,7 @@ use revm::DatabaseCommit;
 use sov_modules_api::{StateMapAccessor, StateVecAccessor};
 
 use super::db::EvmDb;
+use super::storage_rent::{account_size, collect_rent, live_storage_slots, StorageRentAccount};
 use super::DbAccount;
 
 impl<'a, C: sov_modules_api::Context> DatabaseCommit for EvmDb<'a, C> {
@@ -18,10 +19,15 @@ impl<'a, C: sov_modules_api::Context> DatabaseCommit for EvmDb<'a, C> {
             }
             let accounts_prefix = self.accounts.prefix();
 
-            let mut db_account = self
-                .accounts
-                .get(&address, self.working_set)
-                .unwrap_or_else(|| DbAccount::new(accounts_prefix, address));
+            let mut db_account = match self.accounts.get(&address, self.working_set) {
+                Some(db_account) => db_account,
+                None => {
+                    #[cfg(feature = "native")]
+                    self.account_filter
+                        .insert(&address, &mut self.working_set.accessory_state());
+                    DbAccount::new(accounts_prefix, address)
+                }
+            };
 
             // https://github.com/Sovereign-Labs/sovereign-sdk/issues/425
             if account.is_selfdestructed() {
@@ -38,14 +44,30 @@ impl<'a, C: sov_modules_api::Context> DatabaseCommit for EvmDb<'a, C> {
                 }
                 db_account.keys.clear(self.working_set);
                 self.accounts.set(&address, &db_account, self.working_set);
+                self.settle_storage_rent(address, &db_account, None, 0);
                 continue;
             }
 
             let account_info = account.info;
+            let code_size = match &account_info.code {
+                Some(code) => code.len(),
+                None => self
+                    .code
+                    .get(&account_info.code_hash, self.working_set)
+                    .map(|code| code.len())
+                    .unwrap_or_default(),
+            };
 
             if let Some(ref code) = account_info.code {
-                if !code.is_empty() {
-                    // TODO: would be good to have a contains_key method on the StateMap that would be optimized, so we can check the hash before storing the code
+                // Code is stored once per code hash and shared by the accounts deploying it,
+                // so deploying a clone does not add the code to the state diff again.
+                // TODO: would be good to have a contains_key method on the StateMap that would be optimized
+                if !code.is_empty()
+                    && self
+                        .code
+                        .get(&account_info.code_hash, self.working_set)
+                        .is_none()
+                {
                     self.code
                         .set(&account_info.code_hash, code, self.working_set);
                 }
@@ -54,16 +76,67 @@ impl<'a, C: sov_modules_api::Context> DatabaseCommit for EvmDb<'a, C> {
             db_account.info = account_info.into();
 
             let storage_slots = account.storage.into_iter().collect::<BTreeMap<_, _>>();
+            let mut live_slots_added = 0;
             // insert to StateVec keys must sorted -- or else nodes will have different state roots
             for (key, value) in storage_slots.into_iter() {
                 let value = value.present_value();
-                if db_account.storage.get(&key, self.working_set).is_none() {
+                let previous_value = db_account.storage.get(&key, self.working_set);
+                if previous_value.is_none() {
                     db_account.keys.push(&key, self.working_set);
                 }
+                let was_live = previous_value.is_some_and(|value| !value.is_zero());
+                live_slots_added += i64::from(!value.is_zero()) - i64::from(was_live);
                 db_account.storage.set(&key, &value, self.working_set);
             }
 
-            self.accounts.set(&address, &db_account, self.working_set)
+            self.accounts.set(&address, &db_account, self.working_set);
+
+            self.settle_storage_rent(address, &db_account, Some(code_size), live_slots_added);
         }
     }
 }
+
+impl<'a, C: sov_modules_api::Context> EvmDb<'a, C> {
+    /// Charges the storage rent of the account up to the current block and records its new size.
+    /// `live_slots_added` is the change of its storage slots with a non-zero value, the slots
+    /// of an account charged for the first time are counted in its storage.
+    /// The code size is `None` if the account is destroyed.
+    fn settle_storage_rent(
+        &mut self,
+        address: Address,
+        db_account: &DbAccount,
+        code_size: Option<usize>,
+        live_slots_added: i64,
+    ) {
+        let Some(storage_rent) = &self.storage_rent else {
+            return;
+        };
+        let (mut rent_account, storage_slots) =
+            match storage_rent.accounts.get(&address, self.working_set) {
+                Some(rent_account) => {
+                    let storage_slots = rent_account
+                        .storage_slots
+                        .saturating_add_signed(live_slots_added);
+                    (rent_account, storage_slots)
+                }
+                // Rent of a new account starts when it is first changed
+                None => (
+                    StorageRentAccount {
+                        settled_at: storage_rent.block_number,
+                        ..Default::default()
+                    },
+                    live_storage_slots(db_account, self.working_set),
+                ),
+            };
+        let (size, storage_slots) = match code_size {
+            Some(code_size) => (account_size(code_size, storage_slots), storage_slots),
+            None => (0, 0),
+        };
+        let paid = rent_account.settle(&storage_rent.config, storage_rent.block_number, size);
+        rent_account.storage_slots = storage_slots;
+        storage_rent
+            .accounts
+            .set(&address, &rent_account, self.working_set);
+        collect_rent(&storage_rent.collected, paid, self.working_set);
+    }
+}
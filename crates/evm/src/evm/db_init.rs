@@ -20,6 +20,9 @@ impl<'a, C: sov_modules_api::Context> InitEvmDb for EvmDb<'a, C> {
         let db_account = DbAccount::new_with_info(parent_prefix, sender, info);
 
         self.accounts.set(&sender, &db_account, self.working_set);
+        #[cfg(feature = "native")]
+        self.account_filter
+            .insert(&sender, &mut self.working_set.accessory_state());
     }
 
     fn insert_code(&mut self, code_hash: B256, code: Bytecode) {
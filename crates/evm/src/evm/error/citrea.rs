@@ -0,0 +1,148 @@
+//! Citrea specific errors, returned to RPC clients with stable error codes.
+
+use std::str::FromStr;
+
+use reth_primitives::{B256, U256};
+use revm::primitives::EVMError;
+use serde::{Deserialize, Serialize};
+use sov_modules_api::hooks::ApplySoftConfirmationError;
+
+const INSUFFICIENT_FUNDS_FOR_L1_FEE: &str = "Not enough funds for L1 fee: ";
+const BLOCK_GAS_LIMIT_EXCEEDED: &str = "Gas used exceeds block gas limit ";
+const INVALID_SYSTEM_TRANSACTION: &str = "Invalid system transaction: ";
+
+/// Errors specific to Citrea.
+///
+/// Every error has a stable JSON-RPC error code, and is returned with its fields as the error data,
+/// so clients can tell L1 fee failures, rule enforcer rejections and mempool errors apart
+/// without matching on error messages.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CitreaError {
+    /// The sender can not pay the L1 fee of the executed transaction
+    #[error("Not enough funds for L1 fee: {l1_fee}")]
+    InsufficientFundsForL1Fee {
+        /// L1 fee of the transaction
+        l1_fee: U256,
+    },
+    /// The transaction does not fit into the gas left in the block
+    #[error("Gas used exceeds block gas limit {block_gas_limit}")]
+    BlockGasLimitExceeded {
+        /// Gas limit of the block
+        block_gas_limit: u64,
+    },
+    /// A transaction of the system signer among the user transactions
+    #[error("Invalid system transaction: {tx_hash}")]
+    InvalidSystemTransaction {
+        /// Hash of the transaction
+        tx_hash: B256,
+    },
+    /// The soft confirmation rule enforcer rejected the soft confirmation
+    #[error("Soft confirmation rejected by the rule enforcer: {reason}")]
+    SoftConfirmationRejected {
+        /// Rule the soft confirmation violates
+        reason: String,
+    },
+    /// The mempool does not accept transactions of the system signer
+    #[error("system transactions from rpc are not allowed")]
+    SystemTransactionNotAllowed,
+    /// The max fee per gas of the transaction is below the min. gas price of the mempool
+    #[error("transaction underpriced: max fee per gas must be at least {min_gas_price}")]
+    Underpriced {
+        /// Min. gas price at the current L1 fee rate
+        min_gas_price: u128,
+    },
+    /// The sender has as many transactions in the mempool as allowed
+    #[error("account limit exceeded")]
+    AccountLimitExceeded,
+    /// The nonce of the transaction is too far ahead of the sender's nonce
+    #[error("nonce too high")]
+    NonceTooHigh,
+    /// The sender can not pay the max gas cost and value of the transaction and the min. L1 fee
+    #[error("insufficient funds for gas * price + value + l1 fee")]
+    InsufficientFundsForGasAndL1Fee {
+        /// Min. L1 fee of a transaction at the current L1 fee rate
+        l1_fee: U256,
+    },
+    /// The mempool is full and the transaction does not pay more than the ones it would evict
+    #[error("txpool is full")]
+    TxPoolFull,
+    /// The state of the requested block has been pruned on this node
+    #[error("state of block {block_number} is pruned, state is available from block {earliest_available_block}")]
+    StatePruned {
+        /// Requested block number
+        block_number: u64,
+        /// Earliest block number whose state is available
+        earliest_available_block: u64,
+    },
+}
+
+impl CitreaError {
+    /// The JSON-RPC error code of the error.
+    ///
+    /// Codes are stable: -3205x for execution failures, -3206x for rule enforcer rejections,
+    /// -3207x for mempool rejections and -3208x for unavailable data.
+    pub fn code(&self) -> i32 {
+        match self {
+            CitreaError::InsufficientFundsForL1Fee { .. } => -32050,
+            CitreaError::BlockGasLimitExceeded { .. } => -32051,
+            CitreaError::InvalidSystemTransaction { .. } => -32052,
+            CitreaError::SoftConfirmationRejected { .. } => -32060,
+            CitreaError::SystemTransactionNotAllowed => -32070,
+            CitreaError::Underpriced { .. } => -32071,
+            CitreaError::AccountLimitExceeded => -32072,
+            CitreaError::NonceTooHigh => -32073,
+            CitreaError::InsufficientFundsForGasAndL1Fee { .. } => -32074,
+            CitreaError::TxPoolFull => -32075,
+            CitreaError::StatePruned { .. } => -32080,
+        }
+    }
+
+    /// Recovers the Citrea error from an EVM error.
+    ///
+    /// revm only carries custom errors as strings, so execution failures are passed
+    /// as their messages, and parsed back here.
+    pub fn from_evm_error<E>(error: &EVMError<E>) -> Option<Self> {
+        let EVMError::Custom(msg) = error else {
+            return None;
+        };
+        if let Some(l1_fee) = msg.strip_prefix(INSUFFICIENT_FUNDS_FOR_L1_FEE) {
+            return U256::from_str(l1_fee)
+                .ok()
+                .map(|l1_fee| CitreaError::InsufficientFundsForL1Fee { l1_fee });
+        }
+        if let Some(block_gas_limit) = msg.strip_prefix(BLOCK_GAS_LIMIT_EXCEEDED) {
+            return block_gas_limit
+                .parse()
+                .ok()
+                .map(|block_gas_limit| CitreaError::BlockGasLimitExceeded { block_gas_limit });
+        }
+        if let Some(tx_hash) = msg.strip_prefix(INVALID_SYSTEM_TRANSACTION) {
+            return B256::from_str(tx_hash)
+                .ok()
+                .map(|tx_hash| CitreaError::InvalidSystemTransaction { tx_hash });
+        }
+        None
+    }
+}
+
+impl<E> From<CitreaError> for EVMError<E> {
+    fn from(error: CitreaError) -> Self {
+        EVMError::Custom(error.to_string())
+    }
+}
+
+impl From<ApplySoftConfirmationError> for CitreaError {
+    fn from(error: ApplySoftConfirmationError) -> Self {
+        CitreaError::SoftConfirmationRejected {
+            reason: error.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+impl From<CitreaError> for jsonrpsee::types::ErrorObjectOwned {
+    fn from(error: CitreaError) -> Self {
+        jsonrpsee::types::ErrorObjectOwned::owned(error.code(), error.to_string(), Some(&error))
+    }
+}
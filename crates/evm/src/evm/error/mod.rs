@@ -1,3 +1,7 @@
+pub mod citrea;
+#[cfg(feature = "native")]
 pub(crate) mod pool;
+#[cfg(feature = "native")]
 pub(crate) mod result;
+#[cfg(feature = "native")]
 pub mod rpc;
@@ -2,9 +2,10 @@
 
 use jsonrpsee::types::ErrorObject;
 use reth_primitives::{Bytes, U256};
-use reth_rpc::eth::error::{EthResult, RevertError, RpcInvalidTransactionError};
-use revm::primitives::{ExecutionResult, HaltReason, OutOfGasError};
+use reth_rpc::eth::error::{EthApiError, EthResult, RevertError, RpcInvalidTransactionError};
+use revm::primitives::{EVMError, ExecutionResult, HaltReason, OutOfGasError};
 
+use super::citrea::CitreaError;
 use super::pool::{
     Eip4844PoolTransactionError, InvalidPoolTransactionError, PoolError, PoolErrorKind,
     PoolTransactionError,
@@ -55,6 +56,17 @@ use super::result::internal_rpc_err;
 //     HaltedDepositPostRegolith,
 // }
 
+/// Converts an EVM error to an RPC error. Citrea errors keep their own codes and data.
+pub(crate) fn evm_error_to_rpc<E>(error: EVMError<E>) -> ErrorObject<'static>
+where
+    EthApiError: From<EVMError<E>>,
+{
+    match CitreaError::from_evm_error(&error) {
+        Some(error) => error.into(),
+        None => EthApiError::from(error).into(),
+    }
+}
+
 /// A helper error type that's mainly used to mirror `geth` Txpool's error messages
 #[derive(Debug, thiserror::Error)]
 pub enum RpcPoolError {
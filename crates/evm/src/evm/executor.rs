@@ -1,4 +1,6 @@
 use reth_primitives::TransactionSignedEcRecovered;
+#[cfg(feature = "native")]
+use revm::primitives::{Account, Address, B256, U256};
 use revm::primitives::{
     CfgEnvWithHandlerCfg, EVMError, Env, ExecutionResult, ResultAndState, State,
 };
@@ -8,10 +10,12 @@ use sov_modules_api::{native_error, native_trace};
 use tracing::trace_span;
 
 use super::conversions::create_tx_env;
+#[cfg(feature = "native")]
+use super::handler::TxInfo;
 use super::handler::{citrea_handler, CitreaExternalExt};
 use super::primitive_types::BlockEnv;
 use crate::db::DBError;
-use crate::SYSTEM_SIGNER;
+use crate::{CitreaError, SYSTEM_SIGNER};
 
 pub(crate) struct CitreaEvm<'a, EXT, DB: Database> {
     evm: revm::Evm<'a, EXT, DB>,
@@ -32,6 +36,19 @@ where
         Self { evm }
     }
 
+    /// Returns the database of the EVM.
+    #[cfg(feature = "native")]
+    pub(crate) fn db_mut(&mut self) -> &mut DB {
+        &mut self.evm.context.evm.db
+    }
+
+    /// Sets the info of a transaction executed by another EVM.
+    #[cfg(feature = "native")]
+    pub(crate) fn set_tx_info(&mut self, tx_hash: B256, info: TxInfo) {
+        self.evm.context.external.set_current_tx_hash(tx_hash);
+        self.evm.context.external.set_tx_info(info);
+    }
+
     /// Sets all required parameters and executes a transaction.
     fn transact_commit(
         &mut self,
@@ -47,7 +64,7 @@ where
 
     /// Runs a single transaction in the configured environment and proceeds
     /// to return the result and state diff (without applying it).
-    fn transact(
+    pub(crate) fn transact(
         &mut self,
         tx: &TransactionSignedEcRecovered,
     ) -> Result<ResultAndState, EVMError<DB::Error>> {
@@ -57,12 +74,26 @@ where
     }
 
     /// Commits the given state diff to the database.
-    fn commit(&mut self, state: State)
+    pub(crate) fn commit(&mut self, state: State)
     where
         DB: DatabaseCommit,
     {
         self.evm.context.evm.db.commit(state)
     }
+
+    /// Increases the balance of the account by the given amount.
+    #[cfg(feature = "native")]
+    pub(crate) fn credit_balance(&mut self, address: Address, amount: U256) -> Result<(), DB::Error>
+    where
+        DB: DatabaseCommit,
+    {
+        let db = &mut self.evm.context.evm.db;
+        let mut account = Account::from(db.basic(address)?.unwrap_or_default());
+        account.info.balance = account.info.balance.saturating_add(amount);
+        account.mark_touch();
+        db.commit(State::from_iter([(address, account)]));
+        Ok(())
+    }
 }
 
 #[allow(dead_code)]
@@ -116,15 +147,9 @@ pub(crate) fn execute_multiple_tx<
         // Check if the transaction used more gas than the available block gas limit
         let result = if cumulative_gas_used + result_and_state.result.gas_used() > block_gas_limit {
             native_error!("Gas used exceeds block gas limit");
-            Err(EVMError::Custom(format!(
-                "Gas used exceeds block gas limit {:?}",
-                block_gas_limit
-            )))
+            Err(CitreaError::BlockGasLimitExceeded { block_gas_limit }.into())
         } else if tx.signer() == SYSTEM_SIGNER {
-            Err(EVMError::Custom(format!(
-                "Invalid system transaction: {:?}",
-                hex::encode(tx.hash())
-            )))
+            Err(CitreaError::InvalidSystemTransaction { tx_hash: tx.hash() }.into())
         } else {
             native_trace!("Commiting tx to DB");
             evm.commit(result_and_state.state);
@@ -21,7 +21,7 @@ use sov_modules_api::{native_debug, native_error, native_warn};
 use tracing::instrument;
 
 use crate::system_events::SYSTEM_SIGNER;
-use crate::{BASE_FEE_VAULT, L1_FEE_VAULT};
+use crate::{CitreaError, BASE_FEE_VAULT, L1_FEE_VAULT};
 
 #[derive(Copy, Clone, Default, Debug)]
 pub struct TxInfo {
@@ -34,12 +34,17 @@ pub struct TxInfo {
 pub(crate) trait CitreaExternalExt {
     /// Get current l1 fee rate.
     fn l1_fee_rate(&self) -> u128;
+    /// Whether deploying code that is already stored only adds the code hash to the diff size.
+    fn shares_code(&self) -> bool;
     /// Set tx hash for the current execution context.
     fn set_current_tx_hash(&mut self, hash: B256);
     /// Set tx info for the current tx hash.
     fn set_tx_info(&mut self, info: TxInfo);
     /// Get tx info for the given tx by its hash.
     fn get_tx_info(&self, tx_hash: B256) -> Option<TxInfo>;
+    /// Records the fee instead of crediting it to the vault, if fee payments are deferred.
+    /// Returns whether the fee was deferred.
+    fn try_defer_fee(&mut self, vault: Address, amount: U256) -> bool;
 }
 
 // Blanked impl for &mut T: CitreaExternalExt
@@ -47,6 +52,9 @@ impl<T: CitreaExternalExt> CitreaExternalExt for &mut T {
     fn l1_fee_rate(&self) -> u128 {
         (**self).l1_fee_rate()
     }
+    fn shares_code(&self) -> bool {
+        (**self).shares_code()
+    }
     fn set_current_tx_hash(&mut self, hash: B256) {
         (**self).set_current_tx_hash(hash);
     }
@@ -56,6 +64,9 @@ impl<T: CitreaExternalExt> CitreaExternalExt for &mut T {
     fn get_tx_info(&self, tx_hash: B256) -> Option<TxInfo> {
         (**self).get_tx_info(tx_hash)
     }
+    fn try_defer_fee(&mut self, vault: Address, amount: U256) -> bool {
+        (**self).try_defer_fee(vault, amount)
+    }
 }
 
 /// This is an external context to be passed to the EVM.
@@ -63,8 +74,11 @@ impl<T: CitreaExternalExt> CitreaExternalExt for &mut T {
 #[derive(Default)]
 pub(crate) struct CitreaExternal {
     l1_fee_rate: u128,
+    shares_code: bool,
     current_tx_hash: Option<B256>,
     tx_infos: BTreeMap<B256, TxInfo>,
+    /// Fees left to the executor to credit to the vaults, if set
+    deferred_fees: Option<Vec<(Address, U256)>>,
 }
 
 impl CitreaExternal {
@@ -74,12 +88,38 @@ impl CitreaExternal {
             ..Default::default()
         }
     }
+
+    /// Charges clones of stored code only for their code hash, see [`Evm::shares_code`](crate::Evm::shares_code).
+    pub(crate) fn with_shared_code(mut self, shares_code: bool) -> Self {
+        self.shares_code = shares_code;
+        self
+    }
+
+    /// Defers the fee payments, so that transactions executed speculatively
+    /// do not read and write the fee vaults.
+    #[cfg(feature = "native")]
+    pub(crate) fn with_deferred_fees(mut self) -> Self {
+        self.deferred_fees = Some(vec![]);
+        self
+    }
+
+    /// Takes the deferred fees of the executed transactions.
+    #[cfg(feature = "native")]
+    pub(crate) fn take_deferred_fees(&mut self) -> Vec<(Address, U256)> {
+        self.deferred_fees
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
 }
 
 impl CitreaExternalExt for CitreaExternal {
     fn l1_fee_rate(&self) -> u128 {
         self.l1_fee_rate
     }
+    fn shares_code(&self) -> bool {
+        self.shares_code
+    }
     #[cfg_attr(feature = "native", instrument(level = "trace", skip(self)))]
     fn set_current_tx_hash(&mut self, hash: B256) {
         self.current_tx_hash.replace(hash);
@@ -96,6 +136,15 @@ impl CitreaExternalExt for CitreaExternal {
     fn get_tx_info(&self, tx_hash: B256) -> Option<TxInfo> {
         self.tx_infos.get(&tx_hash).copied()
     }
+    fn try_defer_fee(&mut self, vault: Address, amount: U256) -> bool {
+        match &mut self.deferred_fees {
+            Some(deferred_fees) => {
+                deferred_fees.push((vault, amount));
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 #[cfg(feature = "native")]
@@ -119,6 +168,23 @@ where
             _ph: Default::default(),
         }
     }
+
+    /// Charges clones of stored code only for their code hash, see [`CitreaExternal::with_shared_code`].
+    pub(crate) fn with_shared_code(mut self, shares_code: bool) -> Self {
+        self.ext = self.ext.with_shared_code(shares_code);
+        self
+    }
+
+    /// Defers the fee payments, see [`CitreaExternal::with_deferred_fees`].
+    pub(crate) fn with_deferred_fees(mut self) -> Self {
+        self.ext = self.ext.with_deferred_fees();
+        self
+    }
+
+    /// Takes the deferred fees of the executed transactions.
+    pub(crate) fn take_deferred_fees(&mut self) -> Vec<(Address, U256)> {
+        self.ext.take_deferred_fees()
+    }
 }
 
 #[cfg(feature = "native")]
@@ -127,6 +193,9 @@ impl<I, DB> CitreaExternalExt for TracingCitreaExternal<I, DB> {
     fn l1_fee_rate(&self) -> u128 {
         self.ext.l1_fee_rate()
     }
+    fn shares_code(&self) -> bool {
+        self.ext.shares_code()
+    }
     fn set_current_tx_hash(&mut self, hash: B256) {
         self.ext.set_current_tx_hash(hash);
     }
@@ -136,6 +205,9 @@ impl<I, DB> CitreaExternalExt for TracingCitreaExternal<I, DB> {
     fn get_tx_info(&self, tx_hash: B256) -> Option<TxInfo> {
         self.ext.get_tx_info(tx_hash)
     }
+    fn try_defer_fee(&mut self, vault: Address, amount: U256) -> bool {
+        self.ext.try_defer_fee(vault, amount)
+    }
 }
 
 #[cfg(feature = "native")]
@@ -334,11 +406,23 @@ impl<SPEC: Spec, EXT: CitreaExternalExt, DB: Database> CitreaHandler<SPEC, EXT,
             // add base fee to base fee vault
             let base_fee_per_gas = context.evm.env.block.basefee;
             let base_fee = base_fee_per_gas * gas_used;
-            change_balance(context, base_fee, true, BASE_FEE_VAULT)?;
+            credit_vault(context, base_fee, BASE_FEE_VAULT)?;
         }
 
         // send priority fee to coinbase using revm mainnet behaviour
-        revm::handler::mainnet::reward_beneficiary::<SPEC, EXT, DB>(context, gas)?;
+        let coinbase = context.evm.env.block.coinbase;
+        let effective_gas_price = context.evm.env.effective_gas_price();
+        let coinbase_gas_price = if SPEC::enabled(SpecId::LONDON) {
+            effective_gas_price.saturating_sub(context.evm.env.block.basefee)
+        } else {
+            effective_gas_price
+        };
+        if !context
+            .external
+            .try_defer_fee(coinbase, coinbase_gas_price * gas_used)
+        {
+            revm::handler::mainnet::reward_beneficiary::<SPEC, EXT, DB>(context, gas)?;
+        }
 
         Ok(())
     }
@@ -358,13 +442,10 @@ impl<SPEC: Spec, EXT: CitreaExternalExt, DB: Database> CitreaHandler<SPEC, EXT,
             // System caller doesn't pay L1 fee.
         } else {
             if let Some(_out_of_funds) = decrease_caller_balance(context, l1_fee)? {
-                return Err(EVMError::Custom(format!(
-                    "Not enough funds for L1 fee: {}",
-                    l1_fee
-                )));
+                return Err(CitreaError::InsufficientFundsForL1Fee { l1_fee }.into());
             }
             // add l1 fee to l1 fee vault
-            change_balance(context, l1_fee, true, L1_FEE_VAULT)?;
+            credit_vault(context, l1_fee, L1_FEE_VAULT)?;
         }
 
         revm::handler::mainnet::output(context, result)
@@ -373,9 +454,10 @@ impl<SPEC: Spec, EXT: CitreaExternalExt, DB: Database> CitreaHandler<SPEC, EXT,
 
 /// Calculates the diff of the modified state.
 #[cfg_attr(feature = "native", instrument(level = "trace", skip_all))]
-fn calc_diff_size<EXT, DB: Database>(
+fn calc_diff_size<EXT: CitreaExternalExt, DB: Database>(
     context: &mut Context<EXT, DB>,
 ) -> Result<usize, <DB as Database>::Error> {
+    let shares_code = context.external.shares_code();
     let InnerEvmContext {
         db,
         journaled_state,
@@ -447,6 +529,7 @@ fn calc_diff_size<EXT, DB: Database>(
 
     let slot_size = 2 * size_of::<U256>(); // key + value;
     let mut diff_size = 0usize;
+    let mut new_code_hashes = BTreeSet::new();
 
     // no matter the type of transaction or its fee rates, a tx must pay at least base fee and L1 fee
     // thus we increment the diff size by 20 (coinbase address) + 32 (coinbase balance change)
@@ -496,7 +579,14 @@ fn calc_diff_size<EXT, DB: Database>(
             let account = &state[addr];
             diff_size += size_of::<B256>(); // Code hashes are B256
             if let Some(code) = account.info.code.as_ref() {
-                diff_size += code.len()
+                // Code is stored once per code hash, once activated clones of stored code
+                // are only charged for the code hash
+                let code_hash = account.info.code_hash;
+                if !shares_code
+                    || (new_code_hashes.insert(code_hash) && db.code_by_hash(code_hash)?.is_empty())
+                {
+                    diff_size += code.len()
+                }
             } else {
                 native_warn!(
                     "Code must exist for account when calculating diff: {}",
@@ -542,6 +632,19 @@ fn change_balance<EXT, DB: Database>(
     Ok(None)
 }
 
+/// Increases the balance of the fee vault by the given amount,
+/// unless the fee payments are deferred to the executor.
+fn credit_vault<EXT: CitreaExternalExt, DB: Database>(
+    context: &mut Context<EXT, DB>,
+    amount: U256,
+    vault: Address,
+) -> Result<(), EVMError<DB::Error>> {
+    if !context.external.try_defer_fee(vault, amount) {
+        change_balance(context, amount, true, vault)?;
+    }
+    Ok(())
+}
+
 /// Decreases the balance of the caller by the given amount.
 /// Returns Ok(Some) if the caller's balance is not enough.
 fn decrease_caller_balance<EXT, DB: Database>(
@@ -4,13 +4,17 @@ use serde::{Deserialize, Serialize};
 use sov_modules_api::{StateMap, StateVec};
 use sov_state::Prefix;
 
+pub(crate) mod account_filter;
+pub(crate) mod compact_receipts;
 pub(crate) mod conversions;
 pub(crate) mod db;
 mod db_commit;
 pub(crate) mod db_init;
+pub(crate) mod error;
 pub(crate) mod executor;
 pub(crate) mod handler;
 pub(crate) mod primitive_types;
+pub(crate) mod storage_rent;
 /// System contracts used for system transactions
 pub mod system_contracts;
 pub(crate) mod system_events;
@@ -18,13 +22,17 @@ pub(crate) mod system_events;
 #[cfg(feature = "native")]
 pub(crate) mod call;
 #[cfg(feature = "native")]
-pub(crate) mod error;
+pub(crate) mod parallel_executor;
 
 #[cfg(all(test, feature = "native"))]
 mod tests;
 
+pub use error::citrea::CitreaError;
+#[cfg(feature = "native")]
+pub use parallel_executor::set_parallel_execution;
 pub use primitive_types::RlpEvmTransaction;
 use sov_state::codec::BcsCodec;
+pub use storage_rent::{StorageRentAccount, StorageRentConfig, STORAGE_RENT_DEPOSIT_ADDRESS};
 
 #[cfg(all(test, feature = "native"))]
 use crate::tests::DEFAULT_CHAIN_ID;
@@ -40,6 +48,14 @@ pub const BASE_FEE_VAULT: Address = address!("3100000000000000000000000000000000
 pub const L1_FEE_VAULT: Address = address!("3100000000000000000000000000000000000004");
 /// Priority fee vault address
 pub const PRIORITY_FEE_VAULT: Address = address!("3100000000000000000000000000000000000005");
+/// Randomness beacon address
+pub const RANDOMNESS_BEACON: Address = address!("3100000000000000000000000000000000000006");
+/// Trusted forwarder of meta-transactions address
+pub const TRUSTED_FORWARDER: Address = address!("3100000000000000000000000000000000000007");
+/// Fee vaults swept to their recipients by system transactions
+pub const FEE_VAULTS: [Address; 3] = [BASE_FEE_VAULT, L1_FEE_VAULT, PRIORITY_FEE_VAULT];
+/// Fee vaults are swept at the beginning of every `FEE_VAULT_SWEEP_INTERVAL`th L2 block
+pub const FEE_VAULT_SWEEP_INTERVAL: u64 = 1000;
 
 // Stores information about an EVM account
 #[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
@@ -0,0 +1,371 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use rayon::prelude::*;
+use reth_primitives::TransactionSignedEcRecovered;
+use revm::interpreter::{
+    opcode, CallInputs, CallOutcome, CreateInputs, CreateOutcome, Interpreter,
+};
+use revm::primitives::{
+    AccountInfo as ReVmAccountInfo, Address, Bytecode, CfgEnvWithHandlerCfg, EVMError,
+    ExecutionResult, ResultAndState, State, B256, U256,
+};
+use revm::{inspector_handle_register, Database, DatabaseCommit, EvmContext, Inspector};
+use sov_modules_api::{native_error, native_trace};
+use tracing::trace_span;
+
+use super::conversions::create_tx_env;
+use super::executor::CitreaEvm;
+use super::handler::{citrea_handle_register, CitreaExternalExt, TracingCitreaExternal, TxInfo};
+use super::primitive_types::BlockEnv;
+use crate::db::DBError;
+use crate::{CitreaError, BASE_FEE_VAULT, L1_FEE_VAULT, SYSTEM_SIGNER};
+
+static PARALLEL_EXECUTION: AtomicBool = AtomicBool::new(false);
+
+/// Enables the optimistic parallel execution of the transactions of an L2 block.
+///
+/// Transactions are executed speculatively, so state is read into the witness
+/// out of execution order. Must not be enabled by nodes generating witnesses for proofs.
+pub fn set_parallel_execution(enabled: bool) {
+    PARALLEL_EXECUTION.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether the transactions of an L2 block are executed in parallel.
+pub(crate) fn parallel_execution_enabled() -> bool {
+    PARALLEL_EXECUTION.load(Ordering::Relaxed)
+}
+
+/// Accounts and storage slots accessed by transactions
+#[derive(Default)]
+struct AccessSet {
+    accounts: HashSet<Address>,
+    slots: HashSet<(Address, U256)>,
+    /// Self destructed accounts, all of their storage slots are cleared
+    destroyed: HashSet<Address>,
+    /// Hashes of code missing from the code store when read, or stored when written
+    codes: HashSet<B256>,
+}
+
+impl AccessSet {
+    /// Whether the reads of a transaction are stale after the writes.
+    fn conflicts_with(&self, writes: &AccessSet) -> bool {
+        self.accounts
+            .iter()
+            .any(|address| writes.accounts.contains(address))
+            || self.slots.iter().any(|(address, key)| {
+                writes.destroyed.contains(address) || writes.slots.contains(&(*address, *key))
+            })
+            || self
+                .codes
+                .iter()
+                .any(|code_hash| writes.codes.contains(code_hash))
+    }
+
+    /// Records the changes of the state diff, before it is committed to the database.
+    /// Accounts which are only touched are not changed.
+    fn record_writes<DB: Database>(&mut self, db: &mut DB, state: &State) -> Result<(), DB::Error> {
+        for (address, account) in state {
+            if !account.is_touched() {
+                continue;
+            }
+            if account.is_selfdestructed() {
+                self.accounts.insert(*address);
+                self.destroyed.insert(*address);
+                continue;
+            }
+
+            let (changed, code_changed) = match db.basic(*address)? {
+                Some(info) => (
+                    info.balance != account.info.balance
+                        || info.nonce != account.info.nonce
+                        || info.code_hash != account.info.code_hash,
+                    info.code_hash != account.info.code_hash,
+                ),
+                None => (true, true),
+            };
+            if changed {
+                self.accounts.insert(*address);
+            }
+            if code_changed {
+                self.codes.insert(account.info.code_hash);
+            }
+            for (key, slot) in &account.storage {
+                if slot.is_changed() {
+                    self.slots.insert((*address, *key));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Accounts credited with the fees of every transaction
+fn fee_vaults(block_env: &BlockEnv) -> [Address; 3] {
+    [BASE_FEE_VAULT, L1_FEE_VAULT, block_env.coinbase]
+}
+
+/// Database of a speculative execution, reads the shared database and records the accessed state.
+///
+/// Fee vaults are always loaded, as the coinbase is warm. Whether a transaction
+/// uses them is detected by the [`FeeVaultInspector`] instead.
+struct SpeculativeDb<'a, DB> {
+    db: &'a Mutex<DB>,
+    fee_vaults: [Address; 3],
+    reads: AccessSet,
+}
+
+impl<'a, DB: Database<Error = DBError>> Database for SpeculativeDb<'a, DB> {
+    type Error = DBError;
+
+    fn basic(&mut self, address: Address) -> Result<Option<ReVmAccountInfo>, Self::Error> {
+        if !self.fee_vaults.contains(&address) {
+            self.reads.accounts.insert(address);
+        }
+        self.db.lock().expect("EVM db lock poisoned").basic(address)
+    }
+
+    fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        // Code is addressed by its hash, it can not change once stored
+        let code = self
+            .db
+            .lock()
+            .expect("EVM db lock poisoned")
+            .code_by_hash(code_hash)?;
+        if code.is_empty() {
+            self.reads.codes.insert(code_hash);
+        }
+        Ok(code)
+    }
+
+    fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.reads.slots.insert((address, index));
+        self.db
+            .lock()
+            .expect("EVM db lock poisoned")
+            .storage(address, index)
+    }
+
+    fn block_hash(&mut self, number: U256) -> Result<B256, Self::Error> {
+        // Block hashes do not change within the block
+        self.db
+            .lock()
+            .expect("EVM db lock poisoned")
+            .block_hash(number)
+    }
+}
+
+/// Detects whether a transaction uses a fee vault, whose balance changes with every transaction.
+struct FeeVaultInspector {
+    fee_vaults: [Address; 3],
+    used: bool,
+}
+
+impl FeeVaultInspector {
+    fn check(&mut self, address: Address) {
+        self.used |= self.fee_vaults.contains(&address);
+    }
+}
+
+impl<DB: Database> Inspector<DB> for FeeVaultInspector {
+    fn step(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        match interp.current_opcode() {
+            opcode::BALANCE | opcode::EXTCODESIZE | opcode::EXTCODECOPY | opcode::EXTCODEHASH => {
+                if let Ok(address) = interp.stack().peek(0) {
+                    self.check(Address::from_word(B256::from(address.to_be_bytes::<32>())));
+                }
+            }
+            opcode::SELFBALANCE => self.check(interp.contract.address),
+            _ => {}
+        }
+    }
+
+    fn call(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CallInputs,
+    ) -> Option<CallOutcome> {
+        self.check(inputs.contract);
+        self.check(inputs.context.address);
+        self.check(inputs.transfer.source);
+        self.check(inputs.transfer.target);
+        None
+    }
+
+    fn create(
+        &mut self,
+        _context: &mut EvmContext<DB>,
+        inputs: &mut CreateInputs,
+    ) -> Option<CreateOutcome> {
+        self.check(inputs.caller);
+        None
+    }
+
+    fn selfdestruct(&mut self, contract: Address, target: Address, _value: U256) {
+        self.check(contract);
+        self.check(target);
+    }
+}
+
+/// Result of a transaction executed against the state before the transactions of the block
+struct Speculation {
+    result: Result<ResultAndState, EVMError<DBError>>,
+    reads: AccessSet,
+    /// Whether the transaction used a fee vault
+    uses_fee_vaults: bool,
+    tx_info: Option<TxInfo>,
+    /// Fees to credit to the vaults, once the transaction is committed
+    deferred_fees: Vec<(Address, U256)>,
+}
+
+/// Executes the transaction against the shared database, without paying the fee vaults.
+fn speculate<DB: Database<Error = DBError>>(
+    db: &Mutex<DB>,
+    block_env: BlockEnv,
+    config_env: CfgEnvWithHandlerCfg,
+    l1_fee_rate: u128,
+    shares_code: bool,
+    tx: &TransactionSignedEcRecovered,
+) -> Speculation {
+    let fee_vaults = fee_vaults(&block_env);
+    let ext = TracingCitreaExternal::new(
+        FeeVaultInspector {
+            fee_vaults,
+            used: false,
+        },
+        l1_fee_rate,
+    )
+    .with_shared_code(shares_code)
+    .with_deferred_fees();
+
+    let mut evm = revm::Evm::builder()
+        .with_db(SpeculativeDb {
+            db,
+            fee_vaults,
+            reads: AccessSet::default(),
+        })
+        .with_external_context(ext)
+        .with_cfg_env_with_handler_cfg(config_env)
+        .with_block_env(block_env.into())
+        .with_tx_env(create_tx_env(tx))
+        .append_handler_register(citrea_handle_register)
+        .append_handler_register(inspector_handle_register)
+        .build();
+    evm.context.external.set_current_tx_hash(tx.hash());
+    let result = evm.transact();
+
+    let ext = &mut evm.context.external;
+    Speculation {
+        result,
+        reads: std::mem::take(&mut evm.context.evm.db.reads),
+        uses_fee_vaults: ext.inspector.used,
+        tx_info: ext.get_tx_info(tx.hash()),
+        deferred_fees: ext.take_deferred_fees(),
+    }
+}
+
+/// Executes the transactions in parallel, with the same results as [`super::executor::execute_multiple_tx`].
+///
+/// All transactions are first executed speculatively against the state before the block,
+/// without paying the fee vaults every transaction would conflict on.
+/// The speculative results are then committed in order, a transaction which read state
+/// changed by the transactions before it is executed again.
+pub(crate) fn execute_multiple_tx_parallel<
+    DB: Database<Error = DBError> + DatabaseCommit + Send,
+    EXT: CitreaExternalExt,
+>(
+    db: DB,
+    block_env: BlockEnv,
+    txs: &[TransactionSignedEcRecovered],
+    config_env: CfgEnvWithHandlerCfg,
+    ext: &mut EXT,
+    prev_gas_used: u64,
+) -> Vec<Result<ExecutionResult, EVMError<DBError>>> {
+    if txs.is_empty() {
+        return vec![];
+    }
+
+    let block_gas_limit = block_env.gas_limit;
+    let l1_fee_rate = ext.l1_fee_rate();
+    let shares_code = ext.shares_code();
+
+    let shared_db = Mutex::new(db);
+    let speculations: Vec<Speculation> = txs
+        .par_iter()
+        .map(|tx| {
+            speculate(
+                &shared_db,
+                block_env,
+                config_env.clone(),
+                l1_fee_rate,
+                shares_code,
+                tx,
+            )
+        })
+        .collect();
+
+    let db = shared_db.into_inner().expect("EVM db lock poisoned");
+    let mut evm = CitreaEvm::new(db, block_env, config_env, ext);
+
+    let mut writes = AccessSet::default();
+    // Fee vaults are credited by every committed transaction
+    let mut fee_vaults_credited = false;
+    let mut cumulative_gas_used = prev_gas_used;
+    let mut reexecuted = 0;
+
+    let mut tx_results = Vec::with_capacity(txs.len());
+    for ((i, tx), speculation) in txs.iter().enumerate().zip(speculations) {
+        let _span =
+            trace_span!("Committing tx", i, signer = %tx.signer(), tx_hash = %tx.hash()).entered();
+        let conflicts = speculation.reads.conflicts_with(&writes)
+            || (speculation.uses_fee_vaults && fee_vaults_credited);
+        let (result_and_state, deferred_fees) = if conflicts {
+            native_trace!("Re-executing conflicting tx");
+            reexecuted += 1;
+            (evm.transact(tx), vec![])
+        } else {
+            if let Some(tx_info) = speculation.tx_info {
+                evm.set_tx_info(tx.hash(), tx_info);
+            }
+            (speculation.result, speculation.deferred_fees)
+        };
+        let result_and_state = match result_and_state {
+            Ok(result_and_state) => result_and_state,
+            Err(e) => {
+                native_error!(error = %e, "Transaction failed");
+                tx_results.push(Err(e));
+                continue;
+            }
+        };
+
+        // Check if the transaction used more gas than the available block gas limit
+        let result = if cumulative_gas_used + result_and_state.result.gas_used() > block_gas_limit {
+            native_error!("Gas used exceeds block gas limit");
+            Err(CitreaError::BlockGasLimitExceeded { block_gas_limit }.into())
+        } else if tx.signer() == SYSTEM_SIGNER {
+            Err(CitreaError::InvalidSystemTransaction { tx_hash: tx.hash() }.into())
+        } else {
+            native_trace!("Commiting tx to DB");
+            writes
+                .record_writes(evm.db_mut(), &result_and_state.state)
+                .unwrap_or_else(|e| match e {});
+            evm.commit(result_and_state.state);
+            for (vault, amount) in deferred_fees {
+                evm.credit_balance(vault, amount)
+                    .unwrap_or_else(|e| match e {});
+            }
+            fee_vaults_credited = true;
+            cumulative_gas_used += result_and_state.result.gas_used();
+            Ok(result_and_state.result)
+        };
+        tx_results.push(result);
+    }
+
+    native_trace!(
+        txs = txs.len(),
+        reexecuted,
+        "Executed transactions in parallel"
+    );
+    tx_results
+}
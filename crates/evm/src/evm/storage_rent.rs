@@ -0,0 +1,121 @@
+use std::mem::size_of;
+
+use reth_primitives::{address, Address, B256, U256};
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{
+    StateMap, StateMapAccessor, StateValue, StateValueAccessor, StateVecAccessor, WorkingSet,
+};
+use sov_state::codec::BcsCodec;
+
+use super::DbAccount;
+
+/// Value sent to this address is added to the storage rent deposit of the sender,
+/// or of the account given as the 20 bytes of calldata.
+/// A transaction without value withdraws the remaining deposit of the sender.
+/// The deposits are held by the address, the rent paid from them is moved to the base fee vault.
+pub const STORAGE_RENT_DEPOSIT_ADDRESS: Address =
+    address!("3100000000000000000000000000000000000008");
+
+/// Size of an account without its code and storage: address, nonce, balance and code hash.
+const ACCOUNT_INFO_SIZE: usize = size_of::<Address>() + size_of::<u64>() + 2 * size_of::<B256>();
+/// Size of a storage slot: key and value.
+const STORAGE_SLOT_SIZE: usize = 2 * size_of::<U256>();
+
+/// Parameters of the experimental storage rent accounting.
+/// Storage rent is accounted only, unpaid rent does not restrict the account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct StorageRentConfig {
+    /// Rent in wei charged per byte of account state per L2 block
+    pub rent_per_byte_per_block: u64,
+}
+
+/// Storage rent of an EVM account.
+/// Rent accrues lazily, it is settled whenever the account is changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageRentAccount {
+    /// Remaining rent deposit
+    pub deposit: U256,
+    /// Rent accrued after the deposit ran out
+    pub unpaid: U256,
+    /// Size of the account state in bytes, as of the last settlement
+    pub size: u64,
+    /// Storage slots of the account with a non-zero value, as of the last settlement
+    pub storage_slots: u64,
+    /// L2 block the rent was last settled at
+    pub settled_at: u64,
+}
+
+impl StorageRentAccount {
+    /// Charges the rent for the blocks since the last settlement against the deposit,
+    /// and records the new size of the account. Returns the rent paid from the deposit.
+    pub(crate) fn settle(
+        &mut self,
+        config: &StorageRentConfig,
+        block_number: u64,
+        size: u64,
+    ) -> U256 {
+        let blocks = block_number.saturating_sub(self.settled_at);
+        let due = U256::from(self.size)
+            .saturating_mul(U256::from(config.rent_per_byte_per_block))
+            .saturating_mul(U256::from(blocks));
+        let paid = due.min(self.deposit);
+        self.deposit -= paid;
+        self.unpaid = self.unpaid.saturating_add(due - paid);
+        self.size = size;
+        self.settled_at = block_number;
+        paid
+    }
+
+    /// Adds to the deposit, paying off the unpaid rent first. Returns the rent paid off.
+    pub(crate) fn add_deposit(&mut self, amount: U256) -> U256 {
+        let repaid = amount.min(self.unpaid);
+        self.unpaid -= repaid;
+        self.deposit = self.deposit.saturating_add(amount - repaid);
+        repaid
+    }
+}
+
+/// Size of an account state the storage rent is charged for.
+pub(crate) fn account_size(code_size: usize, storage_slots: u64) -> u64 {
+    (ACCOUNT_INFO_SIZE + code_size) as u64 + STORAGE_SLOT_SIZE as u64 * storage_slots
+}
+
+/// Storage slots of the account with a non-zero value.
+/// Slots cleared to zero stay in the keys of the account, but are not charged.
+pub(crate) fn live_storage_slots<C: sov_modules_api::Context>(
+    db_account: &DbAccount,
+    working_set: &mut WorkingSet<C>,
+) -> u64 {
+    let keys: Vec<U256> = db_account.keys.iter(working_set).collect();
+    keys.iter()
+        .filter(|key| {
+            db_account
+                .storage
+                .get(key, working_set)
+                .is_some_and(|value| !value.is_zero())
+        })
+        .count() as u64
+}
+
+/// Adds rent paid from the deposits to the rent to be moved to the base fee vault.
+pub(crate) fn collect_rent<C: sov_modules_api::Context>(
+    collected: &StateValue<U256, BcsCodec>,
+    amount: U256,
+    working_set: &mut WorkingSet<C>,
+) {
+    if amount.is_zero() {
+        return;
+    }
+    let total = collected.get(working_set).unwrap_or_default();
+    collected.set(&total.saturating_add(amount), working_set);
+}
+
+/// Storage rent accounting of the block the EVM db is used in.
+pub(crate) struct StorageRent {
+    pub(crate) config: StorageRentConfig,
+    pub(crate) accounts: StateMap<Address, StorageRentAccount, BcsCodec>,
+    /// Rent paid from the deposits, which is not moved to the base fee vault yet
+    pub(crate) collected: StateValue<U256, BcsCodec>,
+    pub(crate) block_number: u64,
+}
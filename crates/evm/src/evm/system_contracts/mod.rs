@@ -1,5 +1,5 @@
-use alloy_primitives::{address, Address, Bytes, U256};
-use alloy_sol_types::{sol, SolCall};
+use alloy_primitives::{address, keccak256, Address, Bytes, Log, B256, U256};
+use alloy_sol_types::{eip712_domain, sol, SolCall, SolEvent, SolStruct};
 
 // BitcoinLightClient wrapper.
 sol! {
@@ -123,6 +123,183 @@ impl Bridge {
         func_selector.extend(params);
         func_selector.into()
     }
+
+    /// Decodes a `Withdrawal` event of the Bridge contract into the withdrawal index and Bitcoin address.
+    /// None if the log is not a `Withdrawal` event of the Bridge.
+    pub(crate) fn decode_withdrawal(log: &Log) -> Option<(u64, [u8; 32])> {
+        match Self::decode_event(log)? {
+            BridgeEvent::Withdrawal {
+                index,
+                bitcoin_address,
+            } => Some((index, bitcoin_address)),
+            BridgeEvent::Deposit { .. } => None,
+        }
+    }
+
+    /// Decodes a `Deposit` or `Withdrawal` event of the Bridge contract.
+    /// None if the log is not one of them.
+    pub fn decode_event(log: &Log) -> Option<BridgeEvent> {
+        if log.address != Self::address() {
+            return None;
+        }
+        if let Ok(deposit) = BridgeContract::Deposit::decode_log(log, true) {
+            return Some(BridgeEvent::Deposit {
+                wtx_id: deposit.data.wtxId.0,
+                recipient: deposit.data.recipient,
+            });
+        }
+        let withdrawal = BridgeContract::Withdrawal::decode_log(log, true).ok()?;
+        Some(BridgeEvent::Withdrawal {
+            index: withdrawal.data.index.saturating_to(),
+            bitcoin_address: withdrawal.data.bitcoin_address.0,
+        })
+    }
+}
+
+/// Amount of wei minted by a deposit and burned by a withdrawal, `DEPOSIT_AMOUNT` of the Bridge contract.
+pub const BRIDGE_DEPOSIT_AMOUNT: u128 = 10_000_000_000_000_000;
+
+/// An event of the Bridge contract moving funds between Bitcoin and Citrea
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BridgeEvent {
+    /// A deposit of a Bitcoin transaction is minted to the recipient
+    Deposit {
+        /// Witness txid of the deposit transaction
+        wtx_id: [u8; 32],
+        /// Address the deposit is minted to
+        recipient: Address,
+    },
+    /// A withdrawal to Bitcoin is initiated
+    Withdrawal {
+        /// Index of the withdrawal in the Bridge contract
+        index: u64,
+        /// Bitcoin address the withdrawal is paid to
+        bitcoin_address: [u8; 32],
+    },
+}
+
+sol! {
+    #[allow(missing_docs)]
+    interface FeeVaultContract {
+        function withdraw() external;
+    }
+}
+
+/// FeeVault wrapper, shared by the BaseFeeVault, L1FeeVault and PriorityFeeVault contracts.
+pub struct FeeVault {}
+
+impl FeeVault {
+    /// Storage slot of the recipient of the withdrawn fees
+    pub(crate) const RECIPIENT_SLOT: U256 = U256::ZERO;
+    /// Storage slot of the minimum balance the fees are withdrawn at
+    pub(crate) const MIN_WITHDRAW_SLOT: U256 = U256::from_limbs([1, 0, 0, 0]);
+
+    /// Return input data to withdraw the accumulated fees to the recipient.
+    pub fn withdraw() -> Bytes {
+        FeeVaultContract::withdrawCall {}.abi_encode().into()
+    }
+}
+
+sol! {
+    #[allow(missing_docs)]
+    interface RandomnessBeaconContract {
+        function getRandomness(uint256 _blockNumber) external view returns (bytes32);
+    }
+}
+
+/// RandomnessBeacon wrapper.
+pub struct RandomnessBeacon {}
+
+impl RandomnessBeacon {
+    /// Number of recent blocks whose randomness is kept in the storage of the beacon
+    pub(crate) const HISTORY: u64 = 256;
+
+    /// Randomness of the L2 block, used as its `PREVRANDAO`.
+    /// Mixes the Bitcoin block hash of the L2 block into the randomness of its parent,
+    /// so it depends on every Bitcoin block hash seen by the rollup.
+    pub(crate) fn randomness(
+        parent_randomness: B256,
+        l1_hash: [u8; 32],
+        block_number: u64,
+    ) -> B256 {
+        let mut preimage = Vec::with_capacity(32 + 32 + 8);
+        preimage.extend_from_slice(parent_randomness.as_slice());
+        preimage.extend_from_slice(&l1_hash);
+        preimage.extend_from_slice(&block_number.to_be_bytes());
+        keccak256(preimage)
+    }
+
+    /// Storage slot the randomness of the L2 block is written to
+    pub(crate) fn slot(block_number: u64) -> U256 {
+        U256::from(block_number % Self::HISTORY)
+    }
+
+    /// Return input data to query the randomness of one of the recent blocks
+    pub fn get_randomness(block_number: u64) -> Bytes {
+        RandomnessBeaconContract::getRandomnessCall {
+            _blockNumber: U256::from(block_number),
+        }
+        .abi_encode()
+        .into()
+    }
+}
+
+sol! {
+    /// Call signed by its sender, to be executed by the trusted forwarder
+    #[allow(missing_docs)]
+    struct ForwardRequest {
+        address from;
+        address to;
+        uint256 value;
+        uint256 gas;
+        uint256 nonce;
+        uint256 deadline;
+        bytes data;
+    }
+
+    #[allow(missing_docs)]
+    interface TrustedForwarderContract {
+        function execute(ForwardRequest calldata _request, bytes calldata _signature) external payable returns (bool success, bytes memory returndata);
+        function nonces(address from) external view returns (uint256);
+    }
+}
+
+/// TrustedForwarder wrapper.
+pub struct TrustedForwarder {}
+
+impl TrustedForwarder {
+    /// Return the address of the TrustedForwarder contract.
+    pub fn address() -> Address {
+        address!("3100000000000000000000000000000000000007")
+    }
+
+    /// EIP-712 hash of the request, which its sender signs
+    pub fn signing_hash(request: &ForwardRequest, chain_id: u64) -> B256 {
+        let domain = eip712_domain! {
+            name: "CitreaForwarder",
+            version: "1",
+            chain_id: chain_id,
+            verifying_contract: Self::address(),
+        };
+        request.eip712_signing_hash(&domain)
+    }
+
+    /// Return input data to execute the request on behalf of its sender.
+    pub fn execute(request: ForwardRequest, signature: Bytes) -> Bytes {
+        TrustedForwarderContract::executeCall {
+            _request: request,
+            _signature: signature,
+        }
+        .abi_encode()
+        .into()
+    }
+
+    /// Return input data to query the nonce of the next request of a sender.
+    pub fn nonces(from: Address) -> Bytes {
+        TrustedForwarderContract::noncesCall { from }
+            .abi_encode()
+            .into()
+    }
 }
 
 sol! {
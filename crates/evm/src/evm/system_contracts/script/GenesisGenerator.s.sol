@@ -15,6 +15,7 @@ import "../src/Bridge.sol";
 import "../src/BaseFeeVault.sol";
 import "../src/L1FeeVault.sol";
 import "../src/PriorityFeeVault.sol";
+import "../src/TrustedForwarder.sol";
 import {VmSafe} from "forge-std/Vm.sol";
 
 // Taken from Optimism
@@ -130,6 +131,7 @@ contract GenesisGenerator is Script {
         deployContract(address(new BaseFeeVault()), 3);
         deployContract(address(new L1FeeVault()), 4);
         deployContract(address(new PriorityFeeVault()), 5);
+        deployContract(address(new TrustedForwarder()), 7);
     }
 
     function deployContract(address initImpl, uint160 index) internal {
@@ -0,0 +1,20 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.26;
+
+/// @title Randomness beacon of the recent L2 blocks
+/// @author Citrea
+/// @notice Randomness of an L2 block mixes the Bitcoin block hashes seen by the rollup, and is the `PREVRANDAO` of the block.
+/// It is written into the storage of this contract by the system at the beginning of every block,
+/// so randomness of the last 256 blocks can be read. The sequencer can bias it by choosing the L1 block of an L2 block.
+
+contract RandomnessBeacon {
+    /// @dev Randomness of block `n` is stored at slot `n % 256`, written by the system without a transaction
+    bytes32[256] private randomness;
+
+    /// @notice Returns the randomness of one of the last 256 blocks, including the current one
+    /// @param _blockNumber Number of the L2 block
+    function getRandomness(uint256 _blockNumber) external view returns (bytes32) {
+        require(_blockNumber <= block.number && block.number - _blockNumber < 256, "Block is not recent");
+        return randomness[_blockNumber % 256];
+    }
+}
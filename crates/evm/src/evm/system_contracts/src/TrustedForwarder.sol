@@ -0,0 +1,107 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.26;
+
+/// @title Trusted forwarder of meta-transactions
+/// @author Citrea
+/// @notice Executes calls signed with EIP-712 by their sender and submitted by a relayer paying their fees,
+/// following EIP-2771: the sender is appended to the calldata of the call, for recipients trusting this forwarder.
+/// It is a predeploy, so the EIP-712 domain separator is computed from the chain ID and address at every call.
+
+contract TrustedForwarder {
+    struct ForwardRequest {
+        address from;
+        address to;
+        uint256 value;
+        uint256 gas;
+        uint256 nonce;
+        uint256 deadline;
+        bytes data;
+    }
+
+    bytes32 private constant DOMAIN_TYPEHASH =
+        keccak256("EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)");
+    bytes32 private constant FORWARD_REQUEST_TYPEHASH = keccak256(
+        "ForwardRequest(address from,address to,uint256 value,uint256 gas,uint256 nonce,uint256 deadline,bytes data)"
+    );
+    /// @dev Upper half of the secp256k1 curve order, signatures with a higher `s` are malleable
+    uint256 private constant SECP256K1N_HALF = 0x7fffffffffffffffffffffffffffffff5d576e7357a4501ddfe92f46681b20a0;
+
+    mapping(address => uint256) public nonces;
+
+    event Executed(address indexed from, uint256 nonce, bool success);
+
+    /// @notice EIP-712 domain separator of the requests
+    function domainSeparator() public view returns (bytes32) {
+        return keccak256(
+            abi.encode(DOMAIN_TYPEHASH, keccak256("CitreaForwarder"), keccak256("1"), block.chainid, address(this))
+        );
+    }
+
+    /// @notice Whether the request is signed by its sender and can be executed now
+    /// @param _request Request to execute
+    /// @param _signature 65 bytes signature of the EIP-712 hash of the request by its sender
+    function verify(ForwardRequest calldata _request, bytes calldata _signature) public view returns (bool) {
+        return _request.deadline >= block.timestamp && _request.nonce == nonces[_request.from]
+            && _recoverSigner(_hashRequest(_request), _signature) == _request.from;
+    }
+
+    /// @notice Executes the call of the request on behalf of its sender
+    /// @param _request Request to execute
+    /// @param _signature 65 bytes signature of the EIP-712 hash of the request by its sender
+    /// @return success Whether the call succeeded
+    /// @return returndata Data returned by the call
+    function execute(ForwardRequest calldata _request, bytes calldata _signature)
+        external
+        payable
+        returns (bool success, bytes memory returndata)
+    {
+        require(msg.value == _request.value, "Value mismatch");
+        require(_request.deadline >= block.timestamp, "Request expired");
+        require(_request.nonce == nonces[_request.from], "Invalid nonce");
+        require(_recoverSigner(_hashRequest(_request), _signature) == _request.from, "Invalid signature");
+
+        nonces[_request.from] = _request.nonce + 1;
+        (success, returndata) =
+            _request.to.call{gas: _request.gas, value: _request.value}(abi.encodePacked(_request.data, _request.from));
+
+        // The relayer must not be able to make the call fail by giving it less gas than requested, see EIP-150
+        if (gasleft() <= _request.gas / 63) {
+            assembly {
+                invalid()
+            }
+        }
+        emit Executed(_request.from, _request.nonce, success);
+    }
+
+    function _hashRequest(ForwardRequest calldata _request) internal view returns (bytes32) {
+        bytes32 structHash = keccak256(
+            abi.encode(
+                FORWARD_REQUEST_TYPEHASH,
+                _request.from,
+                _request.to,
+                _request.value,
+                _request.gas,
+                _request.nonce,
+                _request.deadline,
+                keccak256(_request.data)
+            )
+        );
+        return keccak256(abi.encodePacked("\x19\x01", domainSeparator(), structHash));
+    }
+
+    function _recoverSigner(bytes32 _hash, bytes calldata _signature) internal pure returns (address) {
+        if (_signature.length != 65) {
+            return address(0);
+        }
+        bytes32 r = bytes32(_signature[0:32]);
+        bytes32 s = bytes32(_signature[32:64]);
+        uint8 v = uint8(_signature[64]);
+        if (v < 27) {
+            v += 27;
+        }
+        if (uint256(s) > SECP256K1N_HALF || (v != 27 && v != 28)) {
+            return address(0);
+        }
+        return ecrecover(_hash, v, r, s);
+    }
+}
@@ -0,0 +1,34 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.13;
+
+import "forge-std/Test.sol";
+import "../src/RandomnessBeacon.sol";
+
+contract RandomnessBeaconTest is Test {
+    RandomnessBeacon beacon = RandomnessBeacon(address(0x3100000000000000000000000000000000000006));
+
+    function setUp() public {
+        vm.etch(address(beacon), address(new RandomnessBeacon()).code);
+    }
+
+    function testGetRandomness() public {
+        vm.roll(300);
+        // Written by the system at the beginning of the block
+        vm.store(address(beacon), bytes32(uint256(300 % 256)), keccak256("current"));
+        vm.store(address(beacon), bytes32(uint256(45 % 256)), keccak256("oldest"));
+        assertEq(beacon.getRandomness(300), keccak256("current"));
+        assertEq(beacon.getRandomness(45), keccak256("oldest"));
+    }
+
+    function testCannotGetRandomnessOfOldBlock() public {
+        vm.roll(300);
+        vm.expectRevert("Block is not recent");
+        beacon.getRandomness(44);
+    }
+
+    function testCannotGetRandomnessOfFutureBlock() public {
+        vm.roll(300);
+        vm.expectRevert("Block is not recent");
+        beacon.getRandomness(301);
+    }
+}
@@ -0,0 +1,101 @@
+// SPDX-License-Identifier: UNLICENSED
+pragma solidity ^0.8.13;
+
+import "forge-std/Test.sol";
+import "../src/TrustedForwarder.sol";
+
+contract Recipient {
+    address public lastSender;
+
+    function setSender() external {
+        lastSender = _msgSender();
+    }
+
+    function _msgSender() internal view returns (address sender) {
+        sender = address(bytes20(msg.data[msg.data.length - 20:]));
+    }
+}
+
+contract TrustedForwarderTest is Test {
+    TrustedForwarder forwarder = TrustedForwarder(address(0x3100000000000000000000000000000000000007));
+    Recipient recipient;
+    uint256 signerKey = 0x1234;
+    address signer = vm.addr(signerKey);
+
+    bytes32 constant FORWARD_REQUEST_TYPEHASH = keccak256(
+        "ForwardRequest(address from,address to,uint256 value,uint256 gas,uint256 nonce,uint256 deadline,bytes data)"
+    );
+
+    function setUp() public {
+        vm.etch(address(forwarder), address(new TrustedForwarder()).code);
+        recipient = new Recipient();
+    }
+
+    function _request(uint256 nonce) internal view returns (TrustedForwarder.ForwardRequest memory) {
+        return TrustedForwarder.ForwardRequest({
+            from: signer,
+            to: address(recipient),
+            value: 0,
+            gas: 100000,
+            nonce: nonce,
+            deadline: block.timestamp + 60,
+            data: abi.encodeCall(Recipient.setSender, ())
+        });
+    }
+
+    function _sign(TrustedForwarder.ForwardRequest memory request, uint256 key) internal view returns (bytes memory) {
+        bytes32 structHash = keccak256(
+            abi.encode(
+                FORWARD_REQUEST_TYPEHASH,
+                request.from,
+                request.to,
+                request.value,
+                request.gas,
+                request.nonce,
+                request.deadline,
+                keccak256(request.data)
+            )
+        );
+        bytes32 digest = keccak256(abi.encodePacked("\x19\x01", forwarder.domainSeparator(), structHash));
+        (uint8 v, bytes32 r, bytes32 s) = vm.sign(key, digest);
+        return abi.encodePacked(r, s, v);
+    }
+
+    function testExecute() public {
+        TrustedForwarder.ForwardRequest memory request = _request(0);
+        bytes memory signature = _sign(request, signerKey);
+        assertTrue(forwarder.verify(request, signature));
+
+        (bool success,) = forwarder.execute(request, signature);
+        assertTrue(success);
+        assertEq(recipient.lastSender(), signer);
+        assertEq(forwarder.nonces(signer), 1);
+    }
+
+    function testCannotReplay() public {
+        TrustedForwarder.ForwardRequest memory request = _request(0);
+        bytes memory signature = _sign(request, signerKey);
+        forwarder.execute(request, signature);
+
+        vm.expectRevert("Invalid nonce");
+        forwarder.execute(request, signature);
+    }
+
+    function testCannotExecuteWithWrongSigner() public {
+        TrustedForwarder.ForwardRequest memory request = _request(0);
+        bytes memory signature = _sign(request, 0x5678);
+        assertFalse(forwarder.verify(request, signature));
+
+        vm.expectRevert("Invalid signature");
+        forwarder.execute(request, signature);
+    }
+
+    function testCannotExecuteExpiredRequest() public {
+        TrustedForwarder.ForwardRequest memory request = _request(0);
+        bytes memory signature = _sign(request, signerKey);
+        vm.warp(request.deadline + 1);
+
+        vm.expectRevert("Request expired");
+        forwarder.execute(request, signature);
+    }
+}
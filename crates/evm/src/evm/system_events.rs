@@ -3,7 +3,7 @@ use reth_primitives::{
     TransactionSignedNoHash, TxEip1559, TxKind, U256,
 };
 
-use super::system_contracts::{BitcoinLightClient, Bridge};
+use super::system_contracts::{BitcoinLightClient, Bridge, FeeVault};
 
 /// This is a special signature to force tx.signer to be set to SYSTEM_SIGNER
 pub const SYSTEM_SIGNATURE: Signature = Signature {
@@ -23,6 +23,7 @@ pub(crate) enum SystemEvent {
     BitcoinLightClientSetBlockInfo(/*hash*/ [u8; 32], /*merkle root*/ [u8; 32]),
     BridgeInitialize,
     BridgeDeposit(Vec<u8>), // version, flag, vin, vout, witness, locktime, intermediate nodes, block height, index
+    FeeVaultWithdraw(/*vault*/ Address),
 }
 
 fn system_event_to_transaction(event: SystemEvent, nonce: u64, chain_id: u64) -> Transaction {
@@ -67,6 +68,16 @@ fn system_event_to_transaction(event: SystemEvent, nonce: u64, chain_id: u64) ->
             max_fee_per_gas: u64::MAX as u128,
             ..Default::default()
         },
+        SystemEvent::FeeVaultWithdraw(vault) => TxEip1559 {
+            to: TxKind::Call(vault),
+            input: FeeVault::withdraw(),
+            nonce,
+            chain_id,
+            value: U256::ZERO,
+            gas_limit: 1_000_000u64,
+            max_fee_per_gas: u64::MAX as u128,
+            ..Default::default()
+        },
     };
     Transaction::Eip1559(body)
 }
@@ -10,7 +10,7 @@ use sov_modules_api::WorkingSet;
 
 use crate::evm::db_init::InitEvmDb;
 use crate::evm::primitive_types::Block;
-use crate::evm::{AccountInfo, EvmChainConfig};
+use crate::evm::{AccountInfo, EvmChainConfig, StorageRentConfig};
 #[cfg(all(test, feature = "native"))]
 use crate::tests::DEFAULT_CHAIN_ID;
 use crate::Evm;
@@ -133,6 +133,13 @@ pub struct EvmConfig {
     pub nonce: u64,
     /// Difficulty of the genesis block.
     pub difficulty: U256,
+    /// Enables the experimental storage rent accounting, off by default.
+    #[serde(default)]
+    pub storage_rent: Option<StorageRentConfig>,
+    /// L2 block from which deploying code that is already stored only adds the code hash
+    /// to the L1 diff size. Every deployment is charged for its code if it is missing.
+    #[serde(default)]
+    pub shared_code_height: Option<u64>,
 }
 
 #[cfg(all(test, feature = "native"))]
@@ -151,6 +158,8 @@ impl Default for EvmConfig {
             extra_data: Bytes::default(),
             nonce: 0,
             difficulty: U256::ZERO,
+            storage_rent: None,
+            shared_code_height: None,
         }
     }
 }
@@ -216,6 +225,15 @@ impl<C: sov_modules_api::Context> Evm<C> {
 
         self.cfg.set(&chain_cfg, working_set);
 
+        if let Some(storage_rent) = &config.storage_rent {
+            self.storage_rent.set(storage_rent, working_set);
+        }
+
+        if let Some(shared_code_height) = config.shared_code_height {
+            self.shared_code_height
+                .set(&shared_code_height, working_set);
+        }
+
         let header = reth_primitives::Header {
             parent_hash: B256::default(),
             ommers_hash: EMPTY_OMMER_ROOT_HASH,
@@ -255,6 +273,9 @@ impl<C: sov_modules_api::Context> Evm<C> {
         self.head.set(&block, working_set);
         self.pending_head
             .set(&block, &mut working_set.accessory_state());
+        // Genesis accounts were added to the filter as they were inserted
+        self.account_filter_complete
+            .set(&true, &mut working_set.accessory_state());
 
         Ok(())
     }
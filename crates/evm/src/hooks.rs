@@ -1,5 +1,5 @@
 use alloy_primitives::B256;
-use reth_primitives::{Bloom, Bytes, U256};
+use reth_primitives::{Address, Bloom, Bytes, KECCAK_EMPTY, U256};
 use sov_modules_api::hooks::HookSoftConfirmationInfo;
 use sov_modules_api::prelude::*;
 use sov_modules_api::{AccessoryWorkingSet, Spec, WorkingSet};
@@ -7,9 +7,16 @@ use sov_state::Storage;
 #[cfg(feature = "native")]
 use tracing::instrument;
 
+use crate::evm::compact_receipts::BlockReceipts;
 use crate::evm::primitive_types::{Block, BlockEnv};
+use crate::evm::system_contracts::{Bridge, FeeVault, RandomnessBeacon};
 use crate::evm::system_events::SystemEvent;
-use crate::{Evm, PendingTransaction};
+use crate::evm::DbAccount;
+use crate::{Evm, PendingTransaction, FEE_VAULTS, FEE_VAULT_SWEEP_INTERVAL, RANDOMNESS_BEACON};
+
+/// Number of blocks whose receipts are moved from the legacy `receipts` to `block_receipts`
+/// after each L2 block, so upgraded nodes migrate their receipts without a long pause.
+const RECEIPT_BACKFILL_BLOCKS_PER_L2_BLOCK: usize = 10;
 
 impl<C: sov_modules_api::Context> Evm<C>
 where
@@ -72,15 +79,33 @@ where
                 system_events.push(SystemEvent::BridgeDeposit(params.clone()));
             });
 
+        system_events.extend(self.fee_vault_sweeps(parent_block.header.number + 1, working_set));
+
         let cfg = self
             .cfg
             .get(working_set)
             .expect("EVM chain config should be set");
+        let block_number = parent_block.header.number + 1;
+        // The randomness beacon is activated by deploying its code with the genesis or a system tx,
+        // before that the Bitcoin block hash is used as is. Transfers to it don't activate it
+        let prevrandao = match self.accounts.get(&RANDOMNESS_BEACON, working_set) {
+            Some(beacon) if beacon.info.code_hash != KECCAK_EMPTY => {
+                let randomness = RandomnessBeacon::randomness(
+                    parent_block.header.mix_hash,
+                    soft_confirmation_info.da_slot_hash,
+                    block_number,
+                );
+                Self::record_randomness(&beacon, block_number, randomness, working_set);
+                randomness
+            }
+            _ => soft_confirmation_info.da_slot_hash.into(),
+        };
+
         let new_pending_env = BlockEnv {
-            number: parent_block.header.number + 1,
+            number: block_number,
             coinbase: cfg.coinbase,
             timestamp: soft_confirmation_info.timestamp,
-            prevrandao: soft_confirmation_info.da_slot_hash.into(),
+            prevrandao,
             basefee: parent_block
                 .header
                 .next_block_base_fee(cfg.base_fee_params)
@@ -110,6 +135,86 @@ where
             .set(&soft_confirmation_info.da_slot_hash.into(), working_set);
     }
 
+    /// Writes the randomness of the L2 block into the storage of the randomness beacon,
+    /// overwriting the randomness of the block [`RandomnessBeacon::HISTORY`] blocks before.
+    fn record_randomness(
+        beacon: &DbAccount,
+        block_number: u64,
+        randomness: B256,
+        working_set: &mut WorkingSet<C>,
+    ) {
+        let slot = RandomnessBeacon::slot(block_number);
+        if beacon.storage.get(&slot, working_set).is_none() {
+            beacon.keys.push(&slot, working_set);
+        }
+        beacon
+            .storage
+            .set(&slot, &U256::from_be_bytes(randomness.0), working_set);
+    }
+
+    /// Returns the number of the pending block.
+    pub fn pending_block_number(&self, working_set: &mut WorkingSet<C>) -> u64 {
+        self.block_env
+            .get(working_set)
+            .expect("Pending block should always be set")
+            .number
+    }
+
+    /// Sets the beneficiary of the priority fees of the pending block,
+    /// which is the coinbase of the chain config unless a sequencer is registered on chain.
+    pub fn set_pending_coinbase(&self, coinbase: Address, working_set: &mut WorkingSet<C>) {
+        let mut block_env = self
+            .block_env
+            .get(working_set)
+            .expect("Pending block should always be set");
+        block_env.coinbase = coinbase;
+        self.block_env.set(&block_env, working_set);
+    }
+
+    /// Returns the withdrawals initiated in the pending block, as `(index, bitcoin_address)` pairs
+    /// of the `Withdrawal` events of the Bridge contract.
+    /// Must be called before [`Self::end_soft_confirmation_hook`] clears the pending transactions.
+    pub fn pending_withdrawals(&self, working_set: &mut WorkingSet<C>) -> Vec<(u64, [u8; 32])> {
+        self.pending_transactions
+            .iter(working_set)
+            .flat_map(|tx| tx.receipt.receipt.logs)
+            .filter_map(|log| Bridge::decode_withdrawal(&log))
+            .collect()
+    }
+
+    /// Withdrawals of the fee vaults to their recipients at the L2 block.
+    /// Vaults are swept every [`FEE_VAULT_SWEEP_INTERVAL`] blocks, if they have a recipient
+    /// and hold at least their minimum withdraw amount, so the withdrawals do not revert.
+    pub(crate) fn fee_vault_sweeps(
+        &self,
+        block_number: u64,
+        working_set: &mut WorkingSet<C>,
+    ) -> Vec<SystemEvent> {
+        if block_number % FEE_VAULT_SWEEP_INTERVAL != 0 {
+            return vec![];
+        }
+
+        FEE_VAULTS
+            .into_iter()
+            .filter(|vault| {
+                let Some(account) = self.accounts.get(vault, working_set) else {
+                    return false;
+                };
+                let recipient = account
+                    .storage
+                    .get(&FeeVault::RECIPIENT_SLOT, working_set)
+                    .unwrap_or_default();
+                let min_withdraw = account
+                    .storage
+                    .get(&FeeVault::MIN_WITHDRAW_SLOT, working_set)
+                    .unwrap_or_default();
+                let balance = account.info.balance;
+                !recipient.is_zero() && !balance.is_zero() && balance >= min_withdraw
+            })
+            .map(SystemEvent::FeeVaultWithdraw)
+            .collect()
+    }
+
     /// Logic executed at the end of the slot. Here, we generate an authenticated block and set it as the new head of the chain.
     /// It's important to note that the state root hash is not known at this moment, so we postpone setting this field until the begin_slot_hook of the next slot.
     #[cfg_attr(feature = "native", instrument(level = "trace", skip_all, ret))]
@@ -213,13 +318,8 @@ where
         self.pending_head.set(&block, &mut accessory_state);
 
         let mut tx_index = start_tx_index;
-        for PendingTransaction {
-            transaction,
-            receipt,
-        } in &pending_transactions
-        {
+        for PendingTransaction { transaction, .. } in &pending_transactions {
             self.transactions.push(transaction, &mut accessory_state);
-            self.receipts.push(receipt, &mut accessory_state);
 
             self.transaction_hashes.set(
                 &transaction.signed_transaction.hash,
@@ -229,6 +329,13 @@ where
 
             tx_index += 1
         }
+
+        if !pending_transactions.is_empty() {
+            let block_receipts =
+                BlockReceipts::new(pending_transactions.iter().map(|tx| &tx.receipt));
+            self.block_receipts
+                .set(&block.header.number, &block_receipts, &mut accessory_state);
+        }
     }
 
     /// This logic is executed after calculating the root hash.
@@ -278,6 +385,48 @@ where
             self.pending_head.delete(accessory_working_set);
 
             self.l1_fee_failed_txs.clear(accessory_working_set);
+
+            self.backfill_block_receipts(accessory_working_set);
+        }
+    }
+
+    /// Moves the receipts of the latest blocks left in the legacy `receipts` to `block_receipts`,
+    /// at most `RECEIPT_BACKFILL_BLOCKS_PER_L2_BLOCK` blocks at a time.
+    /// Receipts are popped from the end, so the indexes of the ones left stay valid for the RPC.
+    fn backfill_block_receipts(&self, accessory_working_set: &mut AccessoryWorkingSet<C>) {
+        for _ in 0..RECEIPT_BACKFILL_BLOCKS_PER_L2_BLOCK {
+            let legacy_len = self.receipts.len(accessory_working_set);
+            let Some(last_tx_index) = legacy_len.checked_sub(1) else {
+                return;
+            };
+
+            let block_number = self
+                .transactions
+                .get(last_tx_index, accessory_working_set)
+                .expect("Transaction of a receipt must be set")
+                .block_number;
+            let first_tx_index = self
+                .blocks
+                .get(block_number as usize, accessory_working_set)
+                .expect("Block of a transaction must be set")
+                .transactions
+                .start as usize;
+
+            let mut receipts = Vec::with_capacity(legacy_len - first_tx_index);
+            for _ in first_tx_index..legacy_len {
+                receipts.push(
+                    self.receipts
+                        .pop(accessory_working_set)
+                        .expect("Receipt of a transaction must be set"),
+                );
+            }
+            receipts.reverse();
+
+            self.block_receipts.set(
+                &block_number,
+                &BlockReceipts::new(&receipts),
+                accessory_working_set,
+            );
         }
     }
 }
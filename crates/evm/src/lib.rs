@@ -36,12 +36,17 @@ use evm::DbAccount;
 use reth_primitives::{Address, TxHash, B256};
 pub use revm::primitives::SpecId;
 use revm::primitives::U256;
-use sov_modules_api::{AccessoryWorkingSet, Error, ModuleInfo, StateVecAccessor, WorkingSet};
+use sov_modules_api::{
+    AccessoryWorkingSet, Error, ModuleInfo, StateValueAccessor, StateVecAccessor, WorkingSet,
+};
 use sov_state::codec::BcsCodec;
 
+use crate::evm::account_filter::AccountFilter;
+use crate::evm::compact_receipts::BlockReceipts;
 use crate::evm::primitive_types::{
     Block, BlockEnv, Receipt, SealedBlock, TransactionSignedAndRecovered,
 };
+use crate::evm::storage_rent::StorageRent;
 use crate::evm::system_events::SystemEvent;
 pub use crate::EvmConfig;
 
@@ -62,6 +67,11 @@ impl PendingTransaction {
     pub fn cumulative_gas_used(&self) -> u64 {
         self.receipt.receipt.cumulative_gas_used
     }
+
+    /// Returns the estimated size of the state diff this transaction produced
+    pub fn l1_diff_size(&self) -> u64 {
+        self.receipt.l1_diff_size
+    }
 }
 
 /// The citrea-evm module provides compatibility with the EVM.
@@ -130,6 +140,25 @@ pub struct Evm<C: sov_modules_api::Context> {
     #[state]
     pub(crate) blocks: sov_modules_api::AccessoryStateVec<SealedBlock, BcsCodec>,
 
+    /// Parameters of the experimental storage rent accounting.
+    /// Set in genesis, storage rent is not accounted if it is missing.
+    #[state]
+    pub(crate) storage_rent: sov_modules_api::StateValue<StorageRentConfig, BcsCodec>,
+
+    /// Storage rent of the accounts, only used if `storage_rent` is set.
+    #[state]
+    pub(crate) storage_rent_accounts:
+        sov_modules_api::StateMap<Address, StorageRentAccount, BcsCodec>,
+
+    /// Rent paid from the storage rent deposits, which is not moved to the base fee vault yet.
+    #[state]
+    pub(crate) storage_rent_collected: sov_modules_api::StateValue<U256, BcsCodec>,
+
+    /// L2 block from which clones of stored code are charged only for their code hash.
+    /// Set in genesis, every deployment is charged for its code if it is missing.
+    #[state]
+    pub(crate) shared_code_height: sov_modules_api::StateValue<u64, BcsCodec>,
+
     /// Used only by the RPC: block_hash => block_number mapping,
     #[state]
     pub(crate) block_hashes:
@@ -145,9 +174,23 @@ pub struct Evm<C: sov_modules_api::Context> {
     pub(crate) transaction_hashes:
         sov_modules_api::AccessoryStateMap<reth_primitives::B256, u64, BcsCodec>,
 
-    /// Used only by the RPC: Receipts.
+    /// Used only by the RPC: Receipts stored before `block_receipts`, indexed by transaction.
+    /// They are moved to `block_receipts` a few blocks at a time, starting from the latest ones.
     #[state]
     pub(crate) receipts: sov_modules_api::AccessoryStateVec<Receipt, BcsCodec>,
+
+    /// Used only by the RPC: block_number => receipts of the transactions of the block.
+    /// Blocks without transactions have no entry.
+    #[state]
+    pub(crate) block_receipts: sov_modules_api::AccessoryStateMap<u64, BlockReceipts, BcsCodec>,
+
+    /// Used only by the RPC: Words of the bloom filter of the existing accounts.
+    #[state]
+    pub(crate) account_filter: sov_modules_api::AccessoryStateMap<u32, u64, BcsCodec>,
+
+    /// Used only by the RPC: Set in genesis, the account filter has all accounts only if it is set.
+    #[state]
+    pub(crate) account_filter_complete: sov_modules_api::AccessoryStateValue<bool, BcsCodec>,
 }
 
 impl<C: sov_modules_api::Context> sov_modules_api::Module for Evm<C> {
@@ -174,15 +217,54 @@ impl<C: sov_modules_api::Context> sov_modules_api::Module for Evm<C> {
 }
 
 impl<C: sov_modules_api::Context> Evm<C> {
+    /// Whether deploying code that is already stored only adds the code hash to the L1 diff size
+    /// in the given L2 block.
+    pub(crate) fn shares_code(&self, block_number: u64, working_set: &mut WorkingSet<C>) -> bool {
+        self.shared_code_height
+            .get(working_set)
+            .is_some_and(|height| block_number >= height)
+    }
+
     pub(crate) fn get_db<'a>(&self, working_set: &'a mut WorkingSet<C>) -> EvmDb<'a, C> {
+        let storage_rent = self
+            .storage_rent
+            .get(working_set)
+            .map(|config| StorageRent {
+                config,
+                accounts: self.storage_rent_accounts.clone(),
+                collected: self.storage_rent_collected.clone(),
+                block_number: self
+                    .block_env
+                    .get(working_set)
+                    .map(|block_env| block_env.number)
+                    .unwrap_or_default(),
+            });
         EvmDb::new(
             self.accounts.clone(),
             self.code.clone(),
             self.latest_block_hashes.clone(),
+            storage_rent,
+            AccountFilter {
+                words: self.account_filter.clone(),
+                skip_missing: false,
+            },
             working_set,
         )
     }
 
+    /// Returns the EVM database for RPC queries, which skips the lookups of
+    /// accounts missing from the account filter.
+    #[cfg(feature = "native")]
+    pub(crate) fn get_query_db<'a>(&self, working_set: &'a mut WorkingSet<C>) -> EvmDb<'a, C> {
+        let filter_complete = self
+            .account_filter_complete
+            .get(&mut working_set.accessory_state())
+            .unwrap_or(false);
+        let mut evm_db = self.get_db(working_set);
+        evm_db.account_filter.skip_missing = filter_complete;
+        evm_db
+    }
+
     /// Returns transaction hashes that failed to pay the L1 fee.
     pub fn get_l1_fee_failed_txs(
         &self,
@@ -4,6 +4,7 @@ use std::ops::{Range, RangeInclusive};
 use alloy_primitives::Uint;
 use alloy_rlp::Encodable;
 use jsonrpsee::core::RpcResult;
+use jsonrpsee::types::ErrorObjectOwned;
 use reth_interfaces::provider::ProviderError;
 use reth_primitives::constants::GWEI_TO_WEI;
 use reth_primitives::revm::env::tx_env_with_recovered;
@@ -30,16 +31,23 @@ use serde::{Deserialize, Serialize};
 use sov_modules_api::macros::rpc_gen;
 use sov_modules_api::prelude::*;
 use sov_modules_api::WorkingSet;
+use sov_rollup_interface::rpc::deadline::{check_deadline, RPC_TIMEOUT_ERROR_CODE};
 use tracing::debug;
 
 use crate::call::get_cfg_env;
-use crate::error::rpc::{ensure_success, RpcInvalidTransactionErrorExt};
+use crate::error::rpc::{ensure_success, evm_error_to_rpc, RpcInvalidTransactionErrorExt};
 use crate::evm::call::prepare_call_env;
+use crate::evm::compact_receipts::BlockReceipts;
 use crate::evm::db::EvmDb;
+use crate::evm::error::result::rpc_error_with_code;
 use crate::evm::primitive_types::{BlockEnv, Receipt, SealedBlock, TransactionSignedAndRecovered};
 use crate::handler::TxInfo;
 use crate::rpc_helpers::*;
-use crate::{BloomFilter, Evm, EvmChainConfig, FilterBlockOption, FilterError};
+use crate::system_contracts::{Bridge, BridgeEvent};
+use crate::{
+    BloomFilter, CitreaError, Evm, EvmChainConfig, FilterBlockOption, FilterError,
+    StorageRentAccount,
+};
 
 /// Gas per transaction not creating a contract.
 pub const MIN_TRANSACTION_GAS: u64 = 21_000u64;
@@ -265,25 +273,22 @@ impl<C: sov_modules_api::Context> Evm<C> {
             }
         };
 
-        let receipts = &block
-            .transactions
-            .clone()
-            .map(|id| {
+        let receipts = self
+            .stored_block_receipts(&block, working_set)
+            .receipts()
+            .into_iter()
+            .zip(block.transactions.clone())
+            .map(|(receipt, id)| {
                 let tx = self
                     .transactions
                     .get(id as usize, &mut working_set.accessory_state())
                     .expect("Transaction must be set");
 
-                let receipt = self
-                    .receipts
-                    .get(id as usize, &mut working_set.accessory_state())
-                    .expect("Receipt for known transaction must be set");
-
                 build_rpc_receipt(&block, tx, id, receipt)
             })
             .collect::<Vec<_>>();
 
-        Ok(Some(receipts.clone()))
+        Ok(Some(receipts))
     }
 
     /// Handler for: `eth_getBalance`
@@ -309,11 +314,13 @@ impl<C: sov_modules_api::Context> Evm<C> {
                 if num > curr_block_number {
                     return Err(EthApiError::UnknownBlockNumber.into());
                 }
+                ensure_state_available(num, working_set)?;
                 set_state_to_end_of_evm_block(num, working_set);
             }
             // Working state here is already at the latest state, so no need to anything
             Some(BlockNumberOrTag::Latest) | Some(BlockNumberOrTag::Pending) | None => {}
             Some(BlockNumberOrTag::Earliest) => {
+                ensure_state_available(0, working_set)?;
                 set_state_to_end_of_evm_block(0, working_set);
             }
             _ => {
@@ -357,11 +364,13 @@ impl<C: sov_modules_api::Context> Evm<C> {
                 if num > curr_block_number {
                     return Err(EthApiError::UnknownBlockNumber.into());
                 }
+                ensure_state_available(num, working_set)?;
                 set_state_to_end_of_evm_block(num, working_set);
             }
             // Working state here is already at the latest state, so no need to anything
             Some(BlockNumberOrTag::Latest) | Some(BlockNumberOrTag::Pending) | None => {}
             Some(BlockNumberOrTag::Earliest) => {
+                ensure_state_available(0, working_set)?;
                 set_state_to_end_of_evm_block(0, working_set);
             }
             _ => {
@@ -404,11 +413,13 @@ impl<C: sov_modules_api::Context> Evm<C> {
                 if num > curr_block_number {
                     return Err(EthApiError::UnknownBlockNumber.into());
                 }
+                ensure_state_available(num, working_set)?;
                 set_state_to_end_of_evm_block(num, working_set);
             }
             // Working state here is already at the latest state, so no need to anything
             Some(BlockNumberOrTag::Latest) | Some(BlockNumberOrTag::Pending) | None => {}
             Some(BlockNumberOrTag::Earliest) => {
+                ensure_state_available(0, working_set)?;
                 set_state_to_end_of_evm_block(0, working_set);
             }
             _ => {
@@ -450,11 +461,13 @@ impl<C: sov_modules_api::Context> Evm<C> {
                 if num > curr_block_number {
                     return Err(EthApiError::UnknownBlockNumber.into());
                 }
+                ensure_state_available(num, working_set)?;
                 set_state_to_end_of_evm_block(num, working_set);
             }
             // Working state here is already at the latest state, so no need to anything
             Some(BlockNumberOrTag::Latest) | Some(BlockNumberOrTag::Pending) | None => {}
             Some(BlockNumberOrTag::Earliest) => {
+                ensure_state_available(0, working_set)?;
                 set_state_to_end_of_evm_block(0, working_set);
             }
             // Is this the way?
@@ -597,10 +610,16 @@ impl<C: sov_modules_api::Context> Evm<C> {
                 .get(tx.block_number as usize, &mut accessory_state)
                 .expect("Block number for known transaction must be set");
 
-            let receipt = self
-                .receipts
-                .get(number as usize, &mut accessory_state)
-                .expect("Receipt for known transaction must be set");
+            let receipt = match self
+                .block_receipts
+                .get(&block.header.number, &mut accessory_state)
+            {
+                Some(block_receipts) => {
+                    block_receipts.receipt((number - block.transactions.start) as usize)
+                }
+                None => self.receipts.get(number as usize, &mut accessory_state),
+            }
+            .expect("Receipt for known transaction must be set");
 
             build_rpc_receipt(&block, tx, number, receipt)
         });
@@ -661,7 +680,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
         // but still cap it to prevent DoS
         block_env.gas_limit = 100_000_000;
 
-        let mut evm_db = self.get_db(working_set);
+        let mut evm_db = self.get_query_db(working_set);
         let mut tx_env = prepare_call_env(
             &block_env,
             request.clone(),
@@ -686,7 +705,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
         ) {
             Ok(result) => result.result,
             Err(err) => {
-                return Err(EthApiError::from(err).into());
+                return Err(evm_error_to_rpc(err));
             }
         };
 
@@ -766,7 +785,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
         // but still cap it to prevent DoS
         block_env.gas_limit = 100_000_000;
 
-        let mut evm_db = self.get_db(working_set);
+        let mut evm_db = self.get_query_db(working_set);
 
         let mut tx_env = prepare_call_env(
             &block_env,
@@ -803,7 +822,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
             tx_env.clone(),
             &mut inspector,
         )
-        .map_err(EthApiError::from)?;
+        .map_err(evm_error_to_rpc)?;
 
         match result.result {
             ExecutionResult::Halt { reason, .. } => Err(match reason {
@@ -933,6 +952,35 @@ impl<C: sov_modules_api::Context> Evm<C> {
         })
     }
 
+    /// Handler for: `eth_getStorageRent`
+    /// Returns the storage rent of the account, settled up to the latest block.
+    /// None if the storage rent accounting is disabled or the account was never charged.
+    #[rpc_method(name = "eth_getStorageRent")]
+    pub fn eth_get_storage_rent(
+        &self,
+        address: reth_primitives::Address,
+        working_set: &mut WorkingSet<C>,
+    ) -> RpcResult<Option<StorageRentAccount>> {
+        debug!("evm module: eth_getStorageRent");
+        let Some(config) = self.storage_rent.get(working_set) else {
+            return Ok(None);
+        };
+        let Some(mut rent_account) = self.storage_rent_accounts.get(&address, working_set) else {
+            return Ok(None);
+        };
+
+        let curr_block_number = self
+            .blocks
+            .last(&mut working_set.accessory_state())
+            .expect("Head block must be set")
+            .header
+            .number;
+        let size = rent_account.size;
+        rent_account.settle(&config, curr_block_number, size);
+
+        Ok(Some(rent_account))
+    }
+
     /// Handler for: `eth_getBlockTransactionCountByHash`
     // https://github.com/paradigmxyz/reth/blob/main/crates/rpc/rpc/src/eth/api/call.rs#L172
     #[rpc_method(name = "eth_getBlockTransactionCountByHash")]
@@ -980,6 +1028,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
         let request_gas_price = request.gas_price;
         let env_gas_limit = block_env.gas_limit.into();
         let env_base_fee = U256::from(block_env.basefee);
+        let shares_code = self.shares_code(block_env.number, working_set);
 
         // get the highest possible gas limit, either the request's set value or the currently
         // configured gas limit
@@ -1011,11 +1060,12 @@ impl<C: sov_modules_api::Context> Evm<C> {
                     tx_env.gas_limit = MIN_TRANSACTION_GAS;
 
                     let res = inspect_no_tracing(
-                        self.get_db(working_set),
+                        self.get_query_db(working_set),
                         cfg_env.clone(),
                         block_env,
                         tx_env.clone(),
                         l1_fee_rate,
+                        shares_code,
                     );
 
                     if let Ok((res, tx_info)) = res {
@@ -1047,7 +1097,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
         let gas_limit: u64 = std::cmp::min(tx_env.gas_limit, highest_gas_limit as u64); // highest_gas_limit is capped to u64::MAX
         tx_env.gas_limit = gas_limit;
 
-        let evm_db = self.get_db(working_set);
+        let evm_db = self.get_query_db(working_set);
 
         // execute the call without writing to db
         let result = inspect_no_tracing(
@@ -1056,6 +1106,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
             block_env,
             tx_env.clone(),
             l1_fee_rate,
+            shares_code,
         );
 
         // Exceptional case: init used too much gas, we need to increase the gas limit and try
@@ -1065,15 +1116,15 @@ impl<C: sov_modules_api::Context> Evm<C> {
             // if price or limit was included in the request then we can execute the request
             // again with the block's gas limit to check if revert is gas related or not
             if request_gas.is_some() || request_gas_price.is_some() {
-                let evm_db = self.get_db(working_set);
+                let evm_db = self.get_query_db(working_set);
                 return Err(map_out_of_gas_err(
                     block_env,
                     tx_env.clone(),
                     cfg_env,
                     evm_db,
                     l1_fee_rate,
-                )
-                .into());
+                    shares_code,
+                ));
             }
         }
 
@@ -1089,22 +1140,22 @@ impl<C: sov_modules_api::Context> Evm<C> {
                     // if price or limit was included in the request then we can execute the request
                     // again with the block's gas limit to check if revert is gas related or not
                     return if request_gas.is_some() || request_gas_price.is_some() {
-                        let evm_db = self.get_db(working_set);
+                        let evm_db = self.get_query_db(working_set);
                         Err(map_out_of_gas_err(
                             block_env,
                             tx_env.clone(),
                             cfg_env,
                             evm_db,
                             l1_fee_rate,
-                        )
-                        .into())
+                            shares_code,
+                        ))
                     } else {
                         // the transaction did revert
                         Err(RpcInvalidTransactionError::Revert(RevertError::new(output)).into())
                     };
                 }
             },
-            Err(err) => return Err(EthApiError::from(err).into()),
+            Err(err) => return Err(evm_error_to_rpc(err)),
         };
 
         // at this point we know the call succeeded but want to find the _best_ (lowest) gas the
@@ -1130,15 +1181,16 @@ impl<C: sov_modules_api::Context> Evm<C> {
             tx_env.gas_limit = optimistic_gas_limit;
             // (result, env) = executor::transact(&mut db, env)?;
             let curr_result = inspect_no_tracing(
-                self.get_db(working_set),
+                self.get_query_db(working_set),
                 cfg_env.clone(),
                 block_env,
                 tx_env.clone(),
                 l1_fee_rate,
+                shares_code,
             );
             let (curr_result, tx_info) = match curr_result {
                 Ok(result) => result,
-                Err(err) => return Err(EthApiError::from(err).into()),
+                Err(err) => return Err(evm_error_to_rpc(err)),
             };
             update_estimated_gas_range(
                 curr_result.result,
@@ -1170,13 +1222,14 @@ impl<C: sov_modules_api::Context> Evm<C> {
             let mut tx_env = tx_env.clone();
             tx_env.gas_limit = mid_gas_limit;
 
-            let evm_db = self.get_db(working_set);
+            let evm_db = self.get_query_db(working_set);
             let result = inspect_no_tracing(
                 evm_db,
                 cfg_env.clone(),
                 block_env,
                 tx_env.clone(),
                 l1_fee_rate,
+                shares_code,
             );
 
             // Exceptional case: init used too much gas, we need to increase the gas limit and try
@@ -1189,7 +1242,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
             } else {
                 let (result, tx_info) = match result {
                     Ok(result) => result,
-                    Err(err) => return Err(EthApiError::from(err).into()),
+                    Err(err) => return Err(evm_error_to_rpc(err)),
                 };
 
                 update_estimated_gas_range(
@@ -1304,6 +1357,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
             .expect("EVM chain config should be set");
         let cfg_env = get_cfg_env(&block_env, cfg);
         let l1_fee_rate = sealed_block.l1_fee_rate;
+        let shares_code = self.shares_code(block_env.number, working_set);
 
         // EvmDB is the replacement of revm::CacheDB because cachedb requires immutable state
         // TODO: Move to CacheDB once immutable state is implemented
@@ -1314,6 +1368,8 @@ impl<C: sov_modules_api::Context> Evm<C> {
         let mut transactions = block_txs.into_iter().enumerate().peekable();
         let limit = stop_at.unwrap_or(usize::MAX);
         while let Some((index, tx)) = transactions.next() {
+            check_deadline()
+                .map_err(|e| rpc_error_with_code(RPC_TIMEOUT_ERROR_CODE, e.to_string()))?;
             let (trace, state_changes) = trace_transaction(
                 opts.clone().unwrap_or_default(),
                 cfg_env.clone(),
@@ -1322,6 +1378,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
                 tx.hash(),
                 &mut evm_db,
                 l1_fee_rate,
+                shares_code,
             )?;
             traces.push(trace);
 
@@ -1434,6 +1491,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
             BlockRangeInclusiveIter::new(from_block_number..=to_block_number, max_headers_range)
         {
             for idx in from..=to {
+                check_deadline()?;
                 let block = match self
                     .blocks
                     .get((idx) as usize, &mut working_set.accessory_state())
@@ -1476,43 +1534,41 @@ impl<C: sov_modules_api::Context> Evm<C> {
         filter: &Filter,
         block: SealedBlock,
     ) {
-        // tracks the index of a log in the entire block
-        let mut log_index: u32 = 0;
-
         // TODO: Understand how to handle this
         // TAG - true when the log was removed, due to a chain reorganization. false if its a valid log.
         let removed = false;
 
-        let tx_range = block.transactions;
+        let block_hash = block.header.hash();
+        let block_receipts = self.stored_block_receipts(&block, working_set);
+
+        // The index of a log in the entire block is its position in the logs of the block.
+        // Logs are matched without their data, which is only copied for the matching ones.
+        for (log_index, log) in block_receipts.logs().enumerate() {
+            if !log_matches_filter(
+                &log.to_log_without_data(),
+                filter,
+                &block_hash,
+                &block.header.number,
+            ) {
+                continue;
+            }
 
-        for i in tx_range {
-            let receipt = self
-                .receipts
-                .get(i as usize, &mut working_set.accessory_state())
-                .expect("Transaction must be set");
+            let i = block.transactions.start + log.receipt_index as u64;
             let tx = self
                 .transactions
                 .get(i as usize, &mut working_set.accessory_state())
                 .unwrap();
-            let logs = receipt.receipt.logs;
-
-            for log in logs.into_iter() {
-                if log_matches_filter(&log, filter, &block.header.hash(), &block.header.number) {
-                    let log = LogResponse {
-                        address: log.address,
-                        topics: log.topics().to_vec(),
-                        data: log.data.data.to_vec().into(),
-                        block_hash: Some(block.header.hash()),
-                        block_number: Some(U256::from(block.header.number)),
-                        transaction_hash: Some(tx.signed_transaction.hash),
-                        transaction_index: Some(U256::from(i)),
-                        log_index: Some(U256::from(log_index)),
-                        removed,
-                    };
-                    all_logs.push(log);
-                }
-                log_index += 1;
-            }
+            all_logs.push(LogResponse {
+                address: log.address,
+                topics: log.topics(),
+                data: log.data.to_vec().into(),
+                block_hash: Some(block_hash),
+                block_number: Some(U256::from(block.header.number)),
+                transaction_hash: Some(tx.signed_transaction.hash),
+                transaction_index: Some(U256::from(i)),
+                log_index: Some(U256::from(log_index as u64)),
+                removed,
+            });
         }
     }
 
@@ -1523,6 +1579,66 @@ impl<C: sov_modules_api::Context> Evm<C> {
             .expect("EVM chain config should be set")
     }
 
+    /// The block the sequencer builds next with the txs, served for the `pending` tag.
+    /// Its txs are executed once it is built, so it has no hash, and no receipts,
+    /// gas used or state root of its own yet.
+    pub fn pending_block(
+        &self,
+        txs: Vec<TransactionSignedEcRecovered>,
+        timestamp: u64,
+        details: bool,
+        working_set: &mut WorkingSet<C>,
+    ) -> reth_rpc_types::RichBlock {
+        let parent = self
+            .blocks
+            .last(&mut working_set.accessory_state())
+            .expect("Head block must be set");
+        let cfg = self.get_chain_config(working_set);
+
+        let body: Vec<_> = txs.iter().map(|tx| tx.clone().into_signed()).collect();
+        let header = reth_primitives::Header {
+            parent_hash: parent.header.hash(),
+            number: parent.header.number + 1,
+            timestamp,
+            transactions_root: reth_primitives::proofs::calculate_transaction_root(&body),
+            receipts_root: reth_primitives::constants::EMPTY_RECEIPTS,
+            logs_bloom: Default::default(),
+            gas_used: 0,
+            base_fee_per_gas: parent.header.next_block_base_fee(cfg.base_fee_params),
+            ..parent.header.header().clone()
+        };
+        let size = Block {
+            header: header.clone(),
+            body,
+            ommers: Default::default(),
+            withdrawals: Default::default(),
+        }
+        .length();
+
+        let mut header = from_primitive_with_hash(header.seal_slow());
+        header.hash = None;
+        header.total_difficulty = Some(header.difficulty);
+        let transactions = if details {
+            reth_rpc_types::BlockTransactions::Full(
+                txs.into_iter()
+                    .map(reth_rpc_types_compat::transaction::from_recovered)
+                    .collect(),
+            )
+        } else {
+            reth_rpc_types::BlockTransactions::Hashes(txs.iter().map(|tx| tx.hash()).collect())
+        };
+
+        reth_rpc_types::Block {
+            header,
+            size: Some(U256::from(size)),
+            uncles: Default::default(),
+            transactions,
+            withdrawals: Default::default(),
+            other: Default::default(),
+        }
+        .into()
+    }
+
     /// Helper function to get block hash from block number
     pub fn block_hash_from_number(
         &self,
@@ -1561,7 +1677,7 @@ impl<C: sov_modules_api::Context> Evm<C> {
     ) -> Result<u64, EthApiError> {
         match block_id {
             BlockNumberOrTag::Earliest => Ok(0),
-            BlockNumberOrTag::Latest => Ok(self
+            BlockNumberOrTag::Latest | BlockNumberOrTag::Pending => Ok(self
                 .blocks
                 .last(&mut working_set.accessory_state())
                 .map(|block| block.header.number)
@@ -1573,8 +1689,9 @@ impl<C: sov_modules_api::Context> Evm<C> {
                     Err(EthApiError::UnknownBlockNumber)
                 }
             }
+            // Safe and finalized are resolved to numbers by the RPC server
             _ => Err(EthApiError::InvalidParams(
-                "Please provide a number or earliest/latest tag".to_string(),
+                "Please provide a number or earliest/latest/pending tag".to_string(),
             )),
         }
     }
@@ -1586,7 +1703,8 @@ impl<C: sov_modules_api::Context> Evm<C> {
         block_number: Option<BlockNumberOrTag>,
         working_set: &mut WorkingSet<C>,
     ) -> Result<Option<SealedBlock>, EthApiError> {
-        // safe, finalized, and pending are not supported
+        // Safe and finalized are resolved to numbers by the RPC server, and the pending
+        // block is served by the sequencer. Others see the state it is built on, the latest one
         match block_number {
             Some(BlockNumberOrTag::Number(block_number)) => Ok(self
                 .blocks
@@ -1596,18 +1714,13 @@ impl<C: sov_modules_api::Context> Evm<C> {
                     .get(0, &mut working_set.accessory_state())
                     .expect("Genesis block must be set"),
             )),
-            Some(BlockNumberOrTag::Latest) => Ok(Some(
-                self.blocks
-                    .last(&mut working_set.accessory_state())
-                    .expect("Head block must be set"),
-            )),
-            None => Ok(Some(
+            Some(BlockNumberOrTag::Latest | BlockNumberOrTag::Pending) | None => Ok(Some(
                 self.blocks
                     .last(&mut working_set.accessory_state())
                     .expect("Head block must be set"),
             )),
             _ => Err(EthApiError::InvalidParams(
-                "pending/safe/finalized block not supported".to_string(),
+                "safe/finalized block not supported".to_string(),
             )),
         }
     }
@@ -1625,6 +1738,58 @@ impl<C: sov_modules_api::Context> Evm<C> {
         block_number
     }
 
+    /// Returns the deposits and withdrawals of the Bridge contract in the block, in order.
+    /// Empty if the block is not known.
+    pub fn bridge_events_in_block(
+        &self,
+        block_number: u64,
+        working_set: &mut WorkingSet<C>,
+    ) -> Vec<BridgeEvent> {
+        let Some(block) = self
+            .blocks
+            .get(block_number as usize, &mut working_set.accessory_state())
+        else {
+            return vec![];
+        };
+
+        self.stored_block_receipts(&block, working_set)
+            .logs()
+            .filter(|log| log.address == Bridge::address())
+            .filter_map(|log| Bridge::decode_event(&log.to_log()))
+            .collect()
+    }
+
+    /// Returns the receipts of the transactions of the block.
+    /// Receipts of blocks not moved to `block_receipts` yet are read from the legacy `receipts`.
+    pub(crate) fn stored_block_receipts(
+        &self,
+        block: &SealedBlock,
+        working_set: &mut WorkingSet<C>,
+    ) -> BlockReceipts {
+        if block.transactions.is_empty() {
+            return BlockReceipts::default();
+        }
+
+        let mut accessory_state = working_set.accessory_state();
+        if let Some(block_receipts) = self
+            .block_receipts
+            .get(&block.header.number, &mut accessory_state)
+        {
+            return block_receipts;
+        }
+
+        let receipts: Vec<Receipt> = block
+            .transactions
+            .clone()
+            .map(|id| {
+                self.receipts
+                    .get(id as usize, &mut accessory_state)
+                    .expect("Receipt for known transaction must be set")
+            })
+            .collect();
+        BlockReceipts::new(&receipts)
+    }
+
     /// Returns the cumulative gas used in pending transactions
     /// Used to calculate how much gas system transactions use at the beginning of the block
     pub fn get_pending_txs_cumulative_gas_used(&self, working_set: &mut WorkingSet<C>) -> u128 {
@@ -1740,11 +1905,12 @@ fn map_out_of_gas_err<C: sov_modules_api::Context>(
     cfg_env: revm::primitives::CfgEnvWithHandlerCfg,
     db: EvmDb<'_, C>,
     l1_fee_rate: u128,
-) -> EthApiError {
+    shares_code: bool,
+) -> ErrorObjectOwned {
     let req_gas_limit = tx_env.gas_limit;
     tx_env.gas_limit = block_env.gas_limit;
 
-    match inspect_no_tracing(db, cfg_env, block_env, tx_env, l1_fee_rate) {
+    match inspect_no_tracing(db, cfg_env, block_env, tx_env, l1_fee_rate, shares_code) {
         Ok((res, _tx_info)) => match res.result {
             ExecutionResult::Success { .. } => {
                 // transaction succeeded by manually increasing the gas limit to
@@ -1759,7 +1925,7 @@ fn map_out_of_gas_err<C: sov_modules_api::Context>(
                 RpcInvalidTransactionError::EvmHalt(reason).into()
             }
         },
-        Err(err) => EthApiError::from(err),
+        Err(err) => evm_error_to_rpc(err),
     }
 }
 
@@ -1812,6 +1978,25 @@ fn update_estimated_gas_range(
     Ok(())
 }
 
+/// Returns [`CitreaError::StatePruned`] if the state at the end of the block
+/// has been pruned by the node
+fn ensure_state_available<C: sov_modules_api::Context>(
+    block_number: u64,
+    working_set: &WorkingSet<C>,
+) -> Result<(), CitreaError> {
+    // genesis is committed at db version 1
+    // so every block is offset by 1
+    match working_set.earliest_archival_version() {
+        Some(earliest_version) if block_number + 1 < earliest_version => {
+            Err(CitreaError::StatePruned {
+                block_number,
+                earliest_available_block: earliest_version - 1,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
 #[inline]
 fn set_state_to_end_of_evm_block<C: sov_modules_api::Context>(
     block_number: u64,
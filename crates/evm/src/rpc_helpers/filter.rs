@@ -12,6 +12,7 @@ use reth_rpc::eth::error::EthApiError;
 use serde::de::{DeserializeOwned, MapAccess, Visitor};
 use serde::ser::SerializeStruct;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sov_rollup_interface::rpc::deadline::{DeadlineExceeded, RPC_TIMEOUT_ERROR_CODE};
 
 use crate::evm::error::result::rpc_error_with_code;
 
@@ -645,7 +646,9 @@ pub fn log_matches_filter(
     true
 }
 
-/// TODO: docs + discuss finalized, safe and pending
+/// Converts a block tag of a filter to a block number, `start_block` being the latest block.
+/// Finalized and safe are resolved to numbers by the RPC server, so they only get here
+/// if the filter is not coming from the RPC.
 pub fn convert_block_number(
     num: BlockNumberOrTag,
     start_block: u64,
@@ -733,6 +736,9 @@ pub enum FilterError {
     /// Error thrown when the eth api returns an error
     #[error(transparent)]
     EthAPIError(#[from] EthApiError),
+    /// The request timed out before all blocks of the range were searched.
+    #[error(transparent)]
+    DeadlineExceeded(#[from] DeadlineExceeded),
     /// Error thrown when a spawned task failed to deliver a response.
     #[error("internal filter error")]
     InternalError,
@@ -751,6 +757,9 @@ impl From<FilterError> for jsonrpsee::types::error::ErrorObject<'static> {
                 err.to_string(),
             ),
             FilterError::EthAPIError(err) => err.into(),
+            err @ FilterError::DeadlineExceeded(_) => {
+                rpc_error_with_code(RPC_TIMEOUT_ERROR_CODE, err.to_string())
+            }
             err @ FilterError::QueryExceedsMaxBlocks(_) => rpc_error_with_code(
                 jsonrpsee::types::error::INVALID_PARAMS_CODE,
                 err.to_string(),
@@ -17,6 +17,7 @@ use crate::handler::{
     citrea_handle_register, CitreaExternal, CitreaExternalExt, TracingCitreaExternal, TxInfo,
 };
 
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn trace_transaction<C: sov_modules_api::Context>(
     opts: GethDebugTracingOptions,
     config_env: CfgEnvWithHandlerCfg,
@@ -25,6 +26,7 @@ pub(crate) fn trace_transaction<C: sov_modules_api::Context>(
     tx_hash: TxHash,
     db: &mut EvmDb<'_, C>,
     l1_fee_rate: u128,
+    shares_code: bool,
 ) -> EthResult<(GethTrace, revm::primitives::State)> {
     let GethDebugTracingOptions {
         config,
@@ -38,7 +40,8 @@ pub(crate) fn trace_transaction<C: sov_modules_api::Context>(
             GethDebugTracerType::BuiltInTracer(tracer) => match tracer {
                 GethDebugBuiltInTracerType::FourByteTracer => {
                     let inspector = FourByteInspector::default();
-                    let mut citrea_inspector = TracingCitreaExternal::new(inspector, l1_fee_rate);
+                    let mut citrea_inspector = TracingCitreaExternal::new(inspector, l1_fee_rate)
+                        .with_shared_code(shares_code);
                     let res = inspect_citrea(
                         db,
                         config_env,
@@ -60,7 +63,8 @@ pub(crate) fn trace_transaction<C: sov_modules_api::Context>(
                         TracingInspectorConfig::from_geth_config(&config)
                             .set_record_logs(call_config.with_log.unwrap_or_default()),
                     );
-                    let mut citrea_inspector = TracingCitreaExternal::new(inspector, l1_fee_rate);
+                    let mut citrea_inspector = TracingCitreaExternal::new(inspector, l1_fee_rate)
+                        .with_shared_code(shares_code);
                     let res = inspect_citrea(
                         db,
                         config_env,
@@ -98,7 +102,8 @@ pub(crate) fn trace_transaction<C: sov_modules_api::Context>(
     let inspector_config = TracingInspectorConfig::from_geth_config(&config);
 
     let inspector = TracingInspector::new(inspector_config);
-    let mut citrea_inspector = TracingCitreaExternal::new(inspector, l1_fee_rate);
+    let mut citrea_inspector =
+        TracingCitreaExternal::new(inspector, l1_fee_rate).with_shared_code(shares_code);
 
     let res = inspect_citrea(
         db,
@@ -179,12 +184,13 @@ pub(crate) fn inspect_no_tracing<DB>(
     block_env: BlockEnv,
     tx_env: TxEnv,
     l1_fee_rate: u128,
+    shares_code: bool,
 ) -> Result<(ResultAndState, TxInfo), EVMError<DB::Error>>
 where
     DB: Database,
 {
     let tmp_hash: TxHash = b"hash_of_an_ephemeral_transaction".into();
-    let mut ext = CitreaExternal::new(l1_fee_rate);
+    let mut ext = CitreaExternal::new(l1_fee_rate).with_shared_code(shares_code);
     ext.set_current_tx_hash(tmp_hash);
 
     let mut evm = revm::Evm::builder()
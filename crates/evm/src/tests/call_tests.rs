@@ -1,25 +1,35 @@
 use std::str::FromStr;
 
 use reth_primitives::constants::ETHEREUM_BLOCK_GAS_LIMIT;
-use reth_primitives::{address, Address, BlockNumberOrTag, Bytes, TxKind};
+use reth_primitives::{
+    address, Address, BlockNumberOrTag, Bytes, TransactionSignedEcRecovered, TxKind,
+};
 use reth_rpc_types::request::{TransactionInput, TransactionRequest};
-use revm::primitives::{SpecId, KECCAK_EMPTY, U256};
+use revm::primitives::{EVMError, SpecId, B256, KECCAK_EMPTY, U256};
+use revm::Database;
 use sov_modules_api::default_context::DefaultContext;
 use sov_modules_api::hooks::HookSoftConfirmationInfo;
 use sov_modules_api::utils::generate_address;
-use sov_modules_api::{Context, Module, StateMapAccessor, StateVecAccessor};
+use sov_modules_api::{
+    Context, Module, StateMapAccessor, StateValueAccessor, StateVecAccessor, WorkingSet,
+};
 
-use crate::call::CallMessage;
+use crate::call::{get_cfg_env, CallMessage};
+use crate::evm::db::DBError;
+use crate::evm::executor::execute_multiple_tx;
+use crate::evm::handler::{CitreaExternal, CitreaExternalExt};
+use crate::evm::parallel_executor::execute_multiple_tx_parallel;
 use crate::evm::primitive_types::Receipt;
 use crate::smart_contracts::{
     BlockHashContract, InfiniteLoopContract, LogsContract, SelfDestructorContract,
     SimpleStorageContract, TestContract,
 };
 use crate::tests::test_signer::TestSigner;
-use crate::tests::utils::get_evm;
+use crate::tests::utils::{get_evm, stored_receipts};
 use crate::tests::DEFAULT_CHAIN_ID;
 use crate::{
-    AccountData, EvmConfig, RlpEvmTransaction, BASE_FEE_VAULT, L1_FEE_VAULT, PRIORITY_FEE_VAULT,
+    AccountData, CitreaError, EvmConfig, RlpEvmTransaction, StorageRentAccount, StorageRentConfig,
+    BASE_FEE_VAULT, L1_FEE_VAULT, PRIORITY_FEE_VAULT, STORAGE_RENT_DEPOSIT_ADDRESS,
 };
 
 type C = DefaultContext;
@@ -59,6 +69,8 @@ fn call_multiple_test() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -97,9 +109,7 @@ fn call_multiple_test() {
     assert_eq!(U256::from(set_arg + 3), storage_value);
 
     assert_eq!(
-        evm.receipts
-            .iter(&mut working_set.accessory_state())
-            .collect::<Vec<_>>(),
+        stored_receipts(&evm, &mut working_set),
         [
             Receipt {
                 receipt: reth_primitives::Receipt {
@@ -167,6 +177,8 @@ fn call_test() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -199,9 +211,7 @@ fn call_test() {
 
     assert_eq!(U256::from(set_arg), storage_value);
     assert_eq!(
-        evm.receipts
-            .iter(&mut working_set.accessory_state())
-            .collect::<Vec<_>>(),
+        stored_receipts(&evm, &mut working_set),
         [
             Receipt {
                 receipt: reth_primitives::Receipt {
@@ -246,6 +256,8 @@ fn failed_transaction_test() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         working_set,
     );
@@ -303,6 +315,8 @@ fn self_destruct_test() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -363,6 +377,8 @@ fn self_destruct_test() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -396,10 +412,7 @@ fn self_destruct_test() {
         .get(&die_to_address, &mut working_set)
         .expect("die to address should exist");
 
-    let receipts = evm
-        .receipts
-        .iter(&mut working_set.accessory_state())
-        .collect::<Vec<_>>();
+    let receipts = stored_receipts(&evm, &mut working_set);
 
     // the tx should be a success
     assert!(receipts[0].receipt.success);
@@ -444,6 +457,8 @@ fn test_block_hash_in_evm() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -479,6 +494,8 @@ fn test_block_hash_in_evm() {
                 deposit_data: vec![],
                 l1_fee_rate,
                 timestamp: 0,
+                da_slot_timestamp: 0,
+                slot_inbox: Default::default(),
             },
             &mut working_set,
         );
@@ -555,6 +572,8 @@ fn test_block_gas_limit() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -834,6 +853,8 @@ fn test_l1_fee_success() {
                 deposit_data: vec![],
                 l1_fee_rate,
                 timestamp: 0,
+                da_slot_timestamp: 0,
+                slot_inbox: Default::default(),
             },
             &mut working_set,
         );
@@ -882,9 +903,7 @@ fn test_l1_fee_success() {
         assert_eq!(l1_fee_valut.info.balance, expected_l1_fee_vault_balance);
 
         assert_eq!(
-            evm.receipts
-                .iter(&mut working_set.accessory_state())
-                .collect::<Vec<_>>(),
+            stored_receipts(&evm, &mut working_set),
             [Receipt {
                 receipt: reth_primitives::Receipt {
                     tx_type: reth_primitives::TxType::Eip1559,
@@ -937,6 +956,8 @@ fn test_l1_fee_not_enough_funds() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -947,6 +968,7 @@ fn test_l1_fee_not_enough_funds() {
 
         let deploy_message =
             create_contract_message_with_fee(&dev_signer, 0, BlockHashContract::default(), 1);
+        let deploy_tx: TransactionSignedEcRecovered = deploy_message.clone().try_into().unwrap();
 
         let call_result = evm.call(
             CallMessage {
@@ -960,6 +982,12 @@ fn test_l1_fee_not_enough_funds() {
 
         let block = evm.blocks.last(&mut working_set.accessory_state()).unwrap();
         assert!(block.transactions.is_empty());
+
+        // The tx is reported as failing to pay the L1 fee
+        assert_eq!(
+            evm.get_l1_fee_failed_txs(&mut working_set.accessory_state()),
+            vec![deploy_tx.hash()]
+        );
     }
 
     evm.end_soft_confirmation_hook(&mut working_set);
@@ -979,6 +1007,29 @@ fn test_l1_fee_not_enough_funds() {
     assert!(db_coinbase.is_none());
 }
 
+#[test]
+fn test_citrea_errors_from_evm_errors() {
+    let errors = [
+        CitreaError::InsufficientFundsForL1Fee {
+            l1_fee: U256::from(123456),
+        },
+        CitreaError::BlockGasLimitExceeded {
+            block_gas_limit: 30_000_000,
+        },
+        CitreaError::InvalidSystemTransaction {
+            tx_hash: B256::repeat_byte(7),
+        },
+    ];
+    for error in errors {
+        let evm_error: EVMError<DBError> = error.clone().into();
+        assert_eq!(CitreaError::from_evm_error(&evm_error), Some(error));
+    }
+
+    let evm_error: EVMError<DBError> = EVMError::Custom("other".to_string());
+    assert_eq!(CitreaError::from_evm_error(&evm_error), None);
+    assert_eq!(CitreaError::NonceTooHigh.code(), -32073);
+}
+
 #[test]
 fn test_l1_fee_halt() {
     let (config, dev_signer, _) =
@@ -997,6 +1048,8 @@ fn test_l1_fee_halt() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -1034,9 +1087,7 @@ fn test_l1_fee_halt() {
     evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
 
     assert_eq!(
-        evm.receipts
-            .iter(&mut working_set.accessory_state())
-            .collect::<Vec<_>>(),
+        stored_receipts(&evm, &mut working_set),
         [
             Receipt {
                 receipt: reth_primitives::Receipt {
@@ -1085,3 +1136,515 @@ fn test_l1_fee_halt() {
     assert_eq!(base_fee_valut.info.balance, U256::from(1106947));
     assert_eq!(l1_fee_valut.info.balance, U256::from(445 + 52));
 }
+
+#[test]
+fn test_storage_rent_deposits_and_charges() {
+    let (mut config, dev_signer, _) =
+        get_evm_config_starting_base_fee(U256::from_str("100000000").unwrap(), None, 1);
+    config.storage_rent = Some(StorageRentConfig {
+        rent_per_byte_per_block: 1,
+    });
+    let beneficiary = address!("0000000000000000000000000000000000000abc");
+
+    let (evm, mut working_set) = get_evm(&config);
+
+    evm.begin_soft_confirmation_hook(
+        &HookSoftConfirmationInfo {
+            da_slot_hash: [5u8; 32],
+            da_slot_height: 1,
+            da_slot_txs_commitment: [42u8; 32],
+            pre_state_root: [10u8; 32].to_vec(),
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate: 0,
+            timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
+        },
+        &mut working_set,
+    );
+    {
+        let sender_address = generate_address::<C>("sender");
+        let sequencer_address = generate_address::<C>("sequencer");
+        let context = C::new(sender_address, sequencer_address, 1);
+
+        let txs = vec![
+            // Deposit for the sender
+            dev_signer
+                .sign_default_transaction_with_fee(
+                    TxKind::Call(STORAGE_RENT_DEPOSIT_ADDRESS),
+                    vec![],
+                    0,
+                    1_000_000,
+                    1,
+                )
+                .unwrap(),
+            // Deposit for the account in calldata
+            dev_signer
+                .sign_default_transaction_with_fee(
+                    TxKind::Call(STORAGE_RENT_DEPOSIT_ADDRESS),
+                    beneficiary.to_vec(),
+                    1,
+                    500_000,
+                    1,
+                )
+                .unwrap(),
+        ];
+        evm.call(CallMessage { txs }, &context, &mut working_set)
+            .unwrap();
+    }
+    evm.end_soft_confirmation_hook(&mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    let deposit_block = evm
+        .blocks
+        .last(&mut working_set.accessory_state())
+        .unwrap()
+        .header
+        .number;
+
+    // An empty block charges the rent of the sender for one block
+    evm.begin_soft_confirmation_hook(
+        &HookSoftConfirmationInfo {
+            da_slot_hash: [5u8; 32],
+            da_slot_height: 1,
+            da_slot_txs_commitment: [42u8; 32],
+            pre_state_root: [99u8; 32].to_vec(),
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate: 0,
+            timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
+        },
+        &mut working_set,
+    );
+    evm.end_soft_confirmation_hook(&mut working_set);
+    evm.finalize_hook(&[100u8; 32].into(), &mut working_set.accessory_state());
+
+    // Address, nonce, balance and code hash
+    let sender_size = 20 + 8 + 32 + 32;
+    assert_eq!(
+        evm.eth_get_storage_rent(dev_signer.address(), &mut working_set)
+            .unwrap(),
+        Some(StorageRentAccount {
+            deposit: U256::from(1_000_000 - sender_size),
+            unpaid: U256::ZERO,
+            size: sender_size,
+            storage_slots: 0,
+            settled_at: deposit_block + 1,
+        })
+    );
+    // The beneficiary has no state yet, so it is not charged
+    assert_eq!(
+        evm.eth_get_storage_rent(beneficiary, &mut working_set)
+            .unwrap(),
+        Some(StorageRentAccount {
+            deposit: U256::from(500_000),
+            unpaid: U256::ZERO,
+            size: 0,
+            storage_slots: 0,
+            settled_at: deposit_block + 1,
+        })
+    );
+}
+
+#[test]
+fn test_storage_rent_settlement() {
+    let config = StorageRentConfig {
+        rent_per_byte_per_block: 3,
+    };
+    let mut rent_account = StorageRentAccount {
+        deposit: U256::from(100),
+        unpaid: U256::ZERO,
+        size: 10,
+        storage_slots: 0,
+        settled_at: 5,
+    };
+
+    // The deposit runs out after 4 of 5 blocks
+    assert_eq!(rent_account.settle(&config, 10, 20), U256::from(100));
+    assert_eq!(
+        rent_account,
+        StorageRentAccount {
+            deposit: U256::ZERO,
+            unpaid: U256::from(50),
+            size: 20,
+            storage_slots: 0,
+            settled_at: 10,
+        }
+    );
+
+    // Unpaid rent is paid off first
+    assert_eq!(rent_account.add_deposit(U256::from(80)), U256::from(50));
+    assert_eq!(rent_account.unpaid, U256::ZERO);
+    assert_eq!(rent_account.deposit, U256::from(30));
+}
+
+#[test]
+fn test_storage_rent_of_live_slots_and_withdrawals() {
+    let (mut config, dev_signer, contract_addr) =
+        get_evm_config_starting_base_fee(U256::from_str("100000000000000000000").unwrap(), None, 1);
+    config.storage_rent = Some(StorageRentConfig {
+        rent_per_byte_per_block: 1,
+    });
+
+    let (evm, mut working_set) = get_evm(&config);
+    let execute_block = |txs: Vec<RlpEvmTransaction>, working_set: &mut WorkingSet<C>| {
+        evm.begin_soft_confirmation_hook(
+            &HookSoftConfirmationInfo {
+                da_slot_hash: [5u8; 32],
+                da_slot_height: 1,
+                da_slot_txs_commitment: [42u8; 32],
+                pre_state_root: [10u8; 32].to_vec(),
+                pub_key: vec![],
+                deposit_data: vec![],
+                l1_fee_rate: 0,
+                timestamp: 0,
+                da_slot_timestamp: 0,
+                slot_inbox: Default::default(),
+            },
+            working_set,
+        );
+        let sender_address = generate_address::<C>("sender");
+        let sequencer_address = generate_address::<C>("sequencer");
+        let context = C::new(sender_address, sequencer_address, 1);
+        evm.call(CallMessage { txs }, &context, working_set)
+            .unwrap();
+        evm.end_soft_confirmation_hook(working_set);
+        evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+    };
+    let rent_account = |address: Address, working_set: &mut WorkingSet<C>| {
+        evm.storage_rent_accounts
+            .get(&address, working_set)
+            .unwrap()
+    };
+    let balance = |address: Address, working_set: &mut WorkingSet<C>| {
+        evm.accounts
+            .get(&address, working_set)
+            .map(|account| account.info.balance)
+            .unwrap_or_default()
+    };
+
+    execute_block(
+        vec![
+            create_contract_message(&dev_signer, 0, SimpleStorageContract::default()),
+            set_arg_message(contract_addr, &dev_signer, 1, 42),
+            // Deposit for the contract
+            dev_signer
+                .sign_default_transaction(
+                    TxKind::Call(STORAGE_RENT_DEPOSIT_ADDRESS),
+                    contract_addr.to_vec(),
+                    2,
+                    1_000_000,
+                )
+                .unwrap(),
+        ],
+        &mut working_set,
+    );
+    assert_eq!(
+        rent_account(contract_addr, &mut working_set).storage_slots,
+        1
+    );
+    let size_with_slot = rent_account(contract_addr, &mut working_set).size;
+
+    // Clearing the slot keeps its key, but it is not charged anymore
+    execute_block(
+        vec![set_arg_message(contract_addr, &dev_signer, 3, 0)],
+        &mut working_set,
+    );
+    let contract_rent = rent_account(contract_addr, &mut working_set);
+    assert_eq!(contract_rent.storage_slots, 0);
+    assert_eq!(contract_rent.size, size_with_slot - 64);
+    assert_eq!(
+        evm.accounts
+            .get(&contract_addr, &mut working_set)
+            .unwrap()
+            .keys
+            .len(&mut working_set),
+        1
+    );
+
+    // The rent paid for the block is moved to the base fee vault
+    assert_eq!(
+        contract_rent.deposit,
+        U256::from(1_000_000 - size_with_slot)
+    );
+    assert_eq!(
+        balance(STORAGE_RENT_DEPOSIT_ADDRESS, &mut working_set),
+        contract_rent.deposit
+    );
+
+    // The sender withdraws its deposit with a transaction without value
+    execute_block(
+        vec![dev_signer
+            .sign_default_transaction(TxKind::Call(STORAGE_RENT_DEPOSIT_ADDRESS), vec![], 4, 5_000)
+            .unwrap()],
+        &mut working_set,
+    );
+    let sender_deposit = rent_account(dev_signer.address(), &mut working_set).deposit;
+    assert!(!sender_deposit.is_zero());
+    assert_eq!(
+        balance(STORAGE_RENT_DEPOSIT_ADDRESS, &mut working_set),
+        rent_account(contract_addr, &mut working_set).deposit + sender_deposit
+    );
+    execute_block(
+        vec![dev_signer
+            .sign_default_transaction(TxKind::Call(STORAGE_RENT_DEPOSIT_ADDRESS), vec![], 5, 0)
+            .unwrap()],
+        &mut working_set,
+    );
+    assert_eq!(
+        rent_account(dev_signer.address(), &mut working_set).deposit,
+        U256::ZERO
+    );
+    // Only the deposit of the contract is left
+    assert_eq!(
+        balance(STORAGE_RENT_DEPOSIT_ADDRESS, &mut working_set),
+        rent_account(contract_addr, &mut working_set).deposit
+    );
+}
+
+#[test]
+fn test_parallel_execution_matches_serial() {
+    let (mut config, dev_signer1, _) =
+        get_evm_config_starting_base_fee(U256::from_str("100000000000000000000").unwrap(), None, 1);
+    let dev_signer2 = TestSigner::new_random();
+    config.data.push(AccountData::new(
+        dev_signer2.address(),
+        U256::from_str("100000000000000000000").unwrap(),
+        Bytes::default(),
+        0,
+        Default::default(),
+    ));
+    let contract_addr = dev_signer1.address().create(0);
+    let recipient = address!("0000000000000000000000000000000000000abc");
+
+    let txs: Vec<TransactionSignedEcRecovered> = vec![
+        create_contract_message_with_priority_fee(
+            &dev_signer1,
+            0,
+            SimpleStorageContract::default(),
+            10,
+            2,
+        ),
+        // Independent of the deployment
+        dev_signer2
+            .sign_default_transaction(TxKind::Call(recipient), vec![], 0, 1000)
+            .unwrap(),
+        // Conflicts with the deployment
+        set_arg_message(contract_addr, &dev_signer1, 1, 42),
+        // Conflicts with the transfer
+        dev_signer2
+            .sign_default_transaction(TxKind::Call(dev_signer1.address()), vec![], 1, 1000)
+            .unwrap(),
+        // Invalid nonce
+        dev_signer2
+            .sign_default_transaction(TxKind::Call(recipient), vec![], 1, 1000)
+            .unwrap(),
+    ]
+    .into_iter()
+    .map(|tx| tx.try_into().unwrap())
+    .collect();
+
+    let execute = |parallel: bool| {
+        let (evm, mut working_set) = get_evm(&config);
+        evm.begin_soft_confirmation_hook(
+            &HookSoftConfirmationInfo {
+                da_slot_hash: [5u8; 32],
+                da_slot_height: 1,
+                da_slot_txs_commitment: [42u8; 32],
+                pre_state_root: [10u8; 32].to_vec(),
+                pub_key: vec![],
+                deposit_data: vec![],
+                l1_fee_rate: 1,
+                timestamp: 0,
+                da_slot_timestamp: 0,
+                slot_inbox: Default::default(),
+            },
+            &mut working_set,
+        );
+
+        let block_env = evm.block_env.get(&mut working_set).unwrap();
+        let cfg_env = get_cfg_env(&block_env, evm.cfg.get(&mut working_set).unwrap());
+        let mut ext = CitreaExternal::new(1);
+        let db = evm.get_db(&mut working_set);
+        let results = if parallel {
+            execute_multiple_tx_parallel(db, block_env, &txs, cfg_env, &mut ext, 0)
+        } else {
+            execute_multiple_tx(db, block_env, &txs, cfg_env, &mut ext, 0)
+        };
+        let results: Vec<_> = results
+            .into_iter()
+            .map(|result| result.map(|result| (result.is_success(), result.gas_used())))
+            .map(|result| result.ok())
+            .collect();
+        let tx_infos: Vec<_> = txs
+            .iter()
+            .map(|tx| ext.get_tx_info(tx.hash()).map(|info| info.l1_diff_size))
+            .collect();
+
+        let balances: Vec<_> = [
+            dev_signer1.address(),
+            dev_signer2.address(),
+            recipient,
+            BASE_FEE_VAULT,
+            L1_FEE_VAULT,
+            PRIORITY_FEE_VAULT,
+        ]
+        .iter()
+        .map(|address| {
+            evm.accounts
+                .get(address, &mut working_set)
+                .map(|account| account.info)
+        })
+        .collect();
+        let storage = evm
+            .accounts
+            .get(&contract_addr, &mut working_set)
+            .unwrap()
+            .storage
+            .get(&U256::ZERO, &mut working_set);
+
+        (results, tx_infos, balances, storage)
+    };
+
+    let serial = execute(false);
+    assert_eq!(serial.0.iter().filter(|result| result.is_some()).count(), 4);
+    assert_eq!(serial.3, Some(U256::from(42)));
+    assert_eq!(execute(true), serial);
+}
+
+#[test]
+fn test_account_filter() {
+    let (config, dev_signer, contract_addr) =
+        get_evm_config(U256::from_str("100000000000000000000").unwrap(), None);
+    let (evm, mut working_set) = get_evm(&config);
+    let recipient = address!("0000000000000000000000000000000000000abc");
+
+    {
+        let mut db = evm.get_query_db(&mut working_set);
+        assert!(db.account_filter.skip_missing);
+        assert!(db.basic(dev_signer.address()).unwrap().is_some());
+        assert!(db.basic(contract_addr).unwrap().is_none());
+        assert!(db.basic(recipient).unwrap().is_none());
+    }
+
+    evm.begin_soft_confirmation_hook(
+        &HookSoftConfirmationInfo {
+            da_slot_hash: [5u8; 32],
+            da_slot_height: 1,
+            da_slot_txs_commitment: [42u8; 32],
+            pre_state_root: [10u8; 32].to_vec(),
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate: 0,
+            timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
+        },
+        &mut working_set,
+    );
+    {
+        let sender_address = generate_address::<C>("sender");
+        let sequencer_address = generate_address::<C>("sequencer");
+        let context = C::new(sender_address, sequencer_address, 1);
+
+        let call_message = CallMessage {
+            txs: vec![
+                create_contract_message(&dev_signer, 0, SimpleStorageContract::default()),
+                dev_signer
+                    .sign_default_transaction(TxKind::Call(recipient), vec![], 1, 1000)
+                    .unwrap(),
+            ],
+        };
+
+        evm.call(call_message, &context, &mut working_set).unwrap();
+    }
+    evm.end_soft_confirmation_hook(&mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    // Accounts created by transactions are added to the filter
+    let mut db = evm.get_query_db(&mut working_set);
+    assert!(db.basic(contract_addr).unwrap().is_some());
+    assert_eq!(
+        db.basic(recipient).unwrap().map(|account| account.balance),
+        Some(U256::from(1000))
+    );
+}
+
+/// Deploys the same contract twice in the first L2 block.
+/// Returns the L1 diff sizes of the deployments and the size of the code.
+fn deploy_identical_code(shared_code_height: Option<u64>) -> (Vec<u64>, u64) {
+    let (mut config, dev_signer, contract_addr) =
+        get_evm_config(U256::from_str("100000000000000000000").unwrap(), None);
+    config.shared_code_height = shared_code_height;
+    let (evm, mut working_set) = get_evm(&config);
+    let clone_addr = dev_signer.address().create(1);
+
+    evm.begin_soft_confirmation_hook(
+        &HookSoftConfirmationInfo {
+            da_slot_hash: [5u8; 32],
+            da_slot_height: 1,
+            da_slot_txs_commitment: [42u8; 32],
+            pre_state_root: [10u8; 32].to_vec(),
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate: 0,
+            timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
+        },
+        &mut working_set,
+    );
+    {
+        let sender_address = generate_address::<C>("sender");
+        let sequencer_address = generate_address::<C>("sequencer");
+        let context = C::new(sender_address, sequencer_address, 1);
+
+        let call_message = CallMessage {
+            txs: vec![
+                create_contract_message(&dev_signer, 0, SimpleStorageContract::default()),
+                create_contract_message(&dev_signer, 1, SimpleStorageContract::default()),
+            ],
+        };
+
+        evm.call(call_message, &context, &mut working_set).unwrap();
+    }
+    evm.end_soft_confirmation_hook(&mut working_set);
+    evm.finalize_hook(&[99u8; 32].into(), &mut working_set.accessory_state());
+
+    let code_hash = evm
+        .accounts
+        .get(&contract_addr, &mut working_set)
+        .unwrap()
+        .info
+        .code_hash;
+    let clone = evm.accounts.get(&clone_addr, &mut working_set).unwrap();
+    assert_eq!(clone.info.code_hash, code_hash);
+    let code = evm.code.get(&code_hash, &mut working_set).unwrap();
+
+    let diff_sizes: Vec<u64> = stored_receipts(&evm, &mut working_set)
+        .into_iter()
+        .map(|receipt| receipt.l1_diff_size)
+        .collect();
+    (diff_sizes, code.len() as u64)
+}
+
+#[test]
+fn test_identical_code_is_stored_once() {
+    // The clone only pays for the code hash
+    let (diff_sizes, code_size) = deploy_identical_code(Some(1));
+    assert_eq!(diff_sizes, vec![565, 565 - code_size]);
+    let (diff_sizes, code_size) = deploy_identical_code(Some(0));
+    assert_eq!(diff_sizes, vec![565, 565 - code_size]);
+}
+
+#[test]
+fn test_identical_code_is_charged_before_activation() {
+    // Blocks before the activation keep charging every deployment for its code
+    let (diff_sizes, _) = deploy_identical_code(None);
+    assert_eq!(diff_sizes, vec![565, 565]);
+    let (diff_sizes, _) = deploy_identical_code(Some(2));
+    assert_eq!(diff_sizes, vec![565, 565]);
+}
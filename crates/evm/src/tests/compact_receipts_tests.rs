@@ -0,0 +1,102 @@
+use reth_primitives::{Address, Bytes, Log, TxType, B256};
+use sov_state::codec::{BcsCodec, StateValueCodec};
+
+use crate::evm::compact_receipts::BlockReceipts;
+use crate::evm::primitive_types::Receipt;
+
+fn receipt(logs: Vec<Log>, cumulative_gas_used: u64, log_index_start: u64) -> Receipt {
+    Receipt {
+        receipt: reth_primitives::Receipt {
+            tx_type: TxType::Eip1559,
+            success: !logs.is_empty(),
+            cumulative_gas_used,
+            logs,
+        },
+        gas_used: 21000,
+        log_index_start,
+        l1_diff_size: 52,
+    }
+}
+
+fn transfer_log(token: u8, from: u8, to: u8, amount: u8) -> Log {
+    Log::new_unchecked(
+        Address::with_last_byte(token),
+        vec![
+            B256::with_last_byte(0xdd),
+            B256::with_last_byte(from),
+            B256::with_last_byte(to),
+        ],
+        Bytes::from(vec![amount; 32]),
+    )
+}
+
+/// Receipts of swaps through the same pools, with a failed tx without logs in between
+fn defi_block_receipts() -> Vec<Receipt> {
+    vec![
+        receipt(
+            vec![transfer_log(1, 10, 20, 1), transfer_log(2, 20, 10, 2)],
+            21000,
+            0,
+        ),
+        receipt(vec![], 42000, 2),
+        receipt(
+            vec![
+                transfer_log(1, 11, 20, 3),
+                transfer_log(2, 20, 11, 4),
+                Log::new_unchecked(Address::with_last_byte(20), vec![], Bytes::new()),
+            ],
+            63000,
+            2,
+        ),
+    ]
+}
+
+#[test]
+fn compact_receipts_round_trip() {
+    let receipts = defi_block_receipts();
+    let block_receipts = BlockReceipts::new(&receipts);
+
+    assert_eq!(block_receipts.receipts(), receipts);
+    for (index, receipt) in receipts.iter().enumerate() {
+        assert_eq!(block_receipts.receipt(index).as_ref(), Some(receipt));
+    }
+    assert_eq!(block_receipts.receipt(receipts.len()), None);
+
+    let encoded = BcsCodec.encode_value(&block_receipts);
+    let decoded: BlockReceipts = BcsCodec.try_decode_value(&encoded).unwrap();
+    assert_eq!(decoded, block_receipts);
+
+    // Addresses and topics shared by the logs are only stored once
+    let legacy_size: usize = receipts
+        .iter()
+        .map(|receipt| BcsCodec.encode_value(receipt).len())
+        .sum();
+    assert!(encoded.len() < legacy_size);
+
+    assert_eq!(BlockReceipts::new(&[]).receipts(), vec![]);
+}
+
+#[test]
+fn compact_receipts_log_views() {
+    let receipts = defi_block_receipts();
+    let block_receipts = BlockReceipts::new(&receipts);
+
+    let logs: Vec<_> = block_receipts.logs().collect();
+    assert_eq!(logs.len(), 5);
+
+    // The tx without logs is skipped
+    let receipt_indexes: Vec<_> = logs.iter().map(|log| log.receipt_index).collect();
+    assert_eq!(receipt_indexes, vec![0, 0, 2, 2, 2]);
+
+    let expected_logs = receipts.iter().flat_map(|receipt| &receipt.receipt.logs);
+    for (log, expected) in logs.iter().zip(expected_logs) {
+        assert_eq!(log.address, expected.address);
+        assert_eq!(log.topics(), expected.topics());
+        assert_eq!(log.data, &expected.data.data[..]);
+        assert_eq!(&log.to_log(), expected);
+
+        let without_data = log.to_log_without_data();
+        assert_eq!(without_data.topics(), expected.topics());
+        assert!(without_data.data.data.is_empty());
+    }
+}
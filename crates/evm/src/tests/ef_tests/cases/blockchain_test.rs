@@ -73,6 +73,8 @@ impl BlockchainTestCase {
                 deposit_data: vec![],
                 l1_fee_rate: 0,
                 timestamp: 0,
+                da_slot_timestamp: 0,
+                slot_inbox: Default::default(),
             },
             &mut working_set,
         );
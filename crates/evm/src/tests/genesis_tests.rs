@@ -58,6 +58,8 @@ lazy_static! {
         difficulty: U256::ZERO,
         extra_data: Bytes::default(),
         nonce: 0,
+        storage_rent: None,
+        shared_code_height: None,
     };
 
     pub(crate) static ref GENESIS_DA_TXS_COMMITMENT: B256 = B256::from(hex!(
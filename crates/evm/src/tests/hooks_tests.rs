@@ -5,17 +5,19 @@ use reth_primitives::{
     Address, Bloom, Bytes, Header, SealedHeader, Signature, TransactionSigned, B256,
     EMPTY_OMMER_ROOT_HASH, KECCAK_EMPTY, U256,
 };
+use sov_modules_api::default_context::DefaultContext;
 use sov_modules_api::hooks::HookSoftConfirmationInfo;
-use sov_modules_api::{StateMapAccessor, StateValueAccessor, StateVecAccessor};
+use sov_modules_api::{StateMapAccessor, StateValueAccessor, StateVecAccessor, WorkingSet};
 
 use super::genesis_tests::{GENESIS_DA_TXS_COMMITMENT, TEST_CONFIG};
 use crate::evm::primitive_types::{
     Block, BlockEnv, Receipt, SealedBlock, TransactionSignedAndRecovered,
 };
+use crate::evm::system_contracts::RandomnessBeacon;
 use crate::tests::genesis_tests::BENEFICIARY;
-use crate::tests::utils::{get_evm, GENESIS_STATE_ROOT};
+use crate::tests::utils::{get_evm, stored_receipts, GENESIS_STATE_ROOT};
 use crate::tests::DEFAULT_CHAIN_ID;
-use crate::PendingTransaction;
+use crate::{AccountData, PendingTransaction, RANDOMNESS_BEACON};
 
 lazy_static! {
     pub(crate) static ref DA_ROOT_HASH: B256 = B256::from([5u8; 32]);
@@ -35,6 +37,8 @@ fn begin_soft_confirmation_hook_creates_pending_block() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 54,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -52,6 +56,126 @@ fn begin_soft_confirmation_hook_creates_pending_block() {
     );
 }
 
+#[test]
+fn set_pending_coinbase_overrides_config_coinbase() {
+    let (evm, mut working_set) = get_evm(&TEST_CONFIG);
+    evm.begin_soft_confirmation_hook(
+        &HookSoftConfirmationInfo {
+            da_slot_hash: DA_ROOT_HASH.0,
+            da_slot_height: 1,
+            da_slot_txs_commitment: [42u8; 32],
+            pre_state_root: [10u8; 32].to_vec(),
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate: 0,
+            timestamp: 54,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
+        },
+        &mut working_set,
+    );
+
+    let sequencer_address = Address::from([7u8; 20]);
+    evm.set_pending_coinbase(sequencer_address, &mut working_set);
+
+    let pending_block = evm.block_env.get(&mut working_set).unwrap();
+    assert_eq!(pending_block.coinbase, sequencer_address);
+    assert_eq!(pending_block.number, 2);
+    assert_eq!(pending_block.timestamp, 54);
+}
+
+#[test]
+fn begin_soft_confirmation_hook_records_beacon_randomness() {
+    let mut config = TEST_CONFIG.clone();
+    config.data.push(AccountData::new(
+        RANDOMNESS_BEACON,
+        U256::ZERO,
+        Bytes::from_static(&[0x00]),
+        0,
+        Default::default(),
+    ));
+    let (evm, mut working_set) = get_evm(&config);
+    let parent_block = evm.head.get(&mut working_set).unwrap();
+
+    evm.begin_soft_confirmation_hook(
+        &HookSoftConfirmationInfo {
+            da_slot_hash: DA_ROOT_HASH.0,
+            da_slot_height: 1,
+            da_slot_txs_commitment: [42u8; 32],
+            pre_state_root: [10u8; 32].to_vec(),
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate: 0,
+            timestamp: 54,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
+        },
+        &mut working_set,
+    );
+
+    let randomness = RandomnessBeacon::randomness(parent_block.header.mix_hash, DA_ROOT_HASH.0, 2);
+    assert_ne!(randomness, *DA_ROOT_HASH);
+
+    let pending_block = evm.block_env.get(&mut working_set).unwrap();
+    assert_eq!(pending_block.prevrandao, randomness);
+
+    let beacon = evm
+        .accounts
+        .get(&RANDOMNESS_BEACON, &mut working_set)
+        .unwrap();
+    assert_eq!(
+        beacon
+            .storage
+            .get(&RandomnessBeacon::slot(2), &mut working_set),
+        Some(U256::from_be_bytes(randomness.0))
+    );
+}
+
+#[test]
+fn begin_soft_confirmation_hook_ignores_beacon_address_without_code() {
+    let mut config = TEST_CONFIG.clone();
+    // An account which received a transfer
+    config.data.push(AccountData::new(
+        RANDOMNESS_BEACON,
+        U256::from(1000),
+        Bytes::new(),
+        0,
+        Default::default(),
+    ));
+    let (evm, mut working_set) = get_evm(&config);
+
+    evm.begin_soft_confirmation_hook(
+        &HookSoftConfirmationInfo {
+            da_slot_hash: DA_ROOT_HASH.0,
+            da_slot_height: 1,
+            da_slot_txs_commitment: [42u8; 32],
+            pre_state_root: [10u8; 32].to_vec(),
+            pub_key: vec![],
+            deposit_data: vec![],
+            l1_fee_rate: 0,
+            timestamp: 54,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
+        },
+        &mut working_set,
+    );
+
+    let pending_block = evm.block_env.get(&mut working_set).unwrap();
+    assert_eq!(pending_block.prevrandao, *DA_ROOT_HASH);
+
+    let account = evm
+        .accounts
+        .get(&RANDOMNESS_BEACON, &mut working_set)
+        .unwrap();
+    assert_eq!(account.keys.len(&mut working_set), 0);
+    assert_eq!(
+        account
+            .storage
+            .get(&RandomnessBeacon::slot(2), &mut working_set),
+        None
+    );
+}
+
 #[test]
 fn end_soft_confirmation_hook_sets_head() {
     let (evm, mut working_set) = get_evm(&TEST_CONFIG);
@@ -70,6 +194,8 @@ fn end_soft_confirmation_hook_sets_head() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 54,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -145,6 +271,8 @@ fn end_soft_confirmation_hook_moves_transactions_and_receipts() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -168,9 +296,7 @@ fn end_soft_confirmation_hook_moves_transactions_and_receipts() {
     );
 
     assert_eq!(
-        evm.receipts
-            .iter(&mut working_set.accessory_state())
-            .collect::<Vec<_>>(),
+        stored_receipts(&evm, &mut working_set),
         [tx1.receipt, tx2.receipt]
     );
 
@@ -191,6 +317,89 @@ fn end_soft_confirmation_hook_moves_transactions_and_receipts() {
     assert_eq!(evm.pending_transactions.len(&mut working_set), 0);
 }
 
+#[test]
+fn finalize_hook_backfills_block_receipts() {
+    let (evm, mut working_set) = get_evm(&TEST_CONFIG);
+
+    let produce_block = |working_set: &mut WorkingSet<DefaultContext>,
+                         txs: &[PendingTransaction],
+                         root_hash: [u8; 32]| {
+        evm.begin_soft_confirmation_hook(
+            &HookSoftConfirmationInfo {
+                da_slot_hash: DA_ROOT_HASH.0,
+                da_slot_height: 1,
+                da_slot_txs_commitment: [42u8; 32],
+                pre_state_root: [10u8; 32].to_vec(),
+                pub_key: vec![],
+                deposit_data: vec![],
+                l1_fee_rate: 0,
+                timestamp: 0,
+                da_slot_timestamp: 0,
+                slot_inbox: Default::default(),
+            },
+            working_set,
+        );
+        for tx in txs {
+            evm.pending_transactions.push(tx, working_set);
+        }
+        evm.end_soft_confirmation_hook(working_set);
+        evm.finalize_hook(&root_hash.into(), &mut working_set.accessory_state());
+    };
+    // Stores the receipts of the block like nodes did before `block_receipts`
+    let move_to_legacy_receipts = |working_set: &mut WorkingSet<DefaultContext>,
+                                   block_number: u64| {
+        let mut accessory_state = working_set.accessory_state();
+        let block_receipts = evm
+            .block_receipts
+            .get(&block_number, &mut accessory_state)
+            .unwrap();
+        evm.block_receipts
+            .delete(&block_number, &mut accessory_state);
+        for receipt in block_receipts.receipts() {
+            evm.receipts.push(&receipt, &mut accessory_state);
+        }
+    };
+
+    let mut txs: Vec<_> = (1..=3)
+        .map(|index| create_pending_transaction(B256::from([index as u8; 32]), index))
+        .collect();
+    txs[0].transaction.block_number = 2;
+    txs[1].transaction.block_number = 2;
+    txs[2].transaction.block_number = 3;
+
+    produce_block(&mut working_set, &txs[..2], [99u8; 32]);
+    move_to_legacy_receipts(&mut working_set, 2);
+    produce_block(&mut working_set, &txs[2..], [100u8; 32]);
+    move_to_legacy_receipts(&mut working_set, 3);
+    assert_eq!(evm.receipts.len(&mut working_set.accessory_state()), 3);
+
+    // Receipts of the blocks not moved yet are read from the legacy receipts
+    let block = evm
+        .blocks
+        .get(2, &mut working_set.accessory_state())
+        .unwrap();
+    assert_eq!(
+        evm.stored_block_receipts(&block, &mut working_set)
+            .receipts(),
+        [txs[0].receipt.clone(), txs[1].receipt.clone()]
+    );
+
+    produce_block(&mut working_set, &[], [101u8; 32]);
+
+    assert_eq!(evm.receipts.len(&mut working_set.accessory_state()), 0);
+    assert_eq!(
+        stored_receipts(&evm, &mut working_set),
+        txs.iter().map(|tx| tx.receipt.clone()).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        evm.block_receipts
+            .get(&3, &mut working_set.accessory_state())
+            .unwrap()
+            .receipts(),
+        [txs[2].receipt.clone()]
+    );
+}
+
 fn create_pending_transaction(hash: B256, index: u64) -> PendingTransaction {
     PendingTransaction {
         transaction: TransactionSignedAndRecovered {
@@ -250,6 +459,8 @@ fn finalize_hook_creates_final_block() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 54,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -280,6 +491,8 @@ fn finalize_hook_creates_final_block() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 54,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -366,6 +579,8 @@ fn begin_soft_confirmation_hook_appends_last_block_hashes() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -407,6 +622,8 @@ fn begin_soft_confirmation_hook_appends_last_block_hashes() {
                 deposit_data: vec![],
                 l1_fee_rate,
                 timestamp: 0,
+                da_slot_timestamp: 0,
+                slot_inbox: Default::default(),
             },
             &mut working_set,
         );
@@ -429,6 +646,8 @@ fn begin_soft_confirmation_hook_appends_last_block_hashes() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
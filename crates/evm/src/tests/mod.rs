@@ -1,5 +1,6 @@
 mod call_tests;
 mod cfg_tests;
+mod compact_receipts_tests;
 mod ef_tests;
 mod genesis_tests;
 mod hooks_tests;
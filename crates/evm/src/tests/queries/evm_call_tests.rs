@@ -80,6 +80,8 @@ fn test_state_change() {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -75,6 +75,8 @@ fn log_filter_test_at_block_hash() {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -281,6 +283,8 @@ fn log_filter_test_with_range() {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -342,6 +346,8 @@ fn log_filter_test_with_range() {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -403,6 +409,8 @@ fn test_log_limits() {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -503,6 +511,8 @@ fn test_log_limits() {
                 deposit_data: vec![],
                 l1_fee_rate: 1,
                 timestamp: 0,
+                da_slot_timestamp: 0,
+                slot_inbox: Default::default(),
             },
             &mut working_set,
         );
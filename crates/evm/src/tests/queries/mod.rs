@@ -71,6 +71,8 @@ fn init_evm() -> (Evm<C>, WorkingSet<C>, TestSigner) {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 24,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -111,6 +113,8 @@ fn init_evm() -> (Evm<C>, WorkingSet<C>, TestSigner) {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 24,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -152,6 +156,8 @@ fn init_evm() -> (Evm<C>, WorkingSet<C>, TestSigner) {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 24,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -228,6 +234,8 @@ pub fn init_evm_single_block() -> (Evm<C>, WorkingSet<C>, TestSigner) {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -299,6 +307,8 @@ pub fn init_evm_with_caller_contract() -> (Evm<C>, WorkingSet<C>, TestSigner) {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -338,6 +348,8 @@ pub fn init_evm_with_caller_contract() -> (Evm<C>, WorkingSet<C>, TestSigner) {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
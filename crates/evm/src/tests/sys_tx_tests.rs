@@ -14,6 +14,7 @@ use sov_modules_api::{Context, Module, StateMapAccessor, StateVecAccessor};
 use crate::call::CallMessage;
 use crate::evm::primitive_types::Receipt;
 use crate::evm::system_contracts::BitcoinLightClient;
+use crate::evm::system_events::SystemEvent;
 use crate::smart_contracts::{BlockHashContract, LogsContract};
 use crate::system_contracts::{Bridge, ProxyAdmin};
 use crate::tests::call_tests::{
@@ -21,8 +22,11 @@ use crate::tests::call_tests::{
     publish_event_message,
 };
 use crate::tests::test_signer::TestSigner;
-use crate::tests::utils::get_evm;
-use crate::{AccountData, EvmConfig, BASE_FEE_VAULT, L1_FEE_VAULT, SYSTEM_SIGNER};
+use crate::tests::utils::{get_evm, stored_receipts};
+use crate::{
+    AccountData, EvmConfig, BASE_FEE_VAULT, FEE_VAULT_SWEEP_INTERVAL, L1_FEE_VAULT,
+    PRIORITY_FEE_VAULT, SYSTEM_SIGNER,
+};
 
 type C = DefaultContext;
 
@@ -36,9 +40,7 @@ fn test_sys_bitcoin_light_client() {
     let (evm, mut working_set) = get_evm(&config);
 
     assert_eq!(
-        evm.receipts
-            .iter(&mut working_set.accessory_state())
-            .collect::<Vec<_>>(),
+        stored_receipts(&evm, &mut working_set),
         [
             Receipt { // BitcoinLightClient::initializeBlockNumber(U256)
                 receipt: reth_primitives::Receipt {
@@ -148,6 +150,8 @@ fn test_sys_bitcoin_light_client() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 42,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -176,10 +180,7 @@ fn test_sys_bitcoin_light_client() {
     assert_eq!(system_account.info.balance, U256::from(0));
     assert_eq!(system_account.info.nonce, 4);
 
-    let receipts: Vec<_> = evm
-        .receipts
-        .iter(&mut working_set.accessory_state())
-        .collect();
+    let receipts: Vec<_> = stored_receipts(&evm, &mut working_set);
     assert_eq!(receipts.len(), 5); // 3 from first L2 block + 2 from second L2 block
     let receipts = receipts[3..].to_vec();
 
@@ -284,6 +285,8 @@ fn test_sys_tx_gas_usage_effect_on_block_gas_limit() {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -315,6 +318,8 @@ fn test_sys_tx_gas_usage_effect_on_block_gas_limit() {
             deposit_data: vec![],
             l1_fee_rate,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -452,6 +457,8 @@ fn test_bridge() {
             .to_vec()],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -525,6 +532,8 @@ fn test_upgrade_light_client() {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -649,6 +658,8 @@ fn test_change_upgrade_owner() {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -684,6 +695,8 @@ fn test_change_upgrade_owner() {
             deposit_data: vec![],
             l1_fee_rate: 1,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -758,6 +771,47 @@ fn test_change_upgrade_owner() {
     );
 }
 
+#[test]
+fn test_fee_vault_sweeps() {
+    let (mut config, _, _) =
+        get_evm_config_starting_base_fee(U256::from_str("1000000").unwrap(), None, 1);
+
+    let recipient =
+        U256::from_be_slice(address!("0000000000000000000000000000000000000abc").as_slice());
+    let min_withdraw = U256::from(1000);
+    // Only the base fee vault can be swept, the L1 fee vault holds less than
+    // its minimum withdraw amount and the priority fee vault has no recipient
+    for (vault, balance, recipient) in [
+        (BASE_FEE_VAULT, U256::from(1000), recipient),
+        (L1_FEE_VAULT, U256::from(999), recipient),
+        (PRIORITY_FEE_VAULT, U256::from(1000), U256::ZERO),
+    ] {
+        config.data.push(AccountData::new(
+            vault,
+            balance,
+            Bytes::from_static(&hex!("00")),
+            0,
+            [(U256::ZERO, recipient), (U256::from(1), min_withdraw)]
+                .into_iter()
+                .collect(),
+        ));
+    }
+
+    let (evm, mut working_set) = get_evm(&config);
+
+    assert!(evm
+        .fee_vault_sweeps(FEE_VAULT_SWEEP_INTERVAL - 1, &mut working_set)
+        .is_empty());
+    assert_eq!(
+        evm.fee_vault_sweeps(FEE_VAULT_SWEEP_INTERVAL, &mut working_set),
+        vec![SystemEvent::FeeVaultWithdraw(BASE_FEE_VAULT)]
+    );
+    assert_eq!(
+        evm.fee_vault_sweeps(2 * FEE_VAULT_SWEEP_INTERVAL, &mut working_set),
+        vec![SystemEvent::FeeVaultWithdraw(BASE_FEE_VAULT)]
+    );
+}
+
 fn config_push_contracts(config: &mut EvmConfig) {
     config.data.push(AccountData::new(
         BitcoinLightClient::address(),
@@ -162,3 +162,51 @@ fn prepare_call_block_env() {
     assert_eq!(block_env.gas_limit, block.header.gas_limit);
     assert_eq!(block_env.prevrandao, block.header.mix_hash);
 }
+
+#[test]
+fn forward_request_signing_hash_matches_forwarder() {
+    use alloy_primitives::keccak256;
+
+    use crate::system_contracts::{ForwardRequest, TrustedForwarder};
+
+    let request = ForwardRequest {
+        from: Address::from([1u8; 20]),
+        to: Address::from([2u8; 20]),
+        value: U256::ZERO,
+        gas: U256::from(100_000),
+        nonce: U256::from(3),
+        deadline: U256::from(1_700_000_000),
+        data: Bytes::from(vec![4u8; 36]),
+    };
+
+    // Hashed the way TrustedForwarder.sol does
+    let word = |address: Address| address.into_word().0;
+    let mut domain = Vec::new();
+    domain.extend(keccak256(
+        "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)",
+    ));
+    domain.extend(keccak256("CitreaForwarder"));
+    domain.extend(keccak256("1"));
+    domain.extend(U256::from(DEFAULT_CHAIN_ID).to_be_bytes::<32>());
+    domain.extend(word(TrustedForwarder::address()));
+
+    let mut request_data = Vec::new();
+    request_data.extend(keccak256(
+        "ForwardRequest(address from,address to,uint256 value,uint256 gas,uint256 nonce,uint256 deadline,bytes data)",
+    ));
+    request_data.extend(word(request.from));
+    request_data.extend(word(request.to));
+    for value in [request.value, request.gas, request.nonce, request.deadline] {
+        request_data.extend(value.to_be_bytes::<32>());
+    }
+    request_data.extend(keccak256(&request.data));
+
+    let mut digest = vec![0x19, 0x01];
+    digest.extend(keccak256(domain));
+    digest.extend(keccak256(request_data));
+
+    assert_eq!(
+        TrustedForwarder::signing_hash(&request, DEFAULT_CHAIN_ID),
+        keccak256(digest)
+    );
+}
@@ -3,10 +3,11 @@ use reth_primitives::hex_literal::hex;
 use reth_primitives::B256;
 use sov_modules_api::default_context::DefaultContext;
 use sov_modules_api::hooks::HookSoftConfirmationInfo;
-use sov_modules_api::{Module, WorkingSet};
+use sov_modules_api::{Module, StateMapAccessor, StateValueAccessor, WorkingSet};
 use sov_prover_storage_manager::{new_orphan_storage, SnapshotManager};
 use sov_state::{DefaultStorageSpec, ProverStorage, Storage};
 
+use crate::evm::primitive_types::Receipt;
 use crate::{Evm, EvmConfig};
 
 type C = DefaultContext;
@@ -65,6 +66,8 @@ pub(crate) fn get_evm(config: &EvmConfig) -> (Evm<C>, WorkingSet<C>) {
             deposit_data: vec![],
             l1_fee_rate: 0,
             timestamp: 0,
+            da_slot_timestamp: 0,
+            slot_inbox: Default::default(),
         },
         &mut working_set,
     );
@@ -101,3 +104,20 @@ pub(crate) fn commit(
 
     root.0
 }
+
+/// Receipts of the transactions of all the blocks up to the head, in order
+pub(crate) fn stored_receipts(evm: &Evm<C>, working_set: &mut WorkingSet<C>) -> Vec<Receipt> {
+    let head_number = evm
+        .head
+        .get(working_set)
+        .expect("Head block should be set")
+        .header
+        .number;
+    (0..=head_number)
+        .filter_map(|number| {
+            evm.block_receipts
+                .get(&number, &mut working_set.accessory_state())
+        })
+        .flat_map(|block_receipts| block_receipts.receipts())
+        .collect()
+}
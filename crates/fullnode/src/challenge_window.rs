@@ -0,0 +1,116 @@
+use sov_db::ledger_db::NodeLedgerOps;
+use sov_db::schema::types::{BatchNumber, ChallengeableCommitment, SlotNumber};
+use sov_rollup_interface::da::SequencerCommitment;
+use sov_rollup_interface::rpc::{
+    CommitmentChallengeResponse, CommitmentChallengeStatus, SoftConfirmationStatus,
+};
+use tracing::{info, warn};
+
+use crate::metrics::{
+    CHALLENGEABLE_COMMITMENTS, EXPIRED_CHALLENGE_WINDOWS, PROVEN_IN_CHALLENGE_WINDOW,
+};
+
+/// Challenge status of the commitment once the L1 block at `l1_height` is processed.
+pub(crate) fn challenge_status<DB: NodeLedgerOps>(
+    ledger_db: &DB,
+    commitment: &SequencerCommitment,
+    challengeable_until: u64,
+    l1_height: u64,
+) -> anyhow::Result<CommitmentChallengeStatus> {
+    let end_status =
+        ledger_db.get_l2_soft_confirmation_status(BatchNumber(commitment.l2_end_block_number))?;
+    if end_status == Some(SoftConfirmationStatus::Proven) {
+        return Ok(CommitmentChallengeStatus::Proven);
+    }
+    if l1_height >= challengeable_until {
+        return Ok(CommitmentChallengeStatus::Expired);
+    }
+    Ok(CommitmentChallengeStatus::Challengeable)
+}
+
+/// Opens the challenge windows of the commitments found on the L1 block at `l1_height`,
+/// and closes the windows of the commitments which are proven or expired once it is processed.
+/// The open windows are only written when one of them is opened or closed.
+pub fn update_challenge_windows<DB: NodeLedgerOps>(
+    ledger_db: &DB,
+    challenge_window: u64,
+    l1_height: u64,
+    new_commitments: Vec<SequencerCommitment>,
+) -> anyhow::Result<()> {
+    let mut challengeable = ledger_db.get_challengeable_commitments()?;
+    let mut changed = false;
+
+    for commitment in new_commitments {
+        if challengeable
+            .iter()
+            .any(|challengeable| challengeable.commitment == commitment)
+        {
+            continue;
+        }
+        // The window of a commitment posted again was opened by the first one
+        if ledger_db
+            .get_l1_height_of_sequencer_commitment(&commitment)?
+            .is_some_and(|first_l1_height| first_l1_height.0 != l1_height)
+        {
+            continue;
+        }
+        challengeable.push(ChallengeableCommitment {
+            commitment,
+            l1_height: SlotNumber(l1_height),
+            challengeable_until: SlotNumber(l1_height + challenge_window),
+        });
+        changed = true;
+    }
+
+    let open_windows = challengeable.len();
+    let mut still_challengeable = Vec::with_capacity(challengeable.len());
+    for commitment in challengeable {
+        match challenge_status(
+            ledger_db,
+            &commitment.commitment,
+            commitment.challengeable_until.0,
+            l1_height,
+        )? {
+            CommitmentChallengeStatus::Challengeable => still_challengeable.push(commitment),
+            CommitmentChallengeStatus::Proven => {
+                PROVEN_IN_CHALLENGE_WINDOW.inc();
+                info!(
+                    "Sequencer commitment of L2 blocks {}-{} is proven within its challenge window",
+                    commitment.commitment.l2_start_block_number,
+                    commitment.commitment.l2_end_block_number
+                );
+            }
+            CommitmentChallengeStatus::Expired => {
+                EXPIRED_CHALLENGE_WINDOWS.inc();
+                warn!(
+                    "Challenge window of the sequencer commitment of L2 blocks {}-{} closed at L1 height {} without a proof",
+                    commitment.commitment.l2_start_block_number,
+                    commitment.commitment.l2_end_block_number,
+                    commitment.challengeable_until.0
+                );
+            }
+        }
+    }
+    CHALLENGEABLE_COMMITMENTS.set(still_challengeable.len() as i64);
+    if !changed && still_challengeable.len() == open_windows {
+        return Ok(());
+    }
+
+    still_challengeable.sort_by_key(|commitment| commitment.l1_height);
+    ledger_db.set_challengeable_commitments(&still_challengeable)
+}
+
+pub(crate) fn challenge_response(
+    commitment: &SequencerCommitment,
+    found_in_l1: u64,
+    challengeable_until: u64,
+    status: CommitmentChallengeStatus,
+) -> CommitmentChallengeResponse {
+    CommitmentChallengeResponse {
+        found_in_l1,
+        l2_start_block_number: commitment.l2_start_block_number,
+        l2_end_block_number: commitment.l2_end_block_number,
+        challengeable_until,
+        status,
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::VecDeque;
+
+use citrea_primitives::SharedClock;
+use sov_db::ledger_db::NodeLedgerOps;
+use sov_db::schema::types::{BatchNumber, ReorgHaltReport, SlotNumber};
+use sov_rollup_interface::da::{BlobReaderTrait, BlockHeaderTrait};
+use sov_rollup_interface::services::da::{DaService, SlotData};
+use sov_stf_runner::DaMonitorConfig;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::metrics::{DA_DEEP_REORGS, DA_LAST_REORG_DEPTH, DA_REORGS};
+
+/// A block of the L1 chain tracked by the DA monitor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackedBlock {
+    pub height: u64,
+    pub hash: [u8; 32],
+    pub prev_hash: [u8; 32],
+    /// Hashes of the Citrea blobs in the block
+    pub blob_hashes: Vec<[u8; 32]>,
+}
+
+/// A reorg of the L1 chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reorg {
+    /// Height of the last block both chains have in common.
+    /// Only an upper bound if the reorg is deeper than the tracked chain.
+    pub fork_height: u64,
+    /// Number of tracked blocks which are replaced
+    pub depth: u64,
+    /// Hashes of the Citrea blobs of the replaced blocks which are not in the new chain
+    pub dropped_blobs: Vec<[u8; 32]>,
+}
+
+/// Keeps the recent blocks of the L1 chain to detect reorgs.
+#[derive(Debug)]
+pub struct DaChainTracker {
+    capacity: usize,
+    /// Consecutive blocks of the best chain, in ascending heights
+    blocks: VecDeque<TrackedBlock>,
+}
+
+impl DaChainTracker {
+    /// Creates a tracker keeping enough blocks to measure reorgs up to twice the finality depth
+    pub fn new(finality_depth: u64) -> Self {
+        Self {
+            capacity: 2 * (finality_depth as usize + 1),
+            blocks: VecDeque::new(),
+        }
+    }
+
+    /// The tracked tip of the chain
+    pub fn tip(&self) -> Option<&TrackedBlock> {
+        self.blocks.back()
+    }
+
+    /// The tracked block at the height
+    pub fn get(&self, height: u64) -> Option<&TrackedBlock> {
+        let first = self.blocks.front()?;
+        self.blocks
+            .get(height.checked_sub(first.height)?.try_into().ok()?)
+    }
+
+    /// Whether the block is a child of a tracked block
+    pub fn connects(&self, block: &TrackedBlock) -> bool {
+        block
+            .height
+            .checked_sub(1)
+            .and_then(|height| self.get(height))
+            .is_some_and(|parent| parent.hash == block.prev_hash)
+    }
+
+    /// Whether the parent of a block at the height is not tracked
+    pub fn is_before_window(&self, height: u64) -> bool {
+        self.blocks
+            .front()
+            .map_or(true, |first| height <= first.height)
+    }
+
+    /// Height of the block the tracked chain is moved to next, towards the head at the height.
+    /// A head too far ahead is caught up with in steps, so the walk back from each step still
+    /// reaches the tracked chain and reorgs of the tracked blocks are detected.
+    pub fn next_height(&self, head_height: u64) -> u64 {
+        self.tip().map_or(head_height, |tip| {
+            head_height.min(tip.height + self.capacity as u64)
+        })
+    }
+
+    /// Adds consecutive blocks to the tracked chain, replacing the tracked blocks from the height
+    /// of the first one on. Returns the reorg if tracked blocks are replaced.
+    pub fn apply(&mut self, new_blocks: Vec<TrackedBlock>) -> Option<Reorg> {
+        let first = new_blocks.first()?;
+        let reorg = match (self.blocks.front(), self.tip()) {
+            (Some(front), Some(tip)) => {
+                let tip_height = tip.height;
+                // If the new blocks don't connect, the fork is below the tracked chain
+                let fork_height = if self.connects(first) {
+                    first.height - 1
+                } else {
+                    first.height.min(front.height).saturating_sub(1)
+                };
+                let mut replaced = Vec::new();
+                while self.tip().is_some_and(|tip| tip.height > fork_height) {
+                    replaced.extend(self.blocks.pop_back());
+                }
+
+                (!replaced.is_empty()).then(|| Reorg {
+                    fork_height,
+                    depth: tip_height - fork_height,
+                    dropped_blobs: replaced
+                        .iter()
+                        .flat_map(|block| &block.blob_hashes)
+                        .filter(|hash| {
+                            !new_blocks
+                                .iter()
+                                .any(|block| block.blob_hashes.contains(*hash))
+                        })
+                        .copied()
+                        .collect(),
+                })
+            }
+            _ => None,
+        };
+
+        self.blocks.extend(new_blocks);
+        while self.blocks.len() > self.capacity {
+            self.blocks.pop_front();
+        }
+        reorg
+    }
+}
+
+/// Follows the head of the L1 chain and sends its reorgs to `reorg_tx`.
+/// The runner applies the halts of the reorgs between blocks, see [`halt_after_reorg`].
+pub(crate) async fn da_monitor<Da>(
+    da_service: Da,
+    config: DaMonitorConfig,
+    reorg_tx: mpsc::UnboundedSender<Reorg>,
+    clock: SharedClock,
+) where
+    Da: DaService,
+{
+    let mut tracker = DaChainTracker::new(
+        config
+            .finality_depth
+            .max(config.max_handled_reorg_depth.unwrap_or_default()),
+    );
+    info!(
+        "Monitoring DA reorgs with finality depth {}",
+        config.finality_depth
+    );
+
+    loop {
+        match poll_head(&da_service, &mut tracker).await {
+            Ok(Some(reorg)) => {
+                DA_REORGS.inc();
+                DA_LAST_REORG_DEPTH.set(reorg.depth as i64);
+                warn!(
+                    "DA reorg of {} blocks after L1 height {}, {} Citrea blobs dropped: {:?}",
+                    reorg.depth,
+                    reorg.fork_height,
+                    reorg.dropped_blobs.len(),
+                    reorg
+                        .dropped_blobs
+                        .iter()
+                        .map(hex::encode)
+                        .collect::<Vec<_>>()
+                );
+                if reorg.depth > config.finality_depth {
+                    DA_DEEP_REORGS.inc();
+                }
+                if reorg_tx.send(reorg).is_err() {
+                    // The runner has stopped
+                    return;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => error!("Could not monitor DA reorgs: {}", e),
+        }
+
+        clock.sleep(Duration::from_secs(config.poll_interval)).await;
+    }
+}
+
+/// Halts of the node caused by a DA reorg
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ReorgHalts {
+    /// The reorg is deeper than the finality depth. It replaced L1 blocks the node scanned
+    /// as final, sequencer commitments are not accepted until it is resumed.
+    pub commitments: bool,
+    /// The reorg is deeper than the max handled reorg depth,
+    /// L2 blocks are not executed until it is resumed.
+    pub execution: bool,
+}
+
+/// Persists the reports of the halts the reorg causes, so that the node stays halted
+/// across restarts until it is resumed with `citrea_resumeAfterReorg`.
+///
+/// The runner calls it between blocks, so no block is processed once the reorg is applied
+/// and the reports hold the last L2 height committed before the halt.
+pub fn halt_after_reorg<DB: NodeLedgerOps>(
+    ledger_db: &DB,
+    config: &DaMonitorConfig,
+    reorg: &Reorg,
+    halted_at: u64,
+) -> ReorgHalts {
+    let halts = ReorgHalts {
+        commitments: reorg.depth > config.finality_depth,
+        execution: config
+            .max_handled_reorg_depth
+            .is_some_and(|max_depth| reorg.depth > max_depth),
+    };
+    if halts.commitments {
+        let report = reorg_report(ledger_db, reorg, config.finality_depth, halted_at);
+        if let Err(e) = report.and_then(|report| ledger_db.set_commitments_halt(&report)) {
+            error!(
+                "Could not persist the DA reorg commitments halt report: {}",
+                e
+            );
+        }
+        error!(
+            "DA reorg of {} blocks is deeper than the finality depth {}, no sequencer commitment is accepted until resumed with citrea_resumeAfterReorg",
+            reorg.depth,
+            config.finality_depth
+        );
+    }
+    if let Some(max_handled_reorg_depth) =
+        config.max_handled_reorg_depth.filter(|_| halts.execution)
+    {
+        let report = reorg_report(ledger_db, reorg, max_handled_reorg_depth, halted_at);
+        if let Err(e) = report.and_then(|report| ledger_db.set_reorg_halt(&report)) {
+            error!("Could not persist the DA reorg halt report: {}", e);
+        }
+        error!(
+            "DA reorg of {} blocks is deeper than the max handled reorg depth {}, execution is halted until resumed with citrea_resumeAfterReorg",
+            reorg.depth,
+            max_handled_reorg_depth
+        );
+    }
+    halts
+}
+
+/// The report of the reorg which is persisted, so that the node stays halted after a restart
+fn reorg_report<DB: NodeLedgerOps>(
+    ledger_db: &DB,
+    reorg: &Reorg,
+    max_handled_reorg_depth: u64,
+    halted_at: u64,
+) -> anyhow::Result<ReorgHaltReport> {
+    let l2_height = ledger_db
+        .get_head_soft_batch()?
+        .map_or(BatchNumber(0), |(l2_height, _)| l2_height);
+    Ok(ReorgHaltReport {
+        fork_height: SlotNumber(reorg.fork_height),
+        depth: reorg.depth,
+        max_handled_reorg_depth,
+        dropped_blobs: reorg.dropped_blobs.clone(),
+        l2_height,
+        halted_at,
+    })
+}
+
+/// Moves the tracked chain to the head of the L1 chain.
+/// Returns the first reorg found on the way.
+async fn poll_head<Da: DaService>(
+    da_service: &Da,
+    tracker: &mut DaChainTracker,
+) -> Result<Option<Reorg>, anyhow::Error> {
+    let head = da_service
+        .get_head_block_header()
+        .await
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+    let head_hash: [u8; 32] = head.hash().into();
+
+    while tracker.tip().map_or(true, |tip| tip.hash != head_hash) {
+        let next_height = tracker.next_height(head.height());
+        let next_hash = if next_height == head.height() {
+            head_hash
+        } else {
+            da_service
+                .get_block_at(next_height)
+                .await
+                .map_err(|e| anyhow::anyhow!("{}", e))?
+                .header()
+                .hash()
+                .into()
+        };
+        if let Some(reorg) = move_to(da_service, tracker, next_hash).await? {
+            return Ok(Some(reorg));
+        }
+    }
+    Ok(None)
+}
+
+/// Moves the tracked chain to the block with the hash, which is at most the capacity of the
+/// tracker ahead of its tip
+async fn move_to<Da: DaService>(
+    da_service: &Da,
+    tracker: &mut DaChainTracker,
+    block_hash: [u8; 32],
+) -> Result<Option<Reorg>, anyhow::Error> {
+    // Walks back from the block until the new blocks connect to the tracked chain
+    let mut new_blocks = VecDeque::new();
+    let mut hash = block_hash;
+    loop {
+        let block = da_service
+            .get_block_by_hash(hash)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        let block = tracked_block(da_service, &block);
+        hash = block.prev_hash;
+        let done = tracker.connects(&block) || tracker.is_before_window(block.height);
+        new_blocks.push_front(block);
+        if done {
+            break;
+        }
+    }
+
+    Ok(tracker.apply(new_blocks.into()))
+}
+
+fn tracked_block<Da: DaService>(da_service: &Da, block: &Da::FilteredBlock) -> TrackedBlock {
+    let header = block.header();
+    TrackedBlock {
+        height: header.height(),
+        hash: header.hash().into(),
+        prev_hash: header.prev_hash().into(),
+        blob_hashes: da_service
+            .extract_relevant_blobs(block)
+            .iter()
+            .map(|blob| blob.hash())
+            .collect(),
+    }
+}
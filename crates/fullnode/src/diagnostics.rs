@@ -0,0 +1,47 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use sov_rollup_interface::soft_confirmation::SignedSoftConfirmationBatch;
+use tracing::info;
+
+/// Inputs and outputs of an L2 block whose computed state root differs from the signed one,
+/// enough to re-execute it outside of the node
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StateRootMismatchDiagnostics<'a, StateRoot, Header, Witness> {
+    pub l2_height: u64,
+    /// State root the L2 block is executed on
+    pub pre_state_root: &'a StateRoot,
+    /// State root the sequencer signed for the L2 block
+    #[serde(with = "hex::serde")]
+    pub expected_state_root: &'a [u8],
+    /// State root the full node computed for the L2 block
+    pub state_root: &'a StateRoot,
+    /// The L2 block as it is passed to the STF, before its execution
+    pub soft_batch: &'a SignedSoftConfirmationBatch,
+    /// Header of the L1 block the L2 block is executed on
+    pub l1_block_header: &'a Header,
+    /// State read and written by the execution of the L2 block
+    pub witness: &'a Witness,
+}
+
+/// Writes the diagnostics of the L2 block as JSON into the directory, and returns the path of the file.
+pub(crate) fn write_state_root_mismatch_diagnostics<StateRoot, Header, Witness>(
+    dir: &Path,
+    diagnostics: &StateRootMismatchDiagnostics<'_, StateRoot, Header, Witness>,
+) -> anyhow::Result<PathBuf>
+where
+    StateRoot: Serialize,
+    Header: Serialize,
+    Witness: Serialize,
+{
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("l2-block-{}.json", diagnostics.l2_height));
+    std::fs::write(&path, serde_json::to_vec_pretty(diagnostics)?)?;
+    info!(
+        "Recorded the inputs of L2 block #{} to {}",
+        diagnostics.l2_height,
+        path.display()
+    );
+    Ok(path)
+}
@@ -1,13 +1,30 @@
 use std::net::SocketAddr;
 
+pub use challenge_window::update_challenge_windows;
+pub use da_monitor::{halt_after_reorg, DaChainTracker, Reorg, ReorgHalts, TrackedBlock};
+pub use light_verifier::*;
+pub use replica::*;
 pub use runner::*;
 use sov_db::ledger_db::LedgerDB;
+use sov_modules_api::Spec;
 use sov_modules_rollup_blueprint::RollupBlueprint;
 use sov_modules_stf_blueprint::StfBlueprint;
+use sov_state::Storage;
 use tokio::sync::oneshot;
 use tracing::instrument;
+pub use watchtower::{
+    check_withdrawal_claims, check_withdrawal_roots, BridgeWatchtower, WatchtowerAlert,
+};
 
+mod challenge_window;
+mod da_monitor;
+mod diagnostics;
+mod light_verifier;
+mod metrics;
+mod replica;
+mod rpc;
 mod runner;
+mod watchtower;
 
 /// Dependencies needed to run the rollup.
 pub struct FullNode<S: RollupBlueprint> {
@@ -34,7 +51,7 @@ impl<S: RollupBlueprint> FullNode<S> {
 
     /// Only run the rpc.
     pub async fn run_rpc(self) -> Result<(), anyhow::Error> {
-        self.runner.start_rpc_server(self.rpc_methods, None).await;
+        self.runner.start_rpc_server(self.rpc_methods, None).await?;
         Ok(())
     }
 
@@ -44,7 +61,69 @@ impl<S: RollupBlueprint> FullNode<S> {
         channel: Option<oneshot::Sender<SocketAddr>>,
     ) -> Result<(), anyhow::Error> {
         let mut runner = self.runner;
-        runner.start_rpc_server(self.rpc_methods, channel).await;
+        runner.start_rpc_server(self.rpc_methods, channel).await?;
+
+        runner.run().await?;
+        Ok(())
+    }
+}
+
+/// Dependencies needed to run the light verifier.
+pub struct LightVerifier<S: RollupBlueprint> {
+    /// The light verifier runner.
+    #[allow(clippy::type_complexity)]
+    pub runner: CitreaLightVerifier<
+        S::DaService,
+        S::Vm,
+        <<S::NativeContext as Spec>::Storage as Storage>::Root,
+        LedgerDB,
+    >,
+    /// Rpc methods for the light verifier.
+    pub rpc_methods: jsonrpsee::RpcModule<()>,
+}
+
+impl<S: RollupBlueprint> LightVerifier<S> {
+    /// Runs the light verifier.
+    #[instrument(level = "trace", skip(self), err, ret(level = "error"))]
+    pub async fn run(self) -> Result<(), anyhow::Error> {
+        self.run_and_report_rpc_port(None).await
+    }
+
+    /// Runs the light verifier. Reports rpc port to the caller using the provided channel.
+    pub async fn run_and_report_rpc_port(
+        self,
+        channel: Option<oneshot::Sender<SocketAddr>>,
+    ) -> Result<(), anyhow::Error> {
+        let mut runner = self.runner;
+        runner.start_rpc_server(self.rpc_methods, channel).await?;
+
+        runner.run().await?;
+        Ok(())
+    }
+}
+
+/// Dependencies needed to run a read-only RPC replica.
+pub struct RpcReplica<S: RollupBlueprint> {
+    /// The replica runner.
+    pub runner: CitreaRpcReplica<S::StorageManager, S::DaSpec>,
+    /// Rpc methods for the replica.
+    pub rpc_methods: jsonrpsee::RpcModule<()>,
+}
+
+impl<S: RollupBlueprint> RpcReplica<S> {
+    /// Runs the replica.
+    #[instrument(level = "trace", skip(self), err, ret(level = "error"))]
+    pub async fn run(self) -> Result<(), anyhow::Error> {
+        self.run_and_report_rpc_port(None).await
+    }
+
+    /// Runs the replica. Reports rpc port to the caller using the provided channel.
+    pub async fn run_and_report_rpc_port(
+        self,
+        channel: Option<oneshot::Sender<SocketAddr>>,
+    ) -> Result<(), anyhow::Error> {
+        let mut runner = self.runner;
+        runner.start_rpc_server(self.rpc_methods, channel).await?;
 
         runner.run().await?;
         Ok(())
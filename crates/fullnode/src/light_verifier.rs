@@ -0,0 +1,604 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail};
+use borsh::de::BorshDeserialize;
+use citrea_primitives::{L1BlockCache, SystemClock};
+use citrea_sequencer_registry::is_sequencer_da_pub_key_at;
+use jsonrpsee::RpcModule;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sov_db::ledger_db::NodeLedgerOps;
+use sov_db::schema::types::{
+    BatchNumber, SlotNumber, StoredSequencerCommitment, StoredStateTransition, VerifiedStateRoot,
+};
+use sov_rollup_interface::da::{
+    BlobReaderTrait, BlockHeaderTrait, CommitmentWithStateDiff, DaData, SequencerCommitment,
+};
+use sov_rollup_interface::rpc::SoftConfirmationStatus;
+use sov_rollup_interface::services::da::{DaService, SlotData};
+use sov_rollup_interface::zk::{AggregatedStateTransition, Proof, StateTransition, Zkvm, ZkvmHost};
+use sov_stf_runner::{
+    spawn_rpc_server, ProvingStrategy, RollupPublicKeys, RpcConfig, RpcServerOptions, RunnerConfig,
+};
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tracing::{error, info, instrument, warn};
+
+use crate::metrics::DUPLICATE_COMMITMENTS;
+use crate::rpc::{
+    create_light_verifier_rpc_module, create_rpc_module, LightVerifierRpcContext, RpcContext,
+};
+use crate::runner::l1_sync;
+
+/// Node which only follows the DA layer. It verifies the posted sequencer commitments
+/// and ZK proofs, without fetching or executing L2 blocks.
+pub struct CitreaLightVerifier<Da, Vm, Root, DB>
+where
+    Da: DaService,
+    Vm: ZkvmHost + Zkvm,
+    DB: NodeLedgerOps,
+{
+    start_l1_height: u64,
+    da_service: Da,
+    ledger_db: DB,
+    genesis_state_root: Root,
+    rpc_config: RpcConfig,
+    sequencer_pub_key: Vec<u8>,
+    public_keys: RollupPublicKeys,
+    prover_da_pub_key: Vec<u8>,
+    code_commitment: Vm::CodeCommitment,
+    aggregation_code_commitment: Vm::CodeCommitment,
+    accept_public_input_as_proven: bool,
+    proving_strategy: ProvingStrategy,
+    l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
+}
+
+impl<Da, Vm, Root, DB> CitreaLightVerifier<Da, Vm, Root, DB>
+where
+    Da: DaService<Error = anyhow::Error> + Clone + Send + Sync + 'static,
+    Vm: ZkvmHost + Zkvm,
+    Root: Serialize + DeserializeOwned + BorshDeserialize + AsRef<[u8]>,
+    DB: NodeLedgerOps,
+{
+    /// Creates a new light verifier.
+    ///
+    /// Proofs are only accepted if they link back to the given genesis state root.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        runner_config: RunnerConfig,
+        public_keys: RollupPublicKeys,
+        rpc_config: RpcConfig,
+        da_service: Da,
+        ledger_db: DB,
+        genesis_state_root: Root,
+        code_commitment: Vm::CodeCommitment,
+        aggregation_code_commitment: Vm::CodeCommitment,
+    ) -> Result<Self, anyhow::Error> {
+        // Last L1 height processed before shutdown
+        let start_l1_height = ledger_db
+            .get_sync_checkpoint()?
+            .unwrap_or_default()
+            .l1_height
+            .0;
+        info!("Resuming light verifier from L1 height {}", start_l1_height);
+
+        Ok(Self {
+            start_l1_height,
+            da_service,
+            ledger_db,
+            genesis_state_root,
+            rpc_config,
+            sequencer_pub_key: public_keys.sequencer_public_key.clone(),
+            prover_da_pub_key: public_keys.prover_da_pub_key.clone(),
+            public_keys,
+            code_commitment,
+            aggregation_code_commitment,
+            accept_public_input_as_proven: runner_config
+                .accept_public_input_as_proven
+                .unwrap_or(false),
+            proving_strategy: runner_config.proving_strategy,
+            l1_block_cache: Arc::new(Mutex::new(L1BlockCache::new())),
+        })
+    }
+
+    /// Starts a RPC server with provided rpc methods.
+    pub async fn start_rpc_server(
+        &self,
+        methods: RpcModule<()>,
+        channel: Option<oneshot::Sender<SocketAddr>>,
+    ) -> anyhow::Result<()>
+    where
+        DB: Clone + Send + Sync + 'static,
+    {
+        let methods = self.register_rpc_methods(methods)?;
+        spawn_rpc_server(
+            &self.rpc_config,
+            methods,
+            self.ledger_db.clone(),
+            RpcServerOptions::default(),
+            channel,
+        )
+    }
+
+    /// Updates the given RpcModule with light verifier methods.
+    pub fn register_rpc_methods(
+        &self,
+        mut rpc_methods: RpcModule<()>,
+    ) -> Result<RpcModule<()>, jsonrpsee::core::RegisterMethodError>
+    where
+        DB: Clone + Send + Sync + 'static,
+    {
+        let rpc = create_rpc_module(RpcContext {
+            ledger_db: self.ledger_db.clone(),
+        })?;
+        rpc_methods.merge(rpc)?;
+
+        let rpc = create_light_verifier_rpc_module(LightVerifierRpcContext {
+            da_service: self.da_service.clone(),
+            ledger_db: self.ledger_db.clone(),
+        })?;
+        rpc_methods.merge(rpc)?;
+
+        Ok(rpc_methods)
+    }
+
+    /// Runs the light verifier.
+    #[instrument(level = "trace", skip_all, err)]
+    pub async fn run(&mut self) -> Result<(), anyhow::Error> {
+        let (l1_tx, mut l1_rx) = mpsc::channel(1);
+        let l1_sync_worker = l1_sync(
+            self.start_l1_height,
+            self.da_service.clone(),
+            l1_tx,
+            self.l1_block_cache.clone(),
+            SystemClock::shared(),
+        );
+        tokio::pin!(l1_sync_worker);
+
+        loop {
+            tokio::select! {
+                _ = &mut l1_sync_worker => {},
+                Some(l1_block) = l1_rx.recv() => {
+                    self.process_l1_block(l1_block).await?;
+                },
+            }
+        }
+    }
+
+    async fn process_l1_block(&self, l1_block: Da::FilteredBlock) -> anyhow::Result<()> {
+        let l1_height = l1_block.header().height();
+        self.ledger_db
+            .set_l1_height_of_l1_hash(l1_block.header().hash().into(), l1_height)?;
+
+        let (sequencer_commitments, zk_proofs) = self.extract_relevant_l1_data(&l1_block);
+
+        for zk_proof in zk_proofs {
+            let result = match zk_proof {
+                DaData::AggregatedZKProof(proof) => {
+                    self.process_aggregated_zk_proof(&l1_block, proof).await
+                }
+                DaData::ZKProof(proof) => self.process_zk_proof(&l1_block, proof).await,
+                _ => unreachable!("Only proofs are extracted"),
+            };
+            if let Err(e) = result {
+                error!("Could not process ZK proof: {}... skipping", e);
+            }
+        }
+
+        for (sequencer_commitment, da_tx_id) in sequencer_commitments {
+            self.process_sequencer_commitment(l1_height, sequencer_commitment, da_tx_id)?;
+        }
+
+        self.ledger_db
+            .set_sync_checkpoint_l1_height(SlotNumber(l1_height))?;
+
+        Ok(())
+    }
+
+    /// Records a sequencer commitment. The DA layer authenticates the sender, but the
+    /// merkle root cannot be checked against L2 blocks, as they are not synced.
+    fn process_sequencer_commitment(
+        &self,
+        l1_height: u64,
+        sequencer_commitment: SequencerCommitment,
+        da_tx_id: Option<[u8; 32]>,
+    ) -> anyhow::Result<()> {
+        info!(
+            "Recording sequencer commitment. L2 Range = {}-{}.",
+            sequencer_commitment.l2_start_block_number, sequencer_commitment.l2_end_block_number,
+        );
+
+        if let Some(first_l1_height) = self
+            .ledger_db
+            .get_l1_height_of_sequencer_commitment(&sequencer_commitment)?
+        {
+            if first_l1_height.0 != l1_height {
+                DUPLICATE_COMMITMENTS.inc();
+                warn!(
+                    "Skipping duplicate sequencer commitment of L2 blocks {}-{}, first posted at L1 height {}",
+                    sequencer_commitment.l2_start_block_number,
+                    sequencer_commitment.l2_end_block_number,
+                    first_l1_height.0
+                );
+                return Ok(());
+            }
+        }
+
+        for i in
+            sequencer_commitment.l2_start_block_number..=sequencer_commitment.l2_end_block_number
+        {
+            self.ledger_db
+                .put_soft_confirmation_status(BatchNumber(i), SoftConfirmationStatus::Finalized)?;
+        }
+        if let Some(da_tx_id) = da_tx_id {
+            self.ledger_db
+                .index_sequencer_commitment(&StoredSequencerCommitment {
+                    da_tx_id,
+                    l1_height: SlotNumber(l1_height),
+                    commitment: sequencer_commitment.clone(),
+                })?;
+        }
+        self.ledger_db
+            .update_commitments_on_da_slot(l1_height, sequencer_commitment)?;
+
+        Ok(())
+    }
+
+    async fn process_zk_proof(
+        &self,
+        l1_block: &Da::FilteredBlock,
+        proof: Proof,
+    ) -> anyhow::Result<()> {
+        info!(
+            "Processing zk proof at height: {}",
+            l1_block.header().height()
+        );
+
+        let state_transition = match &proof {
+            Proof::Full(serialized_proof) => {
+                let Ok(state_transition) = Vm::verify_and_extract_output::<Da::Spec, Root>(
+                    serialized_proof,
+                    &self.code_commitment,
+                ) else {
+                    bail!("Proof verification: SNARK verification failed");
+                };
+                state_transition
+            }
+            Proof::PublicInput(_) => {
+                if !self.accept_public_input_as_proven {
+                    bail!(
+                        "Found public input in da block number: {}",
+                        l1_block.header().height()
+                    );
+                }
+                // public input is accepted only in tests, so ok to expect
+                let state_transition: StateTransition<Da::Spec, Root> =
+                    Vm::extract_output(&proof).expect("Proof should be deserializable");
+                if !self
+                    .proving_strategy
+                    .may_prove(&state_transition.da_slot_hash.clone().into())
+                {
+                    bail!(
+                        "Found public input of an L1 block the prover does not prove in da block number: {}",
+                        l1_block.header().height()
+                    );
+                }
+                state_transition
+            }
+        };
+
+        // Commitments proven by the proof were read from this L1 block
+        let l1_hash: [u8; 32] = state_transition.da_slot_hash.clone().into();
+        if !self
+            .is_sequencer_da_pub_key_of_slot(l1_hash, &state_transition.sequencer_da_public_key)?
+            || state_transition.sequencer_public_key != self.sequencer_pub_key
+        {
+            bail!("Proof verification: Sequencer public key or sequencer da public key mismatch");
+        }
+        let (first_l2_height, last_l2_height) =
+            self.proven_l2_range(l1_hash, state_transition.sequencer_commitments_range)?;
+
+        let stored_state_transition = StoredStateTransition {
+            initial_state_root: state_transition.initial_state_root.as_ref().to_vec(),
+            final_state_root: state_transition.final_state_root.as_ref().to_vec(),
+            state_diff: state_transition.state_diff,
+            da_slot_hash: l1_hash,
+            sequencer_commitments_range: state_transition.sequencer_commitments_range,
+            sequencer_public_key: state_transition.sequencer_public_key,
+            sequencer_da_public_key: state_transition.sequencer_da_public_key,
+            validity_condition: borsh::to_vec(&state_transition.validity_condition).unwrap(),
+        };
+        self.verify_l2_range(
+            l1_block,
+            proof,
+            first_l2_height,
+            last_l2_height,
+            stored_state_transition,
+        )
+    }
+
+    async fn process_aggregated_zk_proof(
+        &self,
+        l1_block: &Da::FilteredBlock,
+        proof: Proof,
+    ) -> anyhow::Result<()> {
+        info!(
+            "Processing aggregated zk proof at height: {}",
+            l1_block.header().height()
+        );
+
+        let aggregated = match &proof {
+            Proof::Full(serialized_proof) => {
+                let Ok(aggregated) = Vm::verify_and_extract_aggregated_output::<Da::Spec, Root>(
+                    serialized_proof,
+                    &self.aggregation_code_commitment,
+                ) else {
+                    bail!("Proof verification: SNARK verification failed");
+                };
+                aggregated
+            }
+            Proof::PublicInput(_) => {
+                if !self.accept_public_input_as_proven {
+                    bail!(
+                        "Found public input in da block number: {}",
+                        l1_block.header().height()
+                    );
+                }
+                // public input is accepted only in tests, so ok to expect
+                let aggregated: AggregatedStateTransition<Da::Spec, Root> =
+                    Vm::extract_aggregated_output(&proof).expect("Proof should be deserializable");
+                if !aggregated.da_slot_hashes.iter().all(|da_slot_hash| {
+                    self.proving_strategy
+                        .may_prove(&da_slot_hash.clone().into())
+                }) {
+                    bail!(
+                        "Found public input of an L1 block the prover does not prove in da block number: {}",
+                        l1_block.header().height()
+                    );
+                }
+                aggregated
+            }
+        };
+
+        if aggregated.range_code_commitment != self.code_commitment.as_ref() {
+            bail!("Proof verification: Aggregated proofs are not proven with the expected code commitment");
+        }
+
+        // The aggregated state transitions are consecutive, so they prove
+        // the L2 blocks from the first range of the first DA slot to the last range of the last one.
+        let l1_hashes: Vec<[u8; 32]> = aggregated
+            .da_slot_hashes
+            .iter()
+            .map(|da_slot_hash| da_slot_hash.clone().into())
+            .collect();
+        if aggregated.sequencer_commitments_ranges.len() != l1_hashes.len() {
+            bail!("Proof verification: Aggregated proof does not have a commitments range per DA slot");
+        }
+        // The DA key of the sequencer may be rotated between the aggregated DA slots
+        if aggregated.sequencer_da_public_keys.len() != l1_hashes.len()
+            || aggregated.sequencer_public_key != self.sequencer_pub_key
+        {
+            bail!("Proof verification: Sequencer public key or sequencer da public key mismatch");
+        }
+        for (l1_hash, da_pub_key) in l1_hashes.iter().zip(&aggregated.sequencer_da_public_keys) {
+            if !self.is_sequencer_da_pub_key_of_slot(*l1_hash, da_pub_key)? {
+                bail!("Proof verification: Sequencer da public key mismatch");
+            }
+        }
+        let ranges = &aggregated.sequencer_commitments_ranges;
+        let (
+            Some(first_l1_hash),
+            Some(last_l1_hash),
+            Some(first_range),
+            Some(last_range),
+            Some(last_da_pub_key),
+        ) = (
+            l1_hashes.first(),
+            l1_hashes.last(),
+            ranges.first(),
+            ranges.last(),
+            aggregated.sequencer_da_public_keys.last(),
+        )
+        else {
+            bail!("Proof verification: Aggregated proof has no state transitions");
+        };
+        let (first_l2_height, _) = self.proven_l2_range(*first_l1_hash, *first_range)?;
+        let (_, last_l2_height) = self.proven_l2_range(*last_l1_hash, *last_range)?;
+
+        // A stored state transition refers to a single DA slot, the last aggregated one
+        let stored_state_transition = StoredStateTransition {
+            initial_state_root: aggregated.initial_state_root.as_ref().to_vec(),
+            final_state_root: aggregated.final_state_root.as_ref().to_vec(),
+            state_diff: aggregated.state_diff,
+            da_slot_hash: *last_l1_hash,
+            sequencer_commitments_range: *last_range,
+            sequencer_public_key: aggregated.sequencer_public_key,
+            sequencer_da_public_key: last_da_pub_key.clone(),
+            validity_condition: borsh::to_vec(&aggregated.validity_conditions).unwrap(),
+        };
+        self.verify_l2_range(
+            l1_block,
+            proof,
+            first_l2_height,
+            last_l2_height,
+            stored_state_transition,
+        )
+    }
+
+    /// Whether the key is the DA public key of the sequencer at the L1 height of the DA slot,
+    /// the only key the commitments of the DA slot are accepted from
+    fn is_sequencer_da_pub_key_of_slot(
+        &self,
+        l1_hash: [u8; 32],
+        da_pub_key: &[u8],
+    ) -> anyhow::Result<bool> {
+        let l1_height = self
+            .ledger_db
+            .get_l1_height_of_l1_hash(l1_hash)?
+            .ok_or(anyhow!(
+                "Proof verification: L1 height not found for l1 hash: {:?}",
+                l1_hash
+            ))?;
+        // The light verifier does not execute the L2 blocks, so it does not track the
+        // sequencers registered on chain
+        Ok(is_sequencer_da_pub_key_at(
+            self.public_keys.sequencer_da_pub_key_at(l1_height),
+            None,
+            da_pub_key,
+        ))
+    }
+
+    /// Returns the L2 range of the commitments in `range` among the ones read from the DA slot.
+    fn proven_l2_range(&self, l1_hash: [u8; 32], range: (u32, u32)) -> anyhow::Result<(u64, u64)> {
+        let commitments_l1_height =
+            self.ledger_db
+                .get_l1_height_of_l1_hash(l1_hash)?
+                .ok_or(anyhow!(
+                    "Proof verification: L1 height not found for l1 hash: {:?}",
+                    l1_hash
+                ))?;
+        let commitments = self
+            .ledger_db
+            .get_commitments_on_da_slot(commitments_l1_height)?
+            .ok_or(anyhow!(
+                "Proof verification: No commitments found for l1 height: {}",
+                commitments_l1_height
+            ))?;
+        let (start, end) = range;
+        let proven_commitments = commitments
+            .get(start as usize..=end as usize)
+            .ok_or(anyhow!(
+                "Proof verification: Commitment range {}-{} is out of bounds",
+                start,
+                end
+            ))?;
+
+        let first_l2_height = proven_commitments[0].l2_start_block_number;
+        let last_l2_height = proven_commitments[proven_commitments.len() - 1].l2_end_block_number;
+
+        Ok((first_l2_height, last_l2_height))
+    }
+
+    /// Marks the L2 range as proven if it follows the last verified state root,
+    /// and moves the verified state root to the end of the range.
+    fn verify_l2_range(
+        &self,
+        l1_block: &Da::FilteredBlock,
+        proof: Proof,
+        first_l2_height: u64,
+        last_l2_height: u64,
+        stored_state_transition: StoredStateTransition,
+    ) -> anyhow::Result<()> {
+        let last_verified = self
+            .ledger_db
+            .get_last_verified_state_root()?
+            .unwrap_or_else(|| VerifiedStateRoot {
+                l1_height: SlotNumber(0),
+                l2_height: BatchNumber(0),
+                state_root: self.genesis_state_root.as_ref().to_vec(),
+            });
+
+        if last_l2_height <= last_verified.l2_height.0 {
+            info!(
+                "Proof verification: L2 range {}-{} is already verified",
+                first_l2_height, last_l2_height
+            );
+            return Ok(());
+        }
+        if first_l2_height != last_verified.l2_height.0 + 1 {
+            bail!(
+                "Proof verification: L2 range {}-{} does not follow last verified L2 height {}",
+                first_l2_height,
+                last_l2_height,
+                last_verified.l2_height.0
+            );
+        }
+        if stored_state_transition.initial_state_root != last_verified.state_root {
+            bail!(
+                "Proof verification: Pre state root mismatch - expected 0x{} but got 0x{}",
+                hex::encode(&last_verified.state_root),
+                hex::encode(&stored_state_transition.initial_state_root)
+            );
+        }
+
+        for i in first_l2_height..=last_l2_height {
+            self.ledger_db
+                .put_soft_confirmation_status(BatchNumber(i), SoftConfirmationStatus::Proven)?;
+        }
+        self.ledger_db
+            .set_last_proven_l2_height(BatchNumber(last_l2_height))?;
+
+        let verified_state_root = VerifiedStateRoot {
+            l1_height: SlotNumber(l1_block.header().height()),
+            l2_height: BatchNumber(last_l2_height),
+            state_root: stored_state_transition.final_state_root.clone(),
+        };
+
+        self.ledger_db.update_verified_proof_data(
+            l1_block.header().height(),
+            proof,
+            stored_state_transition,
+        )?;
+
+        info!(
+            "Verified state root after L2 height {} is: 0x{}",
+            last_l2_height,
+            hex::encode(&verified_state_root.state_root)
+        );
+        self.ledger_db
+            .set_last_verified_state_root(&verified_state_root)?;
+
+        Ok(())
+    }
+
+    fn extract_relevant_l1_data(
+        &self,
+        l1_block: &Da::FilteredBlock,
+    ) -> (Vec<(SequencerCommitment, Option<[u8; 32]>)>, Vec<DaData>) {
+        let mut sequencer_commitments = Vec::<(SequencerCommitment, Option<[u8; 32]>)>::new();
+        let mut zk_proofs = Vec::<DaData>::new();
+        let sequencer_da_pub_key = self
+            .public_keys
+            .sequencer_da_pub_key_at(l1_block.header().height());
+
+        for mut tx in self.da_service.extract_relevant_blobs(l1_block) {
+            let sender = tx.sender();
+            let from_sequencer =
+                is_sequencer_da_pub_key_at(sequencer_da_pub_key, None, sender.as_ref());
+            if !from_sequencer && sender.as_ref() != self.prover_da_pub_key.as_slice() {
+                continue;
+            }
+
+            match DaData::decode(tx.full_data()) {
+                Ok(
+                    DaData::SequencerCommitment(seq_com)
+                    | DaData::CommitmentWithStateDiff(CommitmentWithStateDiff {
+                        commitment: seq_com,
+                        ..
+                    }),
+                ) if from_sequencer => {
+                    sequencer_commitments.push((seq_com, tx.da_tx_id()));
+                }
+                Ok(proof @ (DaData::ZKProof(_) | DaData::AggregatedZKProof(_)))
+                    if sender.as_ref() == self.prover_da_pub_key.as_slice() =>
+                {
+                    zk_proofs.push(proof);
+                }
+                // Light client proofs are verified by light clients, not by the full node
+                Ok(DaData::LightClientProof(_)) => {}
+                // Leases only fence the instances of the sequencer
+                Ok(DaData::SequencerLease(_)) if from_sequencer => {}
+                // Withdrawal claims are checked by the watchtower of the full node
+                Ok(DaData::WithdrawalClaim(_)) => {}
+                data => {
+                    warn!(
+                        "Found broken DA data in block 0x{}: {:?}",
+                        hex::encode(l1_block.hash()),
+                        data
+                    );
+                }
+            }
+        }
+        (sequencer_commitments, zk_proofs)
+    }
+}
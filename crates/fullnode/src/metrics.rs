@@ -0,0 +1,152 @@
+use once_cell::sync::Lazy;
+use prometheus::{register_int_counter, register_int_gauge, IntCounter, IntGauge};
+
+pub static DA_REORGS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "fullnode_da_reorgs",
+        // metric description
+        "Reorgs of the DA layer seen by the DA monitor"
+    )
+    .unwrap()
+});
+
+pub static DA_DEEP_REORGS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "fullnode_da_deep_reorgs",
+        // metric description
+        "Reorgs of the DA layer replacing more blocks than the finality depth"
+    )
+    .unwrap()
+});
+
+pub static DA_LAST_REORG_DEPTH: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "fullnode_da_last_reorg_depth",
+        // metric description
+        "Number of DA blocks replaced by the last reorg"
+    )
+    .unwrap()
+});
+
+pub static DA_COMMITMENTS_HALTED: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "fullnode_da_commitments_halted",
+        // metric description
+        "1 if sequencer commitments are not accepted anymore because of a deep DA reorg"
+    )
+    .unwrap()
+});
+
+pub static DA_EXECUTION_HALTED: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "fullnode_da_execution_halted",
+        // metric description
+        "1 if the execution of L2 blocks is halted because of a DA reorg deeper than the node handles"
+    )
+    .unwrap()
+});
+
+pub static STATE_ROOT_MISMATCH_HALTED: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "fullnode_state_root_mismatch_halted",
+        // metric description
+        "1 if the execution of L2 blocks is halted because a computed state root differs from the signed one"
+    )
+    .unwrap()
+});
+
+pub static COMMITTED_CONTIGUOUS_L2_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "fullnode_committed_contiguous_l2_height",
+        // metric description
+        "Highest L2 height up to which every L2 height is covered by a sequencer commitment"
+    )
+    .unwrap()
+});
+
+pub static COMMITMENT_GAPS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "fullnode_commitment_gaps",
+        // metric description
+        "Number of L2 ranges no sequencer commitment covers, before the last commitment seen"
+    )
+    .unwrap()
+});
+
+pub static COMMITMENT_OVERLAPS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "fullnode_commitment_overlaps",
+        // metric description
+        "Sequencer commitments which covered L2 heights covered already"
+    )
+    .unwrap()
+});
+
+pub static WATCHTOWER_ALERTS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "fullnode_watchtower_alerts",
+        // metric description
+        "Violations of the bridge invariants found by the watchtower"
+    )
+    .unwrap()
+});
+
+pub static WATCHTOWER_PENDING_WITHDRAWALS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "fullnode_watchtower_pending_withdrawals",
+        // metric description
+        "Withdrawals initiated on L2 which are not proven on L1 yet"
+    )
+    .unwrap()
+});
+
+pub static DUPLICATE_COMMITMENTS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "fullnode_duplicate_commitments",
+        // metric description
+        "Sequencer commitments posted again on a later L1 block, which are skipped"
+    )
+    .unwrap()
+});
+
+pub static CHALLENGEABLE_COMMITMENTS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "fullnode_challengeable_commitments",
+        // metric description
+        "Sequencer commitments whose challenge window is open and which no proof covers yet"
+    )
+    .unwrap()
+});
+
+pub static PROVEN_IN_CHALLENGE_WINDOW: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "fullnode_proven_in_challenge_window",
+        // metric description
+        "Sequencer commitments a proof covered before their challenge window closed"
+    )
+    .unwrap()
+});
+
+pub static EXPIRED_CHALLENGE_WINDOWS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "fullnode_expired_challenge_windows",
+        // metric description
+        "Sequencer commitments whose challenge window closed before a proof covered them"
+    )
+    .unwrap()
+});
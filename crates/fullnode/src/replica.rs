@@ -0,0 +1,107 @@
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use jsonrpsee::RpcModule;
+use sov_db::ledger_db::LedgerDB;
+use sov_rollup_interface::da::DaSpec;
+use sov_rollup_interface::storage::HierarchicalStorageManager;
+use sov_stf_runner::{spawn_rpc_server, ReplicaConfig, RpcConfig, RpcServerOptions};
+use tokio::sync::oneshot;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{debug, error, instrument};
+
+use crate::rpc::{create_rpc_module, RpcContext};
+
+/// Node which serves RPC from the databases of another, syncing node.
+/// The databases are opened in secondary mode and periodically caught up with the primary,
+/// so any number of replicas can attach to the same storage.
+pub struct CitreaRpcReplica<Sm, Da>
+where
+    Da: DaSpec,
+    Sm: HierarchicalStorageManager<Da>,
+{
+    storage_manager: Sm,
+    ledger_db: LedgerDB,
+    rpc_config: RpcConfig,
+    catch_up_interval: Duration,
+    phantom: PhantomData<Da>,
+}
+
+impl<Sm, Da> CitreaRpcReplica<Sm, Da>
+where
+    Da: DaSpec,
+    Sm: HierarchicalStorageManager<Da>,
+{
+    /// Creates a new replica.
+    ///
+    /// Both `storage_manager` and `ledger_db` must be opened in secondary mode.
+    pub fn new(
+        replica_config: ReplicaConfig,
+        rpc_config: RpcConfig,
+        storage_manager: Sm,
+        ledger_db: LedgerDB,
+    ) -> Self {
+        Self {
+            storage_manager,
+            ledger_db,
+            rpc_config,
+            catch_up_interval: Duration::from_millis(replica_config.catch_up_interval_ms),
+            phantom: PhantomData,
+        }
+    }
+
+    /// Starts a RPC server with provided rpc methods.
+    pub async fn start_rpc_server(
+        &self,
+        methods: RpcModule<()>,
+        channel: Option<oneshot::Sender<SocketAddr>>,
+    ) -> anyhow::Result<()> {
+        let methods = self.register_rpc_methods(methods)?;
+        spawn_rpc_server(
+            &self.rpc_config,
+            methods,
+            self.ledger_db.clone(),
+            RpcServerOptions::default(),
+            channel,
+        )
+    }
+
+    /// Updates the given RpcModule with replica methods.
+    pub fn register_rpc_methods(
+        &self,
+        mut rpc_methods: RpcModule<()>,
+    ) -> Result<RpcModule<()>, jsonrpsee::core::RegisterMethodError> {
+        let rpc = create_rpc_module(RpcContext {
+            ledger_db: self.ledger_db.clone(),
+        })?;
+        rpc_methods.merge(rpc)?;
+        Ok(rpc_methods)
+    }
+
+    /// Periodically catches up the databases with the primary node.
+    #[instrument(level = "trace", skip_all, err)]
+    pub async fn run(&mut self) -> Result<(), anyhow::Error> {
+        let mut catch_up_interval = interval(self.catch_up_interval);
+        catch_up_interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            catch_up_interval.tick().await;
+            // A failed catch up only delays new data, the replica keeps serving what it has
+            if let Err(e) = self.catch_up_with_primary() {
+                error!("Could not catch up with primary: {:?}", e);
+            }
+        }
+    }
+
+    fn catch_up_with_primary(&self) -> anyhow::Result<()> {
+        // Primary commits ledger data before state, so catching up the state first
+        // never exposes state of an L2 block which is missing in the ledger
+        tokio::task::block_in_place(|| {
+            self.storage_manager.try_catch_up_with_primary()?;
+            self.ledger_db.try_catch_up_with_primary()
+        })?;
+        debug!("Caught up with primary");
+        Ok(())
+    }
+}
@@ -0,0 +1,413 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::RpcModule;
+use rs_merkle::algorithms::Sha256;
+use rs_merkle::MerkleTree;
+use serde::Deserialize;
+use sov_db::backup::backup_path;
+use sov_db::ledger_db::NodeLedgerOps;
+use sov_db::schema::types::BatchNumber;
+use sov_modules_api::utils::to_jsonrpsee_error_object;
+use sov_rollup_interface::da::BlockHeaderTrait;
+use sov_rollup_interface::rpc::{
+    BlockInclusionProofResponse, CommitmentChallengeResponse, CommitmentChallengeStatus,
+    CommitmentCoverageResponse, DaInclusionProofResponse, HexHash,
+    IndexedSequencerCommitmentResponse, ReorgHaltResponse, StateRootMismatchResponse,
+    VerifiedStateRootResponse,
+};
+use sov_rollup_interface::services::da::{DaService, SlotData};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{debug, warn};
+
+use crate::challenge_window::{challenge_response, challenge_status};
+use crate::metrics::{DA_COMMITMENTS_HALTED, DA_EXECUTION_HALTED, STATE_ROOT_MISMATCH_HALTED};
+use crate::runner::BackupRequest;
+
+pub(crate) struct RpcContext<DB: NodeLedgerOps> {
+    pub ledger_db: DB,
+}
+
+/// Hex encoded id of a DA transaction
+#[derive(Deserialize)]
+struct DaTxId(#[serde(with = "sov_rollup_interface::rpc::utils::rpc_hex")] [u8; 32]);
+
+pub(crate) struct BackupRpcContext {
+    pub backup_tx: mpsc::Sender<BackupRequest>,
+    pub backup_dir: PathBuf,
+}
+
+pub(crate) struct ReorgHaltRpcContext<DB: NodeLedgerOps> {
+    pub ledger_db: DB,
+    pub commitments_halted: Arc<AtomicBool>,
+    pub execution_halted: Arc<AtomicBool>,
+}
+
+pub(crate) struct StateRootMismatchRpcContext<DB: NodeLedgerOps> {
+    pub ledger_db: DB,
+    pub state_root_mismatch_halted: Arc<AtomicBool>,
+}
+
+pub(crate) struct HealthRpcContext {
+    pub state_root_mismatch_halted: Arc<AtomicBool>,
+    pub execution_halted: Arc<AtomicBool>,
+}
+
+pub(crate) struct ChallengeWindowRpcContext<DB: NodeLedgerOps> {
+    pub ledger_db: DB,
+    pub challenge_window: u64,
+}
+
+pub(crate) struct LightVerifierRpcContext<Da: DaService, DB: NodeLedgerOps> {
+    pub da_service: Da,
+    pub ledger_db: DB,
+}
+
+pub(crate) fn create_rpc_module<DB: NodeLedgerOps + Send + Sync + 'static>(
+    rpc_context: RpcContext<DB>,
+) -> Result<RpcModule<RpcContext<DB>>, jsonrpsee::core::RegisterMethodError> {
+    let mut rpc = RpcModule::new(rpc_context);
+
+    rpc.register_async_method("citrea_getProvenHeight", |_, ctx| async move {
+        debug!("Full Node: citrea_getProvenHeight");
+        ctx.ledger_db
+            .get_last_proven_l2_height()
+            .map(|l2_height| l2_height.map(|l2_height| l2_height.0))
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))
+    })?;
+
+    rpc.register_async_method(
+        "citrea_getSequencerCommitmentByTxid",
+        |params, ctx| async move {
+            let DaTxId(da_tx_id) = params.one()?;
+            debug!(
+                "Full Node: citrea_getSequencerCommitmentByTxid({})",
+                hex::encode(da_tx_id)
+            );
+            ctx.ledger_db
+                .get_sequencer_commitment_by_da_tx_id(da_tx_id)
+                .map(|commitment| commitment.map(IndexedSequencerCommitmentResponse::from))
+                .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))
+        },
+    )?;
+
+    rpc.register_async_method(
+        "citrea_getSequencerCommitmentByL2Height",
+        |params, ctx| async move {
+            let l2_height: u64 = params.one()?;
+            debug!(
+                "Full Node: citrea_getSequencerCommitmentByL2Height({})",
+                l2_height
+            );
+            ctx.ledger_db
+                .get_sequencer_commitment_by_l2_height(BatchNumber(l2_height))
+                .map(|commitment| commitment.map(IndexedSequencerCommitmentResponse::from))
+                .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))
+        },
+    )?;
+
+    rpc.register_async_method("citrea_getCommitmentCoverage", |_, ctx| async move {
+        debug!("Full Node: citrea_getCommitmentCoverage");
+        ctx.ledger_db
+            .get_commitment_coverage()
+            .map(|coverage| CommitmentCoverageResponse::from(coverage.unwrap_or_default()))
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))
+    })?;
+
+    rpc.register_async_method("citrea_getBlockInclusionProof", |params, ctx| async move {
+        let l2_height: u64 = params.one()?;
+        debug!("Full Node: citrea_getBlockInclusionProof({})", l2_height);
+        block_inclusion_proof(&ctx.ledger_db, l2_height)
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))
+    })?;
+
+    rpc.register_async_method("citrea_getReorgHalt", |_, ctx| async move {
+        debug!("Full Node: citrea_getReorgHalt");
+        ctx.ledger_db
+            .get_reorg_halt()
+            .map(|report| report.map(ReorgHaltResponse::from))
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))
+    })?;
+
+    rpc.register_async_method("citrea_getStateRootMismatch", |_, ctx| async move {
+        debug!("Full Node: citrea_getStateRootMismatch");
+        ctx.ledger_db
+            .get_state_root_mismatch()
+            .map(|report| report.map(StateRootMismatchResponse::from))
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))
+    })?;
+
+    Ok(rpc)
+}
+
+/// Proof that the L2 block is in the sequencer commitment of its range,
+/// `None` if the block is not in a commitment found on DA yet
+fn block_inclusion_proof<DB: NodeLedgerOps>(
+    ledger_db: &DB,
+    l2_height: u64,
+) -> anyhow::Result<Option<BlockInclusionProofResponse>> {
+    let Some(commitment) =
+        ledger_db.get_sequencer_commitment_by_l2_height(BatchNumber(l2_height))?
+    else {
+        return Ok(None);
+    };
+    let commitment = IndexedSequencerCommitmentResponse::from(commitment);
+    let l2_start = commitment.l2_start_block_number;
+    let l2_end = commitment.l2_end_block_number;
+
+    let soft_confirmation_hashes = ledger_db
+        .soft_batch_iter(BatchNumber(l2_start)..BatchNumber(l2_end + 1))?
+        .map(|soft_batch| soft_batch.map(|soft_batch| soft_batch.hash))
+        .collect::<anyhow::Result<Vec<[u8; 32]>>>()?;
+    anyhow::ensure!(
+        soft_confirmation_hashes.len() as u64 == l2_end - l2_start + 1,
+        "L2 blocks {}-{} of the commitment are not all synced",
+        l2_start,
+        l2_end
+    );
+
+    let tree = MerkleTree::<Sha256>::from_leaves(&soft_confirmation_hashes);
+    anyhow::ensure!(
+        tree.root() == Some(commitment.merkle_root),
+        "Merkle root of the L2 blocks {}-{} does not match their commitment",
+        l2_start,
+        l2_end
+    );
+
+    let leaf_index = (l2_height - l2_start) as usize;
+    let proof = tree.proof(&[leaf_index]);
+    Ok(Some(BlockInclusionProofResponse {
+        l2_height,
+        soft_confirmation_hash: soft_confirmation_hashes[leaf_index],
+        leaf_index: leaf_index as u64,
+        leaf_count: soft_confirmation_hashes.len() as u64,
+        proof: proof.proof_hashes().iter().copied().map(HexHash).collect(),
+        commitment,
+    }))
+}
+
+pub(crate) fn create_backup_rpc_module(
+    rpc_context: BackupRpcContext,
+) -> Result<RpcModule<BackupRpcContext>, jsonrpsee::core::RegisterMethodError> {
+    let mut rpc = RpcModule::new(rpc_context);
+
+    rpc.register_async_method("citrea_createBackup", |params, ctx| async move {
+        let name: String = params.one()?;
+        debug!("Full Node: citrea_createBackup({})", name);
+
+        let path = backup_path(&ctx.backup_dir, &name)
+            .map_err(|e| to_jsonrpsee_error_object("BACKUP_ERROR", e))?;
+
+        let (reply_tx, reply_rx) = oneshot::channel();
+        ctx.backup_tx
+            .send((path, reply_tx))
+            .await
+            .map_err(|e| to_jsonrpsee_error_object("BACKUP_ERROR", e))?;
+        reply_rx
+            .await
+            .map_err(|e| to_jsonrpsee_error_object("BACKUP_ERROR", e))?
+            .map_err(|e| to_jsonrpsee_error_object("BACKUP_ERROR", e))
+    })?;
+
+    Ok(rpc)
+}
+
+pub(crate) fn create_reorg_halt_rpc_module<DB: NodeLedgerOps + Send + Sync + 'static>(
+    rpc_context: ReorgHaltRpcContext<DB>,
+) -> Result<RpcModule<ReorgHaltRpcContext<DB>>, jsonrpsee::core::RegisterMethodError> {
+    let mut rpc = RpcModule::new(rpc_context);
+
+    // Resumes both execution and the acceptance of sequencer commitments,
+    // returns whether execution was halted
+    rpc.register_async_method("citrea_resumeAfterReorg", |_, ctx| async move {
+        debug!("Full Node: citrea_resumeAfterReorg");
+        let report = ctx
+            .ledger_db
+            .get_reorg_halt()
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))?;
+        ctx.ledger_db
+            .clear_reorg_halt()
+            .and_then(|_| ctx.ledger_db.clear_commitments_halt())
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))?;
+        ctx.commitments_halted.store(false, Ordering::SeqCst);
+        ctx.execution_halted.store(false, Ordering::SeqCst);
+        DA_COMMITMENTS_HALTED.set(0);
+        DA_EXECUTION_HALTED.set(0);
+        if let Some(report) = &report {
+            warn!(
+                "Execution resumed by an operator after a DA reorg: {:?}",
+                report
+            );
+        }
+        Ok::<bool, ErrorObjectOwned>(report.is_some())
+    })?;
+
+    Ok(rpc)
+}
+
+pub(crate) fn create_state_root_mismatch_rpc_module<DB: NodeLedgerOps + Send + Sync + 'static>(
+    rpc_context: StateRootMismatchRpcContext<DB>,
+) -> Result<RpcModule<StateRootMismatchRpcContext<DB>>, jsonrpsee::core::RegisterMethodError> {
+    let mut rpc = RpcModule::new(rpc_context);
+
+    // Resumes execution with the L2 block whose state root did not match,
+    // returns whether execution was halted
+    rpc.register_async_method("citrea_clearStateRootMismatch", |_, ctx| async move {
+        debug!("Full Node: citrea_clearStateRootMismatch");
+        let report = ctx
+            .ledger_db
+            .get_state_root_mismatch()
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))?;
+        ctx.ledger_db
+            .clear_state_root_mismatch()
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))?;
+        ctx.state_root_mismatch_halted.store(false, Ordering::SeqCst);
+        STATE_ROOT_MISMATCH_HALTED.set(0);
+        if let Some(report) = &report {
+            warn!("State root mismatch cleared by an operator: {:?}", report);
+        }
+        Ok::<bool, ErrorObjectOwned>(report.is_some())
+    })?;
+
+    Ok(rpc)
+}
+
+pub(crate) fn create_health_rpc_module(
+    rpc_context: HealthRpcContext,
+) -> Result<RpcModule<HealthRpcContext>, jsonrpsee::core::RegisterMethodError> {
+    let mut rpc = RpcModule::new(rpc_context);
+
+    // Served on GET /health, fails while the execution of L2 blocks is halted
+    rpc.register_method("citrea_health", |_, ctx| {
+        if ctx.state_root_mismatch_halted.load(Ordering::SeqCst) {
+            return Err(to_jsonrpsee_error_object(
+                "UNHEALTHY",
+                "Execution is halted on a state root mismatch",
+            ));
+        }
+        if ctx.execution_halted.load(Ordering::SeqCst) {
+            return Err(to_jsonrpsee_error_object(
+                "UNHEALTHY",
+                "Execution is halted after a DA reorg",
+            ));
+        }
+        Ok::<(), ErrorObjectOwned>(())
+    })?;
+
+    Ok(rpc)
+}
+
+pub(crate) fn create_challenge_window_rpc_module<DB: NodeLedgerOps + Send + Sync + 'static>(
+    rpc_context: ChallengeWindowRpcContext<DB>,
+) -> Result<RpcModule<ChallengeWindowRpcContext<DB>>, jsonrpsee::core::RegisterMethodError> {
+    let mut rpc = RpcModule::new(rpc_context);
+
+    rpc.register_async_method("citrea_getChallengeableCommitments", |_, ctx| async move {
+        debug!("Full Node: citrea_getChallengeableCommitments");
+        ctx.ledger_db
+            .get_challengeable_commitments()
+            .map(|commitments| {
+                commitments
+                    .iter()
+                    .map(|commitment| {
+                        challenge_response(
+                            &commitment.commitment,
+                            commitment.l1_height.0,
+                            commitment.challengeable_until.0,
+                            CommitmentChallengeStatus::Challengeable,
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))
+    })?;
+
+    rpc.register_async_method(
+        "citrea_getCommitmentChallengeStatus",
+        |params, ctx| async move {
+            let l2_height: u64 = params.one()?;
+            debug!(
+                "Full Node: citrea_getCommitmentChallengeStatus({})",
+                l2_height
+            );
+            commitment_challenge_status(&ctx.ledger_db, ctx.challenge_window, l2_height)
+                .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))
+        },
+    )?;
+
+    Ok(rpc)
+}
+
+/// Challenge status of the sequencer commitment of the L2 block,
+/// `None` if the block is not in a commitment found on DA yet
+fn commitment_challenge_status<DB: NodeLedgerOps>(
+    ledger_db: &DB,
+    challenge_window: u64,
+    l2_height: u64,
+) -> anyhow::Result<Option<CommitmentChallengeResponse>> {
+    let Some(stored) = ledger_db.get_sequencer_commitment_by_l2_height(BatchNumber(l2_height))?
+    else {
+        return Ok(None);
+    };
+    let challengeable_until = stored.l1_height.0 + challenge_window;
+    let processed_l1_height = ledger_db
+        .get_sync_checkpoint()?
+        .map_or(stored.l1_height.0, |checkpoint| checkpoint.l1_height.0);
+    let status = challenge_status(
+        ledger_db,
+        &stored.commitment,
+        challengeable_until,
+        processed_l1_height,
+    )?;
+    Ok(Some(challenge_response(
+        &stored.commitment,
+        stored.l1_height.0,
+        challengeable_until,
+        status,
+    )))
+}
+
+pub(crate) fn create_light_verifier_rpc_module<Da, DB>(
+    rpc_context: LightVerifierRpcContext<Da, DB>,
+) -> Result<RpcModule<LightVerifierRpcContext<Da, DB>>, jsonrpsee::core::RegisterMethodError>
+where
+    Da: DaService<Error = anyhow::Error> + Send + Sync + 'static,
+    DB: NodeLedgerOps + Send + Sync + 'static,
+{
+    let mut rpc = RpcModule::new(rpc_context);
+
+    rpc.register_async_method("citrea_getLastVerifiedStateRoot", |_, ctx| async move {
+        debug!("Light Verifier: citrea_getLastVerifiedStateRoot");
+        ctx.ledger_db
+            .get_last_verified_state_root()
+            .map(|state_root| state_root.map(VerifiedStateRootResponse::from))
+            .map_err(|e| to_jsonrpsee_error_object("LEDGER_RPC_ERROR", e))
+    })?;
+
+    rpc.register_async_method("citrea_getDaInclusionProof", |params, ctx| async move {
+        let l1_height: u64 = params.one()?;
+        debug!("Light Verifier: citrea_getDaInclusionProof({})", l1_height);
+
+        let l1_block = ctx
+            .da_service
+            .get_block_at(l1_height)
+            .await
+            .map_err(|e| to_jsonrpsee_error_object("DA_RPC_ERROR", e))?;
+        let (_, inclusion_proof, completeness_proof) = ctx
+            .da_service
+            .extract_relevant_blobs_with_proof(&l1_block)
+            .await;
+
+        Ok::<DaInclusionProofResponse, ErrorObjectOwned>(DaInclusionProofResponse {
+            da_slot_hash: l1_block.header().hash().into(),
+            inclusion_proof: borsh::to_vec(&inclusion_proof)
+                .map_err(|e| to_jsonrpsee_error_object("DA_RPC_ERROR", e))?,
+            completeness_proof: borsh::to_vec(&completeness_proof)
+                .map_err(|e| to_jsonrpsee_error_object("DA_RPC_ERROR", e))?,
+        })
+    })?;
+
+    Ok(rpc)
+}
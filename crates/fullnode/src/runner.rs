@@ -1,41 +1,105 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
 use std::marker::PhantomData;
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use anyhow::{anyhow, bail};
 use backoff::future::retry as retry_backoff;
 use backoff::ExponentialBackoffBuilder;
-use borsh::de::BorshDeserialize;
+use citrea_evm::system_contracts::Bridge;
+use citrea_evm::Evm;
 use citrea_primitives::types::SoftConfirmationHash;
-use citrea_primitives::{get_da_block_at_height, L1BlockCache, SyncError};
+use citrea_primitives::{
+    extract_slot_inbox, get_da_block_at_height, AdaptiveSyncBatchSize, L1BlockCache, SharedClock,
+    SyncError, SystemClock,
+};
+use citrea_sequencer_registry::{
+    is_sequencer_da_pub_key_at, RegisteredSequencers, SequencerRegistry,
+};
+use citrea_withdrawal_queue::{withdrawal_root, WithdrawalQueue};
+use digest::Digest;
 use jsonrpsee::core::client::Error as JsonrpseeError;
-use jsonrpsee::server::{BatchRequestConfig, ServerBuilder};
 use jsonrpsee::RpcModule;
 use rs_merkle::algorithms::Sha256;
 use rs_merkle::MerkleTree;
-use sequencer_client::{GetSoftBatchResponse, SequencerClient};
+use sequencer_client::{FailoverSequencerClient, GetSoftBatchResponse};
+use serde::Serialize;
 use sov_db::ledger_db::{NodeLedgerOps, SlotCommit};
-use sov_db::schema::types::{BatchNumber, SlotNumber, StoredSoftBatch, StoredStateTransition};
-use sov_modules_api::Context;
-use sov_modules_stf_blueprint::StfBlueprintTrait;
+use sov_db::schema::types::{
+    BatchNumber, CommitmentCoverage, CommitmentCoverageUpdate, EventNumber, L2HeightRange,
+    SlotNumber, StateRootMismatchReport, StoredSequencerCommitment, StoredSoftBatch,
+    StoredStateTransition, StoredTransaction, TxNumber,
+};
+use sov_modules_api::{Context, Spec, WorkingSet};
+use sov_modules_stf_blueprint::{verify_soft_batch, StfBlueprintTrait};
 use sov_rollup_interface::da::{
-    BlobReaderTrait, BlockHeaderTrait, DaData, DaSpec, SequencerCommitment,
+    BlobReaderTrait, BlockHeaderTrait, CommitmentWithStateDiff, DaData, DaSpec,
+    SequencerCommitment, WithdrawalClaim,
 };
 use sov_rollup_interface::rpc::SoftConfirmationStatus;
 use sov_rollup_interface::services::da::{DaService, SlotData};
+use sov_rollup_interface::soft_confirmation::SignedSoftConfirmationBatch;
 pub use sov_rollup_interface::stf::BatchReceipt;
-use sov_rollup_interface::stf::{SoftBatchReceipt, StateTransitionFunction};
+use sov_rollup_interface::stf::{SoftBatchReceipt, StateDiff, StateTransitionFunction};
 use sov_rollup_interface::storage::HierarchicalStorageManager;
-use sov_rollup_interface::zk::{Proof, Zkvm, ZkvmHost};
-use sov_stf_runner::{InitVariant, RollupPublicKeys, RpcConfig, RunnerConfig};
+use sov_rollup_interface::zk::{AggregatedStateTransition, Proof, StateTransition, Zkvm, ZkvmHost};
+use sov_stf_runner::{
+    apply_fee_ceilings, create_config_rpc_module, spawn_rpc_server, ConfigReloader,
+    DaMonitorConfig, InitVariant, ProvingStrategy, PruningConfig, PruningMode, ReloadableConfig,
+    RollupPublicKeys, RpcConfig, RpcServerOptions, RunnerConfig,
+};
 use tokio::select;
-use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
-use tokio::time::{sleep, Duration};
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Mutex};
+use tokio::time::{sleep, Duration, Instant};
 use tracing::{debug, error, info, instrument, warn};
 
+use crate::challenge_window::update_challenge_windows;
+use crate::da_monitor::{da_monitor, halt_after_reorg, Reorg, ReorgHalts};
+use crate::diagnostics::{write_state_root_mismatch_diagnostics, StateRootMismatchDiagnostics};
+use crate::metrics::{
+    COMMITMENT_GAPS, COMMITMENT_OVERLAPS, COMMITTED_CONTIGUOUS_L2_HEIGHT, DA_COMMITMENTS_HALTED,
+    DA_EXECUTION_HALTED, DUPLICATE_COMMITMENTS, STATE_ROOT_MISMATCH_HALTED,
+    WATCHTOWER_PENDING_WITHDRAWALS,
+};
+use crate::rpc::{
+    create_backup_rpc_module, create_challenge_window_rpc_module, create_health_rpc_module,
+    create_reorg_halt_rpc_module, create_rpc_module, create_state_root_mismatch_rpc_module,
+    BackupRpcContext, ChallengeWindowRpcContext, HealthRpcContext, ReorgHaltRpcContext, RpcContext,
+    StateRootMismatchRpcContext,
+};
+use crate::watchtower::{check_withdrawal_claims, check_withdrawal_roots, Watchtower};
+
 type StateRoot<ST, Vm, Da> = <ST as StateTransitionFunction<Vm, Da>>::StateRoot;
 
+type L2LedgerReceipt<ST, Vm, Da> =
+    SoftBatchReceipt<u64, <ST as StateTransitionFunction<Vm, Da>>::TxReceiptContents, Da>;
+
+/// Request to back up the node databases into the given directory.
+/// Replies with the L2 height of the backed up state.
+pub(crate) type BackupRequest = (PathBuf, oneshot::Sender<anyhow::Result<Option<u64>>>);
+
+/// Upper bound of L2 blocks pruned in a single pruning round.
+const MAX_PRUNED_L2_BLOCKS_PER_ROUND: u64 = 1000;
+
+/// Number of L2 blocks fetched from the sequencer at once while backfilling history.
+const L2_BACKFILL_BATCH_SIZE: u64 = 100;
+
+/// How often sequencer endpoints are health checked during L2 sync.
+const SEQUENCER_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Number of L2 blocks that can be queued between two stages of the L2 pipeline.
+const L2_PIPELINE_DEPTH: usize = 4;
+
+/// L2 block which passed the stateless checks and is ready to be executed.
+struct VerifiedL2Block<Da: DaService> {
+    l2_height: u64,
+    soft_batch: GetSoftBatchResponse,
+    signed_batch: SignedSoftConfirmationBatch,
+    l1_block: Da::FilteredBlock,
+}
+
 /// Citrea's own STF runner implementation.
 pub struct CitreaFullnode<Stf, Sm, Da, Vm, C, DB>
 where
@@ -56,24 +120,53 @@ where
     state_root: StateRoot<Stf, Vm, Da::Spec>,
     batch_hash: SoftConfirmationHash,
     rpc_config: RpcConfig,
-    sequencer_client: SequencerClient,
+    sequencer_client: FailoverSequencerClient,
     sequencer_pub_key: Vec<u8>,
-    sequencer_da_pub_key: Vec<u8>,
+    public_keys: RollupPublicKeys,
+    /// Sequencers registered in the latest state,
+    /// the keys of the rollup config are used until the sequencer is rotated on chain.
+    registered_sequencers: RegisteredSequencers,
     prover_da_pub_key: Vec<u8>,
     phantom: std::marker::PhantomData<C>,
     include_tx_body: bool,
     code_commitment: Vm::CodeCommitment,
+    aggregation_code_commitment: Vm::CodeCommitment,
     accept_public_input_as_proven: bool,
+    proving_strategy: ProvingStrategy,
     l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
     sync_blocks_count: u64,
     soft_confirmation_tx: broadcast::Sender<u64>,
+    pruning_config: PruningConfig,
+    /// Whether L2 blocks missing below the oldest stored one are fetched in the background
+    backfill_history: bool,
+    /// Directory backups are created in, backups are disabled if not set
+    backup_dir: Option<PathBuf>,
+    backup_tx: mpsc::Sender<BackupRequest>,
+    backup_rx: Option<mpsc::Receiver<BackupRequest>>,
+    da_monitor: Option<DaMonitorConfig>,
+    /// Set by the DA monitor after a reorg deeper than the finality depth
+    commitments_halted: Arc<AtomicBool>,
+    /// Set by the DA monitor after a reorg deeper than the max handled reorg depth,
+    /// persisted until an operator resumes execution
+    execution_halted: Arc<AtomicBool>,
+    /// Set when the computed state root of an L2 block differs from the signed one,
+    /// persisted until an operator clears it
+    state_root_mismatch_halted: Arc<AtomicBool>,
+    /// L2 block whose state root did not match, executed again once the halt is cleared
+    mismatched_l2_block: Option<VerifiedL2Block<Da>>,
+    diagnostics_dir: Option<PathBuf>,
+    /// Number of L1 blocks sequencer commitments are challengeable for, if tracked
+    challenge_window: Option<u64>,
+    watchtower: Option<Watchtower>,
+    clock: SharedClock,
+    config_reloader: Option<Arc<ConfigReloader>>,
 }
 
 impl<Stf, Sm, Da, Vm, C, DB> CitreaFullnode<Stf, Sm, Da, Vm, C, DB>
 where
     Da: DaService<Error = anyhow::Error> + Clone + Send + Sync + 'static,
     Vm: ZkvmHost + Zkvm,
-    Sm: HierarchicalStorageManager<Da::Spec>,
+    Sm: HierarchicalStorageManager<Da::Spec, NativeStorage = C::Storage>,
     Stf: StateTransitionFunction<
             Vm,
             Da::Spec,
@@ -100,9 +193,15 @@ where
         mut storage_manager: Sm,
         init_variant: InitVariant<Stf, Vm, Da::Spec>,
         code_commitment: Vm::CodeCommitment,
+        aggregation_code_commitment: Vm::CodeCommitment,
         sync_blocks_count: u64,
         soft_confirmation_tx: broadcast::Sender<u64>,
     ) -> Result<Self, anyhow::Error> {
+        // Backfilled L2 blocks would be pruned again, or be what is left of a pruned range
+        if runner_config.backfill_history && runner_config.pruning_config.is_enabled() {
+            bail!("History can only be backfilled in the archive pruning mode");
+        }
+
         let (prev_state_root, prev_batch_hash) = match init_variant {
             InitVariant::Initialized((state_root, batch_hash)) => {
                 debug!("Chain is already initialized. Skipping initialization.");
@@ -125,10 +224,65 @@ where
 
         // Start the main rollup loop
         let item_numbers = ledger_db.get_next_items_numbers();
+        let sync_checkpoint = ledger_db.get_sync_checkpoint()?.unwrap_or_default();
+
+        // Ledger data of an L2 block is committed before its state,
+        // so the last finalized state tells which L2 block was executed last.
+        let start_l2_height = match storage_manager.get_last_finalized_l2_height()? {
+            Some(l2_height) => {
+                if l2_height >= item_numbers.soft_batch_number {
+                    bail!(
+                        "State is ahead of ledger: state l2 height={}, ledger l2 height={}",
+                        l2_height,
+                        item_numbers.soft_batch_number.saturating_sub(1)
+                    );
+                }
+                l2_height + 1
+            }
+            None => item_numbers.soft_batch_number,
+        };
 
         // Last L1/L2 height before shutdown.
-        let start_l1_height = item_numbers.slot_number;
-        let start_l2_height = item_numbers.soft_batch_number;
+        let start_l1_height = sync_checkpoint.l1_height.0.max(item_numbers.slot_number);
+        info!(
+            "Resuming sync from L1 height {} and L2 height {}. Checkpoint: {:?}",
+            start_l1_height, start_l2_height, sync_checkpoint
+        );
+
+        let (backup_tx, backup_rx) = mpsc::channel(1);
+
+        let registered_sequencers = RegisteredSequencers::from_state::<C>(&mut WorkingSet::new(
+            storage_manager.create_finalized_storage()?,
+        ));
+
+        let reorg_halt = ledger_db.get_reorg_halt()?;
+        if let Some(report) = &reorg_halt {
+            error!(
+                "Execution is halted since a DA reorg of {} blocks after L1 height {}, resume it with citrea_resumeAfterReorg: {:?}",
+                report.depth, report.fork_height.0, report
+            );
+            DA_EXECUTION_HALTED.set(1);
+        }
+
+        let commitments_halt = ledger_db.get_commitments_halt()?;
+        if let Some(report) = &commitments_halt {
+            error!(
+                "No sequencer commitment is accepted since a DA reorg of {} blocks after L1 height {}, resume it with citrea_resumeAfterReorg: {:?}",
+                report.depth, report.fork_height.0, report
+            );
+            DA_COMMITMENTS_HALTED.set(1);
+        }
+
+        let state_root_mismatch = ledger_db.get_state_root_mismatch()?;
+        if let Some(report) = &state_root_mismatch {
+            error!(
+                "Execution is halted since the state root of L2 block #{} did not match, clear it with citrea_clearStateRootMismatch on the operator RPC server: {:?}",
+                report.l2_height.0, report
+            );
+            STATE_ROOT_MISMATCH_HALTED.set(1);
+        }
+
+        citrea_evm::set_parallel_execution(runner_config.parallel_evm_execution);
 
         Ok(Self {
             start_l1_height,
@@ -140,84 +294,176 @@ where
             state_root: prev_state_root,
             batch_hash: prev_batch_hash,
             rpc_config,
-            sequencer_client: SequencerClient::new(runner_config.sequencer_client_url),
-            sequencer_pub_key: public_keys.sequencer_public_key,
-            sequencer_da_pub_key: public_keys.sequencer_da_pub_key,
-            prover_da_pub_key: public_keys.prover_da_pub_key,
+            sequencer_client: FailoverSequencerClient::new(
+                runner_config.sequencer_client_url.urls().to_vec(),
+            )?,
+            sequencer_pub_key: public_keys.sequencer_public_key.clone(),
+            registered_sequencers,
+            prover_da_pub_key: public_keys.prover_da_pub_key.clone(),
+            public_keys,
             phantom: std::marker::PhantomData,
             include_tx_body: runner_config.include_tx_body,
             code_commitment,
+            aggregation_code_commitment,
             accept_public_input_as_proven: runner_config
                 .accept_public_input_as_proven
                 .unwrap_or(false),
+            proving_strategy: runner_config.proving_strategy,
             sync_blocks_count,
             l1_block_cache: Arc::new(Mutex::new(L1BlockCache::new())),
             soft_confirmation_tx,
+            pruning_config: runner_config.pruning_config,
+            backfill_history: runner_config.backfill_history,
+            backup_dir: runner_config.backup_dir,
+            backup_tx,
+            backup_rx: Some(backup_rx),
+            da_monitor: runner_config.da_monitor,
+            commitments_halted: Arc::new(AtomicBool::new(commitments_halt.is_some())),
+            execution_halted: Arc::new(AtomicBool::new(reorg_halt.is_some())),
+            state_root_mismatch_halted: Arc::new(AtomicBool::new(state_root_mismatch.is_some())),
+            mismatched_l2_block: None,
+            diagnostics_dir: runner_config.diagnostics_dir,
+            challenge_window: runner_config.challenge_window,
+            watchtower: runner_config.watchtower.map(Watchtower::new),
+            clock: SystemClock::shared(),
+            config_reloader: None,
         })
     }
 
+    /// Uses the clock for DA polling instead of the system time
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Applies reloads of the config to the RPC server, the sync and the fee ceilings of the DA
+    /// service, and serves `citrea_reloadConfig` on the operator RPC server
+    pub fn with_config_reloader(mut self, config_reloader: Arc<ConfigReloader>) -> Self {
+        self.config_reloader = Some(config_reloader);
+        self
+    }
+
     /// Starts a RPC server with provided rpc methods.
     pub async fn start_rpc_server(
         &self,
         methods: RpcModule<()>,
         channel: Option<oneshot::Sender<SocketAddr>>,
-    ) {
-        let bind_host = match self.rpc_config.bind_host.parse() {
-            Ok(bind_host) => bind_host,
-            Err(e) => {
-                error!("Failed to parse bind host: {}", e);
-                return;
+    ) -> anyhow::Result<()>
+    where
+        DB: Clone + Send + Sync + 'static,
+    {
+        let methods = self.register_rpc_methods(methods)?;
+        let options = RpcServerOptions {
+            // GET /health fails while the execution of L2 blocks is halted
+            health_method: Some("citrea_health"),
+            config_updates: self
+                .config_reloader
+                .as_ref()
+                .map(|config_reloader| config_reloader.subscribe()),
+            ..Default::default()
+        };
+        spawn_rpc_server(
+            &self.rpc_config,
+            methods,
+            self.ledger_db.clone(),
+            options,
+            channel,
+        )
+    }
+
+    /// Updates the given RpcModule with full node methods.
+    pub fn register_rpc_methods(
+        &self,
+        mut rpc_methods: RpcModule<()>,
+    ) -> Result<RpcModule<()>, jsonrpsee::core::RegisterMethodError>
+    where
+        DB: Clone + Send + Sync + 'static,
+    {
+        let rpc_context = RpcContext {
+            ledger_db: self.ledger_db.clone(),
+        };
+        let rpc = create_rpc_module(rpc_context)?;
+        rpc_methods.merge(rpc)?;
+
+        match (&self.backup_dir, &self.rpc_config.operator) {
+            (Some(backup_dir), Some(_)) => {
+                let backup_rpc_context = BackupRpcContext {
+                    backup_tx: self.backup_tx.clone(),
+                    backup_dir: backup_dir.clone(),
+                };
+                rpc_methods.merge(create_backup_rpc_module(backup_rpc_context)?)?;
             }
+            (Some(_), None) => {
+                warn!(
+                    "citrea_createBackup is disabled, it is only served by the operator RPC server"
+                );
+            }
+            (None, _) => {}
+        }
+
+        match (&self.da_monitor, &self.rpc_config.operator) {
+            (_, Some(_)) => {
+                let reorg_halt_rpc_context = ReorgHaltRpcContext {
+                    ledger_db: self.ledger_db.clone(),
+                    commitments_halted: self.commitments_halted.clone(),
+                    execution_halted: self.execution_halted.clone(),
+                };
+                rpc_methods.merge(create_reorg_halt_rpc_module(reorg_halt_rpc_context)?)?;
+            }
+            (Some(_), None) => {
+                warn!(
+                    "citrea_resumeAfterReorg is disabled, it is only served by the operator RPC server"
+                );
+            }
+            (None, None) => {}
+        }
+
+        if self.rpc_config.operator.is_some() {
+            let state_root_mismatch_rpc_context = StateRootMismatchRpcContext {
+                ledger_db: self.ledger_db.clone(),
+                state_root_mismatch_halted: self.state_root_mismatch_halted.clone(),
+            };
+            rpc_methods.merge(create_state_root_mismatch_rpc_module(
+                state_root_mismatch_rpc_context,
+            )?)?;
+        }
+
+        let health_rpc_context = HealthRpcContext {
+            state_root_mismatch_halted: self.state_root_mismatch_halted.clone(),
+            execution_halted: self.execution_halted.clone(),
         };
-        let listen_address = SocketAddr::new(bind_host, self.rpc_config.bind_port);
-
-        let max_connections = self.rpc_config.max_connections;
-        let max_subscriptions_per_connection = self.rpc_config.max_subscriptions_per_connection;
-        let max_request_body_size = self.rpc_config.max_request_body_size;
-        let max_response_body_size = self.rpc_config.max_response_body_size;
-        let batch_requests_limit = self.rpc_config.batch_requests_limit;
-
-        let _handle = tokio::spawn(async move {
-            let server = ServerBuilder::default()
-                .max_connections(max_connections)
-                .max_subscriptions_per_connection(max_subscriptions_per_connection)
-                .max_request_body_size(max_request_body_size)
-                .max_response_body_size(max_response_body_size)
-                .set_batch_request_config(BatchRequestConfig::Limit(batch_requests_limit))
-                .build([listen_address].as_ref())
-                .await;
-
-            match server {
-                Ok(server) => {
-                    let bound_address = match server.local_addr() {
-                        Ok(address) => address,
-                        Err(e) => {
-                            error!("{}", e);
-                            return;
-                        }
-                    };
-                    if let Some(channel) = channel {
-                        if let Err(e) = channel.send(bound_address) {
-                            error!("Could not send bound_address {}: {}", bound_address, e);
-                            return;
-                        }
-                    }
-                    info!("Starting RPC server at {} ", &bound_address);
+        rpc_methods.merge(create_health_rpc_module(health_rpc_context)?)?;
 
-                    let _server_handle = server.start(methods);
-                    futures::future::pending::<()>().await;
-                }
-                Err(e) => {
-                    error!("Could not start RPC server: {}", e);
-                }
+        if let Some(challenge_window) = self.challenge_window {
+            let challenge_window_rpc_context = ChallengeWindowRpcContext {
+                ledger_db: self.ledger_db.clone(),
+                challenge_window,
+            };
+            rpc_methods.merge(create_challenge_window_rpc_module(
+                challenge_window_rpc_context,
+            )?)?;
+        }
+
+        match (&self.config_reloader, &self.rpc_config.operator) {
+            (Some(config_reloader), Some(_)) => {
+                rpc_methods.merge(create_config_rpc_module(config_reloader.clone())?)?;
             }
-        });
+            (Some(_), None) => {
+                warn!(
+                    "citrea_reloadConfig is disabled, it is only served by the operator RPC server"
+                );
+            }
+            (None, _) => {}
+        }
+        Ok(rpc_methods)
     }
 
+    #[instrument(level = "info", skip_all, fields(da_height = l1_block.header().height()))]
     async fn process_zk_proof(
         &self,
         l1_block: Da::FilteredBlock,
         proof: Proof,
+        da_tx_id: Option<[u8; 32]>,
     ) -> Result<(), SyncError> {
         tracing::info!(
             "Processing zk proof at height: {}",
@@ -238,8 +484,11 @@ where
                     Stf::StateRoot,
                 >(&proof, &code_commitment)
                 {
-                    if proof_data.sequencer_da_public_key != self.sequencer_da_pub_key
-                        || proof_data.sequencer_public_key != self.sequencer_pub_key
+                    // Commitments before a rotation of the sequencer are signed with the keys of the rollup config
+                    if !self.is_sequencer_da_pub_key_of_slot(
+                        proof_data.da_slot_hash.clone().into(),
+                        &proof_data.sequencer_da_public_key,
+                    )? || proof_data.sequencer_public_key != self.sequencer_pub_key
                     {
                         return Err(anyhow!(
                             "Proof verification: Sequencer public key or sequencer da public key mismatch. Skipping proof."
@@ -262,10 +511,30 @@ where
                     .into());
                 }
                 // public input is accepted only in tests, so ok to expect
-                Vm::extract_output(&proof).expect("Proof should be deserializable")
+                let state_transition: StateTransition<<Da as DaService>::Spec, Stf::StateRoot> =
+                    Vm::extract_output(&proof).expect("Proof should be deserializable");
+                if !self
+                    .proving_strategy
+                    .may_prove(&state_transition.da_slot_hash.clone().into())
+                {
+                    return Err(anyhow!(
+                        "Found public input of an L1 block the prover does not prove in da block number: {:?}, Skipping to next proof..",
+                        l1_block.header().height(),
+                    )
+                    .into());
+                }
+                state_transition
             }
         };
 
+        if let Err(e) = self.watch_withdrawal_roots(
+            l1_block.header().height(),
+            &[state_transition.da_slot_hash.clone().into()],
+            &state_transition.withdrawal_roots,
+        ) {
+            warn!("Watchtower could not check the withdrawal roots: {}", e);
+        }
+
         let stored_state_transition = StoredStateTransition {
             initial_state_root: state_transition.initial_state_root.as_ref().to_vec(),
             final_state_root: state_transition.final_state_root.as_ref().to_vec(),
@@ -277,8 +546,257 @@ where
             validity_condition: borsh::to_vec(&state_transition.validity_condition).unwrap(),
         };
 
-        let l1_hash = state_transition.da_slot_hash.into();
+        self.mark_commitments_proven(
+            state_transition.da_slot_hash.into(),
+            Some(state_transition.initial_state_root.as_ref()),
+            da_tx_id,
+        )?;
+        // store in ledger db
+        self.ledger_db.update_verified_proof_data(
+            l1_block.header().height(),
+            proof.clone(),
+            stored_state_transition,
+        )?;
+        Ok(())
+    }
 
+    #[instrument(level = "info", skip_all, fields(da_height = l1_block.header().height()))]
+    async fn process_aggregated_zk_proof(
+        &self,
+        l1_block: Da::FilteredBlock,
+        proof: Proof,
+        da_tx_id: Option<[u8; 32]>,
+    ) -> Result<(), SyncError> {
+        tracing::info!(
+            "Processing aggregated zk proof at height: {}",
+            l1_block.header().height()
+        );
+        let aggregated = match &proof {
+            Proof::Full(serialized_proof) => Vm::verify_and_extract_aggregated_output::<
+                <Da as DaService>::Spec,
+                Stf::StateRoot,
+            >(
+                serialized_proof, &self.aggregation_code_commitment
+            )
+            .map_err(|_| {
+                anyhow!("Proof verification: SNARK verification failed. Skipping to next proof..")
+            })?,
+            Proof::PublicInput(_) => {
+                if !self.accept_public_input_as_proven {
+                    return Err(anyhow!(
+                        "Found public input in da block number: {:?}, Skipping to next proof..",
+                        l1_block.header().height(),
+                    )
+                    .into());
+                }
+                // public input is accepted only in tests, so ok to expect
+                let aggregated: AggregatedStateTransition<<Da as DaService>::Spec, Stf::StateRoot> =
+                    Vm::extract_aggregated_output(&proof).expect("Proof should be deserializable");
+                if !aggregated.da_slot_hashes.iter().all(|da_slot_hash| {
+                    self.proving_strategy
+                        .may_prove(&da_slot_hash.clone().into())
+                }) {
+                    return Err(anyhow!(
+                        "Found public input of an L1 block the prover does not prove in da block number: {:?}, Skipping to next proof..",
+                        l1_block.header().height(),
+                    )
+                    .into());
+                }
+                aggregated
+            }
+        };
+
+        // The aggregation guest only checks that the aggregated proofs come from the same program
+        if aggregated.range_code_commitment != self.code_commitment.as_ref() {
+            return Err(anyhow!(
+                "Proof verification: Aggregated proofs are not proven with the expected code commitment. Skipping proof."
+            )
+            .into());
+        }
+        // The stored state transition is the one of the last DA slot
+        if aggregated.da_slot_hashes.is_empty()
+            || aggregated.sequencer_commitments_ranges.len() != aggregated.da_slot_hashes.len()
+        {
+            return Err(anyhow!(
+                "Proof verification: Aggregated proof does not have a commitments range per DA slot. Skipping proof."
+            )
+            .into());
+        }
+        // Commitments before a rotation of the sequencer are signed with the keys of the rollup config
+        let mut da_pub_keys_valid =
+            aggregated.sequencer_da_public_keys.len() == aggregated.da_slot_hashes.len();
+        for (da_slot_hash, da_pub_key) in aggregated
+            .da_slot_hashes
+            .iter()
+            .zip(&aggregated.sequencer_da_public_keys)
+        {
+            da_pub_keys_valid &=
+                self.is_sequencer_da_pub_key_of_slot(da_slot_hash.clone().into(), da_pub_key)?;
+        }
+        if !da_pub_keys_valid || aggregated.sequencer_public_key != self.sequencer_pub_key {
+            return Err(anyhow!(
+                "Proof verification: Sequencer public key or sequencer da public key mismatch. Skipping proof."
+            )
+            .into());
+        }
+
+        // The state transitions are consecutive, so only the first initial state root is checked
+        for (i, da_slot_hash) in aggregated.da_slot_hashes.iter().enumerate() {
+            let initial_state_root = (i == 0).then(|| aggregated.initial_state_root.as_ref());
+            self.mark_commitments_proven(
+                da_slot_hash.clone().into(),
+                initial_state_root,
+                da_tx_id,
+            )?;
+        }
+
+        let da_slot_hashes: Vec<[u8; 32]> = aggregated
+            .da_slot_hashes
+            .iter()
+            .map(|da_slot_hash| da_slot_hash.clone().into())
+            .collect();
+        if let Err(e) = self.watch_withdrawal_roots(
+            l1_block.header().height(),
+            &da_slot_hashes,
+            &aggregated.withdrawal_roots,
+        ) {
+            warn!("Watchtower could not check the withdrawal roots: {}", e);
+        }
+
+        // A stored state transition refers to a single DA slot, the last aggregated one
+        let stored_state_transition = StoredStateTransition {
+            initial_state_root: aggregated.initial_state_root.as_ref().to_vec(),
+            final_state_root: aggregated.final_state_root.as_ref().to_vec(),
+            state_diff: aggregated.state_diff,
+            da_slot_hash: aggregated
+                .da_slot_hashes
+                .last()
+                .expect("Aggregated proofs are not empty")
+                .clone()
+                .into(),
+            sequencer_commitments_range: *aggregated
+                .sequencer_commitments_ranges
+                .last()
+                .expect("Aggregated proofs are not empty"),
+            sequencer_public_key: aggregated.sequencer_public_key,
+            sequencer_da_public_key: aggregated
+                .sequencer_da_public_keys
+                .last()
+                .expect("Aggregated proofs are not empty")
+                .clone(),
+            validity_condition: borsh::to_vec(&aggregated.validity_conditions).unwrap(),
+        };
+        self.ledger_db.update_verified_proof_data(
+            l1_block.header().height(),
+            proof,
+            stored_state_transition,
+        )?;
+        Ok(())
+    }
+
+    /// Compares the withdrawal roots of a proof with the withdrawals initiated in the L2 blocks
+    /// of the sequencer commitments read from the proven DA slots.
+    /// Skipped if the watchtower is disabled or the L2 blocks are not executed yet.
+    fn watch_withdrawal_roots(
+        &self,
+        l1_height: u64,
+        da_slot_hashes: &[[u8; 32]],
+        proven_withdrawal_roots: &[[u8; 32]],
+    ) -> anyhow::Result<()> {
+        let Some(watchtower) = &self.watchtower else {
+            return Ok(());
+        };
+
+        let last_l2_height = self.storage_manager.get_last_finalized_l2_height()?;
+        let mut working_set = WorkingSet::new(self.storage_manager.create_finalized_storage()?);
+        let withdrawal_queue = WithdrawalQueue::<C>::default();
+
+        let mut withdrawal_roots = vec![];
+        for da_slot_hash in da_slot_hashes {
+            let Some(da_slot_height) = self.ledger_db.get_l1_height_of_l1_hash(*da_slot_hash)?
+            else {
+                return Ok(());
+            };
+            for commitment in self
+                .ledger_db
+                .get_commitments_on_da_slot(da_slot_height)?
+                .unwrap_or_default()
+            {
+                if last_l2_height < Some(commitment.l2_end_block_number) {
+                    debug!(
+                        "Watchtower skips the withdrawal roots of the proof at L1 height {}, L2 blocks are not executed yet",
+                        l1_height
+                    );
+                    return Ok(());
+                }
+                withdrawal_roots.push(withdrawal_root(&withdrawal_queue.withdrawals_in_l2_range(
+                    commitment.l2_start_block_number..=commitment.l2_end_block_number,
+                    &mut working_set,
+                )?));
+            }
+        }
+
+        watchtower.alert(check_withdrawal_roots(
+            l1_height,
+            proven_withdrawal_roots,
+            &withdrawal_roots,
+        ));
+        Ok(())
+    }
+
+    /// Checks the withdrawal claims posted on the L1 block against the withdrawals
+    /// of the L2 blocks proven before it. Skipped if the watchtower is disabled.
+    fn watch_withdrawal_claims(
+        &self,
+        l1_height: u64,
+        claims: &[WithdrawalClaim],
+    ) -> Result<(), SyncError> {
+        let Some(watchtower) = &self.watchtower else {
+            return Ok(());
+        };
+        if claims.is_empty() {
+            return Ok(());
+        }
+
+        let last_proven_l2_height = self
+            .ledger_db
+            .get_last_proven_l2_height()?
+            .map(|l2_height| l2_height.0);
+        let last_l2_height = self.storage_manager.get_last_finalized_l2_height()?;
+        if let Some(last_proven_l2_height) = last_proven_l2_height {
+            if last_l2_height < Some(last_proven_l2_height) {
+                return Err(SyncError::MissingL2(
+                    "Proven L2 blocks not executed yet",
+                    BatchNumber(last_l2_height.map_or(0, |l2_height| l2_height + 1)),
+                    BatchNumber(last_proven_l2_height),
+                ));
+            }
+        }
+
+        let mut working_set = WorkingSet::new(self.storage_manager.create_finalized_storage()?);
+        let withdrawal_queue = WithdrawalQueue::<C>::default();
+        let first_recorded_index = withdrawal_queue
+            .first_withdrawal_index(&mut working_set)
+            .unwrap_or_default();
+        watchtower.alert(check_withdrawal_claims(
+            l1_height,
+            claims,
+            first_recorded_index,
+            last_proven_l2_height,
+            |index| withdrawal_queue.withdrawal(index, &mut working_set),
+        ));
+        Ok(())
+    }
+
+    /// Marks the L2 blocks of the sequencer commitments read from the DA slot as proven.
+    /// If given, `initial_state_root` must be the state root before the first of them,
+    /// and `proof_da_tx_id` the id of the DA transaction which carried the proof.
+    fn mark_commitments_proven(
+        &self,
+        l1_hash: [u8; 32],
+        initial_state_root: Option<&[u8]>,
+        proof_da_tx_id: Option<[u8; 32]>,
+    ) -> Result<(), SyncError> {
         // This is the l1 height where the sequencer commitment was read by the prover and proof generated by those commitments
         // We need to get commitments in this l1 height and set them as proven
         let l1_height = match self.ledger_db.get_l1_height_of_l1_hash(l1_hash)? {
@@ -303,23 +821,24 @@ where
             }
         };
 
-        let l2_height = proven_commitments[0].l2_start_block_number;
-        // Fetch the block prior to the one at l2_height so compare state roots
-        let prior_soft_batch = self
-            .ledger_db
-            .get_soft_batch_by_number(&(BatchNumber(l2_height - 1)))?;
-        if let Some(prior_soft_batch) = prior_soft_batch {
-            if prior_soft_batch.state_root.as_slice()
-                != state_transition.initial_state_root.as_ref()
-            {
-                return Err(anyhow!(
-                    "Proof verification: For a known and verified sequencer commitment. Pre state root mismatch - expected 0x{} but got 0x{}. Skipping proof.",
-                    hex::encode(&prior_soft_batch.state_root),
-                    hex::encode(&state_transition.initial_state_root)
-                ).into());
+        if let Some(initial_state_root) = initial_state_root {
+            let l2_height = proven_commitments[0].l2_start_block_number;
+            // Fetch the block prior to the one at l2_height so compare state roots
+            let prior_soft_batch = self
+                .ledger_db
+                .get_soft_batch_by_number(&(BatchNumber(l2_height - 1)))?;
+            if let Some(prior_soft_batch) = prior_soft_batch {
+                if prior_soft_batch.state_root.as_slice() != initial_state_root {
+                    return Err(anyhow!(
+                        "Proof verification: For a known and verified sequencer commitment. Pre state root mismatch - expected 0x{} but got 0x{}. Skipping proof.",
+                        hex::encode(&prior_soft_batch.state_root),
+                        hex::encode(initial_state_root)
+                    ).into());
+                }
             }
         }
 
+        let mut last_proven_l2_height = self.ledger_db.get_last_proven_l2_height()?;
         for commitment in proven_commitments {
             // TODO: put_soft_confirmation_status to use L2 range
             let l2_start_height = commitment.l2_start_block_number;
@@ -328,20 +847,27 @@ where
                 self.ledger_db
                     .put_soft_confirmation_status(BatchNumber(i), SoftConfirmationStatus::Proven)?;
             }
+            last_proven_l2_height = last_proven_l2_height.max(Some(BatchNumber(l2_end_height)));
+        }
+        if let Some(last_proven_l2_height) = last_proven_l2_height {
+            self.ledger_db
+                .set_last_proven_l2_height(last_proven_l2_height)?;
+        }
+        if let Some(proof_da_tx_id) = proof_da_tx_id {
+            self.ledger_db
+                .put_proof_da_tx_id(SlotNumber(l1_height), proof_da_tx_id)?;
         }
-        // store in ledger db
-        self.ledger_db.update_verified_proof_data(
-            l1_block.header().height(),
-            proof.clone(),
-            stored_state_transition,
-        )?;
         Ok(())
     }
 
+    #[instrument(level = "info", skip_all, fields(da_height = l1_block.header().height()))]
     async fn process_sequencer_commitment(
         &self,
         l1_block: Da::FilteredBlock,
         sequencer_commitment: SequencerCommitment,
+        posted_state_diff: Option<StateDiff>,
+        da_tx_id: Option<[u8; 32]>,
+        sender: &[u8],
     ) -> Result<(), SyncError> {
         let start_l2_height = sequencer_commitment.l2_start_block_number;
         let end_l2_height = sequencer_commitment.l2_end_block_number;
@@ -352,16 +878,35 @@ where
             end_l2_height,
         );
 
+        // The same commitment can land on DA twice, e.g. when a fee bump races the original tx
+        if let Some(first_l1_height) = self
+            .ledger_db
+            .get_l1_height_of_sequencer_commitment(&sequencer_commitment)?
+        {
+            if first_l1_height.0 != l1_block.header().height() {
+                DUPLICATE_COMMITMENTS.inc();
+                warn!(
+                    "Skipping duplicate sequencer commitment of L2 blocks {}-{}, first posted at L1 height {}",
+                    start_l2_height, end_l2_height, first_l1_height.0
+                );
+                return Ok(());
+            }
+        }
+
         // Traverse each item's field of vector of transactions, put them in merkle tree
         // and compare the root with the one from the ledger
-        let stored_soft_batches: Vec<StoredSoftBatch> = self.ledger_db.get_soft_batch_range(
-            &(BatchNumber(start_l2_height)..BatchNumber(end_l2_height + 1)),
-        )?;
+        let (soft_batch_hashes, soft_batch_pub_keys): (Vec<_>, Vec<_>) = self
+            .ledger_db
+            .soft_batch_iter(BatchNumber(start_l2_height)..BatchNumber(end_l2_height + 1))?
+            .map(|soft_batch| soft_batch.map(|soft_batch| (soft_batch.hash, soft_batch.pub_key)))
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
 
         // Make sure that the number of stored soft batches is equal to the range's length.
         // Otherwise, if it is smaller, then we don't have some L2 blocks within the range
         // synced yet.
-        if stored_soft_batches.len() < ((end_l2_height - start_l2_height) as usize) {
+        if soft_batch_hashes.len() < ((end_l2_height - start_l2_height) as usize) {
             return Err(SyncError::MissingL2(
                 "L2 range not synced yet",
                 BatchNumber(start_l2_height),
@@ -369,13 +914,28 @@ where
             ));
         }
 
-        let soft_batches_tree = MerkleTree::<Sha256>::from_leaves(
-            stored_soft_batches
-                .iter()
-                .map(|x| x.hash)
-                .collect::<Vec<_>>()
-                .as_slice(),
-        );
+        // The commitment is signed by the sequencer which signed its last L2 block,
+        // so the last commitments of a sequencer are accepted after it is rotated
+        let Some(signer_pub_key) = soft_batch_pub_keys.last() else {
+            return Err(SyncError::MissingL2(
+                "L2 range not synced yet",
+                BatchNumber(start_l2_height),
+                BatchNumber(end_l2_height),
+            ));
+        };
+        let expected_sender =
+            self.sequencer_da_pub_key_of(signer_pub_key, l1_block.header().height());
+        if expected_sender != Some(sender) {
+            return Err(anyhow!(
+                "Commitment of L2 blocks {}-{} is signed by 0x{}, not by the sequencer of the L2 blocks. Skipping commitment.",
+                start_l2_height,
+                end_l2_height,
+                hex::encode(sender)
+            )
+            .into());
+        }
+
+        let soft_batches_tree = MerkleTree::<Sha256>::from_leaves(&soft_batch_hashes);
 
         if soft_batches_tree.root() != Some(sequencer_commitment.merkle_root) {
             return Err(anyhow!(
@@ -389,27 +949,152 @@ where
             )
             .into());
         } else {
+            if let Some(posted_state_diff) = &posted_state_diff {
+                self.check_posted_state_diff(start_l2_height, end_l2_height, posted_state_diff)?;
+            }
+            // The state diffs of the committed L2 blocks are not needed anymore
+            self.ledger_db
+                .delete_l2_state_diffs(BatchNumber(end_l2_height))?;
+
+            // Commitments of an L1 block are processed again if the node stopped before
+            // its checkpoint, they must not be taken for overlaps
+            let already_processed = self
+                .ledger_db
+                .get_commitments_on_da_slot(l1_block.header().height())?
+                .is_some_and(|commitments| commitments.contains(&sequencer_commitment));
+
             self.ledger_db.update_commitments_on_da_slot(
                 l1_block.header().height(),
                 sequencer_commitment.clone(),
             )?;
+            if let Some(da_tx_id) = da_tx_id {
+                self.ledger_db
+                    .index_sequencer_commitment(&StoredSequencerCommitment {
+                        da_tx_id,
+                        l1_height: SlotNumber(l1_block.header().height()),
+                        commitment: sequencer_commitment.clone(),
+                    })?;
+            }
 
-            for i in start_l2_height..=end_l2_height {
-                self.ledger_db.put_soft_confirmation_status(
-                    BatchNumber(i),
-                    SoftConfirmationStatus::Finalized,
-                )?;
+            if already_processed {
+                return Ok(());
+            }
+            for (start, end) in self.record_commitment_coverage(start_l2_height, end_l2_height)? {
+                for i in start.0..=end.0 {
+                    self.ledger_db.put_soft_confirmation_status(
+                        BatchNumber(i),
+                        SoftConfirmationStatus::Finalized,
+                    )?;
+                }
             }
         }
         Ok(())
     }
 
+    /// Checks the state diff posted with a commitment against the state diffs of its L2 blocks.
+    /// Not checked if the state diffs of the L2 blocks are not known, e.g. because they were
+    /// executed before the node stored them.
+    fn check_posted_state_diff(
+        &self,
+        start_l2_height: u64,
+        end_l2_height: u64,
+        posted_state_diff: &StateDiff,
+    ) -> anyhow::Result<()> {
+        let mut state_diff = BTreeMap::new();
+        for l2_height in start_l2_height..=end_l2_height {
+            let Some(diff) = self.ledger_db.get_l2_state_diff(BatchNumber(l2_height))? else {
+                debug!(
+                    "State diff of L2 block {} is not known, not checking the state diff posted with the commitment of L2 blocks {}-{}",
+                    l2_height, start_l2_height, end_l2_height
+                );
+                return Ok(());
+            };
+            state_diff.extend(diff);
+        }
+
+        if !posted_state_diff.iter().cloned().eq(state_diff) {
+            bail!(
+                "State diff posted with the commitment of L2 blocks {}-{} does not match their execution. Skipping commitment.",
+                start_l2_height,
+                end_l2_height
+            );
+        }
+        Ok(())
+    }
+
+    /// Adds the range of a verified commitment to the commitment coverage,
+    /// returns the L2 ranges which can be marked as finalized now.
+    /// Nothing after a gap in the commitments is finalized until the gap is closed.
+    fn record_commitment_coverage(
+        &self,
+        start_l2_height: u64,
+        end_l2_height: u64,
+    ) -> anyhow::Result<Vec<L2HeightRange>> {
+        let mut coverage = match self.ledger_db.get_commitment_coverage()? {
+            Some(coverage) => coverage,
+            None => {
+                // Commitments before the first one seen are known if their L2 blocks are
+                // finalized already, e.g. by a node which did not track the coverage yet
+                let mut coverage = CommitmentCoverage::default();
+                let previous_height = start_l2_height.saturating_sub(1);
+                if previous_height > 0
+                    && self
+                        .ledger_db
+                        .get_l2_soft_confirmation_status(BatchNumber(previous_height))?
+                        .is_some_and(|status| status != SoftConfirmationStatus::Trusted)
+                {
+                    coverage.contiguous_l2_height = BatchNumber(previous_height);
+                }
+                coverage
+            }
+        };
+
+        let update = coverage.record((BatchNumber(start_l2_height), BatchNumber(end_l2_height)));
+        self.ledger_db.set_commitment_coverage(&coverage)?;
+        COMMITTED_CONTIGUOUS_L2_HEIGHT.set(coverage.contiguous_l2_height.0 as i64);
+        COMMITMENT_GAPS.set(coverage.gaps().len() as i64);
+
+        match update {
+            CommitmentCoverageUpdate::Extended(ranges) => {
+                if ranges.len() > 1 {
+                    info!(
+                        "Sequencer commitments are contiguous again up to L2 height {}",
+                        coverage.contiguous_l2_height.0
+                    );
+                }
+                Ok(ranges)
+            }
+            CommitmentCoverageUpdate::Gap((gap_start, gap_end)) => {
+                error!(
+                    "Gap in sequencer commitments: L2 blocks {}-{} are not committed. L2 blocks {}-{} are not finalized until the gap is closed",
+                    gap_start.0, gap_end.0, start_l2_height, end_l2_height
+                );
+                Ok(vec![])
+            }
+            CommitmentCoverageUpdate::Overlap => {
+                COMMITMENT_OVERLAPS.inc();
+                warn!(
+                    "Sequencer commitment of L2 blocks {}-{} overlaps L2 blocks committed already",
+                    start_l2_height, end_l2_height
+                );
+                Ok(vec![])
+            }
+        }
+    }
+
+    #[instrument(level = "info", skip_all, fields(l2_height = l2_block.l2_height))]
     async fn process_l2_block(
         &mut self,
-        l2_height: u64,
-        soft_batch: GetSoftBatchResponse,
-        current_l1_block: Da::FilteredBlock,
+        l2_block: VerifiedL2Block<Da>,
+        ledger_tx: &mpsc::Sender<(u64, L2LedgerReceipt<Stf, Vm, Da::Spec>)>,
     ) -> anyhow::Result<()> {
+        let VerifiedL2Block {
+            l2_height,
+            soft_batch,
+            mut signed_batch,
+            l1_block: current_l1_block,
+        } = l2_block;
+
         info!(
             "Running soft confirmation batch #{} with hash: 0x{} on DA block #{}",
             l2_height,
@@ -421,12 +1106,40 @@ where
             bail!("Previous hash mismatch at height: {}", l2_height);
         }
 
+        // Forced transactions and deposits in the inbox are checked by the STF
+        let slot_inbox = extract_slot_inbox(&self.da_service, &current_l1_block);
+
         let mut data_to_commit = SlotCommit::new(current_l1_block.clone());
 
         let pre_state = self
             .storage_manager
             .create_storage_on_l2_height(l2_height)?;
 
+        // The sequencer can be rotated on chain, the previous L2 blocks decide who signs this one
+        let active_sequencer = SequencerRegistry::<C>::default()
+            .active_sequencer(&mut WorkingSet::new(pre_state.clone()));
+        let sequencer_pub_key = active_sequencer
+            .as_ref()
+            .map_or(self.sequencer_pub_key.as_slice(), |sequencer| {
+                sequencer.public_key.as_slice()
+            });
+        if signed_batch.sequencer_pub_key() != sequencer_pub_key {
+            bail!(
+                "L2 block #{} is not signed by the active sequencer 0x{}",
+                l2_height,
+                hex::encode(sequencer_pub_key)
+            );
+        }
+        if let Some(sequencer) = self.registered_sequencers.update(active_sequencer) {
+            info!(
+                "Sequencer is rotated to 0x{} with DA public key 0x{}",
+                hex::encode(&sequencer.public_key),
+                hex::encode(&sequencer.da_pub_key)
+            );
+        }
+
+        // The STF takes the L2 block mutably, keep it as passed to record and retry it on a mismatch
+        let unexecuted_batch = signed_batch.clone();
         let slot_result = self.stf.apply_soft_batch(
             self.sequencer_pub_key.as_slice(),
             // TODO(https://github.com/Sovereign-Labs/sovereign-sdk/issues/1247): incorrect pre-state root in case of re-org
@@ -434,13 +1147,31 @@ where
             pre_state,
             Default::default(),
             current_l1_block.header(),
+            &slot_inbox,
             &current_l1_block.validity_condition(),
-            &mut soft_batch.clone().into(),
+            &mut signed_batch,
         );
 
         let next_state_root = slot_result.state_root;
         // Check if post state root is the same as the one in the soft batch
         if next_state_root.as_ref().to_vec() != soft_batch.state_root {
+            let diagnostics = StateRootMismatchDiagnostics {
+                l2_height,
+                pre_state_root: &self.state_root,
+                expected_state_root: &soft_batch.state_root,
+                state_root: &next_state_root,
+                soft_batch: &unexecuted_batch,
+                l1_block_header: current_l1_block.header(),
+                witness: &slot_result.witness,
+            };
+            let halted = self.halt_on_state_root_mismatch(&diagnostics);
+            self.mismatched_l2_block = Some(VerifiedL2Block {
+                l2_height,
+                soft_batch,
+                signed_batch: unexecuted_batch,
+                l1_block: current_l1_block,
+            });
+            halted?;
             bail!("Post state root mismatch at height: {}", l2_height)
         }
 
@@ -466,21 +1197,12 @@ where
             timestamp: soft_batch.timestamp,
         };
 
+        // The next block is executed on top of this snapshot, before it is finalized
         self.storage_manager
             .save_change_set_l2(l2_height, slot_result.change_set)?;
-
-        self.storage_manager.finalize_l2(l2_height)?;
-
+        // Kept to check the state diff posted with the commitment of the block
         self.ledger_db
-            .commit_soft_batch(soft_batch_receipt, self.include_tx_body)?;
-
-        self.ledger_db.extend_l2_range_of_l1_slot(
-            SlotNumber(current_l1_block.header().height()),
-            BatchNumber(l2_height),
-        )?;
-
-        // Only errors when there are no receivers
-        let _ = self.soft_confirmation_tx.send(l2_height);
+            .put_l2_state_diff(BatchNumber(l2_height), &slot_result.state_diff)?;
 
         self.state_root = next_state_root;
         self.batch_hash = soft_batch.hash;
@@ -490,55 +1212,334 @@ where
             l2_height, self.state_root
         );
 
+        // State is finalized once the ledger writer has persisted the block
+        ledger_tx
+            .send((l2_height, soft_batch_receipt))
+            .await
+            .map_err(|_| anyhow!("L2 ledger writer has stopped"))?;
+
+        Ok(())
+    }
+
+    /// Records the inputs of the L2 block to the diagnostics directory,
+    /// and halts execution until an operator clears the mismatch.
+    fn halt_on_state_root_mismatch<Header: Serialize, Witness: Serialize>(
+        &self,
+        diagnostics: &StateRootMismatchDiagnostics<
+            '_,
+            StateRoot<Stf, Vm, Da::Spec>,
+            Header,
+            Witness,
+        >,
+    ) -> anyhow::Result<()> {
+        let diagnostics_path = self.diagnostics_dir.as_ref().and_then(|dir| {
+            write_state_root_mismatch_diagnostics(dir, diagnostics)
+                .map_err(|e| {
+                    error!(
+                        "Could not record the inputs of L2 block #{}: {}",
+                        diagnostics.l2_height, e
+                    )
+                })
+                .ok()
+        });
+        let report = StateRootMismatchReport {
+            l2_height: BatchNumber(diagnostics.l2_height),
+            expected_state_root: diagnostics.expected_state_root.to_vec(),
+            state_root: diagnostics.state_root.as_ref().to_vec(),
+            diagnostics_path: diagnostics_path.map(|path| path.display().to_string()),
+            halted_at: self.clock.unix_timestamp(),
+        };
+        self.state_root_mismatch_halted.store(true, Ordering::SeqCst);
+        STATE_ROOT_MISMATCH_HALTED.set(1);
+        error!(
+            "Execution halted, state root of L2 block #{} does not match: {:?}",
+            diagnostics.l2_height, report
+        );
+        self.ledger_db.set_state_root_mismatch(&report)
+    }
+
+    /// Halts the node after a DA reorg, see [`halt_after_reorg`].
+    fn halt_after_reorg(&self, reorg: &Reorg) -> ReorgHalts {
+        let Some(config) = &self.da_monitor else {
+            return ReorgHalts::default();
+        };
+        let halts = halt_after_reorg(&self.ledger_db, config, reorg, self.clock.unix_timestamp());
+        if halts.commitments {
+            DA_COMMITMENTS_HALTED.set(1);
+            self.commitments_halted.store(true, Ordering::SeqCst);
+        }
+        if halts.execution {
+            DA_EXECUTION_HALTED.set(1);
+            self.execution_halted.store(true, Ordering::SeqCst);
+        }
+        halts
+    }
+
+    /// Whether L2 blocks are not executed, because of a DA reorg or a state root mismatch
+    fn is_execution_halted(&self) -> bool {
+        self.execution_halted.load(Ordering::SeqCst)
+            || self.state_root_mismatch_halted.load(Ordering::SeqCst)
+    }
+
+    /// Executes the L2 block whose state root did not match again, once the halt is cleared.
+    /// Fails if the block can't be processed for another reason than a state root mismatch,
+    /// which halts execution again.
+    async fn retry_mismatched_l2_block(
+        &mut self,
+        ledger_tx: &mpsc::Sender<(u64, L2LedgerReceipt<Stf, Vm, Da::Spec>)>,
+    ) -> anyhow::Result<()> {
+        if self.is_execution_halted() {
+            return Ok(());
+        }
+        let Some(l2_block) = self.mismatched_l2_block.take() else {
+            return Ok(());
+        };
+        info!(
+            "Executing L2 block #{} again after the state root mismatch is cleared",
+            l2_block.l2_height
+        );
+        if let Err(e) = self.process_l2_block(l2_block, ledger_tx).await {
+            if self.mismatched_l2_block.is_none() {
+                return Err(e.context("Could not process L2 block"));
+            }
+            error!("Could not process L2 block: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Finalizes the state of an L2 block whose ledger data is persisted.
+    fn finalize_l2_block(&mut self, l2_height: u64) -> anyhow::Result<()> {
+        self.storage_manager.finalize_l2(l2_height)?;
+
+        // Only errors when there are no receivers
+        let _ = self.soft_confirmation_tx.send(l2_height);
+
+        if let Err(e) = self.watch_l2_block(l2_height) {
+            warn!("Watchtower could not check L2 block #{}: {}", l2_height, e);
+        }
+
+        Ok(())
+    }
+
+    /// Checks the deposits and withdrawals of the finalized L2 block, if the watchtower is enabled.
+    fn watch_l2_block(&mut self, l2_height: u64) -> anyhow::Result<()> {
+        let Some(watchtower) = &mut self.watchtower else {
+            return Ok(());
+        };
+
+        let mut working_set = WorkingSet::new(self.storage_manager.create_finalized_storage()?);
+        let evm = Evm::<C>::default();
+        let events = evm.bridge_events_in_block(l2_height, &mut working_set);
+        let bridge_balance = evm
+            .get_balance(Bridge::address(), None, &mut working_set)
+            .map_err(|e| anyhow!("Could not read the Bridge balance: {}", e))?
+            .saturating_to::<u128>();
+
+        let withdrawal_queue = WithdrawalQueue::<C>::default();
+        let recorded_withdrawals =
+            withdrawal_queue.withdrawals_in_l2_range(l2_height..=l2_height, &mut working_set)?;
+
+        let posted_deposits = self
+            .ledger_db
+            .get_soft_batch_by_number(&BatchNumber(l2_height))?
+            .map(|soft_batch| soft_batch.deposit_data.len())
+            .unwrap_or_default();
+
+        let alerts = watchtower.checks.check_l2_block(
+            l2_height,
+            &events,
+            &recorded_withdrawals,
+            posted_deposits,
+            bridge_balance,
+        );
+        watchtower.alert(alerts);
+
+        let proven_withdrawals = match self.ledger_db.get_last_proven_l2_height()? {
+            Some(last_proven_l2_height) => withdrawal_queue
+                .withdrawal_count_until(last_proven_l2_height.0, &mut working_set)?,
+            None => withdrawal_queue
+                .first_withdrawal_index(&mut working_set)
+                .unwrap_or_default(),
+        };
+        WATCHTOWER_PENDING_WITHDRAWALS.set(
+            withdrawal_queue
+                .withdrawal_count(&mut working_set)
+                .saturating_sub(proven_withdrawals) as i64,
+        );
+
         Ok(())
     }
 
     /// Runs the rollup.
+    ///
+    /// L2 blocks go through a pipeline of stages connected by bounded channels:
+    /// fetch, stateless verification, execution, ledger write and state finalization.
     #[instrument(level = "trace", skip_all, err)]
-    pub async fn run(&mut self) -> Result<(), anyhow::Error> {
+    pub async fn run(&mut self) -> Result<(), anyhow::Error>
+    where
+        DB: Clone + Send + Sync + 'static,
+        <Stf as StateTransitionFunction<Vm, Da::Spec>>::TxReceiptContents: Send + 'static,
+    {
         let (l1_tx, mut l1_rx) = mpsc::channel(1);
+        let (reorg_tx, mut reorg_rx) = mpsc::unbounded_channel();
+        if let Some(config) = self.da_monitor {
+            tokio::spawn(da_monitor(
+                self.da_service.clone(),
+                config,
+                reorg_tx,
+                self.clock.clone(),
+            ));
+        }
+
+        if let Some(config_reloader) = &self.config_reloader {
+            tokio::spawn(apply_fee_ceilings(
+                self.da_service.clone(),
+                config_reloader.subscribe(),
+            ));
+        }
+
+        if self.backfill_history {
+            let ledger_db = self.ledger_db.clone();
+            let sequencer_client = self.sequencer_client.clone();
+            let include_tx_body = self.include_tx_body;
+            tokio::spawn(async move {
+                if let Err(e) =
+                    backfill_l2::<Da, C, DB>(ledger_db, sequencer_client, include_tx_body).await
+                {
+                    error!("Stopped backfilling L2 blocks: {}", e);
+                }
+            });
+        }
+
         let l1_sync_worker = l1_sync(
             self.start_l1_height,
             self.da_service.clone(),
             l1_tx,
             self.l1_block_cache.clone(),
+            self.clock.clone(),
         );
         tokio::pin!(l1_sync_worker);
 
-        let (l2_tx, mut l2_rx) = mpsc::channel(1);
+        let (l2_tx, l2_rx) = mpsc::channel(1);
         let l2_sync_worker = sync_l2::<Da>(
             self.start_l2_height,
             self.sequencer_client.clone(),
             l2_tx,
             self.sync_blocks_count,
+            self.config_reloader
+                .as_ref()
+                .map(|config_reloader| config_reloader.subscribe()),
         );
         tokio::pin!(l2_sync_worker);
 
+        let (verified_tx, mut verified_rx) = mpsc::channel(L2_PIPELINE_DEPTH);
+        let mut l2_verifier = tokio::spawn(verify_l2::<Da, C>(
+            self.da_service.clone(),
+            self.l1_block_cache.clone(),
+            l2_rx,
+            verified_tx,
+        ));
+
+        // Acknowledgements are not bounded, so the ledger writer never waits on the executor
+        let (ledger_tx, ledger_rx) = mpsc::channel(L2_PIPELINE_DEPTH);
+        let (committed_tx, mut committed_rx) = mpsc::unbounded_channel();
+        let ledger_db = self.ledger_db.clone();
+        let include_tx_body = self.include_tx_body;
+        let mut l2_ledger_writer = tokio::task::spawn_blocking(move || {
+            write_l2_ledger(ledger_db, include_tx_body, ledger_rx, committed_tx)
+        });
+
         let mut pending_l1_blocks: VecDeque<<Da as DaService>::FilteredBlock> =
             VecDeque::<Da::FilteredBlock>::new();
         let pending_l1 = &mut pending_l1_blocks;
+        let mut last_l1_height = self.start_l1_height;
 
         let mut interval = tokio::time::interval(Duration::from_secs(1));
         interval.tick().await;
 
+        let mut pruning_interval =
+            tokio::time::interval(Duration::from_secs(self.pruning_config.interval.max(1)));
+        pruning_interval.tick().await;
+
+        let mut backup_rx = self
+            .backup_rx
+            .take()
+            .ok_or(anyhow!("Full node is already running"))?;
+        let mut pruning_task: Option<tokio::task::JoinHandle<()>> = None;
+
         loop {
             select! {
                 _ = &mut l1_sync_worker => {},
                 _ = &mut l2_sync_worker => {},
                 Some(l1_block) = l1_rx.recv() => {
+                    last_l1_height = l1_block.header().height();
                     pending_l1.push_back(l1_block);
                 },
+                // Halts are applied between blocks, so no block is processed after the reorg.
+                // The L1 blocks after the fork of a reorg deeper than the finality depth were
+                // scanned on the replaced chain, they are scanned again on the new chain
+                Some(reorg) = reorg_rx.recv() => {
+                    let fork_height = reorg.fork_height;
+                    if self.halt_after_reorg(&reorg).commitments && fork_height < last_l1_height {
+                        warn!(
+                            "Scanning L1 blocks again from L1 height {} after a DA reorg",
+                            fork_height + 1
+                        );
+                        pending_l1.retain(|l1_block| l1_block.header().height() <= fork_height);
+                        self.l1_block_cache.lock().await.0.clear();
+                        let (l1_tx, new_l1_rx) = mpsc::channel(1);
+                        l1_rx = new_l1_rx;
+                        l1_sync_worker.set(l1_sync(
+                            fork_height,
+                            self.da_service.clone(),
+                            l1_tx,
+                            self.l1_block_cache.clone(),
+                            self.clock.clone(),
+                        ));
+                        last_l1_height = fork_height;
+                    }
+                },
                 _ = interval.tick() => {
+                    self.retry_mismatched_l2_block(&ledger_tx).await?;
                     self.process_l1_block(pending_l1).await
                 },
-                Some(l2_blocks) = l2_rx.recv() => {
-                    for (l2_height, l2_block) in l2_blocks {
-                        let l1_block = get_da_block_at_height(&self.da_service, l2_block.da_slot_height, self.l1_block_cache.clone()).await?;
-                        if let Err(e) = self.process_l2_block(l2_height, l2_block, l1_block).await {
-                            error!("Could not process L2 block: {}", e);
+                // A pruning pass can take longer than the interval, the next one starts after it
+                _ = pruning_interval.tick(), if self.pruning_config.is_enabled()
+                    && pruning_task.as_ref().map_or(true, |task| task.is_finished()) => {
+                    let ledger_db = self.ledger_db.clone();
+                    let state_pruner = self.storage_manager.l2_pruner();
+                    let pruning_config = self.pruning_config;
+                    pruning_task = Some(tokio::task::spawn_blocking(move || {
+                        if let Err(e) = prune(&ledger_db, state_pruner, &pruning_config) {
+                            error!("Could not prune: {}", e);
+                        }
+                    }));
+                },
+                result = &mut l2_verifier => {
+                    result??;
+                    bail!("L2 block verifier has stopped");
+                },
+                result = &mut l2_ledger_writer => {
+                    result??;
+                    bail!("L2 ledger writer has stopped");
+                },
+                // Verified blocks back up in the pipeline while execution is halted,
+                // the block whose state root did not match is executed first once it is cleared
+                Some(l2_block) = verified_rx.recv(), if !self.is_execution_halted() && self.mismatched_l2_block.is_none() => {
+                    if let Err(e) = self.process_l2_block(l2_block, &ledger_tx).await {
+                        // The L2 blocks after it can't be applied, only a state root mismatch is retried
+                        if self.mismatched_l2_block.is_none() {
+                            return Err(e.context("Could not process L2 block"));
                         }
+                        error!("Could not process L2 block: {}", e);
                     }
                 },
+                Some(l2_height) = committed_rx.recv() => {
+                    self.finalize_l2_block(l2_height)?;
+                },
+                Some((path, reply_tx)) = backup_rx.recv() => {
+                    let _ = reply_tx.send(self.create_backup(&path));
+                },
             }
         }
     }
@@ -547,6 +1548,21 @@ where
         &self,
         pending_l1_blocks: &mut VecDeque<<Da as DaService>::FilteredBlock>,
     ) {
+        if self.execution_halted.load(Ordering::SeqCst) {
+            warn!(
+                "Not processing L1 blocks while execution is halted after a DA reorg, {} pending",
+                pending_l1_blocks.len()
+            );
+            return;
+        }
+        if self.commitments_halted.load(Ordering::SeqCst) {
+            warn!(
+                "Not processing L1 blocks after a DA reorg deeper than the finality depth, {} pending",
+                pending_l1_blocks.len()
+            );
+            return;
+        }
+
         while !pending_l1_blocks.is_empty() {
             let l1_block = pending_l1_blocks
                 .front()
@@ -559,14 +1575,34 @@ where
                 )
                 .unwrap();
 
-            let (sequencer_commitments, zk_proofs) =
+            let (sequencer_commitments, zk_proofs, withdrawal_claims) =
                 self.extract_relevant_l1_data(l1_block.clone());
 
-            for zk_proof in zk_proofs.clone().iter() {
-                if let Err(e) = self
-                    .process_zk_proof(l1_block.clone(), zk_proof.clone())
-                    .await
-                {
+            // Claims are checked against the withdrawals proven before their L1 block
+            match self.watch_withdrawal_claims(l1_block.header().height(), &withdrawal_claims) {
+                Ok(()) => {}
+                Err(SyncError::MissingL2(msg, start_l2_height, end_l2_height)) => {
+                    warn!("Could not check withdrawal claims. Missing L2 blocks {:?} - {:?}, msg = {}", start_l2_height, end_l2_height, msg);
+                    return;
+                }
+                Err(SyncError::Error(e)) => {
+                    warn!("Watchtower could not check the withdrawal claims: {}", e);
+                }
+            }
+
+            for (zk_proof, da_tx_id) in zk_proofs {
+                let result = match zk_proof {
+                    DaData::AggregatedZKProof(proof) => {
+                        self.process_aggregated_zk_proof(l1_block.clone(), proof, da_tx_id)
+                            .await
+                    }
+                    DaData::ZKProof(proof) => {
+                        self.process_zk_proof(l1_block.clone(), proof, da_tx_id)
+                            .await
+                    }
+                    _ => unreachable!("Only proofs are extracted"),
+                };
+                if let Err(e) = result {
                     match e {
                         SyncError::MissingL2(msg, start_l2_height, end_l2_height) => {
                             warn!("Could not completely process ZK proofs. Missing L2 blocks {:?} - {:?}. msg = {}", start_l2_height, end_l2_height, msg);
@@ -579,44 +1615,156 @@ where
                 }
             }
 
-            for sequencer_commitment in sequencer_commitments.clone().iter() {
-                if let Err(e) = self
-                    .process_sequencer_commitment(l1_block.clone(), sequencer_commitment.clone())
+            let mut processed_commitments = vec![];
+            for (sequencer_commitment, posted_state_diff, da_tx_id, sender) in sequencer_commitments
+            {
+                match self
+                    .process_sequencer_commitment(
+                        l1_block.clone(),
+                        sequencer_commitment.clone(),
+                        posted_state_diff,
+                        da_tx_id,
+                        &sender,
+                    )
                     .await
                 {
-                    match e {
-                        SyncError::MissingL2(msg, start_l2_height, end_l2_height) => {
-                            warn!("Could not completely process sequencer commitments. Missing L2 blocks {:?} - {:?}, msg = {}", start_l2_height, end_l2_height, msg);
-                            return;
-                        }
-                        SyncError::Error(e) => {
-                            error!("Could not process sequencer commitments: {}... skipping", e);
-                        }
+                    Ok(()) => processed_commitments.push(sequencer_commitment),
+                    Err(SyncError::MissingL2(msg, start_l2_height, end_l2_height)) => {
+                        warn!("Could not completely process sequencer commitments. Missing L2 blocks {:?} - {:?}, msg = {}", start_l2_height, end_l2_height, msg);
+                        return;
                     }
+                    Err(SyncError::Error(e)) => {
+                        error!("Could not process sequencer commitments: {}... skipping", e);
+                    }
+                }
+            }
+
+            if let Some(challenge_window) = self.challenge_window {
+                if let Err(e) = update_challenge_windows(
+                    &self.ledger_db,
+                    challenge_window,
+                    l1_block.header().height(),
+                    processed_commitments,
+                ) {
+                    error!("Could not update the challenge windows: {}", e);
                 }
             }
 
+            if let Err(e) = self
+                .ledger_db
+                .set_sync_checkpoint_l1_height(SlotNumber(l1_block.header().height()))
+            {
+                error!("Could not save sync checkpoint: {}", e);
+            }
+
             pending_l1_blocks.pop_front();
         }
     }
 
+    /// Backs up state and ledger dbs into `path`, which can later be restored as the storage path.
+    /// Returns the L2 height of the backed up state.
+    fn create_backup(&self, path: &Path) -> anyhow::Result<Option<u64>> {
+        if path.exists() {
+            bail!("Backup path {} already exists", path.display());
+        }
+        std::fs::create_dir_all(path)?;
+
+        // State is finalized only after its ledger entries are written, so checkpointing
+        // the state first guarantees the ledger backup is never behind it
+        let l2_height = self.storage_manager.get_last_finalized_l2_height()?;
+        self.storage_manager.create_checkpoint(path)?;
+        self.ledger_db.create_checkpoint(path)?;
+
+        info!(
+            "Created backup at {} with state at L2 height {:?}",
+            path.display(),
+            l2_height
+        );
+
+        Ok(l2_height)
+    }
+
+    /// DA public key the sequencer with the soft confirmation public key signs commitments
+    /// with at the L1 height. `None` if it is neither the sequencer of the rollup config,
+    /// nor the active or previous sequencer registered on chain.
+    fn sequencer_da_pub_key_of(&self, pub_key: &[u8], l1_height: u64) -> Option<&[u8]> {
+        self.registered_sequencers.da_pub_key_at(
+            pub_key,
+            &self.sequencer_pub_key,
+            self.public_keys.sequencer_da_pub_key_at(l1_height),
+        )
+    }
+
+    /// Whether commitments of the L1 height may have been signed with the DA public key
+    fn is_sequencer_da_pub_key_at(&self, pub_key: &[u8], l1_height: u64) -> bool {
+        is_sequencer_da_pub_key_at(
+            self.public_keys.sequencer_da_pub_key_at(l1_height),
+            Some(&self.registered_sequencers),
+            pub_key,
+        )
+    }
+
+    /// Whether commitments of the DA slot may have been signed with the DA public key
+    fn is_sequencer_da_pub_key_of_slot(
+        &self,
+        l1_hash: [u8; 32],
+        da_pub_key: &[u8],
+    ) -> anyhow::Result<bool> {
+        let l1_height = self
+            .ledger_db
+            .get_l1_height_of_l1_hash(l1_hash)?
+            .ok_or(anyhow!(
+                "Proof verification: L1 height not found for l1 hash: {:?}",
+                l1_hash
+            ))?;
+        Ok(self.is_sequencer_da_pub_key_at(da_pub_key, l1_height))
+    }
+
     fn extract_relevant_l1_data(
         &self,
         l1_block: Da::FilteredBlock,
-    ) -> (Vec<SequencerCommitment>, Vec<Proof>) {
-        let mut sequencer_commitments = Vec::<SequencerCommitment>::new();
-        let mut zk_proofs = Vec::<Proof>::new();
+    ) -> (
+        Vec<(
+            SequencerCommitment,
+            Option<StateDiff>,
+            Option<[u8; 32]>,
+            Vec<u8>,
+        )>,
+        Vec<(DaData, Option<[u8; 32]>)>,
+        Vec<WithdrawalClaim>,
+    ) {
+        let mut sequencer_commitments = vec![];
+        let mut zk_proofs = Vec::<(DaData, Option<[u8; 32]>)>::new();
+        let mut withdrawal_claims = vec![];
 
         self.da_service
             .extract_relevant_blobs(&l1_block)
             .into_iter()
             .for_each(|mut tx| {
-                let data = DaData::try_from_slice(tx.full_data());
-                // Check for commitment
-                if tx.sender().as_ref() == self.sequencer_da_pub_key.as_slice() {
+                let data = DaData::decode(tx.full_data());
+                // Check for commitment, its sender is checked against the signer
+                // of its L2 blocks once they are synced
+                if self.is_sequencer_da_pub_key_at(tx.sender().as_ref(), l1_block.header().height())
+                {
                     if let Ok(DaData::SequencerCommitment(seq_com)) = data {
-                        sequencer_commitments.push(seq_com);
-                    } else {
+                        sequencer_commitments.push((
+                            seq_com,
+                            None,
+                            tx.da_tx_id(),
+                            tx.sender().as_ref().to_vec(),
+                        ));
+                    } else if let Ok(DaData::CommitmentWithStateDiff(CommitmentWithStateDiff {
+                        commitment,
+                        state_diff,
+                    })) = data
+                    {
+                        sequencer_commitments.push((
+                            commitment,
+                            Some(state_diff),
+                            tx.da_tx_id(),
+                            tx.sender().as_ref().to_vec(),
+                        ));
+                    } else if !matches!(data, Ok(DaData::SequencerLease(_))) {
                         tracing::warn!(
                             "Found broken DA data in block 0x{}: {:?}",
                             hex::encode(l1_block.hash()),
@@ -624,24 +1772,26 @@ where
                         );
                     }
                 }
-                let data = DaData::try_from_slice(tx.full_data());
+                let data = DaData::decode(tx.full_data());
                 // Check for proof
                 if tx.sender().as_ref() == self.prover_da_pub_key.as_slice() {
-                    if let Ok(DaData::ZKProof(proof)) = data {
-                        zk_proofs.push(proof);
-                    } else {
+                    if let Ok(proof @ (DaData::ZKProof(_) | DaData::AggregatedZKProof(_))) = data {
+                        zk_proofs.push((proof, tx.da_tx_id()));
+                    } else if !matches!(data, Ok(DaData::LightClientProof(_))) {
                         tracing::warn!(
                             "Found broken DA data in block 0x{}: {:?}",
                             hex::encode(l1_block.hash()),
                             data
                         );
                     }
-                } else {
-                    warn!("Force transactions are not implemented yet");
-                    // TODO: This is where force transactions will land - try to parse DA data force transaction
                 }
+                // Claims are posted by the bridge operators, they are checked by the watchtower
+                if let Ok(DaData::WithdrawalClaim(claim)) = DaData::decode(tx.full_data()) {
+                    withdrawal_claims.push(claim);
+                }
+                // Forced transactions are checked when the L2 blocks on this L1 block are applied
             });
-        (sequencer_commitments, zk_proofs)
+        (sequencer_commitments, zk_proofs, withdrawal_claims)
     }
 
     /// Allows to read current state root
@@ -650,11 +1800,12 @@ where
     }
 }
 
-async fn l1_sync<Da>(
+pub(crate) async fn l1_sync<Da>(
     start_l1_height: u64,
     da_service: Da,
     sender: mpsc::Sender<Da::FilteredBlock>,
     l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
+    clock: SharedClock,
 ) where
     Da: DaService,
 {
@@ -669,7 +1820,7 @@ async fn l1_sync<Da>(
                 Ok(header) => header,
                 Err(e) => {
                     error!("Could not fetch last finalized L1 block header: {}", e);
-                    sleep(Duration::from_secs(2)).await;
+                    clock.sleep(Duration::from_secs(2)).await;
                     continue;
                 }
             };
@@ -684,7 +1835,7 @@ async fn l1_sync<Da>(
                     Ok(block) => block,
                     Err(e) => {
                         error!("Could not fetch last finalized L1 block: {}", e);
-                        sleep(Duration::from_secs(2)).await;
+                        clock.sleep(Duration::from_secs(2)).await;
                         continue 'block_sync;
                     }
                 };
@@ -698,31 +1849,222 @@ async fn l1_sync<Da>(
             }
         }
 
-        sleep(Duration::from_secs(2)).await;
+        clock.sleep(Duration::from_secs(2)).await;
+    }
+}
+
+/// Resolves the L1 block of each fetched L2 block and checks its hash and signature,
+/// so that only blocks passing stateless checks reach the executor.
+async fn verify_l2<Da, C>(
+    da_service: Da,
+    l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
+    mut receiver: mpsc::Receiver<Vec<(u64, GetSoftBatchResponse)>>,
+    sender: mpsc::Sender<VerifiedL2Block<Da>>,
+) -> anyhow::Result<()>
+where
+    Da: DaService<Error = anyhow::Error>,
+    C: Context,
+{
+    while let Some(l2_blocks) = receiver.recv().await {
+        for (l2_height, soft_batch) in l2_blocks {
+            let l1_block = get_da_block_at_height(
+                &da_service,
+                soft_batch.da_slot_height,
+                l1_block_cache.clone(),
+            )
+            .await?;
+
+            let signed_batch: SignedSoftConfirmationBatch = soft_batch.clone().into();
+            // Whether the signer is the active sequencer depends on the state, it is checked on execution
+            // The L2 blocks after it can't be applied, so syncing stops at it
+            if let Err(e) = verify_soft_batch::<C>(signed_batch.sequencer_pub_key(), &signed_batch)
+            {
+                bail!("Could not verify L2 block #{}: {}", l2_height, e);
+            }
+
+            let l2_block = VerifiedL2Block {
+                l2_height,
+                soft_batch,
+                signed_batch,
+                l1_block,
+            };
+            if sender.send(l2_block).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Persists executed L2 blocks to the ledger in order and reports back
+/// the heights whose state can now be finalized.
+fn write_l2_ledger<DB, T, DS>(
+    ledger_db: DB,
+    include_tx_body: bool,
+    mut receiver: mpsc::Receiver<(u64, SoftBatchReceipt<u64, T, DS>)>,
+    committed: mpsc::UnboundedSender<u64>,
+) -> anyhow::Result<()>
+where
+    DB: NodeLedgerOps,
+    T: serde::Serialize,
+    DS: DaSpec,
+{
+    while let Some((l2_height, soft_batch_receipt)) = receiver.blocking_recv() {
+        // Ledger is written before state, so that a block whose state is finalized
+        // is always in the ledger. After a restart, the block might already be in the ledger.
+        match ledger_db.get_soft_batch_by_number(&BatchNumber(l2_height))? {
+            Some(stored_soft_batch) => {
+                if stored_soft_batch.hash != soft_batch_receipt.hash {
+                    bail!(
+                        "Soft batch hash mismatch with ledger at height: {}",
+                        l2_height
+                    );
+                }
+                debug!(
+                    "Soft batch #{} is already in ledger. Skipping ledger commit.",
+                    l2_height
+                );
+            }
+            None => {
+                ledger_db.commit_soft_batch_with_checkpoint(soft_batch_receipt, include_tx_body)?;
+            }
+        }
+
+        if committed.send(l2_height).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Prunes state and ledger data below the retention horizon of the pruning mode.
+/// Only L2 heights which are covered by a verified proof are ever pruned.
+/// Runs on a blocking thread, the state is pruned by `state_pruner`.
+fn prune<DB>(
+    ledger_db: &DB,
+    state_pruner: Box<dyn FnOnce(u64) -> anyhow::Result<()> + Send>,
+    pruning_config: &PruningConfig,
+) -> anyhow::Result<()>
+where
+    DB: NodeLedgerOps,
+{
+    let Some(last_proven_l2_height) = ledger_db.get_last_proven_l2_height()? else {
+        return Ok(());
+    };
+
+    if let Some(body_retention) = pruning_config.body_retention {
+        prune_bodies(
+            ledger_db,
+            last_proven_l2_height.0.saturating_sub(body_retention),
+        )?;
+    }
+
+    let (horizon, keep_headers) = match pruning_config.mode {
+        PruningMode::Archive => return Ok(()),
+        PruningMode::Full { distance } => (last_proven_l2_height.0.saturating_sub(distance), true),
+        PruningMode::Minimal => (last_proven_l2_height.0, false),
+    };
+
+    let start = ledger_db
+        .get_last_pruned_l2_height()?
+        .map(|height| height.0 + 1)
+        .unwrap_or(1);
+    let horizon = horizon.min(start + MAX_PRUNED_L2_BLOCKS_PER_ROUND);
+    if horizon <= start {
+        return Ok(());
+    }
+
+    if ledger_db.get_l2_soft_confirmation_status(BatchNumber(horizon))?
+        != Some(SoftConfirmationStatus::Proven)
+    {
+        warn!(
+            "Pruning: L2 height {} is not proven. Skipping pruning round.",
+            horizon
+        );
+        return Ok(());
     }
+
+    info!("Pruning L2 heights {}-{}", start, horizon - 1);
+
+    state_pruner(horizon)?;
+    ledger_db.prune_l2_range(&(BatchNumber(start)..BatchNumber(horizon)), keep_headers)?;
+
+    Ok(())
+}
+
+/// Prunes transaction bodies and witnesses of soft confirmations below `horizon`.
+fn prune_bodies<DB>(ledger_db: &DB, horizon: u64) -> anyhow::Result<()>
+where
+    DB: NodeLedgerOps,
+{
+    let start = ledger_db
+        .get_last_body_pruned_l2_height()?
+        .max(ledger_db.get_last_pruned_l2_height()?)
+        .map(|height| height.0 + 1)
+        .unwrap_or(1);
+    let horizon = horizon.min(start + MAX_PRUNED_L2_BLOCKS_PER_ROUND);
+    if horizon <= start {
+        return Ok(());
+    }
+
+    info!(
+        "Pruning transaction bodies of L2 heights {}-{}",
+        start,
+        horizon - 1
+    );
+
+    ledger_db.prune_l2_bodies(&(BatchNumber(start)..BatchNumber(horizon)))
 }
 
 async fn sync_l2<Da>(
     start_l2_height: u64,
-    sequencer_client: SequencerClient,
+    sequencer_client: FailoverSequencerClient,
     sender: mpsc::Sender<Vec<(u64, GetSoftBatchResponse)>>,
     sync_blocks_count: u64,
+    mut reloaded_config: Option<watch::Receiver<ReloadableConfig>>,
 ) where
     Da: DaService,
 {
     let mut l2_height = start_l2_height;
     info!("Starting to sync from L2 height {}", l2_height);
+
+    let mut sync_batch_size = AdaptiveSyncBatchSize::new(sync_blocks_count);
+    let mut sync_blocks_count = sync_blocks_count;
+
+    let mut last_health_check: Option<Instant> = None;
     loop {
+        if last_health_check.map_or(true, |checked_at| {
+            checked_at.elapsed() >= SEQUENCER_HEALTH_CHECK_INTERVAL
+        }) {
+            if let Err(e) = sequencer_client.health_check().await {
+                warn!("Sequencer health check failed: {}", e);
+            }
+            last_health_check = Some(Instant::now());
+        }
+
+        if let Some(reloaded_config) = reloaded_config.as_mut() {
+            if reloaded_config.has_changed().unwrap_or(false) {
+                let reloaded = reloaded_config.borrow_and_update().sync_blocks_count;
+                if reloaded != sync_blocks_count {
+                    info!("Sync batch size reloaded to {}", reloaded);
+                    sync_blocks_count = reloaded;
+                    sync_batch_size = AdaptiveSyncBatchSize::new(sync_blocks_count);
+                }
+            }
+        }
+
         let exponential_backoff = ExponentialBackoffBuilder::new()
             .with_initial_interval(Duration::from_secs(1))
             .with_max_elapsed_time(Some(Duration::from_secs(15 * 60)))
             .build();
 
         let inner_client = &sequencer_client;
+        let batch_size = sync_batch_size.get();
+        let fetch_started_at = Instant::now();
         let soft_batches: Vec<GetSoftBatchResponse> =
             match retry_backoff(exponential_backoff.clone(), || async move {
                 match inner_client
-                    .get_soft_batch_range::<Da::Spec>(l2_height..l2_height + sync_blocks_count)
+                    .get_soft_batch_range::<Da::Spec>(l2_height..l2_height + batch_size)
                     .await
                 {
                     Ok(soft_batches) => Ok(soft_batches.into_iter().flatten().collect::<Vec<_>>()),
@@ -750,6 +2092,7 @@ async fn sync_l2<Da>(
                     continue;
                 }
             };
+        let fetch_latency = fetch_started_at.elapsed();
 
         if soft_batches.is_empty() {
             debug!(
@@ -761,6 +2104,12 @@ async fn sync_l2<Da>(
             continue;
         }
 
+        let fetched = soft_batches.len() as u64;
+        let response_bytes = soft_batches
+            .iter()
+            .map(GetSoftBatchResponse::body_size)
+            .sum();
+
         let soft_batches: Vec<(u64, GetSoftBatchResponse)> = (l2_height
             ..l2_height + soft_batches.len() as u64)
             .zip(soft_batches)
@@ -768,8 +2117,106 @@ async fn sync_l2<Da>(
 
         l2_height += soft_batches.len() as u64;
 
+        // The channel is bounded, so this waits until the executor catches up
+        let handoff_started_at = Instant::now();
         if let Err(e) = sender.send(soft_batches).await {
             error!("Could not notify about L2 block: {}", e);
         }
+
+        sync_batch_size.record(
+            fetched,
+            fetch_latency,
+            response_bytes,
+            handoff_started_at.elapsed(),
+        );
+    }
+}
+
+/// Head-first sync: fetches the L2 blocks missing below the oldest stored one from the sequencer,
+/// e.g. after restoring a backup of a pruned node, and stores them in the ledger without executing them.
+/// L2 blocks are backfilled from the newest one, each is trusted because its hash is the
+/// previous hash of the stored L2 block after it. Until then, ledger RPCs fail for their L2 heights.
+async fn backfill_l2<Da, C, DB>(
+    ledger_db: DB,
+    sequencer_client: FailoverSequencerClient,
+    include_tx_body: bool,
+) -> anyhow::Result<()>
+where
+    Da: DaService,
+    C: Context,
+    DB: NodeLedgerOps,
+{
+    while let Some(BatchNumber(end)) = ledger_db.get_last_pruned_l2_height()? {
+        let start = end.saturating_sub(L2_BACKFILL_BATCH_SIZE - 1).max(1);
+        info!("Backfilling L2 blocks {}-{}", start, end);
+
+        let soft_batches = match sequencer_client
+            .get_soft_batch_range::<Da::Spec>(start..end)
+            .await
+        {
+            Ok(soft_batches) => soft_batches,
+            Err(e) => {
+                warn!(
+                    "Could not fetch L2 blocks {}-{} to backfill: {}",
+                    start, end, e
+                );
+                sleep(Duration::from_secs(5)).await;
+                continue;
+            }
+        };
+        // The end of the range is inclusive
+        let soft_batches: Vec<_> = (start..=end).zip(soft_batches).collect();
+        if soft_batches.len() as u64 != end - start + 1 {
+            bail!("Sequencer did not return L2 blocks {}-{}", start, end);
+        }
+
+        for (l2_height, soft_batch) in soft_batches.into_iter().rev() {
+            let soft_batch = soft_batch
+                .ok_or_else(|| anyhow!("Sequencer does not have L2 block #{}", l2_height))?;
+
+            let signed_batch: SignedSoftConfirmationBatch = soft_batch.clone().into();
+            verify_soft_batch::<C>(signed_batch.sequencer_pub_key(), &signed_batch)
+                .map_err(|e| anyhow!("Could not verify L2 block #{}: {}", l2_height, e))?;
+
+            let txs = soft_batch
+                .txs
+                .iter()
+                .flatten()
+                .map(|tx| StoredTransaction {
+                    hash: <C as Spec>::Hasher::digest(&tx.tx).into(),
+                    events: EventNumber(0)..EventNumber(0),
+                    body: include_tx_body.then(|| tx.tx.clone()),
+                })
+                .collect();
+            let deposit_data = if include_tx_body {
+                soft_batch
+                    .deposit_data
+                    .into_iter()
+                    .map(|tx| tx.tx)
+                    .collect()
+            } else {
+                vec![]
+            };
+            // The ledger checks the hash and numbers the transactions
+            ledger_db.backfill_soft_batch(StoredSoftBatch {
+                da_slot_height: soft_batch.da_slot_height,
+                l2_height,
+                da_slot_hash: soft_batch.da_slot_hash,
+                da_slot_txs_commitment: soft_batch.da_slot_txs_commitment,
+                hash: soft_batch.hash,
+                prev_hash: soft_batch.prev_hash,
+                tx_range: TxNumber(0)..TxNumber(0),
+                txs,
+                deposit_data,
+                state_root: soft_batch.state_root,
+                soft_confirmation_signature: soft_batch.soft_confirmation_signature,
+                pub_key: soft_batch.pub_key,
+                l1_fee_rate: soft_batch.l1_fee_rate,
+                timestamp: soft_batch.timestamp,
+            })?;
+        }
     }
+
+    info!("All L2 blocks are backfilled");
+    Ok(())
 }
@@ -0,0 +1,215 @@
+use citrea_evm::system_contracts::{BridgeEvent, BRIDGE_DEPOSIT_AMOUNT};
+use citrea_withdrawal_queue::Withdrawal;
+use serde::Serialize;
+use sov_rollup_interface::da::WithdrawalClaim;
+use sov_stf_runner::WatchtowerConfig;
+use tracing::{error, warn};
+
+use crate::metrics::WATCHTOWER_ALERTS;
+
+/// A violation of the bridge invariants found by the watchtower
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WatchtowerAlert {
+    /// The Bridge contract emitted a withdrawal which is not in the withdrawal queue,
+    /// so it can not be proven and paid on L1
+    UnrecordedWithdrawal { l2_height: u64, index: u64 },
+    /// A withdrawal was claimed on L1 which does not match a withdrawal burned
+    /// in the proven L2 blocks, so the bridge funds pay out what was never burned
+    UnburnedWithdrawal { l1_height: u64, index: u64 },
+    /// The Bridge contract minted more deposits than the sequencer included from L1
+    UnbackedDeposit {
+        l2_height: u64,
+        minted: usize,
+        posted: usize,
+    },
+    /// The Bridge contract holds less than its deposits and withdrawals account for
+    BridgeBalanceDeficit {
+        l2_height: u64,
+        expected: u128,
+        actual: u128,
+    },
+    /// A proof posted on L1 commits to withdrawals which differ from the ones initiated on L2
+    WithdrawalRootMismatch {
+        l1_height: u64,
+        /// Index of the sequencer commitment among the proven ones
+        commitment_index: usize,
+        #[serde(with = "hex::serde")]
+        proven: [u8; 32],
+        #[serde(with = "hex::serde")]
+        expected: [u8; 32],
+    },
+    /// A proof posted on L1 commits to withdrawals of a different number of sequencer commitments
+    WithdrawalRootCountMismatch {
+        l1_height: u64,
+        proven: usize,
+        expected: usize,
+    },
+}
+
+/// Checks the bridge invariants of the executed L2 blocks.
+#[derive(Debug, Default)]
+pub struct BridgeWatchtower {
+    /// Balance of the Bridge contract after the last checked L2 block
+    bridge_balance: Option<u128>,
+}
+
+impl BridgeWatchtower {
+    /// Checks the events of the Bridge contract in the L2 block against the withdrawals
+    /// recorded in the withdrawal queue, the number of deposits posted on L1 and the balance of the Bridge.
+    /// L2 blocks must be checked in order, the balance is compared to the one of the previous block.
+    pub fn check_l2_block(
+        &mut self,
+        l2_height: u64,
+        events: &[BridgeEvent],
+        recorded_withdrawals: &[Withdrawal],
+        posted_deposits: usize,
+        bridge_balance: u128,
+    ) -> Vec<WatchtowerAlert> {
+        let mut alerts = vec![];
+
+        let mut minted = 0;
+        let mut burned = vec![];
+        for event in events {
+            match event {
+                BridgeEvent::Deposit { .. } => minted += 1,
+                BridgeEvent::Withdrawal {
+                    index,
+                    bitcoin_address,
+                } => burned.push((*index, *bitcoin_address)),
+            }
+        }
+
+        for (index, bitcoin_address) in &burned {
+            let is_recorded = recorded_withdrawals.iter().any(|withdrawal| {
+                withdrawal.index == *index && withdrawal.bitcoin_address == *bitcoin_address
+            });
+            if !is_recorded {
+                alerts.push(WatchtowerAlert::UnrecordedWithdrawal {
+                    l2_height,
+                    index: *index,
+                });
+            }
+        }
+
+        if minted > posted_deposits {
+            alerts.push(WatchtowerAlert::UnbackedDeposit {
+                l2_height,
+                minted,
+                posted: posted_deposits,
+            });
+        }
+
+        // Funds can be forced into the Bridge, so only a deficit is a violation
+        if let Some(previous_balance) = self.bridge_balance {
+            let expected = previous_balance
+                .saturating_add(burned.len() as u128 * BRIDGE_DEPOSIT_AMOUNT)
+                .saturating_sub(minted as u128 * BRIDGE_DEPOSIT_AMOUNT);
+            if bridge_balance < expected {
+                alerts.push(WatchtowerAlert::BridgeBalanceDeficit {
+                    l2_height,
+                    expected,
+                    actual: bridge_balance,
+                });
+            }
+        }
+        self.bridge_balance = Some(bridge_balance);
+
+        alerts
+    }
+}
+
+/// Checks the withdrawal claims posted on an L1 block against the withdrawals recorded
+/// in the L2 blocks proven before it. A claim has to match a proven withdrawal,
+/// the withdrawals before the first recorded one can't be checked.
+pub fn check_withdrawal_claims(
+    l1_height: u64,
+    claims: &[WithdrawalClaim],
+    first_recorded_index: u64,
+    last_proven_l2_height: Option<u64>,
+    mut withdrawal: impl FnMut(u64) -> Option<Withdrawal>,
+) -> Vec<WatchtowerAlert> {
+    claims
+        .iter()
+        .filter(|claim| claim.index >= first_recorded_index)
+        .filter(|claim| {
+            !withdrawal(claim.index).is_some_and(|withdrawal| {
+                withdrawal.bitcoin_address == claim.bitcoin_address
+                    && Some(withdrawal.l2_height) <= last_proven_l2_height
+            })
+        })
+        .map(|claim| WatchtowerAlert::UnburnedWithdrawal {
+            l1_height,
+            index: claim.index,
+        })
+        .collect()
+}
+
+/// Compares the withdrawal roots of a proof posted on L1 with the ones computed from the L2 blocks,
+/// one per proven sequencer commitment.
+pub fn check_withdrawal_roots(
+    l1_height: u64,
+    proven: &[[u8; 32]],
+    expected: &[[u8; 32]],
+) -> Vec<WatchtowerAlert> {
+    if proven.len() != expected.len() {
+        return vec![WatchtowerAlert::WithdrawalRootCountMismatch {
+            l1_height,
+            proven: proven.len(),
+            expected: expected.len(),
+        }];
+    }
+
+    proven
+        .iter()
+        .zip(expected)
+        .enumerate()
+        .filter(|(_, (proven, expected))| proven != expected)
+        .map(
+            |(commitment_index, (proven, expected))| WatchtowerAlert::WithdrawalRootMismatch {
+                l1_height,
+                commitment_index,
+                proven: *proven,
+                expected: *expected,
+            },
+        )
+        .collect()
+}
+
+/// Bridge watchtower of the full node, reports the alerts to the log, metrics and the webhook.
+pub(crate) struct Watchtower {
+    pub(crate) checks: BridgeWatchtower,
+    webhook: Option<(reqwest::Client, String)>,
+}
+
+impl Watchtower {
+    pub(crate) fn new(config: WatchtowerConfig) -> Self {
+        Self {
+            checks: BridgeWatchtower::default(),
+            webhook: config
+                .webhook_url
+                .map(|webhook_url| (reqwest::Client::new(), webhook_url)),
+        }
+    }
+
+    /// Reports the alerts. Webhook requests are sent in the background, not to hold back the sync.
+    pub(crate) fn alert(&self, alerts: Vec<WatchtowerAlert>) {
+        for alert in alerts {
+            error!("Watchtower alert: {:?}", alert);
+            WATCHTOWER_ALERTS.inc();
+
+            if let Some((client, webhook_url)) = &self.webhook {
+                let request = client.post(webhook_url).json(&alert);
+                tokio::spawn(async move {
+                    if let Err(e) = request
+                        .send()
+                        .await
+                        .and_then(|response| response.error_for_status())
+                    {
+                        warn!("Could not post watchtower alert: {}", e);
+                    }
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use citrea_fullnode::update_challenge_windows;
+use sov_db::ledger_db::{LedgerDB, NodeLedgerOps};
+use sov_db::schema::types::{BatchNumber, ChallengeableCommitment, SlotNumber};
+use sov_rollup_interface::da::SequencerCommitment;
+use sov_rollup_interface::rpc::SoftConfirmationStatus;
+
+const CHALLENGE_WINDOW: u64 = 10;
+
+fn commitment(l2_start_block_number: u64, l2_end_block_number: u64) -> SequencerCommitment {
+    SequencerCommitment {
+        merkle_root: [l2_start_block_number as u8; 32],
+        l2_start_block_number,
+        l2_end_block_number,
+    }
+}
+
+/// Processes the L1 block at `l1_height` with the commitments found on it
+fn process_l1_block(ledger_db: &LedgerDB, l1_height: u64, commitments: Vec<SequencerCommitment>) {
+    for commitment in &commitments {
+        ledger_db
+            .update_commitments_on_da_slot(l1_height, commitment.clone())
+            .unwrap();
+    }
+    update_challenge_windows(ledger_db, CHALLENGE_WINDOW, l1_height, commitments).unwrap();
+}
+
+fn open_window(commitment: SequencerCommitment, l1_height: u64) -> ChallengeableCommitment {
+    ChallengeableCommitment {
+        commitment,
+        l1_height: SlotNumber(l1_height),
+        challengeable_until: SlotNumber(l1_height + CHALLENGE_WINDOW),
+    }
+}
+
+#[test]
+fn test_commitments_are_challengeable_until_their_window_expires() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+
+    process_l1_block(&ledger_db, 100, vec![commitment(1, 10)]);
+    process_l1_block(&ledger_db, 104, vec![commitment(11, 20)]);
+    process_l1_block(&ledger_db, 109, vec![]);
+    assert_eq!(
+        ledger_db.get_challengeable_commitments().unwrap(),
+        vec![
+            open_window(commitment(1, 10), 100),
+            open_window(commitment(11, 20), 104)
+        ]
+    );
+
+    // The window of the first commitment closes at L1 height 110
+    process_l1_block(&ledger_db, 110, vec![]);
+    assert_eq!(
+        ledger_db.get_challengeable_commitments().unwrap(),
+        vec![open_window(commitment(11, 20), 104)]
+    );
+
+    process_l1_block(&ledger_db, 114, vec![]);
+    assert_eq!(ledger_db.get_challengeable_commitments().unwrap(), vec![]);
+}
+
+#[test]
+fn test_proven_commitments_close_their_window() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+
+    process_l1_block(&ledger_db, 100, vec![commitment(1, 10), commitment(11, 20)]);
+    ledger_db
+        .put_soft_confirmation_status(BatchNumber(10), SoftConfirmationStatus::Proven)
+        .unwrap();
+    process_l1_block(&ledger_db, 101, vec![]);
+
+    assert_eq!(
+        ledger_db.get_challengeable_commitments().unwrap(),
+        vec![open_window(commitment(11, 20), 100)]
+    );
+}
+
+#[test]
+fn test_reposted_commitments_keep_the_window_of_the_first_post() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+
+    process_l1_block(&ledger_db, 100, vec![commitment(1, 10)]);
+    process_l1_block(&ledger_db, 105, vec![commitment(1, 10)]);
+    assert_eq!(
+        ledger_db.get_challengeable_commitments().unwrap(),
+        vec![open_window(commitment(1, 10), 100)]
+    );
+
+    // Posting it again after its window expired doesn't open another one
+    process_l1_block(&ledger_db, 110, vec![]);
+    process_l1_block(&ledger_db, 115, vec![commitment(1, 10)]);
+    assert_eq!(ledger_db.get_challengeable_commitments().unwrap(), vec![]);
+}
+
+#[test]
+fn test_processing_an_l1_block_again_keeps_its_windows() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+
+    // The commitments of an L1 block are processed again after a restart
+    process_l1_block(&ledger_db, 100, vec![commitment(1, 10)]);
+    process_l1_block(&ledger_db, 100, vec![commitment(1, 10)]);
+
+    assert_eq!(
+        ledger_db.get_challengeable_commitments().unwrap(),
+        vec![open_window(commitment(1, 10), 100)]
+    );
+}
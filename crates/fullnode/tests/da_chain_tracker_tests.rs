@@ -0,0 +1,102 @@
+use citrea_fullnode::{DaChainTracker, TrackedBlock};
+
+/// Block of a chain identified by the fork, so forks have different hashes at the same height
+fn block(fork: u8, parent_fork: u8, height: u64, blob_hashes: Vec<[u8; 32]>) -> TrackedBlock {
+    TrackedBlock {
+        height,
+        hash: hash(fork, height),
+        prev_hash: hash(parent_fork, height - 1),
+        blob_hashes,
+    }
+}
+
+fn hash(fork: u8, height: u64) -> [u8; 32] {
+    let mut hash = [fork; 32];
+    hash[..8].copy_from_slice(&height.to_be_bytes());
+    hash
+}
+
+#[test]
+fn test_tracks_extensions_without_reorgs() {
+    let mut tracker = DaChainTracker::new(2);
+    for height in 1..=10 {
+        let block = block(0, 0, height, vec![]);
+        assert!(height == 1 || tracker.connects(&block));
+        assert_eq!(tracker.apply(vec![block]), None);
+    }
+
+    assert_eq!(tracker.tip().unwrap().height, 10);
+    // Only the last blocks are kept
+    assert!(tracker.get(4).is_none());
+    assert!(tracker.get(5).is_some());
+}
+
+#[test]
+fn test_detects_reorgs_and_dropped_blobs() {
+    let mut tracker = DaChainTracker::new(2);
+    for height in 1..=5 {
+        let blobs = if height == 4 {
+            vec![[4; 32], [5; 32]]
+        } else {
+            vec![]
+        };
+        tracker.apply(vec![block(0, 0, height, blobs)]);
+    }
+
+    // The blocks at heights 4 and 5 are replaced, one blob is included again
+    let fork = vec![
+        block(1, 0, 4, vec![]),
+        block(1, 1, 5, vec![[5; 32]]),
+        block(1, 1, 6, vec![]),
+    ];
+    assert!(tracker.connects(&fork[0]));
+    assert!(!tracker.connects(&fork[1]));
+
+    let reorg = tracker.apply(fork).unwrap();
+    assert_eq!(reorg.fork_height, 3);
+    assert_eq!(reorg.depth, 2);
+    assert_eq!(reorg.dropped_blobs, vec![[4; 32]]);
+    assert_eq!(tracker.tip().unwrap().hash, hash(1, 6));
+    assert!(tracker.connects(&block(1, 1, 7, vec![])));
+}
+
+#[test]
+fn test_reorgs_deeper_than_tracked_chain() {
+    let mut tracker = DaChainTracker::new(1);
+    for height in 1..=10 {
+        tracker.apply(vec![block(0, 0, height, vec![])]);
+    }
+
+    // Walking back from the new head stops at the first tracked height
+    assert!(tracker.is_before_window(7));
+    let fork: Vec<_> = (7..=11).map(|height| block(1, 1, height, vec![])).collect();
+    assert!(!tracker.connects(&fork[0]));
+
+    let reorg = tracker.apply(fork).unwrap();
+    assert_eq!(reorg.fork_height, 6);
+    assert_eq!(reorg.depth, 4);
+    assert_eq!(tracker.tip().unwrap().height, 11);
+}
+
+#[test]
+fn test_detects_reorgs_while_catching_up_with_a_far_head() {
+    // Keeps 6 blocks
+    let mut tracker = DaChainTracker::new(2);
+    for height in 1..=10 {
+        tracker.apply(vec![block(0, 0, height, vec![])]);
+    }
+
+    // The head is caught up with in steps the walk back can connect to the tracked chain
+    assert_eq!(tracker.next_height(12), 12);
+    assert_eq!(tracker.next_height(30), 16);
+
+    // Walked back from the block at the next height of a fork from height 8
+    let mut fork = vec![block(1, 0, 9, vec![])];
+    fork.extend((10..=16).map(|height| block(1, 1, height, vec![])));
+    assert!(tracker.connects(&fork[0]));
+
+    let reorg = tracker.apply(fork).unwrap();
+    assert_eq!(reorg.fork_height, 8);
+    assert_eq!(reorg.depth, 2);
+    assert_eq!(tracker.next_height(30), 22);
+}
@@ -0,0 +1,96 @@
+use citrea_fullnode::{halt_after_reorg, Reorg, ReorgHalts};
+use sov_db::ledger_db::{LedgerDB, NodeLedgerOps};
+use sov_db::schema::types::{BatchNumber, ReorgHaltReport, SlotNumber};
+use sov_stf_runner::DaMonitorConfig;
+
+const DA_MONITOR_CONFIG: DaMonitorConfig = DaMonitorConfig {
+    finality_depth: 6,
+    poll_interval: 10,
+    max_handled_reorg_depth: Some(12),
+};
+
+fn reorg(depth: u64) -> Reorg {
+    Reorg {
+        fork_height: 100,
+        depth,
+        dropped_blobs: vec![[7; 32]],
+    }
+}
+
+#[test]
+fn test_reorgs_within_the_finality_depth_do_not_halt() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+
+    let halts = halt_after_reorg(&ledger_db, &DA_MONITOR_CONFIG, &reorg(6), 1_700_000_000);
+
+    assert_eq!(halts, ReorgHalts::default());
+    assert_eq!(ledger_db.get_commitments_halt().unwrap(), None);
+    assert_eq!(ledger_db.get_reorg_halt().unwrap(), None);
+}
+
+#[test]
+fn test_reorgs_deeper_than_the_finality_depth_halt_commitments() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+
+    let halts = halt_after_reorg(&ledger_db, &DA_MONITOR_CONFIG, &reorg(12), 1_700_000_000);
+
+    assert_eq!(
+        halts,
+        ReorgHalts {
+            commitments: true,
+            execution: false,
+        }
+    );
+    assert_eq!(
+        ledger_db.get_commitments_halt().unwrap(),
+        Some(ReorgHaltReport {
+            fork_height: SlotNumber(100),
+            depth: 12,
+            max_handled_reorg_depth: 6,
+            dropped_blobs: vec![[7; 32]],
+            l2_height: BatchNumber(0),
+            halted_at: 1_700_000_000,
+        })
+    );
+    assert_eq!(ledger_db.get_reorg_halt().unwrap(), None);
+}
+
+#[test]
+fn test_reorgs_deeper_than_handled_halt_execution_across_restarts() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    {
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        let halts = halt_after_reorg(&ledger_db, &DA_MONITOR_CONFIG, &reorg(13), 1_700_000_000);
+        assert_eq!(
+            halts,
+            ReorgHalts {
+                commitments: true,
+                execution: true,
+            }
+        );
+    }
+
+    let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+    let report = ledger_db.get_reorg_halt().unwrap().unwrap();
+    assert_eq!(report.depth, 13);
+    assert_eq!(report.max_handled_reorg_depth, 12);
+    assert!(ledger_db.get_commitments_halt().unwrap().is_some());
+}
+
+#[test]
+fn test_execution_is_not_halted_without_max_handled_reorg_depth() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+    let config = DaMonitorConfig {
+        max_handled_reorg_depth: None,
+        ..DA_MONITOR_CONFIG
+    };
+
+    let halts = halt_after_reorg(&ledger_db, &config, &reorg(100), 1_700_000_000);
+
+    assert!(halts.commitments);
+    assert!(!halts.execution);
+    assert_eq!(ledger_db.get_reorg_halt().unwrap(), None);
+}
@@ -6,7 +6,7 @@ use sov_mock_zkvm::MockZkvm;
 use sov_modules_api::Context;
 use sov_modules_stf_blueprint::StfBlueprintTrait;
 use sov_prover_storage_manager::{new_orphan_storage, SnapshotManager};
-use sov_rollup_interface::da::{BlobReaderTrait, BlockHeaderTrait, DaSpec};
+use sov_rollup_interface::da::{BlobReaderTrait, BlockHeaderTrait, DaSpec, SlotInbox};
 use sov_rollup_interface::stf::{SlotResult, StateTransitionFunction};
 use sov_rollup_interface::zk::{CumulativeStateDiff, ValidityCondition, Zkvm};
 use sov_state::storage::{NativeStorage, StorageKey, StorageValue};
@@ -75,6 +75,7 @@ impl<C: Context, Da: DaSpec, Vm: Zkvm, Cond: ValidityCondition> StfBlueprintTrai
         _pre_state: Self::PreState,
         _witness: <<C as sov_modules_api::Spec>::Storage as Storage>::Witness,
         _slot_header: &<Da as DaSpec>::BlockHeader,
+        _slot_inbox: &SlotInbox,
         _soft_batch: &mut sov_modules_api::SignedSoftConfirmationBatch,
     ) -> (
         Result<(), sov_modules_api::hooks::ApplySoftConfirmationError>,
@@ -210,6 +211,7 @@ impl<Vm: Zkvm, Cond: ValidityCondition, Da: DaSpec> StateTransitionFunction<Vm,
         _pre_state: Self::PreState,
         _witness: Self::Witness,
         _slot_header: &<Da as DaSpec>::BlockHeader,
+        _slot_inbox: &SlotInbox,
         _validity_condition: &<Da as DaSpec>::ValidityCondition,
         _soft_batch: &mut sov_modules_api::SignedSoftConfirmationBatch,
     ) -> SlotResult<
@@ -233,6 +235,7 @@ impl<Vm: Zkvm, Cond: ValidityCondition, Da: DaSpec> StateTransitionFunction<Vm,
         _sequencer_commitments_range: (u32, u32),
         _witnesses: std::collections::VecDeque<Vec<Self::Witness>>,
         _slot_headers: std::collections::VecDeque<Vec<<Da as DaSpec>::BlockHeader>>,
+        _slot_inboxes: std::collections::VecDeque<Vec<SlotInbox>>,
         _validity_condition: &<Da as DaSpec>::ValidityCondition,
         _soft_confirmations: std::collections::VecDeque<
             Vec<sov_modules_api::SignedSoftConfirmationBatch>,
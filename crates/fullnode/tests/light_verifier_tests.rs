@@ -0,0 +1,324 @@
+use std::time::Duration;
+
+use citrea_fullnode::CitreaLightVerifier;
+use sov_db::ledger_db::{LedgerDB, NodeLedgerOps};
+use sov_mock_da::{MockAddress, MockDaService, MockDaSpec, MockHash, MockValidityCond};
+use sov_mock_zkvm::{MockCodeCommitment, MockProof, MockZkvm};
+use sov_rollup_interface::da::{BlockHeaderTrait, DaData, SequencerCommitment};
+use sov_rollup_interface::services::da::{DaService, SlotData};
+use sov_rollup_interface::zk::{AggregatedStateTransition, Proof, StateTransition};
+use sov_stf_runner::{DaPubKeyRotation, RollupPublicKeys, RpcConfig, RunnerConfig};
+
+type LightVerifier =
+    CitreaLightVerifier<MockDaService, MockZkvm<MockValidityCond>, [u8; 32], LedgerDB>;
+
+const SEQUENCER_PUB_KEY: [u8; 32] = [1; 32];
+const SEQUENCER_DA_ADDRESS: [u8; 32] = [2; 32];
+const PROVER_DA_ADDRESS: [u8; 32] = [3; 32];
+const CODE_COMMITMENT: MockCodeCommitment = MockCodeCommitment([4; 32]);
+const GENESIS_STATE_ROOT: [u8; 32] = [5; 32];
+const ROTATED_SEQUENCER_DA_ADDRESS: [u8; 32] = [8; 32];
+
+/// Posts a commitment from the sequencer, and returns the hash of its L1 block
+async fn post_commitment(sequencer_da: &MockDaService, l2_range: (u64, u64)) -> MockHash {
+    let commitment = DaData::SequencerCommitment(SequencerCommitment {
+        merkle_root: [0; 32],
+        l2_start_block_number: l2_range.0,
+        l2_end_block_number: l2_range.1,
+    });
+    sequencer_da
+        .send_transaction(&commitment.encode())
+        .await
+        .unwrap();
+    let l1_height = sequencer_da.get_height().await;
+    sequencer_da
+        .get_block_at(l1_height)
+        .await
+        .unwrap()
+        .header()
+        .hash()
+}
+
+/// Posts a proof of the first commitment of the L1 block from the prover
+async fn post_proof(
+    prover_da: &MockDaService,
+    commitments_l1_hash: MockHash,
+    initial_state_root: [u8; 32],
+    final_state_root: [u8; 32],
+) {
+    post_proof_of_da_key(
+        prover_da,
+        commitments_l1_hash,
+        initial_state_root,
+        final_state_root,
+        SEQUENCER_DA_ADDRESS,
+    )
+    .await
+}
+
+/// Posts a proof of the first commitment signed with the DA key on the L1 block
+async fn post_proof_of_da_key(
+    prover_da: &MockDaService,
+    commitments_l1_hash: MockHash,
+    initial_state_root: [u8; 32],
+    final_state_root: [u8; 32],
+    sequencer_da_public_key: [u8; 32],
+) {
+    let state_transition = StateTransition::<MockDaSpec, [u8; 32]> {
+        initial_state_root,
+        final_state_root,
+        initial_batch_hash: [0; 32],
+        state_diff: Default::default(),
+        da_slot_hash: commitments_l1_hash,
+        sequencer_commitments_range: (0, 0),
+        withdrawal_roots: vec![],
+        sequencer_public_key: SEQUENCER_PUB_KEY.to_vec(),
+        sequencer_da_public_key: sequencer_da_public_key.to_vec(),
+        validity_condition: MockValidityCond::default(),
+    };
+    let proof = MockProof {
+        program_id: CODE_COMMITMENT,
+        is_valid: true,
+        log: bincode::serialize(&state_transition).unwrap(),
+    };
+    let proof = DaData::ZKProof(Proof::Full(proof.encode_to_vec()));
+    prover_da.send_transaction(&proof.encode()).await.unwrap();
+}
+
+fn light_verifier(ledger_db: LedgerDB, da_service: MockDaService) -> LightVerifier {
+    light_verifier_with_rotations(ledger_db, da_service, vec![])
+}
+
+fn light_verifier_with_rotations(
+    ledger_db: LedgerDB,
+    da_service: MockDaService,
+    sequencer_da_pub_key_rotations: Vec<DaPubKeyRotation>,
+) -> LightVerifier {
+    let runner_config = RunnerConfig {
+        sequencer_client_url: "http://127.0.0.1:4444".to_string().into(),
+        include_tx_body: false,
+        accept_public_input_as_proven: None,
+        pruning_config: Default::default(),
+        proving_strategy: Default::default(),
+        da_monitor: None,
+        watchtower: None,
+        parallel_evm_execution: false,
+        diagnostics_dir: None,
+        challenge_window: None,
+        backfill_history: false,
+        backup_dir: None,
+    };
+    let rpc_config = RpcConfig {
+        bind_host: "127.0.0.1".to_string(),
+        bind_port: 0,
+        max_connections: 100,
+        max_request_body_size: 10 * 1024 * 1024,
+        max_response_body_size: 10 * 1024 * 1024,
+        batch_requests_limit: 50,
+        enable_subscriptions: true,
+        max_subscriptions_per_connection: 100,
+        trace_cache_size: None,
+        operator: None,
+        public_operator_methods: false,
+        sequencer_tx_fallback: true,
+        sequencer_tx_fallback_negative_cache_ms: 1000,
+        method_timeouts_ms: Default::default(),
+        default_method_timeout_ms: None,
+    };
+    let public_keys = RollupPublicKeys {
+        sequencer_public_key: SEQUENCER_PUB_KEY.to_vec(),
+        sequencer_da_pub_key: SEQUENCER_DA_ADDRESS.to_vec(),
+        prover_da_pub_key: PROVER_DA_ADDRESS.to_vec(),
+        sequencer_da_pub_key_rotations,
+    };
+
+    CitreaLightVerifier::new(
+        runner_config,
+        public_keys,
+        rpc_config,
+        da_service,
+        ledger_db,
+        GENESIS_STATE_ROOT,
+        CODE_COMMITMENT,
+        CODE_COMMITMENT,
+    )
+    .unwrap()
+}
+
+/// Waits until the light verifier has processed the L1 block
+async fn wait_for_l1_height(ledger_db: &LedgerDB, l1_height: u64) {
+    for _ in 0..100 {
+        let checkpoint = ledger_db.get_sync_checkpoint().unwrap();
+        if checkpoint.is_some_and(|checkpoint| checkpoint.l1_height.0 >= l1_height) {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("L1 block {} was not processed", l1_height);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_chains_verified_proofs_onto_genesis() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let da_path = tmpdir.path().join("da");
+    // Both services append to the same mock DA chain
+    let sequencer_da = MockDaService::new(MockAddress::new(SEQUENCER_DA_ADDRESS), &da_path);
+    let prover_da = MockDaService::new(MockAddress::new(PROVER_DA_ADDRESS), &da_path);
+
+    let first_commitment = post_commitment(&sequencer_da, (1, 5)).await;
+    post_proof(&prover_da, first_commitment, GENESIS_STATE_ROOT, [6; 32]).await;
+    let second_commitment = post_commitment(&sequencer_da, (6, 10)).await;
+    post_proof(&prover_da, second_commitment, [6; 32], [7; 32]).await;
+
+    let ledger_db = LedgerDB::with_path(tmpdir.path().join("ledger")).unwrap();
+    let mut verifier = light_verifier(ledger_db.clone(), sequencer_da);
+    tokio::spawn(async move { verifier.run().await });
+    wait_for_l1_height(&ledger_db, 4).await;
+
+    let verified = ledger_db.get_last_verified_state_root().unwrap().unwrap();
+    assert_eq!(verified.l1_height.0, 4);
+    assert_eq!(verified.l2_height.0, 10);
+    assert_eq!(verified.state_root, [7; 32].to_vec());
+    assert_eq!(
+        ledger_db.get_last_proven_l2_height().unwrap().unwrap().0,
+        10
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rejects_proofs_not_linked_to_verified_root() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let da_path = tmpdir.path().join("da");
+    let sequencer_da = MockDaService::new(MockAddress::new(SEQUENCER_DA_ADDRESS), &da_path);
+    let prover_da = MockDaService::new(MockAddress::new(PROVER_DA_ADDRESS), &da_path);
+
+    // Does not start from the genesis state root
+    let first_commitment = post_commitment(&sequencer_da, (1, 5)).await;
+    post_proof(&prover_da, first_commitment, [9; 32], [6; 32]).await;
+    // Skips the L2 blocks of the first commitment
+    let second_commitment = post_commitment(&sequencer_da, (6, 10)).await;
+    post_proof(&prover_da, second_commitment, GENESIS_STATE_ROOT, [7; 32]).await;
+    // Posted by the sequencer instead of the prover
+    post_proof(&sequencer_da, first_commitment, GENESIS_STATE_ROOT, [6; 32]).await;
+
+    let ledger_db = LedgerDB::with_path(tmpdir.path().join("ledger")).unwrap();
+    let mut verifier = light_verifier(ledger_db.clone(), sequencer_da);
+    tokio::spawn(async move { verifier.run().await });
+    wait_for_l1_height(&ledger_db, 5).await;
+
+    assert_eq!(ledger_db.get_last_verified_state_root().unwrap(), None);
+    assert_eq!(ledger_db.get_last_proven_l2_height().unwrap(), None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_accepts_the_sequencer_da_key_valid_at_the_l1_height() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let da_path = tmpdir.path().join("da");
+    let sequencer_da = MockDaService::new(MockAddress::new(SEQUENCER_DA_ADDRESS), &da_path);
+    let rotated_sequencer_da =
+        MockDaService::new(MockAddress::new(ROTATED_SEQUENCER_DA_ADDRESS), &da_path);
+    let prover_da = MockDaService::new(MockAddress::new(PROVER_DA_ADDRESS), &da_path);
+
+    // L1 blocks 1 and 2, before the rotation
+    let first_commitment = post_commitment(&sequencer_da, (1, 5)).await;
+    post_proof(&prover_da, first_commitment, GENESIS_STATE_ROOT, [6; 32]).await;
+    // L1 blocks 3 and 4, signed with the key rotated away from at L1 height 3
+    let stale_commitment = post_commitment(&sequencer_da, (6, 10)).await;
+    post_proof(&prover_da, stale_commitment, [6; 32], [9; 32]).await;
+    // L1 blocks 5 and 6, signed with the rotated key
+    let rotated_commitment = post_commitment(&rotated_sequencer_da, (6, 10)).await;
+    post_proof_of_da_key(
+        &prover_da,
+        rotated_commitment,
+        [6; 32],
+        [7; 32],
+        ROTATED_SEQUENCER_DA_ADDRESS,
+    )
+    .await;
+
+    let ledger_db = LedgerDB::with_path(tmpdir.path().join("ledger")).unwrap();
+    let rotations = vec![DaPubKeyRotation {
+        activation_l1_height: 3,
+        pub_key: ROTATED_SEQUENCER_DA_ADDRESS.to_vec(),
+    }];
+    let mut verifier = light_verifier_with_rotations(ledger_db.clone(), sequencer_da, rotations);
+    tokio::spawn(async move { verifier.run().await });
+    wait_for_l1_height(&ledger_db, 6).await;
+
+    let verified = ledger_db.get_last_verified_state_root().unwrap().unwrap();
+    assert_eq!(verified.l1_height.0, 6);
+    assert_eq!(verified.l2_height.0, 10);
+    assert_eq!(verified.state_root, [7; 32].to_vec());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_skips_commitments_posted_again() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let da_path = tmpdir.path().join("da");
+    let sequencer_da = MockDaService::new(MockAddress::new(SEQUENCER_DA_ADDRESS), &da_path);
+    let prover_da = MockDaService::new(MockAddress::new(PROVER_DA_ADDRESS), &da_path);
+
+    let first_commitment = post_commitment(&sequencer_da, (1, 5)).await;
+    // Posted again, e.g. by a fee bump racing the original tx
+    post_commitment(&sequencer_da, (1, 5)).await;
+    post_proof(&prover_da, first_commitment, GENESIS_STATE_ROOT, [6; 32]).await;
+
+    let ledger_db = LedgerDB::with_path(tmpdir.path().join("ledger")).unwrap();
+    let mut verifier = light_verifier(ledger_db.clone(), sequencer_da);
+    tokio::spawn(async move { verifier.run().await });
+    wait_for_l1_height(&ledger_db, 3).await;
+
+    assert_eq!(
+        ledger_db
+            .get_commitments_on_da_slot(1)
+            .unwrap()
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(ledger_db.get_commitments_on_da_slot(2).unwrap(), None);
+    let verified = ledger_db.get_last_verified_state_root().unwrap().unwrap();
+    assert_eq!(verified.l2_height.0, 5);
+    assert_eq!(verified.state_root, [6; 32].to_vec());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_rejects_aggregated_proofs_without_a_range_per_da_slot() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let da_path = tmpdir.path().join("da");
+    let sequencer_da = MockDaService::new(MockAddress::new(SEQUENCER_DA_ADDRESS), &da_path);
+    let prover_da = MockDaService::new(MockAddress::new(PROVER_DA_ADDRESS), &da_path);
+
+    let commitment = post_commitment(&sequencer_da, (1, 5)).await;
+    let aggregated = AggregatedStateTransition::<MockDaSpec, [u8; 32]> {
+        initial_state_root: GENESIS_STATE_ROOT,
+        final_state_root: [6; 32],
+        initial_batch_hash: [0; 32],
+        state_diff: Default::default(),
+        da_slot_hashes: vec![commitment],
+        sequencer_commitments_ranges: vec![],
+        withdrawal_roots: vec![],
+        sequencer_public_key: SEQUENCER_PUB_KEY.to_vec(),
+        sequencer_da_public_keys: vec![SEQUENCER_DA_ADDRESS.to_vec()],
+        range_code_commitment: CODE_COMMITMENT.0.to_vec(),
+        validity_conditions: vec![MockValidityCond::default()],
+    };
+    let proof = MockProof {
+        program_id: CODE_COMMITMENT,
+        is_valid: true,
+        log: borsh::to_vec(&aggregated).unwrap(),
+    };
+    let proof = DaData::AggregatedZKProof(Proof::Full(proof.encode_to_vec()));
+    prover_da.send_transaction(&proof.encode()).await.unwrap();
+    // Verified once the malformed proof is skipped
+    post_proof(&prover_da, commitment, GENESIS_STATE_ROOT, [7; 32]).await;
+
+    let ledger_db = LedgerDB::with_path(tmpdir.path().join("ledger")).unwrap();
+    let mut verifier = light_verifier(ledger_db.clone(), sequencer_da);
+    tokio::spawn(async move { verifier.run().await });
+    wait_for_l1_height(&ledger_db, 3).await;
+
+    let verified = ledger_db.get_last_verified_state_root().unwrap().unwrap();
+    assert_eq!(verified.l1_height.0, 3);
+    assert_eq!(verified.state_root, [7; 32].to_vec());
+}
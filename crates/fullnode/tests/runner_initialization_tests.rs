@@ -1,11 +1,15 @@
 use citrea_fullnode::CitreaFullnode;
-use sov_db::ledger_db::LedgerDB;
+use jsonrpsee::RpcModule;
+use sov_db::ledger_db::{LedgerDB, NodeLedgerOps};
+use sov_db::schema::types::{BatchNumber, StateRootMismatchReport};
 use sov_mock_da::{MockAddress, MockDaConfig, MockDaService, MockDaSpec, MockValidityCond};
 use sov_mock_zkvm::{MockCodeCommitment, MockZkvm};
 use sov_prover_storage_manager::ProverStorageManager;
+use sov_rollup_interface::rpc::StateRootMismatchResponse;
 use sov_state::DefaultStorageSpec;
 use sov_stf_runner::{
-    FullNodeConfig, InitVariant, RollupPublicKeys, RpcConfig, RunnerConfig, StorageConfig,
+    DaMonitorConfig, FullNodeConfig, InitVariant, OperatorRpcConfig, PruningMode, RollupPublicKeys,
+    RpcConfig, RunnerConfig, StorageConfig,
 };
 
 mod hash_stf;
@@ -26,30 +30,166 @@ async fn init_and_restart() {
     let init_variant: MockInitVariant = InitVariant::Genesis(genesis_params);
 
     let state_root_after_genesis = {
-        let runner = initialize_runner(tmpdir.path(), init_variant);
+        let runner = initialize_runner(tmpdir.path(), init_variant, |_, _| {}).unwrap();
         *runner.get_state_root()
     };
 
     let init_variant_2: MockInitVariant =
         InitVariant::Initialized((state_root_after_genesis, [0; 32]));
 
-    let runner_2 = initialize_runner(tmpdir.path(), init_variant_2);
+    let runner_2 = initialize_runner(tmpdir.path(), init_variant_2, |_, _| {}).unwrap();
 
     let state_root_2 = *runner_2.get_state_root();
 
     assert_eq!(state_root_after_genesis, state_root_2);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn backfill_requires_archive_pruning() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let init_variant: MockInitVariant = InitVariant::Genesis(vec![1, 2, 3, 4, 5]);
+
+    let result = initialize_runner(tmpdir.path(), init_variant, |runner_config, _| {
+        runner_config.backfill_history = true;
+        runner_config.pruning_config.mode = PruningMode::Minimal;
+    });
+    assert!(result.is_err());
+
+    let result = initialize_runner(
+        tmpdir.path(),
+        InitVariant::Genesis(vec![1, 2, 3, 4, 5]),
+        |runner_config, _| runner_config.backfill_history = true,
+    );
+    assert!(result.is_ok());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reorg_resume_is_only_served_by_the_operator_server() {
+    let serves_resume = |operator: Option<OperatorRpcConfig>| {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let runner = initialize_runner(
+            tmpdir.path(),
+            InitVariant::Genesis(vec![1, 2, 3, 4, 5]),
+            |runner_config, rpc_config| {
+                runner_config.da_monitor = Some(DaMonitorConfig {
+                    finality_depth: 6,
+                    poll_interval: 10,
+                    max_handled_reorg_depth: Some(12),
+                });
+                rpc_config.operator = operator;
+            },
+        )
+        .unwrap();
+        let methods = runner.register_rpc_methods(RpcModule::new(())).unwrap();
+        // The halt report is public
+        assert!(methods
+            .method_names()
+            .any(|method| method == "citrea_getReorgHalt"));
+        methods
+            .method_names()
+            .any(|method| method == "citrea_resumeAfterReorg")
+    };
+
+    assert!(!serves_resume(None));
+    assert!(serves_resume(Some(operator_rpc_config())));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn state_root_mismatch_halts_until_cleared_by_the_operator() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let report = StateRootMismatchReport {
+        l2_height: BatchNumber(7),
+        expected_state_root: vec![1; 32],
+        state_root: vec![2; 32],
+        diagnostics_path: None,
+        halted_at: 1_700_000_000,
+    };
+    {
+        let rollup_storage_path = tmpdir.path().join("rollup");
+        std::fs::create_dir(&rollup_storage_path).unwrap();
+        let ledger_db = LedgerDB::with_path(&rollup_storage_path).unwrap();
+        ledger_db.set_state_root_mismatch(&report).unwrap();
+    }
+    let methods = |operator: Option<OperatorRpcConfig>| {
+        let runner = initialize_runner(
+            tmpdir.path(),
+            InitVariant::Genesis(vec![1, 2, 3, 4, 5]),
+            |_, rpc_config| rpc_config.operator = operator,
+        )
+        .unwrap();
+        runner.register_rpc_methods(RpcModule::new(())).unwrap()
+    };
+    let no_params: &[u8] = &[];
+
+    // The halt is kept across restarts, the report and the health check are public
+    {
+        let methods = methods(None);
+        let halted: Option<StateRootMismatchResponse> = methods
+            .call("citrea_getStateRootMismatch", no_params)
+            .await
+            .unwrap();
+        assert_eq!(halted, Some(report.into()));
+        assert!(methods
+            .call::<_, ()>("citrea_health", no_params)
+            .await
+            .is_err());
+        assert!(!methods
+            .method_names()
+            .any(|method| method == "citrea_clearStateRootMismatch"));
+    }
+
+    {
+        let methods = methods(Some(operator_rpc_config()));
+        let was_halted: bool = methods
+            .call("citrea_clearStateRootMismatch", no_params)
+            .await
+            .unwrap();
+        assert!(was_halted);
+        methods
+            .call::<_, ()>("citrea_health", no_params)
+            .await
+            .unwrap();
+    }
+
+    // Execution is resumed after a restart as well
+    let methods = methods(None);
+    let halted: Option<StateRootMismatchResponse> = methods
+        .call("citrea_getStateRootMismatch", no_params)
+        .await
+        .unwrap();
+    assert_eq!(halted, None);
+    methods
+        .call::<_, ()>("citrea_health", no_params)
+        .await
+        .unwrap();
+}
+
+fn operator_rpc_config() -> OperatorRpcConfig {
+    OperatorRpcConfig {
+        bind_host: "127.0.0.1".to_string(),
+        bind_port: 0,
+        max_connections: 100,
+        max_request_body_size: 10 * 1024 * 1024,
+        max_response_body_size: 10 * 1024 * 1024,
+        batch_requests_limit: 50,
+        max_subscriptions_per_connection: 100,
+        public_namespaces: vec!["eth".to_string(), "citrea".to_string()],
+    }
+}
+
 fn initialize_runner(
     storage_path: &std::path::Path,
     init_variant: MockInitVariant,
-) -> CitreaFullnode<
-    HashStf<MockValidityCond>,
-    StorageManager,
-    MockDaService,
-    MockZkvm<MockValidityCond>,
-    sov_modules_api::default_context::DefaultContext,
-    LedgerDB,
+    configure: impl FnOnce(&mut RunnerConfig, &mut RpcConfig),
+) -> anyhow::Result<
+    CitreaFullnode<
+        HashStf<MockValidityCond>,
+        StorageManager,
+        MockDaService,
+        MockZkvm<MockValidityCond>,
+        sov_modules_api::default_context::DefaultContext,
+        LedgerDB,
+    >,
 > {
     let da_storage_path = storage_path.join("da").to_path_buf();
     let rollup_storage_path = storage_path.join("rollup").to_path_buf();
@@ -62,9 +202,13 @@ fn initialize_runner(
     }
 
     let address = MockAddress::new([11u8; 32]);
-    let rollup_config = FullNodeConfig::<MockDaConfig> {
+    let mut rollup_config = FullNodeConfig::<MockDaConfig> {
         storage: StorageConfig {
             path: rollup_storage_path.clone(),
+            ledger_db: Default::default(),
+            state_db: Default::default(),
+            replica: None,
+            state_cache_size: None,
         },
         rpc: RpcConfig {
             bind_host: "127.0.0.1".to_string(),
@@ -75,11 +219,27 @@ fn initialize_runner(
             batch_requests_limit: 50,
             enable_subscriptions: true,
             max_subscriptions_per_connection: 100,
+            trace_cache_size: None,
+            operator: None,
+            public_operator_methods: false,
+            sequencer_tx_fallback: true,
+            sequencer_tx_fallback_negative_cache_ms: 1000,
+            method_timeouts_ms: Default::default(),
+            default_method_timeout_ms: None,
         },
         runner: Some(RunnerConfig {
-            sequencer_client_url: "http://127.0.0.1:4444".to_string(),
+            sequencer_client_url: "http://127.0.0.1:4444".to_string().into(),
             include_tx_body: true,
             accept_public_input_as_proven: None,
+            pruning_config: Default::default(),
+            proving_strategy: Default::default(),
+            da_monitor: None,
+            watchtower: None,
+            parallel_evm_execution: false,
+            diagnostics_dir: None,
+            challenge_window: None,
+            backfill_history: false,
+            backup_dir: None,
         }),
         da: MockDaConfig {
             sender_address: address,
@@ -89,10 +249,16 @@ fn initialize_runner(
             sequencer_public_key: vec![],
             sequencer_da_pub_key: vec![],
             prover_da_pub_key: vec![],
+            sequencer_da_pub_key_rotations: vec![],
         },
         sync_blocks_count: 10,
     };
 
+    configure(
+        rollup_config.runner.as_mut().unwrap(),
+        &mut rollup_config.rpc,
+    );
+
     let da_service = MockDaService::new(address, &da_storage_path);
 
     let ledger_db = LedgerDB::with_path(rollup_storage_path.clone()).unwrap();
@@ -117,8 +283,8 @@ fn initialize_runner(
         storage_manager,
         init_variant,
         MockCodeCommitment([1u8; 32]),
+        MockCodeCommitment([2u8; 32]),
         10,
         broadcast::channel(1).0,
     )
-    .unwrap()
 }
@@ -0,0 +1,183 @@
+use citrea_evm::system_contracts::{BridgeEvent, BRIDGE_DEPOSIT_AMOUNT};
+use citrea_fullnode::{
+    check_withdrawal_claims, check_withdrawal_roots, BridgeWatchtower, WatchtowerAlert,
+};
+use citrea_withdrawal_queue::Withdrawal;
+use sov_rollup_interface::da::WithdrawalClaim;
+
+fn deposit(wtx_id: u8) -> BridgeEvent {
+    BridgeEvent::Deposit {
+        wtx_id: [wtx_id; 32],
+        recipient: Default::default(),
+    }
+}
+
+fn withdrawal_event(index: u64) -> BridgeEvent {
+    BridgeEvent::Withdrawal {
+        index,
+        bitcoin_address: [index as u8; 32],
+    }
+}
+
+fn withdrawal(index: u64, l2_height: u64) -> Withdrawal {
+    Withdrawal {
+        index,
+        l2_height,
+        bitcoin_address: [index as u8; 32],
+    }
+}
+
+fn claim(index: u64) -> WithdrawalClaim {
+    WithdrawalClaim {
+        index,
+        bitcoin_address: [index as u8; 32],
+    }
+}
+
+#[test]
+fn test_accepts_matching_deposits_and_withdrawals() {
+    let mut watchtower = BridgeWatchtower::default();
+    let balance = 100 * BRIDGE_DEPOSIT_AMOUNT;
+    assert!(watchtower
+        .check_l2_block(1, &[], &[], 0, balance)
+        .is_empty());
+
+    // Two deposits are minted and a withdrawal burns one
+    let balance = balance - BRIDGE_DEPOSIT_AMOUNT;
+    assert!(watchtower
+        .check_l2_block(
+            2,
+            &[deposit(1), deposit(2), withdrawal_event(0)],
+            &[withdrawal(0, 2)],
+            2,
+            balance,
+        )
+        .is_empty());
+
+    // Funds forced into the Bridge are not a violation
+    assert!(watchtower
+        .check_l2_block(3, &[], &[], 0, balance + 1)
+        .is_empty());
+}
+
+#[test]
+fn test_alerts_on_withdrawal_mismatches() {
+    let mut watchtower = BridgeWatchtower::default();
+    let balance = 100 * BRIDGE_DEPOSIT_AMOUNT;
+
+    let alerts = watchtower.check_l2_block(5, &[withdrawal_event(0)], &[], 0, balance);
+    assert_eq!(
+        alerts,
+        vec![WatchtowerAlert::UnrecordedWithdrawal {
+            l2_height: 5,
+            index: 0
+        }]
+    );
+}
+
+#[test]
+fn test_alerts_on_claims_of_unburned_withdrawals() {
+    // Withdrawals 2 and 3 are recorded, the first ones were initiated before the queue
+    let withdrawals = [withdrawal(2, 10), withdrawal(3, 20)];
+    let lookup = |index| {
+        withdrawals
+            .iter()
+            .find(|withdrawal| withdrawal.index == index)
+            .cloned()
+    };
+
+    // Proven withdrawals can be claimed, the ones before the queue can't be checked
+    assert!(check_withdrawal_claims(7, &[claim(1), claim(2)], 2, Some(15), lookup).is_empty());
+
+    // Withdrawal 3 is not proven yet, 2 is claimed for another address and 4 was never burned
+    let mut paid_elsewhere = claim(2);
+    paid_elsewhere.bitcoin_address = [9; 32];
+    assert_eq!(
+        check_withdrawal_claims(
+            7,
+            &[claim(3), paid_elsewhere, claim(4)],
+            2,
+            Some(15),
+            lookup
+        ),
+        vec![
+            WatchtowerAlert::UnburnedWithdrawal {
+                l1_height: 7,
+                index: 3
+            },
+            WatchtowerAlert::UnburnedWithdrawal {
+                l1_height: 7,
+                index: 2
+            },
+            WatchtowerAlert::UnburnedWithdrawal {
+                l1_height: 7,
+                index: 4
+            },
+        ]
+    );
+    // Nothing is claimable before the first proof
+    assert_eq!(
+        check_withdrawal_claims(7, &[claim(2)], 2, None, lookup),
+        vec![WatchtowerAlert::UnburnedWithdrawal {
+            l1_height: 7,
+            index: 2
+        }]
+    );
+}
+
+#[test]
+fn test_alerts_on_unbacked_deposits_and_balance_deficit() {
+    let mut watchtower = BridgeWatchtower::default();
+    let balance = 100 * BRIDGE_DEPOSIT_AMOUNT;
+    assert!(watchtower
+        .check_l2_block(1, &[], &[], 0, balance)
+        .is_empty());
+
+    // Two deposits are minted for a single L1 deposit, and the Bridge pays out a third one
+    let alerts = watchtower.check_l2_block(
+        2,
+        &[deposit(1), deposit(2)],
+        &[],
+        1,
+        balance - 3 * BRIDGE_DEPOSIT_AMOUNT,
+    );
+    assert_eq!(
+        alerts,
+        vec![
+            WatchtowerAlert::UnbackedDeposit {
+                l2_height: 2,
+                minted: 2,
+                posted: 1
+            },
+            WatchtowerAlert::BridgeBalanceDeficit {
+                l2_height: 2,
+                expected: balance - 2 * BRIDGE_DEPOSIT_AMOUNT,
+                actual: balance - 3 * BRIDGE_DEPOSIT_AMOUNT,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_checks_proven_withdrawal_roots() {
+    assert!(check_withdrawal_roots(10, &[[1; 32], [2; 32]], &[[1; 32], [2; 32]]).is_empty());
+
+    assert_eq!(
+        check_withdrawal_roots(10, &[[1; 32], [3; 32]], &[[1; 32], [2; 32]]),
+        vec![WatchtowerAlert::WithdrawalRootMismatch {
+            l1_height: 10,
+            commitment_index: 1,
+            proven: [3; 32],
+            expected: [2; 32],
+        }]
+    );
+
+    assert_eq!(
+        check_withdrawal_roots(10, &[[1; 32]], &[[1; 32], [2; 32]]),
+        vec![WatchtowerAlert::WithdrawalRootCountMismatch {
+            l1_height: 10,
+            proven: 1,
+            expected: 2,
+        }]
+    );
+}
@@ -0,0 +1,129 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use tokio::sync::watch;
+
+/// Source of time of the timers of the nodes.
+/// Nodes use the system time, tests can control the time with a [`TestClock`].
+#[async_trait]
+pub trait Clock: Send + Sync + 'static {
+    /// Monotonic time, to measure durations
+    fn now(&self) -> Instant;
+
+    /// Wall clock time in seconds since the unix epoch, for block timestamps
+    fn unix_timestamp(&self) -> u64;
+
+    /// Waits until the duration has passed
+    async fn sleep(&self, duration: Duration);
+}
+
+/// A clock which can be shared between tasks
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The time of the system
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl SystemClock {
+    /// A [`SharedClock`] of the system time
+    pub fn shared() -> SharedClock {
+        Arc::new(Self)
+    }
+}
+
+#[async_trait]
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn unix_timestamp(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+/// A clock which only moves when advanced, so timing dependent behavior is deterministic.
+/// Sleeps complete once the clock is advanced past their deadline.
+#[derive(Debug)]
+pub struct TestClock {
+    start: Instant,
+    start_unix_timestamp: u64,
+    elapsed: watch::Sender<Duration>,
+}
+
+impl TestClock {
+    /// A clock starting at the unix timestamp
+    pub fn new(start_unix_timestamp: u64) -> Self {
+        Self {
+            start: Instant::now(),
+            start_unix_timestamp,
+            elapsed: watch::channel(Duration::ZERO).0,
+        }
+    }
+
+    /// Moves the clock forward, completing the sleeps which are due
+    pub fn advance(&self, duration: Duration) {
+        self.elapsed.send_modify(|elapsed| *elapsed += duration);
+    }
+
+    /// Time passed since the clock was created
+    pub fn elapsed(&self) -> Duration {
+        *self.elapsed.borrow()
+    }
+}
+
+#[async_trait]
+impl Clock for TestClock {
+    fn now(&self) -> Instant {
+        self.start + self.elapsed()
+    }
+
+    fn unix_timestamp(&self) -> u64 {
+        self.start_unix_timestamp + self.elapsed().as_secs()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let mut elapsed = self.elapsed.subscribe();
+        let deadline = *elapsed.borrow() + duration;
+        // The sender lives as long as the clock, so this only returns at the deadline
+        let _ = elapsed.wait_for(|elapsed| *elapsed >= deadline).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_clock_only_moves_when_advanced() {
+        let clock = Arc::new(TestClock::new(1_000));
+        let start = clock.now();
+
+        let sleeping = tokio::spawn({
+            let clock = clock.clone();
+            async move { clock.sleep(Duration::from_secs(5)).await }
+        });
+        tokio::task::yield_now().await;
+
+        clock.advance(Duration::from_secs(3));
+        tokio::task::yield_now().await;
+        assert!(!sleeping.is_finished());
+        assert_eq!(clock.unix_timestamp(), 1_003);
+
+        clock.advance(Duration::from_secs(2));
+        sleeping.await.unwrap();
+        assert_eq!(clock.now() - start, Duration::from_secs(5));
+        assert_eq!(clock.unix_timestamp(), 1_005);
+
+        // Sleeping for nothing doesn't need the clock to move
+        clock.sleep(Duration::ZERO).await;
+    }
+}
@@ -1,5 +1,12 @@
+use sov_rollup_interface::da::DaPayloadMode;
+
 pub const ROLLUP_NAME: &str = "citrea-devnet";
 
+/// What the sequencer of the network posts to DA with its commitments.
+/// With [`DaPayloadMode::StateDiff`], the guest only proves commitments posted with their
+/// state diff, and the sequencer has to be configured to post them.
+pub const DA_PAYLOAD_MODE: DaPayloadMode = DaPayloadMode::Commitment;
+
 /// Leading zeros prefix for the reveal transaction id.
 pub const DA_TX_ID_LEADING_ZEROS: &[u8] = [0, 0].as_slice();
 
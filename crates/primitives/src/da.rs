@@ -4,8 +4,11 @@ use std::time::Duration;
 use anyhow::anyhow;
 use backoff::future::retry as retry_backoff;
 use backoff::ExponentialBackoffBuilder;
-use sov_rollup_interface::da::BlockHeaderTrait;
+use sov_rollup_interface::da::{
+    BlobReaderTrait, BlockHeaderTrait, DaData, SequencerLease, SlotInbox,
+};
 use sov_rollup_interface::services::da::{DaService, SlotData};
+use sov_rollup_interface::zk::SlotBlobs;
 use tokio::sync::Mutex;
 
 use crate::L1BlockCache;
@@ -37,3 +40,69 @@ pub async fn get_da_block_at_height<Da: DaService>(
         .put(l1_block.header().height(), l1_block.clone());
     Ok(l1_block)
 }
+
+/// Returns the inbox of the given L1 block, which the STF checks the L2 blocks on it against.
+pub fn extract_slot_inbox<Da: DaService>(
+    da_service: &Da,
+    l1_block: &Da::FilteredBlock,
+) -> SlotInbox {
+    let mut blobs = da_service.extract_relevant_blobs(l1_block);
+    // The inbox is read from the verified data, which is populated by reading the full data
+    for blob in &mut blobs {
+        blob.full_data();
+    }
+    SlotInbox::from_blobs(&blobs)
+}
+
+/// Returns the relevant blobs of the given L1 block with the proofs of their extraction,
+/// which the guest reads the inbox of the block from.
+pub async fn extract_slot_blobs<Da: DaService>(
+    da_service: &Da,
+    l1_block: &Da::FilteredBlock,
+) -> SlotBlobs<Da::Spec> {
+    let mut blobs = da_service.extract_relevant_blobs(l1_block);
+    for blob in &mut blobs {
+        blob.full_data();
+    }
+    let (inclusion_proof, completeness_proof) =
+        da_service.get_extraction_proof(l1_block, &blobs).await;
+    SlotBlobs {
+        blobs,
+        inclusion_proof,
+        completeness_proof,
+    }
+}
+
+/// Returns the heights of the L1 blocks whose proofs are requested in the given L1 block.
+/// Challenges can be posted by anyone, so the sender of the blob is not checked.
+pub fn extract_proof_challenges<Da: DaService>(
+    da_service: &Da,
+    l1_block: &Da::FilteredBlock,
+) -> Vec<u64> {
+    da_service
+        .extract_relevant_blobs(l1_block)
+        .into_iter()
+        .filter_map(|mut blob| match DaData::decode(blob.full_data()) {
+            Ok(DaData::ProofChallenge(l1_height)) => Some(l1_height),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the leases of the sequencer keys posted in the given L1 block, in order.
+/// Only leases posted by the sequencer are returned.
+pub fn extract_sequencer_leases<Da: DaService>(
+    da_service: &Da,
+    l1_block: &Da::FilteredBlock,
+    sequencer_da_pub_key: &[u8],
+) -> Vec<SequencerLease> {
+    da_service
+        .extract_relevant_blobs(l1_block)
+        .into_iter()
+        .filter(|blob| blob.sender().as_ref() == sequencer_da_pub_key)
+        .filter_map(|mut blob| match DaData::decode(blob.full_data()) {
+            Ok(DaData::SequencerLease(lease)) => Some(lease),
+            _ => None,
+        })
+        .collect()
+}
@@ -1,16 +1,24 @@
 #[cfg(feature = "native")]
 mod cache;
+#[cfg(feature = "native")]
+mod clock;
 mod constants;
 #[cfg(feature = "native")]
 mod da;
 #[cfg(feature = "native")]
 mod error;
+#[cfg(feature = "native")]
+mod sync;
 pub mod types;
 
 #[cfg(feature = "native")]
 pub use cache::*;
+#[cfg(feature = "native")]
+pub use clock::*;
 pub use constants::*;
 #[cfg(feature = "native")]
 pub use da::*;
 #[cfg(feature = "native")]
 pub use error::*;
+#[cfg(feature = "native")]
+pub use sync::*;
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+/// `ledger_getSoftBatchRange` serves at most 20 soft batches per request.
+pub const MAX_SYNC_BLOCKS_COUNT: u64 = 19;
+
+/// Sync rounds slower than this shrink the batch size.
+const TARGET_ROUND_LATENCY: Duration = Duration::from_secs(2);
+
+/// Responses larger than this shrink the batch size.
+const MAX_RESPONSE_BYTES: usize = 8 * 1024 * 1024;
+
+/// Number of L2 blocks requested from the sequencer per sync round.
+///
+/// Grows by one block after every full, fast round and halves when the sequencer
+/// responds slowly, the response is too large or the executor can't keep up,
+/// i.e. handing fetched blocks over to it takes too long.
+#[derive(Debug, Clone)]
+pub struct AdaptiveSyncBatchSize {
+    current: u64,
+}
+
+impl AdaptiveSyncBatchSize {
+    pub fn new(initial: u64) -> Self {
+        Self {
+            current: initial.clamp(1, MAX_SYNC_BLOCKS_COUNT),
+        }
+    }
+
+    pub fn get(&self) -> u64 {
+        self.current
+    }
+
+    /// Adjusts the batch size according to the stats of the last sync round
+    pub fn record(
+        &mut self,
+        fetched: u64,
+        fetch_latency: Duration,
+        response_bytes: usize,
+        handoff_latency: Duration,
+    ) {
+        if fetch_latency > TARGET_ROUND_LATENCY
+            || handoff_latency > TARGET_ROUND_LATENCY
+            || response_bytes > MAX_RESPONSE_BYTES
+        {
+            self.current = (self.current / 2).max(1);
+        } else if fetched >= self.current {
+            self.current = (self.current + 1).min(MAX_SYNC_BLOCKS_COUNT);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{AdaptiveSyncBatchSize, MAX_SYNC_BLOCKS_COUNT};
+
+    #[test]
+    fn test_adaptive_sync_batch_size() {
+        let fast = Duration::from_millis(10);
+        let slow = Duration::from_secs(10);
+
+        let mut batch_size = AdaptiveSyncBatchSize::new(10);
+        batch_size.record(10, fast, 0, fast);
+        assert_eq!(batch_size.get(), 11);
+
+        // Not at the chain tip, don't grow
+        batch_size.record(3, fast, 0, fast);
+        assert_eq!(batch_size.get(), 11);
+
+        batch_size.record(11, slow, 0, fast);
+        assert_eq!(batch_size.get(), 5);
+
+        batch_size.record(5, fast, 0, slow);
+        assert_eq!(batch_size.get(), 2);
+
+        batch_size.record(2, fast, usize::MAX, fast);
+        assert_eq!(batch_size.get(), 1);
+
+        batch_size.record(1, slow, 0, fast);
+        assert_eq!(batch_size.get(), 1);
+
+        let mut batch_size = AdaptiveSyncBatchSize::new(100);
+        assert_eq!(batch_size.get(), MAX_SYNC_BLOCKS_COUNT);
+        batch_size.record(MAX_SYNC_BLOCKS_COUNT, fast, 0, fast);
+        assert_eq!(batch_size.get(), MAX_SYNC_BLOCKS_COUNT);
+    }
+}
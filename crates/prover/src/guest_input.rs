@@ -0,0 +1,219 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+use anyhow::{anyhow, bail};
+use citrea_primitives::extract_slot_blobs;
+use serde::de::DeserializeOwned;
+use sov_db::ledger_db::{NodeLedgerOps, ProverLedgerOps};
+use sov_db::schema::types::BatchNumber;
+use sov_rollup_interface::da::{
+    BlobReaderTrait, CommitmentWithStateDiff, DaData, DaSpec, SequencerCommitment,
+};
+use sov_rollup_interface::services::da::{DaService, SlotData};
+use sov_rollup_interface::soft_confirmation::SignedSoftConfirmationBatch;
+use sov_rollup_interface::zk::{CompactWitnesses, HintWitness, StateTransitionData};
+use tracing::info;
+
+/// Builds the zk guest input proving the sequencer commitments of an L1 block,
+/// from the witnesses recorded by the prover while syncing.
+///
+/// The commitments are the ones the prover stored for the L1 block, so the input matches
+/// the one the prover submits for it, if the sequencer DA public key is the one of the L1 block.
+pub async fn generate_guest_input<Da, DB, StateRoot, Witness>(
+    ledger_db: &DB,
+    da_service: &Da,
+    l1_height: u64,
+    sequencer_public_key: &[u8],
+    sequencer_da_public_key: &[u8],
+) -> anyhow::Result<StateTransitionData<StateRoot, Witness, Da::Spec>>
+where
+    Da: DaService<Error = anyhow::Error>,
+    DB: ProverLedgerOps + NodeLedgerOps,
+    StateRoot: DeserializeOwned,
+    Witness: HintWitness + DeserializeOwned,
+{
+    let sequencer_commitments = ledger_db
+        .get_commitments_on_da_slot(l1_height)?
+        .filter(|commitments| !commitments.is_empty())
+        .ok_or_else(|| anyhow!("No sequencer commitments found on L1 block #{}", l1_height))?;
+
+    let l1_block = da_service.get_block_at(l1_height).await?;
+    let mut da_data = da_service.extract_relevant_blobs(&l1_block);
+    // The guest reads the sequencer commitments from the full data of the blobs
+    da_data.iter_mut().for_each(|blob| {
+        blob.full_data();
+    });
+    let sequencer_commitments_range = commitments_range(
+        &block_commitments::<Da::Spec>(&mut da_data, sequencer_da_public_key),
+        &sequencer_commitments,
+    )
+    .ok_or_else(|| {
+        anyhow!(
+            "Sequencer commitments of L1 block #{} are not in its blobs",
+            l1_height
+        )
+    })?;
+
+    let first_l2_height = sequencer_commitments[0].l2_start_block_number;
+    let last_l2_height = sequencer_commitments[sequencer_commitments.len() - 1].l2_end_block_number;
+
+    let mut soft_confirmations = VecDeque::new();
+    let mut witnesses = VecDeque::new();
+    let mut da_block_headers_of_soft_confirmations = VecDeque::new();
+    let mut da_blobs_of_soft_confirmations = VecDeque::new();
+    for commitment in &sequencer_commitments {
+        let mut commitment_soft_confirmations = vec![];
+        let mut commitment_witnesses = vec![];
+        // The DB iterator is not held across awaits, DA blocks are fetched afterwards
+        let mut da_slot_heights: Vec<u64> = vec![];
+        for soft_batch in ledger_db.soft_batch_iter(
+            BatchNumber(commitment.l2_start_block_number)
+                ..BatchNumber(commitment.l2_end_block_number + 1),
+        )? {
+            let soft_batch = soft_batch?;
+            let l2_height = soft_batch.l2_height;
+            if soft_batch.txs.iter().any(|tx| tx.body.is_none()) {
+                bail!("Transaction bodies of L2 block #{} are pruned", l2_height);
+            }
+            if da_slot_heights.last() != Some(&soft_batch.da_slot_height) {
+                da_slot_heights.push(soft_batch.da_slot_height);
+            }
+            commitment_witnesses.push(
+                ledger_db
+                    .get_l2_witness::<Witness>(l2_height)?
+                    .ok_or_else(|| anyhow!("No witness of L2 block #{}", l2_height))?,
+            );
+            commitment_soft_confirmations.push(SignedSoftConfirmationBatch::from(soft_batch));
+        }
+
+        let l2_blocks = commitment.l2_end_block_number - commitment.l2_start_block_number + 1;
+        if commitment_witnesses.len() as u64 != l2_blocks {
+            bail!(
+                "L2 blocks {} to {} are not all in the ledger",
+                commitment.l2_start_block_number,
+                commitment.l2_end_block_number
+            );
+        }
+        let mut da_block_headers: Vec<<Da::Spec as DaSpec>::BlockHeader> = vec![];
+        let mut da_blobs = vec![];
+        for da_slot_height in da_slot_heights {
+            let block = da_service.get_block_at(da_slot_height).await?;
+            da_block_headers.push(block.header().clone());
+            da_blobs.push(extract_slot_blobs(da_service, &block).await);
+        }
+        soft_confirmations.push_back(commitment_soft_confirmations);
+        witnesses.push_back(commitment_witnesses);
+        da_block_headers_of_soft_confirmations.push_back(da_block_headers);
+        da_blobs_of_soft_confirmations.push_back(da_blobs);
+    }
+
+    let initial_state_root = ledger_db
+        .get_l2_state_root(first_l2_height - 1)?
+        .ok_or_else(|| anyhow!("No state root of L2 block #{}", first_l2_height - 1))?;
+    let final_state_root = ledger_db
+        .get_l2_state_root(last_l2_height)?
+        .ok_or_else(|| anyhow!("No state root of L2 block #{}", last_l2_height))?;
+    let initial_batch_hash = ledger_db
+        .get_soft_batch_by_number(&BatchNumber(first_l2_height))?
+        .ok_or_else(|| anyhow!("L2 block #{} is not in the ledger", first_l2_height))?
+        .prev_hash;
+
+    let (inclusion_proof, completeness_proof) =
+        da_service.get_extraction_proof(&l1_block, &da_data).await;
+
+    Ok(StateTransitionData {
+        initial_state_root,
+        final_state_root,
+        initial_batch_hash,
+        da_data,
+        da_block_header_of_commitments: l1_block.header().clone(),
+        inclusion_proof,
+        completeness_proof,
+        soft_confirmations,
+        state_transition_witnesses: CompactWitnesses::compact(witnesses),
+        da_block_headers_of_soft_confirmations,
+        da_blobs_of_soft_confirmations,
+        sequencer_public_key: sequencer_public_key.to_vec(),
+        sequencer_da_public_key: sequencer_da_public_key.to_vec(),
+        sequencer_commitments_range,
+    })
+}
+
+/// Writes the guest input to the file, serialized the way the prover passes it to the guest.
+pub fn write_guest_input<StateRoot, Witness, Da>(
+    input: &StateTransitionData<StateRoot, Witness, Da>,
+    path: &Path,
+) -> anyhow::Result<()>
+where
+    Da: DaSpec,
+    StateTransitionData<StateRoot, Witness, Da>: borsh::BorshSerialize,
+{
+    let input = borsh::to_vec(input)?;
+    std::fs::write(path, &input)?;
+    info!(
+        "Wrote {} bytes of guest input to {}",
+        input.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// All sequencer commitments in the blobs, in order
+fn block_commitments<Da: DaSpec>(
+    da_data: &mut [Da::BlobTransaction],
+    sequencer_da_public_key: &[u8],
+) -> Vec<SequencerCommitment> {
+    da_data
+        .iter_mut()
+        .filter(|blob| blob.sender().as_ref() == sequencer_da_public_key)
+        .filter_map(|blob| match DaData::decode_activated(blob.full_data()) {
+            Ok(
+                DaData::SequencerCommitment(commitment)
+                | DaData::CommitmentWithStateDiff(CommitmentWithStateDiff { commitment, .. }),
+            ) => Some(commitment),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Inclusive index range of the proven commitments among all commitments of the L1 block
+fn commitments_range(
+    block_commitments: &[SequencerCommitment],
+    proven_commitments: &[SequencerCommitment],
+) -> Option<(u32, u32)> {
+    let start = block_commitments
+        .windows(proven_commitments.len())
+        .position(|commitments| commitments == proven_commitments)?;
+    let end = start + proven_commitments.len() - 1;
+    Some((start.try_into().ok()?, end.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commitment(l2_start_block_number: u64) -> SequencerCommitment {
+        SequencerCommitment {
+            merkle_root: [l2_start_block_number as u8; 32],
+            l2_start_block_number,
+            l2_end_block_number: l2_start_block_number + 9,
+        }
+    }
+
+    #[test]
+    fn finds_range_of_proven_commitments() {
+        let block_commitments = [commitment(1), commitment(11), commitment(21)];
+        assert_eq!(
+            commitments_range(&block_commitments, &block_commitments[1..]),
+            Some((1, 2))
+        );
+        assert_eq!(
+            commitments_range(&block_commitments, &block_commitments[..1]),
+            Some((0, 0))
+        );
+        assert_eq!(
+            commitments_range(&block_commitments, &[commitment(31)]),
+            None
+        );
+    }
+}
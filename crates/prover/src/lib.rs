@@ -6,8 +6,18 @@ use sov_modules_stf_blueprint::StfBlueprint;
 use tokio::sync::oneshot;
 use tracing::instrument;
 
+mod guest_input;
+mod progress;
 pub mod prover_service;
+mod replay;
+mod rpc;
 mod runner;
+pub use guest_input::{generate_guest_input, write_guest_input};
+pub use progress::{LastProofInfo, ProverStatusResponse, ProvingJobInfo, ProvingJobState};
+pub use replay::{
+    fetch_replay_input, replay_range, ReplayDivergence, ReplayInput, ReplayReport, TxDivergence,
+};
+pub use rpc::CycleReportResponse;
 pub use runner::*;
 
 /// Dependencies needed to run the rollup.
@@ -36,7 +46,7 @@ impl<S: RollupBlueprint> Prover<S> {
 
     /// Only run the rpc.
     pub async fn run_rpc(self) -> Result<(), anyhow::Error> {
-        self.runner.start_rpc_server(self.rpc_methods, None).await;
+        self.runner.start_rpc_server(self.rpc_methods, None).await?;
         Ok(())
     }
 
@@ -46,7 +56,7 @@ impl<S: RollupBlueprint> Prover<S> {
         channel: Option<oneshot::Sender<SocketAddr>>,
     ) -> Result<(), anyhow::Error> {
         let mut runner = self.runner;
-        runner.start_rpc_server(self.rpc_methods, channel).await;
+        runner.start_rpc_server(self.rpc_methods, channel).await?;
 
         runner.run().await?;
         Ok(())
@@ -0,0 +1,209 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+/// Rough number of cycles the guest spends on a soft confirmation, besides reading state
+const CYCLES_PER_L2_BLOCK: u64 = 2_000_000;
+/// Rough number of cycles the guest spends on a witness hint, mostly verifying a JMT proof
+const CYCLES_PER_HINT: u64 = 100_000;
+
+/// Estimates the cycles of proving the L2 blocks from the size of their witnesses
+pub(crate) fn estimate_cycles(l2_blocks: u64, hints: usize) -> u64 {
+    l2_blocks * CYCLES_PER_L2_BLOCK + hints as u64 * CYCLES_PER_HINT
+}
+
+/// Step of a proving job of the prover
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProvingJobState {
+    /// The witness is submitted, but the prover service is busy
+    Pending,
+    /// Being proven
+    InProgress,
+    /// Proven, but the proof is not sent to DA yet
+    Proven,
+}
+
+/// A proving job reported by `prover_getProvingJobs`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvingJobInfo {
+    pub l1_height: u64,
+    #[serde(with = "hex::serde")]
+    pub l1_hash: [u8; 32],
+    /// First and last L2 heights of the proven sequencer commitments
+    pub l2_range: (u64, u64),
+    pub state: ProvingJobState,
+    /// Seconds since the job was submitted to the prover service
+    pub elapsed_secs: u64,
+    /// Rough estimate of the cycles of the job.
+    /// Not known for jobs which were proven before a restart.
+    pub estimated_cycles: Option<u64>,
+}
+
+/// The latest proof sent to DA, reported by `prover_getLastProof`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastProofInfo {
+    /// L1 height of the sequencer commitments proven by the proof
+    pub l1_height: u64,
+    /// Id of the DA transaction of the proof, the txid on Bitcoin
+    #[serde(with = "hex::serde")]
+    pub da_tx_id: [u8; 32],
+    /// Unix timestamp of when the proof was sent
+    pub sent_at: u64,
+}
+
+/// Response of `prover_getStatus`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProverStatusResponse {
+    pub jobs: Vec<ProvingJobInfo>,
+    pub queue_depth: usize,
+    pub last_proof: Option<LastProofInfo>,
+}
+
+struct TrackedJob {
+    l1_hash: [u8; 32],
+    l2_range: (u64, u64),
+    state: ProvingJobState,
+    submitted_at: Instant,
+    estimated_cycles: Option<u64>,
+}
+
+#[derive(Default)]
+struct Progress {
+    jobs: BTreeMap<u64, TrackedJob>,
+    queue_depth: usize,
+    last_proof: Option<LastProofInfo>,
+}
+
+/// Keeps the progress of the prover in memory, so it is reported over RPC.
+#[derive(Default)]
+pub(crate) struct ProverProgress {
+    progress: Mutex<Progress>,
+}
+
+impl ProverProgress {
+    /// Called after the witness of the L1 block is submitted to the prover service,
+    /// or its proof is found in the ledger after a restart.
+    pub(crate) fn job_submitted(
+        &self,
+        l1_height: u64,
+        l1_hash: [u8; 32],
+        l2_range: (u64, u64),
+        state: ProvingJobState,
+        estimated_cycles: Option<u64>,
+    ) {
+        self.progress.lock().unwrap().jobs.insert(
+            l1_height,
+            TrackedJob {
+                l1_hash,
+                l2_range,
+                state,
+                submitted_at: Instant::now(),
+                estimated_cycles,
+            },
+        );
+    }
+
+    pub(crate) fn set_job_state(&self, l1_height: u64, state: ProvingJobState) {
+        if let Some(job) = self.progress.lock().unwrap().jobs.get_mut(&l1_height) {
+            job.state = state;
+        }
+    }
+
+    /// Called after the proof of the L1 block is sent to DA
+    pub(crate) fn proof_sent(&self, l1_height: u64, da_tx_id: [u8; 32]) {
+        let mut progress = self.progress.lock().unwrap();
+        progress.jobs.remove(&l1_height);
+        progress.last_proof = Some(LastProofInfo {
+            l1_height,
+            da_tx_id,
+            sent_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("Time went backwards")
+                .as_secs(),
+        });
+    }
+
+    /// Sets the number of L1 blocks waiting to be processed
+    pub(crate) fn set_queue_depth(&self, queue_depth: usize) {
+        self.progress.lock().unwrap().queue_depth = queue_depth;
+    }
+
+    pub(crate) fn jobs(&self) -> Vec<ProvingJobInfo> {
+        let progress = self.progress.lock().unwrap();
+        progress
+            .jobs
+            .iter()
+            .map(|(l1_height, job)| ProvingJobInfo {
+                l1_height: *l1_height,
+                l1_hash: job.l1_hash,
+                l2_range: job.l2_range,
+                state: job.state,
+                elapsed_secs: job.submitted_at.elapsed().as_secs(),
+                estimated_cycles: job.estimated_cycles,
+            })
+            .collect()
+    }
+
+    pub(crate) fn queue_depth(&self) -> usize {
+        self.progress.lock().unwrap().queue_depth
+    }
+
+    pub(crate) fn last_proof(&self) -> Option<LastProofInfo> {
+        self.progress.lock().unwrap().last_proof.clone()
+    }
+
+    pub(crate) fn status(&self) -> ProverStatusResponse {
+        ProverStatusResponse {
+            jobs: self.jobs(),
+            queue_depth: self.queue_depth(),
+            last_proof: self.last_proof(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_jobs_until_proof_is_sent() {
+        let progress = ProverProgress::default();
+        progress.job_submitted(
+            12,
+            [2; 32],
+            (11, 20),
+            ProvingJobState::Pending,
+            Some(estimate_cycles(10, 5)),
+        );
+        progress.job_submitted(10, [1; 32], (1, 10), ProvingJobState::Proven, None);
+        progress.set_job_state(12, ProvingJobState::InProgress);
+        progress.set_queue_depth(3);
+
+        let status = progress.status();
+        assert_eq!(status.queue_depth, 3);
+        assert_eq!(
+            status
+                .jobs
+                .iter()
+                .map(|job| (job.l1_height, job.state))
+                .collect::<Vec<_>>(),
+            vec![
+                (10, ProvingJobState::Proven),
+                (12, ProvingJobState::InProgress)
+            ]
+        );
+        assert_eq!(status.jobs[1].estimated_cycles, Some(20_500_000));
+        assert_eq!(status.last_proof, None);
+
+        progress.proof_sent(10, [3; 32]);
+        assert_eq!(progress.jobs().len(), 1);
+        let last_proof = progress.last_proof().unwrap();
+        assert_eq!((last_proof.l1_height, last_proof.da_tx_id), (10, [3; 32]));
+    }
+}
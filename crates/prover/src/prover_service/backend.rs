@@ -0,0 +1,187 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use borsh::BorshSerialize;
+use sov_rollup_interface::zk::{CycleReport, Proof, ZkvmHost};
+
+/// A zkVM the prover proves state transitions with.
+///
+/// Unlike [`ZkvmHost`], the trait is object safe, so the backend is picked by the
+/// prover config at runtime. This allows running different backends side by side.
+pub trait ProvingBackend: Send + Sync {
+    /// Name of the backend, used in logs
+    fn name(&self) -> &'static str;
+
+    /// Code commitment of the guest program the backend proves
+    fn code_commitment(&self) -> Vec<u8>;
+
+    /// Creates a session to prove a single execution of the guest
+    fn new_session(&self) -> Box<dyn ProvingSession>;
+}
+
+/// A single proving job of a [`ProvingBackend`].
+pub trait ProvingSession: Send {
+    /// Adds borsh serialized input, which the guest reads in the same order.
+    fn write_input(&mut self, input: &[u8]);
+
+    /// Adds a proof whose output the guest verifies recursively.
+    fn add_assumption(&mut self, _proof: Proof) -> anyhow::Result<()> {
+        anyhow::bail!("The proving backend does not support recursion")
+    }
+
+    /// Executes the guest with the written input.
+    /// A full proof is only created if `with_proof` is set.
+    fn prove(self: Box<Self>, with_proof: bool) -> anyhow::Result<Proof>;
+
+    /// Executes the guest with the written input without proving,
+    /// and reports the cycles it spent.
+    fn profile(self: Box<Self>) -> anyhow::Result<(Proof, CycleReport)> {
+        anyhow::bail!("The proving backend does not support cycle profiling")
+    }
+}
+
+/// A [`ProvingBackend`] on top of any [`ZkvmHost`].
+pub struct ZkvmBackend<Vm> {
+    name: &'static str,
+    vm: Vm,
+    code_commitment: Vec<u8>,
+    create_proofs: bool,
+}
+
+impl<Vm: ZkvmHost> ZkvmBackend<Vm> {
+    /// Creates a backend proving with `vm`, which must run the guest of `code_commitment`.
+    pub fn new(name: &'static str, vm: Vm, code_commitment: Vm::CodeCommitment) -> Self {
+        Self {
+            name,
+            vm,
+            code_commitment: code_commitment.as_ref().to_vec(),
+            create_proofs: true,
+        }
+    }
+
+    /// Only executes the guest, even when proofs are requested.
+    /// The outputs are returned as public inputs, which only test nodes accept.
+    pub fn without_proofs(mut self) -> Self {
+        self.create_proofs = false;
+        self
+    }
+}
+
+impl<Vm: ZkvmHost + Send + 'static> ProvingBackend for ZkvmBackend<Vm> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn code_commitment(&self) -> Vec<u8> {
+        self.code_commitment.clone()
+    }
+
+    fn new_session(&self) -> Box<dyn ProvingSession> {
+        Box::new(ZkvmSession {
+            vm: self.vm.clone(),
+            create_proofs: self.create_proofs,
+        })
+    }
+}
+
+struct ZkvmSession<Vm> {
+    vm: Vm,
+    create_proofs: bool,
+}
+
+impl<Vm: ZkvmHost + Send> ProvingSession for ZkvmSession<Vm> {
+    fn write_input(&mut self, input: &[u8]) {
+        self.vm.add_hint(SerializedInput(input));
+    }
+
+    fn add_assumption(&mut self, proof: Proof) -> anyhow::Result<()> {
+        self.vm.add_assumption(proof)
+    }
+
+    fn prove(mut self: Box<Self>, with_proof: bool) -> anyhow::Result<Proof> {
+        self.vm.run(with_proof && self.create_proofs)
+    }
+
+    fn profile(mut self: Box<Self>) -> anyhow::Result<(Proof, CycleReport)> {
+        self.vm.profile()
+    }
+}
+
+/// The guest program run natively on the guest of a zkVM, which returns its output
+/// as a public input proof.
+pub type NativeGuestProgram<Vm> =
+    Arc<dyn Fn(<Vm as ZkvmHost>::Guest) -> anyhow::Result<Proof> + Send + Sync>;
+
+/// A [`ProvingBackend`] running the guest program natively instead of in the zkVM,
+/// which is much faster than executing it. Only test nodes accept its public input proofs.
+/// Assumptions are not verified natively.
+pub struct NativeBackend<Vm: ZkvmHost> {
+    name: &'static str,
+    vm: Vm,
+    code_commitment: Vec<u8>,
+    program: NativeGuestProgram<Vm>,
+}
+
+impl<Vm: ZkvmHost> NativeBackend<Vm> {
+    /// Creates a backend running `program` on the hints written to `vm`, in place of the
+    /// guest of `code_commitment`.
+    pub fn new(
+        name: &'static str,
+        vm: Vm,
+        code_commitment: Vm::CodeCommitment,
+        program: NativeGuestProgram<Vm>,
+    ) -> Self {
+        Self {
+            name,
+            vm,
+            code_commitment: code_commitment.as_ref().to_vec(),
+            program,
+        }
+    }
+}
+
+impl<Vm: ZkvmHost + Send + 'static> ProvingBackend for NativeBackend<Vm> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn code_commitment(&self) -> Vec<u8> {
+        self.code_commitment.clone()
+    }
+
+    fn new_session(&self) -> Box<dyn ProvingSession> {
+        Box::new(NativeSession {
+            vm: self.vm.clone(),
+            program: self.program.clone(),
+        })
+    }
+}
+
+struct NativeSession<Vm: ZkvmHost> {
+    vm: Vm,
+    program: NativeGuestProgram<Vm>,
+}
+
+impl<Vm: ZkvmHost + Send> ProvingSession for NativeSession<Vm> {
+    fn write_input(&mut self, input: &[u8]) {
+        self.vm.add_hint(SerializedInput(input));
+    }
+
+    fn add_assumption(&mut self, _proof: Proof) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    fn prove(mut self: Box<Self>, _with_proof: bool) -> anyhow::Result<Proof> {
+        let guest = self.vm.simulate_with_hints();
+        (self.program)(guest)
+    }
+}
+
+/// Input which is already borsh serialized, written as is.
+pub(crate) struct SerializedInput<'a>(pub(crate) &'a [u8]);
+
+impl BorshSerialize for SerializedInput<'_> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        writer.write_all(self.0)
+    }
+}
@@ -3,8 +3,16 @@ use sov_rollup_interface::services::da::DaService;
 use sov_rollup_interface::stf::StateTransitionFunction;
 use sov_rollup_interface::zk::ZkvmHost;
 
+mod backend;
 mod parallel;
+mod remote;
+#[cfg(feature = "sp1")]
+mod sp1;
+pub use backend::*;
 pub use parallel::*;
+pub use remote::*;
+#[cfg(feature = "sp1")]
+pub use sp1::*;
 
 /// Represents the possible modes of execution for a zkVM program
 pub enum ProofGenConfig<Stf, Da: DaService, Vm: ZkvmHost>
@@ -18,6 +26,8 @@ where
     /// The executor runs the rollup verification logic in the zkVM, but does not actually
     /// produce a zk proof
     Execute,
+    /// Like [`ProofGenConfig::Execute`], and reports the cycles spent by the guest
+    Profile,
     /// The prover runs the rollup verification logic in the zkVM and produces a zk proof
     Prover,
 }
@@ -7,10 +7,13 @@ use citrea_stf::verifier::StateTransitionVerifier;
 use prover::Prover;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use sov_rollup_interface::da::{DaData, DaSpec};
+use sov_rollup_interface::da::{BlockHeaderTrait, DaChainState, DaData, DaSpec, DaVerifier};
 use sov_rollup_interface::services::da::DaService;
 use sov_rollup_interface::stf::StateTransitionFunction;
-use sov_rollup_interface::zk::{Proof, StateTransitionData, ZkvmHost};
+use sov_rollup_interface::zk::{
+    AggregatedStateTransition, AggregationData, CycleReport, LightClientData, Proof,
+    StateTransitionData, ZkvmHost,
+};
 use sov_stf_runner::config::ProverConfig;
 use sov_stf_runner::{
     ProofProcessingStatus, ProverGuestRunConfig, ProverService, ProverServiceError,
@@ -18,7 +21,7 @@ use sov_stf_runner::{
 };
 
 use self::prover::ProverStatus;
-use crate::prover_service::ProofGenConfig;
+use crate::prover_service::{ProofGenConfig, ProvingBackend};
 
 /// Prover service that generates proofs in parallel.
 pub struct ParallelProverService<StateRoot, Witness, Da, Vm, V>
@@ -30,10 +33,24 @@ where
     V: StateTransitionFunction<Vm::Guest, Da::Spec> + Send + Sync,
 {
     vm: Vm,
+    /// Proves the state transitions in the execute and prove modes
+    backend: Arc<dyn ProvingBackend>,
     prover_config: Arc<ProofGenConfig<V, Da, Vm>>,
 
     zk_storage: V::PreState,
     prover_state: Prover<StateRoot, Witness, Da>,
+
+    /// Proves the aggregation guest, if proofs are aggregated
+    aggregation_backend: Option<Arc<dyn ProvingBackend>>,
+    /// Code commitment of the proofs the aggregation guest verifies
+    range_code_commitment: Vec<u8>,
+
+    /// The zkVM running the light client guest, if light client proofs are created
+    light_client_vm: Option<Vm>,
+    /// Code commitment of the light client guest
+    light_client_code_commitment: Vec<u8>,
+    /// Verifies the DA block headers like the light client guest
+    light_client_da_verifier: Option<Da::Verifier>,
 }
 
 impl<StateRoot, Witness, Da, Vm, V> ParallelProverService<StateRoot, Witness, Da, Vm, V>
@@ -57,6 +74,7 @@ where
     /// Creates a new prover.
     pub fn new(
         vm: Vm,
+        backend: Arc<dyn ProvingBackend>,
         zk_stf: V,
         da_verifier: Da::Verifier,
         config: ProverGuestRunConfig,
@@ -70,6 +88,7 @@ where
             ProverGuestRunConfig::Skip => ProofGenConfig::Skip,
             ProverGuestRunConfig::Simulate => ProofGenConfig::Simulate(stf_verifier),
             ProverGuestRunConfig::Execute => ProofGenConfig::Execute,
+            ProverGuestRunConfig::Profile => ProofGenConfig::Profile,
             ProverGuestRunConfig::Prove => ProofGenConfig::Prover,
         };
 
@@ -84,24 +103,74 @@ where
             ProofGenConfig::Execute => {
                 tracing::info!("Prover is configured to execute proving");
             }
+            ProofGenConfig::Profile => {
+                tracing::info!("Prover is configured to execute proving and report the cycles");
+            }
             ProofGenConfig::Prover => {
                 tracing::info!("Prover is configured to prove");
             }
         }
+        tracing::info!(
+            "Proving with the {} backend, code commitment: 0x{}",
+            backend.name(),
+            hex::encode(backend.code_commitment())
+        );
 
         let prover_config = Arc::new(config);
 
         Ok(Self {
             vm,
+            backend,
             prover_config,
             prover_state: Prover::new(num_threads)?,
             zk_storage,
+            aggregation_backend: None,
+            range_code_commitment: vec![],
+            light_client_vm: None,
+            light_client_code_commitment: vec![],
+            light_client_da_verifier: None,
         })
     }
 
+    /// Limits the total size in bytes of the guest inputs proven at the same time.
+    /// A job over the budget is still proven when no other job is.
+    pub fn with_memory_budget(mut self, memory_budget: usize) -> Self {
+        self.prover_state.set_memory_budget(memory_budget);
+        self
+    }
+
+    /// Enables the aggregation of proofs with a backend proving the aggregation guest.
+    /// `range_code_commitment` is the code commitment of the proofs to aggregate.
+    pub fn with_aggregation(
+        mut self,
+        aggregation_backend: Arc<dyn ProvingBackend>,
+        range_code_commitment: Vm::CodeCommitment,
+    ) -> Self {
+        self.aggregation_backend = Some(aggregation_backend);
+        self.range_code_commitment = range_code_commitment.as_ref().to_vec();
+        self
+    }
+
+    /// Enables light client proofs with a zkVM running the light client guest.
+    /// `light_client_code_commitment` is the code commitment of the light client guest,
+    /// which verifies its previous proof, and `da_verifier` verifies the DA block headers
+    /// with the rules of the guest.
+    pub fn with_light_client(
+        mut self,
+        light_client_vm: Vm,
+        light_client_code_commitment: Vm::CodeCommitment,
+        da_verifier: Da::Verifier,
+    ) -> Self {
+        self.light_client_vm = Some(light_client_vm);
+        self.light_client_code_commitment = light_client_code_commitment.as_ref().to_vec();
+        self.light_client_da_verifier = Some(da_verifier);
+        self
+    }
+
     /// Creates a new prover.
     pub fn new_with_default_workers(
         vm: Vm,
+        backend: Arc<dyn ProvingBackend>,
         zk_stf: V,
         da_verifier: Da::Verifier,
         prover_config: ProverConfig,
@@ -110,14 +179,21 @@ where
         let num_cpus = num_cpus::get();
         assert!(num_cpus > 1, "Unable to create parallel prover service");
 
-        Self::new(
+        let prover_service = Self::new(
             vm,
+            backend,
             zk_stf,
             da_verifier,
             prover_config.proving_mode,
             zk_storage,
             num_cpus - 1,
-        )
+        )?;
+        Ok(match prover_config.proving_memory_budget_mb {
+            Some(memory_budget_mb) => {
+                prover_service.with_memory_budget(memory_budget_mb.saturating_mul(1024 * 1024))
+            }
+            None => prover_service,
+        })
     }
 }
 
@@ -170,6 +246,7 @@ where
             block_header_hash,
             self.prover_config.clone(),
             vm,
+            self.backend.clone(),
             zk_storage,
         )
     }
@@ -179,6 +256,20 @@ where
         block_header_hash: <Da::Spec as DaSpec>::SlotHash,
         da_service: &Self::DaService,
     ) -> Result<(<Da as DaService>::TransactionId, Proof), anyhow::Error> {
+        let proof = self.wait_for_proving(block_header_hash).await?;
+        let da_data = DaData::ZKProof(proof.clone());
+
+        let tx_id = da_service
+            .send_transaction(da_data.encode().as_slice())
+            .await
+            .map_err(|e| anyhow::anyhow!(e))?;
+        Ok((tx_id, proof))
+    }
+
+    async fn wait_for_proving(
+        &self,
+        block_header_hash: <Da::Spec as DaSpec>::SlotHash,
+    ) -> Result<Proof, anyhow::Error> {
         loop {
             let status = self
                 .prover_state
@@ -186,17 +277,7 @@ where
 
             match status {
                 ProverStatus::Proved(proof) => {
-                    let da_data = DaData::ZKProof(proof.clone());
-
-                    let tx_id = da_service
-                        .send_transaction(
-                            borsh::to_vec(&da_data)
-                                .expect("Should serialize")
-                                .as_slice(),
-                        )
-                        .await
-                        .map_err(|e| anyhow::anyhow!(e))?;
-                    break Ok((tx_id, proof));
+                    break Ok(proof);
                 }
                 ProverStatus::ProvingInProgress => {
                     tokio::time::sleep(std::time::Duration::from_secs(5)).await;
@@ -207,4 +288,95 @@ where
             }
         }
     }
+
+    async fn aggregate_proofs(&self, proofs: Vec<Proof>) -> Result<Proof, anyhow::Error> {
+        let aggregation_backend = self
+            .aggregation_backend
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Proof aggregation is not enabled"))?;
+
+        let state_transitions = proofs
+            .iter()
+            .map(|proof| {
+                Vm::extract_output::<Da::Spec, StateRoot>(proof)
+                    .map_err(|e| anyhow::anyhow!("Failed to extract output of proof: {:?}", e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        // The guest can't prove state transitions which do not chain, they are rejected
+        // before running it
+        AggregatedStateTransition::aggregate(
+            self.range_code_commitment.clone(),
+            state_transitions.clone(),
+        )?;
+
+        let mut session = aggregation_backend.new_session();
+        session.write_input(&borsh::to_vec(&AggregationData::<Da::Spec, StateRoot> {
+            range_code_commitment: self.range_code_commitment.clone(),
+            state_transitions,
+        })?);
+        for proof in proofs {
+            session.add_assumption(proof)?;
+        }
+
+        tracing::info!(
+            "Aggregating proofs with the {} backend",
+            aggregation_backend.name()
+        );
+        tokio::task::spawn_blocking(move || session.prove(true)).await?
+    }
+
+    fn light_client_code_commitment(&self) -> Option<Vec<u8>> {
+        self.light_client_vm
+            .as_ref()
+            .map(|_| self.light_client_code_commitment.clone())
+    }
+
+    fn verify_light_client_headers(
+        &self,
+        chain_state: &DaChainState,
+        block_headers: &[<Da::Spec as DaSpec>::BlockHeader],
+    ) -> Result<DaChainState, anyhow::Error> {
+        let da_verifier = self
+            .light_client_da_verifier
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Light client proofs are not enabled"))?;
+        da_verifier
+            .verify_headers(chain_state, block_headers)
+            .map_err(|e| anyhow::anyhow!("Invalid DA block headers: {:?}", e))
+    }
+
+    async fn prove_light_client(
+        &self,
+        data: LightClientData<Da::Spec, StateRoot>,
+        previous_proof: Option<Proof>,
+        batch_proofs: Vec<Proof>,
+    ) -> Result<Proof, anyhow::Error> {
+        let mut vm = self
+            .light_client_vm
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Light client proofs are not enabled"))?;
+
+        let latest_da_height = data
+            .da_block_headers
+            .last()
+            .map(|header| header.height())
+            .unwrap_or_default();
+        vm.add_hint(data);
+        if let Some(previous_proof) = previous_proof {
+            vm.add_assumption(previous_proof)?;
+        }
+        for proof in batch_proofs {
+            vm.add_assumption(proof)?;
+        }
+
+        tracing::info!("Proving light client up to DA block {}", latest_da_height);
+        tokio::task::spawn_blocking(move || vm.run(true)).await?
+    }
+
+    async fn take_cycle_report(
+        &self,
+        block_header_hash: <Da::Spec as DaSpec>::SlotHash,
+    ) -> Option<CycleReport> {
+        self.prover_state.take_cycle_report(&block_header_hash)
+    }
 }
@@ -10,10 +10,10 @@ use serde::Serialize;
 use sov_rollup_interface::da::{BlockHeaderTrait, DaSpec};
 use sov_rollup_interface::services::da::DaService;
 use sov_rollup_interface::stf::StateTransitionFunction;
-use sov_rollup_interface::zk::{Proof, StateTransitionData, ZkvmHost};
+use sov_rollup_interface::zk::{CycleReport, Proof, StateTransitionData, ZkvmHost};
 use sov_stf_runner::{ProofProcessingStatus, ProverServiceError, WitnessSubmissionStatus};
 
-use crate::prover_service::ProofGenConfig;
+use crate::prover_service::{ProofGenConfig, ProvingBackend, SerializedInput};
 
 pub(crate) enum ProverStatus<StateRoot, Witness, Da: DaSpec> {
     WitnessSubmitted(StateTransitionData<StateRoot, Witness, Da>),
@@ -25,7 +25,11 @@ pub(crate) enum ProverStatus<StateRoot, Witness, Da: DaSpec> {
 
 struct ProverState<StateRoot, Witness, Da: DaSpec> {
     prover_status: HashMap<Da::SlotHash, ProverStatus<StateRoot, Witness, Da>>,
+    /// Cycle reports of the profiled proofs, until they are taken
+    cycle_reports: HashMap<Da::SlotHash, CycleReport>,
     pending_tasks_count: usize,
+    /// Total size of the inputs of the pending tasks
+    pending_input_size: usize,
 }
 
 impl<StateRoot, Witness, Da: DaSpec> ProverState<StateRoot, Witness, Da> {
@@ -44,10 +48,15 @@ impl<StateRoot, Witness, Da: DaSpec> ProverState<StateRoot, Witness, Da> {
     fn set_to_proved(
         &mut self,
         hash: Da::SlotHash,
-        proof: Result<Proof, anyhow::Error>,
+        proof: Result<(Proof, Option<CycleReport>), anyhow::Error>,
     ) -> Option<ProverStatus<StateRoot, Witness, Da>> {
         match proof {
-            Ok(p) => self.prover_status.insert(hash, ProverStatus::Proved(p)),
+            Ok((p, cycle_report)) => {
+                if let Some(cycle_report) = cycle_report {
+                    self.cycle_reports.insert(hash.clone(), cycle_report);
+                }
+                self.prover_status.insert(hash, ProverStatus::Proved(p))
+            }
             Err(e) => self.prover_status.insert(hash, ProverStatus::Err(e)),
         }
     }
@@ -59,26 +68,43 @@ impl<StateRoot, Witness, Da: DaSpec> ProverState<StateRoot, Witness, Da> {
         self.prover_status.get(&hash)
     }
 
-    fn inc_task_count_if_not_busy(&mut self, num_threads: usize) -> bool {
+    /// A task over the memory budget is still started when no other task is pending.
+    fn inc_task_count_if_not_busy(
+        &mut self,
+        num_threads: usize,
+        memory_budget: Option<usize>,
+        input_size: usize,
+    ) -> bool {
         if self.pending_tasks_count >= num_threads {
             return false;
         }
+        if let Some(memory_budget) = memory_budget {
+            if self.pending_tasks_count > 0
+                && self.pending_input_size.saturating_add(input_size) > memory_budget
+            {
+                return false;
+            }
+        }
 
         self.pending_tasks_count += 1;
+        self.pending_input_size += input_size;
         true
     }
 
-    fn dec_task_count(&mut self) {
+    fn dec_task_count(&mut self, input_size: usize) {
         assert!(self.pending_tasks_count > 0);
         self.pending_tasks_count -= 1;
+        self.pending_input_size -= input_size;
     }
 }
 
 // A prover that generates proofs in parallel using a thread pool. If the pool is saturated,
-// the prover will reject new jobs.
+// or the inputs being proven exceed the memory budget, the prover will reject new jobs.
 pub(crate) struct Prover<StateRoot, Witness, Da: DaService> {
     prover_state: Arc<RwLock<ProverState<StateRoot, Witness, Da::Spec>>>,
     num_threads: usize,
+    /// Max. total size in bytes of the inputs proven at the same time
+    memory_budget: Option<usize>,
     pool: rayon::ThreadPool,
 }
 
@@ -100,6 +126,7 @@ where
     pub(crate) fn new(num_threads: usize) -> anyhow::Result<Self> {
         Ok(Self {
             num_threads,
+            memory_budget: None,
             pool: rayon::ThreadPoolBuilder::new()
                 .num_threads(num_threads)
                 .build()
@@ -107,11 +134,17 @@ where
 
             prover_state: Arc::new(RwLock::new(ProverState {
                 prover_status: Default::default(),
+                cycle_reports: Default::default(),
                 pending_tasks_count: Default::default(),
+                pending_input_size: Default::default(),
             })),
         })
     }
 
+    pub(crate) fn set_memory_budget(&mut self, memory_budget: usize) {
+        self.memory_budget = Some(memory_budget);
+    }
+
     pub(crate) fn submit_witness(
         &self,
         state_transition_data: StateTransitionData<StateRoot, Witness, Da::Spec>,
@@ -135,7 +168,8 @@ where
         &self,
         block_header_hash: <Da::Spec as DaSpec>::SlotHash,
         config: Arc<ProofGenConfig<V, Da, Vm>>,
-        mut vm: Vm,
+        vm: Vm,
+        backend: Arc<dyn ProvingBackend>,
         zk_storage: V::PreState,
     ) -> Result<ProofProcessingStatus, ProverServiceError>
     where
@@ -147,33 +181,38 @@ where
         let mut prover_state = self.prover_state.write().expect("Lock was poisoned");
 
         let prover_status = prover_state
-            .remove(&block_header_hash)
+            .get_prover_status(block_header_hash.clone())
             .ok_or_else(|| anyhow::anyhow!("Missing witness for block: {:?}", block_header_hash))?;
 
         match prover_status {
             ProverStatus::WitnessSubmitted(state_transition_data) => {
-                let start_prover = prover_state.inc_task_count_if_not_busy(self.num_threads);
+                let input = borsh::to_vec(state_transition_data)
+                    .expect("State transition data serialization is infallible");
+                let input_size = input.len();
                 // Initiate a new proving job only if the prover is not busy.
-                if start_prover {
-                    prover_state.set_to_proving(block_header_hash.clone());
-                    vm.add_hint(state_transition_data);
+                // Otherwise the witness is kept, so that proving can be retried.
+                if !prover_state.inc_task_count_if_not_busy(
+                    self.num_threads,
+                    self.memory_budget,
+                    input_size,
+                ) {
+                    return Ok(ProofProcessingStatus::Busy);
+                }
+                prover_state.set_to_proving(block_header_hash.clone());
 
-                    self.pool.spawn(move || {
-                        tracing::debug_span!("guest_execution").in_scope(|| {
-                            let proof = make_proof(vm, config, zk_storage);
+                self.pool.spawn(move || {
+                    tracing::debug_span!("guest_execution").in_scope(|| {
+                        let proof = make_proof(vm, backend, config, zk_storage, &input);
 
-                            let mut prover_state =
-                                prover_state_clone.write().expect("Lock was poisoned");
+                        let mut prover_state =
+                            prover_state_clone.write().expect("Lock was poisoned");
 
-                            prover_state.set_to_proved(block_header_hash, proof);
-                            prover_state.dec_task_count();
-                        })
-                    });
+                        prover_state.set_to_proved(block_header_hash, proof);
+                        prover_state.dec_task_count(input_size);
+                    })
+                });
 
-                    Ok(ProofProcessingStatus::ProvingInProgress)
-                } else {
-                    Ok(ProofProcessingStatus::Busy)
-                }
+                Ok(ProofProcessingStatus::ProvingInProgress)
             }
             ProverStatus::ProvingInProgress => Err(anyhow::anyhow!(
                 "Proof generation for {:?} still in progress",
@@ -185,7 +224,12 @@ where
                 block_header_hash,
             )
             .into()),
-            ProverStatus::Err(e) => Err(e.into()),
+            ProverStatus::Err(_) => {
+                let Some(ProverStatus::Err(e)) = prover_state.remove(&block_header_hash) else {
+                    unreachable!("The status was checked above");
+                };
+                Err(e.into())
+            }
         }
     }
 
@@ -216,13 +260,23 @@ where
             )),
         }
     }
+
+    pub(crate) fn take_cycle_report(
+        &self,
+        block_header_hash: &<Da::Spec as DaSpec>::SlotHash,
+    ) -> Option<CycleReport> {
+        let mut prover_state = self.prover_state.write().expect("Lock was poisoned");
+        prover_state.cycle_reports.remove(block_header_hash)
+    }
 }
 
 fn make_proof<V, Vm, Da>(
     mut vm: Vm,
+    backend: Arc<dyn ProvingBackend>,
     config: Arc<ProofGenConfig<V, Da, Vm>>,
     zk_storage: V::PreState,
-) -> Result<Proof, anyhow::Error>
+    input: &[u8],
+) -> Result<(Proof, Option<CycleReport>), anyhow::Error>
 where
     Da: DaService,
     Vm: ZkvmHost + 'static,
@@ -230,12 +284,38 @@ where
     V::PreState: Send + Sync + 'static,
 {
     match config.deref() {
-        ProofGenConfig::Skip => Ok(Proof::PublicInput(Vec::default())),
-        ProofGenConfig::Simulate(verifier) => verifier
-            .run_sequencer_commitments_in_da_slot(vm.simulate_with_hints(), zk_storage)
-            .map(|_| Proof::PublicInput(Vec::default()))
-            .map_err(|e| anyhow::anyhow!("Guest execution must succeed but failed with {:?}", e)),
-        ProofGenConfig::Execute => vm.run(false),
-        ProofGenConfig::Prover => vm.run(true),
+        ProofGenConfig::Skip => Ok((Proof::PublicInput(Vec::default()), None)),
+        ProofGenConfig::Simulate(verifier) => {
+            vm.add_hint(SerializedInput(input));
+            verifier
+                .run_sequencer_commitments_in_da_slot(vm.simulate_with_hints(), zk_storage)
+                .map(|_| (Proof::PublicInput(Vec::default()), None))
+                .map_err(|e| {
+                    anyhow::anyhow!("Guest execution must succeed but failed with {:?}", e)
+                })
+        }
+        ProofGenConfig::Execute => {
+            prove_with_backend(backend.as_ref(), input, false).map(|proof| (proof, None))
+        }
+        ProofGenConfig::Profile => {
+            let mut session = backend.new_session();
+            session.write_input(input);
+            session
+                .profile()
+                .map(|(proof, cycle_report)| (proof, Some(cycle_report)))
+        }
+        ProofGenConfig::Prover => {
+            prove_with_backend(backend.as_ref(), input, true).map(|proof| (proof, None))
+        }
     }
 }
+
+fn prove_with_backend(
+    backend: &dyn ProvingBackend,
+    input: &[u8],
+    with_proof: bool,
+) -> Result<Proof, anyhow::Error> {
+    let mut session = backend.new_session();
+    session.write_input(input);
+    session.prove(with_proof)
+}
@@ -0,0 +1,347 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, ensure};
+use backoff::future::retry as retry_backoff;
+use backoff::ExponentialBackoffBuilder;
+use borsh::BorshDeserialize;
+use jsonrpsee::core::client::{ClientT, Error as JsonrpseeError};
+use jsonrpsee::core::params::ArrayParams;
+use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use jsonrpsee::rpc_params;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sov_rollup_interface::da::{BlockHeaderTrait, DaSpec};
+use sov_rollup_interface::zk::{Proof, StateTransition, StateTransitionData, Zkvm, ZkvmHost};
+use sov_stf_runner::RemoteProverConfig;
+use tokio::runtime::Handle;
+use tracing::{info, warn};
+
+use crate::prover_service::{ProvingBackend, ProvingSession};
+
+/// Status of a job of the remote proving service
+#[derive(Debug, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+enum RemoteJobStatus {
+    /// Still being proven
+    Pending,
+    /// Proven. The proof is borsh serialized and hex encoded.
+    Proven { proof: String },
+    /// Proving failed
+    Failed { error: String },
+}
+
+/// Delegates proving to a remote proving service, and falls back to a local backend
+/// when the remote is down or doesn't return a valid proof in time.
+///
+/// The remote service serves JSON-RPC over HTTP:
+/// - `prover_submitJob(inputs, withProof, codeCommitment)` takes the hex encoded inputs
+///   of the guest and its code commitment, and returns a job id.
+/// - `prover_getJob(jobId)` returns the status of the job, which is polled until proven.
+///
+/// Proofs returned by the remote are verified before being accepted,
+/// and their output must be the state transition of the proving job.
+pub struct RemoteBackend<Vm: Zkvm, Da, Root> {
+    prover: Arc<RemoteProver<Vm, Da, Root>>,
+}
+
+struct RemoteProver<Vm: Zkvm, Da, Root> {
+    client: HttpClient,
+    config: RemoteProverConfig,
+    code_commitment: Vm::CodeCommitment,
+    fallback: Arc<dyn ProvingBackend>,
+    runtime: Handle,
+    phantom: PhantomData<fn() -> (Da, Root)>,
+}
+
+impl<Vm, Da, Root> RemoteBackend<Vm, Da, Root>
+where
+    Vm: Zkvm,
+    Da: DaSpec,
+    Root: Serialize + DeserializeOwned,
+{
+    /// Creates a backend delegating the proving of the guest of `code_commitment`.
+    /// Must be called within a tokio runtime, which the requests to the remote are made on.
+    pub fn new(
+        config: RemoteProverConfig,
+        code_commitment: Vm::CodeCommitment,
+        fallback: Arc<dyn ProvingBackend>,
+    ) -> anyhow::Result<Self> {
+        let client = HttpClientBuilder::default()
+            .max_request_size(u32::MAX)
+            .max_response_size(u32::MAX)
+            .build(&config.url)?;
+        Ok(Self {
+            prover: Arc::new(RemoteProver {
+                client,
+                config,
+                code_commitment,
+                fallback,
+                runtime: Handle::current(),
+                phantom: PhantomData,
+            }),
+        })
+    }
+}
+
+impl<Vm, Da, Root> ProvingBackend for RemoteBackend<Vm, Da, Root>
+where
+    Vm: ZkvmHost + 'static,
+    Vm::CodeCommitment: Send + Sync,
+    Da: DaSpec + 'static,
+    Root: Serialize + DeserializeOwned + BorshDeserialize + AsRef<[u8]> + 'static,
+{
+    fn name(&self) -> &'static str {
+        "remote"
+    }
+
+    fn code_commitment(&self) -> Vec<u8> {
+        self.prover.code_commitment.as_ref().to_vec()
+    }
+
+    fn new_session(&self) -> Box<dyn ProvingSession> {
+        Box::new(RemoteSession {
+            prover: self.prover.clone(),
+            inputs: vec![],
+        })
+    }
+}
+
+struct RemoteSession<Vm: Zkvm, Da, Root> {
+    prover: Arc<RemoteProver<Vm, Da, Root>>,
+    inputs: Vec<Vec<u8>>,
+}
+
+impl<Vm, Da, Root> ProvingSession for RemoteSession<Vm, Da, Root>
+where
+    Vm: ZkvmHost + 'static,
+    Vm::CodeCommitment: Send + Sync,
+    Da: DaSpec + 'static,
+    Root: Serialize + DeserializeOwned + BorshDeserialize + AsRef<[u8]> + 'static,
+{
+    fn write_input(&mut self, input: &[u8]) {
+        self.inputs.push(input.to_vec());
+    }
+
+    fn prove(self: Box<Self>, with_proof: bool) -> anyhow::Result<Proof> {
+        let prover = &self.prover;
+        match prover
+            .runtime
+            .block_on(prover.prove_remotely(&self.inputs, with_proof))
+        {
+            Ok(proof) => Ok(proof),
+            Err(e) => {
+                warn!(
+                    "Remote proving failed, proving with the {} backend instead: {:?}",
+                    prover.fallback.name(),
+                    e
+                );
+                let mut session = prover.fallback.new_session();
+                for input in &self.inputs {
+                    session.write_input(input);
+                }
+                session.prove(with_proof)
+            }
+        }
+    }
+}
+
+impl<Vm, Da, Root> RemoteProver<Vm, Da, Root>
+where
+    Vm: ZkvmHost,
+    Da: DaSpec,
+    Root: Serialize + DeserializeOwned + BorshDeserialize + AsRef<[u8]>,
+{
+    async fn prove_remotely(&self, inputs: &[Vec<u8>], with_proof: bool) -> anyhow::Result<Proof> {
+        let hex_inputs: Vec<String> = inputs.iter().map(hex::encode).collect();
+        let job_id: String = self
+            .request(
+                "prover_submitJob",
+                rpc_params![
+                    hex_inputs,
+                    with_proof,
+                    hex::encode(self.code_commitment.as_ref())
+                ],
+            )
+            .await?;
+        info!("Submitted proving job {} to the remote prover", job_id);
+
+        let started_at = Instant::now();
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let max_poll_interval = Duration::from_secs(self.config.max_poll_interval_secs);
+        let mut poll_interval = Duration::from_secs(self.config.poll_interval_secs);
+        let proof = loop {
+            if started_at.elapsed() >= timeout {
+                bail!("Remote proving job {} timed out", job_id);
+            }
+            tokio::time::sleep(poll_interval).await;
+            poll_interval = (poll_interval * 2).min(max_poll_interval);
+
+            match self
+                .request::<RemoteJobStatus>("prover_getJob", rpc_params![&job_id])
+                .await?
+            {
+                RemoteJobStatus::Pending => continue,
+                RemoteJobStatus::Proven { proof } => {
+                    break Proof::try_from_slice(&hex::decode(proof)?)?
+                }
+                RemoteJobStatus::Failed { error } => {
+                    bail!("Remote proving job {} failed: {}", job_id, error)
+                }
+            }
+        };
+
+        let output = match &proof {
+            Proof::Full(serialized_proof) => {
+                Vm::verify_and_extract_output::<Da, Root>(serialized_proof, &self.code_commitment)
+                    .map_err(|e| anyhow!("Remote proof verification failed: {:?}", e))?
+            }
+            Proof::PublicInput(_) if with_proof => {
+                bail!("Remote prover returned public input instead of a proof");
+            }
+            Proof::PublicInput(_) => Vm::extract_output::<Da, Root>(&proof)
+                .map_err(|e| anyhow!("Failed to extract the output of the remote: {:?}", e))?,
+        };
+        check_output(inputs, &output)
+            .map_err(|e| anyhow!("Remote proof of job {} is invalid: {}", job_id, e))?;
+        info!(
+            "Received the proof of job {} from the remote prover",
+            job_id
+        );
+
+        Ok(proof)
+    }
+
+    /// Retries failed requests until the remote is considered down
+    async fn request<R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: ArrayParams,
+    ) -> anyhow::Result<R> {
+        let exponential_backoff = ExponentialBackoffBuilder::new()
+            .with_initial_interval(Duration::from_secs(1))
+            .with_max_elapsed_time(Some(Duration::from_secs(self.config.retry_secs)))
+            .build();
+
+        Ok(retry_backoff(exponential_backoff, || async {
+            self.client
+                .request(method, params.clone())
+                .await
+                .map_err(|e| match e {
+                    // The remote is up, but rejected the request
+                    JsonrpseeError::Call(_) => backoff::Error::permanent(e),
+                    _ => backoff::Error::transient(e),
+                })
+        })
+        .await?)
+    }
+}
+
+/// Checks that the output of a proof is the state transition of the guest input,
+/// so the remote can't return the valid proof of another state transition.
+fn check_output<Da, Root>(
+    inputs: &[Vec<u8>],
+    output: &StateTransition<Da, Root>,
+) -> anyhow::Result<()>
+where
+    Da: DaSpec,
+    Root: BorshDeserialize + AsRef<[u8]>,
+{
+    // The encoding of the witnesses does not depend on their type
+    let input = inputs.concat();
+    let data: StateTransitionData<Root, (), Da> =
+        BorshDeserialize::deserialize(&mut input.as_slice())?;
+
+    ensure!(
+        output.initial_state_root.as_ref() == data.initial_state_root.as_ref(),
+        "Initial state root does not match"
+    );
+    ensure!(
+        output.final_state_root.as_ref() == data.final_state_root.as_ref(),
+        "Final state root does not match"
+    );
+    ensure!(
+        output.initial_batch_hash == data.initial_batch_hash,
+        "Initial batch hash does not match"
+    );
+    ensure!(
+        output.da_slot_hash == data.da_block_header_of_commitments.hash(),
+        "DA slot hash does not match"
+    );
+    ensure!(
+        output.sequencer_commitments_range == data.sequencer_commitments_range,
+        "Sequencer commitments range does not match"
+    );
+    ensure!(
+        output.sequencer_public_key == data.sequencer_public_key
+            && output.sequencer_da_public_key == data.sequencer_da_public_key,
+        "Sequencer public keys do not match"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use sov_mock_da::{MockBlockHeader, MockDaSpec, MockHash, MockValidityCond};
+    use sov_rollup_interface::da::Time;
+    use sov_rollup_interface::zk::CompactWitnesses;
+
+    use super::*;
+
+    fn input(final_state_root: [u8; 32]) -> Vec<Vec<u8>> {
+        let data = StateTransitionData::<[u8; 32], Vec<u8>, MockDaSpec> {
+            initial_state_root: [1; 32],
+            final_state_root,
+            initial_batch_hash: [0; 32],
+            inclusion_proof: [0; 32],
+            completeness_proof: (),
+            da_data: vec![],
+            sequencer_commitments_range: (0, 1),
+            da_block_header_of_commitments: MockBlockHeader {
+                prev_hash: [0; 32].into(),
+                hash: MockHash::from([3; 32]),
+                txs_commitment: MockHash::from([3; 32]),
+                height: 3,
+                time: Time::now(),
+            },
+            soft_confirmations: VecDeque::new(),
+            state_transition_witnesses: CompactWitnesses::default(),
+            da_block_headers_of_soft_confirmations: VecDeque::new(),
+            da_blobs_of_soft_confirmations: VecDeque::new(),
+            sequencer_public_key: vec![4; 32],
+            sequencer_da_public_key: vec![5; 32],
+        };
+        vec![borsh::to_vec(&data).unwrap()]
+    }
+
+    fn output(final_state_root: [u8; 32]) -> StateTransition<MockDaSpec, [u8; 32]> {
+        StateTransition {
+            initial_state_root: [1; 32],
+            final_state_root,
+            initial_batch_hash: [0; 32],
+            state_diff: Default::default(),
+            da_slot_hash: MockHash::from([3; 32]),
+            sequencer_commitments_range: (0, 1),
+            withdrawal_roots: vec![],
+            sequencer_public_key: vec![4; 32],
+            sequencer_da_public_key: vec![5; 32],
+            validity_condition: MockValidityCond::default(),
+        }
+    }
+
+    #[test]
+    fn accepts_only_the_state_transition_of_the_input() {
+        check_output(&input([2; 32]), &output([2; 32])).unwrap();
+
+        let err = check_output(&input([2; 32]), &output([6; 32])).unwrap_err();
+        assert_eq!(err.to_string(), "Final state root does not match");
+
+        let mut other_slot = output([2; 32]);
+        other_slot.da_slot_hash = MockHash::from([7; 32]);
+        let err = check_output(&input([2; 32]), &other_slot).unwrap_err();
+        assert_eq!(err.to_string(), "DA slot hash does not match");
+    }
+}
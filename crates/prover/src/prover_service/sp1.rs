@@ -0,0 +1,109 @@
+use std::sync::Arc;
+
+use sov_rollup_interface::zk::{CycleReport, Proof};
+use sp1_sdk::{HashableKey, ProverClient, SP1ProvingKey, SP1Stdin, SP1VerifyingKey};
+
+use crate::prover_service::{ProvingBackend, ProvingSession};
+
+/// A [`ProvingBackend`] proving an SP1 build of a guest program, locally or on the SP1
+/// prover network as picked by the `SP1_PROVER` environment variable.
+///
+/// Each input is written as a separate buffer, which the guest reads with
+/// `sp1_zkvm::io::read_vec` in the same order. The output is read from the public values.
+pub struct Sp1Backend {
+    prover: Arc<Sp1Prover>,
+}
+
+struct Sp1Prover {
+    client: ProverClient,
+    elf: Vec<u8>,
+    proving_key: SP1ProvingKey,
+    verifying_key: SP1VerifyingKey,
+}
+
+impl Sp1Backend {
+    /// Creates a backend proving the guest program of `elf`
+    pub fn new(elf: Vec<u8>) -> Self {
+        let client = ProverClient::new();
+        let (proving_key, verifying_key) = client.setup(&elf);
+        Self {
+            prover: Arc::new(Sp1Prover {
+                client,
+                elf,
+                proving_key,
+                verifying_key,
+            }),
+        }
+    }
+}
+
+impl ProvingBackend for Sp1Backend {
+    fn name(&self) -> &'static str {
+        "sp1"
+    }
+
+    fn code_commitment(&self) -> Vec<u8> {
+        self.prover
+            .verifying_key
+            .hash_u32()
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect()
+    }
+
+    fn new_session(&self) -> Box<dyn ProvingSession> {
+        Box::new(Sp1Session {
+            prover: self.prover.clone(),
+            stdin: SP1Stdin::new(),
+        })
+    }
+}
+
+struct Sp1Session {
+    prover: Arc<Sp1Prover>,
+    stdin: SP1Stdin,
+}
+
+impl ProvingSession for Sp1Session {
+    fn write_input(&mut self, input: &[u8]) {
+        self.stdin.write_vec(input.to_vec());
+    }
+
+    fn prove(self: Box<Self>, with_proof: bool) -> anyhow::Result<Proof> {
+        if !with_proof {
+            let (public_values, _) = self
+                .prover
+                .client
+                .execute(&self.prover.elf, self.stdin)
+                .run()?;
+            return Ok(Proof::PublicInput(public_values.to_vec()));
+        }
+
+        let proof = self
+            .prover
+            .client
+            .prove(&self.prover.proving_key, self.stdin)
+            .compressed()
+            .run()?;
+        self.prover
+            .client
+            .verify(&proof, &self.prover.verifying_key)?;
+        Ok(Proof::Full(bincode::serialize(&proof)?))
+    }
+
+    fn profile(self: Box<Self>) -> anyhow::Result<(Proof, CycleReport)> {
+        let (public_values, report) = self
+            .prover
+            .client
+            .execute(&self.prover.elf, self.stdin)
+            .run()?;
+        let cycles = report.total_instruction_count();
+        let cycle_report = CycleReport {
+            user_cycles: cycles,
+            total_cycles: cycles,
+            segments: 1,
+            phases: vec![],
+        };
+        Ok((Proof::PublicInput(public_values.to_vec()), cycle_report))
+    }
+}
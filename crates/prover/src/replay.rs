@@ -0,0 +1,356 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use anyhow::{anyhow, bail};
+use citrea_primitives::extract_slot_inbox;
+use serde::Serialize;
+use sov_db::ledger_db::{NodeLedgerOps, ProverLedgerOps};
+use sov_db::schema::types::{BatchNumber, StoredTransaction};
+use sov_rollup_interface::da::{BlockHeaderTrait, DaSpec, SlotInbox};
+use sov_rollup_interface::rpc::{EventIdentifier, LedgerRpcProvider};
+use sov_rollup_interface::services::da::{DaService, SlotData};
+use sov_rollup_interface::soft_confirmation::SignedSoftConfirmationBatch;
+use sov_rollup_interface::stf::{Event, StateTransitionFunction, TransactionReceipt};
+use sov_rollup_interface::zk::Zkvm;
+use tracing::info;
+
+/// Result of replaying the L2 blocks proven for an L1 block, see [`replay_range`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayReport {
+    pub l1_height: u64,
+    /// First and last L2 heights of the sequencer commitments of the L1 block
+    pub l2_range: (u64, u64),
+    /// State root after the last replayed L2 block
+    pub final_state_root: String,
+    /// Final state root of the stored proof of the L1 block, if it was proven
+    pub proven_state_root: Option<String>,
+    /// The first L2 block whose replay does not match the native execution
+    pub divergence: Option<ReplayDivergence>,
+}
+
+/// An L2 block whose replay does not match the native execution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayDivergence {
+    pub l2_height: u64,
+    /// State root the native execution recorded
+    pub expected_state_root: String,
+    /// State root the replay computed. `None` if the replay panicked.
+    pub state_root: Option<String>,
+    /// Panic message of the replay, if it panicked
+    pub panic: Option<String>,
+    /// The first transaction whose receipt does not match the native execution
+    pub tx: Option<TxDivergence>,
+}
+
+/// A transaction whose replayed receipt does not match the native execution
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxDivergence {
+    /// Index of the transaction in the L2 block
+    pub index: usize,
+    #[serde(with = "hex::serde")]
+    pub hash: [u8; 32],
+    pub reason: String,
+}
+
+/// L1 data needed to replay the L2 blocks proven for an L1 block
+pub struct ReplayInput<Da: DaSpec> {
+    l1_height: u64,
+    l2_range: (u64, u64),
+    /// The guest applies the L2 blocks with the validity condition of the L1 block of the commitments
+    validity_condition: Da::ValidityCondition,
+    /// Headers of the L1 blocks the L2 blocks are built on
+    da_block_headers: Vec<Da::BlockHeader>,
+    /// Inboxes of the L1 blocks the L2 blocks are built on, in the order of their headers
+    slot_inboxes: Vec<SlotInbox>,
+}
+
+/// Fetches the L1 data to replay the L2 blocks of the sequencer commitments found in the L1 block
+pub async fn fetch_replay_input<Da, DB>(
+    ledger_db: &DB,
+    da_service: &Da,
+    l1_height: u64,
+) -> anyhow::Result<ReplayInput<Da::Spec>>
+where
+    Da: DaService<Error = anyhow::Error>,
+    DB: ProverLedgerOps + NodeLedgerOps,
+{
+    let commitments = ledger_db
+        .get_commitments_on_da_slot(l1_height)?
+        .filter(|commitments| !commitments.is_empty())
+        .ok_or_else(|| anyhow!("No sequencer commitments found on L1 block #{}", l1_height))?;
+    let l2_range = (
+        commitments[0].l2_start_block_number,
+        commitments[commitments.len() - 1].l2_end_block_number,
+    );
+
+    let validity_condition = da_service
+        .get_block_at(l1_height)
+        .await?
+        .validity_condition();
+
+    // The DB iterator is not held across awaits
+    let mut da_slot_heights: Vec<u64> = vec![];
+    for soft_batch in
+        ledger_db.soft_batch_iter(BatchNumber(l2_range.0)..BatchNumber(l2_range.1 + 1))?
+    {
+        let da_slot_height = soft_batch?.da_slot_height;
+        if da_slot_heights.last() != Some(&da_slot_height) {
+            da_slot_heights.push(da_slot_height);
+        }
+    }
+    let mut da_block_headers: Vec<<Da::Spec as DaSpec>::BlockHeader> = vec![];
+    let mut slot_inboxes = vec![];
+    for da_slot_height in da_slot_heights {
+        let block = da_service.get_block_at(da_slot_height).await?;
+        da_block_headers.push(block.header().clone());
+        slot_inboxes.push(extract_slot_inbox(da_service, &block));
+    }
+
+    Ok(ReplayInput {
+        l1_height,
+        l2_range,
+        validity_condition,
+        da_block_headers,
+        slot_inboxes,
+    })
+}
+
+/// Replays the L2 blocks of the sequencer commitments found in the L1 block the way the guest
+/// executes them, with the zk STF, from the witnesses recorded by the prover while syncing.
+/// Each resulting state root is compared to the one of the native execution,
+/// and the replay stops at the first L2 block which diverges.
+pub fn replay_range<Stf, Vm, Da, DB>(
+    stf: &Stf,
+    pre_state: Stf::PreState,
+    ledger_db: &DB,
+    sequencer_public_key: &[u8],
+    input: ReplayInput<Da>,
+) -> anyhow::Result<ReplayReport>
+where
+    Vm: Zkvm,
+    Da: DaSpec,
+    Stf: StateTransitionFunction<Vm, Da, Condition = Da::ValidityCondition>,
+    Stf::PreState: Clone,
+    DB: ProverLedgerOps + NodeLedgerOps + LedgerRpcProvider,
+{
+    let ReplayInput {
+        l1_height,
+        l2_range,
+        validity_condition,
+        da_block_headers,
+        slot_inboxes,
+    } = input;
+
+    let mut state_root: Stf::StateRoot = ledger_db
+        .get_l2_state_root(l2_range.0 - 1)?
+        .ok_or_else(|| anyhow!("No state root of L2 block #{}", l2_range.0 - 1))?;
+    let mut divergence = None;
+
+    let mut soft_batches =
+        ledger_db.soft_batch_iter(BatchNumber(l2_range.0)..BatchNumber(l2_range.1 + 1))?;
+    for l2_height in l2_range.0..=l2_range.1 {
+        let soft_batch = soft_batches
+            .next()
+            .transpose()?
+            .filter(|soft_batch| soft_batch.l2_height == l2_height)
+            .ok_or_else(|| anyhow!("L2 block #{} is not in the ledger", l2_height))?;
+        if soft_batch.txs.iter().any(|tx| tx.body.is_none()) {
+            bail!("Transaction bodies of L2 block #{} are pruned", l2_height);
+        }
+        let witness = ledger_db
+            .get_l2_witness::<Stf::Witness>(l2_height)?
+            .ok_or_else(|| anyhow!("No witness of L2 block #{}", l2_height))?;
+        let da_block_index = da_block_headers
+            .iter()
+            .position(|header| header.height() == soft_batch.da_slot_height)
+            .ok_or_else(|| anyhow!("No header of L1 block #{}", soft_batch.da_slot_height))?;
+        let da_block_header = &da_block_headers[da_block_index];
+
+        let stored_txs = soft_batch.txs.clone();
+        let expected_state_root = soft_batch.state_root.clone();
+        let mut soft_confirmation: SignedSoftConfirmationBatch = soft_batch.into();
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            stf.apply_soft_batch(
+                sequencer_public_key,
+                &state_root,
+                pre_state.clone(),
+                witness,
+                da_block_header,
+                &slot_inboxes[da_block_index],
+                &validity_condition,
+                &mut soft_confirmation,
+            )
+        }));
+
+        let result = match result {
+            Ok(result) => result,
+            Err(panic) => {
+                divergence = Some(ReplayDivergence {
+                    l2_height,
+                    expected_state_root: hex::encode(expected_state_root),
+                    state_root: None,
+                    panic: Some(panic_message(panic)),
+                    tx: None,
+                });
+                break;
+            }
+        };
+
+        if result.state_root.as_ref() != expected_state_root.as_slice() {
+            let stored_txs = stored_txs
+                .into_iter()
+                .map(|tx| stored_tx_events(ledger_db, tx))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let tx_receipts = result
+                .batch_receipts
+                .into_iter()
+                .flat_map(|receipt| receipt.tx_receipts)
+                .collect::<Vec<_>>();
+            divergence = Some(ReplayDivergence {
+                l2_height,
+                expected_state_root: hex::encode(expected_state_root),
+                state_root: Some(hex::encode(result.state_root.as_ref())),
+                panic: None,
+                tx: first_divergent_tx(&tx_receipts, &stored_txs),
+            });
+            break;
+        }
+
+        info!(
+            "Replayed L2 block #{} with state root 0x{}",
+            l2_height,
+            hex::encode(result.state_root.as_ref())
+        );
+        state_root = result.state_root;
+    }
+
+    let proven_state_root = ledger_db
+        .get_proof_data(l1_height)?
+        .map(|proof| hex::encode(proof.state_transition.final_state_root));
+
+    Ok(ReplayReport {
+        l1_height,
+        l2_range,
+        final_state_root: hex::encode(state_root.as_ref()),
+        proven_state_root,
+        divergence,
+    })
+}
+
+/// Hash and events of a transaction as recorded by the native execution
+fn stored_tx_events<DB: LedgerRpcProvider>(
+    ledger_db: &DB,
+    tx: StoredTransaction,
+) -> anyhow::Result<([u8; 32], Vec<Event>)> {
+    let event_ids = (tx.events.start.0..tx.events.end.0)
+        .map(EventIdentifier::Number)
+        .collect::<Vec<_>>();
+    let events = ledger_db
+        .get_events(&event_ids)?
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| {
+            anyhow!(
+                "Events of transaction 0x{} are missing",
+                hex::encode(tx.hash)
+            )
+        })?;
+    Ok((tx.hash, events))
+}
+
+/// Finds the first replayed transaction whose hash or events differ from the native execution
+fn first_divergent_tx<R>(
+    tx_receipts: &[TransactionReceipt<R>],
+    stored_txs: &[([u8; 32], Vec<Event>)],
+) -> Option<TxDivergence> {
+    for (index, (receipt, (hash, events))) in tx_receipts.iter().zip(stored_txs).enumerate() {
+        let reason = if receipt.tx_hash != *hash {
+            format!("replayed transaction 0x{}", hex::encode(receipt.tx_hash))
+        } else if receipt.events != *events {
+            format!(
+                "replay emitted {} events, native execution emitted {}",
+                receipt.events.len(),
+                events.len()
+            )
+        } else {
+            continue;
+        };
+        return Some(TxDivergence {
+            index,
+            hash: *hash,
+            reason,
+        });
+    }
+
+    if tx_receipts.len() != stored_txs.len() {
+        let index = tx_receipts.len().min(stored_txs.len());
+        let hash = tx_receipts
+            .get(index)
+            .map(|receipt| receipt.tx_hash)
+            .or_else(|| stored_txs.get(index).map(|(hash, _)| *hash))
+            .expect("One of the transaction lists is longer");
+        return Some(TxDivergence {
+            index,
+            hash,
+            reason: format!(
+                "replay applied {} transactions, native execution applied {}",
+                tx_receipts.len(),
+                stored_txs.len()
+            ),
+        });
+    }
+
+    None
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(hash: u8, events: Vec<Event>) -> TransactionReceipt<()> {
+        TransactionReceipt {
+            tx_hash: [hash; 32],
+            body_to_save: None,
+            events,
+            receipt: (),
+        }
+    }
+
+    #[test]
+    fn finds_first_divergent_tx() {
+        let stored = vec![
+            ([1; 32], vec![Event::new("key", "value")]),
+            ([2; 32], vec![Event::new("key", "value")]),
+        ];
+
+        let replayed = vec![
+            receipt(1, vec![Event::new("key", "value")]),
+            receipt(2, vec![Event::new("key", "value")]),
+        ];
+        assert_eq!(first_divergent_tx(&replayed, &stored), None);
+
+        let replayed = vec![
+            receipt(1, vec![Event::new("key", "value")]),
+            receipt(2, vec![]),
+        ];
+        let divergence = first_divergent_tx(&replayed, &stored).unwrap();
+        assert_eq!((divergence.index, divergence.hash), (1, [2; 32]));
+
+        let replayed = vec![receipt(1, vec![Event::new("key", "value")])];
+        let divergence = first_divergent_tx(&replayed, &stored).unwrap();
+        assert_eq!((divergence.index, divergence.hash), (1, [2; 32]));
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG};
+use jsonrpsee::types::ErrorObjectOwned;
+use jsonrpsee::RpcModule;
+use serde::Serialize;
+use sov_db::ledger_db::ProverLedgerOps;
+use sov_rollup_interface::zk::CycleReport;
+use tracing::debug;
+
+use crate::progress::ProverProgress;
+
+/// The cycle report of a proof, reported by `prover_getCycleReport`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CycleReportResponse {
+    pub l1_height: u64,
+    /// First and last L2 heights of the proven sequencer commitments
+    pub l2_range: (u64, u64),
+    #[serde(flatten)]
+    pub report: CycleReport,
+}
+
+pub(crate) struct RpcContext<DB: ProverLedgerOps> {
+    pub progress: Arc<ProverProgress>,
+    pub ledger_db: DB,
+}
+
+pub(crate) fn create_rpc_module<DB: ProverLedgerOps + Send + Sync + 'static>(
+    rpc_context: RpcContext<DB>,
+) -> Result<RpcModule<RpcContext<DB>>, jsonrpsee::core::RegisterMethodError> {
+    let mut rpc = RpcModule::new(rpc_context);
+
+    rpc.register_method("prover_getStatus", |_, ctx| {
+        debug!("Prover: prover_getStatus");
+        ctx.progress.status()
+    })?;
+
+    rpc.register_method("prover_getProvingJobs", |_, ctx| {
+        debug!("Prover: prover_getProvingJobs");
+        ctx.progress.jobs()
+    })?;
+
+    rpc.register_method("prover_getQueueDepth", |_, ctx| {
+        debug!("Prover: prover_getQueueDepth");
+        ctx.progress.queue_depth()
+    })?;
+
+    rpc.register_method("prover_getLastProof", |_, ctx| {
+        debug!("Prover: prover_getLastProof");
+        ctx.progress.last_proof()
+    })?;
+
+    rpc.register_method("prover_getCycleReport", |params, ctx| {
+        debug!("Prover: prover_getCycleReport");
+        let l1_height: u64 = params.one()?;
+        let report = ctx.ledger_db.get_cycle_report(l1_height).map_err(|e| {
+            ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, Some(e.to_string()))
+        })?;
+
+        Ok::<_, ErrorObjectOwned>(report.map(|stored| CycleReportResponse {
+            l1_height,
+            l2_range: (stored.l2_range.0 .0, stored.l2_range.1 .0),
+            report: stored.report,
+        }))
+    })?;
+
+    Ok(rpc)
+}
@@ -1,5 +1,5 @@
 use core::panic;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -10,50 +10,146 @@ use backoff::exponential::ExponentialBackoffBuilder;
 use backoff::future::retry as retry_backoff;
 use borsh::de::BorshDeserialize;
 use citrea_primitives::types::SoftConfirmationHash;
-use citrea_primitives::{get_da_block_at_height, L1BlockCache};
+use citrea_primitives::{
+    extract_proof_challenges, extract_slot_blobs, extract_slot_inbox, get_da_block_at_height,
+    AdaptiveSyncBatchSize, L1BlockCache,
+};
+use citrea_sequencer_registry::{
+    is_sequencer_da_pub_key_at, RegisteredSequencers, SequencerRegistry,
+};
+use citrea_stf::verifier::light_client_output;
 use jsonrpsee::core::client::Error as JsonrpseeError;
-use jsonrpsee::server::{BatchRequestConfig, ServerBuilder};
 use jsonrpsee::RpcModule;
-use rand::Rng;
 use sequencer_client::{GetSoftBatchResponse, SequencerClient};
 use shared_backup_db::{DbPoolError, PostgresConnector, ProofType};
-use sov_db::ledger_db::{ProverLedgerOps, SlotCommit};
-use sov_db::schema::types::{BatchNumber, SlotNumber, StoredStateTransition};
+use sov_db::ledger_db::{NodeLedgerOps, ProverLedgerOps, SlotCommit};
+use sov_db::schema::types::{
+    BatchNumber, ProvingJobStatus, SlotNumber, StoredCycleReport, StoredLightClientProof,
+    StoredProvingJob, StoredStateTransition,
+};
 use sov_modules_api::storage::HierarchicalStorageManager;
-use sov_modules_api::{BlobReaderTrait, Context, SignedSoftConfirmationBatch, SlotData};
+use sov_modules_api::{
+    BlobReaderTrait, Context, SignedSoftConfirmationBatch, SlotData, WorkingSet,
+};
 use sov_modules_stf_blueprint::StfBlueprintTrait;
-use sov_rollup_interface::da::{BlockHeaderTrait, DaData, DaSpec, SequencerCommitment};
+use sov_rollup_interface::da::{
+    BlockHeaderTrait, CommitmentWithStateDiff, DaData, DaSpec, SequencerCommitment,
+};
 use sov_rollup_interface::rpc::SoftConfirmationStatus;
-use sov_rollup_interface::services::da::DaService;
+use sov_rollup_interface::services::da::{DaService, FeeBump, TxStatus};
 use sov_rollup_interface::stf::{SoftBatchReceipt, StateTransitionFunction};
-use sov_rollup_interface::zk::{Proof, StateTransitionData, ZkvmHost};
+use sov_rollup_interface::zk::{
+    CompactWitnesses, CycleReport, LightClientData, LightClientOutput, Proof, SlotBlobs,
+    StateTransition, StateTransitionData, ZkvmHost, LIGHT_CLIENT_DA_WINDOW,
+};
 use sov_stf_runner::{
-    InitVariant, ProverConfig, ProverService, RollupPublicKeys, RpcConfig, RunnerConfig,
+    apply_fee_ceilings, create_config_rpc_module, spawn_rpc_server, ConfigReloader, InitVariant,
+    ProofPostingConfig, ProofProcessingStatus, ProverConfig, ProverService, RollupPublicKeys,
+    RpcConfig, RpcServerOptions, RunnerConfig, WitnessSubmissionStatus,
 };
 use tokio::select;
 use tokio::sync::{broadcast, mpsc, oneshot, Mutex};
-use tokio::time::sleep;
+use tokio::time::{sleep, Instant};
 use tracing::{debug, error, info, instrument, warn};
 
+use crate::progress::{estimate_cycles, ProverProgress, ProvingJobState};
+use crate::rpc::{create_rpc_module, RpcContext};
+
 type StateRoot<ST, Vm, Da> = <ST as StateTransitionFunction<Vm, Da>>::StateRoot;
 
 type CommitmentStateTransitionData<Stf, Vm, Da> = (
     VecDeque<Vec<<Stf as StateTransitionFunction<Vm, <Da as DaService>::Spec>>::Witness>>,
     VecDeque<Vec<SignedSoftConfirmationBatch>>,
     VecDeque<Vec<<<Da as DaService>::Spec as DaSpec>::BlockHeader>>,
+    VecDeque<Vec<SlotBlobs<<Da as DaService>::Spec>>>,
 );
 
+/// Consecutive new commitments of an L1 block, which are proven together,
+/// with their index range among the commitments of the L1 block
+type CommitmentSegment = (Vec<SequencerCommitment>, (u32, u32));
+
+/// An L1 block scanned for sequencer commitments.
+/// Its proof, if any, is sent to DA after the proofs of the previous L1 blocks.
+struct ProvingJob<Da: DaSpec> {
+    l1_height: u64,
+    hash: Da::SlotHash,
+    sequencer_commitments: Vec<SequencerCommitment>,
+    /// Whether a proof is generated for the L1 block
+    prove: bool,
+    /// Whether the prover service accepted the proving job
+    started: bool,
+    /// Proof of the job, if it was proven before a restart
+    proof: Option<Proof>,
+    /// Whether the L1 block was scanned before, and is only proven because it is challenged
+    rescanned: bool,
+    /// New commitments of the L1 block after a duplicate commitment, which are proven
+    /// on their own once the proof of the job is sent
+    later_segments: Vec<CommitmentSegment>,
+}
+
+/// Groups the jobs whose proofs can be aggregated into runs of more than one job, by index.
+/// The state transitions of a run are consecutive: the L1 blocks skipped in between do not
+/// have commitments, and the jobs of L1 blocks proven again because they are challenged,
+/// or proven with several proofs, are proven on their own.
+fn aggregation_runs<Da: DaSpec>(jobs: &[ProvingJob<Da>]) -> Vec<Vec<usize>> {
+    let mut runs = vec![];
+    let mut run = vec![];
+    for (i, job) in jobs.iter().enumerate() {
+        if job.prove && !job.rescanned && job.later_segments.is_empty() {
+            run.push(i);
+            continue;
+        }
+        // The state changes of L1 blocks with commitments which are not proven break the chain
+        if job.rescanned || !job.sequencer_commitments.is_empty() {
+            runs.push(std::mem::take(&mut run));
+        }
+    }
+    runs.push(run);
+    runs.retain(|run| run.len() > 1);
+    runs
+}
+
+/// Splits the commitments of an L1 block into the segments of consecutive new commitments.
+/// A proof covers a contiguous range of the commitments, so the new commitments before and
+/// after a duplicate one are proven separately.
+fn new_commitment_segments(
+    sequencer_commitments: Vec<SequencerCommitment>,
+    is_duplicate: &[bool],
+) -> Vec<CommitmentSegment> {
+    let mut segments: Vec<CommitmentSegment> = vec![];
+    for (i, (commitment, duplicate)) in sequencer_commitments
+        .into_iter()
+        .zip(is_duplicate)
+        .enumerate()
+    {
+        if *duplicate {
+            continue;
+        }
+        let index: u32 = i
+            .try_into()
+            .expect("cant be more than 4 billion commitments in a da block; qed");
+        match segments.last_mut() {
+            Some((commitments, range)) if range.1 + 1 == index => {
+                commitments.push(commitment);
+                range.1 = index;
+            }
+            _ => segments.push((vec![commitment], (index, index))),
+        }
+    }
+    segments
+}
+
 pub struct CitreaProver<C, Da, Sm, Vm, Stf, Ps, DB>
 where
     C: Context,
     Da: DaService,
-    Sm: HierarchicalStorageManager<Da::Spec>,
+    Sm: HierarchicalStorageManager<Da::Spec, NativeStorage = C::Storage>,
     Vm: ZkvmHost,
     Stf: StateTransitionFunction<Vm, Da::Spec, Condition = <Da::Spec as DaSpec>::ValidityCondition>
         + StfBlueprintTrait<C, Da::Spec, Vm>,
 
     Ps: ProverService<Vm>,
-    DB: ProverLedgerOps + Clone,
+    DB: ProverLedgerOps + NodeLedgerOps + Send + Sync + Clone + 'static,
 {
     start_l2_height: u64,
     da_service: Da,
@@ -67,13 +163,18 @@ where
     prover_service: Option<Ps>,
     sequencer_client: SequencerClient,
     sequencer_pub_key: Vec<u8>,
-    sequencer_da_pub_key: Vec<u8>,
+    public_keys: RollupPublicKeys,
+    /// Sequencers registered in the latest state,
+    /// the keys of the rollup config are used until the sequencer is rotated on chain.
+    registered_sequencers: RegisteredSequencers,
     phantom: std::marker::PhantomData<C>,
     prover_config: Option<ProverConfig>,
     code_commitment: Vm::CodeCommitment,
     l1_block_cache: Arc<Mutex<L1BlockCache<Da>>>,
     sync_blocks_count: u64,
     soft_confirmation_tx: broadcast::Sender<u64>,
+    progress: Arc<ProverProgress>,
+    config_reloader: Option<Arc<ConfigReloader>>,
 }
 
 impl<C, Da, Sm, Vm, Stf, Ps, DB> CitreaProver<C, Da, Sm, Vm, Stf, Ps, DB>
@@ -90,7 +191,7 @@ where
             ChangeSet = Sm::NativeChangeSet,
         > + StfBlueprintTrait<C, Da::Spec, Vm>,
     Ps: ProverService<Vm, StateRoot = Stf::StateRoot, Witness = Stf::Witness, DaService = Da>,
-    DB: ProverLedgerOps + Clone,
+    DB: ProverLedgerOps + NodeLedgerOps + Send + Sync + Clone + 'static,
 {
     /// Creates a new `StateTransitionRunner`.
     ///
@@ -139,6 +240,10 @@ where
         // Last L1/L2 height before shutdown.
         let start_l2_height = last_soft_batch_processed_before_shutdown;
 
+        let registered_sequencers = RegisteredSequencers::from_state::<C>(&mut WorkingSet::new(
+            storage_manager.create_finalized_storage()?,
+        ));
+
         Ok(Self {
             start_l2_height,
             da_service,
@@ -149,74 +254,75 @@ where
             batch_hash: prev_batch_hash,
             rpc_config,
             prover_service,
-            sequencer_client: SequencerClient::new(runner_config.sequencer_client_url),
-            sequencer_pub_key: public_keys.sequencer_public_key,
-            sequencer_da_pub_key: public_keys.sequencer_da_pub_key,
+            sequencer_client: SequencerClient::new(
+                runner_config.sequencer_client_url.primary().to_owned(),
+            ),
+            sequencer_pub_key: public_keys.sequencer_public_key.clone(),
+            public_keys,
+            registered_sequencers,
             phantom: std::marker::PhantomData,
             prover_config,
             code_commitment,
             l1_block_cache: Arc::new(Mutex::new(L1BlockCache::new())),
             sync_blocks_count,
             soft_confirmation_tx,
+            progress: Default::default(),
+            config_reloader: None,
         })
     }
 
+    /// Applies reloads of the config to the RPC server and the fee ceilings of the DA service,
+    /// and serves `citrea_reloadConfig` on the operator RPC server
+    pub fn with_config_reloader(mut self, config_reloader: Arc<ConfigReloader>) -> Self {
+        self.config_reloader = Some(config_reloader);
+        self
+    }
+
     /// Starts a RPC server with provided rpc methods.
     pub async fn start_rpc_server(
         &self,
         methods: RpcModule<()>,
         channel: Option<oneshot::Sender<SocketAddr>>,
-    ) {
-        let bind_host = match self.rpc_config.bind_host.parse() {
-            Ok(bind_host) => bind_host,
-            Err(e) => {
-                error!("Failed to parse bind host: {}", e);
-                return;
-            }
-        };
-        let listen_address = SocketAddr::new(bind_host, self.rpc_config.bind_port);
-
-        let max_connections = self.rpc_config.max_connections;
-        let max_subscriptions_per_connection = self.rpc_config.max_subscriptions_per_connection;
-        let max_request_body_size = self.rpc_config.max_request_body_size;
-        let max_response_body_size = self.rpc_config.max_response_body_size;
-        let batch_requests_limit = self.rpc_config.batch_requests_limit;
-
-        let _handle = tokio::spawn(async move {
-            let server = ServerBuilder::default()
-                .max_connections(max_connections)
-                .max_subscriptions_per_connection(max_subscriptions_per_connection)
-                .max_request_body_size(max_request_body_size)
-                .max_response_body_size(max_response_body_size)
-                .set_batch_request_config(BatchRequestConfig::Limit(batch_requests_limit))
-                .build([listen_address].as_ref())
-                .await;
-
-            match server {
-                Ok(server) => {
-                    let bound_address = match server.local_addr() {
-                        Ok(address) => address,
-                        Err(e) => {
-                            error!("{}", e);
-                            return;
-                        }
-                    };
-                    if let Some(channel) = channel {
-                        if let Err(e) = channel.send(bound_address) {
-                            error!("Could not send bound_address {}: {}", bound_address, e);
-                            return;
-                        }
-                    }
-                    info!("Starting RPC server at {} ", &bound_address);
+    ) -> anyhow::Result<()> {
+        let methods = self.register_rpc_methods(methods)?;
+        spawn_rpc_server(
+            &self.rpc_config,
+            methods,
+            self.ledger_db.clone(),
+            RpcServerOptions {
+                config_updates: self
+                    .config_reloader
+                    .as_ref()
+                    .map(|config_reloader| config_reloader.subscribe()),
+                ..Default::default()
+            },
+            channel,
+        )
+    }
 
-                    let _server_handle = server.start(methods);
-                    futures::future::pending::<()>().await;
-                }
-                Err(e) => {
-                    error!("Could not start RPC server: {}", e);
-                }
+    /// Updates the given RpcModule with prover methods.
+    pub fn register_rpc_methods(
+        &self,
+        mut rpc_methods: RpcModule<()>,
+    ) -> Result<RpcModule<()>, jsonrpsee::core::RegisterMethodError> {
+        let rpc = create_rpc_module(RpcContext {
+            progress: self.progress.clone(),
+            ledger_db: self.ledger_db.clone(),
+        })?;
+        rpc_methods.merge(rpc)?;
+
+        match (&self.config_reloader, &self.rpc_config.operator) {
+            (Some(config_reloader), Some(_)) => {
+                rpc_methods.merge(create_config_rpc_module(config_reloader.clone())?)?;
             }
-        });
+            (Some(_), None) => {
+                warn!(
+                    "citrea_reloadConfig is disabled, it is only served by the operator RPC server"
+                );
+            }
+            (None, _) => {}
+        }
+        Ok(rpc_methods)
     }
 
     /// Runs the rollup.
@@ -225,6 +331,13 @@ where
         let skip_submission_until_l1 = std::env::var("SKIP_PROOF_SUBMISSION_UNTIL_L1")
             .map_or(0u64, |v| v.parse().unwrap_or(0));
 
+        if let Some(config_reloader) = &self.config_reloader {
+            tokio::spawn(apply_fee_ceilings(
+                self.da_service.clone(),
+                config_reloader.subscribe(),
+            ));
+        }
+
         // Prover node should sync when a new sequencer commitment arrives
         // Check da block get and sync up to the latest block in the latest commitment
         let last_scanned_l1_height = self
@@ -237,6 +350,20 @@ where
             None => get_initial_slot_height::<Da::Spec>(&self.sequencer_client).await,
         };
 
+        // Jobs of the scanned L1 blocks have their proofs sent to DA already.
+        // The rest are resumed once their L1 blocks are processed again.
+        let mut resumed_jobs = 0;
+        for (l1_height, _) in self.ledger_db.get_proving_jobs()? {
+            if last_scanned_l1_height.is_some_and(|height| l1_height.0 <= height.0) {
+                self.ledger_db.delete_proving_job(l1_height.0)?;
+            } else {
+                resumed_jobs += 1;
+            }
+        }
+        if resumed_jobs > 0 {
+            info!("Resuming {} proving jobs", resumed_jobs);
+        }
+
         let prover_config = self.prover_config.clone().unwrap();
 
         let pg_client = match prover_config.clone().db_config {
@@ -286,6 +413,7 @@ where
                 _ = &mut l2_handle => {panic!("l2 sync handle exited unexpectedly");},
                 Some(l1_block) = l1_rx.recv() => {
                     pending_l1.push_back(l1_block);
+                    self.progress.set_queue_depth(pending_l1.len());
                  },
                 _ = interval.tick() => {
                     if let Err(e) = self.process_l1_block(
@@ -308,6 +436,7 @@ where
         }
     }
 
+    #[instrument(level = "info", skip_all, fields(l2_height = l2_height))]
     async fn process_l2_block(
         &mut self,
         l2_height: u64,
@@ -331,6 +460,29 @@ where
             .storage_manager
             .create_storage_on_l2_height(l2_height)?;
 
+        // The sequencer can be rotated on chain, the previous L2 blocks decide who signs this one
+        let active_sequencer = SequencerRegistry::<C>::default()
+            .active_sequencer(&mut WorkingSet::new(pre_state.clone()));
+        let sequencer_pub_key = active_sequencer
+            .as_ref()
+            .map_or(self.sequencer_pub_key.as_slice(), |sequencer| {
+                sequencer.public_key.as_slice()
+            });
+        if soft_batch.pub_key != sequencer_pub_key {
+            bail!(
+                "L2 block #{} is not signed by the active sequencer 0x{}",
+                l2_height,
+                hex::encode(sequencer_pub_key)
+            );
+        }
+        if let Some(sequencer) = self.registered_sequencers.update(active_sequencer) {
+            info!(
+                "Sequencer is rotated to 0x{} with DA public key 0x{}",
+                hex::encode(&sequencer.public_key),
+                hex::encode(&sequencer.da_pub_key)
+            );
+        }
+
         let slot_result = self.stf.apply_soft_batch(
             self.sequencer_pub_key.as_slice(),
             // TODO(https://github.com/Sovereign-Labs/sovereign-sdk/issues/1247): incorrect pre-state root in case of re-org
@@ -338,6 +490,7 @@ where
             pre_state,
             Default::default(),
             current_l1_block.header(),
+            &extract_slot_inbox(&self.da_service, &current_l1_block),
             &current_l1_block.validity_condition(),
             &mut soft_batch.clone().into(),
         );
@@ -407,143 +560,148 @@ where
         pg_client: &Option<Result<PostgresConnector, DbPoolError>>,
         prover_config: &ProverConfig,
     ) -> Result<(), anyhow::Error> {
-        while !pending_l1_blocks.is_empty() {
-            let l1_block = pending_l1_blocks
-                .front()
-                .expect("Pending l1 blocks cannot be empty");
-            // work on the first unprocessed l1 block
-            let l1_height = l1_block.header().height();
-
-            // Set the l1 height of the l1 hash
-            self.ledger_db
-                .set_l1_height_of_l1_hash(
-                    l1_block.header().hash().into(),
-                    l1_block.header().height(),
-                )
-                .unwrap();
-
-            let mut da_data = self.da_service.extract_relevant_blobs(l1_block);
-            // if we don't do this, the zk circuit can't read the sequencer commitments
-            da_data.iter_mut().for_each(|blob| {
-                blob.full_data();
-            });
-            let sequencer_commitments: Vec<SequencerCommitment> =
-                self.extract_sequencer_commitments(l1_block.header().hash().into(), &mut da_data);
-
-            if sequencer_commitments.is_empty() {
-                info!("No sequencer commitment found at height {}", l1_height,);
-                self.ledger_db
-                    .set_prover_last_scanned_l1_height(SlotNumber(l1_height))
-                    .unwrap_or_else(|_| {
-                        panic!(
-                            "Failed to put prover last scanned l1 height in the ledger db {}",
-                            l1_height
-                        )
-                    });
-
-                pending_l1_blocks.pop_front();
-                continue;
+        // The proof of an L1 block only depends on the witnesses of its own L2 range,
+        // so the L1 blocks whose L2 ranges are synced are proven in parallel.
+        let max_proving_jobs = prover_config.max_parallel_proving_jobs.max(1);
+        let mut jobs: Vec<ProvingJob<Da::Spec>> = vec![];
+        for l1_block in pending_l1_blocks.iter() {
+            if jobs.iter().filter(|job| job.prove).count() == max_proving_jobs {
+                break;
             }
-
-            info!(
-                "Processing {} sequencer commitments at height {}",
-                sequencer_commitments.len(),
-                l1_block.header().height(),
-            );
-
-            let first_l2_height_of_l1 = sequencer_commitments[0].l2_start_block_number;
-            let last_l2_height_of_l1 =
-                sequencer_commitments[sequencer_commitments.len() - 1].l2_end_block_number;
-
+            let l1_height = l1_block.header().height();
+            let challenges = extract_proof_challenges(&self.da_service, l1_block);
+            // Commitments of the earlier jobs are not saved yet, but are not new either
+            let earlier_commitments: Vec<SequencerCommitment> = jobs
+                .iter()
+                .filter(|job| !job.rescanned)
+                .flat_map(|job| job.sequencer_commitments.iter().cloned())
+                .collect();
             // If the L2 range does not exist, we break off the local loop getting back to
             // the outer loop / select to make room for other tasks to run.
             // We retry the L1 block there as well.
-            if !self.check_l2_range_exists(first_l2_height_of_l1, last_l2_height_of_l1) {
+            let Some(job) = self
+                .prepare_proving_job(
+                    l1_block,
+                    skip_submission_until_l1,
+                    prover_config,
+                    challenges.contains(&l1_height),
+                    &earlier_commitments,
+                )
+                .await?
+            else {
                 break;
-            }
+            };
 
-            let (
-                state_transition_witnesses,
-                soft_confirmations,
-                da_block_headers_of_soft_confirmations,
-            ) = self
-                .get_state_transition_data_from_commitments(
-                    &sequencer_commitments,
+            // Challenged L1 blocks which were scanned without being proven are proven now,
+            // within the limits on challenges
+            for challenged_l1_height in prover_config
+                .proof_challenges
+                .answered_challenges(l1_height, &challenges)
+            {
+                if jobs
+                    .iter()
+                    .any(|job| job.prove && job.l1_height == challenged_l1_height)
+                    || self
+                        .ledger_db
+                        .get_proof_data(challenged_l1_height)?
+                        .is_some()
+                {
+                    continue;
+                }
+                info!(
+                    "L1 block {} is challenged in L1 block {}",
+                    challenged_l1_height, l1_height
+                );
+                let challenged_l1_block = get_da_block_at_height(
                     &self.da_service,
+                    challenged_l1_height,
+                    self.l1_block_cache.clone(),
                 )
                 .await?;
+                if let Some(mut challenged_job) = self
+                    .prepare_proving_job(
+                        &challenged_l1_block,
+                        skip_submission_until_l1,
+                        prover_config,
+                        true,
+                        &[],
+                    )
+                    .await?
+                {
+                    challenged_job.rescanned = true;
+                    jobs.push(challenged_job);
+                }
+            }
+            jobs.push(job);
+        }
+        // Challenged L1 blocks are proven before later L1 blocks, so the proofs are sent
+        // in L1 block order. The scanned L1 blocks stay in the order they are pending in.
+        jobs.sort_by_key(|job| job.l1_height);
 
-            let da_block_header_of_commitments = l1_block.header().clone();
-
-            let hash = da_block_header_of_commitments.hash();
-            let initial_state_root = self
-                .ledger_db
-                .get_l2_state_root::<Stf::StateRoot>(first_l2_height_of_l1 - 1)?
-                .expect("There should be a state root");
-            let initial_batch_hash = self
-                .ledger_db
-                .get_soft_batch_by_number(&BatchNumber(first_l2_height_of_l1))?
-                .ok_or(anyhow!(
-                    "Could not find soft batch at height {}",
-                    first_l2_height_of_l1
-                ))?
-                .prev_hash;
-
-            let final_state_root = self
-                .ledger_db
-                .get_l2_state_root::<Stf::StateRoot>(last_l2_height_of_l1)?
-                .expect("There should be a state root");
-
-            let (inclusion_proof, completeness_proof) = self
-                .da_service
-                .get_extraction_proof(l1_block, &da_data)
-                .await;
-
-            let transition_data: StateTransitionData<Stf::StateRoot, Stf::Witness, Da::Spec> =
-                StateTransitionData {
-                    initial_state_root,
-                    final_state_root,
-                    initial_batch_hash,
-                    da_data,
-                    da_block_header_of_commitments,
-                    inclusion_proof,
-                    completeness_proof,
-                    soft_confirmations,
-                    state_transition_witnesses,
-                    da_block_headers_of_soft_confirmations,
-                    sequencer_commitments_range: (
-                        0,
-                        (sequencer_commitments.len() - 1)
-                            .try_into()
-                            .expect("cant be more than 4 billion commitments in a da block; qed"),
-                    ), // for now process all commitments
-                    sequencer_public_key: self.sequencer_pub_key.clone(),
-                    sequencer_da_public_key: self.sequencer_da_pub_key.clone(),
-                };
+        for job in jobs.iter_mut().filter(|job| job.prove && !job.started) {
+            self.start_proving_job(job).await?;
+        }
 
-            let should_prove: bool = {
-                let mut rng = rand::thread_rng();
-                // if proof_sampling_number is 0, then we always prove and submit
-                // otherwise we submit and prove with a probability of 1/proof_sampling_number
-                if prover_config.proof_sampling_number == 0 {
-                    true
-                } else {
-                    rng.gen_range(0..prover_config.proof_sampling_number) == 0
-                }
-            };
+        // The proofs of each run of consecutive state transitions are sent as one proof,
+        // when the run reaches its first job
+        let aggregation_runs = if prover_config.aggregate_proofs {
+            aggregation_runs(&jobs)
+        } else {
+            vec![]
+        };
+        let mut aggregated_l1_blocks = HashMap::new();
+        for run in &aggregation_runs {
+            let mut l1_blocks = vec![];
+            for &i in run {
+                let job = &mut jobs[i];
+                self.wait_until_proving_started(job).await?;
+                l1_blocks.push((job.l1_height, job.hash.clone(), job.proof.take()));
+            }
+            aggregated_l1_blocks.insert(run[0], l1_blocks);
+        }
 
-            // Skip submission until l1 height
-            if l1_height >= skip_submission_until_l1 && should_prove {
-                self.generate_and_submit_proof(transition_data, pg_client, l1_height, hash)
+        // Proofs are sent to DA in L1 block order
+        for (i, mut job) in jobs.into_iter().enumerate() {
+            if let Some(l1_blocks) = aggregated_l1_blocks.remove(&i) {
+                self.wait_for_proofs_and_submit_aggregated(pg_client, l1_blocks)
                     .await?;
-            } else {
-                info!("Skipping proving for l1 height {}", l1_height);
+            } else if job.prove && !aggregation_runs.iter().any(|run| run.contains(&i)) {
+                self.wait_until_proving_started(&mut job).await?;
+                self.wait_for_proof_and_submit(
+                    pg_client,
+                    job.l1_height,
+                    job.hash.clone(),
+                    job.proof.take(),
+                )
+                .await?;
+                for segment in std::mem::take(&mut job.later_segments) {
+                    self.prove_later_segment(pg_client, job.l1_height, job.hash.clone(), segment)
+                        .await?;
+                }
+            } else if !job.prove && !job.sequencer_commitments.is_empty() {
+                info!("Skipping proving for l1 height {}", job.l1_height);
+            }
+            if job.rescanned {
+                continue;
+            }
+            self.save_commitments(job.sequencer_commitments, job.l1_height);
+
+            if prover_config.light_client_proofs {
+                let l1_block = pending_l1_blocks
+                    .front()
+                    .expect("Pending L1 block of the job should be present");
+                // The light client proof is not required by anyone waiting on the batch proofs
+                if let Err(e) = self.extend_light_client(l1_block).await {
+                    warn!(
+                        "Failed to extend light client proof to l1 height {}: {}",
+                        job.l1_height, e
+                    );
+                }
             }
-            self.save_commitments(sequencer_commitments, l1_height);
 
             if let Err(e) = self
                 .ledger_db
-                .set_prover_last_scanned_l1_height(SlotNumber(l1_height))
+                .set_prover_last_scanned_l1_height(SlotNumber(job.l1_height))
             {
                 panic!(
                     "Failed to put prover last scanned l1 height in the ledger db: {}",
@@ -552,13 +710,443 @@ where
             }
 
             pending_l1_blocks.pop_front();
+            self.progress.set_queue_depth(pending_l1_blocks.len());
         }
         Ok(())
     }
 
+    /// Extracts the sequencer commitments of the L1 block and submits the witness
+    /// of their L2 range to the prover service.
+    /// Commitments posted before, on an earlier L1 block or in `earlier_commitments`, are skipped,
+    /// and the new commitments after them are left to [`ProvingJob::later_segments`].
+    /// Returns `None` if the L2 range is not synced yet.
+    #[instrument(level = "info", skip_all, fields(da_height = l1_block.header().height()))]
+    async fn prepare_proving_job(
+        &self,
+        l1_block: &<Da as DaService>::FilteredBlock,
+        skip_submission_until_l1: u64,
+        prover_config: &ProverConfig,
+        challenged: bool,
+        earlier_commitments: &[SequencerCommitment],
+    ) -> Result<Option<ProvingJob<Da::Spec>>, anyhow::Error> {
+        let l1_height = l1_block.header().height();
+        let hash = l1_block.header().hash();
+
+        // Set the l1 height of the l1 hash
+        self.ledger_db
+            .set_l1_height_of_l1_hash(l1_block.header().hash().into(), l1_block.header().height())
+            .unwrap();
+
+        let mut da_data = self.da_service.extract_relevant_blobs(l1_block);
+        // if we don't do this, the zk circuit can't read the sequencer commitments
+        da_data.iter_mut().for_each(|blob| {
+            blob.full_data();
+        });
+        let Some(sequencer_da_pub_key) = self.commitments_da_pub_key(l1_height, &mut da_data)?
+        else {
+            return Ok(None);
+        };
+        let mut segments = self.skip_duplicate_commitments(
+            l1_height,
+            self.extract_sequencer_commitments(
+                l1_block.header().hash().into(),
+                &sequencer_da_pub_key,
+                &mut da_data,
+            ),
+            earlier_commitments,
+        )?;
+        let sequencer_commitments: Vec<SequencerCommitment> = segments
+            .iter()
+            .flat_map(|(commitments, _)| commitments.iter().cloned())
+            .collect();
+
+        if sequencer_commitments.is_empty() {
+            info!("No sequencer commitment found at height {}", l1_height,);
+            return Ok(Some(ProvingJob {
+                l1_height,
+                hash,
+                sequencer_commitments,
+                prove: false,
+                started: false,
+                proof: None,
+                rescanned: false,
+                later_segments: vec![],
+            }));
+        }
+
+        info!(
+            "Processing {} sequencer commitments at height {}",
+            sequencer_commitments.len(),
+            l1_block.header().height(),
+        );
+
+        let first_l2_height_of_l1 = sequencer_commitments[0].l2_start_block_number;
+        let last_l2_height_of_l1 =
+            sequencer_commitments[sequencer_commitments.len() - 1].l2_end_block_number;
+
+        if !self.check_l2_range_exists(first_l2_height_of_l1, last_l2_height_of_l1) {
+            return Ok(None);
+        }
+
+        // A job stored before a restart is always finished, regardless of the strategy
+        let stored_job = self.ledger_db.get_proving_job(l1_height)?;
+        let should_prove = stored_job.is_some()
+            || challenged
+            || prover_config
+                .proving_strategy()
+                .proves_unchallenged(&hash.clone().into());
+
+        // Skip submission until l1 height
+        if l1_height < skip_submission_until_l1 || !should_prove {
+            return Ok(Some(ProvingJob {
+                l1_height,
+                hash,
+                sequencer_commitments,
+                prove: false,
+                started: false,
+                proof: None,
+                rescanned: false,
+                later_segments: vec![],
+            }));
+        }
+
+        // The proofs of the first segments are sent already if the prover restarted in between
+        if let Some(sent_proof) = self.ledger_db.get_proof_data(l1_height)? {
+            let last_sent = sent_proof.state_transition.sequencer_commitments_range.1;
+            segments.retain(|(_, range)| range.0 > last_sent);
+        }
+        if segments.is_empty() {
+            info!("Proofs of l1 height {} are sent already", l1_height);
+            return Ok(Some(ProvingJob {
+                l1_height,
+                hash,
+                sequencer_commitments,
+                prove: false,
+                started: false,
+                proof: None,
+                rescanned: false,
+                later_segments: vec![],
+            }));
+        }
+
+        let (first_segment, first_segment_range) = segments.remove(0);
+        let (started, proof) = self
+            .submit_commitments(
+                l1_block,
+                da_data,
+                sequencer_da_pub_key,
+                &first_segment,
+                first_segment_range,
+            )
+            .await?;
+
+        Ok(Some(ProvingJob {
+            l1_height,
+            hash,
+            sequencer_commitments,
+            prove: true,
+            started,
+            proof,
+            rescanned: false,
+            later_segments: segments,
+        }))
+    }
+
+    /// Proves new commitments of the L1 block which follow a duplicate commitment,
+    /// once the proof of the commitments before them is sent.
+    #[instrument(level = "info", skip_all, fields(da_height = l1_height))]
+    async fn prove_later_segment(
+        &self,
+        pg_client: &Option<Result<PostgresConnector, DbPoolError>>,
+        l1_height: u64,
+        hash: <<Da as DaService>::Spec as DaSpec>::SlotHash,
+        (sequencer_commitments, sequencer_commitments_range): CommitmentSegment,
+    ) -> Result<(), anyhow::Error> {
+        info!(
+            "Proving sequencer commitments {}-{} of l1 height {} after a duplicate commitment",
+            sequencer_commitments_range.0, sequencer_commitments_range.1, l1_height
+        );
+        let l1_block =
+            get_da_block_at_height(&self.da_service, l1_height, self.l1_block_cache.clone())
+                .await?;
+        let mut da_data = self.da_service.extract_relevant_blobs(&l1_block);
+        da_data.iter_mut().for_each(|blob| {
+            blob.full_data();
+        });
+        let sequencer_da_pub_key = self
+            .commitments_da_pub_key(l1_height, &mut da_data)?
+            .ok_or_else(|| anyhow!("L2 blocks of l1 height {} are not synced", l1_height))?;
+        let (started, proof) = self
+            .submit_commitments(
+                &l1_block,
+                da_data,
+                sequencer_da_pub_key,
+                &sequencer_commitments,
+                sequencer_commitments_range,
+            )
+            .await?;
+
+        let mut job = ProvingJob {
+            l1_height,
+            hash,
+            sequencer_commitments,
+            prove: true,
+            started,
+            proof,
+            rescanned: false,
+            later_segments: vec![],
+        };
+        self.wait_until_proving_started(&mut job).await?;
+        self.wait_for_proof_and_submit(pg_client, job.l1_height, job.hash, job.proof)
+            .await
+    }
+
+    /// Submits the witness of the L2 range of the commitments of the L1 block to the prover
+    /// service. Returns whether proving started, and the proof if it was proven before a restart.
+    async fn submit_commitments(
+        &self,
+        l1_block: &<Da as DaService>::FilteredBlock,
+        da_data: Vec<<<Da as DaService>::Spec as DaSpec>::BlobTransaction>,
+        sequencer_da_pub_key: Vec<u8>,
+        sequencer_commitments: &[SequencerCommitment],
+        sequencer_commitments_range: (u32, u32),
+    ) -> Result<(bool, Option<Proof>), anyhow::Error> {
+        let l1_height = l1_block.header().height();
+        let hash = l1_block.header().hash();
+        let first_l2_height_of_l1 = sequencer_commitments[0].l2_start_block_number;
+        let last_l2_height_of_l1 =
+            sequencer_commitments[sequencer_commitments.len() - 1].l2_end_block_number;
+
+        // A stored job of another segment of the L1 block is replaced
+        let stored_job = self.ledger_db.get_proving_job(l1_height)?.filter(|job| {
+            job.l2_range
+                == (
+                    BatchNumber(first_l2_height_of_l1),
+                    BatchNumber(last_l2_height_of_l1),
+                )
+        });
+        match stored_job.map(|job| job.status) {
+            Some(ProvingJobStatus::Proven(proof)) => {
+                info!("Resuming proven job of l1 height {}", l1_height);
+                self.progress.job_submitted(
+                    l1_height,
+                    hash.clone().into(),
+                    (first_l2_height_of_l1, last_l2_height_of_l1),
+                    ProvingJobState::Proven,
+                    None,
+                );
+                return Ok((true, Some(proof)));
+            }
+            Some(_) => info!("Restarting proving job of l1 height {}", l1_height),
+            None => {}
+        }
+
+        let (
+            state_transition_witnesses,
+            soft_confirmations,
+            da_block_headers_of_soft_confirmations,
+            da_blobs_of_soft_confirmations,
+        ) = self
+            .get_state_transition_data_from_commitments(sequencer_commitments, &self.da_service)
+            .await?;
+
+        let da_block_header_of_commitments = l1_block.header().clone();
+
+        let initial_state_root = self
+            .ledger_db
+            .get_l2_state_root::<Stf::StateRoot>(first_l2_height_of_l1 - 1)?
+            .expect("There should be a state root");
+        let initial_batch_hash = self
+            .ledger_db
+            .get_soft_batch_by_number(&BatchNumber(first_l2_height_of_l1))?
+            .ok_or(anyhow!(
+                "Could not find soft batch at height {}",
+                first_l2_height_of_l1
+            ))?
+            .prev_hash;
+
+        let final_state_root = self
+            .ledger_db
+            .get_l2_state_root::<Stf::StateRoot>(last_l2_height_of_l1)?
+            .expect("There should be a state root");
+
+        let (inclusion_proof, completeness_proof) = self
+            .da_service
+            .get_extraction_proof(l1_block, &da_data)
+            .await;
+
+        let state_transition_witnesses = CompactWitnesses::compact(state_transition_witnesses);
+        let estimated_cycles = estimate_cycles(
+            last_l2_height_of_l1 - first_l2_height_of_l1 + 1,
+            state_transition_witnesses.distinct_hints(),
+        );
+        debug!(
+            "Compacted the witnesses of l1 height {} to {} of {} hints",
+            l1_height,
+            state_transition_witnesses.distinct_hints(),
+            state_transition_witnesses.total_hints()
+        );
+
+        let transition_data: StateTransitionData<Stf::StateRoot, Stf::Witness, Da::Spec> =
+            StateTransitionData {
+                initial_state_root,
+                final_state_root,
+                initial_batch_hash,
+                da_data,
+                da_block_header_of_commitments,
+                inclusion_proof,
+                completeness_proof,
+                soft_confirmations,
+                state_transition_witnesses,
+                da_block_headers_of_soft_confirmations,
+                da_blobs_of_soft_confirmations,
+                sequencer_commitments_range,
+                sequencer_public_key: self.sequencer_pub_key.clone(),
+                sequencer_da_public_key: sequencer_da_pub_key,
+            };
+
+        let prover_service = self
+            .prover_service
+            .as_ref()
+            .expect("Prover service should be present");
+
+        // The witness is already in the prover service if a previous attempt to send
+        // the proofs failed, in which case its proving job is already started.
+        let started = matches!(
+            prover_service.submit_witness(transition_data).await,
+            WitnessSubmissionStatus::WitnessExist
+        );
+        self.progress.job_submitted(
+            l1_height,
+            hash.clone().into(),
+            (first_l2_height_of_l1, last_l2_height_of_l1),
+            if started {
+                ProvingJobState::InProgress
+            } else {
+                ProvingJobState::Pending
+            },
+            Some(estimated_cycles),
+        );
+        if !started {
+            self.ledger_db.put_proving_job(
+                l1_height,
+                &StoredProvingJob {
+                    l1_hash: hash.clone().into(),
+                    l2_range: (
+                        BatchNumber(first_l2_height_of_l1),
+                        BatchNumber(last_l2_height_of_l1),
+                    ),
+                    status: ProvingJobStatus::Pending,
+                },
+            )?;
+        }
+
+        Ok((started, None))
+    }
+
+    /// DA public key the sequencer with the soft confirmation public key signs commitments
+    /// with at the L1 height. `None` if it is neither the sequencer of the rollup config,
+    /// nor the active or previous sequencer registered on chain.
+    fn sequencer_da_pub_key_of(&self, pub_key: &[u8], l1_height: u64) -> Option<&[u8]> {
+        self.registered_sequencers.da_pub_key_at(
+            pub_key,
+            &self.sequencer_pub_key,
+            self.public_keys.sequencer_da_pub_key_at(l1_height),
+        )
+    }
+
+    /// Whether commitments of the L1 height may have been signed with the DA public key
+    fn is_sequencer_da_pub_key_at(&self, pub_key: &[u8], l1_height: u64) -> bool {
+        is_sequencer_da_pub_key_at(
+            self.public_keys.sequencer_da_pub_key_at(l1_height),
+            Some(&self.registered_sequencers),
+            pub_key,
+        )
+    }
+
+    /// DA public key of the commitments proven on the L1 block, the key of the first new
+    /// commitment signed by the sequencer of its last L2 block.
+    /// A proof covers the commitments of a single key, so when the sequencer is rotated,
+    /// commitments of the other sequencer on the same L1 block are not proven with them.
+    /// Returns `None` if the L2 blocks of a commitment are not synced yet.
+    fn commitments_da_pub_key(
+        &self,
+        l1_height: u64,
+        da_data: &mut [<<Da as DaService>::Spec as DaSpec>::BlobTransaction],
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        for tx in da_data.iter_mut() {
+            if !self.is_sequencer_da_pub_key_at(tx.sender().as_ref(), l1_height) {
+                continue;
+            }
+            let Ok(
+                DaData::SequencerCommitment(commitment)
+                | DaData::CommitmentWithStateDiff(CommitmentWithStateDiff { commitment, .. }),
+            ) = DaData::decode_activated(tx.full_data())
+            else {
+                continue;
+            };
+            if self
+                .ledger_db
+                .get_l1_height_of_sequencer_commitment(&commitment)?
+                .is_some_and(|first_l1_height| first_l1_height.0 != l1_height)
+            {
+                continue;
+            }
+            let Some(last_soft_batch) = self
+                .ledger_db
+                .get_soft_batch_by_number(&BatchNumber(commitment.l2_end_block_number))?
+            else {
+                return Ok(None);
+            };
+            if self.sequencer_da_pub_key_of(&last_soft_batch.pub_key, l1_height)
+                == Some(tx.sender().as_ref())
+            {
+                return Ok(Some(tx.sender().as_ref().to_vec()));
+            }
+            warn!(
+                "Sequencer commitment of L2 blocks {}-{} at L1 height {} is not signed by the sequencer of the L2 blocks",
+                commitment.l2_start_block_number, commitment.l2_end_block_number, l1_height
+            );
+        }
+        Ok(Some(
+            self.public_keys.sequencer_da_pub_key_at(l1_height).to_vec(),
+        ))
+    }
+
+    /// Drops the commitments of the L1 block which were posted before.
+    /// Returns the segments of consecutive new commitments, see [`new_commitment_segments`].
+    fn skip_duplicate_commitments(
+        &self,
+        l1_height: u64,
+        sequencer_commitments: Vec<SequencerCommitment>,
+        earlier_commitments: &[SequencerCommitment],
+    ) -> anyhow::Result<Vec<CommitmentSegment>> {
+        let mut is_duplicate = Vec::with_capacity(sequencer_commitments.len());
+        for (i, commitment) in sequencer_commitments.iter().enumerate() {
+            let first_l1_height = self
+                .ledger_db
+                .get_l1_height_of_sequencer_commitment(commitment)?;
+            let duplicate = first_l1_height.is_some_and(|height| height.0 != l1_height)
+                || earlier_commitments.contains(commitment)
+                || sequencer_commitments[..i].contains(commitment);
+            if duplicate {
+                warn!(
+                    "Skipping duplicate sequencer commitment of L2 blocks {}-{} at L1 height {}",
+                    commitment.l2_start_block_number, commitment.l2_end_block_number, l1_height
+                );
+            }
+            is_duplicate.push(duplicate);
+        }
+        Ok(new_commitment_segments(
+            sequencer_commitments,
+            &is_duplicate,
+        ))
+    }
+
     fn extract_sequencer_commitments(
         &self,
         l1_block_hash: [u8; 32],
+        sequencer_da_pub_key: &[u8],
         da_data: &mut [<<Da as DaService>::Spec as DaSpec>::BlobTransaction],
     ) -> Vec<SequencerCommitment> {
         let mut sequencer_commitments = vec![];
@@ -567,12 +1155,19 @@ where
             blob.full_data();
         });
         da_data.iter_mut().for_each(|tx| {
-            let data = DaData::try_from_slice(tx.full_data());
+            let data = DaData::decode_activated(tx.full_data());
             // Check for commitment
-            if tx.sender().as_ref() == self.sequencer_da_pub_key.as_slice() {
-                if let Ok(DaData::SequencerCommitment(seq_com)) = data {
+            if tx.sender().as_ref() == sequencer_da_pub_key {
+                if let Ok(
+                    DaData::SequencerCommitment(seq_com)
+                    | DaData::CommitmentWithStateDiff(CommitmentWithStateDiff {
+                        commitment: seq_com,
+                        ..
+                    }),
+                ) = data
+                {
                     sequencer_commitments.push(seq_com);
-                } else {
+                } else if !matches!(data, Ok(DaData::SequencerLease(_))) {
                     tracing::warn!(
                         "Found broken DA data in block 0x{}: {:?}",
                         hex::encode(l1_block_hash),
@@ -596,56 +1191,55 @@ where
         let mut da_block_headers_of_soft_confirmations: VecDeque<
             Vec<<<Da as DaService>::Spec as DaSpec>::BlockHeader>,
         > = VecDeque::new();
+        let mut da_blobs_of_soft_confirmations: VecDeque<Vec<SlotBlobs<Da::Spec>>> =
+            VecDeque::new();
         for sequencer_commitment in sequencer_commitments.to_owned().iter() {
             // get the l2 height ranges of each seq_commitments
             let mut witnesses = vec![];
             let start_l2 = sequencer_commitment.l2_start_block_number;
             let end_l2 = sequencer_commitment.l2_end_block_number;
-            let soft_batches_in_commitment = match self
+            let mut commitment_soft_confirmations = vec![];
+            // The DB iterator is not held across awaits, DA blocks are fetched afterwards
+            let mut da_slot_heights: Vec<u64> = vec![];
+            for soft_batch in self
                 .ledger_db
-                .get_soft_batch_range(&(BatchNumber(start_l2)..BatchNumber(end_l2 + 1)))
+                .soft_batch_iter(BatchNumber(start_l2)..BatchNumber(end_l2 + 1))
+                .map_err(|e| anyhow!("Failed to get soft batches from the ledger db: {}", e))?
             {
-                Ok(soft_batches) => soft_batches,
-                Err(e) => {
-                    return Err(anyhow!(
-                        "Failed to get soft batches from the ledger db: {}",
-                        e
-                    ));
+                let soft_batch = soft_batch
+                    .map_err(|e| anyhow!("Failed to get soft batches from the ledger db: {}", e))?;
+                if da_slot_heights.last() != Some(&soft_batch.da_slot_height) {
+                    da_slot_heights.push(soft_batch.da_slot_height);
                 }
-            };
-            let mut commitment_soft_confirmations = vec![];
+                commitment_soft_confirmations.push(SignedSoftConfirmationBatch::from(soft_batch));
+            }
             let mut da_block_headers_to_push: Vec<
                 <<Da as DaService>::Spec as DaSpec>::BlockHeader,
             > = vec![];
-            for soft_batch in soft_batches_in_commitment {
-                if da_block_headers_to_push.is_empty()
-                    || da_block_headers_to_push.last().unwrap().height()
-                        != soft_batch.da_slot_height
+            let mut da_blobs_to_push = vec![];
+            for da_slot_height in da_slot_heights {
+                let filtered_block = match get_da_block_at_height(
+                    da_service,
+                    da_slot_height,
+                    self.l1_block_cache.clone(),
+                )
+                .await
                 {
-                    let filtered_block = match get_da_block_at_height(
-                        da_service,
-                        soft_batch.da_slot_height,
-                        self.l1_block_cache.clone(),
-                    )
-                    .await
-                    {
-                        Ok(block) => block,
-                        Err(_) => {
-                            return Err(anyhow!(
-                                "Error while fetching DA block at height: {}",
-                                soft_batch.da_slot_height
-                            ));
-                        }
-                    };
-                    da_block_headers_to_push.push(filtered_block.header().clone());
-                }
-                let signed_soft_confirmation: SignedSoftConfirmationBatch =
-                    soft_batch.clone().into();
-                commitment_soft_confirmations.push(signed_soft_confirmation.clone());
+                    Ok(block) => block,
+                    Err(_) => {
+                        return Err(anyhow!(
+                            "Error while fetching DA block at height: {}",
+                            da_slot_height
+                        ));
+                    }
+                };
+                da_block_headers_to_push.push(filtered_block.header().clone());
+                da_blobs_to_push.push(extract_slot_blobs(da_service, &filtered_block).await);
             }
             soft_confirmations.push_back(commitment_soft_confirmations);
 
             da_block_headers_of_soft_confirmations.push_back(da_block_headers_to_push);
+            da_blobs_of_soft_confirmations.push_back(da_blobs_to_push);
             for l2_height in sequencer_commitment.l2_start_block_number
                 ..=sequencer_commitment.l2_end_block_number
             {
@@ -664,49 +1258,431 @@ where
             state_transition_witnesses,
             soft_confirmations,
             da_block_headers_of_soft_confirmations,
+            da_blobs_of_soft_confirmations,
         ))
     }
 
     fn check_l2_range_exists(&self, first_l2_height_of_l1: u64, last_l2_height_of_l1: u64) -> bool {
-        let ledger_db = &self.ledger_db.clone();
-        if let Ok(range) = ledger_db.clone().get_soft_batch_range(
-            &(BatchNumber(first_l2_height_of_l1)..BatchNumber(last_l2_height_of_l1 + 1)),
-        ) {
-            if (range.len() as u64) >= (last_l2_height_of_l1 - first_l2_height_of_l1 + 1) {
-                return true;
-            }
+        let Ok(soft_batches) = self.ledger_db.soft_batch_iter(
+            BatchNumber(first_l2_height_of_l1)..BatchNumber(last_l2_height_of_l1 + 1),
+        ) else {
+            return false;
+        };
+        let synced = soft_batches
+            .take_while(|soft_batch| soft_batch.is_ok())
+            .count() as u64;
+        synced >= last_l2_height_of_l1 - first_l2_height_of_l1 + 1
+    }
+
+    /// Starts proving the L1 block whose witness is submitted.
+    /// Returns `false` if the prover service is busy.
+    async fn start_proving(
+        &self,
+        hash: <<Da as DaService>::Spec as DaSpec>::SlotHash,
+    ) -> Result<bool, anyhow::Error> {
+        let prover_service = self
+            .prover_service
+            .as_ref()
+            .expect("Prover service should be present");
+
+        match prover_service.prove(hash).await? {
+            ProofProcessingStatus::ProvingInProgress => Ok(true),
+            ProofProcessingStatus::Busy => Ok(false),
         }
-        false
     }
 
-    async fn generate_and_submit_proof(
+    /// Starts proving the job, and marks the stored job as in progress if it started.
+    #[instrument(level = "info", skip_all, fields(da_height = job.l1_height))]
+    async fn start_proving_job(&self, job: &mut ProvingJob<Da::Spec>) -> Result<(), anyhow::Error> {
+        job.started = self.start_proving(job.hash.clone()).await?;
+        if job.started {
+            self.progress
+                .set_job_state(job.l1_height, ProvingJobState::InProgress);
+            self.set_proving_job_status(job.l1_height, ProvingJobStatus::InProgress)?;
+        }
+        Ok(())
+    }
+
+    async fn wait_until_proving_started(
+        &self,
+        job: &mut ProvingJob<Da::Spec>,
+    ) -> Result<(), anyhow::Error> {
+        while !job.started {
+            // Wait for the jobs of the next L1 blocks to free the prover
+            sleep(Duration::from_secs(5)).await;
+            self.start_proving_job(job).await?;
+        }
+        Ok(())
+    }
+
+    /// Updates the status of the stored proving job of the L1 height
+    fn set_proving_job_status(
         &self,
-        transition_data: StateTransitionData<Stf::StateRoot, Stf::Witness, Da::Spec>,
-        pg_client: &Option<Result<PostgresConnector, DbPoolError>>,
         l1_height: u64,
-        hash: <<Da as DaService>::Spec as DaSpec>::SlotHash,
+        status: ProvingJobStatus,
     ) -> Result<(), anyhow::Error> {
+        let mut job = self
+            .ledger_db
+            .get_proving_job(l1_height)?
+            .ok_or_else(|| anyhow!("No proving job found for l1 height {}", l1_height))?;
+        job.status = status;
+        self.ledger_db.put_proving_job(l1_height, &job)
+    }
+
+    /// Returns the proof of the job, waiting for the prover service if it is not proven yet.
+    /// The proof is stored with the job, so it is not proven again after a restart.
+    async fn wait_for_proof(
+        &self,
+        l1_height: u64,
+        hash: <<Da as DaService>::Spec as DaSpec>::SlotHash,
+        proof: Option<Proof>,
+    ) -> Result<Proof, anyhow::Error> {
+        if let Some(proof) = proof {
+            return Ok(proof);
+        }
+
         let prover_service = self
             .prover_service
             .as_ref()
             .expect("Prover service should be present");
 
-        prover_service.submit_witness(transition_data).await;
+        let proof = prover_service
+            .wait_for_proving(hash.clone())
+            .await
+            .map_err(|e| anyhow!("Failed to prove: {}", e))?;
+        if let Some(cycle_report) = prover_service.take_cycle_report(hash).await {
+            self.save_cycle_report(l1_height, cycle_report)?;
+        }
+        self.progress
+            .set_job_state(l1_height, ProvingJobState::Proven);
+        self.set_proving_job_status(l1_height, ProvingJobStatus::Proven(proof.clone()))?;
+        Ok(proof)
+    }
+
+    /// Logs the cycles spent on proving the L1 height and stores them with the proven L2 range
+    fn save_cycle_report(&self, l1_height: u64, report: CycleReport) -> Result<(), anyhow::Error> {
+        let job = self
+            .ledger_db
+            .get_proving_job(l1_height)?
+            .ok_or_else(|| anyhow!("No proving job found for l1 height {}", l1_height))?;
+
+        info!(
+            "Guest execution of l1 height {} (l2 range {}-{}) took {} cycles, {} with the zkVM overhead, in {} segments",
+            l1_height,
+            job.l2_range.0 .0,
+            job.l2_range.1 .0,
+            report.user_cycles,
+            report.total_cycles,
+            report.segments
+        );
+        for phase in &report.phases {
+            info!(
+                "  {}: {} cycles in {} calls",
+                phase.name, phase.cycles, phase.calls
+            );
+        }
+
+        self.ledger_db.put_cycle_report(
+            l1_height,
+            &StoredCycleReport {
+                l2_range: job.l2_range,
+                report,
+            },
+        )
+    }
 
-        prover_service.prove(hash.clone()).await?;
+    #[instrument(level = "info", skip_all, fields(da_height = l1_height))]
+    async fn wait_for_proof_and_submit(
+        &self,
+        pg_client: &Option<Result<PostgresConnector, DbPoolError>>,
+        l1_height: u64,
+        hash: <<Da as DaService>::Spec as DaSpec>::SlotHash,
+        proof: Option<Proof>,
+    ) -> Result<(), anyhow::Error> {
+        let proof = self.wait_for_proof(l1_height, hash, proof).await?;
 
-        let (tx_id, proof) = match prover_service
-            .wait_for_proving_and_send_to_da(hash.clone(), &self.da_service)
+        let tx_id = self
+            .send_to_da(DaData::ZKProof(proof.clone()), vec![l1_height])
             .await
+            .map_err(|e| anyhow!("Failed to send proof to DA: {}", e))?;
+
+        self.store_proof(pg_client, l1_height, tx_id, proof).await;
+        Ok(())
+    }
+
+    /// Sends a single proof aggregating the proofs of the L1 blocks to DA.
+    /// The proofs are sent one by one if they can't be aggregated.
+    async fn wait_for_proofs_and_submit_aggregated(
+        &self,
+        pg_client: &Option<Result<PostgresConnector, DbPoolError>>,
+        l1_blocks: Vec<(
+            u64,
+            <<Da as DaService>::Spec as DaSpec>::SlotHash,
+            Option<Proof>,
+        )>,
+    ) -> Result<(), anyhow::Error> {
+        let prover_service = self
+            .prover_service
+            .as_ref()
+            .expect("Prover service should be present");
+
+        let mut proofs = vec![];
+        for (l1_height, hash, proof) in l1_blocks {
+            let proof = self.wait_for_proof(l1_height, hash, proof).await?;
+            proofs.push((l1_height, proof));
+        }
+
+        // Public inputs are not proofs, there is nothing to verify in the aggregation guest
+        let aggregated_proof = if proofs
+            .iter()
+            .all(|(_, proof)| matches!(proof, Proof::Full(_)))
         {
-            Ok((tx_id, proof)) => (tx_id, proof),
-            Err(e) => {
-                return Err(anyhow!("Failed to prove and send to DA: {}", e));
+            let range_proofs = proofs.iter().map(|(_, proof)| proof.clone()).collect();
+            match prover_service.aggregate_proofs(range_proofs).await {
+                Ok(aggregated_proof) => Some(aggregated_proof),
+                Err(e) => {
+                    warn!("Failed to aggregate proofs, sending them one by one: {}", e);
+                    None
+                }
             }
+        } else {
+            None
         };
 
-        let tx_id_u8 = tx_id.into();
+        match aggregated_proof {
+            Some(aggregated_proof) => {
+                info!(
+                    "Sending proof aggregating the proofs of {} L1 blocks",
+                    proofs.len()
+                );
+                let l1_heights = proofs.iter().map(|(l1_height, _)| *l1_height).collect();
+                let tx_id = self
+                    .send_to_da(DaData::AggregatedZKProof(aggregated_proof), l1_heights)
+                    .await?;
+                for (l1_height, proof) in proofs {
+                    self.store_proof(pg_client, l1_height, tx_id, proof).await;
+                }
+            }
+            None => {
+                for (l1_height, proof) in proofs {
+                    let tx_id = self
+                        .send_to_da(DaData::ZKProof(proof.clone()), vec![l1_height])
+                        .await?;
+                    self.store_proof(pg_client, l1_height, tx_id, proof).await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Extends the light client proof to the L1 block, if batch proofs continuing the latest
+    /// proven L2 state root are posted in it. The light client proof verifies the headers of the
+    /// L1 blocks since its previous proof, so L1 blocks without batch proofs are covered later.
+    /// The first light client proof starts from the first batch proof posted on DA.
+    async fn extend_light_client(
+        &self,
+        l1_block: &<Da as DaService>::FilteredBlock,
+    ) -> Result<(), anyhow::Error> {
+        let prover_service = self
+            .prover_service
+            .as_ref()
+            .expect("Prover service should be present");
+        let light_client_code_commitment = prover_service
+            .light_client_code_commitment()
+            .ok_or_else(|| anyhow!("Prover service has no light client guest"))?;
+        let l1_height = l1_block.header().height();
+
+        let previous = self.ledger_db.get_latest_light_client_proof()?;
+        let (previous_output, previous_proof, recent_da_hashes, first_l1_height) = match previous {
+            // Already extended before a restart
+            Some((previous_l1_height, _)) if previous_l1_height >= l1_height => return Ok(()),
+            Some((previous_l1_height, stored)) => (
+                Some(LightClientOutput::<Stf::StateRoot>::try_from_slice(
+                    &stored.output,
+                )?),
+                Some(stored.proof),
+                stored.recent_da_hashes,
+                previous_l1_height + 1,
+            ),
+            None => (None, None, vec![], l1_height),
+        };
 
+        let mut batch_proofs: Vec<(Proof, StateTransition<Da::Spec, Stf::StateRoot>)> = self
+            .da_service
+            .extract_relevant_blobs(l1_block)
+            .into_iter()
+            .filter_map(|mut blob| match DaData::decode(blob.full_data()) {
+                // Public inputs can't be verified by the light client guest
+                Ok(DaData::ZKProof(proof @ Proof::Full(_))) => {
+                    let state_transition = Vm::extract_output(&proof).ok()?;
+                    Some((proof, state_transition))
+                }
+                _ => None,
+            })
+            .collect();
+        if batch_proofs.is_empty() {
+            return Ok(());
+        }
+
+        // The light client guest reads the batch proofs from the blobs of the blocks
+        let mut da_block_headers = vec![];
+        let mut da_blobs = vec![];
+        for height in first_l1_height..l1_height {
+            let block =
+                get_da_block_at_height(&self.da_service, height, self.l1_block_cache.clone())
+                    .await?;
+            da_block_headers.push(block.header().clone());
+            da_blobs.push(extract_slot_blobs(&self.da_service, &block).await);
+        }
+        da_block_headers.push(l1_block.header().clone());
+        da_blobs.push(extract_slot_blobs(&self.da_service, l1_block).await);
+
+        // Only the proofs of sequencer commitments in the latest L1 blocks are accepted
+        let recent_window: Vec<[u8; 32]> = recent_da_hashes
+            .iter()
+            .copied()
+            .chain(da_block_headers.iter().map(|header| header.hash().into()))
+            .collect();
+        let recent_window =
+            &recent_window[recent_window.len().saturating_sub(LIGHT_CLIENT_DA_WINDOW)..];
+        batch_proofs.retain(|(_, state_transition)| {
+            recent_window.contains(&state_transition.da_slot_hash.clone().into())
+        });
+
+        // Apply the state transitions continuing the latest proven state root
+        let mut l2_state_root = match (&previous_output, batch_proofs.first()) {
+            (Some(output), _) => output.l2_state_root.clone(),
+            (None, Some((_, state_transition))) => state_transition.initial_state_root.clone(),
+            (None, None) => return Ok(()),
+        };
+        let mut proofs = vec![];
+        let mut state_transitions = vec![];
+        while let Some(index) = batch_proofs.iter().position(|(_, state_transition)| {
+            state_transition.initial_state_root.as_ref() == l2_state_root.as_ref()
+        }) {
+            let (proof, state_transition) = batch_proofs.swap_remove(index);
+            l2_state_root = state_transition.final_state_root.clone();
+            proofs.push(proof);
+            state_transitions.push(state_transition);
+        }
+        if state_transitions.is_empty() {
+            debug!(
+                "No batch proof in l1 height {} continues the light client",
+                l1_height
+            );
+            return Ok(());
+        }
+
+        // The first proof is anchored at the L1 block before its first header
+        let (initial_state_root, initial_da_hash, initial_da_height, initial_chain_state) =
+            match &previous_output {
+                Some(output) => (
+                    output.initial_state_root.clone(),
+                    output.initial_da_hash,
+                    output.initial_da_height,
+                    output.initial_chain_state.clone(),
+                ),
+                None => (
+                    state_transitions[0].initial_state_root.clone(),
+                    da_block_headers[0].prev_hash().into(),
+                    first_l1_height.saturating_sub(1),
+                    self.da_service
+                        .get_chain_state_at(first_l1_height.saturating_sub(1))
+                        .await
+                        .map_err(|e| anyhow!("Failed to get the DA chain state: {}", e))?,
+                ),
+            };
+        let data = LightClientData {
+            light_client_code_commitment,
+            batch_proof_code_commitment: self.code_commitment.as_ref().to_vec(),
+            previous_output,
+            initial_state_root,
+            initial_da_hash,
+            initial_da_height,
+            initial_chain_state,
+            recent_da_hashes,
+            da_block_headers,
+            da_blobs,
+            state_transitions,
+        };
+        let chain_state = prover_service
+            .verify_light_client_headers(data.chain_state(), &data.da_block_headers)?;
+        let (output, recent_da_hashes) = light_client_output(&data, chain_state)?;
+
+        info!(
+            "Proving light client over l1 heights {}-{} with {} batch proofs",
+            first_l1_height,
+            l1_height,
+            proofs.len()
+        );
+        let proof = prover_service
+            .prove_light_client(data, previous_proof, proofs)
+            .await?;
+
+        let tx_id = self
+            .send_to_da(DaData::LightClientProof(proof.clone()), vec![])
+            .await
+            .map_err(|e| anyhow!("Failed to send light client proof to DA: {}", e))?;
+        info!(
+            "Sent light client proof of L2 state root 0x{} at l1 height {} in DA tx 0x{}",
+            hex::encode(output.l2_state_root.as_ref()),
+            l1_height,
+            hex::encode(tx_id)
+        );
+
+        self.ledger_db.put_light_client_proof(
+            l1_height,
+            &StoredLightClientProof {
+                proof,
+                output: borsh::to_vec(&output).expect("Should serialize"),
+                recent_da_hashes,
+            },
+        )
+    }
+
+    /// Sends the blob to DA. With proof posting configured, the inclusion of the tx is
+    /// monitored in the background, so the proofs of later L1 blocks are not held up.
+    /// The proofs stored for `l1_heights` are updated with the txs replacing the tx.
+    #[instrument(level = "info", skip_all, err)]
+    async fn send_to_da(
+        &self,
+        da_data: DaData,
+        l1_heights: Vec<u64>,
+    ) -> Result<[u8; 32], anyhow::Error> {
+        let blob = da_data.encode();
+        let tx_id = self.da_service.send_transaction(blob.as_slice()).await?;
+
+        if let Some(proof_posting) = self
+            .prover_config
+            .as_ref()
+            .and_then(|config| config.proof_posting)
+        {
+            let da_service = self.da_service.clone();
+            let ledger_db = self.ledger_db.clone();
+            let first_tx = tx_id.clone();
+            tokio::spawn(async move {
+                monitor_inclusion(
+                    da_service,
+                    first_tx,
+                    blob,
+                    proof_posting,
+                    |replaced_tx, tx_id| {
+                        update_proof_tx_ids(&ledger_db, &l1_heights, replaced_tx, tx_id)
+                    },
+                )
+                .await
+            });
+        }
+        Ok(tx_id.into())
+    }
+
+    async fn store_proof(
+        &self,
+        pg_client: &Option<Result<PostgresConnector, DbPoolError>>,
+        l1_height: u64,
+        tx_id_u8: [u8; 32],
+        proof: Proof,
+    ) {
         // l1_height => (tx_id, proof, transition_data)
         // save proof along with tx id to db, should be queriable by slot number or slot hash
         let transition_data: sov_modules_api::StateTransition<
@@ -774,7 +1750,12 @@ where
         {
             panic!("Failed to put proof data in the ledger db: {}", e);
         }
-        Ok(())
+
+        // The proof is on DA, the job is done
+        self.progress.proof_sent(l1_height, tx_id_u8);
+        if let Err(e) = self.ledger_db.delete_proving_job(l1_height) {
+            warn!("Failed to delete proving job from the ledger db: {}", e);
+        }
     }
 
     fn save_commitments(&self, sequencer_commitments: Vec<SequencerCommitment>, l1_height: u64) {
@@ -805,6 +1786,128 @@ where
     }
 }
 
+/// Interval the inclusion of proof txs is checked at
+const PROOF_INCLUSION_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+/// What is done about a proof tx, given its status
+#[derive(Debug, PartialEq, Eq)]
+enum InclusionAction {
+    /// The tx is included, nothing is left to do
+    Done,
+    /// The tx is waited for
+    Wait,
+    /// The tx is replaced with one paying a higher fee
+    Replace,
+    /// The tx is sent again
+    Resend,
+}
+
+/// Txs pending for longer than the inclusion timeout are replaced, up to `max_fee_bumps`
+/// times, and are waited for after that. Dropped txs are sent again.
+fn inclusion_action(
+    status: TxStatus,
+    pending_for: Duration,
+    fee_bumps: u32,
+    config: &ProofPostingConfig,
+) -> InclusionAction {
+    match status {
+        TxStatus::Confirmed(_) => InclusionAction::Done,
+        TxStatus::Dropped => InclusionAction::Resend,
+        TxStatus::Pending
+            if pending_for >= Duration::from_secs(config.inclusion_timeout_secs)
+                && fee_bumps < config.max_fee_bumps =>
+        {
+            InclusionAction::Replace
+        }
+        TxStatus::Pending => InclusionAction::Wait,
+    }
+}
+
+/// Waits for the tx of the blob to be included on DA, replacing it if it stalls and sending
+/// it again if it is dropped. `on_new_tx` is called with each tx replaced and its successor.
+async fn monitor_inclusion<Da>(
+    da_service: Da,
+    mut tx_id: Da::TransactionId,
+    blob: Vec<u8>,
+    config: ProofPostingConfig,
+    on_new_tx: impl Fn([u8; 32], [u8; 32]),
+) where
+    Da: DaService,
+{
+    let mut fee_bumps = 0;
+    let mut sent_at = Instant::now();
+    loop {
+        sleep(PROOF_INCLUSION_POLL_INTERVAL).await;
+        let status = match da_service.get_transaction_status(&tx_id).await {
+            Ok(status) => status,
+            Err(e) => {
+                warn!("Failed to get the status of proof tx: {}", e);
+                continue;
+            }
+        };
+        let new_tx = match inclusion_action(status, sent_at.elapsed(), fee_bumps, &config) {
+            InclusionAction::Done => return,
+            InclusionAction::Wait => continue,
+            InclusionAction::Replace => {
+                warn!("Proof tx is stalled after {} fee bumps", fee_bumps);
+                fee_bumps += 1;
+                da_service
+                    .replace_transaction(
+                        &blob,
+                        FeeBump {
+                            replaced_tx: tx_id.clone(),
+                            fee_bump_percent: config.fee_bump_percent,
+                        },
+                    )
+                    .await
+            }
+            InclusionAction::Resend => {
+                warn!("Proof tx is dropped, sending it again");
+                fee_bumps = 0;
+                da_service.send_transaction(&blob).await
+            }
+        };
+        match new_tx {
+            Ok(new_tx) => {
+                on_new_tx(tx_id.into(), new_tx.clone().into());
+                tx_id = new_tx;
+                sent_at = Instant::now();
+            }
+            Err(e) => warn!("Failed to send proof tx again: {}", e),
+        }
+    }
+}
+
+/// Updates the proofs of the L1 heights which were sent in `replaced_tx` to be sent in `tx_id`
+fn update_proof_tx_ids<DB>(
+    ledger_db: &DB,
+    l1_heights: &[u64],
+    replaced_tx: [u8; 32],
+    tx_id: [u8; 32],
+) where
+    DB: ProverLedgerOps,
+{
+    for &l1_height in l1_heights {
+        let result = ledger_db
+            .get_proof_data(l1_height)
+            .and_then(|stored| match stored {
+                Some(stored) if stored.l1_tx_id == replaced_tx => ledger_db.put_proof_data(
+                    l1_height,
+                    tx_id,
+                    stored.proof,
+                    stored.state_transition,
+                ),
+                _ => Ok(()),
+            });
+        if let Err(e) = result {
+            warn!(
+                "Failed to update the proof tx of l1 height {}: {}",
+                l1_height, e
+            );
+        }
+    }
+}
+
 async fn l1_sync<Da>(
     start_l1_height: u64,
     da_service: Da,
@@ -866,6 +1969,8 @@ async fn sync_l2<Da>(
 {
     let mut l2_height = start_l2_height;
     info!("Starting to sync from L2 height {}", l2_height);
+
+    let mut sync_batch_size = AdaptiveSyncBatchSize::new(sync_blocks_count);
     loop {
         let exponential_backoff = ExponentialBackoffBuilder::<backoff::SystemClock>::new()
             .with_initial_interval(Duration::from_secs(1))
@@ -874,10 +1979,12 @@ async fn sync_l2<Da>(
             .build();
 
         let inner_client = &sequencer_client;
+        let batch_size = sync_batch_size.get();
+        let fetch_started_at = Instant::now();
         let soft_batches: Vec<GetSoftBatchResponse> =
             match retry_backoff(exponential_backoff.clone(), || async move {
                 let soft_batches = inner_client
-                    .get_soft_batch_range::<Da::Spec>(l2_height..l2_height + sync_blocks_count)
+                    .get_soft_batch_range::<Da::Spec>(l2_height..l2_height + batch_size)
                     .await;
 
                 match soft_batches {
@@ -906,6 +2013,7 @@ async fn sync_l2<Da>(
                     continue;
                 }
             };
+        let fetch_latency = fetch_started_at.elapsed();
 
         if soft_batches.is_empty() {
             debug!(
@@ -917,6 +2025,12 @@ async fn sync_l2<Da>(
             continue;
         }
 
+        let fetched = soft_batches.len() as u64;
+        let response_bytes = soft_batches
+            .iter()
+            .map(GetSoftBatchResponse::body_size)
+            .sum();
+
         let soft_batches: Vec<(u64, GetSoftBatchResponse)> = (l2_height
             ..l2_height + soft_batches.len() as u64)
             .zip(soft_batches)
@@ -924,9 +2038,18 @@ async fn sync_l2<Da>(
 
         l2_height += soft_batches.len() as u64;
 
+        // The channel is bounded, so this waits until the executor catches up
+        let handoff_started_at = Instant::now();
         if let Err(e) = sender.send(soft_batches).await {
             error!("Could not notify about L2 block: {}", e);
         }
+
+        sync_batch_size.record(
+            fetched,
+            fetch_latency,
+            response_bytes,
+            handoff_started_at.elapsed(),
+        );
     }
 }
 
@@ -942,3 +2065,131 @@ async fn get_initial_slot_height<Da: DaSpec>(client: &SequencerClient) -> u64 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sov_mock_da::{MockDaSpec, MockHash};
+
+    use super::*;
+
+    fn job(
+        l1_height: u64,
+        commitments: usize,
+        prove: bool,
+        rescanned: bool,
+    ) -> ProvingJob<MockDaSpec> {
+        ProvingJob {
+            l1_height,
+            hash: MockHash([l1_height as u8; 32]),
+            sequencer_commitments: vec![
+                SequencerCommitment {
+                    merkle_root: [0; 32],
+                    l2_start_block_number: 1,
+                    l2_end_block_number: 1,
+                };
+                commitments
+            ],
+            prove,
+            started: false,
+            proof: None,
+            rescanned,
+            later_segments: vec![],
+        }
+    }
+
+    fn commitment(l2_height: u64) -> SequencerCommitment {
+        SequencerCommitment {
+            merkle_root: [0; 32],
+            l2_start_block_number: l2_height,
+            l2_end_block_number: l2_height,
+        }
+    }
+
+    #[test]
+    fn aggregates_only_consecutive_proofs() {
+        let jobs = vec![
+            job(1, 1, true, false),
+            // Without commitments, does not change the state
+            job(2, 0, false, false),
+            job(3, 2, true, false),
+            // Not sampled, breaks the chain of state transitions
+            job(4, 1, false, false),
+            job(5, 1, true, false),
+            job(6, 1, true, false),
+            // Challenged, proven on its own
+            job(7, 1, true, true),
+            job(8, 1, true, false),
+        ];
+        assert_eq!(aggregation_runs(&jobs), vec![vec![0, 2], vec![4, 5]]);
+    }
+
+    #[test]
+    fn proves_jobs_with_several_proofs_on_their_own() {
+        let mut jobs = vec![
+            job(1, 1, true, false),
+            job(2, 1, true, false),
+            job(3, 1, true, false),
+            job(4, 1, true, false),
+        ];
+        jobs[1].later_segments = vec![(vec![commitment(2)], (2, 2))];
+        assert_eq!(aggregation_runs(&jobs), vec![vec![2, 3]]);
+    }
+
+    #[test]
+    fn splits_new_commitments_at_duplicates() {
+        let commitments: Vec<_> = (1..=6).map(commitment).collect();
+
+        let segments = new_commitment_segments(
+            commitments.clone(),
+            &[false, true, false, false, true, false],
+        );
+        assert_eq!(
+            segments,
+            vec![
+                (vec![commitment(1)], (0, 0)),
+                (vec![commitment(3), commitment(4)], (2, 3)),
+                (vec![commitment(6)], (5, 5)),
+            ]
+        );
+
+        let segments = new_commitment_segments(
+            commitments.clone(),
+            &[true, true, false, false, false, false],
+        );
+        assert_eq!(segments, vec![(commitments[2..].to_vec(), (2, 5))]);
+
+        assert!(new_commitment_segments(commitments, &[true; 6]).is_empty());
+    }
+
+    #[test]
+    fn replaces_stalled_proof_txs_up_to_max_fee_bumps() {
+        let config = ProofPostingConfig {
+            inclusion_timeout_secs: 600,
+            fee_bump_percent: 25,
+            max_fee_bumps: 2,
+        };
+        let timeout = Duration::from_secs(600);
+
+        assert_eq!(
+            inclusion_action(TxStatus::Pending, timeout / 2, 0, &config),
+            InclusionAction::Wait
+        );
+        assert_eq!(
+            inclusion_action(TxStatus::Pending, timeout, 1, &config),
+            InclusionAction::Replace
+        );
+        // Waits for the last replacement instead of bumping the fee further
+        assert_eq!(
+            inclusion_action(TxStatus::Pending, timeout * 10, 2, &config),
+            InclusionAction::Wait
+        );
+        assert_eq!(
+            inclusion_action(TxStatus::Dropped, Duration::ZERO, 2, &config),
+            InclusionAction::Resend
+        );
+        assert_eq!(
+            inclusion_action(TxStatus::Confirmed(1), timeout, 0, &config),
+            InclusionAction::Done
+        );
+    }
+}
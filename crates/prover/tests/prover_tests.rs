@@ -1,17 +1,24 @@
 use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
 
-use citrea_prover::prover_service::ParallelProverService;
+use citrea_prover::prover_service::{
+    NativeBackend, ParallelProverService, ProvingBackend, ProvingSession, RemoteBackend,
+    ZkvmBackend,
+};
 use sov_mock_da::{
     MockAddress, MockBlockHeader, MockDaService, MockDaSpec, MockDaVerifier, MockHash,
     MockValidityCond,
 };
-use sov_mock_zkvm::MockZkvm;
+use sov_mock_zkvm::{MockCodeCommitment, MockZkvm};
 use sov_rollup_interface::da::Time;
-use sov_rollup_interface::zk::StateTransitionData;
+use sov_rollup_interface::zk::{
+    AggregatedStateTransition, AggregationData, CompactWitnesses, Proof, StateTransition,
+    StateTransitionData,
+};
 use sov_stf_runner::mock::MockStf;
 use sov_stf_runner::{
     ProofProcessingStatus, ProverGuestRunConfig, ProverService, ProverServiceError,
-    WitnessSubmissionStatus,
+    RemoteProverConfig, WitnessSubmissionStatus,
 };
 
 #[tokio::test]
@@ -90,10 +97,10 @@ async fn test_prover_status_busy() -> Result<(), anyhow::Error> {
             .await
             .unwrap_err();
 
-        // The new job wasn't accepted.
+        // The new job wasn't accepted, but its witness is kept.
         assert_eq!(
         proof_submission_status.to_string(),
-        "Missing witness for: 0x0000000000000000000000000000000000000000000000000000000000000000");
+        "Witness for 0x0000000000000000000000000000000000000000000000000000000000000000 was submitted, but the proof generation is not triggered.");
     }
 
     vm.make_proof();
@@ -105,15 +112,59 @@ async fn test_prover_status_busy() -> Result<(), anyhow::Error> {
 
     // Retry once the prover is available to process new proofs.
     {
-        let header_hash = MockHash::from([(num_worker_threads + 1) as u8; 32]);
+        let header_hash = MockHash::from([0; 32]);
+        let status = prover_service.prove(header_hash).await?;
+        assert_eq!(ProofProcessingStatus::ProvingInProgress, status);
+
+        vm.make_proof();
+        prover_service
+            .wait_for_proving_and_send_to_da(header_hash, &da_service)
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_prover_memory_budget() -> Result<(), anyhow::Error> {
+    let temp = tempfile::tempdir().unwrap();
+    let da_service = MockDaService::new(MockAddress::from([0; 32]), temp.path());
+    let TestProver {
+        prover_service, vm, ..
+    } = make_new_prover();
+    // Smaller than the input of a single job
+    let prover_service = prover_service.with_memory_budget(1);
+
+    let first_hash = MockHash::from([1; 32]);
+    let second_hash = MockHash::from([2; 32]);
+    for header_hash in [first_hash, second_hash] {
         prover_service
             .submit_witness(make_transition_data(header_hash))
             .await;
-
-        let status = prover_service.prove(header_hash).await?;
-        assert_eq!(ProofProcessingStatus::ProvingInProgress, status);
     }
 
+    // A job over the budget is proven when no other job is
+    let status = prover_service.prove(first_hash).await?;
+    assert_eq!(ProofProcessingStatus::ProvingInProgress, status);
+
+    // Threads are available, but the budget is exhausted
+    let status = prover_service.prove(second_hash).await?;
+    assert_eq!(ProofProcessingStatus::Busy, status);
+
+    vm.make_proof();
+    prover_service
+        .wait_for_proving_and_send_to_da(first_hash, &da_service)
+        .await?;
+
+    // The budget is released once the first proof is done
+    let status = prover_service.prove(second_hash).await?;
+    assert_eq!(ProofProcessingStatus::ProvingInProgress, status);
+
+    vm.make_proof();
+    prover_service
+        .wait_for_proving_and_send_to_da(second_hash, &da_service)
+        .await?;
+
     Ok(())
 }
 
@@ -170,6 +221,199 @@ async fn test_generate_multiple_proofs_for_the_same_witness() -> Result<(), anyh
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn test_remote_prover_falls_back_to_local_backend() {
+    let config = RemoteProverConfig {
+        // Nothing listens on port 1
+        url: "http://127.0.0.1:1".to_string(),
+        retry_secs: 0,
+        poll_interval_secs: 1,
+        max_poll_interval_secs: 1,
+        timeout_secs: 1,
+    };
+    let backend = RemoteBackend::<MockZkvm<MockValidityCond>, MockDaSpec, [u8; 0]>::new(
+        config,
+        MockCodeCommitment([0; 32]),
+        Arc::new(EchoBackend::default()),
+    )
+    .unwrap();
+
+    let proof = tokio::task::spawn_blocking(move || {
+        let mut session = backend.new_session();
+        session.write_input(&[1, 2]);
+        session.write_input(&[3]);
+        session.prove(true)
+    })
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(proof, Proof::PublicInput(vec![1, 2, 3]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_aggregates_proofs_on_the_aggregation_backend() -> Result<(), anyhow::Error> {
+    let assumptions = Arc::new(Mutex::new(vec![]));
+    let TestProver { prover_service, .. } = make_new_prover();
+    let prover_service = prover_service.with_aggregation(
+        Arc::new(EchoBackend {
+            assumptions: Some(assumptions.clone()),
+        }),
+        MockCodeCommitment([1; 32]),
+    );
+
+    let proofs = vec![
+        make_proof(MockHash::from([1; 32])),
+        make_proof(MockHash::from([2; 32])),
+    ];
+    let Proof::PublicInput(input) = prover_service.aggregate_proofs(proofs.clone()).await? else {
+        panic!("Echo backend returns the input");
+    };
+
+    let data: AggregationData<MockDaSpec, [u8; 0]> = borsh::from_slice(&input)?;
+    assert_eq!(data.range_code_commitment, vec![1; 32]);
+    assert_eq!(
+        data.state_transitions
+            .iter()
+            .map(|state_transition| state_transition.da_slot_hash)
+            .collect::<Vec<_>>(),
+        vec![MockHash::from([1; 32]), MockHash::from([2; 32])]
+    );
+    // The guest verifies the aggregated proofs as assumptions
+    assert_eq!(*assumptions.lock().unwrap(), proofs);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_aggregation_is_not_enabled_by_default() {
+    let TestProver { prover_service, .. } = make_new_prover();
+    let err = prover_service
+        .aggregate_proofs(vec![make_proof(MockHash::from([1; 32]))])
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Proof aggregation is not enabled");
+}
+
+#[test]
+fn test_aggregates_only_consecutive_state_transitions() {
+    let transition = |da_slot: u8, initial_state_root: u8, final_state_root: u8| StateTransition::<
+        MockDaSpec,
+        [u8; 32],
+    > {
+        initial_state_root: [initial_state_root; 32],
+        final_state_root: [final_state_root; 32],
+        initial_batch_hash: [0; 32],
+        state_diff: Default::default(),
+        da_slot_hash: MockHash::from([da_slot; 32]),
+        sequencer_commitments_range: (0, 0),
+        withdrawal_roots: vec![],
+        sequencer_public_key: vec![1; 32],
+        sequencer_da_public_key: vec![da_slot; 32],
+        validity_condition: MockValidityCond::default(),
+    };
+
+    let aggregated = AggregatedStateTransition::aggregate(
+        vec![0; 32],
+        vec![
+            transition(1, 1, 2),
+            transition(2, 2, 3),
+            transition(3, 3, 4),
+        ],
+    )
+    .unwrap();
+    assert_eq!(aggregated.initial_state_root, [1; 32]);
+    assert_eq!(aggregated.final_state_root, [4; 32]);
+    assert_eq!(aggregated.da_slot_hashes.len(), 3);
+    // The DA key of the sequencer is kept for each DA slot, it may be rotated in between
+    assert_eq!(
+        aggregated.sequencer_da_public_keys,
+        vec![vec![1; 32], vec![2; 32], vec![3; 32]]
+    );
+
+    // The state transition of the second DA slot is missing
+    let err = AggregatedStateTransition::aggregate(
+        vec![0; 32],
+        vec![transition(1, 1, 2), transition(3, 3, 4)],
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "State transitions are not consecutive");
+
+    assert!(
+        AggregatedStateTransition::<MockDaSpec, [u8; 32]>::aggregate(vec![0; 32], vec![]).is_err()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_native_backend_runs_the_guest_program_natively() {
+    let vm = MockZkvm::new(MockValidityCond::default());
+    let backend = NativeBackend::new(
+        "mock",
+        vm.clone(),
+        MockCodeCommitment([0; 32]),
+        Arc::new(|_guest| Ok(Proof::PublicInput(vec![7]))),
+    );
+    assert_eq!(backend.code_commitment(), vec![0; 32]);
+
+    // The mock zkVM blocks until a proof is made, the native backend never executes it
+    let proof = tokio::task::spawn_blocking(move || {
+        let mut session = backend.new_session();
+        session.write_input(&[1, 2]);
+        session.add_assumption(Proof::PublicInput(vec![3]))?;
+        session.prove(true)
+    })
+    .await
+    .unwrap()
+    .unwrap();
+
+    assert_eq!(proof, Proof::PublicInput(vec![7]));
+}
+
+/// Returns the inputs as the proof, and records the assumptions if it proves recursively
+#[derive(Default)]
+struct EchoBackend {
+    assumptions: Option<Arc<Mutex<Vec<Proof>>>>,
+}
+
+impl ProvingBackend for EchoBackend {
+    fn name(&self) -> &'static str {
+        "echo"
+    }
+
+    fn code_commitment(&self) -> Vec<u8> {
+        vec![0; 32]
+    }
+
+    fn new_session(&self) -> Box<dyn ProvingSession> {
+        Box::new(EchoSession {
+            input: vec![],
+            assumptions: self.assumptions.clone(),
+        })
+    }
+}
+
+struct EchoSession {
+    input: Vec<u8>,
+    assumptions: Option<Arc<Mutex<Vec<Proof>>>>,
+}
+
+impl ProvingSession for EchoSession {
+    fn write_input(&mut self, input: &[u8]) {
+        self.input.extend_from_slice(input);
+    }
+
+    fn add_assumption(&mut self, proof: Proof) -> anyhow::Result<()> {
+        let Some(assumptions) = &self.assumptions else {
+            anyhow::bail!("The proving backend does not support recursion");
+        };
+        assumptions.lock().unwrap().push(proof);
+        Ok(())
+    }
+
+    fn prove(self: Box<Self>, _with_proof: bool) -> anyhow::Result<Proof> {
+        Ok(Proof::PublicInput(self.input))
+    }
+}
+
 struct TestProver {
     prover_service: ParallelProverService<
         [u8; 0],
@@ -192,6 +436,11 @@ fn make_new_prover() -> TestProver {
     TestProver {
         prover_service: ParallelProverService::new(
             vm.clone(),
+            Arc::new(ZkvmBackend::new(
+                "mock",
+                vm.clone(),
+                MockCodeCommitment([0; 32]),
+            )),
             zk_stf,
             da_verifier,
             prover_config,
@@ -223,9 +472,16 @@ fn make_transition_data(
             time: Time::now(),
         },
         soft_confirmations: VecDeque::new(),
-        state_transition_witnesses: VecDeque::new(),
+        state_transition_witnesses: CompactWitnesses::default(),
         da_block_headers_of_soft_confirmations: VecDeque::new(),
+        da_blobs_of_soft_confirmations: VecDeque::new(),
         sequencer_public_key: vec![],
         sequencer_da_public_key: vec![],
     }
 }
+
+/// A mock proof of the state transition of the L1 block, in the encoding of the mock zkVM
+fn make_proof(header_hash: MockHash) -> Proof {
+    let hint = borsh::to_vec(&make_transition_data(header_hash)).unwrap();
+    Proof::PublicInput(bincode::serialize(&(hint, MockValidityCond::default())).unwrap())
+}
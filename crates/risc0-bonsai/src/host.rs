@@ -15,7 +15,7 @@ use risc0_zkvm::{
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use sov_risc0_adapter::guest::Risc0Guest;
-use sov_rollup_interface::zk::{Proof, Zkvm, ZkvmHost};
+use sov_rollup_interface::zk::{AggregatedStateTransition, Proof, Zkvm, ZkvmHost};
 use tracing::{debug, error, instrument, trace, warn};
 
 /// Requests to bonsai client. Each variant represents its own method.
@@ -30,6 +30,10 @@ enum BonsaiRequest {
         buf: Vec<u8>,
         notify: Sender<String>,
     },
+    UploadReceipt {
+        buf: Vec<u8>,
+        notify: Sender<String>,
+    },
     Download {
         url: String,
         notify: Sender<Vec<u8>>,
@@ -141,6 +145,12 @@ impl BonsaiClient {
                             let res = unwrap_bonsai_response!(res, 'client, 'queue);
                             let _ = notify.send(res);
                         }
+                        BonsaiRequest::UploadReceipt { buf, notify } => {
+                            debug!("Bonsai:upload_receipt");
+                            let res = client.upload_receipt(buf);
+                            let res = unwrap_bonsai_response!(res, 'client, 'queue);
+                            let _ = notify.send(res);
+                        }
                         BonsaiRequest::Download { url, notify } => {
                             debug!(%url, "Bonsai:download");
                             let res = client.download(&url);
@@ -211,6 +221,15 @@ impl BonsaiClient {
         rx.recv().unwrap()
     }
 
+    #[instrument(level = "trace", skip_all, ret)]
+    fn upload_receipt(&self, buf: Vec<u8>) -> String {
+        let (notify, rx) = mpsc::channel();
+        self.queue
+            .send(BonsaiRequest::UploadReceipt { buf, notify })
+            .expect("Bonsai processing queue is dead");
+        rx.recv().unwrap()
+    }
+
     #[instrument(level = "trace", skip(self))]
     fn download(&self, url: String) -> Vec<u8> {
         let (notify, rx) = mpsc::channel();
@@ -291,9 +310,20 @@ pub struct Risc0BonsaiHost<'a> {
     image_id: Digest,
     client: Option<BonsaiClient>,
     last_input_id: Option<String>,
+    /// Receipts verified by the guest, for running locally
+    assumptions: Vec<Receipt>,
+    /// Bonsai ids of the receipts verified by the guest
+    assumption_ids: Vec<String>,
 }
 
 impl<'a> Risc0BonsaiHost<'a> {
+    /// Encodes the output of a guest program run natively like the journal of an execution
+    /// without proof, so it is read like the output of the proofs of the guest.
+    pub fn public_input_proof<T: BorshSerialize>(output: &T) -> anyhow::Result<Proof> {
+        let journal = Journal::new(borsh::to_vec(output)?);
+        Ok(Proof::PublicInput(bincode::serialize(&journal)?))
+    }
+
     /// Create a new Risc0Host to prove the given binary.
     pub fn new(elf: &'a [u8], api_url: String, api_key: String) -> Self {
         // Compute the image_id, then upload the ELF with the image_id as its key.
@@ -322,6 +352,8 @@ impl<'a> Risc0BonsaiHost<'a> {
             image_id,
             client,
             last_input_id: None,
+            assumptions: Default::default(),
+            assumption_ids: Default::default(),
         }
     }
 
@@ -387,11 +419,12 @@ impl<'a> ZkvmHost for Risc0BonsaiHost<'a> {
     /// Proofs are created on the Bonsai API.
     fn run(&mut self, with_proof: bool) -> Result<Proof, anyhow::Error> {
         if !with_proof {
-            let env =
-                sov_risc0_adapter::host::add_benchmarking_callbacks(ExecutorEnvBuilder::default())
-                    .write_slice(&self.env)
-                    .build()
-                    .unwrap();
+            let mut env =
+                sov_risc0_adapter::host::add_benchmarking_callbacks(ExecutorEnvBuilder::default());
+            for assumption in std::mem::take(&mut self.assumptions) {
+                env.add_assumption(assumption);
+            }
+            let env = env.write_slice(&self.env).build().unwrap();
             let mut executor = ExecutorImpl::from_elf(env, self.elf)?;
 
             let session = executor.run()?;
@@ -418,7 +451,11 @@ impl<'a> ZkvmHost for Risc0BonsaiHost<'a> {
             };
 
             // Start a session running the prover
-            let session = client.create_session(hex::encode(self.image_id), input_id, vec![]);
+            let session = client.create_session(
+                hex::encode(self.image_id),
+                input_id,
+                std::mem::take(&mut self.assumption_ids),
+            );
             tracing::info!("Session created: {}", session.uuid);
             let receipt = loop {
                 // handle error
@@ -523,6 +560,36 @@ impl<'a> ZkvmHost for Risc0BonsaiHost<'a> {
         };
         Ok(BorshDeserialize::try_from_slice(&journal.bytes)?)
     }
+
+    fn add_assumption(&mut self, proof: Proof) -> Result<(), anyhow::Error> {
+        let Proof::Full(data) = proof else {
+            return Err(anyhow!("Only full proofs can be verified by the guest"));
+        };
+
+        if let Some(client) = self.client.as_ref() {
+            let receipt_id = client.upload_receipt(data.clone());
+            tracing::info!("Uploaded assumption with id: {}", receipt_id);
+            self.assumption_ids.push(receipt_id);
+        }
+        self.assumptions.push(bincode::deserialize(&data)?);
+        Ok(())
+    }
+
+    fn extract_aggregated_output<Da: sov_rollup_interface::da::DaSpec, Root: BorshDeserialize>(
+        proof: &Proof,
+    ) -> Result<AggregatedStateTransition<Da, Root>, Self::Error> {
+        let journal = match proof {
+            Proof::PublicInput(journal) => {
+                let journal: Journal = bincode::deserialize(journal)?;
+                journal
+            }
+            Proof::Full(data) => {
+                let receipt: Receipt = bincode::deserialize(data)?;
+                receipt.journal
+            }
+        };
+        Ok(BorshDeserialize::try_from_slice(&journal.bytes)?)
+    }
 }
 
 impl<'host> Zkvm for Risc0BonsaiHost<'host> {
@@ -551,4 +618,19 @@ impl<'host> Zkvm for Risc0BonsaiHost<'host> {
 
         Ok(receipt.journal.decode()?)
     }
+
+    fn verify_and_extract_aggregated_output<
+        Da: sov_rollup_interface::da::DaSpec,
+        Root: BorshDeserialize,
+    >(
+        serialized_proof: &[u8],
+        code_commitment: &Self::CodeCommitment,
+    ) -> Result<AggregatedStateTransition<Da, Root>, Self::Error> {
+        let receipt: Receipt = bincode::deserialize(serialized_proof)?;
+
+        #[allow(clippy::clone_on_copy)]
+        receipt.verify(code_commitment.clone())?;
+
+        Ok(BorshDeserialize::try_from_slice(&receipt.journal.bytes)?)
+    }
 }
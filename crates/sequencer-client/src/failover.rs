@@ -0,0 +1,168 @@
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::bail;
+use once_cell::sync::Lazy;
+use prometheus::{register_int_counter, IntCounter};
+use tracing::{info, instrument, warn};
+
+use crate::{GetSoftBatchResponse, SequencerClient};
+
+static SEQUENCER_CLIENT_FAILOVERS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        // metric name
+        "sequencer_client_failovers",
+        // metric description
+        "Number of times the sequencer client switched to another sequencer endpoint"
+    )
+    .unwrap()
+});
+
+/// Health of a single sequencer endpoint, as seen by the last request or health check.
+#[derive(Debug, Clone, Copy)]
+struct EndpointHealth {
+    healthy: bool,
+    latency: Option<Duration>,
+}
+
+#[derive(Debug)]
+struct FailoverState {
+    endpoints: Vec<EndpointHealth>,
+    active: usize,
+}
+
+/// Sequencer client over multiple sequencer endpoints.
+/// Requests go to the healthy endpoint with the lowest latency
+/// and fail over to the other endpoints on errors.
+#[derive(Debug, Clone)]
+pub struct FailoverSequencerClient {
+    clients: Vec<SequencerClient>,
+    state: Arc<Mutex<FailoverState>>,
+}
+
+impl FailoverSequencerClient {
+    /// Creates the client. The first url is preferred until the first health check.
+    pub fn new(rpc_urls: Vec<String>) -> anyhow::Result<Self> {
+        if rpc_urls.is_empty() {
+            bail!("At least one sequencer url is required");
+        }
+        let clients: Vec<_> = rpc_urls.into_iter().map(SequencerClient::new).collect();
+        let endpoints = vec![
+            EndpointHealth {
+                healthy: true,
+                latency: None,
+            };
+            clients.len()
+        ];
+        Ok(Self {
+            clients,
+            state: Arc::new(Mutex::new(FailoverState {
+                endpoints,
+                active: 0,
+            })),
+        })
+    }
+
+    /// Returns the client of the currently active endpoint
+    pub fn active(&self) -> &SequencerClient {
+        &self.clients[self.state.lock().unwrap().active]
+    }
+
+    /// Measures the latency of every endpoint and switches to the
+    /// healthy endpoint with the lowest latency.
+    /// Errors if no endpoint is healthy, the active endpoint is kept then.
+    #[instrument(level = "trace", skip(self), err)]
+    pub async fn health_check(&self) -> anyhow::Result<()> {
+        let mut results = Vec::with_capacity(self.clients.len());
+        for client in &self.clients {
+            let start = Instant::now();
+            let health = match client.block_number().await {
+                Ok(_) => EndpointHealth {
+                    healthy: true,
+                    latency: Some(start.elapsed()),
+                },
+                Err(e) => {
+                    warn!("Sequencer endpoint {} is unhealthy: {}", client.rpc_url, e);
+                    EndpointHealth {
+                        healthy: false,
+                        latency: None,
+                    }
+                }
+            };
+            results.push(health);
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.endpoints = results;
+        let fastest = state
+            .endpoints
+            .iter()
+            .enumerate()
+            .filter(|(_, health)| health.healthy)
+            .min_by_key(|(_, health)| health.latency)
+            .map(|(index, _)| index);
+        let Some(fastest) = fastest else {
+            bail!(
+                "None of the {} sequencer endpoints is healthy",
+                self.clients.len()
+            );
+        };
+        self.switch_to(&mut state, fastest);
+        Ok(())
+    }
+
+    /// Gets l2 blocks given a range, failing over to other endpoints on errors
+    #[instrument(level = "trace", skip(self), err)]
+    pub async fn get_soft_batch_range<DaSpec: sov_rollup_interface::da::DaSpec>(
+        &self,
+        range: Range<u64>,
+    ) -> anyhow::Result<Vec<Option<GetSoftBatchResponse>>> {
+        let mut last_error = None;
+        for _ in 0..self.clients.len() {
+            let active = self.state.lock().unwrap().active;
+            let client = &self.clients[active];
+
+            let start = Instant::now();
+            match client.get_soft_batch_range::<DaSpec>(range.clone()).await {
+                Ok(soft_batches) => {
+                    let mut state = self.state.lock().unwrap();
+                    state.endpoints[active] = EndpointHealth {
+                        healthy: true,
+                        latency: Some(start.elapsed()),
+                    };
+                    return Ok(soft_batches);
+                }
+                Err(e) => {
+                    warn!(
+                        "Request to sequencer endpoint {} failed: {}",
+                        client.rpc_url, e
+                    );
+                    let mut state = self.state.lock().unwrap();
+                    state.endpoints[active].healthy = false;
+                    // Prefer the next healthy endpoint, otherwise just try the next one
+                    let next = (1..self.clients.len())
+                        .map(|offset| (active + offset) % self.clients.len())
+                        .find(|&index| state.endpoints[index].healthy)
+                        .unwrap_or((active + 1) % self.clients.len());
+                    self.switch_to(&mut state, next);
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.expect("At least one request has been made"))
+    }
+
+    fn switch_to(&self, state: &mut FailoverState, index: usize) {
+        if state.active == index {
+            return;
+        }
+        info!(
+            "Switching sequencer endpoint from {} to {}",
+            self.clients[state.active].rpc_url, self.clients[index].rpc_url
+        );
+        state.active = index;
+        SEQUENCER_CLIENT_FAILOVERS.inc();
+    }
+}
@@ -1,29 +1,53 @@
 use std::ops::Range;
 
 use citrea_primitives::types::SoftConfirmationHash;
+use hyper::header::{HeaderName, HeaderValue};
 use jsonrpsee::core::client::{ClientT, Error};
+use jsonrpsee::core::params::ArrayParams;
+use jsonrpsee::http_client::transport::HttpBackend;
 use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
 use jsonrpsee::rpc_params;
 use reth_primitives::{Bytes, B256};
 use serde::Deserialize;
+use sov_rollup_interface::rpc::request_id::{current_request_id, REQUEST_ID_HEADER};
 use sov_rollup_interface::rpc::HexTx;
 use sov_rollup_interface::soft_confirmation::SignedSoftConfirmationBatch;
+use tower_http::set_header::{SetRequestHeader, SetRequestHeaderLayer};
 use tracing::instrument;
 
+mod failover;
+
+pub use failover::FailoverSequencerClient;
+
+/// Header of the correlation ID of the RPC request a call to the sequencer is made for
+type RequestIdHeader = fn(&hyper::Request<hyper::Body>) -> Option<HeaderValue>;
+
 /// Configuration for SequencerClient.
 #[derive(Debug, Clone)]
 pub struct SequencerClient {
     /// Host config for soft confirmation
     pub rpc_url: String,
     /// Client object for soft confirmation
-    pub client: HttpClient,
+    pub client: HttpClient<SetRequestHeader<HttpBackend, RequestIdHeader>>,
 }
 
 impl SequencerClient {
-    /// Creates the sequencer client
+    /// Creates the sequencer client.
+    /// Calls made while handling an RPC request send its correlation ID along,
+    /// so the sequencer logs its work with the same ID.
     #[instrument(level = "trace")]
     pub fn new(rpc_url: String) -> Self {
-        let client = HttpClientBuilder::default().build(&rpc_url).unwrap();
+        let request_id_header: RequestIdHeader =
+            |_| current_request_id().and_then(|request_id| HeaderValue::from_str(&request_id).ok());
+        let client = HttpClientBuilder::default()
+            .set_http_middleware(tower::ServiceBuilder::new().layer(
+                SetRequestHeaderLayer::if_not_present(
+                    HeaderName::from_static(REQUEST_ID_HEADER),
+                    request_id_header,
+                ),
+            ))
+            .build(&rpc_url)
+            .unwrap();
         Self { rpc_url, client }
     }
 
@@ -99,6 +123,21 @@ impl SequencerClient {
             )
             .await
     }
+
+    /// Calls a method of the sequencer with the params of a request to this node,
+    /// for what only the sequencer knows, e.g. the block it is building
+    #[instrument(level = "trace", skip(self), err, ret)]
+    pub async fn forward(
+        &self,
+        method: &str,
+        params: Vec<serde_json::Value>,
+    ) -> Result<serde_json::Value, Error> {
+        let mut array_params = ArrayParams::new();
+        for param in params {
+            array_params.insert(param)?;
+        }
+        self.client.request(method, array_params).await
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -126,6 +165,15 @@ pub struct GetSoftBatchResponse {
     pub timestamp: u64,
 }
 
+impl GetSoftBatchResponse {
+    /// Size of the transactions and deposit data of the soft batch in bytes
+    pub fn body_size(&self) -> usize {
+        let txs_size: usize = self.txs.iter().flatten().map(|tx| tx.tx.len()).sum();
+        let deposit_data_size: usize = self.deposit_data.iter().map(|tx| tx.tx.len()).sum();
+        txs_size + deposit_data_size
+    }
+}
+
 impl From<GetSoftBatchResponse> for SignedSoftConfirmationBatch {
     fn from(val: GetSoftBatchResponse) -> Self {
         SignedSoftConfirmationBatch::new(
@@ -0,0 +1,75 @@
+use jsonrpsee::server::{RpcModule, ServerBuilder, ServerHandle};
+use sequencer_client::FailoverSequencerClient;
+use sov_mock_da::MockDaSpec;
+
+/// Nothing listens on it, so requests fail right away
+const DEAD_URL: &str = "http://127.0.0.1:1";
+
+/// Starts a sequencer endpoint which returns `range_len` missing blocks for any range,
+/// so tests can tell endpoints apart by the length of the responses.
+async fn start_sequencer(range_len: usize) -> (ServerHandle, String) {
+    let mut module = RpcModule::new(());
+    module
+        .register_method("ledger_getHeadSoftBatchHeight", |_, _| 1u64)
+        .unwrap();
+    module
+        .register_method("ledger_getSoftBatchRange", move |_, _| {
+            vec![serde_json::Value::Null; range_len]
+        })
+        .unwrap();
+
+    let server = ServerBuilder::default().build("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", server.local_addr().unwrap());
+    (server.start(module), url)
+}
+
+#[test]
+fn failover_client_needs_an_endpoint() {
+    assert!(FailoverSequencerClient::new(vec![]).is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requests_fail_over_to_the_next_endpoint() {
+    let (_server, url) = start_sequencer(2).await;
+    let client = FailoverSequencerClient::new(vec![DEAD_URL.to_owned(), url.clone()]).unwrap();
+    assert_eq!(client.active().rpc_url, DEAD_URL);
+
+    let soft_batches = client
+        .get_soft_batch_range::<MockDaSpec>(0..2)
+        .await
+        .unwrap();
+    assert_eq!(soft_batches.len(), 2);
+    assert_eq!(client.active().rpc_url, url);
+
+    // The healthy endpoint is kept
+    client
+        .get_soft_batch_range::<MockDaSpec>(0..2)
+        .await
+        .unwrap();
+    assert_eq!(client.active().rpc_url, url);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn requests_fail_when_every_endpoint_is_down() {
+    let client =
+        FailoverSequencerClient::new(vec![DEAD_URL.to_owned(), DEAD_URL.to_owned()]).unwrap();
+    assert!(client
+        .get_soft_batch_range::<MockDaSpec>(0..1)
+        .await
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn health_check_switches_to_a_healthy_endpoint() {
+    let (server, url) = start_sequencer(1).await;
+    let client = FailoverSequencerClient::new(vec![DEAD_URL.to_owned(), url.clone()]).unwrap();
+
+    client.health_check().await.unwrap();
+    assert_eq!(client.active().rpc_url, url);
+
+    // With no healthy endpoint left the active one is kept
+    server.stop().unwrap();
+    server.stopped().await;
+    assert!(client.health_check().await.is_err());
+    assert_eq!(client.active().rpc_url, url);
+}
@@ -0,0 +1,57 @@
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::{rpc_params, RpcModule};
+use sequencer_client::SequencerClient;
+use sov_db::ledger_db::LedgerDB;
+use sov_rollup_interface::rpc::request_id::scope_request_id;
+use sov_stf_runner::{current_request_id, spawn_rpc_server, RpcConfig, RpcServerOptions};
+use tokio::sync::oneshot;
+
+/// Starts a node whose `test_requestId` returns the correlation ID of the request
+async fn start_node(ledger_db: LedgerDB) -> String {
+    let rpc_config: RpcConfig = toml::from_str(
+        r#"
+        bind_host = "127.0.0.1"
+        bind_port = 0
+    "#,
+    )
+    .unwrap();
+    let mut methods = RpcModule::new(());
+    methods
+        .register_method("test_requestId", |_, _| current_request_id())
+        .unwrap();
+    let (address_tx, address_rx) = oneshot::channel();
+    spawn_rpc_server(
+        &rpc_config,
+        methods,
+        ledger_db,
+        RpcServerOptions::default(),
+        Some(address_tx),
+    )
+    .unwrap();
+    format!("http://{}", address_rx.await.unwrap())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn calls_keep_the_request_id_they_are_made_for() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let url = start_node(LedgerDB::with_path(tmpdir.path()).unwrap()).await;
+    let client = SequencerClient::new(url);
+
+    let request_id: Option<String> = scope_request_id(
+        "fullnode-request-1".to_string(),
+        client.client.request("test_requestId", rpc_params![]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(request_id.as_deref(), Some("fullnode-request-1"));
+
+    // Calls made outside of requests get a new ID
+    let request_id: Option<String> = client
+        .client
+        .request("test_requestId", rpc_params![])
+        .await
+        .unwrap();
+    let request_id = request_id.unwrap();
+    assert_eq!(request_id.len(), 16);
+    assert_ne!(request_id, "fullnode-request-1");
+}
@@ -0,0 +1,69 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use sov_modules_api::{CallResponse, Context, StateValueAccessor, WorkingSet};
+
+use crate::{SequencerInfo, SequencerRegistry};
+
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    derive(serde::Deserialize)
+)]
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize, Eq, PartialEq)]
+pub enum CallMessage<C: Context> {
+    /// Change the authority of the sequencer registry.
+    ChangeAuthority {
+        /// The sov address of the new authority.
+        new_authority: C::Address,
+    },
+    /// Replace the active sequencer.
+    /// L2 blocks after the one including this call must be signed by the new sequencer.
+    RotateSequencer {
+        /// The keys and address of the new sequencer.
+        sequencer: SequencerInfo,
+    },
+}
+
+impl<C: Context> SequencerRegistry<C> {
+    /// Returns the address of authority.
+    fn get_authority(&self, working_set: &mut WorkingSet<C>) -> C::Address {
+        self.authority
+            .get(working_set)
+            .expect("Authority must be set")
+    }
+
+    pub(crate) fn change_authority(
+        &self,
+        address: C::Address,
+        context: &C,
+        working_set: &mut WorkingSet<C>,
+    ) -> anyhow::Result<CallResponse> {
+        anyhow::ensure!(
+            *context.sender() == self.get_authority(working_set),
+            "Only authority can change the authority"
+        );
+        self.authority.set(&address, working_set);
+        Ok(CallResponse::default())
+    }
+
+    pub(crate) fn rotate_sequencer(
+        &self,
+        sequencer: SequencerInfo,
+        context: &C,
+        working_set: &mut WorkingSet<C>,
+    ) -> anyhow::Result<CallResponse> {
+        anyhow::ensure!(
+            *context.sender() == self.get_authority(working_set),
+            "Only authority can rotate the sequencer"
+        );
+        anyhow::ensure!(
+            C::PublicKey::try_from(sequencer.public_key.as_slice()).is_ok(),
+            "Invalid sequencer public key"
+        );
+        if let Some(previous_sequencer) = self.sequencer.get(working_set) {
+            self.previous_sequencer
+                .set(&previous_sequencer, working_set);
+        }
+        self.sequencer.set(&sequencer, working_set);
+        Ok(CallResponse::default())
+    }
+}
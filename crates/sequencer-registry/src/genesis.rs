@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{Context, StateValueAccessor, WorkingSet};
+
+use crate::{SequencerInfo, SequencerRegistry};
+
+/// Config for the SequencerRegistry module.
+/// Sets the authority and optionally the initial sequencer.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub struct SequencerRegistryConfig<C: Context> {
+    /// Authority address.
+    /// This address is allowed to rotate the sequencer.
+    pub(crate) authority: C::Address,
+    /// The initial sequencer.
+    /// The keys of the rollup config are used until the first rotation if not set.
+    #[serde(default)]
+    pub(crate) sequencer: Option<SequencerInfo>,
+}
+
+impl<C: Context> SequencerRegistry<C> {
+    pub(crate) fn init_module(
+        &self,
+        config: &<Self as sov_modules_api::Module>::Config,
+        working_set: &mut WorkingSet<C>,
+    ) -> anyhow::Result<()> {
+        self.authority.set(&config.authority, working_set);
+        if let Some(sequencer) = &config.sequencer {
+            self.sequencer.set(sequencer, working_set);
+        }
+        Ok(())
+    }
+}
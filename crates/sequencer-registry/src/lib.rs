@@ -0,0 +1,98 @@
+mod call;
+mod genesis;
+mod registered;
+pub use call::*;
+pub use genesis::*;
+pub use registered::*;
+
+#[cfg(feature = "native")]
+mod query;
+#[cfg(feature = "native")]
+pub use query::*;
+
+#[cfg(all(test, feature = "native"))]
+mod tests;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{Context, ModuleInfo, StateValue, StateValueAccessor, WorkingSet};
+use sov_state::codec::BcsCodec;
+
+/// Keys and address of a sequencer
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Eq, PartialEq)]
+pub struct SequencerInfo {
+    /// Soft confirmation signing public key
+    #[serde(with = "hex::serde")]
+    pub public_key: Vec<u8>,
+    /// DA signing public key
+    #[serde(with = "hex::serde")]
+    pub da_pub_key: Vec<u8>,
+    /// EVM address receiving the priority fees of the L2 blocks of the sequencer
+    #[serde(with = "hex::serde")]
+    pub evm_address: [u8; 20],
+}
+
+#[derive(ModuleInfo, Clone)]
+pub struct SequencerRegistry<C: Context> {
+    /// Address of the SequencerRegistry module.
+    #[address]
+    address: C::Address,
+    /// Authority address.
+    /// This address is allowed to rotate the sequencer.
+    #[state]
+    pub(crate) authority: StateValue<C::Address, BcsCodec>,
+    /// The active sequencer.
+    /// Not set until the first rotation if not given at genesis, the keys of the rollup config are used meanwhile.
+    #[state]
+    pub(crate) sequencer: StateValue<SequencerInfo, BcsCodec>,
+    /// The sequencer replaced by the last rotation.
+    /// Its commitments of the L2 blocks it signed are still accepted after the rotation.
+    #[state]
+    pub(crate) previous_sequencer: StateValue<SequencerInfo, BcsCodec>,
+}
+
+impl<C: Context> SequencerRegistry<C> {
+    /// Returns the active sequencer, if any is registered.
+    pub fn active_sequencer(&self, working_set: &mut WorkingSet<C>) -> Option<SequencerInfo> {
+        self.sequencer.get(working_set)
+    }
+
+    /// Returns the sequencer replaced by the last rotation, if it was registered.
+    pub fn previous_sequencer(&self, working_set: &mut WorkingSet<C>) -> Option<SequencerInfo> {
+        self.previous_sequencer.get(working_set)
+    }
+}
+
+impl<C: Context> sov_modules_api::Module for SequencerRegistry<C> {
+    type Context = C;
+
+    type Config = SequencerRegistryConfig<C>;
+
+    type CallMessage = CallMessage<C>;
+
+    type Event = ();
+
+    fn call(
+        &self,
+        message: Self::CallMessage,
+        context: &Self::Context,
+        working_set: &mut WorkingSet<Self::Context>,
+    ) -> Result<sov_modules_api::CallResponse, sov_modules_api::Error> {
+        match message {
+            CallMessage::ChangeAuthority { new_authority } => {
+                Ok(self.change_authority(new_authority, context, working_set)?)
+            }
+            CallMessage::RotateSequencer { sequencer } => {
+                Ok(self.rotate_sequencer(sequencer, context, working_set)?)
+            }
+        }
+    }
+
+    fn genesis(
+        &self,
+        config: &Self::Config,
+        working_set: &mut WorkingSet<Self::Context>,
+    ) -> Result<(), sov_modules_api::Error> {
+        Ok(self.init_module(config, working_set)?)
+    }
+}
@@ -0,0 +1,37 @@
+use jsonrpsee::core::RpcResult;
+use sov_modules_api::macros::rpc_gen;
+use sov_modules_api::{Context, StateValueAccessor, WorkingSet};
+
+use crate::{SequencerInfo, SequencerRegistry};
+
+#[rpc_gen(client, server, namespace = "sequencerRegistry")]
+impl<C: Context> SequencerRegistry<C> {
+    #[rpc_method(name = "getSequencer")]
+    /// Get the active sequencer.
+    /// None if no sequencer was registered yet, the keys of the rollup config are used then.
+    pub fn get_sequencer(
+        &self,
+        working_set: &mut WorkingSet<C>,
+    ) -> RpcResult<Option<SequencerInfo>> {
+        Ok(self.sequencer.get(working_set))
+    }
+
+    #[rpc_method(name = "getPreviousSequencer")]
+    /// Get the sequencer replaced by the last rotation.
+    /// None if the keys of the rollup config were replaced, or no sequencer was rotated yet.
+    pub fn get_previous_sequencer(
+        &self,
+        working_set: &mut WorkingSet<C>,
+    ) -> RpcResult<Option<SequencerInfo>> {
+        Ok(self.previous_sequencer.get(working_set))
+    }
+
+    #[rpc_method(name = "getAuthority")]
+    /// Get the address allowed to rotate the sequencer.
+    pub fn get_authority_address(&self, working_set: &mut WorkingSet<C>) -> RpcResult<C::Address> {
+        Ok(self
+            .authority
+            .get(working_set)
+            .expect("Authority must be set"))
+    }
+}
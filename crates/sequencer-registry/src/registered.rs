@@ -0,0 +1,81 @@
+use sov_modules_api::{Context, WorkingSet};
+
+use crate::{SequencerInfo, SequencerRegistry};
+
+/// The active sequencer registered on chain and the one it replaced,
+/// as tracked by the nodes checking the commitments of the sequencer.
+///
+/// The last commitments of a rotated sequencer can land on DA after the rotation,
+/// they are attributed to the sequencer which signed their L2 blocks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegisteredSequencers {
+    /// The active sequencer, `None` while the keys of the rollup config are used.
+    pub active: Option<SequencerInfo>,
+    /// The sequencer replaced by the last rotation.
+    pub previous: Option<SequencerInfo>,
+}
+
+impl RegisteredSequencers {
+    /// Reads the registered sequencers from the state
+    pub fn from_state<C: Context>(working_set: &mut WorkingSet<C>) -> Self {
+        let registry = SequencerRegistry::<C>::default();
+        Self {
+            active: registry.active_sequencer(working_set),
+            previous: registry.previous_sequencer(working_set),
+        }
+    }
+
+    /// Records the sequencer active before an L2 block.
+    /// Returns the new sequencer if it was rotated.
+    pub fn update(&mut self, active: Option<SequencerInfo>) -> Option<&SequencerInfo> {
+        if active == self.active {
+            return None;
+        }
+        self.previous = std::mem::replace(&mut self.active, active);
+        self.active.as_ref()
+    }
+
+    /// DA public key of the registered sequencer with the soft confirmation public key
+    pub fn da_pub_key_of(&self, public_key: &[u8]) -> Option<&[u8]> {
+        self.iter()
+            .find(|sequencer| sequencer.public_key == public_key)
+            .map(|sequencer| sequencer.da_pub_key.as_slice())
+    }
+
+    /// Whether the DA public key is the one of a registered sequencer
+    pub fn is_da_pub_key(&self, da_pub_key: &[u8]) -> bool {
+        self.iter()
+            .any(|sequencer| sequencer.da_pub_key == da_pub_key)
+    }
+
+    /// DA public key the sequencer with the soft confirmation public key signs commitments
+    /// with at an L1 height. `config_public_key` is the soft confirmation public key of the
+    /// rollup config, and `config_da_pub_key` its DA public key valid at the L1 height.
+    /// `None` if it is neither the sequencer of the rollup config, nor a registered sequencer.
+    pub fn da_pub_key_at<'a>(
+        &'a self,
+        public_key: &[u8],
+        config_public_key: &[u8],
+        config_da_pub_key: &'a [u8],
+    ) -> Option<&'a [u8]> {
+        self.da_pub_key_of(public_key)
+            .or_else(|| (public_key == config_public_key).then_some(config_da_pub_key))
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &SequencerInfo> {
+        self.active.iter().chain(self.previous.iter())
+    }
+}
+
+/// Whether commitments of an L1 height may be signed with the DA public key.
+/// `config_da_pub_key` is the DA public key of the rollup config valid at the L1 height,
+/// so keys rotated away from are not accepted after the rotation. `registered` are the
+/// sequencers registered on chain, if they are tracked, whose keys are valid at any L1 height.
+pub fn is_sequencer_da_pub_key_at(
+    config_da_pub_key: &[u8],
+    registered: Option<&RegisteredSequencers>,
+    da_pub_key: &[u8],
+) -> bool {
+    config_da_pub_key == da_pub_key
+        || registered.is_some_and(|registered| registered.is_da_pub_key(da_pub_key))
+}
@@ -0,0 +1,136 @@
+use sov_modules_api::default_context::DefaultContext;
+use sov_modules_api::default_signature::private_key::DefaultPrivateKey;
+use sov_modules_api::utils::generate_address;
+use sov_modules_api::{Context, Module, PrivateKey, StateValueAccessor};
+
+use crate::call::CallMessage;
+use crate::tests::genesis_tests::{get_sequencer_registry, TEST_CONFIG};
+use crate::SequencerInfo;
+
+type C = DefaultContext;
+
+pub(crate) fn new_sequencer() -> SequencerInfo {
+    SequencerInfo {
+        public_key: borsh::to_vec(&DefaultPrivateKey::generate().pub_key()).unwrap(),
+        da_pub_key: vec![7; 33],
+        evm_address: [1; 20],
+    }
+}
+
+#[test]
+fn rotate_sequencer_and_authority() {
+    let (sequencer_registry, mut working_set) = get_sequencer_registry(&TEST_CONFIG);
+
+    let sequencer_address = generate_address::<C>("sequencer");
+    let context = C::new(TEST_CONFIG.authority, sequencer_address, 1);
+
+    let sequencer = new_sequencer();
+    sequencer_registry
+        .call(
+            CallMessage::RotateSequencer {
+                sequencer: sequencer.clone(),
+            },
+            &context,
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(
+        sequencer_registry.active_sequencer(&mut working_set),
+        Some(sequencer.clone())
+    );
+
+    let new_authority = generate_address::<C>("braveNewWorld");
+    sequencer_registry
+        .call(
+            CallMessage::ChangeAuthority { new_authority },
+            &context,
+            &mut working_set,
+        )
+        .unwrap();
+
+    // after the authority is changed the old authority cannot rotate the sequencer
+    let rotate_message = CallMessage::RotateSequencer {
+        sequencer: new_sequencer(),
+    };
+    assert!(sequencer_registry
+        .call(rotate_message.clone(), &context, &mut working_set)
+        .is_err());
+    assert_eq!(
+        sequencer_registry.active_sequencer(&mut working_set),
+        Some(sequencer)
+    );
+
+    let context = C::new(new_authority, sequencer_address, 1);
+    sequencer_registry
+        .call(rotate_message, &context, &mut working_set)
+        .unwrap();
+    assert_eq!(
+        sequencer_registry.authority.get(&mut working_set).unwrap(),
+        new_authority
+    );
+}
+
+#[test]
+fn rotate_sequencer_rejects_invalid_public_key() {
+    let (sequencer_registry, mut working_set) = get_sequencer_registry(&TEST_CONFIG);
+
+    let sequencer_address = generate_address::<C>("sequencer");
+    let context = C::new(TEST_CONFIG.authority, sequencer_address, 1);
+
+    let sequencer = SequencerInfo {
+        public_key: vec![1, 2, 3],
+        ..new_sequencer()
+    };
+    assert!(sequencer_registry
+        .call(
+            CallMessage::RotateSequencer { sequencer },
+            &context,
+            &mut working_set,
+        )
+        .is_err());
+    assert_eq!(sequencer_registry.active_sequencer(&mut working_set), None);
+}
+
+#[test]
+fn rotate_sequencer_keeps_previous_sequencer() {
+    let (sequencer_registry, mut working_set) = get_sequencer_registry(&TEST_CONFIG);
+
+    let sequencer_address = generate_address::<C>("sequencer");
+    let context = C::new(TEST_CONFIG.authority, sequencer_address, 1);
+
+    let first_sequencer = new_sequencer();
+    sequencer_registry
+        .call(
+            CallMessage::RotateSequencer {
+                sequencer: first_sequencer.clone(),
+            },
+            &context,
+            &mut working_set,
+        )
+        .unwrap();
+    // The keys of the rollup config were replaced
+    assert_eq!(
+        sequencer_registry.previous_sequencer(&mut working_set),
+        None
+    );
+
+    let second_sequencer = new_sequencer();
+    sequencer_registry
+        .call(
+            CallMessage::RotateSequencer {
+                sequencer: second_sequencer.clone(),
+            },
+            &context,
+            &mut working_set,
+        )
+        .unwrap();
+    assert_eq!(
+        sequencer_registry.active_sequencer(&mut working_set),
+        Some(second_sequencer)
+    );
+    assert_eq!(
+        sequencer_registry.previous_sequencer(&mut working_set),
+        Some(first_sequencer)
+    );
+}
@@ -0,0 +1,75 @@
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use sov_modules_api::default_context::DefaultContext;
+use sov_modules_api::{Module, Spec, StateValueAccessor, WorkingSet};
+use sov_prover_storage_manager::new_orphan_storage;
+
+use crate::{SequencerRegistry, SequencerRegistryConfig};
+
+type C = DefaultContext;
+
+lazy_static! {
+    pub(crate) static ref TEST_CONFIG: SequencerRegistryConfig<C> = SequencerRegistryConfig {
+        authority: <DefaultContext as Spec>::Address::from_str(
+            "sov1l6n2cku82yfqld30lanm2nfw43n2auc8clw7r5u5m6s7p8jrm4zqrr8r94"
+        )
+        .unwrap(),
+        sequencer: None,
+    };
+}
+
+#[test]
+fn genesis_data() {
+    let (sequencer_registry, mut working_set) = get_sequencer_registry(&TEST_CONFIG);
+
+    assert_eq!(
+        sequencer_registry.authority.get(&mut working_set).unwrap(),
+        TEST_CONFIG.authority
+    );
+    // the keys of the rollup config are used until the first rotation
+    assert_eq!(sequencer_registry.active_sequencer(&mut working_set), None);
+}
+
+#[test]
+fn genesis_config_from_json() {
+    let config: SequencerRegistryConfig<C> = serde_json::from_str(
+        r#"{
+            "authority": "sov1l6n2cku82yfqld30lanm2nfw43n2auc8clw7r5u5m6s7p8jrm4zqrr8r94",
+            "sequencer": {
+                "public_key": "204040e364c10f2bec9c1fe500a1cd4c247c89d650a01ed7e82caba867877c21",
+                "da_pub_key": "0000000000000000000000000000000000000000000000000000000000000000",
+                "evm_address": "f39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+            }
+        }"#,
+    )
+    .unwrap();
+
+    let (sequencer_registry, mut working_set) = get_sequencer_registry(&config);
+
+    let sequencer = sequencer_registry
+        .active_sequencer(&mut working_set)
+        .unwrap();
+    assert_eq!(
+        hex::encode(sequencer.public_key),
+        "204040e364c10f2bec9c1fe500a1cd4c247c89d650a01ed7e82caba867877c21"
+    );
+    assert_eq!(sequencer.da_pub_key, vec![0; 32]);
+    assert_eq!(
+        hex::encode(sequencer.evm_address),
+        "f39fd6e51aad88f6f4ce6ab8827279cfffb92266"
+    );
+}
+
+pub(crate) fn get_sequencer_registry(
+    config: &SequencerRegistryConfig<C>,
+) -> (SequencerRegistry<C>, WorkingSet<DefaultContext>) {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let mut working_set = WorkingSet::new(new_orphan_storage(tmpdir.path()).unwrap());
+    let sequencer_registry = SequencerRegistry::<C>::default();
+    sequencer_registry
+        .genesis(config, &mut working_set)
+        .unwrap();
+
+    (sequencer_registry, working_set)
+}
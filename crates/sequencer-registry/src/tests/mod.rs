@@ -0,0 +1,6 @@
+#[cfg(test)]
+mod call_tests;
+#[cfg(test)]
+mod genesis_tests;
+#[cfg(test)]
+mod registered_tests;
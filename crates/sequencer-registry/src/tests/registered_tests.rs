@@ -0,0 +1,127 @@
+use sov_modules_api::default_context::DefaultContext;
+use sov_modules_api::utils::generate_address;
+use sov_modules_api::{Context, Module};
+
+use crate::call::CallMessage;
+use crate::tests::call_tests::new_sequencer;
+use crate::tests::genesis_tests::{get_sequencer_registry, TEST_CONFIG};
+use crate::{is_sequencer_da_pub_key_at, RegisteredSequencers, SequencerInfo};
+
+type C = DefaultContext;
+
+fn sequencer(id: u8) -> SequencerInfo {
+    SequencerInfo {
+        public_key: vec![id; 32],
+        da_pub_key: vec![id; 33],
+        evm_address: [id; 20],
+    }
+}
+
+#[test]
+fn attributes_commitments_of_the_previous_sequencer() {
+    let mut sequencers = RegisteredSequencers::default();
+    assert_eq!(sequencers.update(None), None);
+    assert_eq!(sequencers.da_pub_key_of(&[1; 32]), None);
+
+    assert_eq!(sequencers.update(Some(sequencer(1))), Some(&sequencer(1)));
+    // Not rotated again by the following L2 blocks
+    assert_eq!(sequencers.update(Some(sequencer(1))), None);
+    assert_eq!(sequencers.update(Some(sequencer(2))), Some(&sequencer(2)));
+
+    // The last commitments of the rotated sequencer are still attributed to it
+    assert_eq!(sequencers.da_pub_key_of(&[1; 32]), Some(&[1; 33][..]));
+    assert_eq!(sequencers.da_pub_key_of(&[2; 32]), Some(&[2; 33][..]));
+    assert!(sequencers.is_da_pub_key(&[1; 33]));
+    assert!(sequencers.is_da_pub_key(&[2; 33]));
+
+    sequencers.update(Some(sequencer(3)));
+    assert_eq!(sequencers.da_pub_key_of(&[1; 32]), None);
+    assert!(!sequencers.is_da_pub_key(&[1; 33]));
+}
+
+#[test]
+fn accepts_the_da_pub_key_valid_at_the_l1_height() {
+    // The DA public key of the rollup config is rotated at L1 height 100
+    let config_da_pub_key_at = |l1_height: u64| -> Vec<u8> {
+        if l1_height < 100 {
+            vec![1; 33]
+        } else {
+            vec![2; 33]
+        }
+    };
+
+    assert!(is_sequencer_da_pub_key_at(
+        &config_da_pub_key_at(99),
+        None,
+        &[1; 33]
+    ));
+    assert!(!is_sequencer_da_pub_key_at(
+        &config_da_pub_key_at(99),
+        None,
+        &[2; 33]
+    ));
+    // The rotated key is not accepted from the activation height on
+    assert!(!is_sequencer_da_pub_key_at(
+        &config_da_pub_key_at(100),
+        None,
+        &[1; 33]
+    ));
+    assert!(is_sequencer_da_pub_key_at(
+        &config_da_pub_key_at(100),
+        None,
+        &[2; 33]
+    ));
+
+    // Registered sequencers sign with their own keys at any L1 height
+    let mut sequencers = RegisteredSequencers::default();
+    sequencers.update(Some(sequencer(3)));
+    assert!(is_sequencer_da_pub_key_at(
+        &config_da_pub_key_at(99),
+        Some(&sequencers),
+        &[3; 33]
+    ));
+    assert_eq!(
+        sequencers.da_pub_key_at(&[9; 32], &[9; 32], &config_da_pub_key_at(100)),
+        Some(&[2; 33][..])
+    );
+    assert_eq!(
+        sequencers.da_pub_key_at(&[3; 32], &[9; 32], &config_da_pub_key_at(100)),
+        Some(&[3; 33][..])
+    );
+    assert_eq!(
+        sequencers.da_pub_key_at(&[4; 32], &[9; 32], &config_da_pub_key_at(100)),
+        None
+    );
+}
+
+#[test]
+fn reads_registered_sequencers_from_state() {
+    let (sequencer_registry, mut working_set) = get_sequencer_registry(&TEST_CONFIG);
+    assert_eq!(
+        RegisteredSequencers::from_state(&mut working_set),
+        RegisteredSequencers::default()
+    );
+
+    let sequencer_address = generate_address::<C>("sequencer");
+    let context = C::new(TEST_CONFIG.authority, sequencer_address, 1);
+    let mut tracked = RegisteredSequencers::default();
+    for id in [1, 2] {
+        let sequencer = SequencerInfo {
+            public_key: new_sequencer().public_key,
+            ..sequencer(id)
+        };
+        sequencer_registry
+            .call(
+                CallMessage::RotateSequencer {
+                    sequencer: sequencer.clone(),
+                },
+                &context,
+                &mut working_set,
+            )
+            .unwrap();
+        tracked.update(Some(sequencer));
+    }
+
+    // A node restarting reads what it tracked while executing the L2 blocks
+    assert_eq!(RegisteredSequencers::from_state(&mut working_set), tracked);
+}
@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+
+use serde::Serialize;
+
+use crate::metrics::{
+    SEQUENCER_BLOCK_BUILD_SECONDS, SEQUENCER_BLOCK_GAS_USED, SEQUENCER_BLOCK_STATE_DIFF_BYTES,
+    SEQUENCER_BLOCK_TX_COUNT,
+};
+
+/// Number of the most recent L2 blocks whose stats are kept
+pub(crate) const BLOCK_STATS_WINDOW: usize = 1000;
+
+/// Number of L2 blocks needed before recommendations are made
+const MIN_BLOCKS_FOR_RECOMMENDATIONS: usize = 20;
+
+/// Share in percent of the block gas limit the busy blocks, at the 95th percentile of gas used,
+/// are expected to use at most
+const TARGET_GAS_UTILIZATION_PERCENT: u64 = 80;
+
+/// Recommended block gas limits are rounded up to a multiple of it
+const GAS_LIMIT_GRANULARITY: u64 = 1_000_000;
+
+/// Usage of a produced L2 block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BlockBuildStats {
+    pub l2_height: u64,
+    pub gas_used: u64,
+    pub gas_limit: u64,
+    /// Compressed size the block added to the state diff of the pending commitment
+    pub state_diff_bytes: u64,
+    /// Number of EVM txs of the block, system txs excluded
+    pub tx_count: u64,
+    /// Time it took to build, execute and commit the block
+    pub build_time_ms: u64,
+}
+
+/// Settings recommended by the usage of the recent L2 blocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TuningRecommendations {
+    /// Current block gas limit of the chain
+    pub block_gas_limit: u64,
+    /// Block gas limit leaving headroom to the busy blocks.
+    /// Never below the current one, unused gas does not cost anything.
+    pub recommended_block_gas_limit: u64,
+    /// Current `min_soft_confirmations_per_commitment` of the sequencer
+    pub min_soft_confirmations_per_commitment: u64,
+    /// Number of L2 blocks whose state diffs fill a commitment on average,
+    /// so commitments are not made with less data than they could carry
+    pub recommended_min_soft_confirmations_per_commitment: u64,
+}
+
+/// Response of `citrea_getBlockBuildStats`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BlockBuildReport {
+    /// Stats of the recent L2 blocks, oldest first
+    pub blocks: Vec<BlockBuildStats>,
+    /// Not made until enough L2 blocks are produced
+    pub recommendations: Option<TuningRecommendations>,
+}
+
+/// Keeps the stats of the most recent L2 blocks built by the sequencer.
+#[derive(Debug)]
+pub(crate) struct BlockStatsTracker {
+    blocks: VecDeque<BlockBuildStats>,
+    min_soft_confirmations_per_commitment: u64,
+    max_commitment_state_diff_size: u64,
+}
+
+impl BlockStatsTracker {
+    pub(crate) fn new(
+        min_soft_confirmations_per_commitment: u64,
+        max_commitment_state_diff_size: u64,
+    ) -> Self {
+        Self {
+            blocks: VecDeque::with_capacity(BLOCK_STATS_WINDOW),
+            min_soft_confirmations_per_commitment,
+            max_commitment_state_diff_size,
+        }
+    }
+
+    /// Records the stats of a new L2 block and exports them as metrics
+    pub(crate) fn record(&mut self, stats: BlockBuildStats) {
+        SEQUENCER_BLOCK_GAS_USED.set(stats.gas_used as i64);
+        SEQUENCER_BLOCK_STATE_DIFF_BYTES.set(stats.state_diff_bytes as i64);
+        SEQUENCER_BLOCK_TX_COUNT.set(stats.tx_count as i64);
+        SEQUENCER_BLOCK_BUILD_SECONDS.observe(stats.build_time_ms as f64 / 1000.0);
+
+        if self.blocks.len() == BLOCK_STATS_WINDOW {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back(stats);
+    }
+
+    /// Report of the last `count` L2 blocks, recommendations are made from all the kept blocks
+    pub(crate) fn report(&self, count: usize) -> BlockBuildReport {
+        let skipped = self.blocks.len().saturating_sub(count);
+        BlockBuildReport {
+            blocks: self.blocks.iter().skip(skipped).copied().collect(),
+            recommendations: self.recommendations(),
+        }
+    }
+
+    fn recommendations(&self) -> Option<TuningRecommendations> {
+        if self.blocks.len() < MIN_BLOCKS_FOR_RECOMMENDATIONS {
+            return None;
+        }
+        let block_gas_limit = self.blocks.back()?.gas_limit;
+
+        let busy_gas_used = percentile(self.blocks.iter().map(|block| block.gas_used), 95);
+        let needed_gas_limit = (busy_gas_used * 100).div_ceil(TARGET_GAS_UTILIZATION_PERCENT);
+        let recommended_block_gas_limit = block_gas_limit
+            .max(needed_gas_limit.div_ceil(GAS_LIMIT_GRANULARITY) * GAS_LIMIT_GRANULARITY);
+
+        // Compression is better on larger diffs, so this is a lower bound
+        let total_state_diff_bytes: u64 = self.blocks.iter().map(|b| b.state_diff_bytes).sum();
+        let avg_state_diff_bytes = (total_state_diff_bytes / self.blocks.len() as u64).max(1);
+        let recommended_min_soft_confirmations_per_commitment =
+            (self.max_commitment_state_diff_size / avg_state_diff_bytes).max(1);
+
+        Some(TuningRecommendations {
+            block_gas_limit,
+            recommended_block_gas_limit,
+            min_soft_confirmations_per_commitment: self.min_soft_confirmations_per_commitment,
+            recommended_min_soft_confirmations_per_commitment,
+        })
+    }
+}
+
+/// Nearest-rank percentile of the values
+fn percentile(values: impl Iterator<Item = u64>, percent: usize) -> u64 {
+    let mut values: Vec<u64> = values.collect();
+    if values.is_empty() {
+        return 0;
+    }
+    values.sort_unstable();
+    let rank = (values.len() * percent).div_ceil(100).max(1);
+    values[rank - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(l2_height: u64, gas_used: u64, state_diff_bytes: u64) -> BlockBuildStats {
+        BlockBuildStats {
+            l2_height,
+            gas_used,
+            gas_limit: 30_000_000,
+            state_diff_bytes,
+            tx_count: 1,
+            build_time_ms: 10,
+        }
+    }
+
+    #[test]
+    fn test_keeps_recent_blocks() {
+        let mut tracker = BlockStatsTracker::new(10, 300 * 1024);
+        for l2_height in 0..BLOCK_STATS_WINDOW as u64 + 5 {
+            tracker.record(block(l2_height, 21_000, 100));
+        }
+
+        let report = tracker.report(BLOCK_STATS_WINDOW * 2);
+        assert_eq!(report.blocks.len(), BLOCK_STATS_WINDOW);
+        assert_eq!(report.blocks[0].l2_height, 5);
+
+        let report = tracker.report(2);
+        let heights: Vec<_> = report.blocks.iter().map(|b| b.l2_height).collect();
+        assert_eq!(
+            heights,
+            vec![BLOCK_STATS_WINDOW as u64 + 3, BLOCK_STATS_WINDOW as u64 + 4]
+        );
+    }
+
+    #[test]
+    fn test_recommendations() {
+        let mut tracker = BlockStatsTracker::new(10, 100_000);
+        for l2_height in 0..MIN_BLOCKS_FOR_RECOMMENDATIONS as u64 - 1 {
+            tracker.record(block(l2_height, 1_000_000, 1000));
+        }
+        assert_eq!(tracker.report(0).recommendations, None);
+
+        // Quiet blocks keep the gas limit
+        tracker.record(block(19, 1_000_000, 1000));
+        assert_eq!(
+            tracker.report(0).recommendations,
+            Some(TuningRecommendations {
+                block_gas_limit: 30_000_000,
+                recommended_block_gas_limit: 30_000_000,
+                min_soft_confirmations_per_commitment: 10,
+                recommended_min_soft_confirmations_per_commitment: 100,
+            })
+        );
+
+        // Busy blocks ask for headroom
+        for l2_height in 20..40 {
+            tracker.record(block(l2_height, 29_000_000, 1000));
+        }
+        let recommendations = tracker.report(0).recommendations.unwrap();
+        assert_eq!(recommendations.recommended_block_gas_limit, 37_000_000);
+    }
+
+    #[test]
+    fn test_percentile() {
+        assert_eq!(percentile([].into_iter(), 95), 0);
+        assert_eq!(percentile([5].into_iter(), 95), 5);
+        assert_eq!(percentile((1..=100).rev(), 95), 95);
+        assert_eq!(percentile((1..=100).rev(), 50), 50);
+    }
+}
@@ -0,0 +1,149 @@
+use citrea_evm::RlpEvmTransaction;
+use reth_primitives::{keccak256, TxHash, B256};
+
+/// Max. number of txs in a bundle
+pub(crate) const MAX_BUNDLE_TXS: usize = 32;
+/// Max. number of bundles waiting to be included
+const MAX_BUNDLES: usize = 1024;
+/// Max. number of L2 blocks a bundle without an L2 height is tried in
+const MAX_BUNDLE_ATTEMPTS: u32 = 25;
+
+/// An ordered set of txs which are included in a single L2 block all together or not at all
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Bundle {
+    pub(crate) hash: B256,
+    pub(crate) txs: Vec<RlpEvmTransaction>,
+    pub(crate) tx_hashes: Vec<TxHash>,
+    /// L2 height the bundle is for. The next L2 blocks if not set.
+    pub(crate) l2_height: Option<u64>,
+    /// Number of L2 blocks the bundle was tried in
+    attempts: u32,
+}
+
+impl Bundle {
+    pub(crate) fn new(txs: Vec<(TxHash, RlpEvmTransaction)>, l2_height: Option<u64>) -> Self {
+        let (tx_hashes, txs): (Vec<_>, Vec<_>) = txs.into_iter().unzip();
+        Self {
+            hash: keccak256(tx_hashes.concat()),
+            txs,
+            tx_hashes,
+            l2_height,
+            attempts: 0,
+        }
+    }
+}
+
+/// Keeps the bundles sent to the sequencer.
+///
+/// Bundles are private: unlike the txs of the mempool, they are not persisted,
+/// gossiped to other sequencers or reported to tx status subscribers.
+#[derive(Debug, Default)]
+pub(crate) struct BundlePool {
+    bundles: Vec<Bundle>,
+}
+
+impl BundlePool {
+    pub(crate) fn add(&mut self, bundle: Bundle) -> anyhow::Result<()> {
+        if self.bundles.iter().any(|b| b.hash == bundle.hash) {
+            anyhow::bail!("bundle already known");
+        }
+        if self.bundles.len() >= MAX_BUNDLES {
+            anyhow::bail!("bundle pool is full");
+        }
+        self.bundles.push(bundle);
+        Ok(())
+    }
+
+    /// Removes and returns the bundles to try in the L2 block at `l2_height`, in arrival order.
+    /// Bundles for earlier L2 heights can't be included anymore and are dropped.
+    pub(crate) fn take(&mut self, l2_height: u64) -> Vec<Bundle> {
+        let (bundles, later_bundles) = std::mem::take(&mut self.bundles)
+            .into_iter()
+            .filter(|bundle| bundle.l2_height.map_or(true, |height| height >= l2_height))
+            .partition(|bundle| bundle.l2_height.map_or(true, |height| height == l2_height));
+        self.bundles = later_bundles;
+        bundles
+    }
+
+    /// Puts back the bundles which could not be included in the L2 block they were taken for.
+    /// Bundles without an L2 height are tried again in the next L2 blocks, ahead of the bundles
+    /// which arrived after them, until they ran out of attempts.
+    pub(crate) fn retry(&mut self, bundles: Vec<Bundle>) {
+        let mut retried: Vec<_> = bundles
+            .into_iter()
+            .filter(|bundle| bundle.l2_height.is_none())
+            .filter_map(|mut bundle| {
+                bundle.attempts += 1;
+                (bundle.attempts < MAX_BUNDLE_ATTEMPTS).then_some(bundle)
+            })
+            .collect();
+        retried.truncate(MAX_BUNDLES.saturating_sub(self.bundles.len()));
+        retried.append(&mut self.bundles);
+        self.bundles = retried;
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.bundles.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(tx: u8, l2_height: Option<u64>) -> Bundle {
+        Bundle::new(
+            vec![(
+                TxHash::with_last_byte(tx),
+                RlpEvmTransaction { rlp: vec![tx] },
+            )],
+            l2_height,
+        )
+    }
+
+    #[test]
+    fn takes_bundles_of_l2_height() {
+        let mut pool = BundlePool::default();
+        pool.add(bundle(1, Some(4))).unwrap();
+        pool.add(bundle(2, None)).unwrap();
+        pool.add(bundle(3, Some(5))).unwrap();
+        pool.add(bundle(4, Some(6))).unwrap();
+
+        assert_eq!(pool.take(5), vec![bundle(2, None), bundle(3, Some(5))]);
+        assert_eq!(pool.take(6), vec![bundle(4, Some(6))]);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn retries_bundles_without_l2_height() {
+        let mut pool = BundlePool::default();
+        pool.add(bundle(1, None)).unwrap();
+        pool.add(bundle(2, Some(5))).unwrap();
+        let failed = pool.take(5);
+        pool.add(bundle(3, None)).unwrap();
+
+        // Only the bundle without an L2 height is tried again, before the later bundle
+        pool.retry(failed);
+        let taken = pool.take(6);
+        assert_eq!(
+            taken.iter().map(|b| b.hash).collect::<Vec<_>>(),
+            vec![bundle(1, None).hash, bundle(3, None).hash]
+        );
+
+        // Until it ran out of attempts
+        let mut failed = taken;
+        for l2_height in 7..7 + MAX_BUNDLE_ATTEMPTS as u64 {
+            pool.retry(failed);
+            failed = pool.take(l2_height);
+        }
+        pool.retry(failed);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn rejects_known_bundles() {
+        let mut pool = BundlePool::default();
+        pool.add(bundle(1, None)).unwrap();
+        assert!(pool.add(bundle(1, None)).is_err());
+    }
+}
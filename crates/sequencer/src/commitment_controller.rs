@@ -1,28 +1,128 @@
 use std::cmp;
 use std::ops::RangeInclusive;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use rs_merkle::algorithms::Sha256;
 use rs_merkle::MerkleTree;
+use serde::Serialize;
 use sov_db::ledger_db::SequencerLedgerOps;
 use sov_db::schema::types::BatchNumber;
 use sov_rollup_interface::da::SequencerCommitment;
 use tracing::{debug, instrument};
 
+use crate::config::CommitmentPolicyConfig;
+
 #[derive(Clone, Debug)]
 pub struct CommitmentInfo {
     /// L2 heights to commit
     pub l2_height_range: RangeInclusive<BatchNumber>,
 }
 
+/// Condition which triggered a commitment
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitmentTrigger {
+    /// Enough soft confirmations to commit
+    SoftConfirmationCount,
+    /// Compressed state diff size threshold reached
+    StateDiffSize,
+    /// Max. interval since the last commitment passed
+    Interval,
+    /// A commitment has been deferred because of the DA fee rate for too many L1 blocks
+    DeferralDeadline,
+    /// Sequencer is shutting down
+    Shutdown,
+}
+
+impl CommitmentTrigger {
+    /// Label of the trigger in metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommitmentTrigger::SoftConfirmationCount => "soft_confirmation_count",
+            CommitmentTrigger::StateDiffSize => "state_diff_size",
+            CommitmentTrigger::Interval => "interval",
+            CommitmentTrigger::DeferralDeadline => "deferral_deadline",
+            CommitmentTrigger::Shutdown => "shutdown",
+        }
+    }
+}
+
+/// State of the sequencer which the commitment policy is evaluated against
+#[derive(Clone, Copy, Debug)]
+pub struct CommitmentConditions {
+    /// Whether the compressed state diff since the last commitment reached the threshold
+    pub state_diff_threshold_reached: bool,
+    /// Time passed since the last commitment
+    pub since_last_commitment: Duration,
+    /// Current DA fee rate, if known
+    pub da_fee_rate: Option<u128>,
+    /// L1 blocks passed since a due commitment was first deferred
+    pub l1_blocks_deferred: u64,
+}
+
+/// What to do about the uncommitted soft confirmations
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitmentDecision {
+    /// Commit now because of the trigger
+    Commit(CommitmentTrigger),
+    /// A commitment is due because of the trigger, but deferred because of the DA fee rate
+    Defer(CommitmentTrigger),
+}
+
+/// Deferral state of the commitments, exposed over RPC
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitmentDeferral {
+    /// L1 height at which the due commitment was first deferred, if it is deferred
+    pub deferred_since_l1_height: Option<u64>,
+    /// DA fee rate seen by the last commitment check, if known
+    pub last_da_fee_rate: Option<u128>,
+}
+
 /// Checks if the sequencer should commit
-/// Returns none if the commitable L2 block range is shorter than `min_soft_confirmations_per_commitment`
-/// Returns `CommitmentInfo` if the sequencer should commit
+/// Returns none if no commitment policy condition is met.
+/// Returns `CommitmentInfo` and whether to commit now or defer the commitment
+/// because of the DA fee rate otherwise.
 #[instrument(level = "debug", skip_all, fields(prev_l1_height), err)]
 pub fn get_commitment_info<T: SequencerLedgerOps>(
     ledger_db: &T,
     min_soft_confirmations_per_commitment: u64,
-    state_diff_threshold_reached: bool,
+    policy: &CommitmentPolicyConfig,
+    conditions: CommitmentConditions,
+) -> anyhow::Result<Option<(CommitmentInfo, CommitmentDecision)>> {
+    let Some(commitment_info) = get_uncommitted_l2_range(ledger_db)? else {
+        return Ok(None);
+    };
+
+    let l2_start = commitment_info.l2_height_range.start().0;
+    let l2_end = commitment_info.l2_height_range.end().0;
+    let l2_range_length = 1 + l2_end - l2_start;
+    let Some(decision) = commitment_decision(
+        l2_range_length,
+        min_soft_confirmations_per_commitment,
+        policy,
+        conditions,
+    ) else {
+        return Ok(None);
+    };
+
+    match decision {
+        CommitmentDecision::Commit(trigger) => {
+            debug!("Commitment triggered by {}", trigger.as_str())
+        }
+        CommitmentDecision::Defer(trigger) => debug!(
+            "Commitment triggered by {} is deferred for {} L1 blocks",
+            trigger.as_str(),
+            conditions.l1_blocks_deferred
+        ),
+    }
+
+    Ok(Some((commitment_info, decision)))
+}
+
+/// Returns the L2 blocks which are neither committed nor in a pending commitment, if any
+pub fn get_uncommitted_l2_range<T: SequencerLedgerOps>(
+    ledger_db: &T,
 ) -> anyhow::Result<Option<CommitmentInfo>> {
     let Some((head_soft_batch_number, _)) = ledger_db.get_head_soft_batch()? else {
         // No soft batches have been created yet.
@@ -48,25 +148,54 @@ pub fn get_commitment_info<T: SequencerLedgerOps>(
         return Ok(None);
     }
 
-    let l2_start = last_committed_l2_height.0 + 1;
-    let l2_end = head_soft_batch_number.0;
+    Ok(Some(CommitmentInfo {
+        l2_height_range: BatchNumber(last_committed_l2_height.0 + 1)..=head_soft_batch_number,
+    }))
+}
 
-    let l2_range_length = 1 + l2_end - l2_start;
-    if !state_diff_threshold_reached && (l2_range_length < min_soft_confirmations_per_commitment) {
-        return Ok(None);
+/// Returns the decision of the commitment policy for `l2_range_length`
+/// uncommitted soft confirmations, if any of its conditions is met
+fn commitment_decision(
+    l2_range_length: u64,
+    min_soft_confirmations_per_commitment: u64,
+    policy: &CommitmentPolicyConfig,
+    conditions: CommitmentConditions,
+) -> Option<CommitmentDecision> {
+    // A full state diff has to be committed whatever the fee rate is,
+    // it can't grow any larger
+    if conditions.state_diff_threshold_reached {
+        return Some(CommitmentDecision::Commit(CommitmentTrigger::StateDiffSize));
     }
 
-    if l2_range_length >= min_soft_confirmations_per_commitment {
-        debug!("Enough soft confirmations to submit commitment");
-    }
+    let trigger = if l2_range_length >= min_soft_confirmations_per_commitment {
+        CommitmentTrigger::SoftConfirmationCount
+    } else if policy.max_interval_ms.is_some_and(|max_interval_ms| {
+        conditions.since_last_commitment >= Duration::from_millis(max_interval_ms)
+    }) {
+        CommitmentTrigger::Interval
+    } else {
+        return None;
+    };
 
-    if state_diff_threshold_reached {
-        debug!("State diff threshold reached. Committing...");
+    if let (Some(max_da_fee_rate), Some(da_fee_rate)) =
+        (policy.max_da_fee_rate, conditions.da_fee_rate)
+    {
+        if da_fee_rate > max_da_fee_rate {
+            // Soft confirmations can't stay uncommitted forever
+            if conditions.l1_blocks_deferred >= policy.max_deferred_l1_blocks {
+                return Some(CommitmentDecision::Commit(
+                    CommitmentTrigger::DeferralDeadline,
+                ));
+            }
+            debug!(
+                "DA fee rate {} is above {}, deferring commitment",
+                da_fee_rate, max_da_fee_rate
+            );
+            return Some(CommitmentDecision::Defer(trigger));
+        }
     }
 
-    Ok(Some(CommitmentInfo {
-        l2_height_range: BatchNumber(l2_start)..=BatchNumber(l2_end),
-    }))
+    Some(CommitmentDecision::Commit(trigger))
 }
 
 #[instrument(level = "debug", skip_all, err)]
@@ -91,3 +220,104 @@ pub fn get_commitment(
         l2_end_block_number: commitment_info.l2_height_range.end().0,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conditions(
+        state_diff_threshold_reached: bool,
+        since_last_commitment_ms: u64,
+        da_fee_rate: Option<u128>,
+    ) -> CommitmentConditions {
+        CommitmentConditions {
+            state_diff_threshold_reached,
+            since_last_commitment: Duration::from_millis(since_last_commitment_ms),
+            da_fee_rate,
+            l1_blocks_deferred: 0,
+        }
+    }
+
+    #[test]
+    fn test_commitment_decision() {
+        let policy = CommitmentPolicyConfig {
+            max_compressed_state_diff_size: 1024,
+            max_interval_ms: Some(1000),
+            max_da_fee_rate: Some(50),
+            max_deferred_l1_blocks: 3,
+        };
+
+        assert_eq!(
+            commitment_decision(3, 10, &policy, conditions(false, 0, Some(1))),
+            None
+        );
+        assert_eq!(
+            commitment_decision(10, 10, &policy, conditions(false, 0, Some(1))),
+            Some(CommitmentDecision::Commit(
+                CommitmentTrigger::SoftConfirmationCount
+            ))
+        );
+        assert_eq!(
+            commitment_decision(3, 10, &policy, conditions(false, 1000, Some(1))),
+            Some(CommitmentDecision::Commit(CommitmentTrigger::Interval))
+        );
+        assert_eq!(
+            commitment_decision(3, 10, &policy, conditions(true, 0, Some(1))),
+            Some(CommitmentDecision::Commit(CommitmentTrigger::StateDiffSize))
+        );
+
+        // Deferred while the DA fee rate is too high, unless the state diff is full
+        assert_eq!(
+            commitment_decision(10, 10, &policy, conditions(false, 1000, Some(51))),
+            Some(CommitmentDecision::Defer(
+                CommitmentTrigger::SoftConfirmationCount
+            ))
+        );
+        assert_eq!(
+            commitment_decision(3, 10, &policy, conditions(true, 0, Some(51))),
+            Some(CommitmentDecision::Commit(CommitmentTrigger::StateDiffSize))
+        );
+        // Not deferred anymore once the deferral deadline is reached
+        let deferred_for = |l1_blocks_deferred| CommitmentConditions {
+            l1_blocks_deferred,
+            ..conditions(false, 0, Some(51))
+        };
+        assert_eq!(
+            commitment_decision(10, 10, &policy, deferred_for(2)),
+            Some(CommitmentDecision::Defer(
+                CommitmentTrigger::SoftConfirmationCount
+            ))
+        );
+        assert_eq!(
+            commitment_decision(10, 10, &policy, deferred_for(3)),
+            Some(CommitmentDecision::Commit(
+                CommitmentTrigger::DeferralDeadline
+            ))
+        );
+        // Nothing to defer if no condition is met
+        assert_eq!(commitment_decision(3, 10, &policy, deferred_for(3)), None);
+        // Not deferred if the fee rate is unknown
+        assert_eq!(
+            commitment_decision(10, 10, &policy, conditions(false, 0, None)),
+            Some(CommitmentDecision::Commit(
+                CommitmentTrigger::SoftConfirmationCount
+            ))
+        );
+    }
+
+    #[test]
+    fn test_commitment_trigger_default_policy() {
+        let policy = CommitmentPolicyConfig::default();
+
+        assert_eq!(
+            commitment_decision(3, 10, &policy, conditions(false, u64::MAX, Some(1000))),
+            None
+        );
+        assert_eq!(
+            commitment_decision(10, 10, &policy, conditions(false, 0, Some(1000))),
+            Some(CommitmentDecision::Commit(
+                CommitmentTrigger::SoftConfirmationCount
+            ))
+        );
+    }
+}
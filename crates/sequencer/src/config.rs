@@ -1,5 +1,8 @@
+use std::time::Duration;
+
 use serde::Deserialize;
 use shared_backup_db::SharedBackupDbConfig;
+pub use sov_rollup_interface::da::DaPayloadMode;
 
 /// Rollup Configuration
 #[derive(Debug, Clone, PartialEq, Deserialize)]
@@ -20,6 +23,209 @@ pub struct SequencerConfig {
     pub da_update_interval_ms: u64,
     /// Block production interval in ms
     pub block_production_interval_ms: u64,
+    /// Max. bytes of state diff a single L2 block may produce.
+    /// Txs which would exceed it are left in the mempool for a later block.
+    /// if not set defaults to 102400.
+    #[serde(default = "default_max_l2_block_state_diff_size")]
+    pub max_l2_block_state_diff_size: u64,
+    /// What to do on a block production tick when there are no txs or deposits to include
+    #[serde(default)]
+    pub empty_block_policy: EmptyBlockPolicy,
+    /// Max ms between two blocks when empty blocks are skipped,
+    /// so downstream tooling still gets a steady heartbeat.
+    /// if not set defaults to 60000.
+    #[serde(default = "default_empty_block_heartbeat_ms")]
+    pub empty_block_heartbeat_ms: u64,
+    /// Conditions other than the soft confirmation count which trigger a commitment
+    #[serde(default)]
+    pub commitment_policy: CommitmentPolicyConfig,
+    /// Whether to register the admin RPC methods, e.g. `citrea_shutdown`
+    #[serde(default)]
+    pub enable_admin_rpc: bool,
+    /// Runs the sequencer as a hot standby of another sequencer until promoted to primary
+    #[serde(default)]
+    pub standby: Option<StandbyConfig>,
+    /// Lease of the sequencer keys on DA, which instances sharing the keys, like a primary
+    /// and its standbys, hold to produce soft confirmations. Required to run a standby.
+    #[serde(default)]
+    pub lease: Option<LeaseConfig>,
+    /// RPC urls of the sequencers which accepted txs are forwarded to
+    #[serde(default)]
+    pub tx_gossip_peers: Vec<String>,
+    /// What the sequencer posts to DA for its commitments.
+    /// Must be the same for all sequencers of a network.
+    #[serde(default)]
+    pub da_payload_mode: DaPayloadMode,
+    /// Relays meta-transactions of users who can't pay fees if set
+    #[serde(default)]
+    pub relay: Option<RelayConfig>,
+}
+
+/// Relay of EIP-712 signed meta-transactions, which are executed through the trusted forwarder
+/// by txs of a relayer account paying their gas and L1 fees
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RelayConfig {
+    /// Private key of the relayer account
+    pub relayer_private_key: String,
+    /// Max. meta-transactions relayed for a sender per quota window.
+    /// if not set defaults to 10.
+    #[serde(default = "default_relay_max_txs_per_origin")]
+    pub max_txs_per_origin: u64,
+    /// Length of the quota windows in ms.
+    /// if not set defaults to 3600000.
+    #[serde(default = "default_relay_quota_window_ms")]
+    pub quota_window_ms: u64,
+    /// Max. gas of the relayed calls.
+    /// if not set defaults to 500000.
+    #[serde(default = "default_relay_max_gas")]
+    pub max_gas: u64,
+    /// Max. gas of the relay txs per quota window, shared by all senders
+    /// so the relayer's spending is bounded however many senders there are.
+    /// if not set defaults to 50000000.
+    #[serde(default = "default_relay_max_gas_per_window")]
+    pub max_gas_per_window: u64,
+}
+
+#[inline]
+const fn default_relay_max_txs_per_origin() -> u64 {
+    10
+}
+
+#[inline]
+const fn default_relay_quota_window_ms() -> u64 {
+    60 * 60 * 1000
+}
+
+#[inline]
+const fn default_relay_max_gas() -> u64 {
+    500_000
+}
+
+#[inline]
+const fn default_relay_max_gas_per_window() -> u64 {
+    50_000_000
+}
+
+/// Hot standby Config for the sequencer
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct StandbyConfig {
+    /// RPC url of the primary sequencer
+    pub primary_rpc_url: String,
+    /// Interval in ms between two syncs of the soft confirmations of the primary.
+    /// if not set defaults to 1000.
+    #[serde(default = "default_standby_sync_interval_ms")]
+    pub sync_interval_ms: u64,
+}
+
+#[inline]
+const fn default_standby_sync_interval_ms() -> u64 {
+    1000
+}
+
+/// Lease of the sequencer keys on DA.
+/// `start_l1_height` and `duration_l1_blocks` must be the same for all instances.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LeaseConfig {
+    /// Id of this instance, distinct for every instance sharing the sequencer keys
+    pub node_id: String,
+    /// DA height the instances start reading leases from
+    pub start_l1_height: u64,
+    /// DA blocks a lease is valid for after it is posted.
+    /// if not set defaults to 6.
+    #[serde(default = "default_lease_duration_l1_blocks")]
+    pub duration_l1_blocks: u64,
+}
+
+#[inline]
+const fn default_lease_duration_l1_blocks() -> u64 {
+    6
+}
+
+/// Policy for block production ticks with nothing to include
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmptyBlockPolicy {
+    /// Produce an empty soft confirmation on every tick
+    #[default]
+    Produce,
+    /// Skip the tick, unless a new DA block has to be referenced or
+    /// the heartbeat interval has passed since the last block
+    Skip,
+}
+
+impl EmptyBlockPolicy {
+    /// Whether a block production tick is skipped when there is nothing to include.
+    /// A new DA block must be referenced by a soft confirmation, so ticks are only
+    /// skipped while the last block is on the latest DA block.
+    pub(crate) fn skips_tick(
+        &self,
+        on_latest_da_block: bool,
+        since_last_block: Duration,
+        heartbeat: Duration,
+    ) -> bool {
+        match self {
+            Self::Produce => false,
+            Self::Skip => on_latest_da_block && since_last_block < heartbeat,
+        }
+    }
+}
+
+#[inline]
+const fn default_max_l2_block_state_diff_size() -> u64 {
+    100 * 1024
+}
+
+#[inline]
+const fn default_empty_block_heartbeat_ms() -> u64 {
+    60_000
+}
+
+/// Commitment policy of the sequencer.
+/// A commitment is made once `min_soft_confirmations_per_commitment` soft confirmations,
+/// `max_compressed_state_diff_size` compressed state diff bytes or `max_interval_ms` are reached,
+/// whichever comes first.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct CommitmentPolicyConfig {
+    /// Max. compressed size in bytes of the state diff accumulated since the last commitment.
+    /// Replaces the 300KB threshold on the uncompressed diff, state diffs compress about 3x.
+    /// if not set defaults to 102400.
+    #[serde(default = "default_max_compressed_state_diff_size")]
+    pub max_compressed_state_diff_size: u64,
+    /// Max. ms since the last commitment before uncommitted soft confirmations are committed.
+    /// Disabled if not set.
+    #[serde(default)]
+    pub max_interval_ms: Option<u64>,
+    /// DA fee rate above which commitments are deferred.
+    /// Commitments triggered by the state diff size are never deferred.
+    /// Disabled if not set.
+    #[serde(default)]
+    pub max_da_fee_rate: Option<u128>,
+    /// Max. L1 blocks a due commitment is deferred because of the DA fee rate.
+    /// Once reached, the commitment is made whatever the fee rate is.
+    /// if not set defaults to 6.
+    #[serde(default = "default_max_deferred_l1_blocks")]
+    pub max_deferred_l1_blocks: u64,
+}
+
+#[inline]
+const fn default_max_compressed_state_diff_size() -> u64 {
+    100 * 1024
+}
+
+#[inline]
+const fn default_max_deferred_l1_blocks() -> u64 {
+    6
+}
+
+impl Default for CommitmentPolicyConfig {
+    fn default() -> Self {
+        Self {
+            max_compressed_state_diff_size: default_max_compressed_state_diff_size(),
+            max_interval_ms: None,
+            max_da_fee_rate: None,
+            max_deferred_l1_blocks: default_max_deferred_l1_blocks(),
+        }
+    }
 }
 
 /// Mempool Config for the sequencer
@@ -40,6 +246,42 @@ pub struct SequencerMempoolConfig {
     pub base_fee_tx_size: u64,
     /// Max number of executable transaction slots guaranteed per account
     pub max_account_slots: u64,
+    /// Min. percentage a replacement tx must raise both the max fee and the priority fee
+    /// of a pending tx with the same sender and nonce.
+    /// if not set defaults to 10.
+    #[serde(default = "default_price_bump")]
+    pub price_bump: u128,
+    /// Max number of transactions of a single sender in the pool.
+    /// Not limited if not set.
+    #[serde(default)]
+    pub max_txs_per_sender: Option<u64>,
+    /// Max megabytes of transactions in all sub-pools together.
+    /// Once reached, the lowest paying transactions are evicted for better paying ones.
+    /// Only the sub-pool limits apply if not set.
+    #[serde(default)]
+    pub max_total_tx_size: Option<u64>,
+    /// Min. max fee per gas in wei a transaction must offer
+    #[serde(default)]
+    pub min_gas_price: u128,
+    /// Min. max fee per gas a transaction must offer, as a percentage of the current L1 fee rate.
+    /// The higher of this and `min_gas_price` applies.
+    #[serde(default)]
+    pub min_gas_price_l1_fee_rate_percentage: u128,
+    /// Ms a transaction which can't pay the L1 fee is kept in the pool, in case the
+    /// L1 fee rate drops. Such transactions are dropped right away if set to 0.
+    /// if not set defaults to 600000.
+    #[serde(default = "default_l1_fee_failed_tx_ttl_ms")]
+    pub l1_fee_failed_tx_ttl_ms: u64,
+}
+
+#[inline]
+const fn default_price_bump() -> u128 {
+    10
+}
+
+#[inline]
+const fn default_l1_fee_failed_tx_ttl_ms() -> u64 {
+    600_000
 }
 
 impl Default for SequencerMempoolConfig {
@@ -52,6 +294,12 @@ impl Default for SequencerMempoolConfig {
             base_fee_tx_limit: 100000,
             base_fee_tx_size: 200,
             max_account_slots: 16,
+            price_bump: default_price_bump(),
+            max_txs_per_sender: None,
+            max_total_tx_size: None,
+            min_gas_price: 0,
+            min_gas_price_l1_fee_rate_percentage: 0,
+            l1_fee_failed_tx_ttl_ms: default_l1_fee_failed_tx_ttl_ms(),
         }
     }
 }
@@ -80,6 +328,25 @@ mod tests {
             deposit_mempool_fetch_limit = 10
             da_update_interval_ms = 1000
             block_production_interval_ms = 1000
+            max_l2_block_state_diff_size = 51200
+            empty_block_policy = "skip"
+            empty_block_heartbeat_ms = 10000
+            enable_admin_rpc = true
+            tx_gossip_peers = ["http://localhost:12346"]
+            da_payload_mode = "state_diff"
+            [standby]
+            primary_rpc_url = "http://localhost:12346"
+            [lease]
+            node_id = "standby-1"
+            start_l1_height = 100
+            [relay]
+            relayer_private_key = "3434343434343434343434343434343434343434343434343434343434343434"
+            max_txs_per_origin = 5
+            [commitment_policy]
+            max_compressed_state_diff_size = 102400
+            max_interval_ms = 600000
+            max_da_fee_rate = 50
+            max_deferred_l1_blocks = 3
             [mempool_conf]
             pending_tx_limit = 100000
             pending_tx_size = 200
@@ -88,6 +355,12 @@ mod tests {
             base_fee_tx_limit = 100000
             base_fee_tx_size = 200
             max_account_slots = 16
+            price_bump = 25
+            max_txs_per_sender = 32
+            max_total_tx_size = 100
+            min_gas_price = 1000000
+            min_gas_price_l1_fee_rate_percentage = 50
+            l1_fee_failed_tx_ttl_ms = 60000
             [db_config]
             db_host = "localhost"
             db_port = 5432
@@ -114,11 +387,61 @@ mod tests {
                 base_fee_tx_limit: 100000,
                 base_fee_tx_size: 200,
                 max_account_slots: 16,
+                price_bump: 25,
+                max_txs_per_sender: Some(32),
+                max_total_tx_size: Some(100),
+                min_gas_price: 1000000,
+                min_gas_price_l1_fee_rate_percentage: 50,
+                l1_fee_failed_tx_ttl_ms: 60000,
             },
             db_config: Some(SharedBackupDbConfig::default()),
             da_update_interval_ms: 1000,
             block_production_interval_ms: 1000,
+            max_l2_block_state_diff_size: 51200,
+            empty_block_policy: EmptyBlockPolicy::Skip,
+            empty_block_heartbeat_ms: 10000,
+            commitment_policy: CommitmentPolicyConfig {
+                max_compressed_state_diff_size: 102400,
+                max_interval_ms: Some(600000),
+                max_da_fee_rate: Some(50),
+                max_deferred_l1_blocks: 3,
+            },
+            enable_admin_rpc: true,
+            standby: Some(StandbyConfig {
+                primary_rpc_url: "http://localhost:12346".to_string(),
+                sync_interval_ms: 1000,
+            }),
+            lease: Some(LeaseConfig {
+                node_id: "standby-1".to_string(),
+                start_l1_height: 100,
+                duration_l1_blocks: 6,
+            }),
+            tx_gossip_peers: vec!["http://localhost:12346".to_string()],
+            da_payload_mode: DaPayloadMode::StateDiff,
+            relay: Some(RelayConfig {
+                relayer_private_key:
+                    "3434343434343434343434343434343434343434343434343434343434343434".to_string(),
+                max_txs_per_origin: 5,
+                quota_window_ms: 3600000,
+                max_gas: 500000,
+                max_gas_per_window: 50000000,
+            }),
         };
         assert_eq!(config, expected);
     }
+
+    #[test]
+    fn test_empty_block_policy_skips_ticks_until_heartbeat() {
+        let heartbeat = Duration::from_secs(60);
+
+        assert!(!EmptyBlockPolicy::Produce.skips_tick(true, Duration::ZERO, heartbeat));
+
+        let policy = EmptyBlockPolicy::Skip;
+        assert!(policy.skips_tick(true, Duration::ZERO, heartbeat));
+        assert!(policy.skips_tick(true, Duration::from_secs(59), heartbeat));
+        // The heartbeat block is produced even without txs
+        assert!(!policy.skips_tick(true, heartbeat, heartbeat));
+        // A new DA block is referenced right away
+        assert!(!policy.skips_tick(false, Duration::ZERO, heartbeat));
+    }
 }
@@ -45,6 +45,15 @@ impl<C: sov_modules_api::Context> DbProvider<C> {
         }
     }
 
+    /// Gas used and gas limit of the last block
+    pub fn last_block_gas(&self) -> RpcResult<(u64, u64)> {
+        let mut working_set = WorkingSet::<C>::new(self.storage.clone());
+        let rich_block = self.evm.get_block_by_number(None, None, &mut working_set)?;
+        Ok(rich_block.map_or((0, 0), |block| {
+            (block.header.gas_used as u64, block.header.gas_limit as u64)
+        }))
+    }
+
     pub fn last_block(&self) -> RpcResult<Option<Rich<Block>>> {
         let mut working_set = WorkingSet::<C>::new(self.storage.clone());
         let rich_block = self
@@ -39,6 +39,10 @@ impl DepositDataMempool {
             .collect()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.accepted_deposit_txs.is_empty()
+    }
+
     #[instrument(level = "trace", skip_all, ret)]
     pub fn add_deposit_tx(&mut self, req: Vec<u8>) {
         self.accepted_deposit_txs.push_back(req);
@@ -0,0 +1,239 @@
+//! Lease of the sequencer keys on DA.
+//!
+//! Instances sharing the keys of the sequencer, like a primary and its standbys, post leases
+//! to DA and only produce soft confirmations while they hold the lease. All instances read the
+//! leases from the same DA height on and apply the same rules, so they agree on the holder
+//! at every DA height, and only one of them produces soft confirmations at a time.
+
+use sov_db::schema::types::{SequencerLeaseCheckpoint, SlotNumber};
+use sov_rollup_interface::da::SequencerLease;
+
+use crate::config::LeaseConfig;
+
+/// L1 blocks before its expiry the holder stops producing on a lease which was not renewed,
+/// so it stops before another instance can take the lease over
+const LEASE_EXPIRY_MARGIN: u64 = 1;
+
+/// L1 blocks after which a lease which did not land on DA is posted again
+const LEASE_REPOST_INTERVAL: u64 = 2;
+
+#[derive(Debug)]
+pub(crate) struct LeaseTracker {
+    node_id: String,
+    duration: u64,
+    checkpoint: SequencerLeaseCheckpoint,
+    /// L1 height the last lease of this instance was posted at
+    last_posted_l1_height: Option<u64>,
+}
+
+impl LeaseTracker {
+    /// Resumes from the checkpoint of the leases scanned before a restart
+    pub(crate) fn new(config: &LeaseConfig, checkpoint: Option<SequencerLeaseCheckpoint>) -> Self {
+        let checkpoint = checkpoint.unwrap_or(SequencerLeaseCheckpoint {
+            scanned_l1_height: SlotNumber(config.start_l1_height.saturating_sub(1)),
+            lease: None,
+        });
+        Self {
+            node_id: config.node_id.clone(),
+            duration: config.duration_l1_blocks,
+            checkpoint,
+            last_posted_l1_height: None,
+        }
+    }
+
+    pub(crate) fn checkpoint(&self) -> &SequencerLeaseCheckpoint {
+        &self.checkpoint
+    }
+
+    pub(crate) fn next_l1_height_to_scan(&self) -> u64 {
+        self.checkpoint.scanned_l1_height.0 + 1
+    }
+
+    /// Applies the leases posted by the sequencer in the L1 block, in order.
+    /// A lease is renewed or released by its holder before it expires,
+    /// and taken over with a later term once it expired.
+    pub(crate) fn apply(&mut self, l1_height: u64, leases: Vec<SequencerLease>) {
+        for lease in leases {
+            let accepted = match &self.checkpoint.lease {
+                None => true,
+                Some(current) if l1_height < current.expires_at_l1_height => {
+                    lease.holder == current.holder && lease.term == current.term
+                }
+                Some(current) => lease.term > current.term,
+            };
+            if accepted {
+                self.checkpoint.lease = Some(lease);
+            }
+        }
+        self.checkpoint.scanned_l1_height = SlotNumber(l1_height);
+    }
+
+    /// The lease in effect at the L1 height, if it did not expire
+    pub(crate) fn current(&self, l1_height: u64) -> Option<&SequencerLease> {
+        self.checkpoint
+            .lease
+            .as_ref()
+            .filter(|lease| l1_height < lease.expires_at_l1_height)
+    }
+
+    /// Whether this instance holds the lease at the L1 height,
+    /// far enough from its expiry to produce soft confirmations
+    pub(crate) fn is_held(&self, l1_height: u64) -> bool {
+        self.current(l1_height).is_some_and(|lease| {
+            lease.holder == self.node_id
+                && l1_height + LEASE_EXPIRY_MARGIN < lease.expires_at_l1_height
+        })
+    }
+
+    /// Holder of the lease at the L1 height, if it is another instance
+    pub(crate) fn held_by_other(&self, l1_height: u64) -> Option<&str> {
+        self.current(l1_height)
+            .filter(|lease| lease.holder != self.node_id)
+            .map(|lease| lease.holder.as_str())
+    }
+
+    /// Whether the last lease, expired or not, was held by another instance
+    pub(crate) fn last_held_by_other(&self) -> bool {
+        self.checkpoint
+            .lease
+            .as_ref()
+            .is_some_and(|lease| lease.holder != self.node_id)
+    }
+
+    /// The lease this instance posts at the L1 height to take or renew the lease, if any.
+    /// Leases are renewed once half of their duration passed, and posted again
+    /// if they did not land on DA after a few L1 blocks.
+    pub(crate) fn lease_to_post(&mut self, l1_height: u64) -> Option<SequencerLease> {
+        if self
+            .last_posted_l1_height
+            .is_some_and(|posted_at| l1_height < posted_at + LEASE_REPOST_INTERVAL)
+        {
+            return None;
+        }
+
+        let term = match self.current(l1_height) {
+            Some(lease) if lease.holder != self.node_id => return None,
+            Some(lease) if lease.expires_at_l1_height - l1_height > self.duration / 2 => {
+                return None;
+            }
+            Some(lease) => lease.term,
+            None => self
+                .checkpoint
+                .lease
+                .as_ref()
+                .map_or(0, |lease| lease.term + 1),
+        };
+        self.last_posted_l1_height = Some(l1_height);
+        Some(SequencerLease {
+            holder: self.node_id.clone(),
+            term,
+            expires_at_l1_height: l1_height + self.duration,
+        })
+    }
+
+    /// The lease this instance posts at the L1 height to release the lease it holds,
+    /// so another instance can take it over without waiting for its expiry
+    pub(crate) fn release(&self, l1_height: u64) -> Option<SequencerLease> {
+        self.current(l1_height)
+            .filter(|lease| lease.holder == self.node_id)
+            .map(|lease| SequencerLease {
+                holder: lease.holder.clone(),
+                term: lease.term,
+                expires_at_l1_height: l1_height,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(node_id: &str) -> LeaseTracker {
+        LeaseTracker::new(
+            &LeaseConfig {
+                node_id: node_id.to_string(),
+                start_l1_height: 1,
+                duration_l1_blocks: 6,
+            },
+            None,
+        )
+    }
+
+    fn lease(holder: &str, term: u64, expires_at_l1_height: u64) -> SequencerLease {
+        SequencerLease {
+            holder: holder.to_string(),
+            term,
+            expires_at_l1_height,
+        }
+    }
+
+    #[test]
+    fn test_lease_is_taken_renewed_and_released() {
+        let mut primary = tracker("primary");
+        assert_eq!(primary.next_l1_height_to_scan(), 1);
+
+        let taken = primary.lease_to_post(1).unwrap();
+        assert_eq!(taken, lease("primary", 0, 7));
+        primary.apply(2, vec![taken]);
+        assert!(primary.is_held(2));
+        // Not renewed before half of the lease passed
+        assert_eq!(primary.lease_to_post(3), None);
+
+        let renewed = primary.lease_to_post(4).unwrap();
+        assert_eq!(renewed, lease("primary", 0, 10));
+        // Stops producing right before the expiry of a lease which is not renewed
+        assert!(primary.is_held(5));
+        assert!(!primary.is_held(6));
+
+        primary.apply(5, vec![renewed]);
+        assert!(primary.is_held(8));
+
+        let released = primary.release(8).unwrap();
+        primary.apply(9, vec![released]);
+        assert!(!primary.is_held(9));
+        assert_eq!(primary.current(9), None);
+    }
+
+    #[test]
+    fn test_lease_is_only_taken_over_once_expired() {
+        let mut standby = tracker("standby");
+        standby.apply(2, vec![lease("primary", 0, 8)]);
+        assert_eq!(standby.held_by_other(2), Some("primary"));
+        assert_eq!(standby.lease_to_post(2), None);
+
+        // A takeover landing before the expiry is rejected
+        standby.apply(3, vec![lease("standby", 1, 9)]);
+        assert_eq!(standby.held_by_other(3), Some("primary"));
+
+        let takeover = standby.lease_to_post(8).unwrap();
+        assert_eq!(takeover, lease("standby", 1, 14));
+        // A renewal of the expired lease landing in the same block is rejected
+        standby.apply(8, vec![takeover, lease("primary", 0, 14)]);
+        assert!(standby.is_held(8));
+        assert_eq!(standby.held_by_other(8), None);
+        assert!(!standby.last_held_by_other());
+    }
+
+    #[test]
+    fn test_only_the_first_of_competing_leases_is_accepted() {
+        let mut primary = tracker("primary");
+        let mut standby = tracker("standby");
+        let leases = vec![lease("standby", 0, 7), lease("primary", 0, 7)];
+        primary.apply(1, leases.clone());
+        standby.apply(1, leases);
+
+        // Both instances agree on the holder
+        assert!(standby.is_held(1));
+        assert!(!primary.is_held(1));
+        assert_eq!(primary.held_by_other(1), Some("standby"));
+        assert!(primary.last_held_by_other());
+    }
+
+    #[test]
+    fn test_lease_is_posted_again_if_it_does_not_land() {
+        let mut primary = tracker("primary");
+        assert!(primary.lease_to_post(1).is_some());
+        assert_eq!(primary.lease_to_post(2), None);
+        assert_eq!(primary.lease_to_post(3), Some(lease("primary", 0, 9)));
+    }
+}
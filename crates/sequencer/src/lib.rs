@@ -1,21 +1,32 @@
+mod block_stats;
+mod bundle_pool;
 mod commitment_controller;
 mod config;
 mod db_provider;
 mod deposit_data_mempool;
+mod lease;
 mod mempool;
+mod metrics;
+mod relay;
 mod rpc;
 mod sequencer;
+mod simulation;
+mod tx_status;
 mod utils;
 
 use std::net::SocketAddr;
 
-pub use config::{SequencerConfig, SequencerMempoolConfig};
+pub use config::{
+    CommitmentPolicyConfig, DaPayloadMode, EmptyBlockPolicy, LeaseConfig, RelayConfig,
+    SequencerConfig, SequencerMempoolConfig, StandbyConfig,
+};
 pub use sequencer::CitreaSequencer;
 use sov_db::ledger_db::LedgerDB;
 use sov_modules_rollup_blueprint::RollupBlueprint;
 use sov_modules_stf_blueprint::StfBlueprint;
 use tokio::sync::oneshot;
 use tracing::{instrument, Instrument};
+pub use tx_status::{TxStatus, TxStatusEvent};
 
 /// Sequencer stf runner
 pub struct Sequencer<S: RollupBlueprint> {
@@ -1,16 +1,21 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, bail};
-use citrea_evm::SYSTEM_SIGNER;
-use reth_primitives::{Chain, ChainSpecBuilder, Genesis, TxHash};
+use citrea_evm::{CitreaError, SYSTEM_SIGNER};
+use reth_primitives::{Address, Chain, ChainSpecBuilder, Genesis, TxHash, U256};
+use reth_provider::{AccountReader, BlockReaderIdExt};
 use reth_tasks::TokioTaskExecutor;
 use reth_transaction_pool::blobstore::NoopBlobStore;
 use reth_transaction_pool::error::PoolError;
 use reth_transaction_pool::{
-    BestTransactions, BestTransactionsAttributes, ChangedAccount, CoinbaseTipOrdering,
-    EthPooledTransaction, EthTransactionValidator, Pool, PoolConfig, PoolResult, SubPoolLimit,
-    TransactionPool, TransactionPoolExt, TransactionValidationTaskExecutor, ValidPoolTransaction,
+    AllTransactionsEvents, BestTransactions, BestTransactionsAttributes, ChangedAccount,
+    CoinbaseTipOrdering, EthPooledTransaction, EthTransactionValidator, Pool, PoolConfig,
+    PoolResult, PoolTransaction, PriceBumpConfig, SubPoolLimit, TransactionPool,
+    TransactionPoolExt, TransactionValidationTaskExecutor, ValidPoolTransaction,
 };
+use serde::Serialize;
 
 use crate::config::SequencerMempoolConfig;
 pub use crate::db_provider::DbProvider;
@@ -23,7 +28,90 @@ type CitreaMempoolImpl<C> = Pool<
 
 type Transaction<C> = <CitreaMempoolImpl<C> as TransactionPool>::Transaction;
 
-pub(crate) struct CitreaMempool<C: sov_modules_api::Context>(CitreaMempoolImpl<C>);
+/// The smallest state diff any tx can produce: the sender's nonce and balance plus the
+/// coinbase balance, each with its address.
+/// Used to estimate the L1 fee a tx has to pay before it is executed.
+pub(crate) const MIN_L1_DIFF_SIZE: u64 = (20 + 8 + 32) + (20 + 32);
+
+/// Why a tx of the mempool can't be included in the next block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "reason")]
+pub(crate) enum NotExecutableReason {
+    /// Txs of the sender with lower nonces are missing from the mempool
+    NonceGap,
+    /// The max fee per gas is below the base fee of the next block
+    FeeCapBelowBaseFee {
+        /// Base fee of the next block
+        base_fee: u64,
+    },
+    /// The sender can't pay the max cost of this tx together with its earlier txs
+    InsufficientFunds,
+    /// The tx failed to pay the L1 fee and is retried once the L1 fee rate drops below the failed rate
+    L1FeeRateTooHigh {
+        /// L1 fee rate the tx last failed at
+        failed_l1_fee_rate: u128,
+    },
+    /// An earlier tx of the sender can't be included
+    BlockedByEarlierTx,
+}
+
+/// A tx of a sender in the mempool
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AccountQueueTx {
+    pub hash: TxHash,
+    pub nonce: u64,
+    pub max_fee_per_gas: u128,
+    /// Why the tx can't be included in the next block, `None` if it can
+    #[serde(flatten)]
+    pub not_executable: Option<NotExecutableReason>,
+}
+
+/// The txs of a sender in the mempool, in nonce order
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AccountQueue {
+    /// Nonce of the sender's account in the latest state
+    pub account_nonce: u64,
+    /// Txs which can be included in the next block
+    pub pending: Vec<AccountQueueTx>,
+    /// Txs which can't be included until the reason they are queued for is resolved
+    pub queued: Vec<AccountQueueTx>,
+    /// Inclusive ranges of nonces missing before the queued txs
+    pub nonce_gaps: Vec<(u64, u64)>,
+}
+
+/// A tx added to the mempool
+#[derive(Debug)]
+pub(crate) struct AddedTransaction {
+    pub hash: TxHash,
+    /// Lower paying txs evicted from the full mempool to make room for the tx
+    pub evicted: Vec<TxHash>,
+}
+
+/// A tx which can be evicted from the full mempool
+#[derive(Debug, Clone)]
+struct EvictionCandidate {
+    hash: TxHash,
+    sender: Address,
+    nonce: u64,
+    max_fee_per_gas: u128,
+    size: usize,
+}
+
+pub(crate) struct CitreaMempool<C: sov_modules_api::Context> {
+    pool: CitreaMempoolImpl<C>,
+    client: DbProvider<C>,
+    max_nonce_gap: u64,
+    max_txs_per_sender: Option<usize>,
+    /// Max bytes of txs in all sub-pools together
+    max_total_size: Option<usize>,
+    min_gas_price: u128,
+    min_gas_price_l1_fee_rate_percentage: u128,
+    /// Txs which failed to pay the L1 fee => (L1 fee rate of the last failure, first failure)
+    l1_fee_failed_txs: Mutex<HashMap<TxHash, (u128, Instant)>>,
+    l1_fee_failed_tx_ttl: Duration,
+}
 
 impl<C: sov_modules_api::Context> CitreaMempool<C> {
     pub(crate) fn new(
@@ -78,6 +166,10 @@ impl<C: sov_modules_api::Context> CitreaMempool<C> {
                 max_size: 0,
             },
             max_account_slots: mempool_conf.max_account_slots as usize,
+            price_bumps: PriceBumpConfig {
+                default_price_bump: mempool_conf.price_bump,
+                ..Default::default()
+            },
             ..pool_config
         };
 
@@ -86,44 +178,411 @@ impl<C: sov_modules_api::Context> CitreaMempool<C> {
             // .no_eip4844() cannot use since underlying impl. disables eip1559
             .set_shanghai(true)
             .with_additional_tasks(0)
-            .build_with_tasks(client, TokioTaskExecutor::default(), blob_store);
+            .build_with_tasks(client.clone(), TokioTaskExecutor::default(), blob_store);
 
-        Ok(Self(Pool::eth_pool(validator, blob_store, pool_config)))
+        Ok(Self {
+            pool: Pool::eth_pool(validator, blob_store, pool_config),
+            client,
+            max_nonce_gap: mempool_conf.max_account_slots,
+            max_txs_per_sender: mempool_conf.max_txs_per_sender.map(|limit| limit as usize),
+            max_total_size: mempool_conf
+                .max_total_tx_size
+                .map(|size| (size * 1024 * 1024) as usize),
+            min_gas_price: mempool_conf.min_gas_price,
+            min_gas_price_l1_fee_rate_percentage: mempool_conf.min_gas_price_l1_fee_rate_percentage,
+            l1_fee_failed_txs: Default::default(),
+            l1_fee_failed_tx_ttl: Duration::from_millis(mempool_conf.l1_fee_failed_tx_ttl_ms),
+        })
     }
 
+    /// Adds a tx received from a user.
+    ///
+    /// Besides the checks of the pool validator (nonce too low, balance for max gas cost,
+    /// intrinsic gas), rejects txs which can't be executed in the near future: txs with a
+    /// nonce too far ahead of the sender's account nonce and txs whose sender can't pay the
+    /// estimated L1 fee on top of the max gas cost.
+    ///
+    /// Spam is limited by a min. gas price, a cap on the txs of a single sender and a cap on
+    /// the size of the whole pool. When the pool is full, the lowest paying txs are evicted
+    /// for a better paying one.
     pub(crate) async fn add_external_transaction(
         &self,
         transaction: EthPooledTransaction,
-    ) -> PoolResult<TxHash> {
+        l1_fee_rate: u128,
+    ) -> PoolResult<AddedTransaction> {
+        let hash = transaction.transaction().hash();
         if transaction.transaction().signer() == SYSTEM_SIGNER {
             return Err(PoolError::other(
-                transaction.transaction().hash(),
-                "system transactions from rpc are not allowed",
+                hash,
+                CitreaError::SystemTransactionNotAllowed,
+            ));
+        }
+
+        let min_gas_price = self.min_gas_price(l1_fee_rate);
+        if transaction.max_fee_per_gas() < min_gas_price {
+            return Err(PoolError::other(
+                hash,
+                CitreaError::Underpriced { min_gas_price },
             ));
         }
-        self.0.add_external_transaction(transaction).await
+
+        if let Some(max_txs_per_sender) = self.max_txs_per_sender {
+            let sender_txs = self.pool.get_transactions_by_sender(transaction.sender());
+            // A replacement does not take up another slot
+            let is_replacement = sender_txs
+                .iter()
+                .any(|tx| tx.transaction.nonce() == transaction.nonce());
+            if !is_replacement && sender_txs.len() >= max_txs_per_sender {
+                return Err(PoolError::other(hash, CitreaError::AccountLimitExceeded));
+            }
+        }
+
+        let account = self
+            .client
+            .basic_account(transaction.sender())
+            .map_err(|e| PoolError::other(hash, e))?
+            .unwrap_or_default();
+
+        if transaction.nonce() > account.nonce.saturating_add(self.max_nonce_gap) {
+            return Err(PoolError::other(hash, CitreaError::NonceTooHigh));
+        }
+
+        let l1_fee = U256::from(MIN_L1_DIFF_SIZE) * U256::from(l1_fee_rate);
+        if account.balance < transaction.cost().saturating_add(l1_fee) {
+            return Err(PoolError::other(
+                hash,
+                CitreaError::InsufficientFundsForGasAndL1Fee { l1_fee },
+            ));
+        }
+
+        let evicted = match self.max_total_size {
+            Some(max_total_size) => self.make_room(&transaction, max_total_size)?,
+            None => vec![],
+        };
+
+        let hash = self.pool.add_external_transaction(transaction).await?;
+        Ok(AddedTransaction { hash, evicted })
+    }
+
+    /// The min. max fee per gas a tx must offer at the given L1 fee rate
+    pub(crate) fn min_gas_price(&self, l1_fee_rate: u128) -> u128 {
+        let l1_fee_rate_floor =
+            l1_fee_rate.saturating_mul(self.min_gas_price_l1_fee_rate_percentage) / 100;
+        self.min_gas_price.max(l1_fee_rate_floor)
+    }
+
+    /// Evicts the lowest paying txs until the given tx fits in the pool, returns the evicted txs.
+    /// Fails without evicting anything if the tx does not pay more than the txs it would replace.
+    fn make_room(
+        &self,
+        transaction: &EthPooledTransaction,
+        max_total_size: usize,
+    ) -> PoolResult<Vec<TxHash>> {
+        let hash = *transaction.hash();
+        let pool_size = self.pool.pool_size();
+        let total_size = pool_size.pending_size + pool_size.basefee_size + pool_size.queued_size;
+        if total_size + transaction.size() <= max_total_size {
+            return Ok(vec![]);
+        }
+
+        let candidates = self
+            .all_transactions()
+            .iter()
+            .map(|tx| EvictionCandidate {
+                hash: *tx.hash(),
+                sender: tx.sender(),
+                nonce: tx.nonce(),
+                max_fee_per_gas: tx.transaction.max_fee_per_gas(),
+                size: tx.transaction.size(),
+            })
+            .collect();
+        let Some(evicted) = select_evictions(
+            candidates,
+            transaction.sender(),
+            transaction.max_fee_per_gas(),
+            total_size + transaction.size() - max_total_size,
+        ) else {
+            return Err(PoolError::other(hash, CitreaError::TxPoolFull));
+        };
+
+        tracing::debug!(
+            "Evicting {} txs to make room for tx {}",
+            evicted.len(),
+            hash
+        );
+        self.remove_transactions(evicted.clone());
+        Ok(evicted)
+    }
+
+    /// Returns the txs of the sender in the mempool, telling apart the txs which can be
+    /// included in the next block and the ones which can't, with the reason why
+    pub(crate) fn account_queue(
+        &self,
+        sender: Address,
+        l1_fee_rate: u128,
+    ) -> anyhow::Result<AccountQueue> {
+        let account = self
+            .client
+            .basic_account(sender)
+            .map_err(|e| anyhow!("Failed to get account: {}", e))?
+            .unwrap_or_default();
+        let base_fee = self.next_block_base_fee()?;
+
+        let mut txs = self.pool.get_transactions_by_sender(sender);
+        txs.sort_by_key(|tx| tx.transaction.nonce());
+
+        let mut queue = AccountQueue {
+            account_nonce: account.nonce,
+            pending: vec![],
+            queued: vec![],
+            nonce_gaps: vec![],
+        };
+        let mut next_nonce = account.nonce;
+        let mut cumulative_cost = U256::ZERO;
+        let mut blocked = false;
+        for tx in txs {
+            let nonce = tx.transaction.nonce();
+            let max_fee_per_gas = tx.transaction.max_fee_per_gas();
+            cumulative_cost = cumulative_cost.saturating_add(tx.transaction.cost());
+
+            let not_executable = if nonce > next_nonce {
+                queue.nonce_gaps.push((next_nonce, nonce - 1));
+                Some(NotExecutableReason::NonceGap)
+            } else if blocked {
+                Some(NotExecutableReason::BlockedByEarlierTx)
+            } else if max_fee_per_gas < base_fee as u128 {
+                Some(NotExecutableReason::FeeCapBelowBaseFee { base_fee })
+            } else if cumulative_cost > account.balance {
+                Some(NotExecutableReason::InsufficientFunds)
+            } else {
+                self.l1_fee_failed_rate(tx.hash())
+                    .filter(|failed_l1_fee_rate| l1_fee_rate >= *failed_l1_fee_rate)
+                    .map(|failed_l1_fee_rate| NotExecutableReason::L1FeeRateTooHigh {
+                        failed_l1_fee_rate,
+                    })
+            };
+            next_nonce = nonce + 1;
+
+            let tx = AccountQueueTx {
+                hash: *tx.hash(),
+                nonce,
+                max_fee_per_gas,
+                not_executable,
+            };
+            if tx.not_executable.is_some() {
+                // A sender's txs are only included in nonce order
+                blocked = true;
+                queue.queued.push(tx);
+            } else {
+                queue.pending.push(tx);
+            }
+        }
+        Ok(queue)
+    }
+
+    /// Base fee of the next block
+    pub(crate) fn next_block_base_fee(&self) -> anyhow::Result<u64> {
+        self.client
+            .latest_header()
+            .map_err(|e| anyhow!("Failed to get latest header: {}", e))?
+            .ok_or(anyhow!("Latest header must always exist"))?
+            .next_block_base_fee(self.client.cfg().base_fee_params)
+            .ok_or(anyhow!("Failed to get next block base fee"))
+    }
+
+    /// Nonce of the next tx of the sender, following its txs in the mempool
+    pub(crate) fn next_nonce(&self, sender: Address) -> anyhow::Result<u64> {
+        let account_nonce = self
+            .client
+            .basic_account(sender)
+            .map_err(|e| anyhow!("Failed to get account: {}", e))?
+            .unwrap_or_default()
+            .nonce;
+        let next_pool_nonce = self
+            .pool
+            .get_transactions_by_sender(sender)
+            .iter()
+            .map(|tx| tx.transaction.nonce() + 1)
+            .max();
+        Ok(next_pool_nonce.map_or(account_nonce, |nonce| nonce.max(account_nonce)))
+    }
+
+    /// Whether the mempool has a tx of the sender with the nonce
+    pub(crate) fn has_nonce(&self, sender: Address, nonce: u64) -> bool {
+        self.pool
+            .get_transactions_by_sender(sender)
+            .iter()
+            .any(|tx| tx.transaction.nonce() == nonce)
     }
 
     pub(crate) fn get(&self, hash: &TxHash) -> Option<Arc<ValidPoolTransaction<Transaction<C>>>> {
-        self.0.get(hash)
+        self.pool.get(hash)
+    }
+
+    /// Returns the pending and queued txs of the pool
+    pub(crate) fn all_transactions(&self) -> Vec<Arc<ValidPoolTransaction<Transaction<C>>>> {
+        let all = self.pool.all_transactions();
+        all.pending.into_iter().chain(all.queued).collect()
     }
 
     pub(crate) fn remove_transactions(
         &self,
         tx_hashes: Vec<TxHash>,
     ) -> Vec<Arc<ValidPoolTransaction<Transaction<C>>>> {
-        self.0.remove_transactions(tx_hashes)
+        let mut l1_fee_failed_txs = self.l1_fee_failed_txs.lock().unwrap();
+        for tx_hash in &tx_hashes {
+            l1_fee_failed_txs.remove(tx_hash);
+        }
+        drop(l1_fee_failed_txs);
+
+        self.pool.remove_transactions(tx_hashes)
+    }
+
+    /// Keeps the txs which failed to pay the L1 fee at `l1_fee_rate` in the pool,
+    /// so they are retried once the L1 fee rate drops.
+    /// Returns the txs which have been failing for longer than the TTL, to be removed.
+    pub(crate) fn requeue_l1_fee_failed_txs(
+        &self,
+        tx_hashes: Vec<TxHash>,
+        l1_fee_rate: u128,
+        now: Instant,
+    ) -> Vec<TxHash> {
+        let mut l1_fee_failed_txs = self.l1_fee_failed_txs.lock().unwrap();
+        // Forget the txs which left the pool in another way, e.g. replaced
+        l1_fee_failed_txs.retain(|tx_hash, _| self.pool.contains(tx_hash));
+
+        let mut expired = vec![];
+        for tx_hash in tx_hashes {
+            let (failed_l1_fee_rate, first_failed_at) = l1_fee_failed_txs
+                .entry(tx_hash)
+                .or_insert((l1_fee_rate, now));
+            *failed_l1_fee_rate = l1_fee_rate;
+            if now.duration_since(*first_failed_at) >= self.l1_fee_failed_tx_ttl {
+                l1_fee_failed_txs.remove(&tx_hash);
+                expired.push(tx_hash);
+            }
+        }
+        expired
+    }
+
+    /// The L1 fee rate the tx last failed to pay the L1 fee at, if it is requeued
+    pub(crate) fn l1_fee_failed_rate(&self, tx_hash: &TxHash) -> Option<u128> {
+        self.l1_fee_failed_txs
+            .lock()
+            .unwrap()
+            .get(tx_hash)
+            .map(|(l1_fee_rate, _)| *l1_fee_rate)
     }
 
     pub(crate) fn update_accounts(&self, account_updates: Vec<ChangedAccount>) {
-        self.0.update_accounts(account_updates);
+        self.pool.update_accounts(account_updates);
+    }
+
+    /// Returns a stream of the events of all txs in the pool
+    pub(crate) fn all_transactions_event_listener(&self) -> AllTransactionsEvents<Transaction<C>> {
+        self.pool.all_transactions_event_listener()
     }
 
     pub(crate) fn best_transactions_with_attributes(
         &self,
         best_transactions_attributes: BestTransactionsAttributes,
     ) -> Box<dyn BestTransactions<Item = Arc<ValidPoolTransaction<Transaction<C>>>>> {
-        self.0
+        self.pool
             .best_transactions_with_attributes(best_transactions_attributes)
     }
 }
+
+/// Picks the lowest paying txs to evict until `size_to_free` bytes are freed.
+/// A sender's txs are evicted from its highest nonce down, so no nonce gap is left behind
+/// its remaining txs. The txs of the sender of the incoming tx are kept, it may depend on them.
+/// Returns `None` if evicting the txs paying less than `max_fee_per_gas` does not free enough.
+fn select_evictions(
+    candidates: Vec<EvictionCandidate>,
+    incoming_sender: Address,
+    max_fee_per_gas: u128,
+    size_to_free: usize,
+) -> Option<Vec<TxHash>> {
+    let mut senders: HashMap<Address, Vec<EvictionCandidate>> = HashMap::new();
+    for tx in candidates {
+        if tx.sender != incoming_sender {
+            senders.entry(tx.sender).or_default().push(tx);
+        }
+    }
+    for txs in senders.values_mut() {
+        txs.sort_by_key(|tx| tx.nonce);
+    }
+
+    let mut freed = 0;
+    let mut evicted = vec![];
+    while freed < size_to_free {
+        // The lowest paying of the last txs of the senders
+        let (_, sender) = senders
+            .iter()
+            .filter_map(|(sender, txs)| txs.last().map(|tx| (tx.max_fee_per_gas, *sender)))
+            .min()?;
+        let tx = senders.get_mut(&sender)?.pop()?;
+        if tx.max_fee_per_gas >= max_fee_per_gas {
+            return None;
+        }
+        freed += tx.size;
+        evicted.push(tx.hash);
+    }
+    Some(evicted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(id: u8, sender: u8, nonce: u64, max_fee_per_gas: u128) -> EvictionCandidate {
+        EvictionCandidate {
+            hash: TxHash::from([id; 32]),
+            sender: Address::from([sender; 20]),
+            nonce,
+            max_fee_per_gas,
+            size: 100,
+        }
+    }
+
+    #[test]
+    fn test_evicts_lowest_paying_txs_first() {
+        let candidates = vec![tx(1, 1, 0, 30), tx(2, 2, 0, 10), tx(3, 3, 0, 20)];
+        assert_eq!(
+            select_evictions(candidates, Address::from([9; 20]), 40, 150),
+            Some(vec![TxHash::from([2; 32]), TxHash::from([3; 32])])
+        );
+    }
+
+    #[test]
+    fn test_evicts_txs_of_a_sender_from_the_highest_nonce() {
+        // The cheap tx of sender 1 can't be evicted before its later tx,
+        // which would be left behind a nonce gap
+        let candidates = vec![tx(1, 1, 0, 10), tx(2, 1, 1, 30), tx(3, 2, 0, 20)];
+        assert_eq!(
+            select_evictions(candidates.clone(), Address::from([9; 20]), 40, 100),
+            Some(vec![TxHash::from([3; 32])])
+        );
+        assert_eq!(
+            select_evictions(candidates, Address::from([9; 20]), 40, 300),
+            Some(vec![
+                TxHash::from([3; 32]),
+                TxHash::from([2; 32]),
+                TxHash::from([1; 32])
+            ])
+        );
+    }
+
+    #[test]
+    fn test_does_not_evict_better_paying_txs_or_txs_of_the_sender() {
+        let candidates = vec![tx(1, 1, 0, 10), tx(2, 2, 0, 50)];
+        assert_eq!(
+            select_evictions(candidates.clone(), Address::from([9; 20]), 40, 200),
+            None
+        );
+        // The incoming tx may follow the txs of its sender
+        assert_eq!(
+            select_evictions(candidates, Address::from([1; 20]), 40, 100),
+            None
+        );
+    }
+}
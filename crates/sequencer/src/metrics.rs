@@ -0,0 +1,67 @@
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_histogram, register_int_counter_vec, register_int_gauge, Histogram, IntCounterVec,
+    IntGauge,
+};
+
+pub static SEQUENCER_COMMITMENTS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!(
+        // metric name
+        "sequencer_commitments",
+        // metric description
+        "Sequencer commitments submitted to DA",
+        // metric labels (dimensions)
+        &["trigger"]
+    )
+    .unwrap()
+});
+
+pub static SEQUENCER_STATE_DIFF_COMPRESSED_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "sequencer_state_diff_compressed_bytes",
+        // metric description
+        "Estimated compressed size of the state diff accumulated since the last commitment"
+    )
+    .unwrap()
+});
+
+pub static SEQUENCER_BLOCK_GAS_USED: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "sequencer_block_gas_used",
+        // metric description
+        "Gas used by the last L2 block"
+    )
+    .unwrap()
+});
+
+pub static SEQUENCER_BLOCK_STATE_DIFF_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "sequencer_block_state_diff_bytes",
+        // metric description
+        "Compressed size of the state diff of the last L2 block"
+    )
+    .unwrap()
+});
+
+pub static SEQUENCER_BLOCK_TX_COUNT: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!(
+        // metric name
+        "sequencer_block_tx_count",
+        // metric description
+        "Number of EVM txs of the last L2 block"
+    )
+    .unwrap()
+});
+
+pub static SEQUENCER_BLOCK_BUILD_SECONDS: Lazy<Histogram> = Lazy::new(|| {
+    register_histogram!(
+        // metric name
+        "sequencer_block_build_seconds",
+        // metric description
+        "Time it took to build, execute and commit L2 blocks"
+    )
+    .unwrap()
+});
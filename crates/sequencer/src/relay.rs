@@ -0,0 +1,513 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::anyhow;
+use citrea_evm::system_contracts::{ForwardRequest, TrustedForwarder};
+use jsonrpsee::types::error::INVALID_PARAMS_CODE;
+use jsonrpsee::types::ErrorObjectOwned;
+use reth_primitives::{
+    public_key_to_address, sign_message, Address, Bytes, Signature, Transaction, TransactionSigned,
+    TxEip1559, TxKind, B256, U256,
+};
+use secp256k1::{PublicKey, SecretKey, SECP256K1};
+use serde::Deserialize;
+use sov_db::schema::types::{PendingMetaTransaction, RelayQuotaState};
+
+use crate::config::RelayConfig;
+
+/// Error code of meta-transactions rejected because their sender used up its quota
+pub(crate) const QUOTA_EXCEEDED_ERROR_CODE: i32 = -32005;
+
+/// Params of `citrea_relayMetaTransaction`, a call signed by its sender
+/// following EIP-712 for the trusted forwarder
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MetaTransactionRequest {
+    pub from: Address,
+    pub to: Address,
+    /// The relayer does not pay the value of the calls, so it must be zero
+    #[serde(default)]
+    pub value: U256,
+    /// Gas forwarded to the call
+    pub gas: U256,
+    /// Nonce of the sender in the trusted forwarder
+    pub nonce: U256,
+    /// Unix timestamp after which the request can't be executed
+    pub deadline: U256,
+    #[serde(default)]
+    pub data: Bytes,
+    /// 65 bytes signature of the EIP-712 hash of the request
+    pub signature: Bytes,
+}
+
+impl MetaTransactionRequest {
+    fn forward_request(&self) -> ForwardRequest {
+        ForwardRequest {
+            from: self.from,
+            to: self.to,
+            value: self.value,
+            gas: self.gas,
+            nonce: self.nonce,
+            deadline: self.deadline,
+            data: self.data.clone(),
+        }
+    }
+}
+
+/// Meta-transactions relayed in the current quota window, which all senders share
+#[derive(Debug, Default)]
+struct RelayQuotas {
+    /// Unix timestamp in seconds the window started at
+    window_start: u64,
+    /// Gas of the relay txs sent in the window
+    gas_used: u64,
+    relayed: HashMap<Address, u64>,
+    /// Relayed meta-transactions whose relay txs may not be included yet, by sender.
+    /// Kept across windows, until their relay txs leave the mempool.
+    pending: HashMap<Address, PendingRequest>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PendingRequest {
+    nonce: U256,
+    tx_hash: B256,
+}
+
+impl From<RelayQuotaState> for RelayQuotas {
+    fn from(state: RelayQuotaState) -> Self {
+        Self {
+            window_start: state.window_start,
+            gas_used: state.gas_used,
+            relayed: state
+                .relayed
+                .into_iter()
+                .map(|(from, relayed)| (Address::from(from), relayed))
+                .collect(),
+            pending: state
+                .pending
+                .into_iter()
+                .map(|pending| {
+                    (
+                        Address::from(pending.from),
+                        PendingRequest {
+                            nonce: U256::from_be_bytes(pending.nonce),
+                            tx_hash: B256::from(pending.tx_hash),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Relays meta-transactions through the trusted forwarder in txs of the relayer account,
+/// which pays their gas and L1 fees. Each sender can have a limited number of
+/// meta-transactions relayed per quota window, and all of them a limited amount of gas.
+pub(crate) struct MetaTxRelay {
+    relayer_key: SecretKey,
+    relayer: Address,
+    max_txs_per_origin: u64,
+    quota_window: Duration,
+    max_gas: u64,
+    max_gas_per_window: u64,
+    quotas: RelayQuotas,
+}
+
+impl MetaTxRelay {
+    /// Creates the relay with the quotas persisted before a restart, if any
+    pub(crate) fn new(
+        config: &RelayConfig,
+        quotas: Option<RelayQuotaState>,
+    ) -> anyhow::Result<Self> {
+        let relayer_key = SecretKey::from_str(&config.relayer_private_key)
+            .map_err(|e| anyhow!("Invalid relayer private key: {}", e))?;
+        let relayer = public_key_to_address(PublicKey::from_secret_key(SECP256K1, &relayer_key));
+        Ok(Self {
+            relayer_key,
+            relayer,
+            max_txs_per_origin: config.max_txs_per_origin,
+            quota_window: Duration::from_millis(config.quota_window_ms),
+            max_gas: config.max_gas,
+            max_gas_per_window: config.max_gas_per_window,
+            quotas: quotas.map(RelayQuotas::from).unwrap_or_default(),
+        })
+    }
+
+    /// Address of the relayer account
+    pub(crate) fn relayer(&self) -> Address {
+        self.relayer
+    }
+
+    /// Checks the request can be relayed and returns the input data of the forwarder call
+    /// executing it. Whether the call succeeds is left to the caller to simulate.
+    /// `now` is the unix timestamp in seconds.
+    pub(crate) fn forwarder_input(
+        &mut self,
+        request: &MetaTransactionRequest,
+        chain_id: u64,
+        now: u64,
+    ) -> Result<Bytes, ErrorObjectOwned> {
+        if request.gas > U256::from(self.max_gas) {
+            return Err(invalid_meta_transaction(format!(
+                "gas must not be more than {}",
+                self.max_gas
+            )));
+        }
+        if !request.value.is_zero() {
+            return Err(invalid_meta_transaction(
+                "value transfers are not relayed".to_string(),
+            ));
+        }
+
+        let forward_request = request.forward_request();
+        let signer = recover_signer(
+            &request.signature,
+            TrustedForwarder::signing_hash(&forward_request, chain_id),
+        )
+        .ok_or_else(|| invalid_meta_transaction("invalid signature".to_string()))?;
+        if signer != request.from {
+            return Err(invalid_meta_transaction(
+                "signature is not from the sender".to_string(),
+            ));
+        }
+
+        // The forwarder only executes the next nonce of a sender, so while a relay tx is pending
+        // another one would be the same request again, or fail once the pending one is included
+        if let Some(pending) = self.quotas.pending.get(&request.from) {
+            return Err(invalid_meta_transaction(format!(
+                "meta-transaction with nonce {} of the sender is pending",
+                pending.nonce
+            )));
+        }
+
+        self.start_window(now);
+        if self.quotas.relayed.get(&request.from).copied().unwrap_or(0) >= self.max_txs_per_origin {
+            return Err(ErrorObjectOwned::owned(
+                QUOTA_EXCEEDED_ERROR_CODE,
+                format!(
+                    "sender can have at most {} meta-transactions relayed per {} ms",
+                    self.max_txs_per_origin,
+                    self.quota_window.as_millis()
+                ),
+                None::<String>,
+            ));
+        }
+
+        Ok(TrustedForwarder::execute(
+            forward_request,
+            request.signature.clone(),
+        ))
+    }
+
+    /// Signs the tx of the relayer calling the trusted forwarder.
+    /// The relayer tips nothing, relayed txs are included as long as they pay the base fee.
+    pub(crate) fn sign_relay_tx(
+        &self,
+        input: Bytes,
+        chain_id: u64,
+        nonce: u64,
+        gas_limit: u64,
+        max_fee_per_gas: u128,
+    ) -> anyhow::Result<TransactionSigned> {
+        let transaction = Transaction::Eip1559(TxEip1559 {
+            chain_id,
+            nonce,
+            gas_limit,
+            max_fee_per_gas,
+            max_priority_fee_per_gas: 0,
+            to: TxKind::Call(TrustedForwarder::address()),
+            value: U256::ZERO,
+            input,
+            ..Default::default()
+        });
+        let signature = sign_message(
+            B256::from_slice(&self.relayer_key.secret_bytes()),
+            transaction.signature_hash(),
+        )
+        .map_err(|e| anyhow!("Failed to sign relay tx: {}", e))?;
+        Ok(TransactionSigned::from_transaction_and_signature(
+            transaction,
+            signature,
+        ))
+    }
+
+    /// Checks the relay tx fits in the gas all senders can have relayed per quota window
+    pub(crate) fn check_gas_budget(&self, gas_limit: u64) -> Result<(), ErrorObjectOwned> {
+        if self.quotas.gas_used.saturating_add(gas_limit) > self.max_gas_per_window {
+            return Err(ErrorObjectOwned::owned(
+                QUOTA_EXCEEDED_ERROR_CODE,
+                format!(
+                    "relayer can spend at most {} gas per {} ms, try again later",
+                    self.max_gas_per_window,
+                    self.quota_window.as_millis()
+                ),
+                None::<String>,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Counts a relayed meta-transaction against the quota of its sender and the gas budget,
+    /// and keeps it pending until its relay tx leaves the mempool
+    pub(crate) fn consume_quota(
+        &mut self,
+        request: &MetaTransactionRequest,
+        gas_limit: u64,
+        tx_hash: B256,
+    ) {
+        self.quotas.gas_used = self.quotas.gas_used.saturating_add(gas_limit);
+        *self.quotas.relayed.entry(request.from).or_default() += 1;
+        self.quotas.pending.insert(
+            request.from,
+            PendingRequest {
+                nonce: request.nonce,
+                tx_hash,
+            },
+        );
+    }
+
+    /// Forgets the pending meta-transactions whose relay txs were included or dropped
+    pub(crate) fn prune_pending(&mut self, in_mempool: impl Fn(&B256) -> bool) {
+        self.quotas
+            .pending
+            .retain(|_, pending| in_mempool(&pending.tx_hash));
+    }
+
+    /// The quotas to persist, so a restart does not reset them
+    pub(crate) fn quota_state(&self) -> RelayQuotaState {
+        RelayQuotaState {
+            window_start: self.quotas.window_start,
+            gas_used: self.quotas.gas_used,
+            relayed: self
+                .quotas
+                .relayed
+                .iter()
+                .map(|(from, relayed)| (from.0 .0, *relayed))
+                .collect(),
+            pending: self
+                .quotas
+                .pending
+                .iter()
+                .map(|(from, pending)| PendingMetaTransaction {
+                    from: from.0 .0,
+                    nonce: pending.nonce.to_be_bytes(),
+                    tx_hash: pending.tx_hash.0,
+                })
+                .collect(),
+        }
+    }
+
+    /// Starts a new quota window if the current one is over
+    fn start_window(&mut self, now: u64) {
+        let elapsed = Duration::from_secs(now.saturating_sub(self.quotas.window_start));
+        if elapsed >= self.quota_window {
+            self.quotas.window_start = now;
+            self.quotas.gas_used = 0;
+            self.quotas.relayed.clear();
+        }
+    }
+}
+
+/// Recovers the signer of a 65 bytes `r || s || v` signature, `v` being 27/28 or 0/1
+fn recover_signer(signature: &[u8], hash: B256) -> Option<Address> {
+    if signature.len() != 65 {
+        return None;
+    }
+    let odd_y_parity = match signature[64] {
+        0 | 27 => false,
+        1 | 28 => true,
+        _ => return None,
+    };
+    Signature {
+        r: U256::from_be_slice(&signature[..32]),
+        s: U256::from_be_slice(&signature[32..64]),
+        odd_y_parity,
+    }
+    .recover_signer(hash)
+}
+
+fn invalid_meta_transaction(message: String) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(
+        INVALID_PARAMS_CODE,
+        "Invalid meta-transaction",
+        Some(message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAIN_ID: u64 = 5655;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn relay(max_txs_per_origin: u64) -> MetaTxRelay {
+        MetaTxRelay::new(&config(max_txs_per_origin), None).unwrap()
+    }
+
+    fn config(max_txs_per_origin: u64) -> RelayConfig {
+        RelayConfig {
+            relayer_private_key: "34".repeat(32),
+            max_txs_per_origin,
+            quota_window_ms: 1000,
+            max_gas: 100_000,
+            max_gas_per_window: 250_000,
+        }
+    }
+
+    fn signed_request(key: &SecretKey) -> MetaTransactionRequest {
+        signed_request_with_nonce(key, U256::ZERO)
+    }
+
+    fn signed_request_with_nonce(key: &SecretKey, nonce: U256) -> MetaTransactionRequest {
+        let mut request = MetaTransactionRequest {
+            from: public_key_to_address(PublicKey::from_secret_key(SECP256K1, key)),
+            to: Address::with_last_byte(2),
+            value: U256::ZERO,
+            gas: U256::from(50_000),
+            nonce,
+            deadline: U256::from(u64::MAX),
+            data: Bytes::from(vec![1, 2, 3, 4]),
+            signature: Bytes::new(),
+        };
+        let hash = TrustedForwarder::signing_hash(&request.forward_request(), CHAIN_ID);
+        let signature = sign_message(B256::from_slice(&key.secret_bytes()), hash).unwrap();
+        let mut signature_bytes = Vec::with_capacity(65);
+        signature_bytes.extend(signature.r.to_be_bytes::<32>());
+        signature_bytes.extend(signature.s.to_be_bytes::<32>());
+        signature_bytes.push(27 + signature.odd_y_parity as u8);
+        request.signature = signature_bytes.into();
+        request
+    }
+
+    #[test]
+    fn test_checks_signature() {
+        let mut relay = relay(1);
+        let key = SecretKey::from_slice(&[7; 32]).unwrap();
+        let now = NOW;
+
+        let request = signed_request(&key);
+        assert!(relay.forwarder_input(&request, CHAIN_ID, now).is_ok());
+        // Signed for another chain
+        assert!(relay.forwarder_input(&request, CHAIN_ID + 1, now).is_err());
+
+        let mut tampered = request.clone();
+        tampered.data = Bytes::from(vec![5]);
+        assert!(relay.forwarder_input(&tampered, CHAIN_ID, now).is_err());
+
+        let mut too_much_gas = signed_request(&key);
+        too_much_gas.gas = U256::from(100_001);
+        assert!(relay.forwarder_input(&too_much_gas, CHAIN_ID, now).is_err());
+    }
+
+    #[test]
+    fn test_quota_per_origin() {
+        let mut relay = relay(2);
+        let key = SecretKey::from_slice(&[7; 32]).unwrap();
+        let other_key = SecretKey::from_slice(&[8; 32]).unwrap();
+        let other_request = signed_request(&other_key);
+
+        for nonce in 0..2 {
+            let request = signed_request_with_nonce(&key, U256::from(nonce));
+            assert!(relay.forwarder_input(&request, CHAIN_ID, NOW).is_ok());
+            relay.consume_quota(&request, 50_000, B256::with_last_byte(nonce));
+            relay.prune_pending(|_| false);
+        }
+        let request = signed_request_with_nonce(&key, U256::from(2));
+        let err = relay.forwarder_input(&request, CHAIN_ID, NOW).unwrap_err();
+        assert_eq!(err.code(), QUOTA_EXCEEDED_ERROR_CODE);
+        assert!(relay.forwarder_input(&other_request, CHAIN_ID, NOW).is_ok());
+
+        // A new window starts
+        assert!(relay.forwarder_input(&request, CHAIN_ID, NOW + 1).is_ok());
+        assert!(relay.quotas.relayed.is_empty());
+    }
+
+    #[test]
+    fn test_gas_budget_is_shared_by_all_senders() {
+        let mut relay = relay(10);
+
+        // Each sender is within its own quota, but together they spend the budget
+        for i in 0..5u8 {
+            let request = signed_request(&SecretKey::from_slice(&[i + 1; 32]).unwrap());
+            assert!(relay.forwarder_input(&request, CHAIN_ID, NOW).is_ok());
+            assert!(relay.check_gas_budget(50_000).is_ok());
+            relay.consume_quota(&request, 50_000, B256::with_last_byte(i));
+        }
+        let request = signed_request(&SecretKey::from_slice(&[9; 32]).unwrap());
+        assert!(relay.forwarder_input(&request, CHAIN_ID, NOW).is_ok());
+        let err = relay.check_gas_budget(1).unwrap_err();
+        assert_eq!(err.code(), QUOTA_EXCEEDED_ERROR_CODE);
+
+        // A new window starts
+        assert!(relay.forwarder_input(&request, CHAIN_ID, NOW + 1).is_ok());
+        assert!(relay.check_gas_budget(50_000).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_requests_while_relayed_one_is_pending() {
+        let mut relay = relay(10);
+        let key = SecretKey::from_slice(&[7; 32]).unwrap();
+        let request = signed_request(&key);
+        let tx_hash = B256::with_last_byte(1);
+
+        assert!(relay.forwarder_input(&request, CHAIN_ID, NOW).is_ok());
+        relay.consume_quota(&request, 50_000, tx_hash);
+
+        // The same request again, and the next one, until the relay tx leaves the mempool
+        relay.prune_pending(|hash| *hash == tx_hash);
+        let err = relay.forwarder_input(&request, CHAIN_ID, NOW).unwrap_err();
+        assert_eq!(err.code(), INVALID_PARAMS_CODE);
+        let next = signed_request_with_nonce(&key, U256::from(1));
+        assert!(relay.forwarder_input(&next, CHAIN_ID, NOW).is_err());
+        // Also in the next window
+        assert!(relay.forwarder_input(&next, CHAIN_ID, NOW + 1).is_err());
+
+        relay.prune_pending(|_| false);
+        assert!(relay.forwarder_input(&next, CHAIN_ID, NOW + 1).is_ok());
+    }
+
+    #[test]
+    fn test_quotas_are_kept_across_restarts() {
+        let mut relay = relay(1);
+        let key = SecretKey::from_slice(&[7; 32]).unwrap();
+        let other_key = SecretKey::from_slice(&[8; 32]).unwrap();
+        let request = signed_request(&key);
+        let other_request = signed_request(&other_key);
+        assert!(relay.forwarder_input(&request, CHAIN_ID, NOW).is_ok());
+        relay.consume_quota(&request, 50_000, B256::with_last_byte(1));
+        assert!(relay.forwarder_input(&other_request, CHAIN_ID, NOW).is_ok());
+        relay.consume_quota(&other_request, 60_000, B256::with_last_byte(2));
+        relay.prune_pending(|hash| *hash == B256::with_last_byte(2));
+
+        let state = relay.quota_state();
+        let mut restarted = MetaTxRelay::new(&config(1), Some(state)).unwrap();
+        assert_eq!(restarted.quotas.gas_used, 110_000);
+        assert_eq!(restarted.quotas.relayed.len(), 2);
+        let pending = restarted.quotas.pending[&other_request.from];
+        assert_eq!(pending.nonce, U256::ZERO);
+        assert_eq!(pending.tx_hash, B256::with_last_byte(2));
+
+        // Out of quota and pending as before the restart
+        let next = signed_request_with_nonce(&key, U256::from(1));
+        let err = restarted.forwarder_input(&next, CHAIN_ID, NOW).unwrap_err();
+        assert_eq!(err.code(), QUOTA_EXCEEDED_ERROR_CODE);
+        let err = restarted
+            .forwarder_input(&other_request, CHAIN_ID, NOW)
+            .unwrap_err();
+        assert_eq!(err.code(), INVALID_PARAMS_CODE);
+    }
+
+    #[test]
+    fn test_signs_relay_tx_from_relayer() {
+        let relay = relay(1);
+        let tx = relay
+            .sign_relay_tx(Bytes::from(vec![1]), CHAIN_ID, 3, 100_000, 10)
+            .unwrap();
+        assert_eq!(tx.recover_signer(), Some(relay.relayer()));
+        assert_eq!(tx.to(), Some(TrustedForwarder::address()));
+        assert_eq!(tx.nonce(), 3);
+    }
+}
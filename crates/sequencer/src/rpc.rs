@@ -1,70 +1,319 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-use citrea_evm::Evm;
+use citrea_evm::system_contracts::TrustedForwarder;
+use citrea_evm::{CitreaError, Evm, RlpEvmTransaction, MIN_TRANSACTION_GAS, SYSTEM_SIGNER};
+use citrea_primitives::SharedClock;
 use futures::channel::mpsc::UnboundedSender;
-use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG};
+use jsonrpsee::core::SubscriptionResult;
+use jsonrpsee::types::error::{INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, INVALID_PARAMS_CODE};
 use jsonrpsee::types::ErrorObjectOwned;
-use jsonrpsee::RpcModule;
-use reth_primitives::{Bytes, FromRecoveredPooledTransaction, IntoRecoveredTransaction, B256};
+use jsonrpsee::{RpcModule, SubscriptionMessage};
+use reth_primitives::{
+    Address, Bytes, FromRecoveredPooledTransaction, IntoRecoveredTransaction,
+    TransactionSignedEcRecovered, TxKind, B256, U256, U64,
+};
 use reth_rpc::eth::error::EthApiError;
+use reth_rpc_types::{Index, RichBlock, TransactionInput, TransactionRequest};
 use reth_rpc_types_compat::transaction::from_recovered;
-use reth_transaction_pool::EthPooledTransaction;
+use reth_transaction_pool::error::{PoolError, PoolErrorKind};
+use reth_transaction_pool::{BestTransactionsAttributes, EthPooledTransaction};
+use sequencer_client::SequencerClient;
+use serde::{Deserialize, Serialize};
 use shared_backup_db::PostgresConnector;
+use sov_db::ledger_db::SequencerLedgerOps;
 use sov_modules_api::WorkingSet;
-use tokio::sync::Mutex;
-use tracing::{debug, error};
+use sov_stf_runner::current_request_id;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{mpsc, Mutex, Semaphore};
+use tracing::{debug, error, info, warn};
 
+use crate::block_stats::{BlockBuildReport, BlockStatsTracker, BLOCK_STATS_WINDOW};
+use crate::bundle_pool::{Bundle, BundlePool, MAX_BUNDLE_TXS};
+use crate::commitment_controller::{self, CommitmentDeferral};
 use crate::deposit_data_mempool::DepositDataMempool;
-use crate::mempool::CitreaMempool;
-use crate::utils::recover_raw_transaction;
+use crate::mempool::{AccountQueue, AddedTransaction, CitreaMempool};
+use crate::relay::{MetaTransactionRequest, MetaTxRelay, QUOTA_EXCEEDED_ERROR_CODE};
+use crate::simulation::{SimulateBlockRequest, MAX_PENDING_SIMULATIONS, MAX_SIMULATED_TXS};
+use crate::tx_status::{TxStatus, TxStatusNotifier};
+use crate::utils::{latest_l1_fee_rate, recover_raw_transaction};
 
-pub(crate) struct RpcContext<C: sov_modules_api::Context> {
+pub(crate) struct RpcContext<C: sov_modules_api::Context, DB: SequencerLedgerOps> {
     pub mempool: Arc<CitreaMempool<C>>,
     pub deposit_mempool: Arc<Mutex<DepositDataMempool>>,
+    pub bundle_pool: Arc<Mutex<BundlePool>>,
     pub l2_force_block_tx: UnboundedSender<()>,
+    pub shutdown_tx: UnboundedSender<()>,
+    pub shutting_down: Arc<AtomicBool>,
+    pub promote_tx: UnboundedSender<bool>,
+    pub simulate_tx: mpsc::Sender<SimulateBlockRequest>,
+    pub simulation_permits: Arc<Semaphore>,
+    pub tx_gossip_peers: Vec<SequencerClient>,
     pub storage: C::Storage,
     pub test_mode: bool,
+    pub enable_admin_rpc: bool,
     pub pg_pool: Option<Arc<PostgresConnector>>,
+    pub ledger_db: DB,
+    pub tx_status: Arc<TxStatusNotifier>,
+    pub commitment_deferral: Arc<std::sync::Mutex<CommitmentDeferral>>,
+    pub block_stats: Arc<std::sync::Mutex<BlockStatsTracker>>,
+    pub relay: Option<Arc<Mutex<MetaTxRelay>>>,
+    pub clock: SharedClock,
 }
 
-pub(crate) fn create_rpc_module<C: sov_modules_api::Context>(
-    rpc_context: RpcContext<C>,
-) -> Result<RpcModule<RpcContext<C>>, jsonrpsee::core::RegisterMethodError> {
+/// Params of `eth_sendBundle`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SendBundleRequest {
+    /// RLP encoded signed txs, in execution order
+    txs: Vec<Bytes>,
+    /// L2 height the bundle is for. The next L2 block if not set.
+    #[serde(default)]
+    block_number: Option<U64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SendBundleResponse {
+    bundle_hash: B256,
+}
+
+/// Response of `citrea_getCommitmentQueue`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CommitmentQueueResponse {
+    /// L2 heights which are neither committed nor in a pending commitment
+    uncommitted_l2_range: Option<(u64, u64)>,
+    /// L2 ranges of the commitments sent to DA which are not confirmed yet
+    pending_commitments: Vec<(u64, u64)>,
+    #[serde(flatten)]
+    deferral: CommitmentDeferral,
+}
+
+pub(crate) fn create_rpc_module<
+    C: sov_modules_api::Context,
+    DB: SequencerLedgerOps + Send + Sync + 'static,
+>(
+    rpc_context: RpcContext<C, DB>,
+) -> Result<RpcModule<RpcContext<C, DB>>, jsonrpsee::core::RegisterMethodError> {
     let test_mode = rpc_context.test_mode;
+    let enable_admin_rpc = rpc_context.enable_admin_rpc;
+    let enable_relay = rpc_context.relay.is_some();
     let mut rpc = RpcModule::new(rpc_context);
     rpc.register_async_method("eth_sendRawTransaction", |parameters, ctx| async move {
         debug!("Sequencer: eth_sendRawTransaction");
+        ensure_not_shutting_down(&ctx)?;
         let data: Bytes = parameters.one()?;
+        send_raw_transaction(&ctx, data).await
+    })?;
 
-        // Only check if the signature is valid for now
-        let recovered: reth_primitives::PooledTransactionsElementEcRecovered =
-            recover_raw_transaction(data.clone())?;
+    // Bundle txs skip the mempool, so they are never visible to other users before inclusion
+    rpc.register_async_method("eth_sendBundle", |parameters, ctx| async move {
+        debug!("Sequencer: eth_sendBundle");
+        ensure_not_shutting_down(&ctx)?;
+        let request: SendBundleRequest = parameters.one()?;
 
-        let pool_transaction = EthPooledTransaction::from_recovered_pooled_transaction(recovered);
+        if request.txs.is_empty() || request.txs.len() > MAX_BUNDLE_TXS {
+            return Err(invalid_bundle(format!(
+                "bundle must have between 1 and {MAX_BUNDLE_TXS} txs"
+            )));
+        }
 
-        // submit the transaction to the pool with an `External` origin
-        let hash: B256 = ctx
-            .mempool
-            .add_external_transaction(pool_transaction.clone())
-            .await
-            .map_err(EthApiError::from)?;
+        let l2_height = request.block_number.map(|height| height.to::<u64>());
+        if let Some(l2_height) = l2_height {
+            let head_l2_height = ctx
+                .ledger_db
+                .get_head_soft_batch()
+                .map_err(|e| {
+                    ErrorObjectOwned::owned(
+                        INTERNAL_ERROR_CODE,
+                        INTERNAL_ERROR_MSG,
+                        Some(e.to_string()),
+                    )
+                })?
+                .map(|(l2_height, _)| l2_height.0);
+            if head_l2_height.is_some_and(|head_l2_height| l2_height <= head_l2_height) {
+                return Err(invalid_bundle("block number is in the past".to_string()));
+            }
+        }
 
-        if let Some(pool) = &ctx.pg_pool {
-            let mut rlp_encoded_tx = Vec::new();
-            pool_transaction
-                .to_recovered_transaction()
-                .into_signed()
-                .encode_enveloped(&mut rlp_encoded_tx);
-            // Do not return error here just log
-            match pool.insert_mempool_tx(hash.to_vec(), rlp_encoded_tx).await {
-                Ok(_) => (),
-                Err(e) => tracing::warn!("Failed to insert mempool tx into db: {:?}", e),
+        let mut txs = Vec::with_capacity(request.txs.len());
+        for data in request.txs {
+            let recovered = recover_raw_transaction(data.clone())?.into_ecrecovered_transaction();
+            if recovered.signer() == SYSTEM_SIGNER {
+                return Err(invalid_bundle(
+                    "system transactions are not allowed".to_string(),
+                ));
+            }
+            // A public tx with the same nonce would make one of them fail
+            if ctx.mempool.has_nonce(recovered.signer(), recovered.nonce()) {
+                return Err(invalid_bundle(format!(
+                    "nonce of tx {} is already used by a mempool tx",
+                    recovered.hash()
+                )));
+            }
+            let rlp_tx = RlpEvmTransaction {
+                rlp: recovered.clone().into_signed().envelope_encoded().to_vec(),
             };
+            txs.push((recovered.hash(), rlp_tx));
         }
 
-        Ok::<B256, ErrorObjectOwned>(hash)
+        let bundle = Bundle::new(txs, l2_height);
+        let bundle_hash = bundle.hash;
+        ctx.bundle_pool
+            .lock()
+            .await
+            .add(bundle)
+            .map_err(|e| invalid_bundle(e.to_string()))?;
+
+        Ok::<SendBundleResponse, ErrorObjectOwned>(SendBundleResponse { bundle_hash })
     })?;
 
+    rpc.register_async_method("citrea_getCommitmentQueue", |_, ctx| async move {
+        debug!("Sequencer: citrea_getCommitmentQueue");
+        let to_rpc_error = |e: anyhow::Error| {
+            ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, Some(e.to_string()))
+        };
+
+        let uncommitted_l2_range = commitment_controller::get_uncommitted_l2_range(&ctx.ledger_db)
+            .map_err(to_rpc_error)?
+            .map(|info| (info.l2_height_range.start().0, info.l2_height_range.end().0));
+        let pending_commitments = ctx
+            .ledger_db
+            .get_pending_commitments_l2_range()
+            .map_err(to_rpc_error)?
+            .into_iter()
+            .map(|(start, end)| (start.0, end.0))
+            .collect();
+        let deferral = *ctx.commitment_deferral.lock().unwrap();
+
+        Ok::<CommitmentQueueResponse, ErrorObjectOwned>(CommitmentQueueResponse {
+            uncommitted_l2_range,
+            pending_commitments,
+            deferral,
+        })
+    })?;
+
+    // Usage of the recent blocks, to tune the block gas limit and the commitment cadence
+    rpc.register_async_method("citrea_getBlockBuildStats", |parameters, ctx| async move {
+        let count: Option<usize> = parameters.sequence().optional_next()?;
+        debug!("Sequencer: citrea_getBlockBuildStats({:?})", count);
+        let report = ctx
+            .block_stats
+            .lock()
+            .unwrap()
+            .report(count.unwrap_or(BLOCK_STATS_WINDOW));
+        Ok::<BlockBuildReport, ErrorObjectOwned>(report)
+    })?;
+
+    // Lets users debug their stuck txs without operator help
+    rpc.register_async_method("citrea_getAccountQueue", |parameters, ctx| async move {
+        let address: Address = parameters.one()?;
+        debug!("Sequencer: citrea_getAccountQueue({})", address);
+        let to_rpc_error = |e: anyhow::Error| {
+            ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, Some(e.to_string()))
+        };
+
+        let l1_fee_rate = latest_l1_fee_rate(&ctx.ledger_db).map_err(to_rpc_error)?;
+        let queue = ctx
+            .mempool
+            .account_queue(address, l1_fee_rate)
+            .map_err(to_rpc_error)?;
+
+        Ok::<AccountQueue, ErrorObjectOwned>(queue)
+    })?;
+
+    // Nothing is committed, so searchers can see how their txs would be built into the next block.
+    // Blocks are simulated off the block production loop, only a few at a time.
+    rpc.register_async_method("citrea_simulateBlock", |parameters, ctx| async move {
+        debug!("Sequencer: citrea_simulateBlock");
+        let raw_txs: Vec<Bytes> = parameters.one()?;
+        if raw_txs.is_empty() || raw_txs.len() > MAX_SIMULATED_TXS {
+            return Err(ErrorObjectOwned::owned(
+                INVALID_PARAMS_CODE,
+                format!("must simulate between 1 and {MAX_SIMULATED_TXS} txs"),
+                None::<String>,
+            ));
+        }
+
+        let mut txs = Vec::with_capacity(raw_txs.len());
+        for data in raw_txs {
+            let recovered = recover_raw_transaction(data)?.into_ecrecovered_transaction();
+            if recovered.signer() == SYSTEM_SIGNER {
+                return Err(CitreaError::SystemTransactionNotAllowed.into());
+            }
+            txs.push(recovered);
+        }
+
+        let to_rpc_error =
+            |e: String| ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, Some(e));
+        let Ok(permit) = ctx.simulation_permits.clone().try_acquire_owned() else {
+            return Err(ErrorObjectOwned::owned(
+                QUOTA_EXCEEDED_ERROR_CODE,
+                format!("{MAX_PENDING_SIMULATIONS} simulations are pending, try again later"),
+                None::<String>,
+            ));
+        };
+        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+        ctx.simulate_tx
+            .try_send(SimulateBlockRequest {
+                txs,
+                response_tx,
+                _permit: permit,
+            })
+            .map_err(|e| to_rpc_error(format!("Could not send simulation request: {e}")))?;
+        response_rx
+            .await
+            .map_err(|e| to_rpc_error(format!("Simulation was cancelled: {e}")))?
+            .map_err(|e| to_rpc_error(e.to_string()))
+    })?;
+
+    rpc.register_subscription(
+        "citrea_subscribeTxStatus",
+        "citrea_txStatus",
+        "citrea_unsubscribeTxStatus",
+        |parameters, pending, ctx| async move {
+            // Without tx hashes, the events of all txs are sent
+            let tx_hashes: Option<HashSet<B256>> = match parameters.sequence().optional_next() {
+                Ok(tx_hashes) => tx_hashes,
+                Err(err) => {
+                    pending.reject(err).await;
+                    return Ok(());
+                }
+            };
+
+            let mut rx = ctx.tx_status.subscribe();
+            let subscription = pending.accept().await?;
+            loop {
+                let event = tokio::select! {
+                    _ = subscription.closed() => break,
+                    event = rx.recv() => event,
+                };
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Tx status subscriber lagged behind by {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if tx_hashes
+                    .as_ref()
+                    .is_some_and(|tx_hashes| !tx_hashes.contains(&event.tx_hash))
+                {
+                    continue;
+                }
+
+                let msg = SubscriptionMessage::from_json(&event)?;
+                if subscription.send(msg).await.is_err() {
+                    break;
+                }
+            }
+            SubscriptionResult::Ok(())
+        },
+    )?;
+
     if test_mode {
         rpc.register_async_method("citrea_testPublishBlock", |_, ctx| async move {
             debug!("Sequencer: citrea_testPublishBlock");
@@ -79,6 +328,122 @@ pub(crate) fn create_rpc_module<C: sov_modules_api::Context>(
         })?;
     }
 
+    if enable_relay {
+        // Calls of users without funds are executed through the trusted forwarder,
+        // in txs of the relayer which pays their gas and L1 fees
+        rpc.register_async_method(
+            "citrea_relayMetaTransaction",
+            |parameters, ctx| async move {
+                debug!("Sequencer: citrea_relayMetaTransaction");
+                ensure_not_shutting_down(&ctx)?;
+                let request: MetaTransactionRequest = parameters.one()?;
+                let to_rpc_error = |e: anyhow::Error| {
+                    ErrorObjectOwned::owned(
+                        INTERNAL_ERROR_CODE,
+                        INTERNAL_ERROR_MSG,
+                        Some(e.to_string()),
+                    )
+                };
+
+                // Held until the relay tx is submitted, so relay txs get consecutive nonces
+                let mut relay = ctx
+                    .relay
+                    .as_ref()
+                    .expect("Relay must be set when its method is registered")
+                    .lock()
+                    .await;
+
+                let evm = Evm::<C>::default();
+                let mut working_set = WorkingSet::<C>::new(ctx.storage.clone());
+                let chain_id = evm.get_chain_config(&mut working_set).chain_id;
+
+                // Calls to an empty account succeed, the relayer would pay for doing nothing
+                if evm
+                    .get_code(TrustedForwarder::address(), None, &mut working_set)?
+                    .is_empty()
+                {
+                    return Err(to_rpc_error(anyhow::anyhow!(
+                        "Trusted forwarder is not deployed, meta-transactions are not relayed"
+                    )));
+                }
+
+                relay.prune_pending(|tx_hash| ctx.mempool.get(tx_hash).is_some());
+                let input =
+                    relay.forwarder_input(&request, chain_id, ctx.clock.unix_timestamp())?;
+
+                // Fails if the forwarder reverts, so the relayer does not pay for failing requests
+                let gas_limit = evm.eth_estimate_gas(
+                    TransactionRequest {
+                        from: Some(relay.relayer()),
+                        to: Some(TxKind::Call(TrustedForwarder::address())),
+                        input: TransactionInput::new(input.clone()),
+                        ..Default::default()
+                    },
+                    None,
+                    &mut working_set,
+                )?;
+                let gas_limit = gas_limit.saturating_to();
+                relay.check_gas_budget(gas_limit)?;
+
+                let l1_fee_rate = latest_l1_fee_rate(&ctx.ledger_db).map_err(to_rpc_error)?;
+                let base_fee = ctx.mempool.next_block_base_fee().map_err(to_rpc_error)?;
+                // Leaves room for the base fee to rise before the tx is included
+                let max_fee_per_gas = ctx
+                    .mempool
+                    .min_gas_price(l1_fee_rate)
+                    .max(base_fee as u128 * 2);
+                let nonce = ctx
+                    .mempool
+                    .next_nonce(relay.relayer())
+                    .map_err(to_rpc_error)?;
+
+                let tx = relay
+                    .sign_relay_tx(input, chain_id, nonce, gas_limit, max_fee_per_gas)
+                    .map_err(to_rpc_error)?;
+                let hash = send_raw_transaction(&ctx, tx.envelope_encoded()).await?;
+                relay.consume_quota(&request, gas_limit, hash);
+                // The tx is sent already, so failing to persist the quotas does not fail the request
+                if let Err(e) = ctx.ledger_db.set_relay_quotas(&relay.quota_state()) {
+                    error!("Failed to persist relay quotas: {}", e);
+                }
+                debug!(tx_hash = %hash, from = %request.from, "Meta-transaction relayed");
+
+                Ok::<B256, ErrorObjectOwned>(hash)
+            },
+        )?;
+    }
+
+    if enable_admin_rpc {
+        rpc.register_async_method("citrea_shutdown", |_, ctx| async move {
+            info!("Sequencer: citrea_shutdown");
+            // Stop accepting txs right away, the sequencer shuts down after the current block
+            ctx.shutting_down.store(true, Ordering::SeqCst);
+            ctx.shutdown_tx.unbounded_send(()).map_err(|e| {
+                ErrorObjectOwned::owned(
+                    INTERNAL_ERROR_CODE,
+                    INTERNAL_ERROR_MSG,
+                    Some(format!("Could not send shutdown request: {e}")),
+                )
+            })?;
+            Ok::<(), ErrorObjectOwned>(())
+        })?;
+
+        rpc.register_async_method("citrea_promoteToPrimary", |parameters, ctx| async move {
+            let force: Option<bool> = parameters.sequence().optional_next()?;
+            info!("Sequencer: citrea_promoteToPrimary");
+            ctx.promote_tx
+                .unbounded_send(force.unwrap_or(false))
+                .map_err(|e| {
+                    ErrorObjectOwned::owned(
+                        INTERNAL_ERROR_CODE,
+                        INTERNAL_ERROR_MSG,
+                        Some(format!("Could not send promotion request: {e}")),
+                    )
+                })?;
+            Ok::<(), ErrorObjectOwned>(())
+        })?;
+    }
+
     rpc.register_async_method("eth_getTransactionByHash", |parameters, ctx| async move {
         let mut params = parameters.sequence();
         let hash: B256 = params.next()?;
@@ -109,6 +474,64 @@ pub(crate) fn create_rpc_module<C: sov_modules_api::Context>(
         }
     })?;
 
+    // The RPC server serves the `pending` block tag with the block being built
+    rpc.register_async_method("citrea_getPendingBlock", |parameters, ctx| async move {
+        let details: Option<bool> = parameters.sequence().optional_next()?;
+        debug!("Sequencer: citrea_getPendingBlock({:?})", details);
+
+        let txs = pending_txs(&ctx)?;
+        let evm = Evm::<C>::default();
+        let mut working_set = WorkingSet::<C>::new(ctx.storage.clone());
+        let block = evm.pending_block(
+            txs,
+            ctx.clock.unix_timestamp(),
+            details.unwrap_or(false),
+            &mut working_set,
+        );
+        Ok::<Option<RichBlock>, ErrorObjectOwned>(Some(block))
+    })?;
+
+    rpc.register_async_method(
+        "citrea_getPendingBlockTransactionCount",
+        |_, ctx| async move {
+            debug!("Sequencer: citrea_getPendingBlockTransactionCount");
+            let txs = pending_txs(&ctx)?;
+            Ok::<Option<U256>, ErrorObjectOwned>(Some(U256::from(txs.len())))
+        },
+    )?;
+
+    rpc.register_async_method(
+        "citrea_getPendingTransactionByIndex",
+        |parameters, ctx| async move {
+            let index: Index = parameters.one()?;
+            debug!(
+                "Sequencer: citrea_getPendingTransactionByIndex({:?})",
+                index
+            );
+            let tx = pending_txs(&ctx)?
+                .into_iter()
+                .nth(usize::from(index))
+                .map(from_recovered);
+            Ok::<Option<reth_rpc_types::Transaction>, ErrorObjectOwned>(tx)
+        },
+    )?;
+
+    rpc.register_async_method(
+        "citrea_getPendingTransactionCount",
+        |parameters, ctx| async move {
+            let address: Address = parameters.one()?;
+            debug!("Sequencer: citrea_getPendingTransactionCount({})", address);
+            let nonce = ctx.mempool.next_nonce(address).map_err(|e| {
+                ErrorObjectOwned::owned(
+                    INTERNAL_ERROR_CODE,
+                    INTERNAL_ERROR_MSG,
+                    Some(e.to_string()),
+                )
+            })?;
+            Ok::<U256, ErrorObjectOwned>(U256::from(nonce))
+        },
+    )?;
+
     rpc.register_async_method(
         "citrea_sendRawDepositTransaction",
         |parameters, ctx| async move {
@@ -116,6 +539,7 @@ pub(crate) fn create_rpc_module<C: sov_modules_api::Context>(
             let deposit: Bytes = params.next()?;
 
             debug!("Sequencer: citrea_sendRawDepositTransaction");
+            ensure_not_shutting_down(&ctx)?;
 
             let evm = Evm::<C>::default();
             let mut working_set = WorkingSet::<C>::new(ctx.storage.clone());
@@ -147,3 +571,147 @@ pub(crate) fn create_rpc_module<C: sov_modules_api::Context>(
     )?;
     Ok(rpc)
 }
+
+/// Txs of the block being built: the ones the block builder takes next from the mempool,
+/// up to the block gas limit by their gas limits
+fn pending_txs<C: sov_modules_api::Context, DB: SequencerLedgerOps>(
+    ctx: &RpcContext<C, DB>,
+) -> Result<Vec<TransactionSignedEcRecovered>, ErrorObjectOwned> {
+    let evm = Evm::<C>::default();
+    let mut working_set = WorkingSet::<C>::new(ctx.storage.clone());
+    let block_gas_limit = evm.get_chain_config(&mut working_set).block_gas_limit;
+    let base_fee = ctx.mempool.next_block_base_fee().map_err(|e| {
+        ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, Some(e.to_string()))
+    })?;
+
+    let mut best_txs = ctx
+        .mempool
+        .best_transactions_with_attributes(BestTransactionsAttributes::base_fee(base_fee));
+    let mut txs = vec![];
+    let mut gas = 0;
+    while let Some(tx) = best_txs.next() {
+        if gas + tx.gas_limit() > block_gas_limit {
+            // The later txs of the sender can't be included without it
+            best_txs.mark_invalid(&tx);
+            continue;
+        }
+        gas += tx.gas_limit();
+        txs.push(tx.to_recovered_transaction());
+        if gas + MIN_TRANSACTION_GAS > block_gas_limit {
+            break;
+        }
+    }
+    Ok(txs)
+}
+
+fn invalid_bundle(msg: String) -> ErrorObjectOwned {
+    ErrorObjectOwned::owned(INVALID_PARAMS_CODE, msg, None::<String>)
+}
+
+/// Adds the tx to the mempool, persists it and gossips it to the other sequencers
+async fn send_raw_transaction<C: sov_modules_api::Context, DB: SequencerLedgerOps>(
+    ctx: &RpcContext<C, DB>,
+    data: Bytes,
+) -> Result<B256, ErrorObjectOwned> {
+    // Only check if the signature is valid for now
+    let recovered: reth_primitives::PooledTransactionsElementEcRecovered =
+        recover_raw_transaction(data.clone())?;
+
+    let pool_transaction = EthPooledTransaction::from_recovered_pooled_transaction(recovered);
+    ctx.tx_status
+        .notify(*pool_transaction.hash(), TxStatus::Received);
+
+    let l1_fee_rate = latest_l1_fee_rate(&ctx.ledger_db).map_err(|e| {
+        ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, INTERNAL_ERROR_MSG, Some(e.to_string()))
+    })?;
+
+    // submit the transaction to the pool with an `External` origin
+    let AddedTransaction { hash, evicted } = ctx
+        .mempool
+        .add_external_transaction(pool_transaction.clone(), l1_fee_rate)
+        .await
+        .map_err(pool_error_to_rpc)?;
+    if let Some(request_id) = current_request_id() {
+        ctx.tx_status.track_request_id(hash, request_id);
+    }
+    debug!(tx_hash = %hash, "Transaction added to mempool");
+
+    let mut rlp_encoded_tx = Vec::new();
+    pool_transaction
+        .to_recovered_transaction()
+        .into_signed()
+        .encode_enveloped(&mut rlp_encoded_tx);
+
+    // Persist the tx so it is not lost if the sequencer restarts before including it.
+    // Do not return error here just log
+    if let Err(e) = ctx
+        .ledger_db
+        .insert_mempool_tx(hash.0, rlp_encoded_tx.clone())
+    {
+        tracing::warn!("Failed to persist mempool tx: {:?}", e);
+    }
+
+    if let Some(pool) = &ctx.pg_pool {
+        // Do not return error here just log
+        match pool.insert_mempool_tx(hash.to_vec(), rlp_encoded_tx).await {
+            Ok(_) => (),
+            Err(e) => tracing::warn!("Failed to insert mempool tx into db: {:?}", e),
+        };
+    }
+
+    // The evicted txs are not restored after a restart
+    if !evicted.is_empty() {
+        for tx_hash in &evicted {
+            ctx.tx_status.take_request_id(tx_hash);
+            ctx.tx_status.notify(*tx_hash, TxStatus::Dropped);
+        }
+        if let Err(e) = ctx
+            .ledger_db
+            .remove_mempool_txs(evicted.iter().map(|tx_hash| tx_hash.0).collect())
+        {
+            tracing::warn!("Failed to remove evicted mempool txs: {:?}", e);
+        }
+        if let Some(pool) = &ctx.pg_pool {
+            let txs = evicted.iter().map(|tx_hash| tx_hash.to_vec()).collect();
+            if let Err(e) = pool.delete_txs_by_tx_hashes(txs).await {
+                tracing::warn!("Failed to remove evicted txs from mempool db: {:?}", e);
+            }
+        }
+    }
+
+    // Keep the mempools of the other sequencers warm.
+    // Peers which already know the tx reject it, so gossip does not loop.
+    for peer in ctx.tx_gossip_peers.iter().cloned() {
+        let data = data.clone();
+        tokio::spawn(async move {
+            if let Err(e) = peer.send_raw_tx(data).await {
+                debug!("Failed to gossip tx to {}: {:?}", peer.rpc_url, e);
+            }
+        });
+    }
+
+    Ok(hash)
+}
+
+/// Converts a mempool error to an RPC error, Citrea's own rejections keep their codes and data.
+fn pool_error_to_rpc(error: PoolError) -> ErrorObjectOwned {
+    if let PoolErrorKind::Other(other) = &error.kind {
+        if let Some(error) = other.downcast_ref::<CitreaError>() {
+            return error.clone().into();
+        }
+    }
+    EthApiError::from(error).into()
+}
+
+fn ensure_not_shutting_down<C: sov_modules_api::Context, DB: SequencerLedgerOps>(
+    ctx: &RpcContext<C, DB>,
+) -> Result<(), ErrorObjectOwned> {
+    if ctx.shutting_down.load(Ordering::SeqCst) {
+        return Err(ErrorObjectOwned::owned(
+            INTERNAL_ERROR_CODE,
+            "Sequencer is shutting down",
+            None::<String>,
+        ));
+    }
+    Ok(())
+}
@@ -1,30 +1,39 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::vec;
 
-use anyhow::anyhow;
-use borsh::BorshDeserialize;
-use citrea_evm::{CallMessage, Evm, RlpEvmTransaction, MIN_TRANSACTION_GAS};
+use anyhow::{anyhow, bail};
+use citrea_evm::{
+    CallMessage, CitreaError, Evm, PendingTransaction, RlpEvmTransaction, MIN_TRANSACTION_GAS,
+};
 use citrea_primitives::types::SoftConfirmationHash;
+use citrea_primitives::{
+    extract_sequencer_leases, extract_slot_inbox, SharedClock, SystemClock, DA_PAYLOAD_MODE,
+};
+use citrea_sequencer_registry::SequencerRegistry;
 use citrea_stf::runtime::Runtime;
 use digest::Digest;
 use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
 use futures::StreamExt;
-use hyper::Method;
-use jsonrpsee::server::{BatchRequestConfig, ServerBuilder};
 use jsonrpsee::RpcModule;
-use reth_primitives::{Address, FromRecoveredPooledTransaction, IntoRecoveredTransaction, TxHash};
+use reth_primitives::{
+    keccak256, Address, FromRecoveredPooledTransaction, IntoRecoveredTransaction, TxHash,
+};
 use reth_provider::{AccountReader, BlockReaderIdExt};
 use reth_transaction_pool::{
     BestTransactions, BestTransactionsAttributes, ChangedAccount, EthPooledTransaction,
-    ValidPoolTransaction,
+    FullTransactionEvent, ValidPoolTransaction,
 };
+use sequencer_client::{GetSoftBatchResponse, SequencerClient};
 use shared_backup_db::{CommitmentStatus, PostgresConnector};
-use soft_confirmation_rule_enforcer::SoftConfirmationRuleEnforcer;
+use soft_confirmation_rule_enforcer::{
+    forced_tx_hash, PendingForcedTx, SoftConfirmationRuleEnforcer,
+};
 use sov_accounts::Accounts;
 use sov_accounts::Response::{AccountEmpty, AccountExists};
 use sov_db::ledger_db::{SequencerLedgerOps, SlotCommit};
@@ -36,29 +45,47 @@ use sov_modules_api::{
     StateDiff, UnsignedSoftConfirmationBatch, WorkingSet,
 };
 use sov_modules_stf_blueprint::StfBlueprintTrait;
-use sov_rollup_interface::da::{BlockHeaderTrait, DaData, DaSpec, SequencerCommitment};
+use sov_rollup_interface::da::{
+    BlockHeaderTrait, CommitmentWithStateDiff, DaData, DaSpec, SequencerCommitment, SequencerLease,
+    SlotInbox,
+};
 use sov_rollup_interface::services::da::{BlobWithNotifier, DaService};
 use sov_rollup_interface::stf::{SoftBatchReceipt, StateTransitionFunction};
 use sov_rollup_interface::storage::HierarchicalStorageManager;
-use sov_rollup_interface::zk::ZkvmHost;
-use sov_stf_runner::{InitVariant, RollupPublicKeys, RpcConfig};
-use tokio::sync::oneshot::channel as oneshot_channel;
-use tokio::sync::{broadcast, mpsc, Mutex};
-use tokio::time::{sleep, Instant};
-use tower_http::cors::{Any, CorsLayer};
-use tracing::{debug, error, info, instrument, trace, warn};
-
-use crate::commitment_controller;
-use crate::config::SequencerConfig;
+use sov_rollup_interface::zk::{Zkvm, ZkvmHost};
+use sov_stf_runner::{
+    apply_fee_ceilings, create_config_rpc_module, spawn_rpc_server, ConfigReloader, InitVariant,
+    RollupPublicKeys, RpcConfig, RpcServerOptions,
+};
+use tokio::sync::oneshot::{self, channel as oneshot_channel};
+use tokio::sync::{broadcast, mpsc, Mutex, Semaphore};
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, instrument, trace, warn, Instrument};
+
+use crate::block_stats::{BlockBuildStats, BlockStatsTracker};
+use crate::bundle_pool::{Bundle, BundlePool};
+use crate::commitment_controller::{
+    self, CommitmentDecision, CommitmentDeferral, CommitmentTrigger,
+};
+use crate::config::{DaPayloadMode, SequencerConfig, StandbyConfig};
 use crate::db_provider::DbProvider;
 use crate::deposit_data_mempool::DepositDataMempool;
-use crate::mempool::CitreaMempool;
+use crate::lease::LeaseTracker;
+use crate::mempool::{CitreaMempool, MIN_L1_DIFF_SIZE};
+use crate::metrics::{SEQUENCER_COMMITMENTS, SEQUENCER_STATE_DIFF_COMPRESSED_BYTES};
+use crate::relay::MetaTxRelay;
 use crate::rpc::{create_rpc_module, RpcContext};
-use crate::utils::recover_raw_transaction;
-
-const MAX_STATEDIFF_SIZE_COMMITMENT_THRESHOLD: u64 = 300 * 1024;
+use crate::simulation::{SimulateBlockRequest, SimulationSnapshot, MAX_PENDING_SIMULATIONS};
+use crate::tx_status::{TxStatus, TxStatusNotifier};
+use crate::utils::{
+    block_timestamp, latest_l1_fee_rate, recover_raw_transaction, StateDiffSizeEstimator,
+};
 
 type StateRoot<ST, Vm, Da> = <ST as StateTransitionFunction<Vm, Da>>::StateRoot;
+
+/// Txs skipped for the state diff budget before the block is considered full,
+/// each skip replays the dry run of the block
+pub(crate) const MAX_STATE_DIFF_SKIPS: usize = 4;
 /// Represents information about the current DA state.
 ///
 /// Contains previous height, latest finalized block and fee rate.
@@ -72,27 +99,54 @@ where
     Vm: ZkvmHost,
     Stf: StateTransitionFunction<Vm, Da::Spec, Condition = <Da::Spec as DaSpec>::ValidityCondition>
         + StfBlueprintTrait<C, Da::Spec, Vm>,
-    DB: SequencerLedgerOps + Send + Clone + 'static,
+    DB: SequencerLedgerOps + Send + Sync + Clone + 'static,
 {
     da_service: Da,
     mempool: Arc<CitreaMempool<C>>,
-    sov_tx_signer_priv_key: C::PrivateKey,
+    sov_tx_signer_priv_key: Arc<C::PrivateKey>,
     l2_force_block_tx: UnboundedSender<()>,
     l2_force_block_rx: UnboundedReceiver<()>,
+    shutdown_tx: UnboundedSender<()>,
+    shutdown_rx: UnboundedReceiver<()>,
+    shutting_down: Arc<AtomicBool>,
+    promote_tx: UnboundedSender<bool>,
+    promote_rx: UnboundedReceiver<bool>,
+    simulate_tx: mpsc::Sender<SimulateBlockRequest>,
+    simulate_rx: mpsc::Receiver<SimulateBlockRequest>,
+    /// Permits of the simulations which are queued or running
+    simulation_permits: Arc<Semaphore>,
+    commitment_tasks: Vec<JoinHandle<()>>,
     db_provider: DbProvider<C>,
     storage: C::Storage,
     ledger_db: DB,
     config: SequencerConfig,
-    stf: Stf,
+    stf: Arc<Stf>,
     deposit_mempool: Arc<Mutex<DepositDataMempool>>,
+    bundle_pool: Arc<Mutex<BundlePool>>,
     storage_manager: Sm,
     state_root: StateRoot<Stf, Vm, Da::Spec>,
     batch_hash: SoftConfirmationHash,
     sequencer_pub_key: Vec<u8>,
+    prover_da_pub_key: Vec<u8>,
+    public_keys: RollupPublicKeys,
+    /// Lease of the sequencer keys, if instances sharing them are fenced
+    lease: Option<LeaseTracker>,
     rpc_config: RpcConfig,
     soft_confirmation_rule_enforcer: SoftConfirmationRuleEnforcer<C, Da::Spec>,
+    /// Forced txs posted on DA which are not included yet, by the hash the rule enforcer tracks them by
+    forced_txs: HashMap<[u8; 32], RlpEvmTransaction>,
     last_state_diff: StateDiff,
+    /// Estimated compressed size of `last_state_diff`
+    last_state_diff_size: u64,
+    state_diff_size_estimator: StateDiffSizeEstimator,
+    last_commitment_instant: Instant,
+    commitment_deferral: Arc<std::sync::Mutex<CommitmentDeferral>>,
+    block_stats: Arc<std::sync::Mutex<BlockStatsTracker>>,
+    relay: Option<Arc<Mutex<MetaTxRelay>>>,
     soft_confirmation_tx: broadcast::Sender<u64>,
+    tx_status: Arc<TxStatusNotifier>,
+    clock: SharedClock,
+    config_reloader: Option<Arc<ConfigReloader>>,
 }
 
 enum L2BlockMode {
@@ -100,20 +154,32 @@ enum L2BlockMode {
     NotEmpty,
 }
 
+/// A forced tx the next block can include
+struct ForcedTx {
+    rlp: RlpEvmTransaction,
+    /// The next block has to include it, as it can be on the DA block of its deadline
+    due: bool,
+}
+
 impl<C, Da, Sm, Vm, Stf, DB> CitreaSequencer<C, Da, Sm, Vm, Stf, DB>
 where
     C: Context,
     Da: DaService + Clone,
     Sm: HierarchicalStorageManager<Da::Spec>,
-    Vm: ZkvmHost,
+    Vm: ZkvmHost + 'static,
     Stf: StateTransitionFunction<
             Vm,
             Da::Spec,
             Condition = <Da::Spec as DaSpec>::ValidityCondition,
             PreState = Sm::NativeStorage,
             ChangeSet = Sm::NativeChangeSet,
-        > + StfBlueprintTrait<C, Da::Spec, Vm>,
-    DB: SequencerLedgerOps + Send + Clone + 'static,
+        > + StfBlueprintTrait<C, Da::Spec, Vm>
+        + Send
+        + Sync
+        + 'static,
+    Sm::NativeStorage: Send + 'static,
+    StateRoot<Stf, Vm, Da::Spec>: Send,
+    DB: SequencerLedgerOps + Send + Sync + Clone + 'static,
 {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -129,6 +195,9 @@ where
         soft_confirmation_tx: broadcast::Sender<u64>,
     ) -> anyhow::Result<Self> {
         let (l2_force_block_tx, l2_force_block_rx) = unbounded();
+        let (shutdown_tx, shutdown_rx) = unbounded();
+        let (promote_tx, promote_rx) = unbounded();
+        let (simulate_tx, simulate_rx) = mpsc::channel(MAX_PENDING_SIMULATIONS);
 
         let (prev_state_root, prev_batch_hash) = match init_variant {
             InitVariant::Initialized((state_root, batch_hash)) => {
@@ -157,13 +226,50 @@ where
 
         let deposit_mempool = Arc::new(Mutex::new(DepositDataMempool::new()));
 
-        let sov_tx_signer_priv_key = C::PrivateKey::try_from(&hex::decode(&config.private_key)?)?;
+        let sov_tx_signer_priv_key =
+            Arc::new(C::PrivateKey::try_from(&hex::decode(&config.private_key)?)?);
 
         let soft_confirmation_rule_enforcer =
             SoftConfirmationRuleEnforcer::<C, <Da as DaService>::Spec>::default();
 
         // Initialize the sequencer with the last state diff from DB.
         let last_state_diff = ledger_db.get_state_diff()?;
+        let mut state_diff_size_estimator =
+            StateDiffSizeEstimator::new(config.commitment_policy.max_compressed_state_diff_size);
+        let last_state_diff_size =
+            state_diff_size_estimator.estimate(&bincode::serialize(&last_state_diff)?);
+
+        let block_stats = Arc::new(std::sync::Mutex::new(BlockStatsTracker::new(
+            config.min_soft_confirmations_per_commitment,
+            config.commitment_policy.max_compressed_state_diff_size,
+        )));
+
+        if DA_PAYLOAD_MODE == DaPayloadMode::StateDiff
+            && config.da_payload_mode != DaPayloadMode::StateDiff
+        {
+            bail!("Commitments of this network are only proven with their state diff, set `da_payload_mode = \"state_diff\"` in the sequencer config");
+        }
+        if config.standby.is_some() && config.lease.is_none() {
+            bail!("A standby sequencer requires a lease of the sequencer keys, set `lease` in the sequencer config");
+        }
+        let lease = config
+            .lease
+            .as_ref()
+            .map(|lease_config| {
+                ledger_db
+                    .get_sequencer_lease_checkpoint()
+                    .map(|checkpoint| LeaseTracker::new(lease_config, checkpoint))
+            })
+            .transpose()?;
+
+        let relay = config
+            .relay
+            .as_ref()
+            .map(|relay_config| {
+                MetaTxRelay::new(relay_config, ledger_db.get_relay_quotas()?)
+                    .map(|relay| Arc::new(Mutex::new(relay)))
+            })
+            .transpose()?;
 
         Ok(Self {
             da_service,
@@ -171,100 +277,97 @@ where
             sov_tx_signer_priv_key,
             l2_force_block_tx,
             l2_force_block_rx,
+            shutdown_tx,
+            shutdown_rx,
+            shutting_down: Arc::new(AtomicBool::new(false)),
+            promote_tx,
+            promote_rx,
+            simulate_tx,
+            simulate_rx,
+            simulation_permits: Arc::new(Semaphore::new(MAX_PENDING_SIMULATIONS)),
+            commitment_tasks: vec![],
             db_provider,
             storage,
             ledger_db,
             config,
-            stf,
+            stf: Arc::new(stf),
             deposit_mempool,
+            bundle_pool: Default::default(),
             storage_manager,
             state_root: prev_state_root,
             batch_hash: prev_batch_hash,
-            sequencer_pub_key: public_keys.sequencer_public_key,
+            sequencer_pub_key: public_keys.sequencer_public_key.clone(),
+            prover_da_pub_key: public_keys.prover_da_pub_key.clone(),
+            public_keys,
+            lease,
             rpc_config,
             soft_confirmation_rule_enforcer,
+            forced_txs: HashMap::new(),
             last_state_diff,
+            last_state_diff_size,
+            state_diff_size_estimator,
+            last_commitment_instant: Instant::now(),
+            commitment_deferral: Default::default(),
+            block_stats,
+            relay,
             soft_confirmation_tx,
+            tx_status: Arc::new(TxStatusNotifier::new()),
+            clock: SystemClock::shared(),
+            config_reloader: None,
         })
     }
 
+    /// Uses the clock for block production, block timestamps, DA polling and timeouts
+    /// instead of the system time
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.last_commitment_instant = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Applies reloads of the config to the RPC server and the fee ceilings of the DA service,
+    /// and serves `citrea_reloadConfig` on the operator RPC server
+    pub fn with_config_reloader(mut self, config_reloader: Arc<ConfigReloader>) -> Self {
+        self.config_reloader = Some(config_reloader);
+        self
+    }
+
     pub async fn start_rpc_server(
         &self,
         channel: Option<tokio::sync::oneshot::Sender<SocketAddr>>,
         methods: RpcModule<()>,
     ) -> anyhow::Result<()> {
         let methods = self.register_rpc_methods(methods).await?;
-
-        let listen_address = SocketAddr::new(
-            self.rpc_config
-                .bind_host
-                .parse()
-                .map_err(|e| anyhow!("Failed to parse bind host: {}", e))?,
-            self.rpc_config.bind_port,
-        );
-
-        let max_connections = self.rpc_config.max_connections;
-        let max_subscriptions_per_connection = self.rpc_config.max_subscriptions_per_connection;
-        let max_request_body_size = self.rpc_config.max_request_body_size;
-        let max_response_body_size = self.rpc_config.max_response_body_size;
-        let batch_requests_limit = self.rpc_config.batch_requests_limit;
-
-        let cors = CorsLayer::new()
-            .allow_methods([Method::POST, Method::OPTIONS])
-            .allow_origin(Any)
-            .allow_headers(Any);
-        let middleware = tower::ServiceBuilder::new().layer(cors);
-
-        let _handle = tokio::spawn(async move {
-            let server = ServerBuilder::default()
-                .max_connections(max_connections)
-                .max_subscriptions_per_connection(max_subscriptions_per_connection)
-                .max_request_body_size(max_request_body_size)
-                .max_response_body_size(max_response_body_size)
-                .set_batch_request_config(BatchRequestConfig::Limit(batch_requests_limit))
-                .set_http_middleware(middleware)
-                .build([listen_address].as_ref())
-                .await;
-
-            match server {
-                Ok(server) => {
-                    let bound_address = match server.local_addr() {
-                        Ok(address) => address,
-                        Err(e) => {
-                            error!("{}", e);
-                            return;
-                        }
-                    };
-                    if let Some(channel) = channel {
-                        if let Err(e) = channel.send(bound_address) {
-                            error!("Could not send bound_address {}: {}", bound_address, e);
-                            return;
-                        }
-                    }
-                    info!("Starting RPC server at {} ", &bound_address);
-
-                    let _server_handle = server.start(methods);
-                    futures::future::pending::<()>().await;
-                }
-                Err(e) => {
-                    error!("Could not start RPC server: {}", e);
-                }
-            }
-        });
-        Ok(())
+        spawn_rpc_server(
+            &self.rpc_config,
+            methods,
+            self.ledger_db.clone(),
+            RpcServerOptions {
+                allow_cors: true,
+                config_updates: self
+                    .config_reloader
+                    .as_ref()
+                    .map(|config_reloader| config_reloader.subscribe()),
+                ..Default::default()
+            },
+            channel,
+        )
     }
 
     #[allow(clippy::too_many_arguments)]
     async fn dry_run_transactions(
         &mut self,
-        transactions: Box<
+        mut transactions: Box<
             dyn BestTransactions<Item = Arc<ValidPoolTransaction<EthPooledTransaction>>>,
         >,
         pub_key: &[u8],
         prestate: <Sm as HierarchicalStorageManager<<Da as DaService>::Spec>>::NativeStorage,
         da_block_header: <<Da as DaService>::Spec as DaSpec>::BlockHeader,
         mut signed_batch: SignedSoftConfirmationBatch,
+        slot_inbox: &SlotInbox,
         l2_block_mode: L2BlockMode,
+        forced_txs: Vec<ForcedTx>,
+        bundles: Vec<Bundle>,
     ) -> anyhow::Result<(Vec<RlpEvmTransaction>, Vec<TxHash>)> {
         match self.stf.begin_soft_batch(
             pub_key,
@@ -272,18 +375,116 @@ where
             prestate.clone(),
             Default::default(),
             &da_block_header,
+            slot_inbox,
             &mut signed_batch,
         ) {
             (Ok(()), mut working_set_to_discard) => {
                 let block_gas_limit = self.db_provider.cfg().block_gas_limit;
 
                 let evm = Evm::<C>::default();
+                let max_state_diff_size = self.config.max_l2_block_state_diff_size;
+                let mut all_txs = vec![];
+                let mut state_diff_size = 0u64;
+
+                // Applied txs are checkpointed right away, so a tx which is applied but left out
+                // is dropped by replaying the dry run of the txs included so far
+                let replay = |txs: &[RlpEvmTransaction]| {
+                    self.replay_dry_run(
+                        pub_key,
+                        prestate.clone(),
+                        &da_block_header,
+                        &signed_batch,
+                        slot_inbox,
+                        txs,
+                    )
+                };
+                // Txs which failed to pay the L1 fee, recorded in the state the replays drop
+                let mut l1_fee_failed_txs = vec![];
+
+                // Forced txs go first, the earliest posted first, and count against the gas and
+                // state diff budgets of the block. One which does not fit, or fails to execute,
+                // waits for a later block, unless the block has to include it by its deadline.
+                for forced_tx in forced_txs {
+                    let (batch_workspace, last_tx) =
+                        self.dry_run_tx(forced_tx.rlp.clone(), working_set_to_discard)?;
+
+                    let tx_state_diff_size = last_tx
+                        .filter(|last_tx| last_tx.hash() == keccak256(&forced_tx.rlp.rlp))
+                        .map(|last_tx| last_tx.l1_diff_size());
+                    let fits = tx_state_diff_size.is_some_and(|tx_state_diff_size| {
+                        state_diff_size + tx_state_diff_size <= max_state_diff_size
+                    });
+                    if fits || forced_tx.due {
+                        working_set_to_discard = batch_workspace;
+                        state_diff_size += tx_state_diff_size.unwrap_or_default();
+                        all_txs.push(forced_tx.rlp);
+                    } else {
+                        debug!(
+                            "Forced tx 0x{} is deferred to a later block",
+                            hex::encode(keccak256(&forced_tx.rlp.rlp))
+                        );
+                        working_set_to_discard = replay(&all_txs)?;
+                    }
+                }
 
                 match l2_block_mode {
                     L2BlockMode::NotEmpty => {
-                        let mut all_txs = vec![];
+                        let l1_fee_rate = signed_batch.l1_fee_rate();
+
+                        // Bundles go first, each is included with all of its txs or not at all
+                        let mut failed_bundles = vec![];
+                        for bundle in bundles {
+                            let mut batch_workspace = working_set_to_discard;
+                            let mut bundle_state_diff_size = 0;
+                            let mut all_included = true;
+                            for (rlp_tx, tx_hash) in bundle.txs.iter().zip(&bundle.tx_hashes) {
+                                let last_tx;
+                                (batch_workspace, last_tx) =
+                                    self.dry_run_tx(rlp_tx.clone(), batch_workspace)?;
+                                match last_tx {
+                                    Some(last_tx) if last_tx.hash() == *tx_hash => {
+                                        bundle_state_diff_size += last_tx.l1_diff_size();
+                                    }
+                                    _ => {
+                                        all_included = false;
+                                        break;
+                                    }
+                                }
+                            }
+
+                            if all_included
+                                && state_diff_size + bundle_state_diff_size <= max_state_diff_size
+                            {
+                                working_set_to_discard = batch_workspace;
+                                state_diff_size += bundle_state_diff_size;
+                                all_txs.extend(bundle.txs);
+                            } else {
+                                debug!("Bundle {} can't be included in this block", bundle.hash);
+                                working_set_to_discard = replay(&all_txs)?;
+                                failed_bundles.push(bundle);
+                            }
+                        }
+                        self.bundle_pool.lock().await.retry(failed_bundles);
+
+                        // Txs are yielded by effective priority fee, and a sender's txs only
+                        // in nonce order, so a later tx of a sender is never picked before an
+                        // earlier one.
+                        // Blocks are packed greedily: a tx which does not fit in the remaining
+                        // gas or state diff budget is skipped and the next ones are tried.
+                        // Skipping a tx over the state diff budget replays the dry run, so only
+                        // a few are skipped before the block is considered full.
+                        let mut state_diff_skips = 0;
+                        while let Some(evm_tx) = transactions.next() {
+                            // Requeued txs can't pay the L1 fee until the L1 fee rate drops
+                            if self
+                                .mempool
+                                .l1_fee_failed_rate(evm_tx.hash())
+                                .is_some_and(|failed_l1_fee_rate| l1_fee_rate >= failed_l1_fee_rate)
+                            {
+                                transactions.mark_invalid(&evm_tx);
+                                continue;
+                            }
 
-                        for evm_tx in transactions {
                             let rlp_tx = RlpEvmTransaction {
                                 rlp: evm_tx
                                     .to_recovered_transaction()
@@ -292,47 +493,72 @@ where
                                     .to_vec(),
                             };
 
-                            let call_txs = CallMessage {
-                                txs: vec![rlp_tx.clone()],
-                            };
-                            let raw_message = <Runtime<C, Da::Spec> as EncodeCall<
-                                citrea_evm::Evm<C>,
-                            >>::encode_call(call_txs);
-                            let signed_blob =
-                                self.make_blob(raw_message, &mut working_set_to_discard)?;
-
-                            let txs = vec![signed_blob.clone()];
-
-                            let (batch_workspace, _) = self
-                                .stf
-                                .apply_soft_batch_txs(txs.clone(), working_set_to_discard);
-
-                            working_set_to_discard = batch_workspace;
-
-                            let last_tx =
-                                evm.get_last_pending_transaction(&mut working_set_to_discard);
-
-                            if let Some(last_tx) = last_tx {
-                                if last_tx.hash() == *evm_tx.hash() {
+                            let (mut batch_workspace, last_tx) =
+                                self.dry_run_tx(rlp_tx.clone(), working_set_to_discard)?;
+
+                            match last_tx {
+                                Some(last_tx) if last_tx.hash() == *evm_tx.hash() => {
+                                    if state_diff_size + last_tx.l1_diff_size()
+                                        > max_state_diff_size
+                                    {
+                                        trace!(
+                                            "Tx {} does not fit in the state diff budget of the block",
+                                            evm_tx.hash()
+                                        );
+                                        l1_fee_failed_txs.extend(evm.get_l1_fee_failed_txs(
+                                            &mut batch_workspace.accessory_state(),
+                                        ));
+                                        working_set_to_discard = replay(&all_txs)?;
+                                        // Its descendants can't be executed in this block either
+                                        transactions.mark_invalid(&evm_tx);
+                                        state_diff_skips += 1;
+                                        if state_diff_skips >= MAX_STATE_DIFF_SKIPS {
+                                            break;
+                                        }
+                                        continue;
+                                    }
+
+                                    working_set_to_discard = batch_workspace;
+                                    state_diff_size += last_tx.l1_diff_size();
                                     all_txs.push(rlp_tx);
-                                }
 
-                                if last_tx.cumulative_gas_used()
-                                    >= block_gas_limit - MIN_TRANSACTION_GAS
-                                {
-                                    break;
+                                    if last_tx.cumulative_gas_used()
+                                        >= block_gas_limit - MIN_TRANSACTION_GAS
+                                        || state_diff_size + MIN_L1_DIFF_SIZE > max_state_diff_size
+                                    {
+                                        break;
+                                    }
+                                }
+                                last_tx => {
+                                    working_set_to_discard = batch_workspace;
+                                    // The tx was not included, so the sender now has a nonce
+                                    // gap and its descendants can't be executed in this block
+                                    transactions.mark_invalid(&evm_tx);
+
+                                    // Forced txs may have used up the block gas already
+                                    if last_tx.is_some_and(|last_tx| {
+                                        last_tx.cumulative_gas_used()
+                                            >= block_gas_limit - MIN_TRANSACTION_GAS
+                                    }) {
+                                        break;
+                                    }
                                 }
                             }
                         }
 
                         // before finalize we can get tx hashes that failed due to L1 fees.
                         // nasty hack to access state
-                        let l1_fee_failed_txs = evm
-                            .get_l1_fee_failed_txs(&mut working_set_to_discard.accessory_state());
+                        l1_fee_failed_txs.extend(
+                            evm.get_l1_fee_failed_txs(
+                                &mut working_set_to_discard.accessory_state(),
+                            ),
+                        );
+                        l1_fee_failed_txs.sort();
+                        l1_fee_failed_txs.dedup();
 
                         Ok((all_txs, l1_fee_failed_txs))
                     }
-                    L2BlockMode::Empty => Ok((vec![], vec![])),
+                    L2BlockMode::Empty => Ok((all_txs, vec![])),
                 }
             }
             (Err(err), batch_workspace) => {
@@ -349,6 +575,129 @@ where
         }
     }
 
+    /// Dry runs the block again with only the given txs
+    fn replay_dry_run(
+        &self,
+        pub_key: &[u8],
+        prestate: <Sm as HierarchicalStorageManager<<Da as DaService>::Spec>>::NativeStorage,
+        da_block_header: &<<Da as DaService>::Spec as DaSpec>::BlockHeader,
+        signed_batch: &SignedSoftConfirmationBatch,
+        slot_inbox: &SlotInbox,
+        txs: &[RlpEvmTransaction],
+    ) -> anyhow::Result<WorkingSet<C>> {
+        let (result, mut working_set) = self.stf.begin_soft_batch(
+            pub_key,
+            &self.state_root,
+            prestate,
+            Default::default(),
+            da_block_header,
+            slot_inbox,
+            &mut signed_batch.clone(),
+        );
+        result.map_err(|err| {
+            anyhow!(
+                "DryRun: Failed to apply begin soft confirmation hook: {:?}",
+                err
+            )
+        })?;
+        for rlp_tx in txs {
+            (working_set, _) = self.dry_run_tx(rlp_tx.clone(), working_set)?;
+        }
+        Ok(working_set)
+    }
+
+    /// Applies a single tx on top of the dry run state.
+    /// Returns the last pending tx of the block, which is the given tx if it was included.
+    fn dry_run_tx(
+        &self,
+        rlp_tx: RlpEvmTransaction,
+        working_set: WorkingSet<C>,
+    ) -> anyhow::Result<(WorkingSet<C>, Option<PendingTransaction>)> {
+        dry_run_tx::<C, Da::Spec, Vm, Stf>(
+            &self.stf,
+            &self.sov_tx_signer_priv_key,
+            rlp_tx,
+            working_set,
+        )
+    }
+
+    /// Returns the forced txs which are not included yet, the earliest posted first.
+    /// The forced txs of the inbox are registered by the next block, which is on its DA block.
+    fn pending_forced_txs(
+        &mut self,
+        slot_inbox: &SlotInbox,
+        da_height: u64,
+    ) -> anyhow::Result<Vec<ForcedTx>> {
+        let mut pending_forced_txs = self
+            .soft_confirmation_rule_enforcer
+            .get_pending_forced_txs(&mut WorkingSet::new(self.storage.clone()))
+            .map_err(|e| anyhow!("Failed to read the pending forced txs: {}", e))?;
+        for tx in &slot_inbox.forced_txs {
+            let tx_hash = forced_tx_hash::<C>(tx);
+            if !pending_forced_txs.iter().any(|tx| tx.tx_hash == tx_hash) {
+                pending_forced_txs.push(PendingForcedTx {
+                    tx_hash,
+                    l1_height: da_height,
+                });
+            }
+            self.forced_txs
+                .entry(tx_hash)
+                .or_insert_with(|| RlpEvmTransaction { rlp: tx.clone() });
+        }
+
+        // Included forced txs are not needed anymore
+        self.forced_txs
+            .retain(|tx_hash, _| pending_forced_txs.iter().any(|tx| tx.tx_hash == *tx_hash));
+
+        Ok(pending_forced_txs
+            .iter()
+            .filter_map(|pending_forced_tx| {
+                let Some(rlp) = self.forced_txs.get(&pending_forced_tx.tx_hash) else {
+                    warn!(
+                        "Forced tx 0x{} posted on DA block {} is not found",
+                        hex::encode(pending_forced_tx.tx_hash),
+                        pending_forced_tx.l1_height
+                    );
+                    return None;
+                };
+                Some(ForcedTx {
+                    rlp: rlp.clone(),
+                    // The block after the next one can be on the next DA block
+                    due: pending_forced_tx.deadline() <= da_height + 1,
+                })
+            })
+            .collect())
+    }
+
+    /// Reads the pending forced txs back from the DA blocks they are posted on, after a restart
+    async fn restore_forced_txs(&mut self) -> anyhow::Result<()> {
+        let pending_forced_txs = self
+            .soft_confirmation_rule_enforcer
+            .get_pending_forced_txs(&mut WorkingSet::new(self.storage.clone()))
+            .map_err(|e| anyhow!("Failed to read the pending forced txs: {}", e))?;
+        let l1_heights: BTreeSet<u64> = pending_forced_txs.iter().map(|tx| tx.l1_height).collect();
+        for l1_height in l1_heights {
+            let da_block = self
+                .da_service
+                .get_block_at(l1_height)
+                .await
+                .map_err(|e| anyhow!(e))?;
+            for tx in extract_slot_inbox(&self.da_service, &da_block).forced_txs {
+                self.forced_txs
+                    .insert(forced_tx_hash::<C>(&tx), RlpEvmTransaction { rlp: tx });
+            }
+        }
+        if !self.forced_txs.is_empty() {
+            info!("Restored {} pending forced txs", self.forced_txs.len());
+        }
+        Ok(())
+    }
+
+    #[instrument(
+        level = "info",
+        skip_all,
+        fields(da_height = da_block.header().height(), l2_height = tracing::field::Empty)
+    )]
     async fn produce_l2_block(
         &mut self,
         da_block: <Da as DaService>::FilteredBlock,
@@ -357,6 +706,7 @@ where
         pg_pool: &Option<PostgresConnector>,
         last_used_l1_height: u64,
     ) -> anyhow::Result<(u64, bool)> {
+        let build_started = Instant::now();
         let da_height = da_block.header().height();
         let (l2_height, l1_height) = match self
             .ledger_db
@@ -366,20 +716,51 @@ where
             Some((l2_height, sb)) => (l2_height.0 + 1, sb.da_slot_height),
             None => (0, da_height),
         };
+        tracing::Span::current().record("l2_height", l2_height);
         anyhow::ensure!(
             l1_height == da_height || l1_height + 1 == da_height,
             "Sequencer: L1 height mismatch, expected {da_height} (or {da_height}-1), got {l1_height}",
         );
 
-        let timestamp = chrono::Local::now().timestamp() as u64;
+        // The sequencer can be rotated on chain, blocks of a retired sequencer are rejected
+        ensure_active_sequencer::<C>(self.storage.clone(), &self.sov_tx_signer_priv_key)?;
+
+        // Keep the timestamp within the bounds enforced by the soft confirmation rule enforcer
+        let da_slot_timestamp = u64::try_from(da_block.header().time().secs()).unwrap_or_default();
+        let timestamp_range = get_timestamp_range::<C, Da>(
+            self.storage.clone(),
+            self.soft_confirmation_rule_enforcer.clone(),
+            da_slot_timestamp,
+        )?;
+        let timestamp = block_timestamp(self.clock.as_ref(), &timestamp_range);
         let pub_key = borsh::to_vec(&self.sov_tx_signer_priv_key.pub_key())
             .map_err(Into::<anyhow::Error>::into)?;
 
-        let deposit_data = self
-            .deposit_mempool
-            .lock()
-            .await
-            .fetch_deposits(self.config.deposit_mempool_fetch_limit);
+        // The inbox of a DA block is given to the first L2 block on it,
+        // which has to include its deposits and registers its forced txs
+        let is_first_l2_block_on_da_block = l2_height == 0 || l1_height < da_height;
+        let slot_inbox = if is_first_l2_block_on_da_block {
+            extract_slot_inbox(&self.da_service, &da_block)
+        } else {
+            SlotInbox::default()
+        };
+
+        // Deposits of the DA block go first, in DA order, so full nodes can derive them
+        let mut deposit_data = slot_inbox.deposits.clone();
+        if !deposit_data.is_empty() {
+            info!(
+                "Including {} deposits posted on DA block {}",
+                deposit_data.len(),
+                da_height
+            );
+        }
+        deposit_data.extend(
+            self.deposit_mempool.lock().await.fetch_deposits(
+                self.config
+                    .deposit_mempool_fetch_limit
+                    .saturating_sub(deposit_data.len()),
+            ),
+        );
 
         let batch_info = HookSoftConfirmationInfo {
             da_slot_height: da_block.header().height(),
@@ -390,6 +771,8 @@ where
             pub_key,
             l1_fee_rate,
             timestamp,
+            da_slot_timestamp,
+            slot_inbox: slot_inbox.clone(),
         };
         // initially create sc info and call begin soft confirmation hook with it
         let mut signed_batch: SignedSoftConfirmationBatch = batch_info.clone().into();
@@ -406,6 +789,15 @@ where
         let pub_key = signed_batch.pub_key().clone();
 
         let evm_txs = self.get_best_transactions()?;
+        let bundles = match l2_block_mode {
+            L2BlockMode::NotEmpty => self.bundle_pool.lock().await.take(l2_height),
+            L2BlockMode::Empty => vec![],
+        };
+
+        let forced_txs = self.pending_forced_txs(&slot_inbox, da_height)?;
+        if !forced_txs.is_empty() {
+            info!("{} forced txs are pending inclusion", forced_txs.len());
+        }
 
         // Dry running transactions would basically allow for figuring out a list of
         // all transactions that would fit into the current block and the list of transactions
@@ -417,7 +809,10 @@ where
                 prestate.clone(),
                 da_block.header().clone(),
                 signed_batch.clone(),
+                &slot_inbox,
                 l2_block_mode,
+                forced_txs,
+                bundles,
             )
             .await?;
 
@@ -433,6 +828,7 @@ where
             prestate.clone(),
             Default::default(),
             da_block.header(),
+            &slot_inbox,
             &mut signed_batch,
         ) {
             (Ok(()), mut batch_workspace) => {
@@ -547,33 +943,85 @@ where
                 self.batch_hash = signed_soft_batch.hash();
 
                 let mut txs_to_remove = self.db_provider.last_block_tx_hashes()?;
-                txs_to_remove.extend(l1_fee_failed_txs);
+                self.tx_status.l2_block_produced(
+                    l2_height,
+                    self.state_root.as_ref(),
+                    txs_to_remove.clone(),
+                );
+
+                // Txs which can't pay the L1 fee are kept in the pool for a while,
+                // in case the L1 fee rate drops
+                for tx_hash in &l1_fee_failed_txs {
+                    self.tx_status
+                        .notify(*tx_hash, TxStatus::L1FeeTooLow { l1_fee_rate });
+                }
+                let expired_txs = self.mempool.requeue_l1_fee_failed_txs(
+                    l1_fee_failed_txs,
+                    l1_fee_rate,
+                    self.clock.now(),
+                );
+                for tx_hash in &expired_txs {
+                    debug!(
+                        tx_hash = %tx_hash,
+                        request_id = self.tx_status.take_request_id(tx_hash).as_deref(),
+                        "Dropped transaction which could not pay the L1 fee"
+                    );
+                    self.tx_status.notify(*tx_hash, TxStatus::Dropped);
+                }
+                txs_to_remove.extend(expired_txs);
 
                 self.mempool.remove_transactions(txs_to_remove.clone());
+                self.ledger_db
+                    .remove_mempool_txs(txs_to_remove.iter().map(|tx_hash| tx_hash.0).collect())?;
 
                 let account_updates = self.get_account_updates()?;
 
                 self.mempool.update_accounts(account_updates);
 
+                self.track_state_diff(l2_height, &slot_result.state_diff)?;
+
                 let merged_state_diff = self.merge_state_diffs(
                     self.last_state_diff.clone(),
                     slot_result.state_diff.clone(),
                 );
-                // Serialize the state diff to check size later.
+                // Serialize the state diff to estimate its compressed size.
                 let serialized_state_diff = bincode::serialize(&merged_state_diff)?;
+                let state_diff_size = self
+                    .state_diff_size_estimator
+                    .estimate(&serialized_state_diff);
+                SEQUENCER_STATE_DIFF_COMPRESSED_BYTES.set(state_diff_size as i64);
                 let state_diff_threshold_reached =
-                    serialized_state_diff.len() as u64 > MAX_STATEDIFF_SIZE_COMMITMENT_THRESHOLD;
+                    state_diff_size > self.config.commitment_policy.max_compressed_state_diff_size;
+                // The block's share of the diff is how much it grew the merged one
+                let state_diff_bytes = state_diff_size.saturating_sub(self.last_state_diff_size);
                 if state_diff_threshold_reached {
                     self.last_state_diff.clone_from(&slot_result.state_diff);
+                    self.last_state_diff_size = state_diff_bytes;
                     self.ledger_db
                         .set_state_diff(self.last_state_diff.clone())?;
                 } else {
                     // Store state diff.
                     self.last_state_diff = merged_state_diff;
+                    self.last_state_diff_size = state_diff_size;
                     self.ledger_db
                         .set_state_diff(self.last_state_diff.clone())?;
                 }
 
+                // The block is committed already, so it is only left out of the stats
+                match self.db_provider.last_block_gas() {
+                    Ok((gas_used, gas_limit)) => {
+                        self.block_stats.lock().unwrap().record(BlockBuildStats {
+                            l2_height,
+                            gas_used,
+                            gas_limit,
+                            state_diff_bytes,
+                            tx_count: evm_txs_count as u64,
+                            build_time_ms: build_started.elapsed().as_millis() as u64,
+                        });
+                    }
+                    Err(e) => warn!("Failed to read the gas of block {}: {:?}", l2_height, e),
+                }
+
                 if let Some(pg_pool) = pg_pool.clone() {
                     // TODO: Is this okay? I'm not sure because we have a loop in this and I can't do async in spawn_blocking
                     tokio::spawn(async move {
@@ -595,28 +1043,136 @@ where
                     err
                 );
                 batch_workspace.revert();
-                Err(anyhow!(
-                    "Failed to apply begin soft confirmation hook: {:?}",
-                    err
-                ))
+                Err(CitreaError::from(err).into())
             }
         }
     }
 
+    /// Runs the simulation of the request off the block production loop,
+    /// which only takes the snapshot of the head L2 block it builds on.
+    fn spawn_simulation(
+        &mut self,
+        request: SimulateBlockRequest,
+        da_block: &Da::FilteredBlock,
+        l1_fee_rate: u128,
+    ) {
+        let snapshot = match self.simulation_snapshot(da_block, l1_fee_rate) {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                let _ = request.response_tx.send(Err(e));
+                return;
+            }
+        };
+        tokio::task::spawn_blocking(move || {
+            let SimulateBlockRequest {
+                txs,
+                response_tx,
+                _permit,
+            } = request;
+            let _ = response_tx.send(snapshot.simulate(txs));
+        });
+    }
+
+    /// Takes the state a block built on top of the head L2 block would start from
+    fn simulation_snapshot(
+        &mut self,
+        da_block: &Da::FilteredBlock,
+        l1_fee_rate: u128,
+    ) -> anyhow::Result<SimulationSnapshot<C, Da::Spec, Vm, Stf>> {
+        let l2_height = self
+            .ledger_db
+            .get_head_soft_batch()?
+            .map_or(0, |(l2_height, _)| l2_height.0 + 1);
+        let l1_fee_rate_range = get_l1_fee_rate_range::<C, Da>(
+            self.storage.clone(),
+            self.soft_confirmation_rule_enforcer.clone(),
+        )?;
+        let da_slot_timestamp = u64::try_from(da_block.header().time().secs()).unwrap_or_default();
+        let timestamp_range = get_timestamp_range::<C, Da>(
+            self.storage.clone(),
+            self.soft_confirmation_rule_enforcer.clone(),
+            da_slot_timestamp,
+        )?;
+
+        Ok(SimulationSnapshot {
+            stf: self.stf.clone(),
+            sov_tx_signer_priv_key: self.sov_tx_signer_priv_key.clone(),
+            sequencer_pub_key: self.sequencer_pub_key.clone(),
+            // Blocks are finalized once they are produced
+            prestate: self.storage_manager.create_finalized_snapshot()?,
+            state_root: self.state_root.clone(),
+            batch_hash: self.batch_hash,
+            l2_height,
+            da_block_header: da_block.header().clone(),
+            da_slot_timestamp,
+            l1_fee_rate: l1_fee_rate.clamp(*l1_fee_rate_range.start(), *l1_fee_rate_range.end()),
+            timestamp: block_timestamp(self.clock.as_ref(), &timestamp_range),
+            base_fee: self.next_block_base_fee()?,
+            block_gas_limit: self.db_provider.cfg().block_gas_limit,
+            max_state_diff_size: self.config.max_l2_block_state_diff_size,
+            phantom: PhantomData,
+        })
+    }
+
     async fn try_submit_commitment(
         &mut self,
         state_diff_threshold_reached: bool,
+        l1_height: u64,
     ) -> anyhow::Result<()> {
         debug!("Sequencer: Checking if commitment should be submitted");
 
+        let da_fee_rate = if self.config.commitment_policy.max_da_fee_rate.is_some() {
+            match self.da_service.get_fee_rate().await {
+                Ok(fee_rate) => Some(fee_rate),
+                Err(e) => {
+                    warn!("Failed to get DA fee rate for commitment policy: {:?}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let deferred_since_l1_height = self
+            .commitment_deferral
+            .lock()
+            .unwrap()
+            .deferred_since_l1_height;
+
         let commitment_info = commitment_controller::get_commitment_info(
             &self.ledger_db,
             self.config.min_soft_confirmations_per_commitment,
-            state_diff_threshold_reached,
+            &self.config.commitment_policy,
+            commitment_controller::CommitmentConditions {
+                state_diff_threshold_reached,
+                since_last_commitment: self
+                    .clock
+                    .now()
+                    .saturating_duration_since(self.last_commitment_instant),
+                da_fee_rate,
+                l1_blocks_deferred: deferred_since_l1_height
+                    .map(|deferred_since| l1_height.saturating_sub(deferred_since))
+                    .unwrap_or_default(),
+            },
         )?;
-        if let Some(commitment_info) = commitment_info {
-            self.submit_commitment(commitment_info, false).await?;
-        }
+
+        let deferred_since_l1_height = match commitment_info {
+            Some((commitment_info, CommitmentDecision::Commit(trigger))) => {
+                SEQUENCER_COMMITMENTS
+                    .with_label_values(&[trigger.as_str()])
+                    .inc();
+                self.submit_commitment(commitment_info, false).await?;
+                None
+            }
+            Some((_, CommitmentDecision::Defer(_))) => {
+                Some(deferred_since_l1_height.unwrap_or(l1_height))
+            }
+            None => None,
+        };
+        *self.commitment_deferral.lock().unwrap() = CommitmentDeferral {
+            deferred_since_l1_height,
+            last_da_fee_rate: da_fee_rate,
+        };
         Ok(())
     }
 
@@ -679,6 +1235,14 @@ where
         Ok(())
     }
 
+    #[instrument(
+        level = "info",
+        skip_all,
+        fields(
+            l2_start = commitment_info.l2_height_range.start().0,
+            l2_end = commitment_info.l2_height_range.end().0
+        )
+    )]
     async fn submit_commitment(
         &mut self,
         commitment_info: commitment_controller::CommitmentInfo,
@@ -690,6 +1254,8 @@ where
         // Clear state diff early
         self.ledger_db.set_state_diff(vec![])?;
         self.last_state_diff = vec![];
+        self.last_state_diff_size = 0;
+        self.last_commitment_instant = self.clock.now();
 
         // calculate exclusive range end
         let range_end = BatchNumber(l2_end.0 + 1); // cannnot add u64 to BatchNumber directly
@@ -706,10 +1272,20 @@ where
 
         debug!("Sequencer: submitting commitment: {:?}", commitment);
 
-        let blob = borsh::to_vec(&DaData::SequencerCommitment(commitment.clone()))
-            .map_err(|e| anyhow!(e))?;
+        let da_data = match self.commitment_state_diff(l2_start, l2_end)? {
+            Some(state_diff) => DaData::CommitmentWithStateDiff(CommitmentWithStateDiff {
+                commitment: commitment.clone(),
+                state_diff,
+            }),
+            None => DaData::SequencerCommitment(commitment.clone()),
+        };
+        let blob = da_data.encode();
         let (notify, rx) = oneshot_channel();
-        let request = BlobWithNotifier { blob, notify };
+        let request = BlobWithNotifier {
+            blob,
+            notify,
+            fee_bump: None,
+        };
         self.da_service
             .get_send_transaction_queue()
             .send(request)
@@ -722,25 +1298,30 @@ where
 
         let ledger_db = self.ledger_db.clone();
         let db_config = self.config.db_config.clone();
+        let tx_status = self.tx_status.clone();
         let handle_da_response = async move {
             let result: anyhow::Result<()> = async move {
                 let tx_id = rx
                     .await
                     .map_err(|_| anyhow!("DA service is dead!"))?
-                    .map_err(|_| anyhow!("Send transaction cannot fail"))?;
+                    .map_err(|e| anyhow!("Failed to send commitment to DA: {}", e))?;
+                let tx_id: [u8; 32] = tx_id.into();
+
+                tx_status.commitment_sent(l2_start.0..=l2_end.0, hex::encode(tx_id));
 
                 ledger_db
                     .set_last_sequencer_commitment_l2_height(l2_end)
                     .map_err(|_| {
                         anyhow!("Sequencer: Failed to set last sequencer commitment L2 height")
                     })?;
+                ledger_db.delete_l2_state_diffs(l2_end)?;
 
                 if let Some(db_config) = db_config {
                     match PostgresConnector::new(db_config).await {
                         Ok(pg_connector) => {
                             pg_connector
                                 .insert_sequencer_commitment(
-                                    Into::<[u8; 32]>::into(tx_id).to_vec(),
+                                    tx_id.to_vec(),
                                     l2_start.0 as u32,
                                     l2_end.0 as u32,
                                     commitment.merkle_root.to_vec(),
@@ -781,7 +1362,10 @@ where
                 .put_pending_commitment_l2_range(&(l2_start, l2_end))?;
 
             // Handle DA response non-blocking
-            tokio::spawn(handle_da_response);
+            self.commitment_tasks
+                .retain(|commitment_task| !commitment_task.is_finished());
+            self.commitment_tasks
+                .push(tokio::spawn(handle_da_response.in_current_span()));
         }
         Ok(())
     }
@@ -791,11 +1375,8 @@ where
             .get_relevant_blobs_of_pending_transactions()
             .await
             .into_iter()
-            .filter_map(|mut blob| match DaData::try_from_slice(blob.full_data()) {
-                Ok(da_data) => match da_data {
-                    DaData::SequencerCommitment(commitment) => Some(commitment),
-                    _ => None,
-                },
+            .filter_map(|mut blob| match DaData::decode(blob.full_data()) {
+                Ok(da_data) => da_data.into_sequencer_commitment(),
                 Err(err) => {
                     warn!("Pending transaction blob failed to be parsed: {}", err);
                     None
@@ -822,18 +1403,16 @@ where
                 .await
                 .map_err(|e| anyhow!(e))?;
             let blobs = self.da_service.extract_relevant_blobs(&block);
-            let iter = blobs.into_iter().filter_map(|mut blob| {
-                match DaData::try_from_slice(blob.full_data()) {
-                    Ok(da_data) => match da_data {
-                        DaData::SequencerCommitment(commitment) => Some(commitment),
-                        _ => None,
-                    },
-                    Err(err) => {
-                        warn!("Pending transaction blob failed to be parsed: {}", err);
-                        None
-                    }
-                }
-            });
+            let iter =
+                blobs
+                    .into_iter()
+                    .filter_map(|mut blob| match DaData::decode(blob.full_data()) {
+                        Ok(da_data) => da_data.into_sequencer_commitment(),
+                        Err(err) => {
+                            warn!("Pending transaction blob failed to be parsed: {}", err);
+                            None
+                        }
+                    });
             mined_commitments.extend(iter);
         }
 
@@ -842,6 +1421,35 @@ where
 
     #[instrument(level = "trace", skip(self), err, ret)]
     pub async fn run(&mut self) -> Result<(), anyhow::Error> {
+        let shutdown_tx = self.shutdown_tx.clone();
+        tokio::spawn(async move {
+            termination_signal().await;
+            info!("Sequencer: Received termination signal");
+            let _ = shutdown_tx.unbounded_send(());
+        });
+
+        self.notify_mempool_tx_events();
+
+        if let Some(config_reloader) = &self.config_reloader {
+            tokio::spawn(apply_fee_ceilings(
+                self.da_service.clone(),
+                config_reloader.subscribe(),
+            ));
+        }
+
+        if let Some(standby_config) = self.config.standby.clone() {
+            if !self.follow_primary(standby_config).await? {
+                // Shut down while in standby, there is nothing to commit
+                self.persist_mempool()?;
+                return Ok(());
+            }
+        }
+
+        if !self.acquire_lease().await? {
+            self.persist_mempool()?;
+            return Ok(());
+        }
+
         // Resubmit if there were pending commitments on restart
         self.resubmit_pending_commitments().await?;
 
@@ -851,6 +1459,13 @@ where
             .await
             .map_err(|e| anyhow!(e))?;
 
+        self.restore_forced_txs().await?;
+
+        // Bring back the txs which were accepted but not included before a restart
+        if let Err(e) = self.restore_persisted_mempool().await {
+            warn!("Sequencer: Persisted mempool restore error: {:?}", e);
+        }
+
         // If connected to offchain db first check if the commitments are in sync
         let mut pg_pool = None;
         if let Some(db_config) = self.config.db_config.clone() {
@@ -906,32 +1521,54 @@ where
         // Setup required workers to update our knowledge of the DA layer every X seconds (configurable).
         let (da_height_update_tx, mut da_height_update_rx) = mpsc::channel(1);
         let (da_commitment_tx, mut da_commitment_rx) = unbounded::<bool>();
+        let clock = self.clock.clone();
         let da_monitor = da_block_monitor(
             self.da_service.clone(),
             da_height_update_tx,
             self.config.da_update_interval_ms,
+            clock.clone(),
         );
         tokio::pin!(da_monitor);
 
         let target_block_time = Duration::from_millis(self.config.block_production_interval_ms);
         let mut parent_block_exec_time = Duration::from_secs(0);
+        let empty_block_heartbeat = Duration::from_millis(self.config.empty_block_heartbeat_ms);
+        let mut last_block_instant = clock.now();
 
         // In case the sequencer falls behind on DA blocks, we need to produce at least 1
         // empty block per DA block. Which means that we have to keep count of missed blocks
         // and only resume normal operations once the sequencer has caught up.
         let mut missed_da_blocks_count = 0;
 
+        // Whether this instance holds the lease of the sequencer keys, far enough from its expiry
+        let mut lease_held = true;
+
         loop {
-            let mut interval = tokio::time::interval(target_block_time - parent_block_exec_time);
-            // The first ticket completes immediately.
-            // See: https://docs.rs/tokio/latest/tokio/time/struct.Interval.html#method.tick
-            interval.tick().await;
+            // A block which took longer than the block time is followed by the next one right away
+            let mut block_timer = clock.sleep(
+                target_block_time
+                    .saturating_sub(parent_block_exec_time)
+                    .max(Duration::from_millis(1)),
+            );
 
             tokio::select! {
                 // Run the DA monitor worker
                 _ = &mut da_monitor => {},
                 // Receive updates from DA layer worker.
                 l1_data = da_height_update_rx.recv() => {
+                    if let Some((l1_block, _)) = &l1_data {
+                        let l1_height = l1_block.header().height();
+                        lease_held = match self.update_lease(l1_height).await {
+                            Ok(held) => held,
+                            Err(e) => {
+                                error!("Sequencer: Could not update the lease: {:?}", e);
+                                false
+                            }
+                        };
+                        if let Some(holder) = self.lease.as_ref().and_then(|lease| lease.held_by_other(l1_height)) {
+                            bail!("Sequencer: The lease of the sequencer keys is held by {}, stopping", holder);
+                        }
+                    }
                     // Stop receiving updates from DA layer until we have caught up.
                     if missed_da_blocks_count > 0 {
                         continue;
@@ -940,6 +1577,8 @@ where
                         (last_finalized_block, l1_fee_rate) = l1_data;
                         last_finalized_height = last_finalized_block.header().height();
 
+                        self.notify_proven_txs(&last_finalized_block);
+
                         if last_finalized_block.header().height() > last_used_l1_height {
                             let skipped_blocks = last_finalized_height - last_used_l1_height - 1;
                             if skipped_blocks > 0 {
@@ -955,8 +1594,16 @@ where
                         }
                     }
                 },
+                // Blocks are built inside the select arms, so a block in progress
+                // is always finished before shutting down.
+                _ = self.shutdown_rx.next() => {
+                    return self.shutdown().await;
+                },
+                Some(request) = self.simulate_rx.recv() => {
+                    self.spawn_simulation(request, &last_finalized_block, l1_fee_rate);
+                },
                 commitment_threshold_reached = da_commitment_rx.select_next_some() => {
-                    if let Err(e) = self.try_submit_commitment(commitment_threshold_reached).await {
+                    if let Err(e) = self.try_submit_commitment(commitment_threshold_reached, last_finalized_height).await {
                         error!("Failed to submit commitment: {}", e);
                     }
                 },
@@ -964,6 +1611,10 @@ where
                 // The RPC from which the sender can be called is only registered for test mode. This means
                 // that evey though we check the receiver here, it'll never be "ready" to be consumed unless in test mode.
                 _ = self.l2_force_block_rx.next(), if self.config.test_mode => {
+                    if !lease_held {
+                        warn!("Sequencer: Not holding the lease, skipping block production");
+                        continue;
+                    }
                     if missed_da_blocks_count > 0 {
                         debug!("We have {} missed DA blocks", missed_da_blocks_count);
                         for i in 1..=missed_da_blocks_count {
@@ -1005,11 +1656,15 @@ where
                     }
                 },
                 // If sequencer is in production mode, it will build a block every 2 seconds
-                _ = interval.tick(), if !self.config.test_mode => {
+                _ = &mut block_timer, if !self.config.test_mode => {
                     // By default, we produce a non-empty block IFF we were caught up all the way to
                     // last_finalized_block. If there are missed DA blocks, we start producing
                     // empty blocks at ~2 second rate, 1 L2 block per respective missed DA block
                     // until we know we caught up with L1.
+                    if !lease_held {
+                        debug!("Sequencer: Not holding the lease, skipping block production");
+                        continue;
+                    }
                     let da_block = last_finalized_block.clone();
 
                     if missed_da_blocks_count > 0 {
@@ -1040,14 +1695,32 @@ where
                         };
                     let l1_fee_rate = l1_fee_rate.clamp(*l1_fee_rate_range.start(), *l1_fee_rate_range.end());
 
-                    let instant = Instant::now();
+                    if self.config.empty_block_policy.skips_tick(
+                        last_used_l1_height == last_finalized_height,
+                        clock.now().saturating_duration_since(last_block_instant),
+                        empty_block_heartbeat,
+                    ) {
+                        match self.has_pending_txs().await {
+                            Ok(false) => {
+                                parent_block_exec_time = Duration::from_secs(0);
+                                continue;
+                            }
+                            Ok(true) => {}
+                            Err(e) => {
+                                error!("Could not check pending txs: {}", e);
+                            }
+                        }
+                    }
+
+                    let instant = clock.now();
                     match self.produce_l2_block(da_block, l1_fee_rate, L2BlockMode::NotEmpty, &pg_pool, last_used_l1_height).await {
                         Ok((l1_block_number, state_diff_threshold_reached)) => {
                             // Set the next iteration's wait time to produce a block based on the
                             // previous block's execution time.
                             // This is mainly to make sure we account for the execution time to
                             // achieve consistent 2-second block production.
-                            parent_block_exec_time = instant.elapsed();
+                            parent_block_exec_time = clock.now().saturating_duration_since(instant);
+                            last_block_instant = instant;
 
                             last_used_l1_height = l1_block_number;
 
@@ -1064,11 +1737,81 @@ where
         }
     }
 
+    /// Marks the txs up to the final state root of the ZK proofs in the DA block as proven
+    fn notify_proven_txs(&self, da_block: &Da::FilteredBlock) {
+        for mut blob in self.da_service.extract_relevant_blobs(da_block) {
+            if blob.sender().as_ref() != self.prover_da_pub_key.as_slice() {
+                continue;
+            }
+            let final_state_root = match DaData::decode(blob.full_data()) {
+                Ok(DaData::ZKProof(proof)) => {
+                    Vm::extract_output::<Da::Spec, StateRoot<Stf, Vm, Da::Spec>>(&proof)
+                        .map(|state_transition| state_transition.final_state_root)
+                }
+                Ok(DaData::AggregatedZKProof(proof)) => {
+                    Vm::extract_aggregated_output::<Da::Spec, StateRoot<Stf, Vm, Da::Spec>>(&proof)
+                        .map(|aggregated| aggregated.final_state_root)
+                }
+                _ => continue,
+            };
+            match final_state_root {
+                Ok(final_state_root) => self.tx_status.proof_found(final_state_root.as_ref()),
+                Err(e) => warn!("Failed to extract output of proof: {:?}", e),
+            }
+        }
+    }
+
+    /// Forwards the queued and pending events of the mempool to the tx status subscribers,
+    /// and forgets the request IDs of the txs which left the mempool without being included
+    fn notify_mempool_tx_events(&self) {
+        let mut events = self.mempool.all_transactions_event_listener();
+        let tx_status = self.tx_status.clone();
+        tokio::spawn(async move {
+            while let Some(event) = events.next().await {
+                match event {
+                    FullTransactionEvent::Queued(tx_hash) => {
+                        tx_status.notify(tx_hash, TxStatus::Queued)
+                    }
+                    FullTransactionEvent::Pending(tx_hash) => {
+                        tx_status.notify(tx_hash, TxStatus::Pending)
+                    }
+                    FullTransactionEvent::Replaced { transaction, .. } => {
+                        tx_status.take_request_id(transaction.hash());
+                    }
+                    FullTransactionEvent::Discarded(tx_hash)
+                    | FullTransactionEvent::Invalid(tx_hash) => {
+                        tx_status.take_request_id(&tx_hash);
+                    }
+                    _ => {}
+                }
+            }
+        });
+    }
+
+    /// Whether there are txs, bundles or deposits to include in the next block
+    async fn has_pending_txs(&self) -> anyhow::Result<bool> {
+        if !self.deposit_mempool.lock().await.is_empty()
+            || !self.bundle_pool.lock().await.is_empty()
+        {
+            return Ok(true);
+        }
+        Ok(self.get_best_transactions()?.next().is_some())
+    }
+
     fn get_best_transactions(
         &self,
     ) -> anyhow::Result<
         Box<dyn BestTransactions<Item = Arc<ValidPoolTransaction<EthPooledTransaction>>>>,
     > {
+        let base_fee = self.next_block_base_fee()?;
+        let best_txs_with_base_fee = self
+            .mempool
+            .best_transactions_with_attributes(BestTransactionsAttributes::base_fee(base_fee));
+
+        Ok(best_txs_with_base_fee)
+    }
+
+    fn next_block_base_fee(&self) -> anyhow::Result<u64> {
         let cfg = self.db_provider.cfg();
         let latest_header = self
             .db_provider
@@ -1077,33 +1820,19 @@ where
             .ok_or(anyhow!("Latest header must always exist"))?
             .unseal();
 
-        let base_fee = latest_header
+        latest_header
             .next_block_base_fee(cfg.base_fee_params)
-            .ok_or(anyhow!("Failed to get next block base fee"))?;
-
-        let best_txs_with_base_fee = self
-            .mempool
-            .best_transactions_with_attributes(BestTransactionsAttributes::base_fee(base_fee));
-
-        Ok(best_txs_with_base_fee)
+            .ok_or(anyhow!("Failed to get next block base fee"))
     }
 
     /// Signs batch of messages with sovereign priv key turns them into a sov blob
     /// Returns a single sovereign transaction made up of multiple ethereum transactions
     fn make_blob(
-        &mut self,
+        &self,
         raw_message: Vec<u8>,
         working_set: &mut WorkingSet<C>,
     ) -> anyhow::Result<Vec<u8>> {
-        // if a batch failed need to refetch nonce
-        // so sticking to fetching from state makes sense
-        let nonce = self.get_nonce(working_set)?;
-        // TODO: figure out what to do with sov-tx fields
-        // chain id gas tip and gas limit
-
-        let transaction =
-            Transaction::<C>::new_signed_tx(&self.sov_tx_signer_priv_key, raw_message, 0, nonce);
-        borsh::to_vec(&transaction).map_err(|e| anyhow!(e))
+        make_blob::<C>(&self.sov_tx_signer_priv_key, raw_message, working_set)
     }
 
     /// Signs necessary info and returns a BlockTemplate
@@ -1112,42 +1841,397 @@ where
         soft_confirmation: UnsignedSoftConfirmationBatch,
         prev_soft_confirmation_hash: [u8; 32],
     ) -> anyhow::Result<SignedSoftConfirmationBatch> {
-        let raw = borsh::to_vec(&soft_confirmation).map_err(|e| anyhow!(e))?;
-
-        let hash = <C as sov_modules_api::Spec>::Hasher::digest(raw.as_slice()).into();
-
-        let signature = self.sov_tx_signer_priv_key.sign(&raw);
-        let pub_key = self.sov_tx_signer_priv_key.pub_key();
-        Ok(SignedSoftConfirmationBatch::new(
-            hash,
+        sign_soft_confirmation_batch::<C>(
+            &self.sov_tx_signer_priv_key,
+            soft_confirmation,
             prev_soft_confirmation_hash,
-            soft_confirmation.da_slot_height(),
-            soft_confirmation.da_slot_hash(),
-            soft_confirmation.da_slot_txs_commitment(),
-            soft_confirmation.l1_fee_rate(),
-            soft_confirmation.txs(),
-            soft_confirmation.deposit_data(),
-            borsh::to_vec(&signature).map_err(|e| anyhow!(e))?,
-            borsh::to_vec(&pub_key).map_err(|e| anyhow!(e))?,
-            soft_confirmation.timestamp(),
-        ))
+        )
     }
 
-    /// Fetches nonce from state
-    fn get_nonce(&self, working_set: &mut WorkingSet<C>) -> anyhow::Result<u64> {
-        let accounts = Accounts::<C>::default();
+    /// Stops accepting txs, commits the uncommitted soft confirmations
+    /// and persists the mempool.
+    async fn shutdown(&mut self) -> anyhow::Result<()> {
+        info!("Sequencer: Shutting down");
+        self.shutting_down.store(true, Ordering::SeqCst);
 
-        match accounts
-            .get_account(self.sov_tx_signer_priv_key.pub_key(), working_set)
-            .map_err(|e| anyhow!("Sequencer: Failed to get sov-account: {}", e))?
+        if let Some(commitment_info) =
+            commitment_controller::get_uncommitted_l2_range(&self.ledger_db)?
         {
-            AccountExists { addr: _, nonce } => Ok(nonce),
-            AccountEmpty => Ok(0),
+            SEQUENCER_COMMITMENTS
+                .with_label_values(&[CommitmentTrigger::Shutdown.as_str()])
+                .inc();
+            // Pending until the DA response, so it is resubmitted on restart
+            // if the sequencer is killed before
+            self.submit_commitment(commitment_info, false).await?;
+        }
+
+        // Wait for the DA responses of all the commitments sent
+        for commitment_task in self.commitment_tasks.drain(..) {
+            if let Err(e) = commitment_task.await {
+                error!("Sequencer: Commitment task failed: {}", e);
+            }
+        }
+
+        // Let a standby take over without waiting for the lease to expire
+        if let Some(lease) = &self.lease {
+            let l1_height = lease.checkpoint().scanned_l1_height.0;
+            if let Some(released) = lease.release(l1_height) {
+                info!("Sequencer: Releasing the lease of the sequencer keys");
+                let response = post_lease(&self.da_service, released)?;
+                if let Err(e) = response.await.map_err(|e| anyhow!(e)).and_then(|r| r) {
+                    error!("Sequencer: Could not release the lease: {:?}", e);
+                }
+            }
+        }
+
+        self.persist_mempool()
+    }
+
+    /// Scans DA up to the L1 height for leases of the sequencer keys, and posts the lease
+    /// of this instance when it is due to be taken or renewed.
+    /// Returns whether this instance holds the lease, always true without a lease configured.
+    async fn update_lease(&mut self, l1_height: u64) -> anyhow::Result<bool> {
+        self.scan_leases(l1_height).await?;
+        self.post_due_lease(l1_height)
+    }
+
+    /// Applies the leases of the sequencer keys posted up to the L1 height
+    async fn scan_leases(&mut self, l1_height: u64) -> anyhow::Result<()> {
+        let Some(lease) = self.lease.as_mut() else {
+            return Ok(());
+        };
+
+        for height in lease.next_l1_height_to_scan()..=l1_height {
+            let l1_block = self
+                .da_service
+                .get_block_at(height)
+                .await
+                .map_err(|e| anyhow!(e))?;
+            let leases = extract_sequencer_leases(
+                &self.da_service,
+                &l1_block,
+                self.public_keys.sequencer_da_pub_key_at(height),
+            );
+            lease.apply(height, leases);
+            self.ledger_db
+                .set_sequencer_lease_checkpoint(lease.checkpoint())?;
+        }
+        Ok(())
+    }
+
+    /// Posts the lease of this instance if it is due to be taken or renewed.
+    /// Returns whether this instance holds the lease, always true without a lease configured.
+    fn post_due_lease(&mut self, l1_height: u64) -> anyhow::Result<bool> {
+        let Some(lease) = self.lease.as_mut() else {
+            return Ok(true);
+        };
+
+        if let Some(new_lease) = lease.lease_to_post(l1_height) {
+            debug!("Sequencer: Posting lease {:?}", new_lease);
+            post_lease(&self.da_service, new_lease)?;
+        }
+        Ok(lease.is_held(l1_height))
+    }
+
+    /// Waits until this instance holds the lease of the sequencer keys, taking it if it is free.
+    /// A lease last held by another instance is only taken over by promoting a standby,
+    /// which syncs the soft confirmations of the other instance first.
+    /// Returns false if the sequencer is shut down before.
+    async fn acquire_lease(&mut self) -> anyhow::Result<bool> {
+        if self.lease.is_none() {
+            return Ok(true);
+        }
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(self.config.da_update_interval_ms));
+
+        loop {
+            tokio::select! {
+                _ = self.shutdown_rx.next() => {
+                    return Ok(false);
+                },
+                _ = interval.tick() => {},
+            }
+
+            let l1_height = self
+                .da_service
+                .get_last_finalized_block_header()
+                .await
+                .map_err(|e| anyhow!(e))?
+                .height();
+            self.scan_leases(l1_height).await?;
+            if self
+                .lease
+                .as_ref()
+                .is_some_and(|lease| lease.last_held_by_other())
+            {
+                bail!("Sequencer: The lease of the sequencer keys was last held by another instance, run this instance as its standby");
+            }
+            if self.post_due_lease(l1_height)? {
+                info!("Sequencer: Holding the lease of the sequencer keys");
+                return Ok(true);
+            }
+        }
+    }
+
+    /// Persists the txs of the mempool.
+    /// Txs accepted over RPC are already persisted, the ones restored from
+    /// the offchain db may not be.
+    fn persist_mempool(&self) -> anyhow::Result<()> {
+        let mempool_txs = self.mempool.all_transactions();
+        for tx in &mempool_txs {
+            let mut rlp_encoded_tx = Vec::new();
+            tx.to_recovered_transaction()
+                .into_signed()
+                .encode_enveloped(&mut rlp_encoded_tx);
+            self.ledger_db
+                .insert_mempool_tx(tx.hash().0, rlp_encoded_tx)?;
         }
+
+        info!(
+            "Sequencer: Shut down with {} txs in the mempool",
+            mempool_txs.len()
+        );
+        Ok(())
+    }
+
+    /// Applies the soft confirmations of the primary sequencer until promoted.
+    /// Once promoted, the standby takes the lease of the sequencer keys when the lease
+    /// of the primary expires or is released, and syncs the last soft confirmations
+    /// of the primary before producing its own.
+    /// Returns false if the sequencer is shut down before being promoted.
+    async fn follow_primary(&mut self, standby_config: StandbyConfig) -> anyhow::Result<bool> {
+        info!(
+            "Sequencer: Running as standby of {}",
+            standby_config.primary_rpc_url
+        );
+        let primary = SequencerClient::new(standby_config.primary_rpc_url);
+        let mut interval =
+            tokio::time::interval(Duration::from_millis(standby_config.sync_interval_ms));
+        let mut promotion_requested = false;
+
+        loop {
+            tokio::select! {
+                _ = self.shutdown_rx.next() => {
+                    return Ok(false);
+                },
+                Some(force) = self.promote_rx.next() => {
+                    // A reachable primary is most likely still producing, the promotion
+                    // would wait for its lease to expire. Forcing only skips this check.
+                    if !force && primary.block_number().await.is_ok() {
+                        error!("Sequencer: Primary is still reachable, not promoting. Stop the primary or force the promotion");
+                        continue;
+                    }
+                    info!("Sequencer: Promotion requested, waiting for the lease of the sequencer keys");
+                    promotion_requested = true;
+                },
+                _ = interval.tick() => {
+                    if let Err(e) = self.sync_from_primary(&primary).await {
+                        warn!("Sequencer: Could not sync from primary: {:?}", e);
+                    }
+                    if !promotion_requested {
+                        continue;
+                    }
+
+                    let l1_height = match self.da_service.get_last_finalized_block_header().await {
+                        Ok(header) => header.height(),
+                        Err(e) => {
+                            warn!("Sequencer: Could not fetch the finalized L1 height: {:?}", e);
+                            continue;
+                        }
+                    };
+                    match self.update_lease(l1_height).await {
+                        Ok(true) => {
+                            // The primary stopped producing before its lease expired,
+                            // catch up with its last soft confirmations
+                            if let Err(e) = self.sync_from_primary(&primary).await {
+                                warn!("Sequencer: Could not sync from primary: {:?}", e);
+                            }
+                            break;
+                        }
+                        Ok(false) => {
+                            if let Some(holder) = self.lease.as_ref().and_then(|lease| lease.held_by_other(l1_height)) {
+                                debug!("Sequencer: The lease is held by {}", holder);
+                            }
+                        }
+                        Err(e) => {
+                            warn!("Sequencer: Could not update the lease: {:?}", e);
+                        }
+                    }
+                },
+            }
+        }
+
+        // Do not commit the soft confirmations which the primary already committed
+        self.sync_commitments_from_da().await?;
+
+        info!("Sequencer: Promoted to primary");
+        Ok(true)
+    }
+
+    /// Applies the soft confirmations of the primary which are not applied yet
+    async fn sync_from_primary(&mut self, primary: &SequencerClient) -> anyhow::Result<()> {
+        loop {
+            let next_l2_height = match self.ledger_db.get_head_soft_batch()? {
+                Some((l2_height, _)) => l2_height.0 + 1,
+                None => 1,
+            };
+
+            let soft_batches: Vec<GetSoftBatchResponse> = primary
+                .get_soft_batch_range::<Da::Spec>(next_l2_height..next_l2_height + 10)
+                .await?
+                .into_iter()
+                .flatten()
+                .collect();
+            if soft_batches.is_empty() {
+                return Ok(());
+            }
+
+            for (l2_height, soft_batch) in (next_l2_height..).zip(soft_batches) {
+                self.apply_primary_soft_batch(l2_height, soft_batch).await?;
+            }
+        }
+    }
+
+    /// Executes a soft confirmation of the primary and stores it as if it was produced here
+    async fn apply_primary_soft_batch(
+        &mut self,
+        l2_height: u64,
+        soft_batch: GetSoftBatchResponse,
+    ) -> anyhow::Result<()> {
+        if self.batch_hash != soft_batch.prev_hash {
+            bail!("Previous hash mismatch at height: {}", l2_height);
+        }
+
+        let da_block = self
+            .da_service
+            .get_block_at(soft_batch.da_slot_height)
+            .await
+            .map_err(|e| anyhow!(e))?;
+
+        let mut signed_batch: SignedSoftConfirmationBatch = soft_batch.clone().into();
+
+        let slot_inbox = extract_slot_inbox(&self.da_service, &da_block);
+
+        let pre_state = self
+            .storage_manager
+            .create_storage_on_l2_height(l2_height)?;
+
+        let slot_result = self.stf.apply_soft_batch(
+            self.sequencer_pub_key.as_slice(),
+            &self.state_root,
+            pre_state,
+            Default::default(),
+            da_block.header(),
+            &slot_inbox,
+            &da_block.validity_condition(),
+            &mut signed_batch,
+        );
+
+        if slot_result.state_root.as_ref().to_vec() != soft_batch.state_root {
+            bail!("Post state root mismatch at height: {}", l2_height)
+        }
+
+        let mut data_to_commit = SlotCommit::new(da_block.clone());
+        for receipt in slot_result.batch_receipts {
+            data_to_commit.add_batch(receipt);
+        }
+
+        let batch_receipt = data_to_commit.batch_receipts()[0].clone();
+
+        let soft_batch_receipt = SoftBatchReceipt::<_, _, Da::Spec> {
+            state_root: slot_result.state_root.as_ref().to_vec(),
+            phantom_data: PhantomData::<u64>,
+            hash: soft_batch.hash,
+            prev_hash: soft_batch.prev_hash,
+            da_slot_hash: da_block.header().hash(),
+            da_slot_height: da_block.header().height(),
+            da_slot_txs_commitment: da_block.header().txs_commitment(),
+            tx_receipts: batch_receipt.tx_receipts,
+            soft_confirmation_signature: soft_batch.soft_confirmation_signature,
+            pub_key: soft_batch.pub_key,
+            deposit_data: soft_batch.deposit_data.into_iter().map(|x| x.tx).collect(),
+            l1_fee_rate: soft_batch.l1_fee_rate,
+            timestamp: soft_batch.timestamp,
+        };
+
+        self.storage_manager
+            .save_change_set_l2(l2_height, slot_result.change_set)?;
+        self.storage_manager.finalize_l2(l2_height)?;
+
+        self.ledger_db.commit_soft_batch(soft_batch_receipt, true)?;
+        self.ledger_db.extend_l2_range_of_l1_slot(
+            SlotNumber(da_block.header().height()),
+            BatchNumber(l2_height),
+        )?;
+
+        self.state_root = slot_result.state_root;
+        self.batch_hash = soft_batch.hash;
+
+        // Drop the txs the primary included
+        let txs_to_remove = self.db_provider.last_block_tx_hashes()?;
+        self.mempool.remove_transactions(txs_to_remove.clone());
+        self.ledger_db
+            .remove_mempool_txs(txs_to_remove.iter().map(|tx_hash| tx_hash.0).collect())?;
+        let account_updates = self.get_account_updates()?;
+        self.mempool.update_accounts(account_updates);
+
+        // Track the state diff for the first commitment after a promotion
+        self.track_state_diff(l2_height, &slot_result.state_diff)?;
+        self.last_state_diff =
+            self.merge_state_diffs(self.last_state_diff.clone(), slot_result.state_diff);
+        self.last_state_diff_size = self
+            .state_diff_size_estimator
+            .estimate(&bincode::serialize(&self.last_state_diff)?);
+        self.ledger_db
+            .set_state_diff(self.last_state_diff.clone())?;
+
+        // Only errors when there are no receivers
+        let _ = self.soft_confirmation_tx.send(l2_height);
+
+        info!(
+            "Sequencer: Applied soft confirmation #{} of the primary",
+            l2_height
+        );
+        Ok(())
+    }
+
+    /// Sets the last committed L2 height from the commitments of the sequencer on DA
+    async fn sync_commitments_from_da(&mut self) -> anyhow::Result<()> {
+        let last_commitment_l1_height = self
+            .ledger_db
+            .get_l1_height_of_last_commitment()?
+            .unwrap_or(SlotNumber(1));
+        let mut commitments = self
+            .get_mined_commitments_from(last_commitment_l1_height)
+            .await?;
+        commitments.extend(self.get_pending_mempool_commitments().await);
+
+        let Some(last_committed_l2_height) = commitments
+            .iter()
+            .map(|commitment| commitment.l2_end_block_number)
+            .max()
+        else {
+            return Ok(());
+        };
+
+        match self.ledger_db.get_last_sequencer_commitment_l2_height()? {
+            Some(l2_height) if l2_height.0 >= last_committed_l2_height => {}
+            _ => {
+                self.ledger_db
+                    .set_last_sequencer_commitment_l2_height(BatchNumber(
+                        last_committed_l2_height,
+                    ))?;
+                info!(
+                    "Sequencer: Primary committed up to L2 height {}",
+                    last_committed_l2_height
+                );
+            }
+        }
+        Ok(())
     }
 
     /// Creates a shared RpcContext with all required data.
-    async fn create_rpc_context(&self) -> RpcContext<C> {
+    async fn create_rpc_context(&self) -> RpcContext<C, DB> {
         let l2_force_block_tx = self.l2_force_block_tx.clone();
         let mut pg_pool = None;
         if let Some(pg_config) = self.config.db_config.clone() {
@@ -1162,10 +2246,30 @@ where
         RpcContext {
             mempool: self.mempool.clone(),
             deposit_mempool: self.deposit_mempool.clone(),
+            bundle_pool: self.bundle_pool.clone(),
             l2_force_block_tx,
+            shutdown_tx: self.shutdown_tx.clone(),
+            shutting_down: self.shutting_down.clone(),
+            promote_tx: self.promote_tx.clone(),
+            simulate_tx: self.simulate_tx.clone(),
+            simulation_permits: self.simulation_permits.clone(),
+            tx_gossip_peers: self
+                .config
+                .tx_gossip_peers
+                .iter()
+                .cloned()
+                .map(SequencerClient::new)
+                .collect(),
             storage: self.storage.clone(),
             test_mode: self.config.test_mode,
+            enable_admin_rpc: self.config.enable_admin_rpc,
             pg_pool,
+            ledger_db: self.ledger_db.clone(),
+            tx_status: self.tx_status.clone(),
+            commitment_deferral: self.commitment_deferral.clone(),
+            block_stats: self.block_stats.clone(),
+            relay: self.relay.clone(),
+            clock: self.clock.clone(),
         }
     }
 
@@ -1177,6 +2281,18 @@ where
         let rpc_context = self.create_rpc_context().await;
         let rpc = create_rpc_module(rpc_context)?;
         rpc_methods.merge(rpc)?;
+
+        match (&self.config_reloader, &self.rpc_config.operator) {
+            (Some(config_reloader), Some(_)) => {
+                rpc_methods.merge(create_config_rpc_module(config_reloader.clone())?)?;
+            }
+            (Some(_), None) => {
+                warn!(
+                    "citrea_reloadConfig is disabled, it is only served by the operator RPC server"
+                );
+            }
+            (None, _) => {}
+        }
         Ok(rpc_methods)
     }
 
@@ -1185,16 +2301,89 @@ where
         pg_connector: PostgresConnector,
     ) -> Result<(), anyhow::Error> {
         let mempool_txs = pg_connector.get_all_txs().await?;
+        let l1_fee_rate = latest_l1_fee_rate(&self.ledger_db)?;
         for tx in mempool_txs {
             let recovered =
                 recover_raw_transaction(reth_primitives::Bytes::from(tx.tx.as_slice().to_vec()))?;
             let pooled_tx = EthPooledTransaction::from_recovered_pooled_transaction(recovered);
+            // Already restored from the ledger db
+            if self.mempool.get(&pooled_tx.transaction().hash()).is_some() {
+                continue;
+            }
 
-            let _ = self.mempool.add_external_transaction(pooled_tx).await?;
+            let added = self
+                .mempool
+                .add_external_transaction(pooled_tx, l1_fee_rate)
+                .await?;
+            if !added.evicted.is_empty() {
+                self.ledger_db
+                    .remove_mempool_txs(added.evicted.iter().map(|tx_hash| tx_hash.0).collect())?;
+                pg_connector
+                    .delete_txs_by_tx_hashes(
+                        added
+                            .evicted
+                            .iter()
+                            .map(|tx_hash| tx_hash.to_vec())
+                            .collect(),
+                    )
+                    .await?;
+            }
         }
         Ok(())
     }
 
+    /// Re-adds the transactions persisted in the ledger db to the mempool.
+    /// Every tx is validated again against the current state, and the ones which are no
+    /// longer valid (e.g. included in a block before the restart) are dropped from the db.
+    pub async fn restore_persisted_mempool(&self) -> Result<(), anyhow::Error> {
+        let mempool_txs = self.ledger_db.get_mempool_txs()?;
+        let l1_fee_rate = latest_l1_fee_rate(&self.ledger_db)?;
+        let mut invalid_txs = vec![];
+        let mut restored_count = 0;
+        for (tx_hash, tx) in mempool_txs {
+            let recovered = match recover_raw_transaction(reth_primitives::Bytes::from(tx)) {
+                Ok(recovered) => recovered,
+                Err(e) => {
+                    warn!(
+                        "Sequencer: Could not decode persisted mempool tx 0x{}: {:?}",
+                        hex::encode(tx_hash),
+                        e
+                    );
+                    invalid_txs.push(tx_hash);
+                    continue;
+                }
+            };
+            let pooled_tx = EthPooledTransaction::from_recovered_pooled_transaction(recovered);
+
+            match self
+                .mempool
+                .add_external_transaction(pooled_tx, l1_fee_rate)
+                .await
+            {
+                Ok(added) => {
+                    // Restored txs evicted for a better paying one are dropped from the db too
+                    restored_count = (restored_count + 1).saturating_sub(added.evicted.len());
+                    invalid_txs.extend(added.evicted.iter().map(|tx_hash| tx_hash.0));
+                }
+                Err(e) => {
+                    debug!(
+                        "Sequencer: Dropping persisted mempool tx 0x{}: {:?}",
+                        hex::encode(tx_hash),
+                        e
+                    );
+                    invalid_txs.push(tx_hash);
+                }
+            }
+        }
+
+        if !invalid_txs.is_empty() {
+            self.ledger_db.remove_mempool_txs(invalid_txs)?;
+        }
+        info!("Sequencer: Restored {} mempool txs", restored_count);
+
+        Ok(())
+    }
+
     pub async fn sync_commitments_from_db(
         &self,
         pg_connector: PostgresConnector,
@@ -1248,6 +2437,49 @@ where
         Ok(updates)
     }
 
+    /// Persists the state diff of an L2 block to be posted with its commitment,
+    /// so it is still known if the commitment is posted after a restart
+    fn track_state_diff(&self, l2_height: u64, state_diff: &StateDiff) -> anyhow::Result<()> {
+        if self.config.da_payload_mode == DaPayloadMode::StateDiff {
+            self.ledger_db
+                .put_l2_state_diff(BatchNumber(l2_height), state_diff)?;
+        }
+        Ok(())
+    }
+
+    /// Cumulative state diff of the L2 blocks of a commitment, sorted by key.
+    /// `None` if the state diff of a block is not known, e.g. because it was produced
+    /// before the state diff DA payload mode was set, in which case only the commitment
+    /// can be posted.
+    fn commitment_state_diff(
+        &self,
+        l2_start: BatchNumber,
+        l2_end: BatchNumber,
+    ) -> anyhow::Result<Option<StateDiff>> {
+        if self.config.da_payload_mode != DaPayloadMode::StateDiff {
+            return Ok(None);
+        }
+
+        let mut state_diff = BTreeMap::new();
+        for l2_height in l2_start.0..=l2_end.0 {
+            let Some(diff) = self.ledger_db.get_l2_state_diff(BatchNumber(l2_height))? else {
+                if DA_PAYLOAD_MODE == DaPayloadMode::StateDiff {
+                    bail!(
+                        "State diff of L2 block #{} is not known, the commitment of L2 blocks #{}-{} could not be proven without it",
+                        l2_height, l2_start.0, l2_end.0
+                    );
+                }
+                warn!(
+                    "State diff of L2 block #{} is not known, posting the commitment of L2 blocks #{}-{} without its state diff",
+                    l2_height, l2_start.0, l2_end.0
+                );
+                return Ok(None);
+            };
+            state_diff.extend(diff);
+        }
+        Ok(Some(state_diff.into_iter().collect()))
+    }
+
     fn merge_state_diffs(&self, old_diff: StateDiff, new_diff: StateDiff) -> StateDiff {
         let mut new_diff_map = HashMap::<Vec<u8>, Option<Vec<u8>>>::from_iter(old_diff);
 
@@ -1256,6 +2488,90 @@ where
     }
 }
 
+/// Applies a single tx on top of the dry run state.
+/// Returns the last pending tx of the block, which is the given tx if it was included.
+pub(crate) fn dry_run_tx<C, Da, Vm, Stf>(
+    stf: &Stf,
+    priv_key: &C::PrivateKey,
+    rlp_tx: RlpEvmTransaction,
+    mut working_set: WorkingSet<C>,
+) -> anyhow::Result<(WorkingSet<C>, Option<PendingTransaction>)>
+where
+    C: Context,
+    Da: DaSpec,
+    Vm: Zkvm,
+    Stf: StfBlueprintTrait<C, Da, Vm>,
+{
+    let call_txs = CallMessage { txs: vec![rlp_tx] };
+    let raw_message = <Runtime<C, Da> as EncodeCall<citrea_evm::Evm<C>>>::encode_call(call_txs);
+    let signed_blob = make_blob::<C>(priv_key, raw_message, &mut working_set)?;
+
+    let (mut working_set, _) = stf.apply_soft_batch_txs(vec![signed_blob], working_set);
+
+    let last_tx = Evm::<C>::default().get_last_pending_transaction(&mut working_set);
+    Ok((working_set, last_tx))
+}
+
+/// Signs batch of messages with sovereign priv key turns them into a sov blob
+/// Returns a single sovereign transaction made up of multiple ethereum transactions
+pub(crate) fn make_blob<C: Context>(
+    priv_key: &C::PrivateKey,
+    raw_message: Vec<u8>,
+    working_set: &mut WorkingSet<C>,
+) -> anyhow::Result<Vec<u8>> {
+    // if a batch failed need to refetch nonce
+    // so sticking to fetching from state makes sense
+    let nonce = get_nonce::<C>(priv_key, working_set)?;
+    // TODO: figure out what to do with sov-tx fields
+    // chain id gas tip and gas limit
+
+    let transaction = Transaction::<C>::new_signed_tx(priv_key, raw_message, 0, nonce);
+    borsh::to_vec(&transaction).map_err(|e| anyhow!(e))
+}
+
+/// Signs necessary info and returns a BlockTemplate
+pub(crate) fn sign_soft_confirmation_batch<C: Context>(
+    priv_key: &C::PrivateKey,
+    soft_confirmation: UnsignedSoftConfirmationBatch,
+    prev_soft_confirmation_hash: [u8; 32],
+) -> anyhow::Result<SignedSoftConfirmationBatch> {
+    let raw = borsh::to_vec(&soft_confirmation).map_err(|e| anyhow!(e))?;
+
+    let hash = <C as sov_modules_api::Spec>::Hasher::digest(raw.as_slice()).into();
+
+    let signature = priv_key.sign(&raw);
+    let pub_key = priv_key.pub_key();
+    Ok(SignedSoftConfirmationBatch::new(
+        hash,
+        prev_soft_confirmation_hash,
+        soft_confirmation.da_slot_height(),
+        soft_confirmation.da_slot_hash(),
+        soft_confirmation.da_slot_txs_commitment(),
+        soft_confirmation.l1_fee_rate(),
+        soft_confirmation.txs(),
+        soft_confirmation.deposit_data(),
+        borsh::to_vec(&signature).map_err(|e| anyhow!(e))?,
+        borsh::to_vec(&pub_key).map_err(|e| anyhow!(e))?,
+        soft_confirmation.timestamp(),
+    ))
+}
+
+/// Fetches nonce from state
+fn get_nonce<C: Context>(
+    priv_key: &C::PrivateKey,
+    working_set: &mut WorkingSet<C>,
+) -> anyhow::Result<u64> {
+    let accounts = Accounts::<C>::default();
+
+    match accounts
+        .get_account(priv_key.pub_key(), working_set)
+        .map_err(|e| anyhow!("Sequencer: Failed to get sov-account: {}", e))?
+    {
+        AccountExists { addr: _, nonce } => Ok(nonce),
+        AccountEmpty => Ok(0),
+    }
+}
+
 fn get_l1_fee_rate_range<C, Da>(
     storage: C::Storage,
     rule_enforcer: SoftConfirmationRuleEnforcer<C, Da::Spec>,
@@ -1271,8 +2587,46 @@ where
         .map_err(|e| anyhow::anyhow!("Error reading min max l1 fee rate: {}", e))
 }
 
-async fn da_block_monitor<Da>(da_service: Da, sender: mpsc::Sender<L1Data<Da>>, loop_interval: u64)
+fn ensure_active_sequencer<C: Context>(
+    storage: C::Storage,
+    priv_key: &C::PrivateKey,
+) -> anyhow::Result<()> {
+    let mut working_set = WorkingSet::<C>::new(storage);
+
+    if let Some(sequencer) = SequencerRegistry::<C>::default().active_sequencer(&mut working_set) {
+        let pub_key = borsh::to_vec(&priv_key.pub_key())?;
+        anyhow::ensure!(
+            sequencer.public_key == pub_key,
+            "Sequencer: not the active sequencer, blocks must be signed by 0x{}",
+            hex::encode(sequencer.public_key)
+        );
+    }
+
+    Ok(())
+}
+
+fn get_timestamp_range<C, Da>(
+    storage: C::Storage,
+    rule_enforcer: SoftConfirmationRuleEnforcer<C, Da::Spec>,
+    da_slot_timestamp: u64,
+) -> Result<RangeInclusive<u64>, anyhow::Error>
 where
+    C: Context,
+    Da: DaService,
+{
+    let mut working_set = WorkingSet::<C>::new(storage);
+
+    rule_enforcer
+        .get_next_min_max_timestamp(da_slot_timestamp, &mut working_set)
+        .map_err(|e| anyhow::anyhow!("Error reading min max timestamp: {}", e))
+}
+
+async fn da_block_monitor<Da>(
+    da_service: Da,
+    sender: mpsc::Sender<L1Data<Da>>,
+    loop_interval: u64,
+    clock: SharedClock,
+) where
     Da: DaService + Clone,
 {
     loop {
@@ -1286,7 +2640,49 @@ where
 
         let _ = sender.send(l1_data).await;
 
-        sleep(Duration::from_millis(loop_interval)).await;
+        clock.sleep(Duration::from_millis(loop_interval)).await;
+    }
+}
+
+/// Sends the lease to DA, returning the receiver of the DA response
+fn post_lease<Da: DaService>(
+    da_service: &Da,
+    lease: SequencerLease,
+) -> anyhow::Result<oneshot::Receiver<anyhow::Result<Da::TransactionId>>> {
+    let (notify, rx) = oneshot_channel();
+    da_service
+        .get_send_transaction_queue()
+        .send(BlobWithNotifier {
+            blob: DaData::SequencerLease(lease).encode(),
+            notify,
+            fee_bump: None,
+        })
+        .map_err(|_| anyhow!("DA service already stopped!"))?;
+    Ok(rx)
+}
+
+/// Resolves on SIGTERM or Ctrl-C
+async fn termination_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = sigterm.recv() => {},
+                    _ = tokio::signal::ctrl_c() => {},
+                }
+            }
+            Err(e) => {
+                error!("Could not listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
     }
 }
 
@@ -0,0 +1,445 @@
+use std::collections::{HashMap, VecDeque};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use citrea_evm::{CallMessage, CitreaError, RlpEvmTransaction, MIN_TRANSACTION_GAS};
+use citrea_primitives::types::SoftConfirmationHash;
+use citrea_stf::runtime::Runtime;
+use reth_primitives::{Address, TransactionSignedEcRecovered, B256};
+use serde::Serialize;
+use sov_modules_api::hooks::HookSoftConfirmationInfo;
+use sov_modules_api::{
+    Context, EncodeCall, PrivateKey, SignedSoftConfirmationBatch, UnsignedSoftConfirmationBatch,
+    WorkingSet,
+};
+use sov_modules_stf_blueprint::StfBlueprintTrait;
+use sov_rollup_interface::da::{BlockHeaderTrait, DaSpec, SlotInbox};
+use sov_rollup_interface::zk::Zkvm;
+use tokio::sync::{oneshot, OwnedSemaphorePermit};
+use tracing::instrument;
+
+use crate::mempool::MIN_L1_DIFF_SIZE;
+use crate::sequencer::{dry_run_tx, make_blob, sign_soft_confirmation_batch, MAX_STATE_DIFF_SKIPS};
+
+/// Max. number of txs of a `citrea_simulateBlock` request
+pub(crate) const MAX_SIMULATED_TXS: usize = 256;
+
+/// Max. number of simulations which are queued or running, more are rejected
+pub(crate) const MAX_PENDING_SIMULATIONS: usize = 4;
+
+/// Txs of a block simulation
+pub(crate) struct SimulateBlockRequest {
+    pub(crate) txs: Vec<TransactionSignedEcRecovered>,
+    /// The result is sent back on it
+    pub(crate) response_tx: oneshot::Sender<anyhow::Result<SimulateBlockResponse>>,
+    /// Held until the simulation is done
+    pub(crate) _permit: OwnedSemaphorePermit,
+}
+
+/// Response of `citrea_simulateBlock`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SimulateBlockResponse {
+    /// L2 height the block would have
+    pub(crate) l2_height: u64,
+    /// State root of the head L2 block
+    #[serde(with = "hex::serde")]
+    pub(crate) parent_state_root: Vec<u8>,
+    /// State root after the simulated block
+    #[serde(with = "hex::serde")]
+    pub(crate) state_root: Vec<u8>,
+    pub(crate) gas_used: u64,
+    /// Estimated state diff size of the included txs
+    pub(crate) state_diff_size: u64,
+    /// Txs in the order the block builder tries them, followed by the ones it never tries
+    pub(crate) txs: Vec<SimulatedTx>,
+}
+
+/// State of the head L2 block a simulated block is built on top of,
+/// taken by the block production loop so the simulation runs off it
+pub(crate) struct SimulationSnapshot<C, Da, Vm, Stf>
+where
+    C: Context,
+    Da: DaSpec,
+    Vm: Zkvm,
+    Stf: StfBlueprintTrait<C, Da, Vm>,
+{
+    pub(crate) stf: Arc<Stf>,
+    pub(crate) sov_tx_signer_priv_key: Arc<C::PrivateKey>,
+    pub(crate) sequencer_pub_key: Vec<u8>,
+    /// Snapshot of the storage, which the blocks produced meanwhile do not change
+    pub(crate) prestate: Stf::PreState,
+    pub(crate) state_root: Stf::StateRoot,
+    pub(crate) batch_hash: SoftConfirmationHash,
+    /// L2 height the block would have
+    pub(crate) l2_height: u64,
+    pub(crate) da_block_header: Da::BlockHeader,
+    pub(crate) da_slot_timestamp: u64,
+    pub(crate) l1_fee_rate: u128,
+    pub(crate) timestamp: u64,
+    pub(crate) base_fee: u64,
+    pub(crate) block_gas_limit: u64,
+    pub(crate) max_state_diff_size: u64,
+    pub(crate) phantom: PhantomData<fn() -> Vm>,
+}
+
+impl<C, Da, Vm, Stf> SimulationSnapshot<C, Da, Vm, Stf>
+where
+    C: Context,
+    Da: DaSpec,
+    Vm: Zkvm,
+    Stf: StfBlueprintTrait<C, Da, Vm>,
+    Stf::PreState: Clone,
+{
+    /// Builds a block of the given txs the way `produce_l2_block` does, without committing it.
+    /// Mempool txs, bundles, deposits and forced txs are left out.
+    #[instrument(level = "debug", skip_all, err)]
+    pub(crate) fn simulate(
+        &self,
+        txs: Vec<TransactionSignedEcRecovered>,
+    ) -> anyhow::Result<SimulateBlockResponse> {
+        let pub_key = borsh::to_vec(&self.sov_tx_signer_priv_key.pub_key())?;
+        let batch_info = HookSoftConfirmationInfo {
+            da_slot_height: self.da_block_header.height(),
+            da_slot_hash: self.da_block_header.hash().into(),
+            da_slot_txs_commitment: self.da_block_header.txs_commitment().into(),
+            pre_state_root: self.state_root.as_ref().to_vec(),
+            deposit_data: vec![],
+            pub_key: pub_key.clone(),
+            l1_fee_rate: self.l1_fee_rate,
+            timestamp: self.timestamp,
+            da_slot_timestamp: self.da_slot_timestamp,
+            slot_inbox: Default::default(),
+        };
+        let mut signed_batch: SignedSoftConfirmationBatch = batch_info.into();
+
+        // Dry run the txs one by one to find out which ones the builder includes
+        let mut working_set = self.dry_run(&pub_key, &signed_batch, &[])?;
+        let mut order = BuilderOrder::new(txs, self.base_fee);
+        let mut simulated_txs = vec![];
+        let mut included_txs = vec![];
+        let mut gas_used = 0;
+        let mut state_diff_size = 0;
+        let mut state_diff_skips = 0;
+        while let Some(tx) = order.next() {
+            let rlp_tx = RlpEvmTransaction {
+                rlp: tx.clone().into_signed().envelope_encoded().to_vec(),
+            };
+            let (batch_workspace, last_tx) = dry_run_tx::<C, Da, Vm, Stf>(
+                &self.stf,
+                &self.sov_tx_signer_priv_key,
+                rlp_tx.clone(),
+                working_set,
+            )?;
+
+            match last_tx {
+                Some(last_tx) if last_tx.hash() == tx.hash() => {
+                    if state_diff_size + last_tx.l1_diff_size() > self.max_state_diff_size {
+                        // Applied txs are checkpointed right away, drop it by replaying the others
+                        working_set = self.dry_run(&pub_key, &signed_batch, &included_txs)?;
+                        simulated_txs.push(SimulatedTx::excluded(
+                            tx.hash(),
+                            "exceeds the state diff budget of the block",
+                        ));
+                        order.mark_invalid(&tx);
+                        state_diff_skips += 1;
+                        if state_diff_skips >= MAX_STATE_DIFF_SKIPS {
+                            break;
+                        }
+                        continue;
+                    }
+
+                    working_set = batch_workspace;
+                    simulated_txs.push(SimulatedTx {
+                        hash: tx.hash(),
+                        included: true,
+                        gas_used: last_tx.cumulative_gas_used() - gas_used,
+                        l1_diff_size: last_tx.l1_diff_size(),
+                        reason: None,
+                    });
+                    gas_used = last_tx.cumulative_gas_used();
+                    state_diff_size += last_tx.l1_diff_size();
+                    included_txs.push(rlp_tx);
+
+                    if gas_used >= self.block_gas_limit - MIN_TRANSACTION_GAS
+                        || state_diff_size + MIN_L1_DIFF_SIZE > self.max_state_diff_size
+                    {
+                        break;
+                    }
+                }
+                _ => {
+                    working_set = batch_workspace;
+                    let reason = if tx.gas_limit() > self.block_gas_limit - gas_used {
+                        "exceeds the gas left in the block"
+                    } else {
+                        "failed to execute"
+                    };
+                    simulated_txs.push(SimulatedTx::excluded(tx.hash(), reason));
+                    order.mark_invalid(&tx);
+                }
+            }
+        }
+        simulated_txs.extend(order.into_excluded("block is full"));
+        drop(working_set);
+
+        // Build the block of the included txs to get its state root
+        let mut batch_workspace = self.begin_block(&pub_key, &mut signed_batch)?;
+        let call_txs = CallMessage { txs: included_txs };
+        let raw_message = <Runtime<C, Da> as EncodeCall<citrea_evm::Evm<C>>>::encode_call(call_txs);
+        let signed_blob = make_blob::<C>(
+            &self.sov_tx_signer_priv_key,
+            raw_message,
+            &mut batch_workspace,
+        )?;
+        let txs = vec![signed_blob];
+        let (batch_workspace, tx_receipts) =
+            self.stf.apply_soft_batch_txs(txs.clone(), batch_workspace);
+
+        let unsigned_batch = UnsignedSoftConfirmationBatch::new(
+            self.da_block_header.height(),
+            self.da_block_header.hash().into(),
+            self.da_block_header.txs_commitment().into(),
+            txs,
+            vec![],
+            self.l1_fee_rate,
+            self.timestamp,
+        );
+        let mut signed_soft_batch = sign_soft_confirmation_batch::<C>(
+            &self.sov_tx_signer_priv_key,
+            unsigned_batch,
+            self.batch_hash,
+        )?;
+        let (batch_receipt, checkpoint) = self.stf.end_soft_batch(
+            self.sequencer_pub_key.as_ref(),
+            &mut signed_soft_batch,
+            tx_receipts,
+            batch_workspace,
+        );
+        // The change set is dropped, so nothing is written to the storage
+        let slot_result = self.stf.finalize_soft_batch(
+            batch_receipt,
+            checkpoint,
+            self.prestate.clone(),
+            &mut signed_soft_batch,
+        );
+
+        Ok(SimulateBlockResponse {
+            l2_height: self.l2_height,
+            parent_state_root: self.state_root.as_ref().to_vec(),
+            state_root: slot_result.state_root.as_ref().to_vec(),
+            gas_used,
+            state_diff_size,
+            txs: simulated_txs,
+        })
+    }
+
+    /// Dry runs the block with only the given txs
+    fn dry_run(
+        &self,
+        pub_key: &[u8],
+        signed_batch: &SignedSoftConfirmationBatch,
+        txs: &[RlpEvmTransaction],
+    ) -> anyhow::Result<WorkingSet<C>> {
+        let mut working_set = self.begin_block(pub_key, &mut signed_batch.clone())?;
+        for rlp_tx in txs {
+            (working_set, _) = dry_run_tx::<C, Da, Vm, Stf>(
+                &self.stf,
+                &self.sov_tx_signer_priv_key,
+                rlp_tx.clone(),
+                working_set,
+            )?;
+        }
+        Ok(working_set)
+    }
+
+    fn begin_block(
+        &self,
+        pub_key: &[u8],
+        signed_batch: &mut SignedSoftConfirmationBatch,
+    ) -> anyhow::Result<WorkingSet<C>> {
+        let (result, mut working_set) = self.stf.begin_soft_batch(
+            pub_key,
+            &self.state_root,
+            self.prestate.clone(),
+            Default::default(),
+            &self.da_block_header,
+            &SlotInbox::default(),
+            signed_batch,
+        );
+        if let Err(err) = result {
+            working_set.revert();
+            return Err(CitreaError::from(err).into());
+        }
+        Ok(working_set)
+    }
+}
+
+/// A tx of a simulated block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SimulatedTx {
+    pub(crate) hash: B256,
+    pub(crate) included: bool,
+    pub(crate) gas_used: u64,
+    /// Estimated state diff size of the tx
+    pub(crate) l1_diff_size: u64,
+    /// Why the tx is not in the block
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) reason: Option<String>,
+}
+
+impl SimulatedTx {
+    pub(crate) fn excluded(hash: B256, reason: &str) -> Self {
+        Self {
+            hash,
+            included: false,
+            gas_used: 0,
+            l1_diff_size: 0,
+            reason: Some(reason.to_string()),
+        }
+    }
+}
+
+/// Yields txs the way the mempool yields them to the block builder: by effective priority fee,
+/// and a sender's txs only in nonce order. Ties go to the tx given first.
+pub(crate) struct BuilderOrder {
+    /// Txs of each sender by nonce, with their position in the request
+    senders: HashMap<Address, VecDeque<(usize, TransactionSignedEcRecovered)>>,
+    base_fee: u64,
+    excluded: Vec<SimulatedTx>,
+}
+
+impl BuilderOrder {
+    pub(crate) fn new(txs: Vec<TransactionSignedEcRecovered>, base_fee: u64) -> Self {
+        let mut senders: HashMap<_, Vec<_>> = HashMap::new();
+        let mut excluded = vec![];
+        for (position, tx) in txs.into_iter().enumerate() {
+            if tx.effective_tip_per_gas(Some(base_fee)).is_none() {
+                excluded.push(SimulatedTx::excluded(
+                    tx.hash(),
+                    "max fee per gas is below the base fee",
+                ));
+                continue;
+            }
+            senders.entry(tx.signer()).or_default().push((position, tx));
+        }
+        let senders = senders
+            .into_iter()
+            .map(|(sender, mut txs)| {
+                txs.sort_by_key(|(_, tx)| tx.nonce());
+                (sender, txs.into())
+            })
+            .collect();
+        Self {
+            senders,
+            base_fee,
+            excluded,
+        }
+    }
+
+    pub(crate) fn next(&mut self) -> Option<TransactionSignedEcRecovered> {
+        let sender = self
+            .senders
+            .iter()
+            .filter_map(|(sender, txs)| {
+                let (position, tx) = txs.front()?;
+                let tip = tx.effective_tip_per_gas(Some(self.base_fee))?;
+                Some((tip, std::cmp::Reverse(*position), *sender))
+            })
+            .max()?
+            .2;
+        let txs = self.senders.get_mut(&sender)?;
+        let (_, tx) = txs.pop_front()?;
+        if txs.is_empty() {
+            self.senders.remove(&sender);
+        }
+        Some(tx)
+    }
+
+    /// Drops the remaining txs of the sender of the excluded tx, which now has a nonce gap.
+    pub(crate) fn mark_invalid(&mut self, tx: &TransactionSignedEcRecovered) {
+        for (_, tx) in self.senders.remove(&tx.signer()).unwrap_or_default() {
+            self.excluded.push(SimulatedTx::excluded(
+                tx.hash(),
+                "follows an excluded tx of the sender",
+            ));
+        }
+    }
+
+    /// The txs which are not tried, the remaining ones with the given reason.
+    pub(crate) fn into_excluded(mut self, reason: &str) -> Vec<SimulatedTx> {
+        let mut remaining: Vec<_> = self.senders.into_values().flatten().collect();
+        remaining.sort_by_key(|(position, _)| *position);
+        self.excluded.extend(
+            remaining
+                .into_iter()
+                .map(|(_, tx)| SimulatedTx::excluded(tx.hash(), reason)),
+        );
+        self.excluded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reth_primitives::{Signature, Transaction, TransactionSigned, TxEip1559};
+
+    use super::*;
+
+    fn tx(sender: u8, nonce: u64, max_priority_fee_per_gas: u128) -> TransactionSignedEcRecovered {
+        let transaction = Transaction::Eip1559(TxEip1559 {
+            nonce,
+            max_fee_per_gas: 100 + max_priority_fee_per_gas,
+            max_priority_fee_per_gas,
+            gas_limit: 21_000,
+            ..Default::default()
+        });
+        TransactionSigned::from_transaction_and_signature(transaction, Signature::default())
+            .with_signer(Address::with_last_byte(sender))
+    }
+
+    fn order(txs: Vec<TransactionSignedEcRecovered>, base_fee: u64) -> Vec<(u8, u64)> {
+        let mut order = BuilderOrder::new(txs, base_fee);
+        std::iter::from_fn(|| order.next())
+            .map(|tx| (tx.signer()[19], tx.nonce()))
+            .collect()
+    }
+
+    #[test]
+    fn orders_by_tip_and_nonce() {
+        let txs = vec![tx(1, 1, 50), tx(1, 0, 5), tx(2, 0, 10), tx(3, 0, 10)];
+        assert_eq!(order(txs, 100), vec![(2, 0), (3, 0), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn excludes_underpriced_txs_and_nonce_gaps() {
+        let txs = vec![tx(1, 0, 10), tx(1, 1, 10), tx(2, 0, 10)];
+        let underpriced = tx(3, 0, 0);
+        let mut txs_with_underpriced = txs.clone();
+        txs_with_underpriced.push(underpriced.clone());
+
+        let mut order = BuilderOrder::new(txs_with_underpriced, 101);
+        let first = order.next().unwrap();
+        assert_eq!(first.hash(), txs[0].hash());
+        order.mark_invalid(&first);
+
+        let excluded = order.into_excluded("block is full");
+        let reasons: Vec<_> = excluded
+            .iter()
+            .map(|tx| (tx.hash, tx.reason.clone().unwrap()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (
+                    underpriced.hash(),
+                    "max fee per gas is below the base fee".to_string()
+                ),
+                (
+                    txs[1].hash(),
+                    "follows an excluded tx of the sender".to_string()
+                ),
+                (txs[2].hash(), "block is full".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,332 @@
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+use std::sync::Mutex;
+
+use reth_primitives::TxHash;
+use schnellru::{ByLength, LruMap};
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tracing::debug;
+
+/// Max. number of L2 blocks whose txs are remembered until they are proven
+const MAX_TRACKED_L2_BLOCKS: usize = 100_000;
+/// Max. number of txs in the mempool whose RPC request IDs are remembered,
+/// the ones of the txs sent longest ago are forgotten first
+const MAX_TRACKED_REQUEST_IDS: u32 = 100_000;
+
+/// A step in the lifecycle of a tx sent to the sequencer
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum TxStatus {
+    /// Accepted by the RPC, before the mempool checks
+    Received,
+    /// In the mempool, but can't be executed yet, e.g. because of a nonce gap
+    Queued,
+    /// In the mempool and executable in the next block
+    Pending,
+    /// Could not pay the L1 fee at the given L1 fee rate.
+    /// Kept in the mempool and retried once the L1 fee rate drops.
+    #[serde(rename_all = "camelCase")]
+    L1FeeTooLow { l1_fee_rate: u128 },
+    /// Removed from the mempool without being included
+    Dropped,
+    /// Included in a soft confirmation
+    #[serde(rename_all = "camelCase")]
+    Included { l2_height: u64 },
+    /// The soft confirmation including the tx is in a sequencer commitment sent to DA
+    #[serde(rename_all = "camelCase")]
+    Committed { l2_height: u64, da_tx_id: String },
+    /// The soft confirmation including the tx is proven by a ZK proof found on DA
+    #[serde(rename_all = "camelCase")]
+    Proven { l2_height: u64 },
+}
+
+/// Event sent to the `citrea_subscribeTxStatus` subscribers
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TxStatusEvent {
+    pub tx_hash: TxHash,
+    #[serde(flatten)]
+    pub status: TxStatus,
+}
+
+#[derive(Default)]
+struct TrackedL2Block {
+    state_root: Vec<u8>,
+    /// Only tracked while there are subscribers
+    tx_hashes: Vec<TxHash>,
+    /// Correlation IDs of the RPC requests which sent txs of the block
+    request_ids: Vec<(TxHash, String)>,
+}
+
+/// Publishes the lifecycle events of txs, and logs them with the correlation IDs
+/// of the RPC requests which sent the txs.
+///
+/// Commitments and proofs refer to L2 heights, so the L2 blocks which are not
+/// proven yet are kept in memory to turn them into per tx events.
+/// Their txs are only tracked while there are subscribers.
+pub(crate) struct TxStatusNotifier {
+    sender: broadcast::Sender<TxStatusEvent>,
+    unproven_blocks: Mutex<BTreeMap<u64, TrackedL2Block>>,
+    /// Correlation IDs of the RPC requests which sent the txs in the mempool
+    request_ids: Mutex<LruMap<TxHash, String, ByLength>>,
+}
+
+impl TxStatusNotifier {
+    pub(crate) fn new() -> Self {
+        let (sender, _) = broadcast::channel(1024);
+        Self {
+            sender,
+            unproven_blocks: Default::default(),
+            request_ids: Mutex::new(LruMap::new(ByLength::new(MAX_TRACKED_REQUEST_IDS))),
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<TxStatusEvent> {
+        self.sender.subscribe()
+    }
+
+    fn has_subscribers(&self) -> bool {
+        self.sender.receiver_count() > 0
+    }
+
+    pub(crate) fn notify(&self, tx_hash: TxHash, status: TxStatus) {
+        // Only errors when there are no receivers
+        let _ = self.sender.send(TxStatusEvent { tx_hash, status });
+    }
+
+    /// Remembers the RPC request which sent the tx, so its inclusion, commitment and proof
+    /// can be logged with it
+    pub(crate) fn track_request_id(&self, tx_hash: TxHash, request_id: String) {
+        self.request_ids.lock().unwrap().insert(tx_hash, request_id);
+    }
+
+    /// Forgets the RPC request which sent the tx, once it left the mempool
+    pub(crate) fn take_request_id(&self, tx_hash: &TxHash) -> Option<String> {
+        self.request_ids.lock().unwrap().remove(tx_hash)
+    }
+
+    /// Called after the L2 block at `l2_height` is committed to the ledger
+    pub(crate) fn l2_block_produced(
+        &self,
+        l2_height: u64,
+        state_root: &[u8],
+        tx_hashes: Vec<TxHash>,
+    ) {
+        let mut request_ids = vec![];
+        for tx_hash in &tx_hashes {
+            let request_id = self.take_request_id(tx_hash);
+            debug!(
+                tx_hash = %tx_hash,
+                request_id = request_id.as_deref(),
+                l2_height,
+                "Included transaction"
+            );
+            if let Some(request_id) = request_id {
+                request_ids.push((*tx_hash, request_id));
+            }
+        }
+
+        let tx_hashes = if self.has_subscribers() {
+            for tx_hash in &tx_hashes {
+                self.notify(*tx_hash, TxStatus::Included { l2_height });
+            }
+            tx_hashes
+        } else {
+            vec![]
+        };
+
+        let mut unproven_blocks = self.unproven_blocks.lock().unwrap();
+        unproven_blocks.insert(
+            l2_height,
+            TrackedL2Block {
+                state_root: state_root.to_vec(),
+                tx_hashes,
+                request_ids,
+            },
+        );
+        while unproven_blocks.len() > MAX_TRACKED_L2_BLOCKS {
+            unproven_blocks.pop_first();
+        }
+    }
+
+    /// Called after a commitment of the L2 blocks in `l2_range` is sent to DA
+    pub(crate) fn commitment_sent(&self, l2_range: RangeInclusive<u64>, da_tx_id: String) {
+        let unproven_blocks = self.unproven_blocks.lock().unwrap();
+        for (l2_height, block) in unproven_blocks.range(l2_range) {
+            for (tx_hash, request_id) in &block.request_ids {
+                debug!(
+                    tx_hash = %tx_hash,
+                    request_id = %request_id,
+                    l2_height,
+                    da_tx_id = %da_tx_id,
+                    "Committed transaction"
+                );
+            }
+            for tx_hash in &block.tx_hashes {
+                self.notify(
+                    *tx_hash,
+                    TxStatus::Committed {
+                        l2_height: *l2_height,
+                        da_tx_id: da_tx_id.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Called for each ZK proof found on DA.
+    /// The L2 blocks up to the one with the proven state root are proven.
+    pub(crate) fn proof_found(&self, final_state_root: &[u8]) {
+        let mut unproven_blocks = self.unproven_blocks.lock().unwrap();
+        let Some(proven_height) = unproven_blocks
+            .iter()
+            .find(|(_, block)| block.state_root == final_state_root)
+            .map(|(l2_height, _)| *l2_height)
+        else {
+            return;
+        };
+
+        let still_unproven = unproven_blocks.split_off(&(proven_height + 1));
+        let proven_blocks = std::mem::replace(&mut *unproven_blocks, still_unproven);
+        for (l2_height, block) in proven_blocks {
+            for (tx_hash, request_id) in &block.request_ids {
+                debug!(
+                    tx_hash = %tx_hash,
+                    request_id = %request_id,
+                    l2_height,
+                    "Proven transaction"
+                );
+            }
+            for tx_hash in block.tx_hashes {
+                self.notify(tx_hash, TxStatus::Proven { l2_height });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_txs_until_proven() {
+        let notifier = TxStatusNotifier::new();
+        let mut rx = notifier.subscribe();
+
+        let tx_1 = TxHash::with_last_byte(1);
+        let tx_2 = TxHash::with_last_byte(2);
+        notifier.l2_block_produced(1, &[1; 32], vec![tx_1]);
+        notifier.l2_block_produced(2, &[2; 32], vec![]);
+        notifier.l2_block_produced(3, &[3; 32], vec![tx_2]);
+        notifier.commitment_sent(1..=2, "aa".to_string());
+        notifier.proof_found(&[2; 32]);
+
+        let events: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+        assert_eq!(
+            events,
+            vec![
+                TxStatusEvent {
+                    tx_hash: tx_1,
+                    status: TxStatus::Included { l2_height: 1 }
+                },
+                TxStatusEvent {
+                    tx_hash: tx_2,
+                    status: TxStatus::Included { l2_height: 3 }
+                },
+                TxStatusEvent {
+                    tx_hash: tx_1,
+                    status: TxStatus::Committed {
+                        l2_height: 1,
+                        da_tx_id: "aa".to_string()
+                    }
+                },
+                TxStatusEvent {
+                    tx_hash: tx_1,
+                    status: TxStatus::Proven { l2_height: 1 }
+                },
+            ]
+        );
+
+        // tx_2 is still waiting for a proof
+        let unproven_blocks = notifier.unproven_blocks.lock().unwrap();
+        assert_eq!(unproven_blocks.keys().copied().collect::<Vec<_>>(), vec![3]);
+    }
+
+    #[test]
+    fn follows_request_ids_until_proven_without_subscribers() {
+        let notifier = TxStatusNotifier::new();
+
+        let tx_1 = TxHash::with_last_byte(1);
+        let tx_2 = TxHash::with_last_byte(2);
+        let tx_3 = TxHash::with_last_byte(3);
+        notifier.track_request_id(tx_1, "request-1".to_string());
+        notifier.track_request_id(tx_2, "request-2".to_string());
+        notifier.track_request_id(tx_3, "request-3".to_string());
+        // tx_3 left the mempool without being included
+        assert_eq!(
+            notifier.take_request_id(&tx_3),
+            Some("request-3".to_string())
+        );
+
+        notifier.l2_block_produced(1, &[1; 32], vec![tx_1]);
+        notifier.l2_block_produced(2, &[2; 32], vec![tx_2]);
+        assert!(notifier.request_ids.lock().unwrap().is_empty());
+        {
+            let unproven_blocks = notifier.unproven_blocks.lock().unwrap();
+            assert!(unproven_blocks[&1].tx_hashes.is_empty());
+            assert_eq!(
+                unproven_blocks[&1].request_ids,
+                vec![(tx_1, "request-1".to_string())]
+            );
+        }
+
+        notifier.proof_found(&[1; 32]);
+        let unproven_blocks = notifier.unproven_blocks.lock().unwrap();
+        assert_eq!(unproven_blocks.keys().copied().collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[test]
+    fn forgets_the_oldest_request_ids() {
+        let tx_hash = |i: u64| {
+            let mut hash = [0; 32];
+            hash[..8].copy_from_slice(&i.to_be_bytes());
+            TxHash::from(hash)
+        };
+        let notifier = TxStatusNotifier::new();
+        for i in 0..=MAX_TRACKED_REQUEST_IDS as u64 {
+            notifier.track_request_id(tx_hash(i), i.to_string());
+        }
+
+        assert_eq!(
+            notifier.request_ids.lock().unwrap().len(),
+            MAX_TRACKED_REQUEST_IDS as usize
+        );
+        assert_eq!(notifier.take_request_id(&tx_hash(0)), None);
+        let newest = MAX_TRACKED_REQUEST_IDS as u64;
+        assert_eq!(
+            notifier.take_request_id(&tx_hash(newest)),
+            Some(newest.to_string())
+        );
+    }
+
+    #[test]
+    fn serializes_events_with_status_tag() {
+        let event = TxStatusEvent {
+            tx_hash: TxHash::ZERO,
+            status: TxStatus::Committed {
+                l2_height: 5,
+                da_tx_id: "ab".to_string(),
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(event).unwrap(),
+            serde_json::json!({
+                "txHash": TxHash::ZERO,
+                "status": "committed",
+                "l2Height": 5,
+                "daTxId": "ab",
+            })
+        );
+    }
+}
@@ -1,7 +1,11 @@
 //! Commonly used code snippets
 
+use std::ops::RangeInclusive;
+
+use citrea_primitives::Clock;
 use reth_primitives::{Bytes, PooledTransactionsElement, PooledTransactionsElementEcRecovered};
 use reth_rpc::eth::error::{EthApiError, EthResult};
+use sov_db::ledger_db::SharedLedgerOps;
 
 /// Recovers a [PooledTransactionsElementEcRecovered] from an enveloped encoded byte stream.
 ///
@@ -20,3 +24,131 @@ pub(crate) fn recover_raw_transaction(
         .try_into_ecrecovered()
         .or(Err(EthApiError::InvalidTransactionSignature))
 }
+
+/// Returns the L1 fee rate of the latest soft confirmation, which is the rate the next
+/// txs are expected to pay. Zero before the first soft confirmation.
+pub(crate) fn latest_l1_fee_rate<DB: SharedLedgerOps>(ledger_db: &DB) -> anyhow::Result<u128> {
+    Ok(ledger_db
+        .get_head_soft_batch()?
+        .map(|(_, soft_batch)| soft_batch.l1_fee_rate)
+        .unwrap_or_default())
+}
+
+/// Timestamp of the next L2 block: the time of the clock,
+/// kept within the bounds enforced by the soft confirmation rule enforcer
+pub(crate) fn block_timestamp(clock: &dyn Clock, timestamp_range: &RangeInclusive<u64>) -> u64 {
+    clock
+        .unix_timestamp()
+        .max(*timestamp_range.start())
+        .min(*timestamp_range.end())
+}
+
+/// Estimates the size of data once compressed for DA.
+/// A faster brotli quality than the DA adapter's is used, so the estimate is slightly higher.
+pub(crate) fn compressed_size(data: &[u8]) -> u64 {
+    use std::io::Write;
+
+    let mut writer = brotli::CompressorWriter::new(Vec::new(), 4096, 5, 22);
+    writer.write_all(data).expect("Writing to a Vec can't fail");
+    writer.into_inner().len() as u64
+}
+
+/// Max. L2 blocks the compressed size of the state diff is estimated for without compressing it
+const STATE_DIFF_COMPRESSION_INTERVAL: u64 = 10;
+
+/// Estimates the compressed size of the state diff accumulated since the last commitment.
+/// Compressing the whole diff on every L2 block gets expensive as it grows, so its size is
+/// estimated from the ratio of the last compression. The diff is only compressed once the
+/// estimate gets within 10% of the threshold, or every [`STATE_DIFF_COMPRESSION_INTERVAL`]
+/// L2 blocks to keep the ratio up to date.
+#[derive(Debug)]
+pub(crate) struct StateDiffSizeEstimator {
+    /// Compressed size in bytes commitments are triggered at
+    threshold: u64,
+    /// Uncompressed bytes per compressed byte in the last compression
+    compression_ratio: f64,
+    blocks_since_compression: u64,
+}
+
+impl StateDiffSizeEstimator {
+    pub(crate) fn new(threshold: u64) -> Self {
+        Self {
+            threshold,
+            compression_ratio: 1.0,
+            blocks_since_compression: 0,
+        }
+    }
+
+    /// Estimated compressed size of the serialized state diff
+    pub(crate) fn estimate(&mut self, serialized_state_diff: &[u8]) -> u64 {
+        let size = serialized_state_diff.len() as u64;
+        let estimate = (size as f64 / self.compression_ratio) as u64;
+        let near_threshold = self.threshold - self.threshold / 10;
+
+        // Compression doesn't grow state diffs, so a smaller one can't be near the threshold
+        if size < near_threshold {
+            return estimate.min(size);
+        }
+        if estimate < near_threshold
+            && self.blocks_since_compression < STATE_DIFF_COMPRESSION_INTERVAL
+        {
+            self.blocks_since_compression += 1;
+            return estimate;
+        }
+
+        let compressed_size = compressed_size(serialized_state_diff);
+        self.compression_ratio = size as f64 / compressed_size.max(1) as f64;
+        self.blocks_since_compression = 0;
+        compressed_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use citrea_primitives::TestClock;
+
+    use super::*;
+
+    #[test]
+    fn test_block_timestamp_follows_the_clock_within_the_rule_enforcer_bounds() {
+        let clock = TestClock::new(1_000);
+        assert_eq!(block_timestamp(&clock, &(990..=1_010)), 1_000);
+
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(block_timestamp(&clock, &(990..=1_010)), 1_005);
+
+        // Never before the previous block, nor too far from the DA block
+        assert_eq!(block_timestamp(&clock, &(1_020..=1_030)), 1_020);
+        assert_eq!(block_timestamp(&clock, &(980..=990)), 990);
+    }
+
+    #[test]
+    fn test_state_diff_size_estimator() {
+        let mut estimator = StateDiffSizeEstimator::new(1000);
+
+        // Small diffs are not compressed
+        assert_eq!(estimator.estimate(&[7; 800]), 800);
+
+        // Compressed once near the threshold, which measures the compression ratio
+        let state_diff = [7; 10_000];
+        let compressed = compressed_size(&state_diff);
+        assert!(compressed < 900);
+        assert_eq!(estimator.estimate(&state_diff), compressed);
+
+        // Estimated from the ratio while the estimate is below the threshold
+        let ratio = 10_000.0 / compressed as f64;
+        let state_diff = [7; 12_000];
+        let estimate = (12_000.0 / ratio) as u64;
+        assert!(estimate < 900);
+        for _ in 0..STATE_DIFF_COMPRESSION_INTERVAL {
+            assert_eq!(estimator.estimate(&state_diff), estimate);
+        }
+        // until the ratio is measured again
+        assert_eq!(
+            estimator.estimate(&state_diff),
+            compressed_size(&state_diff)
+        );
+    }
+}
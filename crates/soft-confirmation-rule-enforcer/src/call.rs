@@ -20,6 +20,11 @@ pub enum CallMessage<C: Context> {
         /// The new max L2 blocks per L1 representing max number of L2 blocks published per L1 block.
         max_l2_blocks_per_l1: u64,
     },
+    /// Change how far block timestamps can be from their DA block's timestamp.
+    ModifyMaxDaSlotTimestampDrift {
+        /// The new max difference in seconds, `None` to stop bounding block timestamps.
+        max_da_slot_timestamp_drift: Option<u64>,
+    },
 }
 
 impl<C: Context, Da: DaSpec> SoftConfirmationRuleEnforcer<C, Da> {
@@ -58,4 +63,23 @@ impl<C: Context, Da: DaSpec> SoftConfirmationRuleEnforcer<C, Da> {
             .set(&max_l2_blocks_per_l1, working_set);
         Ok(CallResponse::default())
     }
+
+    pub(crate) fn modify_max_da_slot_timestamp_drift(
+        &self,
+        max_da_slot_timestamp_drift: Option<u64>,
+        context: &C,
+        working_set: &mut WorkingSet<C>,
+    ) -> anyhow::Result<CallResponse> {
+        anyhow::ensure!(
+            *context.sender() == self.get_authority(working_set),
+            "Only authority can change the max DA slot timestamp drift"
+        );
+        match max_da_slot_timestamp_drift {
+            Some(max_da_slot_timestamp_drift) => self
+                .max_da_slot_timestamp_drift
+                .set(&max_da_slot_timestamp_drift, working_set),
+            None => self.max_da_slot_timestamp_drift.delete(working_set),
+        }
+        Ok(CallResponse::default())
+    }
 }
@@ -15,6 +15,10 @@ pub struct SoftConfirmationRuleEnforcerConfig<C: Context> {
     /// L1 fee rate change percentage
     /// Out of 100.
     pub(crate) l1_fee_rate_change_percentage: u128,
+    /// Maximum difference in seconds between a block's timestamp and its DA block's timestamp.
+    /// Block timestamps are not bound by the DA block timestamps if not set.
+    #[serde(default)]
+    pub(crate) max_da_slot_timestamp_drift: Option<u64>,
 }
 
 impl<C: Context, Da: DaSpec> SoftConfirmationRuleEnforcer<C, Da> {
@@ -28,6 +32,10 @@ impl<C: Context, Da: DaSpec> SoftConfirmationRuleEnforcer<C, Da> {
             .set(&config.max_l2_blocks_per_l1, working_set);
         self.l1_fee_rate_change_percentage
             .set(&config.l1_fee_rate_change_percentage, working_set);
+        if let Some(max_da_slot_timestamp_drift) = config.max_da_slot_timestamp_drift {
+            self.max_da_slot_timestamp_drift
+                .set(&max_da_slot_timestamp_drift, working_set);
+        }
         Ok(())
     }
 }
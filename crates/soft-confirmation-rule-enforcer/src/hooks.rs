@@ -4,12 +4,123 @@ use sov_state::Storage;
 #[cfg(feature = "native")]
 use tracing::instrument;
 
-use crate::SoftConfirmationRuleEnforcer;
+use crate::{forced_tx_hash, PendingForcedTx, SoftConfirmationRuleEnforcer};
 
 impl<C: Context, Da: DaSpec> SoftConfirmationRuleEnforcer<C, Da>
 where
     <C::Storage as Storage>::Root: Into<[u8; 32]>,
 {
+    /// Checks the forced transaction rule.
+    /// The forced transactions in the inbox of a DA block are pending from the first soft
+    /// confirmation on it, and must be included before a soft confirmation on a DA block
+    /// [`crate::FORCED_TX_INCLUSION_WINDOW`] blocks later. DA blocks can't be skipped, so the
+    /// forced transactions of every DA block are seen.
+    /// Runs before the block count rule, which counts the soft confirmation on its DA block.
+    #[cfg_attr(feature = "native", instrument(level = "trace", skip_all, err, ret))]
+    fn apply_forced_tx_rule(
+        &self,
+        soft_batch: &mut HookSoftConfirmationInfo,
+        working_set: &mut WorkingSet<C>,
+    ) -> Result<(), ApplySoftConfirmationError> {
+        let da_slot_height = soft_batch.da_slot_height;
+        let mut pending_forced_txs = self.pending_forced_txs.get(working_set).unwrap_or_default();
+
+        let is_first_on_da_slot = self
+            .da_root_hash_to_number
+            .get(&soft_batch.da_slot_hash(), working_set)
+            .is_none();
+        if is_first_on_da_slot {
+            if let Some(last_da_slot_height) = self.last_da_slot_height.get(working_set) {
+                if da_slot_height > last_da_slot_height + 1 {
+                    return Err(ApplySoftConfirmationError::DaSlotSkipped {
+                        da_slot_height,
+                        last_da_slot_height,
+                    });
+                }
+            }
+            self.last_da_slot_height.set(&da_slot_height, working_set);
+
+            if !soft_batch.slot_inbox().forced_txs.is_empty() {
+                for tx in &soft_batch.slot_inbox().forced_txs {
+                    let tx_hash = forced_tx_hash::<C>(tx);
+                    if !pending_forced_txs.iter().any(|tx| tx.tx_hash == tx_hash) {
+                        pending_forced_txs.push(PendingForcedTx {
+                            tx_hash,
+                            l1_height: da_slot_height,
+                        });
+                    }
+                }
+                self.pending_forced_txs
+                    .set(&pending_forced_txs, working_set);
+            }
+        }
+
+        if let Some(overdue) = pending_forced_txs
+            .iter()
+            .find(|tx| tx.deadline() <= da_slot_height)
+        {
+            return Err(ApplySoftConfirmationError::ForcedTransactionNotIncluded {
+                tx_hash: overdue.tx_hash,
+                l1_height: overdue.l1_height,
+                da_slot_height,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Checks the deposit rule.
+    /// The first soft confirmation on a DA block has to start with the deposits posted on it,
+    /// in the order they are posted. Deposits from the sequencer's own deposit mempool can follow.
+    #[cfg_attr(feature = "native", instrument(level = "trace", skip_all, err, ret))]
+    fn apply_deposit_rule(
+        &self,
+        soft_batch: &mut HookSoftConfirmationInfo,
+        working_set: &mut WorkingSet<C>,
+    ) -> Result<(), ApplySoftConfirmationError> {
+        let deposits = &soft_batch.slot_inbox().deposits;
+        if deposits.is_empty() {
+            return Ok(());
+        }
+
+        let is_first_on_da_slot = self
+            .da_root_hash_to_number
+            .get(&soft_batch.da_slot_hash(), working_set)
+            .is_none();
+        if is_first_on_da_slot && !soft_batch.deposit_data.starts_with(deposits) {
+            return Err(ApplySoftConfirmationError::DepositsNotIncluded {
+                da_slot_height: soft_batch.da_slot_height,
+                deposit_count: deposits.len(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Marks the pending forced transactions among the given RLP encoded transactions as included.
+    /// A forced transaction is included once it is in a soft confirmation, whether it executes or not.
+    pub fn include_forced_txs<'a>(
+        &self,
+        txs: impl IntoIterator<Item = &'a [u8]>,
+        working_set: &mut WorkingSet<C>,
+    ) {
+        let Some(mut pending_forced_txs) = self.pending_forced_txs.get(working_set) else {
+            return;
+        };
+        let pending_count = pending_forced_txs.len();
+        for tx in txs {
+            if pending_forced_txs.is_empty() {
+                break;
+            }
+            let tx_hash = forced_tx_hash::<C>(tx);
+            pending_forced_txs.retain(|tx| tx.tx_hash != tx_hash);
+        }
+        if pending_forced_txs.len() != pending_count {
+            self.pending_forced_txs
+                .set(&pending_forced_txs, working_set);
+        }
+    }
+
     /// Checks the block count rule.
     /// For every L1 block, the number of L2 blocks should not exceed the max L2 blocks per L1.
     /// If the number of L2 blocks exceeds the max L2 blocks per L1, the soft confirmation should fail and not be accepted by full nodes.
@@ -122,8 +233,45 @@ where
         Ok(())
     }
 
+    /// Checks that the current block's timestamp is close to its DA block's timestamp.
+    /// The sequencer cannot move the L2 time arbitrarily far from the L1 time, which would otherwise
+    /// only be bound by the previous block's timestamp.
+    /// A block can keep the previous block's timestamp when it is past the window, as L1 block
+    /// timestamps are not monotonic, so there is always a valid timestamp.
+    /// Skipped if the max drift is not set.
+    /// Runs before the timestamp rule, which overwrites the previous block's timestamp.
+    #[cfg_attr(feature = "native", instrument(level = "trace", skip_all, err, ret))]
+    fn apply_da_slot_timestamp_rule(
+        &self,
+        soft_batch: &mut HookSoftConfirmationInfo,
+        working_set: &mut WorkingSet<C>,
+    ) -> Result<(), ApplySoftConfirmationError> {
+        let Some(max_drift) = self.max_da_slot_timestamp_drift.get(working_set) else {
+            return Ok(());
+        };
+
+        let timestamp = soft_batch.timestamp();
+        let da_slot_timestamp = soft_batch.da_slot_timestamp();
+        let last_timestamp = self.last_timestamp.get(working_set).unwrap_or(0);
+
+        let too_early = timestamp < da_slot_timestamp.saturating_sub(max_drift);
+        let too_late = timestamp
+            > da_slot_timestamp
+                .saturating_add(max_drift)
+                .max(last_timestamp);
+        if too_early || too_late {
+            return Err(ApplySoftConfirmationError::TimestampOutOfDaSlotWindow {
+                timestamp,
+                da_slot_timestamp,
+                max_drift,
+            });
+        }
+
+        Ok(())
+    }
+
     /// Logic executed at the beginning of the soft confirmation.
-    /// Checks two rules: block count rule and fee rate rule.
+    /// Checks the forced transaction, deposit, block count, fee rate and timestamp rules.
     #[cfg_attr(
         feature = "native",
         instrument(level = "trace", skip(self, working_set), err, ret)
@@ -133,10 +281,16 @@ where
         soft_batch: &mut HookSoftConfirmationInfo,
         working_set: &mut WorkingSet<C>,
     ) -> Result<(), ApplySoftConfirmationError> {
+        self.apply_forced_tx_rule(soft_batch, working_set)?;
+
+        self.apply_deposit_rule(soft_batch, working_set)?;
+
         self.apply_block_count_rule(soft_batch, working_set)?;
 
         self.apply_fee_rate_rule(soft_batch, working_set)?;
 
+        self.apply_da_slot_timestamp_rule(soft_batch, working_set)?;
+
         self.apply_timestamp_rule(soft_batch, working_set)?;
 
         Ok(())
@@ -13,9 +13,38 @@ pub use query::*;
 mod tests;
 
 // "Given DA slot hasn't been used for more than N soft confirmation blocks."
-use sov_modules_api::{Context, DaSpec, ModuleInfo, StateMap, StateValue, WorkingSet};
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{Context, DaSpec, ModuleInfo, Spec, StateMap, StateValue, WorkingSet};
+use sov_rollup_interface::digest::Digest;
 use sov_state::codec::BcsCodec;
 
+/// Number of DA blocks in which a forced transaction has to be included in an L2 block.
+/// A forced transaction posted in DA block `h` must be included in an L2 block
+/// whose DA slot height is lower than `h + FORCED_TX_INCLUSION_WINDOW`.
+pub const FORCED_TX_INCLUSION_WINDOW: u64 = 10;
+
+/// A forced transaction posted on DA which is not included in an L2 block yet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PendingForcedTx {
+    /// Hash of the RLP encoded transaction
+    pub tx_hash: [u8; 32],
+    /// Height of the DA block the transaction is posted on
+    pub l1_height: u64,
+}
+
+impl PendingForcedTx {
+    /// Height of the first DA block whose soft confirmations are rejected
+    /// if the transaction is not included yet
+    pub fn deadline(&self) -> u64 {
+        self.l1_height + FORCED_TX_INCLUSION_WINDOW
+    }
+}
+
+/// Hash by which a forced transaction is tracked, of its RLP encoding
+pub fn forced_tx_hash<C: Context>(tx: &[u8]) -> [u8; 32] {
+    <C as Spec>::Hasher::digest(tx).into()
+}
+
 #[derive(ModuleInfo, Clone)]
 pub struct SoftConfirmationRuleEnforcer<C: Context, Da: DaSpec> {
     /// Address of the SoftConfirmationRuleEnforcer module.
@@ -43,6 +72,16 @@ pub struct SoftConfirmationRuleEnforcer<C: Context, Da: DaSpec> {
     /// Sequencer's block timestamp
     #[state]
     pub(crate) last_timestamp: StateValue<u64, BcsCodec>,
+    /// Maximum difference in seconds between a block's timestamp and its DA block's timestamp.
+    /// Not set if block timestamps are not bound by the DA block timestamps.
+    #[state]
+    pub(crate) max_da_slot_timestamp_drift: StateValue<u64, BcsCodec>,
+    /// Forced transactions which are not included yet, in the order they are posted
+    #[state]
+    pub(crate) pending_forced_txs: StateValue<Vec<PendingForcedTx>, BcsCodec>,
+    /// DA slot height of the last soft confirmation
+    #[state]
+    pub(crate) last_da_slot_height: StateValue<u64, BcsCodec>,
     /// Phantom state using the da type.
     /// This is used to make sure that the state is generic over the DA type.
     #[allow(dead_code)]
@@ -74,6 +113,13 @@ impl<C: Context, Da: DaSpec> sov_modules_api::Module for SoftConfirmationRuleEnf
             } => {
                 Ok(self.modify_max_l2_blocks_per_l1(max_l2_blocks_per_l1, context, working_set)?)
             }
+            CallMessage::ModifyMaxDaSlotTimestampDrift {
+                max_da_slot_timestamp_drift,
+            } => Ok(self.modify_max_da_slot_timestamp_drift(
+                max_da_slot_timestamp_drift,
+                context,
+                working_set,
+            )?),
         }
     }
 
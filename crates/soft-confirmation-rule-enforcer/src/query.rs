@@ -4,7 +4,7 @@ use jsonrpsee::core::RpcResult;
 use sov_modules_api::macros::rpc_gen;
 use sov_modules_api::{Context, DaSpec, StateMapAccessor, StateValueAccessor, WorkingSet};
 
-use crate::SoftConfirmationRuleEnforcer;
+use crate::{PendingForcedTx, SoftConfirmationRuleEnforcer};
 
 #[rpc_gen(client, server, namespace = "softConfirmationRuleEnforcer")]
 impl<C: Context, Da: DaSpec> SoftConfirmationRuleEnforcer<C, Da> {
@@ -55,6 +55,44 @@ impl<C: Context, Da: DaSpec> SoftConfirmationRuleEnforcer<C, Da> {
         Ok(self.last_timestamp.get(working_set).unwrap_or(0))
     }
 
+    #[rpc_method(name = "getMaxDaSlotTimestampDrift")]
+    /// Get the max difference in seconds between a block's timestamp and its DA block's timestamp.
+    /// None if block timestamps are not bound by the DA block timestamps.
+    pub fn get_max_da_slot_timestamp_drift(
+        &self,
+        working_set: &mut WorkingSet<C>,
+    ) -> RpcResult<Option<u64>> {
+        Ok(self.max_da_slot_timestamp_drift.get(working_set))
+    }
+
+    #[rpc_method(name = "getPendingForcedTxs")]
+    /// Get the forced transactions posted on DA which are not included in an L2 block yet.
+    pub fn get_pending_forced_txs(
+        &self,
+        working_set: &mut WorkingSet<C>,
+    ) -> RpcResult<Vec<PendingForcedTx>> {
+        Ok(self.pending_forced_txs.get(working_set).unwrap_or_default())
+    }
+
+    /// function to get min and max for the next block's timestamp on a DA block with the given timestamp
+    pub fn get_next_min_max_timestamp(
+        &self,
+        da_slot_timestamp: u64,
+        working_set: &mut WorkingSet<C>,
+    ) -> RpcResult<RangeInclusive<u64>> {
+        let last_timestamp = self.last_timestamp.get(working_set).unwrap_or(0);
+
+        let Some(max_drift) = self.max_da_slot_timestamp_drift.get(working_set) else {
+            return Ok(last_timestamp..=u64::MAX);
+        };
+
+        let min = last_timestamp.max(da_slot_timestamp.saturating_sub(max_drift));
+        // The last timestamp can be past the window of a DA block with an earlier timestamp
+        let max = da_slot_timestamp.saturating_add(max_drift).max(min);
+
+        Ok(min..=max)
+    }
+
     /// function to get min and max for next L1 fee rate
     pub fn get_next_min_max_l1_fee_rate(
         &self,
@@ -19,6 +19,7 @@ lazy_static! {
             )
             .unwrap(),
             l1_fee_rate_change_percentage: 10,
+            max_da_slot_timestamp_drift: None,
         };
 }
 
@@ -3,12 +3,14 @@ use std::str::FromStr;
 use anyhow::anyhow;
 use sov_mock_da::MockDaSpec;
 use sov_modules_api::default_context::DefaultContext;
-use sov_modules_api::hooks::HookSoftConfirmationInfo;
+use sov_modules_api::hooks::{ApplySoftConfirmationError, HookSoftConfirmationInfo};
 use sov_modules_api::utils::generate_address;
-use sov_modules_api::{Context, Module, Spec, StateValueAccessor};
+use sov_modules_api::{Context, Module, Spec, StateValueAccessor, WorkingSet};
+use sov_rollup_interface::da::SlotInbox;
 use sov_rollup_interface::soft_confirmation::SignedSoftConfirmationBatch;
 
 use crate::call::CallMessage;
+use crate::forced_tx_hash;
 use crate::tests::genesis_tests::{get_soft_confirmation_rule_enforcer, TEST_CONFIG};
 
 type C = DefaultContext;
@@ -55,6 +57,8 @@ fn begin_soft_confirmation_hook_checks_max_l2_blocks_per_l1() {
                 &mut HookSoftConfirmationInfo::new(
                     signed_soft_confirmation_batch.clone(),
                     vec![0; 32],
+                    0,
+                    Default::default(),
                 ),
                 &mut working_set,
             )
@@ -87,7 +91,12 @@ fn begin_soft_confirmation_hook_checks_l1_fee_rate() {
 
     // call first with 100 fee rate to set last_l1_fee_rate
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
         &mut working_set,
     );
 
@@ -98,7 +107,12 @@ fn begin_soft_confirmation_hook_checks_l1_fee_rate() {
     signed_soft_confirmation_batch.set_l1_fee_rate(111);
 
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
         &mut working_set,
     );
 
@@ -123,7 +137,12 @@ fn begin_soft_confirmation_hook_checks_l1_fee_rate() {
     signed_soft_confirmation_batch.set_l1_fee_rate(110);
 
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
         &mut working_set,
     );
 
@@ -133,7 +152,12 @@ fn begin_soft_confirmation_hook_checks_l1_fee_rate() {
     signed_soft_confirmation_batch.set_l1_fee_rate(122);
 
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
         &mut working_set,
     );
 
@@ -142,7 +166,12 @@ fn begin_soft_confirmation_hook_checks_l1_fee_rate() {
     signed_soft_confirmation_batch.set_l1_fee_rate(121);
 
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
         &mut working_set,
     );
 
@@ -152,13 +181,23 @@ fn begin_soft_confirmation_hook_checks_l1_fee_rate() {
     signed_soft_confirmation_batch.set_l1_fee_rate(109);
 
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
         &mut working_set,
     );
     assert!(res.is_ok());
     signed_soft_confirmation_batch.set_l1_fee_rate(100);
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
         &mut working_set,
     );
     assert!(res.is_ok());
@@ -170,7 +209,12 @@ fn begin_soft_confirmation_hook_checks_l1_fee_rate() {
     signed_soft_confirmation_batch.set_l1_fee_rate(89);
 
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
         &mut working_set,
     );
 
@@ -194,7 +238,12 @@ fn begin_soft_confirmation_hook_checks_l1_fee_rate() {
     signed_soft_confirmation_batch.set_l1_fee_rate(90);
 
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
         &mut working_set,
     );
 
@@ -205,7 +254,12 @@ fn begin_soft_confirmation_hook_checks_l1_fee_rate() {
     signed_soft_confirmation_batch.set_l1_fee_rate(89);
 
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
         &mut working_set,
     );
 
@@ -235,7 +289,12 @@ fn begin_soft_confirmation_hook_checks_timestamp() {
 
     // call first with `original_timestamp`
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
         &mut working_set,
     );
 
@@ -258,7 +317,12 @@ fn begin_soft_confirmation_hook_checks_timestamp() {
     );
 
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
         &mut working_set,
     );
 
@@ -296,9 +360,287 @@ fn begin_soft_confirmation_hook_checks_timestamp() {
     );
 
     let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
-        &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+        &mut HookSoftConfirmationInfo::new(
+            signed_soft_confirmation_batch.clone(),
+            vec![0; 32],
+            0,
+            Default::default(),
+        ),
+        &mut working_set,
+    );
+
+    assert!(res.is_ok());
+}
+
+#[test]
+fn begin_soft_confirmation_hook_checks_da_slot_timestamp() {
+    let mut config = TEST_CONFIG.clone();
+    config.max_da_slot_timestamp_drift = Some(60);
+    let (soft_confirmation_rule_enforcer, mut working_set) =
+        get_soft_confirmation_rule_enforcer::<MockDaSpec>(&config);
+
+    let da_slot_timestamp = 1_700_000_000;
+
+    let new_batch = |timestamp| {
+        SignedSoftConfirmationBatch::new(
+            [0; 32],
+            [0; 32],
+            0,
+            [0; 32],
+            [0; 32],
+            100,
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            timestamp,
+        )
+    };
+
+    // a timestamp within the window of the DA block passes
+    let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
+        &mut HookSoftConfirmationInfo::new(
+            new_batch(da_slot_timestamp - 60),
+            vec![0; 32],
+            da_slot_timestamp,
+            Default::default(),
+        ),
+        &mut working_set,
+    );
+    assert!(res.is_ok());
+
+    // a timestamp too far ahead of the DA block fails
+    let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
+        &mut HookSoftConfirmationInfo::new(
+            new_batch(da_slot_timestamp + 61),
+            vec![0; 32],
+            da_slot_timestamp,
+            Default::default(),
+        ),
         &mut working_set,
     );
+    assert_eq!(
+        format!(
+            "{}",
+            anyhow!(
+                "Block's timestamp {} is more than {} seconds away from the DA block's timestamp {}",
+                da_slot_timestamp + 61,
+                60,
+                da_slot_timestamp
+            )
+        ),
+        format!("{}", res.unwrap_err())
+    );
 
+    // the window moves with the DA block
+    let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
+        &mut HookSoftConfirmationInfo::new(
+            new_batch(da_slot_timestamp + 61),
+            vec![0; 32],
+            da_slot_timestamp + 1,
+            Default::default(),
+        ),
+        &mut working_set,
+    );
     assert!(res.is_ok());
+
+    // a DA block with an earlier timestamp leaves the last timestamp past its window,
+    // which can only be kept
+    let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
+        &mut HookSoftConfirmationInfo::new(
+            new_batch(da_slot_timestamp + 62),
+            vec![0; 32],
+            da_slot_timestamp - 100,
+            Default::default(),
+        ),
+        &mut working_set,
+    );
+    assert!(res.is_err());
+    let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
+        &mut HookSoftConfirmationInfo::new(
+            new_batch(da_slot_timestamp + 61),
+            vec![0; 32],
+            da_slot_timestamp - 100,
+            Default::default(),
+        ),
+        &mut working_set,
+    );
+    assert!(res.is_ok());
+
+    // the drift can be lifted by the authority
+    let sender_address = <DefaultContext as Spec>::Address::from_str(
+        "sov1l6n2cku82yfqld30lanm2nfw43n2auc8clw7r5u5m6s7p8jrm4zqrr8r94",
+    )
+    .unwrap();
+    let sequencer_address = generate_address::<C>("sequencer");
+    let context = C::new(sender_address, sequencer_address, 1);
+    soft_confirmation_rule_enforcer
+        .call(
+            CallMessage::ModifyMaxDaSlotTimestampDrift {
+                max_da_slot_timestamp_drift: None,
+            },
+            &context,
+            &mut working_set,
+        )
+        .unwrap();
+
+    let res = soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
+        &mut HookSoftConfirmationInfo::new(
+            new_batch(da_slot_timestamp + 1000),
+            vec![0; 32],
+            da_slot_timestamp,
+            Default::default(),
+        ),
+        &mut working_set,
+    );
+    assert!(res.is_ok());
+}
+
+#[test]
+fn begin_soft_confirmation_hook_checks_forced_txs() {
+    let (soft_confirmation_rule_enforcer, mut working_set) =
+        get_soft_confirmation_rule_enforcer::<MockDaSpec>(&TEST_CONFIG);
+
+    let forced_tx = vec![7; 100];
+    let expected_tx_hash = forced_tx_hash::<C>(&forced_tx);
+    let inbox = SlotInbox {
+        forced_txs: vec![forced_tx.clone()],
+        ..Default::default()
+    };
+
+    let new_batch = |da_slot_height: u64| {
+        SignedSoftConfirmationBatch::new(
+            [0; 32],
+            [0; 32],
+            da_slot_height,
+            [da_slot_height as u8; 32],
+            [0; 32],
+            100,
+            vec![],
+            vec![],
+            vec![],
+            vec![],
+            0,
+        )
+    };
+    let begin_soft_confirmation =
+        |da_slot_height: u64, inbox: &SlotInbox, working_set: &mut WorkingSet<C>| {
+            soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
+                &mut HookSoftConfirmationInfo::new(
+                    new_batch(da_slot_height),
+                    vec![0; 32],
+                    0,
+                    inbox.clone(),
+                ),
+                working_set,
+            )
+        };
+
+    // the forced tx is pending from the first block on its DA block
+    assert!(begin_soft_confirmation(1, &inbox, &mut working_set).is_ok());
+    // the inbox of a DA block is only read on its first block
+    assert!(begin_soft_confirmation(1, &inbox, &mut working_set).is_ok());
+    for da_slot_height in 2..10 {
+        assert!(
+            begin_soft_confirmation(da_slot_height, &SlotInbox::default(), &mut working_set)
+                .is_ok()
+        );
+    }
+    // the forced tx is posted again, it is still due by its first DA block
+    assert!(begin_soft_confirmation(10, &inbox, &mut working_set).is_ok());
+    assert_eq!(
+        soft_confirmation_rule_enforcer
+            .get_pending_forced_txs(&mut working_set)
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // blocks on DA blocks can't be skipped, so no inbox is missed
+    assert!(matches!(
+        begin_soft_confirmation(12, &SlotInbox::default(), &mut working_set),
+        Err(ApplySoftConfirmationError::DaSlotSkipped {
+            da_slot_height: 12,
+            last_da_slot_height: 10,
+        })
+    ));
+
+    // the forced tx must be included before the window of its DA block passes
+    assert!(matches!(
+        begin_soft_confirmation(11, &SlotInbox::default(), &mut working_set),
+        Err(ApplySoftConfirmationError::ForcedTransactionNotIncluded {
+            tx_hash,
+            l1_height: 1,
+            da_slot_height: 11,
+        }) if tx_hash == expected_tx_hash
+    ));
+
+    soft_confirmation_rule_enforcer.include_forced_txs([&forced_tx[..]], &mut working_set);
+    assert!(soft_confirmation_rule_enforcer
+        .get_pending_forced_txs(&mut working_set)
+        .unwrap()
+        .is_empty());
+    assert!(begin_soft_confirmation(11, &SlotInbox::default(), &mut working_set).is_ok());
+}
+
+#[test]
+fn begin_soft_confirmation_hook_checks_deposits() {
+    let (soft_confirmation_rule_enforcer, mut working_set) =
+        get_soft_confirmation_rule_enforcer::<MockDaSpec>(&TEST_CONFIG);
+
+    let inbox = SlotInbox {
+        deposits: vec![vec![1; 50], vec![2; 50]],
+        ..Default::default()
+    };
+
+    let begin_soft_confirmation =
+        |da_slot_height: u64, deposit_data: Vec<Vec<u8>>, working_set: &mut WorkingSet<C>| {
+            soft_confirmation_rule_enforcer.begin_soft_confirmation_hook(
+                &mut HookSoftConfirmationInfo::new(
+                    SignedSoftConfirmationBatch::new(
+                        [0; 32],
+                        [0; 32],
+                        da_slot_height,
+                        [da_slot_height as u8; 32],
+                        [0; 32],
+                        100,
+                        vec![],
+                        deposit_data,
+                        vec![],
+                        vec![],
+                        0,
+                    ),
+                    vec![0; 32],
+                    0,
+                    inbox.clone(),
+                ),
+                working_set,
+            )
+        };
+
+    // the deposits of the DA block must come first, in the order they are posted
+    for deposit_data in [
+        vec![],
+        vec![vec![1; 50]],
+        vec![vec![2; 50], vec![1; 50]],
+        vec![vec![3; 50], vec![1; 50], vec![2; 50]],
+    ] {
+        assert!(matches!(
+            begin_soft_confirmation(1, deposit_data, &mut working_set),
+            Err(ApplySoftConfirmationError::DepositsNotIncluded {
+                da_slot_height: 1,
+                deposit_count: 2,
+            })
+        ));
+    }
+
+    // deposits from the deposit mempool can follow them
+    assert!(begin_soft_confirmation(
+        1,
+        vec![vec![1; 50], vec![2; 50], vec![3; 50]],
+        &mut working_set
+    )
+    .is_ok());
+    // the deposits are only included by the first block on the DA block
+    assert!(begin_soft_confirmation(1, vec![], &mut working_set).is_ok());
 }
@@ -30,6 +30,8 @@ fn block_count_per_da_hash_must_be_correct() {
                 &mut HookSoftConfirmationInfo::new(
                     signed_soft_confirmation_batch.clone(),
                     vec![0; 32],
+                    0,
+                    Default::default(),
                 ),
                 &mut working_set,
             )
@@ -49,7 +51,12 @@ fn block_count_per_da_hash_must_be_correct() {
     // call with a different da hash
     soft_confirmation_rule_enforcer
         .begin_soft_confirmation_hook(
-            &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+            &mut HookSoftConfirmationInfo::new(
+                signed_soft_confirmation_batch.clone(),
+                vec![0; 32],
+                0,
+                Default::default(),
+            ),
             &mut working_set,
         )
         .unwrap();
@@ -91,7 +98,12 @@ fn get_max_l1_fee_rate_change_percentage_must_be_correct() {
 
     soft_confirmation_rule_enforcer
         .begin_soft_confirmation_hook(
-            &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+            &mut HookSoftConfirmationInfo::new(
+                signed_soft_confirmation_batch.clone(),
+                vec![0; 32],
+                0,
+                Default::default(),
+            ),
             &mut working_set,
         )
         .unwrap();
@@ -132,7 +144,12 @@ fn get_last_l1_fee_rate_must_be_correct() {
     );
     soft_confirmation_rule_enforcer
         .begin_soft_confirmation_hook(
-            &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+            &mut HookSoftConfirmationInfo::new(
+                signed_soft_confirmation_batch.clone(),
+                vec![0; 32],
+                0,
+                Default::default(),
+            ),
             &mut working_set,
         )
         .unwrap();
@@ -174,7 +191,12 @@ fn get_last_timestamp_must_be_correct() {
     );
     soft_confirmation_rule_enforcer
         .begin_soft_confirmation_hook(
-            &mut HookSoftConfirmationInfo::new(signed_soft_confirmation_batch.clone(), vec![0; 32]),
+            &mut HookSoftConfirmationInfo::new(
+                signed_soft_confirmation_batch.clone(),
+                vec![0; 32],
+                0,
+                Default::default(),
+            ),
             &mut working_set,
         )
         .unwrap();
@@ -193,3 +215,52 @@ fn get_last_timestamp_must_be_correct() {
         timestamp,
     );
 }
+
+#[test]
+fn next_min_max_timestamp_is_never_empty() {
+    let mut config = TEST_CONFIG.clone();
+    config.max_da_slot_timestamp_drift = Some(60);
+    let (soft_confirmation_rule_enforcer, mut working_set) =
+        get_soft_confirmation_rule_enforcer::<MockDaSpec>(&config);
+
+    let da_slot_timestamp = 1_700_000_000;
+    let signed_soft_confirmation_batch = SignedSoftConfirmationBatch::new(
+        [0; 32],
+        [0; 32],
+        0,
+        [0; 32],
+        [0; 32],
+        1,
+        vec![],
+        vec![],
+        vec![],
+        vec![],
+        da_slot_timestamp + 60,
+    );
+    soft_confirmation_rule_enforcer
+        .begin_soft_confirmation_hook(
+            &mut HookSoftConfirmationInfo::new(
+                signed_soft_confirmation_batch,
+                vec![0; 32],
+                da_slot_timestamp,
+                Default::default(),
+            ),
+            &mut working_set,
+        )
+        .unwrap();
+
+    assert_eq!(
+        soft_confirmation_rule_enforcer
+            .get_next_min_max_timestamp(da_slot_timestamp + 10, &mut working_set)
+            .unwrap(),
+        da_slot_timestamp + 60..=da_slot_timestamp + 70
+    );
+
+    // the last timestamp is past the window of a DA block with an earlier timestamp
+    assert_eq!(
+        soft_confirmation_rule_enforcer
+            .get_next_min_max_timestamp(da_slot_timestamp - 100, &mut working_set)
+            .unwrap(),
+        da_slot_timestamp + 60..=da_slot_timestamp + 60
+    );
+}
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Distribution of the latency of submissions to [`crate::MockDaService`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LatencyDistribution {
+    /// Always the same latency
+    Fixed {
+        /// Latency in milliseconds
+        ms: u64,
+    },
+    /// Latency uniformly distributed between the bounds, inclusive
+    Uniform {
+        /// Lowest latency in milliseconds
+        min_ms: u64,
+        /// Highest latency in milliseconds
+        max_ms: u64,
+    },
+    /// Exponentially distributed latency, a few submissions are much slower than most
+    Exponential {
+        /// Mean latency in milliseconds
+        mean_ms: u64,
+    },
+}
+
+/// Faults injected into the submissions of [`crate::MockDaService`].
+/// All faults are drawn from a random number generator seeded with `seed`,
+/// so a test sending the same submissions sees the same faults on every run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FaultInjection {
+    /// Seed of the random number generator
+    pub seed: u64,
+    /// Latency of each submission, none if not set
+    pub submission_latency: Option<LatencyDistribution>,
+    /// Probability that a submission fails without adding a block
+    pub failure_probability: f64,
+    /// Probability that a submitted blob is delivered twice, in two consecutive blocks
+    pub duplicate_probability: f64,
+    /// Probability that a submission is followed by a reorg
+    pub reorg_probability: f64,
+    /// Highest depth of the reorgs, which is uniformly distributed from 1 on.
+    /// Reorgs never replace finalized blocks, so they are shallower with a short finality.
+    pub max_reorg_depth: u64,
+}
+
+impl Default for FaultInjection {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            submission_latency: None,
+            failure_probability: 0.0,
+            duplicate_probability: 0.0,
+            reorg_probability: 0.0,
+            max_reorg_depth: 1,
+        }
+    }
+}
+
+/// Faults drawn for a single submission
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SubmissionFaults {
+    pub(crate) latency: Option<Duration>,
+    pub(crate) fail: bool,
+    pub(crate) duplicate: bool,
+    pub(crate) reorg_depth: Option<u64>,
+}
+
+pub(crate) struct FaultInjector {
+    faults: FaultInjection,
+    rng: StdRng,
+}
+
+impl FaultInjector {
+    pub(crate) fn new(faults: FaultInjection) -> Self {
+        Self {
+            rng: StdRng::seed_from_u64(faults.seed),
+            faults,
+        }
+    }
+
+    /// Draws the faults of the next submission.
+    /// The same number of random values is drawn for every submission,
+    /// so the faults of a submission don't depend on the faults of the previous ones.
+    pub(crate) fn next_submission(&mut self) -> SubmissionFaults {
+        let latency = self.faults.submission_latency.map(|distribution| {
+            let ms = match distribution {
+                LatencyDistribution::Fixed { ms } => ms,
+                LatencyDistribution::Uniform { min_ms, max_ms } => {
+                    self.rng.gen_range(min_ms..=max_ms.max(min_ms))
+                }
+                LatencyDistribution::Exponential { mean_ms } => {
+                    let uniform: f64 = self.rng.gen();
+                    (-(mean_ms as f64) * (1.0 - uniform).ln()) as u64
+                }
+            };
+            Duration::from_millis(ms)
+        });
+        let fail = self.rng.gen_bool(self.faults.failure_probability);
+        let duplicate = self.rng.gen_bool(self.faults.duplicate_probability);
+        let reorg = self.rng.gen_bool(self.faults.reorg_probability);
+        let reorg_depth = self.rng.gen_range(1..=self.faults.max_reorg_depth.max(1));
+
+        SubmissionFaults {
+            latency,
+            fail,
+            duplicate: !fail && duplicate,
+            reorg_depth: (!fail && reorg).then_some(reorg_depth),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faults_are_reproducible() {
+        let faults = FaultInjection {
+            seed: 42,
+            submission_latency: Some(LatencyDistribution::Uniform {
+                min_ms: 5,
+                max_ms: 50,
+            }),
+            failure_probability: 0.2,
+            duplicate_probability: 0.2,
+            reorg_probability: 0.2,
+            max_reorg_depth: 3,
+        };
+
+        let draw = |faults: FaultInjection| {
+            let mut injector = FaultInjector::new(faults);
+            (0..100)
+                .map(|_| injector.next_submission())
+                .collect::<Vec<_>>()
+        };
+        let first = draw(faults.clone());
+        assert_eq!(first, draw(faults.clone()));
+        assert_ne!(first, draw(FaultInjection { seed: 43, ..faults }));
+
+        assert!(first.iter().any(|faults| faults.fail));
+        assert!(first.iter().any(|faults| faults.duplicate));
+        assert!(first.iter().all(|faults| {
+            faults.latency.unwrap() >= Duration::from_millis(5)
+                && faults.latency.unwrap() <= Duration::from_millis(50)
+                && faults
+                    .reorg_depth
+                    .map_or(true, |depth| (1..=3).contains(&depth))
+                && !(faults.fail && faults.duplicate)
+        }));
+    }
+
+    #[test]
+    fn no_faults_by_default() {
+        let mut injector = FaultInjector::new(FaultInjection::default());
+        for _ in 0..100 {
+            assert_eq!(
+                injector.next_submission(),
+                SubmissionFaults {
+                    latency: None,
+                    fail: false,
+                    duplicate: false,
+                    reorg_depth: None,
+                }
+            );
+        }
+    }
+}
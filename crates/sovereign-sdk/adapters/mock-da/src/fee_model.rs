@@ -0,0 +1,74 @@
+/// Fee rate per byte of [`crate::MockDaService`] if no fee model is set
+pub(crate) const DEFAULT_FEE_RATE: u128 = 10;
+
+/// Fee market of [`crate::MockDaService`].
+/// Each block has a floor fee rate per byte, which submissions to it have to pay.
+/// Submissions pay the estimated fee rate, which is the floor of the next block
+/// capped by `max_fee_rate`, so they fail while the floor is above the cap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockFeeModel {
+    /// Floor fee rates per byte of consecutive blocks from height 1 on,
+    /// repeated after the last one
+    pub fee_rates: Vec<u128>,
+    /// Highest fee rate per byte paid by submissions, before the multiplier of a fee bump
+    pub max_fee_rate: u128,
+}
+
+impl MockFeeModel {
+    /// Floor fee rate per byte of the block at the height
+    pub fn floor_at(&self, height: u64) -> u128 {
+        if self.fee_rates.is_empty() {
+            return DEFAULT_FEE_RATE;
+        }
+        let index = height.saturating_sub(1) % self.fee_rates.len() as u64;
+        self.fee_rates[index as usize]
+    }
+
+    /// Fee rate per byte estimated for the block at the height
+    pub fn estimate_at(&self, height: u64) -> u128 {
+        self.floor_at(height).min(self.max_fee_rate)
+    }
+
+    /// Checks that a submission to the block at the height pays at least its floor
+    pub(crate) fn check_submission(
+        &self,
+        height: u64,
+        fee_rate_multiplier: f64,
+    ) -> anyhow::Result<()> {
+        let floor = self.floor_at(height);
+        let fee_rate = (self.estimate_at(height) as f64 * fee_rate_multiplier) as u128;
+        if fee_rate < floor {
+            anyhow::bail!(
+                "Fee rate {} is below the floor {} of block {}",
+                fee_rate,
+                floor,
+                height
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fee_rates_vary_over_blocks() {
+        let model = MockFeeModel {
+            fee_rates: vec![5, 20, 8],
+            max_fee_rate: 10,
+        };
+
+        assert_eq!(model.floor_at(1), 5);
+        assert_eq!(model.floor_at(2), 20);
+        assert_eq!(model.floor_at(4), 5);
+        assert_eq!(model.estimate_at(2), 10);
+
+        assert!(model.check_submission(1, 1.0).is_ok());
+        assert!(model.check_submission(2, 1.0).is_err());
+        assert!(model.check_submission(2, 1.5).is_err());
+        assert!(model.check_submission(2, 2.0).is_ok());
+        assert!(model.check_submission(3, 1.0).is_ok());
+    }
+}
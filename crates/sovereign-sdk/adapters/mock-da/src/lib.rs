@@ -4,12 +4,20 @@
 #[cfg(feature = "native")]
 mod db_connector;
 #[cfg(feature = "native")]
+mod faults;
+#[cfg(feature = "native")]
+mod fee_model;
+#[cfg(feature = "native")]
 mod service;
 mod types;
 mod validity_condition;
 /// Contains DaSpec and DaVerifier
 pub mod verifier;
 
+#[cfg(feature = "native")]
+pub use faults::{FaultInjection, LatencyDistribution};
+#[cfg(feature = "native")]
+pub use fee_model::MockFeeModel;
 #[cfg(feature = "native")]
 pub use service::*;
 pub use types::*;
@@ -7,14 +7,18 @@ use std::time::Duration;
 use async_trait::async_trait;
 use pin_project::pin_project;
 use sha2::Digest;
-use sov_rollup_interface::da::{BlockHeaderTrait, DaSpec, Time};
-use sov_rollup_interface::services::da::{BlobWithNotifier, DaService, SlotData};
+use sov_rollup_interface::da::{BlobReaderTrait, BlockHeaderTrait, DaSpec, Time};
+use sov_rollup_interface::services::da::{
+    BlobWithNotifier, DaService, FeeBump, SlotData, TxStatus,
+};
 use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
 use tokio::sync::{broadcast, Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard};
 use tokio::time;
 use tracing::instrument::Instrument;
 
 use crate::db_connector::DbConnector;
+use crate::faults::{FaultInjection, FaultInjector, SubmissionFaults};
+use crate::fee_model::{MockFeeModel, DEFAULT_FEE_RATE};
 use crate::types::{MockAddress, MockBlob, MockBlock, MockDaVerifier};
 use crate::verifier::MockDaSpec;
 use crate::{MockBlockHeader, MockHash};
@@ -74,6 +78,8 @@ pub struct MockDaService {
     finalized_header_sender: broadcast::Sender<MockBlockHeader>,
     wait_attempts: usize,
     planned_fork: Arc<Mutex<Option<PlannedFork>>>,
+    fault_injector: Arc<Mutex<Option<FaultInjector>>>,
+    fee_model: Arc<Mutex<Option<MockFeeModel>>>,
 }
 
 impl MockDaService {
@@ -104,6 +110,8 @@ impl MockDaService {
             finalized_header_sender: tx,
             wait_attempts: 100_0000,
             planned_fork: Arc::new(Mutex::new(None)),
+            fault_injector: Arc::new(Mutex::new(None)),
+            fee_model: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -172,6 +180,100 @@ impl MockDaService {
         Ok(())
     }
 
+    /// Injects the faults into all following submissions, including those of clones of the service
+    pub fn set_fault_injection(&self, faults: FaultInjection) {
+        *self.fault_injector.lock().unwrap() = Some(FaultInjector::new(faults));
+    }
+
+    /// Sets the fee market, including for clones of the service
+    pub fn set_fee_model(&self, fee_model: MockFeeModel) {
+        *self.fee_model.lock().unwrap() = Some(fee_model);
+    }
+
+    /// Draws the faults of a submission and waits for its latency
+    async fn submission_faults(&self) -> anyhow::Result<Option<SubmissionFaults>> {
+        let faults = self
+            .fault_injector
+            .lock()
+            .unwrap()
+            .as_mut()
+            .map(FaultInjector::next_submission);
+        let Some(faults) = faults else {
+            return Ok(None);
+        };
+
+        if let Some(latency) = faults.latency {
+            time::sleep(latency).await;
+        }
+        if faults.fail {
+            anyhow::bail!("Injected transient submission failure");
+        }
+        Ok(Some(faults))
+    }
+
+    /// Adds the submitted blob, with the injected duplicate delivery and reorg.
+    /// Fails if the fee rate paid is below the floor of the fee model.
+    async fn submit(
+        &self,
+        blob: &[u8],
+        zkp_proof: Vec<u8>,
+        fee_rate_multiplier: f64,
+    ) -> anyhow::Result<u64> {
+        let faults = self.submission_faults().await?;
+
+        let blocks = self.blocks.lock().await;
+        if let Some(fee_model) = self.fee_model.lock().unwrap().as_ref() {
+            fee_model.check_submission(blocks.len() as u64 + 1, fee_rate_multiplier)?;
+        }
+        let mut height = self.add_blob(&blocks, blob, zkp_proof.clone())?;
+        let Some(faults) = faults else {
+            return Ok(height);
+        };
+
+        if faults.duplicate {
+            self.add_blob(&blocks, blob, zkp_proof)?;
+        }
+        if let Some(depth) = faults.reorg_depth {
+            let fork_height = self.reorg(&blocks, depth)?;
+            // The blob is included again after the fork
+            if height > fork_height {
+                height += 1;
+            }
+        }
+        Ok(height)
+    }
+
+    /// Replaces the last non finalized blocks, at most `depth` of them. The fork starts with
+    /// an empty block, after which the blobs of the replaced blocks are included again.
+    /// Returns the height of the fork.
+    fn reorg(&self, blocks: &AsyncMutexGuard<'_, DbConnector>, depth: u64) -> anyhow::Result<u64> {
+        let len = blocks.len() as u64;
+        let depth = depth.min(self.blocks_to_finality as u64).min(len);
+        let fork_height = len - depth;
+        if depth == 0 {
+            return Ok(fork_height);
+        }
+
+        let replaced: Vec<MockBlock> = (fork_height..len)
+            .filter_map(|index| blocks.get(index))
+            .collect();
+        tracing::debug!(
+            "Injected reorg of {} blocks at height {}",
+            depth,
+            fork_height
+        );
+        blocks.prune_above(fork_height);
+
+        self.add_blob(blocks, &[], Default::default())?;
+        for block in replaced {
+            for mut blob in block.blobs {
+                let zkp_proof = std::mem::take(&mut blob.zk_proofs_data);
+                self.add_blob(blocks, blob.full_data(), zkp_proof)?;
+            }
+        }
+        Ok(fork_height)
+    }
+
     /// Returns the latest block number
     pub async fn get_height(&self) -> u64 {
         self.blocks.lock().await.len() as u64
@@ -393,8 +495,7 @@ impl DaService for MockDaService {
     }
 
     async fn send_transaction(&self, blob: &[u8]) -> Result<Self::TransactionId, Self::Error> {
-        let blocks = self.blocks.lock().await;
-        let _ = self.add_blob(&blocks, blob, Default::default())?;
+        let _ = self.submit(blob, Default::default(), 1.0).await?;
         Ok(MockHash([0; 32]))
     }
 
@@ -410,10 +511,28 @@ impl DaService for MockDaService {
         tx
     }
 
-    async fn send_aggregated_zk_proof(&self, proof: &[u8]) -> Result<u64, Self::Error> {
-        let blocks = self.blocks.lock().await;
+    async fn get_transaction_status(
+        &self,
+        _tx_id: &Self::TransactionId,
+    ) -> Result<TxStatus, Self::Error> {
+        // Blobs are added to a block when they are sent
+        Ok(TxStatus::Confirmed(1))
+    }
 
-        self.add_blob(&blocks, Default::default(), proof.to_vec())
+    async fn replace_transaction(
+        &self,
+        blob: &[u8],
+        fee_bump: FeeBump<Self::TransactionId>,
+    ) -> Result<Self::TransactionId, Self::Error> {
+        let fee_rate_multiplier = 1.0 + fee_bump.fee_bump_percent as f64 / 100.0;
+        let _ = self
+            .submit(blob, Default::default(), fee_rate_multiplier)
+            .await?;
+        Ok(MockHash([0; 32]))
+    }
+
+    async fn send_aggregated_zk_proof(&self, proof: &[u8]) -> Result<u64, Self::Error> {
+        self.submit(Default::default(), proof.to_vec(), 1.0).await
     }
 
     async fn get_aggregated_proofs_at(&self, height: u64) -> Result<Vec<Vec<u8>>, Self::Error> {
@@ -422,8 +541,15 @@ impl DaService for MockDaService {
     }
 
     async fn get_fee_rate(&self) -> Result<u128, Self::Error> {
-        // Mock constant
-        Ok(10_u128)
+        let next_height = self.blocks.lock().await.len() as u64 + 1;
+        Ok(self
+            .fee_model
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or(DEFAULT_FEE_RATE, |fee_model| {
+                fee_model.estimate_at(next_height)
+            }))
     }
 
     async fn get_block_by_hash(&self, hash: [u8; 32]) -> Result<Self::FilteredBlock, Self::Error> {
@@ -710,9 +836,39 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_fee_model() {
+        let db_path = tempfile::tempdir().unwrap();
+        let da = MockDaService::new(MockAddress::new([1; 32]), db_path.path());
+        assert_eq!(da.get_fee_rate().await.unwrap(), 10);
+
+        da.set_fee_model(MockFeeModel {
+            fee_rates: vec![5, 20, 8],
+            max_fee_rate: 10,
+        });
+        assert_eq!(da.get_fee_rate().await.unwrap(), 5);
+        da.send_transaction(&[1; 4]).await.unwrap();
+
+        // The floor of block 2 is above the highest fee rate paid
+        assert_eq!(da.get_fee_rate().await.unwrap(), 10);
+        assert!(da.send_transaction(&[2; 4]).await.is_err());
+        assert_eq!(da.get_height().await, 1);
+
+        // A fee bump pays enough
+        let fee_bump = FeeBump {
+            replaced_tx: MockHash([0; 32]),
+            fee_bump_percent: 100,
+        };
+        da.replace_transaction(&[2; 4], fee_bump).await.unwrap();
+        assert_eq!(da.get_height().await, 2);
+
+        assert_eq!(da.get_fee_rate().await.unwrap(), 8);
+        da.send_transaction(&[3; 4]).await.unwrap();
+    }
+
     mod reo4g_control {
         use super::*;
-        use crate::{MockAddress, MockDaService};
+        use crate::{FaultInjection, MockAddress, MockDaService};
 
         #[tokio::test]
         async fn test_reorg_control_success() {
@@ -878,6 +1034,51 @@ mod tests {
             //     block_5.unwrap_err().to_string()
             // );
         }
+
+        #[tokio::test]
+        async fn test_injected_faults() {
+            let db_path = tempfile::tempdir().unwrap();
+            let da = MockDaService::with_finality(MockAddress::new([1; 32]), 3, db_path.path());
+            da.set_fault_injection(FaultInjection {
+                seed: 7,
+                failure_probability: 0.2,
+                duplicate_probability: 0.2,
+                reorg_probability: 0.2,
+                max_reorg_depth: 5,
+                ..Default::default()
+            });
+
+            let mut sent = vec![];
+            let mut failures = 0;
+            for i in 0..50u8 {
+                match da.send_transaction(&[i; 4]).await {
+                    Ok(_) => sent.push(vec![i; 4]),
+                    Err(_) => failures += 1,
+                }
+            }
+            assert!(failures > 0);
+
+            // The chain is consistent and every sent blob is included at least once
+            let height = da.get_height().await;
+            let mut included = vec![];
+            let mut previous: Option<MockBlock> = None;
+            for height in 1..=height {
+                let block = da.get_block_at(height).await.unwrap();
+                if let Some(previous) = &previous {
+                    assert_consecutive_blocks(previous, &block);
+                }
+                included.extend(
+                    block
+                        .blobs
+                        .iter()
+                        .map(|blob| blob.clone().full_data().to_vec()),
+                );
+                previous = Some(block);
+            }
+            // Duplicates and the empty blocks of reorgs make the chain longer
+            assert!(height > sent.len() as u64);
+            assert!(sent.iter().all(|blob| included.contains(blob)));
+        }
     }
 
     fn assert_consecutive_blocks(block1: &MockBlock, block2: &MockBlock) {
@@ -1,6 +1,8 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
-use sov_rollup_interface::da::{BlobReaderTrait, DaSpec, DaVerifier};
+use sov_rollup_interface::da::{
+    BlobReaderTrait, BlockHeaderTrait, DaChainState, DaSpec, DaVerifier,
+};
 
 use crate::{MockAddress, MockBlob, MockBlockHeader, MockDaVerifier, MockHash, MockValidityCond};
 
@@ -23,6 +25,12 @@ impl BlobReaderTrait for MockBlob {
         self.data.total_len()
     }
 
+    #[cfg(feature = "native")]
+    fn da_tx_id(&self) -> Option<[u8; 32]> {
+        // Mock DA has no transactions, a blob is identified by its hash
+        Some(self.hash)
+    }
+
     #[cfg(feature = "native")]
     fn advance(&mut self, num_bytes: usize) -> &[u8] {
         self.data.advance(num_bytes);
@@ -63,4 +71,141 @@ impl DaVerifier for MockDaVerifier {
     ) -> Result<<Self::Spec as DaSpec>::ValidityCondition, Self::Error> {
         Ok(Default::default())
     }
+
+    fn verify_headers(
+        &self,
+        chain_state: &DaChainState,
+        block_headers: &[<Self::Spec as DaSpec>::BlockHeader],
+    ) -> Result<DaChainState, Self::Error> {
+        let mut chain_state = chain_state.clone();
+        if let Some(header) = block_headers.last() {
+            chain_state.tip_time = header.time().secs() as u64;
+        }
+        Ok(chain_state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sov_rollup_interface::zk::{LightClientData, SlotBlobs, StateTransition};
+
+    use super::*;
+
+    fn state_transition(
+        da_height: u64,
+        initial: u8,
+        last: u8,
+    ) -> StateTransition<MockDaSpec, [u8; 1]> {
+        StateTransition {
+            initial_state_root: [initial],
+            final_state_root: [last],
+            initial_batch_hash: [0; 32],
+            state_diff: Default::default(),
+            da_slot_hash: MockBlockHeader::from_height(da_height).hash,
+            sequencer_commitments_range: (0, 0),
+            withdrawal_roots: vec![],
+            sequencer_public_key: vec![],
+            sequencer_da_public_key: vec![],
+            validity_condition: Default::default(),
+        }
+    }
+
+    fn empty_blobs(headers: &[MockBlockHeader]) -> Vec<SlotBlobs<MockDaSpec>> {
+        headers
+            .iter()
+            .map(|_| SlotBlobs {
+                blobs: vec![],
+                inclusion_proof: [0; 32],
+                completeness_proof: (),
+            })
+            .collect()
+    }
+
+    fn chain_state(tip_time: u64) -> DaChainState {
+        DaChainState {
+            tip_time,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn light_client_output_follows_header_chain() {
+        let headers: Vec<MockBlockHeader> = (10..=12).map(MockBlockHeader::from_height).collect();
+        let mut first = LightClientData::<MockDaSpec, [u8; 1]> {
+            light_client_code_commitment: vec![1],
+            batch_proof_code_commitment: vec![2],
+            previous_output: None,
+            initial_state_root: [0],
+            initial_da_hash: MockBlockHeader::from_height(9).hash.into(),
+            initial_da_height: 9,
+            initial_chain_state: chain_state(9),
+            recent_da_hashes: vec![],
+            da_blobs: empty_blobs(&headers),
+            da_block_headers: headers,
+            state_transitions: vec![state_transition(10, 0, 1), state_transition(12, 1, 2)],
+        };
+        assert_eq!(first.chain_state(), &chain_state(9));
+        let (output, recent_da_hashes) = first.output::<sha2::Sha256>(chain_state(12)).unwrap();
+        assert_eq!(output.l2_state_root, [2]);
+        assert_eq!(output.latest_da_height, 12);
+        assert_eq!(output.initial_chain_state, chain_state(9));
+        assert_eq!(output.chain_state, chain_state(12));
+        assert_eq!(recent_da_hashes.len(), 3);
+
+        // the first header must follow the anchor
+        first.initial_da_height = 8;
+        assert!(first.output::<sha2::Sha256>(chain_state(12)).is_err());
+        first.initial_da_height = 9;
+        // and the blobs of every header are given
+        first.da_blobs.pop();
+        assert!(first.output::<sha2::Sha256>(chain_state(12)).is_err());
+
+        let next = |headers: Vec<MockBlockHeader>, state_transitions| LightClientData {
+            light_client_code_commitment: vec![1],
+            batch_proof_code_commitment: vec![2],
+            previous_output: Some(output.clone()),
+            initial_state_root: [0],
+            initial_da_hash: first.initial_da_hash,
+            initial_da_height: 9,
+            initial_chain_state: chain_state(9),
+            recent_da_hashes: recent_da_hashes.clone(),
+            da_blobs: empty_blobs(&headers),
+            da_block_headers: headers,
+            state_transitions,
+        };
+        // later proofs continue from the chain state of the previous one
+        assert_eq!(
+            next(vec![MockBlockHeader::from_height(13)], vec![]).chain_state(),
+            &chain_state(12)
+        );
+
+        // proofs of earlier DA blocks are accepted, if they continue the proven state
+        let (output, _) = next(
+            vec![MockBlockHeader::from_height(13)],
+            vec![state_transition(11, 2, 3)],
+        )
+        .output::<sha2::Sha256>(chain_state(13))
+        .unwrap();
+        assert_eq!(output.l2_state_root, [3]);
+        assert_eq!(output.latest_da_height, 13);
+
+        // headers must follow the latest verified block
+        assert!(next(vec![MockBlockHeader::from_height(14)], vec![])
+            .output::<sha2::Sha256>(chain_state(13))
+            .is_err());
+        // state transitions must continue the latest proven state root
+        assert!(next(
+            vec![MockBlockHeader::from_height(13)],
+            vec![state_transition(13, 1, 3)]
+        )
+        .output::<sha2::Sha256>(chain_state(13))
+        .is_err());
+        // and the DA block of the proven commitments must be verified
+        assert!(next(
+            vec![MockBlockHeader::from_height(13)],
+            vec![state_transition(20, 2, 3)]
+        )
+        .output::<sha2::Sha256>(chain_state(13))
+        .is_err());
+    }
 }
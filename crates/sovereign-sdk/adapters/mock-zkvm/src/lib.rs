@@ -9,12 +9,20 @@ use anyhow::ensure;
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use sov_rollup_interface::da::BlockHeaderTrait;
-use sov_rollup_interface::zk::{Matches, StateTransitionData, ValidityCondition};
+use sov_rollup_interface::zk::{
+    AggregatedStateTransition, Matches, StateTransitionData, ValidityCondition,
+};
 
 /// A mock commitment to a particular zkVM program.
 #[derive(Debug, Clone, PartialEq, Eq, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 pub struct MockCodeCommitment(pub [u8; 32]);
 
+impl AsRef<[u8]> for MockCodeCommitment {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
 impl Matches<MockCodeCommitment> for MockCodeCommitment {
     fn matches(&self, other: &MockCodeCommitment) -> bool {
         self.0 == other.0
@@ -145,6 +153,17 @@ impl<ValidityCond: ValidityCondition> sov_rollup_interface::zk::Zkvm for MockZkv
         let output = Self::verify(serialized_proof, code_commitment)?;
         Ok(bincode::deserialize(output)?)
     }
+
+    fn verify_and_extract_aggregated_output<
+        Da: sov_rollup_interface::da::DaSpec,
+        Root: BorshDeserialize,
+    >(
+        serialized_proof: &[u8],
+        code_commitment: &Self::CodeCommitment,
+    ) -> Result<AggregatedStateTransition<Da, Root>, Self::Error> {
+        let output = Self::verify(serialized_proof, code_commitment)?;
+        Ok(BorshDeserialize::try_from_slice(output)?)
+    }
 }
 
 impl<ValidityCond: ValidityCondition> sov_rollup_interface::zk::ZkvmHost
@@ -192,6 +211,7 @@ impl<ValidityCond: ValidityCondition> sov_rollup_interface::zk::ZkvmHost
                     sequencer_public_key: vec![],
                     sequencer_da_public_key: vec![],
                     sequencer_commitments_range: (0, 0),
+                    withdrawal_roots: vec![],
                 })
             }
             sov_rollup_interface::zk::Proof::Full(_) => {
@@ -199,6 +219,19 @@ impl<ValidityCond: ValidityCondition> sov_rollup_interface::zk::ZkvmHost
             }
         }
     }
+
+    fn add_assumption(
+        &mut self,
+        _proof: sov_rollup_interface::zk::Proof,
+    ) -> Result<(), anyhow::Error> {
+        anyhow::bail!("Mock zkVM doesn't aggregate proofs")
+    }
+
+    fn extract_aggregated_output<Da: sov_rollup_interface::da::DaSpec, Root: BorshDeserialize>(
+        _proof: &sov_rollup_interface::zk::Proof,
+    ) -> Result<AggregatedStateTransition<Da, Root>, Self::Error> {
+        anyhow::bail!("Mock zkVM doesn't aggregate proofs")
+    }
 }
 
 /// A mock implementing the Guest.
@@ -225,6 +258,16 @@ impl sov_rollup_interface::zk::Zkvm for MockZkGuest {
     ) -> Result<sov_rollup_interface::zk::StateTransition<Da, Root>, Self::Error> {
         unimplemented!()
     }
+
+    fn verify_and_extract_aggregated_output<
+        Da: sov_rollup_interface::da::DaSpec,
+        Root: BorshDeserialize,
+    >(
+        _serialized_proof: &[u8],
+        _code_commitment: &Self::CodeCommitment,
+    ) -> Result<AggregatedStateTransition<Da, Root>, Self::Error> {
+        unimplemented!()
+    }
 }
 
 impl sov_rollup_interface::zk::ZkvmGuest for MockZkGuest {
@@ -235,6 +278,16 @@ impl sov_rollup_interface::zk::ZkvmGuest for MockZkGuest {
     fn commit<T: BorshSerialize>(&self, _item: &T) {
         unimplemented!()
     }
+
+    fn verify_assumption(&self, _code_commitment: &[u8], _output: &[u8]) {
+        unimplemented!()
+    }
+
+    fn proof_output(&self, serialized_proof: &[u8]) -> Option<Vec<u8>> {
+        MockProof::decode(serialized_proof)
+            .ok()
+            .map(|proof| proof.log)
+    }
 }
 
 #[derive(Debug, BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
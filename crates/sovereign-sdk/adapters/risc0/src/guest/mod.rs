@@ -3,9 +3,10 @@
 //!  for host(native) and guest(zkvm) part.
 //! The host implementation is used for tests only and brings no real value.
 
+use borsh::BorshDeserialize;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use sov_rollup_interface::zk::Zkvm;
+use sov_rollup_interface::zk::{AggregatedStateTransition, Zkvm, ZkvmGuest};
 
 use crate::Risc0MethodId;
 
@@ -23,6 +24,12 @@ pub use zkvm::Risc0Guest;
 
 // Here goes the common implementation:
 
+/// Journal of a serialized receipt, which is the public output of the proof
+fn receipt_journal(serialized_proof: &[u8]) -> Option<Vec<u8>> {
+    let receipt: risc0_zkvm::Receipt = bincode::deserialize(serialized_proof).ok()?;
+    Some(receipt.journal.bytes)
+}
+
 // This is a dummy impl because T: ZkvmGuest where T: Zkvm.
 impl Zkvm for Risc0Guest {
     type CodeCommitment = Risc0MethodId;
@@ -46,4 +53,17 @@ impl Zkvm for Risc0Guest {
     ) -> Result<sov_rollup_interface::zk::StateTransition<Da, Root>, Self::Error> {
         todo!()
     }
+
+    /// In the guest, `serialized_proof` is the output of the aggregated proof. It is verified
+    /// as an assumption, which is resolved with the proof the host adds for it.
+    fn verify_and_extract_aggregated_output<
+        Da: sov_rollup_interface::da::DaSpec,
+        Root: BorshDeserialize,
+    >(
+        serialized_proof: &[u8],
+        code_commitment: &Self::CodeCommitment,
+    ) -> Result<AggregatedStateTransition<Da, Root>, Self::Error> {
+        Risc0Guest::new().verify_assumption(code_commitment.as_ref(), serialized_proof);
+        Ok(BorshDeserialize::try_from_slice(serialized_proof)?)
+    }
 }
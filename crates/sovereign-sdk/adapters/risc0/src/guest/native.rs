@@ -23,7 +23,6 @@ impl Hints {
 #[derive(Default)]
 pub struct Risc0Guest {
     hints: std::sync::Mutex<Hints>,
-    // commits: std::sync::Mutex<Vec<u32>>,
 }
 
 impl Risc0Guest {
@@ -36,7 +35,6 @@ impl Risc0Guest {
     pub fn with_hints(hints: Vec<u32>) -> Self {
         Self {
             hints: std::sync::Mutex::new(Hints::with_hints(hints)),
-            // commits: Default::default(),
         }
     }
 }
@@ -62,9 +60,15 @@ impl ZkvmGuest for Risc0Guest {
     }
 
     fn commit<T: BorshSerialize>(&self, _item: &T) {
-        unimplemented!("commitment never used in a test code")
-        // self.commits.lock().unwrap().extend_from_slice(
-        //     &risc0_zkvm::serde::to_vec(item).expect("Serialization to vec is infallible"),
-        // );
+        // Natively, the guest programs return their output instead of committing it
+    }
+
+    fn verify_assumption(&self, _code_commitment: &[u8], _output: &[u8]) {
+        // Assumptions can only be verified inside the zkVM, they are resolved with the
+        // proofs added by the host when the guest is proven.
+    }
+
+    fn proof_output(&self, serialized_proof: &[u8]) -> Option<Vec<u8>> {
+        super::receipt_journal(serialized_proof)
     }
 }
@@ -36,4 +36,13 @@ impl ZkvmGuest for Risc0Guest {
         let mut journal = env::journal();
         journal.write_slice(&buf);
     }
+
+    fn verify_assumption(&self, code_commitment: &[u8], output: &[u8]) {
+        let image_id: [u32; 8] = bytemuck::pod_read_unaligned(code_commitment);
+        env::verify(image_id, output).expect("Failed to verify assumption");
+    }
+
+    fn proof_output(&self, serialized_proof: &[u8]) -> Option<Vec<u8>> {
+        super::receipt_journal(serialized_proof)
+    }
 }
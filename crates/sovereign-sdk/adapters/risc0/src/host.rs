@@ -1,10 +1,19 @@
 //! This module implements the [`ZkvmHost`] trait for the RISC0 VM.
 
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
 use borsh::{BorshDeserialize, BorshSerialize};
-use risc0_zkvm::{ExecutorEnvBuilder, ExecutorImpl, InnerReceipt, Journal, Receipt, Session};
+use risc0_zkvm::{
+    Bytes, ExecutorEnvBuilder, ExecutorImpl, InnerReceipt, Journal, Receipt, Session,
+};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use sov_rollup_interface::zk::{Proof, Zkvm, ZkvmHost};
+use sov_rollup_interface::zk::{
+    AggregatedStateTransition, CycleReport, PhaseCycles, Proof, Zkvm, ZkvmHost,
+};
+use sov_zk_cycle_utils::get_syscall_name;
 
 use crate::guest::Risc0Guest;
 use crate::Risc0MethodId;
@@ -15,6 +24,20 @@ use crate::Risc0MethodId;
 pub struct Risc0Host<'a> {
     env: Vec<u32>,
     elf: &'a [u8],
+    assumptions: Vec<Receipt>,
+    options: Risc0ProvingOptions,
+}
+
+/// Options of local proving.
+///
+/// The GPU is used for proving if the adapter is built with the `cuda` or `metal` feature.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Risc0ProvingOptions {
+    /// Log2 of the max. number of cycles of a segment.
+    /// Larger segments take fewer, but more memory hungry, proving rounds.
+    pub segment_limit_po2: Option<u32>,
+    /// Number of threads proving the segments. All cores are used if not set.
+    pub proving_threads: Option<usize>,
 }
 
 #[cfg(not(feature = "bench"))]
@@ -46,23 +69,79 @@ impl<'a> Risc0Host<'a> {
         Self {
             env: Default::default(),
             elf,
+            assumptions: Default::default(),
+            options: Default::default(),
         }
     }
 
+    /// Proves with the given options.
+    pub fn with_options(mut self, options: Risc0ProvingOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     /// Run a computation in the zkVM without generating a receipt.
     /// This creates the "Session" trace without invoking the heavy cryptographic machinery.
     pub fn run_without_proving(&mut self) -> anyhow::Result<Session> {
-        let env = add_benchmarking_callbacks(ExecutorEnvBuilder::default())
-            .write_slice(&self.env)
-            .build()
-            .unwrap();
+        self.execute(add_benchmarking_callbacks(ExecutorEnvBuilder::default()))
+    }
+
+    /// Run a computation in the zkVM without generating a receipt, and report its cycles.
+    /// The cycles of the functions annotated with the `cycle_tracker` macro are reported
+    /// as phases, which requires the guest to be built with the `bench` feature.
+    pub fn run_with_profiling(&mut self) -> anyhow::Result<(Session, CycleReport)> {
+        let phases = Arc::new(Mutex::new(BTreeMap::<String, (u64, u64)>::new()));
+
+        let mut env = ExecutorEnvBuilder::default();
+        let collected = phases.clone();
+        env.io_callback(get_syscall_name(), move |input: Bytes| {
+            let (name, cycles) = deserialize_cycle_metric(input)?;
+            let mut phases = collected.lock().expect("Lock was poisoned");
+            let (sum, calls) = phases.entry(name).or_default();
+            *sum += cycles;
+            *calls += 1;
+            Ok(Bytes::new())
+        });
+        let session = self.execute(env)?;
+
+        let phases = std::mem::take(&mut *phases.lock().expect("Lock was poisoned"));
+        let report = CycleReport {
+            user_cycles: session.user_cycles,
+            total_cycles: session.total_cycles,
+            segments: session.segments.len() as u64,
+            phases: phases
+                .into_iter()
+                .map(|(name, (cycles, calls))| PhaseCycles {
+                    name,
+                    cycles,
+                    calls,
+                })
+                .collect(),
+        };
+        Ok((session, report))
+    }
+
+    fn execute(&mut self, mut env: ExecutorEnvBuilder<'_>) -> anyhow::Result<Session> {
+        if let Some(segment_limit_po2) = self.options.segment_limit_po2 {
+            env.segment_limit_po2(segment_limit_po2);
+        }
+        for assumption in std::mem::take(&mut self.assumptions) {
+            env.add_assumption(assumption);
+        }
+        let env = env.write_slice(&self.env).build().unwrap();
         let mut executor = ExecutorImpl::from_elf(env, self.elf)?;
         executor.run()
     }
     /// Run a computation in the zkvm and generate a receipt.
     pub fn run(&mut self) -> anyhow::Result<Receipt> {
         let session = self.run_without_proving()?;
-        let prove_info = session.prove()?;
+        let prove_info = match self.options.proving_threads {
+            Some(proving_threads) => rayon::ThreadPoolBuilder::new()
+                .num_threads(proving_threads)
+                .build()?
+                .install(|| session.prove())?,
+            None => session.prove()?,
+        };
         Ok(prove_info.receipt)
     }
 }
@@ -103,23 +182,61 @@ impl<'a> ZkvmHost for Risc0Host<'a> {
         }
     }
 
+    fn profile(&mut self) -> Result<(Proof, CycleReport), anyhow::Error> {
+        let (session, report) = self.run_with_profiling()?;
+        let data = bincode::serialize(&session.journal.expect("Journal shouldn't be empty"))?;
+        Ok((Proof::PublicInput(data), report))
+    }
+
     fn extract_output<Da: sov_rollup_interface::da::DaSpec, Root: BorshDeserialize>(
         proof: &Proof,
     ) -> Result<sov_rollup_interface::zk::StateTransition<Da, Root>, Self::Error> {
-        let journal = match proof {
-            Proof::PublicInput(journal) => {
-                let journal: Journal = bincode::deserialize(journal)?;
-                journal
-            }
+        let journal = journal_of(proof)?;
+        Ok(BorshDeserialize::deserialize(&mut journal.bytes.as_ref())?)
+    }
+
+    fn add_assumption(&mut self, proof: Proof) -> Result<(), anyhow::Error> {
+        match proof {
+            Proof::PublicInput(_) => anyhow::bail!("Only full proofs can be verified by the guest"),
             Proof::Full(data) => {
-                let receipt: Receipt = bincode::deserialize(data)?;
-                receipt.journal
+                self.assumptions.push(bincode::deserialize(&data)?);
+                Ok(())
             }
-        };
+        }
+    }
+
+    fn extract_aggregated_output<Da: sov_rollup_interface::da::DaSpec, Root: BorshDeserialize>(
+        proof: &Proof,
+    ) -> Result<AggregatedStateTransition<Da, Root>, Self::Error> {
+        let journal = journal_of(proof)?;
         Ok(BorshDeserialize::deserialize(&mut journal.bytes.as_ref())?)
     }
 }
 
+/// Deserialize a `Bytes` into a null-separated `(String, u64)` tuple, the format the
+/// `cycle_tracker` macro sends the cycles of a function in.
+pub(crate) fn deserialize_cycle_metric(serialized: Bytes) -> Result<(String, u64), anyhow::Error> {
+    let null_pos = serialized
+        .iter()
+        .position(|&b| b == 0)
+        .context("Could not find separator in provided bytes")?;
+    let (string_bytes, size_bytes_with_null) = serialized.split_at(null_pos);
+    let size_bytes = &size_bytes_with_null[1..]; // Skip the null terminator
+    let string = String::from_utf8(string_bytes.to_vec())?;
+    let size = u64::from_ne_bytes(size_bytes.try_into()?); // Convert bytes back into usize
+    Ok((string, size))
+}
+
+fn journal_of(proof: &Proof) -> Result<Journal, anyhow::Error> {
+    Ok(match proof {
+        Proof::PublicInput(journal) => bincode::deserialize(journal)?,
+        Proof::Full(data) => {
+            let receipt: Receipt = bincode::deserialize(data)?;
+            receipt.journal
+        }
+    })
+}
+
 impl<'host> Zkvm for Risc0Host<'host> {
     type CodeCommitment = Risc0MethodId;
 
@@ -142,6 +259,17 @@ impl<'host> Zkvm for Risc0Host<'host> {
         let output = Self::verify(serialized_proof, code_commitment)?;
         Ok(risc0_zkvm::serde::from_slice(output)?)
     }
+
+    fn verify_and_extract_aggregated_output<
+        Da: sov_rollup_interface::da::DaSpec,
+        Root: BorshDeserialize,
+    >(
+        serialized_proof: &[u8],
+        code_commitment: &Self::CodeCommitment,
+    ) -> Result<AggregatedStateTransition<Da, Root>, Self::Error> {
+        let output = Self::verify(serialized_proof, code_commitment)?;
+        Ok(BorshDeserialize::try_from_slice(output)?)
+    }
 }
 
 /// A verifier for Risc0 proofs.
@@ -169,6 +297,17 @@ impl Zkvm for Risc0Verifier {
         let output = Self::verify(serialized_proof, code_commitment)?;
         Ok(risc0_zkvm::serde::from_slice(output)?)
     }
+
+    fn verify_and_extract_aggregated_output<
+        Da: sov_rollup_interface::da::DaSpec,
+        Root: BorshDeserialize,
+    >(
+        serialized_proof: &[u8],
+        code_commitment: &Self::CodeCommitment,
+    ) -> Result<AggregatedStateTransition<Da, Root>, Self::Error> {
+        let output = Self::verify(serialized_proof, code_commitment)?;
+        Ok(BorshDeserialize::try_from_slice(output)?)
+    }
 }
 
 fn verify_from_slice<'a>(
@@ -31,6 +31,12 @@ impl Risc0MethodId {
     }
 }
 
+impl AsRef<[u8]> for Risc0MethodId {
+    fn as_ref(&self) -> &[u8] {
+        bytemuck::cast_slice(&self.0)
+    }
+}
+
 impl Matches<Self> for Risc0MethodId {
     fn matches(&self, other: &Self) -> bool {
         self.0 == other.0
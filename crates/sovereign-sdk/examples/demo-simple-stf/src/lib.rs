@@ -3,7 +3,7 @@
 use std::marker::PhantomData;
 
 use sha2::Digest;
-use sov_rollup_interface::da::{BlobReaderTrait, DaSpec};
+use sov_rollup_interface::da::{BlobReaderTrait, DaSpec, SlotInbox};
 use sov_rollup_interface::soft_confirmation::SignedSoftConfirmationBatch;
 use sov_rollup_interface::stf::{BatchReceipt, SlotResult, StateTransitionFunction};
 use sov_rollup_interface::zk::{CumulativeStateDiff, ValidityCondition, Zkvm};
@@ -117,6 +117,7 @@ impl<Vm: Zkvm, Cond: ValidityCondition, Da: DaSpec> StateTransitionFunction<Vm,
         _pre_state: Self::PreState,
         _witness: Self::Witness,
         _slot_header: &<Da as DaSpec>::BlockHeader,
+        _slot_inbox: &SlotInbox,
         _validity_condition: &<Da as DaSpec>::ValidityCondition,
         _soft_batch: &mut SignedSoftConfirmationBatch,
     ) -> SlotResult<
@@ -140,6 +141,7 @@ impl<Vm: Zkvm, Cond: ValidityCondition, Da: DaSpec> StateTransitionFunction<Vm,
         _sequencer_commitments_range: (u32, u32),
         _witnesses: std::collections::VecDeque<Vec<Self::Witness>>,
         _slot_headers: std::collections::VecDeque<Vec<<Da as DaSpec>::BlockHeader>>,
+        _slot_inboxes: std::collections::VecDeque<Vec<SlotInbox>>,
         _validity_condition: &<Da as DaSpec>::ValidityCondition,
         _soft_batch: std::collections::VecDeque<Vec<SignedSoftConfirmationBatch>>,
     ) -> (Self::StateRoot, CumulativeStateDiff) {
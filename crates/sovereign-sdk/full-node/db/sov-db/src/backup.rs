@@ -0,0 +1,142 @@
+use std::path::{Component, Path, PathBuf};
+
+use tracing::info;
+
+use crate::ledger_db::LEDGER_DB_PATH_SUFFIX;
+use crate::native_db::NativeDB;
+use crate::state_db::StateDB;
+
+/// Directories of the databases inside the storage path, all of which are part of a backup.
+const BACKUP_DBS: [&str; 3] = [
+    LEDGER_DB_PATH_SUFFIX,
+    StateDB::<()>::DB_PATH_SUFFIX,
+    NativeDB::<()>::DB_PATH_SUFFIX,
+];
+
+/// Path of the backup with the given name in `backup_dir`.
+///
+/// The name must be a single path component, so backups are never written outside `backup_dir`.
+pub fn backup_path(backup_dir: &Path, name: &str) -> anyhow::Result<PathBuf> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(component)), None) if component == name => {
+            Ok(backup_dir.join(name))
+        }
+        _ => anyhow::bail!("Invalid backup name {:?}", name),
+    }
+}
+
+/// Restores the ledger, state and native dbs from `backup_path` into `storage_path`.
+///
+/// The backup must contain all databases and `storage_path` must not contain any of them,
+/// so an existing node is never overwritten. The backup itself is left untouched.
+pub fn restore_backup(backup_path: &Path, storage_path: &Path) -> anyhow::Result<()> {
+    for db in BACKUP_DBS {
+        // Every RocksDB directory has a CURRENT file pointing to its manifest
+        if !backup_path.join(db).join("CURRENT").is_file() {
+            anyhow::bail!(
+                "Backup at {} is incomplete: missing {} db",
+                backup_path.display(),
+                db
+            );
+        }
+        if storage_path.join(db).exists() {
+            anyhow::bail!(
+                "Cannot restore backup: {} db already exists in {}",
+                db,
+                storage_path.display()
+            );
+        }
+    }
+
+    std::fs::create_dir_all(storage_path)?;
+
+    for db in BACKUP_DBS {
+        // Copy into a temporary directory first, so a partially restored db is never opened
+        let restoring_path = storage_path.join(format!("{}.restoring", db));
+        if restoring_path.exists() {
+            std::fs::remove_dir_all(&restoring_path)?;
+        }
+        std::fs::create_dir(&restoring_path)?;
+
+        for entry in std::fs::read_dir(backup_path.join(db))? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                anyhow::bail!("Unexpected entry {} in backup", entry.path().display());
+            }
+            std::fs::copy(entry.path(), restoring_path.join(entry.file_name()))?;
+        }
+    }
+
+    for db in BACKUP_DBS {
+        std::fs::rename(
+            storage_path.join(format!("{}.restoring", db)),
+            storage_path.join(db),
+        )?;
+    }
+
+    info!(
+        "Restored backup from {} into {}",
+        backup_path.display(),
+        storage_path.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{backup_path, restore_backup};
+    use crate::ledger_db::{LedgerDB, NodeLedgerOps, SharedLedgerOps};
+    use crate::native_db::NativeDB;
+    use crate::schema::types::BatchNumber;
+    use crate::state_db::StateDB;
+
+    #[test]
+    fn restores_backup_into_empty_storage() {
+        let node_dir = tempfile::tempdir().unwrap();
+        let backup_dir = tempfile::tempdir().unwrap();
+        let backup_path = backup_dir.path().join("backup");
+
+        let ledger_db = LedgerDB::with_path(node_dir.path()).unwrap();
+        ledger_db.set_last_proven_l2_height(BatchNumber(5)).unwrap();
+        let state_db =
+            StateDB::<()>::setup_schema_db(node_dir.path(), &Default::default()).unwrap();
+        let native_db =
+            NativeDB::<()>::setup_schema_db(node_dir.path(), &Default::default()).unwrap();
+
+        ledger_db.create_checkpoint(&backup_path).unwrap();
+        state_db
+            .create_checkpoint(backup_path.join(StateDB::<()>::DB_PATH_SUFFIX))
+            .unwrap();
+        native_db
+            .create_checkpoint(backup_path.join(NativeDB::<()>::DB_PATH_SUFFIX))
+            .unwrap();
+
+        // Refuses to overwrite an existing node
+        assert!(restore_backup(&backup_path, node_dir.path()).is_err());
+
+        let restored_dir = tempfile::tempdir().unwrap();
+        restore_backup(&backup_path, restored_dir.path()).unwrap();
+
+        let restored_ledger_db = LedgerDB::with_path(restored_dir.path()).unwrap();
+        assert_eq!(
+            restored_ledger_db.get_last_proven_l2_height().unwrap(),
+            Some(BatchNumber(5))
+        );
+    }
+
+    #[test]
+    fn backup_names_stay_in_backup_dir() {
+        let backup_dir = Path::new("/backups");
+        assert_eq!(
+            backup_path(backup_dir, "daily").unwrap(),
+            backup_dir.join("daily")
+        );
+        for name in ["", ".", "..", "../etc", "daily/..", "/etc", "a/b", "daily/"] {
+            assert!(backup_path(backup_dir, name).is_err(), "{}", name);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use sov_schema_db::SchemaBatch;
+use tracing::{info, warn};
+
+use super::{LedgerDB, NodeLedgerOps, SharedLedgerOps};
+use crate::schema::tables::{
+    EventByKey, EventByNumber, FullNodeSyncCheckpoint, L1FeeRateByL2Height, L2RangeByL1Height,
+    L2StateDiffs, L2Witness, LastPrunedL2Height, SoftBatchByHash, SoftBatchByNumber,
+    SoftConfirmationStatus, StateRootByL2Height, TraceCache, TxByHash, TxByNumber,
+};
+use crate::schema::types::{BatchNumber, EventNumber, SlotNumber, StoredSoftBatch, TxNumber};
+
+/// Result of checking the L2 blocks of the ledger db.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct L2IntegrityReport {
+    /// The first L2 height that was checked. Pruned heights are not checked.
+    pub checked_from: u64,
+    /// The latest L2 height in the ledger db
+    pub head_l2_height: u64,
+    /// The latest L2 height up to which the ledger db is consistent
+    pub last_consistent_l2_height: u64,
+    /// The inconsistency found at `last_consistent_l2_height + 1`, if any
+    pub issue: Option<String>,
+}
+
+impl L2IntegrityReport {
+    /// Returns true if no inconsistency was found
+    pub fn is_consistent(&self) -> bool {
+        self.issue.is_none()
+    }
+}
+
+impl LedgerDB {
+    /// Walks every unpruned L2 block and checks that its hash and state root indexes,
+    /// transactions, receipts and events are present and linked correctly.
+    /// `state_root_at` returns the state root of the given L2 height, or `None` if that state
+    /// is not available, and is compared against the state root of the soft confirmation.
+    /// Stops at the first inconsistent L2 height.
+    pub fn check_l2_integrity<F>(&self, state_root_at: F) -> anyhow::Result<L2IntegrityReport>
+    where
+        F: Fn(u64) -> anyhow::Result<Option<Vec<u8>>>,
+    {
+        let head_l2_height = self
+            .get_head_soft_batch()?
+            .map(|(l2_height, _)| l2_height.0)
+            .unwrap_or_default();
+        let checked_from = self
+            .db
+            .get::<LastPrunedL2Height>(&())?
+            .map(|l2_height| l2_height.0 + 1)
+            .unwrap_or(1);
+
+        let mut report = L2IntegrityReport {
+            checked_from,
+            head_l2_height,
+            last_consistent_l2_height: checked_from - 1,
+            issue: None,
+        };
+
+        let mut prev_soft_batch: Option<StoredSoftBatch> = None;
+        for l2_height in checked_from..=head_l2_height {
+            if let Err(issue) =
+                self.check_l2_block(l2_height, prev_soft_batch.as_ref(), &state_root_at)?
+            {
+                warn!(
+                    "Ledger db is inconsistent at L2 height {}: {}",
+                    l2_height, issue
+                );
+                report.issue = Some(issue);
+                return Ok(report);
+            }
+
+            prev_soft_batch = self.db.get::<SoftBatchByNumber>(&BatchNumber(l2_height))?;
+            report.last_consistent_l2_height = l2_height;
+
+            if l2_height % 10_000 == 0 {
+                info!("Checked ledger db up to L2 height {}", l2_height);
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Returns `Ok(Err(issue))` if the L2 block at `l2_height` is inconsistent.
+    fn check_l2_block<F>(
+        &self,
+        l2_height: u64,
+        prev_soft_batch: Option<&StoredSoftBatch>,
+        state_root_at: &F,
+    ) -> anyhow::Result<Result<(), String>>
+    where
+        F: Fn(u64) -> anyhow::Result<Option<Vec<u8>>>,
+    {
+        let Some(soft_batch) = self.db.get::<SoftBatchByNumber>(&BatchNumber(l2_height))? else {
+            return Ok(Err("soft confirmation is missing".to_owned()));
+        };
+        if soft_batch.l2_height != l2_height {
+            return Ok(Err(format!(
+                "soft confirmation is stored with L2 height {}",
+                soft_batch.l2_height
+            )));
+        }
+        if self.db.get::<SoftBatchByHash>(&soft_batch.hash)? != Some(BatchNumber(l2_height)) {
+            return Ok(Err(
+                "hash index does not point to the soft confirmation".to_owned()
+            ));
+        }
+        if self
+            .db
+            .get::<StateRootByL2Height>(&BatchNumber(l2_height))?
+            != Some(soft_batch.state_root.clone())
+        {
+            return Ok(Err(
+                "state root index does not match the soft confirmation".to_owned()
+            ));
+        }
+
+        if let Some(prev_soft_batch) = prev_soft_batch {
+            if soft_batch.prev_hash != prev_soft_batch.hash {
+                return Ok(Err("previous hash does not match the parent".to_owned()));
+            }
+            if soft_batch.tx_range.start != prev_soft_batch.tx_range.end {
+                return Ok(Err(
+                    "transaction range is not contiguous with the parent".to_owned()
+                ));
+            }
+        }
+
+        let tx_count = soft_batch.tx_range.end.0 - soft_batch.tx_range.start.0;
+        if tx_count != soft_batch.txs.len() as u64 {
+            return Ok(Err(format!(
+                "expected {} transactions, found {}",
+                tx_count,
+                soft_batch.txs.len()
+            )));
+        }
+
+        for (tx_number, tx) in (soft_batch.tx_range.start.0..).zip(&soft_batch.txs) {
+            let tx_number = TxNumber(tx_number);
+            match self.db.get::<TxByNumber>(&tx_number)? {
+                Some(stored_tx) if stored_tx == *tx => {}
+                Some(_) => {
+                    return Ok(Err(format!(
+                        "receipt of transaction {} does not match",
+                        tx_number.0
+                    )))
+                }
+                None => {
+                    return Ok(Err(format!(
+                        "receipt of transaction {} is missing",
+                        tx_number.0
+                    )))
+                }
+            }
+            if self.db.get::<TxByHash>(&tx.hash)? != Some(tx_number) {
+                return Ok(Err(format!(
+                    "hash index of transaction {} is missing",
+                    tx_number.0
+                )));
+            }
+            for event_number in tx.events.start.0..tx.events.end.0 {
+                if self
+                    .db
+                    .get::<EventByNumber>(&EventNumber(event_number))?
+                    .is_none()
+                {
+                    return Ok(Err(format!("event {} is missing", event_number)));
+                }
+            }
+        }
+
+        if let Some(state_root) = state_root_at(l2_height)? {
+            if state_root != soft_batch.state_root {
+                return Ok(Err(format!(
+                    "state root 0x{} does not match state 0x{}",
+                    hex::encode(&soft_batch.state_root),
+                    hex::encode(state_root)
+                )));
+            }
+        }
+
+        Ok(Ok(()))
+    }
+
+    /// Deletes every L2 block above `l2_height`, together with its transactions, events,
+    /// witness and cached traces, so the node resyncs them on the next start.
+    pub fn truncate_l2(&self, l2_height: BatchNumber) -> anyhow::Result<()> {
+        let Some((head_l2_height, _)) = self.get_head_soft_batch()? else {
+            return Ok(());
+        };
+        if head_l2_height <= l2_height {
+            return Ok(());
+        }
+
+        let mut schema_batch = SchemaBatch::new();
+
+        for soft_batch in
+            self.soft_batch_iter(BatchNumber(l2_height.0 + 1)..BatchNumber(head_l2_height.0 + 1))?
+        {
+            let soft_batch = soft_batch?;
+            for tx_number in soft_batch.tx_range.start.0..soft_batch.tx_range.end.0 {
+                let tx_number = TxNumber(tx_number);
+                if let Some(tx) = self.db.get::<TxByNumber>(&tx_number)? {
+                    for event_number in tx.events.start.0..tx.events.end.0 {
+                        let event_number = EventNumber(event_number);
+                        if let Some(event) = self.db.get::<EventByNumber>(&event_number)? {
+                            schema_batch.delete::<EventByKey>(&(
+                                event.key().clone(),
+                                tx_number,
+                                event_number,
+                            ))?;
+                        }
+                        schema_batch.delete::<EventByNumber>(&event_number)?;
+                    }
+                    schema_batch.delete::<TxByHash>(&tx.hash)?;
+                }
+                schema_batch.delete::<TxByNumber>(&tx_number)?;
+            }
+
+            let soft_batch_l2_height = BatchNumber(soft_batch.l2_height);
+            schema_batch.delete::<SoftBatchByNumber>(&soft_batch_l2_height)?;
+            schema_batch.delete::<SoftBatchByHash>(&soft_batch.hash)?;
+            schema_batch.delete::<StateRootByL2Height>(&soft_batch_l2_height)?;
+            schema_batch.delete::<L1FeeRateByL2Height>(&soft_batch_l2_height)?;
+            schema_batch.delete::<SoftConfirmationStatus>(&soft_batch_l2_height)?;
+            schema_batch.delete::<L2Witness>(&soft_batch_l2_height)?;
+            schema_batch.delete::<L2StateDiffs>(&soft_batch_l2_height)?;
+
+            let l1_height = SlotNumber(soft_batch.da_slot_height);
+            if let Some((start, end)) = self.db.get::<L2RangeByL1Height>(&l1_height)? {
+                if start > l2_height {
+                    schema_batch.delete::<L2RangeByL1Height>(&l1_height)?;
+                } else if end > l2_height {
+                    schema_batch.put::<L2RangeByL1Height>(&l1_height, &(start, l2_height))?;
+                }
+            }
+        }
+
+        // Traces of the deleted L2 blocks would be stale once they are resynced
+        let mut iter = self.db.iter::<TraceCache>()?;
+        iter.seek(&(BatchNumber(l2_height.0 + 1), [0; 32]))?;
+        for item in iter {
+            schema_batch.delete::<TraceCache>(&item?.key)?;
+        }
+
+        if let Some(mut checkpoint) = self.get_sync_checkpoint()? {
+            checkpoint.l2_height = checkpoint.l2_height.min(l2_height);
+            schema_batch.put::<FullNodeSyncCheckpoint>(&(), &checkpoint)?;
+        }
+
+        self.db.write_schemas(schema_batch)?;
+
+        let mut next_item_numbers = self.next_item_numbers.lock().unwrap();
+        next_item_numbers.soft_batch_number =
+            Self::last_version_written(&self.db, SoftBatchByNumber)?.unwrap_or_default() + 1;
+        next_item_numbers.tx_number =
+            Self::last_version_written(&self.db, TxByNumber)?.unwrap_or_default() + 1;
+        next_item_numbers.event_number =
+            Self::last_version_written(&self.db, EventByNumber)?.unwrap_or_default() + 1;
+        drop(next_item_numbers);
+
+        info!(
+            "Truncated ledger db from L2 height {} to {}",
+            head_l2_height.0, l2_height.0
+        );
+
+        Ok(())
+    }
+}
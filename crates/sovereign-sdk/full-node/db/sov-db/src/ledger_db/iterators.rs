@@ -0,0 +1,54 @@
+use std::iter::FusedIterator;
+
+use sov_schema_db::SchemaIterator;
+
+use crate::schema::tables::SoftBatchByNumber;
+use crate::schema::types::{BatchNumber, StoredSoftBatch};
+
+/// Streams the soft confirmations of an L2 height range in ascending order.
+/// They are read from a single DB iterator as they are consumed, instead of a lookup per L2 height.
+/// L2 heights which are pruned or not synced yet are skipped, so callers needing every
+/// L2 height of the range should check the heights of the soft confirmations.
+pub struct SoftBatchIter<'a> {
+    /// `None` once the end of the range is reached
+    iter: Option<SchemaIterator<'a, SoftBatchByNumber>>,
+    end: BatchNumber,
+}
+
+impl<'a> SoftBatchIter<'a> {
+    pub(crate) fn new(
+        mut iter: SchemaIterator<'a, SoftBatchByNumber>,
+        range: std::ops::Range<BatchNumber>,
+    ) -> anyhow::Result<Self> {
+        iter.seek(&range.start)?;
+        Ok(Self {
+            iter: Some(iter),
+            end: range.end,
+        })
+    }
+
+    pub(crate) fn empty() -> Self {
+        Self {
+            iter: None,
+            end: BatchNumber(0),
+        }
+    }
+}
+
+impl<'a> Iterator for SoftBatchIter<'a> {
+    type Item = anyhow::Result<StoredSoftBatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = match self.iter.as_mut()?.next() {
+            Some(Ok(item)) if item.key < self.end => Ok(item.value),
+            Some(Err(e)) => Err(e),
+            _ => {
+                self.iter = None;
+                return None;
+            }
+        };
+        Some(item)
+    }
+}
+
+impl<'a> FusedIterator for SoftBatchIter<'a> {}
@@ -0,0 +1,482 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use sov_schema_db::{Schema, SchemaBatch, DB};
+use tracing::{info, warn};
+
+use crate::schema::tables::{
+    CommitmentL1HeightByHash, CommitmentsByNumber, L1FeeRateByL2Height, LedgerSchemaVersion,
+    SlotByNumber, SoftBatchByNumber, StateRootByL2Height,
+};
+use crate::schema::types::sequencer_commitment_hash;
+
+/// The ledger db schema version this binary reads and writes.
+/// Bump it together with a new migration in `ledger_migrations` whenever a
+/// column family or encoding change is made.
+pub const LEDGER_SCHEMA_VERSION: u64 = 4;
+
+/// A single step in upgrading the ledger db schema.
+pub trait LedgerMigration {
+    /// Human readable name of the migration, used in logs.
+    fn name(&self) -> &'static str;
+    /// The schema version the ledger db is at after this migration is applied.
+    /// Migrations are applied to databases with a lower version, in ascending order.
+    fn version(&self) -> u64;
+    /// Applies the migration to the database.
+    fn execute(&self, db: &DB) -> anyhow::Result<()>;
+}
+
+/// All known ledger migrations, ordered by version.
+/// Version 1 is the first versioned schema and matches the unversioned layout,
+/// so databases created before versioning are upgraded to it without changes.
+pub(crate) fn ledger_migrations() -> Vec<Box<dyn LedgerMigration>> {
+    vec![
+        Box::new(IndexStateRoots),
+        Box::new(IndexCommitmentHashes),
+        Box::new(IndexL1FeeRates),
+    ]
+}
+
+/// Number of rows a migration writes at once, so large tables are not loaded into memory.
+const MIGRATION_BATCH_SIZE: usize = 10_000;
+
+/// Version 2: fills the state root index from the soft batches written before it existed.
+struct IndexStateRoots;
+
+impl LedgerMigration for IndexStateRoots {
+    fn name(&self) -> &'static str {
+        "index_state_roots"
+    }
+
+    fn version(&self) -> u64 {
+        2
+    }
+
+    fn execute(&self, db: &DB) -> anyhow::Result<()> {
+        let mut iter = db.iter::<SoftBatchByNumber>()?;
+        iter.seek_to_first();
+
+        let mut batch = SchemaBatch::new();
+        let mut batch_len = 0;
+        for item in iter {
+            let item = item?;
+            batch.put::<StateRootByL2Height>(&item.key, &item.value.state_root)?;
+            batch_len += 1;
+
+            if batch_len == MIGRATION_BATCH_SIZE {
+                db.write_schemas(std::mem::take(&mut batch))?;
+                batch_len = 0;
+            }
+        }
+        db.write_schemas(batch)?;
+
+        Ok(())
+    }
+}
+
+/// Version 3: fills the content hash index of the sequencer commitments stored before it existed.
+struct IndexCommitmentHashes;
+
+impl LedgerMigration for IndexCommitmentHashes {
+    fn name(&self) -> &'static str {
+        "index_commitment_hashes"
+    }
+
+    fn version(&self) -> u64 {
+        3
+    }
+
+    fn execute(&self, db: &DB) -> anyhow::Result<()> {
+        let mut iter = db.iter::<CommitmentsByNumber>()?;
+        iter.seek_to_first();
+
+        // Slots are iterated in ascending order, the first slot of a commitment is kept
+        let mut indexed = HashSet::new();
+        let mut batch = SchemaBatch::new();
+        let mut batch_len = 0;
+        for item in iter {
+            let item = item?;
+            for commitment in &item.value {
+                let hash = sequencer_commitment_hash(commitment);
+                if !indexed.insert(hash) {
+                    continue;
+                }
+                batch.put::<CommitmentL1HeightByHash>(&hash, &item.key)?;
+                batch_len += 1;
+
+                if batch_len == MIGRATION_BATCH_SIZE {
+                    db.write_schemas(std::mem::take(&mut batch))?;
+                    batch_len = 0;
+                }
+            }
+        }
+        db.write_schemas(batch)?;
+
+        Ok(())
+    }
+}
+
+/// Version 4: fills the L1 fee rate index from the soft batches written before it existed.
+/// Soft batches pruned before have no L1 fee rate.
+struct IndexL1FeeRates;
+
+impl LedgerMigration for IndexL1FeeRates {
+    fn name(&self) -> &'static str {
+        "index_l1_fee_rates"
+    }
+
+    fn version(&self) -> u64 {
+        4
+    }
+
+    fn execute(&self, db: &DB) -> anyhow::Result<()> {
+        let mut iter = db.iter::<SoftBatchByNumber>()?;
+        iter.seek_to_first();
+
+        let mut batch = SchemaBatch::new();
+        let mut batch_len = 0;
+        for item in iter {
+            let item = item?;
+            batch.put::<L1FeeRateByL2Height>(&item.key, &item.value.l1_fee_rate)?;
+            batch_len += 1;
+
+            if batch_len == MIGRATION_BATCH_SIZE {
+                db.write_schemas(std::mem::take(&mut batch))?;
+                batch_len = 0;
+            }
+        }
+        db.write_schemas(batch)?;
+
+        Ok(())
+    }
+}
+
+/// Brings the ledger db at `path` up to `target_version` by running the pending
+/// `migrations` in order.
+///
+/// A checkpoint of the database is taken before any migration runs. If a migration
+/// fails, the database is closed and restored from the checkpoint, so the node can be
+/// restarted with the previous binary.
+pub(crate) fn migrate(
+    db: DB,
+    path: &Path,
+    migrations: &[Box<dyn LedgerMigration>],
+    target_version: u64,
+) -> anyhow::Result<DB> {
+    debug_assert!(
+        migrations
+            .windows(2)
+            .all(|pair| pair[0].version() < pair[1].version()),
+        "Ledger migrations must be ordered by version"
+    );
+
+    let current_version = match db.get::<LedgerSchemaVersion>(&())? {
+        Some(version) => version,
+        // Nothing was ever written, so there is nothing to migrate
+        None if is_empty::<SlotByNumber>(&db)? && is_empty::<SoftBatchByNumber>(&db)? => {
+            db.put::<LedgerSchemaVersion>(&(), &target_version)?;
+            return Ok(db);
+        }
+        // Database was created before schema versioning was introduced
+        None => 0,
+    };
+
+    if current_version > target_version {
+        anyhow::bail!(
+            "Ledger db schema version {} is newer than the supported version {}. Please upgrade the node",
+            current_version,
+            target_version
+        );
+    }
+    if current_version == target_version {
+        return Ok(db);
+    }
+
+    let pending: Vec<_> = migrations
+        .iter()
+        .filter(|migration| {
+            migration.version() > current_version && migration.version() <= target_version
+        })
+        .collect();
+
+    if pending.is_empty() {
+        db.put::<LedgerSchemaVersion>(&(), &target_version)?;
+        return Ok(db);
+    }
+
+    let backup_path = backup_path(path, current_version);
+    if backup_path.exists() {
+        warn!(
+            "Removing stale ledger db backup at {}",
+            backup_path.display()
+        );
+        std::fs::remove_dir_all(&backup_path)?;
+    }
+    info!(
+        "Backing up ledger db to {} before migrating from schema version {} to {}",
+        backup_path.display(),
+        current_version,
+        target_version
+    );
+    db.create_checkpoint(&backup_path)?;
+
+    for migration in pending {
+        info!(
+            "Running ledger db migration {} (version {})",
+            migration.name(),
+            migration.version()
+        );
+        let result = migration
+            .execute(&db)
+            .and_then(|_| db.put::<LedgerSchemaVersion>(&(), &migration.version()));
+
+        if let Err(e) = result {
+            // Release the rocksdb lock before touching the files on disk
+            drop(db);
+            restore_backup(path, &backup_path)?;
+            return Err(e.context(format!(
+                "Ledger db migration {} failed, restored the database from backup",
+                migration.name()
+            )));
+        }
+    }
+
+    db.put::<LedgerSchemaVersion>(&(), &target_version)?;
+    std::fs::remove_dir_all(&backup_path)?;
+    info!("Ledger db migrated to schema version {}", target_version);
+
+    Ok(db)
+}
+
+fn is_empty<S: Schema>(db: &DB) -> anyhow::Result<bool> {
+    let mut iter = db.iter::<S>()?;
+    iter.seek_to_first();
+    Ok(iter.next().is_none())
+}
+
+fn backup_path(path: &Path, version: u64) -> PathBuf {
+    let mut file_name = path
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_default();
+    file_name.push(format!("-backup-v{}", version));
+    path.with_file_name(file_name)
+}
+
+fn restore_backup(path: &Path, backup_path: &Path) -> anyhow::Result<()> {
+    std::fs::remove_dir_all(path)?;
+    std::fs::rename(backup_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use sov_rollup_interface::da::SequencerCommitment;
+    use sov_schema_db::DB;
+
+    use super::{ledger_migrations, migrate, LedgerMigration};
+    use crate::rocks_db_config::gen_rocksdb_options;
+    use crate::schema::tables::{
+        CommitmentL1HeightByHash, CommitmentsByNumber, L1FeeRateByL2Height, LastProvenL2Height,
+        LedgerSchemaVersion, ProverLastScannedSlot, SoftBatchByNumber, StateRootByL2Height,
+        LEDGER_TABLES,
+    };
+    use crate::schema::types::{
+        sequencer_commitment_hash, BatchNumber, SlotNumber, StoredSoftBatch, TxNumber,
+    };
+
+    struct WriteProvenHeight;
+
+    impl LedgerMigration for WriteProvenHeight {
+        fn name(&self) -> &'static str {
+            "write_proven_height"
+        }
+
+        fn version(&self) -> u64 {
+            1
+        }
+
+        fn execute(&self, db: &DB) -> anyhow::Result<()> {
+            db.put::<LastProvenL2Height>(&(), &BatchNumber(7))
+        }
+    }
+
+    struct Failing;
+
+    impl LedgerMigration for Failing {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn version(&self) -> u64 {
+            2
+        }
+
+        fn execute(&self, db: &DB) -> anyhow::Result<()> {
+            db.put::<ProverLastScannedSlot>(&(), &SlotNumber(100))?;
+            anyhow::bail!("migration failed")
+        }
+    }
+
+    fn soft_batch(l2_height: u64) -> StoredSoftBatch {
+        StoredSoftBatch {
+            da_slot_height: 1,
+            l2_height,
+            da_slot_hash: [0; 32],
+            da_slot_txs_commitment: [0; 32],
+            hash: [l2_height as u8; 32],
+            prev_hash: [l2_height as u8 - 1; 32],
+            tx_range: TxNumber(1)..TxNumber(1),
+            txs: vec![],
+            deposit_data: vec![],
+            state_root: vec![l2_height as u8; 32],
+            soft_confirmation_signature: vec![],
+            pub_key: vec![],
+            l1_fee_rate: l2_height as u128 * 10,
+            timestamp: 0,
+        }
+    }
+
+    fn open(path: &Path) -> DB {
+        DB::open(
+            path,
+            "ledger-db",
+            LEDGER_TABLES.iter().copied(),
+            &gen_rocksdb_options(&Default::default(), false),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn fresh_db_is_stamped_with_target_version() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("ledger");
+
+        let migrations: Vec<Box<dyn LedgerMigration>> = vec![Box::new(WriteProvenHeight)];
+
+        let db = migrate(open(&path), &path, &migrations, 1).unwrap();
+
+        assert_eq!(db.get::<LedgerSchemaVersion>(&()).unwrap(), Some(1));
+        assert_eq!(db.get::<LastProvenL2Height>(&()).unwrap(), None);
+    }
+
+    #[test]
+    fn failed_migration_restores_backup() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("ledger");
+
+        let db = open(&path);
+        db.put::<LedgerSchemaVersion>(&(), &0u64).unwrap();
+        let migrations: Vec<Box<dyn LedgerMigration>> =
+            vec![Box::new(WriteProvenHeight), Box::new(Failing)];
+
+        assert!(migrate(db, &path, &migrations, 2).is_err());
+
+        let db = open(&path);
+        assert_eq!(db.get::<LedgerSchemaVersion>(&()).unwrap(), Some(0));
+        assert_eq!(db.get::<LastProvenL2Height>(&()).unwrap(), None);
+        assert_eq!(db.get::<ProverLastScannedSlot>(&()).unwrap(), None);
+
+        let db = migrate(db, &path, &migrations[..1], 1).unwrap();
+        assert_eq!(db.get::<LedgerSchemaVersion>(&()).unwrap(), Some(1));
+        assert_eq!(
+            db.get::<LastProvenL2Height>(&()).unwrap(),
+            Some(BatchNumber(7))
+        );
+        assert!(!tmpdir.path().join("ledger-backup-v0").exists());
+    }
+
+    #[test]
+    fn state_roots_are_indexed() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("ledger");
+
+        let db = open(&path);
+        db.put::<LedgerSchemaVersion>(&(), &1u64).unwrap();
+        for l2_height in 1..=3 {
+            db.put::<SoftBatchByNumber>(&BatchNumber(l2_height), &soft_batch(l2_height))
+                .unwrap();
+        }
+
+        let db = migrate(db, &path, &ledger_migrations(), 2).unwrap();
+
+        assert_eq!(db.get::<LedgerSchemaVersion>(&()).unwrap(), Some(2));
+        for l2_height in 1..=3 {
+            assert_eq!(
+                db.get::<StateRootByL2Height>(&BatchNumber(l2_height))
+                    .unwrap(),
+                Some(vec![l2_height as u8; 32])
+            );
+        }
+    }
+
+    #[test]
+    fn commitment_hashes_are_indexed() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("ledger");
+
+        let commitment = |l2_start_block_number| SequencerCommitment {
+            merkle_root: [l2_start_block_number as u8; 32],
+            l2_start_block_number,
+            l2_end_block_number: l2_start_block_number + 9,
+        };
+
+        let db = open(&path);
+        db.put::<LedgerSchemaVersion>(&(), &2u64).unwrap();
+        db.put::<CommitmentsByNumber>(&SlotNumber(5), &vec![commitment(1), commitment(11)])
+            .unwrap();
+        // The first commitment is posted again
+        db.put::<CommitmentsByNumber>(&SlotNumber(6), &vec![commitment(1), commitment(21)])
+            .unwrap();
+
+        let db = migrate(db, &path, &ledger_migrations(), 3).unwrap();
+
+        assert_eq!(db.get::<LedgerSchemaVersion>(&()).unwrap(), Some(3));
+        for (l2_start_block_number, l1_height) in [(1, 5), (11, 5), (21, 6)] {
+            assert_eq!(
+                db.get::<CommitmentL1HeightByHash>(&sequencer_commitment_hash(&commitment(
+                    l2_start_block_number
+                )))
+                .unwrap(),
+                Some(SlotNumber(l1_height))
+            );
+        }
+    }
+
+    #[test]
+    fn l1_fee_rates_are_indexed() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("ledger");
+
+        let db = open(&path);
+        db.put::<LedgerSchemaVersion>(&(), &3u64).unwrap();
+        for l2_height in 1..=3 {
+            db.put::<SoftBatchByNumber>(&BatchNumber(l2_height), &soft_batch(l2_height))
+                .unwrap();
+        }
+
+        let db = migrate(db, &path, &ledger_migrations(), 4).unwrap();
+
+        assert_eq!(db.get::<LedgerSchemaVersion>(&()).unwrap(), Some(4));
+        for l2_height in 1..=3 {
+            assert_eq!(
+                db.get::<L1FeeRateByL2Height>(&BatchNumber(l2_height))
+                    .unwrap(),
+                Some(l2_height as u128 * 10)
+            );
+        }
+    }
+
+    #[test]
+    fn newer_schema_is_rejected() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().join("ledger");
+
+        let db = open(&path);
+        db.put::<LedgerSchemaVersion>(&(), &2u64).unwrap();
+
+        assert!(migrate(db, &path, &[], 1).is_err());
+    }
+}
@@ -11,26 +11,42 @@ use sov_schema_db::{Schema, SchemaBatch, SeekKeyEncoder, DB};
 use tokio::sync::broadcast;
 use tracing::instrument;
 
-use crate::rocks_db_config::gen_rocksdb_options;
+use crate::rocks_db_config::{open_db, open_secondary_db, RocksdbConfig};
 use crate::schema::tables::{
-    BatchByHash, BatchByNumber, CommitmentsByNumber, EventByKey, EventByNumber, L2GenesisStateRoot,
-    L2RangeByL1Height, L2Witness, LastSequencerCommitmentSent, LastStateDiff,
-    PendingSequencerCommitmentL2Range, ProofBySlotNumber, ProverLastScannedSlot, SlotByHash,
-    SlotByNumber, SoftBatchByHash, SoftBatchByNumber, SoftConfirmationStatus, TxByHash, TxByNumber,
-    VerifiedProofsBySlotNumber, LEDGER_TABLES,
+    BatchByHash, BatchByNumber, ChallengeableCommitments, CommitmentByDaTxId,
+    CommitmentDaTxIdByL2Height, CommitmentL1HeightByHash, CommitmentsByNumber, CommitmentsHalt,
+    CycleReports, EventByKey, EventByNumber, FullNodeSyncCheckpoint, L1FeeRateByL2Height,
+    L2GenesisStateRoot, L2RangeByL1Height, L2StateDiffs, L2Witness, LastBodyPrunedL2Height,
+    LastProvenL2Height, LastPrunedL2Height, LastSequencerCommitmentSent, LastStateDiff,
+    LastVerifiedStateRoot, LedgerSchemaVersion, LightClientProofs, MempoolTxs,
+    PendingSequencerCommitmentL2Range, ProofBySlotNumber, ProofDaTxIdByCommitmentL1Height,
+    ProverLastScannedSlot, ProvingJobs, RelayQuotas, ReorgHalt, SequencerCommitmentCoverage,
+    SequencerLeaseState, SlotByHash, SlotByNumber, SoftBatchByHash, SoftBatchByNumber,
+    SoftConfirmationStatus, StateRootByL2Height, StateRootMismatch, TraceCache, TxByHash,
+    TxByNumber, VerifiedProofsBySlotNumber, LEDGER_TABLES,
 };
 use crate::schema::types::{
-    split_tx_for_storage, BatchNumber, EventNumber, L2HeightRange, SlotNumber, StoredBatch,
-    StoredProof, StoredSlot, StoredSoftBatch, StoredStateTransition, StoredTransaction,
-    StoredVerifiedProof, TxNumber,
+    sequencer_commitment_hash, split_tx_for_storage, BatchNumber, ChallengeableCommitment,
+    CommitmentCoverage, DbHash, EventNumber, L2HeightRange, RelayQuotaState, ReorgHaltReport,
+    SequencerLeaseCheckpoint, SlotNumber, StateRootMismatchReport, StoredBatch, StoredCycleReport,
+    StoredLightClientProof, StoredProof, StoredProvingJob, StoredSequencerCommitment, StoredSlot,
+    StoredSoftBatch, StoredStateTransition, StoredTransaction, StoredVerifiedProof, SyncCheckpoint,
+    TxNumber, VerifiedStateRoot,
 };
 
+mod integrity;
+mod iterators;
+mod migrations;
 mod rpc;
 mod traits;
 
+pub use integrity::L2IntegrityReport;
+pub use iterators::SoftBatchIter;
+pub use migrations::{LedgerMigration, LEDGER_SCHEMA_VERSION};
 pub use traits::*;
 
-const LEDGER_DB_PATH_SUFFIX: &str = "ledger";
+/// Directory of the ledger db inside the storage path
+pub const LEDGER_DB_PATH_SUFFIX: &str = "ledger";
 
 #[derive(Clone, Debug)]
 /// A database which stores the ledger history (slots, transactions, events, etc).
@@ -102,16 +118,69 @@ impl<S: SlotData, B, T> SlotCommit<S, B, T> {
 impl LedgerDB {
     /// Open a [`LedgerDB`] (backed by RocksDB) at the specified path.
     /// The returned instance will be at the path `{path}/ledger-db`.
+    /// Pending schema migrations are applied before the database is returned.
     #[instrument(level = "trace", skip_all, err)]
     pub fn with_path(path: impl AsRef<Path>) -> Result<Self, anyhow::Error> {
+        Self::with_config(path, &RocksdbConfig::default())
+    }
+
+    /// Open a [`LedgerDB`] at the specified path, tuned by the given [`RocksdbConfig`].
+    #[instrument(level = "trace", skip_all, err)]
+    pub fn with_config(
+        path: impl AsRef<Path>,
+        db_config: &RocksdbConfig,
+    ) -> Result<Self, anyhow::Error> {
         let path = path.as_ref().join(LEDGER_DB_PATH_SUFFIX);
-        let inner = DB::open(
-            path,
+        let inner = open_db(&path, "ledger-db", LEDGER_TABLES, LEDGER_TABLES, db_config)?;
+        let inner = migrations::migrate(
+            inner,
+            &path,
+            &migrations::ledger_migrations(),
+            LEDGER_SCHEMA_VERSION,
+        )?;
+
+        Self::with_db(inner)
+    }
+
+    /// Open a [`LedgerDB`] in secondary mode on top of the ledger db of a node running at `path`.
+    /// The secondary instance keeps its own files in `secondary_path` and never writes to
+    /// the primary's database. New data of the primary becomes visible after
+    /// [`LedgerDB::try_catch_up_with_primary`] is called.
+    /// Migrations are never run, so the primary must already be at [`LEDGER_SCHEMA_VERSION`].
+    #[instrument(level = "trace", skip_all, err)]
+    pub fn with_secondary(
+        path: impl AsRef<Path>,
+        secondary_path: impl AsRef<Path>,
+        db_config: &RocksdbConfig,
+    ) -> Result<Self, anyhow::Error> {
+        let inner = open_secondary_db(
+            path.as_ref().join(LEDGER_DB_PATH_SUFFIX),
+            secondary_path.as_ref().join(LEDGER_DB_PATH_SUFFIX),
             "ledger-db",
-            LEDGER_TABLES.iter().copied(),
-            &gen_rocksdb_options(&Default::default(), false),
+            LEDGER_TABLES,
+            LEDGER_TABLES,
+            db_config,
         )?;
 
+        let schema_version = inner.get::<LedgerSchemaVersion>(&())?;
+        if schema_version != Some(LEDGER_SCHEMA_VERSION) {
+            anyhow::bail!(
+                "Primary ledger db is at schema version {:?}, expected {}. Start the primary node with this version first",
+                schema_version,
+                LEDGER_SCHEMA_VERSION
+            );
+        }
+
+        Self::with_db(inner)
+    }
+
+    /// Replays the writes of the primary instance into a [`LedgerDB`] opened with
+    /// [`LedgerDB::with_secondary`].
+    pub fn try_catch_up_with_primary(&self) -> anyhow::Result<()> {
+        self.db.try_catch_up_with_primary()
+    }
+
+    fn with_db(inner: DB) -> Result<Self, anyhow::Error> {
         let next_item_numbers = ItemNumbers {
             slot_number: Self::last_version_written(&inner, SlotByNumber)?.unwrap_or_default() + 1,
             soft_batch_number: Self::last_version_written(&inner, SoftBatchByNumber)?
@@ -147,8 +216,12 @@ impl LedgerDB {
         let iter = raw_iter.take(max_items);
         let mut out = Vec::with_capacity(max_items);
         for res in iter {
-            let batch = res?.value;
-            out.push(batch)
+            let item = res?;
+            // Entries might be missing if the range has been pruned
+            if item.key.into() >= range.end.into() {
+                break;
+            }
+            out.push(item.value)
         }
         Ok(out)
     }
@@ -166,60 +239,15 @@ impl LedgerDB {
             _ => Ok(None),
         }
     }
-}
-
-impl SharedLedgerOps for LedgerDB {
-    #[instrument(level = "trace", skip(self, schema_batch), err, ret)]
-    fn put_soft_batch(
-        &self,
-        batch: &StoredSoftBatch,
-        batch_number: &BatchNumber,
-        schema_batch: &mut SchemaBatch,
-    ) -> Result<(), anyhow::Error> {
-        schema_batch.put::<SoftBatchByNumber>(batch_number, batch)?;
-        schema_batch.put::<SoftBatchByHash>(&batch.hash, batch_number)
-    }
-
-    #[instrument(level = "trace", skip(self, schema_batch), err, ret)]
-    fn put_batch(
-        &self,
-        batch: &StoredBatch,
-        batch_number: &BatchNumber,
-        schema_batch: &mut SchemaBatch,
-    ) -> Result<(), anyhow::Error> {
-        schema_batch.put::<BatchByNumber>(batch_number, batch)?;
-        schema_batch.put::<BatchByHash>(&batch.hash, batch_number)
-    }
-
-    #[instrument(level = "trace", skip(self, tx, schema_batch), err, ret)]
-    fn put_transaction(
-        &self,
-        tx: &StoredTransaction,
-        tx_number: &TxNumber,
-        schema_batch: &mut SchemaBatch,
-    ) -> Result<(), anyhow::Error> {
-        schema_batch.put::<TxByNumber>(tx_number, tx)?;
-        schema_batch.put::<TxByHash>(&tx.hash, tx_number)
-    }
-
-    #[instrument(level = "trace", skip_all, fields(event_number, tx_number), err, ret)]
-    fn put_event(
-        &self,
-        event: &Event,
-        event_number: &EventNumber,
-        tx_number: TxNumber,
-        schema_batch: &mut SchemaBatch,
-    ) -> Result<(), anyhow::Error> {
-        schema_batch.put::<EventByNumber>(event_number, event)?;
-        schema_batch.put::<EventByKey>(&(event.key().clone(), tx_number, *event_number), &())
-    }
 
-    /// Commits a soft batch to the database by inserting its transactions and batches before
-    fn commit_soft_batch<B: Serialize, T: Serialize, DS: DaSpec>(
+    /// Adds a soft batch with its transactions and events to the `schema_batch`.
+    /// Returns the number assigned to the soft batch.
+    fn put_soft_batch_receipt<B: Serialize, T: Serialize, DS: DaSpec>(
         &self,
         batch_receipt: SoftBatchReceipt<B, T, DS>,
         include_tx_body: bool,
-    ) -> Result<(), anyhow::Error> {
+        schema_batch: &mut SchemaBatch,
+    ) -> Result<BatchNumber, anyhow::Error> {
         let mut batch_receipt = batch_receipt;
 
         // Create a scope to ensure that the lock is released before we commit to the db
@@ -237,8 +265,6 @@ impl SharedLedgerOps for LedgerDB {
             // The lock is released here
         };
 
-        let mut schema_batch = SchemaBatch::new();
-
         let mut txs = Vec::with_capacity(batch_receipt.tx_receipts.len());
 
         let first_tx_number = current_item_numbers.tx_number;
@@ -252,7 +278,7 @@ impl SharedLedgerOps for LedgerDB {
                     &event,
                     &EventNumber(current_item_numbers.event_number),
                     TxNumber(current_item_numbers.tx_number),
-                    &mut schema_batch,
+                    schema_batch,
                 )?;
                 current_item_numbers.event_number += 1;
             }
@@ -267,7 +293,7 @@ impl SharedLedgerOps for LedgerDB {
             self.put_transaction(
                 &tx_to_store,
                 &TxNumber(current_item_numbers.tx_number),
-                &mut schema_batch,
+                schema_batch,
             )?;
             current_item_numbers.tx_number += 1;
             txs.push(tx_to_store);
@@ -293,21 +319,18 @@ impl SharedLedgerOps for LedgerDB {
         self.put_soft_batch(
             &batch_to_store,
             &BatchNumber(current_item_numbers.soft_batch_number),
-            &mut schema_batch,
+            schema_batch,
         )?;
-        current_item_numbers.soft_batch_number += 1;
-
-        self.db.write_schemas(schema_batch)?;
 
-        Ok(())
+        Ok(BatchNumber(current_item_numbers.soft_batch_number))
     }
 
-    /// Records the L2 height that was created as a soft confirmaiton of an L1 height
-    #[instrument(level = "trace", skip(self), err, ret)]
-    fn extend_l2_range_of_l1_slot(
+    /// Adds the extension of the L2 range of an L1 slot to the `schema_batch`.
+    fn put_l2_range_of_l1_slot(
         &self,
         l1_height: SlotNumber,
         l2_height: BatchNumber,
+        schema_batch: &mut SchemaBatch,
     ) -> Result<(), anyhow::Error> {
         let current_range = self.db.get::<L2RangeByL1Height>(&l1_height)?;
 
@@ -316,9 +339,83 @@ impl SharedLedgerOps for LedgerDB {
             None => (l2_height, l2_height),
         };
 
+        schema_batch.put::<L2RangeByL1Height>(&l1_height, &new_range)
+    }
+}
+
+impl SharedLedgerOps for LedgerDB {
+    #[instrument(level = "trace", skip(self, schema_batch), err, ret)]
+    fn put_soft_batch(
+        &self,
+        batch: &StoredSoftBatch,
+        batch_number: &BatchNumber,
+        schema_batch: &mut SchemaBatch,
+    ) -> Result<(), anyhow::Error> {
+        schema_batch.put::<SoftBatchByNumber>(batch_number, batch)?;
+        schema_batch.put::<StateRootByL2Height>(batch_number, &batch.state_root)?;
+        schema_batch.put::<L1FeeRateByL2Height>(batch_number, &batch.l1_fee_rate)?;
+        schema_batch.put::<SoftBatchByHash>(&batch.hash, batch_number)
+    }
+
+    #[instrument(level = "trace", skip(self, schema_batch), err, ret)]
+    fn put_batch(
+        &self,
+        batch: &StoredBatch,
+        batch_number: &BatchNumber,
+        schema_batch: &mut SchemaBatch,
+    ) -> Result<(), anyhow::Error> {
+        schema_batch.put::<BatchByNumber>(batch_number, batch)?;
+        schema_batch.put::<BatchByHash>(&batch.hash, batch_number)
+    }
+
+    #[instrument(level = "trace", skip(self, tx, schema_batch), err, ret)]
+    fn put_transaction(
+        &self,
+        tx: &StoredTransaction,
+        tx_number: &TxNumber,
+        schema_batch: &mut SchemaBatch,
+    ) -> Result<(), anyhow::Error> {
+        schema_batch.put::<TxByNumber>(tx_number, tx)?;
+        schema_batch.put::<TxByHash>(&tx.hash, tx_number)
+    }
+
+    #[instrument(level = "trace", skip_all, fields(event_number, tx_number), err, ret)]
+    fn put_event(
+        &self,
+        event: &Event,
+        event_number: &EventNumber,
+        tx_number: TxNumber,
+        schema_batch: &mut SchemaBatch,
+    ) -> Result<(), anyhow::Error> {
+        schema_batch.put::<EventByNumber>(event_number, event)?;
+        schema_batch.put::<EventByKey>(&(event.key().clone(), tx_number, *event_number), &())
+    }
+
+    /// Commits a soft batch to the database by inserting its transactions and batches before
+    fn commit_soft_batch<B: Serialize, T: Serialize, DS: DaSpec>(
+        &self,
+        batch_receipt: SoftBatchReceipt<B, T, DS>,
+        include_tx_body: bool,
+    ) -> Result<(), anyhow::Error> {
+        let mut schema_batch = SchemaBatch::new();
+
+        self.put_soft_batch_receipt(batch_receipt, include_tx_body, &mut schema_batch)?;
+
+        self.db.write_schemas(schema_batch)?;
+
+        Ok(())
+    }
+
+    /// Records the L2 height that was created as a soft confirmaiton of an L1 height
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn extend_l2_range_of_l1_slot(
+        &self,
+        l1_height: SlotNumber,
+        l2_height: BatchNumber,
+    ) -> Result<(), anyhow::Error> {
         let mut schema_batch = SchemaBatch::new();
 
-        schema_batch.put::<L2RangeByL1Height>(&l1_height, &new_range)?;
+        self.put_l2_range_of_l1_slot(l1_height, l2_height, &mut schema_batch)?;
         self.db.write_schemas(schema_batch)?;
 
         Ok(())
@@ -362,6 +459,35 @@ impl SharedLedgerOps for LedgerDB {
             .map(|diff| diff.unwrap_or_default())
     }
 
+    #[instrument(level = "trace", skip(self, state_diff), err)]
+    fn put_l2_state_diff(
+        &self,
+        l2_height: BatchNumber,
+        state_diff: &StateDiff,
+    ) -> anyhow::Result<()> {
+        self.db.put::<L2StateDiffs>(&l2_height, state_diff)
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_l2_state_diff(&self, l2_height: BatchNumber) -> anyhow::Result<Option<StateDiff>> {
+        self.db.get::<L2StateDiffs>(&l2_height)
+    }
+
+    #[instrument(level = "trace", skip(self), err)]
+    fn delete_l2_state_diffs(&self, up_to_l2_height: BatchNumber) -> anyhow::Result<()> {
+        let mut schema_batch = SchemaBatch::new();
+        let mut iter = self.db.iter::<L2StateDiffs>()?;
+        iter.seek_to_first();
+        for item in iter {
+            let l2_height = item?.key;
+            if l2_height > up_to_l2_height {
+                break;
+            }
+            schema_batch.delete::<L2StateDiffs>(&l2_height)?;
+        }
+        self.db.write_schemas(schema_batch)
+    }
+
     /// Sets l1 height of l1 hash
     #[instrument(level = "trace", skip(self), err, ret)]
     fn set_l1_height_of_l1_hash(&self, hash: [u8; 32], height: u64) -> anyhow::Result<()> {
@@ -392,20 +518,36 @@ impl SharedLedgerOps for LedgerDB {
         commitment: SequencerCommitment,
     ) -> anyhow::Result<()> {
         // get commitments
-        let commitments = self.db.get::<CommitmentsByNumber>(&SlotNumber(height))?;
+        let mut commitments = self
+            .db
+            .get::<CommitmentsByNumber>(&SlotNumber(height))?
+            .unwrap_or_default();
+        // The commitments of a da slot are processed again after a restart
+        if commitments.contains(&commitment) {
+            return Ok(());
+        }
 
-        match commitments {
-            // If there were other commitments, upsert
-            Some(mut commitments) => {
-                commitments.push(commitment);
-                self.db
-                    .put::<CommitmentsByNumber>(&SlotNumber(height), &commitments)
-            }
-            // Else insert
-            None => self
-                .db
-                .put::<CommitmentsByNumber>(&SlotNumber(height), &vec![commitment]),
+        let hash = sequencer_commitment_hash(&commitment);
+        commitments.push(commitment);
+
+        let mut schema_batch = SchemaBatch::new();
+        schema_batch.put::<CommitmentsByNumber>(&SlotNumber(height), &commitments)?;
+        if self.db.get::<CommitmentL1HeightByHash>(&hash)?.is_none() {
+            schema_batch.put::<CommitmentL1HeightByHash>(&hash, &SlotNumber(height))?;
         }
+        self.db.write_schemas(schema_batch)?;
+
+        Ok(())
+    }
+
+    /// Gets the L1 height the sequencer commitment was first stored at, by its content hash
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_l1_height_of_sequencer_commitment(
+        &self,
+        commitment: &SequencerCommitment,
+    ) -> anyhow::Result<Option<SlotNumber>> {
+        self.db
+            .get::<CommitmentL1HeightByHash>(&sequencer_commitment_hash(commitment))
     }
 
     /// Set the genesis state root
@@ -456,28 +598,117 @@ impl SharedLedgerOps for LedgerDB {
     ) -> Result<Option<StoredSoftBatch>, anyhow::Error> {
         self.db.get::<SoftBatchByNumber>(number)
     }
-}
 
-impl ProverLedgerOps for LedgerDB {
+    /// Creates a checkpoint of the ledger db inside `path`, laid out as in the storage path
+    #[instrument(level = "trace", skip(self), err)]
+    fn create_checkpoint(&self, path: &Path) -> anyhow::Result<()> {
+        self.db.create_checkpoint(path.join(LEDGER_DB_PATH_SUFFIX))
+    }
+
+    /// Gets a cached JSON encoded trace of a transaction of the L2 height
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_cached_trace(
+        &self,
+        l2_height: BatchNumber,
+        key: DbHash,
+    ) -> anyhow::Result<Option<Vec<u8>>> {
+        self.db.get::<TraceCache>(&(l2_height, key))
+    }
+
+    /// Caches a JSON encoded trace of a transaction of the L2 height
+    #[instrument(level = "trace", skip(self, trace), err)]
+    fn put_cached_trace(
+        &self,
+        l2_height: BatchNumber,
+        key: DbHash,
+        trace: &[u8],
+    ) -> anyhow::Result<()> {
+        self.db
+            .put::<TraceCache>(&(l2_height, key), &trace.to_vec())
+    }
+
+    /// Deletes cached traces
+    #[instrument(level = "trace", skip(self), err)]
+    fn delete_cached_traces(&self, entries: &[(BatchNumber, DbHash)]) -> anyhow::Result<()> {
+        let mut schema_batch = SchemaBatch::new();
+        for entry in entries {
+            schema_batch.delete::<TraceCache>(entry)?;
+        }
+        self.db.write_schemas(schema_batch)
+    }
+
+    /// Gets the keys of all cached traces
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_cached_trace_keys(&self) -> anyhow::Result<Vec<(BatchNumber, DbHash)>> {
+        let mut iter = self.db.iter::<TraceCache>()?;
+        iter.seek_to_first();
+
+        iter.map(|item| item.map(|item| item.key)).collect()
+    }
+
+    /// Gets the newest L2 height committed to DA, the `safe` block of the RPC
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_safe_l2_height(&self) -> anyhow::Result<Option<BatchNumber>> {
+        // Full nodes track the commitments they saw on DA, the sequencer the ones it sent
+        if let Some(coverage) = self.db.get::<SequencerCommitmentCoverage>(&())? {
+            return Ok(Some(coverage.contiguous_l2_height));
+        }
+        self.db.get::<LastSequencerCommitmentSent>(&())
+    }
+
+    /// Gets the newest L2 height covered by a verified proof, the `finalized` block of the RPC
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_finalized_l2_height(&self) -> anyhow::Result<Option<BatchNumber>> {
+        self.db.get::<LastProvenL2Height>(&())
+    }
+
     /// Get the state root by L2 height
     #[instrument(level = "trace", skip_all, err)]
     fn get_l2_state_root<StateRoot: DeserializeOwned>(
         &self,
         l2_height: u64,
     ) -> anyhow::Result<Option<StateRoot>> {
-        if l2_height == 0 {
-            self.db
-                .get::<L2GenesisStateRoot>(&())?
-                .map(|state_root| bincode::deserialize(&state_root).map_err(Into::into))
-                .transpose()
+        let state_root = if l2_height == 0 {
+            self.db.get::<L2GenesisStateRoot>(&())?
         } else {
             self.db
-                .get::<SoftBatchByNumber>(&BatchNumber(l2_height))?
-                .map(|soft_batch| bincode::deserialize(&soft_batch.state_root).map_err(Into::into))
-                .transpose()
+                .get::<StateRootByL2Height>(&BatchNumber(l2_height))?
+        };
+        state_root
+            .map(|state_root| bincode::deserialize(&state_root).map_err(Into::into))
+            .transpose()
+    }
+
+    /// Gets the L1 fee rates of the L2 heights in the range
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_l1_fee_rate_range(
+        &self,
+        range: &std::ops::Range<BatchNumber>,
+    ) -> anyhow::Result<Vec<(BatchNumber, u128)>> {
+        let mut iter = self.db.iter::<L1FeeRateByL2Height>()?;
+        iter.seek(&range.start)?;
+        let mut out = vec![];
+        for item in iter {
+            let item = item?;
+            if item.key >= range.end {
+                break;
+            }
+            out.push((item.key, item.value));
         }
+        Ok(out)
+    }
+
+    /// Gets the range of L2 heights created as soft confirmations of an L1 height
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_l2_range_by_l1_height(
+        &self,
+        l1_height: SlotNumber,
+    ) -> anyhow::Result<Option<L2HeightRange>> {
+        self.db.get::<L2RangeByL1Height>(&l1_height)
     }
+}
 
+impl ProverLedgerOps for LedgerDB {
     /// Get the last scanned slot by the prover
     #[instrument(level = "trace", skip(self), err, ret)]
     fn get_prover_last_scanned_l1_height(&self) -> anyhow::Result<Option<SlotNumber>> {
@@ -529,6 +760,12 @@ impl ProverLedgerOps for LedgerDB {
             .put::<ProofBySlotNumber>(&SlotNumber(l1_height), &data_to_store)
     }
 
+    /// Get the proof sent to DA for the sequencer commitments on the L1 height
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_proof_data(&self, l1_height: u64) -> anyhow::Result<Option<StoredProof>> {
+        self.db.get::<ProofBySlotNumber>(&SlotNumber(l1_height))
+    }
+
     /// Set the witness by L2 height
     #[instrument(level = "trace", skip_all, err, ret)]
     fn set_l2_witness<Witness: Serialize>(
@@ -544,33 +781,102 @@ impl ProverLedgerOps for LedgerDB {
 
         Ok(())
     }
-}
 
-impl SequencerLedgerOps for LedgerDB {
-    /// Put slots
-    #[instrument(level = "trace", skip(self, schema_batch), err, ret)]
-    fn put_slot(
-        &self,
-        slot: &StoredSlot,
-        slot_number: &SlotNumber,
-        schema_batch: &mut SchemaBatch,
-    ) -> Result<(), anyhow::Error> {
-        schema_batch.put::<SlotByNumber>(slot_number, slot)?;
-        schema_batch.put::<SlotByHash>(&slot.hash, slot_number)
+    /// Get the proving job of the sequencer commitments on the L1 height
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_proving_job(&self, l1_height: u64) -> anyhow::Result<Option<StoredProvingJob>> {
+        self.db.get::<ProvingJobs>(&SlotNumber(l1_height))
     }
 
-    /// Commits a slot to the database by inserting its events, transactions, and batches before
-    /// inserting the slot metadata.
-    #[instrument(level = "trace", skip_all, err, ret)]
-    fn commit_slot<S: SlotData, B: Serialize, T: Serialize>(
-        &self,
-        data_to_commit: SlotCommit<S, B, T>,
-    ) -> Result<(), anyhow::Error> {
-        // Create a scope to ensure that the lock is released before we commit to the db
-        let mut current_item_numbers = {
-            let mut next_item_numbers = self.next_item_numbers.lock().unwrap();
-            let item_numbers = next_item_numbers.clone();
-            next_item_numbers.slot_number += 1;
+    /// Get all proving jobs, in ascending L1 height order
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_proving_jobs(&self) -> anyhow::Result<Vec<(SlotNumber, StoredProvingJob)>> {
+        let mut iter = self.db.iter::<ProvingJobs>()?;
+        iter.seek_to_first();
+
+        iter.map(|item| item.map(|item| (item.key, item.value)))
+            .collect()
+    }
+
+    /// Put the proving job of the sequencer commitments on the L1 height
+    #[instrument(level = "trace", skip(self, job), err)]
+    fn put_proving_job(&self, l1_height: u64, job: &StoredProvingJob) -> anyhow::Result<()> {
+        self.db.put::<ProvingJobs>(&SlotNumber(l1_height), job)
+    }
+
+    /// Delete the proving job of the sequencer commitments on the L1 height
+    #[instrument(level = "trace", skip(self), err)]
+    fn delete_proving_job(&self, l1_height: u64) -> anyhow::Result<()> {
+        self.db.delete::<ProvingJobs>(&SlotNumber(l1_height))
+    }
+
+    /// Get the cycle report of the proof of the sequencer commitments on the L1 height
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_cycle_report(&self, l1_height: u64) -> anyhow::Result<Option<StoredCycleReport>> {
+        self.db.get::<CycleReports>(&SlotNumber(l1_height))
+    }
+
+    /// Put the cycle report of the proof of the sequencer commitments on the L1 height
+    #[instrument(level = "trace", skip(self, report), err)]
+    fn put_cycle_report(&self, l1_height: u64, report: &StoredCycleReport) -> anyhow::Result<()> {
+        self.db.put::<CycleReports>(&SlotNumber(l1_height), report)
+    }
+
+    /// Get the latest light client proof and the L1 height it verifies up to
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_latest_light_client_proof(
+        &self,
+    ) -> anyhow::Result<Option<(u64, StoredLightClientProof)>> {
+        let mut iter = self.db.iter::<LightClientProofs>()?;
+        iter.seek_to_last();
+
+        match iter.next() {
+            Some(Ok(item)) => {
+                let (l1_height, proof) = item.into_tuple();
+                Ok(Some((l1_height.0, proof)))
+            }
+            Some(Err(e)) => Err(e),
+            _ => Ok(None),
+        }
+    }
+
+    /// Put the light client proof which verifies up to the L1 height
+    #[instrument(level = "trace", skip(self, proof), err)]
+    fn put_light_client_proof(
+        &self,
+        l1_height: u64,
+        proof: &StoredLightClientProof,
+    ) -> anyhow::Result<()> {
+        self.db
+            .put::<LightClientProofs>(&SlotNumber(l1_height), proof)
+    }
+}
+
+impl SequencerLedgerOps for LedgerDB {
+    /// Put slots
+    #[instrument(level = "trace", skip(self, schema_batch), err, ret)]
+    fn put_slot(
+        &self,
+        slot: &StoredSlot,
+        slot_number: &SlotNumber,
+        schema_batch: &mut SchemaBatch,
+    ) -> Result<(), anyhow::Error> {
+        schema_batch.put::<SlotByNumber>(slot_number, slot)?;
+        schema_batch.put::<SlotByHash>(&slot.hash, slot_number)
+    }
+
+    /// Commits a slot to the database by inserting its events, transactions, and batches before
+    /// inserting the slot metadata.
+    #[instrument(level = "trace", skip_all, err, ret)]
+    fn commit_slot<S: SlotData, B: Serialize, T: Serialize>(
+        &self,
+        data_to_commit: SlotCommit<S, B, T>,
+    ) -> Result<(), anyhow::Error> {
+        // Create a scope to ensure that the lock is released before we commit to the db
+        let mut current_item_numbers = {
+            let mut next_item_numbers = self.next_item_numbers.lock().unwrap();
+            let item_numbers = next_item_numbers.clone();
+            next_item_numbers.slot_number += 1;
             next_item_numbers.batch_number += data_to_commit.batch_receipts.len() as u64;
             next_item_numbers.tx_number += data_to_commit.num_txs as u64;
             next_item_numbers.event_number += data_to_commit.num_events as u64;
@@ -719,9 +1025,131 @@ impl SequencerLedgerOps for LedgerDB {
             None => Ok(None),
         }
     }
+
+    /// Insert mempool transaction
+    #[instrument(level = "trace", skip(self, tx), err)]
+    fn insert_mempool_tx(&self, tx_hash: DbHash, tx: Vec<u8>) -> anyhow::Result<()> {
+        self.db.put::<MempoolTxs>(&tx_hash, &tx)
+    }
+
+    /// Fetch mempool transactions
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_mempool_txs(&self) -> anyhow::Result<Vec<(DbHash, Vec<u8>)>> {
+        let mut iter = self.db.iter::<MempoolTxs>()?;
+        iter.seek_to_first();
+
+        iter.map(|item| item.map(|item| (item.key, item.value)))
+            .collect()
+    }
+
+    /// Remove mempool transactions by their hashes
+    #[instrument(level = "trace", skip(self), err)]
+    fn remove_mempool_txs(&self, tx_hashes: Vec<DbHash>) -> anyhow::Result<()> {
+        let mut schema_batch = SchemaBatch::new();
+        for tx_hash in tx_hashes {
+            schema_batch.delete::<MempoolTxs>(&tx_hash)?;
+        }
+        self.db.write_schemas(schema_batch)
+    }
+
+    /// Gets the leases of the sequencer keys scanned so far
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_sequencer_lease_checkpoint(&self) -> anyhow::Result<Option<SequencerLeaseCheckpoint>> {
+        self.db.get::<SequencerLeaseState>(&())
+    }
+
+    /// Sets the leases of the sequencer keys scanned so far
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn set_sequencer_lease_checkpoint(
+        &self,
+        checkpoint: &SequencerLeaseCheckpoint,
+    ) -> anyhow::Result<()> {
+        self.db.put::<SequencerLeaseState>(&(), checkpoint)
+    }
+
+    /// Gets the meta-transactions relayed in the current quota window
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_relay_quotas(&self) -> anyhow::Result<Option<RelayQuotaState>> {
+        self.db.get::<RelayQuotas>(&())
+    }
+
+    /// Sets the meta-transactions relayed in the current quota window
+    #[instrument(level = "trace", skip_all, err)]
+    fn set_relay_quotas(&self, quotas: &RelayQuotaState) -> anyhow::Result<()> {
+        self.db.put::<RelayQuotas>(&(), quotas)
+    }
 }
 
 impl NodeLedgerOps for LedgerDB {
+    /// Streams the soft confirmations with L2 heights in the range, in ascending order
+    #[instrument(level = "trace", skip(self), err)]
+    fn soft_batch_iter(
+        &self,
+        range: std::ops::Range<BatchNumber>,
+    ) -> anyhow::Result<SoftBatchIter<'_>> {
+        SoftBatchIter::new(self.db.iter::<SoftBatchByNumber>()?, range)
+    }
+
+    /// Streams the soft confirmations created for the L1 slot of the given height, in ascending order
+    #[instrument(level = "trace", skip(self), err)]
+    fn soft_batch_iter_by_l1_height(
+        &self,
+        l1_height: SlotNumber,
+    ) -> anyhow::Result<SoftBatchIter<'_>> {
+        match self.db.get::<L2RangeByL1Height>(&l1_height)? {
+            Some((start, end)) => self.soft_batch_iter(start..BatchNumber(end.0 + 1)),
+            None => Ok(SoftBatchIter::empty()),
+        }
+    }
+
+    /// Stores the soft confirmation at the last pruned L2 height, below the oldest stored one
+    #[instrument(level = "trace", skip(self, soft_batch), err)]
+    fn backfill_soft_batch(&self, mut soft_batch: StoredSoftBatch) -> anyhow::Result<()> {
+        let l2_height = BatchNumber(soft_batch.l2_height);
+        if self.get_last_pruned_l2_height()? != Some(l2_height) {
+            anyhow::bail!("L2 height {} is not the next one to backfill", l2_height.0);
+        }
+        let next_soft_batch = self
+            .db
+            .get::<SoftBatchByNumber>(&BatchNumber(l2_height.0 + 1))?
+            .ok_or_else(|| anyhow::anyhow!("L2 height {} is not stored", l2_height.0 + 1))?;
+        if next_soft_batch.prev_hash != soft_batch.hash {
+            anyhow::bail!(
+                "Hash of L2 height {} is not the previous hash of L2 height {}",
+                l2_height.0,
+                l2_height.0 + 1
+            );
+        }
+
+        // Transaction numbers start at 1
+        let tx_range_end = next_soft_batch.tx_range.start;
+        let tx_range_start = tx_range_end
+            .0
+            .checked_sub(soft_batch.txs.len() as u64)
+            .filter(|tx_number| *tx_number >= 1)
+            .ok_or_else(|| {
+                anyhow::anyhow!("No transaction numbers left for L2 height {}", l2_height.0)
+            })?;
+        soft_batch.tx_range = TxNumber(tx_range_start)..tx_range_end;
+
+        let mut schema_batch = SchemaBatch::new();
+        for (tx_number, tx) in (tx_range_start..).zip(&soft_batch.txs) {
+            self.put_transaction(tx, &TxNumber(tx_number), &mut schema_batch)?;
+        }
+        self.put_soft_batch(&soft_batch, &l2_height, &mut schema_batch)?;
+        // Only proven L2 heights are pruned
+        schema_batch.put::<SoftConfirmationStatus>(
+            &l2_height,
+            &sov_rollup_interface::rpc::SoftConfirmationStatus::Proven,
+        )?;
+        if l2_height.0 > 1 {
+            schema_batch.put::<LastPrunedL2Height>(&(), &BatchNumber(l2_height.0 - 1))?;
+        } else {
+            schema_batch.delete::<LastPrunedL2Height>(&())?;
+        }
+        self.db.write_schemas(schema_batch)
+    }
+
     /// Stores proof related data on disk, accessible via l1 slot height
     #[instrument(level = "trace", skip(self, proof, state_transition), err, ret)]
     fn update_verified_proof_data(
@@ -793,4 +1221,1109 @@ impl NodeLedgerOps for LedgerDB {
     fn get_l1_height_of_l1_hash(&self, hash: [u8; 32]) -> Result<Option<u64>, anyhow::Error> {
         self.db.get::<SlotByHash>(&hash).map(|v| v.map(|a| a.0))
     }
+
+    /// Gets the soft confirmation status of the given L2 height, if recorded
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_l2_soft_confirmation_status(
+        &self,
+        l2_height: BatchNumber,
+    ) -> anyhow::Result<Option<sov_rollup_interface::rpc::SoftConfirmationStatus>> {
+        self.db.get::<SoftConfirmationStatus>(&l2_height)
+    }
+
+    /// Sets the highest L2 height covered by a verified proof
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn set_last_proven_l2_height(&self, l2_height: BatchNumber) -> anyhow::Result<()> {
+        self.db.put::<LastProvenL2Height>(&(), &l2_height)
+    }
+
+    /// Gets the highest L2 height covered by a verified proof
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_last_proven_l2_height(&self) -> anyhow::Result<Option<BatchNumber>> {
+        self.db.get::<LastProvenL2Height>(&())
+    }
+
+    /// Gets the last L2 height that was pruned
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_last_pruned_l2_height(&self) -> anyhow::Result<Option<BatchNumber>> {
+        self.db.get::<LastPrunedL2Height>(&())
+    }
+
+    /// Gets the last L2 height whose transaction bodies and witness were pruned
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_last_body_pruned_l2_height(&self) -> anyhow::Result<Option<BatchNumber>> {
+        self.db.get::<LastBodyPrunedL2Height>(&())
+    }
+
+    /// Deletes transaction bodies and witnesses of soft confirmations in the given range.
+    /// Soft confirmation headers, state roots, transaction hashes and events are kept.
+    #[instrument(level = "trace", skip(self), err)]
+    fn prune_l2_bodies(&self, range: &std::ops::Range<BatchNumber>) -> anyhow::Result<()> {
+        if range.start >= range.end {
+            return Ok(());
+        }
+
+        let mut schema_batch = SchemaBatch::new();
+
+        for mut soft_batch in self.get_soft_batch_range(range)? {
+            for (tx_number, tx) in
+                (soft_batch.tx_range.start.0..soft_batch.tx_range.end.0).zip(&mut soft_batch.txs)
+            {
+                if tx.body.take().is_some() {
+                    schema_batch.put::<TxByNumber>(&TxNumber(tx_number), &*tx)?;
+                }
+            }
+
+            let l2_height = BatchNumber(soft_batch.l2_height);
+            soft_batch.deposit_data = vec![];
+            schema_batch.put::<SoftBatchByNumber>(&l2_height, &soft_batch)?;
+            schema_batch.delete::<L2Witness>(&l2_height)?;
+        }
+
+        schema_batch.put::<LastBodyPrunedL2Height>(&(), &BatchNumber(range.end.0 - 1))?;
+        self.db.write_schemas(schema_batch)?;
+
+        Ok(())
+    }
+
+    /// Prunes events, transaction bodies and cached traces of soft confirmations in the given range.
+    /// If `keep_headers` is false, soft confirmations and transactions are deleted entirely.
+    #[instrument(level = "trace", skip(self), err)]
+    fn prune_l2_range(
+        &self,
+        range: &std::ops::Range<BatchNumber>,
+        keep_headers: bool,
+    ) -> anyhow::Result<()> {
+        if range.start >= range.end {
+            return Ok(());
+        }
+
+        let mut schema_batch = SchemaBatch::new();
+
+        for mut soft_batch in self.get_soft_batch_range(range)? {
+            for (tx_number, tx) in
+                (soft_batch.tx_range.start.0..soft_batch.tx_range.end.0).zip(&mut soft_batch.txs)
+            {
+                for event_number in tx.events.start.0..tx.events.end.0 {
+                    let event_number = EventNumber(event_number);
+                    if let Some(event) = self.db.get::<EventByNumber>(&event_number)? {
+                        schema_batch.delete::<EventByKey>(&(
+                            event.key().clone(),
+                            TxNumber(tx_number),
+                            event_number,
+                        ))?;
+                    }
+                    schema_batch.delete::<EventByNumber>(&event_number)?;
+                }
+
+                tx.body = None;
+                if keep_headers {
+                    schema_batch.put::<TxByNumber>(&TxNumber(tx_number), &*tx)?;
+                } else {
+                    schema_batch.delete::<TxByNumber>(&TxNumber(tx_number))?;
+                    schema_batch.delete::<TxByHash>(&tx.hash)?;
+                }
+            }
+
+            let l2_height = BatchNumber(soft_batch.l2_height);
+            if keep_headers {
+                soft_batch.deposit_data = vec![];
+                schema_batch.put::<SoftBatchByNumber>(&l2_height, &soft_batch)?;
+            } else {
+                schema_batch.delete::<SoftBatchByNumber>(&l2_height)?;
+                schema_batch.delete::<SoftBatchByHash>(&soft_batch.hash)?;
+                schema_batch.delete::<SoftConfirmationStatus>(&l2_height)?;
+            }
+        }
+
+        // Cached traces are deleted together with the events of their L2 blocks
+        let mut iter = self.db.iter::<TraceCache>()?;
+        iter.seek(&(range.start, [0; 32]))?;
+        for item in iter {
+            let key = item?.key;
+            if key.0 >= range.end {
+                break;
+            }
+            schema_batch.delete::<TraceCache>(&key)?;
+        }
+
+        schema_batch.put::<LastPrunedL2Height>(&(), &BatchNumber(range.end.0 - 1))?;
+        self.db.write_schemas(schema_batch)?;
+
+        Ok(())
+    }
+
+    /// Commits a soft batch together with the L2 range of its L1 slot and
+    /// the L2 height of the sync checkpoint in a single atomic write
+    fn commit_soft_batch_with_checkpoint<B: Serialize, T: Serialize, DS: DaSpec>(
+        &self,
+        batch_receipt: SoftBatchReceipt<B, T, DS>,
+        include_tx_body: bool,
+    ) -> anyhow::Result<()> {
+        let l1_height = SlotNumber(batch_receipt.da_slot_height);
+
+        let mut schema_batch = SchemaBatch::new();
+
+        let l2_height =
+            self.put_soft_batch_receipt(batch_receipt, include_tx_body, &mut schema_batch)?;
+        self.put_l2_range_of_l1_slot(l1_height, l2_height, &mut schema_batch)?;
+
+        let checkpoint = SyncCheckpoint {
+            l2_height,
+            ..self.get_sync_checkpoint()?.unwrap_or_default()
+        };
+        schema_batch.put::<FullNodeSyncCheckpoint>(&(), &checkpoint)?;
+
+        self.db.write_schemas(schema_batch)?;
+
+        Ok(())
+    }
+
+    /// Gets the sync checkpoint, if any
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_sync_checkpoint(&self) -> anyhow::Result<Option<SyncCheckpoint>> {
+        self.db.get::<FullNodeSyncCheckpoint>(&())
+    }
+
+    /// Sets the last processed L1 height of the sync checkpoint
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn set_sync_checkpoint_l1_height(&self, l1_height: SlotNumber) -> anyhow::Result<()> {
+        let checkpoint = SyncCheckpoint {
+            l1_height,
+            ..self.get_sync_checkpoint()?.unwrap_or_default()
+        };
+
+        let mut schema_batch = SchemaBatch::new();
+        schema_batch.put::<FullNodeSyncCheckpoint>(&(), &checkpoint)?;
+        self.db.write_schemas(schema_batch)?;
+
+        Ok(())
+    }
+
+    /// Gets the L2 heights covered by the sequencer commitments seen so far
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_commitment_coverage(&self) -> anyhow::Result<Option<CommitmentCoverage>> {
+        self.db.get::<SequencerCommitmentCoverage>(&())
+    }
+
+    /// Sets the L2 heights covered by the sequencer commitments seen so far
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn set_commitment_coverage(&self, coverage: &CommitmentCoverage) -> anyhow::Result<()> {
+        self.db.put::<SequencerCommitmentCoverage>(&(), coverage)
+    }
+
+    /// Gets the sequencer commitments whose challenge window is open, ordered by L1 height
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_challengeable_commitments(&self) -> anyhow::Result<Vec<ChallengeableCommitment>> {
+        Ok(self
+            .db
+            .get::<ChallengeableCommitments>(&())?
+            .unwrap_or_default())
+    }
+
+    /// Sets the sequencer commitments whose challenge window is open
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn set_challengeable_commitments(
+        &self,
+        commitments: &[ChallengeableCommitment],
+    ) -> anyhow::Result<()> {
+        self.db
+            .put::<ChallengeableCommitments>(&(), &commitments.to_vec())
+    }
+
+    /// Sets the latest verified state root
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn set_last_verified_state_root(&self, state_root: &VerifiedStateRoot) -> anyhow::Result<()> {
+        self.db.put::<LastVerifiedStateRoot>(&(), state_root)
+    }
+
+    /// Gets the latest verified state root
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_last_verified_state_root(&self) -> anyhow::Result<Option<VerifiedStateRoot>> {
+        self.db.get::<LastVerifiedStateRoot>(&())
+    }
+
+    /// Persists the report of the DA reorg which halted execution
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn set_reorg_halt(&self, report: &ReorgHaltReport) -> anyhow::Result<()> {
+        self.db.put::<ReorgHalt>(&(), report)
+    }
+
+    /// Gets the report of the DA reorg which halted execution, if it is halted
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_reorg_halt(&self) -> anyhow::Result<Option<ReorgHaltReport>> {
+        self.db.get::<ReorgHalt>(&())
+    }
+
+    /// Removes the report of the DA reorg which halted execution, resuming it
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn clear_reorg_halt(&self) -> anyhow::Result<()> {
+        self.db.delete::<ReorgHalt>(&())
+    }
+
+    /// Persists the report of the DA reorg which halted the acceptance of sequencer commitments
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn set_commitments_halt(&self, report: &ReorgHaltReport) -> anyhow::Result<()> {
+        self.db.put::<CommitmentsHalt>(&(), report)
+    }
+
+    /// Gets the report of the DA reorg which halted the acceptance of sequencer commitments,
+    /// if it is halted
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_commitments_halt(&self) -> anyhow::Result<Option<ReorgHaltReport>> {
+        self.db.get::<CommitmentsHalt>(&())
+    }
+
+    /// Removes the report of the DA reorg which halted the acceptance of sequencer commitments,
+    /// resuming it
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn clear_commitments_halt(&self) -> anyhow::Result<()> {
+        self.db.delete::<CommitmentsHalt>(&())
+    }
+
+    /// Persists the report of the state root mismatch which halted execution
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn set_state_root_mismatch(&self, report: &StateRootMismatchReport) -> anyhow::Result<()> {
+        self.db.put::<StateRootMismatch>(&(), report)
+    }
+
+    /// Gets the report of the state root mismatch which halted execution, if it is halted
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn get_state_root_mismatch(&self) -> anyhow::Result<Option<StateRootMismatchReport>> {
+        self.db.get::<StateRootMismatch>(&())
+    }
+
+    /// Removes the report of the state root mismatch which halted execution, resuming it
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn clear_state_root_mismatch(&self) -> anyhow::Result<()> {
+        self.db.delete::<StateRootMismatch>(&())
+    }
+
+    /// Indexes a sequencer commitment by its DA transaction and L2 range
+    #[instrument(level = "trace", skip(self), err, ret)]
+    fn index_sequencer_commitment(
+        &self,
+        commitment: &StoredSequencerCommitment,
+    ) -> anyhow::Result<()> {
+        let mut schema_batch = SchemaBatch::new();
+        schema_batch.put::<CommitmentByDaTxId>(&commitment.da_tx_id, commitment)?;
+        schema_batch.put::<CommitmentDaTxIdByL2Height>(
+            &BatchNumber(commitment.commitment.l2_end_block_number),
+            &commitment.da_tx_id,
+        )?;
+        self.db.write_schemas(schema_batch)?;
+
+        Ok(())
+    }
+
+    /// Gets the sequencer commitment carried by the given DA transaction
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_sequencer_commitment_by_da_tx_id(
+        &self,
+        da_tx_id: [u8; 32],
+    ) -> anyhow::Result<Option<StoredSequencerCommitment>> {
+        self.db.get::<CommitmentByDaTxId>(&da_tx_id)
+    }
+
+    /// Gets the sequencer commitment whose L2 range contains the given L2 height
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_sequencer_commitment_by_l2_height(
+        &self,
+        l2_height: BatchNumber,
+    ) -> anyhow::Result<Option<StoredSequencerCommitment>> {
+        // Commitment ranges do not overlap, so the first commitment ending at or after
+        // the L2 height is the only one which can contain it
+        let mut iter = self.db.iter::<CommitmentDaTxIdByL2Height>()?;
+        iter.seek(&l2_height)?;
+        let Some(item) = iter.next() else {
+            return Ok(None);
+        };
+
+        let Some(commitment) = self.db.get::<CommitmentByDaTxId>(&item?.value)? else {
+            return Ok(None);
+        };
+        if commitment.commitment.l2_start_block_number > l2_height.0 {
+            return Ok(None);
+        }
+
+        Ok(Some(commitment))
+    }
+
+    /// Records the id of the DA transaction carrying the proof of the commitments at the L1 height
+    #[instrument(level = "trace", skip(self), err)]
+    fn put_proof_da_tx_id(
+        &self,
+        commitments_l1_height: SlotNumber,
+        da_tx_id: [u8; 32],
+    ) -> anyhow::Result<()> {
+        self.db
+            .put::<ProofDaTxIdByCommitmentL1Height>(&commitments_l1_height, &da_tx_id)
+    }
+
+    /// Gets the id of the DA transaction carrying the proof of the commitments at the L1 height
+    #[instrument(level = "trace", skip(self), err)]
+    fn get_proof_da_tx_id(
+        &self,
+        commitments_l1_height: SlotNumber,
+    ) -> anyhow::Result<Option<[u8; 32]>> {
+        self.db
+            .get::<ProofDaTxIdByCommitmentL1Height>(&commitments_l1_height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sov_rollup_interface::da::SequencerCommitment;
+    use sov_rollup_interface::zk::{CycleReport, PhaseCycles, Proof};
+
+    use super::{LedgerDB, NodeLedgerOps, ProverLedgerOps, SequencerLedgerOps, SharedLedgerOps};
+    use crate::schema::tables::{EventByNumber, TxByHash, TxByNumber};
+    use crate::schema::types::{
+        BatchNumber, ChallengeableCommitment, CommitmentCoverage, CommitmentCoverageUpdate,
+        EventNumber, ProvingJobStatus, ReorgHaltReport, SlotNumber, StateRootMismatchReport,
+        StoredCycleReport, StoredLightClientProof, StoredProvingJob, StoredSequencerCommitment,
+        TxNumber,
+    };
+
+    #[test]
+    fn sequencer_commitment_index() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+
+        let commitments = [(1, 10, [1; 32]), (11, 20, [2; 32])].map(|(start, end, da_tx_id)| {
+            StoredSequencerCommitment {
+                da_tx_id,
+                l1_height: SlotNumber(end),
+                commitment: SequencerCommitment {
+                    merkle_root: [0; 32],
+                    l2_start_block_number: start,
+                    l2_end_block_number: end,
+                },
+            }
+        });
+        for commitment in &commitments {
+            ledger_db.index_sequencer_commitment(commitment).unwrap();
+        }
+
+        assert_eq!(
+            ledger_db
+                .get_sequencer_commitment_by_da_tx_id([2; 32])
+                .unwrap(),
+            Some(commitments[1].clone())
+        );
+        assert_eq!(
+            ledger_db
+                .get_sequencer_commitment_by_da_tx_id([3; 32])
+                .unwrap(),
+            None
+        );
+
+        for (l2_height, expected) in [
+            (1, Some(&commitments[0])),
+            (10, Some(&commitments[0])),
+            (11, Some(&commitments[1])),
+            (15, Some(&commitments[1])),
+            (21, None),
+        ] {
+            assert_eq!(
+                ledger_db
+                    .get_sequencer_commitment_by_l2_height(BatchNumber(l2_height))
+                    .unwrap()
+                    .as_ref(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn l2_state_diffs_are_kept_until_committed() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path();
+        {
+            let ledger_db = LedgerDB::with_path(path).unwrap();
+            for l2_height in 1..=4 {
+                let state_diff = vec![(vec![l2_height as u8], Some(vec![1]))];
+                ledger_db
+                    .put_l2_state_diff(BatchNumber(l2_height), &state_diff)
+                    .unwrap();
+            }
+        }
+
+        // Still known after a restart
+        let ledger_db = LedgerDB::with_path(path).unwrap();
+        assert_eq!(
+            ledger_db.get_l2_state_diff(BatchNumber(3)).unwrap(),
+            Some(vec![(vec![3], Some(vec![1]))])
+        );
+
+        ledger_db.delete_l2_state_diffs(BatchNumber(2)).unwrap();
+        assert_eq!(ledger_db.get_l2_state_diff(BatchNumber(1)).unwrap(), None);
+        assert_eq!(ledger_db.get_l2_state_diff(BatchNumber(2)).unwrap(), None);
+        assert!(ledger_db
+            .get_l2_state_diff(BatchNumber(3))
+            .unwrap()
+            .is_some());
+        assert!(ledger_db
+            .get_l2_state_diff(BatchNumber(4))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn proof_da_tx_id_by_commitment_l1_height() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+
+        assert_eq!(ledger_db.get_proof_da_tx_id(SlotNumber(5)).unwrap(), None);
+        ledger_db
+            .put_proof_da_tx_id(SlotNumber(5), [7; 32])
+            .unwrap();
+        assert_eq!(
+            ledger_db.get_proof_da_tx_id(SlotNumber(5)).unwrap(),
+            Some([7; 32])
+        );
+        assert_eq!(ledger_db.get_proof_da_tx_id(SlotNumber(6)).unwrap(), None);
+    }
+
+    #[test]
+    fn reorg_halt_is_persisted_until_cleared() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let report = ReorgHaltReport {
+            fork_height: SlotNumber(90),
+            depth: 12,
+            max_handled_reorg_depth: 6,
+            dropped_blobs: vec![[1; 32]],
+            l2_height: BatchNumber(500),
+            halted_at: 1_700_000_000,
+        };
+        {
+            let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+            assert_eq!(ledger_db.get_reorg_halt().unwrap(), None);
+            ledger_db.set_reorg_halt(&report).unwrap();
+        }
+
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        assert_eq!(ledger_db.get_reorg_halt().unwrap(), Some(report));
+        ledger_db.clear_reorg_halt().unwrap();
+        assert_eq!(ledger_db.get_reorg_halt().unwrap(), None);
+    }
+
+    #[test]
+    fn commitments_halt_is_persisted_until_cleared() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let report = ReorgHaltReport {
+            fork_height: SlotNumber(95),
+            depth: 5,
+            max_handled_reorg_depth: 4,
+            dropped_blobs: vec![],
+            l2_height: BatchNumber(500),
+            halted_at: 1_700_000_000,
+        };
+        {
+            let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+            assert_eq!(ledger_db.get_commitments_halt().unwrap(), None);
+            ledger_db.set_commitments_halt(&report).unwrap();
+        }
+
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        assert_eq!(ledger_db.get_commitments_halt().unwrap(), Some(report));
+        // Halting execution is tracked separately
+        assert_eq!(ledger_db.get_reorg_halt().unwrap(), None);
+        ledger_db.clear_commitments_halt().unwrap();
+        assert_eq!(ledger_db.get_commitments_halt().unwrap(), None);
+    }
+
+    #[test]
+    fn state_root_mismatch_is_persisted_until_cleared() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let report = StateRootMismatchReport {
+            l2_height: BatchNumber(42),
+            expected_state_root: vec![1; 32],
+            state_root: vec![2; 32],
+            diagnostics_path: Some("diagnostics/l2-block-42.json".to_string()),
+            halted_at: 1_700_000_000,
+        };
+        {
+            let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+            assert_eq!(ledger_db.get_state_root_mismatch().unwrap(), None);
+            ledger_db.set_state_root_mismatch(&report).unwrap();
+        }
+
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        assert_eq!(ledger_db.get_state_root_mismatch().unwrap(), Some(report));
+        ledger_db.clear_state_root_mismatch().unwrap();
+        assert_eq!(ledger_db.get_state_root_mismatch().unwrap(), None);
+    }
+
+    #[test]
+    fn commitment_coverage_detects_gaps_and_overlaps() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        let range = |start, end| (BatchNumber(start), BatchNumber(end));
+
+        let mut coverage = CommitmentCoverage::default();
+        assert_eq!(
+            coverage.record(range(1, 10)),
+            CommitmentCoverageUpdate::Extended(vec![range(1, 10)])
+        );
+        // 11-20 is missing
+        assert_eq!(
+            coverage.record(range(21, 30)),
+            CommitmentCoverageUpdate::Gap(range(11, 20))
+        );
+        assert_eq!(
+            coverage.record(range(31, 40)),
+            CommitmentCoverageUpdate::Gap(range(11, 20))
+        );
+        assert_eq!(
+            coverage.record(range(5, 12)),
+            CommitmentCoverageUpdate::Overlap
+        );
+        assert_eq!(
+            coverage.record(range(25, 35)),
+            CommitmentCoverageUpdate::Overlap
+        );
+        assert_eq!(coverage.contiguous_l2_height, BatchNumber(10));
+        assert_eq!(coverage.gaps(), vec![range(11, 20)]);
+
+        ledger_db.set_commitment_coverage(&coverage).unwrap();
+        let mut coverage = ledger_db.get_commitment_coverage().unwrap().unwrap();
+
+        // Closing the gap makes the ranges after it contiguous
+        assert_eq!(
+            coverage.record(range(11, 20)),
+            CommitmentCoverageUpdate::Extended(vec![range(11, 20), range(21, 30), range(31, 40)])
+        );
+        assert_eq!(coverage.contiguous_l2_height, BatchNumber(40));
+        assert!(coverage.gaps().is_empty());
+        assert!(coverage.ahead.is_empty());
+        assert_eq!(coverage.overlaps, vec![range(5, 12), range(25, 35)]);
+    }
+
+    #[test]
+    fn challengeable_commitments_are_persisted() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        assert!(ledger_db
+            .get_challengeable_commitments()
+            .unwrap()
+            .is_empty());
+
+        let commitments: Vec<_> = [(1, 10, 5), (11, 20, 7)]
+            .into_iter()
+            .map(|(start, end, l1_height)| ChallengeableCommitment {
+                commitment: SequencerCommitment {
+                    merkle_root: [start as u8; 32],
+                    l2_start_block_number: start,
+                    l2_end_block_number: end,
+                },
+                l1_height: SlotNumber(l1_height),
+                challengeable_until: SlotNumber(l1_height + 100),
+            })
+            .collect();
+        ledger_db
+            .set_challengeable_commitments(&commitments)
+            .unwrap();
+        assert_eq!(
+            ledger_db.get_challengeable_commitments().unwrap(),
+            commitments
+        );
+
+        ledger_db.set_challengeable_commitments(&[]).unwrap();
+        assert!(ledger_db
+            .get_challengeable_commitments()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn mempool_txs_survive_reopen() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        {
+            let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+            for i in 1..=3 {
+                ledger_db.insert_mempool_tx([i; 32], vec![i; 8]).unwrap();
+            }
+            ledger_db.remove_mempool_txs(vec![[2; 32]]).unwrap();
+        }
+
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        assert_eq!(
+            ledger_db.get_mempool_txs().unwrap(),
+            vec![([1; 32], vec![1; 8]), ([3; 32], vec![3; 8])]
+        );
+    }
+
+    #[test]
+    fn proving_jobs_survive_reopen() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let job = |status| StoredProvingJob {
+            l1_hash: [1; 32],
+            l2_range: (BatchNumber(1), BatchNumber(10)),
+            status,
+        };
+        {
+            let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+            ledger_db
+                .put_proving_job(300, &job(ProvingJobStatus::InProgress))
+                .unwrap();
+            ledger_db
+                .put_proving_job(256, &job(ProvingJobStatus::Pending))
+                .unwrap();
+            ledger_db
+                .put_proving_job(5, &job(ProvingJobStatus::Pending))
+                .unwrap();
+            ledger_db.delete_proving_job(5).unwrap();
+            ledger_db
+                .put_proving_job(256, &job(ProvingJobStatus::Proven(Proof::Full(vec![1]))))
+                .unwrap();
+        }
+
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        assert_eq!(
+            ledger_db.get_proving_jobs().unwrap(),
+            vec![
+                (
+                    SlotNumber(256),
+                    job(ProvingJobStatus::Proven(Proof::Full(vec![1])))
+                ),
+                (SlotNumber(300), job(ProvingJobStatus::InProgress)),
+            ]
+        );
+        assert_eq!(ledger_db.get_proving_job(5).unwrap(), None);
+    }
+
+    #[test]
+    fn cycle_reports_by_l1_height() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        let report = StoredCycleReport {
+            l2_range: (BatchNumber(1), BatchNumber(10)),
+            report: CycleReport {
+                user_cycles: 3_000_000,
+                total_cycles: 4_194_304,
+                segments: 4,
+                phases: vec![PhaseCycles {
+                    name: "jmt_updates".to_string(),
+                    cycles: 1_000_000,
+                    calls: 10,
+                }],
+            },
+        };
+
+        ledger_db.put_cycle_report(7, &report).unwrap();
+        assert_eq!(ledger_db.get_cycle_report(7).unwrap(), Some(report));
+        assert_eq!(ledger_db.get_cycle_report(8).unwrap(), None);
+    }
+
+    #[test]
+    fn cached_traces() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+
+        ledger_db
+            .put_cached_trace(BatchNumber(7), [2; 32], b"{}")
+            .unwrap();
+        ledger_db
+            .put_cached_trace(BatchNumber(3), [1; 32], b"[]")
+            .unwrap();
+        assert_eq!(
+            ledger_db.get_cached_trace(BatchNumber(7), [2; 32]).unwrap(),
+            Some(b"{}".to_vec())
+        );
+        assert_eq!(
+            ledger_db.get_cached_trace(BatchNumber(3), [2; 32]).unwrap(),
+            None
+        );
+        // Keys are ordered by L2 height
+        assert_eq!(
+            ledger_db.get_cached_trace_keys().unwrap(),
+            vec![(BatchNumber(3), [1; 32]), (BatchNumber(7), [2; 32])]
+        );
+
+        ledger_db
+            .delete_cached_traces(&[(BatchNumber(3), [1; 32])])
+            .unwrap();
+        assert_eq!(
+            ledger_db.get_cached_trace_keys().unwrap(),
+            vec![(BatchNumber(7), [2; 32])]
+        );
+    }
+
+    #[test]
+    fn latest_light_client_proof() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        assert_eq!(ledger_db.get_latest_light_client_proof().unwrap(), None);
+
+        let proof = |l1_height: u8| StoredLightClientProof {
+            proof: Proof::Full(vec![l1_height]),
+            output: vec![l1_height; 4],
+            recent_da_hashes: vec![[l1_height; 32]],
+        };
+        ledger_db.put_light_client_proof(12, &proof(12)).unwrap();
+        ledger_db.put_light_client_proof(5, &proof(5)).unwrap();
+
+        assert_eq!(
+            ledger_db.get_latest_light_client_proof().unwrap(),
+            Some((12, proof(12)))
+        );
+    }
+
+    #[test]
+    fn secondary_sees_primary_writes_after_catch_up() {
+        let primary_dir = tempfile::tempdir().unwrap();
+        let secondary_dir = tempfile::tempdir().unwrap();
+
+        let primary = LedgerDB::with_path(primary_dir.path()).unwrap();
+        let secondary = LedgerDB::with_secondary(
+            primary_dir.path(),
+            secondary_dir.path(),
+            &Default::default(),
+        )
+        .unwrap();
+
+        primary.set_last_proven_l2_height(BatchNumber(5)).unwrap();
+        assert_eq!(secondary.get_last_proven_l2_height().unwrap(), None);
+
+        secondary.try_catch_up_with_primary().unwrap();
+        assert_eq!(
+            secondary.get_last_proven_l2_height().unwrap(),
+            Some(BatchNumber(5))
+        );
+    }
+
+    #[test]
+    fn secondary_requires_versioned_primary() {
+        let primary_dir = tempfile::tempdir().unwrap();
+        let secondary_dir = tempfile::tempdir().unwrap();
+
+        // No primary ever opened the db, so it is not stamped with a schema version
+        assert!(LedgerDB::with_secondary(
+            primary_dir.path(),
+            secondary_dir.path(),
+            &Default::default()
+        )
+        .is_err());
+    }
+
+    /// Commits soft batches at the given heights, each with a tx with a body and an event
+    fn commit_soft_batches(ledger_db: &LedgerDB, heights: std::ops::RangeInclusive<u64>) {
+        use sov_mock_da::{MockDaSpec, MockHash};
+        use sov_rollup_interface::stf::{Event, SoftBatchReceipt, TransactionReceipt};
+
+        for l2_height in heights {
+            let receipt = SoftBatchReceipt::<(), (), MockDaSpec> {
+                da_slot_height: 1,
+                da_slot_hash: MockHash::from([1; 32]),
+                da_slot_txs_commitment: MockHash::from([1; 32]),
+                hash: [l2_height as u8; 32],
+                prev_hash: [l2_height as u8 - 1; 32],
+                tx_receipts: vec![TransactionReceipt {
+                    tx_hash: [100 + l2_height as u8; 32],
+                    body_to_save: Some(vec![l2_height as u8; 10]),
+                    events: vec![Event::new("transfer", "value")],
+                    receipt: (),
+                }],
+                phantom_data: Default::default(),
+                state_root: vec![l2_height as u8; 32],
+                soft_confirmation_signature: vec![],
+                pub_key: vec![],
+                deposit_data: vec![vec![1, 2, 3]],
+                l1_fee_rate: 1,
+                timestamp: l2_height,
+            };
+            ledger_db.commit_soft_batch(receipt, true).unwrap();
+        }
+    }
+
+    #[test]
+    fn pruning_l2_bodies_keeps_headers() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        commit_soft_batches(&ledger_db, 1..=2);
+
+        ledger_db
+            .prune_l2_bodies(&(BatchNumber(1)..BatchNumber(2)))
+            .unwrap();
+        assert_eq!(
+            ledger_db.get_last_body_pruned_l2_height().unwrap(),
+            Some(BatchNumber(1))
+        );
+
+        let pruned = ledger_db
+            .get_soft_batch_by_number(&BatchNumber(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(pruned.state_root, vec![1; 32]);
+        assert!(pruned.deposit_data.is_empty());
+        assert_eq!(pruned.txs[0].body, None);
+        assert_eq!(pruned.txs[0].events.end.0 - pruned.txs[0].events.start.0, 1);
+        assert!(ledger_db
+            .db
+            .get::<EventByNumber>(&pruned.txs[0].events.start)
+            .unwrap()
+            .is_some());
+
+        let kept = ledger_db
+            .get_soft_batch_by_number(&BatchNumber(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(kept.txs[0].body, Some(vec![2; 10]));
+        assert_eq!(kept.deposit_data, vec![vec![1, 2, 3]]);
+    }
+
+    #[test]
+    fn pruning_l2_bodies_drops_witnesses_and_continues_in_rounds() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        commit_soft_batches(&ledger_db, 1..=4);
+        for l2_height in 1..=4 {
+            ledger_db.set_l2_witness(l2_height, &vec![1u8]).unwrap();
+        }
+
+        ledger_db
+            .prune_l2_bodies(&(BatchNumber(1)..BatchNumber(3)))
+            .unwrap();
+        // An empty range changes nothing
+        ledger_db
+            .prune_l2_bodies(&(BatchNumber(3)..BatchNumber(3)))
+            .unwrap();
+        assert_eq!(
+            ledger_db.get_last_body_pruned_l2_height().unwrap(),
+            Some(BatchNumber(2))
+        );
+        for (l2_height, pruned) in [(1, true), (2, true), (3, false), (4, false)] {
+            let witness: Option<Vec<u8>> = ledger_db.get_l2_witness(l2_height).unwrap();
+            assert_eq!(witness.is_none(), pruned);
+
+            let soft_batch = ledger_db
+                .get_soft_batch_by_number(&BatchNumber(l2_height))
+                .unwrap()
+                .unwrap();
+            let tx = ledger_db
+                .db
+                .get::<TxByNumber>(&soft_batch.tx_range.start)
+                .unwrap()
+                .unwrap();
+            assert_eq!(tx.body.is_none(), pruned);
+            assert_eq!(soft_batch.txs[0].hash, tx.hash);
+        }
+
+        // The next round starts after the last pruned height
+        ledger_db
+            .prune_l2_bodies(&(BatchNumber(3)..BatchNumber(4)))
+            .unwrap();
+        assert_eq!(
+            ledger_db.get_last_body_pruned_l2_height().unwrap(),
+            Some(BatchNumber(3))
+        );
+        let witness: Option<Vec<u8>> = ledger_db.get_l2_witness(4).unwrap();
+        assert!(witness.is_some());
+
+        // Soft confirmations without bodies can still be pruned entirely
+        ledger_db
+            .prune_l2_range(&(BatchNumber(1)..BatchNumber(3)), false)
+            .unwrap();
+        assert_eq!(
+            ledger_db.get_soft_batch_by_number(&BatchNumber(1)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn backfilled_soft_batches_are_linked_to_the_stored_ones() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        commit_soft_batches(&ledger_db, 1..=3);
+        let pruned: Vec<_> = (1..=2)
+            .map(|l2_height| {
+                ledger_db
+                    .get_soft_batch_by_number(&BatchNumber(l2_height))
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect();
+        ledger_db
+            .prune_l2_range(&(BatchNumber(1)..BatchNumber(3)), false)
+            .unwrap();
+
+        // Soft confirmations are backfilled from the newest one
+        assert!(ledger_db.backfill_soft_batch(pruned[0].clone()).is_err());
+        let mut forged = pruned[1].clone();
+        forged.hash = [9; 32];
+        assert!(ledger_db.backfill_soft_batch(forged).is_err());
+
+        for soft_batch in pruned.iter().rev() {
+            let mut backfilled = soft_batch.clone();
+            backfilled.tx_range = TxNumber(0)..TxNumber(0);
+            ledger_db.backfill_soft_batch(backfilled).unwrap();
+        }
+        assert_eq!(ledger_db.get_last_pruned_l2_height().unwrap(), None);
+
+        for soft_batch in pruned {
+            let l2_height = BatchNumber(soft_batch.l2_height);
+            assert_eq!(
+                ledger_db.get_soft_batch_by_number(&l2_height).unwrap(),
+                Some(soft_batch.clone())
+            );
+            assert_eq!(
+                ledger_db
+                    .db
+                    .get::<TxByHash>(&soft_batch.txs[0].hash)
+                    .unwrap(),
+                Some(soft_batch.tx_range.start)
+            );
+        }
+    }
+
+    #[test]
+    fn integrity_check_stops_at_first_inconsistency() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        commit_soft_batches(&ledger_db, 1..=3);
+
+        let report = ledger_db
+            .check_l2_integrity(|l2_height| Ok(Some(vec![l2_height as u8; 32])))
+            .unwrap();
+        assert!(report.is_consistent());
+        assert_eq!(report.checked_from, 1);
+        assert_eq!(report.head_l2_height, 3);
+        assert_eq!(report.last_consistent_l2_height, 3);
+
+        // State is only compared where it is available
+        let report = ledger_db
+            .check_l2_integrity(|l2_height| Ok((l2_height == 2).then(|| vec![0; 32])))
+            .unwrap();
+        assert_eq!(report.last_consistent_l2_height, 1);
+        assert!(report.issue.unwrap().starts_with("state root"));
+
+        ledger_db.db.delete::<TxByHash>(&[103; 32]).unwrap();
+        let report = ledger_db.check_l2_integrity(|_| Ok(None)).unwrap();
+        assert_eq!(report.last_consistent_l2_height, 2);
+        assert_eq!(
+            report.issue,
+            Some("hash index of transaction 3 is missing".to_owned())
+        );
+    }
+
+    #[test]
+    fn truncated_l2_blocks_are_synced_again() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        commit_soft_batches(&ledger_db, 1..=3);
+
+        ledger_db.truncate_l2(BatchNumber(1)).unwrap();
+        assert_eq!(
+            ledger_db.get_head_soft_batch().unwrap().unwrap().0,
+            BatchNumber(1)
+        );
+        for l2_height in 2..=3 {
+            assert_eq!(
+                ledger_db
+                    .get_soft_batch_by_number(&BatchNumber(l2_height))
+                    .unwrap(),
+                None
+            );
+            assert_eq!(
+                ledger_db
+                    .db
+                    .get::<TxByHash>(&[100 + l2_height as u8; 32])
+                    .unwrap(),
+                None
+            );
+        }
+        // Truncating above the head changes nothing
+        ledger_db.truncate_l2(BatchNumber(5)).unwrap();
+
+        // Item numbers continue after the kept L2 blocks
+        commit_soft_batches(&ledger_db, 2..=3);
+        let soft_batch = ledger_db
+            .get_soft_batch_by_number(&BatchNumber(2))
+            .unwrap()
+            .unwrap();
+        assert_eq!(soft_batch.tx_range.start, TxNumber(2));
+        assert_eq!(soft_batch.txs[0].events.start, EventNumber(2));
+        assert!(ledger_db
+            .check_l2_integrity(|_| Ok(None))
+            .unwrap()
+            .is_consistent());
+    }
+
+    #[test]
+    fn pruning_l2_range_deletes_soft_batches() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        commit_soft_batches(&ledger_db, 1..=3);
+
+        // Headers of the first soft batch are kept, the second one is deleted entirely
+        ledger_db
+            .prune_l2_range(&(BatchNumber(1)..BatchNumber(2)), true)
+            .unwrap();
+        ledger_db
+            .prune_l2_range(&(BatchNumber(2)..BatchNumber(3)), false)
+            .unwrap();
+        assert_eq!(
+            ledger_db.get_last_pruned_l2_height().unwrap(),
+            Some(BatchNumber(2))
+        );
+
+        let headers_only = ledger_db
+            .get_soft_batch_by_number(&BatchNumber(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(headers_only.txs[0].body, None);
+        assert!(ledger_db
+            .db
+            .get::<EventByNumber>(&headers_only.txs[0].events.start)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            ledger_db.db.get::<TxByHash>(&[101; 32]).unwrap(),
+            Some(headers_only.tx_range.start)
+        );
+
+        assert_eq!(
+            ledger_db.get_soft_batch_by_number(&BatchNumber(2)).unwrap(),
+            None
+        );
+        assert_eq!(ledger_db.db.get::<TxByHash>(&[102; 32]).unwrap(), None);
+        let deleted_event = EventNumber(headers_only.txs[0].events.end.0);
+        assert!(ledger_db
+            .db
+            .get::<EventByNumber>(&deleted_event)
+            .unwrap()
+            .is_none());
+
+        let kept = ledger_db
+            .get_soft_batch_by_number(&BatchNumber(3))
+            .unwrap()
+            .unwrap();
+        assert_eq!(kept.txs[0].body, Some(vec![3; 10]));
+        assert!(ledger_db
+            .db
+            .get::<EventByNumber>(&kept.txs[0].events.start)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn pruning_l2_range_deletes_cached_traces() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        commit_soft_batches(&ledger_db, 1..=3);
+        for l2_height in 1..=3 {
+            ledger_db
+                .put_cached_trace(BatchNumber(l2_height), [l2_height as u8; 32], b"trace")
+                .unwrap();
+        }
+
+        ledger_db
+            .prune_l2_range(&(BatchNumber(1)..BatchNumber(3)), true)
+            .unwrap();
+        assert_eq!(
+            ledger_db.get_cached_trace_keys().unwrap(),
+            vec![(BatchNumber(3), [3; 32])]
+        );
+    }
 }
@@ -1,21 +1,21 @@
 use serde::de::DeserializeOwned;
 use sov_rollup_interface::rpc::{
     sequencer_commitment_to_response, BatchIdAndOffset, BatchIdentifier, BatchResponse,
-    EventIdentifier, ItemOrHash, LastVerifiedProofResponse, LedgerRpcProvider, ProofResponse,
-    QueryMode, SequencerCommitmentResponse, SlotIdAndOffset, SlotIdentifier, SlotResponse,
-    SoftBatchIdentifier, SoftBatchResponse, TxIdAndOffset, TxIdentifier, TxResponse,
-    VerifiedProofResponse,
+    EventIdentifier, ItemOrHash, L2BodyUnavailable, L2HeightUnavailable, LastVerifiedProofResponse,
+    LedgerRpcProvider, ProofResponse, QueryMode, SequencerCommitmentResponse, SlotIdAndOffset,
+    SlotIdentifier, SlotResponse, SoftBatchIdentifier, SoftBatchResponse, TxIdAndOffset,
+    TxIdentifier, TxResponse, VerifiedProofResponse,
 };
 use sov_rollup_interface::stf::Event;
 use tokio::sync::broadcast::Receiver;
 
 use crate::schema::tables::{
-    BatchByHash, BatchByNumber, CommitmentsByNumber, EventByNumber, ProofBySlotNumber, SlotByHash,
-    SlotByNumber, SoftBatchByHash, SoftBatchByNumber, SoftConfirmationStatus, TxByHash, TxByNumber,
-    VerifiedProofsBySlotNumber,
+    BatchByHash, BatchByNumber, CommitmentsByNumber, EventByNumber, LastBodyPrunedL2Height,
+    LastPrunedL2Height, ProofBySlotNumber, SlotByHash, SlotByNumber, SoftBatchByHash,
+    SoftBatchByNumber, SoftConfirmationStatus, TxByHash, TxByNumber, VerifiedProofsBySlotNumber,
 };
 use crate::schema::types::{
-    BatchNumber, EventNumber, SlotNumber, StoredBatch, StoredSlot, TxNumber,
+    BatchNumber, EventNumber, SlotNumber, StoredBatch, StoredSlot, StoredSoftBatch, TxNumber,
 };
 
 /// The maximum number of slots that can be requested in a single RPC range query
@@ -70,8 +70,10 @@ impl LedgerRpcProvider for LedgerDB {
         Ok(match batch_num {
             Some(num) => {
                 if let Some(stored_batch) = self.db.get::<SoftBatchByNumber>(&num)? {
+                    self.ensure_l2_body_available(&stored_batch)?;
                     Some(stored_batch.try_into()?)
                 } else {
+                    self.ensure_l2_height_available(num)?;
                     None
                 }
             }
@@ -324,8 +326,26 @@ impl LedgerRpcProvider for LedgerDB {
             "requested batch range too large. Max: {}",
             MAX_BATCHES_PER_REQUEST
         );
-        let ids: Vec<_> = (start..=end).map(SoftBatchIdentifier::Number).collect();
-        self.get_soft_batches(&ids)
+
+        // A single scan of the range, missing L2 heights are looked up only to tell
+        // pruned ones apart from the ones which don't exist yet
+        let mut out = Vec::with_capacity((end - start + 1) as usize);
+        let mut next_l2_height = start;
+        for soft_batch in self.soft_batch_iter(BatchNumber(start)..BatchNumber(end + 1))? {
+            let soft_batch = soft_batch?;
+            for l2_height in next_l2_height..soft_batch.l2_height {
+                self.ensure_l2_height_available(BatchNumber(l2_height))?;
+                out.push(None);
+            }
+            next_l2_height = soft_batch.l2_height + 1;
+            self.ensure_l2_body_available(&soft_batch)?;
+            out.push(Some(soft_batch.try_into()?));
+        }
+        for l2_height in next_l2_height..=end {
+            self.ensure_l2_height_available(BatchNumber(l2_height))?;
+            out.push(None);
+        }
+        Ok(out)
     }
 
     fn get_transactions_range<T: DeserializeOwned>(
@@ -355,6 +375,7 @@ impl LedgerRpcProvider for LedgerDB {
             .flatten()
             .is_none()
         {
+            self.ensure_l2_height_available(BatchNumber(l2_height))?;
             return Err(anyhow::anyhow!(
                 "Soft confirmation at height {} not processed yet.",
                 l2_height
@@ -448,6 +469,7 @@ impl LedgerRpcProvider for LedgerDB {
             .db
             .get::<SoftBatchByNumber>(&BatchNumber(next_ids.soft_batch_number.saturating_sub(1)))?
         {
+            self.ensure_l2_body_available(&stored_soft_batch)?;
             return Ok(Some(stored_soft_batch.try_into()?));
         }
         Ok(None)
@@ -460,6 +482,36 @@ impl LedgerRpcProvider for LedgerDB {
 }
 
 impl LedgerDB {
+    /// Returns [`L2HeightUnavailable`] if data of the given L2 height has been pruned
+    fn ensure_l2_height_available(&self, l2_height: BatchNumber) -> Result<(), anyhow::Error> {
+        if let Some(last_pruned_l2_height) = self.db.get::<LastPrunedL2Height>(&())? {
+            if l2_height <= last_pruned_l2_height {
+                return Err(L2HeightUnavailable {
+                    l2_height: l2_height.0,
+                    available_from: last_pruned_l2_height.0 + 1,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns [`L2BodyUnavailable`] if transaction bodies or deposits of the soft batch
+    /// are not stored, as its response would be incomplete without them
+    fn ensure_l2_body_available(&self, soft_batch: &StoredSoftBatch) -> Result<(), anyhow::Error> {
+        let bodies_pruned = self
+            .db
+            .get::<LastBodyPrunedL2Height>(&())?
+            .is_some_and(|last_body_pruned| soft_batch.l2_height <= last_body_pruned.0);
+        if bodies_pruned || soft_batch.txs.iter().any(|tx| tx.body.is_none()) {
+            return Err(L2BodyUnavailable {
+                l2_height: soft_batch.l2_height,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
     fn resolve_slot_identifier(
         &self,
         slot_id: &SlotIdentifier,
@@ -623,9 +675,39 @@ impl LedgerDB {
 #[cfg(test)]
 mod tests {
     use sov_mock_da::{MockBlob, MockBlock};
-    use sov_rollup_interface::rpc::LedgerRpcProvider;
+    use sov_rollup_interface::rpc::{L2BodyUnavailable, LedgerRpcProvider};
+    use sov_schema_db::SchemaBatch;
+
+    use crate::ledger_db::{
+        LedgerDB, NodeLedgerOps, SequencerLedgerOps, SharedLedgerOps, SlotCommit, SoftBatchIter,
+    };
+    use crate::schema::types::{
+        BatchNumber, EventNumber, SlotNumber, StoredSoftBatch, StoredTransaction, TxNumber,
+    };
+
+    fn soft_batch(l2_height: u64, tx_body: Option<Vec<u8>>) -> StoredSoftBatch {
+        StoredSoftBatch {
+            da_slot_height: 1,
+            l2_height,
+            da_slot_hash: [0; 32],
+            da_slot_txs_commitment: [0; 32],
+            hash: [l2_height as u8; 32],
+            prev_hash: [l2_height as u8 - 1; 32],
+            tx_range: TxNumber(l2_height)..TxNumber(l2_height + 1),
+            txs: vec![StoredTransaction {
+                hash: [l2_height as u8; 32],
+                events: EventNumber(0)..EventNumber(0),
+                body: tx_body,
+            }],
+            deposit_data: vec![],
+            state_root: vec![l2_height as u8; 32],
+            soft_confirmation_signature: vec![],
+            pub_key: vec![],
+            l1_fee_rate: 0,
+            timestamp: 0,
+        }
+    }
 
-    use crate::ledger_db::{LedgerDB, SequencerLedgerOps, SlotCommit};
     #[test]
     fn test_slot_subscription() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -638,4 +720,93 @@ mod tests {
 
         assert_eq!(rx.blocking_recv().unwrap(), 1);
     }
+
+    #[test]
+    fn test_missing_bodies_are_distinct_from_missing_soft_batches() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = LedgerDB::with_path(temp_dir.path()).unwrap();
+
+        let mut schema_batch = SchemaBatch::new();
+        for (l2_height, tx_body) in [(1, Some(vec![1])), (2, Some(vec![2])), (3, None)] {
+            db.put_soft_batch(
+                &soft_batch(l2_height, tx_body),
+                &BatchNumber(l2_height),
+                &mut schema_batch,
+            )
+            .unwrap();
+        }
+        db.db.write_schemas(schema_batch).unwrap();
+
+        let soft_batch = db.get_soft_batch_by_number::<()>(2).unwrap().unwrap();
+        assert_eq!(soft_batch.txs.unwrap().len(), 1);
+
+        // Stored without body, like with `include_tx_body = false`
+        let err = db.get_soft_batch_by_number::<()>(3).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<L2BodyUnavailable>(),
+            Some(&L2BodyUnavailable { l2_height: 3 })
+        );
+        assert!(db.get_soft_batches_range(2, 3).is_err());
+        let soft_batches = db.get_soft_batches_range(1, 2).unwrap();
+        assert_eq!(soft_batches.len(), 2);
+        assert!(soft_batches.iter().all(Option::is_some));
+
+        // Not stored yet
+        assert!(db.get_soft_batch_by_number::<()>(4).unwrap().is_none());
+
+        db.prune_l2_bodies(&(BatchNumber(1)..BatchNumber(2)))
+            .unwrap();
+        let err = db.get_soft_batch_by_number::<()>(1).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<L2BodyUnavailable>(),
+            Some(&L2BodyUnavailable { l2_height: 1 })
+        );
+    }
+
+    #[test]
+    fn test_soft_batch_range_scans() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let db = LedgerDB::with_path(temp_dir.path()).unwrap();
+
+        // L2 height 4 is missing
+        let mut schema_batch = SchemaBatch::new();
+        for l2_height in [1, 2, 3, 5] {
+            db.put_soft_batch(
+                &soft_batch(l2_height, Some(vec![l2_height as u8])),
+                &BatchNumber(l2_height),
+                &mut schema_batch,
+            )
+            .unwrap();
+        }
+        db.db.write_schemas(schema_batch).unwrap();
+        for l2_height in [2, 3] {
+            db.extend_l2_range_of_l1_slot(SlotNumber(7), BatchNumber(l2_height))
+                .unwrap();
+        }
+
+        let l2_heights = |iter: SoftBatchIter| -> Vec<u64> {
+            iter.map(|soft_batch| soft_batch.unwrap().l2_height)
+                .collect()
+        };
+        assert_eq!(
+            l2_heights(db.soft_batch_iter(BatchNumber(2)..BatchNumber(10)).unwrap()),
+            vec![2, 3, 5]
+        );
+        assert_eq!(
+            l2_heights(db.soft_batch_iter(BatchNumber(0)..BatchNumber(2)).unwrap()),
+            vec![1]
+        );
+        assert_eq!(
+            l2_heights(db.soft_batch_iter_by_l1_height(SlotNumber(7)).unwrap()),
+            vec![2, 3]
+        );
+        assert!(l2_heights(db.soft_batch_iter_by_l1_height(SlotNumber(8)).unwrap()).is_empty());
+
+        let soft_batches = db.get_soft_batches_range(2, 6).unwrap();
+        let l2_heights: Vec<_> = soft_batches
+            .iter()
+            .map(|soft_batch| soft_batch.as_ref().map(|soft_batch| soft_batch.l2_height))
+            .collect();
+        assert_eq!(l2_heights, vec![Some(2), Some(3), None, Some(5), None]);
+    }
 }
@@ -7,10 +7,13 @@ use sov_rollup_interface::stf::{Event, SoftBatchReceipt, StateDiff};
 use sov_rollup_interface::zk::Proof;
 use sov_schema_db::SchemaBatch;
 
-use super::{ItemNumbers, SlotCommit};
+use super::{ItemNumbers, SlotCommit, SoftBatchIter};
 use crate::schema::types::{
-    BatchNumber, EventNumber, L2HeightRange, SlotNumber, StoredBatch, StoredSlot, StoredSoftBatch,
-    StoredStateTransition, StoredTransaction, TxNumber,
+    BatchNumber, ChallengeableCommitment, CommitmentCoverage, DbHash, EventNumber, L2HeightRange,
+    RelayQuotaState, ReorgHaltReport, SequencerLeaseCheckpoint, SlotNumber,
+    StateRootMismatchReport, StoredBatch, StoredCycleReport, StoredLightClientProof, StoredProof,
+    StoredProvingJob, StoredSequencerCommitment, StoredSlot, StoredSoftBatch,
+    StoredStateTransition, StoredTransaction, SyncCheckpoint, TxNumber, VerifiedStateRoot,
 };
 
 /// Shared ledger operations
@@ -80,6 +83,15 @@ pub trait SharedLedgerOps {
     /// Gets l1 height of l1 hash
     fn get_state_diff(&self) -> Result<StateDiff>;
 
+    /// Stores the state diff of an L2 block until its commitment is processed
+    fn put_l2_state_diff(&self, l2_height: BatchNumber, state_diff: &StateDiff) -> Result<()>;
+
+    /// Gets the state diff of an L2 block, if it is not committed yet
+    fn get_l2_state_diff(&self, l2_height: BatchNumber) -> Result<Option<StateDiff>>;
+
+    /// Deletes the state diffs of the L2 blocks up to the given L2 height, once they are committed
+    fn delete_l2_state_diffs(&self, up_to_l2_height: BatchNumber) -> Result<()>;
+
     /// Sets l1 height of l1 hash
     fn set_l1_height_of_l1_hash(&self, hash: [u8; 32], height: u64) -> Result<()>;
 
@@ -92,12 +104,19 @@ pub trait SharedLedgerOps {
 
     /// Gets the commitments in the da slot with given height if any
     /// Adds the new coming commitment info
+    /// A commitment already stored in the da slot is not added again
     fn update_commitments_on_da_slot(
         &self,
         height: u64,
         commitment: SequencerCommitment,
     ) -> Result<()>;
 
+    /// Gets the L1 height the sequencer commitment was first stored at, by its content hash
+    fn get_l1_height_of_sequencer_commitment(
+        &self,
+        commitment: &SequencerCommitment,
+    ) -> Result<Option<SlotNumber>>;
+
     /// Set the genesis state root
     fn set_l2_genesis_state_root<StateRoot: Serialize>(
         &self,
@@ -118,10 +137,60 @@ pub trait SharedLedgerOps {
 
     /// Gets all soft confirmations by numbers
     fn get_soft_batch_by_number(&self, number: &BatchNumber) -> Result<Option<StoredSoftBatch>>;
+
+    /// Gets the L1 fee rates of the L2 heights in the range, which exist
+    fn get_l1_fee_rate_range(
+        &self,
+        range: &std::ops::Range<BatchNumber>,
+    ) -> Result<Vec<(BatchNumber, u128)>>;
+
+    /// Get the state root by L2 height
+    fn get_l2_state_root<StateRoot: DeserializeOwned>(
+        &self,
+        l2_height: u64,
+    ) -> anyhow::Result<Option<StateRoot>>;
+
+    /// Gets the range of L2 heights created as soft confirmations of an L1 height, if any
+    fn get_l2_range_by_l1_height(&self, l1_height: SlotNumber) -> Result<Option<L2HeightRange>>;
+
+    /// Creates a checkpoint of the ledger db inside `path`, laid out as in the storage path
+    fn create_checkpoint(&self, path: &std::path::Path) -> Result<()>;
+
+    /// Gets a cached JSON encoded trace of a transaction of the L2 height
+    fn get_cached_trace(&self, l2_height: BatchNumber, key: DbHash) -> Result<Option<Vec<u8>>>;
+
+    /// Caches a JSON encoded trace of a transaction of the L2 height
+    fn put_cached_trace(&self, l2_height: BatchNumber, key: DbHash, trace: &[u8]) -> Result<()>;
+
+    /// Deletes cached traces
+    fn delete_cached_traces(&self, entries: &[(BatchNumber, DbHash)]) -> Result<()>;
+
+    /// Gets the keys of all cached traces
+    fn get_cached_trace_keys(&self) -> Result<Vec<(BatchNumber, DbHash)>>;
+
+    /// Gets the newest L2 height committed to DA, the `safe` block of the RPC
+    fn get_safe_l2_height(&self) -> Result<Option<BatchNumber>>;
+
+    /// Gets the newest L2 height covered by a verified proof, the `finalized` block of the RPC
+    fn get_finalized_l2_height(&self) -> Result<Option<BatchNumber>>;
 }
 
 /// Node ledger operations
 pub trait NodeLedgerOps: SharedLedgerOps {
+    /// Streams the soft confirmations with L2 heights in the range, in ascending order.
+    /// Unlike `get_soft_batch_range`, nothing is preallocated and a single DB iterator is used,
+    /// so it suits large ranges.
+    fn soft_batch_iter(&self, range: std::ops::Range<BatchNumber>) -> Result<SoftBatchIter<'_>>;
+
+    /// Streams the soft confirmations created for the L1 slot of the given height, in ascending order
+    fn soft_batch_iter_by_l1_height(&self, l1_height: SlotNumber) -> Result<SoftBatchIter<'_>>;
+
+    /// Stores the soft confirmation at the last pruned L2 height, whose hash must be the
+    /// previous hash of the soft confirmation after it, and lowers the last pruned L2 height.
+    /// Its transactions are numbered right before the ones of the next soft confirmation,
+    /// their events are not stored.
+    fn backfill_soft_batch(&self, soft_batch: StoredSoftBatch) -> Result<()>;
+
     /// Stores proof related data on disk, accessible via l1 slot height
     fn update_verified_proof_data(
         &self,
@@ -144,16 +213,129 @@ pub trait NodeLedgerOps: SharedLedgerOps {
 
     /// Gets l1 height of l1 hash
     fn get_l1_height_of_l1_hash(&self, hash: [u8; 32]) -> Result<Option<u64>>;
+
+    /// Gets the soft confirmation status of the given L2 height, if recorded
+    fn get_l2_soft_confirmation_status(
+        &self,
+        l2_height: BatchNumber,
+    ) -> Result<Option<sov_rollup_interface::rpc::SoftConfirmationStatus>>;
+
+    /// Sets the highest L2 height covered by a verified proof
+    fn set_last_proven_l2_height(&self, l2_height: BatchNumber) -> Result<()>;
+
+    /// Gets the highest L2 height covered by a verified proof
+    fn get_last_proven_l2_height(&self) -> Result<Option<BatchNumber>>;
+
+    /// Gets the last L2 height that was pruned
+    fn get_last_pruned_l2_height(&self) -> Result<Option<BatchNumber>>;
+
+    /// Gets the last L2 height whose transaction bodies and witness were pruned
+    fn get_last_body_pruned_l2_height(&self) -> Result<Option<BatchNumber>>;
+
+    /// Deletes transaction bodies and witnesses of soft confirmations in the given range.
+    /// Soft confirmation headers, state roots, transaction hashes and events are kept.
+    fn prune_l2_bodies(&self, range: &std::ops::Range<BatchNumber>) -> Result<()>;
+
+    /// Prunes events, transaction bodies and cached traces of soft confirmations in the given range.
+    /// If `keep_headers` is false, soft confirmations and transactions are deleted entirely.
+    fn prune_l2_range(
+        &self,
+        range: &std::ops::Range<BatchNumber>,
+        keep_headers: bool,
+    ) -> Result<()>;
+
+    /// Commits a soft batch together with the L2 range of its L1 slot and
+    /// the L2 height of the sync checkpoint in a single atomic write
+    fn commit_soft_batch_with_checkpoint<B: Serialize, T: Serialize, DS: DaSpec>(
+        &self,
+        batch_receipt: SoftBatchReceipt<B, T, DS>,
+        include_tx_body: bool,
+    ) -> Result<()>;
+
+    /// Gets the sync checkpoint, if any
+    fn get_sync_checkpoint(&self) -> Result<Option<SyncCheckpoint>>;
+
+    /// Sets the last processed L1 height of the sync checkpoint
+    fn set_sync_checkpoint_l1_height(&self, l1_height: SlotNumber) -> Result<()>;
+
+    /// Gets the L2 heights covered by the sequencer commitments seen so far
+    fn get_commitment_coverage(&self) -> Result<Option<CommitmentCoverage>>;
+
+    /// Sets the L2 heights covered by the sequencer commitments seen so far
+    fn set_commitment_coverage(&self, coverage: &CommitmentCoverage) -> Result<()>;
+
+    /// Gets the sequencer commitments whose challenge window is open, ordered by L1 height
+    fn get_challengeable_commitments(&self) -> Result<Vec<ChallengeableCommitment>>;
+
+    /// Sets the sequencer commitments whose challenge window is open
+    fn set_challengeable_commitments(&self, commitments: &[ChallengeableCommitment]) -> Result<()>;
+
+    /// Sets the latest verified state root
+    fn set_last_verified_state_root(&self, state_root: &VerifiedStateRoot) -> Result<()>;
+
+    /// Gets the latest verified state root
+    fn get_last_verified_state_root(&self) -> Result<Option<VerifiedStateRoot>>;
+
+    /// Persists the report of the DA reorg which halted execution
+    fn set_reorg_halt(&self, report: &ReorgHaltReport) -> Result<()>;
+
+    /// Gets the report of the DA reorg which halted execution, if it is halted
+    fn get_reorg_halt(&self) -> Result<Option<ReorgHaltReport>>;
+
+    /// Removes the report of the DA reorg which halted execution, resuming it
+    fn clear_reorg_halt(&self) -> Result<()>;
+
+    /// Persists the report of the DA reorg which halted the acceptance of sequencer commitments
+    fn set_commitments_halt(&self, report: &ReorgHaltReport) -> Result<()>;
+
+    /// Gets the report of the DA reorg which halted the acceptance of sequencer commitments,
+    /// if it is halted
+    fn get_commitments_halt(&self) -> Result<Option<ReorgHaltReport>>;
+
+    /// Removes the report of the DA reorg which halted the acceptance of sequencer commitments,
+    /// resuming it
+    fn clear_commitments_halt(&self) -> Result<()>;
+
+    /// Persists the report of the state root mismatch which halted execution
+    fn set_state_root_mismatch(&self, report: &StateRootMismatchReport) -> Result<()>;
+
+    /// Gets the report of the state root mismatch which halted execution, if it is halted
+    fn get_state_root_mismatch(&self) -> Result<Option<StateRootMismatchReport>>;
+
+    /// Removes the report of the state root mismatch which halted execution, resuming it
+    fn clear_state_root_mismatch(&self) -> Result<()>;
+
+    /// Indexes a sequencer commitment by the id of the DA transaction which carried it
+    /// and by the L2 range it commits to
+    fn index_sequencer_commitment(&self, commitment: &StoredSequencerCommitment) -> Result<()>;
+
+    /// Gets the sequencer commitment carried by the given DA transaction, if indexed
+    fn get_sequencer_commitment_by_da_tx_id(
+        &self,
+        da_tx_id: [u8; 32],
+    ) -> Result<Option<StoredSequencerCommitment>>;
+
+    /// Gets the indexed sequencer commitment whose L2 range contains the given L2 height, if any
+    fn get_sequencer_commitment_by_l2_height(
+        &self,
+        l2_height: BatchNumber,
+    ) -> Result<Option<StoredSequencerCommitment>>;
+
+    /// Records the id of the DA transaction carrying the verified proof
+    /// of the sequencer commitments found at the given L1 height
+    fn put_proof_da_tx_id(
+        &self,
+        commitments_l1_height: SlotNumber,
+        da_tx_id: [u8; 32],
+    ) -> Result<()>;
+
+    /// Gets the id of the DA transaction carrying the verified proof
+    /// of the sequencer commitments found at the given L1 height, if recorded
+    fn get_proof_da_tx_id(&self, commitments_l1_height: SlotNumber) -> Result<Option<[u8; 32]>>;
 }
 
 /// Prover ledger operations
 pub trait ProverLedgerOps: SharedLedgerOps {
-    /// Get the state root by L2 height
-    fn get_l2_state_root<StateRoot: DeserializeOwned>(
-        &self,
-        l2_height: u64,
-    ) -> anyhow::Result<Option<StateRoot>>;
-
     /// Get the last scanned slot by the prover
     fn get_prover_last_scanned_l1_height(&self) -> Result<Option<SlotNumber>>;
 
@@ -173,8 +355,35 @@ pub trait ProverLedgerOps: SharedLedgerOps {
         state_transition: StoredStateTransition,
     ) -> Result<()>;
 
+    /// Get the proof sent to DA for the sequencer commitments on the L1 height
+    fn get_proof_data(&self, l1_height: u64) -> Result<Option<StoredProof>>;
+
     /// Set the witness by L2 height
     fn set_l2_witness<Witness: Serialize>(&self, l2_height: u64, witness: &Witness) -> Result<()>;
+
+    /// Get the proving job of the sequencer commitments on the L1 height
+    fn get_proving_job(&self, l1_height: u64) -> Result<Option<StoredProvingJob>>;
+
+    /// Get all proving jobs, in ascending L1 height order
+    fn get_proving_jobs(&self) -> Result<Vec<(SlotNumber, StoredProvingJob)>>;
+
+    /// Put the proving job of the sequencer commitments on the L1 height
+    fn put_proving_job(&self, l1_height: u64, job: &StoredProvingJob) -> Result<()>;
+
+    /// Delete the proving job of the sequencer commitments on the L1 height
+    fn delete_proving_job(&self, l1_height: u64) -> Result<()>;
+
+    /// Get the cycle report of the proof of the sequencer commitments on the L1 height
+    fn get_cycle_report(&self, l1_height: u64) -> Result<Option<StoredCycleReport>>;
+
+    /// Put the cycle report of the proof of the sequencer commitments on the L1 height
+    fn put_cycle_report(&self, l1_height: u64, report: &StoredCycleReport) -> Result<()>;
+
+    /// Get the latest light client proof and the L1 height it verifies up to
+    fn get_latest_light_client_proof(&self) -> Result<Option<(u64, StoredLightClientProof)>>;
+
+    /// Put the light client proof which verifies up to the L1 height
+    fn put_light_client_proof(&self, l1_height: u64, proof: &StoredLightClientProof) -> Result<()>;
 }
 
 /// Sequencer ledger operations
@@ -216,4 +425,29 @@ pub trait SequencerLedgerOps: SharedLedgerOps {
 
     /// Get the most recent commitment's l1 height
     fn get_l1_height_of_last_commitment(&self) -> anyhow::Result<Option<SlotNumber>>;
+
+    /// Insert mempool transaction
+    fn insert_mempool_tx(&self, tx_hash: DbHash, tx: Vec<u8>) -> anyhow::Result<()>;
+
+    /// Fetch mempool transactions
+    /// Returns (tx hash, RLP encoded tx) pairs.
+    fn get_mempool_txs(&self) -> anyhow::Result<Vec<(DbHash, Vec<u8>)>>;
+
+    /// Remove mempool transactions by their hashes
+    fn remove_mempool_txs(&self, tx_hashes: Vec<DbHash>) -> anyhow::Result<()>;
+
+    /// Gets the leases of the sequencer keys scanned so far
+    fn get_sequencer_lease_checkpoint(&self) -> anyhow::Result<Option<SequencerLeaseCheckpoint>>;
+
+    /// Sets the leases of the sequencer keys scanned so far
+    fn set_sequencer_lease_checkpoint(
+        &self,
+        checkpoint: &SequencerLeaseCheckpoint,
+    ) -> anyhow::Result<()>;
+
+    /// Gets the meta-transactions relayed in the current quota window
+    fn get_relay_quotas(&self) -> anyhow::Result<Option<RelayQuotaState>>;
+
+    /// Sets the meta-transactions relayed in the current quota window
+    fn set_relay_quotas(&self, quotas: &RelayQuotaState) -> anyhow::Result<()>;
 }
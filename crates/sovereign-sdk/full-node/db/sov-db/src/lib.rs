@@ -4,9 +4,12 @@
 //! - DB "Table" definitions can be found in the [`schema`] module
 //! - Types and traits for storing state data can be found in the [`state_db`] module
 //! - The default db configuration is generated in the [`rocks_db_config`] module
+//! - Backups of the node databases can be restored with the [`backup`] module
 #![forbid(unsafe_code)]
 #![deny(missing_docs)]
 
+/// Implements restoring the node databases from a backup.
+pub mod backup;
 /// Implements a wrapper around RocksDB meant for storing rollup history ("the ledger").
 /// This wrapper implements helper traits for writing blocks to the ledger, and for
 /// serving historical data via RPC
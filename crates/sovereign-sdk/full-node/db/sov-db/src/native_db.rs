@@ -4,8 +4,8 @@ use std::sync::Arc;
 use sov_schema_db::snapshot::{DbSnapshot, QueryManager, ReadOnlyDbSnapshot};
 use sov_schema_db::SchemaBatch;
 
-use crate::rocks_db_config::gen_rocksdb_options;
-use crate::schema::tables::{ModuleAccessoryState, NATIVE_TABLES};
+use crate::rocks_db_config::{open_db, open_secondary_db, RocksdbConfig};
+use crate::schema::tables::{ModuleAccessoryState, NATIVE_TABLES, STATE_TABLES};
 use crate::schema::types::AccessoryKey;
 
 /// Specifies a particular version of the Accessory state.
@@ -28,17 +28,40 @@ impl<Q> Clone for NativeDB<Q> {
 }
 
 impl<Q> NativeDB<Q> {
-    const DB_PATH_SUFFIX: &'static str = "native-db";
+    /// Directory of the db inside the storage path
+    pub const DB_PATH_SUFFIX: &'static str = "native-db";
     const DB_NAME: &'static str = "native";
 
     /// Initialize [`sov_schema_db::DB`] that matches tables and columns for NativeDB
-    pub fn setup_schema_db(path: impl AsRef<Path>) -> anyhow::Result<sov_schema_db::DB> {
+    pub fn setup_schema_db(
+        path: impl AsRef<Path>,
+        db_config: &RocksdbConfig,
+    ) -> anyhow::Result<sov_schema_db::DB> {
         let path = path.as_ref().join(Self::DB_PATH_SUFFIX);
-        sov_schema_db::DB::open(
+        // The config is shared with the state db
+        open_db(
             path,
             Self::DB_NAME,
-            NATIVE_TABLES.iter().copied(),
-            &gen_rocksdb_options(&Default::default(), false),
+            NATIVE_TABLES,
+            &[STATE_TABLES, NATIVE_TABLES].concat(),
+            db_config,
+        )
+    }
+
+    /// Initialize [`sov_schema_db::DB`] for NativeDB in secondary mode on top of the db in `path`.
+    /// The secondary instance keeps its own files in `secondary_path`.
+    pub fn setup_secondary_schema_db(
+        path: impl AsRef<Path>,
+        secondary_path: impl AsRef<Path>,
+        db_config: &RocksdbConfig,
+    ) -> anyhow::Result<sov_schema_db::DB> {
+        open_secondary_db(
+            path.as_ref().join(Self::DB_PATH_SUFFIX),
+            secondary_path.as_ref().join(Self::DB_PATH_SUFFIX),
+            Self::DB_NAME,
+            NATIVE_TABLES,
+            &[STATE_TABLES, NATIVE_TABLES].concat(),
+            db_config,
         )
     }
 
@@ -1,13 +1,20 @@
 // Adapted from Aptos-Core.
-// Modified to remove serde dependency
+// Modified to add per column family options
 
-use rocksdb::Options;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use rocksdb::{
+    BlockBasedOptions, Cache, ColumnFamilyDescriptor, DBCompactionStyle, DBCompressionType, Options,
+};
+use serde::Deserialize;
 
 /// Port selected RocksDB options for tuning underlying rocksdb instance of our state db.
 /// The current default values are taken from Aptos. TODO: tune rocksdb for our workload.
 /// see <https://github.com/facebook/rocksdb/blob/master/include/rocksdb/options.h>
 /// for detailed explanations.
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(default)]
 pub struct RocksdbConfig {
     /// The maximum number of files that can be open concurrently. Defaults to 5000
     pub max_open_files: i32,
@@ -16,6 +23,14 @@ pub struct RocksdbConfig {
     pub max_total_wal_size: u64,
     /// The maximum number of background threads, including threads for flushing and compaction. Defaults to 16.
     pub max_background_jobs: i32,
+    /// Size in bytes of the LRU block cache shared by all column families of the db.
+    /// If not set, RocksDB allocates its default 8MB cache per column family.
+    pub block_cache_size: Option<usize>,
+    /// Options applied to every column family
+    pub default_column_family: ColumnFamilyConfig,
+    /// Options overriding `default_column_family` for specific column families, keyed by table name.
+    /// The dbs fail to open if a key is not a table of the dbs the config is used for.
+    pub column_families: BTreeMap<String, ColumnFamilyConfig>,
 }
 
 impl Default for RocksdbConfig {
@@ -29,6 +44,84 @@ impl Default for RocksdbConfig {
             // This includes threads for flushing and compaction. Rocksdb will decide the # of
             // threads to use internally.
             max_background_jobs: 16,
+            block_cache_size: None,
+            default_column_family: ColumnFamilyConfig::default(),
+            column_families: BTreeMap::new(),
+        }
+    }
+}
+
+/// Column family options. Unset options fall back to RocksDB defaults.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct ColumnFamilyConfig {
+    /// Compaction style of the column family
+    pub compaction_style: Option<CompactionStyle>,
+    /// Compression of the column family. Defaults to lz4
+    pub compression: Option<Compression>,
+    /// Size in bytes of a single memtable
+    pub write_buffer_size: Option<usize>,
+    /// Maximum number of memtables kept in memory before flushing
+    pub max_write_buffer_number: Option<i32>,
+}
+
+impl ColumnFamilyConfig {
+    /// Returns options of `self`, falling back to `defaults` for options which are not set.
+    fn or(self, defaults: ColumnFamilyConfig) -> Self {
+        Self {
+            compaction_style: self.compaction_style.or(defaults.compaction_style),
+            compression: self.compression.or(defaults.compression),
+            write_buffer_size: self.write_buffer_size.or(defaults.write_buffer_size),
+            max_write_buffer_number: self
+                .max_write_buffer_number
+                .or(defaults.max_write_buffer_number),
+        }
+    }
+}
+
+/// RocksDB compaction styles.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionStyle {
+    /// Leveled compaction, lower space and read amplification.
+    Level,
+    /// Universal compaction, lower write amplification.
+    Universal,
+    /// FIFO compaction, drops the oldest files once the column family grows too large.
+    Fifo,
+}
+
+impl From<CompactionStyle> for DBCompactionStyle {
+    fn from(style: CompactionStyle) -> Self {
+        match style {
+            CompactionStyle::Level => DBCompactionStyle::Level,
+            CompactionStyle::Universal => DBCompactionStyle::Universal,
+            CompactionStyle::Fifo => DBCompactionStyle::Fifo,
+        }
+    }
+}
+
+/// RocksDB compression algorithms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Compression {
+    /// No compression
+    None,
+    /// Snappy compression
+    Snappy,
+    /// LZ4 compression
+    Lz4,
+    /// Zstandard compression
+    Zstd,
+}
+
+impl From<Compression> for DBCompressionType {
+    fn from(compression: Compression) -> Self {
+        match compression {
+            Compression::None => DBCompressionType::None,
+            Compression::Snappy => DBCompressionType::Snappy,
+            Compression::Lz4 => DBCompressionType::Lz4,
+            Compression::Zstd => DBCompressionType::Zstd,
         }
     }
 }
@@ -47,3 +140,158 @@ pub fn gen_rocksdb_options(config: &RocksdbConfig, readonly: bool) -> Options {
 
     db_opts
 }
+
+/// Generate a [`rocksdb::ColumnFamilyDescriptor`] for each of the given column families
+/// corresponding to the given [`RocksdbConfig`].
+pub fn gen_rocksdb_cf_descriptors(
+    config: &RocksdbConfig,
+    column_families: &[&str],
+) -> Vec<ColumnFamilyDescriptor> {
+    let block_cache = config.block_cache_size.map(Cache::new_lru_cache);
+
+    column_families
+        .iter()
+        .map(|cf_name| {
+            let cf_config = config
+                .column_families
+                .get(*cf_name)
+                .copied()
+                .unwrap_or_default()
+                .or(config.default_column_family);
+
+            let mut cf_opts = Options::default();
+            cf_opts.set_compression_type(
+                cf_config
+                    .compression
+                    .map(Into::into)
+                    .unwrap_or(DBCompressionType::Lz4),
+            );
+            if let Some(compaction_style) = cf_config.compaction_style {
+                cf_opts.set_compaction_style(compaction_style.into());
+            }
+            if let Some(write_buffer_size) = cf_config.write_buffer_size {
+                cf_opts.set_write_buffer_size(write_buffer_size);
+            }
+            if let Some(max_write_buffer_number) = cf_config.max_write_buffer_number {
+                cf_opts.set_max_write_buffer_number(max_write_buffer_number);
+            }
+            if let Some(block_cache) = &block_cache {
+                let mut block_opts = BlockBasedOptions::default();
+                block_opts.set_block_cache(block_cache);
+                cf_opts.set_block_based_table_factory(&block_opts);
+            }
+
+            ColumnFamilyDescriptor::new(*cf_name, cf_opts)
+        })
+        .collect()
+}
+
+/// Fails if `config` tunes column families which are not in `known_tables`, e.g. a misspelled
+/// table name, so tuning which would not be applied is not silently ignored.
+/// `known_tables` are the tables of all the dbs the config is used for.
+pub(crate) fn check_column_families(
+    config: &RocksdbConfig,
+    known_tables: &[&str],
+) -> anyhow::Result<()> {
+    let unknown: Vec<&str> = config
+        .column_families
+        .keys()
+        .map(String::as_str)
+        .filter(|cf_name| !known_tables.contains(cf_name))
+        .collect();
+    if !unknown.is_empty() {
+        anyhow::bail!(
+            "Unknown column families in the RocksDB config: {}. Known column families are: {}",
+            unknown.join(", "),
+            known_tables.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Opens a [`sov_schema_db::DB`] with the given column families, configured by [`RocksdbConfig`].
+/// Fails if the config tunes column families which are not in `known_tables`.
+pub(crate) fn open_db(
+    path: impl AsRef<Path>,
+    name: &'static str,
+    column_families: &[&str],
+    known_tables: &[&str],
+    config: &RocksdbConfig,
+) -> anyhow::Result<sov_schema_db::DB> {
+    check_column_families(config, known_tables)?;
+    sov_schema_db::DB::open_with_cfds(
+        &gen_rocksdb_options(config, false),
+        path,
+        name,
+        gen_rocksdb_cf_descriptors(config, column_families),
+    )
+}
+
+/// Opens a [`sov_schema_db::DB`] in secondary mode on top of the db at `primary_path`.
+/// The secondary instance keeps its own logs in `secondary_path` and only sees new writes of
+/// the primary after [`sov_schema_db::DB::try_catch_up_with_primary`] is called.
+/// Fails if the config tunes column families which are not in `known_tables`.
+pub(crate) fn open_secondary_db(
+    primary_path: impl AsRef<Path>,
+    secondary_path: impl AsRef<Path>,
+    name: &'static str,
+    column_families: &[&'static str],
+    known_tables: &[&str],
+    config: &RocksdbConfig,
+) -> anyhow::Result<sov_schema_db::DB> {
+    check_column_families(config, known_tables)?;
+    let mut db_opts = gen_rocksdb_options(config, true);
+    // Secondary instances must keep all files open, otherwise files deleted by
+    // compactions of the primary can not be read anymore
+    db_opts.set_max_open_files(-1);
+    sov_schema_db::DB::open_cf_as_secondary(
+        &db_opts,
+        primary_path.as_ref(),
+        secondary_path.as_ref(),
+        name,
+        column_families.to_vec(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger_db::LedgerDB;
+    use crate::native_db::NativeDB;
+    use crate::state_db::StateDB;
+
+    fn config_tuning(cf_name: &str) -> RocksdbConfig {
+        RocksdbConfig {
+            column_families: BTreeMap::from([(
+                cf_name.to_string(),
+                ColumnFamilyConfig {
+                    write_buffer_size: Some(1 << 20),
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_unknown_column_families_are_rejected() {
+        let tmpdir = tempfile::tempdir().unwrap();
+
+        let err =
+            LedgerDB::with_config(tmpdir.path(), &config_tuning("SoftBatchByNumbr")).unwrap_err();
+        assert!(err.to_string().contains("SoftBatchByNumbr"));
+        // State tables are not in the ledger db
+        assert!(LedgerDB::with_config(tmpdir.path(), &config_tuning("JmtNodes")).is_err());
+        assert!(LedgerDB::with_config(tmpdir.path(), &config_tuning("SoftBatchByNumber")).is_ok());
+    }
+
+    #[test]
+    fn test_state_and_native_dbs_share_their_config() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let config = config_tuning("ModuleAccessoryState");
+
+        assert!(StateDB::<()>::setup_schema_db(tmpdir.path(), &config).is_ok());
+        assert!(NativeDB::<()>::setup_schema_db(tmpdir.path(), &config).is_ok());
+        assert!(StateDB::<()>::setup_schema_db(tmpdir.path(), &config_tuning("Jmt")).is_err());
+    }
+}
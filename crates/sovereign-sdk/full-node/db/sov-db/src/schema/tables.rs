@@ -21,6 +21,9 @@
 //! - `KeyHash -> Key`
 //! - `(Key, Version) -> JmtValue`
 //! - `NodeKey -> Node`
+//! - `(StaleSinceVersion, NodeKey) -> ()`
+//! - `(StaleSinceVersion, Key, Version) -> ()`
+//! - `() -> EarliestVersion`
 //!
 //! Module Accessory State Table:
 //! - `(ModuleAddress, Key) -> Value`
@@ -35,9 +38,12 @@ use sov_schema_db::schema::{KeyDecoder, KeyEncoder, ValueCodec};
 use sov_schema_db::{CodecError, SeekKeyEncoder};
 
 use super::types::{
-    AccessoryKey, AccessoryStateValue, BatchNumber, DbHash, EventNumber, JmtValue, L2HeightRange,
-    SlotNumber, StateKey, StoredBatch, StoredProof, StoredSlot, StoredSoftBatch, StoredTransaction,
-    StoredVerifiedProof, TxNumber,
+    AccessoryKey, AccessoryStateValue, BatchNumber, ChallengeableCommitment, CommitmentCoverage,
+    DbHash, EventNumber, JmtValue, L2HeightRange, RelayQuotaState, ReorgHaltReport,
+    SequencerLeaseCheckpoint, SlotNumber, StateKey, StateRootMismatchReport, StoredBatch,
+    StoredCycleReport, StoredLightClientProof, StoredProof, StoredProvingJob,
+    StoredSequencerCommitment, StoredSlot, StoredSoftBatch, StoredTransaction, StoredVerifiedProof,
+    SyncCheckpoint, TxNumber, VerifiedStateRoot,
 };
 
 /// A list of all tables used by the StateDB. These tables store rollup state - meaning
@@ -46,22 +52,45 @@ pub const STATE_TABLES: &[&str] = &[
     KeyHashToKey::table_name(),
     JmtValues::table_name(),
     JmtNodes::table_name(),
+    StaleNodes::table_name(),
+    StaleValues::table_name(),
+    EarliestStateVersion::table_name(),
 ];
 
 /// A list of all tables used by the LedgerDB. These tables store rollup "history" - meaning
 /// transaction, events, receipts, etc.
 pub const LEDGER_TABLES: &[&str] = &[
+    LedgerSchemaVersion::table_name(),
     SlotByNumber::table_name(),
     SlotByHash::table_name(),
     SoftBatchByNumber::table_name(),
     SoftBatchByHash::table_name(),
+    StateRootByL2Height::table_name(),
+    L1FeeRateByL2Height::table_name(),
     L2RangeByL1Height::table_name(),
     L2Witness::table_name(),
     L2GenesisStateRoot::table_name(),
     LastStateDiff::table_name(),
+    L2StateDiffs::table_name(),
+    LastPrunedL2Height::table_name(),
+    LastBodyPrunedL2Height::table_name(),
+    LastProvenL2Height::table_name(),
     PendingSequencerCommitmentL2Range::table_name(),
     LastSequencerCommitmentSent::table_name(),
+    MempoolTxs::table_name(),
+    SequencerLeaseState::table_name(),
+    RelayQuotas::table_name(),
     ProverLastScannedSlot::table_name(),
+    ProvingJobs::table_name(),
+    CycleReports::table_name(),
+    LightClientProofs::table_name(),
+    FullNodeSyncCheckpoint::table_name(),
+    SequencerCommitmentCoverage::table_name(),
+    ChallengeableCommitments::table_name(),
+    LastVerifiedStateRoot::table_name(),
+    ReorgHalt::table_name(),
+    CommitmentsHalt::table_name(),
+    StateRootMismatch::table_name(),
     BatchByHash::table_name(),
     BatchByNumber::table_name(),
     SoftConfirmationStatus::table_name(),
@@ -70,8 +99,13 @@ pub const LEDGER_TABLES: &[&str] = &[
     EventByKey::table_name(),
     EventByNumber::table_name(),
     CommitmentsByNumber::table_name(),
+    CommitmentByDaTxId::table_name(),
+    CommitmentDaTxIdByL2Height::table_name(),
+    CommitmentL1HeightByHash::table_name(),
+    ProofDaTxIdByCommitmentL1Height::table_name(),
     ProofBySlotNumber::table_name(),
     VerifiedProofsBySlotNumber::table_name(),
+    TraceCache::table_name(),
 ];
 
 /// A list of all tables used by the NativeDB. These tables store
@@ -225,6 +259,12 @@ define_table_with_seek_key_codec!(
     (LastStateDiff) () => StateDiff
 );
 
+define_table_with_seek_key_codec!(
+    /// State diffs of the L2 blocks which are not committed yet, kept to be posted
+    /// with their commitment or to check the state diff posted with it
+    (L2StateDiffs) BatchNumber => StateDiff
+);
+
 define_table_with_seek_key_codec!(
     /// The primary source for slot data
     (SlotByNumber) SlotNumber => StoredSlot
@@ -240,16 +280,55 @@ define_table_with_default_codec!(
     (CommitmentsByNumber) SlotNumber => Vec<SequencerCommitment>
 );
 
+define_table_with_default_codec!(
+    /// A "secondary index" for sequencer commitments by the id of the DA transaction which carried them
+    (CommitmentByDaTxId) DbHash => StoredSequencerCommitment
+);
+
+define_table_with_default_codec!(
+    /// A "secondary index" for sequencer commitments by their content hash,
+    /// to the L1 height they were first posted at
+    (CommitmentL1HeightByHash) DbHash => SlotNumber
+);
+
+define_table_with_seek_key_codec!(
+    /// A "secondary index" for sequencer commitments by the last L2 height they commit to
+    (CommitmentDaTxIdByL2Height) BatchNumber => DbHash
+);
+define_table_with_default_codec!(
+    /// Full nodes use this table to store the id of the DA transaction carrying the verified proof
+    /// of the sequencer commitments found at an L1 height
+    (ProofDaTxIdByCommitmentL1Height) SlotNumber => DbHash
+);
+
 define_table_with_seek_key_codec!(
     /// The primary source for soft batch data
     (SoftBatchByNumber) BatchNumber => StoredSoftBatch
 );
 
+define_table_with_seek_key_codec!(
+    /// RPC uses this table to cache JSON encoded transaction traces,
+    /// by the L2 height of the transaction and the hash of the transaction and the tracer options
+    (TraceCache) (BatchNumber, DbHash) => Vec<u8>
+);
+
 define_table_with_default_codec!(
     /// A "secondary index" for soft batch data by hash
     (SoftBatchByHash) DbHash => BatchNumber
 );
 
+define_table_with_seek_key_codec!(
+    /// A "secondary index" for the state root after each soft batch.
+    /// Unlike soft batches, state roots are never pruned.
+    (StateRootByL2Height) BatchNumber => Vec<u8>
+);
+
+define_table_with_seek_key_codec!(
+    /// A "secondary index" for the L1 fee rate of each soft batch.
+    /// Unlike soft batches, L1 fee rates are never pruned.
+    (L1FeeRateByL2Height) BatchNumber => u128
+);
+
 define_table_with_default_codec!(
     /// The primary source of reverse look-up L2 height ranges for L1 heights
     (L2RangeByL1Height) SlotNumber => L2HeightRange
@@ -275,11 +354,97 @@ define_table_with_seek_key_codec!(
     (LastSequencerCommitmentSent) () => BatchNumber
 );
 
+define_table_with_default_codec!(
+    /// Sequencer uses this table to persist the RLP encoded transactions accepted
+    /// into its mempool but not yet included in a block, keyed by tx hash
+    (MempoolTxs) DbHash => Vec<u8>
+);
+
+define_table_with_seek_key_codec!(
+    /// Stores the schema version the ledger db was last migrated to
+    (LedgerSchemaVersion) () => u64
+);
+
 define_table_with_seek_key_codec!(
     /// Prover uses this table to store the last slot it scanned
     (ProverLastScannedSlot) () => SlotNumber
 );
 
+define_table_with_seek_key_codec!(
+    /// Prover uses this table to store its proving jobs by L1 height until their proofs are sent to DA
+    (ProvingJobs) SlotNumber => StoredProvingJob
+);
+
+define_table_with_seek_key_codec!(
+    /// Prover uses this table to store the cycle reports of its proofs by L1 height, when it profiles its guest
+    (CycleReports) SlotNumber => StoredCycleReport
+);
+
+define_table_with_seek_key_codec!(
+    /// Prover uses this table to store its light client proofs by the latest L1 height they verify
+    (LightClientProofs) SlotNumber => StoredLightClientProof
+);
+
+define_table_with_seek_key_codec!(
+    /// Full node uses this table to store its sync progress
+    (FullNodeSyncCheckpoint) () => SyncCheckpoint
+);
+
+define_table_with_seek_key_codec!(
+    /// Full node uses this table to store the L2 heights covered by the sequencer commitments it has seen
+    (SequencerCommitmentCoverage) () => CommitmentCoverage
+);
+
+define_table_with_seek_key_codec!(
+    /// Full node uses this table to store the sequencer commitments whose challenge window is open, ordered by L1 height
+    (ChallengeableCommitments) () => Vec<ChallengeableCommitment>
+);
+
+define_table_with_seek_key_codec!(
+    /// Light verifier uses this table to store the latest state root it verified
+    (LastVerifiedStateRoot) () => VerifiedStateRoot
+);
+
+define_table_with_seek_key_codec!(
+    /// Sequencer uses this table to store the leases of the sequencer keys it scanned DA for
+    (SequencerLeaseState) () => SequencerLeaseCheckpoint
+);
+
+define_table_with_seek_key_codec!(
+    /// Sequencer uses this table to store the meta-transactions it relayed in the current quota window
+    (RelayQuotas) () => RelayQuotaState
+);
+
+define_table_with_seek_key_codec!(
+    /// Full node uses this table to store the report of the DA reorg which halted its execution, until it is resumed
+    (ReorgHalt) () => ReorgHaltReport
+);
+
+define_table_with_seek_key_codec!(
+    /// Full node uses this table to store the report of the DA reorg deeper than the finality depth which halted the acceptance of sequencer commitments, until it is resumed
+    (CommitmentsHalt) () => ReorgHaltReport
+);
+
+define_table_with_seek_key_codec!(
+    /// Full node uses this table to store the report of the state root mismatch which halted its execution, until it is cleared
+    (StateRootMismatch) () => StateRootMismatchReport
+);
+
+define_table_with_seek_key_codec!(
+    /// Pruner uses this table to store the last L2 height it pruned
+    (LastPrunedL2Height) () => BatchNumber
+);
+
+define_table_with_seek_key_codec!(
+    /// Pruner uses this table to store the last L2 height whose transaction bodies and witness it pruned
+    (LastBodyPrunedL2Height) () => BatchNumber
+);
+
+define_table_with_seek_key_codec!(
+    /// Full node uses this table to store the highest L2 height covered by a verified proof
+    (LastProvenL2Height) () => BatchNumber
+);
+
 define_table_with_seek_key_codec!(
     /// The primary source for batch data
     (BatchByNumber) BatchNumber => StoredBatch
@@ -367,6 +532,53 @@ impl ValueCodec<JmtNodes> for Node {
     }
 }
 
+define_table_without_codec!(
+    /// Index of JMT nodes which are no longer part of the tree since the given version
+    (StaleNodes) (Version, NodeKey) => ()
+);
+
+impl KeyEncoder<StaleNodes> for (Version, NodeKey) {
+    fn encode_key(&self) -> sov_schema_db::schema::Result<Vec<u8>> {
+        // Write the version in big-endian order so that the oldest stale nodes come first
+        let mut output = self.0.to_be_bytes().to_vec();
+        output.extend(<NodeKey as KeyEncoder<JmtNodes>>::encode_key(&self.1)?);
+        Ok(output)
+    }
+}
+
+impl SeekKeyEncoder<StaleNodes> for (Version, NodeKey) {
+    fn encode_seek_key(&self) -> sov_schema_db::schema::Result<Vec<u8>> {
+        <(Version, NodeKey) as KeyEncoder<StaleNodes>>::encode_key(self)
+    }
+}
+
+impl KeyDecoder<StaleNodes> for (Version, NodeKey) {
+    fn decode_key(data: &[u8]) -> sov_schema_db::schema::Result<Self> {
+        // The stale since version and the version of the node key
+        if data.len() < 16 {
+            return Err(CodecError::InvalidKeyLength {
+                expected: 16,
+                got: data.len(),
+            });
+        }
+        let mut version = [0u8; 8];
+        version.copy_from_slice(&data[..8]);
+        let version = u64::from_be_bytes(version);
+        let node_key = <NodeKey as KeyDecoder<JmtNodes>>::decode_key(&data[8..])?;
+        Ok((version, node_key))
+    }
+}
+
+impl ValueCodec<StaleNodes> for () {
+    fn encode_value(&self) -> sov_schema_db::schema::Result<Vec<u8>> {
+        Ok(vec![])
+    }
+
+    fn decode_value(_data: &[u8]) -> sov_schema_db::schema::Result<Self> {
+        Ok(())
+    }
+}
+
 define_table_without_codec!(
     /// The source of truth for JMT values by version
     (JmtValues) (StateKey, Version) => JmtValue
@@ -412,6 +624,60 @@ impl ValueCodec<JmtValues> for JmtValue {
     }
 }
 
+define_table_without_codec!(
+    /// Index of JMT values which are overwritten since the given version
+    (StaleValues) (Version, StateKey, Version) => ()
+);
+
+impl KeyEncoder<StaleValues> for (Version, StateKey, Version) {
+    fn encode_key(&self) -> sov_schema_db::schema::Result<Vec<u8>> {
+        // Write the version in big-endian order so that the oldest stale values come first
+        let mut output = self.0.to_be_bytes().to_vec();
+        output.extend(<(&StateKey, Version) as KeyEncoder<JmtValues>>::encode_key(
+            &(&self.1, self.2),
+        )?);
+        Ok(output)
+    }
+}
+
+impl SeekKeyEncoder<StaleValues> for (Version, StateKey, Version) {
+    fn encode_seek_key(&self) -> sov_schema_db::schema::Result<Vec<u8>> {
+        <(Version, StateKey, Version) as KeyEncoder<StaleValues>>::encode_key(self)
+    }
+}
+
+impl KeyDecoder<StaleValues> for (Version, StateKey, Version) {
+    fn decode_key(data: &[u8]) -> sov_schema_db::schema::Result<Self> {
+        if data.len() < 8 {
+            return Err(CodecError::InvalidKeyLength {
+                expected: 8,
+                got: data.len(),
+            });
+        }
+        let mut version = [0u8; 8];
+        version.copy_from_slice(&data[..8]);
+        let stale_since_version = u64::from_be_bytes(version);
+        let (key, version) =
+            <(StateKey, Version) as KeyDecoder<JmtValues>>::decode_key(&data[8..])?;
+        Ok((stale_since_version, key, version))
+    }
+}
+
+impl ValueCodec<StaleValues> for () {
+    fn encode_value(&self) -> sov_schema_db::schema::Result<Vec<u8>> {
+        Ok(vec![])
+    }
+
+    fn decode_value(_data: &[u8]) -> sov_schema_db::schema::Result<Self> {
+        Ok(())
+    }
+}
+
+define_table_with_seek_key_codec!(
+    /// The earliest [`Version`] of the tree which can still be read, after stale nodes have been pruned
+    (EarliestStateVersion) () => Version
+);
+
 define_table_with_default_codec!(
     /// A mapping from key-hashes to their preimages and latest version. Since we store raw
     /// key-value pairs instead of keyHash->value pairs,
@@ -4,13 +4,17 @@ use std::sync::Arc;
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sov_rollup_interface::da::{SequencerCommitment, SequencerLease};
 use sov_rollup_interface::rpc::{
-    BatchResponse, HexTx, ProofResponse, ProofRpcResponse, SoftBatchResponse,
-    StateTransitionRpcResponse, TxIdentifier, TxResponse, VerifiedProofResponse,
+    BatchResponse, CommitmentCoverageResponse, HexHash, HexTx, IndexedSequencerCommitmentResponse,
+    L2RangeResponse, ProofResponse, ProofRpcResponse, ReorgHaltResponse, SoftBatchResponse,
+    StateRootMismatchResponse, StateTransitionRpcResponse, TxIdentifier, TxResponse,
+    VerifiedProofResponse, VerifiedStateRootResponse,
 };
 use sov_rollup_interface::soft_confirmation::SignedSoftConfirmationBatch;
 use sov_rollup_interface::stf::{Event, EventKey, TransactionReceipt};
-use sov_rollup_interface::zk::{CumulativeStateDiff, Proof};
+use sov_rollup_interface::zk::{CumulativeStateDiff, CycleReport, Proof};
 
 /// A cheaply cloneable bytes abstraction for use within the trust boundary of the node
 /// (i.e. when interfacing with the database). Serializes and deserializes more efficiently,
@@ -84,6 +88,50 @@ pub struct StoredProof {
     pub state_transition: StoredStateTransition,
 }
 
+/// Progress of a proving job of the prover
+#[derive(Debug, Clone, PartialEq, BorshDeserialize, BorshSerialize)]
+pub enum ProvingJobStatus {
+    /// The witness is submitted, but proving has not started
+    Pending,
+    /// Proving has started
+    InProgress,
+    /// Proven, but the proof may not be sent to DA yet
+    Proven(Proof),
+}
+
+/// The on-disk format of a proving job of the prover.
+/// Kept until the proof of the job is sent to DA.
+#[derive(Debug, Clone, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct StoredProvingJob {
+    /// Hash of the L1 block the proven sequencer commitments are in
+    pub l1_hash: [u8; 32],
+    /// L2 range of the proven sequencer commitments
+    pub l2_range: L2HeightRange,
+    /// Progress of the job
+    pub status: ProvingJobStatus,
+}
+
+/// The on-disk format of the cycle report of a proof, created by a prover profiling its guest
+#[derive(Debug, Clone, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct StoredCycleReport {
+    /// L2 range of the proven sequencer commitments
+    pub l2_range: L2HeightRange,
+    /// Cycles spent by the guest
+    pub report: CycleReport,
+}
+
+/// The on-disk format of a light client proof, created by a prover extending the light client
+/// over the L1 blocks up to the L1 height the proof is stored by
+#[derive(Debug, Clone, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct StoredLightClientProof {
+    /// The light client proof
+    pub proof: Proof,
+    /// Borsh encoded output of the proof
+    pub output: Vec<u8>,
+    /// Hashes of the latest L1 blocks verified by the proof, oldest first
+    pub recent_da_hashes: Vec<[u8; 32]>,
+}
+
 impl From<StoredProof> for ProofResponse {
     fn from(value: StoredProof) -> Self {
         Self {
@@ -216,6 +264,261 @@ impl From<StoredSoftBatch> for SignedSoftConfirmationBatch {
 /// (start, end) inclusive
 pub type L2HeightRange = (BatchNumber, BatchNumber);
 
+/// Sync progress of a full node, persisted so that it can resume where it left off after a restart
+#[derive(Debug, Default, PartialEq, Clone, Copy, BorshDeserialize, BorshSerialize)]
+pub struct SyncCheckpoint {
+    /// Last L2 height committed to the ledger
+    pub l2_height: BatchNumber,
+    /// Last L1 height whose sequencer commitments and proofs have been processed
+    pub l1_height: SlotNumber,
+}
+
+/// Latest state root proven by a verified proof which links back to genesis
+#[derive(Debug, PartialEq, Clone, BorshDeserialize, BorshSerialize)]
+pub struct VerifiedStateRoot {
+    /// L1 height the proof was found in
+    pub l1_height: SlotNumber,
+    /// Last L2 height covered by the proof
+    pub l2_height: BatchNumber,
+    /// State root after the last L2 height covered by the proof
+    pub state_root: Vec<u8>,
+}
+
+impl From<VerifiedStateRoot> for VerifiedStateRootResponse {
+    fn from(value: VerifiedStateRoot) -> Self {
+        Self {
+            l1_height: value.l1_height.0,
+            l2_height: value.l2_height.0,
+            state_root: value.state_root,
+        }
+    }
+}
+
+/// Leases of the sequencer keys the sequencer scanned DA for.
+/// Instances of the sequencer read the leases from the same DA height on, so they agree on the holder.
+#[derive(Debug, PartialEq, Clone, BorshDeserialize, BorshSerialize)]
+pub struct SequencerLeaseCheckpoint {
+    /// Last L1 block scanned for leases
+    pub scanned_l1_height: SlotNumber,
+    /// Last accepted lease, which may be expired
+    pub lease: Option<SequencerLease>,
+}
+
+/// Meta-transactions the sequencer relayed in the current quota window,
+/// kept across restarts so they do not reset the quotas
+#[derive(Debug, PartialEq, Clone, Default, BorshDeserialize, BorshSerialize)]
+pub struct RelayQuotaState {
+    /// Unix timestamp in seconds the quota window started at
+    pub window_start: u64,
+    /// Gas of the relay txs sent in the window
+    pub gas_used: u64,
+    /// Meta-transactions relayed per sender in the window
+    pub relayed: Vec<([u8; 20], u64)>,
+    /// Meta-transactions whose relay txs were sent but may not be included yet
+    pub pending: Vec<PendingMetaTransaction>,
+}
+
+/// A relayed meta-transaction whose relay tx may not be included yet
+#[derive(Debug, PartialEq, Clone, BorshDeserialize, BorshSerialize)]
+pub struct PendingMetaTransaction {
+    /// Sender of the meta-transaction
+    pub from: [u8; 20],
+    /// Nonce of the sender in the trusted forwarder, big endian
+    pub nonce: [u8; 32],
+    /// Hash of the relay tx
+    pub tx_hash: DbHash,
+}
+
+/// Report of a DA reorg deeper than the full node handles,
+/// persisted while the execution of L2 blocks is halted
+#[derive(Debug, PartialEq, Clone, BorshDeserialize, BorshSerialize)]
+pub struct ReorgHaltReport {
+    /// Height of the last L1 block both chains have in common.
+    /// Only an upper bound if the reorg is deeper than the tracked chain.
+    pub fork_height: SlotNumber,
+    /// Number of replaced L1 blocks
+    pub depth: u64,
+    /// Deepest reorg the node handled without halting
+    pub max_handled_reorg_depth: u64,
+    /// Hashes of the Citrea blobs of the replaced blocks which are not in the new chain
+    pub dropped_blobs: Vec<[u8; 32]>,
+    /// Last L2 height committed to the ledger when execution halted
+    pub l2_height: BatchNumber,
+    /// Unix timestamp in seconds of the halt
+    pub halted_at: u64,
+}
+
+impl From<ReorgHaltReport> for ReorgHaltResponse {
+    fn from(value: ReorgHaltReport) -> Self {
+        Self {
+            fork_height: value.fork_height.0,
+            depth: value.depth,
+            max_handled_reorg_depth: value.max_handled_reorg_depth,
+            dropped_blobs: value.dropped_blobs.into_iter().map(HexHash).collect(),
+            l2_height: value.l2_height.0,
+            halted_at: value.halted_at,
+        }
+    }
+}
+
+/// Report of an L2 block whose computed state root differs from the one the sequencer signed,
+/// persisted while the execution of L2 blocks is halted
+#[derive(Debug, PartialEq, Clone, BorshDeserialize, BorshSerialize)]
+pub struct StateRootMismatchReport {
+    /// Height of the L2 block whose state root did not match
+    pub l2_height: BatchNumber,
+    /// State root the sequencer signed for the L2 block
+    pub expected_state_root: Vec<u8>,
+    /// State root the full node computed for the L2 block
+    pub state_root: Vec<u8>,
+    /// Path of the file the inputs of the L2 block are recorded to, if they could be recorded
+    pub diagnostics_path: Option<String>,
+    /// Unix timestamp in seconds of the halt
+    pub halted_at: u64,
+}
+
+impl From<StateRootMismatchReport> for StateRootMismatchResponse {
+    fn from(value: StateRootMismatchReport) -> Self {
+        Self {
+            l2_height: value.l2_height.0,
+            expected_state_root: value.expected_state_root,
+            state_root: value.state_root,
+            diagnostics_path: value.diagnostics_path,
+            halted_at: value.halted_at,
+        }
+    }
+}
+
+/// A sequencer commitment whose challenge window is open and which no proof covers yet
+#[derive(Debug, PartialEq, Clone, BorshDeserialize, BorshSerialize)]
+pub struct ChallengeableCommitment {
+    /// The commitment
+    pub commitment: SequencerCommitment,
+    /// L1 height the commitment was found in
+    pub l1_height: SlotNumber,
+    /// First L1 height at which the commitment can not be challenged anymore
+    pub challengeable_until: SlotNumber,
+}
+
+/// L2 heights covered by the sequencer commitments a full node has seen on DA
+#[derive(Debug, Default, PartialEq, Clone, BorshDeserialize, BorshSerialize)]
+pub struct CommitmentCoverage {
+    /// Highest L2 height up to which every L2 height is covered by a commitment
+    pub contiguous_l2_height: BatchNumber,
+    /// Ranges of the commitments seen after a gap, sorted by their start
+    pub ahead: Vec<L2HeightRange>,
+    /// Ranges of the commitments which covered L2 heights covered already
+    pub overlaps: Vec<L2HeightRange>,
+}
+
+/// How a commitment changed the [`CommitmentCoverage`]
+#[derive(Debug, PartialEq, Clone)]
+pub enum CommitmentCoverageUpdate {
+    /// The commitment extended the contiguous range.
+    /// Contains its range and the ranges ahead which are contiguous now.
+    Extended(Vec<L2HeightRange>),
+    /// The commitment is after L2 heights no commitment covers.
+    /// Contains the first range of them, nothing after it is contiguous.
+    Gap(L2HeightRange),
+    /// The commitment covers L2 heights covered already
+    Overlap,
+}
+
+impl CommitmentCoverage {
+    /// Records the L2 range of a commitment
+    pub fn record(&mut self, range: L2HeightRange) -> CommitmentCoverageUpdate {
+        let (start, end) = range;
+        let overlaps_ahead = self
+            .ahead
+            .iter()
+            .any(|(ahead_start, ahead_end)| start <= *ahead_end && *ahead_start <= end);
+        if start.0 <= self.contiguous_l2_height.0 || overlaps_ahead {
+            self.overlaps.push(range);
+            return CommitmentCoverageUpdate::Overlap;
+        }
+
+        if start.0 > self.contiguous_l2_height.0 + 1 {
+            let index = self
+                .ahead
+                .partition_point(|(ahead_start, _)| *ahead_start < start);
+            self.ahead.insert(index, range);
+            return CommitmentCoverageUpdate::Gap(
+                self.gaps()
+                    .first()
+                    .copied()
+                    .expect("Ranges are ahead of the contiguous range only after a gap"),
+            );
+        }
+
+        self.contiguous_l2_height = end;
+        let mut covered = vec![range];
+        while let Some((ahead_start, ahead_end)) = self.ahead.first().copied() {
+            if ahead_start.0 != self.contiguous_l2_height.0 + 1 {
+                break;
+            }
+            self.contiguous_l2_height = ahead_end;
+            covered.push(self.ahead.remove(0));
+        }
+        CommitmentCoverageUpdate::Extended(covered)
+    }
+
+    /// L2 ranges no commitment covers, before the last range seen
+    pub fn gaps(&self) -> Vec<L2HeightRange> {
+        let mut gaps = vec![];
+        let mut covered_until = self.contiguous_l2_height;
+        for (start, end) in &self.ahead {
+            if start.0 > covered_until.0 + 1 {
+                gaps.push((BatchNumber(covered_until.0 + 1), BatchNumber(start.0 - 1)));
+            }
+            covered_until = *end;
+        }
+        gaps
+    }
+}
+
+impl From<CommitmentCoverage> for CommitmentCoverageResponse {
+    fn from(value: CommitmentCoverage) -> Self {
+        let to_response = |ranges: &[L2HeightRange]| -> Vec<L2RangeResponse> {
+            ranges
+                .iter()
+                .map(|(start, end)| L2RangeResponse {
+                    start: start.0,
+                    end: end.0,
+                })
+                .collect()
+        };
+        Self {
+            contiguous_l2_height: value.contiguous_l2_height.0,
+            gaps: to_response(&value.gaps()),
+            ahead: to_response(&value.ahead),
+            overlaps: to_response(&value.overlaps),
+        }
+    }
+}
+
+/// Sequencer commitment together with the DA transaction which carried it
+#[derive(Debug, PartialEq, Clone, BorshDeserialize, BorshSerialize)]
+pub struct StoredSequencerCommitment {
+    /// Id of the DA transaction
+    pub da_tx_id: DbHash,
+    /// L1 height the commitment was found in
+    pub l1_height: SlotNumber,
+    /// The commitment
+    pub commitment: SequencerCommitment,
+}
+
+impl From<StoredSequencerCommitment> for IndexedSequencerCommitmentResponse {
+    fn from(value: StoredSequencerCommitment) -> Self {
+        Self {
+            da_tx_id: value.da_tx_id,
+            found_in_l1: value.l1_height.0,
+            merkle_root: value.commitment.merkle_root,
+            l2_start_block_number: value.commitment.l2_start_block_number,
+            l2_end_block_number: value.commitment.l2_end_block_number,
+        }
+    }
+}
+
 impl TryFrom<StoredSoftBatch> for SoftBatchResponse {
     type Error = anyhow::Error;
     fn try_from(value: StoredSoftBatch) -> Result<Self, Self::Error> {
@@ -232,7 +535,7 @@ impl TryFrom<StoredSoftBatch> for SoftBatchResponse {
                     .into_iter()
                     .filter_map(|tx| tx.body.map(Into::into))
                     .collect(),
-            ), // Rollup full nodes don't store tx bodies
+            ), // Callers check that the bodies are stored, see `L2BodyUnavailable`
             state_root: value.state_root,
             soft_confirmation_signature: value.soft_confirmation_signature,
             pub_key: value.pub_key,
@@ -294,6 +597,12 @@ impl<R: DeserializeOwned> TryFrom<StoredTransaction> for TxResponse<R> {
     }
 }
 
+/// Content hash of a sequencer commitment, the sha256 hash of its borsh encoding.
+/// The same commitment posted to DA more than once has the same hash.
+pub fn sequencer_commitment_hash(commitment: &SequencerCommitment) -> DbHash {
+    Sha256::digest(borsh::to_vec(commitment).expect("Serialization to vec is infallible")).into()
+}
+
 /// Split a `TransactionReceipt` into a `StoredTransaction` and a list of `Event`s for storage in the database.
 pub fn split_tx_for_storage<R: Serialize>(
     tx: TransactionReceipt<R>,
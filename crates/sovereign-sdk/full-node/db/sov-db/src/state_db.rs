@@ -1,13 +1,16 @@
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 
-use jmt::storage::{HasPreimage, TreeReader, TreeWriter};
+use jmt::storage::{HasPreimage, StaleNodeIndexBatch, TreeReader, TreeWriter};
 use jmt::{KeyHash, Version};
 use sov_schema_db::snapshot::{DbSnapshot, QueryManager, ReadOnlyDbSnapshot};
 use sov_schema_db::SchemaBatch;
 
-use crate::rocks_db_config::gen_rocksdb_options;
-use crate::schema::tables::{JmtNodes, JmtValues, KeyHashToKey, STATE_TABLES};
+use crate::rocks_db_config::{open_db, open_secondary_db, RocksdbConfig};
+use crate::schema::tables::{
+    EarliestStateVersion, JmtNodes, JmtValues, KeyHashToKey, StaleNodes, StaleValues,
+    NATIVE_TABLES, STATE_TABLES,
+};
 use crate::schema::types::StateKey;
 
 /// A typed wrapper around the db for storing rollup state. Internally,
@@ -36,20 +39,123 @@ impl<Q> Clone for StateDB<Q> {
 }
 
 impl<Q> StateDB<Q> {
-    const DB_PATH_SUFFIX: &'static str = "state";
+    /// Directory of the db inside the storage path
+    pub const DB_PATH_SUFFIX: &'static str = "state";
     const DB_NAME: &'static str = "state-db";
 
     /// Initialize [`sov_schema_db::DB`] that should be used by snapshots.
-    pub fn setup_schema_db(path: impl AsRef<Path>) -> anyhow::Result<sov_schema_db::DB> {
+    pub fn setup_schema_db(
+        path: impl AsRef<Path>,
+        db_config: &RocksdbConfig,
+    ) -> anyhow::Result<sov_schema_db::DB> {
         let state_db_path = path.as_ref().join(Self::DB_PATH_SUFFIX);
-        sov_schema_db::DB::open(
+        // The config is shared with the native db
+        open_db(
             state_db_path,
             Self::DB_NAME,
-            STATE_TABLES.iter().copied(),
-            &gen_rocksdb_options(&Default::default(), false),
+            STATE_TABLES,
+            &[STATE_TABLES, NATIVE_TABLES].concat(),
+            db_config,
         )
     }
 
+    /// Initialize [`sov_schema_db::DB`] in secondary mode on top of the state db in `path`.
+    /// The secondary instance keeps its own files in `secondary_path`.
+    pub fn setup_secondary_schema_db(
+        path: impl AsRef<Path>,
+        secondary_path: impl AsRef<Path>,
+        db_config: &RocksdbConfig,
+    ) -> anyhow::Result<sov_schema_db::DB> {
+        open_secondary_db(
+            path.as_ref().join(Self::DB_PATH_SUFFIX),
+            secondary_path.as_ref().join(Self::DB_PATH_SUFFIX),
+            Self::DB_NAME,
+            STATE_TABLES,
+            &[STATE_TABLES, NATIVE_TABLES].concat(),
+            db_config,
+        )
+    }
+
+    /// Deletes at most `max_pruned` JMT nodes and values which became stale at or before
+    /// `up_to_version`, so versions below `up_to_version` can no longer be read.
+    /// The earliest available version is raised in the same batch, to `up_to_version` once
+    /// nothing stale is left, else to the latest version the deleted entries were stale since.
+    /// Works directly on finalized data in [`sov_schema_db::DB`].
+    /// Returns number of deleted nodes and values, pruning is done if it is below `max_pruned`.
+    pub fn prune_stale_state(
+        db: &sov_schema_db::DB,
+        up_to_version: Version,
+        max_pruned: usize,
+    ) -> anyhow::Result<usize> {
+        let mut batch = SchemaBatch::new();
+        let mut pruned = 0;
+        // Deleting an entry stale since a version makes the versions before it unreadable
+        let mut unreadable_below = 0;
+
+        let mut iter = db.iter::<StaleNodes>()?;
+        iter.seek_to_first();
+        for item in iter {
+            if pruned == max_pruned {
+                break;
+            }
+            let (stale_since_version, node_key) = item?.key;
+            if stale_since_version > up_to_version {
+                break;
+            }
+            batch.delete::<JmtNodes>(&node_key)?;
+            batch.delete::<StaleNodes>(&(stale_since_version, node_key))?;
+            unreadable_below = unreadable_below.max(stale_since_version);
+            pruned += 1;
+        }
+
+        let mut iter = db.iter::<StaleValues>()?;
+        iter.seek_to_first();
+        for item in iter {
+            if pruned == max_pruned {
+                break;
+            }
+            let (stale_since_version, key, version) = item?.key;
+            if stale_since_version > up_to_version {
+                break;
+            }
+            batch.delete::<JmtValues>(&(key.clone(), version))?;
+            batch.delete::<StaleValues>(&(stale_since_version, key, version))?;
+            unreadable_below = unreadable_below.max(stale_since_version);
+            pruned += 1;
+        }
+
+        let earliest_version = if pruned < max_pruned {
+            up_to_version
+        } else {
+            unreadable_below
+        };
+        if Self::earliest_available_version(db)?.unwrap_or_default() < earliest_version {
+            batch.put::<EarliestStateVersion>(&(), &earliest_version)?;
+        }
+        db.write_schemas(batch)?;
+
+        Ok(pruned)
+    }
+
+    /// Returns the earliest [`Version`] of the tree which can still be read,
+    /// or [`None`] if the tree has never been pruned.
+    pub fn earliest_available_version(db: &sov_schema_db::DB) -> anyhow::Result<Option<Version>> {
+        db.get::<EarliestStateVersion>(&())
+    }
+
+    /// Returns the latest [`Version`] of the tree which has been written to [`sov_schema_db::DB`].
+    /// Works directly on finalized data, so pending snapshots are not taken into account.
+    pub fn last_finalized_version(db: &sov_schema_db::DB) -> anyhow::Result<Option<Version>> {
+        let mut iter = db.iter::<JmtNodes>()?;
+        iter.seek_to_last();
+
+        match iter.next() {
+            Some(Ok(item)) => Ok(Some(item.key.version())),
+            Some(Err(e)) => Err(e),
+            None => Ok(None),
+        }
+    }
+
     /// Convert it to [`ReadOnlyDbSnapshot`] which cannot be edited anymore
     pub fn freeze(self) -> anyhow::Result<ReadOnlyDbSnapshot> {
         let inner = Arc::into_inner(self.db).ok_or(anyhow::anyhow!(
@@ -83,6 +189,19 @@ impl<Q: QueryManager> StateDB<Q> {
         Ok(())
     }
 
+    /// Record JMT nodes which became stale, so they can be pruned later.
+    pub fn put_stale_node_indices(
+        &self,
+        stale_node_indices: &StaleNodeIndexBatch,
+    ) -> Result<(), anyhow::Error> {
+        let mut batch = SchemaBatch::new();
+        for index in stale_node_indices {
+            batch.put::<StaleNodes>(&(index.stale_since_version, index.node_key.clone()), &())?;
+        }
+        self.db.write_many(batch)?;
+        Ok(())
+    }
+
     /// Get an optional value from the database, given a version and a key hash.
     pub fn get_value_option_by_key(
         &self,
@@ -115,6 +234,11 @@ impl<Q: QueryManager> StateDB<Q> {
         *version
     }
 
+    /// Get the earliest [`Version`] which can still be read, if the tree has been pruned
+    pub fn get_earliest_available_version(&self) -> anyhow::Result<Option<Version>> {
+        self.db.read::<EarliestStateVersion>(&())
+    }
+
     /// Used to always query for latest possible version!
     pub fn max_out_next_version(&self) {
         let mut version = self.next_version.lock().unwrap();
@@ -173,6 +297,20 @@ impl<Q: QueryManager> TreeWriter for StateDB<Q> {
                     .ok_or(anyhow::format_err!(
                         "Could not find preimage for key hash {key_hash:?}. Has `StateDB::put_preimage` been called for this key?"
                     ))?;
+            // The previous value of the key is only read by older versions from now on
+            if let Some(previous_version) = version.checked_sub(1) {
+                if let Some(((found_key, found_version), _)) = self
+                    .db
+                    .get_prev::<JmtValues>(&(&key_preimage, previous_version))?
+                {
+                    if found_key == key_preimage {
+                        batch.put::<StaleValues>(
+                            &(*version, key_preimage.clone(), found_version),
+                            &(),
+                        )?;
+                    }
+                }
+            }
             batch.put::<JmtValues>(&(key_preimage, *version), value)?;
         }
         self.db.write_many(batch)?;
@@ -111,6 +111,13 @@ impl DB {
         Ok(Self::log_construct(name, inner))
     }
 
+    /// Replays the writes of the primary instance into a db opened in secondary mode.
+    /// Has no effect on a db opened in primary or readonly mode.
+    pub fn try_catch_up_with_primary(&self) -> anyhow::Result<()> {
+        self.inner.try_catch_up_with_primary()?;
+        Ok(())
+    }
+
     fn log_construct(name: &'static str, inner: rocksdb::DB) -> DB {
         info!(rocksdb_name = name, "Opened RocksDB.");
         DB { name, inner }
@@ -6,12 +6,21 @@ use jsonrpsee::{RpcModule, SubscriptionMessage};
 use serde::de::DeserializeOwned;
 use sov_modules_api::utils::to_jsonrpsee_error_object;
 use sov_rollup_interface::rpc::{
-    BatchIdentifier, EventIdentifier, LedgerRpcProvider, QueryMode, SlotIdentifier, TxIdentifier,
+    BatchIdentifier, EventIdentifier, L2BodyUnavailable, L2HeightUnavailable, LedgerRpcProvider,
+    QueryMode, SlotIdentifier, TxIdentifier,
 };
 
 use crate::HexHash;
 
 const LEDGER_RPC_ERROR: &str = "LEDGER_RPC_ERROR";
+const L2_HEIGHT_UNAVAILABLE_ERROR: &str = "L2_HEIGHT_UNAVAILABLE";
+const L2_BODY_UNAVAILABLE_ERROR: &str = "L2_BODY_UNAVAILABLE";
+
+/// Code of the error returned for L2 blocks which are not stored by the node, e.g. because they are pruned
+pub const L2_HEIGHT_UNAVAILABLE_ERROR_CODE: i32 = -32010;
+/// Code of the error returned for L2 blocks whose transaction bodies are not stored by the node,
+/// because it runs with `include_tx_body = false` or has pruned them
+pub const L2_BODY_UNAVAILABLE_ERROR_CODE: i32 = -32011;
 
 /// Creates a new [`jsonrpsee::RpcModule`] that exposes all JSON-RPC methods
 /// necessary to interface with the [`LedgerRpcProvider`].
@@ -88,7 +97,7 @@ where
         let args: QueryArgs<HexHash> = extract_query_args(params)?;
         ledger
             .get_soft_batch_by_hash::<Tx>(&args.0 .0)
-            .map_err(|e| to_jsonrpsee_error_object(LEDGER_RPC_ERROR, e))
+            .map_err(to_ledger_rpc_error)
     })?;
     rpc.register_async_method("ledger_getBatchByHash", |params, ledger| async move {
         let args: QueryArgs<HexHash> = extract_query_args(params)?;
@@ -114,7 +123,7 @@ where
         let args: QueryArgs<u64> = extract_query_args(params)?;
         ledger
             .get_soft_batch_by_number::<Tx>(args.0)
-            .map_err(|e| to_jsonrpsee_error_object(LEDGER_RPC_ERROR, e))
+            .map_err(to_ledger_rpc_error)
     })?;
     rpc.register_async_method("ledger_getBatchByNumber", |params, ledger| async move {
         let args: QueryArgs<u64> = extract_query_args(params)?;
@@ -155,7 +164,7 @@ where
         let args: (u64, u64) = params.parse()?;
         ledger
             .get_soft_batches_range(args.0, args.1)
-            .map_err(|e| to_jsonrpsee_error_object(LEDGER_RPC_ERROR, e))
+            .map_err(to_ledger_rpc_error)
     })?;
     rpc.register_async_method("ledger_getTransactionsRange", |params, ledger| async move {
         let args: RangeArgs = params.parse()?;
@@ -169,7 +178,7 @@ where
             let args: QueryArgs<u64> = extract_query_args(params)?;
             ledger
                 .get_soft_confirmation_status(args.0)
-                .map_err(|e| to_jsonrpsee_error_object(LEDGER_RPC_ERROR, e))
+                .map_err(to_ledger_rpc_error)
         },
     )?;
     rpc.register_async_method("prover_getLastScannedL1Slot", |_, ledger| async move {
@@ -284,9 +293,7 @@ where
     )?;
 
     rpc.register_async_method("ledger_getHeadSoftBatch", |_, ledger| async move {
-        ledger
-            .get_head_soft_batch()
-            .map_err(|e: anyhow::Error| to_jsonrpsee_error_object(LEDGER_RPC_ERROR, e))
+        ledger.get_head_soft_batch().map_err(to_ledger_rpc_error)
     })?;
 
     rpc.register_async_method("ledger_getHeadSoftBatchHeight", |_, ledger| async move {
@@ -305,6 +312,25 @@ struct RangeArgs(u64, u64, #[serde(default)] QueryMode);
 #[derive(serde::Deserialize)]
 struct QueryArgs<T>(T, #[serde(default)] QueryMode);
 
+/// Maps ledger errors to RPC errors, keeping unavailable L2 heights and bodies distinguishable
+fn to_ledger_rpc_error(e: anyhow::Error) -> ErrorObjectOwned {
+    if e.downcast_ref::<L2HeightUnavailable>().is_some() {
+        ErrorObjectOwned::owned(
+            L2_HEIGHT_UNAVAILABLE_ERROR_CODE,
+            L2_HEIGHT_UNAVAILABLE_ERROR,
+            Some(e.to_string()),
+        )
+    } else if e.downcast_ref::<L2BodyUnavailable>().is_some() {
+        ErrorObjectOwned::owned(
+            L2_BODY_UNAVAILABLE_ERROR_CODE,
+            L2_BODY_UNAVAILABLE_ERROR,
+            Some(e.to_string()),
+        )
+    } else {
+        to_jsonrpsee_error_object(LEDGER_RPC_ERROR, e)
+    }
+}
+
 /// Extract the args from an RPC query, being liberal in what is accepted.
 /// To query for a list of items, users can either pass a list of ids, or tuple containing a list of ids and a query mode
 fn extract_query_args<T: DeserializeOwned>(
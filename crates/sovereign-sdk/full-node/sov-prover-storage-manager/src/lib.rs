@@ -1,20 +1,25 @@
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 use std::marker::PhantomData;
+use std::num::NonZeroUsize;
 use std::sync::{Arc, RwLock};
 
 use sov_db::native_db::NativeDB;
+use sov_db::rocks_db_config::RocksdbConfig;
 use sov_db::state_db::StateDB;
 use sov_rollup_interface::da::{BlockHeaderTrait, DaSpec};
 use sov_rollup_interface::storage::HierarchicalStorageManager;
 use sov_schema_db::snapshot::{DbSnapshot, ReadOnlyLock, SnapshotId};
-use sov_state::{MerkleProofSpec, ProverStorage};
+use sov_state::{MerkleProofSpec, ProverStorage, StateCache};
 use tracing::{debug, trace};
 
 pub use crate::snapshot_manager::SnapshotManager;
 
 mod snapshot_manager;
 
+/// Max. stale nodes and values deleted in one batch while pruning
+const PRUNING_BATCH_SIZE: usize = 10_000;
+
 /// Implementation of [`HierarchicalStorageManager`] that handles relation between snapshots
 /// And reorgs on Data Availability layer.
 pub struct ProverStorageManager<Da: DaSpec, S: MerkleProofSpec> {
@@ -40,6 +45,9 @@ pub struct ProverStorageManager<Da: DaSpec, S: MerkleProofSpec> {
     state_snapshot_manager: Arc<RwLock<SnapshotManager>>,
     accessory_snapshot_manager: Arc<RwLock<SnapshotManager>>,
 
+    // Latest state values, shared by the storages of l2 blocks
+    state_cache: Option<StateCache>,
+
     phantom_mp_spec: PhantomData<S>,
 }
 
@@ -64,15 +72,56 @@ where
             snapshot_id_to_parent,
             state_snapshot_manager: Arc::new(RwLock::new(state_snapshot_manager)),
             accessory_snapshot_manager: Arc::new(RwLock::new(accessory_snapshot_manager)),
+            state_cache: None,
             phantom_mp_spec: Default::default(),
         }
     }
 
+    /// Caches up to `capacity` values of the latest state across the storages of l2 blocks,
+    /// see [`StateCache`]. Storages based on Da blocks and finalized storages are not cached.
+    pub fn with_state_cache(mut self, capacity: NonZeroUsize) -> Self {
+        self.state_cache = Some(StateCache::new(capacity));
+        self
+    }
+
     /// Create new [`ProverStorageManager`] from state config
     pub fn new(config: sov_state::config::Config) -> anyhow::Result<Self> {
+        Self::with_db_config(config, &RocksdbConfig::default())
+    }
+
+    /// Create new [`ProverStorageManager`] from state config,
+    /// with state and native dbs tuned by the given [`RocksdbConfig`]
+    pub fn with_db_config(
+        config: sov_state::config::Config,
+        db_config: &RocksdbConfig,
+    ) -> anyhow::Result<Self> {
         let path = config.path;
-        let state_db = StateDB::<SnapshotManager>::setup_schema_db(&path)?;
-        let native_db = NativeDB::<SnapshotManager>::setup_schema_db(&path)?;
+        let state_db = StateDB::<SnapshotManager>::setup_schema_db(&path, db_config)?;
+        let native_db = NativeDB::<SnapshotManager>::setup_schema_db(&path, db_config)?;
+
+        Ok(Self::with_db_handles(state_db, native_db))
+    }
+
+    /// Create new [`ProverStorageManager`] which opens the state and native dbs of the node
+    /// at the state config path in secondary mode, keeping its own files in `secondary_path`.
+    /// Such manager only serves reads of finalized storage and must be caught up with
+    /// [`HierarchicalStorageManager::try_catch_up_with_primary`] to see new state.
+    pub fn with_secondary_db(
+        config: sov_state::config::Config,
+        secondary_path: &std::path::Path,
+        db_config: &RocksdbConfig,
+    ) -> anyhow::Result<Self> {
+        let path = config.path;
+        let state_db = StateDB::<SnapshotManager>::setup_secondary_schema_db(
+            &path,
+            secondary_path,
+            db_config,
+        )?;
+        let native_db = NativeDB::<SnapshotManager>::setup_secondary_schema_db(
+            &path,
+            secondary_path,
+            db_config,
+        )?;
 
         Ok(Self::with_db_handles(state_db, native_db))
     }
@@ -108,6 +157,15 @@ where
     }
 
     fn finalize_by_l2_height(&mut self, l2_block_height: u64) -> anyhow::Result<()> {
+        if let Some(prev_height) = l2_block_height.checked_sub(1) {
+            if self.block_height_to_snapshot_id.contains_key(&prev_height) {
+                anyhow::bail!(
+                    "Attempt to finalize l2 block at height={} before its parent",
+                    l2_block_height
+                );
+            }
+        }
+
         let snapshot_id = self
             .block_height_to_snapshot_id
             .remove(&l2_block_height)
@@ -119,8 +177,16 @@ where
         snapshot_id_to_parent.remove(&snapshot_id);
 
         // Return error here, as underlying database can return error
-        state_manager.commit_snapshot(&snapshot_id)?;
+        // State is committed last, so presence of the state version marks the l2 block as finalized
         native_manager.commit_snapshot(&snapshot_id)?;
+        state_manager.commit_snapshot(&snapshot_id)?;
+
+        // Next l2 block now reads this one from the database
+        if let Some(child_snapshot_id) =
+            self.block_height_to_snapshot_id.get(&(l2_block_height + 1))
+        {
+            snapshot_id_to_parent.remove(child_snapshot_id);
+        }
 
         Ok(())
     }
@@ -211,10 +277,40 @@ where
             "Requested native storage for block at height: {:?} ",
             l2_block_height
         );
+        // Previous l2 block might not be finalized yet, so build on top of its snapshot
+        let prev_snapshot_id = l2_block_height
+            .checked_sub(1)
+            .and_then(|prev_height| self.block_height_to_snapshot_id.get(&prev_height))
+            .copied();
+        if let Some(prev_snapshot_id) = prev_snapshot_id {
+            let state_snapshot_manager = self.state_snapshot_manager.read().unwrap();
+            if !state_snapshot_manager.contains_snapshot(&prev_snapshot_id) {
+                anyhow::bail!("Snapshot for previous l2 block has not been saved yet");
+            }
+        }
+
         let snapshot_id = match self.block_height_to_snapshot_id.get(&l2_block_height) {
-            Some(snapshot_id) => *snapshot_id,
+            Some(snapshot_id) => {
+                // The storage given out before was dropped without being saved,
+                // its writes might have been cached
+                if let Some(state_cache) = &self.state_cache {
+                    let state_snapshot_manager = self.state_snapshot_manager.read().unwrap();
+                    if !state_snapshot_manager.contains_snapshot(snapshot_id) {
+                        debug!(
+                            "Clearing state cache, l2 block at height={} is executed again",
+                            l2_block_height
+                        );
+                        state_cache.clear();
+                    }
+                }
+                *snapshot_id
+            }
             None => {
                 let new_snapshot_id = self.latest_snapshot_id + 1;
+                if let Some(prev_snapshot_id) = prev_snapshot_id {
+                    let mut snapshot_id_to_parent = self.snapshot_id_to_parent.write().unwrap();
+                    snapshot_id_to_parent.insert(new_snapshot_id, prev_snapshot_id);
+                }
                 self.block_height_to_snapshot_id
                     .insert(l2_block_height, new_snapshot_id);
                 self.latest_snapshot_id = new_snapshot_id;
@@ -226,7 +322,11 @@ where
             l2_block_height, snapshot_id
         );
 
-        self.get_storage_with_snapshot_id(snapshot_id)
+        let storage = self.get_storage_with_snapshot_id(snapshot_id)?;
+        Ok(match &self.state_cache {
+            Some(state_cache) => storage.with_state_cache(state_cache.clone()),
+            None => storage,
+        })
     }
 
     fn finalize_l2(&mut self, l2_block_height: u64) -> anyhow::Result<()> {
@@ -355,6 +455,16 @@ where
         Ok(ProverStorage::with_db_handles(state_db, native_db))
     }
 
+    fn create_finalized_snapshot(&mut self) -> anyhow::Result<Self::NativeStorage> {
+        self.latest_snapshot_id += 1;
+        let snapshot_id = self.latest_snapshot_id;
+        debug!("Giving 'finalized' snapshot with id {}", snapshot_id);
+        self.orphaned_snapshots.insert(snapshot_id);
+        Ok(self
+            .get_storage_with_snapshot_id(snapshot_id)?
+            .with_pinned_reads())
+    }
+
     fn save_change_set(
         &mut self,
         block_header: &Da::BlockHeader,
@@ -419,6 +529,52 @@ where
         let prev_block_hash = block_header.prev_hash();
         self.finalize_by_hash_pair(prev_block_hash, current_block_hash)
     }
+
+    fn prune_l2(&mut self, l2_block_height: u64) -> anyhow::Result<()> {
+        prune_l2_state(&self.state_snapshot_manager, l2_block_height)
+    }
+
+    fn l2_pruner(&self) -> Box<dyn FnOnce(u64) -> anyhow::Result<()> + Send> {
+        let state_snapshot_manager = self.state_snapshot_manager.clone();
+        Box::new(move |l2_block_height| prune_l2_state(&state_snapshot_manager, l2_block_height))
+    }
+
+    fn get_last_finalized_l2_height(&self) -> anyhow::Result<Option<u64>> {
+        let state_manager = self.state_snapshot_manager.read().unwrap();
+        let version = StateDB::<SnapshotManager>::last_finalized_version(state_manager.db())?;
+        // State of l2 block at height `h` is written with version `h + 1`
+        Ok(version.and_then(|version| version.checked_sub(1)))
+    }
+
+    fn get_earliest_available_l2_height(&self) -> anyhow::Result<Option<u64>> {
+        let state_manager = self.state_snapshot_manager.read().unwrap();
+        let version = StateDB::<SnapshotManager>::earliest_available_version(state_manager.db())?;
+        // State of l2 block at height `h` is written with version `h + 1`
+        Ok(version.and_then(|version| version.checked_sub(1)))
+    }
+
+    fn create_checkpoint(&self, path: &std::path::Path) -> anyhow::Result<()> {
+        // Finalization holds write locks on both managers, so both dbs are checkpointed at the same l2 height
+        let state_manager = self.state_snapshot_manager.read().unwrap();
+        let native_manager = self.accessory_snapshot_manager.read().unwrap();
+        state_manager
+            .db()
+            .create_checkpoint(path.join(StateDB::<SnapshotManager>::DB_PATH_SUFFIX))?;
+        native_manager
+            .db()
+            .create_checkpoint(path.join(NativeDB::<SnapshotManager>::DB_PATH_SUFFIX))?;
+        Ok(())
+    }
+
+    fn try_catch_up_with_primary(&self) -> anyhow::Result<()> {
+        // Primary commits native state before state, so catching up in reverse order
+        // never exposes a state version without its accessory state
+        let state_manager = self.state_snapshot_manager.read().unwrap();
+        let native_manager = self.accessory_snapshot_manager.read().unwrap();
+        state_manager.db().try_catch_up_with_primary()?;
+        native_manager.db().try_catch_up_with_primary()?;
+        Ok(())
+    }
 }
 
 /// Creates orphan [`ProverStorage`] which just points directly to the underlying database for previous data
@@ -427,17 +583,47 @@ where
 pub fn new_orphan_storage<S: MerkleProofSpec>(
     path: impl AsRef<std::path::Path>,
 ) -> anyhow::Result<ProverStorage<S, SnapshotManager>> {
-    let state_db_raw = StateDB::<SnapshotManager>::setup_schema_db(path.as_ref())?;
+    let state_db_raw =
+        StateDB::<SnapshotManager>::setup_schema_db(path.as_ref(), &Default::default())?;
     let state_db_sm = Arc::new(RwLock::new(SnapshotManager::orphan(state_db_raw)));
     let state_db_snapshot = DbSnapshot::<SnapshotManager>::new(0, state_db_sm.into());
     let state_db = StateDB::with_db_snapshot(state_db_snapshot)?;
-    let native_db_raw = NativeDB::<SnapshotManager>::setup_schema_db(path.as_ref())?;
+    let native_db_raw =
+        NativeDB::<SnapshotManager>::setup_schema_db(path.as_ref(), &Default::default())?;
     let native_db_sm = Arc::new(RwLock::new(SnapshotManager::orphan(native_db_raw)));
     let native_db_snapshot = DbSnapshot::<SnapshotManager>::new(0, native_db_sm.into());
     let native_db = NativeDB::with_db_snapshot(native_db_snapshot)?;
     Ok(ProverStorage::with_db_handles(state_db, native_db))
 }
 
+/// Prunes the state below the l2 block height in batches. The state is locked for one batch
+/// at a time, so blocks are finalized while a long pass runs.
+fn prune_l2_state(
+    state_snapshot_manager: &RwLock<SnapshotManager>,
+    l2_block_height: u64,
+) -> anyhow::Result<()> {
+    // State of l2 block at height `h` is written with version `h + 1`
+    let version = l2_block_height + 1;
+    let mut pruned = 0;
+    loop {
+        let state_manager = state_snapshot_manager.read().unwrap();
+        let batch_pruned = StateDB::<SnapshotManager>::prune_stale_state(
+            state_manager.db(),
+            version,
+            PRUNING_BATCH_SIZE,
+        )?;
+        pruned += batch_pruned;
+        if batch_pruned < PRUNING_BATCH_SIZE {
+            break;
+        }
+    }
+    debug!(
+        "Pruned {} stale nodes and values below l2 block height={}",
+        pruned, l2_block_height
+    );
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use sov_mock_da::{MockBlockHeader, MockHash};
@@ -508,8 +694,10 @@ mod tests {
     }
 
     fn build_dbs(path: &std::path::Path) -> (sov_schema_db::DB, sov_schema_db::DB) {
-        let state_db = StateDB::<SnapshotManager>::setup_schema_db(path).unwrap();
-        let native_db = NativeDB::<SnapshotManager>::setup_schema_db(path).unwrap();
+        let state_db =
+            StateDB::<SnapshotManager>::setup_schema_db(path, &Default::default()).unwrap();
+        let native_db =
+            NativeDB::<SnapshotManager>::setup_schema_db(path, &Default::default()).unwrap();
 
         (state_db, native_db)
     }
@@ -844,6 +1032,177 @@ mod tests {
         assert!(storage_manager.is_empty());
     }
 
+    #[test]
+    fn l2_block_on_top_of_pending_parent() {
+        let tmpdir = tempfile::tempdir().unwrap();
+
+        let (state_db, native_db) = build_dbs(tmpdir.path());
+
+        let mut storage_manager =
+            ProverStorageManager::<Da, S>::with_db_handles(state_db, native_db);
+        assert!(storage_manager.is_empty());
+
+        let mut witness = ArrayWitness::default();
+
+        let storage_1 = storage_manager.create_storage_on_l2_height(1).unwrap();
+        // Storage on top of a snapshot that is not saved yet cannot be created
+        let result = storage_manager.create_storage_on_l2_height(2);
+        assert_eq!(
+            "Snapshot for previous l2 block has not been saved yet",
+            result.err().unwrap().to_string()
+        );
+        {
+            let mut state_operations = OrderedReadsAndWrites::default();
+            state_operations.ordered_writes.push(write_op(1, 1));
+            let mut native_operations = OrderedReadsAndWrites::default();
+            native_operations.ordered_writes.push(write_op(1, 10));
+            let (_, state_update, _) = storage_1
+                .compute_state_update(state_operations, &mut witness)
+                .unwrap();
+            storage_1.commit(&state_update, &native_operations);
+        }
+        storage_manager.save_change_set_l2(1, storage_1).unwrap();
+
+        // Block 2 sees block 1, which is not finalized yet
+        let storage_2 = storage_manager.create_storage_on_l2_height(2).unwrap();
+        assert_eq!(
+            Some(value_from(1).into()),
+            storage_2.get(&key_from(1).into(), None, &mut witness)
+        );
+        assert_eq!(
+            Some(value_from(10).into()),
+            storage_2.get_accessory(&key_from(1).into(), None)
+        );
+        {
+            let mut state_operations = OrderedReadsAndWrites::default();
+            state_operations.ordered_writes.push(write_op(2, 2));
+            let (_, state_update, _) = storage_2
+                .compute_state_update(state_operations, &mut witness)
+                .unwrap();
+            storage_2.commit(&state_update, &OrderedReadsAndWrites::default());
+        }
+        storage_manager.save_change_set_l2(2, storage_2).unwrap();
+
+        let result = storage_manager.finalize_l2(2);
+        assert_eq!(
+            "Attempt to finalize l2 block at height=2 before its parent",
+            result.err().unwrap().to_string()
+        );
+
+        storage_manager.finalize_l2(1).unwrap();
+        assert!(storage_manager
+            .snapshot_id_to_parent
+            .read()
+            .unwrap()
+            .is_empty());
+        storage_manager.finalize_l2(2).unwrap();
+        assert!(storage_manager.is_empty());
+
+        let storage_3 = storage_manager.create_storage_on_l2_height(3).unwrap();
+        assert_eq!(
+            Some(value_from(1).into()),
+            storage_3.get(&key_from(1).into(), None, &mut witness)
+        );
+        assert_eq!(
+            Some(value_from(2).into()),
+            storage_3.get(&key_from(2).into(), None, &mut witness)
+        );
+    }
+
+    #[test]
+    fn l2_state_cache_is_cleared_on_reexecution() {
+        let tmpdir = tempfile::tempdir().unwrap();
+
+        let (state_db, native_db) = build_dbs(tmpdir.path());
+
+        let mut storage_manager =
+            ProverStorageManager::<Da, S>::with_db_handles(state_db, native_db)
+                .with_state_cache(NonZeroUsize::new(10).unwrap());
+
+        let mut witness = ArrayWitness::default();
+
+        let storage_1 = storage_manager.create_storage_on_l2_height(1).unwrap();
+        assert_eq!(None, storage_1.get(&key_from(1).into(), None, &mut witness));
+        {
+            let mut state_operations = OrderedReadsAndWrites::default();
+            state_operations.ordered_writes.push(write_op(1, 1));
+            let (_, state_update, _) = storage_1
+                .compute_state_update(state_operations, &mut witness)
+                .unwrap();
+            storage_1.commit(&state_update, &OrderedReadsAndWrites::default());
+        }
+        storage_manager.save_change_set_l2(1, storage_1).unwrap();
+        storage_manager.finalize_l2(1).unwrap();
+
+        // Written value is not served from the cache
+        let storage_2 = storage_manager.create_storage_on_l2_height(2).unwrap();
+        assert_eq!(
+            Some(value_from(1).into()),
+            storage_2.get(&key_from(1).into(), None, &mut witness)
+        );
+        {
+            let mut state_operations = OrderedReadsAndWrites::default();
+            state_operations.ordered_writes.push(write_op(1, 2));
+            let (_, state_update, _) = storage_2
+                .compute_state_update(state_operations, &mut witness)
+                .unwrap();
+            storage_2.commit(&state_update, &OrderedReadsAndWrites::default());
+        }
+        assert_eq!(
+            Some(value_from(2).into()),
+            storage_2.get(&key_from(1).into(), None, &mut witness)
+        );
+        // Block 2 is discarded and executed again
+        drop(storage_2);
+
+        let storage_2 = storage_manager.create_storage_on_l2_height(2).unwrap();
+        assert_eq!(
+            Some(value_from(1).into()),
+            storage_2.get(&key_from(1).into(), None, &mut witness)
+        );
+    }
+
+    #[test]
+    fn finalized_snapshot_is_not_changed_by_later_blocks() {
+        let tmpdir = tempfile::tempdir().unwrap();
+
+        let (state_db, native_db) = build_dbs(tmpdir.path());
+
+        let mut storage_manager =
+            ProverStorageManager::<Da, S>::with_db_handles(state_db, native_db)
+                .with_state_cache(NonZeroUsize::new(10).unwrap());
+
+        let mut witness = ArrayWitness::default();
+
+        let mut snapshot = None;
+        for height in 1..=2 {
+            let storage = storage_manager.create_storage_on_l2_height(height).unwrap();
+            let mut state_operations = OrderedReadsAndWrites::default();
+            state_operations.ordered_writes.push(write_op(1, height));
+            let (_, state_update, _) = storage
+                .compute_state_update(state_operations, &mut witness)
+                .unwrap();
+            storage.commit(&state_update, &OrderedReadsAndWrites::default());
+            storage_manager.save_change_set_l2(height, storage).unwrap();
+            storage_manager.finalize_l2(height).unwrap();
+
+            if height == 1 {
+                snapshot = Some(storage_manager.create_finalized_snapshot().unwrap());
+            }
+        }
+
+        let snapshot = snapshot.unwrap();
+        assert_eq!(
+            Some(value_from(1).into()),
+            snapshot.get(&key_from(1).into(), None, &mut witness)
+        );
+        let storage_3 = storage_manager.create_storage_on_l2_height(3).unwrap();
+        assert_eq!(
+            Some(value_from(2).into()),
+            storage_3.get(&key_from(1).into(), None, &mut witness)
+        );
+    }
+
     #[test]
     fn parallel_forks() {
         let tmpdir = tempfile::tempdir().unwrap();
@@ -972,6 +1331,210 @@ mod tests {
         assert!(storage_manager.is_empty());
     }
 
+    #[test]
+    fn pruning_records_earliest_available_height() {
+        let tmpdir = tempfile::tempdir().unwrap();
+
+        let (state_db, native_db) = build_dbs(tmpdir.path());
+
+        let mut storage_manager =
+            ProverStorageManager::<Da, S>::with_db_handles(state_db, native_db);
+
+        let block_a = MockBlockHeader {
+            prev_hash: MockHash::from([0; 32]),
+            hash: MockHash::from([1; 32]),
+            txs_commitment: MockHash::from([42; 32]),
+            height: 1,
+            time: Time::now(),
+        };
+        let block_b = MockBlockHeader {
+            prev_hash: MockHash::from([1; 32]),
+            hash: MockHash::from([2; 32]),
+            txs_commitment: MockHash::from([43; 32]),
+            height: 2,
+            time: Time::now(),
+        };
+
+        let mut witness = ArrayWitness::default();
+        for (block, value) in [(&block_a, 1), (&block_b, 2)] {
+            let storage = storage_manager.create_storage_on(block).unwrap();
+            let mut state_operations = OrderedReadsAndWrites::default();
+            state_operations.ordered_writes.push(write_op(1, value));
+            let (_, state_update, _) = storage
+                .compute_state_update(state_operations, &mut witness)
+                .unwrap();
+            storage.commit(&state_update, &OrderedReadsAndWrites::default());
+            storage_manager.save_change_set(block, storage).unwrap();
+            storage_manager.finalize(block).unwrap();
+        }
+
+        assert_eq!(
+            None,
+            storage_manager.get_earliest_available_l2_height().unwrap()
+        );
+        assert_eq!(
+            None,
+            storage_manager
+                .create_finalized_storage()
+                .unwrap()
+                .earliest_available_version()
+        );
+
+        storage_manager.prune_l2(1).unwrap();
+        assert_eq!(
+            Some(1),
+            storage_manager.get_earliest_available_l2_height().unwrap()
+        );
+        assert_eq!(
+            Some(2),
+            storage_manager
+                .create_finalized_storage()
+                .unwrap()
+                .earliest_available_version()
+        );
+
+        // Pruning below the recorded height does not make pruned state available again
+        storage_manager.prune_l2(0).unwrap();
+        assert_eq!(
+            Some(1),
+            storage_manager.get_earliest_available_l2_height().unwrap()
+        );
+    }
+
+    #[test]
+    fn pruning_deletes_overwritten_state() {
+        use sov_db::schema::tables::{JmtNodes, JmtValues};
+
+        let tmpdir = tempfile::tempdir().unwrap();
+
+        let (state_db, native_db) = build_dbs(tmpdir.path());
+
+        let mut storage_manager =
+            ProverStorageManager::<Da, S>::with_db_handles(state_db, native_db);
+
+        let mut witness = ArrayWitness::default();
+        for height in 1..=3u8 {
+            let block = MockBlockHeader {
+                prev_hash: MockHash::from([height - 1; 32]),
+                hash: MockHash::from([height; 32]),
+                txs_commitment: MockHash::from([42; 32]),
+                height: height as u64,
+                time: Time::now(),
+            };
+            let storage = storage_manager.create_storage_on(&block).unwrap();
+            let mut state_operations = OrderedReadsAndWrites::default();
+            // Key 1 is overwritten in every block, key 2 is only written once
+            state_operations
+                .ordered_writes
+                .push(write_op(1, height as u64));
+            if height == 1 {
+                state_operations.ordered_writes.push(write_op(2, 20));
+            }
+            let (_, state_update, _) = storage
+                .compute_state_update(state_operations, &mut witness)
+                .unwrap();
+            storage.commit(&state_update, &OrderedReadsAndWrites::default());
+            storage_manager.save_change_set(&block, storage).unwrap();
+            storage_manager.finalize(&block).unwrap();
+        }
+
+        let count_nodes = |storage_manager: &ProverStorageManager<Da, S>| {
+            let state_manager = storage_manager.state_snapshot_manager.read().unwrap();
+            let mut iter = state_manager.db().iter::<JmtNodes>().unwrap();
+            iter.seek_to_first();
+            iter.count()
+        };
+        let value_at = |storage_manager: &ProverStorageManager<Da, S>, key: u64, version| {
+            let state_manager = storage_manager.state_snapshot_manager.read().unwrap();
+            state_manager
+                .db()
+                .get::<JmtValues>(&(key_from(key).key.to_vec(), version))
+                .unwrap()
+        };
+        let nodes_before_pruning = count_nodes(&storage_manager);
+
+        // State of version 2 and later is kept
+        storage_manager.prune_l2(1).unwrap();
+        assert!(count_nodes(&storage_manager) < nodes_before_pruning);
+        assert_eq!(value_at(&storage_manager, 1, 1), None);
+        assert_eq!(
+            value_at(&storage_manager, 1, 2),
+            Some(Some(value_from(2).value.to_vec()))
+        );
+        assert_eq!(
+            value_at(&storage_manager, 1, 3),
+            Some(Some(value_from(3).value.to_vec()))
+        );
+        // Values which are not overwritten are kept
+        assert_eq!(
+            value_at(&storage_manager, 2, 1),
+            Some(Some(value_from(20).value.to_vec()))
+        );
+
+        let storage = storage_manager.create_finalized_storage().unwrap();
+        assert_eq!(
+            Some(value_from(3).into()),
+            storage.get(&key_from(1).into(), None, &mut witness)
+        );
+        assert_eq!(
+            Some(value_from(20).into()),
+            storage.get(&key_from(2).into(), None, &mut witness)
+        );
+    }
+
+    #[test]
+    fn pruning_in_batches_raises_earliest_version_per_batch() {
+        let tmpdir = tempfile::tempdir().unwrap();
+
+        let (state_db, native_db) = build_dbs(tmpdir.path());
+
+        let mut storage_manager =
+            ProverStorageManager::<Da, S>::with_db_handles(state_db, native_db);
+
+        let mut witness = ArrayWitness::default();
+        for height in 1..=4u8 {
+            let block = MockBlockHeader {
+                prev_hash: MockHash::from([height - 1; 32]),
+                hash: MockHash::from([height; 32]),
+                txs_commitment: MockHash::from([42; 32]),
+                height: height as u64,
+                time: Time::now(),
+            };
+            let storage = storage_manager.create_storage_on(&block).unwrap();
+            let mut state_operations = OrderedReadsAndWrites::default();
+            state_operations
+                .ordered_writes
+                .push(write_op(1, height as u64));
+            let (_, state_update, _) = storage
+                .compute_state_update(state_operations, &mut witness)
+                .unwrap();
+            storage.commit(&state_update, &OrderedReadsAndWrites::default());
+            storage_manager.save_change_set(&block, storage).unwrap();
+            storage_manager.finalize(&block).unwrap();
+        }
+
+        let state_manager = storage_manager.state_snapshot_manager.read().unwrap();
+        let db = state_manager.db();
+        let mut earliest_versions = vec![];
+        loop {
+            let pruned = StateDB::<SnapshotManager>::prune_stale_state(db, 4, 1).unwrap();
+            let earliest_version = StateDB::<SnapshotManager>::earliest_available_version(db)
+                .unwrap()
+                .unwrap();
+            assert!(earliest_version <= 4);
+            earliest_versions.push(earliest_version);
+            if pruned < 1 {
+                break;
+            }
+        }
+
+        // Each batch only deleted one entry, the versions it made unreadable are recorded
+        assert!(earliest_versions.len() > 2);
+        assert!(earliest_versions.windows(2).all(|w| w[0] <= w[1]));
+        assert!(earliest_versions[0] < 4);
+        assert_eq!(earliest_versions.last(), Some(&4));
+    }
+
     #[test]
     fn lifecycle_simulation() {
         let tmpdir = tempfile::tempdir().unwrap();
@@ -68,6 +68,10 @@ impl SnapshotManager {
         self.snapshots.is_empty()
     }
 
+    pub(crate) fn db(&self) -> &sov_schema_db::DB {
+        &self.db
+    }
+
     pub(crate) fn contains_snapshot(&self, snapshot_id: &SnapshotId) -> bool {
         self.snapshots.contains_key(snapshot_id)
     }
@@ -0,0 +1,306 @@
+use std::borrow::Cow;
+
+use futures::future::{ready, Either, Ready};
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::server::MethodResponse;
+use jsonrpsee::types::error::INTERNAL_ERROR_CODE;
+use jsonrpsee::types::{ErrorObjectOwned, Request};
+use serde_json::value::RawValue;
+use serde_json::Value;
+use sov_db::ledger_db::SharedLedgerOps;
+
+/// Methods taking a block tag, by the position of the parameter
+const BLOCK_TAG_PARAMS: &[(&str, usize)] = &[
+    ("eth_getBlockByNumber", 0),
+    ("eth_getBlockTransactionCountByNumber", 0),
+    ("eth_getTransactionByBlockNumberAndIndex", 0),
+    ("eth_getBlockReceipts", 0),
+    ("debug_traceBlockByNumber", 0),
+    ("eth_getBalance", 1),
+    ("eth_getTransactionCount", 1),
+    ("eth_getCode", 1),
+    ("eth_call", 1),
+    ("eth_estimateGas", 1),
+    ("eth_estimateDiffSize", 1),
+    ("eth_createAccessList", 1),
+    ("eth_feeHistory", 1),
+    ("eth_getStorageAt", 2),
+];
+
+/// Methods taking a log filter, whose `fromBlock` and `toBlock` can be block tags
+const FILTER_METHODS: &[&str] = &["eth_getLogs"];
+
+/// Methods whose `pending` tag stands for the block the sequencer is building,
+/// by the method serving them with the rest of their params
+const PENDING_BLOCK_METHODS: &[(&str, &str)] = &[
+    ("eth_getBlockByNumber", "citrea_getPendingBlock"),
+    (
+        "eth_getBlockTransactionCountByNumber",
+        "citrea_getPendingBlockTransactionCount",
+    ),
+    (
+        "eth_getTransactionByBlockNumberAndIndex",
+        "citrea_getPendingTransactionByIndex",
+    ),
+    (
+        "eth_getTransactionCount",
+        "citrea_getPendingTransactionCount",
+    ),
+];
+
+/// Methods of executed blocks, the pending block is only executed once it is built
+const EXECUTED_BLOCK_METHODS: &[&str] = &["eth_getBlockReceipts", "debug_traceBlockByNumber"];
+
+/// Code of the unknown block errors of the EVM RPC
+const UNKNOWN_BLOCK_ERROR_CODE: i32 = -32001;
+
+/// RPC middleware which resolves the `safe` and `finalized` block tags to L2 heights,
+/// and routes the `pending` tag to the block the sequencer is building,
+/// so all block and state methods of the EVM RPC agree on them.
+///
+/// `latest` is the newest soft confirmation, `safe` the newest L2 block committed to DA
+/// by the sequencer, and `finalized` the newest one covered by a verified ZK proof.
+/// `pending` is the block the sequencer builds next from its mempool. Its txs are
+/// executed once it is built, so state methods see the state it is built on, the
+/// latest one, except for the nonces, which follow the txs of the senders in the block.
+#[derive(Debug, Clone)]
+pub struct BlockTagLayer<DB> {
+    ledger_db: DB,
+}
+
+impl<DB> BlockTagLayer<DB> {
+    /// Resolves the block tags with the L2 heights tracked in the ledger db of the node
+    pub fn new(ledger_db: DB) -> Self {
+        Self { ledger_db }
+    }
+}
+
+impl<S, DB: Clone> tower::Layer<S> for BlockTagLayer<DB> {
+    type Service = BlockTagService<S, DB>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        BlockTagService {
+            service,
+            ledger_db: self.ledger_db.clone(),
+        }
+    }
+}
+
+/// Service of the [`BlockTagLayer`]
+#[derive(Debug, Clone)]
+pub struct BlockTagService<S, DB> {
+    service: S,
+    ledger_db: DB,
+}
+
+impl<'a, S, DB> RpcServiceT<'a> for BlockTagService<S, DB>
+where
+    S: RpcServiceT<'a>,
+    DB: SharedLedgerOps,
+{
+    type Future = Either<S::Future, Ready<MethodResponse>>;
+
+    fn call(&self, mut request: Request<'a>) -> Self::Future {
+        let Some(params) = &request.params else {
+            return Either::Left(self.service.call(request));
+        };
+        match resolve_block_tags(&self.ledger_db, request.method_name(), params.get()) {
+            Ok(Some(resolved)) => {
+                if let Some(method) = resolved.method {
+                    request.method = Cow::Borrowed(method);
+                }
+                request.params = Some(Cow::Owned(resolved.params));
+            }
+            Ok(None) => {}
+            Err(e) => return Either::Right(ready(MethodResponse::error(request.id, e))),
+        }
+        Either::Left(self.service.call(request))
+    }
+}
+
+/// A request with its block tags resolved
+#[derive(Debug)]
+struct ResolvedRequest {
+    /// Method serving the request instead, if it is for the pending block
+    method: Option<&'static str>,
+    params: Box<RawValue>,
+}
+
+/// Params of a request with their `safe` and `finalized` tags replaced by L2 heights,
+/// or without their `pending` tag for the method serving the pending block,
+/// `None` if they have no such tags
+fn resolve_block_tags(
+    ledger_db: &impl SharedLedgerOps,
+    method: &str,
+    params: &str,
+) -> Result<Option<ResolvedRequest>, ErrorObjectOwned> {
+    let position = BLOCK_TAG_PARAMS
+        .iter()
+        .find(|(name, _)| *name == method)
+        .map(|(_, position)| *position);
+    let is_filter = FILTER_METHODS.contains(&method);
+    if position.is_none() && !is_filter {
+        return Ok(None);
+    }
+    // Invalid params are left to the method to reject
+    let Ok(Value::Array(mut params)) = serde_json::from_str(params) else {
+        return Ok(None);
+    };
+
+    let mut pending_method = None;
+    let mut resolved = false;
+    if let Some(position) = position.filter(|position| *position < params.len()) {
+        if params[position].as_str() == Some("pending") {
+            if EXECUTED_BLOCK_METHODS.contains(&method) {
+                return Err(ErrorObjectOwned::owned(
+                    UNKNOWN_BLOCK_ERROR_CODE,
+                    "pending block is not executed yet",
+                    None::<()>,
+                ));
+            }
+            pending_method = PENDING_BLOCK_METHODS
+                .iter()
+                .find(|(name, _)| *name == method)
+                .map(|(_, pending_method)| *pending_method);
+            if pending_method.is_some() {
+                params.remove(position);
+                resolved = true;
+            }
+        } else {
+            resolved |= resolve_block_tag(ledger_db, &mut params[position])?;
+        }
+    }
+    if is_filter {
+        if let Some(Value::Object(filter)) = params.get_mut(0) {
+            for key in ["fromBlock", "toBlock"] {
+                if let Some(tag) = filter.get_mut(key) {
+                    resolved |= resolve_block_tag(ledger_db, tag)?;
+                }
+            }
+        }
+    }
+    if !resolved {
+        return Ok(None);
+    }
+
+    serde_json::value::to_raw_value(&params)
+        .map(|params| {
+            Some(ResolvedRequest {
+                method: pending_method,
+                params,
+            })
+        })
+        .map_err(|e| ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, e.to_string(), None::<()>))
+}
+
+/// Replaces a `safe` or `finalized` tag by the L2 height it stands for,
+/// fails if there is no such L2 height yet
+fn resolve_block_tag(
+    ledger_db: &impl SharedLedgerOps,
+    tag: &mut Value,
+) -> Result<bool, ErrorObjectOwned> {
+    let (name, l2_height) = match tag.as_str() {
+        Some("safe") => ("safe", ledger_db.get_safe_l2_height()),
+        Some("finalized") => ("finalized", ledger_db.get_finalized_l2_height()),
+        _ => return Ok(false),
+    };
+    let l2_height = l2_height
+        .map_err(|e| ErrorObjectOwned::owned(INTERNAL_ERROR_CODE, e.to_string(), None::<()>))?
+        .ok_or_else(|| {
+            ErrorObjectOwned::owned(
+                UNKNOWN_BLOCK_ERROR_CODE,
+                format!("{} block not found", name),
+                None::<()>,
+            )
+        })?;
+
+    *tag = format!("0x{:x}", l2_height.0).into();
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use sov_db::ledger_db::{LedgerDB, NodeLedgerOps, SequencerLedgerOps};
+    use sov_db::schema::types::BatchNumber;
+
+    use super::*;
+
+    #[test]
+    fn test_resolves_safe_and_finalized_tags() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+
+        let params = r#"["0x0000000000000000000000000000000000000001","finalized"]"#;
+        let err = resolve_block_tags(&ledger_db, "eth_getBalance", params).unwrap_err();
+        assert_eq!(err.code(), UNKNOWN_BLOCK_ERROR_CODE);
+        assert_eq!(err.message(), "finalized block not found");
+
+        ledger_db
+            .set_last_proven_l2_height(BatchNumber(26))
+            .unwrap();
+        ledger_db
+            .set_last_sequencer_commitment_l2_height(BatchNumber(30))
+            .unwrap();
+
+        let resolved = resolve_block_tags(&ledger_db, "eth_getBalance", params)
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.method, None);
+        assert_eq!(
+            resolved.params.get(),
+            r#"["0x0000000000000000000000000000000000000001","0x1a"]"#
+        );
+
+        let params = r#"[{"fromBlock":"finalized","toBlock":"safe"}]"#;
+        let resolved = resolve_block_tags(&ledger_db, "eth_getLogs", params)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            resolved.params.get(),
+            r#"[{"fromBlock":"0x1a","toBlock":"0x1e"}]"#
+        );
+
+        // Other tags and methods are left as they are
+        assert!(
+            resolve_block_tags(&ledger_db, "eth_getBlockByNumber", r#"["latest",false]"#)
+                .unwrap()
+                .is_none()
+        );
+        assert!(resolve_block_tags(&ledger_db, "eth_chainId", r#"["safe"]"#)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_routes_pending_tag_to_the_pending_block() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+
+        let resolved =
+            resolve_block_tags(&ledger_db, "eth_getBlockByNumber", r#"["pending",true]"#)
+                .unwrap()
+                .unwrap();
+        assert_eq!(resolved.method, Some("citrea_getPendingBlock"));
+        assert_eq!(resolved.params.get(), "[true]");
+
+        let params = r#"["0x0000000000000000000000000000000000000001","pending"]"#;
+        let resolved = resolve_block_tags(&ledger_db, "eth_getTransactionCount", params)
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.method, Some("citrea_getPendingTransactionCount"));
+        assert_eq!(
+            resolved.params.get(),
+            r#"["0x0000000000000000000000000000000000000001"]"#
+        );
+
+        // The pending block has no receipts before it is built
+        let err =
+            resolve_block_tags(&ledger_db, "eth_getBlockReceipts", r#"["pending"]"#).unwrap_err();
+        assert_eq!(err.code(), UNKNOWN_BLOCK_ERROR_CODE);
+
+        // State methods see the state the pending block is built on
+        assert!(resolve_block_tags(&ledger_db, "eth_getBalance", params)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,22 +1,189 @@
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
 
+use anyhow::Context as _;
 use serde::de::DeserializeOwned;
 use serde::Deserialize;
 use shared_backup_db::SharedBackupDbConfig;
+use sov_db::rocks_db_config::RocksdbConfig;
 
 use crate::ProverGuestRunConfig;
 
+/// Sequencer endpoints of a node, the first one is preferred
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "OneOrMany")]
+pub struct SequencerClientUrls(Vec<String>);
+
+impl SequencerClientUrls {
+    /// The preferred endpoint
+    pub fn primary(&self) -> &str {
+        &self.0[0]
+    }
+
+    /// All endpoints, in order of preference
+    pub fn urls(&self) -> &[String] {
+        &self.0
+    }
+}
+
+impl From<String> for SequencerClientUrls {
+    fn from(url: String) -> Self {
+        Self(vec![url])
+    }
+}
+
+/// A url, or a list of them
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl TryFrom<OneOrMany> for SequencerClientUrls {
+    type Error = &'static str;
+
+    fn try_from(urls: OneOrMany) -> Result<Self, Self::Error> {
+        match urls {
+            OneOrMany::One(url) => Ok(Self(vec![url])),
+            OneOrMany::Many(urls) if urls.is_empty() => {
+                Err("at least one sequencer client url is required")
+            }
+            OneOrMany::Many(urls) => Ok(Self(urls)),
+        }
+    }
+}
+
 /// Runner configuration.
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct RunnerConfig {
-    /// Sequencer client configuration.
-    pub sequencer_client_url: String,
-    /// Saves sequencer soft batches if set to true
+    /// Sequencer endpoints, a url or a list of urls.
+    /// Full nodes fail over to the next endpoints of a list when the first one is unhealthy.
+    pub sequencer_client_url: SequencerClientUrls,
+    /// Saves the transaction bodies of sequencer soft batches if set to true.
+    /// EVM receipts and logs are stored either way, soft batch RPCs fail with
+    /// `L2_BODY_UNAVAILABLE` for soft batches without bodies.
     pub include_tx_body: bool,
     /// Only true for tests
     pub accept_public_input_as_proven: Option<bool>,
+    /// Pruning configuration of the node's databases
+    #[serde(default)]
+    pub pruning_config: PruningConfig,
+    /// Proving strategy of the prover, which public inputs are checked against
+    #[serde(default)]
+    pub proving_strategy: ProvingStrategy,
+    /// Reorg monitoring of the DA layer, disabled if not set
+    #[serde(default)]
+    pub da_monitor: Option<DaMonitorConfig>,
+    /// Bridge watchtower, disabled if not set
+    #[serde(default)]
+    pub watchtower: Option<WatchtowerConfig>,
+    /// Executes independent EVM transactions of a soft batch in parallel.
+    /// Full nodes only, state is read out of execution order so the recorded witnesses can not be proven.
+    #[serde(default)]
+    pub parallel_evm_execution: bool,
+    /// Directory the inputs of L2 blocks whose state root does not match are recorded to,
+    /// the `diagnostics` directory under the storage path if not set
+    #[serde(default)]
+    pub diagnostics_dir: Option<PathBuf>,
+    /// Number of L1 blocks after the L1 block of a sequencer commitment during which it is
+    /// challengeable, unless a proof covers it first. Challenge windows are not tracked if not set.
+    #[serde(default)]
+    pub challenge_window: Option<u64>,
+    /// Head-first sync: L2 blocks missing below the oldest stored one, e.g. after restoring
+    /// a backup of a pruned node, are fetched from the sequencer in the background.
+    /// Ledger RPCs fail with `L2_HEIGHT_UNAVAILABLE` for L2 heights which are not backfilled yet.
+    /// Only allowed in the archive pruning mode.
+    #[serde(default)]
+    pub backfill_history: bool,
+    /// Directory `citrea_createBackup` writes backups to. Backups are only served by the
+    /// operator RPC server, and can not be created if not set.
+    #[serde(default)]
+    pub backup_dir: Option<PathBuf>,
+}
+
+/// Configuration of the DA reorg monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct DaMonitorConfig {
+    /// Number of L1 blocks after which a block is considered final.
+    /// A reorg replacing more blocks halts the acceptance of sequencer commitments.
+    pub finality_depth: u64,
+    /// Seconds between two polls of the L1 head
+    #[serde(default = "default_da_monitor_poll_interval")]
+    pub poll_interval: u64,
+    /// A reorg replacing more blocks halts the execution of L2 blocks and persists a report of it.
+    /// Execution stays halted, also across restarts, until it is resumed with `citrea_resumeAfterReorg`,
+    /// which is only served by the operator RPC server. Execution is never halted if not set.
+    #[serde(default)]
+    pub max_handled_reorg_depth: Option<u64>,
+}
+
+#[inline]
+const fn default_da_monitor_poll_interval() -> u64 {
+    10
+}
+
+/// Configuration of the bridge watchtower.
+///
+/// The watchtower cross-checks the deposits and withdrawals of the Bridge contract
+/// against the withdrawal queue, and the withdrawal roots of the proofs and the withdrawal
+/// claims posted on L1 against the withdrawals of the L2 blocks.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct WatchtowerConfig {
+    /// URL the alerts are posted to as JSON, alerts are only logged if not set
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+/// Retention policy of historical state and ledger data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum PruningMode {
+    /// Keep everything.
+    Archive,
+    /// Keep the last `distance` L2 heights below the latest proven height.
+    Full {
+        /// Number of proven L2 heights to retain
+        distance: u64,
+    },
+    /// Keep nothing below the latest proven height.
+    Minimal,
+}
+
+/// Pruning configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct PruningConfig {
+    /// Retention policy
+    #[serde(flatten)]
+    pub mode: PruningMode,
+    /// Seconds between two pruning rounds
+    #[serde(default = "default_pruning_interval")]
+    pub interval: u64,
+    /// Number of proven L2 heights for which transaction bodies and witnesses are retained.
+    /// Soft confirmation headers and state roots are kept regardless.
+    /// Bodies are never pruned if not set.
+    #[serde(default)]
+    pub body_retention: Option<u64>,
+}
+
+impl PruningConfig {
+    /// Returns true if any data is ever pruned
+    pub fn is_enabled(&self) -> bool {
+        self.mode != PruningMode::Archive || self.body_retention.is_some()
+    }
+}
+
+impl Default for PruningConfig {
+    fn default() -> Self {
+        Self {
+            mode: PruningMode::Archive,
+            interval: default_pruning_interval(),
+            body_retention: None,
+        }
+    }
 }
 
 /// RPC configuration.
@@ -45,6 +212,79 @@ pub struct RpcConfig {
     /// Maximum number of subscription connections
     #[serde(default = "default_max_subscriptions_per_connection")]
     pub max_subscriptions_per_connection: u32,
+    /// Max. number of `debug_traceTransaction` results cached in the ledger db,
+    /// the least recently used ones are evicted first. Disabled if not set.
+    #[serde(default)]
+    pub trace_cache_size: Option<u32>,
+    /// Operator RPC server. If set, only the public namespaces are served by this server.
+    #[serde(default)]
+    pub operator: Option<OperatorRpcConfig>,
+    /// Serves the operator methods, like `citrea_shutdown`, on this server if no operator server
+    /// is configured. Only meant for local development and tests, off by default.
+    #[serde(default)]
+    pub public_operator_methods: bool,
+    /// Full nodes look up the transactions they have not synced yet in the mempool of the sequencer,
+    /// and return them as pending transactions.
+    #[serde(default = "default_sequencer_tx_fallback")]
+    pub sequencer_tx_fallback: bool,
+    /// Milliseconds during which a transaction the sequencer did not know is not looked up again
+    #[serde(default = "default_sequencer_tx_fallback_negative_cache_ms")]
+    pub sequencer_tx_fallback_negative_cache_ms: u64,
+    /// Milliseconds after which requests are cancelled, by method name.
+    #[serde(default)]
+    pub method_timeouts_ms: HashMap<String, u64>,
+    /// Milliseconds after which requests of the methods without a timeout in `method_timeouts_ms`
+    /// are cancelled. Requests do not time out if not set.
+    #[serde(default)]
+    pub default_method_timeout_ms: Option<u64>,
+}
+
+#[inline]
+const fn default_sequencer_tx_fallback() -> bool {
+    true
+}
+
+#[inline]
+const fn default_sequencer_tx_fallback_negative_cache_ms() -> u64 {
+    1000
+}
+
+/// Configuration of the operator RPC server.
+///
+/// The operator server serves all methods, including the debug, admin and prover ones,
+/// and the server of [`RpcConfig`] only the methods of the public namespaces.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct OperatorRpcConfig {
+    /// RPC host.
+    pub bind_host: String,
+    /// RPC port.
+    pub bind_port: u16,
+    /// Maximum number of concurrent requests.
+    #[serde(default = "default_max_connections")]
+    pub max_connections: u32,
+    /// Max request body request
+    #[serde(default = "default_max_request_body_size")]
+    pub max_request_body_size: u32,
+    /// Max response body request
+    #[serde(default = "default_max_response_body_size")]
+    pub max_response_body_size: u32,
+    /// Maximum number of batch requests
+    #[serde(default = "default_batch_requests_limit")]
+    pub batch_requests_limit: u32,
+    /// Maximum number of subscription connections
+    #[serde(default = "default_max_subscriptions_per_connection")]
+    pub max_subscriptions_per_connection: u32,
+    /// Namespaces served by the public server. Methods without a namespace are public too,
+    /// the admin methods of the `citrea` namespace never are.
+    #[serde(default = "default_public_namespaces")]
+    pub public_namespaces: Vec<String>,
+}
+
+#[inline]
+fn default_public_namespaces() -> Vec<String> {
+    ["eth", "net", "web3", "citrea", "ledger", "txpool"]
+        .map(String::from)
+        .to_vec()
 }
 
 #[inline]
@@ -72,6 +312,66 @@ const fn default_sync_blocks_count() -> u64 {
     10
 }
 
+#[inline]
+const fn default_max_parallel_proving_jobs() -> usize {
+    1
+}
+
+#[inline]
+const fn default_remote_prover_retry_secs() -> u64 {
+    60
+}
+
+#[inline]
+const fn default_remote_prover_poll_interval_secs() -> u64 {
+    5
+}
+
+#[inline]
+const fn default_remote_prover_max_poll_interval_secs() -> u64 {
+    120
+}
+
+#[inline]
+const fn default_remote_prover_timeout_secs() -> u64 {
+    4 * 60 * 60
+}
+
+#[inline]
+const fn default_proof_inclusion_timeout_secs() -> u64 {
+    60 * 60
+}
+
+#[inline]
+const fn default_proof_fee_bump_percent() -> u64 {
+    50
+}
+
+#[inline]
+const fn default_max_proof_fee_bumps() -> u32 {
+    5
+}
+
+#[inline]
+const fn default_max_challenges_per_l1_block() -> usize {
+    4
+}
+
+#[inline]
+const fn default_max_challenge_depth() -> u64 {
+    1000
+}
+
+#[inline]
+const fn default_pruning_interval() -> u64 {
+    60
+}
+
+#[inline]
+const fn default_catch_up_interval_ms() -> u64 {
+    1000
+}
+
 #[inline]
 const fn default_enable_subscriptions() -> bool {
     true
@@ -87,6 +387,31 @@ const fn default_max_subscriptions_per_connection() -> u32 {
 pub struct StorageConfig {
     /// Path that can be utilized by concrete rollup implementation
     pub path: PathBuf,
+    /// RocksDB tuning of the ledger db
+    #[serde(default)]
+    pub ledger_db: RocksdbConfig,
+    /// RocksDB tuning of the state and native dbs
+    #[serde(default)]
+    pub state_db: RocksdbConfig,
+    /// If set, the node runs as a read-only RPC replica of the node owning `path`
+    #[serde(default)]
+    pub replica: Option<ReplicaConfig>,
+    /// Number of latest state values cached in memory across L2 blocks, disabled if not set.
+    /// Speeds up the sync of full nodes, which read the same hot accounts on every block.
+    #[serde(default)]
+    pub state_cache_size: Option<NonZeroUsize>,
+}
+
+/// Read-only RPC replica configuration.
+/// A replica opens the databases of a syncing node in secondary mode and only serves RPC.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ReplicaConfig {
+    /// Path where the replica keeps its own database files.
+    /// Must be different for each replica and must not be the storage path.
+    pub secondary_path: PathBuf,
+    /// Milliseconds between two catch ups with the primary node
+    #[serde(default = "default_catch_up_interval_ms")]
+    pub catch_up_interval_ms: u64,
 }
 
 /// Important public keys for the rollup
@@ -103,6 +428,58 @@ pub struct RollupPublicKeys {
     /// serialized as hex
     #[serde(with = "hex::serde")]
     pub prover_da_pub_key: Vec<u8>,
+    /// DA signing public keys the sequencer rotates to.
+    /// `sequencer_da_pub_key` is valid until the first activation height.
+    #[serde(default)]
+    pub sequencer_da_pub_key_rotations: Vec<DaPubKeyRotation>,
+}
+
+/// A DA signing public key the sequencer rotates to
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DaPubKeyRotation {
+    /// L1 height from which on blobs are signed with the key
+    pub activation_l1_height: u64,
+    /// serialized as hex
+    #[serde(with = "hex::serde")]
+    pub pub_key: Vec<u8>,
+}
+
+impl RollupPublicKeys {
+    /// DA signing public key of the sequencer valid at the L1 height
+    pub fn sequencer_da_pub_key_at(&self, l1_height: u64) -> &[u8] {
+        self.sequencer_da_pub_key_rotations
+            .iter()
+            .filter(|rotation| rotation.activation_l1_height <= l1_height)
+            .max_by_key(|rotation| rotation.activation_l1_height)
+            .map_or(&self.sequencer_da_pub_key, |rotation| &rotation.pub_key)
+    }
+
+    /// Whether the key is a DA signing public key of the sequencer at any L1 height
+    pub fn is_sequencer_da_pub_key(&self, pub_key: &[u8]) -> bool {
+        self.sequencer_da_pub_key == pub_key
+            || self
+                .sequencer_da_pub_key_rotations
+                .iter()
+                .any(|rotation| rotation.pub_key == pub_key)
+    }
+}
+
+/// Export of the tracing spans of the node to an OpenTelemetry collector
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TelemetryConfig {
+    /// Endpoint of the OTLP gRPC receiver of the collector, like `http://localhost:4317`
+    pub otlp_endpoint: String,
+    /// Share of the traces which are exported, between 0 and 1
+    #[serde(default = "default_sampling_ratio")]
+    pub sampling_ratio: f64,
+    /// Name the spans are exported with, defaults to the kind of the node like `citrea-sequencer`
+    #[serde(default)]
+    pub service_name: Option<String>,
+}
+
+#[inline]
+const fn default_sampling_ratio() -> f64 {
+    1.0
 }
 
 /// Rollup Configuration
@@ -118,9 +495,15 @@ pub struct FullNodeConfig<DaServiceConfig> {
     pub da: DaServiceConfig,
     /// Important pubkeys
     pub public_keys: RollupPublicKeys,
-    /// Number of blocks to request during sync
+    /// Initial number of blocks to request during sync. Adapted to sync throughput at runtime.
     #[serde(default = "default_sync_blocks_count")]
     pub sync_blocks_count: u64,
+    /// Log filter in the syntax of `RUST_LOG`, overrides the verbosity of the command line
+    #[serde(default)]
+    pub log_level: Option<String>,
+    /// OpenTelemetry export of the tracing spans, disabled if not set
+    #[serde(default)]
+    pub telemetry: Option<TelemetryConfig>,
 }
 
 /// Prover configuration
@@ -128,10 +511,228 @@ pub struct FullNodeConfig<DaServiceConfig> {
 pub struct ProverConfig {
     /// Prover run mode
     pub proving_mode: ProverGuestRunConfig,
-    /// Average number of commitments to prove
+    /// Average number of commitments to prove.
+    /// Only used if `proving_strategy` is not set, in which case 0 proves every commitment.
     pub proof_sampling_number: usize,
+    /// Which L1 blocks with sequencer commitments are proven
+    #[serde(default)]
+    pub proving_strategy: Option<ProvingStrategy>,
     /// Offchain db config
     pub db_config: Option<SharedBackupDbConfig>,
+    /// Max. number of L1 blocks proven at the same time.
+    /// Proofs are still sent to DA in L1 block order.
+    #[serde(default = "default_max_parallel_proving_jobs")]
+    pub max_parallel_proving_jobs: usize,
+    /// Max. total size in MB of the guest inputs proven at the same time,
+    /// which bounds the memory of the parallel proving sessions.
+    /// A job over the budget is still proven when no other job is.
+    #[serde(default)]
+    pub proving_memory_budget_mb: Option<usize>,
+    /// If set, the proofs of the L1 blocks proven in parallel are aggregated
+    /// into a single proof before being sent to DA.
+    #[serde(default)]
+    pub aggregate_proofs: bool,
+    /// zkVM backend the state transitions are proven with in the execute and prove modes
+    #[serde(default)]
+    pub proving_backend: ProvingBackendConfig,
+    /// If set, proving jobs are delegated to a remote proving service,
+    /// and only proven with `proving_backend` when the remote fails.
+    #[serde(default)]
+    pub remote_prover: Option<RemoteProverConfig>,
+    /// Hardware acceleration of local proving with the `risc0` backend
+    #[serde(default)]
+    pub acceleration: ProvingAccelerationConfig,
+    /// If set, the prover extends a light client proof over every L1 block and sends it to DA.
+    /// The light client proof attests the latest L2 state root proven on DA.
+    #[serde(default)]
+    pub light_client_proofs: bool,
+    /// If set, the prover waits for its proof transactions to be included on DA,
+    /// and replaces stalled ones with transactions paying a higher fee.
+    #[serde(default)]
+    pub proof_posting: Option<ProofPostingConfig>,
+    /// Limits on the proof challenges the prover answers
+    #[serde(default)]
+    pub proof_challenges: ProofChallengeConfig,
+}
+
+/// Limits on the proof challenges a prover answers.
+/// Anyone can post challenges, so they are limited to bound the cost of proving them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct ProofChallengeConfig {
+    /// Max. number of earlier L1 blocks proven for the challenges of a single L1 block.
+    /// The challenges posted first are answered.
+    #[serde(default = "default_max_challenges_per_l1_block")]
+    pub max_challenges_per_l1_block: usize,
+    /// Max. number of L1 blocks a challenged L1 block may be behind the L1 block
+    /// of the challenge. Challenges of older L1 blocks are ignored.
+    #[serde(default = "default_max_challenge_depth")]
+    pub max_challenge_depth: u64,
+}
+
+impl Default for ProofChallengeConfig {
+    fn default() -> Self {
+        Self {
+            max_challenges_per_l1_block: default_max_challenges_per_l1_block(),
+            max_challenge_depth: default_max_challenge_depth(),
+        }
+    }
+}
+
+impl ProofChallengeConfig {
+    /// Returns the earlier L1 heights challenged in the L1 block at `l1_height` which are
+    /// answered, in L1 order. `challenges` are in the order they were posted in.
+    pub fn answered_challenges(&self, l1_height: u64, challenges: &[u64]) -> Vec<u64> {
+        let mut answered: Vec<u64> = vec![];
+        for &challenged_l1_height in challenges {
+            if answered.len() == self.max_challenges_per_l1_block {
+                break;
+            }
+            if challenged_l1_height < l1_height
+                && l1_height - challenged_l1_height <= self.max_challenge_depth
+                && !answered.contains(&challenged_l1_height)
+            {
+                answered.push(challenged_l1_height);
+            }
+        }
+        answered.sort_unstable();
+        answered
+    }
+}
+
+/// Fee bumping of stalled proof transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct ProofPostingConfig {
+    /// Seconds a proof transaction may stay pending before it is replaced
+    #[serde(default = "default_proof_inclusion_timeout_secs")]
+    pub inclusion_timeout_secs: u64,
+    /// Percentage the fee rate is raised by with each replacement,
+    /// up to the maximum fee rate of the DA layer
+    #[serde(default = "default_proof_fee_bump_percent")]
+    pub fee_bump_percent: u64,
+    /// Max. number of replacements of a proof transaction, which is waited for after that.
+    /// A proof transaction dropped from the mempool is sent again.
+    #[serde(default = "default_max_proof_fee_bumps")]
+    pub max_fee_bumps: u32,
+}
+
+/// Hardware acceleration of local proving.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub struct ProvingAccelerationConfig {
+    /// Hardware the proofs are created on
+    #[serde(default)]
+    pub accelerator: Accelerator,
+    /// Log2 of the max. number of cycles of a segment, the zkVM default if not set.
+    /// Larger segments prove faster on GPUs with enough memory.
+    #[serde(default)]
+    pub segment_limit_po2: Option<u32>,
+    /// Number of threads proving the segments. All cores are used if not set.
+    #[serde(default)]
+    pub proving_threads: Option<usize>,
+}
+
+/// Hardware the prover creates proofs on.
+/// GPUs can only be used by binaries built with the matching feature,
+/// and such binaries always prove on the GPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Accelerator {
+    /// Prove on the CPU
+    #[default]
+    Cpu,
+    /// Prove on an NVIDIA GPU, requires the `cuda` feature
+    Cuda,
+    /// Prove on an Apple GPU, requires the `metal` feature
+    Metal,
+}
+
+/// Remote proving service configuration.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RemoteProverConfig {
+    /// JSON-RPC endpoint of the proving service
+    pub url: String,
+    /// Seconds a failing request is retried for before the remote is considered down
+    #[serde(default = "default_remote_prover_retry_secs")]
+    pub retry_secs: u64,
+    /// Seconds between the first two polls of a proving job, doubled after each poll
+    #[serde(default = "default_remote_prover_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// Max. seconds between two polls of a proving job
+    #[serde(default = "default_remote_prover_max_poll_interval_secs")]
+    pub max_poll_interval_secs: u64,
+    /// Seconds after which a proving job is proven locally instead
+    #[serde(default = "default_remote_prover_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+/// zkVM backend of the prover.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProvingBackendConfig {
+    /// Risc0, proven on Bonsai. The API is read from `BONSAI_API_URL` and `BONSAI_API_KEY`.
+    #[default]
+    Bonsai,
+    /// Risc0, proven locally
+    Risc0,
+    /// SP1, proven locally or on the SP1 prover network as picked by `SP1_PROVER`.
+    /// The SP1 builds of the guests are read from `SP1_ELF_PATH` and `SP1_AGGREGATION_ELF_PATH`.
+    /// Only available in builds with the `sp1` feature.
+    Sp1,
+    /// Runs the guest natively without executing it in a zkVM.
+    /// The outputs are sent as public inputs, which only test nodes accept.
+    Mock,
+}
+
+/// Which L1 blocks with sequencer commitments the prover proves.
+/// Challenged L1 blocks are proven regardless of the strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ProvingStrategy {
+    /// Prove every L1 block.
+    #[default]
+    All,
+    /// Prove one in `sampling_number` L1 blocks on average.
+    /// The L1 blocks are picked by their hashes, so every node picks the same ones.
+    Sampled {
+        /// Average number of L1 blocks per proven L1 block
+        sampling_number: u64,
+    },
+    /// Only prove challenged L1 blocks.
+    OnChallenge,
+}
+
+impl ProvingStrategy {
+    /// Whether the L1 block is proven even if it is not challenged
+    pub fn proves_unchallenged(&self, l1_hash: &[u8; 32]) -> bool {
+        match self {
+            ProvingStrategy::All => true,
+            ProvingStrategy::Sampled { sampling_number } => {
+                let seed = l1_hash.chunks_exact(8).fold(0u64, |seed, chunk| {
+                    seed ^ u64::from_le_bytes(chunk.try_into().expect("Chunk is 8 bytes"))
+                });
+                *sampling_number <= 1 || seed % sampling_number == 0
+            }
+            ProvingStrategy::OnChallenge => false,
+        }
+    }
+
+    /// Whether a prover following the strategy may prove the L1 block.
+    /// Any L1 block may be challenged, so only sampling rules out L1 blocks.
+    pub fn may_prove(&self, l1_hash: &[u8; 32]) -> bool {
+        matches!(self, ProvingStrategy::OnChallenge) || self.proves_unchallenged(l1_hash)
+    }
+}
+
+impl ProverConfig {
+    /// Returns the proving strategy, falling back to sampling by `proof_sampling_number`.
+    pub fn proving_strategy(&self) -> ProvingStrategy {
+        self.proving_strategy
+            .unwrap_or(match self.proof_sampling_number {
+                0 => ProvingStrategy::All,
+                n => ProvingStrategy::Sampled {
+                    sampling_number: n as u64,
+                },
+            })
+    }
 }
 
 impl Default for ProverConfig {
@@ -139,7 +740,17 @@ impl Default for ProverConfig {
         Self {
             proving_mode: ProverGuestRunConfig::Execute,
             proof_sampling_number: 0,
+            proving_strategy: None,
             db_config: None,
+            max_parallel_proving_jobs: default_max_parallel_proving_jobs(),
+            proving_memory_budget_mb: None,
+            aggregate_proofs: false,
+            proving_backend: ProvingBackendConfig::default(),
+            remote_prover: None,
+            acceleration: ProvingAccelerationConfig::default(),
+            light_client_proofs: false,
+            proof_posting: None,
+            proof_challenges: ProofChallengeConfig::default(),
         }
     }
 }
@@ -159,10 +770,140 @@ pub fn from_toml_path<P: AsRef<Path>, R: DeserializeOwned>(path: P) -> anyhow::R
     Ok(result)
 }
 
+/// Separator of the path segments in the names of environment variables overriding config fields
+pub const ENV_PATH_SEPARATOR: &str = "__";
+
+/// Loads a toml config file with overrides of its fields.
+///
+/// Fields are addressed by their dotted path, like `rpc.bind_port`. Overrides from the
+/// command line take precedence over environment variables like `CITREA__RPC__BIND_PORT`,
+/// which take precedence over the file.
+#[derive(Debug, Clone)]
+pub struct ConfigLoader {
+    path: PathBuf,
+    env_prefix: Option<String>,
+    overrides: Vec<(String, String)>,
+}
+
+impl ConfigLoader {
+    /// Loads the config file without overrides
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            env_prefix: None,
+            overrides: vec![],
+        }
+    }
+
+    /// Overrides fields with the environment variables starting with the prefix and `__`
+    pub fn with_env_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.env_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Overrides the field at the dotted path, after the environment variables
+    pub fn with_override(mut self, path: impl Into<String>, value: impl Into<String>) -> Self {
+        self.overrides.push((path.into(), value.into()));
+        self
+    }
+
+    /// Path of the config file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the config with all overrides applied
+    pub fn load<R: DeserializeOwned>(&self) -> anyhow::Result<R> {
+        self.load_value()?
+            .try_into()
+            .with_context(|| format!("Invalid config in {}", self.path.display()))
+    }
+
+    /// Reads the config with all overrides applied, without deserializing it
+    pub fn load_value(&self) -> anyhow::Result<toml::Value> {
+        let env_vars = match &self.env_prefix {
+            Some(prefix) => env_overrides(prefix, std::env::vars()),
+            None => vec![],
+        };
+        self.load_value_with_env(env_vars)
+    }
+
+    fn load_value_with_env(&self, env_vars: Vec<(String, String)>) -> anyhow::Result<toml::Value> {
+        let contents = std::fs::read_to_string(&self.path)
+            .with_context(|| format!("Failed to read config from {}", self.path.display()))?;
+        let mut value: toml::Value = toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse config from {}", self.path.display()))?;
+
+        for (path, raw) in env_vars.iter().chain(&self.overrides) {
+            set_field(&mut value, path, raw)
+                .with_context(|| format!("Failed to override config field {}", path))?;
+        }
+        Ok(value)
+    }
+}
+
+/// Dotted paths and values of the environment variables overriding config fields,
+/// `PREFIX__RPC__BIND_PORT` overrides `rpc.bind_port`
+fn env_overrides(
+    prefix: &str,
+    vars: impl IntoIterator<Item = (String, String)>,
+) -> Vec<(String, String)> {
+    let prefix = format!("{}{}", prefix, ENV_PATH_SEPARATOR);
+    let mut overrides: Vec<_> = vars
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let path = name.strip_prefix(&prefix)?;
+            let path = path
+                .split(ENV_PATH_SEPARATOR)
+                .map(str::to_lowercase)
+                .collect::<Vec<_>>()
+                .join(".");
+            Some((path, value))
+        })
+        .collect();
+    // Applied in a stable order, so a field overridden by several variables always gets the same value
+    overrides.sort();
+    overrides
+}
+
+/// Sets the field at the dotted path, creating missing tables.
+/// The value is parsed as toml, unless the field is a string or the value isn't valid toml.
+fn set_field(config: &mut toml::Value, path: &str, raw: &str) -> anyhow::Result<()> {
+    let mut segments: Vec<&str> = path.split('.').collect();
+    let Some(key) = segments.pop().filter(|key| !key.is_empty()) else {
+        anyhow::bail!("Empty field path");
+    };
+
+    let mut table = config
+        .as_table_mut()
+        .ok_or_else(|| anyhow::anyhow!("Config is not a table"))?;
+    for segment in segments {
+        table = table
+            .entry(segment)
+            .or_insert(toml::Value::Table(Default::default()))
+            .as_table_mut()
+            .ok_or_else(|| anyhow::anyhow!("{} is not a table", segment))?;
+    }
+
+    // Keys and addresses may look like numbers, so strings in the file stay strings
+    let value = match table.get(key) {
+        Some(toml::Value::String(_)) => toml::Value::String(raw.to_owned()),
+        _ => parse_toml_value(raw).unwrap_or_else(|| toml::Value::String(raw.to_owned())),
+    };
+    table.insert(key.to_owned(), value);
+    Ok(())
+}
+
+fn parse_toml_value(raw: &str) -> Option<toml::Value> {
+    let mut table: toml::Table = toml::from_str(&format!("value = {}", raw)).ok()?;
+    table.remove("value")
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write;
 
+    use sov_db::rocks_db_config::{ColumnFamilyConfig, CompactionStyle, Compression};
     use tempfile::NamedTempFile;
 
     use super::*;
@@ -188,6 +929,19 @@ mod tests {
             max_connections = 500
             enable_subscriptions = true
             max_subscriptions_per_connection = 200
+            trace_cache_size = 10000
+            sequencer_tx_fallback = false
+            sequencer_tx_fallback_negative_cache_ms = 500
+            default_method_timeout_ms = 10000
+
+            [rpc.method_timeouts_ms]
+            debug_traceBlockByNumber = 60000
+            eth_getLogs = 30000
+
+            [rpc.operator]
+            bind_host = "127.0.0.1"
+            bind_port = 12355
+            max_connections = 10
 
             [da]
             sender_address = "0000000000000000000000000000000000000000000000000000000000000000"
@@ -195,10 +949,43 @@ mod tests {
             
             [storage]
             path = "/tmp/rollup"
+            state_cache_size = 100000
+
+            [storage.ledger_db]
+            block_cache_size = 536870912
+
+            [storage.replica]
+            secondary_path = "/tmp/rollup-replica"
+
+            [storage.ledger_db.default_column_family]
+            compression = "zstd"
+
+            [storage.ledger_db.column_families.SoftBatchByNumber]
+            compaction_style = "universal"
+            write_buffer_size = 134217728
             
             [runner]
             include_tx_body = true
-            sequencer_client_url = "http://0.0.0.0:12346"
+            sequencer_client_url = ["http://0.0.0.0:12346", "http://0.0.0.0:12347"]
+            parallel_evm_execution = true
+            diagnostics_dir = "/tmp/diagnostics"
+            challenge_window = 144
+
+            [runner.pruning_config]
+            mode = "full"
+            distance = 1000
+            body_retention = 100
+
+            [runner.da_monitor]
+            finality_depth = 6
+            max_handled_reorg_depth = 12
+
+            [runner.watchtower]
+            webhook_url = "http://localhost:9000/alerts"
+
+            [telemetry]
+            otlp_endpoint = "http://localhost:4317"
+            sampling_ratio = 0.5
         "#.to_owned();
 
         let config_file = create_config_from(&config);
@@ -208,9 +995,31 @@ mod tests {
 
         let expected = FullNodeConfig {
             runner: Some(RunnerConfig {
-                sequencer_client_url: "http://0.0.0.0:12346".to_owned(),
+                sequencer_client_url: SequencerClientUrls(vec![
+                    "http://0.0.0.0:12346".to_owned(),
+                    "http://0.0.0.0:12347".to_owned(),
+                ]),
                 include_tx_body: true,
                 accept_public_input_as_proven: None,
+                proving_strategy: ProvingStrategy::default(),
+                pruning_config: PruningConfig {
+                    mode: PruningMode::Full { distance: 1000 },
+                    interval: 60,
+                    body_retention: Some(100),
+                },
+                da_monitor: Some(DaMonitorConfig {
+                    finality_depth: 6,
+                    poll_interval: 10,
+                    max_handled_reorg_depth: Some(12),
+                }),
+                watchtower: Some(WatchtowerConfig {
+                    webhook_url: Some("http://localhost:9000/alerts".to_owned()),
+                }),
+                parallel_evm_execution: true,
+                diagnostics_dir: Some("/tmp/diagnostics".into()),
+                challenge_window: Some(144),
+                backfill_history: false,
+                backup_dir: None,
             }),
             da: sov_mock_da::MockDaConfig {
                 sender_address: [0; 32].into(),
@@ -218,6 +1027,29 @@ mod tests {
             },
             storage: StorageConfig {
                 path: "/tmp/rollup".into(),
+                ledger_db: RocksdbConfig {
+                    block_cache_size: Some(512 * 1024 * 1024),
+                    default_column_family: ColumnFamilyConfig {
+                        compression: Some(Compression::Zstd),
+                        ..Default::default()
+                    },
+                    column_families: [(
+                        "SoftBatchByNumber".to_owned(),
+                        ColumnFamilyConfig {
+                            compaction_style: Some(CompactionStyle::Universal),
+                            write_buffer_size: Some(128 * 1024 * 1024),
+                            ..Default::default()
+                        },
+                    )]
+                    .into(),
+                    ..Default::default()
+                },
+                state_db: Default::default(),
+                replica: Some(ReplicaConfig {
+                    secondary_path: "/tmp/rollup-replica".into(),
+                    catch_up_interval_ms: 1000,
+                }),
+                state_cache_size: NonZeroUsize::new(100000),
             },
             rpc: RpcConfig {
                 bind_host: "127.0.0.1".to_string(),
@@ -228,22 +1060,143 @@ mod tests {
                 batch_requests_limit: 50,
                 enable_subscriptions: true,
                 max_subscriptions_per_connection: 200,
+                trace_cache_size: Some(10000),
+                operator: Some(OperatorRpcConfig {
+                    bind_host: "127.0.0.1".to_string(),
+                    bind_port: 12355,
+                    max_connections: 10,
+                    max_request_body_size: 10 * 1024 * 1024,
+                    max_response_body_size: 10 * 1024 * 1024,
+                    batch_requests_limit: 50,
+                    max_subscriptions_per_connection: 100,
+                    public_namespaces: default_public_namespaces(),
+                }),
+                public_operator_methods: false,
+                sequencer_tx_fallback: false,
+                sequencer_tx_fallback_negative_cache_ms: 500,
+                method_timeouts_ms: [
+                    ("debug_traceBlockByNumber".to_string(), 60000),
+                    ("eth_getLogs".to_string(), 30000),
+                ]
+                .into(),
+                default_method_timeout_ms: Some(10000),
             },
             public_keys: RollupPublicKeys {
                 sequencer_public_key: vec![0; 32],
                 sequencer_da_pub_key: vec![119; 32],
                 prover_da_pub_key: vec![],
+                sequencer_da_pub_key_rotations: vec![],
             },
             sync_blocks_count: 10,
+            log_level: None,
+            telemetry: Some(TelemetryConfig {
+                otlp_endpoint: "http://localhost:4317".to_owned(),
+                sampling_ratio: 0.5,
+                service_name: None,
+            }),
         };
         assert_eq!(config, expected);
     }
 
+    #[test]
+    fn test_sequencer_da_pub_key_rotations() {
+        let public_keys: RollupPublicKeys = toml::from_str(
+            r#"
+            sequencer_public_key = "00"
+            sequencer_da_pub_key = "01"
+            prover_da_pub_key = ""
+
+            [[sequencer_da_pub_key_rotations]]
+            activation_l1_height = 200
+            pub_key = "03"
+
+            [[sequencer_da_pub_key_rotations]]
+            activation_l1_height = 100
+            pub_key = "02"
+        "#,
+        )
+        .unwrap();
+
+        assert_eq!(public_keys.sequencer_da_pub_key_at(0), [1]);
+        assert_eq!(public_keys.sequencer_da_pub_key_at(99), [1]);
+        assert_eq!(public_keys.sequencer_da_pub_key_at(100), [2]);
+        assert_eq!(public_keys.sequencer_da_pub_key_at(199), [2]);
+        assert_eq!(public_keys.sequencer_da_pub_key_at(200), [3]);
+        assert!(public_keys.is_sequencer_da_pub_key(&[1]));
+        assert!(public_keys.is_sequencer_da_pub_key(&[3]));
+        assert!(!public_keys.is_sequencer_da_pub_key(&[4]));
+    }
+
+    #[test]
+    fn test_sequencer_client_url_is_a_url_or_a_list() {
+        let parse = |value: &str| {
+            toml::from_str::<RunnerConfig>(&format!(
+                "include_tx_body = true\nsequencer_client_url = {}",
+                value
+            ))
+            .map(|runner| runner.sequencer_client_url)
+        };
+
+        let one = parse(r#""http://0.0.0.0:12346""#).unwrap();
+        assert_eq!(one.primary(), "http://0.0.0.0:12346");
+        assert_eq!(one.urls(), ["http://0.0.0.0:12346"]);
+
+        let many = parse(r#"["http://0.0.0.0:12346", "http://0.0.0.0:12347"]"#).unwrap();
+        assert_eq!(many.primary(), "http://0.0.0.0:12346");
+        assert_eq!(
+            many.urls(),
+            ["http://0.0.0.0:12346", "http://0.0.0.0:12347"]
+        );
+
+        assert!(parse("[]").is_err());
+    }
+
+    #[test]
+    fn test_answered_challenges_are_limited_and_in_l1_order() {
+        let limits = ProofChallengeConfig {
+            max_challenges_per_l1_block: 3,
+            max_challenge_depth: 10,
+        };
+        // Later, too old and duplicate challenges are ignored
+        assert_eq!(
+            limits.answered_challenges(20, &[15, 20, 21, 9, 12, 15, 10]),
+            vec![10, 12, 15]
+        );
+        // The challenges posted first are answered
+        assert_eq!(
+            limits.answered_challenges(20, &[19, 18, 17, 11]),
+            vec![17, 18, 19]
+        );
+    }
+
     #[test]
     fn test_correct_prover_config() {
         let config = r#"
             proving_mode = "skip"
             proof_sampling_number = 500
+            max_parallel_proving_jobs = 4
+            proving_memory_budget_mb = 8192
+            aggregate_proofs = true
+            proving_backend = "risc0"
+            light_client_proofs = true
+
+            [proving_strategy]
+            mode = "sampled"
+            sampling_number = 100
+
+            [acceleration]
+            accelerator = "cuda"
+            segment_limit_po2 = 21
+
+            [remote_prover]
+            url = "http://localhost:3000"
+            poll_interval_secs = 10
+
+            [proof_posting]
+            fee_bump_percent = 25
+
+            [proof_challenges]
+            max_challenges_per_l1_block = 2
 
             [db_config]
             db_host = "localhost"
@@ -259,8 +1212,151 @@ mod tests {
         let expected = ProverConfig {
             proving_mode: ProverGuestRunConfig::Skip,
             proof_sampling_number: 500,
+            proving_strategy: Some(ProvingStrategy::Sampled {
+                sampling_number: 100,
+            }),
             db_config: Some(SharedBackupDbConfig::default()),
+            max_parallel_proving_jobs: 4,
+            proving_memory_budget_mb: Some(8192),
+            aggregate_proofs: true,
+            proving_backend: ProvingBackendConfig::Risc0,
+            remote_prover: Some(RemoteProverConfig {
+                url: "http://localhost:3000".to_string(),
+                retry_secs: 60,
+                poll_interval_secs: 10,
+                max_poll_interval_secs: 120,
+                timeout_secs: 4 * 60 * 60,
+            }),
+            acceleration: ProvingAccelerationConfig {
+                accelerator: Accelerator::Cuda,
+                segment_limit_po2: Some(21),
+                proving_threads: None,
+            },
+            light_client_proofs: true,
+            proof_posting: Some(ProofPostingConfig {
+                inclusion_timeout_secs: 60 * 60,
+                fee_bump_percent: 25,
+                max_fee_bumps: 5,
+            }),
+            proof_challenges: ProofChallengeConfig {
+                max_challenges_per_l1_block: 2,
+                max_challenge_depth: 1000,
+            },
         };
         assert_eq!(config, expected);
     }
+
+    #[test]
+    fn test_body_retention_enables_pruning_of_archive_nodes() {
+        let mut config = PruningConfig::default();
+        assert!(!config.is_enabled());
+
+        config.body_retention = Some(100);
+        assert!(config.is_enabled());
+
+        config.body_retention = None;
+        config.mode = PruningMode::Minimal;
+        assert!(config.is_enabled());
+    }
+
+    #[test]
+    fn test_proving_strategy_falls_back_to_sampling_number() {
+        let mut config = ProverConfig::default();
+        assert_eq!(config.proving_strategy(), ProvingStrategy::All);
+
+        config.proof_sampling_number = 10;
+        assert_eq!(
+            config.proving_strategy(),
+            ProvingStrategy::Sampled {
+                sampling_number: 10
+            }
+        );
+
+        config.proving_strategy = Some(ProvingStrategy::OnChallenge);
+        assert_eq!(config.proving_strategy(), ProvingStrategy::OnChallenge);
+    }
+
+    #[test]
+    fn test_sampled_proving_strategy_is_deterministic() {
+        let strategy = ProvingStrategy::Sampled { sampling_number: 4 };
+        let sampled = (0..=255u8)
+            .filter(|byte| strategy.proves_unchallenged(&[*byte; 32]))
+            .count();
+        // Every 8 byte chunk is the same, so the seed is always 0
+        assert_eq!(sampled, 256);
+
+        let sampled: Vec<u8> = (0..=255u8)
+            .filter(|byte| {
+                let mut l1_hash = [0; 32];
+                l1_hash[0] = *byte;
+                strategy.proves_unchallenged(&l1_hash)
+            })
+            .collect();
+        assert_eq!(sampled.len(), 64);
+        assert!(sampled.iter().all(|byte| byte % 4 == 0));
+        assert!(ProvingStrategy::OnChallenge.may_prove(&[1; 32]));
+        assert!(!ProvingStrategy::OnChallenge.proves_unchallenged(&[1; 32]));
+    }
+
+    #[test]
+    fn test_config_overrides_take_precedence() {
+        let config = r#"
+            [public_keys]
+            sequencer_public_key = "0000000000000000000000000000000000000000000000000000000000000000"
+            sequencer_da_pub_key = "7777777777777777777777777777777777777777777777777777777777777777"
+            prover_da_pub_key = ""
+
+            [rpc]
+            bind_host = "127.0.0.1"
+            bind_port = 12345
+            max_connections = 500
+
+            [da]
+            sender_address = "0000000000000000000000000000000000000000000000000000000000000000"
+            db_path = "/tmp/da"
+
+            [storage]
+            path = "/tmp/rollup"
+        "#;
+        let config_file = create_config_from(config);
+
+        let env_vars = env_overrides(
+            "CITREA",
+            [
+                ("CITREA__RPC__BIND_PORT", "8080"),
+                ("CITREA__RPC__MAX_CONNECTIONS", "100"),
+                ("CITREA__PUBLIC_KEYS__PROVER_DA_PUB_KEY", "1234"),
+                (
+                    "CITREA__RUNNER__SEQUENCER_CLIENT_URL",
+                    "http://sequencer:12346",
+                ),
+                ("CITREA__RUNNER__INCLUDE_TX_BODY", "false"),
+                ("CITREA_RPC__BIND_HOST", "0.0.0.0"),
+                ("OTHER__RPC__BIND_HOST", "0.0.0.0"),
+            ]
+            .map(|(name, value)| (name.to_owned(), value.to_owned())),
+        );
+        let loader = ConfigLoader::new(config_file.path()).with_override("rpc.bind_port", "9090");
+        let config: FullNodeConfig<sov_mock_da::MockDaConfig> = loader
+            .load_value_with_env(env_vars)
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        // The command line wins over the environment, which wins over the file
+        assert_eq!(config.rpc.bind_port, 9090);
+        assert_eq!(config.rpc.max_connections, 100);
+        assert_eq!(config.rpc.bind_host, "127.0.0.1");
+        // Strings of the file stay strings even if they look like numbers
+        assert_eq!(config.public_keys.prover_da_pub_key, vec![0x12, 0x34]);
+        let runner = config.runner.unwrap();
+        assert_eq!(
+            runner.sequencer_client_url.primary(),
+            "http://sequencer:12346"
+        );
+        assert!(!runner.include_tx_body);
+
+        let loader = ConfigLoader::new(config_file.path()).with_override("rpc.bind_port.x", "1");
+        assert!(loader.load_value_with_env(vec![]).is_err());
+    }
 }
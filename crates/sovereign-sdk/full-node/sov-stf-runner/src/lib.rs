@@ -1,6 +1,8 @@
 #![deny(missing_docs)]
 #![doc = include_str!("../README.md")]
 
+#[cfg(feature = "native")]
+mod block_tag;
 #[cfg(feature = "native")]
 /// Config
 pub mod config;
@@ -8,7 +10,17 @@ pub mod config;
 #[cfg(feature = "mock")]
 pub mod mock;
 #[cfg(feature = "native")]
+mod operator_rpc;
+#[cfg(feature = "native")]
 mod prover_service;
+#[cfg(feature = "native")]
+mod reload;
+#[cfg(feature = "native")]
+mod request_id;
+#[cfg(feature = "native")]
+mod rpc_server;
+#[cfg(feature = "native")]
+mod rpc_timeout;
 
 #[cfg(feature = "native")]
 use std::path::Path;
@@ -16,10 +28,22 @@ use std::path::Path;
 #[cfg(feature = "native")]
 use anyhow::Context;
 #[cfg(feature = "native")]
+pub use block_tag::*;
+#[cfg(feature = "native")]
 pub use config::*;
 #[cfg(feature = "native")]
+pub use operator_rpc::*;
+#[cfg(feature = "native")]
 pub use prover_service::*;
 #[cfg(feature = "native")]
+pub use reload::*;
+#[cfg(feature = "native")]
+pub use request_id::*;
+#[cfg(feature = "native")]
+pub use rpc_server::*;
+#[cfg(feature = "native")]
+pub use rpc_timeout::*;
+#[cfg(feature = "native")]
 use sov_modules_api::{DaSpec, Zkvm};
 #[cfg(feature = "native")]
 use sov_rollup_interface::stf::StateTransitionFunction;
@@ -1,6 +1,6 @@
 use std::marker::PhantomData;
 
-use sov_rollup_interface::da::DaSpec;
+use sov_rollup_interface::da::{DaSpec, SlotInbox};
 use sov_rollup_interface::stf::{BatchReceipt, SlotResult, StateTransitionFunction};
 use sov_rollup_interface::zk::{CumulativeStateDiff, ValidityCondition, Zkvm};
 
@@ -70,6 +70,7 @@ impl<Vm: Zkvm, Cond: ValidityCondition, Da: DaSpec> StateTransitionFunction<Vm,
         _pre_state: Self::PreState,
         _witness: Self::Witness,
         _slot_header: &<Da as DaSpec>::BlockHeader,
+        _slot_inbox: &SlotInbox,
         _validity_condition: &<Da as DaSpec>::ValidityCondition,
         _soft_batch: &mut sov_modules_api::SignedSoftConfirmationBatch,
     ) -> SlotResult<
@@ -93,6 +94,7 @@ impl<Vm: Zkvm, Cond: ValidityCondition, Da: DaSpec> StateTransitionFunction<Vm,
         _sequencer_commitments_range: (u32, u32),
         _witnesses: std::collections::VecDeque<Vec<Self::Witness>>,
         _slot_headers: std::collections::VecDeque<Vec<<Da as DaSpec>::BlockHeader>>,
+        _slot_inboxes: std::collections::VecDeque<Vec<SlotInbox>>,
         _validity_condition: &<Da as DaSpec>::ValidityCondition,
         _soft_confirmations: std::collections::VecDeque<
             Vec<sov_modules_api::SignedSoftConfirmationBatch>,
@@ -0,0 +1,226 @@
+use std::convert::Infallible;
+use std::net::{SocketAddr, TcpListener};
+
+use anyhow::{anyhow, Context as _};
+use hyper::server::conn::AddrStream;
+use hyper::service::make_service_fn;
+use jsonrpsee::server::middleware::http::ProxyGetRequestLayer;
+use jsonrpsee::server::middleware::rpc::RpcServiceBuilder;
+use jsonrpsee::server::{stop_channel, BatchRequestConfig, ServerBuilder};
+use jsonrpsee::{Methods, RpcModule};
+use sov_db::ledger_db::SharedLedgerOps;
+use tracing::{error, info};
+
+use crate::{BlockTagLayer, RequestIdHeaderLayer, RequestIdLayer, RpcConfig, RpcTimeoutLayer};
+
+/// Methods of the public namespaces which are only served to operators, as they change the
+/// limits or the halts of the node, write to its disk, or skip its safety checks.
+/// They are stripped from the public server, and the runners only register their own
+/// operator methods when an operator RPC server is configured.
+const OPERATOR_METHODS: &[&str] = &[
+    "citrea_createBackup",
+    "citrea_reloadConfig",
+    "citrea_resumeAfterReorg",
+    "citrea_clearStateRootMismatch",
+    "citrea_simulateBlock",
+    "citrea_promoteToPrimary",
+    "citrea_shutdown",
+    "citrea_testPublishBlock",
+];
+
+/// Whether the method is served by the public RPC server
+pub fn is_public_method(method: &str, public_namespaces: &[String]) -> bool {
+    if OPERATOR_METHODS.contains(&method) {
+        return false;
+    }
+    match method.split_once('_') {
+        Some((namespace, _)) => public_namespaces
+            .iter()
+            .any(|public_namespace| public_namespace == namespace),
+        None => true,
+    }
+}
+
+/// Splits the methods into the ones of the public server and the ones of the operator server,
+/// which serves all of them.
+pub fn split_rpc_methods(
+    methods: RpcModule<()>,
+    public_namespaces: &[String],
+) -> (RpcModule<()>, RpcModule<()>) {
+    let mut public_methods = methods.clone();
+    let operator_only: Vec<&'static str> = methods
+        .method_names()
+        .filter(|method| !is_public_method(method, public_namespaces))
+        .collect();
+    for method in operator_only {
+        public_methods.remove_method(method);
+    }
+    (public_methods, methods)
+}
+
+/// Removes the operator methods, which are only served to operators
+pub fn without_operator_methods(mut methods: RpcModule<()>) -> RpcModule<()> {
+    for method in OPERATOR_METHODS {
+        methods.remove_method(method);
+    }
+    methods
+}
+
+/// Starts the operator RPC server if one is configured, and returns the methods the public
+/// server serves. Without an operator server, the operator methods are not served at all,
+/// unless `public_operator_methods` is set.
+///
+/// Fails if the operator server cannot be bound, so nodes do not start without it.
+pub fn start_operator_rpc_server<DB>(
+    rpc_config: &RpcConfig,
+    methods: RpcModule<()>,
+    ledger_db: DB,
+    health_layer: Option<ProxyGetRequestLayer>,
+) -> anyhow::Result<RpcModule<()>>
+where
+    DB: SharedLedgerOps + Clone + Send + Sync + 'static,
+{
+    let Some(config) = rpc_config.operator.as_ref() else {
+        if rpc_config.public_operator_methods {
+            return Ok(methods);
+        }
+        return Ok(without_operator_methods(methods));
+    };
+    let (public_methods, operator_methods) = split_rpc_methods(methods, &config.public_namespaces);
+
+    let bind_host = config
+        .bind_host
+        .parse()
+        .map_err(|e| anyhow!("Failed to parse operator bind host: {}", e))?;
+    let listen_address = SocketAddr::new(bind_host, config.bind_port);
+    let listener = TcpListener::bind(listen_address)
+        .with_context(|| format!("Could not bind operator RPC server to {}", listen_address))?;
+    listener.set_nonblocking(true)?;
+    let bound_address = listener.local_addr()?;
+
+    let service_builder = ServerBuilder::default()
+        .max_connections(config.max_connections)
+        .max_subscriptions_per_connection(config.max_subscriptions_per_connection)
+        .max_request_body_size(config.max_request_body_size)
+        .max_response_body_size(config.max_response_body_size)
+        .set_batch_request_config(BatchRequestConfig::Limit(config.batch_requests_limit))
+        .set_http_middleware(
+            tower::ServiceBuilder::new()
+                .layer(RequestIdHeaderLayer)
+                .option_layer(health_layer),
+        )
+        .set_rpc_middleware(
+            RpcServiceBuilder::new()
+                .layer(RequestIdLayer)
+                .layer(RpcTimeoutLayer::new(rpc_config))
+                .layer(BlockTagLayer::new(ledger_db)),
+        )
+        .to_service_builder();
+    let methods: Methods = operator_methods.into();
+    let (stop_handle, server_handle) = stop_channel();
+    let make_service = make_service_fn(move |_: &AddrStream| {
+        let service = service_builder
+            .clone()
+            .build(methods.clone(), stop_handle.clone());
+        async move { Ok::<_, Infallible>(service) }
+    });
+    let server = hyper::Server::from_tcp(listener)?.serve(make_service);
+    info!("Starting operator RPC server at {}", bound_address);
+
+    tokio::spawn(async move {
+        // Open connections are closed once the handle is dropped
+        let _server_handle = server_handle;
+        if let Err(e) = server.await {
+            error!("Operator RPC server stopped: {}", e);
+        }
+    });
+    Ok(public_methods)
+}
+
+#[cfg(test)]
+mod tests {
+    use sov_db::ledger_db::LedgerDB;
+
+    use super::*;
+
+    fn test_methods(names: &[&'static str]) -> RpcModule<()> {
+        let mut methods = RpcModule::new(());
+        for method in names {
+            methods
+                .register_method(method, |_, _| {
+                    Ok::<(), jsonrpsee::types::ErrorObjectOwned>(())
+                })
+                .unwrap();
+        }
+        methods
+    }
+
+    #[test]
+    fn test_splits_methods_by_namespace() {
+        let public_namespaces = ["eth".to_owned(), "citrea".to_owned()];
+        let methods = test_methods(&[
+            "eth_call",
+            "citrea_getProvenHeight",
+            "citrea_createBackup",
+            "debug_traceTransaction",
+            "prover_getStatus",
+            "health",
+        ]);
+
+        let (public_methods, operator_methods) = split_rpc_methods(methods, &public_namespaces);
+        let mut public: Vec<_> = public_methods.method_names().collect();
+        public.sort();
+        assert_eq!(public, ["citrea_getProvenHeight", "eth_call", "health"]);
+        assert_eq!(operator_methods.method_names().count(), 6);
+    }
+
+    #[test]
+    fn test_operator_methods_are_not_served_without_operator_server() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        let mut rpc_config: RpcConfig = toml::from_str(
+            r#"
+            bind_host = "127.0.0.1"
+            bind_port = 0
+        "#,
+        )
+        .unwrap();
+        let methods = test_methods(&["eth_call", "citrea_shutdown", "citrea_createBackup"]);
+
+        let public_methods =
+            start_operator_rpc_server(&rpc_config, methods.clone(), ledger_db.clone(), None)
+                .unwrap();
+        assert_eq!(
+            public_methods.method_names().collect::<Vec<_>>(),
+            ["eth_call"]
+        );
+
+        rpc_config.public_operator_methods = true;
+        let public_methods =
+            start_operator_rpc_server(&rpc_config, methods, ledger_db, None).unwrap();
+        assert_eq!(public_methods.method_names().count(), 3);
+    }
+
+    #[test]
+    fn test_fails_if_operator_server_cannot_be_bound() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let rpc_config: RpcConfig = toml::from_str(&format!(
+            r#"
+            bind_host = "127.0.0.1"
+            bind_port = 0
+
+            [operator]
+            bind_host = "127.0.0.1"
+            bind_port = {}
+        "#,
+            taken.local_addr().unwrap().port()
+        ))
+        .unwrap();
+
+        let result =
+            start_operator_rpc_server(&rpc_config, test_methods(&["eth_call"]), ledger_db, None);
+        assert!(result.is_err());
+    }
+}
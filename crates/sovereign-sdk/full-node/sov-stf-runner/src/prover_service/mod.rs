@@ -1,9 +1,9 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use sov_modules_api::Zkvm;
-use sov_rollup_interface::da::DaSpec;
+use sov_rollup_interface::da::{DaChainState, DaSpec};
 use sov_rollup_interface::services::da::DaService;
-use sov_rollup_interface::zk::{Proof, StateTransitionData};
+use sov_rollup_interface::zk::{CycleReport, LightClientData, Proof, StateTransitionData};
 use thiserror::Error;
 
 /// The possible configurations of the prover.
@@ -15,6 +15,8 @@ pub enum ProverGuestRunConfig {
     Simulate,
     /// Run the rollup verifier in a zkVM executor.
     Execute,
+    /// Run the rollup verifier in a zkVM executor and report the cycles spent by its phases.
+    Profile,
     /// Run the rollup verifier and create a SNARK of execution.
     Prove,
 }
@@ -29,6 +31,7 @@ impl<'de> Deserialize<'de> for ProverGuestRunConfig {
             "skip" => Ok(ProverGuestRunConfig::Skip),
             "simulate" => Ok(ProverGuestRunConfig::Simulate),
             "execute" => Ok(ProverGuestRunConfig::Execute),
+            "profile" => Ok(ProverGuestRunConfig::Profile),
             "prove" => Ok(ProverGuestRunConfig::Prove),
             _ => Err(serde::de::Error::custom("invalid prover guest run config")),
         }
@@ -111,4 +114,41 @@ pub trait ProverService<Vm: Zkvm> {
         block_header_hash: <<Self::DaService as DaService>::Spec as DaSpec>::SlotHash,
         da_service: &Self::DaService,
     ) -> Result<(<Self::DaService as DaService>::TransactionId, Proof), anyhow::Error>;
+
+    /// Waits for the ZK proof without sending it to the DA.
+    async fn wait_for_proving(
+        &self,
+        block_header_hash: <<Self::DaService as DaService>::Spec as DaSpec>::SlotHash,
+    ) -> Result<Proof, anyhow::Error>;
+
+    /// Recursively combines the proofs of consecutive state transitions into a single proof.
+    async fn aggregate_proofs(&self, proofs: Vec<Proof>) -> Result<Proof, anyhow::Error>;
+
+    /// Code commitment of the light client guest, if light client proofs are enabled.
+    fn light_client_code_commitment(&self) -> Option<Vec<u8>>;
+
+    /// Verifies the DA block headers following the chain state like the light client guest,
+    /// and returns the chain state after them, which the light client proof outputs.
+    fn verify_light_client_headers(
+        &self,
+        chain_state: &DaChainState,
+        block_headers: &[<<Self::DaService as DaService>::Spec as DaSpec>::BlockHeader],
+    ) -> Result<DaChainState, anyhow::Error>;
+
+    /// Proves the light client output over new DA blocks.
+    /// `previous_proof` is the light client proof the output extends,
+    /// and `batch_proofs` are the proofs of the state transitions in `data`.
+    async fn prove_light_client(
+        &self,
+        data: LightClientData<<Self::DaService as DaService>::Spec, Self::StateRoot>,
+        previous_proof: Option<Proof>,
+        batch_proofs: Vec<Proof>,
+    ) -> Result<Proof, anyhow::Error>;
+
+    /// Takes the cycle report created while proving the block corresponding to `block_header_hash`.
+    /// Reports are only created in the [`ProverGuestRunConfig::Profile`] mode.
+    async fn take_cycle_report(
+        &self,
+        block_header_hash: <<Self::DaService as DaService>::Spec as DaSpec>::SlotHash,
+    ) -> Option<CycleReport>;
 }
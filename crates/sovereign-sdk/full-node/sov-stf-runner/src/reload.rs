@@ -0,0 +1,402 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context as _;
+use jsonrpsee::RpcModule;
+use serde::Serialize;
+use sov_modules_api::utils::to_jsonrpsee_error_object;
+use sov_rollup_interface::services::da::{DaService, FeeCeilings};
+use tokio::sync::watch;
+use tracing::{debug, info};
+
+use crate::{ConfigLoader, FullNodeConfig};
+
+/// Fields of [`FullNodeConfig`] which take effect without restarting the node
+pub const RELOADABLE_FIELDS: &[&str] = &[
+    "log_level",
+    "sync_blocks_count",
+    "rpc.max_connections",
+    "rpc.max_request_body_size",
+    "rpc.max_response_body_size",
+    "rpc.batch_requests_limit",
+    "rpc.max_subscriptions_per_connection",
+    "da.fee_estimation.max_fee_rate",
+    "da.fee_bumping.max_fee_rate",
+];
+
+/// The settings of [`FullNodeConfig`] which take effect without restarting the node
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReloadableConfig {
+    /// Log filter, the one of the command line if not set
+    pub log_level: Option<String>,
+    /// Initial number of blocks to request during sync
+    pub sync_blocks_count: u64,
+    /// Maximum number of connections of the RPC server
+    pub max_connections: u32,
+    /// Maximum size of requests to the RPC server in bytes
+    pub max_request_body_size: u32,
+    /// Maximum size of responses of the RPC server in bytes
+    pub max_response_body_size: u32,
+    /// Maximum number of requests in a batch
+    pub batch_requests_limit: u32,
+    /// Maximum number of subscriptions per connection
+    pub max_subscriptions_per_connection: u32,
+    /// Maximum fee rate of new DA transactions, the one the DA service started with if not set
+    pub max_fee_rate: Option<f64>,
+    /// Maximum fee rate stalled DA transactions are bumped to,
+    /// the one the DA service started with if not set
+    pub max_fee_bump_rate: Option<f64>,
+}
+
+impl From<&FullNodeConfig<toml::Value>> for ReloadableConfig {
+    fn from(config: &FullNodeConfig<toml::Value>) -> Self {
+        Self {
+            log_level: config.log_level.clone(),
+            sync_blocks_count: config.sync_blocks_count,
+            max_connections: config.rpc.max_connections,
+            max_request_body_size: config.rpc.max_request_body_size,
+            max_response_body_size: config.rpc.max_response_body_size,
+            batch_requests_limit: config.rpc.batch_requests_limit,
+            max_subscriptions_per_connection: config.rpc.max_subscriptions_per_connection,
+            max_fee_rate: fee_rate(&config.da, "fee_estimation"),
+            max_fee_bump_rate: fee_rate(&config.da, "fee_bumping"),
+        }
+    }
+}
+
+/// The `max_fee_rate` of a table of the DA config, written as a float or an integer
+fn fee_rate(da: &toml::Value, table: &str) -> Option<f64> {
+    match da.get(table)?.get("max_fee_rate")? {
+        toml::Value::Float(fee_rate) => Some(*fee_rate),
+        toml::Value::Integer(fee_rate) => Some(*fee_rate as f64),
+        _ => None,
+    }
+}
+
+impl ReloadableConfig {
+    /// The fee ceilings of the DA service
+    pub fn fee_ceilings(&self) -> FeeCeilings {
+        FeeCeilings {
+            max_fee_rate: self.max_fee_rate,
+            max_fee_bump_rate: self.max_fee_bump_rate,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.sync_blocks_count > 0,
+            "sync_blocks_count must not be 0"
+        );
+        anyhow::ensure!(
+            self.max_connections > 0,
+            "rpc.max_connections must not be 0"
+        );
+        anyhow::ensure!(
+            self.max_request_body_size > 0,
+            "rpc.max_request_body_size must not be 0"
+        );
+        anyhow::ensure!(
+            self.max_response_body_size > 0,
+            "rpc.max_response_body_size must not be 0"
+        );
+        anyhow::ensure!(
+            self.batch_requests_limit > 0,
+            "rpc.batch_requests_limit must not be 0"
+        );
+        for (path, fee_rate) in [
+            ("da.fee_estimation.max_fee_rate", self.max_fee_rate),
+            ("da.fee_bumping.max_fee_rate", self.max_fee_bump_rate),
+        ] {
+            if let Some(fee_rate) = fee_rate {
+                anyhow::ensure!(
+                    fee_rate.is_finite() && fee_rate > 0.0,
+                    "{} must be a positive number",
+                    path
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of a reload of the node configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigReloadReport {
+    /// Changed fields which are in effect now
+    pub applied: Vec<String>,
+    /// Changed fields which only take effect after a restart
+    pub requires_restart: Vec<String>,
+}
+
+/// Applies a new log filter, fails if the filter is invalid
+pub type LogLevelHook = Box<dyn Fn(&str) -> anyhow::Result<()> + Send + Sync>;
+
+/// Reloads the [`FullNodeConfig`] of a running node from its file.
+///
+/// Changes of the [`RELOADABLE_FIELDS`] are validated and published to the subscribers of
+/// [`ConfigReloader::subscribe`], all other changes are reported until the node is restarted.
+pub struct ConfigReloader {
+    loader: ConfigLoader,
+    /// Fields of the config in effect, by their dotted path
+    fields: Mutex<BTreeMap<String, toml::Value>>,
+    config: watch::Sender<ReloadableConfig>,
+    log_level_hook: Option<LogLevelHook>,
+}
+
+impl ConfigReloader {
+    /// Reads the config in effect from the file the node was started with
+    pub fn from_path(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::from_loader(ConfigLoader::new(path))
+    }
+
+    /// Reads the config in effect with the overrides the node was started with,
+    /// which are applied again on every reload
+    pub fn from_loader(loader: ConfigLoader) -> anyhow::Result<Self> {
+        let (fields, config) = read_config(&loader)?;
+        Ok(Self {
+            loader,
+            fields: Mutex::new(fields),
+            config: watch::channel(config).0,
+            log_level_hook: None,
+        })
+    }
+
+    /// Applies changes of the log level with the hook
+    pub fn with_log_level_hook(mut self, hook: LogLevelHook) -> Self {
+        self.log_level_hook = Some(hook);
+        self
+    }
+
+    /// The reloadable settings in effect
+    pub fn config(&self) -> ReloadableConfig {
+        self.config.borrow().clone()
+    }
+
+    /// Notifies about changes of the reloadable settings
+    pub fn subscribe(&self) -> watch::Receiver<ReloadableConfig> {
+        self.config.subscribe()
+    }
+
+    /// Reads the config file and its overrides again and applies the changes of the reloadable settings.
+    /// Nothing is applied if the file or one of the reloadable settings is invalid.
+    pub fn reload(&self) -> anyhow::Result<ConfigReloadReport> {
+        let (new_fields, new_config) = read_config(&self.loader)?;
+        let mut fields = self.fields.lock().unwrap();
+
+        let mut report = ConfigReloadReport::default();
+        let paths = fields.keys().chain(new_fields.keys()).cloned();
+        for path in paths.collect::<std::collections::BTreeSet<_>>() {
+            if fields.get(&path) == new_fields.get(&path) {
+                continue;
+            }
+            if RELOADABLE_FIELDS.contains(&path.as_str()) {
+                report.applied.push(path);
+            } else {
+                report.requires_restart.push(path);
+            }
+        }
+        if report.applied.is_empty() {
+            return Ok(report);
+        }
+
+        if new_config.log_level != self.config.borrow().log_level {
+            if let (Some(hook), Some(log_level)) = (&self.log_level_hook, &new_config.log_level) {
+                hook(log_level).context("Invalid log_level")?;
+            }
+        }
+        for path in &report.applied {
+            match new_fields.get(path) {
+                Some(value) => fields.insert(path.clone(), value.clone()),
+                None => fields.remove(path),
+            };
+        }
+        self.config.send_replace(new_config);
+
+        info!(
+            "Reloaded config, applied: {:?}, requires restart: {:?}",
+            report.applied, report.requires_restart
+        );
+        Ok(report)
+    }
+}
+
+/// Serves `citrea_reloadConfig`, which reloads the config like a SIGHUP.
+/// Only the operator RPC server serves it.
+pub fn create_config_rpc_module(
+    config_reloader: Arc<ConfigReloader>,
+) -> Result<RpcModule<Arc<ConfigReloader>>, jsonrpsee::core::RegisterMethodError> {
+    let mut rpc = RpcModule::new(config_reloader);
+
+    rpc.register_async_method("citrea_reloadConfig", |_, config_reloader| async move {
+        debug!("citrea_reloadConfig");
+        config_reloader
+            .reload()
+            .map_err(|e| to_jsonrpsee_error_object("CONFIG_RELOAD_ERROR", e))
+    })?;
+
+    Ok(rpc)
+}
+
+/// Applies the reloaded fee ceilings to the DA service, until the reloader is dropped
+pub async fn apply_fee_ceilings<Da: DaService>(
+    da_service: Da,
+    mut config_updates: watch::Receiver<ReloadableConfig>,
+) {
+    let mut fee_ceilings = config_updates.borrow_and_update().fee_ceilings();
+    da_service.set_fee_ceilings(fee_ceilings);
+    while config_updates.changed().await.is_ok() {
+        let reloaded = config_updates.borrow_and_update().fee_ceilings();
+        if reloaded != fee_ceilings {
+            da_service.set_fee_ceilings(reloaded);
+            fee_ceilings = reloaded;
+        }
+    }
+}
+
+fn read_config(
+    loader: &ConfigLoader,
+) -> anyhow::Result<(BTreeMap<String, toml::Value>, ReloadableConfig)> {
+    let value = loader.load_value()?;
+    // The DA config is only compared, so it is kept as it is
+    let config: FullNodeConfig<toml::Value> = value
+        .clone()
+        .try_into()
+        .with_context(|| format!("Invalid config in {}", loader.path().display()))?;
+    let config = ReloadableConfig::from(&config);
+    config.validate()?;
+
+    let mut fields = BTreeMap::new();
+    flatten(String::new(), value, &mut fields);
+    Ok((fields, config))
+}
+
+/// Collects the fields of nested tables by their dotted path
+fn flatten(prefix: String, value: toml::Value, fields: &mut BTreeMap<String, toml::Value>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let path = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(path, value, fields);
+            }
+        }
+        value => {
+            fields.insert(prefix, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Seek, Write};
+
+    use tempfile::NamedTempFile;
+
+    use super::*;
+
+    fn config(max_connections: u32, storage_path: &str) -> String {
+        format!(
+            r#"
+            [public_keys]
+            sequencer_public_key = "0000000000000000000000000000000000000000000000000000000000000000"
+            sequencer_da_pub_key = "7777777777777777777777777777777777777777777777777777777777777777"
+            prover_da_pub_key = ""
+
+            [rpc]
+            bind_host = "127.0.0.1"
+            bind_port = 12345
+            max_connections = {max_connections}
+
+            [da]
+            sender_address = "0000000000000000000000000000000000000000000000000000000000000000"
+            db_path = "/tmp/da"
+
+            [storage]
+            path = "{storage_path}"
+        "#
+        )
+    }
+
+    fn write(file: &mut NamedTempFile, contents: &str) {
+        file.as_file_mut().set_len(0).unwrap();
+        file.rewind().unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_reload_applies_reloadable_fields() {
+        let mut file = NamedTempFile::new().unwrap();
+        write(&mut file, &config(500, "/tmp/rollup"));
+        let reloader = ConfigReloader::from_path(file.path()).unwrap();
+        let mut subscriber = reloader.subscribe();
+
+        write(&mut file, &config(100, "/tmp/other"));
+        let report = reloader.reload().unwrap();
+        assert_eq!(report.applied, vec!["rpc.max_connections".to_string()]);
+        assert_eq!(report.requires_restart, vec!["storage.path".to_string()]);
+        assert!(subscriber.has_changed().unwrap());
+        assert_eq!(subscriber.borrow_and_update().max_connections, 100);
+
+        // Changes which require a restart are reported until the node restarts
+        let report = reloader.reload().unwrap();
+        assert!(report.applied.is_empty());
+        assert_eq!(report.requires_restart, vec!["storage.path".to_string()]);
+
+        // Invalid settings are not applied
+        write(&mut file, &config(0, "/tmp/rollup"));
+        assert!(reloader.reload().is_err());
+        assert_eq!(reloader.config().max_connections, 100);
+    }
+
+    #[test]
+    fn test_reload_applies_fee_ceilings() {
+        let mut file = NamedTempFile::new().unwrap();
+        write(&mut file, &config(500, "/tmp/rollup"));
+        let reloader = ConfigReloader::from_path(file.path()).unwrap();
+        assert_eq!(reloader.config().fee_ceilings(), FeeCeilings::default());
+
+        let fee_ceilings = r#"
+            [da.fee_estimation]
+            max_fee_rate = 50
+
+            [da.fee_bumping]
+            max_fee_rate = 80.5
+        "#;
+        write(
+            &mut file,
+            &format!("{}{}", config(500, "/tmp/rollup"), fee_ceilings),
+        );
+        let report = reloader.reload().unwrap();
+        assert_eq!(
+            report.applied,
+            vec![
+                "da.fee_bumping.max_fee_rate".to_string(),
+                "da.fee_estimation.max_fee_rate".to_string(),
+            ]
+        );
+        assert_eq!(
+            reloader.config().fee_ceilings(),
+            FeeCeilings {
+                max_fee_rate: Some(50.0),
+                max_fee_bump_rate: Some(80.5),
+            }
+        );
+
+        // Fee rates which are not positive are not applied
+        write(
+            &mut file,
+            &format!(
+                "{}{}",
+                config(500, "/tmp/rollup"),
+                fee_ceilings.replace("80.5", "0.0")
+            ),
+        );
+        assert!(reloader.reload().is_err());
+        assert_eq!(reloader.config().max_fee_bump_rate, Some(80.5));
+    }
+}
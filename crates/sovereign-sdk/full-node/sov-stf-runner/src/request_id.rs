@@ -0,0 +1,107 @@
+use std::task::{Context, Poll};
+
+use futures::future::Either;
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::types::Request;
+pub use sov_rollup_interface::rpc::request_id::current_request_id;
+use sov_rollup_interface::rpc::request_id::{
+    is_valid_request_id, scope_request_id, sync_scope_request_id, REQUEST_ID_HEADER,
+};
+use tokio::task::futures::TaskLocalFuture;
+use tracing::instrument::Instrumented;
+use tracing::{info_span, Instrument};
+
+/// RPC middleware which gives every request a correlation ID.
+///
+/// Requests are handled in an `rpc_request` span with the `request_id` and the `method`,
+/// and the ID is available to the handlers with [`current_request_id`].
+/// Requests of other nodes keep the ID they were sent with, see [`RequestIdHeaderLayer`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestIdLayer;
+
+impl<S> tower::Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RequestIdService { service }
+    }
+}
+
+/// Service of the [`RequestIdLayer`]
+#[derive(Debug, Clone)]
+pub struct RequestIdService<S> {
+    service: S,
+}
+
+impl<'a, S> RpcServiceT<'a> for RequestIdService<S>
+where
+    S: RpcServiceT<'a>,
+{
+    type Future = TaskLocalFuture<String, Instrumented<S::Future>>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let request_id =
+            current_request_id().unwrap_or_else(|| format!("{:016x}", rand::random::<u64>()));
+        let span = info_span!(
+            "rpc_request",
+            request_id = %request_id,
+            method = request.method_name()
+        );
+        // Synchronous methods are handled right away, before the future is polled
+        let future = sync_scope_request_id(request_id.clone(), || {
+            let _entered = span.enter();
+            self.service.call(request)
+        });
+        scope_request_id(request_id, future.instrument(span))
+    }
+}
+
+/// HTTP middleware which takes the correlation ID of the requests of other nodes
+/// from their `x-request-id` header, so their work can be followed across the nodes.
+/// Invalid IDs are ignored, the requests get a new one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RequestIdHeaderLayer;
+
+impl<S> tower::Layer<S> for RequestIdHeaderLayer {
+    type Service = RequestIdHeaderService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RequestIdHeaderService { service }
+    }
+}
+
+/// Service of the [`RequestIdHeaderLayer`]
+#[derive(Debug, Clone)]
+pub struct RequestIdHeaderService<S> {
+    service: S,
+}
+
+impl<S, B> tower::Service<hyper::Request<B>> for RequestIdHeaderService<S>
+where
+    S: tower::Service<hyper::Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Either<TaskLocalFuture<String, S::Future>, S::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: hyper::Request<B>) -> Self::Future {
+        let request_id = request
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|request_id| is_valid_request_id(request_id))
+            .map(str::to_string);
+        match request_id {
+            Some(request_id) => {
+                let future =
+                    sync_scope_request_id(request_id.clone(), || self.service.call(request));
+                Either::Left(scope_request_id(request_id, future))
+            }
+            None => Either::Right(self.service.call(request)),
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use std::convert::Infallible;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, RwLock};
+
+use anyhow::{anyhow, Context as _};
+use hyper::server::conn::AddrStream;
+use hyper::service::make_service_fn;
+use hyper::Method;
+use jsonrpsee::server::middleware::http::ProxyGetRequestLayer;
+use jsonrpsee::server::middleware::rpc::RpcServiceBuilder;
+use jsonrpsee::server::{stop_channel, BatchRequestConfig, ServerBuilder};
+use jsonrpsee::{Methods, RpcModule};
+use sov_db::ledger_db::SharedLedgerOps;
+use tokio::sync::{oneshot, watch};
+use tower_http::cors::{Any, CorsLayer};
+use tracing::{error, info};
+
+use crate::{
+    start_operator_rpc_server, BlockTagLayer, ReloadableConfig, RequestIdHeaderLayer,
+    RequestIdLayer, RpcConfig, RpcTimeoutLayer,
+};
+
+/// Settings of the RPC server which differ between nodes
+#[derive(Debug, Clone, Default)]
+pub struct RpcServerOptions {
+    /// Serves cross-origin requests of any origin, e.g. from wallets in the browser
+    pub allow_cors: bool,
+    /// Method whose result answers `GET /health`, if the node serves health checks
+    pub health_method: Option<&'static str>,
+    /// Reloads of the config, whose RPC limits apply to the connections opened after them
+    pub config_updates: Option<watch::Receiver<ReloadableConfig>>,
+}
+
+/// Limits of the RPC server which can be reloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RpcLimits {
+    max_connections: u32,
+    max_subscriptions_per_connection: u32,
+    max_request_body_size: u32,
+    max_response_body_size: u32,
+    batch_requests_limit: u32,
+}
+
+impl From<&RpcConfig> for RpcLimits {
+    fn from(config: &RpcConfig) -> Self {
+        Self {
+            max_connections: config.max_connections,
+            max_subscriptions_per_connection: config.max_subscriptions_per_connection,
+            max_request_body_size: config.max_request_body_size,
+            max_response_body_size: config.max_response_body_size,
+            batch_requests_limit: config.batch_requests_limit,
+        }
+    }
+}
+
+impl From<&ReloadableConfig> for RpcLimits {
+    fn from(config: &ReloadableConfig) -> Self {
+        Self {
+            max_connections: config.max_connections,
+            max_subscriptions_per_connection: config.max_subscriptions_per_connection,
+            max_request_body_size: config.max_request_body_size,
+            max_response_body_size: config.max_response_body_size,
+            batch_requests_limit: config.batch_requests_limit,
+        }
+    }
+}
+
+/// Starts the RPC server of a node with the limits and middleware of the RPC config,
+/// and the operator RPC server if one is configured.
+/// The address the server is bound to is sent to `channel`, once it is bound.
+/// Fails if either server cannot be bound.
+///
+/// The server is bound once. Reloaded limits apply to the connections opened after the
+/// reload, open connections and their subscriptions are kept with the limits they were
+/// opened with.
+pub fn spawn_rpc_server<DB>(
+    rpc_config: &RpcConfig,
+    methods: RpcModule<()>,
+    ledger_db: DB,
+    options: RpcServerOptions,
+    channel: Option<oneshot::Sender<SocketAddr>>,
+) -> anyhow::Result<()>
+where
+    DB: SharedLedgerOps + Clone + Send + Sync + 'static,
+{
+    let bind_host = rpc_config
+        .bind_host
+        .parse()
+        .map_err(|e| anyhow!("Failed to parse bind host: {}", e))?;
+    let listen_address = SocketAddr::new(bind_host, rpc_config.bind_port);
+    let listener = TcpListener::bind(listen_address)
+        .with_context(|| format!("Could not bind RPC server to {}", listen_address))?;
+    listener.set_nonblocking(true)?;
+    let bound_address = listener.local_addr()?;
+
+    let health_layer = options
+        .health_method
+        .map(|method| ProxyGetRequestLayer::new("/health", method))
+        .transpose()?;
+    let methods: Methods =
+        start_operator_rpc_server(rpc_config, methods, ledger_db.clone(), health_layer.clone())?
+            .into();
+
+    let timeout_layer = RpcTimeoutLayer::new(rpc_config);
+    let block_tag_layer = BlockTagLayer::new(ledger_db);
+    let cors_layer = options.allow_cors.then(|| {
+        CorsLayer::new()
+            .allow_methods([Method::POST, Method::OPTIONS])
+            .allow_origin(Any)
+            .allow_headers(Any)
+    });
+    let service_builder = move |limits: &RpcLimits| {
+        ServerBuilder::default()
+            .max_connections(limits.max_connections)
+            .max_subscriptions_per_connection(limits.max_subscriptions_per_connection)
+            .max_request_body_size(limits.max_request_body_size)
+            .max_response_body_size(limits.max_response_body_size)
+            .set_batch_request_config(BatchRequestConfig::Limit(limits.batch_requests_limit))
+            .set_http_middleware(
+                tower::ServiceBuilder::new()
+                    .layer(RequestIdHeaderLayer)
+                    .option_layer(health_layer.clone())
+                    .option_layer(cors_layer.clone()),
+            )
+            .set_rpc_middleware(
+                RpcServiceBuilder::new()
+                    .layer(RequestIdLayer)
+                    .layer(timeout_layer.clone())
+                    .layer(block_tag_layer.clone()),
+            )
+            .to_service_builder()
+    };
+
+    let mut config_updates = options.config_updates;
+    let mut limits = match config_updates.as_mut() {
+        Some(config_updates) => RpcLimits::from(&*config_updates.borrow_and_update()),
+        None => RpcLimits::from(rpc_config),
+    };
+    // Connections are served with the limits in effect when they were opened
+    let current_service = Arc::new(RwLock::new(service_builder(&limits)));
+    let (stop_handle, server_handle) = stop_channel();
+    let make_service = make_service_fn({
+        let current_service = current_service.clone();
+        move |_: &AddrStream| {
+            let service = current_service
+                .read()
+                .unwrap()
+                .clone()
+                .build(methods.clone(), stop_handle.clone());
+            async move { Ok::<_, Infallible>(service) }
+        }
+    });
+    let server = hyper::Server::from_tcp(listener)?.serve(make_service);
+
+    if let Some(channel) = channel {
+        if let Err(e) = channel.send(bound_address) {
+            error!("Could not send bound_address {}: {}", bound_address, e);
+        }
+    }
+    info!("Starting RPC server at {} ", &bound_address);
+
+    tokio::spawn(async move {
+        // Open connections are closed once the handle is dropped
+        let _server_handle = server_handle;
+        if let Err(e) = server.await {
+            error!("RPC server stopped: {}", e);
+        }
+    });
+
+    if let Some(mut config_updates) = config_updates {
+        tokio::spawn(async move {
+            while config_updates.changed().await.is_ok() {
+                let reloaded = RpcLimits::from(&*config_updates.borrow_and_update());
+                if reloaded == limits {
+                    continue;
+                }
+                info!(
+                    "RPC limits reloaded, they apply to the connections opened from now on: {:?}",
+                    reloaded
+                );
+                *current_service.write().unwrap() = service_builder(&reloaded);
+                limits = reloaded;
+            }
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use jsonrpsee::core::client::ClientT;
+    use jsonrpsee::http_client::HttpClientBuilder;
+    use jsonrpsee::rpc_params;
+    use sov_db::ledger_db::LedgerDB;
+
+    use super::*;
+
+    fn reloadable_config(max_request_body_size: u32) -> ReloadableConfig {
+        ReloadableConfig {
+            log_level: None,
+            sync_blocks_count: 10,
+            max_connections: 100,
+            max_request_body_size,
+            max_response_body_size: 10 * 1024 * 1024,
+            batch_requests_limit: 50,
+            max_subscriptions_per_connection: 100,
+            max_fee_rate: None,
+            max_fee_bump_rate: None,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reloaded_limits_apply_without_rebinding() {
+        let tmpdir = tempfile::tempdir().unwrap();
+        let ledger_db = LedgerDB::with_path(tmpdir.path()).unwrap();
+        let rpc_config: RpcConfig = toml::from_str(
+            r#"
+            bind_host = "127.0.0.1"
+            bind_port = 0
+        "#,
+        )
+        .unwrap();
+        let mut methods = RpcModule::new(());
+        methods
+            .register_method("test_echo", |params, _| params.one::<String>())
+            .unwrap();
+        let (config_tx, config_rx) = watch::channel(reloadable_config(10 * 1024 * 1024));
+        let (address_tx, address_rx) = oneshot::channel();
+        spawn_rpc_server(
+            &rpc_config,
+            methods,
+            ledger_db,
+            RpcServerOptions {
+                config_updates: Some(config_rx),
+                ..Default::default()
+            },
+            Some(address_tx),
+        )
+        .unwrap();
+        let address = address_rx.await.unwrap();
+        let echo = |message: String| async move {
+            // A new client opens a new connection
+            let client = HttpClientBuilder::default()
+                .build(format!("http://{}", address))
+                .unwrap();
+            client
+                .request::<String, _>("test_echo", rpc_params![message])
+                .await
+        };
+
+        let large = "a".repeat(1000);
+        assert_eq!(echo(large.clone()).await.unwrap(), large);
+
+        config_tx.send_replace(reloadable_config(100));
+        let mut rejected = false;
+        for _ in 0..50 {
+            if echo(large.clone()).await.is_err() {
+                rejected = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(rejected, "Reloaded request body limit was not applied");
+        // Still served at the same address
+        assert_eq!(echo("small".to_string()).await.unwrap(), "small");
+    }
+}
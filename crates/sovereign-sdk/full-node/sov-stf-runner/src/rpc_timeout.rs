@@ -0,0 +1,147 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::{BoxFuture, Either};
+use futures::FutureExt;
+use jsonrpsee::server::middleware::rpc::RpcServiceT;
+use jsonrpsee::server::MethodResponse;
+use jsonrpsee::types::{ErrorObjectOwned, Request};
+use sov_rollup_interface::rpc::deadline::{
+    DeadlineExceeded, RequestDeadline, RPC_TIMEOUT_ERROR_CODE,
+};
+use tracing::warn;
+
+use crate::RpcConfig;
+
+/// RPC middleware which cancels the requests running longer than the timeout of their method.
+///
+/// Handlers waiting on IO are dropped once the timeout elapses. Handlers computing synchronously
+/// cannot be preempted, so the deadline of the request is visible to
+/// [`check_deadline`](sov_rollup_interface::rpc::deadline::check_deadline), which their long
+/// running loops call to give up the work of timed out requests.
+#[derive(Debug, Clone, Default)]
+pub struct RpcTimeoutLayer {
+    method_timeouts: Arc<HashMap<String, Duration>>,
+    default_timeout: Option<Duration>,
+}
+
+impl RpcTimeoutLayer {
+    /// Times out the requests with the method timeouts of the RPC config
+    pub fn new(config: &RpcConfig) -> Self {
+        let method_timeouts = config
+            .method_timeouts_ms
+            .iter()
+            .map(|(method, ms)| (method.clone(), Duration::from_millis(*ms)))
+            .collect();
+        Self {
+            method_timeouts: Arc::new(method_timeouts),
+            default_timeout: config.default_method_timeout_ms.map(Duration::from_millis),
+        }
+    }
+
+    /// Timeout of the requests of a method, `None` if they do not time out
+    fn timeout(&self, method: &str) -> Option<Duration> {
+        self.method_timeouts
+            .get(method)
+            .copied()
+            .or(self.default_timeout)
+    }
+}
+
+impl<S> tower::Layer<S> for RpcTimeoutLayer {
+    type Service = RpcTimeoutService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RpcTimeoutService {
+            service,
+            timeouts: self.clone(),
+        }
+    }
+}
+
+/// Service of the [`RpcTimeoutLayer`]
+#[derive(Debug, Clone)]
+pub struct RpcTimeoutService<S> {
+    service: S,
+    timeouts: RpcTimeoutLayer,
+}
+
+impl<'a, S> RpcServiceT<'a> for RpcTimeoutService<S>
+where
+    S: RpcServiceT<'a>,
+    S::Future: 'a,
+{
+    type Future = Either<S::Future, BoxFuture<'a, MethodResponse>>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let Some(timeout) = self.timeouts.timeout(request.method_name()) else {
+            return Either::Left(self.service.call(request));
+        };
+        let timeout_at = tokio::time::Instant::now() + timeout;
+        let deadline = RequestDeadline::after(timeout);
+        let id = request.id.clone();
+        let method = request.method_name().to_owned();
+        // Synchronous methods are handled right away, before the future is polled
+        let future = deadline.sync_scope(|| self.service.call(request));
+        Either::Right(
+            async move {
+                match tokio::time::timeout_at(timeout_at, deadline.scope(future)).await {
+                    Ok(response) => response,
+                    Err(_) => {
+                        let error = DeadlineExceeded { timeout };
+                        warn!(method, "RPC request cancelled: {}", error);
+                        MethodResponse::error(
+                            id,
+                            ErrorObjectOwned::owned(
+                                RPC_TIMEOUT_ERROR_CODE,
+                                error.to_string(),
+                                None::<()>,
+                            ),
+                        )
+                    }
+                }
+            }
+            .boxed(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_timeouts_fall_back_to_default() {
+        let config: RpcConfig = toml::from_str(
+            r#"
+            bind_host = "127.0.0.1"
+            bind_port = 12345
+            default_method_timeout_ms = 5000
+
+            [method_timeouts_ms]
+            eth_getLogs = 30000
+        "#,
+        )
+        .unwrap();
+        let layer = RpcTimeoutLayer::new(&config);
+        assert_eq!(
+            layer.timeout("eth_getLogs"),
+            Some(Duration::from_millis(30000))
+        );
+        assert_eq!(
+            layer.timeout("debug_traceTransaction"),
+            Some(Duration::from_millis(5000))
+        );
+
+        let layer = RpcTimeoutLayer::new(&RpcConfig {
+            default_method_timeout_ms: None,
+            ..config
+        });
+        assert_eq!(
+            layer.timeout("eth_getLogs"),
+            Some(Duration::from_millis(30000))
+        );
+        assert_eq!(layer.timeout("debug_traceTransaction"), None);
+    }
+}
@@ -1,7 +1,7 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use sov_modules_core::{AccessoryWorkingSet, Context, Spec, Storage, WorkingSet};
-use sov_rollup_interface::da::{BlobReaderTrait, DaSpec};
+use sov_rollup_interface::da::{BlobReaderTrait, DaSpec, SlotInbox};
 use sov_rollup_interface::soft_confirmation::SignedSoftConfirmationBatch;
 use thiserror::Error;
 
@@ -40,6 +40,46 @@ pub enum ApplySoftConfirmationError {
         prev
     )]
     CurrentTimestampIsNotGreaterThanPrev { current: u64, prev: u64 },
+    #[error(
+        "Block's timestamp {} is more than {} seconds away from the DA block's timestamp {}",
+        timestamp,
+        max_drift,
+        da_slot_timestamp
+    )]
+    TimestampOutOfDaSlotWindow {
+        timestamp: u64,
+        da_slot_timestamp: u64,
+        max_drift: u64,
+    },
+    #[error(
+        "Forced transaction {:?} posted on DA block {} is not included until DA block {}",
+        tx_hash,
+        l1_height,
+        da_slot_height
+    )]
+    ForcedTransactionNotIncluded {
+        tx_hash: [u8; 32],
+        l1_height: u64,
+        da_slot_height: u64,
+    },
+    #[error(
+        "Soft confirmation on DA block {} skips the DA blocks after DA block {}",
+        da_slot_height,
+        last_da_slot_height
+    )]
+    DaSlotSkipped {
+        da_slot_height: u64,
+        last_da_slot_height: u64,
+    },
+    #[error(
+        "Soft confirmation doesn't start with the {} deposits posted on DA block {}",
+        deposit_count,
+        da_slot_height
+    )]
+    DepositsNotIncluded {
+        da_slot_height: u64,
+        deposit_count: usize,
+    },
 }
 
 /// Hooks that execute within the `StateTransitionFunction::apply_blob` function for each processed transaction.
@@ -131,12 +171,18 @@ pub struct HookSoftConfirmationInfo {
     pub l1_fee_rate: u128,
     /// Timestamp
     pub timestamp: u64,
+    /// Timestamp of the DA block this soft confirmation was given for
+    pub da_slot_timestamp: u64,
+    /// Inbox of the DA block this soft confirmation was given for
+    pub slot_inbox: SlotInbox,
 }
 
 impl HookSoftConfirmationInfo {
     pub fn new(
         signed_soft_confirmation: SignedSoftConfirmationBatch,
         pre_state_root: Vec<u8>,
+        da_slot_timestamp: u64,
+        slot_inbox: SlotInbox,
     ) -> Self {
         HookSoftConfirmationInfo {
             da_slot_height: signed_soft_confirmation.da_slot_height(),
@@ -147,6 +193,8 @@ impl HookSoftConfirmationInfo {
             deposit_data: signed_soft_confirmation.deposit_data(),
             l1_fee_rate: signed_soft_confirmation.l1_fee_rate(),
             timestamp: signed_soft_confirmation.timestamp(),
+            da_slot_timestamp,
+            slot_inbox,
         }
     }
 }
@@ -206,6 +254,14 @@ impl HookSoftConfirmationInfo {
     pub fn timestamp(&self) -> u64 {
         self.timestamp
     }
+
+    pub fn da_slot_timestamp(&self) -> u64 {
+        self.da_slot_timestamp
+    }
+
+    pub fn slot_inbox(&self) -> &SlotInbox {
+        &self.slot_inbox
+    }
 }
 
 /// Hooks that execute during the `StateTransitionFunction::begin_slot` and `end_slot` functions.
@@ -3,6 +3,7 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use sov_rollup_interface::zk::HintWitness;
 
 /// A witness is a value produced during native execution that is then used by
 /// the zkVM circuit to produce proofs.
@@ -14,7 +15,7 @@ use serde::Serialize;
 /// they were added via [`Witness::add_hint`].
 // TODO: Refactor witness trait so it only require Serialize / Deserialize
 //   https://github.com/Sovereign-Labs/sovereign-sdk/issues/263
-pub trait Witness: Default + BorshDeserialize + Serialize + DeserializeOwned {
+pub trait Witness: Default + BorshDeserialize + Serialize + DeserializeOwned + HintWitness {
     /// Adds a serializable "hint" to the witness value, which can be later
     /// read by the zkVM circuit.
     ///
@@ -228,6 +228,14 @@ pub trait Storage: Clone {
         None
     }
 
+    /// Returns the earliest version whose state can still be read, or [`None`]
+    /// if every version is available.
+    ///
+    /// Only native storages can be pruned, so this method is blanket-implemented to return [`None`].
+    fn earliest_available_version(&self) -> Option<Version> {
+        None
+    }
+
     /// Calculates new state root but does not commit any changes to the database.
     fn compute_state_update(
         &self,
@@ -382,6 +382,12 @@ impl<C: Context> WorkingSet<C> {
         self.archival_accessory_working_set = None;
     }
 
+    /// Returns the earliest archival version which can still be read,
+    /// or [`None`] if the underlying storage has not been pruned.
+    pub fn earliest_archival_version(&self) -> Option<Version> {
+        self.delta.inner.inner.earliest_available_version()
+    }
+
     /// Returns a handler for the kernel state (priveleged jmt state)
     ///
     /// You can use this method when calling getters and setters on accessory
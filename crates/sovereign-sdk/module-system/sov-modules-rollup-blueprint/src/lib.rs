@@ -13,7 +13,7 @@ use sov_rollup_interface::services::da::DaService;
 use sov_rollup_interface::storage::HierarchicalStorageManager;
 use sov_rollup_interface::zk::{Zkvm, ZkvmHost};
 use sov_state::Storage;
-use sov_stf_runner::{FullNodeConfig, ProverConfig, ProverService};
+use sov_stf_runner::{FullNodeConfig, ProverConfig, ProverService, RpcConfig};
 use tokio::sync::broadcast;
 pub use wallet::*;
 
@@ -28,12 +28,12 @@ pub trait RollupBlueprint: Sized + Send + Sync {
     type DaConfig: Send + Sync;
 
     /// Host of a zkVM program.
-    type Vm: ZkvmHost + Zkvm + Send;
+    type Vm: ZkvmHost + Zkvm + Send + 'static;
 
     /// Context for Zero Knowledge environment.
     type ZkContext: Context;
     /// Context for Native environment.
-    type NativeContext: Context;
+    type NativeContext: Context + Send + Sync;
 
     /// Manager for the native storage lifecycle.
     type StorageManager: HierarchicalStorageManager<
@@ -45,7 +45,11 @@ pub trait RollupBlueprint: Sized + Send + Sync {
     /// Runtime for the Zero Knowledge environment.
     type ZkRuntime: RuntimeTrait<Self::ZkContext, Self::DaSpec> + Default;
     /// Runtime for the Native environment.
-    type NativeRuntime: RuntimeTrait<Self::NativeContext, Self::DaSpec> + Default + Send + Sync;
+    type NativeRuntime: RuntimeTrait<Self::NativeContext, Self::DaSpec>
+        + Default
+        + Send
+        + Sync
+        + 'static;
 
     /// Prover service.
     type ProverService: ProverService<
@@ -61,12 +65,19 @@ pub trait RollupBlueprint: Sized + Send + Sync {
     /// Get code commitment.
     fn get_code_commitment(&self) -> <Self::Vm as Zkvm>::CodeCommitment;
 
+    /// Get code commitment of the guest aggregating proofs.
+    fn get_aggregation_code_commitment(&self) -> <Self::Vm as Zkvm>::CodeCommitment;
+
+    /// Get code commitment of the light client guest.
+    fn get_light_client_code_commitment(&self) -> <Self::Vm as Zkvm>::CodeCommitment;
+
     /// Creates RPC methods for the rollup.
     fn create_rpc_methods(
         &self,
         storage: &<Self::NativeContext as Spec>::Storage,
         ledger_db: &LedgerDB,
         da_service: &Self::DaService,
+        rpc_config: &RpcConfig,
         sequencer_client_url: Option<String>,
         soft_confirmation_rx: Option<broadcast::Receiver<u64>>,
     ) -> Result<jsonrpsee::RpcModule<()>, anyhow::Error>;
@@ -117,7 +128,19 @@ pub trait RollupBlueprint: Sized + Send + Sync {
     ) -> Result<Self::StorageManager, anyhow::Error>;
 
     /// Creates instance of a LedgerDB.
+    /// Opens it in secondary mode if the node is a read-only replica.
     fn create_ledger_db(&self, rollup_config: &FullNodeConfig<Self::DaConfig>) -> LedgerDB {
-        LedgerDB::with_path(&rollup_config.storage.path).expect("Ledger DB failed to open")
+        match &rollup_config.storage.replica {
+            Some(replica_config) => LedgerDB::with_secondary(
+                &rollup_config.storage.path,
+                &replica_config.secondary_path,
+                &rollup_config.storage.ledger_db,
+            ),
+            None => LedgerDB::with_config(
+                &rollup_config.storage.path,
+                &rollup_config.storage.ledger_db,
+            ),
+        }
+        .expect("Ledger DB failed to open")
     }
 }
@@ -6,7 +6,6 @@ mod stf_blueprint;
 mod tx_verifier;
 
 pub use batch::Batch;
-use borsh::BorshDeserialize;
 use itertools::Itertools;
 use rs_merkle::algorithms::Sha256;
 use rs_merkle::MerkleTree;
@@ -19,13 +18,15 @@ use sov_modules_api::{
     native_debug, native_warn, BasicAddress, BlobReaderTrait, Context, DaSpec, DispatchCall,
     Genesis, Signature, Spec, StateCheckpoint, UnsignedSoftConfirmationBatch, WorkingSet, Zkvm,
 };
-use sov_rollup_interface::da::{DaData, SequencerCommitment};
+use sov_rollup_interface::da::{DaData, SequencerCommitment, SlotInbox};
 use sov_rollup_interface::digest::Digest;
 use sov_rollup_interface::soft_confirmation::SignedSoftConfirmationBatch;
 pub use sov_rollup_interface::stf::{BatchReceipt, TransactionReceipt};
-use sov_rollup_interface::stf::{SlotResult, StateTransitionFunction};
+use sov_rollup_interface::stf::{SlotResult, StateDiff, StateTransitionFunction};
 use sov_rollup_interface::zk::CumulativeStateDiff;
 use sov_state::Storage;
+#[cfg(all(target_os = "zkvm", feature = "bench"))]
+use sov_zk_cycle_macros::cycle_tracker;
 pub use stf_blueprint::StfBlueprint;
 pub use tx_verifier::RawTx;
 
@@ -77,6 +78,13 @@ pub trait Runtime<C: Context, Da: DaSpec>:
     fn genesis_config(
         genesis_paths: &Self::GenesisPaths,
     ) -> Result<Self::GenesisConfig, anyhow::Error>;
+
+    /// Public key the next soft confirmation must be signed with, if the runtime keeps track of
+    /// the active sequencer in its state.
+    /// The sequencer public key given to the STF is used otherwise.
+    fn sequencer_public_key(&self, _working_set: &mut WorkingSet<C>) -> Option<Vec<u8>> {
+        None
+    }
 }
 
 /// The receipts of all the transactions in a batch.
@@ -127,6 +135,7 @@ pub trait StfBlueprintTrait<C: Context, Da: DaSpec, Vm: Zkvm>:
     StateTransitionFunction<Vm, Da>
 {
     /// Begin a soft batch
+    #[allow(clippy::too_many_arguments)]
     fn begin_soft_batch(
         &self,
         sequencer_public_key: &[u8],
@@ -134,6 +143,7 @@ pub trait StfBlueprintTrait<C: Context, Da: DaSpec, Vm: Zkvm>:
         pre_state: Self::PreState,
         witness: <<C as Spec>::Storage as Storage>::Witness,
         slot_header: &<Da as DaSpec>::BlockHeader,
+        slot_inbox: &SlotInbox,
         soft_batch: &mut SignedSoftConfirmationBatch,
     ) -> (Result<(), ApplySoftConfirmationError>, WorkingSet<C>);
 
@@ -183,14 +193,23 @@ where
         pre_state: <C>::Storage,
         witness: <<C as Spec>::Storage as Storage>::Witness,
         slot_header: &<Da as DaSpec>::BlockHeader,
+        slot_inbox: &SlotInbox,
         soft_batch: &mut SignedSoftConfirmationBatch,
     ) -> (Result<(), ApplySoftConfirmationError>, WorkingSet<C>) {
         native_debug!("Applying soft batch in STF Blueprint");
 
+        let mut working_set = StateCheckpoint::with_witness(pre_state, witness).to_revertable();
+
+        // the active sequencer can be rotated on chain, the given key is used until then
+        let sequencer_public_key = self
+            .runtime
+            .sequencer_public_key(&mut working_set)
+            .unwrap_or_else(|| sequencer_public_key.to_vec());
+
         // check if soft confirmation is coming from our sequencer
         assert_eq!(
             soft_batch.sequencer_pub_key(),
-            sequencer_public_key,
+            sequencer_public_key.as_slice(),
             "Sequencer public key must match"
         );
 
@@ -208,9 +227,18 @@ where
             "DA slot hashes must match"
         );
 
-        let checkpoint = StateCheckpoint::with_witness(pre_state, witness);
+        let checkpoint = working_set.checkpoint();
 
-        self.begin_soft_confirmation_inner(checkpoint, soft_batch, pre_state_root)
+        // Timestamps before the unix epoch are not expected from any DA layer
+        let da_slot_timestamp = u64::try_from(slot_header.time().secs()).unwrap_or_default();
+
+        self.begin_soft_confirmation_inner(
+            checkpoint,
+            soft_batch,
+            pre_state_root,
+            da_slot_timestamp,
+            slot_inbox,
+        )
     }
 
     fn apply_soft_batch_txs(
@@ -223,7 +251,7 @@ where
 
     fn end_soft_batch(
         &self,
-        sequencer_public_key: &[u8],
+        _sequencer_public_key: &[u8],
         soft_batch: &mut SignedSoftConfirmationBatch,
         tx_receipts: Vec<TransactionReceipt<TxEffect>>,
         batch_workspace: WorkingSet<C>,
@@ -247,12 +275,12 @@ where
             "Soft confirmation hashes must match"
         );
 
-        // verify signature
+        // verify signature, the public key is checked against the active sequencer in `begin_soft_batch`
         assert!(
             verify_soft_batch_signature::<C>(
                 unsigned,
                 soft_batch.signature().as_slice(),
-                sequencer_public_key
+                soft_batch.sequencer_pub_key()
             )
             .is_ok(),
             "Signature verification must succeed"
@@ -412,6 +440,7 @@ where
         pre_state: Self::PreState,
         witness: Self::Witness,
         slot_header: &<Da as DaSpec>::BlockHeader,
+        slot_inbox: &SlotInbox,
         _validity_condition: &<Da as DaSpec>::ValidityCondition,
         soft_batch: &mut SignedSoftConfirmationBatch,
     ) -> SlotResult<
@@ -427,6 +456,7 @@ where
             pre_state.clone(),
             witness,
             slot_header,
+            slot_inbox,
             soft_batch,
         ) {
             (Ok(()), batch_workspace) => {
@@ -470,21 +500,30 @@ where
         sequencer_commitments_range: (u32, u32),
         witnesses: std::collections::VecDeque<Vec<Self::Witness>>,
         slot_headers: std::collections::VecDeque<Vec<<Da as DaSpec>::BlockHeader>>,
+        slot_inboxes: std::collections::VecDeque<Vec<SlotInbox>>,
         validity_condition: &<Da as DaSpec>::ValidityCondition,
         soft_confirmations: std::collections::VecDeque<Vec<SignedSoftConfirmationBatch>>,
     ) -> (Self::StateRoot, CumulativeStateDiff) {
         let mut state_diff = CumulativeStateDiff::default();
 
         // First extract all sequencer commitments
-        // Ignore broken DaData and zk proofs. Also ignore ForcedTransaction's (will be implemented in the future).
-        let mut sequencer_commitments: Vec<SequencerCommitment> = vec![];
+        // Ignore broken DaData and zk proofs. ForcedTransaction's are read from the inboxes of the
+        // DA blocks of the soft confirmations instead.
+        // Commitments posted with a state diff keep it, to be checked against the execution.
+        let mut sequencer_commitments: Vec<(SequencerCommitment, Option<StateDiff>)> = vec![];
         for blob in da_data {
             // TODO: get sequencer da pub key
             if blob.sender().as_ref() == sequencer_da_public_key {
-                let da_data = DaData::try_from_slice(blob.verified_data());
-
-                if let Ok(DaData::SequencerCommitment(commitment)) = da_data {
-                    sequencer_commitments.push(commitment);
+                let da_data = DaData::decode_activated(blob.verified_data());
+
+                match da_data {
+                    Ok(DaData::SequencerCommitment(commitment)) => {
+                        sequencer_commitments.push((commitment, None));
+                    }
+                    Ok(DaData::CommitmentWithStateDiff(data)) => {
+                        sequencer_commitments.push((data.commitment, Some(data.state_diff)));
+                    }
+                    _ => {}
                 }
             }
         }
@@ -496,17 +535,22 @@ where
         let mut last_commitment_end_height: Option<u64> = None;
 
         // should panic if number of sequencer commitments, soft confirmations, slot headers and witnesses don't match
-        for (((sequencer_commitment, soft_confirmations), da_block_headers), witnesses) in
-            sequencer_commitments
-                .into_iter()
-                .skip(sequencer_commitments_range.0 as usize)
-                .take(
-                    sequencer_commitments_range.1 as usize - sequencer_commitments_range.0 as usize
-                        + 1,
-                )
-                .zip_eq(soft_confirmations)
-                .zip_eq(slot_headers)
-                .zip_eq(witnesses)
+        for (
+            (
+                (((sequencer_commitment, posted_state_diff), soft_confirmations), da_block_headers),
+                da_slot_inboxes,
+            ),
+            witnesses,
+        ) in sequencer_commitments
+            .into_iter()
+            .skip(sequencer_commitments_range.0 as usize)
+            .take(
+                sequencer_commitments_range.1 as usize - sequencer_commitments_range.0 as usize + 1,
+            )
+            .zip_eq(soft_confirmations)
+            .zip_eq(slot_headers)
+            .zip_eq(slot_inboxes)
+            .zip_eq(witnesses)
         {
             // if the commitment is not sequential, then the proof is invalid.
             if let Some(end_height) = last_commitment_end_height {
@@ -635,15 +679,17 @@ where
                 "Invalid merkle root"
             );
 
-            let mut da_block_headers_iter = da_block_headers.into_iter().peekable();
-            let mut da_block_header = da_block_headers_iter.next().unwrap();
+            // every DA block header has its inbox
+            let mut da_block_headers_iter = da_block_headers.into_iter().zip_eq(da_slot_inboxes);
+            let (mut da_block_header, mut da_slot_inbox) = da_block_headers_iter.next().unwrap();
+            let mut commitment_state_diff = CumulativeStateDiff::default();
 
             // now that we verified the claimed root, we can apply the soft confirmations
             // should panic if the number of witnesses and soft confirmations don't match
             for (mut soft_confirmation, witness) in soft_confirmations.into_iter().zip_eq(witnesses)
             {
                 if soft_confirmation.da_slot_height() != da_block_header.height() {
-                    da_block_header = da_block_headers_iter.next().unwrap();
+                    (da_block_header, da_slot_inbox) = da_block_headers_iter.next().unwrap();
                 }
 
                 let result = self.apply_soft_batch(
@@ -652,19 +698,64 @@ where
                     pre_state.clone(),
                     witness,
                     &da_block_header,
+                    &da_slot_inbox,
                     validity_condition,
                     &mut soft_confirmation,
                 );
 
                 current_state_root = result.state_root;
-                state_diff.extend(result.state_diff);
+                commitment_state_diff.extend(result.state_diff);
             }
+
+            if let Some(posted_state_diff) = posted_state_diff {
+                assert!(
+                    posted_state_diff
+                        .iter()
+                        .map(|(key, value)| (key, value))
+                        .eq(commitment_state_diff.iter()),
+                    "Posted state diff must match execution"
+                );
+            }
+            state_diff.extend(commitment_state_diff);
         }
 
         (current_state_root, state_diff)
     }
 }
 
+/// Checks the claimed hash and the sequencer signature of a soft batch.
+/// Does not depend on state, so it can run ahead of execution.
+pub fn verify_soft_batch<C: Context>(
+    sequencer_public_key: &[u8],
+    soft_batch: &SignedSoftConfirmationBatch,
+) -> Result<(), anyhow::Error> {
+    let unsigned = UnsignedSoftConfirmationBatch::new(
+        soft_batch.da_slot_height(),
+        soft_batch.da_slot_hash(),
+        soft_batch.da_slot_txs_commitment(),
+        soft_batch.txs(),
+        soft_batch.deposit_data(),
+        soft_batch.l1_fee_rate(),
+        soft_batch.timestamp(),
+    );
+
+    let unsigned_raw = borsh::to_vec(&unsigned).unwrap();
+
+    if soft_batch.hash() != Into::<[u8; 32]>::into(<C as Spec>::Hasher::digest(unsigned_raw)) {
+        anyhow::bail!("Soft confirmation hash mismatch");
+    }
+
+    verify_soft_batch_signature::<C>(
+        unsigned,
+        soft_batch.signature().as_slice(),
+        sequencer_public_key,
+    )
+}
+
+#[cfg_attr(
+    all(target_os = "zkvm", feature = "bench"),
+    cycle_tracker("signature_checks")
+)]
 fn verify_soft_batch_signature<C: Context>(
     unsigned_soft_confirmation: UnsignedSoftConfirmationBatch,
     signature: &[u8],
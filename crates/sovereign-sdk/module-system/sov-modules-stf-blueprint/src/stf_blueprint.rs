@@ -4,6 +4,7 @@ use sov_modules_api::hooks::{ApplySoftConfirmationError, HookSoftConfirmationInf
 use sov_modules_api::{
     native_debug, native_error, Context, DaSpec, DispatchCall, StateCheckpoint, WorkingSet,
 };
+use sov_rollup_interface::da::SlotInbox;
 use sov_rollup_interface::soft_confirmation::SignedSoftConfirmationBatch;
 use sov_rollup_interface::stf::{BatchReceipt, TransactionReceipt};
 use sov_state::Storage;
@@ -58,6 +59,10 @@ where
 
     /// Applies sov txs to the state
     #[cfg_attr(feature = "native", instrument(level = "trace", skip_all))]
+    #[cfg_attr(
+        all(target_os = "zkvm", feature = "bench"),
+        cycle_tracker("evm_execution")
+    )]
     pub fn apply_sov_txs_inner(
         &self,
         txs: Vec<Vec<u8>>,
@@ -156,6 +161,8 @@ where
         checkpoint: StateCheckpoint<C>,
         soft_batch: &mut SignedSoftConfirmationBatch,
         pre_state_root: &<C::Storage as Storage>::Root,
+        da_slot_timestamp: u64,
+        slot_inbox: &SlotInbox,
     ) -> (Result<(), ApplySoftConfirmationError>, WorkingSet<C>) {
         native_debug!(
             "Beginning soft batch 0x{} from sequencer: 0x{}",
@@ -170,6 +177,8 @@ where
             &mut HookSoftConfirmationInfo::new(
                 soft_batch.clone(),
                 pre_state_root.as_ref().to_vec(),
+                da_slot_timestamp,
+                slot_inbox.clone(),
             ),
             &mut batch_workspace,
         ) {
@@ -230,8 +239,14 @@ where
         checkpoint: StateCheckpoint<C>,
         soft_batch: &mut SignedSoftConfirmationBatch,
         pre_state_root: &<C::Storage as Storage>::Root,
+        da_slot_timestamp: u64,
     ) -> (ApplySoftConfirmationResult, StateCheckpoint<C>) {
-        match self.begin_soft_confirmation_inner(checkpoint, soft_batch, pre_state_root) {
+        match self.begin_soft_confirmation_inner(
+            checkpoint,
+            soft_batch,
+            pre_state_root,
+            da_slot_timestamp,
+        ) {
             (Ok(()), batch_workspace) => {
                 // TODO: wait for txs here, apply_sov_txs can be called multiple times
                 let (batch_workspace, tx_receipts) =
@@ -245,6 +260,10 @@ where
 
     // Stateless verification of transaction, such as signature check
     // Single malformed transaction results in sequencer slashing.
+    #[cfg_attr(
+        all(target_os = "zkvm", feature = "bench"),
+        cycle_tracker("signature_checks")
+    )]
     fn verify_txs_stateless_soft(&self, txs: &[Vec<u8>]) -> Vec<TransactionAndRawHash<C>> {
         verify_txs_stateless(
             txs.iter()
@@ -6,12 +6,16 @@ pub mod codec;
 
 #[cfg(feature = "native")]
 mod prover_storage;
+#[cfg(feature = "native")]
+mod state_cache;
 
 mod witness;
 mod zk_storage;
 
 #[cfg(feature = "native")]
 pub use prover_storage::ProverStorage;
+#[cfg(feature = "native")]
+pub use state_cache::StateCache;
 pub use zk_storage::ZkStorage;
 
 pub mod config;
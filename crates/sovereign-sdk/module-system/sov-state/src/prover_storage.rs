@@ -1,7 +1,7 @@
 use std::marker::PhantomData;
 use std::sync::Arc;
 
-use jmt::storage::{NodeBatch, TreeWriter};
+use jmt::storage::{NodeBatch, StaleNodeIndexBatch, TreeWriter};
 use jmt::{JellyfishMerkleTree, KeyHash, Version};
 use sov_db::native_db::NativeDB;
 use sov_db::schema::{QueryManager, ReadOnlyDbSnapshot};
@@ -13,13 +13,16 @@ use sov_modules_core::{
 use sov_rollup_interface::stf::StateDiff;
 
 use crate::config::Config;
-use crate::MerkleProofSpec;
+use crate::{MerkleProofSpec, StateCache};
 
 /// A [`Storage`] implementation to be used by the prover in a native execution
 /// environment (outside of the zkVM).
 pub struct ProverStorage<S: MerkleProofSpec, Q> {
     db: StateDB<Q>,
     native_db: NativeDB<Q>,
+    cache: Option<StateCache>,
+    /// Version the latest state is read at, if reads are pinned
+    read_version: Option<Version>,
     _phantom_hasher: PhantomData<S::Hasher>,
 }
 
@@ -28,6 +31,8 @@ impl<S: MerkleProofSpec, Q> Clone for ProverStorage<S, Q> {
         Self {
             db: self.db.clone(),
             native_db: self.native_db.clone(),
+            cache: self.cache.clone(),
+            read_version: self.read_version,
             _phantom_hasher: Default::default(),
         }
     }
@@ -39,10 +44,26 @@ impl<S: MerkleProofSpec, Q> ProverStorage<S, Q> {
         Self {
             db,
             native_db,
+            cache: None,
+            read_version: None,
             _phantom_hasher: Default::default(),
         }
     }
 
+    /// Serves reads of the latest state through the given [`StateCache`],
+    /// and drops the values written on commit from it.
+    pub fn with_state_cache(mut self, cache: StateCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Reads the latest state at the current version, so blocks committed later are not seen.
+    /// Such reads are not served through the [`StateCache`].
+    pub fn with_pinned_reads(mut self) -> Self {
+        self.read_version = Some(self.db.get_next_version().saturating_sub(1));
+        self
+    }
+
     /// Converts it to pair of readonly [`ReadOnlyDbSnapshot`]s
     /// First is from [`StateDB`]
     /// Second is from [`NativeDB`]
@@ -56,20 +77,32 @@ impl<S: MerkleProofSpec, Q> ProverStorage<S, Q> {
 
 impl<S: MerkleProofSpec, Q: QueryManager> ProverStorage<S, Q> {
     fn read_value(&self, key: &StorageKey, version: Option<Version>) -> Option<StorageValue> {
+        let version = version.or(self.read_version);
+        // Only the latest state is cached
+        let cache = self.cache.as_ref().filter(|_| version.is_none());
+        if let Some(value) = cache.and_then(|cache| cache.get(key)) {
+            return value;
+        }
+
         let version_to_use = version.unwrap_or_else(|| self.db.get_next_version());
-        match self
+        let value: Option<StorageValue> = match self
             .db
             .get_value_option_by_key(version_to_use, key.as_ref())
         {
             Ok(value) => value.map(Into::into),
             // It is ok to panic here, we assume the db is available and consistent.
             Err(e) => panic!("Unable to read value from db: {e}"),
+        };
+        if let Some(cache) = cache {
+            cache.insert(key, value.clone());
         }
+        value
     }
 }
 
 pub struct ProverStateUpdate {
     pub(crate) node_batch: NodeBatch,
+    pub(crate) stale_node_indices: StaleNodeIndexBatch,
     pub key_preimages: Vec<(KeyHash, CacheKey)>,
 }
 
@@ -100,6 +133,15 @@ impl<S: MerkleProofSpec, Q: QueryManager> Storage for ProverStorage<S, Q> {
             .map(Into::into)
     }
 
+    #[cfg(feature = "native")]
+    fn earliest_available_version(&self) -> Option<Version> {
+        match self.db.get_earliest_available_version() {
+            Ok(version) => version,
+            // It is ok to panic here, we assume the db is available and consistent.
+            Err(e) => panic!("Unable to read earliest available version from db: {e}"),
+        }
+    }
+
     fn compute_state_update(
         &self,
         state_accesses: OrderedReadsAndWrites,
@@ -170,6 +212,7 @@ impl<S: MerkleProofSpec, Q: QueryManager> Storage for ProverStorage<S, Q> {
 
         let state_update = ProverStateUpdate {
             node_batch: tree_update.node_batch,
+            stale_node_indices: tree_update.stale_node_index_batch,
             key_preimages,
         };
 
@@ -207,6 +250,19 @@ impl<S: MerkleProofSpec, Q: QueryManager> Storage for ProverStorage<S, Q> {
             .write_node_batch(&state_update.node_batch)
             .expect("db write must succeed");
 
+        self.db
+            .put_stale_node_indices(&state_update.stale_node_indices)
+            .expect("db write must succeed");
+
+        if let Some(cache) = &self.cache {
+            cache.invalidate(
+                state_update
+                    .key_preimages
+                    .iter()
+                    .map(|(_, key)| key.key.as_ref()),
+            );
+        }
+
         // Finally, update our in-memory view of the current item numbers
         self.db.inc_next_version();
     }
@@ -0,0 +1,56 @@
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+use sov_modules_core::{StorageKey, StorageValue};
+
+/// LRU cache of the latest state values, shared by the storages of consecutive L2 blocks.
+///
+/// Values are cached as read by the storage of the latest L2 block and dropped when written,
+/// so hot keys (EVM accounts, storage slots and code) are not read from the database on every block.
+/// Must be cleared when a storage whose writes were committed is discarded.
+#[derive(Clone)]
+pub struct StateCache {
+    values: Arc<Mutex<LruCache<Vec<u8>, Option<StorageValue>>>>,
+}
+
+impl StateCache {
+    /// Creates a cache keeping at most `capacity` values.
+    pub fn new(capacity: NonZeroUsize) -> Self {
+        Self {
+            values: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+
+    /// Returns the cached value of the key, `Some(None)` if the key is known to be unset.
+    pub(crate) fn get(&self, key: &StorageKey) -> Option<Option<StorageValue>> {
+        self.values
+            .lock()
+            .expect("State cache lock poisoned")
+            .get(key.as_ref())
+            .cloned()
+    }
+
+    pub(crate) fn insert(&self, key: &StorageKey, value: Option<StorageValue>) {
+        self.values
+            .lock()
+            .expect("State cache lock poisoned")
+            .put(key.as_ref().clone(), value);
+    }
+
+    /// Drops the cached values of the written keys.
+    pub(crate) fn invalidate<'a>(&self, keys: impl IntoIterator<Item = &'a Vec<u8>>) {
+        let mut values = self.values.lock().expect("State cache lock poisoned");
+        for key in keys {
+            values.pop(key);
+        }
+    }
+
+    /// Drops all cached values.
+    pub fn clear(&self) {
+        self.values
+            .lock()
+            .expect("State cache lock poisoned")
+            .clear();
+    }
+}
@@ -1,6 +1,7 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use sov_modules_core::Witness;
+use sov_rollup_interface::zk::HintWitness;
 
 /// A [`Vec`]-based implementation of [`Witness`] with no special logic.
 ///
@@ -42,3 +43,51 @@ impl Witness for ArrayWitness {
         lhs_hints_lock.extend(rhs_hints_lock.drain(rhs_next_idx..))
     }
 }
+
+impl HintWitness for ArrayWitness {
+    fn into_hints(mut self) -> Vec<Vec<u8>> {
+        self.hints.split_off(self.next_idx)
+    }
+
+    fn from_hints(hints: Vec<Vec<u8>>) -> Self {
+        Self { next_idx: 0, hints }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use sov_rollup_interface::zk::CompactWitnesses;
+
+    use super::*;
+
+    fn witness(hints: &[u64]) -> ArrayWitness {
+        let mut witness = ArrayWitness::default();
+        for hint in hints {
+            witness.add_hint(*hint);
+        }
+        witness
+    }
+
+    #[test]
+    fn compact_witnesses_dedupe_hints() {
+        let witnesses = VecDeque::from([
+            vec![witness(&[1, 2, 3]), witness(&[2, 3, 4])],
+            vec![witness(&[]), witness(&[4, 1, 1])],
+        ]);
+        let compact = CompactWitnesses::compact(witnesses);
+        assert_eq!(compact.distinct_hints(), 4);
+        assert_eq!(compact.total_hints(), 9);
+
+        let compact: CompactWitnesses<ArrayWitness> =
+            borsh::from_slice(&borsh::to_vec(&compact).unwrap()).unwrap();
+        let mut witnesses = compact.expand();
+        assert_eq!(witnesses.len(), 2);
+        let mut last = witnesses.pop_back().unwrap().pop().unwrap();
+        assert_eq!(last.get_hint::<u64>(), 4);
+        assert_eq!(last.get_hint::<u64>(), 1);
+        assert_eq!(last.get_hint::<u64>(), 1);
+        assert_eq!(witnesses[0][1].hints, witness(&[2, 3, 4]).hints);
+    }
+}
@@ -53,7 +53,10 @@ impl<S: MerkleProofSpec> Storage for ZkStorage<S> {
         witness.get_hint()
     }
 
-    #[cfg_attr(all(target_os = "zkvm", feature = "bench"), cycle_tracker)]
+    #[cfg_attr(
+        all(target_os = "zkvm", feature = "bench"),
+        cycle_tracker("jmt_updates")
+    )]
     fn compute_state_update(
         &self,
         state_accesses: OrderedReadsAndWrites,
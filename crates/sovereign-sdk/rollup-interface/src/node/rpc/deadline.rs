@@ -0,0 +1,115 @@
+//! Deadlines of RPC requests, which long running handlers check to stop
+//! the work of requests which already timed out.
+
+use core::future::Future;
+use std::time::{Duration, Instant};
+
+use tokio::task::futures::TaskLocalFuture;
+
+/// Code of the errors of RPC requests which timed out
+pub const RPC_TIMEOUT_ERROR_CODE: i32 = -32098;
+
+tokio::task_local! {
+    static DEADLINE: RequestDeadline;
+}
+
+/// Instant after which the RPC request handled by the current task is cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestDeadline {
+    at: Instant,
+    timeout: Duration,
+}
+
+impl RequestDeadline {
+    /// Deadline of a request starting now which times out after `timeout`
+    pub fn after(timeout: Duration) -> Self {
+        Self {
+            at: Instant::now() + timeout,
+            timeout,
+        }
+    }
+
+    /// Timeout of the request
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Whether the deadline has passed
+    pub fn is_exceeded(&self) -> bool {
+        Instant::now() >= self.at
+    }
+
+    /// Runs `f` with the deadline visible to [`check_deadline`]
+    pub fn sync_scope<R>(self, f: impl FnOnce() -> R) -> R {
+        DEADLINE.sync_scope(self, f)
+    }
+
+    /// Polls `future` with the deadline visible to [`check_deadline`]
+    pub fn scope<F: Future>(self, future: F) -> TaskLocalFuture<RequestDeadline, F> {
+        DEADLINE.scope(self, future)
+    }
+}
+
+/// The deadline of the RPC request handled by the current task has passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("request timed out after {}ms", timeout.as_millis())]
+pub struct DeadlineExceeded {
+    /// Timeout of the request
+    pub timeout: Duration,
+}
+
+/// Cancellation checkpoint of long running RPC handlers.
+///
+/// Returns an error once the deadline of the request handled by the current task has passed,
+/// so loops over blocks or transactions can give up the work of a request nobody waits for anymore.
+/// Always succeeds outside of requests with a deadline.
+pub fn check_deadline() -> Result<(), DeadlineExceeded> {
+    match DEADLINE.try_with(|deadline| *deadline) {
+        Ok(deadline) if deadline.is_exceeded() => Err(DeadlineExceeded {
+            timeout: deadline.timeout,
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_deadline_outside_of_requests() {
+        assert_eq!(check_deadline(), Ok(()));
+    }
+
+    #[test]
+    fn check_deadline_in_scope() {
+        let deadline = RequestDeadline::after(Duration::from_secs(60));
+        assert_eq!(deadline.sync_scope(check_deadline), Ok(()));
+
+        let deadline = RequestDeadline::after(Duration::ZERO);
+        assert_eq!(
+            deadline.sync_scope(check_deadline),
+            Err(DeadlineExceeded {
+                timeout: Duration::ZERO
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn check_deadline_in_future_scope() {
+        let deadline = RequestDeadline::after(Duration::from_millis(20));
+        let result = deadline
+            .scope(async {
+                check_deadline()?;
+                tokio::time::sleep(Duration::from_millis(40)).await;
+                check_deadline()
+            })
+            .await;
+        assert_eq!(
+            result,
+            Err(DeadlineExceeded {
+                timeout: Duration::from_millis(20)
+            })
+        );
+    }
+}
@@ -19,6 +19,9 @@ use crate::stf::Event;
 use crate::stf::EventKey;
 use crate::zk::CumulativeStateDiff;
 
+pub mod deadline;
+pub mod request_id;
+
 /// A struct containing enough information to uniquely specify single batch.
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -246,6 +249,133 @@ pub struct SequencerCommitmentResponse {
     pub l2_end_block_number: u64,
 }
 
+/// The response to a JSON-RPC request for a sequencer commitment and the DA transaction which carried it.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedSequencerCommitmentResponse {
+    /// Hex encoded id of the DA transaction which carried the commitment
+    #[serde(with = "hex::serde")]
+    pub da_tx_id: [u8; 32],
+    /// L1 height the commitment was found in
+    pub found_in_l1: u64,
+    /// Hex encoded Merkle root of soft confirmation hashes
+    #[serde(with = "hex::serde")]
+    pub merkle_root: [u8; 32],
+    /// Start L2 block's number
+    pub l2_start_block_number: u64,
+    /// End L2 block's number
+    pub l2_end_block_number: u64,
+}
+
+/// A hex encoded 32 byte hash
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HexHash(#[serde(with = "utils::rpc_hex")] pub [u8; 32]);
+
+/// The response to a JSON-RPC request for the proof that an L2 block is in the sequencer commitment of its range.
+/// The proof is checked against the merkle root of the commitment like a `rs_merkle` proof.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockInclusionProofResponse {
+    /// L2 height of the block
+    pub l2_height: u64,
+    /// Hex encoded hash of the soft confirmation, the leaf of the proof
+    #[serde(with = "hex::serde")]
+    pub soft_confirmation_hash: [u8; 32],
+    /// Index of the leaf among the soft confirmations of the commitment
+    pub leaf_index: u64,
+    /// Number of soft confirmations in the commitment
+    pub leaf_count: u64,
+    /// Sibling hashes from the leaf up to the merkle root.
+    /// Levels on which the path has no sibling are skipped.
+    pub proof: Vec<HexHash>,
+    /// The commitment and the DA transaction which carried it
+    pub commitment: IndexedSequencerCommitmentResponse,
+}
+
+/// The response to a JSON-RPC request for the proof that a withdrawal is in the withdrawal root of its sequencer commitment.
+/// The proof is checked against the withdrawal root of the commitment, which the batch proof outputs, like a `rs_merkle` proof.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WithdrawalProofResponse {
+    /// Index of the withdrawal in the Bridge contract
+    pub index: u64,
+    /// L2 height of the block the withdrawal was initiated in
+    pub l2_height: u64,
+    /// Hex encoded Bitcoin address the withdrawal is paid to
+    #[serde(with = "hex::serde")]
+    pub bitcoin_address: [u8; 32],
+    /// Hex encoded sha256 hash of the borsh encoded withdrawal, the leaf of the proof
+    #[serde(with = "hex::serde")]
+    pub leaf: [u8; 32],
+    /// Index of the leaf among the withdrawals of the commitment
+    pub leaf_index: u64,
+    /// Number of withdrawals in the commitment
+    pub leaf_count: u64,
+    /// Sibling hashes from the leaf up to the withdrawal root.
+    /// Levels on which the path has no sibling are skipped.
+    pub proof: Vec<HexHash>,
+    /// Hex encoded merkle root of the withdrawals in the commitment
+    #[serde(with = "hex::serde")]
+    pub withdrawal_root: [u8; 32],
+    /// The commitment and the DA transaction which carried it
+    pub commitment: IndexedSequencerCommitmentResponse,
+}
+
+/// The L1 fee rate of an L2 block
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct L1FeeRateResponse {
+    /// L2 height of the block
+    pub l2_height: u64,
+    /// Base layer fee rate the L1 fees of the block were charged at
+    pub l1_fee_rate: u128,
+}
+
+/// The response to a JSON-RPC request for the status of an L2 transaction.
+/// Statuses are listed from the least to the most final one.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum TransactionStatusResponse {
+    /// The transaction is not known to the node
+    Unknown,
+    /// The transaction is in the mempool of the sequencer
+    Pending,
+    /// The transaction is in an L2 block which is not in a sequencer commitment seen on DA yet
+    #[serde(rename_all = "camelCase")]
+    SoftConfirmed {
+        /// L2 height of the block of the transaction
+        l2_height: u64,
+    },
+    /// The L2 block of the transaction is in a sequencer commitment seen on DA
+    #[serde(rename_all = "camelCase")]
+    DaCommitted {
+        /// L2 height of the block of the transaction
+        l2_height: u64,
+        /// Hex encoded id of the DA transaction which carried the commitment
+        #[serde(with = "hex::serde")]
+        da_tx_id: [u8; 32],
+        /// L1 height the commitment was found in
+        l1_height: u64,
+        /// Number of L1 blocks confirming the commitment, counting the one it was found in
+        depth: u64,
+    },
+    /// The sequencer commitment of the transaction is proven by a verified proof
+    #[serde(rename_all = "camelCase")]
+    Proven {
+        /// L2 height of the block of the transaction
+        l2_height: u64,
+        /// Hex encoded id of the DA transaction which carried the commitment
+        #[serde(with = "hex::serde")]
+        da_tx_id: [u8; 32],
+        /// L1 height the commitment was found in
+        l1_height: u64,
+        /// Id of the DA transaction which carried the proof.
+        /// None for proofs verified by nodes which did not record it.
+        proof_da_tx_id: Option<HexHash>,
+    },
+}
+
 /// The rpc response of proof by l1 slot height
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -269,6 +399,124 @@ pub struct VerifiedProofResponse {
     pub state_transition: StateTransitionRpcResponse,
 }
 
+/// The rpc response of the latest verified state root
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifiedStateRootResponse {
+    /// L1 height the proof was found in
+    pub l1_height: u64,
+    /// Last L2 height covered by the proof
+    pub l2_height: u64,
+    /// State root after the last L2 height covered by the proof
+    #[serde(with = "hex::serde")]
+    pub state_root: Vec<u8>,
+}
+
+/// The rpc response of the report of a DA reorg which halted the execution of L2 blocks
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReorgHaltResponse {
+    /// Height of the last L1 block both chains have in common.
+    /// Only an upper bound if the reorg is deeper than the tracked chain.
+    pub fork_height: u64,
+    /// Number of replaced L1 blocks
+    pub depth: u64,
+    /// Deepest reorg the node handles without halting
+    pub max_handled_reorg_depth: u64,
+    /// Hashes of the Citrea blobs of the replaced blocks which are not in the new chain
+    pub dropped_blobs: Vec<HexHash>,
+    /// Last L2 height committed to the ledger when execution halted
+    pub l2_height: u64,
+    /// Unix timestamp in seconds of the halt
+    pub halted_at: u64,
+}
+
+/// The rpc response of the report of a state root mismatch which halted the execution of L2 blocks
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateRootMismatchResponse {
+    /// Height of the L2 block whose state root did not match
+    pub l2_height: u64,
+    /// State root the sequencer signed for the L2 block
+    #[serde(with = "hex::serde")]
+    pub expected_state_root: Vec<u8>,
+    /// State root the full node computed for the L2 block
+    #[serde(with = "hex::serde")]
+    pub state_root: Vec<u8>,
+    /// Path of the file the inputs of the L2 block are recorded to, if they could be recorded
+    pub diagnostics_path: Option<String>,
+    /// Unix timestamp in seconds of the halt
+    pub halted_at: u64,
+}
+
+/// An inclusive range of L2 heights
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct L2RangeResponse {
+    /// First L2 height of the range
+    pub start: u64,
+    /// Last L2 height of the range
+    pub end: u64,
+}
+
+/// The rpc response of the L2 heights covered by the sequencer commitments seen on DA
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitmentCoverageResponse {
+    /// Highest L2 height up to which every L2 height is covered by a commitment.
+    /// L2 blocks after it are not finalized, even if a commitment covers them.
+    pub contiguous_l2_height: u64,
+    /// L2 ranges no commitment covers, before the last commitment seen
+    pub gaps: Vec<L2RangeResponse>,
+    /// Ranges of the commitments seen after a gap
+    pub ahead: Vec<L2RangeResponse>,
+    /// Ranges of the commitments which covered L2 heights covered already
+    pub overlaps: Vec<L2RangeResponse>,
+}
+
+/// Challenge status of a sequencer commitment
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CommitmentChallengeStatus {
+    /// The challenge window is open and no proof covers the commitment yet
+    Challengeable,
+    /// A proof covers the commitment
+    Proven,
+    /// The challenge window closed before a proof covered the commitment
+    Expired,
+}
+
+/// The rpc response of the challenge window of a sequencer commitment
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitmentChallengeResponse {
+    /// L1 height the commitment was found in
+    pub found_in_l1: u64,
+    /// Start L2 block's number
+    pub l2_start_block_number: u64,
+    /// End L2 block's number
+    pub l2_end_block_number: u64,
+    /// First L1 height at which the commitment can not be challenged anymore
+    pub challengeable_until: u64,
+    /// Challenge status of the commitment
+    pub status: CommitmentChallengeStatus,
+}
+
+/// The rpc response of DA inclusion proof for the relevant blobs of an L1 block
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DaInclusionProofResponse {
+    /// Hash of the L1 block
+    #[serde(with = "hex::serde")]
+    pub da_slot_hash: [u8; 32],
+    /// Borsh serialized proof that the relevant blobs are included in the L1 block
+    #[serde(with = "hex::serde")]
+    pub inclusion_proof: Vec<u8>,
+    /// Borsh serialized proof that the relevant blobs are complete
+    #[serde(with = "hex::serde")]
+    pub completeness_proof: Vec<u8>,
+}
+
 /// The rpc response of the last verified proof
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -459,6 +707,28 @@ pub enum SoftConfirmationStatus {
     Proven,
 }
 
+/// Error returned by a [`LedgerRpcProvider`] when data of the requested L2 height
+/// is not stored by the node, e.g. because it has been pruned.
+#[cfg(feature = "native")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Data of L2 height {l2_height} is unavailable on this node. Data is available from L2 height {available_from}")]
+pub struct L2HeightUnavailable {
+    /// The requested L2 height
+    pub l2_height: u64,
+    /// The lowest L2 height whose data is available
+    pub available_from: u64,
+}
+
+/// Error returned by a [`LedgerRpcProvider`] when the L2 block of the requested height is stored,
+/// but the bodies of its transactions are not, e.g. because the node runs with `include_tx_body = false`.
+#[cfg(feature = "native")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Transaction bodies of L2 height {l2_height} are not stored on this node")]
+pub struct L2BodyUnavailable {
+    /// The requested L2 height
+    pub l2_height: u64,
+}
+
 /// A LedgerRpcProvider provides a way to query the ledger for information about slots, batches, transactions, and events.
 #[cfg(feature = "native")]
 pub trait LedgerRpcProvider {
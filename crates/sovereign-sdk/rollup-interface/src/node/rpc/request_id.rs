@@ -0,0 +1,71 @@
+//! Correlation IDs of RPC requests, which follow the work a request starts within the node
+//! and are sent along with the requests it makes to other nodes.
+
+use core::future::Future;
+
+use tokio::task::futures::TaskLocalFuture;
+
+/// HTTP header of the correlation ID of a request made by another node
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest correlation ID accepted from another node
+const MAX_REQUEST_ID_LEN: usize = 64;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Correlation ID of the RPC request being handled by the current task, if any.
+/// Work started by the request can log it to be found together with the request.
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+/// Runs `f` with the correlation ID visible to [`current_request_id`]
+pub fn sync_scope_request_id<R>(request_id: String, f: impl FnOnce() -> R) -> R {
+    REQUEST_ID.sync_scope(request_id, f)
+}
+
+/// Polls `future` with the correlation ID visible to [`current_request_id`]
+pub fn scope_request_id<F: Future>(request_id: String, future: F) -> TaskLocalFuture<String, F> {
+    REQUEST_ID.scope(request_id, future)
+}
+
+/// Whether a correlation ID received from another node is used.
+/// IDs end up in the logs, so only short alphanumeric ones are accepted.
+pub fn is_valid_request_id(request_id: &str) -> bool {
+    !request_id.is_empty()
+        && request_id.len() <= MAX_REQUEST_ID_LEN
+        && request_id
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn request_id_is_visible_in_scope() {
+        assert_eq!(current_request_id(), None);
+        let request_id = scope_request_id("abc".to_string(), async {
+            tokio::task::yield_now().await;
+            current_request_id()
+        })
+        .await;
+        assert_eq!(request_id.as_deref(), Some("abc"));
+        assert_eq!(
+            sync_scope_request_id("def".to_string(), current_request_id).as_deref(),
+            Some("def")
+        );
+    }
+
+    #[test]
+    fn only_short_alphanumeric_request_ids_are_valid() {
+        assert!(is_valid_request_id("00000000deadbeef"));
+        assert!(is_valid_request_id("prover-job_12"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("id\n{\"level\":\"ERROR\"}"));
+        assert!(!is_valid_request_id(&"a".repeat(65)));
+    }
+}
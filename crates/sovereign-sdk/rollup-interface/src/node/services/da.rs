@@ -9,7 +9,7 @@ use tokio::sync::oneshot::Sender as OneshotSender;
 
 use crate::da::BlockHeaderTrait;
 #[cfg(feature = "native")]
-use crate::da::{DaSpec, DaVerifier};
+use crate::da::{DaChainState, DaSpec, DaVerifier};
 use crate::zk::ValidityCondition;
 
 /// This type represents a queued request to send_transaction
@@ -19,6 +19,44 @@ pub struct BlobWithNotifier<TxID> {
     pub blob: Vec<u8>,
     /// Channel to receive result of the operation.
     pub notify: OneshotSender<Result<TxID, anyhow::Error>>,
+    /// If set, the blob was sent before in a transaction which is stalled,
+    /// and is sent again in a transaction replacing it.
+    pub fee_bump: Option<FeeBump<TxID>>,
+}
+
+/// A pending transaction to replace with a transaction paying a higher fee.
+#[cfg(feature = "native")]
+pub struct FeeBump<TxID> {
+    /// The pending transaction
+    pub replaced_tx: TxID,
+    /// Percentage the fee rate of the replacement is raised by, over the fee rate
+    /// the pending transaction pays or the estimated one if it is higher.
+    /// The DA layer caps the raised fee rate at its maximum.
+    pub fee_bump_percent: u64,
+}
+
+/// Inclusion status of a transaction sent to the DA layer.
+#[cfg(feature = "native")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatus {
+    /// Waiting to be included in a block
+    Pending,
+    /// Included in a block of the best fork, with the number of confirmations
+    Confirmed(u64),
+    /// Neither pending nor included, e.g. evicted from the mempool or replaced
+    Dropped,
+}
+
+/// Upper bounds of the fee rates of DA transactions which are set while the node is running,
+/// in the unit of the fee rate of the DA layer. A bound which is not set keeps the one of
+/// the config of the service.
+#[cfg(feature = "native")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FeeCeilings {
+    /// Maximum fee rate of new transactions
+    pub max_fee_rate: Option<f64>,
+    /// Maximum fee rate stalled transactions are bumped to
+    pub max_fee_bump_rate: Option<f64>,
 }
 
 /// A DaService is the local side of an RPC connection talking to a node of the DA layer
@@ -47,7 +85,14 @@ pub trait DaService: Send + Sync + 'static {
     >;
 
     /// A transaction ID, used to identify the transaction in the DA layer.
-    type TransactionId: Send + PartialEq + Eq + PartialOrd + Ord + core::hash::Hash + Into<[u8; 32]>;
+    type TransactionId: Send
+        + Clone
+        + PartialEq
+        + Eq
+        + PartialOrd
+        + Ord
+        + core::hash::Hash
+        + Into<[u8; 32]>;
 
     /// The error type for fallible methods.
     type Error: core::fmt::Debug + Send + Sync + core::fmt::Display;
@@ -79,6 +124,17 @@ pub trait DaService: Send + Sync + 'static {
         &self,
     ) -> Result<<Self::Spec as DaSpec>::BlockHeader, Self::Error>;
 
+    /// The chain state of the block at the given height, which a light client verifying the
+    /// headers of the following blocks starts from, see [`DaVerifier::verify_headers`].
+    /// DA layers without difficulty adjustments only keep the time of the block.
+    async fn get_chain_state_at(&self, height: u64) -> Result<DaChainState, Self::Error> {
+        let block = self.get_block_at(height).await?;
+        Ok(DaChainState {
+            tip_time: block.header().time().secs() as u64,
+            ..Default::default()
+        })
+    }
+
     /// Extract the relevant transactions from a block. For example, this method might return
     /// all of the blob transactions in rollup's namespace on Celestia.
     fn extract_relevant_blobs(
@@ -129,6 +185,21 @@ pub trait DaService: Send + Sync + 'static {
         unimplemented!()
     }
 
+    /// Returns the inclusion status of a transaction sent with [`DaService::send_transaction`].
+    async fn get_transaction_status(
+        &self,
+        tx_id: &Self::TransactionId,
+    ) -> Result<TxStatus, Self::Error>;
+
+    /// Sends the blob of the stalled transaction `fee_bump.replaced_tx` again,
+    /// in a transaction replacing it and paying a higher fee.
+    /// Only the latest transaction sent can be replaced.
+    async fn replace_transaction(
+        &self,
+        blob: &[u8],
+        fee_bump: FeeBump<Self::TransactionId>,
+    ) -> Result<Self::TransactionId, Self::Error>;
+
     /// Sends am aggregated ZK proofs to the DA layer.
     async fn send_aggregated_zk_proof(
         &self,
@@ -145,6 +216,10 @@ pub trait DaService: Send + Sync + 'static {
     async fn get_relevant_blobs_of_pending_transactions(
         &self,
     ) -> Vec<<Self::Spec as DaSpec>::BlobTransaction>;
+
+    /// Applies reloaded upper bounds of the fee rates to the transactions sent from now on.
+    /// DA layers without fee rates ignore them.
+    fn set_fee_ceilings(&self, _ceilings: FeeCeilings) {}
 }
 
 /// `SlotData` is the subset of a DA layer block which is stored in the rollup's database.
@@ -6,6 +6,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use crate::stf::StateDiff;
 use crate::zk::{Proof, ValidityCondition};
 use crate::BasicAddress;
 
@@ -21,14 +22,222 @@ pub struct SequencerCommitment {
     pub l2_end_block_number: u64,
 }
 
-/// Data written to DA can only be one of these two types
-/// Data written to DA and read from DA is must be borsh serialization of this enum
+/// A sequencer commitment posted together with the state diff of its L2 blocks,
+/// so the state can be reconstructed from DA without the transactions
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct CommitmentWithStateDiff {
+    /// The commitment to the L2 blocks
+    pub commitment: SequencerCommitment,
+    /// Cumulative state diff of the committed L2 blocks, sorted by key
+    pub state_diff: StateDiff,
+}
+
+/// Payload of the sequencer commitments posted to DA
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DaPayloadMode {
+    /// Only the merkle root of the committed soft confirmations,
+    /// the state has to be derived by executing their transactions
+    #[default]
+    Commitment,
+    /// The commitment with the cumulative state diff of the committed soft confirmations,
+    /// so the state can be reconstructed from DA alone.
+    /// DA costs grow with the diff size, which is what the L1 fee of txs is charged for.
+    StateDiff,
+}
+
+/// Lease of the sequencer keys, posted by the sequencer. Instances sharing the keys of
+/// the sequencer, like a primary and its standbys, only produce L2 blocks while they hold
+/// the lease, so only one of them produces at a time.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct SequencerLease {
+    /// Id of the instance holding the lease
+    pub holder: String,
+    /// Incremented each time the lease is taken over from an expired lease
+    pub term: u64,
+    /// DA height the lease expires at, unless it is renewed by its holder before
+    pub expires_at_l1_height: u64,
+}
+
+/// Claim of a withdrawal paid out on the DA layer, posted by the bridge operator who paid it
+/// to be reimbursed from the bridge funds. Watchtowers check that the withdrawal was burned on L2.
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
+pub struct WithdrawalClaim {
+    /// Index of the withdrawal in the Bridge contract
+    pub index: u64,
+    /// Address on the DA layer the withdrawal was paid to
+    pub bitcoin_address: [u8; 32],
+}
+
+/// Version of the envelope data is posted to DA with, and the highest version decoded natively.
+/// Versions start after the first bytes of the data posted before envelopes, see [`DaData::decode`].
+pub const DA_DATA_VERSION: u8 = LEGACY_DA_DATA_SCHEMAS;
+
+/// Highest envelope version the guest decodes. Data of later versions is ignored by the guest
+/// until their version is activated, so nodes can decode a new format before it is proven.
+pub const ACTIVATED_DA_DATA_VERSION: u8 = LEGACY_DA_DATA_SCHEMAS;
+
+/// Number of [`DaData`] variants before envelopes, sequencer commitments and zk proofs.
+/// Data posted then is the plain borsh serialization of [`DaData`], starting with the
+/// discriminant of one of them, so its first byte is below the envelope versions.
+const LEGACY_DA_DATA_SCHEMAS: u8 = 2;
+
+/// Number of schemas of the envelope versions up to [`DA_DATA_VERSION`].
+/// The schema id of data is the borsh discriminant of its [`DaData`] variant.
+const DA_DATA_SCHEMAS: u8 = 10;
+
+/// Data written to DA can only be one of these types.
+/// Data is written to DA in a versioned envelope, see [`DaData::encode`].
+///
+/// New variants are only appended, as their borsh discriminants are the schema ids of the envelope.
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq, BorshDeserialize, BorshSerialize)]
 pub enum DaData {
     /// A commitment from the sequencer
     SequencerCommitment(SequencerCommitment),
     /// Or a zk proof and state diff
     ZKProof(Proof),
+    /// Or an RLP encoded L2 transaction posted by a user, which the sequencer
+    /// has to include in an L2 block within the forced transaction inclusion window
+    ForcedTransaction(Vec<u8>),
+    /// Or the data of a bridge deposit, which the sequencer has to include
+    /// at the start of the first L2 block on the DA block it is posted in
+    Deposit(Vec<u8>),
+    /// Or a zk proof aggregating the proofs of multiple DA slots
+    AggregatedZKProof(Proof),
+    /// Or a request to prove the sequencer commitments of the DA block at the given height,
+    /// which provers that don't prove every DA block have to honor
+    ProofChallenge(u64),
+    /// Or a proof of the light client guest, attesting the latest proven L2 state root
+    LightClientProof(Proof),
+    /// Or a commitment from the sequencer with the state diff of the committed L2 blocks,
+    /// which provers check against the execution of the blocks
+    CommitmentWithStateDiff(CommitmentWithStateDiff),
+    /// Or a lease of the sequencer keys, taken, renewed or released by an instance of the sequencer
+    SequencerLease(SequencerLease),
+    /// Or a claim of a withdrawal paid out on the DA layer by a bridge operator
+    WithdrawalClaim(WithdrawalClaim),
+}
+
+/// Error decoding data read from DA
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DaDataError {
+    /// The data is too short for the envelope
+    Truncated,
+    /// The envelope version is not known, or not activated
+    UnknownVersion(u8),
+    /// The schema id is not known in the envelope version
+    UnknownSchema {
+        /// Envelope version
+        version: u8,
+        /// Schema id of the payload
+        schema_id: u8,
+    },
+    /// The payload is not a borsh serialization of its schema
+    InvalidPayload {
+        /// Schema id of the payload
+        schema_id: u8,
+    },
+}
+
+impl core::fmt::Display for DaDataError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            DaDataError::Truncated => write!(f, "DA data is truncated"),
+            DaDataError::UnknownVersion(version) => {
+                write!(f, "Unknown DA data version {}", version)
+            }
+            DaDataError::UnknownSchema { version, schema_id } => write!(
+                f,
+                "Unknown schema id {} of DA data version {}",
+                schema_id, version
+            ),
+            DaDataError::InvalidPayload { schema_id } => {
+                write!(f, "Invalid payload of DA data schema id {}", schema_id)
+            }
+        }
+    }
+}
+
+impl DaData {
+    /// Serializes the data for DA: the envelope version byte and the schema id byte,
+    /// followed by the borsh serialization of the payload.
+    pub fn encode(&self) -> Vec<u8> {
+        // The borsh serialization of an enum starts with the discriminant, the schema id
+        let mut data = vec![DA_DATA_VERSION];
+        data.extend(borsh::to_vec(self).expect("Serialization to a vector never fails"));
+        data
+    }
+
+    /// Decodes data read from DA, of any version known natively.
+    /// Data of unknown versions or schemas is an error callers skip, like malformed data.
+    /// Data posted before envelopes is decoded as it was then, so the history of DA
+    /// can still be synced.
+    pub fn decode(data: &[u8]) -> Result<Self, DaDataError> {
+        Self::decode_up_to_version(data, DA_DATA_VERSION)
+    }
+
+    /// Decodes data read from DA the way the guest does, only of activated versions.
+    /// Anything that reads DA data for the guest has to decode it this way.
+    pub fn decode_activated(data: &[u8]) -> Result<Self, DaDataError> {
+        Self::decode_up_to_version(data, ACTIVATED_DA_DATA_VERSION)
+    }
+
+    fn decode_up_to_version(data: &[u8], max_version: u8) -> Result<Self, DaDataError> {
+        let (&version, payload) = data.split_first().ok_or(DaDataError::Truncated)?;
+        if version < LEGACY_DA_DATA_SCHEMAS {
+            // Posted before envelopes, the first byte is the schema id
+            return Self::try_from_slice(data)
+                .map_err(|_| DaDataError::InvalidPayload { schema_id: version });
+        }
+        if version > max_version {
+            return Err(DaDataError::UnknownVersion(version));
+        }
+        let &schema_id = payload.first().ok_or(DaDataError::Truncated)?;
+        if schema_id >= DA_DATA_SCHEMAS {
+            return Err(DaDataError::UnknownSchema { version, schema_id });
+        }
+        Self::try_from_slice(payload).map_err(|_| DaDataError::InvalidPayload { schema_id })
+    }
+
+    /// The sequencer commitment of the data, whether it was posted with its state diff or not
+    pub fn into_sequencer_commitment(self) -> Option<SequencerCommitment> {
+        match self {
+            DaData::SequencerCommitment(commitment) => Some(commitment),
+            DaData::CommitmentWithStateDiff(data) => Some(data.commitment),
+            _ => None,
+        }
+    }
+}
+
+/// Data posted on a DA block which the sequencer has to include in its L2 blocks.
+/// The state transition function is given the inbox of the DA block of each L2 block,
+/// and rejects L2 blocks which don't include its data in time.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, BorshDeserialize, BorshSerialize,
+)]
+pub struct SlotInbox {
+    /// Forced transactions, in the order they are posted
+    pub forced_txs: Vec<Vec<u8>>,
+    /// Bridge deposits, in the order they are posted.
+    /// The bridge contract verifies them, so they are included as they are.
+    pub deposits: Vec<Vec<u8>>,
+}
+
+impl SlotInbox {
+    /// Reads the inbox from the verified data of the relevant blobs of a DA block.
+    /// Anyone can post to the inbox, so the senders of the blobs are not checked.
+    /// Data is decoded the way the guest does, as the inbox changes the state.
+    pub fn from_blobs<B: BlobReaderTrait>(blobs: &[B]) -> Self {
+        let mut inbox = Self::default();
+        for blob in blobs {
+            match DaData::decode_activated(blob.verified_data()) {
+                Ok(DaData::ForcedTransaction(tx)) => inbox.forced_txs.push(tx),
+                Ok(DaData::Deposit(deposit)) => inbox.deposits.push(deposit),
+                _ => {}
+            }
+        }
+        inbox
+    }
 }
 
 /// A specification for the types used by a DA layer.
@@ -99,6 +308,32 @@ pub trait DaVerifier: Send + Sync {
         inclusion_proof: <Self::Spec as DaSpec>::InclusionMultiProof,
         completeness_proof: <Self::Spec as DaSpec>::CompletenessProof,
     ) -> Result<<Self::Spec as DaSpec>::ValidityCondition, Self::Error>;
+
+    /// Verifies the consensus rules of the block headers following the chain state,
+    /// such as their proof of work and difficulty, and returns the chain state after them.
+    /// Whether the headers form a chain is checked by the caller.
+    fn verify_headers(
+        &self,
+        chain_state: &DaChainState,
+        block_headers: &[<Self::Spec as DaSpec>::BlockHeader],
+    ) -> Result<DaChainState, Self::Error>;
+}
+
+/// State of the DA chain which the consensus rules of the next block header are checked
+/// against, kept by light clients verifying the DA block headers.
+/// DA layers without difficulty adjustments only keep the time of the latest block.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, BorshDeserialize, BorshSerialize,
+)]
+pub struct DaChainState {
+    /// Difficulty target the next block has to meet, in the compact form of the DA layer
+    pub target_bits: u32,
+    /// Timestamp of the latest block
+    pub tip_time: u64,
+    /// Timestamp of the first block of the current difficulty adjustment period
+    pub period_start_time: u64,
+    /// Work of the blocks verified since the light client started, as a big-endian integer
+    pub chain_work: [u8; 32],
 }
 
 #[cfg(feature = "std")]
@@ -181,6 +416,11 @@ pub trait BlobReaderTrait:
     /// Returns the hash of the blob as it appears on the DA layer
     fn hash(&self) -> [u8; 32];
 
+    /// Returns the id of the DA layer transaction which carried the blob, if known.
+    /// The id is not part of the verified data, so it must only be used for indexing.
+    #[cfg(feature = "native")]
+    fn da_tx_id(&self) -> Option<[u8; 32]>;
+
     /// Returns a slice containing all the data accessible to the rollup at this point in time.
     /// When running in native mode, the rollup can extend this slice by calling `advance`. In zk-mode,
     /// the rollup is limited to only the verified data.
@@ -320,3 +560,70 @@ impl Time {
         self.nanos
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn da_data_envelope_round_trip() {
+        let da_data = DaData::ProofChallenge(42);
+        let encoded = da_data.encode();
+        assert_eq!(encoded[..2], [DA_DATA_VERSION, 5]);
+        assert_eq!(DaData::decode(&encoded), Ok(da_data.clone()));
+        assert_eq!(DaData::decode_activated(&encoded), Ok(da_data));
+    }
+
+    #[test]
+    fn unknown_da_data_versions_and_schemas_are_rejected() {
+        let mut encoded = DaData::ForcedTransaction(vec![1, 2, 3]).encode();
+        assert_eq!(DaData::decode(&encoded[..1]), Err(DaDataError::Truncated));
+
+        encoded[0] = DA_DATA_VERSION + 1;
+        assert_eq!(
+            DaData::decode(&encoded),
+            Err(DaDataError::UnknownVersion(DA_DATA_VERSION + 1))
+        );
+
+        encoded[0] = DA_DATA_VERSION;
+        encoded[1] = DA_DATA_SCHEMAS;
+        assert_eq!(
+            DaData::decode(&encoded),
+            Err(DaDataError::UnknownSchema {
+                version: DA_DATA_VERSION,
+                schema_id: DA_DATA_SCHEMAS
+            })
+        );
+
+        // Data of schemas added with envelopes is not decoded without an envelope
+        let unversioned = borsh::to_vec(&DaData::ProofChallenge(42)).unwrap();
+        assert!(DaData::decode(&unversioned).is_err());
+    }
+
+    #[test]
+    fn da_data_posted_before_envelopes_is_decoded() {
+        // A sequencer commitment posted before envelopes
+        let mut commitment_blob = vec![0];
+        commitment_blob.extend([0xab; 32]);
+        commitment_blob.extend([5, 0, 0, 0, 0, 0, 0, 0]);
+        commitment_blob.extend([9, 0, 0, 0, 0, 0, 0, 0]);
+        let commitment = DaData::SequencerCommitment(SequencerCommitment {
+            merkle_root: [0xab; 32],
+            l2_start_block_number: 5,
+            l2_end_block_number: 9,
+        });
+        assert_eq!(DaData::decode(&commitment_blob), Ok(commitment.clone()));
+        assert_eq!(DaData::decode_activated(&commitment_blob), Ok(commitment));
+
+        // A full zk proof posted before envelopes
+        let proof_blob = [1, 1, 3, 0, 0, 0, 7, 8, 9];
+        let proof = DaData::ZKProof(Proof::Full(vec![7, 8, 9]));
+        assert_eq!(DaData::decode(&proof_blob), Ok(proof.clone()));
+        assert_eq!(DaData::decode_activated(&proof_blob), Ok(proof));
+
+        assert_eq!(
+            DaData::decode(&proof_blob[..5]),
+            Err(DaDataError::InvalidPayload { schema_id: 1 })
+        );
+    }
+}
@@ -15,9 +15,9 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use crate::da::DaSpec;
+use crate::da::{DaSpec, SlotInbox};
 use crate::soft_confirmation::SignedSoftConfirmationBatch;
-use crate::zk::{CumulativeStateDiff, ValidityCondition, Zkvm};
+use crate::zk::{CumulativeStateDiff, HintWitness, ValidityCondition, Zkvm};
 
 #[cfg(any(all(test, feature = "sha2"), feature = "fuzzing"))]
 pub mod fuzzing;
@@ -169,7 +169,7 @@ pub trait StateTransitionFunction<Vm: Zkvm, Da: DaSpec> {
 
     /// Witness is a data that is produced during actual batch execution
     /// or validated together with proof during verification
-    type Witness: Default + BorshDeserialize + Serialize + DeserializeOwned;
+    type Witness: Default + BorshDeserialize + Serialize + DeserializeOwned + HintWitness;
 
     /// The validity condition that must be verified outside of the Vm
     type Condition: ValidityCondition;
@@ -224,6 +224,7 @@ pub trait StateTransitionFunction<Vm: Zkvm, Da: DaSpec> {
     /// this parameter is mainly used within the begin_slot hook.
     /// The concrete blob type is defined by the DA layer implementation,
     /// which is why we use a generic here instead of an associated type.
+    /// `slot_inbox` is the inbox of the DA block of `slot_header`.
     ///
     /// Commits state changes to the database
     #[allow(clippy::type_complexity)]
@@ -235,6 +236,7 @@ pub trait StateTransitionFunction<Vm: Zkvm, Da: DaSpec> {
         pre_state: Self::PreState,
         witness: Self::Witness,
         slot_header: &Da::BlockHeader,
+        slot_inbox: &SlotInbox,
         validity_condition: &Da::ValidityCondition,
         soft_batch: &mut SignedSoftConfirmationBatch,
     ) -> SlotResult<
@@ -247,6 +249,7 @@ pub trait StateTransitionFunction<Vm: Zkvm, Da: DaSpec> {
 
     /// Runs a vector of Soft Confirmations
     /// Used for proving the L2 block state transitions
+    /// `slot_inboxes` are the inboxes of the DA blocks of `slot_headers`, which the caller verified.
     // TODO: don't use tuple as return type.
     #[allow(clippy::type_complexity)]
     #[allow(clippy::too_many_arguments)]
@@ -261,6 +264,7 @@ pub trait StateTransitionFunction<Vm: Zkvm, Da: DaSpec> {
         sequencer_commitments_range: (u32, u32),
         witnesses: VecDeque<Vec<Self::Witness>>,
         slot_headers: VecDeque<Vec<Da::BlockHeader>>,
+        slot_inboxes: VecDeque<Vec<SlotInbox>>,
         validity_condition: &Da::ValidityCondition,
         soft_confirmations: VecDeque<Vec<SignedSoftConfirmationBatch>>,
     ) -> (Self::StateRoot, CumulativeStateDiff);
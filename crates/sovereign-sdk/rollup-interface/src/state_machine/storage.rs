@@ -38,6 +38,10 @@ pub trait HierarchicalStorageManager<Da: DaSpec> {
     /// Won't be saved if somehow 'saved'
     fn create_finalized_storage(&mut self) -> anyhow::Result<Self::NativeStorage>;
 
+    /// Snapshot of the finalized storage at its current version,
+    /// which blocks finalized later do not change. Won't be saved.
+    fn create_finalized_snapshot(&mut self) -> anyhow::Result<Self::NativeStorage>;
+
     /// Adds [`Self::NativeChangeSet`] to the storage.
     /// [`DaSpec::BlockHeader`] must be provided for efficient consistency checking.
     fn save_change_set(
@@ -48,4 +52,29 @@ pub trait HierarchicalStorageManager<Da: DaSpec> {
 
     /// Finalizes snapshot on given block header
     fn finalize(&mut self, block_header: &Da::BlockHeader) -> anyhow::Result<()>;
+
+    /// Removes historical state that is not needed to read state at given l2 block height and above.
+    fn prune_l2(&mut self, l2_block_height: u64) -> anyhow::Result<()>;
+
+    /// Returns a function removing historical state like [`Self::prune_l2`], which can run
+    /// on another thread, so a long pruning pass does not hold up storing new blocks.
+    #[cfg(feature = "std")]
+    fn l2_pruner(&self) -> std::boxed::Box<dyn FnOnce(u64) -> anyhow::Result<()> + Send>;
+
+    /// Returns the height of the latest l2 block whose state has been finalized, if any.
+    fn get_last_finalized_l2_height(&self) -> anyhow::Result<Option<u64>>;
+
+    /// Returns the height of the earliest l2 block whose state can still be read,
+    /// or `None` if state has never been pruned.
+    fn get_earliest_available_l2_height(&self) -> anyhow::Result<Option<u64>>;
+
+    /// Creates a consistent checkpoint of the finalized storage inside `path`,
+    /// laid out as in the storage path. Pending snapshots are not included.
+    #[cfg(feature = "std")]
+    fn create_checkpoint(&self, path: &std::path::Path) -> anyhow::Result<()>;
+
+    /// Makes new writes of the primary node visible to finalized storage,
+    /// if the underlying databases are opened in secondary mode.
+    #[cfg(feature = "std")]
+    fn try_catch_up_with_primary(&self) -> anyhow::Result<()>;
 }
@@ -10,15 +10,17 @@
 extern crate alloc;
 
 use alloc::collections::{BTreeMap, VecDeque};
+use alloc::string::String;
 use alloc::vec::Vec;
 use core::fmt::Debug;
+use core::marker::PhantomData;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use digest::Digest;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
-use crate::da::DaSpec;
+use crate::da::{BlockHeaderTrait, DaChainState, DaSpec};
 use crate::soft_confirmation::SignedSoftConfirmationBatch;
 
 /// The ZK proof generated by the [`ZkvmHost::run`] method.
@@ -30,6 +32,34 @@ pub enum Proof {
     Full(Vec<u8>),
 }
 
+/// Cycles spent by an execution of the guest, reported by [`ZkvmHost::profile`].
+#[derive(
+    Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, BorshSerialize, BorshDeserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct CycleReport {
+    /// Cycles of the guest program
+    pub user_cycles: u64,
+    /// Cycles of all segments, including the overhead of the zkVM. Proving time is proportional to it.
+    pub total_cycles: u64,
+    /// Number of segments the execution is split into
+    pub segments: u64,
+    /// Cycles of the instrumented phases of the guest, such as DA verification or JMT updates.
+    /// Only guests built with the `bench` feature report them.
+    pub phases: Vec<PhaseCycles>,
+}
+
+/// Cycles spent by the guest in an instrumented phase
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct PhaseCycles {
+    /// Name of the phase
+    pub name: String,
+    /// Cycles of all calls. Cycles of nested phases are also counted by the outer phase.
+    pub cycles: u64,
+    /// Number of times the phase ran
+    pub calls: u64,
+}
+
 /// A trait implemented by the prover ("host") of a zkVM program.
 pub trait ZkvmHost: Zkvm + Clone {
     /// The associated guest type
@@ -50,17 +80,32 @@ pub trait ZkvmHost: Zkvm + Clone {
     /// with some mild performance overhead and is not as easy to debug as [`simulate_with_hints`](ZkvmHost::simulate_with_hints).
     fn run(&mut self, with_proof: bool) -> Result<Proof, anyhow::Error>;
 
+    /// Executes the guest without proving, like [`run`](ZkvmHost::run) without a proof,
+    /// and reports the cycles the guest spent.
+    fn profile(&mut self) -> Result<(Proof, CycleReport), anyhow::Error> {
+        anyhow::bail!("The zkVM does not support cycle profiling")
+    }
+
     /// Extracts public input form the proof.
     fn extract_output<Da: DaSpec, Root: BorshDeserialize>(
         proof: &Proof,
     ) -> Result<StateTransition<Da, Root>, Self::Error>;
+
+    /// Adds a proof whose output the guest verifies with [`ZkvmGuest::verify_assumption`].
+    /// The proof becomes part of the statement proven by the next [`run`](ZkvmHost::run).
+    fn add_assumption(&mut self, proof: Proof) -> Result<(), anyhow::Error>;
+
+    /// Extracts public input form a proof of the aggregation guest.
+    fn extract_aggregated_output<Da: DaSpec, Root: BorshDeserialize>(
+        proof: &Proof,
+    ) -> Result<AggregatedStateTransition<Da, Root>, Self::Error>;
 }
 
 /// A Zk proof system capable of proving and verifying arbitrary Rust code
 /// Must support recursive proofs.
 pub trait Zkvm: Send + Sync {
     /// A commitment to the zkVM program which is being proven
-    type CodeCommitment: Clone + Debug + Serialize + DeserializeOwned;
+    type CodeCommitment: Clone + Debug + Serialize + DeserializeOwned + AsRef<[u8]>;
 
     /// The error type which is returned when a proof fails to verify
     type Error: Debug;
@@ -79,6 +124,13 @@ pub trait Zkvm: Send + Sync {
         serialized_proof: &[u8],
         code_commitment: &Self::CodeCommitment,
     ) -> Result<StateTransition<Da, Root>, Self::Error>;
+
+    /// Same as [`verify_and_extract_output`](Zkvm::verify_and_extract_output),
+    /// for the proofs of the aggregation guest.
+    fn verify_and_extract_aggregated_output<Da: DaSpec, Root: BorshDeserialize>(
+        serialized_proof: &[u8],
+        code_commitment: &Self::CodeCommitment,
+    ) -> Result<AggregatedStateTransition<Da, Root>, Self::Error>;
 }
 
 /// A trait which is accessible from within a zkVM program.
@@ -87,6 +139,13 @@ pub trait ZkvmGuest: Zkvm + Send + Sync {
     fn read_from_host<T: BorshDeserialize>(&self) -> T;
     /// Add a public output to the zkVM proof
     fn commit<T: BorshSerialize>(&self, item: &T);
+    /// Verify that the program with the given code commitment produced `output`.
+    /// The proof of it must be added by the host with [`ZkvmHost::add_assumption`].
+    fn verify_assumption(&self, code_commitment: &[u8], output: &[u8]);
+    /// Returns the public output of a serialized proof without verifying the proof,
+    /// `None` if it is not a proof of the zkVM. Used to find the proof posted on DA
+    /// an assumption is resolved with.
+    fn proof_output(&self, serialized_proof: &[u8]) -> Option<Vec<u8>>;
 }
 
 /// This trait is implemented on the struct/enum which expresses the validity condition
@@ -133,6 +192,9 @@ pub struct StateTransition<Da: DaSpec, Root> {
     /// The range of sequencer commitments in the DA slot that were processed.
     /// The range is inclusive.
     pub sequencer_commitments_range: (u32, u32),
+    /// Merkle roots of the withdrawals initiated in the L2 blocks of each processed sequencer commitment, in order.
+    /// Withdrawals are claimed on Bitcoin with a merkle proof against these roots.
+    pub withdrawal_roots: Vec<[u8; 32]>,
     /// Sequencer public key.
     pub sequencer_public_key: Vec<u8>,
     /// Sequencer DA public key.
@@ -143,6 +205,421 @@ pub struct StateTransition<Da: DaSpec, Root> {
     pub validity_condition: Da::ValidityCondition,
 }
 
+/// The public output of the aggregation guest, which recursively verifies the proofs of
+/// consecutive [`StateTransition`]s and claims the state transition covering all of them.
+/// One aggregated proof replaces the proofs of multiple DA slots on the DA layer.
+#[derive(Clone, Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub struct AggregatedStateTransition<Da: DaSpec, Root> {
+    /// The state of the rollup before the first state transition
+    pub initial_state_root: Root,
+    /// The state of the rollup after the last state transition
+    pub final_state_root: Root,
+    /// The hash before the first state transition
+    pub initial_batch_hash: [u8; 32],
+    /// State diff of all the aggregated state transitions.
+    pub state_diff: CumulativeStateDiff,
+    /// The DA slot hashes of the aggregated state transitions, in order.
+    pub da_slot_hashes: Vec<Da::SlotHash>,
+    /// The ranges of sequencer commitments processed in each DA slot.
+    pub sequencer_commitments_ranges: Vec<(u32, u32)>,
+    /// The withdrawal roots of all aggregated sequencer commitments, in order.
+    pub withdrawal_roots: Vec<[u8; 32]>,
+    /// Sequencer public key.
+    pub sequencer_public_key: Vec<u8>,
+    /// Sequencer DA public key the commitments of each DA slot were posted with, in order.
+    /// It differs between DA slots if the DA key of the sequencer was rotated between them.
+    pub sequencer_da_public_keys: Vec<Vec<u8>>,
+    /// Code commitment of the program which proved the aggregated state transitions.
+    pub range_code_commitment: Vec<u8>,
+    /// The validity conditions of the aggregated state transitions.
+    /// They can't be combined into one as the DA slots are not necessarily consecutive.
+    pub validity_conditions: Vec<Da::ValidityCondition>,
+}
+
+impl<Da: DaSpec, Root: AsRef<[u8]>> AggregatedStateTransition<Da, Root> {
+    /// Combines consecutive state transitions, each one starting from the final state
+    /// of the previous one. Later writes in the state diff override earlier ones.
+    pub fn aggregate(
+        range_code_commitment: Vec<u8>,
+        state_transitions: Vec<StateTransition<Da, Root>>,
+    ) -> anyhow::Result<Self> {
+        let mut state_transitions = state_transitions.into_iter();
+        let first = state_transitions
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No state transition to aggregate"))?;
+
+        let mut aggregated = Self {
+            initial_state_root: first.initial_state_root,
+            final_state_root: first.final_state_root,
+            initial_batch_hash: first.initial_batch_hash,
+            state_diff: first.state_diff,
+            da_slot_hashes: alloc::vec![first.da_slot_hash],
+            sequencer_commitments_ranges: alloc::vec![first.sequencer_commitments_range],
+            withdrawal_roots: first.withdrawal_roots,
+            sequencer_public_key: first.sequencer_public_key,
+            sequencer_da_public_keys: alloc::vec![first.sequencer_da_public_key],
+            range_code_commitment,
+            validity_conditions: alloc::vec![first.validity_condition],
+        };
+
+        for state_transition in state_transitions {
+            anyhow::ensure!(
+                state_transition.initial_state_root.as_ref()
+                    == aggregated.final_state_root.as_ref(),
+                "State transitions are not consecutive"
+            );
+            anyhow::ensure!(
+                state_transition.sequencer_public_key == aggregated.sequencer_public_key,
+                "State transitions have different sequencer public keys"
+            );
+            aggregated.final_state_root = state_transition.final_state_root;
+            aggregated.state_diff.extend(state_transition.state_diff);
+            aggregated
+                .da_slot_hashes
+                .push(state_transition.da_slot_hash);
+            aggregated
+                .sequencer_commitments_ranges
+                .push(state_transition.sequencer_commitments_range);
+            aggregated
+                .sequencer_da_public_keys
+                .push(state_transition.sequencer_da_public_key);
+            aggregated
+                .withdrawal_roots
+                .extend(state_transition.withdrawal_roots);
+            aggregated
+                .validity_conditions
+                .push(state_transition.validity_condition);
+        }
+
+        Ok(aggregated)
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+/// Data required to aggregate the proofs of consecutive state transitions.
+pub struct AggregationData<Da: DaSpec, Root> {
+    /// Code commitment of the program which proved the state transitions.
+    pub range_code_commitment: Vec<u8>,
+    /// The outputs of the aggregated proofs, in order.
+    /// The proofs themselves are added to the host with [`ZkvmHost::add_assumption`].
+    pub state_transitions: Vec<StateTransition<Da, Root>>,
+}
+
+/// Number of the latest DA blocks whose hashes the light client keeps.
+/// Batch proofs of sequencer commitments in older DA blocks are not accepted by the light client.
+pub const LIGHT_CLIENT_DA_WINDOW: usize = 144;
+
+/// The public output of the light client guest. It attests the latest L2 state root proven by
+/// the batch proofs posted on the DA layer, up to the latest verified DA block.
+#[derive(Clone, Debug, Serialize, Deserialize, BorshSerialize, BorshDeserialize, PartialEq, Eq)]
+pub struct LightClientOutput<Root> {
+    /// Code commitment of the light client program, which verifies its previous proof recursively
+    pub light_client_code_commitment: Vec<u8>,
+    /// Code commitment of the program which proved the state transitions
+    pub batch_proof_code_commitment: Vec<u8>,
+    /// The L2 state root the light client started from
+    pub initial_state_root: Root,
+    /// Hash of the DA block the light client started after
+    pub initial_da_hash: [u8; 32],
+    /// Height of the DA block the light client started after
+    pub initial_da_height: u64,
+    /// Chain state of the DA block the light client started after. Together with
+    /// `initial_da_hash` and `initial_da_height`, it is the anchor of the verified headers,
+    /// which verifiers of the light client proof check against a trusted DA block.
+    pub initial_chain_state: DaChainState,
+    /// The latest proven L2 state root
+    pub l2_state_root: Root,
+    /// Hash of the latest verified DA block
+    pub latest_da_hash: [u8; 32],
+    /// Height of the latest verified DA block
+    pub latest_da_height: u64,
+    /// Digest of the hashes of the latest [`LIGHT_CLIENT_DA_WINDOW`] verified DA blocks
+    pub recent_da_hashes_digest: [u8; 32],
+    /// Chain state after the latest verified DA block, with the work of the verified blocks
+    pub chain_state: DaChainState,
+}
+
+/// Data required to extend the light client output over new DA blocks.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct LightClientData<Da: DaSpec, Root> {
+    /// Code commitment of the light client program
+    pub light_client_code_commitment: Vec<u8>,
+    /// Code commitment of the program which proved the state transitions
+    pub batch_proof_code_commitment: Vec<u8>,
+    /// Output of the previous light client proof, which is added to the host with
+    /// [`ZkvmHost::add_assumption`]. If not set, the light client starts from
+    /// `initial_state_root`, after the DA block `initial_da_hash`.
+    pub previous_output: Option<LightClientOutput<Root>>,
+    /// The L2 state root the light client starts from
+    pub initial_state_root: Root,
+    /// Hash of the DA block the light client starts after
+    pub initial_da_hash: [u8; 32],
+    /// Height of the DA block the light client starts after
+    pub initial_da_height: u64,
+    /// Chain state of the DA block the light client starts after
+    pub initial_chain_state: DaChainState,
+    /// Hashes of the latest DA blocks verified by the previous proof, oldest first
+    pub recent_da_hashes: Vec<[u8; 32]>,
+    /// Consecutive DA block headers following the latest DA block verified by the previous proof
+    pub da_block_headers: Vec<Da::BlockHeader>,
+    /// Relevant blobs of each DA block header, which the batch proofs are posted in
+    pub da_blobs: Vec<SlotBlobs<Da>>,
+    /// Outputs of the batch proofs posted in the DA blocks, in the order they are applied.
+    /// The proofs themselves are added to the host with [`ZkvmHost::add_assumption`].
+    pub state_transitions: Vec<StateTransition<Da, Root>>,
+}
+
+impl<Da: DaSpec, Root: Clone + AsRef<[u8]>> LightClientData<Da, Root> {
+    /// Chain state of the latest DA block verified before the headers,
+    /// which the consensus rules of the first header are checked against.
+    pub fn chain_state(&self) -> &DaChainState {
+        match &self.previous_output {
+            Some(previous) => &previous.chain_state,
+            None => &self.initial_chain_state,
+        }
+    }
+
+    /// Extends the previous light client output over the DA block headers and state transitions.
+    /// `chain_state` is the chain state after the headers.
+    /// Returns the new output, and the hashes of the latest DA blocks the next proof is given.
+    ///
+    /// The consensus rules of the headers and the proofs of the state transitions are not
+    /// verified here, see [`DaVerifier::verify_headers`](crate::da::DaVerifier::verify_headers).
+    pub fn output<H: Digest>(
+        &self,
+        chain_state: DaChainState,
+    ) -> anyhow::Result<(LightClientOutput<Root>, Vec<[u8; 32]>)> {
+        anyhow::ensure!(
+            !self.da_block_headers.is_empty(),
+            "No DA block header to verify"
+        );
+        anyhow::ensure!(
+            self.da_blobs.len() == self.da_block_headers.len(),
+            "Blobs of {} DA blocks are given for {} headers",
+            self.da_blobs.len(),
+            self.da_block_headers.len()
+        );
+
+        let mut output = match &self.previous_output {
+            Some(previous) => {
+                anyhow::ensure!(
+                    previous.light_client_code_commitment == self.light_client_code_commitment
+                        && previous.batch_proof_code_commitment == self.batch_proof_code_commitment,
+                    "Code commitments differ from the previous light client proof"
+                );
+                anyhow::ensure!(
+                    digest_da_hashes::<H>(&self.recent_da_hashes)
+                        == previous.recent_da_hashes_digest,
+                    "Recent DA block hashes don't match the previous light client proof"
+                );
+                previous.clone()
+            }
+            None => {
+                anyhow::ensure!(
+                    self.recent_da_hashes.is_empty(),
+                    "The first light client proof has no recent DA blocks"
+                );
+                LightClientOutput {
+                    light_client_code_commitment: self.light_client_code_commitment.clone(),
+                    batch_proof_code_commitment: self.batch_proof_code_commitment.clone(),
+                    initial_state_root: self.initial_state_root.clone(),
+                    initial_da_hash: self.initial_da_hash,
+                    initial_da_height: self.initial_da_height,
+                    initial_chain_state: self.initial_chain_state.clone(),
+                    l2_state_root: self.initial_state_root.clone(),
+                    latest_da_hash: self.initial_da_hash,
+                    // The first header has to follow the anchor
+                    latest_da_height: self.initial_da_height,
+                    recent_da_hashes_digest: [0; 32],
+                    chain_state: self.initial_chain_state.clone(),
+                }
+            }
+        };
+
+        let mut recent_da_hashes: VecDeque<[u8; 32]> =
+            self.recent_da_hashes.iter().copied().collect();
+        for header in &self.da_block_headers {
+            anyhow::ensure!(
+                header.prev_hash().into() == output.latest_da_hash
+                    && header.height() == output.latest_da_height + 1,
+                "DA block header at height {} does not follow the latest verified block",
+                header.height()
+            );
+            output.latest_da_hash = header.hash().into();
+            output.latest_da_height = header.height();
+
+            recent_da_hashes.push_back(output.latest_da_hash);
+            if recent_da_hashes.len() > LIGHT_CLIENT_DA_WINDOW {
+                recent_da_hashes.pop_front();
+            }
+        }
+
+        for state_transition in &self.state_transitions {
+            let da_slot_hash: [u8; 32] = state_transition.da_slot_hash.clone().into();
+            anyhow::ensure!(
+                recent_da_hashes.contains(&da_slot_hash),
+                "State transition of a DA block which is not among the latest verified blocks"
+            );
+            anyhow::ensure!(
+                state_transition.initial_state_root.as_ref() == output.l2_state_root.as_ref(),
+                "State transition does not start from the latest proven state root"
+            );
+            output.l2_state_root = state_transition.final_state_root.clone();
+        }
+
+        let recent_da_hashes: Vec<[u8; 32]> = recent_da_hashes.into();
+        output.recent_da_hashes_digest = digest_da_hashes::<H>(&recent_da_hashes);
+        output.chain_state = chain_state;
+
+        Ok((output, recent_da_hashes))
+    }
+}
+
+fn digest_da_hashes<H: Digest>(hashes: &[[u8; 32]]) -> [u8; 32] {
+    let mut hasher = H::new();
+    for hash in hashes {
+        hasher.update(hash);
+    }
+    let mut digest = [0; 32];
+    let output = hasher.finalize();
+    digest.copy_from_slice(&output[..32]);
+    digest
+}
+
+/// A witness made of serialized hints, which the guest reads in order.
+pub trait HintWitness: Sized {
+    /// Returns the hints which are not read yet.
+    fn into_hints(self) -> Vec<Vec<u8>>;
+
+    /// Creates a witness reading the given hints.
+    fn from_hints(hints: Vec<Vec<u8>>) -> Self;
+}
+
+impl HintWitness for () {
+    fn into_hints(self) -> Vec<Vec<u8>> {
+        Vec::new()
+    }
+
+    fn from_hints(_hints: Vec<Vec<u8>>) -> Self {}
+}
+
+/// The witnesses of the soft confirmations of a range of sequencer commitments,
+/// grouped by sequencer commitment.
+///
+/// Consecutive soft confirmations mostly read the same state, so the same hints, like the
+/// JMT proofs of frequently read keys, repeat across their witnesses. Every distinct hint is
+/// only serialized once and the witnesses refer to their hints by index, which cuts the size
+/// of the guest input and the cycles spent deserializing it.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(bound = "")]
+pub struct CompactWitnesses<Witness> {
+    /// Distinct hints of all witnesses
+    hints: Vec<Vec<u8>>,
+    /// Indices of the hints of each witness
+    witnesses: VecDeque<Vec<Vec<u32>>>,
+    #[borsh(skip)]
+    #[serde(skip)]
+    phantom: PhantomData<fn() -> Witness>,
+}
+
+impl<Witness> Default for CompactWitnesses<Witness> {
+    fn default() -> Self {
+        Self {
+            hints: Vec::new(),
+            witnesses: VecDeque::new(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<Witness: HintWitness> CompactWitnesses<Witness> {
+    /// Deduplicates the hints of the witnesses.
+    pub fn compact(witnesses: VecDeque<Vec<Witness>>) -> Self {
+        let mut hints = Vec::new();
+        let mut hint_indices: BTreeMap<Vec<u8>, u32> = BTreeMap::new();
+        let witnesses = witnesses
+            .into_iter()
+            .map(|commitment_witnesses| {
+                commitment_witnesses
+                    .into_iter()
+                    .map(|witness| {
+                        let mut indices = Vec::new();
+                        for hint in witness.into_hints() {
+                            let index = match hint_indices.get(&hint) {
+                                Some(index) => *index,
+                                None => {
+                                    let index = u32::try_from(hints.len())
+                                        .expect("cant be more than 4 billion distinct hints; qed");
+                                    hint_indices.insert(hint.clone(), index);
+                                    hints.push(hint);
+                                    index
+                                }
+                            };
+                            indices.push(index);
+                        }
+                        indices
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            hints,
+            witnesses,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Restores the witnesses, grouped by sequencer commitment.
+    /// Hints are moved into the witness reading them last, only the earlier reads are copies.
+    pub fn expand(self) -> VecDeque<Vec<Witness>> {
+        let Self {
+            hints, witnesses, ..
+        } = self;
+        let mut reads_left = vec![0u32; hints.len()];
+        for index in witnesses.iter().flatten().flatten() {
+            reads_left[*index as usize] += 1;
+        }
+        let mut hints: Vec<Option<Vec<u8>>> = hints.into_iter().map(Some).collect();
+
+        witnesses
+            .into_iter()
+            .map(|commitment_witnesses| {
+                commitment_witnesses
+                    .into_iter()
+                    .map(|indices| {
+                        Witness::from_hints(
+                            indices
+                                .into_iter()
+                                .map(|index| {
+                                    let index = index as usize;
+                                    reads_left[index] -= 1;
+                                    let hint = if reads_left[index] == 0 {
+                                        hints[index].take()
+                                    } else {
+                                        hints[index].clone()
+                                    };
+                                    hint.expect("A hint is moved out on its last read; qed")
+                                })
+                                .collect(),
+                        )
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Number of distinct hints
+    pub fn distinct_hints(&self) -> usize {
+        self.hints.len()
+    }
+
+    /// Number of hints of all witnesses, including the duplicates
+    pub fn total_hints(&self) -> usize {
+        self.witnesses.iter().flatten().map(Vec::len).sum()
+    }
+}
+
 /// This trait expresses that a type can check a validity condition.
 pub trait ValidityConditionChecker<Condition: ValidityCondition>:
     BorshDeserialize + BorshSerialize + Debug
@@ -184,9 +661,12 @@ pub struct StateTransitionData<StateRoot, Witness, Da: DaSpec> {
     /// The soft confirmations that are inside the sequencer commitments.
     pub soft_confirmations: VecDeque<Vec<SignedSoftConfirmationBatch>>,
     /// Corresponding witness for the soft confirmations.
-    pub state_transition_witnesses: VecDeque<Vec<Witness>>,
+    pub state_transition_witnesses: CompactWitnesses<Witness>,
     /// DA block headers the soft confirmations was constructed on.
     pub da_block_headers_of_soft_confirmations: VecDeque<Vec<Da::BlockHeader>>,
+    /// Relevant blobs of the DA blocks the soft confirmations was constructed on,
+    /// in the order of their headers. Their inboxes are read from them.
+    pub da_blobs_of_soft_confirmations: VecDeque<Vec<SlotBlobs<Da>>>,
 
     /// Sequencer soft confirmation public key.
     pub sequencer_public_key: Vec<u8>,
@@ -197,3 +677,16 @@ pub struct StateTransitionData<StateRoot, Witness, Da: DaSpec> {
     /// The range is inclusive.
     pub sequencer_commitments_range: (u32, u32),
 }
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(bound = "")]
+/// All relevant blobs of a DA block, with the proofs that they are in the block
+/// and that no relevant blob is left out.
+pub struct SlotBlobs<Da: DaSpec> {
+    /// The relevant blobs of the block
+    pub blobs: Vec<Da::BlobTransaction>,
+    /// The inclusion proof of the blobs
+    pub inclusion_proof: Da::InclusionMultiProof,
+    /// The completeness proof of the blobs
+    pub completeness_proof: Da::CompletenessProof,
+}
@@ -13,8 +13,8 @@ use sov_modules_api::default_context::DefaultContext;
 use sov_modules_api::default_signature::private_key::DefaultPrivateKey;
 use sov_modules_api::transaction::Transaction;
 use sov_modules_api::{Address, AddressBech32, EncodeCall, PrivateKey, PublicKey, Spec};
-use sov_rollup_interface::da::{BlockHeaderTrait, DaSpec, DaVerifier, Time};
-use sov_rollup_interface::services::da::{DaService, SlotData};
+use sov_rollup_interface::da::{BlockHeaderTrait, DaChainState, DaSpec, DaVerifier, Time};
+use sov_rollup_interface::services::da::{DaService, FeeBump, SlotData, TxStatus};
 
 const DEFAULT_CHAIN_ID: u64 = 0;
 
@@ -179,6 +179,21 @@ impl DaService for RngDaService {
         unimplemented!()
     }
 
+    async fn get_transaction_status(
+        &self,
+        _tx_id: &Self::TransactionId,
+    ) -> Result<TxStatus, Self::Error> {
+        unimplemented!()
+    }
+
+    async fn replace_transaction(
+        &self,
+        _blob: &[u8],
+        _fee_bump: FeeBump<Self::TransactionId>,
+    ) -> Result<Self::TransactionId, Self::Error> {
+        unimplemented!()
+    }
+
     async fn send_aggregated_zk_proof(&self, _proof: &[u8]) -> Result<u64, Self::Error> {
         unimplemented!()
     }
@@ -217,6 +232,18 @@ impl DaVerifier for RngDaVerifier {
     ) -> Result<<Self::Spec as DaSpec>::ValidityCondition, Self::Error> {
         Ok(MockValidityCond { is_valid: true })
     }
+
+    fn verify_headers(
+        &self,
+        chain_state: &DaChainState,
+        block_headers: &[<Self::Spec as DaSpec>::BlockHeader],
+    ) -> Result<DaChainState, Self::Error> {
+        let mut chain_state = chain_state.clone();
+        if let Some(header) = block_headers.last() {
+            chain_state.tip_time = header.time().secs() as u64;
+        }
+        Ok(chain_state)
+    }
 }
 
 pub fn generate_transfers(n: usize, start_nonce: u64) -> Vec<u8> {
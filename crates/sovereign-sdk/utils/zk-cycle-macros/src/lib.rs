@@ -4,7 +4,7 @@ extern crate proc_macro;
 
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, ItemFn};
+use syn::{parse_macro_input, ItemFn, LitStr};
 
 /// This macro is used to annotate functions that we want to track the number of riscV cycles being
 /// generated inside the VM. The purpose of the this macro is to measure how many cycles a rust
@@ -18,17 +18,25 @@ use syn::{parse_macro_input, ItemFn};
 /// a custom syscall that is generated when the prover is run with the `bench` feature.
 /// `send_recv_slice` is used to communicate and pass a slice to the syscall that we defined.
 /// The handler for the syscall can be seen in adapters/risc0/src/host.rs and adapters/risc0/src/metrics.rs
+///
+/// The cycles are reported under the name of the function, unless a name is given,
+/// e.g. `#[cycle_tracker("jmt_updates")]`. Functions with the same name are accounted together.
 #[proc_macro_attribute]
-pub fn cycle_tracker(_attr: TokenStream, item: TokenStream) -> TokenStream {
+pub fn cycle_tracker(attr: TokenStream, item: TokenStream) -> TokenStream {
     let input = parse_macro_input!(item as ItemFn);
+    let metric = if attr.is_empty() {
+        input.sig.ident.to_string()
+    } else {
+        parse_macro_input!(attr as LitStr).value()
+    };
 
-    match wrap_function(input) {
+    match wrap_function(input, metric) {
         Ok(ok) => ok,
         Err(err) => err.to_compile_error().into(),
     }
 }
 
-fn wrap_function(input: ItemFn) -> Result<TokenStream, syn::Error> {
+fn wrap_function(input: ItemFn, metric: String) -> Result<TokenStream, syn::Error> {
     let visibility = &input.vis;
     let name = &input.sig.ident;
     let inputs = &input.sig.inputs;
@@ -47,7 +55,7 @@ fn wrap_function(input: ItemFn) -> Result<TokenStream, syn::Error> {
             let after = #risc0_zkvm::guest::env::cycle_count();
 
             // simple serialization to avoid pulling in bincode or other libs
-            let tuple = (stringify!(#name).to_string(), (after - before) as u64);
+            let tuple = (#metric.to_string(), (after - before) as u64);
             let mut serialized = Vec::new();
             serialized.extend(tuple.0.as_bytes());
             serialized.push(0);
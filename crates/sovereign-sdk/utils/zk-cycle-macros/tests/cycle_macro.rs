@@ -68,4 +68,7 @@ fn _function_with_params(_a: u32, _b: usize) {}
 #[cycle_tracker]
 pub fn _function_with_access_specifier(_a: u32, _b: usize) {}
 
+#[cycle_tracker("named_phase")]
+fn _function_with_metric_name(_a: u32) {}
+
 fn main() {}
@@ -0,0 +1,311 @@
+#![deny(missing_docs)]
+#![doc = include_str!("../README.md")]
+
+mod node;
+
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use citrea_primitives::{Clock, SystemClock, TestClock, TEST_PRIVATE_KEY};
+use citrea_sequencer::SequencerConfig;
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::rpc_params;
+pub use node::*;
+use sov_mock_da::{MockAddress, MockDaConfig, MockDaService};
+use sov_stf_runner::{FullNodeConfig, ProverConfig, RollupPublicKeys, RpcConfig, StorageConfig};
+use tempfile::TempDir;
+
+/// Genesis of the integration tests
+pub const DEFAULT_GENESIS_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../resources/test-data/integration-tests"
+);
+
+/// Which nodes a cluster runs
+#[derive(Debug, Clone)]
+pub struct ClusterConfig {
+    /// Directory of the genesis files of all nodes
+    pub genesis_dir: PathBuf,
+    /// Number of full nodes syncing from the sequencer
+    pub full_nodes: usize,
+    /// Configuration of the prover, which is not started if not set
+    pub prover: Option<ProverConfig>,
+    /// Number of L2 blocks after which the sequencer commits to them on DA
+    pub min_soft_confirmations_per_commitment: u64,
+    /// Whether full nodes store the transaction bodies of L2 blocks
+    pub include_tx_body: bool,
+    /// Blocks to finality of the DA handle of the cluster.
+    /// Blocks can only be forked away with [`Cluster::fork_da_at`] before they are final.
+    pub da_blocks_to_finality: u32,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            genesis_dir: DEFAULT_GENESIS_DIR.into(),
+            full_nodes: 1,
+            prover: None,
+            min_soft_confirmations_per_commitment: 1000,
+            include_tx_body: true,
+            da_blocks_to_finality: 0,
+        }
+    }
+}
+
+/// A sequencer, full nodes and an optional prover over a shared [`MockDaService`].
+/// All nodes are stopped when the cluster is dropped.
+pub struct Cluster {
+    /// Handle to the DA layer all nodes read from
+    pub da_service: MockDaService,
+    /// The sequencer, which only produces L2 blocks when they are published
+    pub sequencer: Node,
+    /// Full nodes syncing from the sequencer
+    pub full_nodes: Vec<Node>,
+    /// The prover, if configured
+    pub prover: Option<Node>,
+    /// Time of the nodes, which only moves when advanced or while a helper waits for the nodes
+    pub clock: Arc<TestClock>,
+    // Keeps the databases of the nodes until the cluster is dropped
+    _dir: TempDir,
+}
+
+impl Cluster {
+    /// Starts all nodes of the cluster and waits until their RPC servers are up
+    pub async fn start(config: ClusterConfig) -> anyhow::Result<Self> {
+        let dir = tempfile::tempdir()?;
+        let da_path = dir.path().join("da");
+        std::fs::create_dir_all(&da_path)?;
+        let clock = Arc::new(TestClock::new(SystemClock.unix_timestamp()));
+
+        let sequencer = Node::start(
+            NodeKind::Sequencer(sequencer_config(&config)),
+            rollup_config(&config, &dir.path().join("sequencer"), &da_path, None),
+            config.genesis_dir.clone(),
+            clock.clone(),
+        )
+        .await?;
+
+        let mut full_nodes = Vec::with_capacity(config.full_nodes);
+        for i in 0..config.full_nodes {
+            full_nodes.push(
+                Node::start(
+                    NodeKind::FullNode,
+                    rollup_config(
+                        &config,
+                        &dir.path().join(format!("full-node-{}", i)),
+                        &da_path,
+                        Some(&sequencer),
+                    ),
+                    config.genesis_dir.clone(),
+                    clock.clone(),
+                )
+                .await?,
+            );
+        }
+
+        let prover = match &config.prover {
+            Some(prover_config) => Some(
+                Node::start(
+                    NodeKind::Prover(prover_config.clone()),
+                    rollup_config(
+                        &config,
+                        &dir.path().join("prover"),
+                        &da_path,
+                        Some(&sequencer),
+                    ),
+                    config.genesis_dir.clone(),
+                    clock.clone(),
+                )
+                .await?,
+            ),
+            None => None,
+        };
+
+        Ok(Self {
+            da_service: MockDaService::with_finality(
+                MockAddress::from([0; 32]),
+                config.da_blocks_to_finality,
+                &da_path,
+            ),
+            sequencer,
+            full_nodes,
+            prover,
+            clock,
+            _dir: dir,
+        })
+    }
+
+    /// All nodes following the sequencer
+    pub fn followers(&self) -> impl Iterator<Item = &Node> {
+        self.full_nodes.iter().chain(self.prover.as_ref())
+    }
+
+    /// Makes the sequencer produce an L2 block with the transactions in its mempool
+    pub async fn publish_l2_block(&self) -> anyhow::Result<()> {
+        self.sequencer
+            .client()
+            .request::<(), _>("citrea_testPublishBlock", rpc_params![])
+            .await?;
+        Ok(())
+    }
+
+    /// Makes the sequencer produce `count` L2 blocks and waits until it has stored them
+    pub async fn publish_l2_blocks(&self, count: u64) -> anyhow::Result<u64> {
+        let start = self.sequencer.head_l2_height().await?;
+        for _ in 0..count {
+            self.publish_l2_block().await?;
+        }
+        self.sequencer
+            .wait_for_l2_height(start + count, DEFAULT_TIMEOUT)
+            .await?;
+        Ok(start + count)
+    }
+
+    /// Sends a signed raw transaction to the sequencer, returning its hash
+    pub async fn send_raw_transaction(&self, raw_tx: &[u8]) -> anyhow::Result<String> {
+        Ok(self
+            .sequencer
+            .client()
+            .request(
+                "eth_sendRawTransaction",
+                rpc_params![format!("0x{}", hex::encode(raw_tx))],
+            )
+            .await?)
+    }
+
+    /// Adds `count` empty blocks to the DA layer, returning the new DA height
+    pub async fn advance_da_blocks(&self, count: u64) -> anyhow::Result<u64> {
+        for _ in 0..count {
+            self.da_service.publish_test_block().await?;
+        }
+        Ok(self.da_service.get_height().await)
+    }
+
+    /// Moves the time of the nodes forward, firing their timers which are due
+    pub fn advance_time(&self, duration: Duration) {
+        self.clock.advance(duration);
+    }
+
+    /// Replaces the DA blocks above `height` with one block per blob
+    pub async fn fork_da_at(&self, height: u64, blobs: Vec<Vec<u8>>) -> anyhow::Result<()> {
+        self.da_service.fork_at(height, blobs).await
+    }
+
+    /// Waits until all nodes following the sequencer reached the L2 height
+    pub async fn wait_for_sync(&self, l2_height: u64, timeout: Duration) -> anyhow::Result<()> {
+        for node in self.followers() {
+            node.wait_for_l2_height(l2_height, timeout).await?;
+        }
+        Ok(())
+    }
+
+    /// Checks that all nodes have the state root of the sequencer after the L2 height
+    pub async fn assert_state_roots_match(&self, l2_height: u64) -> anyhow::Result<Vec<u8>> {
+        let Some(expected) = self.sequencer.state_root_at(l2_height).await? else {
+            anyhow::bail!("Sequencer has no L2 block {}", l2_height);
+        };
+        for node in self.followers() {
+            let state_root = node.state_root_at(l2_height).await?;
+            if state_root.as_ref() != Some(&expected) {
+                anyhow::bail!(
+                    "State root of {} after L2 height {} is {:?} instead of 0x{}",
+                    node.name(),
+                    l2_height,
+                    state_root.map(hex::encode),
+                    hex::encode(&expected)
+                );
+            }
+        }
+        Ok(expected)
+    }
+}
+
+/// Sequencer configuration of a cluster, in test mode so L2 blocks are published on request
+fn sequencer_config(config: &ClusterConfig) -> SequencerConfig {
+    SequencerConfig {
+        private_key: TEST_PRIVATE_KEY.to_string(),
+        min_soft_confirmations_per_commitment: config.min_soft_confirmations_per_commitment,
+        test_mode: true,
+        deposit_mempool_fetch_limit: 10,
+        mempool_conf: Default::default(),
+        db_config: None,
+        da_update_interval_ms: 500,
+        block_production_interval_ms: 500,
+        max_l2_block_state_diff_size: 100 * 1024,
+        empty_block_policy: Default::default(),
+        empty_block_heartbeat_ms: 60_000,
+        commitment_policy: Default::default(),
+        enable_admin_rpc: false,
+        standby: None,
+        lease: None,
+        tx_gossip_peers: vec![],
+        da_payload_mode: Default::default(),
+        relay: None,
+    }
+}
+
+/// Rollup configuration of a node of a cluster, following the sequencer if one is given
+fn rollup_config(
+    config: &ClusterConfig,
+    storage_path: &std::path::Path,
+    da_path: &std::path::Path,
+    sequencer: Option<&Node>,
+) -> FullNodeConfig<MockDaConfig> {
+    FullNodeConfig {
+        public_keys: RollupPublicKeys {
+            sequencer_public_key: vec![
+                32, 64, 64, 227, 100, 193, 15, 43, 236, 156, 31, 229, 0, 161, 205, 76, 36, 124,
+                137, 214, 80, 160, 30, 215, 232, 44, 171, 168, 103, 135, 124, 33,
+            ],
+            sequencer_da_pub_key: vec![0; 32],
+            prover_da_pub_key: vec![0; 32],
+            sequencer_da_pub_key_rotations: vec![],
+        },
+        storage: StorageConfig {
+            path: storage_path.to_path_buf(),
+            ledger_db: Default::default(),
+            state_db: Default::default(),
+            replica: None,
+            state_cache_size: None,
+        },
+        rpc: RpcConfig {
+            bind_host: "127.0.0.1".into(),
+            bind_port: 0,
+            max_connections: 100,
+            max_request_body_size: 10 * 1024 * 1024,
+            max_response_body_size: 10 * 1024 * 1024,
+            batch_requests_limit: 50,
+            enable_subscriptions: true,
+            max_subscriptions_per_connection: 100,
+            trace_cache_size: None,
+            operator: None,
+            public_operator_methods: true,
+            sequencer_tx_fallback: true,
+            sequencer_tx_fallback_negative_cache_ms: 1000,
+            method_timeouts_ms: Default::default(),
+            default_method_timeout_ms: None,
+        },
+        runner: sequencer.map(|sequencer| sov_stf_runner::RunnerConfig {
+            sequencer_client_url: sequencer.url().into(),
+            include_tx_body: config.include_tx_body,
+            accept_public_input_as_proven: Some(true),
+            pruning_config: Default::default(),
+            proving_strategy: Default::default(),
+            da_monitor: None,
+            watchtower: None,
+            parallel_evm_execution: false,
+            diagnostics_dir: None,
+            challenge_window: None,
+            backfill_history: false,
+            backup_dir: None,
+        }),
+        da: MockDaConfig {
+            sender_address: MockAddress::from([0; 32]),
+            db_path: da_path.to_path_buf(),
+        },
+        sync_blocks_count: 10,
+        log_level: None,
+        telemetry: None,
+    }
+}
@@ -0,0 +1,218 @@
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use citrea::{CitreaRollupBlueprint, MockDemoRollup};
+use citrea_primitives::TestClock;
+use citrea_sequencer::SequencerConfig;
+use citrea_stf::genesis_config::GenesisPaths;
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use jsonrpsee::rpc_params;
+use sequencer_client::GetSoftBatchResponse;
+use sov_mock_da::MockDaConfig;
+use sov_stf_runner::{FullNodeConfig, ProverConfig};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::sleep;
+use tracing::{error, info_span, Instrument};
+
+/// How long the helpers of a cluster wait for nodes by default
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How far the clock of the nodes moves forward each time a helper polls a node it waits for
+pub const WAIT_CLOCK_STEP: Duration = Duration::from_secs(1);
+
+/// Which kind of node to start
+#[derive(Debug, Clone)]
+pub enum NodeKind {
+    /// Sequencer with its configuration
+    Sequencer(SequencerConfig),
+    /// Full node following the sequencer
+    FullNode,
+    /// Prover following the sequencer, with its configuration
+    Prover(ProverConfig),
+}
+
+impl NodeKind {
+    fn name(&self) -> &'static str {
+        match self {
+            NodeKind::Sequencer(_) => "Sequencer",
+            NodeKind::FullNode => "FullNode",
+            NodeKind::Prover(_) => "Prover",
+        }
+    }
+}
+
+/// A node running in the background of the test, which is stopped when dropped
+pub struct Node {
+    name: &'static str,
+    rpc_addr: SocketAddr,
+    client: HttpClient,
+    clock: Arc<TestClock>,
+    task: JoinHandle<()>,
+}
+
+impl Node {
+    /// Starts a node on the clock and waits until its RPC server is up.
+    /// The timers of the sequencer and full nodes only fire when the clock is advanced.
+    pub async fn start(
+        kind: NodeKind,
+        rollup_config: FullNodeConfig<MockDaConfig>,
+        genesis_dir: PathBuf,
+        clock: Arc<TestClock>,
+    ) -> anyhow::Result<Self> {
+        let name = kind.name();
+        let (port_tx, port_rx) = oneshot::channel();
+        let node_clock = clock.clone();
+        let task = tokio::spawn(
+            async move {
+                if let Err(e) =
+                    run_node(kind, rollup_config, genesis_dir, node_clock, port_tx).await
+                {
+                    error!("Node stopped: {:?}", e);
+                }
+            }
+            .instrument(info_span!("Node", name)),
+        );
+
+        let rpc_addr = port_rx
+            .await
+            .map_err(|_| anyhow::anyhow!("{} stopped before its RPC server was up", name))?;
+        let client = HttpClientBuilder::default()
+            .request_timeout(DEFAULT_TIMEOUT)
+            .build(format!("http://{}", rpc_addr))?;
+
+        Ok(Self {
+            name,
+            rpc_addr,
+            client,
+            clock,
+            task,
+        })
+    }
+
+    /// Kind of the node, for error messages
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Address of the RPC server of the node
+    pub fn rpc_addr(&self) -> SocketAddr {
+        self.rpc_addr
+    }
+
+    /// URL of the RPC server of the node
+    pub fn url(&self) -> String {
+        format!("http://localhost:{}", self.rpc_addr.port())
+    }
+
+    /// Client of the RPC server of the node
+    pub fn client(&self) -> &HttpClient {
+        &self.client
+    }
+
+    /// Height of the latest L2 block of the node
+    pub async fn head_l2_height(&self) -> anyhow::Result<u64> {
+        Ok(self
+            .client
+            .request("ledger_getHeadSoftBatchHeight", rpc_params![])
+            .await?)
+    }
+
+    /// The L2 block at the height, if the node has it
+    pub async fn soft_batch(&self, l2_height: u64) -> anyhow::Result<Option<GetSoftBatchResponse>> {
+        Ok(self
+            .client
+            .request("ledger_getSoftBatchByNumber", rpc_params![l2_height])
+            .await?)
+    }
+
+    /// State root of the node after the L2 block at the height, if the node has it
+    pub async fn state_root_at(&self, l2_height: u64) -> anyhow::Result<Option<Vec<u8>>> {
+        Ok(self
+            .soft_batch(l2_height)
+            .await?
+            .map(|soft_batch| soft_batch.state_root))
+    }
+
+    /// Waits until the node has the L2 block at the height.
+    /// The clock moves forward by [`WAIT_CLOCK_STEP`] on each poll, so the node keeps polling.
+    pub async fn wait_for_l2_height(
+        &self,
+        l2_height: u64,
+        timeout: Duration,
+    ) -> anyhow::Result<()> {
+        let start = Instant::now();
+        loop {
+            let head = self.head_l2_height().await?;
+            if head >= l2_height {
+                return Ok(());
+            }
+            if start.elapsed() > timeout {
+                anyhow::bail!(
+                    "{} is at L2 height {} after {:?}, expected {}",
+                    self.name,
+                    head,
+                    timeout,
+                    l2_height
+                );
+            }
+            self.clock.advance(WAIT_CLOCK_STEP);
+            sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run_node(
+    kind: NodeKind,
+    rollup_config: FullNodeConfig<MockDaConfig>,
+    genesis_dir: PathBuf,
+    clock: Arc<TestClock>,
+    port_tx: oneshot::Sender<SocketAddr>,
+) -> anyhow::Result<()> {
+    let mock_demo_rollup = MockDemoRollup {};
+    let genesis_paths = GenesisPaths::from_dir(genesis_dir);
+
+    match kind {
+        NodeKind::Sequencer(sequencer_config) => {
+            let mut sequencer = CitreaRollupBlueprint::create_new_sequencer(
+                &mock_demo_rollup,
+                &genesis_paths,
+                rollup_config,
+                sequencer_config,
+            )
+            .await?;
+            sequencer.runner = sequencer.runner.with_clock(clock);
+            sequencer.run_and_report_rpc_port(Some(port_tx)).await
+        }
+        NodeKind::FullNode => {
+            let mut full_node = CitreaRollupBlueprint::create_new_rollup(
+                &mock_demo_rollup,
+                &genesis_paths,
+                rollup_config,
+            )
+            .await?;
+            full_node.runner = full_node.runner.with_clock(clock);
+            full_node.run_and_report_rpc_port(Some(port_tx)).await
+        }
+        NodeKind::Prover(prover_config) => {
+            CitreaRollupBlueprint::create_new_prover(
+                &mock_demo_rollup,
+                &genesis_paths,
+                rollup_config,
+                prover_config,
+            )
+            .await?
+            .run_and_report_rpc_port(Some(port_tx))
+            .await
+        }
+    }
+}
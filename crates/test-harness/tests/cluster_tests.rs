@@ -0,0 +1,19 @@
+use citrea_test_harness::{Cluster, ClusterConfig, DEFAULT_TIMEOUT};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_full_nodes_follow_sequencer() -> anyhow::Result<()> {
+    let cluster = Cluster::start(ClusterConfig {
+        full_nodes: 2,
+        ..Default::default()
+    })
+    .await?;
+
+    let l2_height = cluster.publish_l2_blocks(5).await?;
+    assert_eq!(l2_height, 5);
+    cluster.wait_for_sync(l2_height, DEFAULT_TIMEOUT).await?;
+
+    for height in 1..=l2_height {
+        cluster.assert_state_roots_match(height).await?;
+    }
+    Ok(())
+}
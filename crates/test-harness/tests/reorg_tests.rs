@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use citrea_test_harness::{Cluster, ClusterConfig, DEFAULT_TIMEOUT};
+
+/// Time after which the sequencer and the full nodes have polled the DA layer
+const DA_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_full_nodes_follow_sequencer_over_da_fork() -> anyhow::Result<()> {
+    let cluster = Cluster::start(ClusterConfig {
+        da_blocks_to_finality: 5,
+        ..Default::default()
+    })
+    .await?;
+
+    let da_height = cluster.advance_da_blocks(3).await?;
+    let l2_height = cluster.publish_l2_blocks(2).await?;
+    cluster.wait_for_sync(l2_height, DEFAULT_TIMEOUT).await?;
+
+    cluster.fork_da_at(da_height - 2, vec![]).await?;
+    assert_eq!(cluster.advance_da_blocks(1).await?, da_height - 1);
+    cluster.advance_time(DA_POLL_INTERVAL);
+
+    let l2_height = cluster.publish_l2_blocks(2).await?;
+    cluster.wait_for_sync(l2_height, DEFAULT_TIMEOUT).await?;
+    cluster.assert_state_roots_match(l2_height).await?;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_full_nodes_follow_sequencer_over_several_da_forks() -> anyhow::Result<()> {
+    let cluster = Cluster::start(ClusterConfig {
+        full_nodes: 2,
+        da_blocks_to_finality: 5,
+        ..Default::default()
+    })
+    .await?;
+
+    let mut l2_height = 0;
+    for _ in 0..3 {
+        let da_height = cluster.advance_da_blocks(3).await?;
+        l2_height = cluster.publish_l2_blocks(2).await?;
+        cluster.wait_for_sync(l2_height, DEFAULT_TIMEOUT).await?;
+
+        // Each fork replaces the last two blocks with a single one
+        cluster.fork_da_at(da_height - 2, vec![]).await?;
+        assert_eq!(cluster.advance_da_blocks(1).await?, da_height - 1);
+        cluster.advance_time(DA_POLL_INTERVAL);
+    }
+
+    l2_height = cluster.publish_l2_blocks(2).await?;
+    cluster.wait_for_sync(l2_height, DEFAULT_TIMEOUT).await?;
+    for height in 1..=l2_height {
+        cluster.assert_state_roots_match(height).await?;
+    }
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_full_nodes_follow_sequencer_with_instant_finality() -> anyhow::Result<()> {
+    let cluster = Cluster::start(ClusterConfig::default()).await?;
+
+    for _ in 0..3 {
+        cluster.advance_da_blocks(1).await?;
+        cluster.advance_time(DA_POLL_INTERVAL);
+        let l2_height = cluster.publish_l2_blocks(1).await?;
+        cluster.wait_for_sync(l2_height, DEFAULT_TIMEOUT).await?;
+        cluster.assert_state_roots_match(l2_height).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,230 @@
+#[cfg(feature = "native")]
+mod query;
+#[cfg(feature = "native")]
+pub use query::*;
+
+#[cfg(all(test, feature = "native"))]
+mod tests;
+
+use std::collections::BTreeMap;
+use std::ops::RangeInclusive;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use rs_merkle::algorithms::Sha256;
+use rs_merkle::{Hasher, MerkleTree};
+use serde::{Deserialize, Serialize};
+use sov_modules_api::{
+    Context, ModuleInfo, StateMap, StateMapAccessor, StateValue, StateValueAccessor, WorkingSet,
+};
+use sov_state::codec::BorshCodec;
+
+/// A withdrawal from Citrea to Bitcoin, initiated on the Bridge contract
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Withdrawal {
+    /// Index of the withdrawal in the Bridge contract
+    pub index: u64,
+    /// L2 height of the block the withdrawal was initiated in
+    pub l2_height: u64,
+    /// Bitcoin address the withdrawal is paid to
+    #[serde(with = "hex::serde")]
+    pub bitcoin_address: [u8; 32],
+}
+
+impl Withdrawal {
+    /// Merkle leaf of the withdrawal, the sha256 hash of its borsh encoding
+    pub fn leaf(&self) -> [u8; 32] {
+        Sha256::hash(&borsh::to_vec(self).expect("Serialization to vec is infallible"))
+    }
+}
+
+/// Merkle root of the withdrawals, in the given order.
+/// Zero if there are no withdrawals.
+pub fn withdrawal_root(withdrawals: &[Withdrawal]) -> [u8; 32] {
+    let leaves: Vec<[u8; 32]> = withdrawals.iter().map(Withdrawal::leaf).collect();
+    MerkleTree::<Sha256>::from_leaves(&leaves)
+        .root()
+        .unwrap_or_default()
+}
+
+/// Merkle proof of the withdrawal at `leaf_index` against [`withdrawal_root`] of the withdrawals.
+pub fn withdrawal_proof(withdrawals: &[Withdrawal], leaf_index: usize) -> Vec<[u8; 32]> {
+    let leaves: Vec<[u8; 32]> = withdrawals.iter().map(Withdrawal::leaf).collect();
+    MerkleTree::<Sha256>::from_leaves(&leaves)
+        .proof(&[leaf_index])
+        .proof_hashes()
+        .to_vec()
+}
+
+#[derive(ModuleInfo, Clone)]
+pub struct WithdrawalQueue<C: Context> {
+    /// Address of the WithdrawalQueue module.
+    #[address]
+    address: C::Address,
+    /// Withdrawals by their index in the Bridge contract.
+    #[state]
+    pub(crate) withdrawals: StateMap<u64, Withdrawal, BorshCodec>,
+    /// Number of withdrawals, the index of the next one.
+    #[state]
+    pub(crate) withdrawal_count: StateValue<u64, BorshCodec>,
+    /// Index of the first recorded withdrawal.
+    /// Withdrawals initiated on the Bridge contract before the queue are not recorded.
+    #[state]
+    pub(crate) first_withdrawal_index: StateValue<u64, BorshCodec>,
+}
+
+/// A withdrawal the queue should have recorded is not in state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MissingWithdrawal(pub u64);
+
+impl std::fmt::Display for MissingWithdrawal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Withdrawal {} is not recorded", self.0)
+    }
+}
+
+impl std::error::Error for MissingWithdrawal {}
+
+impl<C: Context> WithdrawalQueue<C> {
+    /// Stores the withdrawals initiated in the L2 block, given as `(index, bitcoin_address)` pairs.
+    pub fn record_withdrawals(
+        &self,
+        l2_height: u64,
+        withdrawals: Vec<(u64, [u8; 32])>,
+        working_set: &mut WorkingSet<C>,
+    ) {
+        if withdrawals.is_empty() {
+            return;
+        }
+
+        if self.first_withdrawal_index.get(working_set).is_none() {
+            let first_index = withdrawals
+                .iter()
+                .map(|(index, _)| *index)
+                .min()
+                .expect("Withdrawals are not empty");
+            self.first_withdrawal_index.set(&first_index, working_set);
+        }
+
+        let mut count = self.withdrawal_count(working_set);
+        for (index, bitcoin_address) in withdrawals {
+            self.withdrawals.set(
+                &index,
+                &Withdrawal {
+                    index,
+                    l2_height,
+                    bitcoin_address,
+                },
+                working_set,
+            );
+            count = count.max(index + 1);
+        }
+        self.withdrawal_count.set(&count, working_set);
+    }
+
+    /// Returns the withdrawal with the given index, if it was initiated.
+    pub fn withdrawal(&self, index: u64, working_set: &mut WorkingSet<C>) -> Option<Withdrawal> {
+        self.withdrawals.get(&index, working_set)
+    }
+
+    /// Returns the number of withdrawals initiated so far.
+    pub fn withdrawal_count(&self, working_set: &mut WorkingSet<C>) -> u64 {
+        self.withdrawal_count.get(working_set).unwrap_or_default()
+    }
+
+    /// Returns the index of the first recorded withdrawal, if any was recorded.
+    pub fn first_withdrawal_index(&self, working_set: &mut WorkingSet<C>) -> Option<u64> {
+        self.first_withdrawal_index.get(working_set)
+    }
+
+    /// Returns the withdrawals initiated in the L2 blocks of the range, ordered by index.
+    pub fn withdrawals_in_l2_range(
+        &self,
+        l2_heights: RangeInclusive<u64>,
+        working_set: &mut WorkingSet<C>,
+    ) -> Result<Vec<Withdrawal>, MissingWithdrawal> {
+        let start = match l2_heights.start().checked_sub(1) {
+            Some(l2_height) => self.withdrawal_count_until(l2_height, working_set)?,
+            None => self.first_withdrawal_index(working_set).unwrap_or_default(),
+        };
+        let end = self.withdrawal_count_until(*l2_heights.end(), working_set)?;
+
+        (start..end)
+            .map(|index| {
+                self.withdrawals
+                    .get(&index, working_set)
+                    .ok_or(MissingWithdrawal(index))
+            })
+            .collect()
+    }
+
+    /// Number of withdrawals initiated up to the L2 height, the index of the first one initiated after it.
+    /// Indexes are given in order, so the L2 heights of the withdrawals are sorted by index.
+    /// Withdrawals initiated before the queue are counted as initiated before any L2 height.
+    pub fn withdrawal_count_until(
+        &self,
+        l2_height: u64,
+        working_set: &mut WorkingSet<C>,
+    ) -> Result<u64, MissingWithdrawal> {
+        let mut low = self.first_withdrawal_index(working_set).unwrap_or_default();
+        let mut high = self.withdrawal_count(working_set);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let withdrawal = self
+                .withdrawals
+                .get(&mid, working_set)
+                .ok_or(MissingWithdrawal(mid))?;
+            if withdrawal.l2_height <= l2_height {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low)
+    }
+
+    /// Returns the withdrawals written in the state diff, ordered by index.
+    /// Withdrawals are never overwritten, so these are the withdrawals initiated in the L2 blocks of the diff.
+    pub fn withdrawals_from_state_diff(
+        &self,
+        state_diff: &BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    ) -> Vec<Withdrawal> {
+        let prefix = self.withdrawals.prefix().as_aligned_vec().as_ref();
+        let mut withdrawals: Vec<Withdrawal> = state_diff
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .filter_map(|(_, value)| value.as_deref())
+            .map(|value| {
+                Withdrawal::try_from_slice(value).expect("Withdrawals in state must be valid")
+            })
+            .collect();
+        withdrawals.sort_by_key(|withdrawal| withdrawal.index);
+        withdrawals
+    }
+}
+
+impl<C: Context> sov_modules_api::Module for WithdrawalQueue<C> {
+    type Context = C;
+
+    type Config = ();
+
+    type CallMessage = ();
+
+    type Event = ();
+
+    fn genesis(
+        &self,
+        _config: &Self::Config,
+        _working_set: &mut WorkingSet<Self::Context>,
+    ) -> Result<(), sov_modules_api::Error> {
+        Ok(())
+    }
+
+    fn call(
+        &self,
+        _message: Self::CallMessage,
+        _context: &Self::Context,
+        _working_set: &mut WorkingSet<Self::Context>,
+    ) -> Result<sov_modules_api::CallResponse, sov_modules_api::Error> {
+        Ok(sov_modules_api::CallResponse::default())
+    }
+}
@@ -0,0 +1,25 @@
+use jsonrpsee::core::RpcResult;
+use sov_modules_api::macros::rpc_gen;
+use sov_modules_api::{Context, WorkingSet};
+
+use crate::{Withdrawal, WithdrawalQueue};
+
+#[rpc_gen(client, server, namespace = "withdrawalQueue")]
+impl<C: Context> WithdrawalQueue<C> {
+    #[rpc_method(name = "getWithdrawal")]
+    /// Get the withdrawal with the given index in the Bridge contract.
+    /// None if it wasn't initiated yet.
+    pub fn get_withdrawal(
+        &self,
+        index: u64,
+        working_set: &mut WorkingSet<C>,
+    ) -> RpcResult<Option<Withdrawal>> {
+        Ok(self.withdrawal(index, working_set))
+    }
+
+    #[rpc_method(name = "getWithdrawalCount")]
+    /// Get the number of withdrawals initiated so far.
+    pub fn get_withdrawal_count(&self, working_set: &mut WorkingSet<C>) -> RpcResult<u64> {
+        Ok(self.withdrawal_count(working_set))
+    }
+}
@@ -0,0 +1,2 @@
+#[cfg(test)]
+mod withdrawal_tests;
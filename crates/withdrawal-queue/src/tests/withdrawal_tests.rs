@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+
+use rs_merkle::algorithms::Sha256;
+use rs_merkle::MerkleProof;
+use sov_modules_api::default_context::DefaultContext;
+use sov_modules_api::{StateValueAccessor, WorkingSet};
+use sov_prover_storage_manager::new_orphan_storage;
+
+use crate::{withdrawal_proof, withdrawal_root, MissingWithdrawal, Withdrawal, WithdrawalQueue};
+
+type C = DefaultContext;
+
+fn get_withdrawal_queue() -> (WithdrawalQueue<C>, WorkingSet<C>) {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let working_set = WorkingSet::new(new_orphan_storage(tmpdir.path()).unwrap());
+
+    (WithdrawalQueue::<C>::default(), working_set)
+}
+
+#[test]
+fn record_withdrawals() {
+    let (withdrawal_queue, mut working_set) = get_withdrawal_queue();
+
+    assert_eq!(withdrawal_queue.withdrawal_count(&mut working_set), 0);
+    assert_eq!(withdrawal_queue.withdrawal(0, &mut working_set), None);
+
+    // blocks without withdrawals don't write to state
+    withdrawal_queue.record_withdrawals(1, vec![], &mut working_set);
+    assert_eq!(
+        withdrawal_queue.withdrawal_count.get(&mut working_set),
+        None
+    );
+
+    withdrawal_queue.record_withdrawals(2, vec![(0, [1; 32]), (1, [2; 32])], &mut working_set);
+    withdrawal_queue.record_withdrawals(5, vec![(2, [3; 32])], &mut working_set);
+
+    assert_eq!(withdrawal_queue.withdrawal_count(&mut working_set), 3);
+    assert_eq!(
+        withdrawal_queue.withdrawal(1, &mut working_set),
+        Some(Withdrawal {
+            index: 1,
+            l2_height: 2,
+            bitcoin_address: [2; 32],
+        })
+    );
+    assert_eq!(withdrawal_queue.withdrawal(3, &mut working_set), None);
+}
+
+#[test]
+fn withdrawals_in_l2_range() {
+    let (withdrawal_queue, mut working_set) = get_withdrawal_queue();
+
+    withdrawal_queue.record_withdrawals(2, vec![(0, [1; 32]), (1, [2; 32])], &mut working_set);
+    withdrawal_queue.record_withdrawals(5, vec![(2, [3; 32])], &mut working_set);
+    withdrawal_queue.record_withdrawals(6, vec![(3, [4; 32])], &mut working_set);
+
+    let indexes = |withdrawals: Vec<Withdrawal>| -> Vec<u64> {
+        withdrawals.into_iter().map(|w| w.index).collect()
+    };
+
+    assert_eq!(
+        indexes(
+            withdrawal_queue
+                .withdrawals_in_l2_range(0..=1, &mut working_set)
+                .unwrap()
+        ),
+        Vec::<u64>::new()
+    );
+    assert_eq!(
+        indexes(
+            withdrawal_queue
+                .withdrawals_in_l2_range(0..=2, &mut working_set)
+                .unwrap()
+        ),
+        vec![0, 1]
+    );
+    assert_eq!(
+        indexes(
+            withdrawal_queue
+                .withdrawals_in_l2_range(3..=5, &mut working_set)
+                .unwrap()
+        ),
+        vec![2]
+    );
+    assert_eq!(
+        indexes(
+            withdrawal_queue
+                .withdrawals_in_l2_range(2..=10, &mut working_set)
+                .unwrap()
+        ),
+        vec![0, 1, 2, 3]
+    );
+    assert_eq!(
+        indexes(
+            withdrawal_queue
+                .withdrawals_in_l2_range(7..=10, &mut working_set)
+                .unwrap()
+        ),
+        Vec::<u64>::new()
+    );
+}
+
+#[test]
+fn withdrawals_initiated_before_the_queue() {
+    let (withdrawal_queue, mut working_set) = get_withdrawal_queue();
+
+    // Withdrawals 0-4 were initiated on the Bridge contract before the queue
+    withdrawal_queue.record_withdrawals(2, vec![(5, [1; 32]), (6, [2; 32])], &mut working_set);
+    withdrawal_queue.record_withdrawals(4, vec![(7, [3; 32])], &mut working_set);
+
+    assert_eq!(
+        withdrawal_queue.first_withdrawal_index(&mut working_set),
+        Some(5)
+    );
+    assert_eq!(withdrawal_queue.withdrawal_count(&mut working_set), 8);
+    assert_eq!(
+        withdrawal_queue.withdrawal_count_until(1, &mut working_set),
+        Ok(5)
+    );
+    assert_eq!(
+        withdrawal_queue.withdrawal_count_until(3, &mut working_set),
+        Ok(7)
+    );
+
+    let indexes = |withdrawals: Vec<Withdrawal>| -> Vec<u64> {
+        withdrawals.into_iter().map(|w| w.index).collect()
+    };
+    assert_eq!(
+        indexes(
+            withdrawal_queue
+                .withdrawals_in_l2_range(0..=2, &mut working_set)
+                .unwrap()
+        ),
+        vec![5, 6]
+    );
+    assert_eq!(
+        indexes(
+            withdrawal_queue
+                .withdrawals_in_l2_range(3..=4, &mut working_set)
+                .unwrap()
+        ),
+        vec![7]
+    );
+}
+
+#[test]
+fn missing_withdrawals_are_errors() {
+    let (withdrawal_queue, mut working_set) = get_withdrawal_queue();
+
+    // Withdrawal 1 is missing in the middle of the queue
+    withdrawal_queue.record_withdrawals(2, vec![(0, [1; 32]), (2, [3; 32])], &mut working_set);
+
+    assert_eq!(
+        withdrawal_queue.withdrawal_count_until(2, &mut working_set),
+        Err(MissingWithdrawal(1))
+    );
+    assert_eq!(
+        withdrawal_queue.withdrawals_in_l2_range(2..=2, &mut working_set),
+        Err(MissingWithdrawal(1))
+    );
+}
+
+#[test]
+fn withdrawals_from_state_diff() {
+    let withdrawal_queue = WithdrawalQueue::<C>::default();
+
+    let withdrawals = [
+        Withdrawal {
+            index: 4,
+            l2_height: 10,
+            bitcoin_address: [4; 32],
+        },
+        Withdrawal {
+            index: 3,
+            l2_height: 9,
+            bitcoin_address: [3; 32],
+        },
+    ];
+
+    let mut state_diff = BTreeMap::new();
+    for withdrawal in &withdrawals {
+        let mut key = withdrawal_queue
+            .withdrawals
+            .prefix()
+            .as_aligned_vec()
+            .as_ref()
+            .clone();
+        key.extend(borsh::to_vec(&withdrawal.index).unwrap());
+        state_diff.insert(key, Some(borsh::to_vec(withdrawal).unwrap()));
+    }
+    // writes of other state are ignored
+    let count_key = withdrawal_queue
+        .withdrawal_count
+        .prefix()
+        .as_aligned_vec()
+        .as_ref()
+        .clone();
+    state_diff.insert(count_key, Some(borsh::to_vec(&5u64).unwrap()));
+    state_diff.insert(b"other".to_vec(), None);
+
+    assert_eq!(
+        withdrawal_queue.withdrawals_from_state_diff(&state_diff),
+        vec![withdrawals[1].clone(), withdrawals[0].clone()]
+    );
+}
+
+#[test]
+fn withdrawal_merkle_proofs() {
+    assert_eq!(withdrawal_root(&[]), [0; 32]);
+
+    let withdrawals: Vec<Withdrawal> = (0..5)
+        .map(|index| Withdrawal {
+            index,
+            l2_height: 1,
+            bitcoin_address: [index as u8; 32],
+        })
+        .collect();
+    let leaves: Vec<[u8; 32]> = withdrawals.iter().map(Withdrawal::leaf).collect();
+    let root = withdrawal_root(&withdrawals);
+
+    for (leaf_index, leaf) in leaves.iter().enumerate() {
+        let proof = MerkleProof::<Sha256>::new(withdrawal_proof(&withdrawals, leaf_index));
+        assert!(proof.verify(root, &[leaf_index], &[*leaf], leaves.len()));
+    }
+}